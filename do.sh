@@ -22,6 +22,7 @@ Supported actions:
     test
     e2e-test
     update
+    upgrade
 EOF
 }
 
@@ -90,6 +91,11 @@ perform_action() {
     test)
             cargo test-bpf ${@:2}
         ;;
+    upgrade)
+        (
+            ./scripts/upgrade-stable-swap.sh ${@:2}
+        )
+    ;;
     update)
         (
             exit_code=0
@@ -0,0 +1,486 @@
+// Generated by `cargo xtask layout` from the program account layouts, do not edit.
+
+export const SWAP_INFO_LEN = 3400;
+
+export const SWAP_INFO_OFFSETS = {
+  isInitialized: 0,
+  isPaused: 1,
+  nonce: 2,
+  initialAmpFactor: 3,
+  targetAmpFactor: 11,
+  startRampTs: 19,
+  stopRampTs: 27,
+  futureAdminDeadline: 35,
+  futureAdminKey: 43,
+  adminKey: 75,
+  tokenA: 107,
+  tokenB: 139,
+  poolMint: 171,
+  tokenAMint: 203,
+  tokenBMint: 235,
+  adminFeeKeyA: 267,
+  adminFeeKeyB: 299,
+  fees: 331,
+  accessMint: 501,
+  accessWindowEndSlot: 533,
+  namespace: 541,
+  feeTier: 573,
+  adminFeesAccruedA: 574,
+  adminFeesAccruedB: 582,
+  harvestTipBps: 590,
+  reflectionDistributor: 592,
+  reflectionEpoch: 624,
+  devVestingA: 632,
+  devVestingB: 664,
+  devWallet: 696,
+  devVestingStartTs: 728,
+  devVestingEndTs: 736,
+  devFeesReceivedA: 744,
+  devFeesReceivedB: 752,
+  devFeesClaimedA: 760,
+  devFeesClaimedB: 768,
+  governanceRealm: 776,
+  governanceVault: 808,
+  governanceLockup: 840,
+  adminRecoveryKey: 848,
+  adminHeartbeatSlot: 880,
+  adminTimeoutSlots: 888,
+  pendingWithdrawA: 896,
+  pendingWithdrawB: 904,
+  maxSwapAmountIn: 912,
+  lpSoulbound: 920,
+  veLpVault: 921,
+  veFeeVaultA: 953,
+  veFeeVaultB: 985,
+  veFeeBps: 1017,
+  veSupply: 1019,
+  veEpoch: 1027,
+  veFeesPerVeA: 1035,
+  veFeesPerVeB: 1051,
+  vePendingFeesA: 1067,
+  vePendingFeesB: 1075,
+  orderFlowBatchSlots: 1083,
+  orderFlowBidToken: 1091,
+  orderFlowBatch: 1123,
+  orderFlowBidder: 1131,
+  orderFlowRefundAccount: 1163,
+  orderFlowBid: 1195,
+  dynamicAmpMin: 1203,
+  dynamicAmpMax: 1211,
+  dynamicAmpVolatilityCeiling: 1219,
+  dynamicAmpMaxDrift: 1227,
+  twapPrice: 1235,
+  twapVolatility: 1251,
+  twapSlot: 1259,
+  feeBufferBps: 1267,
+  depositBonusBps: 1269,
+  feeBufferA: 1271,
+  feeBufferB: 1279,
+  insuranceFeeBps: 1287,
+  insuranceAuthority: 1289,
+  insuranceFundA: 1321,
+  insuranceFundB: 1329,
+  insuranceCoveredA: 1337,
+  insuranceCoveredB: 1345,
+  minLpMint: 1353,
+  dustThreshold: 1361,
+  dustSweepSlot: 1369,
+  dustSweepCount: 1377,
+  liquidityVault: 1379,
+  positionLiquidity: 1411,
+  cumulativePositionLiquidity: 1419,
+  positionLiquidityTs: 1435,
+  feeReportEpoch: 1443,
+  epochLpFeesA: 1451,
+  epochLpFeesB: 1459,
+  epochAdminFeesA: 1467,
+  epochAdminFeesB: 1475,
+  epochReflectionFeesA: 1483,
+  epochReflectionFeesB: 1491,
+  epochBuybackFeesA: 1499,
+  epochBuybackFeesB: 1507,
+  epochMarketingFeesA: 1515,
+  epochMarketingFeesB: 1523,
+  epochDeveloperFeesA: 1531,
+  epochDeveloperFeesB: 1539,
+  sequence: 1547,
+  custodian: 1555,
+  custodianWithdrawThreshold: 1587,
+  credentialIssuer: 1595,
+  adminLog: 1627,
+  adminFeeEscrowA: 2284,
+  adminFeeEscrowB: 2285,
+  priceACumulative: 2286,
+  priceBCumulative: 2302,
+  priceCumulativeSlot: 2318,
+  guardianKey: 2326,
+  protocolPoolAmount: 2358,
+  pendingFees: 2366,
+  pendingFeesSlot: 2536,
+  customErrorMessages: 2544,
+  reflectionLpVault: 2820,
+  reflectionVaultA: 2852,
+  reflectionVaultB: 2884,
+  reflectionSupply: 2916,
+  accReflectionPerLpShareA: 2924,
+  accReflectionPerLpShareB: 2940,
+  reflectionPendingA: 2956,
+  reflectionPendingB: 2964,
+  buybackMint: 2972,
+  buybackVaultA: 3004,
+  buybackVaultB: 3036,
+  buybackBurned: 3068,
+  marketingWallet: 3076,
+  developerWallet: 3108,
+  marketingFeesAccruedA: 3140,
+  marketingFeesAccruedB: 3148,
+  developerFeesAccruedA: 3156,
+  developerFeesAccruedB: 3164,
+  configHash: 3172,
+  volatilityFeeCeiling: 3204,
+  volatilityFeeMaxMultiplierBps: 3212,
+  referralFeesAccruedA: 3214,
+  referralFeesAccruedB: 3222,
+  adminFeeEpochCapA: 3230,
+  adminFeeEpochCapB: 3238,
+  adminFeeCapEpoch: 3246,
+  adminFeesTakenA: 3254,
+  adminFeesTakenB: 3262,
+  curveType: 3270,
+  priceBound: 3271,
+  creator: 3279,
+  creatorDepositEndSlot: 3311,
+  depositAllowlistEnabled: 3319,
+  freezeAuthorityAcknowledged: 3320,
+  oracleA: 3321,
+  oracleB: 3353,
+  oracleMaxStalenessSlots: 3385,
+  oracleMaxConfidenceBps: 3393,
+  oracleMaxDeviationBps: 3395,
+  oracleStalePolicy: 3397,
+  oracleStaleBandBps: 3398,
+} as const;
+
+export const SWAP_RECEIPT_LEN = 161;
+
+export const SWAP_RECEIPT_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  owner: 33,
+  sourceMint: 65,
+  destinationMint: 97,
+  amountIn: 129,
+  amountOut: 137,
+  feeAmount: 145,
+  slot: 153,
+} as const;
+
+export const ADMIN_SESSION_LEN = 106;
+
+export const ADMIN_SESSION_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  admin: 33,
+  sessionKey: 65,
+  permissions: 97,
+  expirySlot: 98,
+} as const;
+
+export const REFLECTION_DISTRIBUTION_LEN = 121;
+
+export const REFLECTION_DISTRIBUTION_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  epoch: 33,
+  vault: 41,
+  merkleRoot: 73,
+  totalAmount: 105,
+  claimedAmount: 113,
+} as const;
+
+export const REFLECTION_CLAIM_LEN = 81;
+
+export const REFLECTION_CLAIM_OFFSETS = {
+  isInitialized: 0,
+  distribution: 1,
+  index: 33,
+  claimant: 41,
+  amount: 73,
+} as const;
+
+export const GOVERNANCE_DEPOSIT_LEN = 81;
+
+export const GOVERNANCE_DEPOSIT_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  owner: 33,
+  amount: 65,
+  unlockTs: 73,
+} as const;
+
+export const VE_LOCK_LEN = 123;
+
+export const VE_LOCK_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  owner: 33,
+  amount: 65,
+  unlockTs: 73,
+  veBalance: 81,
+  feesDebtA: 89,
+  feesDebtB: 105,
+  gaugeVoteBps: 121,
+} as const;
+
+export const PARAMETER_SNAPSHOT_LEN = 310;
+
+export const PARAMETER_SNAPSHOT_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  snapshotTs: 33,
+  initialAmpFactor: 41,
+  targetAmpFactor: 49,
+  startRampTs: 57,
+  stopRampTs: 65,
+  fees: 73,
+  feeTier: 243,
+  adminFeeKeyA: 244,
+  adminFeeKeyB: 276,
+  harvestTipBps: 308,
+} as const;
+
+export const WITHDRAW_CONTINUATION_LEN = 161;
+
+export const WITHDRAW_CONTINUATION_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  owner: 33,
+  destinationA: 65,
+  destinationB: 97,
+  tokenAAmount: 129,
+  tokenAAdminFee: 137,
+  tokenBAmount: 145,
+  tokenBAdminFee: 153,
+} as const;
+
+export const LIQUIDITY_POSITION_LEN = 97;
+
+export const LIQUIDITY_POSITION_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  owner: 33,
+  amount: 65,
+  cumulativeLiquidity: 73,
+  checkpointTs: 89,
+} as const;
+
+export const REFLECTION_POSITION_LEN = 121;
+
+export const REFLECTION_POSITION_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  owner: 33,
+  amount: 65,
+  reflectionDebtA: 73,
+  reflectionDebtB: 89,
+  reflectionOwedA: 105,
+  reflectionOwedB: 113,
+} as const;
+
+export const TWAP_ORDER_LEN = 200;
+
+export const TWAP_ORDER_OFFSETS = {
+  isInitialized: 0,
+  nonce: 1,
+  swap: 2,
+  owner: 34,
+  orderId: 66,
+  escrow: 74,
+  destination: 106,
+  amountIn: 138,
+  sliceCount: 146,
+  slicesExecuted: 148,
+  sliceIntervalSlots: 150,
+  maxPriceDeviationBps: 158,
+  lastSliceSlot: 160,
+  priceCumulative: 168,
+  priceCumulativeSlot: 184,
+  amountOut: 192,
+} as const;
+
+export const DRIP_DEPOSIT_LEN = 206;
+
+export const DRIP_DEPOSIT_OFFSETS = {
+  isInitialized: 0,
+  nonce: 1,
+  swap: 2,
+  owner: 34,
+  dripId: 66,
+  escrowA: 74,
+  escrowB: 106,
+  destination: 138,
+  amountA: 170,
+  amountB: 178,
+  trancheCount: 186,
+  tranchesDeposited: 188,
+  trancheIntervalSlots: 190,
+  lastTrancheSlot: 198,
+} as const;
+
+export const TRIGGER_ORDER_LEN = 203;
+
+export const TRIGGER_ORDER_OFFSETS = {
+  isInitialized: 0,
+  nonce: 1,
+  swap: 2,
+  owner: 34,
+  triggerId: 66,
+  escrow: 74,
+  destination: 106,
+  oracle: 138,
+  minimumAmountOut: 170,
+  triggerPrice: 178,
+  triggerAbove: 194,
+  bounty: 195,
+} as const;
+
+export const GLOBAL_STATS_LEN = 42;
+
+export const GLOBAL_STATS_OFFSETS = {
+  isInitialized: 0,
+  nonce: 1,
+  totalPools: 2,
+  totalValueLocked: 10,
+  cumulativeVolume: 26,
+} as const;
+
+export const FEE_EXEMPTIONS_LEN = 546;
+
+export const FEE_EXEMPTIONS_OFFSETS = {
+  isInitialized: 0,
+  nonce: 1,
+  swap: 2,
+  exempt: 34,
+} as const;
+
+export const DEPOSIT_ALLOWLIST_LEN = 1058;
+
+export const DEPOSIT_ALLOWLIST_OFFSETS = {
+  isInitialized: 0,
+  nonce: 1,
+  swap: 2,
+  depositors: 34,
+} as const;
+
+export const REFERRAL_ACCRUAL_LEN = 130;
+
+export const REFERRAL_ACCRUAL_OFFSETS = {
+  isInitialized: 0,
+  nonce: 1,
+  swap: 2,
+  referrer: 34,
+  referredVolumeA: 66,
+  referredVolumeB: 82,
+  feesEarnedA: 98,
+  feesEarnedB: 106,
+  feesClaimedA: 114,
+  feesClaimedB: 122,
+} as const;
+
+export const GAUGE_CONTROLLER_LEN = 73;
+
+export const GAUGE_CONTROLLER_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  emissionPerEpoch: 33,
+  epoch: 41,
+  totalNextWeight: 49,
+  epochTotalWeight: 57,
+  epochEmission: 65,
+} as const;
+
+export const GAUGE_LEN = 97;
+
+export const GAUGE_OFFSETS = {
+  isInitialized: 0,
+  controller: 1,
+  swap: 33,
+  nextWeight: 65,
+  epoch: 73,
+  epochWeight: 81,
+  emissions: 89,
+} as const;
+
+export const GAUGE_VOTE_LEN = 75;
+
+export const GAUGE_VOTE_OFFSETS = {
+  isInitialized: 0,
+  gauge: 1,
+  owner: 33,
+  weightBps: 65,
+  weight: 67,
+} as const;
+
+export const FARM_LEN = 209;
+
+export const FARM_OFFSETS = {
+  isInitialized: 0,
+  swap: 1,
+  poolMint: 33,
+  rewardMint: 65,
+  stakeVault: 97,
+  rewardVault: 129,
+  emissionPerSlot: 161,
+  lastUpdateSlot: 169,
+  accRewardPerShare: 177,
+  totalStaked: 193,
+  rewardsUnharvested: 201,
+} as const;
+
+export const STAKE_ACCOUNT_LEN = 97;
+
+export const STAKE_ACCOUNT_OFFSETS = {
+  isInitialized: 0,
+  farm: 1,
+  owner: 33,
+  amount: 65,
+  rewardDebt: 73,
+  rewardOwed: 89,
+} as const;
+
+export const VOTER_WEIGHT_RECORD_LEN = 123;
+
+export const VOTER_WEIGHT_RECORD_OFFSETS = {
+  discriminator: 0,
+  realm: 8,
+  governingTokenMint: 40,
+  governingTokenOwner: 72,
+  voterWeight: 104,
+  options: 112,
+  reserved: 115,
+} as const;
+
+export const RECEIPT_SEED = "receipt";
+export const TWAP_ORDER_SEED = "twap_order";
+export const TRIGGER_SEED = "trigger";
+export const DRIP_DEPOSIT_SEED = "drip_deposit";
+export const ADMIN_SESSION_SEED = "admin_session";
+export const REFLECTION_DISTRIBUTION_SEED = "reflection_distribution";
+export const REFLECTION_CLAIM_SEED = "reflection_claim";
+export const WITHDRAW_CONTINUATION_SEED = "withdraw_continuation";
+export const PARAMETER_SNAPSHOT_SEED = "parameter_snapshot";
+export const GOVERNANCE_DEPOSIT_SEED = "governance_deposit";
+export const VOTER_WEIGHT_RECORD_SEED = "voter-weight-record";
+export const VE_LOCK_SEED = "ve_lock";
+export const LIQUIDITY_POSITION_SEED = "liquidity_position";
+export const REFLECTION_POSITION_SEED = "reflection_position";
+export const GAUGE_CONTROLLER_SEED = "gauge_controller";
+export const GAUGE_SEED = "gauge";
+export const GAUGE_VOTE_SEED = "gauge_vote";
+export const GLOBAL_STATS_SEED = "global_stats";
+export const FEE_EXEMPTIONS_SEED = "fee_exemptions";
+export const REFERRAL_ACCRUAL_SEED = "referral_accrual";
+export const FARM_SEED = "farm";
+export const STAKE_ACCOUNT_SEED = "stake_account";
+export const DEPOSIT_ALLOWLIST_SEED = "deposit_allowlist";
+export const POOL_SEED = "pool";
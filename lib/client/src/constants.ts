@@ -7,3 +7,11 @@ export const TOKEN_PROGRAM_ID = new PublicKey(
 );
 
 export const ZERO_TS = 0;
+
+export const ORCA_TOKEN_SWAP_PROGRAM_ID = new PublicKey(
+  "9W959DqEETiGZocYWCQPaJ6sBmUzgfxXfqGeTEdp3aQP"
+);
+
+export const RAYDIUM_AMM_PROGRAM_ID = new PublicKey(
+  "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8"
+);
@@ -1,4 +1,7 @@
+export * from "./account-layout";
 export * from "./constants";
 export * as calculator from "./util/calculator";
 export * as instructions from "./instructions";
+export * as migration from "./migration";
+export * as replay from "./replay";
 export { StableSwap } from "./stable-swap";
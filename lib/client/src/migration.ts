@@ -0,0 +1,243 @@
+import * as BufferLayout from "buffer-layout";
+import {
+  PublicKey,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+
+import {
+  ORCA_TOKEN_SWAP_PROGRAM_ID,
+  RAYDIUM_AMM_PROGRAM_ID,
+} from "./constants";
+import * as instructions from "./instructions";
+import { Uint64Layout } from "./layout";
+import type { StableSwap } from "./stable-swap";
+import { NumberU64 } from "./util/u64";
+
+/**
+ * Accounts of an Orca (spl-token-swap) pool
+ */
+export interface OrcaPool {
+  kind: "orca";
+  programId: PublicKey;
+  tokenSwap: PublicKey;
+  authority: PublicKey;
+  poolTokenMint: PublicKey;
+  tokenAccountA: PublicKey;
+  tokenAccountB: PublicKey;
+  feeAccount: PublicKey;
+}
+
+/**
+ * Accounts of a Raydium AMM v4 pool and its Serum market
+ */
+export interface RaydiumPool {
+  kind: "raydium";
+  programId: PublicKey;
+  ammId: PublicKey;
+  ammAuthority: PublicKey;
+  ammOpenOrders: PublicKey;
+  ammTargetOrders: PublicKey;
+  lpMint: PublicKey;
+  poolCoinTokenAccount: PublicKey;
+  poolPcTokenAccount: PublicKey;
+  serumProgramId: PublicKey;
+  serumMarket: PublicKey;
+  serumCoinVaultAccount: PublicKey;
+  serumPcVaultAccount: PublicKey;
+  serumVaultSigner: PublicKey;
+  serumEventQueue: PublicKey;
+  serumBids: PublicKey;
+  serumAsks: PublicKey;
+}
+
+export type MigrationSource = OrcaPool | RaydiumPool;
+
+export const orcaWithdrawInstruction = (
+  pool: OrcaPool,
+  userTransferAuthority: PublicKey,
+  sourcePoolAccount: PublicKey,
+  userAccountA: PublicKey,
+  userAccountB: PublicKey,
+  tokenProgramId: PublicKey,
+  poolTokenAmount: number | NumberU64,
+  minimumTokenA: number | NumberU64,
+  minimumTokenB: number | NumberU64
+): TransactionInstruction => {
+  const dataLayout = BufferLayout.struct([
+    BufferLayout.u8("instruction"),
+    Uint64Layout("poolTokenAmount"),
+    Uint64Layout("minimumTokenA"),
+    Uint64Layout("minimumTokenB"),
+  ]);
+
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: 3, // WithdrawAllTokenTypes instruction
+      poolTokenAmount: new NumberU64(poolTokenAmount).toBuffer(),
+      minimumTokenA: new NumberU64(minimumTokenA).toBuffer(),
+      minimumTokenB: new NumberU64(minimumTokenB).toBuffer(),
+    },
+    data
+  );
+
+  const keys = [
+    { pubkey: pool.tokenSwap, isSigner: false, isWritable: false },
+    { pubkey: pool.authority, isSigner: false, isWritable: false },
+    { pubkey: userTransferAuthority, isSigner: true, isWritable: false },
+    { pubkey: pool.poolTokenMint, isSigner: false, isWritable: true },
+    { pubkey: sourcePoolAccount, isSigner: false, isWritable: true },
+    { pubkey: pool.tokenAccountA, isSigner: false, isWritable: true },
+    { pubkey: pool.tokenAccountB, isSigner: false, isWritable: true },
+    { pubkey: userAccountA, isSigner: false, isWritable: true },
+    { pubkey: userAccountB, isSigner: false, isWritable: true },
+    { pubkey: pool.feeAccount, isSigner: false, isWritable: true },
+    { pubkey: tokenProgramId, isSigner: false, isWritable: false },
+  ];
+  return new TransactionInstruction({
+    keys,
+    programId: pool.programId,
+    data,
+  });
+};
+
+export const raydiumWithdrawInstruction = (
+  pool: RaydiumPool,
+  userOwner: PublicKey,
+  userLpAccount: PublicKey,
+  userCoinAccount: PublicKey,
+  userPcAccount: PublicKey,
+  tokenProgramId: PublicKey,
+  lpAmount: number | NumberU64
+): TransactionInstruction => {
+  const dataLayout = BufferLayout.struct([
+    BufferLayout.u8("instruction"),
+    Uint64Layout("lpAmount"),
+  ]);
+
+  const data = Buffer.alloc(dataLayout.span);
+  dataLayout.encode(
+    {
+      instruction: 4, // Withdraw instruction
+      lpAmount: new NumberU64(lpAmount).toBuffer(),
+    },
+    data
+  );
+
+  const keys = [
+    { pubkey: tokenProgramId, isSigner: false, isWritable: false },
+    { pubkey: pool.ammId, isSigner: false, isWritable: true },
+    { pubkey: pool.ammAuthority, isSigner: false, isWritable: false },
+    { pubkey: pool.ammOpenOrders, isSigner: false, isWritable: true },
+    { pubkey: pool.ammTargetOrders, isSigner: false, isWritable: true },
+    { pubkey: pool.lpMint, isSigner: false, isWritable: true },
+    { pubkey: pool.poolCoinTokenAccount, isSigner: false, isWritable: true },
+    { pubkey: pool.poolPcTokenAccount, isSigner: false, isWritable: true },
+    { pubkey: pool.serumProgramId, isSigner: false, isWritable: false },
+    { pubkey: pool.serumMarket, isSigner: false, isWritable: true },
+    { pubkey: pool.serumCoinVaultAccount, isSigner: false, isWritable: true },
+    { pubkey: pool.serumPcVaultAccount, isSigner: false, isWritable: true },
+    { pubkey: pool.serumVaultSigner, isSigner: false, isWritable: false },
+    { pubkey: userLpAccount, isSigner: false, isWritable: true },
+    { pubkey: userCoinAccount, isSigner: false, isWritable: true },
+    { pubkey: userPcAccount, isSigner: false, isWritable: true },
+    { pubkey: userOwner, isSigner: true, isWritable: false },
+    { pubkey: pool.serumEventQueue, isSigner: false, isWritable: true },
+    { pubkey: pool.serumBids, isSigner: false, isWritable: true },
+    { pubkey: pool.serumAsks, isSigner: false, isWritable: true },
+  ];
+  return new TransactionInstruction({
+    keys,
+    programId: pool.programId,
+    data,
+  });
+};
+
+/**
+ * Withdraw liquidity from a whitelisted Orca or Raydium pool and deposit it
+ * into a stable swap in one transaction. Token A and B of the source pool
+ * must be token A and B of the stable swap, paid out to the user accounts the
+ * deposit is made from. The source pool has no slippage guard on Raydium, so
+ * `tokenAmountA` and `tokenAmountB` should be the least amounts the withdrawal
+ * is expected to pay out: the deposit fails, and the whole migration with it,
+ * if the withdrawal pays out less.
+ * @param stableSwap
+ * @param source
+ * @param owner
+ * @param sourcePoolAccount
+ * @param userAccountA
+ * @param userAccountB
+ * @param poolTokenAccount
+ * @param sourcePoolTokenAmount
+ * @param tokenAmountA
+ * @param tokenAmountB
+ * @param minimumPoolTokenAmount
+ */
+export const migrateLiquidity = (
+  stableSwap: StableSwap,
+  source: MigrationSource,
+  owner: PublicKey,
+  sourcePoolAccount: PublicKey,
+  userAccountA: PublicKey,
+  userAccountB: PublicKey,
+  poolTokenAccount: PublicKey,
+  sourcePoolTokenAmount: number,
+  tokenAmountA: number,
+  tokenAmountB: number,
+  minimumPoolTokenAmount: number
+): Transaction => {
+  const whitelisted =
+    source.kind === "orca"
+      ? ORCA_TOKEN_SWAP_PROGRAM_ID
+      : RAYDIUM_AMM_PROGRAM_ID;
+  if (!source.programId.equals(whitelisted)) {
+    throw new Error(
+      `Migration from ${source.programId.toBase58()} is not supported`
+    );
+  }
+
+  let withdraw: TransactionInstruction;
+  if (source.kind === "orca") {
+    withdraw = orcaWithdrawInstruction(
+      source,
+      owner,
+      sourcePoolAccount,
+      userAccountA,
+      userAccountB,
+      stableSwap.tokenProgramId,
+      sourcePoolTokenAmount,
+      tokenAmountA,
+      tokenAmountB
+    );
+  } else {
+    withdraw = raydiumWithdrawInstruction(
+      source,
+      owner,
+      sourcePoolAccount,
+      userAccountA,
+      userAccountB,
+      stableSwap.tokenProgramId,
+      sourcePoolTokenAmount
+    );
+  }
+
+  return new Transaction().add(
+    withdraw,
+    instructions.depositInstruction(
+      stableSwap.stableSwap,
+      stableSwap.authority,
+      userAccountA,
+      userAccountB,
+      stableSwap.tokenAccountA,
+      stableSwap.tokenAccountB,
+      stableSwap.poolTokenMint,
+      poolTokenAccount,
+      stableSwap.swapProgramId,
+      stableSwap.tokenProgramId,
+      tokenAmountA,
+      tokenAmountB,
+      minimumPoolTokenAmount
+    )
+  );
+};
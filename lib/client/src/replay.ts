@@ -0,0 +1,241 @@
+import BN from "bn.js";
+import type {
+  Connection,
+  ParsedConfirmedTransaction,
+  PartiallyDecodedInstruction,
+  PublicKey,
+  TokenBalance,
+  TransactionSignature,
+} from "@solana/web3.js";
+
+import type { StableSwap } from "./stable-swap";
+import { computeAmpFactor, computeSwapAmountOut } from "./util/calculator";
+
+const SWAP_INSTRUCTION = 1;
+const BASE58_ALPHABET =
+  "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/**
+ * Outcome of replaying one transaction against the pool
+ */
+export interface ReplayReport {
+  signature: TransactionSignature;
+  slot: number;
+  /**
+   * Amount the swap should have paid out, null if it could not be replayed
+   */
+  expectedAmountOut: BN | null;
+  /**
+   * Amount the user destination account actually received
+   */
+  actualAmountOut: BN | null;
+  diverged: boolean;
+  /**
+   * Why the transaction diverged or could not be replayed
+   */
+  reason?: string;
+}
+
+export interface ReplayRange {
+  /**
+   * Start searching backwards from this signature, exclusive
+   */
+  before?: TransactionSignature;
+  /**
+   * Stop searching at this signature, exclusive
+   */
+  until?: TransactionSignature;
+  /**
+   * Maximum number of signatures to return
+   */
+  limit?: number;
+}
+
+const decodeBase58 = (encoded: string): Buffer => {
+  let value = new BN(0);
+  for (const char of encoded) {
+    const digit = BASE58_ALPHABET.indexOf(char);
+    if (digit < 0) {
+      throw new Error(`Invalid base58 character ${char}`);
+    }
+    value = value.muln(58).addn(digit);
+  }
+  let leadingZeros = 0;
+  while (encoded[leadingZeros] === "1") {
+    leadingZeros++;
+  }
+  const bytes = value.isZero() ? [] : value.toArray("be");
+  return Buffer.from([...new Array(leadingZeros).fill(0), ...bytes]);
+};
+
+const tokenBalance = (
+  balances: Array<TokenBalance> | null | undefined,
+  accountIndex: number
+): BN | null => {
+  const balance = (balances ?? []).find(
+    (b) => b.accountIndex === accountIndex
+  );
+  return balance ? new BN(balance.uiTokenAmount.amount) : null;
+};
+
+/**
+ * Fetch signatures of the transactions that touched a pool, newest first
+ * @param connection The connection to use
+ * @param pool Address of the swap account
+ * @param range Signatures to fetch
+ */
+export const fetchPoolSignatures = async (
+  connection: Connection,
+  pool: PublicKey,
+  range: ReplayRange = {}
+): Promise<TransactionSignature[]> => {
+  const infos = await connection.getConfirmedSignaturesForAddress2(pool, {
+    before: range.before,
+    limit: range.limit,
+  });
+  const signatures = infos.map((info) => info.signature);
+  const until = range.until ? signatures.indexOf(range.until) : -1;
+  return until < 0 ? signatures : signatures.slice(0, until);
+};
+
+/**
+ * Re-execute a swap of a confirmed transaction with the swap math, from the
+ * token balances the transaction found, and compare the result with what the
+ * user received.
+ * @param stableSwap The pool the transaction swapped against
+ * @param signature Signature of the transaction
+ * @param transaction The parsed transaction
+ * @param blockTime Unix timestamp of the block the transaction landed in
+ */
+export const replayTransaction = (
+  stableSwap: StableSwap,
+  signature: TransactionSignature,
+  transaction: ParsedConfirmedTransaction,
+  blockTime: number | null
+): ReplayReport => {
+  const report: ReplayReport = {
+    signature,
+    slot: transaction.slot,
+    expectedAmountOut: null,
+    actualAmountOut: null,
+    diverged: false,
+  };
+  const { meta } = transaction;
+  if (!meta) {
+    return { ...report, reason: "Transaction metadata is unavailable" };
+  }
+  if (meta.err) {
+    // A failed transaction leaves the pool untouched
+    return { ...report, reason: "Transaction failed" };
+  }
+
+  const swaps = transaction.transaction.message.instructions.filter(
+    (ix): ix is PartiallyDecodedInstruction =>
+      ix.programId.equals(stableSwap.swapProgramId) &&
+      "accounts" in ix &&
+      ix.accounts.length > 0 &&
+      ix.accounts[0].equals(stableSwap.stableSwap)
+  );
+  if (swaps.length !== 1) {
+    return {
+      ...report,
+      reason: `Expected one pool instruction, found ${swaps.length}`,
+    };
+  }
+  const [swap] = swaps;
+  const data = decodeBase58(swap.data);
+  if (data[0] !== SWAP_INSTRUCTION) {
+    return { ...report, reason: `Instruction ${data[0]} is not a swap` };
+  }
+  if (blockTime === null) {
+    return { ...report, reason: "Block time is unavailable" };
+  }
+
+  const accountKeys = transaction.transaction.message.accountKeys;
+  const indexOf = (key: PublicKey) =>
+    accountKeys.findIndex((account) => account.pubkey.equals(key));
+  const [poolSource, poolDestination, userDestination] = [3, 4, 5].map((i) =>
+    indexOf(swap.accounts[i])
+  );
+  const reserveIn = tokenBalance(meta.preTokenBalances, poolSource);
+  const reserveOut = tokenBalance(meta.preTokenBalances, poolDestination);
+  const preDestination = tokenBalance(meta.preTokenBalances, userDestination);
+  const postDestination = tokenBalance(
+    meta.postTokenBalances,
+    userDestination
+  );
+  if (!reserveIn || !reserveOut || !preDestination || !postDestination) {
+    return { ...report, reason: "Token balances are unavailable" };
+  }
+
+  const amountIn = new BN(data.slice(1, 9), "le");
+  const ampFactor = computeAmpFactor(
+    new BN(stableSwap.initialAmpFactor),
+    new BN(stableSwap.targetAmpFactor),
+    stableSwap.startRampTimestamp,
+    stableSwap.stopRampTimestamp,
+    blockTime
+  );
+  const { amountOut } = computeSwapAmountOut(
+    ampFactor,
+    stableSwap.fees,
+    reserveIn,
+    reserveOut,
+    amountIn
+  );
+  const actualAmountOut = postDestination.sub(preDestination);
+  const diverged = !amountOut.eq(actualAmountOut);
+  return {
+    ...report,
+    expectedAmountOut: amountOut,
+    actualAmountOut,
+    diverged,
+    reason: diverged
+      ? `Expected ${amountOut.toString()}, received ${actualAmountOut.toString()}`
+      : undefined,
+  };
+};
+
+/**
+ * Replay the swaps of a range of a pool's transaction history and flag the
+ * ones that paid out something other than what the swap math gives. Only the
+ * trade fee charged on the output is modeled, so reports against pools that
+ * charge fees on the input or hold pending withdrawals in their reserves are
+ * expected to diverge.
+ * @param connection The connection to use
+ * @param stableSwap The pool to replay
+ * @param range Signatures to replay
+ */
+export const replayPoolHistory = async (
+  connection: Connection,
+  stableSwap: StableSwap,
+  range: ReplayRange = {}
+): Promise<ReplayReport[]> => {
+  const signatures = await fetchPoolSignatures(
+    connection,
+    stableSwap.stableSwap,
+    range
+  );
+  const reports: ReplayReport[] = [];
+  for (const signature of signatures) {
+    const transaction = await connection.getParsedConfirmedTransaction(
+      signature
+    );
+    if (!transaction) {
+      reports.push({
+        signature,
+        slot: 0,
+        expectedAmountOut: null,
+        actualAmountOut: null,
+        diverged: false,
+        reason: "Transaction is unavailable",
+      });
+      continue;
+    }
+    const blockTime = await connection.getBlockTime(transaction.slot);
+    reports.push(
+      replayTransaction(stableSwap, signature, transaction, blockTime)
+    );
+  }
+  return reports;
+};
@@ -1,5 +1,7 @@
 import BN from "bn.js";
 
+import type { Fees } from "../fees";
+
 const ZERO = new BN(0);
 const ONE = new BN(1);
 const N_COINS = new BN(2); // n
@@ -64,3 +66,69 @@ export const computeY = (ampFactor: BN, x: BN, d: BN): BN => {
 
   return y;
 };
+
+/**
+ * Compute the amplification coefficient at a point in time of its ramp
+ * @param initialAmpFactor Amplification coefficient the ramp starts from
+ * @param targetAmpFactor Amplification coefficient the ramp ends at
+ * @param startRampTs Ramp start timestamp
+ * @param stopRampTs Ramp stop timestamp
+ * @param currentTs Timestamp to compute the coefficient at
+ */
+export const computeAmpFactor = (
+  initialAmpFactor: BN,
+  targetAmpFactor: BN,
+  startRampTs: number,
+  stopRampTs: number,
+  currentTs: number
+): BN => {
+  if (currentTs >= stopRampTs) {
+    return targetAmpFactor;
+  }
+  // A clock behind the ramp start leaves the coefficient at its initial value
+  const timeRange = new BN(Math.max(stopRampTs - startRampTs, 0));
+  const timeDelta = new BN(Math.max(currentTs - startRampTs, 0));
+  if (targetAmpFactor.gte(initialAmpFactor)) {
+    return initialAmpFactor.add(
+      targetAmpFactor.sub(initialAmpFactor).mul(timeDelta).div(timeRange)
+    );
+  }
+  return initialAmpFactor.sub(
+    initialAmpFactor.sub(targetAmpFactor).mul(timeDelta).div(timeRange)
+  );
+};
+
+const applyFee = (amount: BN, numerator: number, denominator: number): BN =>
+  denominator === 0
+    ? ZERO
+    : amount.mul(new BN(numerator)).div(new BN(denominator));
+
+/**
+ * Compute what a swap pays out, charging the trade fee on the output
+ * @param ampFactor Amplification coefficient (A)
+ * @param fees Swap fees
+ * @param reserveIn Swap balance of the token swapped in
+ * @param reserveOut Swap balance of the token swapped out
+ * @param amountIn Amount swapped in
+ */
+export const computeSwapAmountOut = (
+  ampFactor: BN,
+  fees: Fees,
+  reserveIn: BN,
+  reserveOut: BN,
+  amountIn: BN
+): { amountOut: BN; fee: BN; adminFee: BN } => {
+  const y = computeY(
+    ampFactor,
+    reserveIn.add(amountIn),
+    computeD(ampFactor, reserveIn, reserveOut)
+  );
+  const dy = reserveOut.sub(y);
+  const fee = applyFee(dy, fees.tradeFeeNumerator, fees.tradeFeeDenominator);
+  const adminFee = applyFee(
+    fee,
+    fees.adminTradeFeeNumerator,
+    fees.adminTradeFeeDenominator
+  );
+  return { amountOut: dy.sub(fee), fee, adminFee };
+};
@@ -1,6 +1,12 @@
 import BN from "bn.js";
 
-import { computeD, computeY } from "./calculator";
+import { DEFAULT_FEES } from "../fees";
+import {
+  computeAmpFactor,
+  computeD,
+  computeSwapAmountOut,
+  computeY,
+} from "./calculator";
 
 const assertBN = (actual: BN, expected: BN) => {
   expect(actual.toString()).toEqual(expected.toString());
@@ -54,4 +60,44 @@ describe("Calculator tests", () => {
       new BN("2661117384496570284")
     );
   });
+
+  it("computeAmpFactor", () => {
+    const initial = new BN(100);
+    assertBN(computeAmpFactor(initial, new BN(200), 0, 100, 50), new BN(150));
+    assertBN(computeAmpFactor(initial, new BN(10), 0, 100, 50), new BN(55));
+    assertBN(computeAmpFactor(initial, new BN(200), 0, 100, 100), new BN(200));
+    // Clock behind the ramp start
+    assertBN(computeAmpFactor(initial, new BN(200), 50, 150, 0), initial);
+  });
+
+  it("computeSwapAmountOut", () => {
+    const fees = {
+      ...DEFAULT_FEES,
+      adminTradeFeeNumerator: 1,
+      adminTradeFeeDenominator: 2,
+      tradeFeeNumerator: 6,
+      tradeFeeDenominator: 100,
+    };
+    const result = computeSwapAmountOut(
+      new BN(100),
+      fees,
+      new BN(1000000),
+      new BN(1000000),
+      new BN(10000)
+    );
+    assertBN(result.amountOut, new BN(9400));
+    assertBN(result.fee, new BN(600));
+    assertBN(result.adminFee, new BN(300));
+
+    const imbalanced = computeSwapAmountOut(
+      new BN(85),
+      fees,
+      new BN(28338),
+      new BN(78889),
+      new BN(1000)
+    );
+    assertBN(imbalanced.amountOut, new BN(957));
+    assertBN(imbalanced.fee, new BN(61));
+    assertBN(imbalanced.adminFee, new BN(30));
+  });
 });
@@ -0,0 +1,176 @@
+//! Big number types
+
+#![allow(clippy::assign_op_pattern)]
+#![allow(clippy::ptr_offset_with_cast)]
+#![allow(clippy::manual_range_contains)]
+
+use core::{convert::TryFrom, fmt, str::FromStr};
+use uint::{construct_uint, FromDecStrErr};
+
+construct_uint! {
+    /// 256-bit unsigned integer.
+    pub struct U256(4);
+}
+
+/// A `U256` too large for the integer type it is narrowed to
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct U256Overflow;
+
+impl fmt::Display for U256Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("integer overflow narrowing a U256")
+    }
+}
+
+impl U256 {
+    /// Size of a `U256` in bytes
+    pub const LEN: usize = 32;
+
+    /// Little-endian bytes of the integer, how a `U256` is packed in account data
+    #[inline]
+    pub fn to_le_bytes(self) -> [u8; Self::LEN] {
+        let mut bytes = [0; Self::LEN];
+        self.to_little_endian(&mut bytes);
+        bytes
+    }
+
+    /// Integer of little-endian `bytes`
+    #[inline]
+    pub fn from_le_bytes(bytes: [u8; Self::LEN]) -> Self {
+        Self::from_little_endian(&bytes)
+    }
+
+    /// Narrows the integer to a `u64`
+    #[inline]
+    pub fn try_into_u64(self) -> Result<u64, U256Overflow> {
+        u64::try_from(self).map_err(|_| U256Overflow)
+    }
+
+    /// Narrows the integer to a `u128`
+    #[inline]
+    pub fn try_into_u128(self) -> Result<u128, U256Overflow> {
+        u128::try_from(self).map_err(|_| U256Overflow)
+    }
+
+    /// Square root of the integer, rounded down
+    pub fn integer_sqrt(self) -> Self {
+        if self < Self::from(2u64) {
+            return self;
+        }
+        // Newton's method from above, decreasing until it settles at the floor
+        let mut x = Self::one() << self.bits().div_ceil(2);
+        loop {
+            let next = (x + self / x) >> 1;
+            if next >= x {
+                return x;
+            }
+            x = next;
+        }
+    }
+
+    // `saturating_add`, `saturating_sub` and `saturating_mul` come with
+    // `construct_uint!`. Like the wrapping variants, they are meant for metrics and
+    // statistics, which must not abort a swap by overflowing, never for amounts.
+
+    /// Sum of the integers modulo 2^256
+    pub fn wrapping_add(self, other: Self) -> Self {
+        self.overflowing_add(other).0
+    }
+
+    /// Difference of the integers modulo 2^256
+    pub fn wrapping_sub(self, other: Self) -> Self {
+        self.overflowing_sub(other).0
+    }
+
+    /// Product of the integers modulo 2^256
+    pub fn wrapping_mul(self, other: Self) -> Self {
+        self.overflowing_mul(other).0
+    }
+}
+
+/// Parses decimal digits, as the integer is displayed
+impl FromStr for U256 {
+    type Err = FromDecStrErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_dec_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_le_bytes() {
+        let value = (U256::from(u128::MAX) << 64) + U256::from(0x0102u64);
+        let bytes = value.to_le_bytes();
+        assert_eq!(&bytes[..2], &[0x02, 0x01]);
+        assert_eq!(&bytes[8..24], &[0xff; 16]);
+        assert_eq!(&bytes[24..], &[0; 8]);
+        assert_eq!(U256::from_le_bytes(bytes), value);
+        assert_eq!(U256::from_le_bytes([0xff; U256::LEN]), U256::MAX);
+        assert_eq!(U256::zero().to_le_bytes(), [0; U256::LEN]);
+    }
+
+    #[test]
+    fn test_try_into() {
+        assert_eq!(U256::from(u64::MAX).try_into_u64(), Ok(u64::MAX));
+        assert_eq!((U256::from(u64::MAX) + 1).try_into_u64(), Err(U256Overflow));
+        assert_eq!(U256::from(u128::MAX).try_into_u128(), Ok(u128::MAX));
+        assert_eq!(
+            (U256::from(u128::MAX) + 1).try_into_u128(),
+            Err(U256Overflow)
+        );
+        assert_eq!(U256::MAX.try_into_u64(), Err(U256Overflow));
+    }
+
+    #[test]
+    fn test_saturating_and_wrapping() {
+        let two = U256::from(2u64);
+        assert_eq!(U256::MAX.saturating_add(two), U256::MAX);
+        assert_eq!(U256::MAX.saturating_mul(two), U256::MAX);
+        assert_eq!(U256::one().saturating_add(two), U256::from(3u64));
+        assert_eq!(U256::from(3u64).saturating_mul(two), U256::from(6u64));
+
+        assert_eq!(U256::MAX.wrapping_add(two), U256::one());
+        assert_eq!(U256::MAX.wrapping_mul(two), U256::MAX - 1);
+        assert_eq!(U256::one().wrapping_sub(two), U256::MAX);
+        assert_eq!(U256::from(3u64).wrapping_sub(two), U256::one());
+        assert_eq!(U256::from(3u64).wrapping_mul(two), U256::from(6u64));
+    }
+
+    #[test]
+    fn test_integer_sqrt() {
+        assert_eq!(U256::zero().integer_sqrt(), U256::zero());
+        assert_eq!(U256::one().integer_sqrt(), U256::one());
+        assert_eq!(U256::from(3u64).integer_sqrt(), U256::one());
+        assert_eq!(U256::from(4u64).integer_sqrt(), U256::from(2u64));
+        assert_eq!(U256::from(99u64).integer_sqrt(), U256::from(9u64));
+        assert_eq!(U256::from(u64::MAX).integer_sqrt(), U256::from(u32::MAX));
+        let root = U256::from(u128::MAX);
+        assert_eq!(U256::MAX.integer_sqrt(), root);
+        assert_eq!((root * root).integer_sqrt(), root);
+        assert_eq!((root * root - 1).integer_sqrt(), root - 1);
+    }
+
+    #[test]
+    fn test_decimal_string() {
+        assert_eq!(U256::zero().to_string(), "0");
+        assert_eq!(U256::from(1_234_567u64).to_string(), "1234567");
+        let max = U256::MAX.to_string();
+        assert_eq!(
+            max,
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+        assert_eq!(max.parse::<U256>(), Ok(U256::MAX));
+        assert_eq!("1234567".parse::<U256>(), Ok(U256::from(1_234_567u64)));
+        assert_eq!("0".parse::<U256>(), Ok(U256::zero()));
+        assert_eq!(
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+                .parse::<U256>(),
+            Err(FromDecStrErr::InvalidLength)
+        );
+        assert_eq!("12a".parse::<U256>(), Err(FromDecStrErr::InvalidCharacter));
+    }
+}
@@ -0,0 +1,2795 @@
+//! Swap calculations and curve invariant implementation
+
+use crate::{
+    bn::U256,
+    fees::{imbalance_bps, Fees},
+};
+use core::convert::TryFrom;
+
+/// Number of coins
+const N_COINS: u64 = 2;
+/// Timestamp at 0
+pub const ZERO_TS: i64 = 0;
+/// Minimum ramp duration
+pub const MIN_RAMP_DURATION: i64 = 86400;
+/// Min amplification coefficient
+pub const MIN_AMP: u64 = 1;
+/// Max amplification coefficient
+pub const MAX_AMP: u64 = 1_000_000;
+/// Fixed-point scale of the virtual price
+pub const VIRTUAL_PRICE_SCALE: u64 = 1_000_000_000_000;
+/// Fixed-point scale of the price bound of a `PriceBounded` curve
+pub const PRICE_BOUND_SCALE: u64 = 1_000_000_000_000;
+/// Most estimates `compute_swap_in` makes at the imbalance fee of the previous one
+const MAX_SWAP_IN_ADJUSTMENTS: usize = 8;
+/// Size of a quote of `StableSwap::quote_in_place`, in bytes
+pub const QUOTE_LEN: usize = 40;
+/// Basis points of a transfer fee withholding the whole amount
+pub const MAX_TRANSFER_FEE_BASIS_POINTS: u16 = 10_000;
+
+/// Encodes all results of swapping from a source token to a destination token
+pub struct SwapResult {
+    /// New amount of source token
+    pub new_source_amount: U256,
+    /// New amount of destination token
+    pub new_destination_amount: U256,
+    /// Amount of destination token swapped
+    pub amount_swapped: U256,
+    /// Admin fee for the swap
+    pub admin_fee: U256,
+}
+
+/// Encodes all results of swapping from a source token to a destination token
+pub struct SwapResultV2 {
+    /// New amount of source token
+    pub new_source_amount: U256,
+    /// New amount of destination token
+    pub new_destination_amount: U256,
+    /// Amount of destination token swapped
+    pub amount_swapped: U256,
+    /// total trade fee for the swap, of which the other fees are a share
+    pub trade_fee: U256,
+    /// reflection fee for the swap
+    pub reflection_fee: U256,
+    /// buyback fee for the swap
+    pub buyback_fee: U256,
+    /// marketing fee for the swap
+    pub marketing_fee: U256,
+    /// developer fee for the swap
+    pub developer_fee: U256,
+    /// admin share of the trade fee, left in the pool
+    pub admin_fee: U256,
+}
+
+/// Fee a Token-2022 mint withholds from every transfer of its tokens, so that the
+/// destination receives less than the amount sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransferFee {
+    /// Fee rate, in basis points of the amount sent, rounded up
+    pub basis_points: u16,
+    /// Most withheld from a single transfer
+    pub maximum_fee: u64,
+}
+
+impl TransferFee {
+    /// Fee withheld from sending `amount`
+    #[inline]
+    pub fn fee(&self, amount: u64) -> Option<u64> {
+        if self.basis_points == 0 || amount == 0 {
+            return Some(0);
+        }
+        let fee = (amount as u128)
+            .checked_mul(self.basis_points.into())?
+            .checked_add(u128::from(MAX_TRANSFER_FEE_BASIS_POINTS) - 1)?
+            / u128::from(MAX_TRANSFER_FEE_BASIS_POINTS);
+        Some(u64::try_from(fee).ok()?.min(self.maximum_fee))
+    }
+
+    /// Amount received when sending `amount`
+    #[inline]
+    pub fn post_fee_amount(&self, amount: u64) -> Option<u64> {
+        amount.checked_sub(self.fee(amount)?)
+    }
+
+    /// Least amount to send for at least `post_fee_amount` to be received
+    #[inline]
+    pub fn pre_fee_amount(&self, post_fee_amount: u64) -> Option<u64> {
+        if self.basis_points == 0 || post_fee_amount == 0 {
+            return Some(post_fee_amount);
+        }
+        let fee = if self.basis_points >= MAX_TRANSFER_FEE_BASIS_POINTS {
+            self.maximum_fee
+        } else {
+            let denominator = u128::from(MAX_TRANSFER_FEE_BASIS_POINTS - self.basis_points);
+            let fee = (post_fee_amount as u128)
+                .checked_mul(self.basis_points.into())?
+                .checked_add(denominator - 1)?
+                / denominator;
+            u64::try_from(fee).ok()?.min(self.maximum_fee)
+        };
+        post_fee_amount.checked_add(fee)
+    }
+}
+
+/// The StableSwap invariant calculator.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StableSwap {
+    /// Initial amplification coefficient (A)
+    initial_amp_factor: U256,
+    /// Target amplificaiton coeffiecient (A)
+    target_amp_factor: U256,
+    /// Current unix timestamp
+    current_ts: i64,
+    /// Ramp A start timestamp
+    start_ramp_ts: i64,
+    /// Ramp A stop timestamp
+    stop_ramp_ts: i64,
+}
+
+impl StableSwap {
+    /// New StableSwap calculator
+    #[inline]
+    pub fn new(
+        initial_amp_factor: u64,
+        target_amp_factor: u64,
+        current_ts: i64,
+        start_ramp_ts: i64,
+        stop_ramp_ts: i64,
+    ) -> Self {
+        Self {
+            initial_amp_factor: U256::from(initial_amp_factor),
+            target_amp_factor: U256::from(target_amp_factor),
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        }
+    }
+
+    fn compute_next_d(
+        &self,
+        amp_factor: U256,
+        d_init: U256,
+        d_prod: U256,
+        sum_x: U256,
+    ) -> Option<U256> {
+        let ann = amp_factor.checked_mul(N_COINS.into())?;
+        let leverage = ann.checked_mul(sum_x)?;
+        // d = (ann * sum_x + d_prod * n_coins) * d / ((ann - 1) * d + (n_coins + 1) * d_prod)
+        let numerator =
+            d_init.checked_mul(d_prod.checked_mul(N_COINS.into())?.checked_add(leverage)?)?;
+        let denominator = d_init
+            .checked_mul(ann.checked_sub(1.into())?)?
+            .checked_add(d_prod.checked_mul((N_COINS + 1).into())?)?;
+        numerator.checked_div(denominator)
+    }
+
+    /// Compute the amplification coefficient (A)
+    #[inline]
+    pub fn compute_amp_factor(&self) -> Option<U256> {
+        if self.current_ts < self.stop_ramp_ts {
+            // A clock behind the ramp start leaves the amp factor at its initial value,
+            // rather than converting a negative time delta
+            let time_range = U256::from(self.stop_ramp_ts.checked_sub(self.start_ramp_ts)?.max(0));
+            let time_delta = U256::from(self.current_ts.checked_sub(self.start_ramp_ts)?.max(0));
+
+            // Compute amp factor based on ramp time
+            if self.target_amp_factor >= self.initial_amp_factor {
+                // Ramp up
+                let amp_range = self
+                    .target_amp_factor
+                    .checked_sub(self.initial_amp_factor)?;
+                let amp_delta = amp_range.checked_mul(time_delta)?.checked_div(time_range)?;
+                self.initial_amp_factor.checked_add(amp_delta)
+            } else {
+                // Ramp down
+                let amp_range = self
+                    .initial_amp_factor
+                    .checked_sub(self.target_amp_factor)?;
+                let amp_delta = amp_range.checked_mul(time_delta)?.checked_div(time_range)?;
+                self.initial_amp_factor.checked_sub(amp_delta)
+            }
+        } else {
+            // when stop_ramp_ts == 0 or current_ts >= stop_ramp_ts
+            Some(self.target_amp_factor)
+        }
+    }
+
+    /// Compute stable swap invariant (D)
+    /// Equation:
+    /// A * sum(x_i) * n**n + D = A * D * n**n + D**(n+1) / (n**n * prod(x_i))
+    pub fn compute_d(&self, amount_a: U256, amount_b: U256) -> Option<U256> {
+        let sum_x = amount_a.checked_add(amount_b)?; // sum(x_i), a.k.a S
+        if sum_x == 0.into() {
+            Some(0.into())
+        } else {
+            let amp_factor = self.compute_amp_factor()?;
+            let amount_a_times_coins = amount_a.checked_mul(N_COINS.into())?;
+            let amount_b_times_coins = amount_b.checked_mul(N_COINS.into())?;
+
+            // Newton's method to approximate D
+            let mut d_prev: U256;
+            let mut d = sum_x;
+            for _ in 0..256 {
+                let mut d_prod = d;
+                d_prod = d_prod.checked_mul(d)?.checked_div(amount_a_times_coins)?;
+                d_prod = d_prod.checked_mul(d)?.checked_div(amount_b_times_coins)?;
+                d_prev = d;
+                d = self.compute_next_d(amp_factor, d, d_prod, sum_x)?;
+                // Equality with the precision of 1
+                if d > d_prev {
+                    if d.checked_sub(d_prev)? <= 1.into() {
+                        break;
+                    }
+                } else if d_prev.checked_sub(d)? <= 1.into() {
+                    break;
+                }
+            }
+
+            Some(d)
+        }
+    }
+
+    /// Compute the virtual price of a pool token, the invariant (D) per pool token
+    /// scaled by `VIRTUAL_PRICE_SCALE`
+    pub fn compute_virtual_price(
+        &self,
+        amount_a: U256,
+        amount_b: U256,
+        pool_token_supply: U256,
+    ) -> Option<U256> {
+        self.compute_d(amount_a, amount_b)?
+            .checked_mul(VIRTUAL_PRICE_SCALE.into())?
+            .checked_div(pool_token_supply)
+    }
+
+    /// Compute the marginal price of token A in token B at reserves `amount_a` and
+    /// `amount_b`, scaled by `scale`:
+    /// (Ann * x + D**3 / (4 * x * y)) * y / ((Ann * y + D**3 / (4 * x * y)) * x)
+    pub fn compute_spot_price(&self, amount_a: U256, amount_b: U256, scale: U256) -> Option<U256> {
+        let ann = self.compute_amp_factor()?.checked_mul(N_COINS.into())?;
+        let d = self.compute_d(amount_a, amount_b)?;
+        let d_prod = d
+            .checked_mul(d)?
+            .checked_div(amount_a.checked_mul(N_COINS.into())?)?
+            .checked_mul(d)?
+            .checked_div(amount_b.checked_mul(N_COINS.into())?)?;
+        ann.checked_mul(amount_a)?
+            .checked_add(d_prod)?
+            .checked_mul(amount_b)?
+            .checked_mul(scale)?
+            .checked_div(
+                ann.checked_mul(amount_b)?
+                    .checked_add(d_prod)?
+                    .checked_mul(amount_a)?,
+            )
+    }
+
+    /// Compute the amount of pool tokens to mint after a deposit
+    pub fn compute_mint_amount_for_deposit(
+        &self,
+        deposit_amount_a: U256,
+        deposit_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<U256> {
+        mint_amount_for_deposit(
+            |amount_a, amount_b| self.compute_d(amount_a, amount_b),
+            deposit_amount_a,
+            deposit_amount_b,
+            swap_amount_a,
+            swap_amount_b,
+            pool_token_supply,
+            fees,
+        )
+    }
+
+    /// Compute the amount of pool tokens to burn for a withdrawal of exact token
+    /// amounts, and the trade fees charged on the imbalance of the withdrawal
+    pub fn compute_burn_amount_for_withdraw(
+        &self,
+        withdraw_amount_a: U256,
+        withdraw_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256, U256)> {
+        burn_amount_for_withdraw(
+            |amount_a, amount_b| self.compute_d(amount_a, amount_b),
+            withdraw_amount_a,
+            withdraw_amount_b,
+            swap_amount_a,
+            swap_amount_b,
+            pool_token_supply,
+            fees,
+        )
+    }
+
+    /// Compute swap amount `y` in proportion to `x`
+    /// Solve for y:
+    /// y**2 + y * (sum' - (A*n**n - 1) * D / (A * n**n)) = D ** (n + 1) / (n ** (2 * n) * prod' * A)
+    /// y**2 + b*y = c
+    #[allow(clippy::many_single_char_names)]
+    pub fn compute_y(&self, x: U256, d: U256) -> Option<U256> {
+        let amp_factor = self.compute_amp_factor()?;
+        let ann: U256 = amp_factor.checked_mul(N_COINS.into())?; // A * n ** n
+
+        // sum' = prod' = x
+        // c =  D ** (n + 1) / (n ** (2 * n) * prod' * A)
+        let mut c = d
+            .checked_mul(d)?
+            .checked_div(x.checked_mul(N_COINS.into())?)?;
+        c = c
+            .checked_mul(d)?
+            .checked_div(ann.checked_mul(N_COINS.into())?)?;
+        // b = sum' - (A*n**n - 1) * D / (A * n**n)
+        let b = d.checked_div(ann)?.checked_add(x)?; // d is subtracted on line 147
+
+        // Solve for y by approximating: y**2 + b*y = c
+        let mut y_prev: U256;
+        let mut y = d;
+        for _ in 0..256 {
+            y_prev = y;
+            // y = (y * y + c) / (2 * y + b - d);
+            let y_numerator = y.checked_pow(2.into())?.checked_add(c)?;
+            let y_denominator = y.checked_mul(2.into())?.checked_add(b)?.checked_sub(d)?;
+            y = y_numerator.checked_div(y_denominator)?;
+            if y > y_prev {
+                if y.checked_sub(y_prev)? <= 1.into() {
+                    break;
+                }
+            } else if y_prev.checked_sub(y)? <= 1.into() {
+                break;
+            }
+        }
+
+        Some(y)
+    }
+
+    /// Calcuate withdrawal amount when withdrawing only one type of token
+    /// Calculation:
+    /// 1. Get current D
+    /// 2. Solve Eqn against y_i for D - _token_amount
+    pub fn compute_withdraw_one(
+        &self,
+        pool_token_amount: U256,
+        pool_token_supply: U256,
+        swap_base_amount: U256,  // Same denomination of token to be withdrawn
+        swap_quote_amount: U256, // Counter denomination of token to be withdrawn
+        fees: &Fees,
+    ) -> Option<(U256, U256)> {
+        withdraw_one(
+            |amount_a, amount_b| self.compute_d(amount_a, amount_b),
+            |x, d| self.compute_y(x, d),
+            pool_token_amount,
+            pool_token_supply,
+            swap_base_amount,
+            swap_quote_amount,
+            fees,
+        )
+    }
+
+    /// Compute SwapResult after an exchange
+    pub fn swap_to(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<SwapResult> {
+        let y = self.compute_y(
+            swap_source_amount.checked_add(source_amount)?,
+            self.compute_d(swap_source_amount, swap_destination_amount)?,
+        )?;
+        let dy = swap_destination_amount.checked_sub(y)?;
+        let dy_fee = fees.trade_fee(dy)?;
+        let admin_fee = fees.admin_trade_fee(dy_fee)?;
+
+        let amount_swapped = dy.checked_sub(dy_fee)?;
+        let new_destination_amount = swap_destination_amount
+            .checked_sub(amount_swapped)?
+            .checked_sub(admin_fee)?;
+        let new_source_amount = swap_source_amount.checked_add(source_amount)?;
+
+        Some(SwapResult {
+            new_source_amount,
+            new_destination_amount,
+            amount_swapped,
+            admin_fee,
+        })
+    }
+
+    /// Compute the destination token paid out by an exchange free of any fee
+    pub fn swap_without_fees(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+    ) -> Option<U256> {
+        let y = self.compute_y(
+            swap_source_amount.checked_add(source_amount)?,
+            self.compute_d(swap_source_amount, swap_destination_amount)?,
+        )?;
+        swap_destination_amount.checked_sub(y)
+    }
+
+    /// Compute SwapResult after an exchange. The trade fees are charged in the
+    /// destination token, or in the source token before the exchange when
+    /// `fees.fee_on_input` is set. Swaps imbalancing the pool past the imbalance
+    /// threshold pay the imbalance fee as part of the trade fee.
+    pub fn swap_to_v2(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<SwapResultV2> {
+        let d = self.compute_d(swap_source_amount, swap_destination_amount)?;
+        let (trade_fee, amount_swapped) = self.swap_amounts(
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            d,
+            fees,
+        )?;
+        swap_result(
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            trade_fee,
+            amount_swapped,
+            fees,
+        )
+    }
+
+    /// Quote an exchange as `swap_to_v2` does, into the `quote` buffer of the
+    /// caller: the destination token paid out, the trade fee, the admin fee and
+    /// the new source and destination amounts of the pool, as little-endian
+    /// `u64`s in that order. Runs on the stack only, for programs quoting the
+    /// pool over CPI within their own compute budget.
+    pub fn quote_in_place(
+        &self,
+        source_amount: u64,
+        swap_source_amount: u64,
+        swap_destination_amount: u64,
+        fees: &Fees,
+        quote: &mut [u8; QUOTE_LEN],
+    ) -> Option<()> {
+        let result = self.swap_to_v2(
+            source_amount.into(),
+            swap_source_amount.into(),
+            swap_destination_amount.into(),
+            fees,
+        )?;
+        for (field, amount) in quote.chunks_exact_mut(8).zip([
+            result.amount_swapped,
+            result.trade_fee,
+            result.admin_fee,
+            result.new_source_amount,
+            result.new_destination_amount,
+        ]) {
+            field.copy_from_slice(&u64::try_from(amount).ok()?.to_le_bytes());
+        }
+        Some(())
+    }
+
+    /// Compute the smallest SOURCE amount for which `swap_to_v2` pays out at
+    /// least `amount_out` of the destination token
+    pub fn compute_swap_in(
+        &self,
+        amount_out: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<U256> {
+        let d = self.compute_d(swap_source_amount, swap_destination_amount)?;
+        // Solve the invariant for the source reserve after the swap, grossing up
+        // the amount the trade fee is charged on
+        let estimate_swap_in = |fees: &Fees| {
+            let fee_denominator = U256::from(fees.trade_fee_denominator);
+            let net_fee_denominator =
+                fee_denominator.checked_sub(fees.trade_fee_numerator.into())?;
+            let dy = if fees.fee_on_input {
+                amount_out
+            } else {
+                ceil_div(
+                    amount_out.checked_mul(fee_denominator)?,
+                    net_fee_denominator,
+                )?
+            };
+            let x = self.compute_y(swap_destination_amount.checked_sub(dy)?, d)?;
+            // The rounding of `compute_y` may pay out a token for nothing
+            let dx = x.saturating_sub(swap_source_amount);
+            if fees.fee_on_input {
+                ceil_div(dx.checked_mul(fee_denominator)?, net_fee_denominator)
+            } else {
+                Some(dx)
+            }
+        };
+        let mut dx = estimate_swap_in(fees)?;
+        // The imbalance fee grows with the amount swapped, estimate again at the
+        // fees of the previous estimate until they settle
+        if fees.is_imbalance_fee_enabled() {
+            for _ in 0..MAX_SWAP_IN_ADJUSTMENTS {
+                let swap_fees =
+                    self.swap_fees(dx, swap_source_amount, swap_destination_amount, d, fees)?;
+                let next_dx = estimate_swap_in(&swap_fees)?;
+                if next_dx == dx {
+                    break;
+                }
+                dx = next_dx;
+            }
+        }
+
+        let amount_swapped = |dx: U256| {
+            self.swap_amounts(dx, swap_source_amount, swap_destination_amount, d, fees)
+                .map(|(_trade_fee, amount_swapped)| amount_swapped)
+        };
+        settle_swap_in(dx, amount_out, amount_swapped)
+    }
+
+    /// Compute the fees of an exchange, given the invariant `d` of the reserves:
+    /// `fees` with the imbalance fee of the reserves after the same exchange
+    /// without fees
+    fn swap_fees(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        d: U256,
+        fees: &Fees,
+    ) -> Option<Fees> {
+        if !fees.is_imbalance_fee_enabled() {
+            return Some(*fees);
+        }
+        let new_source_amount = swap_source_amount.checked_add(source_amount)?;
+        let new_destination_amount = self.compute_y(new_source_amount, d)?;
+        fees.with_imbalance_fee(
+            imbalance_bps(swap_source_amount, swap_destination_amount)?,
+            imbalance_bps(new_source_amount, new_destination_amount)?,
+        )
+    }
+
+    /// Compute the trade fee and the destination token paid out by an exchange,
+    /// given the invariant `d` of the reserves
+    fn swap_amounts(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        d: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256)> {
+        let fees = &self.swap_fees(
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            d,
+            fees,
+        )?;
+        if fees.fee_on_input {
+            let dx_fee = fees.trade_fee(source_amount)?;
+            let y = self.compute_y(
+                swap_source_amount.checked_add(source_amount.checked_sub(dx_fee)?)?,
+                d,
+            )?;
+            Some((dx_fee, swap_destination_amount.checked_sub(y)?))
+        } else {
+            let y = self.compute_y(swap_source_amount.checked_add(source_amount)?, d)?;
+            let dy = swap_destination_amount.checked_sub(y)?;
+            let dy_fee = fees.trade_fee(dy)?;
+            Some((dy_fee, dy.checked_sub(dy_fee)?))
+        }
+    }
+}
+
+/// Curve the tokens of a pool are priced along
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CurveType {
+    /// StableSwap invariant, for tokens of about the same value
+    #[default]
+    StableSwap = 0,
+    /// Constant product `x * y = k`, for tokens of unrelated values
+    ConstantProduct = 1,
+    /// Constant product of virtual reserves, concentrating the liquidity within a
+    /// band of prices, for pegged tokens
+    PriceBounded = 2,
+}
+
+impl CurveType {
+    /// Curve type of its byte, as packed in account and instruction data
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::StableSwap),
+            1 => Some(Self::ConstantProduct),
+            2 => Some(Self::PriceBounded),
+            _ => None,
+        }
+    }
+}
+
+/// Pricing of the exchanges, deposits and withdrawals of a pool of two tokens.
+/// See `StableSwap` for the meaning of each method.
+pub trait SwapCurve {
+    /// Compute the invariant (D) of the reserves, in units of the tokens
+    fn compute_d(&self, amount_a: U256, amount_b: U256) -> Option<U256>;
+
+    /// Compute the virtual price of a pool token
+    fn compute_virtual_price(
+        &self,
+        amount_a: U256,
+        amount_b: U256,
+        pool_token_supply: U256,
+    ) -> Option<U256> {
+        self.compute_d(amount_a, amount_b)?
+            .checked_mul(VIRTUAL_PRICE_SCALE.into())?
+            .checked_div(pool_token_supply)
+    }
+
+    /// Compute the marginal price of token A in token B, scaled by `scale`
+    fn compute_spot_price(&self, amount_a: U256, amount_b: U256, scale: U256) -> Option<U256>;
+
+    /// Compute SwapResultV2 after an exchange
+    fn swap_to_v2(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<SwapResultV2>;
+
+    /// Compute the destination token paid out by an exchange free of any fee
+    fn swap_without_fees(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+    ) -> Option<U256>;
+
+    /// Compute the smallest SOURCE amount for which `swap_to_v2` pays out at
+    /// least `amount_out` of the destination token
+    fn compute_swap_in(
+        &self,
+        amount_out: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<U256>;
+
+    /// Compute the amount of pool tokens to mint after a deposit
+    fn compute_mint_amount_for_deposit(
+        &self,
+        deposit_amount_a: U256,
+        deposit_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<U256>;
+
+    /// Compute the amount of pool tokens to burn for a withdrawal of exact token
+    /// amounts, and the trade fees charged on the imbalance of the withdrawal
+    fn compute_burn_amount_for_withdraw(
+        &self,
+        withdraw_amount_a: U256,
+        withdraw_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256, U256)>;
+
+    /// Compute the token paid out and the trade fee of a withdrawal of a single
+    /// token
+    fn compute_withdraw_one(
+        &self,
+        pool_token_amount: U256,
+        pool_token_supply: U256,
+        swap_base_amount: U256,
+        swap_quote_amount: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256)>;
+}
+
+impl SwapCurve for StableSwap {
+    fn compute_d(&self, amount_a: U256, amount_b: U256) -> Option<U256> {
+        StableSwap::compute_d(self, amount_a, amount_b)
+    }
+
+    fn compute_spot_price(&self, amount_a: U256, amount_b: U256, scale: U256) -> Option<U256> {
+        StableSwap::compute_spot_price(self, amount_a, amount_b, scale)
+    }
+
+    fn swap_to_v2(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<SwapResultV2> {
+        StableSwap::swap_to_v2(
+            self,
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            fees,
+        )
+    }
+
+    fn swap_without_fees(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+    ) -> Option<U256> {
+        StableSwap::swap_without_fees(
+            self,
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+        )
+    }
+
+    fn compute_swap_in(
+        &self,
+        amount_out: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<U256> {
+        StableSwap::compute_swap_in(
+            self,
+            amount_out,
+            swap_source_amount,
+            swap_destination_amount,
+            fees,
+        )
+    }
+
+    fn compute_mint_amount_for_deposit(
+        &self,
+        deposit_amount_a: U256,
+        deposit_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<U256> {
+        StableSwap::compute_mint_amount_for_deposit(
+            self,
+            deposit_amount_a,
+            deposit_amount_b,
+            swap_amount_a,
+            swap_amount_b,
+            pool_token_supply,
+            fees,
+        )
+    }
+
+    fn compute_burn_amount_for_withdraw(
+        &self,
+        withdraw_amount_a: U256,
+        withdraw_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256, U256)> {
+        StableSwap::compute_burn_amount_for_withdraw(
+            self,
+            withdraw_amount_a,
+            withdraw_amount_b,
+            swap_amount_a,
+            swap_amount_b,
+            pool_token_supply,
+            fees,
+        )
+    }
+
+    fn compute_withdraw_one(
+        &self,
+        pool_token_amount: U256,
+        pool_token_supply: U256,
+        swap_base_amount: U256,
+        swap_quote_amount: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256)> {
+        StableSwap::compute_withdraw_one(
+            self,
+            pool_token_amount,
+            pool_token_supply,
+            swap_base_amount,
+            swap_quote_amount,
+            fees,
+        )
+    }
+}
+
+/// The constant product `x * y = k` curve. Its invariant (D) is the geometric
+/// mean of the reserves, `sqrt(x * y)`, and its swaps charge no imbalance fee.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ConstantProduct;
+
+impl ConstantProduct {
+    /// Compute the reserve `y` keeping the invariant `d` at the reserve `x` of the
+    /// other token, rounded up
+    fn compute_y(&self, x: U256, d: U256) -> Option<U256> {
+        ceil_div(d.checked_mul(d)?, x)
+    }
+
+    /// Compute the trade fee and the destination token paid out by an exchange
+    fn swap_amounts(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256)> {
+        if fees.fee_on_input {
+            let dx_fee = fees.trade_fee(source_amount)?;
+            let dy = self.swap_without_fees(
+                source_amount.checked_sub(dx_fee)?,
+                swap_source_amount,
+                swap_destination_amount,
+            )?;
+            Some((dx_fee, dy))
+        } else {
+            let dy =
+                self.swap_without_fees(source_amount, swap_source_amount, swap_destination_amount)?;
+            let dy_fee = fees.trade_fee(dy)?;
+            Some((dy_fee, dy.checked_sub(dy_fee)?))
+        }
+    }
+}
+
+impl SwapCurve for ConstantProduct {
+    fn compute_d(&self, amount_a: U256, amount_b: U256) -> Option<U256> {
+        Some(amount_a.checked_mul(amount_b)?.integer_sqrt())
+    }
+
+    fn compute_spot_price(&self, amount_a: U256, amount_b: U256, scale: U256) -> Option<U256> {
+        amount_b.checked_mul(scale)?.checked_div(amount_a)
+    }
+
+    fn swap_to_v2(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<SwapResultV2> {
+        let (trade_fee, amount_swapped) = self.swap_amounts(
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            fees,
+        )?;
+        swap_result(
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            trade_fee,
+            amount_swapped,
+            fees,
+        )
+    }
+
+    fn swap_without_fees(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+    ) -> Option<U256> {
+        // dy = y * dx / (x + dx), rounded down
+        swap_destination_amount
+            .checked_mul(source_amount)?
+            .checked_div(swap_source_amount.checked_add(source_amount)?)
+    }
+
+    fn compute_swap_in(
+        &self,
+        amount_out: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<U256> {
+        // dx = x * dy / (y - dy), grossing up the amount the trade fee is charged on
+        let fee_denominator = U256::from(fees.trade_fee_denominator);
+        let net_fee_denominator = fee_denominator.checked_sub(fees.trade_fee_numerator.into())?;
+        let dy = if fees.fee_on_input {
+            amount_out
+        } else {
+            ceil_div(
+                amount_out.checked_mul(fee_denominator)?,
+                net_fee_denominator,
+            )?
+        };
+        let dx = ceil_div(
+            swap_source_amount.checked_mul(dy)?,
+            swap_destination_amount.checked_sub(dy)?,
+        )?;
+        let dx = if fees.fee_on_input {
+            ceil_div(dx.checked_mul(fee_denominator)?, net_fee_denominator)?
+        } else {
+            dx
+        };
+        settle_swap_in(dx, amount_out, |dx| {
+            self.swap_amounts(dx, swap_source_amount, swap_destination_amount, fees)
+                .map(|(_trade_fee, amount_swapped)| amount_swapped)
+        })
+    }
+
+    fn compute_mint_amount_for_deposit(
+        &self,
+        deposit_amount_a: U256,
+        deposit_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<U256> {
+        mint_amount_for_deposit(
+            |amount_a, amount_b| self.compute_d(amount_a, amount_b),
+            deposit_amount_a,
+            deposit_amount_b,
+            swap_amount_a,
+            swap_amount_b,
+            pool_token_supply,
+            fees,
+        )
+    }
+
+    fn compute_burn_amount_for_withdraw(
+        &self,
+        withdraw_amount_a: U256,
+        withdraw_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256, U256)> {
+        burn_amount_for_withdraw(
+            |amount_a, amount_b| self.compute_d(amount_a, amount_b),
+            withdraw_amount_a,
+            withdraw_amount_b,
+            swap_amount_a,
+            swap_amount_b,
+            pool_token_supply,
+            fees,
+        )
+    }
+
+    fn compute_withdraw_one(
+        &self,
+        pool_token_amount: U256,
+        pool_token_supply: U256,
+        swap_base_amount: U256,
+        swap_quote_amount: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256)> {
+        withdraw_one(
+            |amount_a, amount_b| self.compute_d(amount_a, amount_b),
+            |x, d| self.compute_y(x, d),
+            pool_token_amount,
+            pool_token_supply,
+            swap_base_amount,
+            swap_quote_amount,
+            fees,
+        )
+    }
+}
+
+/// The constant product curve of virtual reserves, providing all of its
+/// liquidity while the price of either token in the other stays within
+/// `[1 / price_bound, price_bound]`. The liquidity (L) of reserves `x` and `y`
+/// solves `(x + L / sqrt(price_bound)) * (y + L / sqrt(price_bound)) = L^2`, and
+/// swaps trade along the constant product of those virtual reserves until one of
+/// the real reserves runs out at a bound. L is the invariant (D) of the curve.
+///
+/// The band is the same for both tokens since the curve is not told which
+/// reserve is token A's.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PriceBounded {
+    /// `PRICE_BOUND_SCALE / sqrt(price_bound)`, scaled by `PRICE_BOUND_SCALE`
+    inverse_sqrt_bound: U256,
+}
+
+impl PriceBounded {
+    /// New curve of the price bound `price_bound`, scaled by `PRICE_BOUND_SCALE`.
+    /// Bounds at or below `PRICE_BOUND_SCALE` leave no band, every calculation
+    /// then fails.
+    pub fn new(price_bound: u64) -> Self {
+        let scale = U256::from(PRICE_BOUND_SCALE);
+        let inverse_sqrt_bound = scale
+            .checked_mul(scale)
+            .and_then(|scale_2| scale_2.checked_mul(scale))
+            .and_then(|scale_3| scale_3.checked_div(price_bound.into()))
+            .map_or(U256::MAX, U256::integer_sqrt);
+        Self { inverse_sqrt_bound }
+    }
+
+    /// Compute the amount of both virtual reserves beyond the real ones at the
+    /// liquidity `d`, rounded down
+    fn virtual_offset(&self, d: U256) -> Option<U256> {
+        d.checked_mul(self.inverse_sqrt_bound)?
+            .checked_div(PRICE_BOUND_SCALE.into())
+    }
+
+    /// Compute the virtual reserves of the real reserves `amount_a` and `amount_b`
+    fn virtual_reserves(&self, amount_a: U256, amount_b: U256) -> Option<(U256, U256)> {
+        let offset = self.virtual_offset(self.compute_d(amount_a, amount_b)?)?;
+        Some((amount_a.checked_add(offset)?, amount_b.checked_add(offset)?))
+    }
+
+    /// Compute the real reserve `y` keeping the liquidity `d` at the real reserve
+    /// `x` of the other token, rounded up
+    fn compute_y(&self, x: U256, d: U256) -> Option<U256> {
+        let offset = self.virtual_offset(d)?;
+        let virtual_y = ceil_div(d.checked_mul(d)?, x.checked_add(offset)?)?;
+        Some(virtual_y.saturating_sub(offset))
+    }
+}
+
+impl SwapCurve for PriceBounded {
+    fn compute_d(&self, amount_a: U256, amount_b: U256) -> Option<U256> {
+        // With r = 1 / sqrt(price_bound) scaled by s, and k = s^2 - r^2, the
+        // positive root of k L^2 - s r (x + y) L - s^2 x y = 0 is
+        // L = s (r (x + y) + sqrt(r^2 (x + y)^2 + 4 k x y)) / 2k
+        let scale = U256::from(PRICE_BOUND_SCALE);
+        let r = self.inverse_sqrt_bound;
+        let k = scale.checked_mul(scale)?.checked_sub(r.checked_mul(r)?)?;
+        if k.is_zero() {
+            return None;
+        }
+        let b = r.checked_mul(amount_a.checked_add(amount_b)?)?;
+        let discriminant = b.checked_mul(b)?.checked_add(
+            k.checked_mul(amount_a)?
+                .checked_mul(amount_b)?
+                .checked_mul(4.into())?,
+        )?;
+        scale
+            .checked_mul(b.checked_add(discriminant.integer_sqrt())?)?
+            .checked_div(k.checked_mul(2.into())?)
+    }
+
+    fn compute_spot_price(&self, amount_a: U256, amount_b: U256, scale: U256) -> Option<U256> {
+        let (virtual_a, virtual_b) = self.virtual_reserves(amount_a, amount_b)?;
+        ConstantProduct.compute_spot_price(virtual_a, virtual_b, scale)
+    }
+
+    fn swap_to_v2(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<SwapResultV2> {
+        let (virtual_source, virtual_destination) =
+            self.virtual_reserves(swap_source_amount, swap_destination_amount)?;
+        let (trade_fee, amount_swapped) = ConstantProduct.swap_amounts(
+            source_amount,
+            virtual_source,
+            virtual_destination,
+            fees,
+        )?;
+        swap_result(
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            trade_fee,
+            amount_swapped,
+            fees,
+        )
+    }
+
+    fn swap_without_fees(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+    ) -> Option<U256> {
+        let (virtual_source, virtual_destination) =
+            self.virtual_reserves(swap_source_amount, swap_destination_amount)?;
+        let amount_swapped = ConstantProduct.swap_without_fees(
+            source_amount,
+            virtual_source,
+            virtual_destination,
+        )?;
+        // past the bound of the price
+        if amount_swapped > swap_destination_amount {
+            return None;
+        }
+        Some(amount_swapped)
+    }
+
+    fn compute_swap_in(
+        &self,
+        amount_out: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<U256> {
+        if amount_out > swap_destination_amount {
+            return None;
+        }
+        let (virtual_source, virtual_destination) =
+            self.virtual_reserves(swap_source_amount, swap_destination_amount)?;
+        ConstantProduct.compute_swap_in(amount_out, virtual_source, virtual_destination, fees)
+    }
+
+    fn compute_mint_amount_for_deposit(
+        &self,
+        deposit_amount_a: U256,
+        deposit_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<U256> {
+        mint_amount_for_deposit(
+            |amount_a, amount_b| self.compute_d(amount_a, amount_b),
+            deposit_amount_a,
+            deposit_amount_b,
+            swap_amount_a,
+            swap_amount_b,
+            pool_token_supply,
+            fees,
+        )
+    }
+
+    fn compute_burn_amount_for_withdraw(
+        &self,
+        withdraw_amount_a: U256,
+        withdraw_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256, U256)> {
+        burn_amount_for_withdraw(
+            |amount_a, amount_b| self.compute_d(amount_a, amount_b),
+            withdraw_amount_a,
+            withdraw_amount_b,
+            swap_amount_a,
+            swap_amount_b,
+            pool_token_supply,
+            fees,
+        )
+    }
+
+    fn compute_withdraw_one(
+        &self,
+        pool_token_amount: U256,
+        pool_token_supply: U256,
+        swap_base_amount: U256,
+        swap_quote_amount: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256)> {
+        withdraw_one(
+            |amount_a, amount_b| self.compute_d(amount_a, amount_b),
+            |x, d| self.compute_y(x, d),
+            pool_token_amount,
+            pool_token_supply,
+            swap_base_amount,
+            swap_quote_amount,
+            fees,
+        )
+    }
+}
+
+/// Curve of a pool, of the `CurveType` the pool was initialized with
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PoolCurve {
+    /// StableSwap invariant at the current amplification
+    StableSwap(StableSwap),
+    /// Constant product
+    ConstantProduct(ConstantProduct),
+    /// Constant product of virtual reserves within a price band
+    PriceBounded(PriceBounded),
+}
+
+impl PoolCurve {
+    fn curve(&self) -> &dyn SwapCurve {
+        match self {
+            Self::StableSwap(curve) => curve,
+            Self::ConstantProduct(curve) => curve,
+            Self::PriceBounded(curve) => curve,
+        }
+    }
+}
+
+impl SwapCurve for PoolCurve {
+    fn compute_d(&self, amount_a: U256, amount_b: U256) -> Option<U256> {
+        self.curve().compute_d(amount_a, amount_b)
+    }
+
+    fn compute_virtual_price(
+        &self,
+        amount_a: U256,
+        amount_b: U256,
+        pool_token_supply: U256,
+    ) -> Option<U256> {
+        self.curve()
+            .compute_virtual_price(amount_a, amount_b, pool_token_supply)
+    }
+
+    fn compute_spot_price(&self, amount_a: U256, amount_b: U256, scale: U256) -> Option<U256> {
+        self.curve().compute_spot_price(amount_a, amount_b, scale)
+    }
+
+    fn swap_to_v2(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<SwapResultV2> {
+        self.curve().swap_to_v2(
+            source_amount,
+            swap_source_amount,
+            swap_destination_amount,
+            fees,
+        )
+    }
+
+    fn swap_without_fees(
+        &self,
+        source_amount: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+    ) -> Option<U256> {
+        self.curve()
+            .swap_without_fees(source_amount, swap_source_amount, swap_destination_amount)
+    }
+
+    fn compute_swap_in(
+        &self,
+        amount_out: U256,
+        swap_source_amount: U256,
+        swap_destination_amount: U256,
+        fees: &Fees,
+    ) -> Option<U256> {
+        self.curve().compute_swap_in(
+            amount_out,
+            swap_source_amount,
+            swap_destination_amount,
+            fees,
+        )
+    }
+
+    fn compute_mint_amount_for_deposit(
+        &self,
+        deposit_amount_a: U256,
+        deposit_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<U256> {
+        self.curve().compute_mint_amount_for_deposit(
+            deposit_amount_a,
+            deposit_amount_b,
+            swap_amount_a,
+            swap_amount_b,
+            pool_token_supply,
+            fees,
+        )
+    }
+
+    fn compute_burn_amount_for_withdraw(
+        &self,
+        withdraw_amount_a: U256,
+        withdraw_amount_b: U256,
+        swap_amount_a: U256,
+        swap_amount_b: U256,
+        pool_token_supply: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256, U256)> {
+        self.curve().compute_burn_amount_for_withdraw(
+            withdraw_amount_a,
+            withdraw_amount_b,
+            swap_amount_a,
+            swap_amount_b,
+            pool_token_supply,
+            fees,
+        )
+    }
+
+    fn compute_withdraw_one(
+        &self,
+        pool_token_amount: U256,
+        pool_token_supply: U256,
+        swap_base_amount: U256,
+        swap_quote_amount: U256,
+        fees: &Fees,
+    ) -> Option<(U256, U256)> {
+        self.curve().compute_withdraw_one(
+            pool_token_amount,
+            pool_token_supply,
+            swap_base_amount,
+            swap_quote_amount,
+            fees,
+        )
+    }
+}
+
+/// Pool tokens to mint after a deposit, for a curve of invariant `compute_d`
+fn mint_amount_for_deposit(
+    compute_d: impl Fn(U256, U256) -> Option<U256>,
+    deposit_amount_a: U256,
+    deposit_amount_b: U256,
+    swap_amount_a: U256,
+    swap_amount_b: U256,
+    pool_token_supply: U256,
+    fees: &Fees,
+) -> Option<U256> {
+    // Initial invariant
+    let d_0 = compute_d(swap_amount_a, swap_amount_b)?;
+    let old_balances = [swap_amount_a, swap_amount_b];
+    let mut new_balances = [
+        swap_amount_a.checked_add(deposit_amount_a)?,
+        swap_amount_b.checked_add(deposit_amount_b)?,
+    ];
+    // Invariant after change
+    let d_1 = compute_d(new_balances[0], new_balances[1])?;
+    if d_1 <= d_0 {
+        None
+    } else {
+        // Recalculate the invariant accounting for fees
+        for i in 0..new_balances.len() {
+            let ideal_balance = d_1.checked_mul(old_balances[i])?.checked_div(d_0)?;
+            let difference = if ideal_balance > new_balances[i] {
+                ideal_balance.checked_sub(new_balances[i])?
+            } else {
+                new_balances[i].checked_sub(ideal_balance)?
+            };
+            let fee = fees.normalized_trade_fee(N_COINS, difference)?;
+            new_balances[i] = new_balances[i].checked_sub(fee)?;
+        }
+
+        let d_2 = compute_d(new_balances[0], new_balances[1])?;
+        let mint_amount_numerator = pool_token_supply.checked_mul(d_2.checked_sub(d_0)?)?;
+        let mint_amount = mint_amount_numerator.checked_div(d_0)?;
+
+        Some(mint_amount)
+    }
+}
+
+/// Pool tokens to burn for a withdrawal of exact token amounts and the trade
+/// fees charged on its imbalance, for a curve of invariant `compute_d`
+fn burn_amount_for_withdraw(
+    compute_d: impl Fn(U256, U256) -> Option<U256>,
+    withdraw_amount_a: U256,
+    withdraw_amount_b: U256,
+    swap_amount_a: U256,
+    swap_amount_b: U256,
+    pool_token_supply: U256,
+    fees: &Fees,
+) -> Option<(U256, U256, U256)> {
+    // Initial invariant
+    let d_0 = compute_d(swap_amount_a, swap_amount_b)?;
+    let old_balances = [swap_amount_a, swap_amount_b];
+    let mut new_balances = [
+        swap_amount_a.checked_sub(withdraw_amount_a)?,
+        swap_amount_b.checked_sub(withdraw_amount_b)?,
+    ];
+    // Invariant after change
+    let d_1 = compute_d(new_balances[0], new_balances[1])?;
+    if d_1 >= d_0 {
+        return None;
+    }
+    // Recalculate the invariant accounting for fees
+    let mut trade_fees = [U256::zero(); 2];
+    for i in 0..new_balances.len() {
+        let ideal_balance = d_1.checked_mul(old_balances[i])?.checked_div(d_0)?;
+        let difference = if ideal_balance > new_balances[i] {
+            ideal_balance.checked_sub(new_balances[i])?
+        } else {
+            new_balances[i].checked_sub(ideal_balance)?
+        };
+        trade_fees[i] = fees.normalized_trade_fee(N_COINS, difference)?;
+        new_balances[i] = new_balances[i].checked_sub(trade_fees[i])?;
+    }
+
+    let d_2 = compute_d(new_balances[0], new_balances[1])?;
+    // Round the burn up, in favor of the pool
+    let burn_amount = ceil_div(pool_token_supply.checked_mul(d_0.checked_sub(d_2)?)?, d_0)?;
+
+    Some((burn_amount, trade_fees[0], trade_fees[1]))
+}
+
+/// Token paid out and trade fee of a withdrawal of a single token, for a curve
+/// of invariant `compute_d` solved for one token by `compute_y`
+fn withdraw_one(
+    compute_d: impl Fn(U256, U256) -> Option<U256>,
+    compute_y: impl Fn(U256, U256) -> Option<U256>,
+    pool_token_amount: U256,
+    pool_token_supply: U256,
+    swap_base_amount: U256,
+    swap_quote_amount: U256,
+    fees: &Fees,
+) -> Option<(U256, U256)> {
+    let d_0 = compute_d(swap_base_amount, swap_quote_amount)?;
+    let d_1 = d_0.checked_sub(
+        pool_token_amount
+            .checked_mul(d_0)?
+            .checked_div(pool_token_supply)?,
+    )?;
+    let new_y = compute_y(swap_quote_amount, d_1)?;
+
+    // expected_base_amount = swap_base_amount * d_1 / d_0 - new_y;
+    let expected_base_amount = swap_base_amount
+        .checked_mul(d_1)?
+        .checked_div(d_0)?
+        .checked_sub(new_y)?;
+    // expected_quote_amount = swap_quote_amount - swap_quote_amount * d_1 / d_0;
+    let expected_quote_amount =
+        swap_quote_amount.checked_sub(swap_quote_amount.checked_mul(d_1)?.checked_div(d_0)?)?;
+    // new_base_amount = swap_base_amount - expected_base_amount * fee / fee_denominator;
+    let new_base_amount =
+        swap_base_amount.checked_sub(fees.normalized_trade_fee(N_COINS, expected_base_amount)?)?;
+    // new_quote_amount = swap_quote_amount - expected_quote_amount * fee / fee_denominator;
+    let new_quote_amount = swap_quote_amount
+        .checked_sub(fees.normalized_trade_fee(N_COINS, expected_quote_amount)?)?;
+    let dy = new_base_amount
+        .checked_sub(compute_y(new_quote_amount, d_1)?)?
+        .checked_sub(1.into())?; // Withdraw less to account for rounding errors
+    let dy_0 = swap_base_amount.checked_sub(new_y)?;
+
+    Some((dy, dy_0 - dy))
+}
+
+/// SwapResultV2 of an exchange paying out `amount_swapped` and charging
+/// `trade_fee`, shared among the fees of `fees`
+fn swap_result(
+    source_amount: U256,
+    swap_source_amount: U256,
+    swap_destination_amount: U256,
+    trade_fee: U256,
+    amount_swapped: U256,
+    fees: &Fees,
+) -> Option<SwapResultV2> {
+    let reflection_fee = fees.reflection_fee(trade_fee)?;
+    let buyback_fee = fees.buyback_fee(trade_fee)?;
+    let marketing_fee = fees.marketing_fee(trade_fee)?;
+    let developer_fee = fees.developer_fee(trade_fee)?;
+    let admin_fee = fees.admin_trade_fee(trade_fee)?;
+
+    let distributed_fees = reflection_fee
+        .checked_add(buyback_fee)?
+        .checked_add(marketing_fee)?
+        .checked_add(developer_fee)?;
+    let mut new_source_amount = swap_source_amount.checked_add(source_amount)?;
+    let mut new_destination_amount = swap_destination_amount.checked_sub(amount_swapped)?;
+    if fees.fee_on_input {
+        new_source_amount = new_source_amount.checked_sub(distributed_fees)?;
+    } else {
+        new_destination_amount = new_destination_amount.checked_sub(distributed_fees)?;
+    }
+
+    Some(SwapResultV2 {
+        new_source_amount,
+        new_destination_amount,
+        amount_swapped,
+        trade_fee,
+        reflection_fee,
+        buyback_fee,
+        marketing_fee,
+        developer_fee,
+        admin_fee,
+    })
+}
+
+/// Smallest SOURCE amount for which `amount_swapped` pays out at least
+/// `amount_out`, searched from the estimate `dx`
+fn settle_swap_in(
+    dx: U256,
+    amount_out: U256,
+    amount_swapped: impl Fn(U256) -> Option<U256>,
+) -> Option<U256> {
+    // Settle the rounding of the fees and of `compute_y` against the swap itself,
+    // with steps doubling away from the estimate until `lo` pays out less than
+    // `amount_out` and `hi` enough, then by bisection. The estimate may be off
+    // by several tokens where a destination token is worth them.
+    let (mut lo, mut hi) = if amount_swapped(dx)? < amount_out {
+        let mut lo = dx;
+        let mut step = U256::one();
+        loop {
+            let hi = dx.checked_add(step)?;
+            if amount_swapped(hi)? >= amount_out {
+                break (lo, hi);
+            }
+            lo = hi;
+            step = step.checked_mul(2.into())?;
+        }
+    } else {
+        let mut hi = dx;
+        let mut step = U256::one();
+        loop {
+            if hi.is_zero() {
+                return Some(hi);
+            }
+            let lo = dx.saturating_sub(step);
+            if amount_swapped(lo)? < amount_out {
+                break (lo, hi);
+            }
+            hi = lo;
+            step = step.checked_mul(2.into())?;
+        }
+    };
+    while hi.checked_sub(lo)? > U256::one() {
+        let mid = lo.checked_add(hi.checked_sub(lo)? / 2)?;
+        if amount_swapped(mid)? < amount_out {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(hi)
+}
+
+fn ceil_div(numerator: U256, denominator: U256) -> Option<U256> {
+    numerator
+        .checked_add(denominator.checked_sub(1.into())?)?
+        .checked_div(denominator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rand::Rng;
+    use sim::{Model, MODEL_FEE_DENOMINATOR, MODEL_FEE_NUMERATOR};
+    use std::{cmp, convert::TryInto};
+
+    const MODEL_FEES: Fees = Fees {
+        admin_trade_fee_numerator: 0,
+        admin_trade_fee_denominator: 1,
+        admin_withdraw_fee_numerator: 0,
+        admin_withdraw_fee_denominator: 1,
+        trade_fee_numerator: MODEL_FEE_NUMERATOR,
+        trade_fee_denominator: MODEL_FEE_DENOMINATOR,
+        withdraw_fee_numerator: 0,
+        withdraw_fee_denominator: 1,
+        reflection_fee_numerator: 0,
+        reflection_fee_denominator: 1,
+        buyback_fee_numerator: 0,
+        buyback_fee_denominator: 1,
+        marketing_fee_numerator: 0,
+        marketing_fee_denominator: 1,
+        developer_fee_numerator: 0,
+        developer_fee_denominator: 1,
+        admin_fee_in_pool_tokens: false,
+        fee_on_input: false,
+        imbalance_fee_numerator: 0,
+        imbalance_fee_denominator: 1,
+        imbalance_threshold_bps: 0,
+        host_fee_numerator: 0,
+        host_fee_denominator: 1,
+    };
+
+    const RAMP_TICKS: i64 = 100000;
+
+    #[test]
+    fn test_ramp_amp_up() {
+        let mut rng = rand::thread_rng();
+        let initial_amp_factor = 100;
+        let target_amp_factor = initial_amp_factor * 2;
+        let start_ramp_ts = rng.gen_range(ZERO_TS, i64::MAX - RAMP_TICKS);
+        let stop_ramp_ts = start_ramp_ts + MIN_RAMP_DURATION;
+        println!(
+            "start_ramp_ts: {}, stop_ramp_ts: {}",
+            start_ramp_ts, stop_ramp_ts
+        );
+
+        for tick in 0..RAMP_TICKS {
+            let current_ts = start_ramp_ts + tick;
+            let invariant = StableSwap::new(
+                initial_amp_factor,
+                target_amp_factor,
+                current_ts,
+                start_ramp_ts,
+                stop_ramp_ts,
+            );
+            let expected = if tick >= MIN_RAMP_DURATION {
+                target_amp_factor
+            } else {
+                initial_amp_factor + (initial_amp_factor * tick as u64 / MIN_RAMP_DURATION as u64)
+            };
+            assert_eq!(invariant.compute_amp_factor().unwrap(), expected.into());
+        }
+    }
+
+    #[test]
+    fn test_ramp_amp_before_start() {
+        let start_ramp_ts = MIN_RAMP_DURATION;
+        let stop_ramp_ts = start_ramp_ts + MIN_RAMP_DURATION;
+        for current_ts in [ZERO_TS, start_ramp_ts - 1, start_ramp_ts] {
+            let invariant = StableSwap::new(100, 200, current_ts, start_ramp_ts, stop_ramp_ts);
+            assert_eq!(invariant.compute_amp_factor().unwrap(), 100.into());
+        }
+
+        // Ramp stopping before it started
+        let invariant = StableSwap::new(100, 200, ZERO_TS, stop_ramp_ts, start_ramp_ts);
+        assert_eq!(invariant.compute_amp_factor(), None);
+    }
+
+    #[test]
+    fn test_ramp_amp_down() {
+        let mut rng = rand::thread_rng();
+        let initial_amp_factor = 100;
+        let target_amp_factor = initial_amp_factor / 10;
+        let amp_range = initial_amp_factor - target_amp_factor;
+        let start_ramp_ts = rng.gen_range(ZERO_TS, i64::MAX - RAMP_TICKS);
+        let stop_ramp_ts = start_ramp_ts + MIN_RAMP_DURATION;
+        println!(
+            "start_ramp_ts: {}, stop_ramp_ts: {}",
+            start_ramp_ts, stop_ramp_ts
+        );
+
+        for tick in 0..RAMP_TICKS {
+            let current_ts = start_ramp_ts + tick;
+            let invariant = StableSwap::new(
+                initial_amp_factor,
+                target_amp_factor,
+                current_ts,
+                start_ramp_ts,
+                stop_ramp_ts,
+            );
+            let expected = if tick >= MIN_RAMP_DURATION {
+                target_amp_factor
+            } else {
+                initial_amp_factor - (amp_range * tick as u64 / MIN_RAMP_DURATION as u64)
+            };
+            assert_eq!(invariant.compute_amp_factor().unwrap(), expected.into());
+        }
+    }
+
+    fn check_d(
+        model: &Model,
+        amount_a: u64,
+        amount_b: u64,
+        current_ts: i64,
+        start_ramp_ts: i64,
+        stop_ramp_ts: i64,
+    ) -> U256 {
+        let swap = StableSwap {
+            initial_amp_factor: U256::from(model.amp_factor),
+            target_amp_factor: U256::from(model.amp_factor),
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        };
+        let d = swap
+            .compute_d(U256::from(amount_a), U256::from(amount_b))
+            .unwrap();
+        assert_eq!(d, model.sim_d().into());
+        d
+    }
+
+    fn check_y(
+        model: &Model,
+        x: u64,
+        d: U256,
+        current_ts: i64,
+        start_ramp_ts: i64,
+        stop_ramp_ts: i64,
+    ) {
+        let swap = StableSwap {
+            initial_amp_factor: U256::from(model.amp_factor),
+            target_amp_factor: U256::from(model.amp_factor),
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        };
+        assert_eq!(
+            swap.compute_y(x.into(), d).unwrap(),
+            model.sim_y(0, 1, x.into()).into()
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn test_curve_math(
+            current_ts in ZERO_TS..i64::MAX,
+            amp_factor in MIN_AMP..MAX_AMP,
+            amount_a in 1..u64::MAX,    // Start at 1 to prevent divide by 0 when computing d
+            amount_b in 1..u64::MAX,    // Start at 1 to prevent divide by 0 when computing d
+        ) {
+            let start_ramp_ts = cmp::max(0, current_ts - MIN_RAMP_DURATION);
+            let stop_ramp_ts = cmp::min(i64::MAX, current_ts + MIN_RAMP_DURATION);
+            let model = Model::new(amp_factor.into(), vec![amount_a.into(), amount_b.into()], N_COINS.into());
+            let d = check_d(&model, amount_a, amount_b, current_ts, start_ramp_ts, stop_ramp_ts);
+            check_y(&model, amount_a, d, current_ts, start_ramp_ts, stop_ramp_ts);
+        }
+    }
+
+    #[test]
+    fn test_curve_math_specific() {
+        // Specific cases
+        let current_ts = ZERO_TS;
+        let start_ramp_ts = ZERO_TS;
+        let stop_ramp_ts = ZERO_TS;
+        let model_no_balance = Model::new(1, vec![0, 0], N_COINS.into());
+        check_d(
+            &model_no_balance,
+            0,
+            0,
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        );
+
+        let amount_a: u64 = 10461290657254161082;
+        let amount_b: u64 = 12507100355549196829;
+        let model = Model::new(1188, vec![amount_a.into(), amount_b.into()], N_COINS.into());
+        let d = check_d(
+            &model,
+            amount_a,
+            amount_b,
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        );
+        let amount_x: u64 = 2045250484898639148;
+        check_y(
+            &model,
+            amount_x.into(),
+            d,
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        );
+
+        let amount_a: u64 = 8625384579714585493;
+        let amount_b: u64 = 4925481879098236733;
+        let model = Model::new(9, vec![amount_a.into(), amount_b.into()], N_COINS.into());
+        let d = check_d(
+            &model,
+            amount_a,
+            amount_b,
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        );
+        let amount_x: u64 = 8155777549389559399;
+        check_y(
+            &model,
+            amount_x.into(),
+            d,
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        );
+    }
+
+    #[test]
+    fn test_compute_mint_amount_for_deposit() {
+        let initial_amp_factor = MIN_AMP;
+        let target_amp_factor = MAX_AMP;
+        let current_ts = MIN_RAMP_DURATION / 2;
+        let start_ramp_ts = ZERO_TS;
+        let stop_ramp_ts = MIN_RAMP_DURATION;
+        let invariant = StableSwap::new(
+            initial_amp_factor,
+            target_amp_factor,
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        );
+
+        let deposit_amount_a = U256::from(u64::MAX);
+        let deposit_amount_b = U256::from(u64::MAX);
+        let swap_amount_a = U256::from(u64::MAX);
+        let swap_amount_b = U256::from(u64::MAX);
+        let pool_token_supply = U256::from(u64::MAX);
+        let actual_mint_amount = invariant
+            .compute_mint_amount_for_deposit(
+                deposit_amount_a,
+                deposit_amount_b,
+                swap_amount_a,
+                swap_amount_b,
+                pool_token_supply,
+                &MODEL_FEES,
+            )
+            .unwrap();
+        let expected_mint_amount = U256::from(u64::MAX);
+        assert_eq!(actual_mint_amount, expected_mint_amount);
+    }
+
+    #[test]
+    fn test_compute_burn_amount_for_withdraw() {
+        let invariant = StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS);
+        let swap_amount = U256::from(1_000_000_000);
+        let pool_token_supply = U256::from(2_000_000_000);
+        let no_fees = Fees {
+            trade_fee_numerator: 0,
+            ..MODEL_FEES
+        };
+
+        // A balanced withdrawal burns its share of the supply, free of fees
+        let (burn_amount, fee_a, fee_b) = invariant
+            .compute_burn_amount_for_withdraw(
+                U256::from(100_000_000),
+                U256::from(100_000_000),
+                swap_amount,
+                swap_amount,
+                pool_token_supply,
+                &MODEL_FEES,
+            )
+            .unwrap();
+        assert_eq!(burn_amount, U256::from(200_000_000));
+        assert_eq!((fee_a, fee_b), (U256::zero(), U256::zero()));
+
+        // An imbalanced one burns more at the trade fee than without it
+        let compute_burn_amount = |fees: &Fees| {
+            invariant
+                .compute_burn_amount_for_withdraw(
+                    U256::from(100_000_000),
+                    U256::zero(),
+                    swap_amount,
+                    swap_amount,
+                    pool_token_supply,
+                    fees,
+                )
+                .unwrap()
+        };
+        let (burn_amount, fee_a, fee_b) = compute_burn_amount(&MODEL_FEES);
+        let (burn_amount_without_fees, _, _) = compute_burn_amount(&no_fees);
+        assert!(fee_a > U256::zero() && fee_b > U256::zero());
+        assert!(burn_amount > burn_amount_without_fees);
+        // Slightly more than the share of the tokens withdrawn, at the amplification
+        assert!(burn_amount > U256::from(100_000_000));
+        assert!(burn_amount < U256::from(101_000_000));
+
+        // Nothing withdrawn, or more than the reserves
+        assert_eq!(
+            invariant.compute_burn_amount_for_withdraw(
+                U256::zero(),
+                U256::zero(),
+                swap_amount,
+                swap_amount,
+                pool_token_supply,
+                &MODEL_FEES,
+            ),
+            None
+        );
+        assert_eq!(
+            invariant.compute_burn_amount_for_withdraw(
+                swap_amount + 1,
+                U256::zero(),
+                swap_amount,
+                swap_amount,
+                pool_token_supply,
+                &MODEL_FEES,
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_compute_virtual_price() {
+        let invariant = StableSwap::new(MIN_AMP, MIN_AMP, ZERO_TS, ZERO_TS, ZERO_TS);
+        let amount_a = U256::from(1_000_000);
+        let amount_b = U256::from(1_000_000);
+        let d = invariant.compute_d(amount_a, amount_b).unwrap();
+
+        assert_eq!(
+            invariant.compute_virtual_price(amount_a, amount_b, d),
+            Some(VIRTUAL_PRICE_SCALE.into())
+        );
+        assert_eq!(
+            invariant.compute_virtual_price(amount_a, amount_b, d / 2),
+            Some(U256::from(VIRTUAL_PRICE_SCALE) * 2)
+        );
+        assert_eq!(
+            invariant.compute_virtual_price(amount_a, amount_b, 0.into()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_swap_to_v2_new_destination_amount() {
+        let fees = Fees {
+            trade_fee_numerator: 10,
+            trade_fee_denominator: 100,
+            reflection_fee_numerator: 10,
+            reflection_fee_denominator: 100,
+            buyback_fee_numerator: 20,
+            buyback_fee_denominator: 100,
+            marketing_fee_numerator: 30,
+            marketing_fee_denominator: 100,
+            developer_fee_numerator: 10,
+            developer_fee_denominator: 100,
+            ..MODEL_FEES
+        };
+        let invariant = StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS);
+        let result = invariant
+            .swap_to_v2(1_000.into(), 10_000.into(), 10_000.into(), &fees)
+            .unwrap();
+        assert_eq!(result.amount_swapped, 900.into());
+        // Only the fees transferred out leave the vault, the LP share of the
+        // trade fee stays in it
+        let transferred_fees = result.reflection_fee
+            + result.buyback_fee
+            + result.marketing_fee
+            + result.developer_fee;
+        assert_eq!(transferred_fees, 70.into());
+        assert_eq!(result.new_destination_amount, 9_030.into());
+        assert_eq!(
+            result.new_destination_amount,
+            U256::from(10_000) - result.amount_swapped - transferred_fees
+        );
+    }
+
+    #[test]
+    fn test_curve_math_with_random_inputs() {
+        for _ in 0..100 {
+            let mut rng = rand::thread_rng();
+
+            let amp_factor: u64 = rng.gen_range(MIN_AMP, MAX_AMP);
+            let amount_a: u64 = rng.gen_range(1, u64::MAX);
+            let amount_b: u64 = rng.gen_range(1, u64::MAX);
+            let start_ramp_ts: i64 = rng.gen_range(ZERO_TS, i64::MAX);
+            let stop_ramp_ts: i64 = rng.gen_range(start_ramp_ts, i64::MAX);
+            let current_ts: i64 = rng.gen_range(start_ramp_ts, stop_ramp_ts);
+            println!("testing curve_math_with_random_inputs:");
+            println!(
+                "current_ts: {}, start_ramp_ts: {}, stop_ramp_ts: {}",
+                current_ts, start_ramp_ts, stop_ramp_ts
+            );
+            println!(
+                "amp_factor: {}, amount_a: {}, amount_b: {}",
+                amp_factor, amount_a, amount_b,
+            );
+
+            let model = Model::new(
+                amp_factor.into(),
+                vec![amount_a.into(), amount_b.into()],
+                N_COINS.into(),
+            );
+            let d = check_d(
+                &model,
+                amount_a.into(),
+                amount_b.into(),
+                current_ts,
+                start_ramp_ts,
+                stop_ramp_ts,
+            );
+            let amount_x: u64 = rng.gen_range(0, amount_a);
+
+            println!("amount_x: {}", amount_x);
+            check_y(&model, amount_x, d, current_ts, start_ramp_ts, stop_ramp_ts);
+        }
+    }
+
+    fn check_swap(
+        initial_amp_factor: u64,
+        target_amp_factor: u64,
+        current_ts: i64,
+        start_ramp_ts: i64,
+        stop_ramp_ts: i64,
+        source_amount: u64,
+        swap_source_amount: u64,
+        swap_destination_amount: u64,
+    ) {
+        let swap = StableSwap::new(
+            initial_amp_factor,
+            target_amp_factor,
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        );
+        let result = swap
+            .swap_to(
+                source_amount.into(),
+                swap_source_amount.into(),
+                swap_destination_amount.into(),
+                &MODEL_FEES,
+            )
+            .unwrap();
+        let model = Model::new(
+            swap.compute_amp_factor().unwrap().as_u128(),
+            vec![swap_source_amount.into(), swap_destination_amount.into()],
+            N_COINS.into(),
+        );
+
+        assert_eq!(
+            result.amount_swapped,
+            model.sim_exchange(0, 1, source_amount.into()).into()
+        );
+        assert_eq!(
+            result.new_source_amount,
+            U256::from(swap_source_amount) + U256::from(source_amount)
+        );
+        assert_eq!(
+            result.new_destination_amount,
+            U256::from(swap_destination_amount) - result.amount_swapped
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_swap_calculation(
+            current_ts in ZERO_TS..i64::MAX,
+            amp_factor in MIN_AMP..MAX_AMP,
+            source_amount in 0..u64::MAX,
+            swap_source_amount in 0..u64::MAX,
+            swap_destination_amount in 0..u64::MAX,
+        ) {
+            let start_ramp_ts = cmp::max(0, current_ts - MIN_RAMP_DURATION);
+            let stop_ramp_ts = cmp::min(i64::MAX, current_ts + MIN_RAMP_DURATION);
+            check_swap(
+                amp_factor,
+                amp_factor,
+                current_ts,
+                start_ramp_ts,
+                stop_ramp_ts,
+                source_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            );
+        }
+    }
+
+    #[test]
+    fn test_swap_calculation_with_random_inputs() {
+        for _ in 0..100 {
+            let mut rng = rand::thread_rng();
+
+            let initial_amp_factor: u64 = rng.gen_range(MIN_AMP, MAX_AMP);
+            let target_amp_factor: u64 = rng.gen_range(MIN_AMP, MAX_AMP);
+            let start_ramp_ts: i64 = rng.gen_range(ZERO_TS, i64::MAX);
+            let stop_ramp_ts: i64 = rng.gen_range(start_ramp_ts, i64::MAX);
+            let current_ts: i64 = rng.gen_range(start_ramp_ts, stop_ramp_ts);
+            let source_amount: u64 = rng.gen_range(1, u64::MAX);
+            let swap_source_amount: u64 = rng.gen_range(1, u64::MAX);
+            let swap_destination_amount: u64 = rng.gen_range(1, u64::MAX);
+            println!("testing swap_calculation_with_random_inputs:");
+            println!(
+                "current_ts: {}, start_ramp_ts: {}, stop_ramp_ts: {}",
+                current_ts, start_ramp_ts, stop_ramp_ts
+            );
+            println!(
+                "initial_amp_factor: {}, target_amp_factor: {}, source_amount: {}, swap_source_amount: {}, swap_destination_amount: {}",
+                initial_amp_factor, target_amp_factor, source_amount, swap_source_amount, swap_destination_amount
+            );
+
+            check_swap(
+                initial_amp_factor,
+                target_amp_factor,
+                current_ts,
+                start_ramp_ts,
+                stop_ramp_ts,
+                source_amount,
+                swap_source_amount,
+                swap_destination_amount,
+            );
+        }
+    }
+
+    #[test]
+    fn test_swap_to_v2_fee_on_input() {
+        let swap = StableSwap::new(MIN_AMP, MIN_AMP, ZERO_TS, ZERO_TS, ZERO_TS);
+        let source_amount = U256::from(1_000_000);
+        let swap_source_amount = U256::from(100_000_000);
+        let swap_destination_amount = U256::from(100_000_000);
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 100,
+            reflection_fee_numerator: 1,
+            reflection_fee_denominator: 2,
+            admin_trade_fee_numerator: 1,
+            admin_trade_fee_denominator: 4,
+            fee_on_input: true,
+            ..MODEL_FEES
+        };
+        let result = swap
+            .swap_to_v2(
+                source_amount,
+                swap_source_amount,
+                swap_destination_amount,
+                &fees,
+            )
+            .unwrap();
+
+        // the fee is taken from the source amount before exchanging the rest
+        let trade_fee = U256::from(10_000);
+        let no_fee_result = swap
+            .swap_to_v2(
+                source_amount - trade_fee,
+                swap_source_amount,
+                swap_destination_amount,
+                &Fees {
+                    trade_fee_numerator: 0,
+                    ..MODEL_FEES
+                },
+            )
+            .unwrap();
+        assert_eq!(result.trade_fee, trade_fee);
+        assert_eq!(result.reflection_fee, U256::from(5_000));
+        assert_eq!(result.admin_fee, U256::from(2_500));
+        assert_eq!(result.amount_swapped, no_fee_result.amount_swapped);
+        assert_eq!(
+            result.new_source_amount,
+            swap_source_amount + source_amount - result.reflection_fee
+        );
+        assert_eq!(
+            result.new_destination_amount,
+            swap_destination_amount - result.amount_swapped
+        );
+    }
+
+    #[test]
+    fn test_swap_to_v2_imbalance_fee() {
+        let swap = StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS);
+        let fees = Fees {
+            imbalance_fee_numerator: 1,
+            imbalance_fee_denominator: 100,
+            imbalance_threshold_bps: 1_000,
+            host_fee_numerator: 0,
+            host_fee_denominator: 1,
+            ..MODEL_FEES
+        };
+        let swap_to = |source_amount: u64,
+                       swap_source_amount: u64,
+                       swap_destination_amount: u64,
+                       fees: &Fees| {
+            swap.swap_to_v2(
+                source_amount.into(),
+                swap_source_amount.into(),
+                swap_destination_amount.into(),
+                fees,
+            )
+            .unwrap()
+        };
+
+        // swaps keeping the pool balanced, or rebalancing it, pay the trade fee alone
+        for (source_amount, swap_source_amount, swap_destination_amount) in [
+            (1_000, 100_000_000, 100_000_000),
+            (10_000_000, 50_000_000, 150_000_000),
+        ] {
+            let result = swap_to(
+                source_amount,
+                swap_source_amount,
+                swap_destination_amount,
+                &fees,
+            );
+            let base_result = swap_to(
+                source_amount,
+                swap_source_amount,
+                swap_destination_amount,
+                &MODEL_FEES,
+            );
+            assert_eq!(result.trade_fee, base_result.trade_fee);
+            assert_eq!(result.amount_swapped, base_result.amount_swapped);
+        }
+
+        // draining the destination token pays the imbalance fee on top
+        let result = swap_to(30_000_000, 100_000_000, 100_000_000, &fees);
+        let base_result = swap_to(30_000_000, 100_000_000, 100_000_000, &MODEL_FEES);
+        assert!(result.trade_fee > base_result.trade_fee);
+        assert_eq!(
+            result.amount_swapped + result.trade_fee,
+            base_result.amount_swapped + base_result.trade_fee
+        );
+
+        let amount_out = U256::from(20_000_000);
+        let amount_in = swap
+            .compute_swap_in(amount_out, 100_000_000.into(), 100_000_000.into(), &fees)
+            .unwrap();
+        let amount_swapped =
+            |amount_in: U256| swap_to(amount_in.as_u64(), 100_000_000, 100_000_000, &fees);
+        assert!(amount_swapped(amount_in).amount_swapped >= amount_out);
+        assert!(amount_swapped(amount_in - 1).amount_swapped < amount_out);
+    }
+
+    fn check_withdraw_one(
+        initial_amp_factor: u64,
+        target_amp_factor: u64,
+        current_ts: i64,
+        start_ramp_ts: i64,
+        stop_ramp_ts: i64,
+        pool_token_amount: u64,
+        pool_token_supply: u64,
+        swap_base_amount: u64,
+        swap_quote_amount: u64,
+    ) {
+        let swap = StableSwap::new(
+            initial_amp_factor,
+            target_amp_factor,
+            current_ts,
+            start_ramp_ts,
+            stop_ramp_ts,
+        );
+        let result = swap
+            .compute_withdraw_one(
+                pool_token_amount.into(),
+                pool_token_supply.into(),
+                swap_base_amount.into(),
+                swap_quote_amount.into(),
+                &MODEL_FEES,
+            )
+            .unwrap();
+        let model = Model::new_with_pool_tokens(
+            swap.compute_amp_factor().unwrap().as_u128(),
+            vec![swap_base_amount.into(), swap_quote_amount.into()],
+            N_COINS.into(),
+            pool_token_supply.into(),
+        );
+        assert_eq!(
+            result.0,
+            model
+                .sim_calc_withdraw_one_coin(pool_token_amount.into(), 0)
+                .0
+                .into()
+        );
+        assert_eq!(
+            result.1,
+            model
+                .sim_calc_withdraw_one_coin(pool_token_amount.into(), 0)
+                .1
+                .into()
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_compute_withdraw_one(
+            current_ts in ZERO_TS..i64::MAX,
+            amp_factor in MIN_AMP..MAX_AMP,
+            pool_token_amount in 1..u64::MAX / 2,
+            swap_base_amount in 1..u64::MAX / 2,
+            swap_quote_amount in 1..u64::MAX / 2,
+        ) {
+            let pool_token_supply = u64::MAX;
+            let start_ramp_ts = cmp::max(0, current_ts - MIN_RAMP_DURATION);
+            let stop_ramp_ts = cmp::min(i64::MAX, current_ts + MIN_RAMP_DURATION);
+            check_withdraw_one(
+                amp_factor,
+                amp_factor,
+                current_ts,
+                start_ramp_ts,
+                stop_ramp_ts,
+                pool_token_amount,
+                pool_token_supply,
+                swap_base_amount,
+                swap_quote_amount,
+            );
+        }
+    }
+
+    #[test]
+    fn test_compute_withdraw_one_with_random_inputs() {
+        for _ in 0..100 {
+            let mut rng = rand::thread_rng();
+
+            let initial_amp_factor: u64 = rng.gen_range(MIN_AMP, MAX_AMP);
+            let target_amp_factor: u64 = rng.gen_range(MIN_AMP, MAX_AMP);
+            let start_ramp_ts: i64 = rng.gen_range(ZERO_TS, i64::MAX);
+            let stop_ramp_ts: i64 = rng.gen_range(start_ramp_ts, i64::MAX);
+            let current_ts: i64 = rng.gen_range(start_ramp_ts, stop_ramp_ts);
+            let swap_base_amount: u64 = rng.gen_range(1, u64::MAX / 2);
+            let swap_quote_amount: u64 = rng.gen_range(1, u64::MAX / 2);
+            let pool_token_supply = swap_base_amount + swap_quote_amount;
+            let pool_token_amount: u64 = rng.gen_range(1, pool_token_supply);
+            println!("testing compute_withdraw_one_with_random_inputs:");
+            println!(
+                "current_ts: {}, start_ramp_ts: {}, stop_ramp_ts: {}",
+                current_ts, start_ramp_ts, stop_ramp_ts
+            );
+            println!(
+                "initial_amp_factor: {}, target_amp_factor: {}, swap_base_amount: {}, swap_quote_amount: {}, pool_token_amount: {}, pool_token_supply: {}",
+                initial_amp_factor, target_amp_factor,  swap_base_amount, swap_quote_amount, pool_token_amount, pool_token_supply
+            );
+
+            check_withdraw_one(
+                initial_amp_factor,
+                target_amp_factor,
+                current_ts,
+                start_ramp_ts,
+                stop_ramp_ts,
+                pool_token_amount,
+                pool_token_supply,
+                swap_base_amount,
+                swap_quote_amount,
+            );
+        }
+    }
+
+    #[test]
+    fn test_swap_without_fees() {
+        let swap = StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS);
+        let no_fees = Fees {
+            trade_fee_numerator: 0,
+            trade_fee_denominator: 1,
+            ..MODEL_FEES
+        };
+        for (source_amount, swap_source_amount, swap_destination_amount) in [
+            (1, 1_000_000, 1_000_000),
+            (50, 1_000_000, 3_000_000),
+            (1_000, 7_000_000, 1_000_000),
+        ]
+        .iter()
+        {
+            let result = swap
+                .swap_to(
+                    (*source_amount).into(),
+                    (*swap_source_amount).into(),
+                    (*swap_destination_amount).into(),
+                    &no_fees,
+                )
+                .unwrap();
+            assert_eq!(
+                swap.swap_without_fees(
+                    (*source_amount).into(),
+                    (*swap_source_amount).into(),
+                    (*swap_destination_amount).into(),
+                ),
+                Some(result.amount_swapped)
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_compute_swap_in(
+            amp_factor in MIN_AMP..=10_000,
+            amount_out in 1..100_000u64,
+            swap_source_amount in 1_000_000..1_000_000_000u64,
+            swap_destination_amount in 1_000_000..1_000_000_000u64,
+            fee_on_input in proptest::bool::ANY,
+        ) {
+            let swap = StableSwap::new(amp_factor, amp_factor, ZERO_TS, ZERO_TS, ZERO_TS);
+            let fees = Fees {
+                fee_on_input,
+                ..MODEL_FEES
+            };
+            let amount_swapped = |source_amount: U256| {
+                swap.swap_to_v2(
+                    source_amount,
+                    swap_source_amount.into(),
+                    swap_destination_amount.into(),
+                    &fees,
+                )
+                .unwrap()
+                .amount_swapped
+            };
+            let amount_in = swap
+                .compute_swap_in(
+                    amount_out.into(),
+                    swap_source_amount.into(),
+                    swap_destination_amount.into(),
+                    &fees,
+                )
+                .unwrap();
+            // the smallest amount paying out `amount_out`, none at all when the
+            // rounding of `compute_y` pays out a token for nothing
+            prop_assert!(amount_swapped(amount_in) >= amount_out.into());
+            if !amount_in.is_zero() {
+                prop_assert!(amount_swapped(amount_in - 1) < amount_out.into());
+            }
+        }
+    }
+
+    #[test]
+    fn test_quote_in_place() {
+        let swap = StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS);
+        let result = swap
+            .swap_to_v2(
+                10_000.into(),
+                1_000_000.into(),
+                2_000_000.into(),
+                &MODEL_FEES,
+            )
+            .unwrap();
+        let mut quote = [0; QUOTE_LEN];
+        swap.quote_in_place(10_000, 1_000_000, 2_000_000, &MODEL_FEES, &mut quote)
+            .unwrap();
+        let field = |i: usize| {
+            U256::from(u64::from_le_bytes(
+                quote[i * 8..(i + 1) * 8].try_into().unwrap(),
+            ))
+        };
+        assert_eq!(field(0), result.amount_swapped);
+        assert_eq!(field(1), result.trade_fee);
+        assert_eq!(field(2), result.admin_fee);
+        assert_eq!(field(3), result.new_source_amount);
+        assert_eq!(field(4), result.new_destination_amount);
+
+        // the new source amount exceeds a u64
+        assert_eq!(
+            swap.quote_in_place(u64::MAX, 1_000_000, 2_000_000, &MODEL_FEES, &mut quote),
+            None
+        );
+    }
+
+    #[test]
+    fn test_compute_swap_in_exceeding_reserves() {
+        let swap = StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS);
+        assert_eq!(
+            swap.compute_swap_in(
+                1_000_000.into(),
+                1_000_000.into(),
+                1_000_000.into(),
+                &MODEL_FEES
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_compute_spot_price() {
+        let scale = U256::from(VIRTUAL_PRICE_SCALE);
+        let invariant = StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS);
+        let price = |amount_a: u64, amount_b: u64| {
+            invariant
+                .compute_spot_price(amount_a.into(), amount_b.into(), scale)
+                .unwrap()
+        };
+        assert_eq!(price(1_000_000, 1_000_000), scale);
+        // token A is scarce, and worth more than a B
+        let a_in_b = price(500_000, 1_500_000);
+        assert!(a_in_b > scale);
+        let b_in_a = price(1_500_000, 500_000);
+        assert!(b_in_a < scale);
+        let product = a_in_b * b_in_a / scale;
+        // up to the precision of D
+        let tolerance = scale / 1_000_000;
+        assert!(product >= scale - tolerance && product <= scale + tolerance);
+
+        // the price is the rate of a marginal swap
+        let amount_out = invariant
+            .swap_without_fees(100_000.into(), 500_000_000.into(), 1_500_000_000.into())
+            .unwrap();
+        let rate = amount_out.checked_mul(scale).unwrap() / 100_000;
+        assert!(rate * 1_000 / 1_001 <= a_in_b && rate * 1_001 / 1_000 >= a_in_b);
+        // flatter than the constant product
+        assert!(a_in_b < scale * 3);
+
+        assert_eq!(
+            StableSwap::new(1, 1, ZERO_TS, ZERO_TS, ZERO_TS).compute_spot_price(
+                0.into(),
+                1_000.into(),
+                scale
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_curve_type_from_u8() {
+        assert_eq!(CurveType::from_u8(0), Some(CurveType::StableSwap));
+        assert_eq!(CurveType::from_u8(1), Some(CurveType::ConstantProduct));
+        assert_eq!(CurveType::from_u8(2), Some(CurveType::PriceBounded));
+        assert_eq!(CurveType::from_u8(3), None);
+        assert_eq!(CurveType::default(), CurveType::StableSwap);
+    }
+
+    #[test]
+    fn test_constant_product_swap() {
+        let curve = ConstantProduct;
+        assert_eq!(
+            curve.compute_d(1_000_000.into(), 4_000_000.into()),
+            Some(2_000_000.into())
+        );
+        let scale = U256::from(VIRTUAL_PRICE_SCALE);
+        assert_eq!(
+            curve.compute_spot_price(1_000_000.into(), 4_000_000.into(), scale),
+            Some(scale * 4)
+        );
+        assert_eq!(
+            curve.swap_without_fees(1_000_000.into(), 1_000_000.into(), 4_000_000.into()),
+            Some(2_000_000.into())
+        );
+
+        for fee_on_input in [false, true] {
+            let fees = Fees {
+                fee_on_input,
+                ..MODEL_FEES
+            };
+            let result = curve
+                .swap_to_v2(100_000.into(), 1_000_000.into(), 4_000_000.into(), &fees)
+                .unwrap();
+            let amount_without_fees = curve
+                .swap_without_fees(100_000.into(), 1_000_000.into(), 4_000_000.into())
+                .unwrap();
+            assert!(result.amount_swapped < amount_without_fees);
+            assert!(!result.trade_fee.is_zero());
+            // the trade fee stays in the pool, growing the product
+            assert!(
+                result.new_source_amount * result.new_destination_amount
+                    > U256::from(4_000_000_000_000u64)
+            );
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_constant_product_swap_in(
+            amount_out in 1..100_000u64,
+            swap_source_amount in 1_000_000..1_000_000_000u64,
+            swap_destination_amount in 1_000_000..1_000_000_000u64,
+            fee_on_input in proptest::bool::ANY,
+        ) {
+            let curve = ConstantProduct;
+            let fees = Fees {
+                fee_on_input,
+                ..MODEL_FEES
+            };
+            let amount_swapped = |source_amount: U256| {
+                curve
+                    .swap_to_v2(
+                        source_amount,
+                        swap_source_amount.into(),
+                        swap_destination_amount.into(),
+                        &fees,
+                    )
+                    .unwrap()
+                    .amount_swapped
+            };
+            let amount_in = curve
+                .compute_swap_in(
+                    amount_out.into(),
+                    swap_source_amount.into(),
+                    swap_destination_amount.into(),
+                    &fees,
+                )
+                .unwrap();
+            prop_assert!(amount_swapped(amount_in) >= amount_out.into());
+            prop_assert!(amount_swapped(amount_in - 1) < amount_out.into());
+        }
+    }
+
+    #[test]
+    fn test_constant_product_deposit_and_withdraw() {
+        let curve = ConstantProduct;
+        let supply = curve.compute_d(1_000_000.into(), 4_000_000.into()).unwrap();
+        // a balanced deposit mints its share of the pool
+        assert_eq!(
+            curve.compute_mint_amount_for_deposit(
+                100_000.into(),
+                400_000.into(),
+                1_000_000.into(),
+                4_000_000.into(),
+                supply,
+                &MODEL_FEES,
+            ),
+            Some(supply / 10)
+        );
+
+        // burning a tenth of the pool for token A alone pays out less than a
+        // balanced withdrawal and the price of its B
+        let (amount_a, fee) = curve
+            .compute_withdraw_one(
+                supply / 10,
+                supply,
+                1_000_000.into(),
+                4_000_000.into(),
+                &MODEL_FEES,
+            )
+            .unwrap();
+        assert!(amount_a > 100_000.into() && amount_a < 200_000.into());
+        assert!(!fee.is_zero());
+
+        let (burn_amount, fee_a, fee_b) = curve
+            .compute_burn_amount_for_withdraw(
+                100_000.into(),
+                400_000.into(),
+                1_000_000.into(),
+                4_000_000.into(),
+                supply,
+                &MODEL_FEES,
+            )
+            .unwrap();
+        assert_eq!(burn_amount, supply / 10);
+        assert_eq!((fee_a, fee_b), (U256::zero(), U256::zero()));
+
+        assert_eq!(
+            curve.compute_virtual_price(1_000_000.into(), 4_000_000.into(), supply),
+            Some(VIRTUAL_PRICE_SCALE.into())
+        );
+    }
+
+    #[test]
+    fn test_pool_curve() {
+        let stable_swap = StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS);
+        let swap = |curve: &dyn SwapCurve| {
+            curve
+                .swap_to_v2(
+                    100_000.into(),
+                    1_000_000.into(),
+                    2_000_000.into(),
+                    &MODEL_FEES,
+                )
+                .unwrap()
+                .amount_swapped
+        };
+        assert_eq!(
+            swap(&PoolCurve::StableSwap(stable_swap)),
+            swap(&stable_swap)
+        );
+        assert_eq!(
+            swap(&PoolCurve::ConstantProduct(ConstantProduct)),
+            swap(&ConstantProduct)
+        );
+        assert_ne!(swap(&stable_swap), swap(&ConstantProduct));
+    }
+
+    #[test]
+    fn test_price_bounded_liquidity() {
+        let scale = U256::from(PRICE_BOUND_SCALE);
+        // the widest band is close to the constant product
+        let widest = PriceBounded::new(u64::MAX);
+        let d = widest
+            .compute_d(1_000_000.into(), 4_000_000.into())
+            .unwrap();
+        assert!(d > 2_000_000.into() && d < 2_001_000.into());
+
+        // a band of 1% concentrates the liquidity of the reserves
+        let curve = PriceBounded::new(PRICE_BOUND_SCALE / 100 * 101);
+        let d = curve.compute_d(1_000_000.into(), 1_000_000.into()).unwrap();
+        assert!(d > U256::from(200_000_000u64));
+        let (virtual_a, virtual_b) = curve
+            .virtual_reserves(1_000_000.into(), 1_000_000.into())
+            .unwrap();
+        let product = virtual_a * virtual_b;
+        assert!(product <= d * d && product + d * 2 >= d * d);
+        assert_eq!(
+            curve.compute_spot_price(1_000_000.into(), 1_000_000.into(), scale),
+            Some(scale)
+        );
+        // the price reaches the bound as the reserve of the token runs out
+        let price = curve
+            .compute_spot_price(2_000_000.into(), 1.into(), scale)
+            .unwrap();
+        assert!(price * 101 >= scale * 99 && price * 101 <= scale * 101);
+
+        // no band at all
+        assert_eq!(
+            PriceBounded::new(PRICE_BOUND_SCALE).compute_d(1_000.into(), 1_000.into()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_price_bounded_swap() {
+        let curve = PriceBounded::new(PRICE_BOUND_SCALE / 100 * 101);
+        let amount_out = |curve: &dyn SwapCurve| {
+            curve
+                .swap_to_v2(
+                    100_000.into(),
+                    1_000_000.into(),
+                    1_000_000.into(),
+                    &MODEL_FEES,
+                )
+                .unwrap()
+                .amount_swapped
+        };
+        // far less slippage than the constant product of the same reserves
+        assert!(amount_out(&curve) > amount_out(&ConstantProduct) * 21 / 20);
+        assert!(amount_out(&curve) < 100_000.into());
+
+        // the real reserve runs out at the bound
+        assert_eq!(
+            curve.swap_without_fees(10_000_000.into(), 1_000_000.into(), 1_000_000.into()),
+            None
+        );
+        assert_eq!(
+            curve.compute_swap_in(
+                1_000_001.into(),
+                1_000_000.into(),
+                1_000_000.into(),
+                &MODEL_FEES
+            ),
+            None
+        );
+
+        // withdrawing a single token charges less slippage too
+        let supply = curve.compute_d(1_000_000.into(), 1_000_000.into()).unwrap();
+        let (amount_a, _fee) = curve
+            .compute_withdraw_one(
+                supply / 20,
+                supply,
+                1_000_000.into(),
+                1_000_000.into(),
+                &MODEL_FEES,
+            )
+            .unwrap();
+        assert!(amount_a > 99_000.into() && amount_a < 100_000.into());
+        assert_eq!(
+            curve.compute_mint_amount_for_deposit(
+                100_000.into(),
+                100_000.into(),
+                1_000_000.into(),
+                1_000_000.into(),
+                supply,
+                &MODEL_FEES,
+            ),
+            Some(supply / 10)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_price_bounded_swap_in(
+            price_bound in PRICE_BOUND_SCALE + 1_000_000..PRICE_BOUND_SCALE * 4,
+            amount_out in 1..100_000u64,
+            swap_source_amount in 1_000_000..1_000_000_000u64,
+            swap_destination_amount in 1_000_000..1_000_000_000u64,
+            fee_on_input in proptest::bool::ANY,
+        ) {
+            let curve = PriceBounded::new(price_bound);
+            let fees = Fees {
+                fee_on_input,
+                ..MODEL_FEES
+            };
+            let amount_swapped = |source_amount: U256| {
+                curve
+                    .swap_to_v2(
+                        source_amount,
+                        swap_source_amount.into(),
+                        swap_destination_amount.into(),
+                        &fees,
+                    )
+                    .unwrap()
+                    .amount_swapped
+            };
+            let amount_in = curve
+                .compute_swap_in(
+                    amount_out.into(),
+                    swap_source_amount.into(),
+                    swap_destination_amount.into(),
+                    &fees,
+                )
+                .unwrap();
+            prop_assert!(amount_swapped(amount_in) >= amount_out.into());
+            prop_assert!(amount_swapped(amount_in - 1) < amount_out.into());
+        }
+    }
+
+    #[test]
+    fn test_transfer_fee() {
+        let transfer_fee = TransferFee {
+            basis_points: 100,
+            maximum_fee: 50,
+        };
+        assert_eq!(transfer_fee.fee(0), Some(0));
+        assert_eq!(transfer_fee.fee(1), Some(1));
+        assert_eq!(transfer_fee.fee(1_000), Some(10));
+        assert_eq!(transfer_fee.fee(1_001), Some(11));
+        assert_eq!(transfer_fee.fee(1_000_000), Some(50));
+        assert_eq!(transfer_fee.post_fee_amount(1_000), Some(990));
+        assert_eq!(transfer_fee.pre_fee_amount(990), Some(1_000));
+        assert_eq!(transfer_fee.pre_fee_amount(1_000_000), Some(1_000_050));
+        assert_eq!(TransferFee::default().pre_fee_amount(1_000), Some(1_000));
+
+        let transfer_fee = TransferFee {
+            basis_points: MAX_TRANSFER_FEE_BASIS_POINTS,
+            maximum_fee: 50,
+        };
+        assert_eq!(transfer_fee.post_fee_amount(10), Some(0));
+        assert_eq!(transfer_fee.pre_fee_amount(10), Some(60));
+        assert_eq!(transfer_fee.post_fee_amount(60), Some(10));
+    }
+
+    proptest! {
+        #[test]
+        fn test_transfer_fee_pre_fee_amount(
+            basis_points in 0..MAX_TRANSFER_FEE_BASIS_POINTS,
+            maximum_fee in 0..u32::MAX as u64,
+            post_fee_amount in 0..u32::MAX as u64,
+        ) {
+            let transfer_fee = TransferFee { basis_points, maximum_fee };
+            let pre_fee_amount = transfer_fee.pre_fee_amount(post_fee_amount).unwrap();
+            prop_assert!(transfer_fee.post_fee_amount(pre_fee_amount).unwrap() >= post_fee_amount);
+            if pre_fee_amount > 0 {
+                // The least amount to send
+                prop_assert!(transfer_fee.post_fee_amount(pre_fee_amount - 1).unwrap() < post_fee_amount);
+            }
+        }
+    }
+}
@@ -0,0 +1,1068 @@
+//! Program fees
+
+use crate::bn::U256;
+#[cfg(feature = "program")]
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use core::convert::TryFrom;
+#[cfg(feature = "program")]
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{Pack, Sealed},
+};
+
+/// Fees struct
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Fees {
+    /// Admin trade fee numerator
+    pub admin_trade_fee_numerator: u64,
+    /// Admin trade fee denominator
+    pub admin_trade_fee_denominator: u64,
+    /// Admin withdraw fee numerator, the share of the withdraw fee paid to the
+    /// admin. The rest of the withdraw fee stays in the pool, raising the virtual
+    /// price for the remaining LPs.
+    pub admin_withdraw_fee_numerator: u64,
+    /// Admin withdraw fee denominator
+    pub admin_withdraw_fee_denominator: u64,
+    /// Trade fee numerator
+    pub trade_fee_numerator: u64,
+    /// Trade fee denominator
+    pub trade_fee_denominator: u64,
+    /// Withdraw fee numerator
+    pub withdraw_fee_numerator: u64,
+    /// Withdraw fee denominator
+    pub withdraw_fee_denominator: u64,
+    /// Reflection fee numerator
+    pub reflection_fee_numerator: u64,
+    /// Reflection fee denominator
+    pub reflection_fee_denominator: u64,
+    /// Buyback fee numerator
+    pub buyback_fee_numerator: u64,
+    /// Buyback fee denominator
+    pub buyback_fee_denominator: u64,
+    /// Marketing fee numerator
+    pub marketing_fee_numerator: u64,
+    /// Marketing fee denominator
+    pub marketing_fee_denominator: u64,
+    /// Developer fee numerator
+    pub developer_fee_numerator: u64,
+    /// Developer fee denominator
+    pub developer_fee_denominator: u64,
+    /// Take the admin trade fee as newly minted pool tokens instead of
+    /// withdrawing it from the pool
+    pub admin_fee_in_pool_tokens: bool,
+    /// Charge the trade fees in the SOURCE token of a swap instead of the
+    /// DESTINATION token
+    pub fee_on_input: bool,
+    /// Imbalance fee numerator, charged on top of the trade fee by swaps
+    /// imbalancing the pool. Zero disables the imbalance fee.
+    pub imbalance_fee_numerator: u64,
+    /// Imbalance fee denominator
+    pub imbalance_fee_denominator: u64,
+    /// Imbalance of the reserves, in basis points of their sum, above which
+    /// swaps pay the imbalance fee
+    pub imbalance_threshold_bps: u64,
+    /// Host fee numerator, the share of the admin trade fee paid to the referrer
+    /// of a swap. Swaps without a referrer pay the whole admin trade fee to the admin.
+    pub host_fee_numerator: u64,
+    /// Host fee denominator
+    pub host_fee_denominator: u64,
+}
+
+impl Fees {
+    /// Apply admin trade fee
+    #[inline]
+    pub fn admin_trade_fee(&self, fee_amount: U256) -> Option<U256> {
+        fee_amount
+            .checked_mul(self.admin_trade_fee_numerator.into())?
+            .checked_div(self.admin_trade_fee_denominator.into())
+    }
+
+    /// Apply admin withdraw fee, at most the whole fee so that the remaining LPs
+    /// never pay the admin
+    #[inline]
+    pub fn admin_withdraw_fee(&self, fee_amount: U256) -> Option<U256> {
+        let admin_fee = fee_amount
+            .checked_mul(self.admin_withdraw_fee_numerator.into())?
+            .checked_div(self.admin_withdraw_fee_denominator.into())?;
+        Some(admin_fee.min(fee_amount))
+    }
+
+    /// Compute trade fee from amount
+    #[inline]
+    pub fn trade_fee(&self, trade_amount: U256) -> Option<U256> {
+        trade_amount
+            .checked_mul(self.trade_fee_numerator.into())?
+            .checked_div(self.trade_fee_denominator.into())
+    }
+
+    /// Compute withdraw fee from amount
+    #[inline]
+    pub fn withdraw_fee(&self, withdraw_amount: U256) -> Option<U256> {
+        withdraw_amount
+            .checked_mul(self.withdraw_fee_numerator.into())?
+            .checked_div(self.withdraw_fee_denominator.into())
+    }
+
+    /// Compute reflection fee from amount
+    #[inline]
+    pub fn reflection_fee(&self, reflection_amount: U256) -> Option<U256> {
+        reflection_amount
+            .checked_mul(self.reflection_fee_numerator.into())?
+            .checked_div(self.reflection_fee_denominator.into())
+    }
+
+    /// Compute buyback fee from amount
+    #[inline]
+    pub fn buyback_fee(&self, buyback_amount: U256) -> Option<U256> {
+        buyback_amount
+            .checked_mul(self.buyback_fee_numerator.into())?
+            .checked_div(self.buyback_fee_denominator.into())
+    }
+
+    /// Compute marketing fee from amount
+    #[inline]
+    pub fn marketing_fee(&self, marketing_amount: U256) -> Option<U256> {
+        marketing_amount
+            .checked_mul(self.marketing_fee_numerator.into())?
+            .checked_div(self.marketing_fee_denominator.into())
+    }
+
+    /// Compute developer fee from amount
+    #[inline]
+    pub fn developer_fee(&self, developer_amount: U256) -> Option<U256> {
+        developer_amount
+            .checked_mul(self.developer_fee_numerator.into())?
+            .checked_div(self.developer_fee_denominator.into())
+    }
+
+    /// Apply host fee to the admin trade fee, at most the whole admin fee so that the
+    /// referrer never takes a share of the LP fee
+    #[inline]
+    pub fn host_fee(&self, admin_fee: U256) -> Option<U256> {
+        if self.host_fee_numerator == 0 {
+            return Some(U256::zero());
+        }
+        let host_fee = admin_fee
+            .checked_mul(self.host_fee_numerator.into())?
+            .checked_div(self.host_fee_denominator.into())?;
+        Some(host_fee.min(admin_fee))
+    }
+
+    /// Whether swaps imbalancing the pool pay the imbalance fee
+    #[inline]
+    pub fn is_imbalance_fee_enabled(&self) -> bool {
+        self.imbalance_fee_numerator != 0
+    }
+
+    /// Fees of a swap moving the imbalance of the reserves from
+    /// `imbalance_before_bps` to `imbalance_after_bps`. Swaps increasing the
+    /// imbalance past the threshold pay the imbalance fee on top of the trade fee,
+    /// scaled from nothing at the threshold to the whole imbalance fee for a pool
+    /// drained of one token. Other swaps pay the trade fee alone.
+    pub fn with_imbalance_fee(
+        &self,
+        imbalance_before_bps: u64,
+        imbalance_after_bps: u64,
+    ) -> Option<Self> {
+        if !self.is_imbalance_fee_enabled()
+            || imbalance_after_bps <= self.imbalance_threshold_bps
+            || imbalance_after_bps <= imbalance_before_bps
+        {
+            return Some(*self);
+        }
+        let excess_bps = imbalance_after_bps.checked_sub(self.imbalance_threshold_bps)?;
+        let range_bps = FEE_TIER_DENOMINATOR.checked_sub(self.imbalance_threshold_bps)?;
+        // trade_fee + imbalance_fee * excess / range, over a common denominator
+        let imbalance_denominator = self.imbalance_fee_denominator.checked_mul(range_bps)?;
+        let trade_fee_numerator = self
+            .trade_fee_numerator
+            .checked_mul(imbalance_denominator)?
+            .checked_add(
+                self.imbalance_fee_numerator
+                    .checked_mul(excess_bps)?
+                    .checked_mul(self.trade_fee_denominator)?,
+            )?;
+        let trade_fee_denominator = self
+            .trade_fee_denominator
+            .checked_mul(imbalance_denominator)?;
+        Some(Self {
+            trade_fee_numerator: trade_fee_numerator.min(trade_fee_denominator),
+            trade_fee_denominator,
+            ..*self
+        })
+    }
+
+    /// Fees with the trade and imbalance fees scaled by `multiplier_bps`, in basis
+    /// points. Scaled fees round up and never exceed the whole amount.
+    pub fn with_fee_multiplier(&self, multiplier_bps: u64) -> Option<Self> {
+        let scale = |numerator: u64, denominator: u64| -> Option<u64> {
+            let scaled = numerator
+                .checked_mul(multiplier_bps)?
+                .checked_add(FEE_TIER_DENOMINATOR - 1)?
+                .checked_div(FEE_TIER_DENOMINATOR)?;
+            Some(scaled.min(denominator))
+        };
+        Some(Self {
+            trade_fee_numerator: scale(self.trade_fee_numerator, self.trade_fee_denominator)?,
+            imbalance_fee_numerator: scale(
+                self.imbalance_fee_numerator,
+                self.imbalance_fee_denominator,
+            )?,
+            ..*self
+        })
+    }
+
+    /// Compute normalized fee for symmetric/asymmetric deposits/withdraws
+    pub fn normalized_trade_fee(&self, n_coins: u64, amount: U256) -> Option<U256> {
+        // adjusted_fee_numerator: uint256 = self.fee * N_COINS / (4 * (N_COINS - 1))
+        let adjusted_trade_fee_numerator = self
+            .trade_fee_numerator
+            .checked_mul(n_coins)?
+            .checked_div((n_coins.checked_sub(1)?).checked_mul(4)?)?; // XXX: Why divide by 4?
+
+        amount
+            .checked_mul(adjusted_trade_fee_numerator.into())?
+            .checked_div(self.trade_fee_denominator.into())
+    }
+}
+
+#[cfg(feature = "program")]
+impl Sealed for Fees {}
+#[cfg(feature = "program")]
+impl Pack for Fees {
+    const LEN: usize = 170;
+    #[inline]
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 170];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            admin_trade_fee_numerator,
+            admin_trade_fee_denominator,
+            admin_withdraw_fee_numerator,
+            admin_withdraw_fee_denominator,
+            trade_fee_numerator,
+            trade_fee_denominator,
+            withdraw_fee_numerator,
+            withdraw_fee_denominator,
+            reflection_fee_numerator,
+            reflection_fee_denominator,
+            buyback_fee_numerator,
+            buyback_fee_denominator,
+            marketing_fee_numerator,
+            marketing_fee_denominator,
+            developer_fee_numerator,
+            developer_fee_denominator,
+            admin_fee_in_pool_tokens,
+            fee_on_input,
+            imbalance_fee_numerator,
+            imbalance_fee_denominator,
+            imbalance_threshold_bps,
+            host_fee_numerator,
+            host_fee_denominator,
+        ) = array_refs![input, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 1, 8, 8, 8, 8, 8];
+        Ok(Self {
+            admin_trade_fee_numerator: u64::from_le_bytes(*admin_trade_fee_numerator),
+            admin_trade_fee_denominator: u64::from_le_bytes(*admin_trade_fee_denominator),
+            admin_withdraw_fee_numerator: u64::from_le_bytes(*admin_withdraw_fee_numerator),
+            admin_withdraw_fee_denominator: u64::from_le_bytes(*admin_withdraw_fee_denominator),
+            trade_fee_numerator: u64::from_le_bytes(*trade_fee_numerator),
+            trade_fee_denominator: u64::from_le_bytes(*trade_fee_denominator),
+            withdraw_fee_numerator: u64::from_le_bytes(*withdraw_fee_numerator),
+            withdraw_fee_denominator: u64::from_le_bytes(*withdraw_fee_denominator),
+            reflection_fee_numerator: u64::from_le_bytes(*reflection_fee_numerator),
+            reflection_fee_denominator: u64::from_le_bytes(*reflection_fee_denominator),
+            buyback_fee_numerator: u64::from_le_bytes(*buyback_fee_numerator),
+            buyback_fee_denominator: u64::from_le_bytes(*buyback_fee_denominator),
+            marketing_fee_numerator: u64::from_le_bytes(*marketing_fee_numerator),
+            marketing_fee_denominator: u64::from_le_bytes(*marketing_fee_denominator),
+            developer_fee_numerator: u64::from_le_bytes(*developer_fee_numerator),
+            developer_fee_denominator: u64::from_le_bytes(*developer_fee_denominator),
+            admin_fee_in_pool_tokens: match admin_fee_in_pool_tokens {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            fee_on_input: match fee_on_input {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            imbalance_fee_numerator: u64::from_le_bytes(*imbalance_fee_numerator),
+            imbalance_fee_denominator: u64::from_le_bytes(*imbalance_fee_denominator),
+            imbalance_threshold_bps: u64::from_le_bytes(*imbalance_threshold_bps),
+            host_fee_numerator: u64::from_le_bytes(*host_fee_numerator),
+            host_fee_denominator: u64::from_le_bytes(*host_fee_denominator),
+        })
+    }
+
+    #[inline]
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 170];
+        let (
+            admin_trade_fee_numerator,
+            admin_trade_fee_denominator,
+            admin_withdraw_fee_numerator,
+            admin_withdraw_fee_denominator,
+            trade_fee_numerator,
+            trade_fee_denominator,
+            withdraw_fee_numerator,
+            withdraw_fee_denominator,
+            reflection_fee_numerator,
+            reflection_fee_denominator,
+            buyback_fee_numerator,
+            buyback_fee_denominator,
+            marketing_fee_numerator,
+            marketing_fee_denominator,
+            developer_fee_numerator,
+            developer_fee_denominator,
+            admin_fee_in_pool_tokens,
+            fee_on_input,
+            imbalance_fee_numerator,
+            imbalance_fee_denominator,
+            imbalance_threshold_bps,
+            host_fee_numerator,
+            host_fee_denominator,
+        ) = mut_array_refs![
+            output, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 1, 1, 8, 8, 8, 8, 8
+        ];
+        *admin_trade_fee_numerator = self.admin_trade_fee_numerator.to_le_bytes();
+        *admin_trade_fee_denominator = self.admin_trade_fee_denominator.to_le_bytes();
+        *admin_withdraw_fee_numerator = self.admin_withdraw_fee_numerator.to_le_bytes();
+        *admin_withdraw_fee_denominator = self.admin_withdraw_fee_denominator.to_le_bytes();
+        *trade_fee_numerator = self.trade_fee_numerator.to_le_bytes();
+        *trade_fee_denominator = self.trade_fee_denominator.to_le_bytes();
+        *withdraw_fee_numerator = self.withdraw_fee_numerator.to_le_bytes();
+        *withdraw_fee_denominator = self.withdraw_fee_denominator.to_le_bytes();
+        *reflection_fee_numerator = self.reflection_fee_numerator.to_le_bytes();
+        *reflection_fee_denominator = self.reflection_fee_denominator.to_le_bytes();
+        *buyback_fee_numerator = self.buyback_fee_numerator.to_le_bytes();
+        *buyback_fee_denominator = self.buyback_fee_denominator.to_le_bytes();
+        *marketing_fee_numerator = self.marketing_fee_numerator.to_le_bytes();
+        *marketing_fee_denominator = self.marketing_fee_denominator.to_le_bytes();
+        *developer_fee_numerator = self.developer_fee_numerator.to_le_bytes();
+        *developer_fee_denominator = self.developer_fee_denominator.to_le_bytes();
+        admin_fee_in_pool_tokens[0] = self.admin_fee_in_pool_tokens as u8;
+        fee_on_input[0] = self.fee_on_input as u8;
+        *imbalance_fee_numerator = self.imbalance_fee_numerator.to_le_bytes();
+        *imbalance_fee_denominator = self.imbalance_fee_denominator.to_le_bytes();
+        *imbalance_threshold_bps = self.imbalance_threshold_bps.to_le_bytes();
+        *host_fee_numerator = self.host_fee_numerator.to_le_bytes();
+        *host_fee_denominator = self.host_fee_denominator.to_le_bytes();
+    }
+}
+
+/// Imbalance of the reserves, their difference in basis points of their sum.
+/// Tokens of a stable pool are valued at par, balanced reserves are equal.
+pub fn imbalance_bps(reserve_a: U256, reserve_b: U256) -> Option<u64> {
+    let total = reserve_a.checked_add(reserve_b)?;
+    if total.is_zero() {
+        return Some(0);
+    }
+    let difference = if reserve_a > reserve_b {
+        reserve_a - reserve_b
+    } else {
+        reserve_b - reserve_a
+    };
+    let bps = difference
+        .checked_mul(FEE_TIER_DENOMINATOR.into())?
+        .checked_div(total)?;
+    Some(bps.as_u64())
+}
+
+/// Denominator of the fees built from basis points, by a
+/// [FeeTier](enum.FeeTier.html) or [Fees::from_bps](struct.Fees.html#method.from_bps)
+pub const FEE_TIER_DENOMINATOR: u64 = 10_000;
+
+/// The eight fees of a [Fees](struct.Fees.html) in basis points. The admin fees
+/// are shares of the trade and withdraw fees, and the reflection, buyback,
+/// marketing and developer fees shares of the trade fee.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FeesBps {
+    /// Admin trade fee
+    pub admin_trade_bps: u64,
+    /// Admin withdraw fee
+    pub admin_withdraw_bps: u64,
+    /// Trade fee
+    pub trade_bps: u64,
+    /// Withdraw fee
+    pub withdraw_bps: u64,
+    /// Reflection fee
+    pub reflection_bps: u64,
+    /// Buyback fee
+    pub buyback_bps: u64,
+    /// Marketing fee
+    pub marketing_bps: u64,
+    /// Developer fee
+    pub developer_bps: u64,
+}
+
+impl Fees {
+    /// Fees of denominator `FEE_TIER_DENOMINATOR` from basis points, neither
+    /// taking the admin fee in pool tokens, charging fees on the input, charging
+    /// an imbalance fee nor paying a host fee
+    pub fn from_bps(bps: FeesBps) -> Self {
+        Self {
+            admin_trade_fee_numerator: bps.admin_trade_bps,
+            admin_trade_fee_denominator: FEE_TIER_DENOMINATOR,
+            admin_withdraw_fee_numerator: bps.admin_withdraw_bps,
+            admin_withdraw_fee_denominator: FEE_TIER_DENOMINATOR,
+            trade_fee_numerator: bps.trade_bps,
+            trade_fee_denominator: FEE_TIER_DENOMINATOR,
+            withdraw_fee_numerator: bps.withdraw_bps,
+            withdraw_fee_denominator: FEE_TIER_DENOMINATOR,
+            reflection_fee_numerator: bps.reflection_bps,
+            reflection_fee_denominator: FEE_TIER_DENOMINATOR,
+            buyback_fee_numerator: bps.buyback_bps,
+            buyback_fee_denominator: FEE_TIER_DENOMINATOR,
+            marketing_fee_numerator: bps.marketing_bps,
+            marketing_fee_denominator: FEE_TIER_DENOMINATOR,
+            developer_fee_numerator: bps.developer_bps,
+            developer_fee_denominator: FEE_TIER_DENOMINATOR,
+            admin_fee_in_pool_tokens: false,
+            fee_on_input: false,
+            imbalance_fee_numerator: 0,
+            imbalance_fee_denominator: FEE_TIER_DENOMINATOR,
+            imbalance_threshold_bps: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: FEE_TIER_DENOMINATOR,
+        }
+    }
+
+    /// Builder of fees in basis points
+    pub fn builder() -> FeesBuilder {
+        FeesBuilder::default()
+    }
+
+    /// The fees in basis points, for display. Each fee is rounded to the nearest
+    /// basis point, halves rounding up, so fees below half a basis point show as
+    /// zero. `None` if a denominator is zero.
+    pub fn to_bps(&self) -> Option<FeesBps> {
+        let bps = |numerator: u64, denominator: u64| {
+            if denominator == 0 {
+                return None;
+            }
+            let bps = (u128::from(numerator) * u128::from(FEE_TIER_DENOMINATOR) * 2
+                + u128::from(denominator))
+                / (u128::from(denominator) * 2);
+            u64::try_from(bps).ok()
+        };
+        Some(FeesBps {
+            admin_trade_bps: bps(
+                self.admin_trade_fee_numerator,
+                self.admin_trade_fee_denominator,
+            )?,
+            admin_withdraw_bps: bps(
+                self.admin_withdraw_fee_numerator,
+                self.admin_withdraw_fee_denominator,
+            )?,
+            trade_bps: bps(self.trade_fee_numerator, self.trade_fee_denominator)?,
+            withdraw_bps: bps(self.withdraw_fee_numerator, self.withdraw_fee_denominator)?,
+            reflection_bps: bps(
+                self.reflection_fee_numerator,
+                self.reflection_fee_denominator,
+            )?,
+            buyback_bps: bps(self.buyback_fee_numerator, self.buyback_fee_denominator)?,
+            marketing_bps: bps(self.marketing_fee_numerator, self.marketing_fee_denominator)?,
+            developer_bps: bps(self.developer_fee_numerator, self.developer_fee_denominator)?,
+        })
+    }
+}
+
+/// Fluent builder of [Fees](struct.Fees.html) in basis points, unset fees are zero
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FeesBuilder {
+    bps: FeesBps,
+    admin_fee_in_pool_tokens: bool,
+    fee_on_input: bool,
+    imbalance_bps: u64,
+    imbalance_threshold_bps: u64,
+    host_bps: u64,
+}
+
+impl FeesBuilder {
+    /// Sets the admin share of the trade fee
+    pub fn admin_trade_bps(mut self, bps: u64) -> Self {
+        self.bps.admin_trade_bps = bps;
+        self
+    }
+
+    /// Sets the admin share of the withdraw fee
+    pub fn admin_withdraw_bps(mut self, bps: u64) -> Self {
+        self.bps.admin_withdraw_bps = bps;
+        self
+    }
+
+    /// Sets the trade fee
+    pub fn trade_bps(mut self, bps: u64) -> Self {
+        self.bps.trade_bps = bps;
+        self
+    }
+
+    /// Sets the withdraw fee
+    pub fn withdraw_bps(mut self, bps: u64) -> Self {
+        self.bps.withdraw_bps = bps;
+        self
+    }
+
+    /// Sets the reflection share of the trade fee
+    pub fn reflection_bps(mut self, bps: u64) -> Self {
+        self.bps.reflection_bps = bps;
+        self
+    }
+
+    /// Sets the buyback share of the trade fee
+    pub fn buyback_bps(mut self, bps: u64) -> Self {
+        self.bps.buyback_bps = bps;
+        self
+    }
+
+    /// Sets the marketing share of the trade fee
+    pub fn marketing_bps(mut self, bps: u64) -> Self {
+        self.bps.marketing_bps = bps;
+        self
+    }
+
+    /// Sets the developer share of the trade fee
+    pub fn developer_bps(mut self, bps: u64) -> Self {
+        self.bps.developer_bps = bps;
+        self
+    }
+
+    /// Takes the admin trade fee as newly minted pool tokens
+    pub fn admin_fee_in_pool_tokens(mut self, admin_fee_in_pool_tokens: bool) -> Self {
+        self.admin_fee_in_pool_tokens = admin_fee_in_pool_tokens;
+        self
+    }
+
+    /// Charges the trade fees in the source token of a swap
+    pub fn fee_on_input(mut self, fee_on_input: bool) -> Self {
+        self.fee_on_input = fee_on_input;
+        self
+    }
+
+    /// Sets the imbalance fee, charged on top of the trade fee by swaps
+    /// imbalancing the pool past `threshold_bps`
+    pub fn imbalance_bps(mut self, bps: u64, threshold_bps: u64) -> Self {
+        self.imbalance_bps = bps;
+        self.imbalance_threshold_bps = threshold_bps;
+        self
+    }
+
+    /// Sets the host share of the admin trade fee, paid to the referrer of a swap
+    pub fn host_bps(mut self, bps: u64) -> Self {
+        self.host_bps = bps;
+        self
+    }
+
+    /// Builds the fees
+    pub fn build(self) -> Fees {
+        Fees {
+            admin_fee_in_pool_tokens: self.admin_fee_in_pool_tokens,
+            fee_on_input: self.fee_on_input,
+            imbalance_fee_numerator: self.imbalance_bps,
+            imbalance_threshold_bps: self.imbalance_threshold_bps,
+            host_fee_numerator: self.host_bps,
+            ..Fees::from_bps(self.bps)
+        }
+    }
+}
+
+/// Canonical trade fee tiers, in basis points
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FeeTier {
+    /// Fees are supplied explicitly at initialization
+    #[default]
+    Custom = 0,
+    /// 0.01% trade fee
+    OneBps = 1,
+    /// 0.05% trade fee
+    FiveBps = 5,
+    /// 0.3% trade fee
+    ThirtyBps = 30,
+    /// 1% trade fee
+    HundredBps = 100,
+}
+
+impl TryFrom<u8> for FeeTier {
+    type Error = ();
+
+    /// Fee tier of its byte, as packed in account and instruction data
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Custom),
+            1 => Ok(Self::OneBps),
+            5 => Ok(Self::FiveBps),
+            30 => Ok(Self::ThirtyBps),
+            100 => Ok(Self::HundredBps),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FeeTier {
+    /// Full fee schedule for the tier, `None` for a custom tier
+    pub fn fees(&self) -> Option<Fees> {
+        if *self == Self::Custom {
+            return None;
+        }
+        Some(Fees::builder().trade_bps(*self as u64).build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "program")]
+    #[test]
+    fn pack_fees() {
+        let admin_trade_fee_numerator = 1;
+        let admin_trade_fee_denominator = 2;
+        let admin_withdraw_fee_numerator = 3;
+        let admin_withdraw_fee_denominator = 4;
+        let trade_fee_numerator = 5;
+        let trade_fee_denominator = 6;
+        let withdraw_fee_numerator = 7;
+        let withdraw_fee_denominator = 8;
+        let reflection_fee_numerator = 9;
+        let reflection_fee_denominator = 10;
+        let buyback_fee_numerator = 11;
+        let buyback_fee_denominator = 12;
+        let marketing_fee_numerator = 13;
+        let marketing_fee_denominator = 14;
+        let developer_fee_numerator = 15;
+        let developer_fee_denominator = 16;
+        let admin_fee_in_pool_tokens = true;
+        let fee_on_input = true;
+        let imbalance_fee_numerator = 17;
+        let imbalance_fee_denominator = 18;
+        let imbalance_threshold_bps = 19;
+        let host_fee_numerator = 20;
+        let host_fee_denominator = 21;
+        let fees = Fees {
+            admin_trade_fee_numerator,
+            admin_trade_fee_denominator,
+            admin_withdraw_fee_numerator,
+            admin_withdraw_fee_denominator,
+            trade_fee_numerator,
+            trade_fee_denominator,
+            withdraw_fee_numerator,
+            withdraw_fee_denominator,
+            reflection_fee_numerator,
+            reflection_fee_denominator,
+            buyback_fee_numerator,
+            buyback_fee_denominator,
+            marketing_fee_numerator,
+            marketing_fee_denominator,
+            developer_fee_numerator,
+            developer_fee_denominator,
+            admin_fee_in_pool_tokens,
+            fee_on_input,
+            imbalance_fee_numerator,
+            imbalance_fee_denominator,
+            imbalance_threshold_bps,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let mut packed = [0u8; Fees::LEN];
+        Pack::pack_into_slice(&fees, &mut packed[..]);
+        let unpacked = Fees::unpack_from_slice(&packed).unwrap();
+        assert_eq!(fees, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&admin_trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&admin_trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&admin_withdraw_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&admin_withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&withdraw_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&reflection_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&reflection_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&buyback_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&buyback_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&marketing_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&marketing_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&developer_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&developer_fee_denominator.to_le_bytes());
+        packed.push(admin_fee_in_pool_tokens as u8);
+        packed.push(fee_on_input as u8);
+        packed.extend_from_slice(&imbalance_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&imbalance_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&imbalance_threshold_bps.to_le_bytes());
+        packed.extend_from_slice(&host_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&host_fee_denominator.to_le_bytes());
+        let unpacked = Fees::unpack_from_slice(&packed).unwrap();
+        assert_eq!(fees, unpacked);
+    }
+
+    #[cfg(feature = "program")]
+    #[test]
+    fn pack_fees_byte_order() {
+        // Literal bytes rather than `to_le_bytes`, failing the same on any host
+        // byte order
+        let fees = Fees {
+            admin_trade_fee_numerator: 0x0102_0304_0506_0708,
+            admin_fee_in_pool_tokens: true,
+            imbalance_fee_numerator: 0x1122_3344_5566_7788,
+            host_fee_denominator: 0x00ff_0000_0000_0001,
+            ..Fees::default()
+        };
+        let mut packed = [0u8; Fees::LEN];
+        Pack::pack_into_slice(&fees, &mut packed[..]);
+        assert_eq!(packed[..8], [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(packed[8..128], [0; 120]);
+        assert_eq!(packed[128..130], [1, 0]);
+        assert_eq!(
+            packed[130..138],
+            [0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11]
+        );
+        assert_eq!(packed[138..162], [0; 24]);
+        assert_eq!(packed[162..], [1, 0, 0, 0, 0, 0, 0xff, 0]);
+        assert_eq!(Fees::unpack_from_slice(&packed), Ok(fees));
+
+        // flags are a single 0 or 1 byte
+        packed[129] = 2;
+        assert_eq!(
+            Fees::unpack_from_slice(&packed),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[cfg(feature = "program")]
+    proptest::proptest! {
+        #[test]
+        fn pack_fees_round_trip(
+            amounts in proptest::array::uniform21(proptest::prelude::any::<u64>()),
+            admin_fee_in_pool_tokens in proptest::bool::ANY,
+            fee_on_input in proptest::bool::ANY,
+        ) {
+            let fees = Fees {
+                admin_trade_fee_numerator: amounts[0],
+                admin_trade_fee_denominator: amounts[1],
+                admin_withdraw_fee_numerator: amounts[2],
+                admin_withdraw_fee_denominator: amounts[3],
+                trade_fee_numerator: amounts[4],
+                trade_fee_denominator: amounts[5],
+                withdraw_fee_numerator: amounts[6],
+                withdraw_fee_denominator: amounts[7],
+                reflection_fee_numerator: amounts[8],
+                reflection_fee_denominator: amounts[9],
+                buyback_fee_numerator: amounts[10],
+                buyback_fee_denominator: amounts[11],
+                marketing_fee_numerator: amounts[12],
+                marketing_fee_denominator: amounts[13],
+                developer_fee_numerator: amounts[14],
+                developer_fee_denominator: amounts[15],
+                admin_fee_in_pool_tokens,
+                fee_on_input,
+                imbalance_fee_numerator: amounts[16],
+                imbalance_fee_denominator: amounts[17],
+                imbalance_threshold_bps: amounts[18],
+                host_fee_numerator: amounts[19],
+                host_fee_denominator: amounts[20],
+            };
+            let mut packed = [0u8; Fees::LEN];
+            Pack::pack_into_slice(&fees, &mut packed[..]);
+            proptest::prop_assert_eq!(Fees::unpack_from_slice(&packed), Ok(fees));
+
+            // every field in declaration order, little-endian
+            let mut expected = [0u8; Fees::LEN];
+            let (amounts_head, amounts_tail) = amounts.split_at(16);
+            for (field, amount) in expected[..128].chunks_exact_mut(8).zip(amounts_head) {
+                field.copy_from_slice(&amount.to_le_bytes());
+            }
+            expected[128] = admin_fee_in_pool_tokens as u8;
+            expected[129] = fee_on_input as u8;
+            for (field, amount) in expected[130..].chunks_exact_mut(8).zip(amounts_tail) {
+                field.copy_from_slice(&amount.to_le_bytes());
+            }
+            proptest::prop_assert_eq!(&packed[..], &expected[..]);
+        }
+    }
+
+    #[test]
+    fn fee_results() {
+        let admin_trade_fee_numerator = 1;
+        let admin_trade_fee_denominator = 2;
+        let admin_withdraw_fee_numerator = 3;
+        let admin_withdraw_fee_denominator = 4;
+        let trade_fee_numerator = 5;
+        let trade_fee_denominator = 6;
+        let withdraw_fee_numerator = 7;
+        let withdraw_fee_denominator = 8;
+        let reflection_fee_numerator = 9;
+        let reflection_fee_denominator = 10;
+        let buyback_fee_numerator = 11;
+        let buyback_fee_denominator = 12;
+        let marketing_fee_numerator = 13;
+        let marketing_fee_denominator = 14;
+        let developer_fee_numerator = 15;
+        let developer_fee_denominator = 16;
+        let admin_fee_in_pool_tokens = true;
+        let fee_on_input = true;
+        let imbalance_fee_numerator = 17;
+        let imbalance_fee_denominator = 18;
+        let imbalance_threshold_bps = 19;
+        let host_fee_numerator = 20;
+        let host_fee_denominator = 21;
+        let fees = Fees {
+            admin_trade_fee_numerator,
+            admin_trade_fee_denominator,
+            admin_withdraw_fee_numerator,
+            admin_withdraw_fee_denominator,
+            trade_fee_numerator,
+            trade_fee_denominator,
+            withdraw_fee_numerator,
+            withdraw_fee_denominator,
+            reflection_fee_numerator,
+            reflection_fee_denominator,
+            buyback_fee_numerator,
+            buyback_fee_denominator,
+            marketing_fee_numerator,
+            marketing_fee_denominator,
+            developer_fee_numerator,
+            developer_fee_denominator,
+            admin_fee_in_pool_tokens,
+            fee_on_input,
+            imbalance_fee_numerator,
+            imbalance_fee_denominator,
+            imbalance_threshold_bps,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let trade_amount = 1_000_000_000;
+        let expected_trade_fee = trade_amount * trade_fee_numerator / trade_fee_denominator;
+        let trade_fee = fees.trade_fee(trade_amount.into()).unwrap();
+        assert_eq!(trade_fee, expected_trade_fee.into());
+        let expected_admin_trade_fee =
+            expected_trade_fee * admin_trade_fee_numerator / admin_trade_fee_denominator;
+        assert_eq!(
+            fees.admin_trade_fee(trade_fee).unwrap(),
+            expected_admin_trade_fee.into()
+        );
+        let expected_host_fee =
+            expected_admin_trade_fee * host_fee_numerator / host_fee_denominator;
+        assert_eq!(
+            fees.host_fee(expected_admin_trade_fee.into()).unwrap(),
+            expected_host_fee.into()
+        );
+        // no host fee without a numerator, and at most the whole admin fee
+        let hostless_fees = Fees {
+            host_fee_numerator: 0,
+            host_fee_denominator: 0,
+            ..fees
+        };
+        assert_eq!(hostless_fees.host_fee(trade_fee).unwrap(), U256::zero());
+        let greedy_fees = Fees {
+            host_fee_numerator: 2,
+            host_fee_denominator: 1,
+            ..fees
+        };
+        assert_eq!(greedy_fees.host_fee(trade_fee).unwrap(), trade_fee);
+
+        let withdraw_amount = 100_000_000_000;
+        let expected_withdraw_fee =
+            withdraw_amount * withdraw_fee_numerator / withdraw_fee_denominator;
+        let withdraw_fee = fees.withdraw_fee(withdraw_amount.into()).unwrap();
+        assert_eq!(withdraw_fee, expected_withdraw_fee.into());
+        let expected_admin_withdraw_fee =
+            expected_withdraw_fee * admin_withdraw_fee_numerator / admin_withdraw_fee_denominator;
+        assert_eq!(
+            fees.admin_withdraw_fee(expected_withdraw_fee.into())
+                .unwrap(),
+            expected_admin_withdraw_fee.into()
+        );
+        // the admin share is capped at the whole fee
+        let greedy_fees = Fees {
+            admin_withdraw_fee_numerator: 2,
+            admin_withdraw_fee_denominator: 1,
+            ..fees
+        };
+        assert_eq!(
+            greedy_fees.admin_withdraw_fee(withdraw_fee).unwrap(),
+            withdraw_fee
+        );
+
+        let n_coins = 2;
+        let adjusted_trade_fee_numerator = trade_fee_numerator * n_coins / (4 * (n_coins - 1));
+        let expected_normalized_fee =
+            U256::from(trade_amount * adjusted_trade_fee_numerator / trade_fee_denominator);
+        assert_eq!(
+            fees.normalized_trade_fee(n_coins, trade_amount.into())
+                .unwrap(),
+            expected_normalized_fee.into()
+        );
+    }
+
+    #[test]
+    fn imbalance_fees() {
+        assert_eq!(imbalance_bps(1_000.into(), 1_000.into()), Some(0));
+        assert_eq!(imbalance_bps(3_000.into(), 1_000.into()), Some(5_000));
+        assert_eq!(imbalance_bps(1_000.into(), 3_000.into()), Some(5_000));
+        assert_eq!(
+            imbalance_bps(0.into(), 5.into()),
+            Some(FEE_TIER_DENOMINATOR)
+        );
+        assert_eq!(imbalance_bps(0.into(), 0.into()), Some(0));
+
+        let fees = Fees::builder()
+            .trade_bps(30)
+            .imbalance_bps(100, 2_000)
+            .build();
+        assert!(fees.is_imbalance_fee_enabled());
+        assert_eq!(fees.imbalance_fee_denominator, FEE_TIER_DENOMINATOR);
+        let trade_amount = U256::from(1_000_000);
+        let trade_fee = |fees: Fees| fees.trade_fee(trade_amount).unwrap();
+
+        // balanced enough, or rebalancing the pool
+        assert_eq!(fees.with_imbalance_fee(0, 2_000), Some(fees));
+        assert_eq!(fees.with_imbalance_fee(6_000, 5_000), Some(fees));
+        assert_eq!(trade_fee(fees), 3_000.into());
+
+        // half way from the threshold to a drained pool pays half the imbalance fee
+        let imbalanced = fees.with_imbalance_fee(0, 6_000).unwrap();
+        assert_eq!(trade_fee(imbalanced), 8_000.into());
+        assert_eq!(
+            imbalanced.reflection_fee_numerator,
+            fees.reflection_fee_numerator
+        );
+        let drained = fees
+            .with_imbalance_fee(5_000, FEE_TIER_DENOMINATOR)
+            .unwrap();
+        assert_eq!(trade_fee(drained), 13_000.into());
+
+        let fees = Fees::builder().trade_bps(30).build();
+        assert!(!fees.is_imbalance_fee_enabled());
+        assert_eq!(fees.with_imbalance_fee(0, FEE_TIER_DENOMINATOR), Some(fees));
+    }
+
+    #[test]
+    fn fee_multiplier() {
+        let fees = Fees::builder()
+            .trade_bps(30)
+            .imbalance_bps(100, 2_000)
+            .build();
+        assert_eq!(fees.with_fee_multiplier(FEE_TIER_DENOMINATOR), Some(fees));
+
+        let doubled = fees.with_fee_multiplier(2 * FEE_TIER_DENOMINATOR).unwrap();
+        assert_eq!(doubled.trade_fee_numerator, 60);
+        assert_eq!(doubled.imbalance_fee_numerator, 200);
+        assert_eq!(doubled.trade_fee_denominator, fees.trade_fee_denominator);
+        assert_eq!(
+            doubled.admin_trade_fee_numerator,
+            fees.admin_trade_fee_numerator
+        );
+
+        // rounds up
+        let fees = Fees::builder().trade_bps(1).build();
+        let scaled = fees.with_fee_multiplier(15_000).unwrap();
+        assert_eq!(scaled.trade_fee_numerator, 2);
+
+        // never more than the whole amount
+        let fees = Fees::builder().trade_bps(5_000).build();
+        let scaled = fees.with_fee_multiplier(30_000).unwrap();
+        assert_eq!(scaled.trade_fee_numerator, scaled.trade_fee_denominator);
+    }
+
+    #[test]
+    fn fee_tier_fees() {
+        assert_eq!(FeeTier::Custom.fees(), None);
+        for (tier, bps) in [
+            (FeeTier::OneBps, 1),
+            (FeeTier::FiveBps, 5),
+            (FeeTier::ThirtyBps, 30),
+            (FeeTier::HundredBps, 100),
+        ]
+        .iter()
+        {
+            let fees = tier.fees().unwrap();
+            let trade_amount = 1_000_000;
+            assert_eq!(
+                fees.trade_fee(trade_amount.into()).unwrap(),
+                (trade_amount * bps / FEE_TIER_DENOMINATOR).into()
+            );
+            assert_eq!(fees.withdraw_fee(trade_amount.into()).unwrap(), 0.into());
+            assert_eq!(fees.admin_trade_fee(trade_amount.into()).unwrap(), 0.into());
+        }
+    }
+
+    #[test]
+    fn fee_tier_try_from() {
+        for tier in [
+            FeeTier::Custom,
+            FeeTier::OneBps,
+            FeeTier::FiveBps,
+            FeeTier::ThirtyBps,
+            FeeTier::HundredBps,
+        ]
+        .iter()
+        {
+            assert_eq!(FeeTier::try_from(*tier as u8), Ok(*tier));
+        }
+        assert_eq!(FeeTier::try_from(2), Err(()));
+        assert_eq!(FeeTier::try_from(u8::MAX), Err(()));
+    }
+
+    #[test]
+    fn fees_bps() {
+        let bps = FeesBps {
+            admin_trade_bps: 5_000,
+            admin_withdraw_bps: 2_500,
+            trade_bps: 30,
+            withdraw_bps: 5,
+            reflection_bps: 1_000,
+            buyback_bps: 500,
+            marketing_bps: 200,
+            developer_bps: 100,
+        };
+        let fees = Fees::from_bps(bps);
+        assert_eq!(fees.trade_fee_numerator, 30);
+        assert_eq!(fees.trade_fee_denominator, FEE_TIER_DENOMINATOR);
+        assert_eq!(fees.developer_fee_numerator, 100);
+        assert_eq!(fees.to_bps(), Some(bps));
+
+        let built = Fees::builder()
+            .admin_trade_bps(5_000)
+            .admin_withdraw_bps(2_500)
+            .trade_bps(30)
+            .withdraw_bps(5)
+            .reflection_bps(1_000)
+            .buyback_bps(500)
+            .marketing_bps(200)
+            .developer_bps(100)
+            .fee_on_input(true)
+            .host_bps(2_000)
+            .build();
+        assert_eq!(
+            built,
+            Fees {
+                fee_on_input: true,
+                host_fee_numerator: 2_000,
+                ..fees
+            }
+        );
+        assert_eq!(Fees::builder().build(), Fees::from_bps(FeesBps::default()));
+
+        // fees of other denominators round to the nearest basis point, halves up
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 3,
+            withdraw_fee_numerator: 1,
+            withdraw_fee_denominator: 20_000,
+            reflection_fee_numerator: 1,
+            reflection_fee_denominator: 20_001,
+            ..fees
+        };
+        let rounded = fees.to_bps().unwrap();
+        assert_eq!(rounded.trade_bps, 3_333);
+        assert_eq!(rounded.withdraw_bps, 1);
+        assert_eq!(rounded.reflection_bps, 0);
+        assert_eq!(Fees::default().to_bps(), None);
+    }
+}
@@ -0,0 +1,18 @@
+#![deny(missing_docs)]
+#![cfg_attr(not(test), no_std)]
+
+//! StableSwap math shared by the on-chain program and off-chain clients: the
+//! curve invariant, fees, pool token conversions and oracle guard settings.
+//! Builds without `std` and, unless the `program` feature is enabled, without
+//! solana-program. The `client` feature adds quotes of the instructions of a pool
+//! from its raw balances and recommended parameters of new pools.
+
+pub mod bn;
+pub mod curve;
+pub mod fees;
+pub mod oracle;
+#[cfg(any(test, feature = "client"))]
+pub mod params;
+pub mod pool_converter;
+#[cfg(any(test, feature = "client"))]
+pub mod quote;
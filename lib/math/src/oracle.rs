@@ -0,0 +1,26 @@
+//! Oracle guard settings
+
+/// What the oracle guard does with swaps while an oracle price is stale
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum OracleStalePolicy {
+    /// Swaps are rejected
+    #[default]
+    Reject = 0,
+    /// Swaps go through unchecked
+    Ignore = 1,
+    /// Swaps are checked against the stale prices, with a wider band
+    WidenBand = 2,
+}
+
+impl OracleStalePolicy {
+    /// Stale policy of its byte, as packed in account and instruction data
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Reject),
+            1 => Some(Self::Ignore),
+            2 => Some(Self::WidenBand),
+            _ => None,
+        }
+    }
+}
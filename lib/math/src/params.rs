@@ -0,0 +1,178 @@
+//! Recommended parameters of new pools, for operators initializing a pool
+//! without tuning each parameter themselves
+
+use crate::{
+    curve::{CurveType, MIN_AMP},
+    fees::FeeTier,
+    oracle::OracleStalePolicy,
+};
+
+/// How far apart the prices of the two tokens of a pool move
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VolatilityClass {
+    /// Tokens pegged to the same asset, such as two dollar stablecoins
+    Pegged,
+    /// Tokens tracking each other loosely, such as a liquid staking token and its
+    /// underlying token
+    Correlated,
+    /// Tokens of unrelated prices
+    Volatile,
+}
+
+/// Parameters to initialize a pool with
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PoolParams {
+    /// Curve the tokens are priced along
+    pub curve_type: CurveType,
+    /// Amp. Coefficient of a `StableSwap` curve, `MIN_AMP` for other curves
+    pub amp_factor: u64,
+    /// Trade fee tier
+    pub fee_tier: FeeTier,
+    /// Minimum amount of pool tokens a deposit has to mint, a thousandth of a
+    /// pool token
+    pub min_lp_mint: u64,
+    /// Largest balance `SweepDust` swaps free of fees, a ten thousandth of a token
+    pub dust_threshold: u64,
+    /// Oracle guard of the swaps, once the pool has price oracles
+    pub oracle_guard: OracleGuardParams,
+}
+
+/// Oracle guard settings, of `SetOracleGuard` and `SetOracleStalePolicy`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OracleGuardParams {
+    /// Maximum age of the oracle prices, in slots
+    pub max_staleness_slots: u64,
+    /// Maximum confidence interval of the oracle prices, in basis points of the
+    /// price
+    pub max_confidence_bps: u16,
+    /// Band past the oracle value of their input swaps may pay out, in basis points
+    pub max_deviation_bps: u16,
+    /// What happens to swaps while a price is stale
+    pub stale_policy: OracleStalePolicy,
+    /// Band added to `max_deviation_bps` while a price is stale, for the
+    /// `WidenBand` policy
+    pub stale_band_bps: u16,
+}
+
+/// Oracle prices older than about ten seconds are stale
+const ORACLE_MAX_STALENESS_SLOTS: u64 = 25;
+
+/// Recommends the parameters of a pool of two tokens of `token_volatility_class`
+/// and of mints of `decimals_a` and `decimals_b` decimals. `None` when the mints
+/// have different decimals, which pools reject, or more decimals than an amount
+/// of a whole token fits.
+pub fn recommend(
+    token_volatility_class: VolatilityClass,
+    decimals_a: u8,
+    decimals_b: u8,
+) -> Option<PoolParams> {
+    if decimals_a != decimals_b {
+        return None;
+    }
+    // The pool mint has the decimals of the token mints
+    let one_token = 10u64.checked_pow(decimals_a.into())?;
+    let (curve_type, amp_factor, fee_tier) = match token_volatility_class {
+        VolatilityClass::Pegged => (CurveType::StableSwap, 500, FeeTier::OneBps),
+        VolatilityClass::Correlated => (CurveType::StableSwap, 50, FeeTier::FiveBps),
+        VolatilityClass::Volatile => (CurveType::ConstantProduct, MIN_AMP, FeeTier::ThirtyBps),
+    };
+    // The price of pegged tokens barely moves while their oracles are stale, the
+    // price of volatile tokens is anyone's guess
+    let (max_confidence_bps, max_deviation_bps, stale_policy, stale_band_bps) =
+        match token_volatility_class {
+            VolatilityClass::Pegged => (10, 50, OracleStalePolicy::WidenBand, 50),
+            VolatilityClass::Correlated => (50, 200, OracleStalePolicy::WidenBand, 200),
+            VolatilityClass::Volatile => (100, 500, OracleStalePolicy::Reject, 0),
+        };
+    Some(PoolParams {
+        curve_type,
+        amp_factor,
+        fee_tier,
+        min_lp_mint: (one_token / 1_000).max(1),
+        dust_threshold: one_token / 10_000,
+        oracle_guard: OracleGuardParams {
+            max_staleness_slots: ORACLE_MAX_STALENESS_SLOTS,
+            max_confidence_bps,
+            max_deviation_bps,
+            stale_policy,
+            stale_band_bps,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::MAX_AMP;
+
+    #[test]
+    fn test_recommend() {
+        assert_eq!(
+            recommend(VolatilityClass::Pegged, 6, 6),
+            Some(PoolParams {
+                curve_type: CurveType::StableSwap,
+                amp_factor: 500,
+                fee_tier: FeeTier::OneBps,
+                min_lp_mint: 1_000,
+                dust_threshold: 100,
+                oracle_guard: OracleGuardParams {
+                    max_staleness_slots: ORACLE_MAX_STALENESS_SLOTS,
+                    max_confidence_bps: 10,
+                    max_deviation_bps: 50,
+                    stale_policy: OracleStalePolicy::WidenBand,
+                    stale_band_bps: 50,
+                },
+            })
+        );
+        assert_eq!(
+            recommend(VolatilityClass::Volatile, 9, 9),
+            Some(PoolParams {
+                curve_type: CurveType::ConstantProduct,
+                amp_factor: MIN_AMP,
+                fee_tier: FeeTier::ThirtyBps,
+                min_lp_mint: 1_000_000,
+                dust_threshold: 100_000,
+                oracle_guard: OracleGuardParams {
+                    max_staleness_slots: ORACLE_MAX_STALENESS_SLOTS,
+                    max_confidence_bps: 100,
+                    max_deviation_bps: 500,
+                    stale_policy: OracleStalePolicy::Reject,
+                    stale_band_bps: 0,
+                },
+            })
+        );
+
+        // less volatile tokens concentrate more liquidity around the peg, for less fees
+        let pegged = recommend(VolatilityClass::Pegged, 9, 9).unwrap();
+        let correlated = recommend(VolatilityClass::Correlated, 9, 9).unwrap();
+        assert!(pegged.amp_factor > correlated.amp_factor);
+        assert!((pegged.fee_tier as u8) < (correlated.fee_tier as u8));
+        assert!(pegged.oracle_guard.max_deviation_bps < correlated.oracle_guard.max_deviation_bps);
+        for params in [pegged, correlated] {
+            assert!((MIN_AMP..=MAX_AMP).contains(&params.amp_factor));
+        }
+
+        // the oracle guard settings are valid, and its band is wider than the fee
+        for class in [
+            VolatilityClass::Pegged,
+            VolatilityClass::Correlated,
+            VolatilityClass::Volatile,
+        ] {
+            let params = recommend(class, 6, 6).unwrap();
+            let oracle_guard = params.oracle_guard;
+            assert!((1..=10_000).contains(&oracle_guard.max_confidence_bps));
+            assert!(oracle_guard.max_deviation_bps <= 10_000);
+            assert!(oracle_guard.stale_band_bps <= 10_000);
+            assert!(oracle_guard.max_deviation_bps > params.fee_tier as u16);
+        }
+
+        // tokens without decimals
+        let params = recommend(VolatilityClass::Pegged, 0, 0).unwrap();
+        assert_eq!((params.min_lp_mint, params.dust_threshold), (1, 0));
+
+        // mismatched decimals
+        assert_eq!(recommend(VolatilityClass::Pegged, 6, 9), None);
+        // a whole token overflows u64
+        assert_eq!(recommend(VolatilityClass::Volatile, 20, 20), None);
+    }
+}
@@ -36,6 +36,14 @@ impl PoolTokenConverter<'_> {
 
         Some((amount.checked_sub(fee)?, admin_fee))
     }
+
+    /// Pool tokens to mint so that the recipient owns the growth of the
+    /// invariant from `d_0` to `d_1`
+    pub fn pool_tokens_for_invariant_growth(&self, d_0: U256, d_1: U256) -> Option<U256> {
+        self.supply
+            .checked_mul(d_1.checked_sub(d_0)?)?
+            .checked_div(d_0)
+    }
 }
 
 #[cfg(test)]
@@ -66,6 +74,13 @@ mod tests {
             marketing_fee_denominator: 2,
             developer_fee_numerator: 1,
             developer_fee_denominator: 2,
+            admin_fee_in_pool_tokens: false,
+            fee_on_input: false,
+            imbalance_fee_numerator: 0,
+            imbalance_fee_denominator: 1,
+            imbalance_threshold_bps: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 1,
         };
         let calculator = PoolTokenConverter {
             supply,
@@ -93,4 +108,32 @@ mod tests {
         check_pool_token_a_rate(5.into(), U256::MAX, 5.into(), 10.into(), Some(2.into()));
         check_pool_token_a_rate(U256::MAX, U256::MAX, 5.into(), 10.into(), None);
     }
+
+    #[test]
+    fn invariant_growth_tokens() {
+        let fees = Fees::default();
+        let calculator = PoolTokenConverter {
+            supply: 1_000.into(),
+            token_a: 500.into(),
+            token_b: 500.into(),
+            fees: &fees,
+        };
+        // growing D by 1% entitles the recipient to 1% of the existing supply
+        assert_eq!(
+            calculator.pool_tokens_for_invariant_growth(10_000.into(), 10_100.into()),
+            Some(10.into())
+        );
+        assert_eq!(
+            calculator.pool_tokens_for_invariant_growth(10_000.into(), 10_000.into()),
+            Some(0.into())
+        );
+        assert_eq!(
+            calculator.pool_tokens_for_invariant_growth(10_000.into(), 9_999.into()),
+            None
+        );
+        assert_eq!(
+            calculator.pool_tokens_for_invariant_growth(0.into(), 1.into()),
+            None
+        );
+    }
 }
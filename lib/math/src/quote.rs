@@ -0,0 +1,263 @@
+//! Quotes of the swaps, deposits and withdrawals of a pool, from the raw
+//! balances of its reserves, for off-chain clients embedding the exact math of
+//! the program.
+//!
+//! The reserves are the pool amounts of the swap token accounts, without the
+//! fees set aside in them, and `fees` the fees the instruction is charged: the
+//! pool fees scaled by `Fees::with_fee_multiplier` while the volatility fee is
+//! enabled. Token-2022 transfer fees, deposit bonuses and the admin fee epoch
+//! cap depend on accounts other than the reserves and are left out.
+
+use crate::{bn::U256, curve::SwapCurve, fees::Fees, pool_converter::PoolTokenConverter};
+
+/// Outcome of a swap
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SwapQuote {
+    /// Destination token paid out
+    pub amount_out: u64,
+    /// Total trade fee, of which the admin fee is a share
+    pub trade_fee: u64,
+    /// Admin share of the trade fee
+    pub admin_fee: u64,
+    /// Pool amount of the source token after the swap
+    pub new_source_amount: u64,
+    /// Pool amount of the destination token after the swap
+    pub new_destination_amount: u64,
+}
+
+/// Outcome of a deposit of both tokens
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DepositQuote {
+    /// Token A taken from the depositor
+    pub token_a_amount: u64,
+    /// Token B taken from the depositor
+    pub token_b_amount: u64,
+    /// Pool tokens minted
+    pub mint_amount: u64,
+}
+
+/// Outcome of a withdrawal of both tokens
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct WithdrawQuote {
+    /// Token A paid out
+    pub token_a_amount: u64,
+    /// Token B paid out
+    pub token_b_amount: u64,
+    /// Admin share of the token A withdraw fee
+    pub admin_fee_a: u64,
+    /// Admin share of the token B withdraw fee
+    pub admin_fee_b: u64,
+}
+
+/// Quotes swapping `amount_in` of the source token, as `Swap` does
+pub fn quote_swap(
+    curve: &impl SwapCurve,
+    amount_in: u64,
+    swap_source_amount: u64,
+    swap_destination_amount: u64,
+    fees: &Fees,
+) -> Option<SwapQuote> {
+    let result = curve.swap_to_v2(
+        amount_in.into(),
+        swap_source_amount.into(),
+        swap_destination_amount.into(),
+        fees,
+    )?;
+    Some(SwapQuote {
+        amount_out: result.amount_swapped.try_into_u64().ok()?,
+        trade_fee: result.trade_fee.try_into_u64().ok()?,
+        admin_fee: result.admin_fee.try_into_u64().ok()?,
+        new_source_amount: result.new_source_amount.try_into_u64().ok()?,
+        new_destination_amount: result.new_destination_amount.try_into_u64().ok()?,
+    })
+}
+
+/// Quotes depositing up to `token_a_amount` and `token_b_amount`, as `Deposit`
+/// does: only the pool ratio cost of the minted pool tokens is taken when it
+/// mints as much as the whole amounts
+pub fn quote_deposit(
+    curve: &impl SwapCurve,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    pool_token_supply: u64,
+    fees: &Fees,
+) -> Option<DepositQuote> {
+    let compute_mint = |token_a_amount: u64, token_b_amount: u64| {
+        curve
+            .compute_mint_amount_for_deposit(
+                token_a_amount.into(),
+                token_b_amount.into(),
+                reserve_a.into(),
+                reserve_b.into(),
+                pool_token_supply.into(),
+                fees,
+            )?
+            .try_into_u64()
+            .ok()
+    };
+    let mint_amount = compute_mint(token_a_amount, token_b_amount)?;
+    let quote = DepositQuote {
+        token_a_amount,
+        token_b_amount,
+        mint_amount,
+    };
+    if pool_token_supply == 0 {
+        return Some(quote);
+    }
+    // Rounded up against the depositor
+    let cost = |reserve: u64| {
+        U256::from(mint_amount)
+            .checked_mul(reserve.into())?
+            .checked_add(U256::from(pool_token_supply - 1))?
+            .checked_div(pool_token_supply.into())?
+            .try_into_u64()
+            .ok()
+    };
+    let cost_a = cost(reserve_a)?;
+    let cost_b = cost(reserve_b)?;
+    if cost_a <= token_a_amount && cost_b <= token_b_amount {
+        if let Some(cost_mint_amount) = compute_mint(cost_a, cost_b) {
+            if cost_mint_amount >= mint_amount {
+                return Some(DepositQuote {
+                    token_a_amount: cost_a,
+                    token_b_amount: cost_b,
+                    mint_amount: cost_mint_amount,
+                });
+            }
+        }
+    }
+    Some(quote)
+}
+
+/// Quotes burning `pool_token_amount` for both tokens at the pool ratio, as
+/// `Withdraw` does
+pub fn quote_withdraw(
+    pool_token_amount: u64,
+    reserve_a: u64,
+    reserve_b: u64,
+    pool_token_supply: u64,
+    fees: &Fees,
+) -> Option<WithdrawQuote> {
+    let converter = PoolTokenConverter {
+        supply: pool_token_supply.into(),
+        token_a: reserve_a.into(),
+        token_b: reserve_b.into(),
+        fees,
+    };
+    let (token_a_amount, admin_fee_a) = converter.token_a_rate(pool_token_amount.into())?;
+    let (token_b_amount, admin_fee_b) = converter.token_b_rate(pool_token_amount.into())?;
+    Some(WithdrawQuote {
+        token_a_amount: token_a_amount.try_into_u64().ok()?,
+        token_b_amount: token_b_amount.try_into_u64().ok()?,
+        admin_fee_a: admin_fee_a.try_into_u64().ok()?,
+        admin_fee_b: admin_fee_b.try_into_u64().ok()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::{ConstantProduct, PoolCurve, StableSwap, ZERO_TS};
+
+    fn fees() -> Fees {
+        Fees::builder()
+            .admin_trade_bps(5_000)
+            .admin_withdraw_bps(5_000)
+            .trade_bps(30)
+            .withdraw_bps(10)
+            .build()
+    }
+
+    #[test]
+    fn test_quote_swap() {
+        let fees = fees();
+        let stable_swap = StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS);
+        for curve in [
+            PoolCurve::StableSwap(stable_swap),
+            PoolCurve::ConstantProduct(ConstantProduct),
+        ] {
+            let quote = quote_swap(&curve, 10_000, 1_000_000, 1_000_000, &fees).unwrap();
+            let result = curve
+                .swap_to_v2(10_000.into(), 1_000_000.into(), 1_000_000.into(), &fees)
+                .unwrap();
+            assert_eq!(U256::from(quote.amount_out), result.amount_swapped);
+            assert_eq!(U256::from(quote.trade_fee), result.trade_fee);
+            assert_eq!(U256::from(quote.admin_fee), result.admin_fee);
+            assert_eq!(quote.new_source_amount, 1_010_000);
+            assert_eq!(
+                U256::from(quote.new_destination_amount),
+                result.new_destination_amount
+            );
+            assert!(quote.trade_fee > 0 && quote.admin_fee == quote.trade_fee / 2);
+        }
+        // the stable swap pays out more around the peg
+        let stable_quote = quote_swap(&stable_swap, 10_000, 1_000_000, 1_000_000, &fees).unwrap();
+        let constant_product_quote =
+            quote_swap(&ConstantProduct, 10_000, 1_000_000, 1_000_000, &fees).unwrap();
+        assert!(stable_quote.amount_out > constant_product_quote.amount_out);
+
+        // draining the destination
+        assert_eq!(quote_swap(&stable_swap, u64::MAX, 1_000, 0, &fees), None);
+    }
+
+    #[test]
+    fn test_quote_deposit() {
+        let fees = fees();
+        let curve = StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS);
+        let (reserve_a, reserve_b, supply) = (1_000_000, 2_000_000, 3_000_000);
+
+        // at the pool ratio, the remainder lost to rounding stays with the depositor
+        let quote =
+            quote_deposit(&curve, 1_001, 2_002, reserve_a, reserve_b, supply, &fees).unwrap();
+        let mint_amount = curve
+            .compute_mint_amount_for_deposit(
+                1_001.into(),
+                2_002.into(),
+                reserve_a.into(),
+                reserve_b.into(),
+                supply.into(),
+                &fees,
+            )
+            .unwrap();
+        assert_eq!(U256::from(quote.mint_amount), mint_amount);
+        assert!(quote.token_a_amount <= 1_001 && quote.token_b_amount <= 2_002);
+        assert_eq!(
+            (quote.token_a_amount, quote.token_b_amount),
+            (
+                (quote.mint_amount * reserve_a).div_ceil(supply),
+                (quote.mint_amount * reserve_b).div_ceil(supply)
+            )
+        );
+
+        // imbalanced deposits are taken whole
+        let quote = quote_deposit(&curve, 10_000, 0, reserve_a, reserve_b, supply, &fees).unwrap();
+        assert_eq!((quote.token_a_amount, quote.token_b_amount), (10_000, 0));
+        assert!(quote.mint_amount > 0);
+
+        // nothing deposited
+        assert_eq!(
+            quote_deposit(&curve, 0, 0, reserve_a, reserve_b, supply, &fees),
+            None
+        );
+    }
+
+    #[test]
+    fn test_quote_withdraw() {
+        let fees = fees();
+        let quote = quote_withdraw(30_000, 1_000_000, 2_000_000, 3_000_000, &fees).unwrap();
+        // 10 bps withdraw fee, half of it to the admin
+        assert_eq!(
+            quote,
+            WithdrawQuote {
+                token_a_amount: 9_990,
+                token_b_amount: 19_980,
+                admin_fee_a: 5,
+                admin_fee_b: 10,
+            }
+        );
+
+        assert_eq!(quote_withdraw(30_000, 1_000_000, 2_000_000, 0, &fees), None);
+    }
+}
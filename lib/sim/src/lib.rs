@@ -9,7 +9,7 @@ pub const MODEL_FEE_DENOMINATOR: u64 = 10000000000;
 const DEFAULT_POOL_TOKENS: u128 = 0;
 const DEFAULT_TARGET_PRICE: u128 = 1000000000000000000;
 const FILE_NAME: &str = "simulation.py";
-const FILE_PATH: &str = "lib/sim/simulation.py";
+const FILE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/simulation.py");
 const MODULE_NAME: &str = "simulation";
 
 pub struct Model {
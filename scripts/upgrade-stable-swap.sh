@@ -0,0 +1,108 @@
+#!/usr/bin/env bash
+set -e
+
+usage() {
+    cat <<EOF
+Usage: upgrade-stable-swap.sh <action> <network> <action specific arguments>
+Supported actions:
+    prepare <network> <multisig-signer>
+        Build the program reproducibly, write it to a buffer and hand the buffer
+        authority to the admin multisig signer
+    verify <network> <buffer-or-program>
+        Check a buffer or a deployed program against the reproducible build
+    propose <network> <program-id> <buffer> <multisig-signer> <spill>
+        Verify the buffer and print the upgrade instruction to propose to the
+        admin multisig, which must be the program upgrade authority
+EOF
+}
+
+PROGRAM_SO="target/deploy/stable_swap.so"
+BPF_LOADER_UPGRADEABLE_ID="BPFLoaderUpgradeab1e11111111111111111111111"
+SYSVAR_RENT_ID="SysvarRent111111111111111111111111111111111"
+SYSVAR_CLOCK_ID="SysvarC1ock11111111111111111111111111111111"
+
+set_cluster() {
+    if [[ $1 == "localnet" ]]; then
+        CLUSTER_URL="http://localhost:8899"
+    elif [[ $1 == "devnet" ]]; then
+        CLUSTER_URL="https://api.devnet.solana.com"
+    elif [[ $1 == "testnet" ]]; then
+        CLUSTER_URL="https://api.testnet.solana.com"
+    elif [[ $1 == "mainnet" ]]; then
+        CLUSTER_URL="https://api.mainnet-beta.solana.com"
+    else
+        echo "Unsupported network: $1"
+        exit 1
+    fi
+    solana config set --url $CLUSTER_URL >/dev/null
+}
+
+# The build runs in a pinned image, so anyone can rebuild the exact buffer contents
+verifiable_build() {
+    cargo xtask build
+}
+
+verify() {
+    cargo xtask verify --skip-build --url "$CLUSTER_URL" "$1"
+}
+
+if [[ "$#" -lt 2 ]]; then
+    usage
+    exit 1
+fi
+
+cd "$(dirname "$0")/.."
+set_cluster "$2"
+
+case "$1" in
+prepare)
+    verifiable_build
+    BUFFER="$(solana program write-buffer "$PROGRAM_SO" --output json | jq .buffer -r)"
+    solana program set-buffer-authority "$BUFFER" --new-buffer-authority "$3"
+    echo "Buffer:" $BUFFER
+    ;;
+verify)
+    verifiable_build
+    verify "$3"
+    ;;
+propose)
+    verifiable_build
+    verify "$4"
+    PROGRAM_INFO="$(solana program show "$3" --output json)"
+    if [[ "$(echo "$PROGRAM_INFO" | jq .authority -r)" != "$5" ]]; then
+        echo "Upgrade authority of $3 is not $5"
+        exit 1
+    fi
+    if [[ "$(solana program show "$4" --output json | jq .authority -r)" != "$5" ]]; then
+        echo "Buffer authority of $4 is not $5"
+        exit 1
+    fi
+    # Upgrade instruction of the upgradeable loader, `data` is its bincode tag
+    jq -n \
+        --arg PROGRAM_ID "$BPF_LOADER_UPGRADEABLE_ID" \
+        --arg PROGRAM_DATA "$(echo "$PROGRAM_INFO" | jq .programdataAddress -r)" \
+        --arg PROGRAM "$3" \
+        --arg BUFFER "$4" \
+        --arg SPILL "$6" \
+        --arg RENT "$SYSVAR_RENT_ID" \
+        --arg CLOCK "$SYSVAR_CLOCK_ID" \
+        --arg AUTHORITY "$5" \
+        '{
+            programId: $PROGRAM_ID,
+            accounts: [
+                {pubkey: $PROGRAM_DATA, isSigner: false, isWritable: true},
+                {pubkey: $PROGRAM, isSigner: false, isWritable: true},
+                {pubkey: $BUFFER, isSigner: false, isWritable: true},
+                {pubkey: $SPILL, isSigner: false, isWritable: true},
+                {pubkey: $RENT, isSigner: false, isWritable: false},
+                {pubkey: $CLOCK, isSigner: false, isWritable: false},
+                {pubkey: $AUTHORITY, isSigner: true, isWritable: false}
+            ],
+            data: "AwAAAA=="
+        }'
+    ;;
+*)
+    usage
+    exit 1
+    ;;
+esac
@@ -1,23 +1,47 @@
 //! Module for processing admin-only instructions.
 
 use crate::{
-    bn::U256,
-    curve::{StableSwap, MAX_AMP, MIN_AMP, MIN_RAMP_DURATION, ZERO_TS},
+    bn::{U256Conversions, U256},
+    curve::{
+        CurveType, StableSwap, MAX_AMP, MIN_AMP, MIN_RAMP_DURATION, PRICE_BOUND_SCALE, ZERO_TS,
+    },
     error::SwapError,
-    fees::Fees,
-    instruction::{AdminInstruction, RampAData},
-    state::SwapInfo,
-    utils,
+    farm::Farm,
+    fees::{Fees, FeesValidation},
+    gauge::{Gauge, GaugeController},
+    governance,
+    instruction::{
+        AdminInstruction, CreateAdminSessionData, DepositData, InitializeFarmData,
+        InitializeGaugeControllerData, RampAData, SetAdminFeeEpochCapData,
+        SetCustomErrorMessageData, SetDepositBonusData, SetDynamicAmpData, SetOracleGuardData,
+        SetOracleStalePolicyData, SetParamsData, SetPriceBoundData, SetVolatilityFeeData,
+        UpdateLpMetadataData, WithdrawData,
+    },
+    metadata,
+    oracle::{OracleStalePolicy, PythPrice},
+    processor::Processor,
+    state::{
+        AdminAction, AdminSession, DepositAllowlist, FeeExemptions, ParameterSnapshot, SwapInfo,
+        MAX_DEPOSIT_BONUS_BPS, MAX_HARVEST_TIP_BPS, MAX_VE_FEE_BPS,
+        MAX_VOLATILITY_FEE_MULTIPLIER_BPS, NEW_FEES_DELAY_SLOTS, SNAPSHOT_RESTORE_DELAY,
+        VE_EPOCH_DURATION,
+    },
+    utils::{
+        self, ADMIN_SESSION_SEED, DEPOSIT_ALLOWLIST_SEED, FARM_SEED, FEE_EXEMPTIONS_SEED,
+        GAUGE_CONTROLLER_SEED, GAUGE_SEED, PARAMETER_SNAPSHOT_SEED,
+    },
 };
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
     entrypoint::ProgramResult,
-    msg,
+    program::invoke_signed,
     program_error::ProgramError,
     program_pack::Pack,
     pubkey::Pubkey,
-    sysvar::{clock::Clock, Sysvar},
+    system_instruction,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
+use std::slice::Iter;
 
 /// Process admin instruction
 pub fn process_admin_instruction(
@@ -30,40 +54,297 @@ pub fn process_admin_instruction(
             target_amp,
             stop_ramp_ts,
         }) => {
-            msg!("Instruction : RampA");
+            log_info!("Instruction : RampA");
             ramp_a(program_id, target_amp, stop_ramp_ts, accounts)
         }
         AdminInstruction::StopRampA => {
-            msg!("Instruction: StopRampA");
+            log_info!("Instruction: StopRampA");
             stop_ramp_a(program_id, accounts)
         }
         AdminInstruction::Pause => {
-            msg!("Instruction: Pause");
+            log_info!("Instruction: Pause");
             pause(program_id, accounts)
         }
         AdminInstruction::Unpause => {
-            msg!("Instruction: Unpause");
+            log_info!("Instruction: Unpause");
             unpause(program_id, accounts)
         }
         AdminInstruction::SetFeeAccount => {
-            msg!("Instruction: SetFeeAccount");
+            log_info!("Instruction: SetFeeAccount");
             set_fee_account(program_id, accounts)
         }
         AdminInstruction::ApplyNewAdmin => {
-            msg!("Instruction: ApplyNewAdmin");
+            log_info!("Instruction: ApplyNewAdmin");
             apply_new_admin(program_id, accounts)
         }
         AdminInstruction::CommitNewAdmin => {
-            msg!("Instruction: CommitNewAdmin");
+            log_info!("Instruction: CommitNewAdmin");
             commit_new_admin(program_id, accounts)
         }
         AdminInstruction::SetNewFees(new_fees) => {
-            msg!("Instruction: SetNewFees");
+            log_info!("Instruction: SetNewFees");
             set_new_fees(program_id, &new_fees, accounts)
         }
+        AdminInstruction::CreateAdminSession(CreateAdminSessionData {
+            permissions,
+            expiry_slot,
+            nonce,
+        }) => {
+            log_info!("Instruction: CreateAdminSession");
+            create_admin_session(program_id, permissions, expiry_slot, nonce, accounts)
+        }
+        AdminInstruction::RevokeAdminSession => {
+            log_info!("Instruction: RevokeAdminSession");
+            revoke_admin_session(program_id, accounts)
+        }
+        AdminInstruction::SetHarvestTip(harvest_tip_bps) => {
+            log_info!("Instruction: SetHarvestTip");
+            set_harvest_tip(program_id, harvest_tip_bps, accounts)
+        }
+        AdminInstruction::SetReflectionDistributor => {
+            log_info!("Instruction: SetReflectionDistributor");
+            set_reflection_distributor(program_id, accounts)
+        }
+        AdminInstruction::InitializeDevFeeVesting(vesting_duration) => {
+            log_info!("Instruction: InitializeDevFeeVesting");
+            initialize_dev_fee_vesting(program_id, vesting_duration, accounts)
+        }
+        AdminInstruction::SetGovernanceRealm(governance_lockup) => {
+            log_info!("Instruction: SetGovernanceRealm");
+            set_governance_realm(program_id, governance_lockup, accounts)
+        }
+        AdminInstruction::SnapshotState(nonce) => {
+            log_info!("Instruction: SnapshotState");
+            snapshot_state(program_id, nonce, accounts)
+        }
+        AdminInstruction::RestoreState => {
+            log_info!("Instruction: RestoreState");
+            restore_state(program_id, accounts)
+        }
+        AdminInstruction::SetAdminRecovery(admin_timeout_slots) => {
+            log_info!("Instruction: SetAdminRecovery");
+            set_admin_recovery(program_id, admin_timeout_slots, accounts)
+        }
+        AdminInstruction::AdminHeartbeat => {
+            log_info!("Instruction: AdminHeartbeat");
+            admin_heartbeat(program_id, accounts)
+        }
+        AdminInstruction::ClaimAdminAfterTimeout => {
+            log_info!("Instruction: ClaimAdminAfterTimeout");
+            claim_admin_after_timeout(program_id, accounts)
+        }
+        AdminInstruction::SetMaxSwapAmount(max_swap_amount_in) => {
+            log_info!("Instruction: SetMaxSwapAmount");
+            set_max_swap_amount(program_id, max_swap_amount_in, accounts)
+        }
+        AdminInstruction::InitializeVeEscrow(ve_fee_bps) => {
+            log_info!("Instruction: InitializeVeEscrow");
+            initialize_ve_escrow(program_id, ve_fee_bps, accounts)
+        }
+        AdminInstruction::SetVeFeeShare(ve_fee_bps) => {
+            log_info!("Instruction: SetVeFeeShare");
+            set_ve_fee_share(program_id, ve_fee_bps, accounts)
+        }
+        AdminInstruction::InitializeGaugeController(InitializeGaugeControllerData {
+            emission_per_epoch,
+            nonce,
+        }) => {
+            log_info!("Instruction: InitializeGaugeController");
+            initialize_gauge_controller(program_id, emission_per_epoch, nonce, accounts)
+        }
+        AdminInstruction::AddGauge(nonce) => {
+            log_info!("Instruction: AddGauge");
+            add_gauge(program_id, nonce, accounts)
+        }
+        AdminInstruction::SetGaugeEmission(emission_per_epoch) => {
+            log_info!("Instruction: SetGaugeEmission");
+            set_gauge_emission(program_id, emission_per_epoch, accounts)
+        }
+        AdminInstruction::SetOrderFlowAuction(batch_slots) => {
+            log_info!("Instruction: SetOrderFlowAuction");
+            set_order_flow_auction(program_id, batch_slots, accounts)
+        }
+        AdminInstruction::SetDynamicAmp(SetDynamicAmpData {
+            min_amp,
+            max_amp,
+            volatility_ceiling,
+            max_drift_per_slot,
+        }) => {
+            log_info!("Instruction: SetDynamicAmp");
+            set_dynamic_amp(
+                program_id,
+                min_amp,
+                max_amp,
+                volatility_ceiling,
+                max_drift_per_slot,
+                accounts,
+            )
+        }
+        AdminInstruction::SetDepositBonus(SetDepositBonusData {
+            fee_buffer_bps,
+            deposit_bonus_bps,
+        }) => {
+            log_info!("Instruction: SetDepositBonus");
+            set_deposit_bonus(program_id, fee_buffer_bps, deposit_bonus_bps, accounts)
+        }
+        AdminInstruction::SetInsuranceFund(insurance_fee_bps) => {
+            log_info!("Instruction: SetInsuranceFund");
+            set_insurance_fund(program_id, insurance_fee_bps, accounts)
+        }
+        AdminInstruction::SetParams(ref params) => {
+            log_info!("Instruction: SetParams");
+            set_params(program_id, params, accounts)
+        }
+        AdminInstruction::SetMinLpMint(min_lp_mint) => {
+            log_info!("Instruction: SetMinLpMint");
+            set_min_lp_mint(program_id, min_lp_mint, accounts)
+        }
+        AdminInstruction::SetDustThreshold(dust_threshold) => {
+            log_info!("Instruction: SetDustThreshold");
+            set_dust_threshold(program_id, dust_threshold, accounts)
+        }
+        AdminInstruction::InitializeLiquidityPositions => {
+            log_info!("Instruction: InitializeLiquidityPositions");
+            initialize_liquidity_positions(program_id, accounts)
+        }
+        AdminInstruction::SetCustodian(custodian_withdraw_threshold) => {
+            log_info!("Instruction: SetCustodian");
+            set_custodian(program_id, custodian_withdraw_threshold, accounts)
+        }
+        AdminInstruction::SetCredentialIssuer => {
+            log_info!("Instruction: SetCredentialIssuer");
+            set_credential_issuer(program_id, accounts)
+        }
+        AdminInstruction::UpdateLpMetadata(ref data) => {
+            log_info!("Instruction: UpdateLpMetadata");
+            update_lp_metadata(program_id, data, accounts)
+        }
+        AdminInstruction::SetGuardian => {
+            log_info!("Instruction: SetGuardian");
+            set_guardian(program_id, accounts)
+        }
+        AdminInstruction::DeployProtocolLiquidity(ref data) => {
+            log_info!("Instruction: DeployProtocolLiquidity");
+            deploy_protocol_liquidity(program_id, data, accounts)
+        }
+        AdminInstruction::WithdrawProtocolLiquidity(ref data) => {
+            log_info!("Instruction: WithdrawProtocolLiquidity");
+            withdraw_protocol_liquidity(program_id, data, accounts)
+        }
+        AdminInstruction::ApplyNewFees => {
+            log_info!("Instruction: ApplyNewFees");
+            apply_new_fees(program_id, accounts)
+        }
+        AdminInstruction::CancelPendingFees => {
+            log_info!("Instruction: CancelPendingFees");
+            cancel_pending_fees(program_id, accounts)
+        }
+        AdminInstruction::SetCustomErrorMessage(ref data) => {
+            log_info!("Instruction: SetCustomErrorMessage");
+            set_custom_error_message(program_id, data, accounts)
+        }
+        AdminInstruction::InitializeReflection => {
+            log_info!("Instruction: InitializeReflection");
+            initialize_reflection(program_id, accounts)
+        }
+        AdminInstruction::InitializeBuyback => {
+            log_info!("Instruction: InitializeBuyback");
+            initialize_buyback(program_id, accounts)
+        }
+        AdminInstruction::AddFeeExemption(nonce) => {
+            log_info!("Instruction: AddFeeExemption");
+            add_fee_exemption(program_id, nonce, accounts)
+        }
+        AdminInstruction::RemoveFeeExemption => {
+            log_info!("Instruction: RemoveFeeExemption");
+            remove_fee_exemption(program_id, accounts)
+        }
+        AdminInstruction::SetVolatilityFee(SetVolatilityFeeData {
+            volatility_ceiling,
+            max_multiplier_bps,
+        }) => {
+            log_info!("Instruction: SetVolatilityFee");
+            set_volatility_fee(program_id, volatility_ceiling, max_multiplier_bps, accounts)
+        }
+        AdminInstruction::SetAdminFeeEpochCap(SetAdminFeeEpochCapData { cap_a, cap_b }) => {
+            log_info!("Instruction: SetAdminFeeEpochCap");
+            set_admin_fee_epoch_cap(program_id, cap_a, cap_b, accounts)
+        }
+        AdminInstruction::SetPriceBound(SetPriceBoundData { price_bound }) => {
+            log_info!("Instruction: SetPriceBound");
+            set_price_bound(program_id, price_bound, accounts)
+        }
+        AdminInstruction::InitializeFarm(InitializeFarmData {
+            emission_per_slot,
+            nonce,
+        }) => {
+            log_info!("Instruction: InitializeFarm");
+            initialize_farm(program_id, emission_per_slot, nonce, accounts)
+        }
+        AdminInstruction::AddAllowedDepositor(nonce) => {
+            log_info!("Instruction: AddAllowedDepositor");
+            add_allowed_depositor(program_id, nonce, accounts)
+        }
+        AdminInstruction::RemoveAllowedDepositor => {
+            log_info!("Instruction: RemoveAllowedDepositor");
+            remove_allowed_depositor(program_id, accounts)
+        }
+        AdminInstruction::SetDepositAllowlist(enabled) => {
+            log_info!("Instruction: SetDepositAllowlist");
+            set_deposit_allowlist(program_id, enabled, accounts)
+        }
+        AdminInstruction::SetOracleGuard(SetOracleGuardData {
+            max_staleness_slots,
+            max_confidence_bps,
+            max_deviation_bps,
+        }) => {
+            log_info!("Instruction: SetOracleGuard");
+            set_oracle_guard(
+                program_id,
+                max_staleness_slots,
+                max_confidence_bps,
+                max_deviation_bps,
+                accounts,
+            )
+        }
+        AdminInstruction::SetOracleStalePolicy(SetOracleStalePolicyData {
+            stale_policy,
+            stale_band_bps,
+        }) => {
+            log_info!("Instruction: SetOracleStalePolicy");
+            set_oracle_stale_policy(program_id, stale_policy, stale_band_bps, accounts)
+        }
     }
 }
 
+/// Records the admin instruction `tag` in the audit log of the swap it was applied to,
+/// and refreshes the configuration hash of the swap. Instructions that only read the
+/// swap account are not recorded.
+pub fn record_admin_action(
+    program_id: &Pubkey,
+    tag: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let swap_info = match accounts.first() {
+        Some(swap_info) if swap_info.is_writable && swap_info.owner == program_id => swap_info,
+        _ => return Ok(()),
+    };
+    let signer = accounts
+        .iter()
+        .find(|account_info| account_info.is_signer)
+        .map_or_else(Pubkey::default, |account_info| *account_info.key);
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    token_swap.admin_log.record(AdminAction {
+        tag,
+        slot: Clock::get()?.slot,
+        signer,
+    });
+    token_swap.config_hash = token_swap.compute_config_hash();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
 /// Access control for admin only instructions
 fn is_admin(expected_admin_key: &Pubkey, admin_account_info: &AccountInfo) -> ProgramResult {
     if expected_admin_key != admin_account_info.key {
@@ -75,6 +356,76 @@ fn is_admin(expected_admin_key: &Pubkey, admin_account_info: &AccountInfo) -> Pr
     Ok(())
 }
 
+/// Access control for admin instructions wrapping a user instruction, the admin signs
+/// as one of its optional trailing accounts.
+fn is_trailing_admin(expected_admin_key: &Pubkey, trailing_infos: &[AccountInfo]) -> ProgramResult {
+    let admin_info = trailing_infos
+        .iter()
+        .find(|info| info.key == expected_admin_key)
+        .ok_or(SwapError::Unauthorized)?;
+    is_admin(expected_admin_key, admin_info)
+}
+
+/// Access control for admin instructions that may be delegated to an admin session.
+/// Unless signed by the admin, the session account and the clock sysvar are read from
+/// the remaining instruction accounts.
+fn is_admin_or_session(
+    program_id: &Pubkey,
+    swap_info: &AccountInfo,
+    token_swap: &SwapInfo,
+    admin_info: &AccountInfo,
+    permission: u8,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    if *admin_info.key == token_swap.admin_key {
+        return is_admin(&token_swap.admin_key, admin_info);
+    }
+    let admin_session_info = account_info_iter.next().ok_or(SwapError::Unauthorized)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    if admin_session_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let admin_session = AdminSession::unpack(&admin_session_info.data.borrow())?;
+    if admin_session.swap != *swap_info.key
+        || admin_session.admin != token_swap.admin_key
+        || admin_session.session_key != *admin_info.key
+        || !admin_session.has_permission(permission)
+    {
+        return Err(SwapError::Unauthorized.into());
+    }
+    if !admin_info.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    if clock.slot > admin_session.expiry_slot {
+        return Err(SwapError::AdminSessionExpired.into());
+    }
+    Ok(())
+}
+
+/// Access control for the pause switch, held by the guardian besides the admin and
+/// its sessions
+fn is_guardian_admin_or_session(
+    program_id: &Pubkey,
+    swap_info: &AccountInfo,
+    token_swap: &SwapInfo,
+    admin_info: &AccountInfo,
+    account_info_iter: &mut Iter<AccountInfo>,
+) -> ProgramResult {
+    if token_swap.guardian_key != Pubkey::default() && *admin_info.key == token_swap.guardian_key {
+        return is_admin(&token_swap.guardian_key, admin_info);
+    }
+    is_admin_or_session(
+        program_id,
+        swap_info,
+        token_swap,
+        admin_info,
+        AdminSession::PERMISSION_PAUSE,
+        account_info_iter,
+    )
+}
+
 /// Ramp to future a
 fn ramp_a(
     program_id: &Pubkey,
@@ -88,16 +439,40 @@ fn ramp_a(
     let admin_info = next_account_info(account_info_iter)?;
     let clock_sysvar_info = next_account_info(account_info_iter)?;
 
-    if !(MIN_AMP..=MAX_AMP).contains(&target_amp) {
-        return Err(SwapError::InvalidInput.into());
-    }
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-    is_admin(&token_swap.admin_key, admin_info)?;
+    is_admin_or_session(
+        program_id,
+        swap_info,
+        &token_swap,
+        admin_info,
+        AdminSession::PERMISSION_RAMP_A,
+        account_info_iter,
+    )?;
     if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
         return Err(SwapError::InvalidProgramAddress.into());
     }
 
     let clock = Clock::from_account_info(clock_sysvar_info)?;
+    start_ramp(&mut token_swap, target_amp, stop_ramp_ts, &clock)?;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Starts ramping the amplification coefficient of `token_swap` to `target_amp`
+fn start_ramp(
+    token_swap: &mut SwapInfo,
+    target_amp: u64,
+    stop_ramp_ts: i64,
+    clock: &Clock,
+) -> ProgramResult {
+    if !(MIN_AMP..=MAX_AMP).contains(&target_amp) {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if token_swap.is_dynamic_amp_enabled() {
+        return Err(SwapError::DynamicAmpEnabled.into());
+    }
+
     let ramp_lock_ts = token_swap
         .start_ramp_ts
         .checked_add(MIN_RAMP_DURATION)
@@ -140,7 +515,6 @@ fn ramp_a(
     token_swap.target_amp_factor = target_amp;
     token_swap.start_ramp_ts = clock.unix_timestamp;
     token_swap.stop_ramp_ts = stop_ramp_ts;
-    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
     Ok(())
 }
 
@@ -153,10 +527,20 @@ fn stop_ramp_a(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let clock_sysvar_info = next_account_info(account_info_iter)?;
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-    is_admin(&token_swap.admin_key, admin_info)?;
+    is_admin_or_session(
+        program_id,
+        swap_info,
+        &token_swap,
+        admin_info,
+        AdminSession::PERMISSION_RAMP_A,
+        account_info_iter,
+    )?;
     if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
         return Err(SwapError::InvalidProgramAddress.into());
     }
+    if token_swap.is_dynamic_amp_enabled() {
+        return Err(SwapError::DynamicAmpEnabled.into());
+    }
 
     let clock = Clock::from_account_info(clock_sysvar_info)?;
     let invariant = StableSwap::new(
@@ -177,6 +561,7 @@ fn stop_ramp_a(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     token_swap.start_ramp_ts = clock.unix_timestamp;
     token_swap.stop_ramp_ts = clock.unix_timestamp;
     // now (current_ts < stop_ramp_ts) is always False, compute_amp_factor should return target_amp
+    token_swap.bump_sequence();
     SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
     Ok(())
 }
@@ -189,12 +574,19 @@ fn pause(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let admin_info = next_account_info(account_info_iter)?;
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-    is_admin(&token_swap.admin_key, admin_info)?;
+    is_guardian_admin_or_session(
+        program_id,
+        swap_info,
+        &token_swap,
+        admin_info,
+        account_info_iter,
+    )?;
     if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
         return Err(SwapError::InvalidProgramAddress.into());
     }
 
     token_swap.is_paused = true;
+    token_swap.bump_sequence();
     SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
     Ok(())
 }
@@ -207,12 +599,19 @@ fn unpause(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let admin_info = next_account_info(account_info_iter)?;
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-    is_admin(&token_swap.admin_key, admin_info)?;
+    is_guardian_admin_or_session(
+        program_id,
+        swap_info,
+        &token_swap,
+        admin_info,
+        account_info_iter,
+    )?;
     if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
         return Err(SwapError::InvalidProgramAddress.into());
     }
 
     token_swap.is_paused = false;
+    token_swap.bump_sequence();
     SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
     Ok(())
 }
@@ -226,39 +625,50 @@ fn set_fee_account(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResu
     let new_fee_account_info = next_account_info(account_info_iter)?;
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-    is_admin(&token_swap.admin_key, admin_info)?;
+    is_admin_or_session(
+        program_id,
+        swap_info,
+        &token_swap,
+        admin_info,
+        AdminSession::PERMISSION_SET_FEE_ACCOUNT,
+        account_info_iter,
+    )?;
     if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
         return Err(SwapError::InvalidProgramAddress.into());
     }
     let new_admin_fee_account = utils::unpack_token_account(&new_fee_account_info.data.borrow())?;
     if new_admin_fee_account.mint == token_swap.token_a_mint {
         token_swap.admin_fee_key_a = *new_fee_account_info.key;
+        token_swap.admin_fee_escrow_a = false;
     } else if new_admin_fee_account.mint == token_swap.token_b_mint {
         token_swap.admin_fee_key_b = *new_fee_account_info.key;
+        token_swap.admin_fee_escrow_b = false;
     } else {
         return Err(SwapError::InvalidAdmin.into());
     }
 
+    token_swap.bump_sequence();
     SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
     Ok(())
 }
 
-/// Apply new admin (finalize admin transfer)
+/// Apply new admin (finalize admin transfer). The new admin signs, so a transfer
+/// committed to a wrong key can't be applied.
 fn apply_new_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let swap_info = next_account_info(account_info_iter)?;
     let authority_info = next_account_info(account_info_iter)?;
-    let admin_info = next_account_info(account_info_iter)?;
+    let new_admin_info = next_account_info(account_info_iter)?;
     let clock_sysvar_info = next_account_info(account_info_iter)?;
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-    is_admin(&token_swap.admin_key, admin_info)?;
     if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
         return Err(SwapError::InvalidProgramAddress.into());
     }
     if token_swap.future_admin_deadline == ZERO_TS {
         return Err(SwapError::NoActiveTransfer.into());
     }
+    is_admin(&token_swap.future_admin_key, new_admin_info)?;
     let clock = Clock::from_account_info(clock_sysvar_info)?;
     if clock.unix_timestamp > token_swap.future_admin_deadline {
         return Err(SwapError::AdminDeadlineExceeded.into());
@@ -267,6 +677,8 @@ fn apply_new_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResu
     token_swap.admin_key = token_swap.future_admin_key;
     token_swap.future_admin_key = Pubkey::default();
     token_swap.future_admin_deadline = ZERO_TS;
+    token_swap.admin_heartbeat_slot = clock.slot;
+    token_swap.bump_sequence();
     SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
     Ok(())
 }
@@ -297,6 +709,7 @@ fn commit_new_admin(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramRes
         .unix_timestamp
         .checked_add(ADMIN_TRANSFER_DELAY)
         .ok_or(SwapError::CalculationFailure)?;
+    token_swap.bump_sequence();
     SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
     Ok(())
 }
@@ -307,241 +720,5244 @@ fn set_new_fees(program_id: &Pubkey, new_fees: &Fees, accounts: &[AccountInfo])
     let swap_info = next_account_info(account_info_iter)?;
     let authority_info = next_account_info(account_info_iter)?;
     let admin_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
 
     let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-    is_admin(&token_swap.admin_key, admin_info)?;
+    is_admin_or_session(
+        program_id,
+        swap_info,
+        &token_swap,
+        admin_info,
+        AdminSession::PERMISSION_SET_NEW_FEES,
+        account_info_iter,
+    )?;
     if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
         return Err(SwapError::InvalidProgramAddress.into());
     }
 
-    token_swap.fees = *new_fees;
+    new_fees.validate()?;
+
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    token_swap.pending_fees = *new_fees;
+    token_swap.pending_fees_slot = clock
+        .slot
+        .checked_add(NEW_FEES_DELAY_SLOTS)
+        .ok_or(SwapError::CalculationFailure)?;
+    token_swap.bump_sequence();
     SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{curve::ZERO_TS, utils::test_utils::*};
-    use solana_sdk::clock::Epoch;
+/// Apply the staged fees
+fn apply_new_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
 
-    const DEFAULT_TOKEN_A_AMOUNT: u64 = 1_000_000_000;
-    const DEFAULT_TOKEN_B_AMOUNT: u64 = 1_000_000_000;
-    const DEFAULT_POOL_TOKEN_AMOUNT: u64 = 0;
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin_or_session(
+        program_id,
+        swap_info,
+        &token_swap,
+        admin_info,
+        AdminSession::PERMISSION_SET_NEW_FEES,
+        account_info_iter,
+    )?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if token_swap.pending_fees_slot == 0 {
+        return Err(SwapError::NoPendingFees.into());
+    }
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    if clock.slot < token_swap.pending_fees_slot {
+        return Err(SwapError::PendingFeesNotDue.into());
+    }
 
-    #[test]
-    fn test_is_admin() {
-        let admin_key = pubkey_rand();
-        let admin_owner = pubkey_rand();
-        let mut lamports = 0;
-        let mut admin_account_data = vec![];
-        let mut admin_account_info = AccountInfo::new(
-            &admin_key,
-            true,
-            false,
-            &mut lamports,
-            &mut admin_account_data,
-            &admin_owner,
-            false,
-            Epoch::default(),
-        );
+    token_swap.fees = token_swap.pending_fees;
+    token_swap.pending_fees = Fees::default();
+    token_swap.pending_fees_slot = 0;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
 
-        // Correct admin
-        assert_eq!(Ok(()), is_admin(&admin_key, &admin_account_info));
+/// Cancel the staged fees
+fn cancel_pending_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
 
-        // Unauthorized account
-        let fake_admin_key = pubkey_rand();
-        let mut fake_admin_account = admin_account_info.clone();
-        fake_admin_account.key = &fake_admin_key;
-        assert_eq!(
-            Err(SwapError::Unauthorized.into()),
-            is_admin(&admin_key, &fake_admin_account)
-        );
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin_or_session(
+        program_id,
+        swap_info,
+        &token_swap,
+        admin_info,
+        AdminSession::PERMISSION_SET_NEW_FEES,
+        account_info_iter,
+    )?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if token_swap.pending_fees_slot == 0 {
+        return Err(SwapError::NoPendingFees.into());
+    }
 
-        // Admin did not sign
-        admin_account_info.is_signer = false;
-        assert_eq!(
-            Err(ProgramError::MissingRequiredSignature),
-            is_admin(&admin_key, &admin_account_info)
-        );
+    token_swap.pending_fees = Fees::default();
+    token_swap.pending_fees_slot = 0;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set harvest tip
+fn set_harvest_tip(
+    program_id: &Pubkey,
+    harvest_tip_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if harvest_tip_bps > MAX_HARVEST_TIP_BPS {
+        return Err(SwapError::InvalidInput.into());
     }
 
-    #[test]
-    fn test_ramp_a() {
-        let user_key = pubkey_rand();
-        let amp_factor = MIN_AMP * 100;
-        let mut accounts = SwapAccountInfo::new(
-            &user_key,
-            amp_factor,
-            DEFAULT_TOKEN_A_AMOUNT,
-            DEFAULT_TOKEN_B_AMOUNT,
-            DEFAULT_TEST_FEES,
-        );
+    token_swap.harvest_tip_bps = harvest_tip_bps;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
 
-        // swap not initialized
-        {
-            assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.ramp_a(MIN_AMP, ZERO_TS, MIN_RAMP_DURATION)
-            );
-        }
+/// Set max swap amount
+fn set_max_swap_amount(
+    program_id: &Pubkey,
+    max_swap_amount_in: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
 
-        accounts.initialize_swap().unwrap();
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
 
-        // Invalid target amp
-        {
-            let stop_ramp_ts = MIN_RAMP_DURATION;
-            let target_amp = 0;
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.ramp_a(target_amp, ZERO_TS, stop_ramp_ts)
-            );
-            let target_amp = MAX_AMP + 1;
-            assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.ramp_a(target_amp, ZERO_TS, stop_ramp_ts)
-            );
-        }
+    token_swap.max_swap_amount_in = max_swap_amount_in;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
 
-        // wrong nonce for authority_key
+/// Initialize vote-escrow
+fn initialize_ve_escrow(
+    program_id: &Pubkey,
+    ve_fee_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let ve_lp_vault_info = next_account_info(account_info_iter)?;
+    let ve_fee_vault_a_info = next_account_info(account_info_iter)?;
+    let ve_fee_vault_b_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    // Locks and the fees distributed to them are tied to the vaults
+    if token_swap.is_ve_enabled() {
+        return Err(SwapError::AlreadyInUse.into());
+    }
+    if ve_fee_bps > MAX_VE_FEE_BPS {
+        return Err(SwapError::InvalidInput.into());
+    }
+    // Locks transfer pool tokens
+    if token_swap.lp_soulbound {
+        return Err(SwapError::LpSoulbound.into());
+    }
+    for (vault_info, mint) in [
+        (ve_lp_vault_info, token_swap.pool_mint),
+        (ve_fee_vault_a_info, token_swap.token_a_mint),
+        (ve_fee_vault_b_info, token_swap.token_b_mint),
+    ] {
+        if *vault_info.key == token_swap.token_a || *vault_info.key == token_swap.token_b {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        let vault = utils::unpack_token_account(&vault_info.data.borrow())?;
+        if vault.owner != *authority_info.key {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if vault.mint != mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        if vault.delegate.is_some() {
+            return Err(SwapError::InvalidDelegate.into());
+        }
+        if vault.close_authority.is_some() {
+            return Err(SwapError::InvalidCloseAuthority.into());
+        }
+    }
+
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    token_swap.ve_lp_vault = *ve_lp_vault_info.key;
+    token_swap.ve_fee_vault_a = *ve_fee_vault_a_info.key;
+    token_swap.ve_fee_vault_b = *ve_fee_vault_b_info.key;
+    token_swap.ve_fee_bps = ve_fee_bps;
+    token_swap.ve_epoch = clock.unix_timestamp / VE_EPOCH_DURATION;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Initialize reflection positions
+fn initialize_reflection(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let reflection_lp_vault_info = next_account_info(account_info_iter)?;
+    let reflection_vault_a_info = next_account_info(account_info_iter)?;
+    let reflection_vault_b_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    // Positions and the fees accrued to them are tied to the vaults
+    if token_swap.is_reflection_enabled() {
+        return Err(SwapError::AlreadyInUse.into());
+    }
+    // Positions transfer pool tokens
+    if token_swap.lp_soulbound {
+        return Err(SwapError::LpSoulbound.into());
+    }
+    for (vault_info, mint) in [
+        (reflection_lp_vault_info, token_swap.pool_mint),
+        (reflection_vault_a_info, token_swap.token_a_mint),
+        (reflection_vault_b_info, token_swap.token_b_mint),
+    ] {
+        check_vault(&token_swap, authority_info, vault_info, &mint)?;
+    }
+
+    token_swap.reflection_lp_vault = *reflection_lp_vault_info.key;
+    token_swap.reflection_vault_a = *reflection_vault_a_info.key;
+    token_swap.reflection_vault_b = *reflection_vault_b_info.key;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Initialize buyback
+fn initialize_buyback(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let buyback_vault_a_info = next_account_info(account_info_iter)?;
+    let buyback_vault_b_info = next_account_info(account_info_iter)?;
+    let buyback_mint_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    // Buybacks burn the whole buyback vault of the bought back token
+    if token_swap.is_buyback_enabled() {
+        return Err(SwapError::AlreadyInUse.into());
+    }
+    if *buyback_mint_info.key != token_swap.token_a_mint
+        && *buyback_mint_info.key != token_swap.token_b_mint
+    {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    for (vault_info, mint) in [
+        (buyback_vault_a_info, token_swap.token_a_mint),
+        (buyback_vault_b_info, token_swap.token_b_mint),
+    ] {
+        check_vault(&token_swap, authority_info, vault_info, &mint)?;
+    }
+
+    token_swap.buyback_mint = *buyback_mint_info.key;
+    token_swap.buyback_vault_a = *buyback_vault_a_info.key;
+    token_swap.buyback_vault_b = *buyback_vault_b_info.key;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Checks `vault_info` is a `mint` token account only $authority can move tokens out
+/// of, and not one of the swap token accounts or the other vaults of the swap
+fn check_vault(
+    token_swap: &SwapInfo,
+    authority_info: &AccountInfo,
+    vault_info: &AccountInfo,
+    mint: &Pubkey,
+) -> ProgramResult {
+    if [
+        token_swap.token_a,
+        token_swap.token_b,
+        token_swap.reflection_lp_vault,
+        token_swap.reflection_vault_a,
+        token_swap.reflection_vault_b,
+        token_swap.buyback_vault_a,
+        token_swap.buyback_vault_b,
+    ]
+    .contains(vault_info.key)
+    {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    let vault = utils::unpack_token_account(&vault_info.data.borrow())?;
+    if vault.owner != *authority_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if vault.mint != *mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if vault.delegate.is_some() {
+        return Err(SwapError::InvalidDelegate.into());
+    }
+    if vault.close_authority.is_some() {
+        return Err(SwapError::InvalidCloseAuthority.into());
+    }
+    Ok(())
+}
+
+/// Add fee exemption
+fn add_fee_exemption(program_id: &Pubkey, nonce: u8, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let fee_exemptions_info = next_account_info(account_info_iter)?;
+    let trader_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    let fee_exemptions_signature_seeds = [FEE_EXEMPTIONS_SEED, swap_info.key.as_ref(), &[nonce]];
+    let fee_exemptions_key =
+        Pubkey::create_program_address(&fee_exemptions_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+    if *fee_exemptions_info.key != fee_exemptions_key {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    let mut fee_exemptions = if fee_exemptions_info.lamports() == 0 {
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_info.key,
+                fee_exemptions_info.key,
+                rent.minimum_balance(FeeExemptions::LEN),
+                FeeExemptions::LEN as u64,
+                program_id,
+            ),
+            &[
+                admin_info.clone(),
+                fee_exemptions_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&fee_exemptions_signature_seeds],
+        )?;
+        FeeExemptions {
+            is_initialized: true,
+            nonce,
+            swap: *swap_info.key,
+            ..FeeExemptions::default()
+        }
+    } else if fee_exemptions_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    } else {
+        FeeExemptions::unpack(&fee_exemptions_info.data.borrow())?
+    };
+
+    fee_exemptions
+        .add(*trader_info.key)
+        .ok_or(SwapError::InvalidInput)?;
+    FeeExemptions::pack(fee_exemptions, &mut fee_exemptions_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Remove fee exemption
+fn remove_fee_exemption(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let fee_exemptions_info = next_account_info(account_info_iter)?;
+    let trader_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if fee_exemptions_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut fee_exemptions = FeeExemptions::unpack(&fee_exemptions_info.data.borrow())?;
+    if fee_exemptions.swap != *swap_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    fee_exemptions
+        .remove(trader_info.key)
+        .ok_or(SwapError::InvalidInput)?;
+    FeeExemptions::pack(fee_exemptions, &mut fee_exemptions_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Add allowed depositor
+fn add_allowed_depositor(
+    program_id: &Pubkey,
+    nonce: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let deposit_allowlist_info = next_account_info(account_info_iter)?;
+    let depositor_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    let deposit_allowlist_signature_seeds =
+        [DEPOSIT_ALLOWLIST_SEED, swap_info.key.as_ref(), &[nonce]];
+    let deposit_allowlist_key =
+        Pubkey::create_program_address(&deposit_allowlist_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+    if *deposit_allowlist_info.key != deposit_allowlist_key {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    let mut deposit_allowlist = if deposit_allowlist_info.lamports() == 0 {
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_info.key,
+                deposit_allowlist_info.key,
+                rent.minimum_balance(DepositAllowlist::LEN),
+                DepositAllowlist::LEN as u64,
+                program_id,
+            ),
+            &[
+                admin_info.clone(),
+                deposit_allowlist_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&deposit_allowlist_signature_seeds],
+        )?;
+        DepositAllowlist {
+            is_initialized: true,
+            nonce,
+            swap: *swap_info.key,
+            ..DepositAllowlist::default()
+        }
+    } else if deposit_allowlist_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    } else {
+        DepositAllowlist::unpack(&deposit_allowlist_info.data.borrow())?
+    };
+
+    deposit_allowlist
+        .add(*depositor_info.key)
+        .ok_or(SwapError::InvalidInput)?;
+    DepositAllowlist::pack(
+        deposit_allowlist,
+        &mut deposit_allowlist_info.data.borrow_mut(),
+    )?;
+    Ok(())
+}
+
+/// Remove allowed depositor
+fn remove_allowed_depositor(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let deposit_allowlist_info = next_account_info(account_info_iter)?;
+    let depositor_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if deposit_allowlist_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut deposit_allowlist = DepositAllowlist::unpack(&deposit_allowlist_info.data.borrow())?;
+    if deposit_allowlist.swap != *swap_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    deposit_allowlist
+        .remove(depositor_info.key)
+        .ok_or(SwapError::InvalidInput)?;
+    DepositAllowlist::pack(
+        deposit_allowlist,
+        &mut deposit_allowlist_info.data.borrow_mut(),
+    )?;
+    Ok(())
+}
+
+/// Set deposit allowlist
+fn set_deposit_allowlist(
+    program_id: &Pubkey,
+    enabled: bool,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    token_swap.deposit_allowlist_enabled = enabled;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set oracle guard
+fn set_oracle_guard(
+    program_id: &Pubkey,
+    max_staleness_slots: u64,
+    max_confidence_bps: u16,
+    max_deviation_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let oracle_a_info = next_account_info(account_info_iter)?;
+    let oracle_b_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    if *oracle_a_info.key == Pubkey::default() {
+        token_swap.oracle_a = Pubkey::default();
+        token_swap.oracle_b = Pubkey::default();
+        token_swap.oracle_max_staleness_slots = 0;
+        token_swap.oracle_max_confidence_bps = 0;
+        token_swap.oracle_max_deviation_bps = 0;
+    } else {
+        if max_confidence_bps == 0 || max_confidence_bps > 10_000 || max_deviation_bps > 10_000 {
+            return Err(SwapError::InvalidInput.into());
+        }
+        for oracle_info in [oracle_a_info, oracle_b_info] {
+            PythPrice::unpack(&oracle_info.data.borrow())?;
+        }
+        token_swap.oracle_a = *oracle_a_info.key;
+        token_swap.oracle_b = *oracle_b_info.key;
+        token_swap.oracle_max_staleness_slots = max_staleness_slots;
+        token_swap.oracle_max_confidence_bps = max_confidence_bps;
+        token_swap.oracle_max_deviation_bps = max_deviation_bps;
+    }
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set oracle stale policy
+fn set_oracle_stale_policy(
+    program_id: &Pubkey,
+    stale_policy: OracleStalePolicy,
+    stale_band_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if stale_band_bps > 10_000 {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    token_swap.oracle_stale_policy = stale_policy;
+    token_swap.oracle_stale_band_bps = stale_band_bps;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set vote-escrow fee share
+fn set_ve_fee_share(
+    program_id: &Pubkey,
+    ve_fee_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if !token_swap.is_ve_enabled() {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if ve_fee_bps > MAX_VE_FEE_BPS {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    token_swap.ve_fee_bps = ve_fee_bps;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Initialize gauge controller
+fn initialize_gauge_controller(
+    program_id: &Pubkey,
+    emission_per_epoch: u64,
+    nonce: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let controller_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    // Gauges are voted for with veLP
+    if !token_swap.is_ve_enabled() {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let controller_signature_seeds = [GAUGE_CONTROLLER_SEED, swap_info.key.as_ref(), &[nonce]];
+    let controller_key = Pubkey::create_program_address(&controller_signature_seeds, program_id)
+        .or(Err(SwapError::InvalidProgramAddress))?;
+    if *controller_info.key != controller_key {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if controller_info.lamports() != 0 {
+        return Err(SwapError::AlreadyInUse.into());
+    }
+    let rent = Rent::from_account_info(rent_sysvar_info)?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin_info.key,
+            controller_info.key,
+            rent.minimum_balance(GaugeController::LEN),
+            GaugeController::LEN as u64,
+            program_id,
+        ),
+        &[
+            admin_info.clone(),
+            controller_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&controller_signature_seeds],
+    )?;
+
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    let controller = GaugeController {
+        is_initialized: true,
+        swap: *swap_info.key,
+        emission_per_epoch,
+        epoch: clock.unix_timestamp / VE_EPOCH_DURATION,
+        total_next_weight: 0,
+        epoch_total_weight: 0,
+        epoch_emission: 0,
+    };
+    GaugeController::pack(controller, &mut controller_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Unpack the gauge controller of the swap
+fn unpack_gauge_controller(
+    program_id: &Pubkey,
+    swap_info: &AccountInfo,
+    controller_info: &AccountInfo,
+) -> Result<GaugeController, ProgramError> {
+    if controller_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let controller = GaugeController::unpack(&controller_info.data.borrow())?;
+    if controller.swap != *swap_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+    Ok(controller)
+}
+
+/// Add gauge
+fn add_gauge(program_id: &Pubkey, nonce: u8, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let controller_info = next_account_info(account_info_iter)?;
+    let pool_swap_info = next_account_info(account_info_iter)?;
+    let gauge_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    let controller = unpack_gauge_controller(program_id, swap_info, controller_info)?;
+    if pool_swap_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    SwapInfo::unpack(&pool_swap_info.data.borrow())?;
+
+    let gauge_signature_seeds = [
+        GAUGE_SEED,
+        controller_info.key.as_ref(),
+        pool_swap_info.key.as_ref(),
+        &[nonce],
+    ];
+    let gauge_key = Pubkey::create_program_address(&gauge_signature_seeds, program_id)
+        .or(Err(SwapError::InvalidProgramAddress))?;
+    if *gauge_info.key != gauge_key {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if gauge_info.lamports() != 0 {
+        return Err(SwapError::AlreadyInUse.into());
+    }
+    let rent = Rent::from_account_info(rent_sysvar_info)?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin_info.key,
+            gauge_info.key,
+            rent.minimum_balance(Gauge::LEN),
+            Gauge::LEN as u64,
+            program_id,
+        ),
+        &[
+            admin_info.clone(),
+            gauge_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&gauge_signature_seeds],
+    )?;
+
+    // The gauge takes part in the emission from the next epoch
+    let gauge = Gauge {
+        is_initialized: true,
+        controller: *controller_info.key,
+        swap: *pool_swap_info.key,
+        next_weight: 0,
+        epoch: controller.epoch,
+        epoch_weight: 0,
+        emissions: 0,
+    };
+    Gauge::pack(gauge, &mut gauge_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set gauge emission
+fn set_gauge_emission(
+    program_id: &Pubkey,
+    emission_per_epoch: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let controller_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    let mut controller = unpack_gauge_controller(program_id, swap_info, controller_info)?;
+
+    controller.emission_per_epoch = emission_per_epoch;
+    GaugeController::pack(controller, &mut controller_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Initialize farm
+fn initialize_farm(
+    program_id: &Pubkey,
+    emission_per_slot: u64,
+    nonce: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let farm_info = next_account_info(account_info_iter)?;
+    let stake_vault_info = next_account_info(account_info_iter)?;
+    let reward_vault_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    // Stakes transfer pool tokens
+    if token_swap.lp_soulbound {
+        return Err(SwapError::LpSoulbound.into());
+    }
+    check_vault(
+        &token_swap,
+        authority_info,
+        stake_vault_info,
+        &token_swap.pool_mint,
+    )?;
+    // The stakes are counted from an empty vault
+    if utils::unpack_token_account(&stake_vault_info.data.borrow())?.amount != 0 {
+        return Err(SwapError::InvalidInput.into());
+    }
+    // Rewards are never paid out of the stakes
+    let reward_mint = utils::unpack_token_account(&reward_vault_info.data.borrow())?.mint;
+    if reward_mint == token_swap.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    check_vault(&token_swap, authority_info, reward_vault_info, &reward_mint)?;
+
+    let farm_signature_seeds = [FARM_SEED, token_swap.pool_mint.as_ref(), &[nonce]];
+    let farm_key = Pubkey::create_program_address(&farm_signature_seeds, program_id)
+        .or(Err(SwapError::InvalidProgramAddress))?;
+    if *farm_info.key != farm_key {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if farm_info.lamports() != 0 {
+        return Err(SwapError::AlreadyInUse.into());
+    }
+    let rent = Rent::from_account_info(rent_sysvar_info)?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin_info.key,
+            farm_info.key,
+            rent.minimum_balance(Farm::LEN),
+            Farm::LEN as u64,
+            program_id,
+        ),
+        &[
+            admin_info.clone(),
+            farm_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&farm_signature_seeds],
+    )?;
+
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    let farm = Farm {
+        is_initialized: true,
+        swap: *swap_info.key,
+        pool_mint: token_swap.pool_mint,
+        reward_mint,
+        stake_vault: *stake_vault_info.key,
+        reward_vault: *reward_vault_info.key,
+        emission_per_slot,
+        last_update_slot: clock.slot,
+        acc_reward_per_share: 0,
+        total_staked: 0,
+        rewards_unharvested: 0,
+    };
+    Farm::pack(farm, &mut farm_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set order-flow auction
+fn set_order_flow_auction(
+    program_id: &Pubkey,
+    batch_slots: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let bid_token_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if *bid_token_info.key != token_swap.token_a && *bid_token_info.key != token_swap.token_b {
+        return Err(SwapError::IncorrectSwapAccount.into());
+    }
+    // A batch needs a slot to bid in besides its backrun slot
+    if batch_slots == 1 {
+        return Err(SwapError::InvalidInput.into());
+    }
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    token_swap.settle_order_flow_auction(clock.slot);
+    if token_swap.order_flow_bid != 0 {
+        return Err(SwapError::OrderFlowBidPending.into());
+    }
+
+    token_swap.order_flow_batch_slots = batch_slots;
+    token_swap.order_flow_bid_token = *bid_token_info.key;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set dynamic amp
+fn set_dynamic_amp(
+    program_id: &Pubkey,
+    min_amp: u64,
+    max_amp: u64,
+    volatility_ceiling: u64,
+    max_drift_per_slot: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    if min_amp == 0 {
+        token_swap.dynamic_amp_min = 0;
+        token_swap.dynamic_amp_max = 0;
+        token_swap.dynamic_amp_volatility_ceiling = 0;
+        token_swap.dynamic_amp_max_drift = 0;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        return Ok(());
+    }
+    if min_amp < MIN_AMP
+        || max_amp > MAX_AMP
+        || min_amp > max_amp
+        || volatility_ceiling == 0
+        || max_drift_per_slot == 0
+    {
+        return Err(SwapError::InvalidInput.into());
+    }
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    if clock.unix_timestamp < token_swap.stop_ramp_ts {
+        return Err(SwapError::RampLocked.into());
+    }
+
+    // The TWAP restarts with the next swap, A drifts from its current value
+    if !token_swap.is_twap_tracked() {
+        token_swap.twap_price = 0;
+        token_swap.twap_volatility = 0;
+        token_swap.twap_slot = clock.slot;
+    }
+    token_swap.dynamic_amp_min = min_amp;
+    token_swap.dynamic_amp_max = max_amp;
+    token_swap.dynamic_amp_volatility_ceiling = volatility_ceiling;
+    token_swap.dynamic_amp_max_drift = max_drift_per_slot;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set volatility fee
+fn set_volatility_fee(
+    program_id: &Pubkey,
+    volatility_ceiling: u64,
+    max_multiplier_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    if volatility_ceiling == 0 {
+        token_swap.volatility_fee_ceiling = 0;
+        token_swap.volatility_fee_max_multiplier_bps = 0;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        return Ok(());
+    }
+    if !(10_000..=MAX_VOLATILITY_FEE_MULTIPLIER_BPS).contains(&max_multiplier_bps) {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    // The TWAP restarts with the next swap, the fees start unscaled at zero volatility
+    if !token_swap.is_twap_tracked() {
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        token_swap.twap_price = 0;
+        token_swap.twap_volatility = 0;
+        token_swap.twap_slot = clock.slot;
+    }
+    token_swap.volatility_fee_ceiling = volatility_ceiling;
+    token_swap.volatility_fee_max_multiplier_bps = max_multiplier_bps;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set admin fee epoch cap
+fn set_admin_fee_epoch_cap(
+    program_id: &Pubkey,
+    cap_a: u64,
+    cap_b: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    token_swap.admin_fee_epoch_cap_a = cap_a;
+    token_swap.admin_fee_epoch_cap_b = cap_b;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set price bound
+fn set_price_bound(
+    program_id: &Pubkey,
+    price_bound: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if token_swap.curve_type != CurveType::PriceBounded || price_bound <= PRICE_BOUND_SCALE {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    token_swap.price_bound = price_bound;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set deposit bonus
+fn set_deposit_bonus(
+    program_id: &Pubkey,
+    fee_buffer_bps: u16,
+    deposit_bonus_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if fee_buffer_bps > 10_000 || deposit_bonus_bps > MAX_DEPOSIT_BONUS_BPS {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    token_swap.fee_buffer_bps = fee_buffer_bps;
+    token_swap.deposit_bonus_bps = deposit_bonus_bps;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set insurance fund
+fn set_insurance_fund(
+    program_id: &Pubkey,
+    insurance_fee_bps: u16,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let realm_info = next_account_info(account_info_iter)?;
+    let insurance_authority_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if insurance_fee_bps > 10_000 {
+        return Err(SwapError::InvalidInput.into());
+    }
+    // Shortfalls are covered by proposals of the realm, executed by its governance
+    if token_swap.governance_realm == Pubkey::default()
+        || *realm_info.key != token_swap.governance_realm
+    {
+        return Err(SwapError::InvalidInput.into());
+    }
+    if insurance_authority_info.owner != realm_info.owner {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if governance::governance_realm(&insurance_authority_info.data.borrow())
+        != Some(*realm_info.key)
+    {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    token_swap.insurance_fee_bps = insurance_fee_bps;
+    token_swap.insurance_authority = *insurance_authority_info.key;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Stage fees, set amp ramp, swap cap and pause flag
+fn set_params(
+    program_id: &Pubkey,
+    params: &SetParamsData,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    // Nothing is written unless the whole bundle is valid
+    params.fees.validate()?;
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    if params.target_amp != 0 {
+        start_ramp(
+            &mut token_swap,
+            params.target_amp,
+            params.stop_ramp_ts,
+            &clock,
+        )?;
+    }
+    token_swap.pending_fees = params.fees;
+    token_swap.pending_fees_slot = clock
+        .slot
+        .checked_add(NEW_FEES_DELAY_SLOTS)
+        .ok_or(SwapError::CalculationFailure)?;
+    token_swap.max_swap_amount_in = params.max_swap_amount_in;
+    token_swap.is_paused = params.is_paused;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set minimum deposit mint
+fn set_min_lp_mint(
+    program_id: &Pubkey,
+    min_lp_mint: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    token_swap.min_lp_mint = min_lp_mint;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set dust sweep threshold
+fn set_dust_threshold(
+    program_id: &Pubkey,
+    dust_threshold: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    token_swap.dust_threshold = dust_threshold;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Initialize liquidity positions
+fn initialize_liquidity_positions(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let liquidity_vault_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    // Positions are tied to the vault
+    if token_swap.liquidity_vault != Pubkey::default() {
+        return Err(SwapError::AlreadyInUse.into());
+    }
+    // Positions transfer pool tokens
+    if token_swap.lp_soulbound {
+        return Err(SwapError::LpSoulbound.into());
+    }
+    let liquidity_vault = utils::unpack_token_account(&liquidity_vault_info.data.borrow())?;
+    if liquidity_vault.owner != *authority_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if liquidity_vault.mint != token_swap.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if liquidity_vault.delegate.is_some() {
+        return Err(SwapError::InvalidDelegate.into());
+    }
+    if liquidity_vault.close_authority.is_some() {
+        return Err(SwapError::InvalidCloseAuthority.into());
+    }
+
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    token_swap.liquidity_vault = *liquidity_vault_info.key;
+    token_swap.position_liquidity_ts = clock.unix_timestamp;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set custodian
+fn set_custodian(
+    program_id: &Pubkey,
+    custodian_withdraw_threshold: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let custodian_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    token_swap.custodian = *custodian_info.key;
+    token_swap.custodian_withdraw_threshold = custodian_withdraw_threshold;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set guardian
+fn set_guardian(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let guardian_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    token_swap.guardian_key = *guardian_info.key;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set custom error message
+fn set_custom_error_message(
+    program_id: &Pubkey,
+    data: &SetCustomErrorMessageData,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    token_swap
+        .custom_error_messages
+        .set(data.error_code, &data.message)
+        .ok_or(SwapError::InvalidInput)?;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Deploy protocol-owned liquidity
+fn deploy_protocol_liquidity(
+    program_id: &Pubkey,
+    data: &DepositData,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    // Accounts of the deposit
+    let swap_info = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let dest_info = accounts.get(7).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let trailing_infos = accounts
+        .get(10..)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_trailing_admin(&token_swap.admin_key, trailing_infos)?;
+
+    let dest_amount = utils::unpack_token_account(&dest_info.data.borrow())?.amount;
+    Processor::process_deposit(
+        program_id,
+        data.token_a_amount,
+        data.token_b_amount,
+        data.min_mint_amount,
+        data.deadline_slot,
+        data.expected_sequence,
+        accounts,
+    )?;
+    let mint_amount = utils::unpack_token_account(&dest_info.data.borrow())?
+        .amount
+        .checked_sub(dest_amount)
+        .ok_or(SwapError::CalculationFailure)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    token_swap.protocol_pool_amount = token_swap
+        .protocol_pool_amount
+        .checked_add(mint_amount)
+        .ok_or(SwapError::CalculationFailure)?;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Withdraw protocol-owned liquidity
+fn withdraw_protocol_liquidity(
+    program_id: &Pubkey,
+    data: &WithdrawData,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    // Accounts of the withdrawal
+    let swap_info = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let trailing_infos = accounts
+        .get(12..)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_trailing_admin(&token_swap.admin_key, trailing_infos)?;
+    if data.pool_token_amount > token_swap.protocol_pool_amount {
+        return Err(SwapError::InsufficientProtocolLiquidity.into());
+    }
+
+    Processor::withdraw(
+        program_id,
+        data.pool_token_amount,
+        data.minimum_token_a_amount,
+        data.minimum_token_b_amount,
+        data.deadline_slot,
+        data.expected_sequence,
+        true,
+        accounts,
+    )?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    token_swap.protocol_pool_amount -= data.pool_token_amount;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set credential issuer
+fn set_credential_issuer(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let credential_issuer_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    token_swap.credential_issuer = *credential_issuer_info.key;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Update pool token metadata
+fn update_lp_metadata(
+    program_id: &Pubkey,
+    data: &UpdateLpMetadataData,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let metadata_info = next_account_info(account_info_iter)?;
+    let metadata_program_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if *metadata_program_info.key != metadata::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if *metadata_info.key != metadata::find_metadata_address(&token_swap.pool_mint).0 {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    let swap_bytes = swap_info.key.to_bytes();
+    let authority_signature_seeds = [&swap_bytes[..32], &[token_swap.nonce]];
+    invoke_signed(
+        &metadata::update_metadata_accounts_v2(
+            metadata_info.key,
+            authority_info.key,
+            &data.name,
+            &data.symbol,
+            &data.uri,
+        ),
+        &[
+            metadata_info.clone(),
+            authority_info.clone(),
+            metadata_program_info.clone(),
+        ],
+        &[&authority_signature_seeds],
+    )
+}
+
+/// Set reflection distributor
+fn set_reflection_distributor(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let distributor_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    token_swap.reflection_distributor = *distributor_info.key;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Initialize developer fee vesting
+fn initialize_dev_fee_vesting(
+    program_id: &Pubkey,
+    vesting_duration: i64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let dev_vesting_a_info = next_account_info(account_info_iter)?;
+    let dev_vesting_b_info = next_account_info(account_info_iter)?;
+    let dev_wallet_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    // Once routed to the vesting accounts or the developer wallet, the developer fees
+    // can't be redirected
+    if token_swap.is_dev_fee_vesting() || token_swap.developer_wallet != Pubkey::default() {
+        return Err(SwapError::AlreadyInUse.into());
+    }
+    if vesting_duration <= 0 {
+        return Err(SwapError::InvalidInput.into());
+    }
+    for (dev_vesting_info, mint) in [
+        (dev_vesting_a_info, &token_swap.token_a_mint),
+        (dev_vesting_b_info, &token_swap.token_b_mint),
+    ] {
+        if *dev_vesting_info.key == token_swap.token_a
+            || *dev_vesting_info.key == token_swap.token_b
+        {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        let dev_vesting = utils::unpack_token_account(&dev_vesting_info.data.borrow())?;
+        if dev_vesting.owner != *authority_info.key {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if dev_vesting.mint != *mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        if dev_vesting.delegate.is_some() {
+            return Err(SwapError::InvalidDelegate.into());
+        }
+        if dev_vesting.close_authority.is_some() {
+            return Err(SwapError::InvalidCloseAuthority.into());
+        }
+    }
+
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    token_swap.dev_vesting_a = *dev_vesting_a_info.key;
+    token_swap.dev_vesting_b = *dev_vesting_b_info.key;
+    token_swap.dev_wallet = *dev_wallet_info.key;
+    token_swap.dev_vesting_start_ts = clock.unix_timestamp;
+    token_swap.dev_vesting_end_ts = clock
+        .unix_timestamp
+        .checked_add(vesting_duration)
+        .ok_or(SwapError::CalculationFailure)?;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set governance realm
+fn set_governance_realm(
+    program_id: &Pubkey,
+    governance_lockup: i64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let realm_info = next_account_info(account_info_iter)?;
+    let governance_vault_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    // Voter weight records and deposits are tied to the realm and vault
+    if token_swap.governance_realm != Pubkey::default() {
+        return Err(SwapError::AlreadyInUse.into());
+    }
+    if governance_lockup < 0 {
+        return Err(SwapError::InvalidInput.into());
+    }
+    // Governance deposits transfer pool tokens
+    if token_swap.lp_soulbound {
+        return Err(SwapError::LpSoulbound.into());
+    }
+    let governance_vault = utils::unpack_token_account(&governance_vault_info.data.borrow())?;
+    if governance_vault.owner != *authority_info.key {
+        return Err(SwapError::InvalidOwner.into());
+    }
+    if governance_vault.mint != token_swap.pool_mint {
+        return Err(SwapError::IncorrectMint.into());
+    }
+    if governance_vault.delegate.is_some() {
+        return Err(SwapError::InvalidDelegate.into());
+    }
+    if governance_vault.close_authority.is_some() {
+        return Err(SwapError::InvalidCloseAuthority.into());
+    }
+
+    token_swap.governance_realm = *realm_info.key;
+    token_swap.governance_vault = *governance_vault_info.key;
+    token_swap.governance_lockup = governance_lockup;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Snapshot pool parameters
+fn snapshot_state(program_id: &Pubkey, nonce: u8, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let snapshot_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    let snapshot_signature_seeds = [PARAMETER_SNAPSHOT_SEED, swap_info.key.as_ref(), &[nonce]];
+    let snapshot_key = Pubkey::create_program_address(&snapshot_signature_seeds, program_id)
+        .or(Err(SwapError::InvalidProgramAddress))?;
+    if *snapshot_info.key != snapshot_key {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if snapshot_info.lamports() == 0 {
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                admin_info.key,
+                snapshot_info.key,
+                rent.minimum_balance(ParameterSnapshot::LEN),
+                ParameterSnapshot::LEN as u64,
+                program_id,
+            ),
+            &[
+                admin_info.clone(),
+                snapshot_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&snapshot_signature_seeds],
+        )?;
+    } else if snapshot_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    let snapshot = ParameterSnapshot::new(*swap_info.key, &token_swap, clock.unix_timestamp);
+    ParameterSnapshot::pack(snapshot, &mut snapshot_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Restore pool parameters
+fn restore_state(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let snapshot_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if snapshot_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let snapshot = ParameterSnapshot::unpack(&snapshot_info.data.borrow())?;
+    if snapshot.swap != *swap_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+    // The restored parameters must have been public for the whole delay
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    let restorable_ts = snapshot
+        .snapshot_ts
+        .checked_add(SNAPSHOT_RESTORE_DELAY)
+        .ok_or(SwapError::CalculationFailure)?;
+    if clock.unix_timestamp < restorable_ts {
+        return Err(SwapError::SnapshotRestoreLocked.into());
+    }
+    snapshot.fees.validate()?;
+
+    // The Amp. Coefficient ramps to the snapshot's target instead of jumping to it,
+    // dynamic amp pools keep theirs
+    if snapshot.target_amp_factor != token_swap.target_amp_factor
+        && !token_swap.is_dynamic_amp_enabled()
+    {
+        let stop_ramp_ts = clock
+            .unix_timestamp
+            .checked_add(MIN_RAMP_DURATION)
+            .ok_or(SwapError::CalculationFailure)?;
+        start_ramp(
+            &mut token_swap,
+            snapshot.target_amp_factor,
+            stop_ramp_ts,
+            &clock,
+        )?;
+    }
+    let pending_fees_slot = clock
+        .slot
+        .checked_add(NEW_FEES_DELAY_SLOTS)
+        .ok_or(SwapError::CalculationFailure)?;
+    snapshot.restore(&mut token_swap, pending_fees_slot);
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Set admin recovery key
+fn set_admin_recovery(
+    program_id: &Pubkey,
+    admin_timeout_slots: u64,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let recovery_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if admin_timeout_slots == 0 {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    token_swap.admin_recovery_key = *recovery_info.key;
+    token_swap.admin_timeout_slots = admin_timeout_slots;
+    token_swap.admin_heartbeat_slot = clock.slot;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Record admin heartbeat
+fn admin_heartbeat(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    token_swap.admin_heartbeat_slot = clock.slot;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Claim admin rights with the recovery key
+fn claim_admin_after_timeout(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let recovery_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    if token_swap.admin_recovery_key == Pubkey::default() {
+        return Err(SwapError::Unauthorized.into());
+    }
+    is_admin(&token_swap.admin_recovery_key, recovery_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    let timeout_slot = token_swap
+        .admin_heartbeat_slot
+        .checked_add(token_swap.admin_timeout_slots)
+        .ok_or(SwapError::CalculationFailure)?;
+    if clock.slot <= timeout_slot {
+        return Err(SwapError::AdminHeartbeatActive.into());
+    }
+
+    token_swap.admin_key = token_swap.admin_recovery_key;
+    token_swap.future_admin_key = Pubkey::default();
+    token_swap.future_admin_deadline = ZERO_TS;
+    token_swap.admin_recovery_key = Pubkey::default();
+    token_swap.admin_timeout_slots = 0;
+    token_swap.admin_heartbeat_slot = clock.slot;
+    token_swap.bump_sequence();
+    SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Create admin session
+fn create_admin_session(
+    program_id: &Pubkey,
+    permissions: u8,
+    expiry_slot: u64,
+    nonce: u8,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let admin_session_info = next_account_info(account_info_iter)?;
+    let session_key_info = next_account_info(account_info_iter)?;
+    let system_program_info = next_account_info(account_info_iter)?;
+    let rent_sysvar_info = next_account_info(account_info_iter)?;
+    let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if permissions == 0 || permissions & !AdminSession::ALL_PERMISSIONS != 0 {
+        return Err(SwapError::InvalidInput.into());
+    }
+    let clock = Clock::from_account_info(clock_sysvar_info)?;
+    if expiry_slot < clock.slot {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let admin_session_signature_seeds = [
+        ADMIN_SESSION_SEED,
+        swap_info.key.as_ref(),
+        session_key_info.key.as_ref(),
+        &[nonce],
+    ];
+    let admin_session_key =
+        Pubkey::create_program_address(&admin_session_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+    if *admin_session_info.key != admin_session_key {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+
+    let rent = Rent::from_account_info(rent_sysvar_info)?;
+    invoke_signed(
+        &system_instruction::create_account(
+            admin_info.key,
+            admin_session_info.key,
+            rent.minimum_balance(AdminSession::LEN),
+            AdminSession::LEN as u64,
+            program_id,
+        ),
+        &[
+            admin_info.clone(),
+            admin_session_info.clone(),
+            system_program_info.clone(),
+        ],
+        &[&admin_session_signature_seeds],
+    )?;
+
+    let admin_session = AdminSession {
+        is_initialized: true,
+        swap: *swap_info.key,
+        admin: *admin_info.key,
+        session_key: *session_key_info.key,
+        permissions,
+        expiry_slot,
+    };
+    AdminSession::pack(admin_session, &mut admin_session_info.data.borrow_mut())?;
+    Ok(())
+}
+
+/// Revoke admin session
+fn revoke_admin_session(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let swap_info = next_account_info(account_info_iter)?;
+    let authority_info = next_account_info(account_info_iter)?;
+    let admin_info = next_account_info(account_info_iter)?;
+    let admin_session_info = next_account_info(account_info_iter)?;
+
+    let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    is_admin(&token_swap.admin_key, admin_info)?;
+    if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)? {
+        return Err(SwapError::InvalidProgramAddress.into());
+    }
+    if admin_session_info.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let admin_session = AdminSession::unpack(&admin_session_info.data.borrow())?;
+    if admin_session.swap != *swap_info.key {
+        return Err(SwapError::InvalidInput.into());
+    }
+
+    let admin_lamports = admin_info
+        .lamports()
+        .checked_add(admin_session_info.lamports())
+        .ok_or(SwapError::CalculationFailure)?;
+    **admin_info.lamports.borrow_mut() = admin_lamports;
+    **admin_session_info.lamports.borrow_mut() = 0;
+    admin_session_info.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        curve::{SwapCurve, ZERO_TS},
+        instruction::{pause, set_new_fees, unpause, with_admin_session},
+        state::{MAX_ALLOWED_DEPOSITORS, MAX_CUSTOM_ERROR_MESSAGES, MAX_FEE_EXEMPTIONS},
+        twap::PRICE_SCALE,
+        utils::test_utils::*,
+    };
+    use solana_program::{clock::Slot, instruction::Instruction, system_program};
+    use solana_sdk::{account::Account, clock::Epoch};
+
+    const DEFAULT_TOKEN_A_AMOUNT: u64 = 1_000_000_000;
+    const DEFAULT_TOKEN_B_AMOUNT: u64 = 1_000_000_000;
+    const DEFAULT_POOL_TOKEN_AMOUNT: u64 = 0;
+
+    #[test]
+    fn test_is_admin() {
+        let admin_key = pubkey_rand();
+        let admin_owner = pubkey_rand();
+        let mut lamports = 0;
+        let mut admin_account_data = vec![];
+        let mut admin_account_info = AccountInfo::new(
+            &admin_key,
+            true,
+            false,
+            &mut lamports,
+            &mut admin_account_data,
+            &admin_owner,
+            false,
+            Epoch::default(),
+        );
+
+        // Correct admin
+        assert_eq!(Ok(()), is_admin(&admin_key, &admin_account_info));
+
+        // Unauthorized account
+        let fake_admin_key = pubkey_rand();
+        let mut fake_admin_account = admin_account_info.clone();
+        fake_admin_account.key = &fake_admin_key;
+        assert_eq!(
+            Err(SwapError::Unauthorized.into()),
+            is_admin(&admin_key, &fake_admin_account)
+        );
+
+        // Admin did not sign
+        admin_account_info.is_signer = false;
+        assert_eq!(
+            Err(ProgramError::MissingRequiredSignature),
+            is_admin(&admin_key, &admin_account_info)
+        );
+    }
+
+    #[test]
+    fn test_ramp_a() {
+        let user_key = pubkey_rand();
+        let amp_factor = MIN_AMP * 100;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+
+        // swap not initialized
+        {
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.ramp_a(MIN_AMP, ZERO_TS, MIN_RAMP_DURATION)
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // Invalid target amp
+        {
+            let stop_ramp_ts = MIN_RAMP_DURATION;
+            let target_amp = 0;
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.ramp_a(target_amp, ZERO_TS, stop_ramp_ts)
+            );
+            let target_amp = MAX_AMP + 1;
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.ramp_a(target_amp, ZERO_TS, stop_ramp_ts)
+            );
+        }
+
+        // wrong nonce for authority_key
+        {
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.ramp_a(MIN_AMP, ZERO_TS, MIN_RAMP_DURATION)
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // Unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            let fake_admin_key = pubkey_rand();
+            accounts.admin_key = fake_admin_key;
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.ramp_a(MIN_AMP, ZERO_TS, MIN_RAMP_DURATION)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // ramp locked
+        {
+            assert_eq!(
+                Err(SwapError::RampLocked.into()),
+                accounts.ramp_a(MIN_AMP, ZERO_TS, MIN_RAMP_DURATION / 2)
+            );
+        }
+
+        // insufficient ramp time
+        {
+            assert_eq!(
+                Err(SwapError::InsufficientRampTime.into()),
+                accounts.ramp_a(amp_factor, MIN_RAMP_DURATION, ZERO_TS)
+            );
+        }
+
+        // invalid amp targets
+        {
+            // amp target too low
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.ramp_a(MIN_AMP, MIN_RAMP_DURATION, MIN_RAMP_DURATION * 2)
+            );
+            // amp target too high
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.ramp_a(MAX_AMP, MIN_RAMP_DURATION, MIN_RAMP_DURATION * 2)
+            );
+        }
+
+        // valid ramp
+        {
+            let target_amp = MIN_AMP * 200;
+            let current_ts = MIN_RAMP_DURATION;
+            let stop_ramp_ts = MIN_RAMP_DURATION * 2;
+            accounts
+                .ramp_a(target_amp, current_ts, stop_ramp_ts)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.initial_amp_factor, amp_factor);
+            assert_eq!(swap_info.target_amp_factor, target_amp);
+            assert_eq!(swap_info.start_ramp_ts, current_ts);
+            assert_eq!(swap_info.stop_ramp_ts, stop_ramp_ts);
+        }
+    }
+
+    #[test]
+    fn test_stop_ramp_a() {
+        let user_key = pubkey_rand();
+        let amp_factor = MIN_AMP * 100;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+
+        // swap not initialized
+        {
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.ramp_a(MIN_AMP, ZERO_TS, MIN_RAMP_DURATION)
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong nonce for authority_key
+        {
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.stop_ramp_a(ZERO_TS)
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            let fake_admin_key = pubkey_rand();
+            accounts.admin_key = fake_admin_key;
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.stop_ramp_a(ZERO_TS)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            let expected_ts = MIN_RAMP_DURATION;
+            accounts.stop_ramp_a(expected_ts).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.initial_amp_factor, amp_factor);
+            assert_eq!(swap_info.target_amp_factor, amp_factor);
+            assert_eq!(swap_info.start_ramp_ts, expected_ts);
+            assert_eq!(swap_info.stop_ramp_ts, expected_ts);
+        }
+    }
+
+    #[test]
+    fn test_pause() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+
+        // swap not initialized
+        {
+            assert_eq!(Err(ProgramError::UninitializedAccount), accounts.pause());
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong nonce for authority_key
+        {
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.pause()
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            let fake_admin_key = pubkey_rand();
+            accounts.admin_key = fake_admin_key;
+            assert_eq!(Err(SwapError::Unauthorized.into()), accounts.pause());
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            accounts.pause().unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_paused);
+        }
+    }
+
+    #[test]
+    fn test_unpause() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+
+        // swap not initialized
+        {
+            assert_eq!(Err(ProgramError::UninitializedAccount), accounts.unpause());
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong nonce for authority_key
+        {
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.unpause()
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            let fake_admin_key = pubkey_rand();
+            accounts.admin_key = fake_admin_key;
+            assert_eq!(Err(SwapError::Unauthorized.into()), accounts.unpause());
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            // Pause swap pool
+            accounts.pause().unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_paused);
+
+            // Unpause swap pool
+            accounts.unpause().unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(!swap_info.is_paused);
+        }
+    }
+
+    #[test]
+    fn test_set_fee_account() {
+        let user_key = pubkey_rand();
+        let owner_key = pubkey_rand();
+        let amp_factor = MIN_AMP * 100;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        let (
+            admin_fee_key_a,
+            admin_fee_account_a,
+            admin_fee_key_b,
+            admin_fee_account_b,
+            wrong_admin_fee_key,
+            wrong_admin_fee_account,
+        ) = accounts.setup_token_accounts(
+            &user_key,
+            &owner_key,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_POOL_TOKEN_AMOUNT,
+        );
+
+        // swap not initialized
+        {
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.set_admin_fee_account(&admin_fee_key_a, &admin_fee_account_a)
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong nonce for authority_key
+        {
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.set_admin_fee_account(&admin_fee_key_a, &admin_fee_account_a)
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            let fake_admin_key = pubkey_rand();
+            accounts.admin_key = fake_admin_key;
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_admin_fee_account(&admin_fee_key_a, &admin_fee_account_a)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // wrong admin account
+        {
+            assert_eq!(
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.set_admin_fee_account(&wrong_admin_fee_key, &wrong_admin_fee_account)
+            );
+        }
+
+        // valid calls
+        {
+            // set fee account a
+            accounts
+                .set_admin_fee_account(&admin_fee_key_a, &admin_fee_account_a)
+                .unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.admin_fee_key_a, admin_fee_key_a);
+            // set fee acount b
+            accounts
+                .set_admin_fee_account(&admin_fee_key_b, &admin_fee_account_b)
+                .unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.admin_fee_key_b, admin_fee_key_b);
+        }
+    }
+
+    #[test]
+    fn test_apply_new_admin() {
+        let user_key = pubkey_rand();
+        let new_admin_key = pubkey_rand();
+        let amp_factor = MIN_AMP * 100;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+
+        // swap not initialized
+        {
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.apply_new_admin(&new_admin_key, ZERO_TS)
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong nonce for authority_key
+        {
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.apply_new_admin(&new_admin_key, ZERO_TS)
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // no active transfer
+        {
+            assert_eq!(
+                Err(SwapError::NoActiveTransfer.into()),
+                accounts.apply_new_admin(&new_admin_key, ZERO_TS)
+            );
+        }
+
+        // apply new admin
+        {
+            let current_ts = MIN_RAMP_DURATION;
+
+            // Commit to initiate admin transfer
+            accounts
+                .commit_new_admin(&new_admin_key, current_ts)
+                .unwrap();
+
+            // Only the new admin accepts the transfer
+            let admin_key = accounts.admin_key;
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.apply_new_admin(&admin_key, current_ts + 1)
+            );
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.apply_new_admin(&pubkey_rand(), current_ts + 1)
+            );
+
+            // Applying transfer past deadline should fail
+            let apply_deadline = current_ts + MIN_RAMP_DURATION * 3;
+            assert_eq!(
+                Err(SwapError::AdminDeadlineExceeded.into()),
+                accounts.apply_new_admin(&new_admin_key, apply_deadline + 1)
+            );
+
+            // Apply to finalize admin transfer
+            accounts
+                .apply_new_admin(&new_admin_key, current_ts + 1)
+                .unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.admin_key, new_admin_key);
+            assert_eq!(swap_info.future_admin_key, Pubkey::default());
+            assert_eq!(swap_info.future_admin_deadline, ZERO_TS);
+        }
+    }
+
+    #[test]
+    fn test_commit_new_admin() {
+        let user_key = pubkey_rand();
+        let new_admin_key = pubkey_rand();
+        let current_ts = ZERO_TS;
+        let amp_factor = MIN_AMP * 100;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+
+        // swap not initialized
+        {
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.commit_new_admin(&new_admin_key, current_ts)
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong nonce for authority_key
+        {
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.commit_new_admin(&new_admin_key, current_ts)
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            let fake_admin_key = pubkey_rand();
+            accounts.admin_key = fake_admin_key;
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.commit_new_admin(&new_admin_key, current_ts)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // commit new admin
+        {
+            // valid call
+            accounts
+                .commit_new_admin(&new_admin_key, current_ts)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.future_admin_key, new_admin_key);
+            let expected_future_ts = current_ts + MIN_RAMP_DURATION * 3;
+            assert_eq!(swap_info.future_admin_deadline, expected_future_ts);
+
+            // new commit within deadline should fail
+            assert_eq!(
+                Err(SwapError::ActiveTransfer.into()),
+                accounts.commit_new_admin(&new_admin_key, current_ts + 1),
+            );
+
+            // new commit after deadline should be valid
+            let new_admin_key = pubkey_rand();
+            let current_ts = expected_future_ts + 1;
+            accounts
+                .commit_new_admin(&new_admin_key, current_ts)
+                .unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.future_admin_key, new_admin_key);
+            let expected_future_ts = current_ts + MIN_RAMP_DURATION * 3;
+            assert_eq!(swap_info.future_admin_deadline, expected_future_ts);
+        }
+    }
+
+    #[test]
+    fn test_set_new_fees() {
+        let user_key = pubkey_rand();
+        let amp_factor = MIN_AMP * 100;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+
+        let new_fees: Fees = Fees {
+            admin_trade_fee_numerator: 0,
+            admin_trade_fee_denominator: 1,
+            admin_withdraw_fee_numerator: 0,
+            admin_withdraw_fee_denominator: 1,
+            trade_fee_numerator: 0,
+            trade_fee_denominator: 1,
+            withdraw_fee_numerator: 0,
+            withdraw_fee_denominator: 1,
+            reflection_fee_numerator: 0,
+            reflection_fee_denominator: 1,
+            buyback_fee_numerator: 0,
+            buyback_fee_denominator: 1,
+            marketing_fee_numerator: 0,
+            marketing_fee_denominator: 1,
+            developer_fee_numerator: 0,
+            developer_fee_denominator: 1,
+            admin_fee_in_pool_tokens: false,
+            fee_on_input: false,
+            imbalance_fee_numerator: 0,
+            imbalance_fee_denominator: 1,
+            imbalance_threshold_bps: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 1,
+        };
+
+        // swap not initialized
+        {
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.set_new_fees(new_fees, 0)
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong nonce for authority_key
+        {
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.set_new_fees(new_fees, 0)
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            let fake_admin_key = pubkey_rand();
+            accounts.admin_key = fake_admin_key;
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_new_fees(new_fees, 0)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // invalid fees
+        {
+            let invalid_fees = Fees {
+                trade_fee_denominator: 0,
+                ..new_fees
+            };
+            assert_eq!(
+                Err(SwapError::InvalidFees.into()),
+                accounts.set_new_fees(invalid_fees, 0)
+            );
+        }
+
+        // nothing staged
+        {
+            assert_eq!(
+                Err(SwapError::NoPendingFees.into()),
+                accounts.apply_new_fees(NEW_FEES_DELAY_SLOTS)
+            );
+            assert_eq!(
+                Err(SwapError::NoPendingFees.into()),
+                accounts.cancel_pending_fees()
+            );
+        }
+
+        // cancelled
+        {
+            accounts.set_new_fees(new_fees, 0).unwrap();
+            accounts.cancel_pending_fees().unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.pending_fees_slot, 0);
+            assert_eq!(
+                Err(SwapError::NoPendingFees.into()),
+                accounts.apply_new_fees(NEW_FEES_DELAY_SLOTS)
+            );
+        }
+
+        // valid call
+        {
+            let slot = 10;
+            accounts.set_new_fees(new_fees, slot).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.fees, DEFAULT_TEST_FEES);
+            assert_eq!(swap_info.pending_fees, new_fees);
+            assert_eq!(swap_info.pending_fees_slot, slot + NEW_FEES_DELAY_SLOTS);
+
+            assert_eq!(
+                Err(SwapError::PendingFeesNotDue.into()),
+                accounts.apply_new_fees(slot + NEW_FEES_DELAY_SLOTS - 1)
+            );
+            accounts
+                .apply_new_fees(slot + NEW_FEES_DELAY_SLOTS)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.fees, new_fees);
+            assert_eq!(swap_info.pending_fees, Fees::default());
+            assert_eq!(swap_info.pending_fees_slot, 0);
+        }
+    }
+
+    #[test]
+    fn test_set_harvest_tip() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_harvest_tip(10)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // tip too high
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_harvest_tip(MAX_HARVEST_TIP_BPS + 1)
+            );
+        }
+
+        // valid call
+        {
+            accounts.set_harvest_tip(MAX_HARVEST_TIP_BPS).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.harvest_tip_bps, MAX_HARVEST_TIP_BPS);
+        }
+    }
+
+    #[test]
+    fn test_set_max_swap_amount() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_max_swap_amount(1_000)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            accounts.set_max_swap_amount(1_000).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.max_swap_amount_in, 1_000);
+        }
+    }
+
+    #[test]
+    fn test_initialize_ve_escrow() {
+        let user_key = pubkey_rand();
+        let ve_fee_bps = 2_500;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let authority_key = accounts.authority_key;
+        let (ve_lp_vault_key, mut ve_lp_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        let (ve_fee_vault_a_key, mut ve_fee_vault_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let (ve_fee_vault_b_key, mut ve_fee_vault_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+
+        // fee share can't be set before vote-escrow is enabled
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_ve_fee_share(ve_fee_bps)
+            );
+        }
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.initialize_ve_escrow(
+                    &ve_lp_vault_key,
+                    &mut ve_lp_vault_account,
+                    &ve_fee_vault_a_key,
+                    &mut ve_fee_vault_a_account,
+                    &ve_fee_vault_b_key,
+                    &mut ve_fee_vault_b_account,
+                    ve_fee_bps,
+                    ZERO_TS,
+                )
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // fee vaults swapped
+        {
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.initialize_ve_escrow(
+                    &ve_lp_vault_key,
+                    &mut ve_lp_vault_account,
+                    &ve_fee_vault_b_key,
+                    &mut ve_fee_vault_b_account.clone(),
+                    &ve_fee_vault_a_key,
+                    &mut ve_fee_vault_a_account.clone(),
+                    ve_fee_bps,
+                    ZERO_TS,
+                )
+            );
+        }
+
+        // swap token account as fee vault
+        {
+            let token_a_key = accounts.token_a_key;
+            let mut token_a_account = accounts.token_a_account.clone();
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.initialize_ve_escrow(
+                    &ve_lp_vault_key,
+                    &mut ve_lp_vault_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &ve_fee_vault_b_key,
+                    &mut ve_fee_vault_b_account,
+                    ve_fee_bps,
+                    ZERO_TS,
+                )
+            );
+        }
+
+        // pool token vault not owned by the swap authority
+        {
+            let pool_token_key = accounts.pool_token_key;
+            let mut pool_token_account = accounts.pool_token_account.clone();
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_ve_escrow(
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &ve_fee_vault_a_key,
+                    &mut ve_fee_vault_a_account,
+                    &ve_fee_vault_b_key,
+                    &mut ve_fee_vault_b_account,
+                    ve_fee_bps,
+                    ZERO_TS,
+                )
+            );
+        }
+
+        // fee share above the maximum
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.initialize_ve_escrow(
+                    &ve_lp_vault_key,
+                    &mut ve_lp_vault_account,
+                    &ve_fee_vault_a_key,
+                    &mut ve_fee_vault_a_account,
+                    &ve_fee_vault_b_key,
+                    &mut ve_fee_vault_b_account,
+                    MAX_VE_FEE_BPS + 1,
+                    ZERO_TS,
+                )
+            );
+        }
+
+        // valid call
+        {
+            let unix_timestamp = 3 * VE_EPOCH_DURATION + 1;
+            accounts
+                .initialize_ve_escrow(
+                    &ve_lp_vault_key,
+                    &mut ve_lp_vault_account,
+                    &ve_fee_vault_a_key,
+                    &mut ve_fee_vault_a_account,
+                    &ve_fee_vault_b_key,
+                    &mut ve_fee_vault_b_account,
+                    ve_fee_bps,
+                    unix_timestamp,
+                )
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_ve_enabled());
+            assert_eq!(swap_info.ve_lp_vault, ve_lp_vault_key);
+            assert_eq!(swap_info.ve_fee_vault_a, ve_fee_vault_a_key);
+            assert_eq!(swap_info.ve_fee_vault_b, ve_fee_vault_b_key);
+            assert_eq!(swap_info.ve_fee_bps, ve_fee_bps);
+            assert_eq!(swap_info.ve_epoch, 3);
+        }
+
+        // vaults can't be changed
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_ve_escrow(
+                    &ve_lp_vault_key,
+                    &mut ve_lp_vault_account,
+                    &ve_fee_vault_a_key,
+                    &mut ve_fee_vault_a_account,
+                    &ve_fee_vault_b_key,
+                    &mut ve_fee_vault_b_account,
+                    ve_fee_bps,
+                    ZERO_TS,
+                )
+            );
+        }
+
+        // fee share can be changed
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_ve_fee_share(MAX_VE_FEE_BPS + 1)
+            );
+            accounts.set_ve_fee_share(1_000).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.ve_fee_bps, 1_000);
+        }
+    }
+
+    #[test]
+    fn test_initialize_gauge_controller() {
+        let user_key = pubkey_rand();
+        let emission_per_epoch = 1_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let mut pool = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        pool.initialize_swap().unwrap();
+        let mut controller_account = Account::new(0, GaugeController::LEN, &system_program::id());
+        let mut gauge_account = Account::new(0, Gauge::LEN, &system_program::id());
+        let unix_timestamp = 2 * VE_EPOCH_DURATION + 1;
+
+        // gauges need vote-escrow
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.initialize_gauge_controller(
+                    &mut controller_account.clone(),
+                    emission_per_epoch,
+                    unix_timestamp,
+                )
+            );
+        }
+
+        let authority_key = accounts.authority_key;
+        let (ve_lp_vault_key, mut ve_lp_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        let (ve_fee_vault_a_key, mut ve_fee_vault_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let (ve_fee_vault_b_key, mut ve_fee_vault_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        accounts
+            .initialize_ve_escrow(
+                &ve_lp_vault_key,
+                &mut ve_lp_vault_account,
+                &ve_fee_vault_a_key,
+                &mut ve_fee_vault_a_account,
+                &ve_fee_vault_b_key,
+                &mut ve_fee_vault_b_account,
+                0,
+                ZERO_TS,
+            )
+            .unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.initialize_gauge_controller(
+                    &mut controller_account.clone(),
+                    emission_per_epoch,
+                    unix_timestamp,
+                )
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            accounts.admin_account.lamports = Rent::default().minimum_balance(GaugeController::LEN);
+            accounts
+                .initialize_gauge_controller(
+                    &mut controller_account,
+                    emission_per_epoch,
+                    unix_timestamp,
+                )
+                .unwrap();
+
+            let controller = GaugeController::unpack(&controller_account.data).unwrap();
+            assert_eq!(controller.swap, accounts.swap_key);
+            assert_eq!(controller.emission_per_epoch, emission_per_epoch);
+            assert_eq!(controller.epoch, 2);
+            assert_eq!(controller.total_next_weight, 0);
+        }
+
+        // controller already created
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_gauge_controller(
+                    &mut controller_account.clone(),
+                    emission_per_epoch,
+                    unix_timestamp,
+                )
+            );
+        }
+
+        // pool not owned by the program
+        {
+            let pool_swap_key = pool.swap_key;
+            let mut pool_swap_account = pool.swap_account.clone();
+            pool_swap_account.owner = pubkey_rand();
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts.add_gauge(
+                    &mut controller_account,
+                    &pool_swap_key,
+                    &mut pool_swap_account,
+                    &mut gauge_account.clone(),
+                )
+            );
+        }
+
+        // valid call
+        {
+            accounts.admin_account.lamports = Rent::default().minimum_balance(Gauge::LEN);
+            accounts
+                .add_gauge(
+                    &mut controller_account,
+                    &pool.swap_key,
+                    &mut pool.swap_account,
+                    &mut gauge_account,
+                )
+                .unwrap();
+
+            let (controller_key, _nonce) =
+                utils::find_gauge_controller_address(&SWAP_PROGRAM_ID, &accounts.swap_key);
+            let gauge = Gauge::unpack(&gauge_account.data).unwrap();
+            assert_eq!(gauge.controller, controller_key);
+            assert_eq!(gauge.swap, pool.swap_key);
+            assert_eq!(gauge.epoch, 2);
+            assert_eq!(gauge.next_weight, 0);
+        }
+
+        // gauge already added
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.add_gauge(
+                    &mut controller_account,
+                    &pool.swap_key,
+                    &mut pool.swap_account,
+                    &mut gauge_account.clone(),
+                )
+            );
+        }
+
+        // emission can be changed
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_gauge_emission(&mut controller_account, 0)
+            );
+            accounts.admin_key = old_admin_key;
+
+            accounts
+                .set_gauge_emission(&mut controller_account, 2 * emission_per_epoch)
+                .unwrap();
+            let controller = GaugeController::unpack(&controller_account.data).unwrap();
+            assert_eq!(controller.emission_per_epoch, 2 * emission_per_epoch);
+        }
+    }
+
+    #[test]
+    fn test_initialize_farm() {
+        let user_key = pubkey_rand();
+        let emission_per_slot = 1_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let mut farm_account = Account::new(0, Farm::LEN, &system_program::id());
+
+        let authority_key = accounts.authority_key;
+        let (stake_vault_key, mut stake_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        let (reward_mint_key, mut reward_mint_account) =
+            create_mint(&TOKEN_PROGRAM_ID, &user_key, 6, None);
+        let (reward_vault_key, mut reward_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &reward_mint_key,
+            &mut reward_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.initialize_farm(
+                    &mut farm_account.clone(),
+                    &stake_vault_key,
+                    &mut stake_vault_account,
+                    &reward_vault_key,
+                    &mut reward_vault_account,
+                    emission_per_slot,
+                    0,
+                )
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // stake vault of another mint
+        {
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.initialize_farm(
+                    &mut farm_account.clone(),
+                    &reward_vault_key,
+                    &mut reward_vault_account.clone(),
+                    &reward_vault_key,
+                    &mut reward_vault_account,
+                    emission_per_slot,
+                    0,
+                )
+            );
+        }
+
+        // stake vault holding pool tokens
+        {
+            let (stake_vault_key, mut stake_vault_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &authority_key,
+                &authority_key,
+                10,
+            );
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.initialize_farm(
+                    &mut farm_account.clone(),
+                    &stake_vault_key,
+                    &mut stake_vault_account,
+                    &reward_vault_key,
+                    &mut reward_vault_account,
+                    emission_per_slot,
+                    0,
+                )
+            );
+        }
+
+        // rewards paid in pool tokens
+        {
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.initialize_farm(
+                    &mut farm_account.clone(),
+                    &stake_vault_key,
+                    &mut stake_vault_account.clone(),
+                    &stake_vault_key,
+                    &mut stake_vault_account,
+                    emission_per_slot,
+                    0,
+                )
+            );
+        }
+
+        // rewards paid out of the swap token accounts
+        {
+            let token_a_key = accounts.token_a_key;
+            let mut token_a_account = accounts.token_a_account.clone();
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.initialize_farm(
+                    &mut farm_account.clone(),
+                    &stake_vault_key,
+                    &mut stake_vault_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    emission_per_slot,
+                    0,
+                )
+            );
+        }
+
+        // valid call
+        {
+            accounts.admin_account.lamports = Rent::default().minimum_balance(Farm::LEN);
+            accounts
+                .initialize_farm(
+                    &mut farm_account,
+                    &stake_vault_key,
+                    &mut stake_vault_account,
+                    &reward_vault_key,
+                    &mut reward_vault_account,
+                    emission_per_slot,
+                    7,
+                )
+                .unwrap();
+
+            let farm = Farm::unpack(&farm_account.data).unwrap();
+            assert_eq!(farm.swap, accounts.swap_key);
+            assert_eq!(farm.pool_mint, accounts.pool_mint_key);
+            assert_eq!(farm.reward_mint, reward_mint_key);
+            assert_eq!(farm.stake_vault, stake_vault_key);
+            assert_eq!(farm.reward_vault, reward_vault_key);
+            assert_eq!(farm.emission_per_slot, emission_per_slot);
+            assert_eq!(farm.last_update_slot, 7);
+            assert_eq!(farm.total_staked, 0);
+        }
+
+        // farm already created
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_farm(
+                    &mut farm_account.clone(),
+                    &stake_vault_key,
+                    &mut stake_vault_account,
+                    &reward_vault_key,
+                    &mut reward_vault_account,
+                    emission_per_slot,
+                    7,
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_dynamic_amp() {
+        let user_key = pubkey_rand();
+        let amp_factor = MIN_AMP * 100;
+        let volatility_ceiling = PRICE_SCALE / 100;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_dynamic_amp(50, 150, volatility_ceiling, 1, ZERO_TS, 0)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // invalid bounds
+        {
+            for (min_amp, max_amp, volatility_ceiling, max_drift_per_slot) in [
+                (150, 50, volatility_ceiling, 1),
+                (50, MAX_AMP + 1, volatility_ceiling, 1),
+                (50, 150, 0, 1),
+                (50, 150, volatility_ceiling, 0),
+            ] {
+                assert_eq!(
+                    Err(SwapError::InvalidInput.into()),
+                    accounts.set_dynamic_amp(
+                        min_amp,
+                        max_amp,
+                        volatility_ceiling,
+                        max_drift_per_slot,
+                        ZERO_TS,
+                        0
+                    )
+                );
+            }
+        }
+
+        // valid call
+        {
+            accounts
+                .set_dynamic_amp(50, 150, volatility_ceiling, 1, ZERO_TS, 7)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_dynamic_amp_enabled());
+            assert_eq!(swap_info.dynamic_amp_min, 50);
+            assert_eq!(swap_info.dynamic_amp_max, 150);
+            assert_eq!(swap_info.dynamic_amp_volatility_ceiling, volatility_ceiling);
+            assert_eq!(swap_info.dynamic_amp_max_drift, 1);
+            assert_eq!(swap_info.twap_price, 0);
+            assert_eq!(swap_info.twap_slot, 7);
+            assert_eq!(swap_info.target_amp_factor, amp_factor);
+        }
+
+        // no ramping while the dynamic amp is enabled
+        {
+            assert_eq!(
+                Err(SwapError::DynamicAmpEnabled.into()),
+                accounts.ramp_a(amp_factor * 2, MIN_RAMP_DURATION, MIN_RAMP_DURATION * 2)
+            );
+            assert_eq!(
+                Err(SwapError::DynamicAmpEnabled.into()),
+                accounts.stop_ramp_a(MIN_RAMP_DURATION)
+            );
+        }
+
+        // disable
+        {
+            accounts.set_dynamic_amp(0, 0, 0, 0, ZERO_TS, 0).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(!swap_info.is_dynamic_amp_enabled());
+            assert_eq!(swap_info.dynamic_amp_max, 0);
+            assert_eq!(swap_info.target_amp_factor, amp_factor);
+        }
+
+        // not during a ramp
+        {
+            accounts
+                .ramp_a(amp_factor * 2, MIN_RAMP_DURATION, MIN_RAMP_DURATION * 2)
+                .unwrap();
+            assert_eq!(
+                Err(SwapError::RampLocked.into()),
+                accounts.set_dynamic_amp(50, 150, volatility_ceiling, 1, MIN_RAMP_DURATION, 0)
+            );
+            accounts
+                .set_dynamic_amp(50, 150, volatility_ceiling, 1, MIN_RAMP_DURATION * 2, 0)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_set_admin_fee_epoch_cap() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_admin_fee_epoch_cap(1_000, 2_000)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            accounts.set_admin_fee_epoch_cap(1_000, 0).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_admin_fee_capped());
+            assert_eq!(swap_info.admin_fee_epoch_cap_a, 1_000);
+            assert_eq!(swap_info.admin_fee_epoch_cap_b, 0);
+        }
+
+        // remove the caps
+        {
+            accounts.set_admin_fee_epoch_cap(0, 0).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(!swap_info.is_admin_fee_capped());
+        }
+    }
+
+    #[test]
+    fn test_set_price_bound() {
+        let user_key = pubkey_rand();
+        let price_bound = PRICE_BOUND_SCALE / 100 * 101;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // not a price-bounded pool
+        assert_eq!(
+            Err(SwapError::InvalidInput.into()),
+            accounts.set_price_bound(price_bound)
+        );
+
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.curve_type = CurveType::PriceBounded;
+        accounts.price_bound = price_bound;
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_price_bound(price_bound * 2)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // no band
+        assert_eq!(
+            Err(SwapError::InvalidInput.into()),
+            accounts.set_price_bound(PRICE_BOUND_SCALE)
+        );
+
+        // valid call
+        {
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            let virtual_price = |swap_info: &SwapInfo| {
+                swap_info
+                    .curve(ZERO_TS)
+                    .compute_virtual_price(
+                        DEFAULT_TOKEN_A_AMOUNT.into(),
+                        DEFAULT_TOKEN_B_AMOUNT.into(),
+                        1_000_000.into(),
+                    )
+                    .unwrap()
+            };
+            let old_virtual_price = virtual_price(&swap_info);
+
+            accounts.set_price_bound(price_bound * 2).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.price_bound, price_bound * 2);
+            // a wider band holds less liquidity
+            assert!(virtual_price(&swap_info) < old_virtual_price);
+        }
+    }
+
+    #[test]
+    fn test_set_volatility_fee() {
+        let user_key = pubkey_rand();
+        let volatility_ceiling = PRICE_SCALE / 100;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_volatility_fee(volatility_ceiling, 20_000, 0)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // invalid multiplier
+        {
+            for max_multiplier_bps in [9_999, MAX_VOLATILITY_FEE_MULTIPLIER_BPS + 1] {
+                assert_eq!(
+                    Err(SwapError::InvalidInput.into()),
+                    accounts.set_volatility_fee(volatility_ceiling, max_multiplier_bps, 0)
+                );
+            }
+        }
+
+        // valid call
+        {
+            accounts
+                .set_volatility_fee(volatility_ceiling, 20_000, 7)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_volatility_fee_enabled());
+            assert!(swap_info.is_twap_tracked());
+            assert_eq!(swap_info.volatility_fee_ceiling, volatility_ceiling);
+            assert_eq!(swap_info.volatility_fee_max_multiplier_bps, 20_000);
+            assert_eq!(swap_info.twap_price, 0);
+            assert_eq!(swap_info.twap_slot, 7);
+            assert_eq!(swap_info.volatility_fee_multiplier_bps(), Some(10_000));
+        }
+
+        // updating keeps the TWAP
+        {
+            accounts
+                .set_volatility_fee(volatility_ceiling * 2, 30_000, 9)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.volatility_fee_max_multiplier_bps, 30_000);
+            assert_eq!(swap_info.twap_slot, 7);
+        }
+
+        // disable
+        {
+            accounts.set_volatility_fee(0, 0, 0).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(!swap_info.is_volatility_fee_enabled());
+            assert!(!swap_info.is_twap_tracked());
+            assert_eq!(swap_info.volatility_fee_max_multiplier_bps, 0);
+        }
+    }
+
+    #[test]
+    fn test_set_deposit_bonus() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_deposit_bonus(1_000, 10)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // shares too high
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_deposit_bonus(10_001, 10)
+            );
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_deposit_bonus(1_000, MAX_DEPOSIT_BONUS_BPS + 1)
+            );
+        }
+
+        // valid call
+        {
+            accounts
+                .set_deposit_bonus(10_000, MAX_DEPOSIT_BONUS_BPS)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.fee_buffer_bps, 10_000);
+            assert_eq!(swap_info.deposit_bonus_bps, MAX_DEPOSIT_BONUS_BPS);
+        }
+    }
+
+    #[test]
+    fn test_set_params() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let new_fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            ..DEFAULT_TEST_FEES
+        };
+        let current_ts = ZERO_TS + MIN_RAMP_DURATION;
+        let stop_ramp_ts = current_ts + MIN_RAMP_DURATION;
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_params(new_fees, 5, stop_ramp_ts, 1_000, true, current_ts)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // an invalid ramp leaves the rest of the bundle unapplied
+        {
+            assert_eq!(
+                Err(SwapError::InsufficientRampTime.into()),
+                accounts.set_params(new_fees, 5, current_ts + 1, 1_000, true, current_ts)
+            );
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_params(new_fees, MAX_AMP + 1, stop_ramp_ts, 1_000, true, current_ts)
+            );
+            let invalid_fees = Fees {
+                trade_fee_numerator: 2,
+                trade_fee_denominator: 1,
+                ..new_fees
+            };
+            assert_eq!(
+                Err(SwapError::InvalidFees.into()),
+                accounts.set_params(invalid_fees, 0, 0, 1_000, true, current_ts)
+            );
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.fees, DEFAULT_TEST_FEES);
+            assert_eq!(swap_info.max_swap_amount_in, 0);
+            assert!(!swap_info.is_paused);
+        }
+
+        // valid call
+        {
+            accounts
+                .set_params(new_fees, 5, stop_ramp_ts, 1_000, true, current_ts)
+                .unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.fees, DEFAULT_TEST_FEES);
+            assert_eq!(swap_info.pending_fees, new_fees);
+            assert_eq!(swap_info.pending_fees_slot, NEW_FEES_DELAY_SLOTS);
+            assert_eq!(swap_info.initial_amp_factor, MIN_AMP);
+            assert_eq!(swap_info.target_amp_factor, 5);
+            assert_eq!(swap_info.start_ramp_ts, current_ts);
+            assert_eq!(swap_info.stop_ramp_ts, stop_ramp_ts);
+            assert_eq!(swap_info.max_swap_amount_in, 1_000);
+            assert!(swap_info.is_paused);
+        }
+
+        // the staged fees can't apply before the delay
+        {
+            assert_eq!(
+                Err(SwapError::PendingFeesNotDue.into()),
+                accounts.apply_new_fees(NEW_FEES_DELAY_SLOTS - 1)
+            );
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.fees, DEFAULT_TEST_FEES);
+            accounts.apply_new_fees(NEW_FEES_DELAY_SLOTS).unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.fees, new_fees);
+        }
+
+        // a zero target amp leaves the ramp
+        {
+            accounts
+                .set_params(DEFAULT_TEST_FEES, 0, 0, 0, false, current_ts + 1)
+                .unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.fees, new_fees);
+            assert_eq!(swap_info.pending_fees, DEFAULT_TEST_FEES);
+            assert_eq!(swap_info.target_amp_factor, 5);
+            assert_eq!(swap_info.stop_ramp_ts, stop_ramp_ts);
+            assert_eq!(swap_info.max_swap_amount_in, 0);
+            assert!(!swap_info.is_paused);
+        }
+    }
+
+    #[test]
+    fn test_set_min_lp_mint() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_min_lp_mint(1_000)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            accounts.set_min_lp_mint(1_000).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.min_lp_mint, 1_000);
+        }
+    }
+
+    #[test]
+    fn test_set_dust_threshold() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_dust_threshold(100)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            accounts.set_dust_threshold(100).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.dust_threshold, 100);
+        }
+    }
+
+    #[test]
+    fn test_set_custodian() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let custodian_key = pubkey_rand();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_custodian(&custodian_key, 100)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            accounts.set_custodian(&custodian_key, 100).unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.custodian, custodian_key);
+            assert_eq!(swap_info.custodian_withdraw_threshold, 100);
+            assert!(!swap_info.requires_custodian(100));
+            assert!(swap_info.requires_custodian(101));
+        }
+    }
+
+    #[test]
+    fn test_set_guardian() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let guardian_key = pubkey_rand();
+        let admin_key = accounts.admin_key;
+
+        // unauthorized account
+        {
+            accounts.admin_key = guardian_key;
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_guardian(&guardian_key)
+            );
+            assert_eq!(Err(SwapError::Unauthorized.into()), accounts.pause());
+            accounts.admin_key = admin_key;
+        }
+
+        accounts.set_guardian(&guardian_key).unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.guardian_key, guardian_key);
+
+        // the guardian pauses and unpauses, but sets no parameter
+        {
+            accounts.admin_key = guardian_key;
+            accounts.pause().unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_paused);
+            accounts.unpause().unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(!swap_info.is_paused);
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_guardian(&pubkey_rand())
+            );
+            accounts.admin_key = admin_key;
+        }
+
+        // the admin still pauses
+        accounts.pause().unwrap();
+        accounts.unpause().unwrap();
+
+        // removed guardian
+        {
+            accounts.set_guardian(&Pubkey::default()).unwrap();
+            accounts.admin_key = guardian_key;
+            assert_eq!(Err(SwapError::Unauthorized.into()), accounts.pause());
+            accounts.admin_key = admin_key;
+        }
+    }
+
+    #[test]
+    fn test_set_custom_error_message() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let paused = SwapError::IsPaused as u32;
+        let message = |accounts: &SwapAccountInfo, error_code| {
+            SwapInfo::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .custom_error_messages
+                .message(error_code)
+                .map(str::to_string)
+        };
+
+        // unauthorized account
+        {
+            let admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_custom_error_message(paused, "migrating")
+            );
+            accounts.admin_key = admin_key;
+        }
+
+        accounts
+            .set_custom_error_message(paused, "pool migrating to V2, use pool XYZ")
+            .unwrap();
+        assert_eq!(
+            message(&accounts, paused).as_deref(),
+            Some("pool migrating to V2, use pool XYZ")
+        );
+
+        // full registry
+        {
+            for error_code in 1..MAX_CUSTOM_ERROR_MESSAGES as u32 {
+                accounts
+                    .set_custom_error_message(paused + error_code, "paused")
+                    .unwrap();
+            }
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_custom_error_message(paused + 100, "paused")
+            );
+        }
+
+        // removed message
+        {
+            accounts.set_custom_error_message(paused, "").unwrap();
+            assert_eq!(message(&accounts, paused), None);
+            accounts
+                .set_custom_error_message(paused + 100, "paused")
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_protocol_liquidity() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let admin_key = accounts.admin_key;
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let protocol_pool_amount = |accounts: &SwapAccountInfo| {
+            SwapInfo::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .protocol_pool_amount
+        };
+
+        // unauthorized account
+        {
+            let fake_admin_key = pubkey_rand();
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &fake_admin_key, 1_000, 1_000, 0);
+            accounts.admin_key = fake_admin_key;
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.deploy_protocol_liquidity(
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    1_000,
+                    1_000,
+                    0,
+                )
+            );
+            accounts.admin_key = admin_key;
+        }
+
+        let (
+            treasury_a_key,
+            mut treasury_a_account,
+            treasury_b_key,
+            mut treasury_b_account,
+            treasury_pool_key,
+            mut treasury_pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &admin_key, 1_000_000, 1_000_000, 0);
+        accounts
+            .deploy_protocol_liquidity(
+                &treasury_a_key,
+                &mut treasury_a_account,
+                &treasury_b_key,
+                &mut treasury_b_account,
+                &treasury_pool_key,
+                &mut treasury_pool_account,
+                1_000_000,
+                1_000_000,
+                0,
+            )
+            .unwrap();
+        let deployed = amount(&treasury_pool_account);
+        assert!(deployed > 0);
+        assert_eq!(amount(&treasury_a_account), 0);
+        assert_eq!(amount(&treasury_b_account), 0);
+        assert_eq!(protocol_pool_amount(&accounts), deployed);
+
+        // user deposits are not protocol-owned
+        {
+            let depositor_key = pubkey_rand();
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, 1_000, 1_000, 0);
+            accounts
+                .deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    1_000,
+                    1_000,
+                    0,
+                )
+                .unwrap();
+            assert_eq!(protocol_pool_amount(&accounts), deployed);
+        }
+
+        // more than deployed
+        assert_eq!(
+            Err(SwapError::InsufficientProtocolLiquidity.into()),
+            accounts.withdraw_protocol_liquidity(
+                &treasury_pool_key,
+                &mut treasury_pool_account,
+                &treasury_a_key,
+                &mut treasury_a_account,
+                &treasury_b_key,
+                &mut treasury_b_account,
+                deployed + 1,
+                0,
+                0,
+            )
+        );
+
+        accounts
+            .withdraw_protocol_liquidity(
+                &treasury_pool_key,
+                &mut treasury_pool_account,
+                &treasury_a_key,
+                &mut treasury_a_account,
+                &treasury_b_key,
+                &mut treasury_b_account,
+                deployed / 2,
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(amount(&treasury_pool_account), deployed - deployed / 2);
+        assert!(amount(&treasury_a_account) > 0);
+        assert!(amount(&treasury_b_account) > 0);
+        assert_eq!(protocol_pool_amount(&accounts), deployed - deployed / 2);
+
+        // strangers can't withdraw protocol-owned pool tokens the swap authority holds
+        {
+            let stranger_key = pubkey_rand();
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &stranger_key, 0, 0, 0);
+            let authority_key = accounts.authority_key;
+            let (vault_a_key, mut vault_a_account, vault_b_key, mut vault_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &admin_key, 1_000, 1_000, 0);
+            let (_, _, _, _, vault_key, mut vault_account) =
+                accounts.setup_token_accounts(&user_key, &authority_key, 0, 0, 0);
+            accounts
+                .deploy_protocol_liquidity(
+                    &vault_a_key,
+                    &mut vault_a_account,
+                    &vault_b_key,
+                    &mut vault_b_account,
+                    &vault_key,
+                    &mut vault_account,
+                    1_000,
+                    1_000,
+                    0,
+                )
+                .unwrap();
+            let vault_amount = amount(&vault_account);
+            assert!(vault_amount > 0);
+            assert_eq!(
+                Err(SwapError::AuthorityOwnedSource.into()),
+                accounts.withdraw_unapproved(
+                    &stranger_key,
+                    &vault_key,
+                    &mut vault_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    vault_amount,
+                    0,
+                    0,
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_credential_issuer() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let credential_issuer_key = pubkey_rand();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_credential_issuer(&credential_issuer_key)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            accounts
+                .set_credential_issuer(&credential_issuer_key)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.credential_issuer, credential_issuer_key);
+            assert!(swap_info.requires_credential());
+        }
+    }
+
+    #[test]
+    fn test_update_lp_metadata() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let (metadata_key, _) = metadata::find_metadata_address(&accounts.pool_mint_key);
+        let mut metadata_account = Account::new(0, 128, &metadata::id());
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.update_lp_metadata(
+                    &metadata_key,
+                    &mut metadata_account,
+                    "Baby Punk LP",
+                    "BPLP",
+                    "https://babypunk.swap/lp.json",
+                )
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // metadata of another mint
+        {
+            let (other_metadata_key, _) = metadata::find_metadata_address(&pubkey_rand());
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.update_lp_metadata(
+                    &other_metadata_key,
+                    &mut metadata_account,
+                    "Baby Punk LP",
+                    "BPLP",
+                    "https://babypunk.swap/lp.json",
+                )
+            );
+        }
+
+        // valid call
+        {
+            accounts
+                .update_lp_metadata(
+                    &metadata_key,
+                    &mut metadata_account,
+                    "Baby Punk LP",
+                    "BPLP",
+                    "https://babypunk.swap/lp.json",
+                )
+                .unwrap();
+
+            let ix = metadata::update_metadata_accounts_v2(
+                &metadata_key,
+                &accounts.authority_key,
+                "Baby Punk LP",
+                "BPLP",
+                "https://babypunk.swap/lp.json",
+            );
+            assert_eq!(metadata_account.data[..ix.data.len()], ix.data[..]);
+        }
+    }
+
+    #[test]
+    fn test_admin_log() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_log.iter().count(), 0);
+
+        accounts.set_custodian(&pubkey_rand(), 100).unwrap();
+        accounts.set_credential_issuer(&pubkey_rand()).unwrap();
+
+        // failed instructions are not recorded
+        let old_admin_key = accounts.admin_key;
+        accounts.admin_key = pubkey_rand();
+        assert_eq!(
+            Err(SwapError::Unauthorized.into()),
+            accounts.set_custodian(&pubkey_rand(), 200)
+        );
+        accounts.admin_key = old_admin_key;
+
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        let actions: Vec<_> = swap_info.admin_log.iter().collect();
+        assert_eq!(
+            actions,
+            vec![
+                &AdminAction {
+                    tag: 133,
+                    slot: 0,
+                    signer: accounts.admin_key,
+                },
+                &AdminAction {
+                    tag: 134,
+                    slot: 0,
+                    signer: accounts.admin_key,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_config_hash() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        let initial_hash = swap_info.config_hash;
+        assert_eq!(initial_hash, swap_info.compute_config_hash());
+
+        accounts.set_custodian(&pubkey_rand(), 100).unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_ne!(swap_info.config_hash, initial_hash);
+        assert_eq!(swap_info.config_hash, swap_info.compute_config_hash());
+        let custodian_hash = swap_info.config_hash;
+
+        // failed instructions leave the hash unchanged
+        let old_admin_key = accounts.admin_key;
+        accounts.admin_key = pubkey_rand();
+        assert_eq!(
+            Err(SwapError::Unauthorized.into()),
+            accounts.set_custodian(&pubkey_rand(), 200)
+        );
+        accounts.admin_key = old_admin_key;
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.config_hash, custodian_hash);
+    }
+
+    #[test]
+    fn test_initialize_liquidity_positions() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let authority_key = accounts.authority_key;
+        let (liquidity_vault_key, mut liquidity_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.initialize_liquidity_positions(
+                    &liquidity_vault_key,
+                    &mut liquidity_vault_account,
+                    ZERO_TS,
+                )
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // vault of another mint
+        {
+            let token_a_key = accounts.token_a_key;
+            let mut token_a_account = accounts.token_a_account.clone();
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.initialize_liquidity_positions(
+                    &token_a_key,
+                    &mut token_a_account,
+                    ZERO_TS,
+                )
+            );
+        }
+
+        // vault not owned by the swap authority
+        {
+            let pool_token_key = accounts.pool_token_key;
+            let mut pool_token_account = accounts.pool_token_account.clone();
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_liquidity_positions(
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    ZERO_TS,
+                )
+            );
+        }
+
+        // valid call
+        {
+            accounts
+                .initialize_liquidity_positions(
+                    &liquidity_vault_key,
+                    &mut liquidity_vault_account,
+                    1_000,
+                )
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.liquidity_vault, liquidity_vault_key);
+            assert_eq!(swap_info.position_liquidity_ts, 1_000);
+        }
+
+        // vault already set
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_liquidity_positions(
+                    &liquidity_vault_key,
+                    &mut liquidity_vault_account,
+                    ZERO_TS,
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_initialize_reflection() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let authority_key = accounts.authority_key;
+        let (reflection_lp_vault_key, mut reflection_lp_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        let (reflection_vault_a_key, mut reflection_vault_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let (reflection_vault_b_key, mut reflection_vault_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+
+        // unauthorized account
         {
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.initialize_reflection(
+                    &reflection_lp_vault_key,
+                    &mut reflection_lp_vault_account,
+                    &reflection_vault_a_key,
+                    &mut reflection_vault_a_account,
+                    &reflection_vault_b_key,
+                    &mut reflection_vault_b_account,
+                )
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // vaults of the wrong mints
+        {
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.initialize_reflection(
+                    &reflection_lp_vault_key,
+                    &mut reflection_lp_vault_account,
+                    &reflection_vault_b_key,
+                    &mut reflection_vault_b_account.clone(),
+                    &reflection_vault_a_key,
+                    &mut reflection_vault_a_account.clone(),
+                )
+            );
+        }
+
+        // reserve of the swap as vault
+        {
+            let token_a_key = accounts.token_a_key;
+            let mut token_a_account = accounts.token_a_account.clone();
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.initialize_reflection(
+                    &reflection_lp_vault_key,
+                    &mut reflection_lp_vault_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &reflection_vault_b_key,
+                    &mut reflection_vault_b_account,
+                )
+            );
+        }
+
+        // vault not owned by the swap authority
+        {
+            let pool_token_key = accounts.pool_token_key;
+            let mut pool_token_account = accounts.pool_token_account.clone();
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_reflection(
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &reflection_vault_a_key,
+                    &mut reflection_vault_a_account,
+                    &reflection_vault_b_key,
+                    &mut reflection_vault_b_account,
+                )
+            );
+        }
+
+        // valid call
+        {
+            accounts
+                .initialize_reflection(
+                    &reflection_lp_vault_key,
+                    &mut reflection_lp_vault_account,
+                    &reflection_vault_a_key,
+                    &mut reflection_vault_a_account,
+                    &reflection_vault_b_key,
+                    &mut reflection_vault_b_account,
+                )
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_reflection_enabled());
+            assert_eq!(swap_info.reflection_lp_vault, reflection_lp_vault_key);
+            assert_eq!(swap_info.reflection_vault_a, reflection_vault_a_key);
+            assert_eq!(swap_info.reflection_vault_b, reflection_vault_b_key);
+        }
+
+        // vaults already set
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_reflection(
+                    &reflection_lp_vault_key,
+                    &mut reflection_lp_vault_account,
+                    &reflection_vault_a_key,
+                    &mut reflection_vault_a_account,
+                    &reflection_vault_b_key,
+                    &mut reflection_vault_b_account,
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_initialize_buyback() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let authority_key = accounts.authority_key;
+        let token_b_mint_key = accounts.token_b_mint_key;
+        let (buyback_vault_a_key, mut buyback_vault_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let (buyback_vault_b_key, mut buyback_vault_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.initialize_buyback(
+                    &buyback_vault_a_key,
+                    &mut buyback_vault_a_account,
+                    &buyback_vault_b_key,
+                    &mut buyback_vault_b_account,
+                    &token_b_mint_key,
+                )
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // mint of neither token of the pool
+        {
+            let pool_mint_key = accounts.pool_mint_key;
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.initialize_buyback(
+                    &buyback_vault_a_key,
+                    &mut buyback_vault_a_account,
+                    &buyback_vault_b_key,
+                    &mut buyback_vault_b_account,
+                    &pool_mint_key,
+                )
+            );
+        }
+
+        // vaults of the wrong mints
+        {
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.initialize_buyback(
+                    &buyback_vault_b_key,
+                    &mut buyback_vault_b_account.clone(),
+                    &buyback_vault_a_key,
+                    &mut buyback_vault_a_account.clone(),
+                    &token_b_mint_key,
+                )
+            );
+        }
+
+        // reserve of the swap as vault
+        {
+            let token_b_key = accounts.token_b_key;
+            let mut token_b_account = accounts.token_b_account.clone();
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.initialize_buyback(
+                    &buyback_vault_a_key,
+                    &mut buyback_vault_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &token_b_mint_key,
+                )
+            );
+        }
+
+        // reflection vault as vault
+        {
+            let (reflection_lp_vault_key, mut reflection_lp_vault_account) = mint_token(
                 &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &authority_key,
+                &authority_key,
+                0,
             );
-            accounts.authority_key = bad_authority_key;
+            let (reflection_vault_b_key, mut reflection_vault_b_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &authority_key,
+                0,
+            );
+            let swap_account = accounts.swap_account.clone();
+            accounts
+                .initialize_reflection(
+                    &reflection_lp_vault_key,
+                    &mut reflection_lp_vault_account,
+                    &buyback_vault_a_key,
+                    &mut buyback_vault_a_account.clone(),
+                    &reflection_vault_b_key,
+                    &mut reflection_vault_b_account,
+                )
+                .unwrap();
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.ramp_a(MIN_AMP, ZERO_TS, MIN_RAMP_DURATION)
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.initialize_buyback(
+                    &buyback_vault_a_key,
+                    &mut buyback_vault_a_account,
+                    &buyback_vault_b_key,
+                    &mut buyback_vault_b_account,
+                    &token_b_mint_key,
+                )
             );
-            accounts.authority_key = old_authority;
+            accounts.swap_account = swap_account;
+        }
+
+        // vault not owned by the swap authority
+        {
+            let (user_vault_b_key, mut user_vault_b_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &user_key,
+                0,
+            );
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_buyback(
+                    &buyback_vault_a_key,
+                    &mut buyback_vault_a_account,
+                    &user_vault_b_key,
+                    &mut user_vault_b_account,
+                    &token_b_mint_key,
+                )
+            );
+        }
+
+        // valid call
+        {
+            accounts
+                .initialize_buyback(
+                    &buyback_vault_a_key,
+                    &mut buyback_vault_a_account,
+                    &buyback_vault_b_key,
+                    &mut buyback_vault_b_account,
+                    &token_b_mint_key,
+                )
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_buyback_enabled());
+            assert_eq!(swap_info.buyback_mint, token_b_mint_key);
+            assert_eq!(swap_info.buyback_vault_a, buyback_vault_a_key);
+            assert_eq!(swap_info.buyback_vault_b, buyback_vault_b_key);
+            assert_eq!(swap_info.buyback_burned, 0);
+        }
+
+        // buybacks already enabled
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_buyback(
+                    &buyback_vault_a_key,
+                    &mut buyback_vault_a_account,
+                    &buyback_vault_b_key,
+                    &mut buyback_vault_b_account,
+                    &token_b_mint_key,
+                )
+            );
+        }
+    }
+    #[test]
+    fn test_set_insurance_fund() {
+        let user_key = pubkey_rand();
+        let governance_program_id = pubkey_rand();
+        let realm_key = pubkey_rand();
+        let mut realm_account = Account::new(0, 0, &governance_program_id);
+        let insurance_authority_key = pubkey_rand();
+        let mut governance_data = vec![18];
+        governance_data.extend_from_slice(realm_key.as_ref());
+        governance_data.extend_from_slice(pubkey_rand().as_ref());
+        let mut insurance_authority_account = Account {
+            data: governance_data,
+            owner: governance_program_id,
+            ..Account::default()
+        };
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_insurance_fund(
+                    &realm_key,
+                    &mut realm_account,
+                    &insurance_authority_key,
+                    &mut insurance_authority_account,
+                    1_000,
+                )
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // no governance realm
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_insurance_fund(
+                    &realm_key,
+                    &mut realm_account,
+                    &insurance_authority_key,
+                    &mut insurance_authority_account,
+                    1_000,
+                )
+            );
+        }
+
+        let authority_key = accounts.authority_key;
+        let (governance_vault_key, mut governance_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        accounts
+            .set_governance_realm(
+                &realm_key,
+                &governance_vault_key,
+                &mut governance_vault_account,
+                0,
+            )
+            .unwrap();
+
+        // share too high
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_insurance_fund(
+                    &realm_key,
+                    &mut realm_account,
+                    &insurance_authority_key,
+                    &mut insurance_authority_account,
+                    10_001,
+                )
+            );
+        }
+
+        // governance not owned by the governance program of the realm
+        {
+            let mut insurance_authority_account = Account {
+                owner: pubkey_rand(),
+                ..insurance_authority_account.clone()
+            };
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.set_insurance_fund(
+                    &realm_key,
+                    &mut realm_account,
+                    &insurance_authority_key,
+                    &mut insurance_authority_account,
+                    1_000,
+                )
+            );
+        }
+
+        // governance of another realm
+        {
+            let mut insurance_authority_account = insurance_authority_account.clone();
+            insurance_authority_account.data[1..33].copy_from_slice(pubkey_rand().as_ref());
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_insurance_fund(
+                    &realm_key,
+                    &mut realm_account,
+                    &insurance_authority_key,
+                    &mut insurance_authority_account,
+                    1_000,
+                )
+            );
+        }
+
+        // valid call
+        {
+            accounts
+                .set_insurance_fund(
+                    &realm_key,
+                    &mut realm_account,
+                    &insurance_authority_key,
+                    &mut insurance_authority_account,
+                    1_000,
+                )
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.insurance_fee_bps, 1_000);
+            assert_eq!(swap_info.insurance_authority, insurance_authority_key);
+        }
+    }
+
+    #[test]
+    fn test_set_order_flow_auction() {
+        let user_key = pubkey_rand();
+        let bidder_key = pubkey_rand();
+        let batch_slots = 4;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let token_a_key = accounts.token_a_key;
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_order_flow_auction(&token_a_key, batch_slots, 0)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // bids paid outside of the pool
+        {
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.set_order_flow_auction(&pubkey_rand(), batch_slots, 0)
+            );
+        }
+
+        // no slot to bid in
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_order_flow_auction(&token_a_key, 1, 0)
+            );
+        }
+
+        // valid call
+        {
+            accounts
+                .set_order_flow_auction(&token_a_key, batch_slots, 0)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_order_flow_auction_enabled());
+            assert_eq!(swap_info.order_flow_batch_slots, batch_slots);
+            assert_eq!(swap_info.order_flow_bid_token, token_a_key);
+        }
+
+        // bid pending until the end of its batch
+        {
+            let (bid_key, mut bid_account, _key, _account, _pool_key, _pool_account) =
+                accounts.setup_token_accounts(&user_key, &bidder_key, 1_000, 0, 0);
+            accounts
+                .bid_order_flow(
+                    &bidder_key,
+                    &bid_key,
+                    &mut bid_account,
+                    &pubkey_rand(),
+                    &mut Account::default(),
+                    100,
+                    0,
+                )
+                .unwrap();
+            assert_eq!(
+                Err(SwapError::OrderFlowBidPending.into()),
+                accounts.set_order_flow_auction(&token_a_key, 0, batch_slots - 1)
+            );
+            accounts
+                .set_order_flow_auction(&token_a_key, 0, batch_slots)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(!swap_info.is_order_flow_auction_enabled());
+            assert_eq!(swap_info.order_flow_bid, 0);
+        }
+    }
+
+    #[test]
+    fn test_set_reflection_distributor() {
+        let user_key = pubkey_rand();
+        let distributor_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_reflection_distributor(&distributor_key)
+            );
+            accounts.admin_key = old_admin_key;
+        }
+
+        // valid call
+        {
+            accounts
+                .set_reflection_distributor(&distributor_key)
+                .unwrap();
+
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.reflection_distributor, distributor_key);
         }
+    }
 
-        // Unauthorized account
+    #[test]
+    fn test_initialize_dev_fee_vesting() {
+        let user_key = pubkey_rand();
+        let dev_wallet_key = pubkey_rand();
+        let vesting_duration = 1_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let authority_key = accounts.authority_key;
+        let (dev_vesting_a_key, mut dev_vesting_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let (dev_vesting_b_key, mut dev_vesting_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+
+        // unauthorized account
         {
             let old_admin_key = accounts.admin_key;
-            let fake_admin_key = pubkey_rand();
-            accounts.admin_key = fake_admin_key;
+            accounts.admin_key = pubkey_rand();
             assert_eq!(
                 Err(SwapError::Unauthorized.into()),
-                accounts.ramp_a(MIN_AMP, ZERO_TS, MIN_RAMP_DURATION)
+                accounts.initialize_dev_fee_vesting(
+                    &dev_vesting_a_key,
+                    &mut dev_vesting_a_account,
+                    &dev_vesting_b_key,
+                    &mut dev_vesting_b_account,
+                    &dev_wallet_key,
+                    vesting_duration,
+                )
             );
             accounts.admin_key = old_admin_key;
         }
 
-        // ramp locked
+        // no vesting duration
         {
             assert_eq!(
-                Err(SwapError::RampLocked.into()),
-                accounts.ramp_a(MIN_AMP, ZERO_TS, MIN_RAMP_DURATION / 2)
+                Err(SwapError::InvalidInput.into()),
+                accounts.initialize_dev_fee_vesting(
+                    &dev_vesting_a_key,
+                    &mut dev_vesting_a_account,
+                    &dev_vesting_b_key,
+                    &mut dev_vesting_b_account,
+                    &dev_wallet_key,
+                    0,
+                )
             );
         }
 
-        // insufficient ramp time
+        // pool reserves as vesting account
         {
+            let token_a_key = accounts.token_a_key;
+            let mut token_a_account = accounts.token_a_account.clone();
             assert_eq!(
-                Err(SwapError::InsufficientRampTime.into()),
-                accounts.ramp_a(amp_factor, MIN_RAMP_DURATION, ZERO_TS)
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.initialize_dev_fee_vesting(
+                    &token_a_key,
+                    &mut token_a_account,
+                    &dev_vesting_b_key,
+                    &mut dev_vesting_b_account,
+                    &dev_wallet_key,
+                    vesting_duration,
+                )
             );
         }
 
-        // invalid amp targets
+        // vesting account not owned by the swap authority
         {
-            // amp target too low
+            let (wrong_vesting_key, mut wrong_vesting_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &dev_wallet_key,
+                0,
+            );
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.ramp_a(MIN_AMP, MIN_RAMP_DURATION, MIN_RAMP_DURATION * 2)
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_dev_fee_vesting(
+                    &wrong_vesting_key,
+                    &mut wrong_vesting_account,
+                    &dev_vesting_b_key,
+                    &mut dev_vesting_b_account,
+                    &dev_wallet_key,
+                    vesting_duration,
+                )
             );
-            // amp target too high
+        }
+
+        // swapped vesting accounts
+        {
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.ramp_a(MAX_AMP, MIN_RAMP_DURATION, MIN_RAMP_DURATION * 2)
+                Err(SwapError::IncorrectMint.into()),
+                accounts.initialize_dev_fee_vesting(
+                    &dev_vesting_b_key,
+                    &mut dev_vesting_b_account.clone(),
+                    &dev_vesting_a_key,
+                    &mut dev_vesting_a_account.clone(),
+                    &dev_wallet_key,
+                    vesting_duration,
+                )
             );
         }
 
-        // valid ramp
+        // valid call
         {
-            let target_amp = MIN_AMP * 200;
-            let current_ts = MIN_RAMP_DURATION;
-            let stop_ramp_ts = MIN_RAMP_DURATION * 2;
             accounts
-                .ramp_a(target_amp, current_ts, stop_ramp_ts)
+                .initialize_dev_fee_vesting(
+                    &dev_vesting_a_key,
+                    &mut dev_vesting_a_account,
+                    &dev_vesting_b_key,
+                    &mut dev_vesting_b_account,
+                    &dev_wallet_key,
+                    vesting_duration,
+                )
                 .unwrap();
 
             let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert_eq!(swap_info.initial_amp_factor, amp_factor);
-            assert_eq!(swap_info.target_amp_factor, target_amp);
-            assert_eq!(swap_info.start_ramp_ts, current_ts);
-            assert_eq!(swap_info.stop_ramp_ts, stop_ramp_ts);
+            assert_eq!(swap_info.dev_vesting_a, dev_vesting_a_key);
+            assert_eq!(swap_info.dev_vesting_b, dev_vesting_b_key);
+            assert_eq!(swap_info.dev_wallet, dev_wallet_key);
+            assert_eq!(swap_info.dev_vesting_start_ts, ZERO_TS);
+            assert_eq!(swap_info.dev_vesting_end_ts, ZERO_TS + vesting_duration);
+        }
+
+        // vesting can't be redirected
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_dev_fee_vesting(
+                    &dev_vesting_a_key,
+                    &mut dev_vesting_a_account,
+                    &dev_vesting_b_key,
+                    &mut dev_vesting_b_account,
+                    &pubkey_rand(),
+                    vesting_duration,
+                )
+            );
         }
     }
 
     #[test]
-    fn test_stop_ramp_a() {
+    fn test_claim_admin_after_timeout() {
         let user_key = pubkey_rand();
-        let amp_factor = MIN_AMP * 100;
+        let recovery_key = pubkey_rand();
+        let admin_timeout_slots = 1_000;
         let mut accounts = SwapAccountInfo::new(
             &user_key,
-            amp_factor,
+            MIN_AMP,
             DEFAULT_TOKEN_A_AMOUNT,
             DEFAULT_TOKEN_B_AMOUNT,
             DEFAULT_TEST_FEES,
         );
+        accounts.initialize_swap().unwrap();
 
-        // swap not initialized
+        // recovery not set up
         {
             assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.ramp_a(MIN_AMP, ZERO_TS, MIN_RAMP_DURATION)
+                Err(SwapError::Unauthorized.into()),
+                accounts.claim_admin_after_timeout(&recovery_key, admin_timeout_slots + 1)
             );
         }
 
-        accounts.initialize_swap().unwrap();
-
-        // wrong nonce for authority_key
+        // unauthorized account
         {
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_admin_recovery(&recovery_key, admin_timeout_slots, 0)
             );
-            accounts.authority_key = bad_authority_key;
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.stop_ramp_a(ZERO_TS)
+                Err(SwapError::Unauthorized.into()),
+                accounts.admin_heartbeat(0)
             );
-            accounts.authority_key = old_authority;
+            accounts.admin_key = old_admin_key;
         }
 
-        // unauthorized account
+        // zero timeout
         {
-            let old_admin_key = accounts.admin_key;
-            let fake_admin_key = pubkey_rand();
-            accounts.admin_key = fake_admin_key;
             assert_eq!(
-                Err(SwapError::Unauthorized.into()),
-                accounts.stop_ramp_a(ZERO_TS)
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_admin_recovery(&recovery_key, 0, 0)
             );
-            accounts.admin_key = old_admin_key;
         }
 
-        // valid call
+        accounts
+            .set_admin_recovery(&recovery_key, admin_timeout_slots, 0)
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_recovery_key, recovery_key);
+        assert_eq!(swap_info.admin_timeout_slots, admin_timeout_slots);
+
+        // wrong recovery key
         {
-            let expected_ts = MIN_RAMP_DURATION;
-            accounts.stop_ramp_a(expected_ts).unwrap();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.claim_admin_after_timeout(&pubkey_rand(), admin_timeout_slots + 1)
+            );
+        }
 
-            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert_eq!(swap_info.initial_amp_factor, amp_factor);
-            assert_eq!(swap_info.target_amp_factor, amp_factor);
-            assert_eq!(swap_info.start_ramp_ts, expected_ts);
-            assert_eq!(swap_info.stop_ramp_ts, expected_ts);
+        // heartbeat not timed out
+        {
+            assert_eq!(
+                Err(SwapError::AdminHeartbeatActive.into()),
+                accounts.claim_admin_after_timeout(&recovery_key, admin_timeout_slots)
+            );
         }
+
+        // a heartbeat postpones the timeout
+        let heartbeat_slot = 500;
+        accounts.admin_heartbeat(heartbeat_slot).unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_heartbeat_slot, heartbeat_slot);
+        assert_eq!(
+            Err(SwapError::AdminHeartbeatActive.into()),
+            accounts.claim_admin_after_timeout(&recovery_key, admin_timeout_slots + 1)
+        );
+
+        let claim_slot = heartbeat_slot + admin_timeout_slots + 1;
+        accounts
+            .claim_admin_after_timeout(&recovery_key, claim_slot)
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_key, recovery_key);
+        assert_eq!(swap_info.admin_recovery_key, Pubkey::default());
+        assert_eq!(swap_info.admin_timeout_slots, 0);
+        assert_eq!(swap_info.admin_heartbeat_slot, claim_slot);
+
+        // the former admin lost its rights
+        assert_eq!(
+            Err(SwapError::Unauthorized.into()),
+            accounts.admin_heartbeat(claim_slot)
+        );
     }
 
     #[test]
-    fn test_pause() {
+    fn test_snapshot_and_restore_state() {
         let user_key = pubkey_rand();
+        let snapshot_ts = 1_000;
         let mut accounts = SwapAccountInfo::new(
             &user_key,
             MIN_AMP,
@@ -549,50 +5965,141 @@ mod tests {
             DEFAULT_TOKEN_B_AMOUNT,
             DEFAULT_TEST_FEES,
         );
-
-        // swap not initialized
-        {
-            assert_eq!(Err(ProgramError::UninitializedAccount), accounts.pause());
-        }
-
         accounts.initialize_swap().unwrap();
+        accounts.admin_account.lamports = Rent::default().minimum_balance(ParameterSnapshot::LEN);
+        let new_parameter_snapshot_account =
+            || Account::new(0, ParameterSnapshot::LEN, &system_program::id());
 
-        // wrong nonce for authority_key
+        // unauthorized account
         {
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
-            );
-            accounts.authority_key = bad_authority_key;
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.pause()
+                Err(SwapError::Unauthorized.into()),
+                accounts.snapshot_state(&mut new_parameter_snapshot_account(), snapshot_ts)
             );
-            accounts.authority_key = old_authority;
+            accounts.admin_key = old_admin_key;
         }
 
+        let mut parameter_snapshot_account = new_parameter_snapshot_account();
+        accounts
+            .snapshot_state(&mut parameter_snapshot_account, snapshot_ts)
+            .unwrap();
+        assert_eq!(parameter_snapshot_account.owner, SWAP_PROGRAM_ID);
+        let snapshot = ParameterSnapshot::unpack(&parameter_snapshot_account.data).unwrap();
+        assert_eq!(snapshot.swap, accounts.swap_key);
+        assert_eq!(snapshot.snapshot_ts, snapshot_ts);
+        assert_eq!(snapshot.fees, DEFAULT_TEST_FEES);
+
+        let new_fees = Fees {
+            trade_fee_numerator: DEFAULT_TEST_FEES.trade_fee_numerator + 1,
+            ..DEFAULT_TEST_FEES
+        };
+        accounts.set_new_fees(new_fees, 0).unwrap();
+        accounts.apply_new_fees(NEW_FEES_DELAY_SLOTS).unwrap();
+        let new_amp = 5;
+        accounts
+            .ramp_a(new_amp, MIN_RAMP_DURATION, 2 * MIN_RAMP_DURATION)
+            .unwrap();
+        let restorable_ts = snapshot_ts + SNAPSHOT_RESTORE_DELAY;
+
         // unauthorized account
         {
             let old_admin_key = accounts.admin_key;
-            let fake_admin_key = pubkey_rand();
-            accounts.admin_key = fake_admin_key;
-            assert_eq!(Err(SwapError::Unauthorized.into()), accounts.pause());
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.restore_state(&mut parameter_snapshot_account, restorable_ts)
+            );
             accounts.admin_key = old_admin_key;
         }
 
-        // valid call
+        // snapshot not owned by the program
         {
-            accounts.pause().unwrap();
+            let mut fake_parameter_snapshot_account = parameter_snapshot_account.clone();
+            fake_parameter_snapshot_account.owner = pubkey_rand();
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts.restore_state(&mut fake_parameter_snapshot_account, restorable_ts)
+            );
+        }
 
+        // restore delay not elapsed
+        {
+            assert_eq!(
+                Err(SwapError::SnapshotRestoreLocked.into()),
+                accounts.restore_state(&mut parameter_snapshot_account, restorable_ts - 1)
+            );
             let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert!(swap_info.is_paused);
+            assert_eq!(swap_info.fees, new_fees);
+        }
+
+        // invalid snapshot fees
+        {
+            let mut invalid_parameter_snapshot_account = parameter_snapshot_account.clone();
+            let mut invalid_snapshot =
+                ParameterSnapshot::unpack(&invalid_parameter_snapshot_account.data).unwrap();
+            invalid_snapshot.fees.trade_fee_numerator = 2;
+            invalid_snapshot.fees.trade_fee_denominator = 1;
+            ParameterSnapshot::pack(
+                invalid_snapshot,
+                &mut invalid_parameter_snapshot_account.data,
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidFees.into()),
+                accounts.restore_state(
+                    &mut invalid_parameter_snapshot_account,
+                    2 * MIN_RAMP_DURATION
+                )
+            );
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(swap_info.pending_fees_slot, 0);
         }
+
+        // the amp ramp started after the snapshot is still locked
+        assert_eq!(
+            Err(SwapError::RampLocked.into()),
+            accounts.restore_state(&mut parameter_snapshot_account, restorable_ts)
+        );
+
+        let restore_ts = 2 * MIN_RAMP_DURATION;
+        accounts
+            .restore_state(&mut parameter_snapshot_account, restore_ts)
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        // the fees are staged, the amp ramps back from its current value
+        assert_eq!(swap_info.fees, new_fees);
+        assert_eq!(swap_info.pending_fees, DEFAULT_TEST_FEES);
+        assert_eq!(swap_info.pending_fees_slot, NEW_FEES_DELAY_SLOTS);
+        assert_eq!(swap_info.initial_amp_factor, new_amp);
+        assert_eq!(swap_info.target_amp_factor, MIN_AMP);
+        assert_eq!(swap_info.start_ramp_ts, restore_ts);
+        assert_eq!(swap_info.stop_ramp_ts, restore_ts + MIN_RAMP_DURATION);
+        assert_eq!(
+            Err(SwapError::PendingFeesNotDue.into()),
+            accounts.apply_new_fees(NEW_FEES_DELAY_SLOTS - 1)
+        );
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.fees, new_fees);
+        accounts.apply_new_fees(NEW_FEES_DELAY_SLOTS).unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.fees, DEFAULT_TEST_FEES);
+
+        // a new snapshot replaces the previous one and restarts the delay
+        accounts
+            .snapshot_state(&mut parameter_snapshot_account, restore_ts)
+            .unwrap();
+        assert_eq!(
+            Err(SwapError::SnapshotRestoreLocked.into()),
+            accounts.restore_state(&mut parameter_snapshot_account, restore_ts)
+        );
     }
 
     #[test]
-    fn test_unpause() {
+    fn test_add_and_remove_fee_exemption() {
         let user_key = pubkey_rand();
+        let trader_key = pubkey_rand();
         let mut accounts = SwapAccountInfo::new(
             &user_key,
             MIN_AMP,
@@ -600,380 +6107,680 @@ mod tests {
             DEFAULT_TOKEN_B_AMOUNT,
             DEFAULT_TEST_FEES,
         );
+        accounts.initialize_swap().unwrap();
+        accounts.admin_account.lamports = Rent::default().minimum_balance(FeeExemptions::LEN);
+        let mut fee_exemptions_account = Account::new(0, FeeExemptions::LEN, &system_program::id());
 
-        // swap not initialized
+        // unauthorized account
         {
-            assert_eq!(Err(ProgramError::UninitializedAccount), accounts.unpause());
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.add_fee_exemption(&mut fee_exemptions_account.clone(), &trader_key)
+            );
+            accounts.admin_key = old_admin_key;
         }
 
-        accounts.initialize_swap().unwrap();
+        accounts
+            .add_fee_exemption(&mut fee_exemptions_account, &trader_key)
+            .unwrap();
+        let fee_exemptions = FeeExemptions::unpack(&fee_exemptions_account.data).unwrap();
+        assert_eq!(fee_exemptions.swap, accounts.swap_key);
+        assert!(fee_exemptions.is_exempt(&trader_key));
 
-        // wrong nonce for authority_key
+        // exemptions not owned by the program
         {
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
+            let mut fake_fee_exemptions_account = fee_exemptions_account.clone();
+            fake_fee_exemptions_account.owner = pubkey_rand();
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts.add_fee_exemption(&mut fake_fee_exemptions_account, &pubkey_rand())
             );
-            accounts.authority_key = bad_authority_key;
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.unpause()
+                Err(ProgramError::IncorrectProgramId),
+                accounts.remove_fee_exemption(&mut fake_fee_exemptions_account, &trader_key)
+            );
+        }
+
+        // list full
+        {
+            for _ in 1..MAX_FEE_EXEMPTIONS {
+                accounts
+                    .add_fee_exemption(&mut fee_exemptions_account, &pubkey_rand())
+                    .unwrap();
+            }
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.add_fee_exemption(&mut fee_exemptions_account, &pubkey_rand())
             );
-            accounts.authority_key = old_authority;
         }
 
         // unauthorized account
         {
             let old_admin_key = accounts.admin_key;
-            let fake_admin_key = pubkey_rand();
-            accounts.admin_key = fake_admin_key;
-            assert_eq!(Err(SwapError::Unauthorized.into()), accounts.unpause());
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.remove_fee_exemption(&mut fee_exemptions_account, &trader_key)
+            );
             accounts.admin_key = old_admin_key;
         }
 
-        // valid call
-        {
-            // Pause swap pool
-            accounts.pause().unwrap();
-            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert!(swap_info.is_paused);
+        accounts
+            .remove_fee_exemption(&mut fee_exemptions_account, &trader_key)
+            .unwrap();
+        let fee_exemptions = FeeExemptions::unpack(&fee_exemptions_account.data).unwrap();
+        assert!(!fee_exemptions.is_exempt(&trader_key));
 
-            // Unpause swap pool
-            accounts.unpause().unwrap();
-            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert!(!swap_info.is_paused);
+        // trader not exempt
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.remove_fee_exemption(&mut fee_exemptions_account, &trader_key)
+            );
         }
     }
 
     #[test]
-    fn test_set_fee_account() {
+    fn test_add_and_remove_allowed_depositor() {
         let user_key = pubkey_rand();
-        let owner_key = pubkey_rand();
-        let amp_factor = MIN_AMP * 100;
+        let depositor_key = pubkey_rand();
         let mut accounts = SwapAccountInfo::new(
             &user_key,
-            amp_factor,
+            MIN_AMP,
             DEFAULT_TOKEN_A_AMOUNT,
             DEFAULT_TOKEN_B_AMOUNT,
             DEFAULT_TEST_FEES,
         );
-        let (
-            admin_fee_key_a,
-            admin_fee_account_a,
-            admin_fee_key_b,
-            admin_fee_account_b,
-            wrong_admin_fee_key,
-            wrong_admin_fee_account,
-        ) = accounts.setup_token_accounts(
-            &user_key,
-            &owner_key,
-            DEFAULT_TOKEN_A_AMOUNT,
-            DEFAULT_TOKEN_B_AMOUNT,
-            DEFAULT_POOL_TOKEN_AMOUNT,
-        );
+        accounts.initialize_swap().unwrap();
+        accounts.admin_account.lamports = Rent::default().minimum_balance(DepositAllowlist::LEN);
+        let mut deposit_allowlist_account =
+            Account::new(0, DepositAllowlist::LEN, &system_program::id());
 
-        // swap not initialized
+        // unauthorized account
         {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
             assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.set_admin_fee_account(&admin_fee_key_a, &admin_fee_account_a)
+                Err(SwapError::Unauthorized.into()),
+                accounts
+                    .add_allowed_depositor(&mut deposit_allowlist_account.clone(), &depositor_key)
             );
+            accounts.admin_key = old_admin_key;
         }
 
-        accounts.initialize_swap().unwrap();
+        accounts
+            .add_allowed_depositor(&mut deposit_allowlist_account, &depositor_key)
+            .unwrap();
+        let deposit_allowlist = DepositAllowlist::unpack(&deposit_allowlist_account.data).unwrap();
+        assert_eq!(deposit_allowlist.swap, accounts.swap_key);
+        assert!(deposit_allowlist.is_allowed(&depositor_key));
 
-        // wrong nonce for authority_key
+        // allowlist not owned by the program
         {
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
+            let mut fake_deposit_allowlist_account = deposit_allowlist_account.clone();
+            fake_deposit_allowlist_account.owner = pubkey_rand();
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts.add_allowed_depositor(&mut fake_deposit_allowlist_account, &pubkey_rand())
+            );
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                accounts
+                    .remove_allowed_depositor(&mut fake_deposit_allowlist_account, &depositor_key)
             );
-            accounts.authority_key = bad_authority_key;
+        }
+
+        // list full
+        {
+            for _ in 1..MAX_ALLOWED_DEPOSITORS {
+                accounts
+                    .add_allowed_depositor(&mut deposit_allowlist_account, &pubkey_rand())
+                    .unwrap();
+            }
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.set_admin_fee_account(&admin_fee_key_a, &admin_fee_account_a)
+                Err(SwapError::InvalidInput.into()),
+                accounts.add_allowed_depositor(&mut deposit_allowlist_account, &pubkey_rand())
             );
-            accounts.authority_key = old_authority;
         }
 
         // unauthorized account
         {
             let old_admin_key = accounts.admin_key;
-            let fake_admin_key = pubkey_rand();
-            accounts.admin_key = fake_admin_key;
+            accounts.admin_key = pubkey_rand();
             assert_eq!(
                 Err(SwapError::Unauthorized.into()),
-                accounts.set_admin_fee_account(&admin_fee_key_a, &admin_fee_account_a)
+                accounts.remove_allowed_depositor(&mut deposit_allowlist_account, &depositor_key)
             );
             accounts.admin_key = old_admin_key;
         }
 
-        // wrong admin account
+        accounts
+            .remove_allowed_depositor(&mut deposit_allowlist_account, &depositor_key)
+            .unwrap();
+        let deposit_allowlist = DepositAllowlist::unpack(&deposit_allowlist_account.data).unwrap();
+        assert!(!deposit_allowlist.is_allowed(&depositor_key));
+
+        // depositor not allowed
         {
             assert_eq!(
-                Err(SwapError::InvalidAdmin.into()),
-                accounts.set_admin_fee_account(&wrong_admin_fee_key, &wrong_admin_fee_account)
+                Err(SwapError::InvalidInput.into()),
+                accounts.remove_allowed_depositor(&mut deposit_allowlist_account, &depositor_key)
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_deposit_allowlist() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert!(!swap_info.deposit_allowlist_enabled);
+
+        // unauthorized account
+        {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_deposit_allowlist(true)
             );
+            accounts.admin_key = old_admin_key;
         }
 
         // valid calls
         {
-            // set fee account a
-            accounts
-                .set_admin_fee_account(&admin_fee_key_a, &admin_fee_account_a)
-                .unwrap();
+            accounts.set_deposit_allowlist(true).unwrap();
             let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert_eq!(swap_info.admin_fee_key_a, admin_fee_key_a);
-            // set fee acount b
-            accounts
-                .set_admin_fee_account(&admin_fee_key_b, &admin_fee_account_b)
-                .unwrap();
+            assert!(swap_info.deposit_allowlist_enabled);
+
+            accounts.set_deposit_allowlist(false).unwrap();
             let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert_eq!(swap_info.admin_fee_key_b, admin_fee_key_b);
+            assert!(!swap_info.deposit_allowlist_enabled);
         }
     }
 
     #[test]
-    fn test_apply_new_admin() {
+    fn test_set_oracle_guard() {
         let user_key = pubkey_rand();
-        let amp_factor = MIN_AMP * 100;
         let mut accounts = SwapAccountInfo::new(
             &user_key,
-            amp_factor,
+            MIN_AMP,
             DEFAULT_TOKEN_A_AMOUNT,
             DEFAULT_TOKEN_B_AMOUNT,
             DEFAULT_TEST_FEES,
         );
+        accounts.initialize_swap().unwrap();
+        let oracle_a_key = pubkey_rand();
+        let oracle_b_key = pubkey_rand();
+        accounts.oracles = Some([
+            (oracle_a_key, pyth_price_account(100_000_000, 10_000, -8, 0)),
+            (oracle_b_key, pyth_price_account(1_000_000, 100, -6, 0)),
+        ]);
 
-        // swap not initialized
+        // unauthorized account
         {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
             assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.apply_new_admin(ZERO_TS)
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_oracle_guard(25, 50, 100)
             );
+            accounts.admin_key = old_admin_key;
         }
 
-        accounts.initialize_swap().unwrap();
+        // invalid ratios
+        for (max_confidence_bps, max_deviation_bps) in [(0, 100), (10_001, 100), (50, 10_001)] {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_oracle_guard(25, max_confidence_bps, max_deviation_bps)
+            );
+        }
 
-        // wrong nonce for authority_key
+        // not a Pyth price account
         {
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
-            );
-            accounts.authority_key = bad_authority_key;
+            let oracles = accounts.oracles.clone();
+            accounts.oracles.as_mut().unwrap()[1].1 = Account::default();
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.apply_new_admin(ZERO_TS)
+                Err(SwapError::InvalidOracle.into()),
+                accounts.set_oracle_guard(25, 50, 100)
             );
-            accounts.authority_key = old_authority;
+            accounts.oracles = oracles;
+        }
+
+        // valid call
+        {
+            accounts.set_oracle_guard(25, 50, 100).unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(swap_info.is_oracle_guarded());
+            assert_eq!(swap_info.oracle_a, oracle_a_key);
+            assert_eq!(swap_info.oracle_b, oracle_b_key);
+            assert_eq!(swap_info.oracle_max_staleness_slots, 25);
+            assert_eq!(swap_info.oracle_max_confidence_bps, 50);
+            assert_eq!(swap_info.oracle_max_deviation_bps, 100);
+        }
+
+        // disabled by the default pubkey
+        {
+            let oracles = accounts.oracles.take();
+            accounts.set_oracle_guard(25, 50, 100).unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(!swap_info.is_oracle_guarded());
+            assert_eq!(swap_info.oracle_b, Pubkey::default());
+            assert_eq!(swap_info.oracle_max_deviation_bps, 0);
+            accounts.oracles = oracles;
         }
+    }
+
+    #[test]
+    fn test_set_oracle_stale_policy() {
+        let user_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            DEFAULT_TOKEN_A_AMOUNT,
+            DEFAULT_TOKEN_B_AMOUNT,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.oracle_stale_policy, OracleStalePolicy::Reject);
 
         // unauthorized account
         {
             let old_admin_key = accounts.admin_key;
-            let fake_admin_key = pubkey_rand();
-            accounts.admin_key = fake_admin_key;
+            accounts.admin_key = pubkey_rand();
             assert_eq!(
                 Err(SwapError::Unauthorized.into()),
-                accounts.apply_new_admin(ZERO_TS)
+                accounts.set_oracle_stale_policy(OracleStalePolicy::Ignore, 0)
             );
             accounts.admin_key = old_admin_key;
         }
 
-        // no active transfer
-        {
-            assert_eq!(
-                Err(SwapError::NoActiveTransfer.into()),
-                accounts.apply_new_admin(ZERO_TS)
-            );
-        }
+        // band wider than the price
+        assert_eq!(
+            Err(SwapError::InvalidInput.into()),
+            accounts.set_oracle_stale_policy(OracleStalePolicy::WidenBand, 10_001)
+        );
 
-        // apply new admin
+        // valid call
         {
-            let new_admin_key = pubkey_rand();
-            let current_ts = MIN_RAMP_DURATION;
-
-            // Commit to initiate admin transfer
             accounts
-                .commit_new_admin(&new_admin_key, current_ts)
+                .set_oracle_stale_policy(OracleStalePolicy::WidenBand, 200)
                 .unwrap();
-
-            // Applying transfer past deadline should fail
-            let apply_deadline = current_ts + MIN_RAMP_DURATION * 3;
-            assert_eq!(
-                Err(SwapError::AdminDeadlineExceeded.into()),
-                accounts.apply_new_admin(apply_deadline + 1)
-            );
-
-            // Apply to finalize admin transfer
-            accounts.apply_new_admin(current_ts + 1).unwrap();
             let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert_eq!(swap_info.admin_key, new_admin_key);
-            assert_eq!(swap_info.future_admin_key, Pubkey::default());
-            assert_eq!(swap_info.future_admin_deadline, ZERO_TS);
+            assert_eq!(swap_info.oracle_stale_policy, OracleStalePolicy::WidenBand);
+            assert_eq!(swap_info.oracle_stale_band_bps, 200);
+            assert_eq!(swap_info.oracle_guard().stale_band_bps, 200);
         }
     }
 
     #[test]
-    fn test_commit_new_admin() {
+    fn test_set_governance_realm() {
         let user_key = pubkey_rand();
-        let new_admin_key = pubkey_rand();
-        let current_ts = ZERO_TS;
-        let amp_factor = MIN_AMP * 100;
+        let realm_key = pubkey_rand();
+        let governance_lockup = 100;
         let mut accounts = SwapAccountInfo::new(
             &user_key,
-            amp_factor,
+            MIN_AMP,
             DEFAULT_TOKEN_A_AMOUNT,
             DEFAULT_TOKEN_B_AMOUNT,
             DEFAULT_TEST_FEES,
         );
+        accounts.initialize_swap().unwrap();
+        let authority_key = accounts.authority_key;
+        let (governance_vault_key, mut governance_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
 
-        // swap not initialized
+        // unauthorized account
         {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
             assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.commit_new_admin(&new_admin_key, current_ts)
+                Err(SwapError::Unauthorized.into()),
+                accounts.set_governance_realm(
+                    &realm_key,
+                    &governance_vault_key,
+                    &mut governance_vault_account,
+                    governance_lockup,
+                )
             );
+            accounts.admin_key = old_admin_key;
         }
 
-        accounts.initialize_swap().unwrap();
-
-        // wrong nonce for authority_key
+        // vault not holding pool tokens
         {
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
+            let token_a_key = accounts.token_a_key;
+            let mut token_a_account = accounts.token_a_account.clone();
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.set_governance_realm(
+                    &realm_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    governance_lockup,
+                )
             );
-            accounts.authority_key = bad_authority_key;
+        }
+
+        // vault not owned by the swap authority
+        {
+            let pool_token_key = accounts.pool_token_key;
+            let mut pool_token_account = accounts.pool_token_account.clone();
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.commit_new_admin(&new_admin_key, current_ts)
+                Err(SwapError::InvalidOwner.into()),
+                accounts.set_governance_realm(
+                    &realm_key,
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    governance_lockup,
+                )
             );
-            accounts.authority_key = old_authority;
         }
 
-        // unauthorized account
+        // negative lockup
         {
-            let old_admin_key = accounts.admin_key;
-            let fake_admin_key = pubkey_rand();
-            accounts.admin_key = fake_admin_key;
             assert_eq!(
-                Err(SwapError::Unauthorized.into()),
-                accounts.commit_new_admin(&new_admin_key, current_ts)
+                Err(SwapError::InvalidInput.into()),
+                accounts.set_governance_realm(
+                    &realm_key,
+                    &governance_vault_key,
+                    &mut governance_vault_account,
+                    -1,
+                )
             );
-            accounts.admin_key = old_admin_key;
         }
 
-        // commit new admin
+        // valid call
         {
-            // valid call
             accounts
-                .commit_new_admin(&new_admin_key, current_ts)
+                .set_governance_realm(
+                    &realm_key,
+                    &governance_vault_key,
+                    &mut governance_vault_account,
+                    governance_lockup,
+                )
                 .unwrap();
 
             let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert_eq!(swap_info.future_admin_key, new_admin_key);
-            let expected_future_ts = current_ts + MIN_RAMP_DURATION * 3;
-            assert_eq!(swap_info.future_admin_deadline, expected_future_ts);
+            assert_eq!(swap_info.governance_realm, realm_key);
+            assert_eq!(swap_info.governance_vault, governance_vault_key);
+            assert_eq!(swap_info.governance_lockup, governance_lockup);
+        }
 
-            // new commit within deadline should fail
+        // realm can't be changed
+        {
             assert_eq!(
-                Err(SwapError::ActiveTransfer.into()),
-                accounts.commit_new_admin(&new_admin_key, current_ts + 1),
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.set_governance_realm(
+                    &pubkey_rand(),
+                    &governance_vault_key,
+                    &mut governance_vault_account,
+                    governance_lockup,
+                )
             );
-
-            // new commit after deadline should be valid
-            let new_admin_key = pubkey_rand();
-            let current_ts = expected_future_ts + 1;
-            accounts
-                .commit_new_admin(&new_admin_key, current_ts)
-                .unwrap();
-            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert_eq!(swap_info.future_admin_key, new_admin_key);
-            let expected_future_ts = current_ts + MIN_RAMP_DURATION * 3;
-            assert_eq!(swap_info.future_admin_deadline, expected_future_ts);
         }
     }
 
+    fn process_with_admin_session(
+        instruction: Instruction,
+        accounts: &mut SwapAccountInfo,
+        admin_session_key: &Pubkey,
+        admin_session_account: &mut Account,
+        slot: Slot,
+    ) -> ProgramResult {
+        do_process_instruction(
+            with_admin_session(instruction, admin_session_key),
+            vec![
+                &mut accounts.swap_account,
+                &mut Account::default(),
+                &mut Account::default(),
+                admin_session_account,
+                &mut clock_account_at_slot(ZERO_TS, slot),
+            ],
+        )
+    }
+
     #[test]
-    fn test_set_new_fees() {
+    fn test_admin_session() {
         let user_key = pubkey_rand();
-        let amp_factor = MIN_AMP * 100;
+        let session_key = pubkey_rand();
         let mut accounts = SwapAccountInfo::new(
             &user_key,
-            amp_factor,
+            MIN_AMP,
             DEFAULT_TOKEN_A_AMOUNT,
             DEFAULT_TOKEN_B_AMOUNT,
             DEFAULT_TEST_FEES,
         );
+        accounts.initialize_swap().unwrap();
+        let session_lamports = Rent::default().minimum_balance(AdminSession::LEN);
+        accounts.admin_account.lamports = session_lamports;
 
-        let new_fees: Fees = Fees {
-            admin_trade_fee_numerator: 0,
-            admin_trade_fee_denominator: 0,
-            admin_withdraw_fee_numerator: 0,
-            admin_withdraw_fee_denominator: 0,
-            trade_fee_numerator: 0,
-            trade_fee_denominator: 0,
-            withdraw_fee_numerator: 0,
-            withdraw_fee_denominator: 0,
-            reflection_fee_numerator: 0,
-            reflection_fee_denominator: 0,
-            buyback_fee_numerator: 0,
-            buyback_fee_denominator: 0,
-            marketing_fee_numerator: 0,
-            marketing_fee_denominator: 0,
-            developer_fee_numerator: 0,
-            developer_fee_denominator: 0,
+        let (admin_session_key, _nonce) =
+            utils::find_admin_session_address(&SWAP_PROGRAM_ID, &accounts.swap_key, &session_key);
+        let new_admin_session_account =
+            || Account::new(0, AdminSession::LEN, &system_program::id());
+        let expiry_slot = 100;
+        let session_pause = |accounts: &SwapAccountInfo| {
+            pause(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &accounts.authority_key,
+                &session_key,
+            )
+            .unwrap()
         };
 
-        // swap not initialized
+        // unauthorized account
         {
+            let old_admin_key = accounts.admin_key;
+            accounts.admin_key = pubkey_rand();
             assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.set_new_fees(new_fees)
+                Err(SwapError::Unauthorized.into()),
+                accounts.create_admin_session(
+                    &session_key,
+                    &mut new_admin_session_account(),
+                    AdminSession::PERMISSION_PAUSE,
+                    expiry_slot,
+                    0,
+                )
             );
+            accounts.admin_key = old_admin_key;
         }
 
-        accounts.initialize_swap().unwrap();
+        // no or unknown permissions
+        for permissions in [0, 1 << 7] {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.create_admin_session(
+                    &session_key,
+                    &mut new_admin_session_account(),
+                    permissions,
+                    expiry_slot,
+                    0,
+                )
+            );
+        }
 
-        // wrong nonce for authority_key
+        // already expired
         {
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.create_admin_session(
+                    &session_key,
+                    &mut new_admin_session_account(),
+                    AdminSession::PERMISSION_PAUSE,
+                    expiry_slot,
+                    expiry_slot + 1,
+                )
             );
-            accounts.authority_key = bad_authority_key;
+        }
+
+        // valid session
+        let mut admin_session_account = new_admin_session_account();
+        accounts
+            .create_admin_session(
+                &session_key,
+                &mut admin_session_account,
+                AdminSession::PERMISSION_PAUSE,
+                expiry_slot,
+                0,
+            )
+            .unwrap();
+        assert_eq!(admin_session_account.owner, SWAP_PROGRAM_ID);
+        assert_eq!(admin_session_account.lamports, session_lamports);
+        assert_eq!(accounts.admin_account.lamports, 0);
+        assert_eq!(
+            AdminSession::unpack(&admin_session_account.data).unwrap(),
+            AdminSession {
+                is_initialized: true,
+                swap: accounts.swap_key,
+                admin: accounts.admin_key,
+                session_key,
+                permissions: AdminSession::PERMISSION_PAUSE,
+                expiry_slot,
+            }
+        );
+
+        // session key not signing
+        {
+            let mut instruction = session_pause(&accounts);
+            instruction.accounts[2].is_signer = false;
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.set_new_fees(new_fees)
+                Err(ProgramError::MissingRequiredSignature),
+                process_with_admin_session(
+                    instruction,
+                    &mut accounts,
+                    &admin_session_key,
+                    &mut admin_session_account,
+                    0,
+                )
             );
-            accounts.authority_key = old_authority;
         }
 
-        // unauthorized account
+        // instruction not granted to the session
         {
-            let old_admin_key = accounts.admin_key;
-            let fake_admin_key = pubkey_rand();
-            accounts.admin_key = fake_admin_key;
+            let instruction = set_new_fees(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &accounts.authority_key,
+                &session_key,
+                DEFAULT_TEST_FEES,
+            )
+            .unwrap();
             assert_eq!(
                 Err(SwapError::Unauthorized.into()),
-                accounts.set_new_fees(new_fees)
+                do_process_instruction(
+                    with_admin_session(instruction, &admin_session_key),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                        &mut clock_account_at_slot(ZERO_TS, 0),
+                        &mut admin_session_account,
+                        &mut clock_account_at_slot(ZERO_TS, 0),
+                    ],
+                )
             );
-            accounts.admin_key = old_admin_key;
         }
 
-        // valid call
+        // session expired
         {
-            accounts.set_new_fees(new_fees).unwrap();
+            assert_eq!(
+                Err(SwapError::AdminSessionExpired.into()),
+                process_with_admin_session(
+                    session_pause(&accounts),
+                    &mut accounts,
+                    &admin_session_key,
+                    &mut admin_session_account,
+                    expiry_slot + 1,
+                )
+            );
+        }
 
+        // pause and unpause through the session
+        {
+            process_with_admin_session(
+                session_pause(&accounts),
+                &mut accounts,
+                &admin_session_key,
+                &mut admin_session_account,
+                expiry_slot,
+            )
+            .unwrap();
             let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-            assert_eq!(swap_info.fees, new_fees);
+            assert!(swap_info.is_paused);
+
+            let instruction = unpause(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &accounts.authority_key,
+                &session_key,
+            )
+            .unwrap();
+            process_with_admin_session(
+                instruction,
+                &mut accounts,
+                &admin_session_key,
+                &mut admin_session_account,
+                expiry_slot,
+            )
+            .unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(!swap_info.is_paused);
+        }
+
+        // session void after an admin change
+        {
+            let mut swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            let old_admin_key = swap_info.admin_key;
+            swap_info.admin_key = pubkey_rand();
+            SwapInfo::pack(swap_info, &mut accounts.swap_account.data).unwrap();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                process_with_admin_session(
+                    session_pause(&accounts),
+                    &mut accounts,
+                    &admin_session_key,
+                    &mut admin_session_account,
+                    0,
+                )
+            );
+            swap_info.admin_key = old_admin_key;
+            SwapInfo::pack(swap_info, &mut accounts.swap_account.data).unwrap();
+        }
+
+        // revoke
+        {
+            accounts
+                .revoke_admin_session(&admin_session_key, &mut admin_session_account)
+                .unwrap();
+            assert_eq!(admin_session_account.lamports, 0);
+            assert_eq!(accounts.admin_account.lamports, session_lamports);
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                process_with_admin_session(
+                    session_pause(&accounts),
+                    &mut accounts,
+                    &admin_session_key,
+                    &mut admin_session_account,
+                    0,
+                )
+            );
         }
     }
 }
@@ -1,25 +1,22 @@
 //! Big number types
 
-#![allow(clippy::assign_op_pattern)]
-#![allow(clippy::ptr_offset_with_cast)]
-#![allow(clippy::manual_range_contains)]
-
 use crate::error::SwapError;
-use std::convert::TryInto;
-use uint::construct_uint;
+pub use baby_punk_swap_math::bn::U256;
 
-construct_uint! {
-    /// 256-bit unsigned integer.
-    pub struct U256(4);
+/// Narrowing conversions of a `U256`, failing with `SwapError::ConversionFailure`
+pub trait U256Conversions {
+    /// Convert u256 to u64
+    fn to_u64(val: U256) -> Result<u64, SwapError>;
+    /// Convert u256 to u128
+    fn to_u128(val: U256) -> Result<u128, SwapError>;
 }
 
-impl U256 {
-    /// Convert u256 to u64
-    pub fn to_u64(val: U256) -> Result<u64, SwapError> {
-        val.try_into().map_err(|_| SwapError::ConversionFailure)
+impl U256Conversions for U256 {
+    fn to_u64(val: U256) -> Result<u64, SwapError> {
+        val.try_into_u64().map_err(|_| SwapError::ConversionFailure)
     }
-    /// Convert u256 to u128
-    pub fn to_u128(val: U256) -> Result<u128, SwapError> {
-        val.try_into().map_err(|_| SwapError::ConversionFailure)
+    fn to_u128(val: U256) -> Result<u128, SwapError> {
+        val.try_into_u128()
+            .map_err(|_| SwapError::ConversionFailure)
     }
 }
@@ -0,0 +1,251 @@
+//! Declarative account constraints of instruction processors
+//!
+//! `accounts!` takes the next accounts of an instruction, in order, and checks
+//! the constraints listed after each of them. `constrain!` checks constraints of
+//! accounts already taken, for expected values only known once the swap is
+//! unpacked. Constraints are checked in the order they are listed and fail with:
+//!
+//! - `signer`: `SwapError::Unauthorized`
+//! - `writable`: `ProgramError::InvalidArgument`
+//! - `owned(program_id)`: `ProgramError::IncorrectProgramId`
+//! - `key(expected)`: `SwapError::IncorrectSwapAccount`
+//! - `authority(program_id, swap_key, nonce)`: `SwapError::InvalidProgramAddress`
+//! - `token(mint = expected, owner = expected)`: `SwapError::ExpectedAccount` if
+//!   the account is not a token account, `SwapError::IncorrectMint` or
+//!   `SwapError::InvalidOwner`. Either field may be left out.
+
+/// Checks a single constraint of the account `$info`
+macro_rules! account_constraint {
+    (@token $account:ident, mint, $expected:expr) => {
+        if $account.mint != $expected {
+            return Err($crate::error::SwapError::IncorrectMint.into());
+        }
+    };
+    (@token $account:ident, owner, $expected:expr) => {
+        if $account.owner != $expected {
+            return Err($crate::error::SwapError::InvalidOwner.into());
+        }
+    };
+    ($info:ident, signer) => {
+        if !$info.is_signer {
+            return Err($crate::error::SwapError::Unauthorized.into());
+        }
+    };
+    ($info:ident, writable) => {
+        if !$info.is_writable {
+            return Err(solana_program::program_error::ProgramError::InvalidArgument);
+        }
+    };
+    ($info:ident, owned($program_id:expr)) => {
+        if $info.owner != $program_id {
+            return Err(solana_program::program_error::ProgramError::IncorrectProgramId);
+        }
+    };
+    ($info:ident, key($expected:expr)) => {
+        if *$info.key != $expected {
+            return Err($crate::error::SwapError::IncorrectSwapAccount.into());
+        }
+    };
+    ($info:ident, authority($program_id:expr, $swap_key:expr, $nonce:expr)) => {
+        if *$info.key != $crate::utils::authority_id($program_id, $swap_key, $nonce)? {
+            return Err($crate::error::SwapError::InvalidProgramAddress.into());
+        }
+    };
+    ($info:ident, token($($field:ident = $expected:expr),+ $(,)?)) => {{
+        let token_account = $crate::utils::unpack_token_account(&$info.data.borrow())?;
+        $(account_constraint!(@token token_account, $field, $expected);)+
+    }};
+}
+
+/// Takes the next accounts of `$iter`, binding each to its name, and checks the
+/// constraints listed after it
+macro_rules! accounts {
+    ($iter:expr; $($name:ident $(: $($constraint:ident $(($($arg:tt)*))?)+)?),* $(,)?) => {
+        $(
+            let $name = solana_program::account_info::next_account_info($iter)?;
+            $($(account_constraint!($name, $constraint $(($($arg)*))?);)+)?
+        )*
+    };
+}
+
+/// Checks the constraints listed after accounts already taken
+macro_rules! constrain {
+    ($($name:ident: $($constraint:ident $(($($arg:tt)*))?)+),* $(,)?) => {
+        $(
+            $(account_constraint!($name, $constraint $(($($arg)*))?);)+
+        )*
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::SwapError, utils::authority_id};
+    use solana_program::{
+        account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+        program_pack::Pack, pubkey::Pubkey,
+    };
+    use spl_token::state::{Account, AccountState};
+
+    fn check(
+        program_id: &Pubkey,
+        token_key: Pubkey,
+        mint: Pubkey,
+        owner: Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: writable owned(program_id),
+            authority_info,
+            user_info: signer,
+            token_info: token(mint = mint, owner = owner),
+        }
+        let (_, nonce) = Pubkey::find_program_address(&[swap_info.key.as_ref()], program_id);
+        constrain! {
+            authority_info: authority(program_id, swap_info.key, nonce),
+            token_info: key(token_key) token(owner = owner),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_account_constraints() {
+        let program_id = Pubkey::new_unique();
+        let token_program_id = spl_token::id();
+        let swap_key = Pubkey::new_unique();
+        let user_key = Pubkey::new_unique();
+        let token_key = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (authority_key, nonce) =
+            Pubkey::find_program_address(&[swap_key.as_ref()], &program_id);
+        assert_eq!(
+            authority_id(&program_id, &swap_key, nonce),
+            Ok(authority_key)
+        );
+        let mut token_data = vec![0; Account::LEN];
+        Account {
+            mint,
+            owner: authority_key,
+            state: AccountState::Initialized,
+            ..Account::default()
+        }
+        .pack_into_slice(&mut token_data);
+
+        let mut lamports = [0; 4];
+        let mut swap_data = [];
+        let mut authority_data = [];
+        let mut user_data = [];
+        let [swap_lamports, authority_lamports, user_lamports, token_lamports] = &mut lamports;
+        let mut accounts = vec![
+            AccountInfo::new(
+                &swap_key,
+                false,
+                true,
+                swap_lamports,
+                &mut swap_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &authority_key,
+                false,
+                false,
+                authority_lamports,
+                &mut authority_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &user_key,
+                true,
+                false,
+                user_lamports,
+                &mut user_data,
+                &program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &token_key,
+                false,
+                true,
+                token_lamports,
+                &mut token_data,
+                &token_program_id,
+                false,
+                0,
+            ),
+        ];
+        assert_eq!(
+            check(&program_id, token_key, mint, authority_key, &accounts),
+            Ok(())
+        );
+
+        assert_eq!(
+            check(&program_id, token_key, mint, authority_key, &accounts[..3]),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(
+            check(
+                &program_id,
+                token_key,
+                Pubkey::new_unique(),
+                authority_key,
+                &accounts
+            ),
+            Err(SwapError::IncorrectMint.into())
+        );
+        assert_eq!(
+            check(&program_id, token_key, mint, user_key, &accounts),
+            Err(SwapError::InvalidOwner.into())
+        );
+        assert_eq!(
+            check(
+                &Pubkey::new_unique(),
+                token_key,
+                mint,
+                authority_key,
+                &accounts
+            ),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        accounts[2].is_signer = false;
+        assert_eq!(
+            check(&program_id, token_key, mint, authority_key, &accounts),
+            Err(SwapError::Unauthorized.into())
+        );
+        accounts[2].is_signer = true;
+
+        accounts[0].is_writable = false;
+        assert_eq!(
+            check(&program_id, token_key, mint, authority_key, &accounts),
+            Err(ProgramError::InvalidArgument)
+        );
+        accounts[0].is_writable = true;
+
+        let other_key = Pubkey::new_unique();
+        accounts[1].key = &other_key;
+        assert_eq!(
+            check(&program_id, token_key, mint, authority_key, &accounts),
+            Err(SwapError::InvalidProgramAddress.into())
+        );
+        accounts[1].key = &authority_key;
+
+        accounts[3].key = &other_key;
+        assert_eq!(
+            check(&program_id, token_key, mint, authority_key, &accounts),
+            Err(SwapError::IncorrectSwapAccount.into())
+        );
+        accounts[3].key = &token_key;
+
+        accounts[3].data = std::rc::Rc::new(std::cell::RefCell::new(&mut []));
+        assert_eq!(
+            check(&program_id, token_key, mint, authority_key, &accounts),
+            Err(SwapError::ExpectedAccount.into())
+        );
+    }
+}
@@ -2,7 +2,7 @@
 
 #![cfg(not(feature = "no-entrypoint"))]
 
-use crate::{error::SwapError, processor::Processor};
+use crate::{error::SwapError, logging::LOG_ERRORS, processor::Processor};
 use solana_program::{
     account_info::AccountInfo, entrypoint, entrypoint::ProgramResult,
     program_error::PrintProgramError, pubkey::Pubkey,
@@ -16,7 +16,9 @@ fn process_instruction<'a>(
 ) -> ProgramResult {
     if let Err(error) = Processor::process(program_id, accounts, instruction_data) {
         // catch the error so we can print it
-        error.print::<SwapError>();
+        if LOG_ERRORS {
+            error.print::<SwapError>();
+        }
         return Err(error);
     }
     Ok(())
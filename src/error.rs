@@ -64,7 +64,7 @@ pub enum SwapError {
     /// The provided token account has a close authority.
     #[error("Token account has a close authority")]
     InvalidCloseAuthority,
-    /// The pool token mint has a freeze authority.
+    /// The pool token mint has a freeze authority other than the swap authority.
     #[error("Pool token mint has a freeze authority")]
     InvalidFreezeAuthority,
     /// ConversionFailure
@@ -94,6 +94,145 @@ pub enum SwapError {
     /// Token mint decimals must be the same.
     #[error("Token mints must have same decimals")]
     MismatchedDecimals,
+    /// Swaps are restricted to access token holders during the launch window.
+    #[error("Access token required during the launch window")]
+    AccessTokenRequired,
+    /// The admin session is past its expiry slot.
+    #[error("Admin session expired")]
+    AdminSessionExpired,
+    /// The merkle proof does not match the distribution root.
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof,
+    /// The governance deposit lockup has not elapsed yet.
+    #[error("Governance deposit is locked")]
+    GovernanceDepositLocked,
+    /// The parameter snapshot restore delay has not elapsed yet.
+    #[error("Parameter snapshot is not restorable yet")]
+    SnapshotRestoreLocked,
+    /// The admin heartbeat has not timed out yet.
+    #[error("Admin heartbeat has not timed out")]
+    AdminHeartbeatActive,
+    /// The swap input exceeds the maximum trade size of the pool.
+    #[error("Swap amount exceeds the pool maximum, split the order into smaller swaps")]
+    SwapAmountTooLarge,
+    /// The pool tokens are soulbound and can't be transferred.
+    #[error("Pool tokens are non-transferable")]
+    LpSoulbound,
+    /// The vote-escrow lock has not expired yet.
+    #[error("Vote-escrow lock is active")]
+    VeLockActive,
+    /// The vote-escrow lock still votes for gauges.
+    #[error("Vote-escrow lock has gauge votes")]
+    GaugeVoteActive,
+    /// The bid doesn't exceed the best order-flow bid of the batch.
+    #[error("Order-flow bid too low")]
+    OrderFlowBidTooLow,
+    /// Bids are closed in the last slot of an order-flow auction batch.
+    #[error("Order-flow auction is closed for the batch")]
+    OrderFlowAuctionClosed,
+    /// The last slot of an order-flow auction batch is reserved to the best bidder.
+    #[error("Slot is reserved to the order-flow auction winner")]
+    OrderFlowSlotReserved,
+    /// The order-flow auction can't be changed while a bid is pending.
+    #[error("Order-flow bid is pending")]
+    OrderFlowBidPending,
+    /// The amplification coefficient can't be ramped while the dynamic amp sets it.
+    #[error("Dynamic amp is enabled")]
+    DynamicAmpEnabled,
+    /// A simulated swap always fails so that its changes are reverted.
+    #[error("Swap simulated, changes reverted")]
+    SwapSimulated,
+    /// The shortfall to cover exceeds the insurance fund.
+    #[error("Insufficient insurance fund")]
+    InsufficientInsuranceFund,
+    /// The deposit mints fewer pool tokens than the pool minimum, or none at all.
+    #[error("Deposit mint amount too small")]
+    MintAmountTooSmall,
+    /// The balance to sweep exceeds the dust threshold of the pool.
+    #[error("Balance exceeds the dust threshold")]
+    NotDust,
+    /// The dust sweeps of the slot are used up.
+    #[error("Dust sweeps of the slot exhausted")]
+    DustSweepLimit,
+    /// The pool sequence number is not the one the instruction expects.
+    #[error("Pool changed since the instruction was quoted")]
+    SequenceMismatch,
+    /// The withdrawal exceeds the custodian threshold without the custodian signature.
+    #[error("Withdrawal above the threshold requires the custodian signature")]
+    CustodianSignatureRequired,
+    /// The pool requires a credential the swapper or depositor did not present.
+    #[error("Credential of the pool issuer required")]
+    CredentialRequired,
+    /// The pool token accounts and mint don't all belong to the given token program.
+    #[error("Incorrect token program id")]
+    IncorrectTokenProgramId,
+    /// The TWAP order has no slice left, or its next slice is not due yet.
+    #[error("No TWAP order slice is due")]
+    TwapSliceNotDue,
+    /// The oracle price has not crossed the trigger price.
+    #[error("Trigger price not reached")]
+    TriggerNotReached,
+    /// The oracle pool TWAP was not observed for a whole TWAP window, or a Pyth price
+    /// of the oracle guard is not trading or older than the pool allows.
+    #[error("Oracle price is stale")]
+    OracleStale,
+    /// The drip deposit has no tranche left, or its next tranche is not due yet.
+    #[error("No drip deposit tranche is due")]
+    DripTrancheNotDue,
+    /// The admin withdrew more pool tokens than the protocol-owned liquidity.
+    #[error("Withdrawal exceeds the protocol-owned liquidity")]
+    InsufficientProtocolLiquidity,
+    /// No fees are staged by SetNewFees.
+    #[error("No pending fees")]
+    NoPendingFees,
+    /// The delay of the staged fees has not elapsed yet.
+    #[error("Pending fees not applicable yet")]
+    PendingFeesNotDue,
+    /// The fee schedule has a zero denominator, a fee above 100% or exceeds the
+    /// maximum trade fee.
+    #[error("Invalid fees")]
+    InvalidFees,
+    /// The swap output received by the destination differs from the computed amount.
+    #[error("Output amount mismatch")]
+    OutputAmountMismatch,
+    /// The instruction landed after its deadline slot.
+    #[error("Deadline exceeded")]
+    DeadlineExceeded,
+    /// Deposits are restricted to the pool creator during the first deposit window.
+    #[error("Only the pool creator may deposit during the first deposit window")]
+    CreatorDepositWindow,
+    /// The pool restricts its deposits to an allowlist the depositor is not on.
+    #[error("Depositor is not on the deposit allowlist of the pool")]
+    DepositorNotAllowed,
+    /// A token mint has a freeze authority the creator did not acknowledge at
+    /// initialization.
+    #[error("Token mint has a freeze authority that was not acknowledged")]
+    FreezeAuthorityNotAcknowledged,
+    /// A swap token account was frozen by the freeze authority of its mint.
+    #[error("Swap token account is frozen")]
+    VaultFrozen,
+    /// An oracle account is missing, is not the one of the pool or is not a Pyth
+    /// price account, or its price is not positive.
+    #[error("Invalid oracle account")]
+    InvalidOracle,
+    /// The confidence interval of an oracle price exceeds the ratio the pool allows.
+    #[error("Oracle confidence interval too wide")]
+    OracleConfidenceTooWide,
+    /// The swap pays out more than the oracle value of its input, past the band the
+    /// pool allows.
+    #[error("Swap price deviates from the oracle price")]
+    OraclePriceDeviation,
+    /// A user source account is owned by $authority, as only the vaults of the pool
+    /// are.
+    #[error("Source account is owned by the swap authority")]
+    AuthorityOwnedSource,
+    /// The referrer of a swap is owned by the owner of its SOURCE account.
+    #[error("Swap referred by its own user")]
+    SelfReferral,
+    /// A pool is created in the canonical namespace without the upgrade authority of
+    /// the program signing as its payer.
+    #[error("Only the upgrade authority creates pools in the canonical namespace")]
+    CanonicalNamespaceReserved,
 }
 impl From<SwapError> for ProgramError {
     fn from(e: SwapError) -> Self {
@@ -0,0 +1,387 @@
+//! Farms emitting a reward token to the stakers of the pool tokens of a swap.
+//!
+//! Account flow:
+//!
+//! 1. The admin of a swap creates the `Farm` of its pool mint with `InitializeFarm`,
+//!    passing a pool token vault and a reward vault held by the swap authority, and
+//!    sets the rewards emitted each slot. Anyone funds the farm by transferring the
+//!    reward token to the reward vault.
+//! 2. Pool token holders `Stake` pool tokens in their `StakeAccount`, created on the
+//!    first stake, and `Unstake` them at any time.
+//! 3. Each slot emits the rewards of the slot to the stakers, pro rata to their
+//!    stake, as long as the reward vault holds rewards not emitted yet. Nothing is
+//!    emitted while nothing is staked. Stakers `HarvestRewards` emitted to them.
+
+use crate::layout;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    clock::Slot,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+use std::convert::TryFrom;
+
+/// Scale of the rewards emitted per staked pool token
+pub const REWARD_PER_SHARE_SCALE: u128 = 1_000_000_000_000;
+
+/// Rewards owed to `amount` staked pool tokens at `acc_reward_per_share` rewards
+/// emitted per staked pool token
+pub fn farm_reward(amount: u64, acc_reward_per_share: u128) -> Option<u128> {
+    (amount as u128)
+        .checked_mul(acc_reward_per_share)?
+        .checked_div(REWARD_PER_SHARE_SCALE)
+}
+
+/// Reward emission to the stakers of the pool tokens of a swap
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Farm {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap whose pool tokens are staked
+    pub swap: Pubkey,
+    /// Pool mint of the swap
+    pub pool_mint: Pubkey,
+    /// Mint of the reward token
+    pub reward_mint: Pubkey,
+    /// Pool token vault holding the stakes, owned by the swap authority
+    pub stake_vault: Pubkey,
+    /// Reward token vault funding the emission, owned by the swap authority
+    pub reward_vault: Pubkey,
+    /// Rewards emitted each slot
+    pub emission_per_slot: u64,
+    /// Slot the emission was last accounted at
+    pub last_update_slot: Slot,
+    /// Rewards emitted per staked pool token so far, scaled by
+    /// `REWARD_PER_SHARE_SCALE`
+    pub acc_reward_per_share: u128,
+    /// Pool tokens staked
+    pub total_staked: u64,
+    /// Rewards emitted and not harvested yet, held in the reward vault
+    pub rewards_unharvested: u64,
+}
+
+impl Farm {
+    /// Accounts for the rewards emitted up to `slot`, out of the `reward_vault_amount`
+    /// rewards of the reward vault not emitted yet
+    pub fn update(&mut self, slot: Slot, reward_vault_amount: u64) -> Option<()> {
+        if slot <= self.last_update_slot {
+            return Some(());
+        }
+        let elapsed = slot - self.last_update_slot;
+        self.last_update_slot = slot;
+        if self.total_staked == 0 {
+            return Some(());
+        }
+        let available = reward_vault_amount.saturating_sub(self.rewards_unharvested);
+        let emission = (self.emission_per_slot as u128)
+            .saturating_mul(elapsed as u128)
+            .min(available as u128);
+        self.acc_reward_per_share = self.acc_reward_per_share.checked_add(
+            emission
+                .checked_mul(REWARD_PER_SHARE_SCALE)?
+                .checked_div(self.total_staked as u128)?,
+        )?;
+        self.rewards_unharvested = self
+            .rewards_unharvested
+            .checked_add(u64::try_from(emission).ok()?)?;
+        Some(())
+    }
+}
+
+impl Sealed for Farm {}
+impl IsInitialized for Farm {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Farm {
+    const LEN: usize = layout::farm::LEN;
+
+    /// Unpacks a byte buffer into a [Farm](struct.Farm.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 209];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            swap,
+            pool_mint,
+            reward_mint,
+            stake_vault,
+            reward_vault,
+            emission_per_slot,
+            last_update_slot,
+            acc_reward_per_share,
+            total_staked,
+            rewards_unharvested,
+        ) = array_refs![input, 1, 32, 32, 32, 32, 32, 8, 8, 16, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            pool_mint: Pubkey::new_from_array(*pool_mint),
+            reward_mint: Pubkey::new_from_array(*reward_mint),
+            stake_vault: Pubkey::new_from_array(*stake_vault),
+            reward_vault: Pubkey::new_from_array(*reward_vault),
+            emission_per_slot: u64::from_le_bytes(*emission_per_slot),
+            last_update_slot: u64::from_le_bytes(*last_update_slot),
+            acc_reward_per_share: u128::from_le_bytes(*acc_reward_per_share),
+            total_staked: u64::from_le_bytes(*total_staked),
+            rewards_unharvested: u64::from_le_bytes(*rewards_unharvested),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 209];
+        let (
+            is_initialized,
+            swap,
+            pool_mint,
+            reward_mint,
+            stake_vault,
+            reward_vault,
+            emission_per_slot,
+            last_update_slot,
+            acc_reward_per_share,
+            total_staked,
+            rewards_unharvested,
+        ) = mut_array_refs![output, 1, 32, 32, 32, 32, 32, 8, 8, 16, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        pool_mint.copy_from_slice(self.pool_mint.as_ref());
+        reward_mint.copy_from_slice(self.reward_mint.as_ref());
+        stake_vault.copy_from_slice(self.stake_vault.as_ref());
+        reward_vault.copy_from_slice(self.reward_vault.as_ref());
+        *emission_per_slot = self.emission_per_slot.to_le_bytes();
+        *last_update_slot = self.last_update_slot.to_le_bytes();
+        *acc_reward_per_share = self.acc_reward_per_share.to_le_bytes();
+        *total_staked = self.total_staked.to_le_bytes();
+        *rewards_unharvested = self.rewards_unharvested.to_le_bytes();
+    }
+}
+
+/// Pool tokens a holder staked in a farm
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StakeAccount {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Farm the pool tokens are staked in
+    pub farm: Pubkey,
+    /// Holder the stake belongs to
+    pub owner: Pubkey,
+    /// Pool tokens staked
+    pub amount: u64,
+    /// Rewards owed to the staked pool tokens that were already counted or emitted
+    /// before they were staked
+    pub reward_debt: u128,
+    /// Rewards counted and not harvested yet
+    pub reward_owed: u64,
+}
+
+impl StakeAccount {
+    /// Counts the rewards emitted to the stake up to `acc_reward_per_share` and sets
+    /// its pool tokens to `amount`, which only earn the rewards emitted from then on.
+    pub fn set_amount(&mut self, amount: u64, acc_reward_per_share: u128) -> Option<()> {
+        let accrued =
+            farm_reward(self.amount, acc_reward_per_share)?.checked_sub(self.reward_debt)?;
+        self.reward_owed = self.reward_owed.checked_add(u64::try_from(accrued).ok()?)?;
+        self.reward_debt = farm_reward(amount, acc_reward_per_share)?;
+        self.amount = amount;
+        Some(())
+    }
+}
+
+impl Sealed for StakeAccount {}
+impl IsInitialized for StakeAccount {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for StakeAccount {
+    const LEN: usize = layout::stake_account::LEN;
+
+    /// Unpacks a byte buffer into a [StakeAccount](struct.StakeAccount.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 97];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, farm, owner, amount, reward_debt, reward_owed) =
+            array_refs![input, 1, 32, 32, 8, 16, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            farm: Pubkey::new_from_array(*farm),
+            owner: Pubkey::new_from_array(*owner),
+            amount: u64::from_le_bytes(*amount),
+            reward_debt: u128::from_le_bytes(*reward_debt),
+            reward_owed: u64::from_le_bytes(*reward_owed),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 97];
+        let (is_initialized, farm, owner, amount, reward_debt, reward_owed) =
+            mut_array_refs![output, 1, 32, 32, 8, 16, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        farm.copy_from_slice(self.farm.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *amount = self.amount.to_le_bytes();
+        *reward_debt = self.reward_debt.to_le_bytes();
+        *reward_owed = self.reward_owed.to_le_bytes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_farm_packing() {
+        let swap_raw = [1u8; 32];
+        let pool_mint_raw = [2u8; 32];
+        let reward_mint_raw = [3u8; 32];
+        let stake_vault_raw = [4u8; 32];
+        let reward_vault_raw = [5u8; 32];
+        let emission_per_slot: u64 = 1_000;
+        let last_update_slot: Slot = 42;
+        let acc_reward_per_share: u128 = 7 * REWARD_PER_SHARE_SCALE;
+        let total_staked: u64 = 5_000;
+        let rewards_unharvested: u64 = 35_000;
+        let farm = Farm {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            pool_mint: Pubkey::new_from_array(pool_mint_raw),
+            reward_mint: Pubkey::new_from_array(reward_mint_raw),
+            stake_vault: Pubkey::new_from_array(stake_vault_raw),
+            reward_vault: Pubkey::new_from_array(reward_vault_raw),
+            emission_per_slot,
+            last_update_slot,
+            acc_reward_per_share,
+            total_staked,
+            rewards_unharvested,
+        };
+
+        let mut packed = [0u8; Farm::LEN];
+        Farm::pack(farm, &mut packed).unwrap();
+        let unpacked = Farm::unpack(&packed).unwrap();
+        assert_eq!(farm, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&pool_mint_raw);
+        packed.extend_from_slice(&reward_mint_raw);
+        packed.extend_from_slice(&stake_vault_raw);
+        packed.extend_from_slice(&reward_vault_raw);
+        packed.extend_from_slice(&emission_per_slot.to_le_bytes());
+        packed.extend_from_slice(&last_update_slot.to_le_bytes());
+        packed.extend_from_slice(&acc_reward_per_share.to_le_bytes());
+        packed.extend_from_slice(&total_staked.to_le_bytes());
+        packed.extend_from_slice(&rewards_unharvested.to_le_bytes());
+        let unpacked = Farm::unpack(&packed).unwrap();
+        assert_eq!(farm, unpacked);
+
+        let packed = [0u8; Farm::LEN];
+        let err = Farm::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_stake_account_packing() {
+        let farm_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let amount: u64 = 5_000;
+        let reward_debt: u128 = 35_000;
+        let reward_owed: u64 = 700;
+        let stake_account = StakeAccount {
+            is_initialized: true,
+            farm: Pubkey::new_from_array(farm_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            amount,
+            reward_debt,
+            reward_owed,
+        };
+
+        let mut packed = [0u8; StakeAccount::LEN];
+        StakeAccount::pack(stake_account, &mut packed).unwrap();
+        let unpacked = StakeAccount::unpack(&packed).unwrap();
+        assert_eq!(stake_account, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&farm_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&amount.to_le_bytes());
+        packed.extend_from_slice(&reward_debt.to_le_bytes());
+        packed.extend_from_slice(&reward_owed.to_le_bytes());
+        let unpacked = StakeAccount::unpack(&packed).unwrap();
+        assert_eq!(stake_account, unpacked);
+
+        let packed = [0u8; StakeAccount::LEN];
+        let err = StakeAccount::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_farm_emission() {
+        let mut farm = Farm {
+            is_initialized: true,
+            emission_per_slot: 100,
+            last_update_slot: 10,
+            ..Farm::default()
+        };
+        let mut stake = StakeAccount::default();
+        let mut other_stake = StakeAccount::default();
+
+        // nothing is emitted while nothing is staked
+        farm.update(20, 10_000).unwrap();
+        assert_eq!(farm.last_update_slot, 20);
+        assert_eq!(farm.acc_reward_per_share, 0);
+
+        stake.set_amount(1_000, farm.acc_reward_per_share).unwrap();
+        farm.total_staked = 1_000;
+        farm.update(30, 10_000).unwrap();
+        assert_eq!(farm.rewards_unharvested, 1_000);
+
+        // a later stake only earns the rewards emitted from then on
+        other_stake
+            .set_amount(3_000, farm.acc_reward_per_share)
+            .unwrap();
+        farm.total_staked = 4_000;
+        farm.update(40, 10_000).unwrap();
+        stake
+            .set_amount(stake.amount, farm.acc_reward_per_share)
+            .unwrap();
+        other_stake
+            .set_amount(other_stake.amount, farm.acc_reward_per_share)
+            .unwrap();
+        assert_eq!(stake.reward_owed, 1_000 + 250);
+        assert_eq!(other_stake.reward_owed, 750);
+
+        // the emission stops once the reward vault is emitted
+        farm.update(1_000, 10_000).unwrap();
+        assert_eq!(farm.rewards_unharvested, 10_000);
+        farm.update(2_000, 10_000).unwrap();
+        assert_eq!(farm.rewards_unharvested, 10_000);
+        let acc_reward_per_share = farm.acc_reward_per_share;
+
+        // and resumes once funded again
+        farm.update(2_010, 10_500).unwrap();
+        assert_eq!(farm.rewards_unharvested, 10_500);
+        assert!(farm.acc_reward_per_share > acc_reward_per_share);
+
+        // past slots are ignored
+        farm.update(2_000, 20_000).unwrap();
+        assert_eq!(farm.last_update_slot, 2_010);
+        assert_eq!(farm.rewards_unharvested, 10_500);
+    }
+}
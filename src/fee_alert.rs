@@ -0,0 +1,142 @@
+//! Admin fee accounts found unable to receive fees by `CheckFeeDestinations`
+
+use crate::{token_2022, utils};
+use solana_program::{account_info::AccountInfo, pubkey::Pubkey};
+use std::fmt;
+
+/// Prefix of the fee destination alert log line
+pub const FEE_DESTINATION_ALERT_PREFIX: &str = "fee_destination_alert";
+
+/// Reason an admin fee account can't receive fees
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeeDestinationFault {
+    /// The account was closed
+    Closed,
+    /// The account is not a token account
+    NotTokenAccount,
+    /// The account holds another token than the fees it receives
+    WrongMint,
+}
+
+impl FeeDestinationFault {
+    /// Fault of `destination_info` as the account receiving fees of `mint`, if any
+    pub fn check(destination_info: &AccountInfo, mint: &Pubkey) -> Option<Self> {
+        if destination_info.lamports() == 0 || destination_info.data_is_empty() {
+            return Some(Self::Closed);
+        }
+        if !token_2022::is_token_program(destination_info.owner) {
+            return Some(Self::NotTokenAccount);
+        }
+        match utils::unpack_token_account(&destination_info.data.borrow()) {
+            Ok(destination) if destination.mint == *mint => None,
+            Ok(_) => Some(Self::WrongMint),
+            Err(_) => Some(Self::NotTokenAccount),
+        }
+    }
+}
+
+impl fmt::Display for FeeDestinationFault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Closed => "closed",
+            Self::NotTokenAccount => "not_token_account",
+            Self::WrongMint => "wrong_mint",
+        })
+    }
+}
+
+/// Admin fee account unable to receive fees, whose fees are escrowed in the swap
+/// token account from then on. Displays as a single line of space separated
+/// `key=value` pairs, led by `FEE_DESTINATION_ALERT_PREFIX`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeeDestinationAlert {
+    /// Token-swap
+    pub swap: Pubkey,
+    /// Mint of the fees
+    pub mint: Pubkey,
+    /// Admin fee account
+    pub destination: Pubkey,
+    /// Reason the account can't receive fees
+    pub fault: FeeDestinationFault,
+}
+
+impl fmt::Display for FeeDestinationAlert {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} swap={} mint={} destination={} fault={}",
+            FEE_DESTINATION_ALERT_PREFIX, self.swap, self.mint, self.destination, self.fault,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::program_pack::Pack;
+    use spl_token::state::Account;
+
+    #[test]
+    fn test_fee_destination_alert_display() {
+        let swap = Pubkey::new_from_array([1u8; 32]);
+        let mint = Pubkey::new_from_array([2u8; 32]);
+        let destination = Pubkey::new_from_array([3u8; 32]);
+        let alert = FeeDestinationAlert {
+            swap,
+            mint,
+            destination,
+            fault: FeeDestinationFault::WrongMint,
+        };
+
+        assert_eq!(
+            alert.to_string(),
+            format!(
+                "fee_destination_alert swap={} mint={} destination={} fault=wrong_mint",
+                swap, mint, destination
+            )
+        );
+    }
+
+    #[test]
+    fn test_fee_destination_fault_check() {
+        let key = Pubkey::new_from_array([3u8; 32]);
+        let mint = Pubkey::new_from_array([2u8; 32]);
+        let token_program = spl_token::id();
+        let mut data = vec![0u8; Account::LEN];
+        Account::pack(
+            Account {
+                mint,
+                state: spl_token::state::AccountState::Initialized,
+                ..Account::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+        let mut lamports = 1;
+        let destination_info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &token_program,
+            false,
+            0,
+        );
+        assert_eq!(FeeDestinationFault::check(&destination_info, &mint), None);
+        assert_eq!(
+            FeeDestinationFault::check(&destination_info, &key),
+            Some(FeeDestinationFault::WrongMint)
+        );
+        destination_info.data.borrow_mut().fill(0);
+        assert_eq!(
+            FeeDestinationFault::check(&destination_info, &mint),
+            Some(FeeDestinationFault::NotTokenAccount)
+        );
+        **destination_info.lamports.borrow_mut() = 0;
+        assert_eq!(
+            FeeDestinationFault::check(&destination_info, &mint),
+            Some(FeeDestinationFault::Closed)
+        );
+    }
+}
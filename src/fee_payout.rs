@@ -0,0 +1,96 @@
+//! Marketing and developer fees paid out to their wallet, logged by
+//! `WithdrawMarketingFees` and `WithdrawDeveloperFees` for treasury accounting
+
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+
+/// Prefix of the fee payout log line
+pub const FEE_PAYOUT_PREFIX: &str = "fee_payout";
+
+/// Fee bucket paid out to a wallet
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FeeBucket {
+    /// Marketing fees, paid to the marketing wallet
+    Marketing,
+    /// Developer fees, paid to the developer wallet
+    Developer,
+}
+
+impl fmt::Display for FeeBucket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Marketing => "marketing",
+            Self::Developer => "developer",
+        })
+    }
+}
+
+/// Accrued fees of a bucket withdrawn to its wallet. Displays as a single line of
+/// space separated `key=value` pairs, led by `FEE_PAYOUT_PREFIX`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeePayout {
+    /// Token-swap
+    pub swap: Pubkey,
+    /// Fee bucket paid out
+    pub bucket: FeeBucket,
+    /// Wallet the fees are paid to
+    pub wallet: Pubkey,
+    /// Token A Account of the wallet receiving the token A fees
+    pub destination_a: Pubkey,
+    /// Token B Account of the wallet receiving the token B fees
+    pub destination_b: Pubkey,
+    /// Token A fees paid out
+    pub amount_a: u64,
+    /// Token B fees paid out
+    pub amount_b: u64,
+}
+
+impl fmt::Display for FeePayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} swap={} bucket={} wallet={} destination_a={} destination_b={} amount_a={} \
+             amount_b={}",
+            FEE_PAYOUT_PREFIX,
+            self.swap,
+            self.bucket,
+            self.wallet,
+            self.destination_a,
+            self.destination_b,
+            self.amount_a,
+            self.amount_b,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_payout_display() {
+        let swap = Pubkey::new_from_array([1u8; 32]);
+        let wallet = Pubkey::new_from_array([2u8; 32]);
+        let destination_a = Pubkey::new_from_array([3u8; 32]);
+        let destination_b = Pubkey::new_from_array([4u8; 32]);
+        let payout = FeePayout {
+            swap,
+            bucket: FeeBucket::Developer,
+            wallet,
+            destination_a,
+            destination_b,
+            amount_a: 100,
+            amount_b: 0,
+        };
+
+        assert_eq!(
+            payout.to_string(),
+            format!(
+                "fee_payout swap={} bucket=developer wallet={} destination_a={} \
+                 destination_b={} amount_a=100 amount_b=0",
+                swap, wallet, destination_a, destination_b
+            )
+        );
+        assert_eq!(FeeBucket::Marketing.to_string(), "marketing");
+    }
+}
@@ -0,0 +1,104 @@
+//! Trade fees of a Solana epoch logged by `ReportEpochFees` for treasury accounting
+
+use solana_program::{clock::Epoch, pubkey::Pubkey};
+use std::fmt;
+
+/// Prefix of the epoch fee report log line
+pub const EPOCH_FEE_REPORT_PREFIX: &str = "epoch_fee_report";
+
+/// Trade fees charged by a pool during a Solana epoch, per bucket. Displays as a
+/// single line of space separated `key=value` pairs, led by `EPOCH_FEE_REPORT_PREFIX`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EpochFeeReport {
+    /// Token-swap
+    pub swap: Pubkey,
+    /// Epoch the fees were charged in
+    pub epoch: Epoch,
+    /// Token A trade fees left to the LPs, insurance fund share included
+    pub lp_fees_a: u64,
+    /// Token B trade fees left to the LPs, insurance fund share included
+    pub lp_fees_b: u64,
+    /// Token A admin trade fees
+    pub admin_fees_a: u64,
+    /// Token B admin trade fees
+    pub admin_fees_b: u64,
+    /// Token A reflection fees
+    pub reflection_fees_a: u64,
+    /// Token B reflection fees
+    pub reflection_fees_b: u64,
+    /// Token A buyback fees
+    pub buyback_fees_a: u64,
+    /// Token B buyback fees
+    pub buyback_fees_b: u64,
+    /// Token A marketing fees
+    pub marketing_fees_a: u64,
+    /// Token B marketing fees
+    pub marketing_fees_b: u64,
+    /// Token A developer fees
+    pub developer_fees_a: u64,
+    /// Token B developer fees
+    pub developer_fees_b: u64,
+}
+
+impl fmt::Display for EpochFeeReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} swap={} epoch={} lp_fees_a={} lp_fees_b={} admin_fees_a={} admin_fees_b={} \
+             reflection_fees_a={} reflection_fees_b={} buyback_fees_a={} buyback_fees_b={} \
+             marketing_fees_a={} marketing_fees_b={} developer_fees_a={} developer_fees_b={}",
+            EPOCH_FEE_REPORT_PREFIX,
+            self.swap,
+            self.epoch,
+            self.lp_fees_a,
+            self.lp_fees_b,
+            self.admin_fees_a,
+            self.admin_fees_b,
+            self.reflection_fees_a,
+            self.reflection_fees_b,
+            self.buyback_fees_a,
+            self.buyback_fees_b,
+            self.marketing_fees_a,
+            self.marketing_fees_b,
+            self.developer_fees_a,
+            self.developer_fees_b,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_epoch_fee_report_display() {
+        let swap = Pubkey::new_from_array([1u8; 32]);
+        let report = EpochFeeReport {
+            swap,
+            epoch: 7,
+            lp_fees_a: 100,
+            lp_fees_b: 200,
+            admin_fees_a: 300,
+            admin_fees_b: 400,
+            reflection_fees_a: 500,
+            reflection_fees_b: 600,
+            buyback_fees_a: 700,
+            buyback_fees_b: 800,
+            marketing_fees_a: 900,
+            marketing_fees_b: 1_000,
+            developer_fees_a: 1_100,
+            developer_fees_b: 1_200,
+        };
+
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "epoch_fee_report swap={} epoch=7 lp_fees_a=100 lp_fees_b=200 admin_fees_a=300 \
+                 admin_fees_b=400 reflection_fees_a=500 reflection_fees_b=600 \
+                 buyback_fees_a=700 buyback_fees_b=800 marketing_fees_a=900 \
+                 marketing_fees_b=1000 developer_fees_a=1100 developer_fees_b=1200",
+                swap
+            )
+        );
+    }
+}
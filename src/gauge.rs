@@ -0,0 +1,413 @@
+//! Gauges allocating a reward emission across pools by veLP votes.
+//!
+//! Account flow:
+//!
+//! 1. The admin of a swap with vote-escrow enabled creates a `GaugeController`
+//!    with `InitializeGaugeController`, setting the emission of each epoch, and
+//!    adds a `Gauge` per pool with `AddGauge`.
+//! 2. veLP lockers of that swap split their veLP balance across gauges with
+//!    `VoteGaugeWeight`, recorded in a `GaugeVote` per lock and gauge. Votes
+//!    persist across epochs and take effect from the next epoch. A lock can't be
+//!    withdrawn while it has votes.
+//! 3. Each epoch, `CheckpointGauge` is cranked for every gauge. The first checkpoint
+//!    of the controller in an epoch snapshots the total vote weight and the
+//!    emission, and the first checkpoint of a gauge snapshots its vote weight and
+//!    allocates it the share of the emission its weight holds. Gauges not
+//!    checkpointed in an epoch miss the emission of that epoch.
+
+use crate::layout;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+use std::convert::TryFrom;
+
+/// Reward emission of a swap shared across gauges by veLP votes
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GaugeController {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap whose veLP lockers vote
+    pub swap: Pubkey,
+    /// Reward emission of each epoch
+    pub emission_per_epoch: u64,
+    /// Vote-escrow epoch of the last checkpoint
+    pub epoch: i64,
+    /// Vote weight of all gauges, effective from the next epoch
+    pub total_next_weight: u64,
+    /// Vote weight of all gauges in the current epoch
+    pub epoch_total_weight: u64,
+    /// Reward emission of the current epoch
+    pub epoch_emission: u64,
+}
+
+impl GaugeController {
+    /// Snapshots the vote weight and the emission at the first checkpoint in `epoch`
+    pub fn checkpoint(&mut self, epoch: i64) {
+        if epoch > self.epoch {
+            self.epoch = epoch;
+            self.epoch_total_weight = self.total_next_weight;
+            self.epoch_emission = self.emission_per_epoch;
+        }
+    }
+}
+
+impl Sealed for GaugeController {}
+impl IsInitialized for GaugeController {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for GaugeController {
+    const LEN: usize = layout::gauge_controller::LEN;
+
+    /// Unpacks a byte buffer into a [GaugeController](struct.GaugeController.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 73];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            swap,
+            emission_per_epoch,
+            epoch,
+            total_next_weight,
+            epoch_total_weight,
+            epoch_emission,
+        ) = array_refs![input, 1, 32, 8, 8, 8, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            emission_per_epoch: u64::from_le_bytes(*emission_per_epoch),
+            epoch: i64::from_le_bytes(*epoch),
+            total_next_weight: u64::from_le_bytes(*total_next_weight),
+            epoch_total_weight: u64::from_le_bytes(*epoch_total_weight),
+            epoch_emission: u64::from_le_bytes(*epoch_emission),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 73];
+        let (
+            is_initialized,
+            swap,
+            emission_per_epoch,
+            epoch,
+            total_next_weight,
+            epoch_total_weight,
+            epoch_emission,
+        ) = mut_array_refs![output, 1, 32, 8, 8, 8, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        *emission_per_epoch = self.emission_per_epoch.to_le_bytes();
+        *epoch = self.epoch.to_le_bytes();
+        *total_next_weight = self.total_next_weight.to_le_bytes();
+        *epoch_total_weight = self.epoch_total_weight.to_le_bytes();
+        *epoch_emission = self.epoch_emission.to_le_bytes();
+    }
+}
+
+/// Share of the emission of a gauge controller allocated to a pool
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Gauge {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Gauge controller the gauge belongs to
+    pub controller: Pubkey,
+    /// Swap of the pool
+    pub swap: Pubkey,
+    /// Vote weight of the gauge, effective from the next epoch
+    pub next_weight: u64,
+    /// Vote-escrow epoch of the last checkpoint
+    pub epoch: i64,
+    /// Vote weight of the gauge in the current epoch
+    pub epoch_weight: u64,
+    /// Reward emission allocated to the pool so far
+    pub emissions: u64,
+}
+
+impl Gauge {
+    /// Snapshots the vote weight of the gauge at its first checkpoint in the epoch of
+    /// `controller`, allocating the gauge its share of the epoch emission
+    pub fn checkpoint(&mut self, controller: &GaugeController) -> Option<()> {
+        if self.epoch >= controller.epoch {
+            return Some(());
+        }
+        self.epoch = controller.epoch;
+        self.epoch_weight = self.next_weight;
+        if controller.epoch_total_weight == 0 {
+            return Some(());
+        }
+        let emission = (controller.epoch_emission as u128)
+            .checked_mul(self.epoch_weight as u128)?
+            .checked_div(controller.epoch_total_weight as u128)?;
+        self.emissions = self.emissions.checked_add(u64::try_from(emission).ok()?)?;
+        Some(())
+    }
+}
+
+impl Sealed for Gauge {}
+impl IsInitialized for Gauge {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for Gauge {
+    const LEN: usize = layout::gauge::LEN;
+
+    /// Unpacks a byte buffer into a [Gauge](struct.Gauge.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 97];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, controller, swap, next_weight, epoch, epoch_weight, emissions) =
+            array_refs![input, 1, 32, 32, 8, 8, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            controller: Pubkey::new_from_array(*controller),
+            swap: Pubkey::new_from_array(*swap),
+            next_weight: u64::from_le_bytes(*next_weight),
+            epoch: i64::from_le_bytes(*epoch),
+            epoch_weight: u64::from_le_bytes(*epoch_weight),
+            emissions: u64::from_le_bytes(*emissions),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 97];
+        let (is_initialized, controller, swap, next_weight, epoch, epoch_weight, emissions) =
+            mut_array_refs![output, 1, 32, 32, 8, 8, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        controller.copy_from_slice(self.controller.as_ref());
+        swap.copy_from_slice(self.swap.as_ref());
+        *next_weight = self.next_weight.to_le_bytes();
+        *epoch = self.epoch.to_le_bytes();
+        *epoch_weight = self.epoch_weight.to_le_bytes();
+        *emissions = self.emissions.to_le_bytes();
+    }
+}
+
+/// Part of the veLP balance of a vote-escrow lock voted for a gauge
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GaugeVote {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Gauge voted for
+    pub gauge: Pubkey,
+    /// Owner of the vote-escrow lock
+    pub owner: Pubkey,
+    /// Share of the veLP balance voted, in basis points
+    pub weight_bps: u16,
+    /// Vote weight added to the gauge
+    pub weight: u64,
+}
+
+impl Sealed for GaugeVote {}
+impl IsInitialized for GaugeVote {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for GaugeVote {
+    const LEN: usize = layout::gauge_vote::LEN;
+
+    /// Unpacks a byte buffer into a [GaugeVote](struct.GaugeVote.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 75];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, gauge, owner, weight_bps, weight) =
+            array_refs![input, 1, 32, 32, 2, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            gauge: Pubkey::new_from_array(*gauge),
+            owner: Pubkey::new_from_array(*owner),
+            weight_bps: u16::from_le_bytes(*weight_bps),
+            weight: u64::from_le_bytes(*weight),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 75];
+        let (is_initialized, gauge, owner, weight_bps, weight) =
+            mut_array_refs![output, 1, 32, 32, 2, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        gauge.copy_from_slice(self.gauge.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *weight_bps = self.weight_bps.to_le_bytes();
+        *weight = self.weight.to_le_bytes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gauge_controller_packing() {
+        let swap_raw = [1u8; 32];
+        let emission_per_epoch: u64 = 1_000_000;
+        let epoch: i64 = 3;
+        let total_next_weight: u64 = 5_000;
+        let epoch_total_weight: u64 = 4_000;
+        let epoch_emission: u64 = 900_000;
+        let controller = GaugeController {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            emission_per_epoch,
+            epoch,
+            total_next_weight,
+            epoch_total_weight,
+            epoch_emission,
+        };
+
+        let mut packed = [0u8; GaugeController::LEN];
+        GaugeController::pack(controller, &mut packed).unwrap();
+        let unpacked = GaugeController::unpack(&packed).unwrap();
+        assert_eq!(controller, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&emission_per_epoch.to_le_bytes());
+        packed.extend_from_slice(&epoch.to_le_bytes());
+        packed.extend_from_slice(&total_next_weight.to_le_bytes());
+        packed.extend_from_slice(&epoch_total_weight.to_le_bytes());
+        packed.extend_from_slice(&epoch_emission.to_le_bytes());
+        let unpacked = GaugeController::unpack(&packed).unwrap();
+        assert_eq!(controller, unpacked);
+
+        let packed = [0u8; GaugeController::LEN];
+        let err = GaugeController::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_gauge_packing() {
+        let controller_raw = [1u8; 32];
+        let swap_raw = [2u8; 32];
+        let next_weight: u64 = 5_000;
+        let epoch: i64 = 3;
+        let epoch_weight: u64 = 4_000;
+        let emissions: u64 = 900_000;
+        let gauge = Gauge {
+            is_initialized: true,
+            controller: Pubkey::new_from_array(controller_raw),
+            swap: Pubkey::new_from_array(swap_raw),
+            next_weight,
+            epoch,
+            epoch_weight,
+            emissions,
+        };
+
+        let mut packed = [0u8; Gauge::LEN];
+        Gauge::pack(gauge, &mut packed).unwrap();
+        let unpacked = Gauge::unpack(&packed).unwrap();
+        assert_eq!(gauge, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&controller_raw);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&next_weight.to_le_bytes());
+        packed.extend_from_slice(&epoch.to_le_bytes());
+        packed.extend_from_slice(&epoch_weight.to_le_bytes());
+        packed.extend_from_slice(&emissions.to_le_bytes());
+        let unpacked = Gauge::unpack(&packed).unwrap();
+        assert_eq!(gauge, unpacked);
+
+        let packed = [0u8; Gauge::LEN];
+        let err = Gauge::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_gauge_vote_packing() {
+        let gauge_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let weight_bps: u16 = 2_500;
+        let weight: u64 = 250;
+        let gauge_vote = GaugeVote {
+            is_initialized: true,
+            gauge: Pubkey::new_from_array(gauge_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            weight_bps,
+            weight,
+        };
+
+        let mut packed = [0u8; GaugeVote::LEN];
+        GaugeVote::pack(gauge_vote, &mut packed).unwrap();
+        let unpacked = GaugeVote::unpack(&packed).unwrap();
+        assert_eq!(gauge_vote, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&gauge_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&weight_bps.to_le_bytes());
+        packed.extend_from_slice(&weight.to_le_bytes());
+        let unpacked = GaugeVote::unpack(&packed).unwrap();
+        assert_eq!(gauge_vote, unpacked);
+
+        let packed = [0u8; GaugeVote::LEN];
+        let err = GaugeVote::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_gauge_checkpoint() {
+        let mut controller = GaugeController {
+            is_initialized: true,
+            emission_per_epoch: 1_000,
+            epoch: 1,
+            total_next_weight: 300,
+            ..GaugeController::default()
+        };
+        let mut gauge = Gauge {
+            is_initialized: true,
+            next_weight: 100,
+            epoch: 1,
+            ..Gauge::default()
+        };
+
+        // weights are snapshotted at the first checkpoint of the epoch
+        controller.checkpoint(2);
+        controller.total_next_weight = 600;
+        controller.emission_per_epoch = 2_000;
+        controller.checkpoint(2);
+        assert_eq!(controller.epoch_total_weight, 300);
+        assert_eq!(controller.epoch_emission, 1_000);
+
+        gauge.checkpoint(&controller).unwrap();
+        assert_eq!(gauge.epoch, 2);
+        assert_eq!(gauge.epoch_weight, 100);
+        assert_eq!(gauge.emissions, 333);
+
+        // the emission is allocated once per epoch
+        gauge.next_weight = 400;
+        gauge.checkpoint(&controller).unwrap();
+        assert_eq!(gauge.emissions, 333);
+
+        controller.checkpoint(3);
+        gauge.checkpoint(&controller).unwrap();
+        assert_eq!(gauge.epoch_weight, 400);
+        assert_eq!(gauge.emissions, 333 + 1_333);
+    }
+}
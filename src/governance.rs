@@ -0,0 +1,175 @@
+//! Voter weight plugin letting LPs vote in an SPL Governance realm with their
+//! pool tokens.
+//!
+//! Account flow:
+//!
+//! 1. The admin sets the realm and a pool token vault owned by the swap authority
+//!    with `SetGovernanceRealm`. The realm is configured with this program as its
+//!    community voter weight addin and the pool mint as its community mint.
+//! 2. An LP approves the swap authority and calls `DepositGovernanceTokens`. The
+//!    pool tokens move to the vault and the LP's `VoterWeightRecord`, a program
+//!    address derived from `["voter-weight-record", realm, pool mint, LP]`, is
+//!    credited with the deposit.
+//! 3. The LP passes the record to the governance instructions needing a voter
+//!    weight, such as `CreateProposal` and `CastVote`.
+//! 4. Once the lockup since the last deposit elapsed, `WithdrawGovernanceTokens`
+//!    returns pool tokens and debits the record. Realms should use a lockup at
+//!    least as long as their voting time, so withdrawn tokens can't vote twice.
+//!
+//! A governance account of the realm can also be made the insurance authority with
+//! `SetInsuranceFund`, so that only its executed proposals may `CoverShortfall`.
+
+use crate::layout;
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+/// Account discriminator of voter weight records, the first 8 bytes of
+/// `sha256("account:VoterWeightRecord")`
+pub const VOTER_WEIGHT_RECORD_DISCRIMINATOR: [u8; 8] = [46, 249, 155, 75, 153, 248, 116, 9];
+
+/// Voter weight of a governing token owner, laid out as the SPL Governance addin
+/// `VoterWeightRecord`. The weight doesn't expire and applies to any action, so
+/// the expiry, action and action target are always packed as `None`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VoterWeightRecord {
+    /// Realm the weight is valid in
+    pub realm: Pubkey,
+    /// Governing token mint, the pool mint
+    pub governing_token_mint: Pubkey,
+    /// Owner of the weight
+    pub governing_token_owner: Pubkey,
+    /// Pool tokens deposited by the owner
+    pub voter_weight: u64,
+}
+
+impl Sealed for VoterWeightRecord {}
+impl IsInitialized for VoterWeightRecord {
+    fn is_initialized(&self) -> bool {
+        self.realm != Pubkey::default()
+    }
+}
+
+impl Pack for VoterWeightRecord {
+    const LEN: usize = layout::voter_weight_record::LEN;
+
+    /// Unpacks a byte buffer into a [VoterWeightRecord](struct.VoterWeightRecord.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 123];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            discriminator,
+            realm,
+            governing_token_mint,
+            governing_token_owner,
+            voter_weight,
+            options,
+            _reserved,
+        ) = array_refs![input, 8, 32, 32, 32, 8, 3, 8];
+        if *discriminator != VOTER_WEIGHT_RECORD_DISCRIMINATOR || *options != [0; 3] {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            realm: Pubkey::new_from_array(*realm),
+            governing_token_mint: Pubkey::new_from_array(*governing_token_mint),
+            governing_token_owner: Pubkey::new_from_array(*governing_token_owner),
+            voter_weight: u64::from_le_bytes(*voter_weight),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 123];
+        let (
+            discriminator,
+            realm,
+            governing_token_mint,
+            governing_token_owner,
+            voter_weight,
+            options,
+            reserved,
+        ) = mut_array_refs![output, 8, 32, 32, 32, 8, 3, 8];
+        *discriminator = VOTER_WEIGHT_RECORD_DISCRIMINATOR;
+        realm.copy_from_slice(self.realm.as_ref());
+        governing_token_mint.copy_from_slice(self.governing_token_mint.as_ref());
+        governing_token_owner.copy_from_slice(self.governing_token_owner.as_ref());
+        *voter_weight = self.voter_weight.to_le_bytes();
+        *options = [0; 3];
+        *reserved = [0; 8];
+    }
+}
+
+/// Realm of an SPL Governance `Governance` account, laid out as its account type
+/// followed by the realm
+pub fn governance_realm(data: &[u8]) -> Option<Pubkey> {
+    if data.len() < 33 {
+        return None;
+    }
+    Some(Pubkey::new_from_array(*array_ref![data, 1, 32]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::hash::hash;
+
+    #[test]
+    fn test_voter_weight_record_discriminator() {
+        let preimage_hash = hash(b"account:VoterWeightRecord");
+        assert_eq!(
+            VOTER_WEIGHT_RECORD_DISCRIMINATOR,
+            preimage_hash.to_bytes()[..8]
+        );
+    }
+
+    #[test]
+    fn test_voter_weight_record_packing() {
+        let realm_raw = [1u8; 32];
+        let governing_token_mint_raw = [2u8; 32];
+        let governing_token_owner_raw = [3u8; 32];
+        let voter_weight: u64 = 1_000;
+        let record = VoterWeightRecord {
+            realm: Pubkey::new_from_array(realm_raw),
+            governing_token_mint: Pubkey::new_from_array(governing_token_mint_raw),
+            governing_token_owner: Pubkey::new_from_array(governing_token_owner_raw),
+            voter_weight,
+        };
+
+        let mut packed = [0u8; VoterWeightRecord::LEN];
+        VoterWeightRecord::pack(record, &mut packed).unwrap();
+        let unpacked = VoterWeightRecord::unpack(&packed).unwrap();
+        assert_eq!(record, unpacked);
+
+        let mut packed = vec![];
+        packed.extend_from_slice(&VOTER_WEIGHT_RECORD_DISCRIMINATOR);
+        packed.extend_from_slice(&realm_raw);
+        packed.extend_from_slice(&governing_token_mint_raw);
+        packed.extend_from_slice(&governing_token_owner_raw);
+        packed.extend_from_slice(&voter_weight.to_le_bytes());
+        // no expiry, action or action target
+        packed.extend_from_slice(&[0, 0, 0]);
+        packed.extend_from_slice(&[0u8; 8]);
+        let unpacked = VoterWeightRecord::unpack(&packed).unwrap();
+        assert_eq!(record, unpacked);
+
+        let packed = [0u8; VoterWeightRecord::LEN];
+        let err = VoterWeightRecord::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidAccountData);
+    }
+
+    #[test]
+    fn test_governance_realm() {
+        let realm_raw = [1u8; 32];
+        let mut data = vec![18];
+        data.extend_from_slice(&realm_raw);
+        data.extend_from_slice(&[2u8; 32]);
+        assert_eq!(
+            governance_realm(&data),
+            Some(Pubkey::new_from_array(realm_raw))
+        );
+        assert_eq!(governance_realm(&data[..32]), None);
+    }
+}
@@ -2,18 +2,30 @@
 
 #![allow(clippy::too_many_arguments)]
 
+use crate::curve::CurveType;
 use crate::error::SwapError;
-use crate::fees::Fees;
+use crate::fees::{FeeTier, Fees};
+use crate::merkle::MAX_PROOF_LEN;
+use crate::metadata::{self, MAX_NAME_LENGTH, MAX_SYMBOL_LENGTH, MAX_URI_LENGTH};
+use crate::oracle::OracleStalePolicy;
+use crate::state::MAX_CUSTOM_ERROR_MESSAGE_LEN;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
     program_error::ProgramError,
     program_pack::Pack,
     pubkey::Pubkey,
-    sysvar::clock,
+    system_program,
+    sysvar::{clock, rent},
 };
-use std::convert::TryInto;
+use std::convert::{TryFrom, TryInto};
 use std::mem::size_of;
 
+/// Maximum number of swaps of a [Route](enum.SwapInstruction.html#variant.Route)
+pub const MAX_ROUTE_LEGS: usize = 4;
+/// Accounts of a `Swap` a route leg does not take, as they are shared by all legs:
+/// the source, the token program and the clock sysvar
+const ROUTE_SHARED_ACCOUNTS: usize = 3;
+
 /// Initialize instruction data
 #[repr(C)]
 #[derive(Debug, PartialEq)]
@@ -24,6 +36,32 @@ pub struct InitializeData {
     pub amp_factor: u64,
     /// Fees
     pub fees: Fees,
+    /// Number of slots after initialization during which only access token
+    /// holders may swap. Zero disables the access window.
+    pub access_window_slots: u64,
+    /// Creator-chosen namespace, allowing several pools for the same mint pair.
+    /// `CANONICAL_NAMESPACE` is reserved to the upgrade authority of the program.
+    pub namespace: [u8; 32],
+    /// Fee tier preset. Any tier other than `Custom` replaces `fees`.
+    pub fee_tier: FeeTier,
+    /// Owner the marketing fees are withdrawn to, unset pays them to the marketing
+    /// destination of each swap
+    pub marketing_wallet: Pubkey,
+    /// Owner the developer fees are withdrawn to, unset pays them to the developer
+    /// destination of each swap
+    pub developer_wallet: Pubkey,
+    /// Curve the pool prices its tokens along. The constant product curve leaves
+    /// `amp_factor` unused.
+    pub curve_type: CurveType,
+    /// Price of either token in the other, scaled by `PRICE_BOUND_SCALE`, bounding
+    /// the liquidity of a `PriceBounded` curve. Zero for the other curves.
+    pub price_bound: u64,
+    /// Number of slots after initialization during which only the payer signing
+    /// it may deposit. Zero disables the window.
+    pub creator_window_slots: u64,
+    /// Acknowledges that the freeze authority of a token mint can freeze the swap
+    /// token accounts and halt the pool. Required when either mint has one.
+    pub freeze_authority_acknowledged: bool,
 }
 
 /// Swap instruction data
@@ -34,6 +72,41 @@ pub struct SwapData {
     pub amount_in: u64,
     /// Minimum amount of DESTINATION token to output, prevents excessive slippage
     pub minimum_amount_out: u64,
+    /// Last slot the swap may execute in, rejects a swap landing at stale prices.
+    /// Zero for no deadline.
+    pub deadline_slot: u64,
+    /// Sequence number the pool must be at, rejects the swap if the pool changed
+    /// since it was quoted
+    pub expected_sequence: Option<u64>,
+}
+
+/// SwapOut instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapOutData {
+    /// DESTINATION amount to output, the SOURCE amount is based on the exchange rate
+    pub amount_out: u64,
+    /// Maximum amount of SOURCE token to transfer, prevents excessive slippage
+    pub maximum_amount_in: u64,
+    /// Last slot the swap may execute in, rejects a swap landing at stale prices.
+    /// Zero for no deadline.
+    pub deadline_slot: u64,
+    /// Sequence number the pool must be at, rejects the swap if the pool changed
+    /// since it was quoted
+    pub expected_sequence: Option<u64>,
+}
+
+/// Route instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RouteData {
+    /// SOURCE amount to transfer on the first leg
+    pub amount_in: u64,
+    /// Minimum amount of DESTINATION token the last leg pays out, prevents excessive
+    /// slippage over the whole route
+    pub minimum_amount_out: u64,
+    /// Number of accounts of each leg, in order
+    pub leg_account_counts: Vec<u8>,
 }
 
 /// Deposit instruction data
@@ -46,6 +119,11 @@ pub struct DepositData {
     pub token_b_amount: u64,
     /// Minimum LP tokens to mint, prevents excessive slippage
     pub min_mint_amount: u64,
+    /// Last slot the deposit may execute in. Zero for no deadline.
+    pub deadline_slot: u64,
+    /// Sequence number the pool must be at, rejects the deposit if the pool
+    /// changed since it was quoted
+    pub expected_sequence: Option<u64>,
 }
 
 /// Withdraw instruction data
@@ -59,6 +137,11 @@ pub struct WithdrawData {
     pub minimum_token_a_amount: u64,
     /// Minimum amount of token B to receive, prevents excessive slippage
     pub minimum_token_b_amount: u64,
+    /// Last slot the withdrawal may execute in. Zero for no deadline.
+    pub deadline_slot: u64,
+    /// Sequence number the pool must be at, rejects the withdrawal if the pool
+    /// changed since it was quoted
+    pub expected_sequence: Option<u64>,
 }
 
 /// Withdraw instruction data
@@ -70,512 +153,1457 @@ pub struct WithdrawOneData {
     pub pool_token_amount: u64,
     /// Minimum amount of token A or B to receive, prevents excessive slippage
     pub minimum_token_amount: u64,
+    /// Last slot the withdrawal may execute in. Zero for no deadline.
+    pub deadline_slot: u64,
+    /// Sequence number the pool must be at, rejects the withdrawal if the pool
+    /// changed since it was quoted
+    pub expected_sequence: Option<u64>,
 }
 
-/// RampA instruction data
+/// WithdrawImbalanced instruction data
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq)]
-pub struct RampAData {
-    /// Amp. Coefficient to ramp to
-    pub target_amp: u64,
-    /// Unix timestamp to stop ramp
-    pub stop_ramp_ts: i64,
+pub struct WithdrawImbalancedData {
+    /// Token A amount to withdraw
+    pub token_a_amount: u64,
+    /// Token B amount to withdraw
+    pub token_b_amount: u64,
+    /// Maximum pool tokens to burn, prevents excessive slippage
+    pub maximum_pool_token_amount: u64,
+    /// Last slot the withdrawal may execute in. Zero for no deadline.
+    pub deadline_slot: u64,
+    /// Sequence number the pool must be at, rejects the withdrawal if the pool
+    /// changed since it was quoted
+    pub expected_sequence: Option<u64>,
 }
 
-/// Admin only instructions.
+/// DepositOne instruction data
 #[repr(C)]
-#[derive(Debug, PartialEq)]
-pub enum AdminInstruction {
-    /// TODO: Docs
-    RampA(RampAData),
-    /// TODO: Docs
-    StopRampA,
-    /// TODO: Docs
-    Pause,
-    /// TODO: Docs
-    Unpause,
-    /// TODO: Docs
-    SetFeeAccount,
-    /// TODO: Docs
-    ApplyNewAdmin,
-    /// TODO: Docs
-    CommitNewAdmin,
-    /// TODO: Docs
-    SetNewFees(Fees),
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepositOneData {
+    /// Amount of token A or B to deposit
+    pub token_amount: u64,
+    /// Minimum LP tokens to mint, prevents excessive slippage
+    pub min_mint_amount: u64,
+    /// Last slot the deposit may execute in. Zero for no deadline.
+    pub deadline_slot: u64,
+    /// Sequence number the pool must be at, rejects the deposit if the pool
+    /// changed since it was quoted
+    pub expected_sequence: Option<u64>,
 }
 
-impl AdminInstruction {
-    /// Unpacks a byte buffer into a [AdminInstruction](enum.AdminInstruction.html).
-    pub fn unpack(input: &[u8]) -> Result<Option<Self>, ProgramError> {
-        let (&tag, rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
-        Ok(match tag {
-            100 => {
-                let (target_amp, rest) = unpack_u64(rest)?;
-                let (stop_ramp_ts, _rest) = unpack_i64(rest)?;
-                Some(Self::RampA(RampAData {
-                    target_amp,
-                    stop_ramp_ts,
-                }))
-            }
-            101 => Some(Self::StopRampA),
-            102 => Some(Self::Pause),
-            103 => Some(Self::Unpause),
-            104 => Some(Self::SetFeeAccount),
-            105 => Some(Self::ApplyNewAdmin),
-            106 => Some(Self::CommitNewAdmin),
-            107 => {
-                let fees = Fees::unpack_unchecked(rest)?;
-                Some(Self::SetNewFees(fees))
-            }
-            _ => None,
-        })
-    }
-
-    /// Packs a [AdminInstruction](enum.AdminInstruciton.html) into a byte buffer.
-    pub fn pack(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(size_of::<Self>());
-        match *self {
-            Self::RampA(RampAData {
-                target_amp,
-                stop_ramp_ts,
-            }) => {
-                buf.push(100);
-                buf.extend_from_slice(&target_amp.to_le_bytes());
-                buf.extend_from_slice(&stop_ramp_ts.to_le_bytes());
-            }
-            Self::StopRampA => buf.push(101),
-            Self::Pause => buf.push(102),
-            Self::Unpause => buf.push(103),
-            Self::SetFeeAccount => buf.push(104),
-            Self::ApplyNewAdmin => buf.push(105),
-            Self::CommitNewAdmin => buf.push(106),
-            Self::SetNewFees(fees) => {
-                buf.push(107);
-                let mut fees_slice = [0u8; Fees::LEN];
-                Pack::pack_into_slice(&fees, &mut fees_slice[..]);
-                buf.extend_from_slice(&fees_slice);
-            }
-        }
-        buf
-    }
+/// SwapWithReceipt instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapWithReceiptData {
+    /// SOURCE amount to transfer, output to DESTINATION is based on the exchange rate
+    pub amount_in: u64,
+    /// Minimum amount of DESTINATION token to output, prevents excessive slippage
+    pub minimum_amount_out: u64,
+    /// Receipt identifier chosen by the owner, unique per swap and owner
+    pub receipt_id: u64,
+    /// Nonce used to create the receipt program address
+    pub receipt_nonce: u8,
+    /// Sequence number the pool must be at, rejects the swap if the pool changed
+    /// since it was quoted
+    pub expected_sequence: Option<u64>,
 }
 
-/// Creates a 'ramp_a' instruction
-pub fn ramp_a(
-    program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
-    admin_pubkey: &Pubkey,
-    target_amp: u64,
-    stop_ramp_ts: i64,
-) -> Result<Instruction, ProgramError> {
-    let data = AdminInstruction::RampA(RampAData {
-        target_amp,
-        stop_ramp_ts,
-    })
-    .pack();
+/// CreateTwapOrder instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateTwapOrderData {
+    /// SOURCE amount to escrow and swap over all slices
+    pub amount_in: u64,
+    /// Number of equal slices to swap `amount_in` in
+    pub slice_count: u16,
+    /// Minimum number of slots between two slices
+    pub slice_interval_slots: u64,
+    /// Maximum deviation of the price of a slice below the pool TWAP, in basis points
+    pub max_price_deviation_bps: u16,
+    /// Order identifier chosen by the owner, unique per swap and owner
+    pub order_id: u64,
+    /// Nonce used to create the order program address
+    pub order_nonce: u8,
+}
 
-    let accounts = vec![
-        AccountMeta::new(*swap_pubkey, true),
-        AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*admin_pubkey, true),
-        AccountMeta::new(clock::id(), false),
-    ];
+/// CreateTrigger instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateTriggerData {
+    /// SOURCE amount to escrow and swap once triggered
+    pub amount_in: u64,
+    /// Minimum amount of DESTINATION token to receive, prevents excessive slippage
+    pub minimum_amount_out: u64,
+    /// Oracle price of token A in token B, scaled by `PRICE_SCALE`, to trigger at
+    pub trigger_price: u128,
+    /// Whether to trigger at or above the trigger price (take-profit), or at or
+    /// below it (stop-loss)
+    pub trigger_above: bool,
+    /// Lamports paid to the cranker executing the trigger
+    pub bounty: u64,
+    /// Trigger identifier chosen by the owner, unique per swap and owner
+    pub trigger_id: u64,
+    /// Nonce used to create the trigger program address
+    pub trigger_nonce: u8,
+}
 
-    Ok(Instruction {
-        program_id: *program_id,
-        accounts,
-        data,
-    })
+/// CreateDripDeposit instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateDripDepositData {
+    /// Token A amount to escrow and deposit over all tranches
+    pub amount_a: u64,
+    /// Token B amount to escrow and deposit over all tranches
+    pub amount_b: u64,
+    /// Number of equal tranches to deposit the amounts in
+    pub tranche_count: u16,
+    /// Minimum number of slots between two tranches
+    pub tranche_interval_slots: u64,
+    /// Drip deposit identifier chosen by the owner, unique per swap and owner
+    pub drip_id: u64,
+    /// Nonce used to create the drip deposit program address
+    pub drip_nonce: u8,
 }
 
-/// Creates a 'stop_ramp_a' instruction
-pub fn stop_ramp_a(
-    program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
-    admin_pubkey: &Pubkey,
-) -> Result<Instruction, ProgramError> {
-    let data = AdminInstruction::StopRampA.pack();
+/// CommitReflectionRoot instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommitReflectionRootData {
+    /// Root of the tree of `merkle::reflection_leaf` entitlements
+    pub merkle_root: [u8; 32],
+    /// Sum of all entitlements
+    pub total_amount: u64,
+    /// Nonce used to create the distribution program address
+    pub nonce: u8,
+}
 
-    let accounts = vec![
-        AccountMeta::new(*swap_pubkey, true),
-        AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*admin_pubkey, true),
-        AccountMeta::new(clock::id(), false),
-    ];
+/// ClaimReflectionWithProof instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClaimReflectionData {
+    /// Index of the entitlement in the distribution
+    pub index: u64,
+    /// Amount of the entitlement
+    pub amount: u64,
+    /// Nonce used to create the claim program address
+    pub nonce: u8,
+    /// Sibling nodes from the entitlement leaf up to the root
+    pub proof: Vec<[u8; 32]>,
+}
 
-    Ok(Instruction {
-        program_id: *program_id,
-        accounts,
-        data,
-    })
+/// DepositGovernanceTokens instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepositGovernanceTokensData {
+    /// Amount of pool tokens to deposit
+    pub amount: u64,
+    /// Nonce used to create the governance deposit program address
+    pub deposit_nonce: u8,
+    /// Nonce used to create the voter weight record program address
+    pub record_nonce: u8,
 }
 
-/// Creates a 'pause' instruction
-pub fn pause(
-    program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
-    admin_pubkey: &Pubkey,
-) -> Result<Instruction, ProgramError> {
-    let data = AdminInstruction::Pause.pack();
+/// BeginWithdraw instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct BeginWithdrawData {
+    /// Amount of pool tokens to burn. User is owed an output of token a
+    /// and b based on the percentage of the pool tokens that are returned.
+    pub pool_token_amount: u64,
+    /// Minimum amount of token A to receive, prevents excessive slippage
+    pub minimum_token_a_amount: u64,
+    /// Minimum amount of token B to receive, prevents excessive slippage
+    pub minimum_token_b_amount: u64,
+    /// Nonce used to create the withdraw continuation program address
+    pub nonce: u8,
+    /// Sequence number the pool must be at, rejects the withdrawal if the pool
+    /// changed since it was quoted
+    pub expected_sequence: Option<u64>,
+}
 
-    let accounts = vec![
-        AccountMeta::new(*swap_pubkey, true),
-        AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*admin_pubkey, true),
-    ];
+/// CreateVeLock instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateVeLockData {
+    /// Amount of pool tokens to lock
+    pub amount: u64,
+    /// Duration of the lock in seconds
+    pub lock_duration: i64,
+    /// Nonce used to create the vote-escrow lock program address
+    pub nonce: u8,
+}
 
-    Ok(Instruction {
-        program_id: *program_id,
-        accounts,
-        data,
-    })
+/// DepositLiquidityPosition instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepositLiquidityPositionData {
+    /// Amount of pool tokens to deposit
+    pub amount: u64,
+    /// Nonce used to create the liquidity position program address
+    pub nonce: u8,
 }
 
-/// Creates a 'unpause' instruction
-pub fn unpause(
-    program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
-    admin_pubkey: &Pubkey,
-) -> Result<Instruction, ProgramError> {
-    let data = AdminInstruction::Unpause.pack();
+/// DepositReflectionPosition instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepositReflectionPositionData {
+    /// Amount of pool tokens to deposit
+    pub amount: u64,
+    /// Nonce used to create the reflection position program address
+    pub nonce: u8,
+}
 
-    let accounts = vec![
-        AccountMeta::new(*swap_pubkey, true),
-        AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*admin_pubkey, true),
-    ];
+/// VoteGaugeWeight instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VoteGaugeWeightData {
+    /// Share of the veLP balance of the lock to vote for the gauge, in basis points
+    pub weight_bps: u16,
+    /// Nonce used to create the gauge vote program address
+    pub nonce: u8,
+}
 
-    Ok(Instruction {
-        program_id: *program_id,
-        accounts,
-        data,
-    })
+/// InitializeGaugeController instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct InitializeGaugeControllerData {
+    /// Reward emission of each epoch
+    pub emission_per_epoch: u64,
+    /// Nonce used to create the gauge controller program address
+    pub nonce: u8,
 }
 
-/// Creates a 'apply_new_admin' instruction
-pub fn apply_new_admin(
-    program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
-    admin_pubkey: &Pubkey,
-) -> Result<Instruction, ProgramError> {
-    let data = AdminInstruction::ApplyNewAdmin.pack();
+/// RampA instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RampAData {
+    /// Amp. Coefficient to ramp to
+    pub target_amp: u64,
+    /// Unix timestamp to stop ramp
+    pub stop_ramp_ts: i64,
+}
 
-    let accounts = vec![
-        AccountMeta::new(*swap_pubkey, true),
-        AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*admin_pubkey, true),
-        AccountMeta::new(clock::id(), false),
-    ];
+/// CreateAdminSession instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CreateAdminSessionData {
+    /// Bitmask of the `AdminSession::PERMISSION_*` instructions the session may execute
+    pub permissions: u8,
+    /// Last slot the session is valid in
+    pub expiry_slot: u64,
+    /// Nonce used to create the session program address
+    pub nonce: u8,
+}
 
-    Ok(Instruction {
-        program_id: *program_id,
-        accounts,
-        data,
-    })
+/// SetDynamicAmp instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetDynamicAmpData {
+    /// Amp. Coefficient at or above the volatility ceiling, zero disables the dynamic amp
+    pub min_amp: u64,
+    /// Amp. Coefficient at zero volatility
+    pub max_amp: u64,
+    /// Volatility, scaled by `PRICE_SCALE`, at which the minimum is reached
+    pub volatility_ceiling: u64,
+    /// Maximum change of the Amp. Coefficient per slot
+    pub max_drift_per_slot: u64,
 }
 
-/// Creates a 'commit_new_admin' instruction
-pub fn commit_new_admin(
-    program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
-    admin_pubkey: &Pubkey,
-    new_admin_pubkey: &Pubkey,
-) -> Result<Instruction, ProgramError> {
-    let data = AdminInstruction::CommitNewAdmin.pack();
+/// SetVolatilityFee instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetVolatilityFeeData {
+    /// Volatility, scaled by `PRICE_SCALE`, at which the maximum multiplier is
+    /// reached, zero disables the volatility fee
+    pub volatility_ceiling: u64,
+    /// Multiplier of the swap fees at or above the volatility ceiling, in basis points
+    pub max_multiplier_bps: u16,
+}
 
-    let accounts = vec![
-        AccountMeta::new(*swap_pubkey, true),
-        AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*admin_pubkey, true),
-        AccountMeta::new(*new_admin_pubkey, false),
-        AccountMeta::new(clock::id(), false),
-    ];
+/// SetAdminFeeEpochCap instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetAdminFeeEpochCapData {
+    /// Cap of the token A admin fees taken per epoch, zero for none
+    pub cap_a: u64,
+    /// Cap of the token B admin fees taken per epoch, zero for none
+    pub cap_b: u64,
+}
 
-    Ok(Instruction {
-        program_id: *program_id,
-        accounts,
-        data,
-    })
+/// SetPriceBound instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetPriceBoundData {
+    /// Price of either token in the other bounding the liquidity, scaled by
+    /// `PRICE_BOUND_SCALE`
+    pub price_bound: u64,
 }
 
-/// Creates a 'set_fee_account' instruction
-pub fn set_fee_account(
-    program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
-    admin_pubkey: &Pubkey,
-    new_fee_account_pubkey: &Pubkey,
-) -> Result<Instruction, ProgramError> {
-    let data = AdminInstruction::SetFeeAccount.pack();
+/// InitializeFarm instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct InitializeFarmData {
+    /// Rewards emitted each slot
+    pub emission_per_slot: u64,
+    /// Nonce used to create the farm program address
+    pub nonce: u8,
+}
 
-    let accounts = vec![
-        AccountMeta::new(*swap_pubkey, true),
-        AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*admin_pubkey, true),
-        AccountMeta::new(*new_fee_account_pubkey, false),
-    ];
+/// Stake instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StakeData {
+    /// Amount of pool tokens to stake
+    pub amount: u64,
+    /// Nonce used to create the stake account program address
+    pub nonce: u8,
+}
 
-    Ok(Instruction {
-        program_id: *program_id,
-        accounts,
-        data,
-    })
+/// SetDepositBonus instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetDepositBonusData {
+    /// Share of the admin trade fees set aside in the fee buffer, in basis points
+    pub fee_buffer_bps: u16,
+    /// Maximum deposit bonus, in basis points of the deposited amounts
+    pub deposit_bonus_bps: u16,
 }
 
-/// Creates a 'set_new_fees' instruction
-pub fn set_new_fees(
-    program_id: &Pubkey,
-    swap_pubkey: &Pubkey,
-    authority_pubkey: &Pubkey,
-    admin_pubkey: &Pubkey,
-    new_fees: Fees,
-) -> Result<Instruction, ProgramError> {
-    let data = AdminInstruction::SetNewFees(new_fees).pack();
+/// SetParams instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetParamsData {
+    /// New fees, staged
+    pub fees: Fees,
+    /// Amp. Coefficient to ramp to, zero leaves the Amp. Coefficient and its ramp
+    pub target_amp: u64,
+    /// Unix timestamp to stop the ramp
+    pub stop_ramp_ts: i64,
+    /// Maximum amount of source token a single swap may take, zero disables the cap
+    pub max_swap_amount_in: u64,
+    /// Whether the swap is paused
+    pub is_paused: bool,
+}
 
-    let accounts = vec![
-        AccountMeta::new(*swap_pubkey, true),
-        AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*admin_pubkey, true),
-    ];
+/// SetOracleGuard instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetOracleGuardData {
+    /// Maximum age of the oracle prices, in slots
+    pub max_staleness_slots: u64,
+    /// Maximum confidence interval of the oracle prices, in basis points of the
+    /// price
+    pub max_confidence_bps: u16,
+    /// Band past the oracle value of their input swaps may pay out, in basis points
+    pub max_deviation_bps: u16,
+}
 
-    Ok(Instruction {
-        program_id: *program_id,
-        accounts,
-        data,
-    })
+/// SetOracleStalePolicy instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetOracleStalePolicyData {
+    /// What the oracle guard does with swaps while an oracle price is stale
+    pub stale_policy: OracleStalePolicy,
+    /// Band added to the oracle deviation band while an oracle price is stale, in
+    /// basis points, for the `WidenBand` policy
+    pub stale_band_bps: u16,
 }
 
-/// Instructions supported by the SwapInfo program.
+/// UpdateLpMetadata instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpdateLpMetadataData {
+    /// Pool token name, at most `MAX_NAME_LENGTH` bytes
+    pub name: String,
+    /// Pool token symbol, at most `MAX_SYMBOL_LENGTH` bytes
+    pub symbol: String,
+    /// URI of the pool token off-chain metadata, at most `MAX_URI_LENGTH` bytes
+    pub uri: String,
+}
+
+/// SetCustomErrorMessage instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetCustomErrorMessageData {
+    /// Code of the `SwapError` the message is logged with
+    pub error_code: u32,
+    /// Message, at most `MAX_CUSTOM_ERROR_MESSAGE_LEN` bytes, empty to remove it
+    pub message: String,
+}
+
+/// CoverShortfall instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoverShortfallData {
+    /// Token A returned from the insurance fund to the pool
+    pub amount_a: u64,
+    /// Token B returned from the insurance fund to the pool
+    pub amount_b: u64,
+}
+
+/// ClonePool instruction data
+#[repr(C)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClonePoolData {
+    /// Nonce used to create valid program address
+    pub nonce: u8,
+    /// Creator-chosen namespace, allowing several pools for the same mint pair.
+    /// `CANONICAL_NAMESPACE` is reserved to the upgrade authority of the program.
+    pub namespace: [u8; 32],
+    /// Fee tier preset. Any tier other than `Custom` replaces the copied fees.
+    pub fee_tier: FeeTier,
+    /// Acknowledges that the freeze authority of a token mint can freeze the swap
+    /// token accounts and halt the pool. Required when either mint has one,
+    /// whether or not the copied pool acknowledged its own.
+    pub freeze_authority_acknowledged: bool,
+}
+
+/// Admin only instructions.
+///
+/// Admin instructions other than session management and admin transfer may be signed
+/// by an admin session key instead: the admin account is then the session key, and
+/// the session account and the clock sysvar are appended to the instruction accounts.
 #[repr(C)]
 #[derive(Debug, PartialEq)]
-pub enum SwapInstruction {
-    ///   Initializes a new SwapInfo.
+pub enum AdminInstruction {
+    /// TODO: Docs
+    RampA(RampAData),
+    /// TODO: Docs
+    StopRampA,
+    /// Pauses swaps and deposits, withdrawals stay enabled.
     ///
-    ///   0. `[writable, signer]` New Token-swap to create.
-    ///   1. `[]` $authority derived from `create_program_address(&[Token-swap account])`
-    ///   2. `[]` admin Account.
-    ///   3. `[]` admin_fee_a admin fee Account for token_a.
-    ///   4. `[]` admin_fee_b admin fee Account for token_b.
-    ///   5. `[]` token_a Account. Must be non zero, owned by $authority.
-    ///   6. `[]` token_b Account. Must be non zero, owned by $authority.
-    ///   7. `[writable]` Pool Token Mint. Must be empty, owned by $authority.
-    Initialize(InitializeData),
-
-    ///   Swap the tokens in the pool.
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin, admin session key or guardian
+    ///   3. `[]` Admin session, for an admin session key only.
+    ///   4. `[]` Clock sysvar, for an admin session key only.
+    Pause,
+    /// Unpauses swaps and deposits.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin, admin session key or guardian
+    ///   3. `[]` Admin session, for an admin session key only.
+    ///   4. `[]` Clock sysvar, for an admin session key only.
+    Unpause,
+    /// TODO: Docs
+    SetFeeAccount,
+    /// Accepts the admin transfer committed to the signer, before the commit
+    /// deadline.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` New admin
+    ///   3. `[]` Clock sysvar
+    ApplyNewAdmin,
+    /// Commits to transfer admin control to the new admin, who accepts it with
+    /// ApplyNewAdmin. A commit replaces an expired one.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` New admin
+    ///   4. `[]` Clock sysvar
+    CommitNewAdmin,
+    /// Stages new fees, which ApplyNewFees applies once `NEW_FEES_DELAY_SLOTS`
+    /// elapsed. Staging again replaces the pending fees and restarts the delay.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin or admin session key
+    ///   3. `[]` Clock sysvar
+    ///   4. `[]` Admin session, for an admin session key only.
+    ///   5. `[]` Clock sysvar, for an admin session key only.
+    SetNewFees(Fees),
+    /// Creates an admin session, allowing a temporary key to execute some admin
+    /// instructions until an expiry slot.
     ///
     ///   0. `[]` Token-swap
     ///   1. `[]` $authority
-    ///   2. `[writable]` token_(A|B) SOURCE Account, amount is transferable by $authority,
-    ///   3. `[writable]` token_(A|B) Base Account to swap INTO.  Must be the SOURCE token.
-    ///   4. `[writable]` token_(A|B) Base Account to swap FROM.  Must be the DESTINATION token.
-    ///   5. `[writable]` token_(A|B) DESTINATION Account assigned to USER as the owner.
-    ///   6. `[writable]` token_(A|B) admin fee Account. Must have same mint as DESTINATION token.
-    ///   7. `[]` Token program id
-    ///   8. `[]` Clock sysvar
-    Swap(SwapData),
-
-    ///   Deposit some tokens into the pool.  The output is a "pool" token representing ownership
-    ///   into the pool. Inputs are converted to the current ratio.
+    ///   2. `[writable, signer]` Admin, pays for the session account.
+    ///   3. `[writable]` Session account, program address derived from
+    ///      `["admin_session", Token-swap, session key]`. Must not exist yet.
+    ///   4. `[]` Session key
+    ///   5. `[]` System program id
+    ///   6. `[]` Rent sysvar
+    ///   7. `[]` Clock sysvar
+    CreateAdminSession(CreateAdminSessionData),
+    /// Closes an admin session, returning its rent to the admin.
     ///
     ///   0. `[]` Token-swap
     ///   1. `[]` $authority
-    ///   2. `[writable]` token_a $authority can transfer amount,
-    ///   3. `[writable]` token_b $authority can transfer amount,
-    ///   4. `[writable]` token_a Base Account to deposit into.
-    ///   5. `[writable]` token_b Base Account to deposit into.
-    ///   6. `[writable]` Pool MINT account, $authority is the owner.
-    ///   7. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
-    ///   8. `[]` Token program id
-    ///   9. `[]` Clock sysvar
-    Deposit(DepositData),
-
-    ///   Withdraw tokens from the pool at the current ratio.
+    ///   2. `[writable, signer]` Admin
+    ///   3. `[writable]` Session account
+    RevokeAdminSession,
+    /// Sets the share of harvested admin fees paid to the harvest caller, in basis
+    /// points. At most `MAX_HARVEST_TIP_BPS`.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetHarvestTip(u16),
+    /// Sets the key allowed to commit reflection distributions.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` New reflection distributor
+    SetReflectionDistributor,
+    /// Routes the developer fees into vesting accounts, released linearly to the
+    /// developer wallet over the given number of seconds. Can only be set once, and
+    /// not on a pool withdrawing the developer fees to its developer wallet.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` token_a vesting Account. Must be owned by $authority.
+    ///   4. `[]` token_b vesting Account. Must be owned by $authority.
+    ///   5. `[]` Developer wallet
+    ///   6. `[]` Clock sysvar
+    InitializeDevFeeVesting(i64),
+    /// Sets the SPL Governance realm LPs vote in with deposited pool tokens, and
+    /// the lockup of the deposits in seconds. Can only be set once, and not for
+    /// soulbound pool tokens.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Governance realm
+    ///   4. `[]` Pool token vault Account. Must be owned by $authority.
+    SetGovernanceRealm(i64),
+    /// Copies the pool parameters (amplification, fees, admin fee accounts and
+    /// harvest tip) to the parameter snapshot of the swap, replacing the previous
+    /// snapshot. Can't be signed by an admin session.
     ///
     ///   0. `[]` Token-swap
     ///   1. `[]` $authority
-    ///   2. `[writable]` Pool mint account, $authority is the owner
-    ///   3. `[writable]` SOURCE Pool account, amount is transferable by $authority.
-    ///   4. `[writable]` token_a Swap Account to withdraw FROM.
-    ///   5. `[writable]` token_b Swap Account to withdraw FROM.
-    ///   6. `[writable]` token_a user Account to credit.
-    ///   7. `[writable]` token_b user Account to credit.
-    ///   8. `[writable]` admin_fee_a admin fee Account for token_a.
-    ///   9. `[writable]` admin_fee_b admin fee Account for token_b.
-    ///   10. `[]` Token program id
-    Withdraw(WithdrawData),
-
-    ///   Withdraw one token from the pool at the current ratio.
+    ///   2. `[writable, signer]` Admin, pays for the snapshot account.
+    ///   3. `[writable]` Snapshot account, program address derived from
+    ///      `["parameter_snapshot", Token-swap]`. Created on the first snapshot.
+    ///   4. `[]` System program id
+    ///   5. `[]` Rent sysvar
+    ///   6. `[]` Clock sysvar
+    SnapshotState(u8),
+    /// Restores the pool parameters from the parameter snapshot, once it is
+    /// `SNAPSHOT_RESTORE_DELAY` old. Can't be signed by an admin session. The
+    /// fees are staged to apply through `ApplyNewFees` and the Amp. Coefficient
+    /// ramps from its current value to the snapshot's target.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Snapshot account
+    ///   4. `[]` Clock sysvar
+    RestoreState,
+    /// Sets the recovery key and the number of slots the admin may go without a
+    /// heartbeat before the recovery key can claim admin rights. The recovery key
+    /// may be a governance account. Counts as a heartbeat.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Recovery key
+    ///   4. `[]` Clock sysvar
+    SetAdminRecovery(u64),
+    /// Records an admin heartbeat at the current slot.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Clock sysvar
+    AdminHeartbeat,
+    /// Makes the recovery key the admin once the admin heartbeat timed out, and
+    /// cancels any pending admin transfer. Recovery must be set up again by the
+    /// new admin.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Recovery key
+    ///   3. `[]` Clock sysvar
+    ClaimAdminAfterTimeout,
+    /// Sets the maximum input amount of a single swap. Larger swaps are rejected
+    /// and must be split into several smaller ones. Zero disables the cap.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetMaxSwapAmount(u64),
+    /// Enables vote-escrow locks of pool tokens, with the share of harvested admin
+    /// trade fees paid to veLP lockers in basis points. Can only be set once, and not
+    /// for soulbound pool tokens.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Pool token vault Account. Must be owned by $authority.
+    ///   4. `[]` Token A fee vault Account. Must be owned by $authority.
+    ///   5. `[]` Token B fee vault Account. Must be owned by $authority.
+    ///   6. `[]` Clock sysvar
+    InitializeVeEscrow(u16),
+    /// Sets the share of harvested admin trade fees paid to veLP lockers, in basis
+    /// points.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetVeFeeShare(u16),
+    /// Creates the gauge controller allocating a reward emission across pools by
+    /// the votes of the veLP lockers of the swap. See the `gauge` module.
+    ///
+    ///   0. `[]` Token-swap, vote-escrow must be enabled.
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Admin, pays for the gauge controller.
+    ///   3. `[writable]` Gauge controller, program address derived from
+    ///      `["gauge_controller", Token-swap]`. Must not exist yet.
+    ///   4. `[]` System program id
+    ///   5. `[]` Rent sysvar
+    ///   6. `[]` Clock sysvar
+    InitializeGaugeController(InitializeGaugeControllerData),
+    /// Adds the gauge of a pool to the gauge controller of the swap.
     ///
     ///   0. `[]` Token-swap
     ///   1. `[]` $authority
-    ///   2. `[writable]` Pool mint account, $authority is the owner
-    ///   3. `[writable]` SOURCE Pool account, amount is transferable by $authority.
-    ///   4. `[writable]` token_(A|B) BASE token Swap Account to withdraw FROM.
-    ///   5. `[writable]` token_(A|B) QUOTE token Swap Account to exchange to base token.
-    ///   6. `[writable]` token_(A|B) BASE token user Account to credit.
-    ///   7. `[writable]` token_(A|B) admin fee Account. Must have same mint as BASE token.
-    ///   8. `[]` Token program id
-    ///   9. `[]` Clock sysvar
-    WithdrawOne(WithdrawOneData),
-}
-
-impl SwapInstruction {
-    /// Unpacks a byte buffer into a [SwapInstruction](enum.SwapInstruction.html).
-    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let (&tag, rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
-        Ok(match tag {
-            0 => {
-                let (&nonce, rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
-                let (amp_factor, rest) = unpack_u64(rest)?;
-                let fees = Fees::unpack_unchecked(rest)?;
-                Self::Initialize(InitializeData {
-                    nonce,
-                    amp_factor,
-                    fees,
-                })
-            }
-            1 => {
-                let (amount_in, rest) = unpack_u64(rest)?;
-                let (minimum_amount_out, _rest) = unpack_u64(rest)?;
-                Self::Swap(SwapData {
-                    amount_in,
-                    minimum_amount_out,
-                })
-            }
-            2 => {
-                let (token_a_amount, rest) = unpack_u64(rest)?;
-                let (token_b_amount, rest) = unpack_u64(rest)?;
-                let (min_mint_amount, _rest) = unpack_u64(rest)?;
-                Self::Deposit(DepositData {
-                    token_a_amount,
-                    token_b_amount,
-                    min_mint_amount,
-                })
-            }
-            3 => {
-                let (pool_token_amount, rest) = unpack_u64(rest)?;
-                let (minimum_token_a_amount, rest) = unpack_u64(rest)?;
-                let (minimum_token_b_amount, _rest) = unpack_u64(rest)?;
-                Self::Withdraw(WithdrawData {
-                    pool_token_amount,
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                })
-            }
-            4 => {
-                let (pool_token_amount, rest) = unpack_u64(rest)?;
-                let (minimum_token_amount, _rest) = unpack_u64(rest)?;
-                Self::WithdrawOne(WithdrawOneData {
+    ///   2. `[writable, signer]` Admin, pays for the gauge.
+    ///   3. `[]` Gauge controller
+    ///   4. `[]` Token-swap of the pool
+    ///   5. `[writable]` Gauge, program address derived from
+    ///      `["gauge", gauge controller, Token-swap of the pool]`. Must not exist yet.
+    ///   6. `[]` System program id
+    ///   7. `[]` Rent sysvar
+    AddGauge(u8),
+    /// Sets the reward emission of the gauge controller of the swap, effective from
+    /// the next epoch.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[writable]` Gauge controller
+    SetGaugeEmission(u64),
+    /// Configures the order-flow auction, selling the right to backrun each batch of
+    /// `batch_slots` slots. Zero disables the auction. Can't be called while a bid
+    /// is pending.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` token_(A|B) Base Account the bids are paid into, crediting the pool.
+    ///   4. `[]` Clock sysvar
+    SetOrderFlowAuction(u64),
+    /// Makes the Amp. Coefficient follow the volatility of the realized swap prices:
+    /// each swap moves it toward `max_amp` when prices are stable and toward `min_amp`
+    /// when they are volatile, by at most `max_drift_per_slot` per slot. A zero
+    /// `min_amp` disables the dynamic amp, leaving the current Amp. Coefficient. Can't
+    /// be enabled during a ramp, and ramping is disabled while it is enabled.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Clock sysvar
+    SetDynamicAmp(SetDynamicAmpData),
+    /// Sets the share of the admin trade fees set aside in the fee buffer, and the
+    /// bonus paid from it to deposits reducing the pool imbalance, at most
+    /// `MAX_DEPOSIT_BONUS_BPS` of the deposited amounts. Admin fees taken in pool
+    /// tokens don't fund the buffer. Buffered tokens stay set aside when the bonus
+    /// is disabled.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetDepositBonus(SetDepositBonusData),
+    /// Sets the share of the LP trade fees set aside in the insurance fund, in basis
+    /// points, and makes a governance account of the governance realm the insurance
+    /// authority, the only signer of `CoverShortfall`. Requires the governance realm.
+    /// Insured tokens stay set aside when the share is set to zero.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Governance realm
+    ///   4. `[]` Governance account of the realm, the insurance authority.
+    SetInsuranceFund(u16),
+    /// Stages the fees and sets the amplification ramp, the swap cap and the pause
+    /// flag at once. The whole bundle is validated before any of it applies, with
+    /// the rules of `SetNewFees`, `RampA`, `SetMaxSwapAmount` and `Pause` /
+    /// `Unpause`. The fees apply through `ApplyNewFees`, as staged by `SetNewFees`.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Clock sysvar
+    SetParams(SetParamsData),
+    /// Sets the minimum amount of pool tokens a deposit has to mint. Deposits
+    /// minting less are rejected rather than taking the deposited tokens for
+    /// (next to) nothing. A deposit always has to mint at least one pool token.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetMinLpMint(u64),
+    /// Sets the largest token balance `SweepDust` swaps free of fees. Zero disables
+    /// dust sweeps.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetDustThreshold(u64),
+    /// Sets the custodian that has to co-sign withdrawals burning more pool
+    /// tokens than the threshold. The default pubkey disables the custodian.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Custodian
+    SetCustodian(u64),
+    /// Sets the issuer of the credential swappers and depositors have to present,
+    /// either a mint or a program. The default pubkey lifts the requirement.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Credential issuer
+    SetCredentialIssuer,
+    /// Enables liquidity positions, setting the vault holding their pool tokens.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Pool token vault Account. Must be owned by $authority.
+    ///   4. `[]` Clock sysvar
+    InitializeLiquidityPositions,
+    /// Replaces the name, symbol and URI of the pool mint Metaplex metadata,
+    /// which has $authority as its update authority.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[writable]` Pool mint metadata account, the metadata program address of
+    ///      the pool mint
+    ///   4. `[]` Metadata program
+    UpdateLpMetadata(UpdateLpMetadataData),
+    /// Sets the guardian that may pause and unpause the pool without the admin
+    /// key. The default pubkey removes the guardian.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Guardian
+    SetGuardian,
+    /// Deposits protocol-owned liquidity from accounts of the admin treasury,
+    /// the pool tokens minted are counted as protocol-owned liquidity.
+    ///
+    ///   The accounts of the Deposit, followed by
+    ///   `[signer]` Admin, among the optional trailing accounts.
+    DeployProtocolLiquidity(DepositData),
+    /// Withdraws protocol-owned liquidity, at most the pool tokens deployed.
+    ///
+    ///   The accounts of the Withdraw, followed by
+    ///   `[signer]` Admin, among the optional trailing accounts.
+    WithdrawProtocolLiquidity(WithdrawData),
+    /// Applies the fees staged by SetNewFees once their delay elapsed.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin or admin session key
+    ///   3. `[]` Clock sysvar
+    ///   4. `[]` Admin session, for an admin session key only.
+    ///   5. `[]` Clock sysvar, for an admin session key only.
+    ApplyNewFees,
+    /// Discards the fees staged by SetNewFees.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin or admin session key
+    ///   3. `[]` Admin session, for an admin session key only.
+    ///   4. `[]` Clock sysvar, for an admin session key only.
+    CancelPendingFees,
+    /// Registers a message logged along with an error of the pool, such as the
+    /// pool a paused pool migrated to. An empty message removes it.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetCustomErrorMessage(SetCustomErrorMessageData),
+    /// Enables reflection positions, setting the vault holding their pool tokens
+    /// and the vaults the reflection fees are paid into from then on. Can only be
+    /// set once, and not for soulbound pool tokens.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Pool token vault Account. Must be owned by $authority.
+    ///   4. `[]` Token A reflection vault Account. Must be owned by $authority.
+    ///   5. `[]` Token B reflection vault Account. Must be owned by $authority.
+    InitializeReflection,
+    /// Enables buybacks of the token of a mint of the pool, setting the vaults the
+    /// buyback fees are paid into from then on. Can only be set once.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Token A buyback vault Account. Must be owned by $authority.
+    ///   4. `[]` Token B buyback vault Account. Must be owned by $authority.
+    ///   5. `[]` Token A or B MINT of the token bought back
+    InitializeBuyback,
+    /// Exempts a trader from the trade, reflection and buyback fees of the swaps
+    /// of the pool. At most `MAX_FEE_EXEMPTIONS` traders are exempt.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Admin, pays for the fee exemptions account.
+    ///   3. `[writable]` Fee exemptions account, program address derived from
+    ///      `["fee_exemptions", Token-swap]`. Created on the first exemption.
+    ///   4. `[]` Trader, owner of the source token accounts of its swaps
+    ///   5. `[]` System program id
+    ///   6. `[]` Rent sysvar
+    AddFeeExemption(u8),
+    /// Removes the fee exemption of a trader.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[writable]` Fee exemptions account
+    ///   4. `[]` Trader
+    RemoveFeeExemption,
+    /// Scales the trade and imbalance fees of swaps with the realized volatility
+    /// of the TWAP, from one at zero volatility up to `max_multiplier_bps`, at most
+    /// `MAX_VOLATILITY_FEE_MULTIPLIER_BPS`, at the volatility ceiling. A zero
+    /// `volatility_ceiling` disables the volatility fee.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Clock sysvar
+    SetVolatilityFee(SetVolatilityFeeData),
+    /// Caps the admin fees taken from swaps per epoch, in each token. Admin fees
+    /// past the cap are left to the LPs for the rest of the epoch. Admin fees
+    /// already taken in the current epoch count towards the new cap.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetAdminFeeEpochCap(SetAdminFeeEpochCapData),
+    /// Moves the band of prices a `PriceBounded` pool provides its liquidity
+    /// within. The same reserves hold another liquidity within another band, so
+    /// the virtual price of the pool tokens moves with the bound.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetPriceBound(SetPriceBoundData),
+    /// Creates the farm emitting a reward token to the stakers of the pool tokens
+    /// of the swap. See the `farm` module.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Admin, pays for the farm.
+    ///   3. `[writable]` Farm, program address derived from `["farm", Pool mint]`.
+    ///      Must not exist yet.
+    ///   4. `[]` Stake vault, an empty pool token Account owned by $authority.
+    ///   5. `[]` Reward vault, a reward token Account owned by $authority, neither
+    ///      a pool token Account nor a swap token Account.
+    ///   6. `[]` System program id
+    ///   7. `[]` Rent sysvar
+    ///   8. `[]` Clock sysvar
+    InitializeFarm(InitializeFarmData),
+    /// Allows a depositor to deposit into the pool while it restricts its
+    /// deposits. At most `MAX_ALLOWED_DEPOSITORS` depositors are allowed.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Admin, pays for the deposit allowlist account.
+    ///   3. `[writable]` Deposit allowlist account, program address derived from
+    ///      `["deposit_allowlist", Token-swap]`. Created on the first depositor.
+    ///   4. `[]` Depositor, owner of the source token accounts of its deposits
+    ///   5. `[]` System program id
+    ///   6. `[]` Rent sysvar
+    AddAllowedDepositor(u8),
+    /// Removes a depositor from the deposit allowlist.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[writable]` Deposit allowlist account
+    ///   4. `[]` Depositor
+    RemoveAllowedDepositor,
+    /// Restricts the deposits of the pool to the depositors of its deposit
+    /// allowlist, or opens them to everyone again. Swaps and withdrawals stay
+    /// open to everyone.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetDepositAllowlist(bool),
+    /// Checks the swaps of the pool against the Pyth prices of both tokens in a
+    /// common quote currency. Swaps are rejected when a confidence interval exceeds
+    /// `max_confidence_bps` of the price, or when they pay out more than the oracle
+    /// value of their input and `max_deviation_bps` besides. A price not trading or
+    /// older than `max_staleness_slots` is handled by the stale policy of the pool.
+    /// The default pubkey as price account of token A disables the guard. Swaps
+    /// pass both price accounts among their optional trailing accounts.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    ///   3. `[]` Pyth price account of token A
+    ///   4. `[]` Pyth price account of token B
+    SetOracleGuard(SetOracleGuardData),
+    /// Sets what the oracle guard does with swaps while an oracle price is not
+    /// trading or older than its staleness limit: reject them, let them through
+    /// unchecked, or check them with the deviation band widened by
+    /// `stale_band_bps`, at most 10_000. Pools reject them until set.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Admin
+    SetOracleStalePolicy(SetOracleStalePolicyData),
+}
+
+impl AdminInstruction {
+    /// Unpacks a byte buffer into a [AdminInstruction](enum.AdminInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Option<Self>, ProgramError> {
+        let (&tag, rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
+        Ok(match tag {
+            100 => {
+                let (target_amp, rest) = unpack_u64(rest)?;
+                let (stop_ramp_ts, _rest) = unpack_i64(rest)?;
+                Some(Self::RampA(RampAData {
+                    target_amp,
+                    stop_ramp_ts,
+                }))
+            }
+            101 => Some(Self::StopRampA),
+            102 => Some(Self::Pause),
+            103 => Some(Self::Unpause),
+            104 => Some(Self::SetFeeAccount),
+            105 => Some(Self::ApplyNewAdmin),
+            106 => Some(Self::CommitNewAdmin),
+            107 => {
+                let fees = Fees::unpack_unchecked(rest)?;
+                Some(Self::SetNewFees(fees))
+            }
+            108 => {
+                let (&permissions, rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let (expiry_slot, rest) = unpack_u64(rest)?;
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Some(Self::CreateAdminSession(CreateAdminSessionData {
+                    permissions,
+                    expiry_slot,
+                    nonce,
+                }))
+            }
+            109 => Some(Self::RevokeAdminSession),
+            110 => {
+                let (harvest_tip_bps, _rest) = unpack_u16(rest)?;
+                Some(Self::SetHarvestTip(harvest_tip_bps))
+            }
+            111 => Some(Self::SetReflectionDistributor),
+            112 => {
+                let (vesting_duration, _rest) = unpack_i64(rest)?;
+                Some(Self::InitializeDevFeeVesting(vesting_duration))
+            }
+            113 => {
+                let (governance_lockup, _rest) = unpack_i64(rest)?;
+                Some(Self::SetGovernanceRealm(governance_lockup))
+            }
+            114 => {
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Some(Self::SnapshotState(nonce))
+            }
+            115 => Some(Self::RestoreState),
+            116 => {
+                let (admin_timeout_slots, _rest) = unpack_u64(rest)?;
+                Some(Self::SetAdminRecovery(admin_timeout_slots))
+            }
+            117 => Some(Self::AdminHeartbeat),
+            118 => Some(Self::ClaimAdminAfterTimeout),
+            119 => {
+                let (max_swap_amount_in, _rest) = unpack_u64(rest)?;
+                Some(Self::SetMaxSwapAmount(max_swap_amount_in))
+            }
+            120 => {
+                let (ve_fee_bps, _rest) = unpack_u16(rest)?;
+                Some(Self::InitializeVeEscrow(ve_fee_bps))
+            }
+            121 => {
+                let (ve_fee_bps, _rest) = unpack_u16(rest)?;
+                Some(Self::SetVeFeeShare(ve_fee_bps))
+            }
+            122 => {
+                let (emission_per_epoch, rest) = unpack_u64(rest)?;
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Some(Self::InitializeGaugeController(
+                    InitializeGaugeControllerData {
+                        emission_per_epoch,
+                        nonce,
+                    },
+                ))
+            }
+            123 => {
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Some(Self::AddGauge(nonce))
+            }
+            124 => {
+                let (emission_per_epoch, _rest) = unpack_u64(rest)?;
+                Some(Self::SetGaugeEmission(emission_per_epoch))
+            }
+            125 => {
+                let (batch_slots, _rest) = unpack_u64(rest)?;
+                Some(Self::SetOrderFlowAuction(batch_slots))
+            }
+            126 => {
+                let (min_amp, rest) = unpack_u64(rest)?;
+                let (max_amp, rest) = unpack_u64(rest)?;
+                let (volatility_ceiling, rest) = unpack_u64(rest)?;
+                let (max_drift_per_slot, _rest) = unpack_u64(rest)?;
+                Some(Self::SetDynamicAmp(SetDynamicAmpData {
+                    min_amp,
+                    max_amp,
+                    volatility_ceiling,
+                    max_drift_per_slot,
+                }))
+            }
+            127 => {
+                let (fee_buffer_bps, rest) = unpack_u16(rest)?;
+                let (deposit_bonus_bps, _rest) = unpack_u16(rest)?;
+                Some(Self::SetDepositBonus(SetDepositBonusData {
+                    fee_buffer_bps,
+                    deposit_bonus_bps,
+                }))
+            }
+            128 => {
+                let (insurance_fee_bps, _rest) = unpack_u16(rest)?;
+                Some(Self::SetInsuranceFund(insurance_fee_bps))
+            }
+            129 => {
+                let (fees, rest) = unpack_fees(rest)?;
+                let (target_amp, rest) = unpack_u64(rest)?;
+                let (stop_ramp_ts, rest) = unpack_i64(rest)?;
+                let (max_swap_amount_in, rest) = unpack_u64(rest)?;
+                let is_paused = match rest.first() {
+                    Some(0) => false,
+                    Some(1) => true,
+                    _ => return Err(SwapError::InvalidInstruction.into()),
+                };
+                Some(Self::SetParams(SetParamsData {
+                    fees,
+                    target_amp,
+                    stop_ramp_ts,
+                    max_swap_amount_in,
+                    is_paused,
+                }))
+            }
+            130 => {
+                let (min_lp_mint, _rest) = unpack_u64(rest)?;
+                Some(Self::SetMinLpMint(min_lp_mint))
+            }
+            131 => {
+                let (dust_threshold, _rest) = unpack_u64(rest)?;
+                Some(Self::SetDustThreshold(dust_threshold))
+            }
+            132 => Some(Self::InitializeLiquidityPositions),
+            133 => {
+                let (custodian_withdraw_threshold, _rest) = unpack_u64(rest)?;
+                Some(Self::SetCustodian(custodian_withdraw_threshold))
+            }
+            134 => Some(Self::SetCredentialIssuer),
+            135 => {
+                let (name, rest) = unpack_str(rest, MAX_NAME_LENGTH)?;
+                let (symbol, rest) = unpack_str(rest, MAX_SYMBOL_LENGTH)?;
+                let (uri, _rest) = unpack_str(rest, MAX_URI_LENGTH)?;
+                Some(Self::UpdateLpMetadata(UpdateLpMetadataData {
+                    name,
+                    symbol,
+                    uri,
+                }))
+            }
+            136 => Some(Self::SetGuardian),
+            137 => {
+                let (token_a_amount, rest) = unpack_u64(rest)?;
+                let (token_b_amount, rest) = unpack_u64(rest)?;
+                let (min_mint_amount, rest) = unpack_u64(rest)?;
+                let (deadline_slot, expected_sequence) = unpack_deadline_and_sequence(rest)?;
+                Some(Self::DeployProtocolLiquidity(DepositData {
+                    token_a_amount,
+                    token_b_amount,
+                    min_mint_amount,
+                    deadline_slot,
+                    expected_sequence,
+                }))
+            }
+            138 => {
+                let (pool_token_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_a_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_b_amount, rest) = unpack_u64(rest)?;
+                let (deadline_slot, expected_sequence) = unpack_deadline_and_sequence(rest)?;
+                Some(Self::WithdrawProtocolLiquidity(WithdrawData {
                     pool_token_amount,
-                    minimum_token_amount,
-                })
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                    deadline_slot,
+                    expected_sequence,
+                }))
             }
-            _ => return Err(SwapError::InvalidInstruction.into()),
+            139 => Some(Self::ApplyNewFees),
+            140 => Some(Self::CancelPendingFees),
+            141 => {
+                let (error_code, rest) = unpack_u32(rest)?;
+                let (message, _rest) = unpack_str(rest, MAX_CUSTOM_ERROR_MESSAGE_LEN)?;
+                Some(Self::SetCustomErrorMessage(SetCustomErrorMessageData {
+                    error_code,
+                    message,
+                }))
+            }
+            142 => Some(Self::InitializeReflection),
+            143 => Some(Self::InitializeBuyback),
+            144 => {
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Some(Self::AddFeeExemption(nonce))
+            }
+            145 => Some(Self::RemoveFeeExemption),
+            146 => {
+                let (volatility_ceiling, rest) = unpack_u64(rest)?;
+                let (max_multiplier_bps, _rest) = unpack_u16(rest)?;
+                Some(Self::SetVolatilityFee(SetVolatilityFeeData {
+                    volatility_ceiling,
+                    max_multiplier_bps,
+                }))
+            }
+            147 => {
+                let (cap_a, rest) = unpack_u64(rest)?;
+                let (cap_b, _rest) = unpack_u64(rest)?;
+                Some(Self::SetAdminFeeEpochCap(SetAdminFeeEpochCapData {
+                    cap_a,
+                    cap_b,
+                }))
+            }
+            148 => {
+                let (price_bound, _rest) = unpack_u64(rest)?;
+                Some(Self::SetPriceBound(SetPriceBoundData { price_bound }))
+            }
+            149 => {
+                let (emission_per_slot, rest) = unpack_u64(rest)?;
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Some(Self::InitializeFarm(InitializeFarmData {
+                    emission_per_slot,
+                    nonce,
+                }))
+            }
+            150 => {
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Some(Self::AddAllowedDepositor(nonce))
+            }
+            151 => Some(Self::RemoveAllowedDepositor),
+            152 => {
+                let enabled = match rest.first() {
+                    Some(0) => false,
+                    Some(1) => true,
+                    _ => return Err(SwapError::InvalidInstruction.into()),
+                };
+                Some(Self::SetDepositAllowlist(enabled))
+            }
+            153 => {
+                let (max_staleness_slots, rest) = unpack_u64(rest)?;
+                let (max_confidence_bps, rest) = unpack_u16(rest)?;
+                let (max_deviation_bps, _rest) = unpack_u16(rest)?;
+                Some(Self::SetOracleGuard(SetOracleGuardData {
+                    max_staleness_slots,
+                    max_confidence_bps,
+                    max_deviation_bps,
+                }))
+            }
+            154 => {
+                let (&stale_policy, rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let stale_policy = OracleStalePolicy::from_u8(stale_policy)
+                    .ok_or(SwapError::InvalidInstruction)?;
+                let (stale_band_bps, _rest) = unpack_u16(rest)?;
+                Some(Self::SetOracleStalePolicy(SetOracleStalePolicyData {
+                    stale_policy,
+                    stale_band_bps,
+                }))
+            }
+            _ => None,
         })
     }
 
-    /// Packs a [SwapInstruction](enum.SwapInstruction.html) into a byte buffer.
+    /// Packs a [AdminInstruction](enum.AdminInstruciton.html) into a byte buffer.
     pub fn pack(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(size_of::<Self>());
         match *self {
-            Self::Initialize(InitializeData {
+            Self::RampA(RampAData {
+                target_amp,
+                stop_ramp_ts,
+            }) => {
+                buf.push(100);
+                buf.extend_from_slice(&target_amp.to_le_bytes());
+                buf.extend_from_slice(&stop_ramp_ts.to_le_bytes());
+            }
+            Self::StopRampA => buf.push(101),
+            Self::Pause => buf.push(102),
+            Self::Unpause => buf.push(103),
+            Self::SetFeeAccount => buf.push(104),
+            Self::ApplyNewAdmin => buf.push(105),
+            Self::CommitNewAdmin => buf.push(106),
+            Self::SetNewFees(fees) => {
+                buf.push(107);
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(&fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+            }
+            Self::CreateAdminSession(CreateAdminSessionData {
+                permissions,
+                expiry_slot,
                 nonce,
-                amp_factor,
-                fees,
             }) => {
-                buf.push(0);
+                buf.push(108);
+                buf.push(permissions);
+                buf.extend_from_slice(&expiry_slot.to_le_bytes());
                 buf.push(nonce);
-                buf.extend_from_slice(&amp_factor.to_le_bytes());
+            }
+            Self::RevokeAdminSession => buf.push(109),
+            Self::SetHarvestTip(harvest_tip_bps) => {
+                buf.push(110);
+                buf.extend_from_slice(&harvest_tip_bps.to_le_bytes());
+            }
+            Self::SetReflectionDistributor => buf.push(111),
+            Self::InitializeDevFeeVesting(vesting_duration) => {
+                buf.push(112);
+                buf.extend_from_slice(&vesting_duration.to_le_bytes());
+            }
+            Self::SetGovernanceRealm(governance_lockup) => {
+                buf.push(113);
+                buf.extend_from_slice(&governance_lockup.to_le_bytes());
+            }
+            Self::SnapshotState(nonce) => {
+                buf.push(114);
+                buf.push(nonce);
+            }
+            Self::RestoreState => buf.push(115),
+            Self::SetAdminRecovery(admin_timeout_slots) => {
+                buf.push(116);
+                buf.extend_from_slice(&admin_timeout_slots.to_le_bytes());
+            }
+            Self::AdminHeartbeat => buf.push(117),
+            Self::ClaimAdminAfterTimeout => buf.push(118),
+            Self::SetMaxSwapAmount(max_swap_amount_in) => {
+                buf.push(119);
+                buf.extend_from_slice(&max_swap_amount_in.to_le_bytes());
+            }
+            Self::InitializeVeEscrow(ve_fee_bps) => {
+                buf.push(120);
+                buf.extend_from_slice(&ve_fee_bps.to_le_bytes());
+            }
+            Self::SetVeFeeShare(ve_fee_bps) => {
+                buf.push(121);
+                buf.extend_from_slice(&ve_fee_bps.to_le_bytes());
+            }
+            Self::InitializeGaugeController(InitializeGaugeControllerData {
+                emission_per_epoch,
+                nonce,
+            }) => {
+                buf.push(122);
+                buf.extend_from_slice(&emission_per_epoch.to_le_bytes());
+                buf.push(nonce);
+            }
+            Self::AddGauge(nonce) => {
+                buf.push(123);
+                buf.push(nonce);
+            }
+            Self::SetGaugeEmission(emission_per_epoch) => {
+                buf.push(124);
+                buf.extend_from_slice(&emission_per_epoch.to_le_bytes());
+            }
+            Self::SetOrderFlowAuction(batch_slots) => {
+                buf.push(125);
+                buf.extend_from_slice(&batch_slots.to_le_bytes());
+            }
+            Self::SetDynamicAmp(SetDynamicAmpData {
+                min_amp,
+                max_amp,
+                volatility_ceiling,
+                max_drift_per_slot,
+            }) => {
+                buf.push(126);
+                buf.extend_from_slice(&min_amp.to_le_bytes());
+                buf.extend_from_slice(&max_amp.to_le_bytes());
+                buf.extend_from_slice(&volatility_ceiling.to_le_bytes());
+                buf.extend_from_slice(&max_drift_per_slot.to_le_bytes());
+            }
+            Self::SetDepositBonus(SetDepositBonusData {
+                fee_buffer_bps,
+                deposit_bonus_bps,
+            }) => {
+                buf.push(127);
+                buf.extend_from_slice(&fee_buffer_bps.to_le_bytes());
+                buf.extend_from_slice(&deposit_bonus_bps.to_le_bytes());
+            }
+            Self::SetInsuranceFund(insurance_fee_bps) => {
+                buf.push(128);
+                buf.extend_from_slice(&insurance_fee_bps.to_le_bytes());
+            }
+            Self::SetParams(SetParamsData {
+                fees,
+                target_amp,
+                stop_ramp_ts,
+                max_swap_amount_in,
+                is_paused,
+            }) => {
+                buf.push(129);
                 let mut fees_slice = [0u8; Fees::LEN];
                 Pack::pack_into_slice(&fees, &mut fees_slice[..]);
                 buf.extend_from_slice(&fees_slice);
+                buf.extend_from_slice(&target_amp.to_le_bytes());
+                buf.extend_from_slice(&stop_ramp_ts.to_le_bytes());
+                buf.extend_from_slice(&max_swap_amount_in.to_le_bytes());
+                buf.push(is_paused as u8);
             }
-            Self::Swap(SwapData {
-                amount_in,
-                minimum_amount_out,
+            Self::SetMinLpMint(min_lp_mint) => {
+                buf.push(130);
+                buf.extend_from_slice(&min_lp_mint.to_le_bytes());
+            }
+            Self::SetDustThreshold(dust_threshold) => {
+                buf.push(131);
+                buf.extend_from_slice(&dust_threshold.to_le_bytes());
+            }
+            Self::InitializeLiquidityPositions => buf.push(132),
+            Self::SetCustodian(custodian_withdraw_threshold) => {
+                buf.push(133);
+                buf.extend_from_slice(&custodian_withdraw_threshold.to_le_bytes());
+            }
+            Self::SetCredentialIssuer => buf.push(134),
+            Self::UpdateLpMetadata(UpdateLpMetadataData {
+                ref name,
+                ref symbol,
+                ref uri,
             }) => {
-                buf.push(1);
-                buf.extend_from_slice(&amount_in.to_le_bytes());
-                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                buf.push(135);
+                pack_str(&mut buf, name);
+                pack_str(&mut buf, symbol);
+                pack_str(&mut buf, uri);
             }
-            Self::Deposit(DepositData {
+            Self::SetGuardian => buf.push(136),
+            Self::DeployProtocolLiquidity(DepositData {
                 token_a_amount,
                 token_b_amount,
                 min_mint_amount,
+                deadline_slot,
+                expected_sequence,
             }) => {
-                buf.push(2);
+                buf.push(137);
                 buf.extend_from_slice(&token_a_amount.to_le_bytes());
                 buf.extend_from_slice(&token_b_amount.to_le_bytes());
                 buf.extend_from_slice(&min_mint_amount.to_le_bytes());
+                pack_deadline_and_sequence(&mut buf, deadline_slot, expected_sequence);
             }
-            Self::Withdraw(WithdrawData {
+            Self::WithdrawProtocolLiquidity(WithdrawData {
                 pool_token_amount,
                 minimum_token_a_amount,
                 minimum_token_b_amount,
+                deadline_slot,
+                expected_sequence,
             }) => {
-                buf.push(3);
+                buf.push(138);
                 buf.extend_from_slice(&pool_token_amount.to_le_bytes());
                 buf.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
                 buf.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+                pack_deadline_and_sequence(&mut buf, deadline_slot, expected_sequence);
             }
-            Self::WithdrawOne(WithdrawOneData {
-                pool_token_amount,
-                minimum_token_amount,
+            Self::ApplyNewFees => buf.push(139),
+            Self::CancelPendingFees => buf.push(140),
+            Self::SetCustomErrorMessage(SetCustomErrorMessageData {
+                error_code,
+                ref message,
             }) => {
-                buf.push(4);
-                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
-                buf.extend_from_slice(&minimum_token_amount.to_le_bytes());
+                buf.push(141);
+                buf.extend_from_slice(&error_code.to_le_bytes());
+                pack_str(&mut buf, message);
+            }
+            Self::InitializeReflection => buf.push(142),
+            Self::InitializeBuyback => buf.push(143),
+            Self::AddFeeExemption(nonce) => {
+                buf.push(144);
+                buf.push(nonce);
+            }
+            Self::RemoveFeeExemption => buf.push(145),
+            Self::SetVolatilityFee(SetVolatilityFeeData {
+                volatility_ceiling,
+                max_multiplier_bps,
+            }) => {
+                buf.push(146);
+                buf.extend_from_slice(&volatility_ceiling.to_le_bytes());
+                buf.extend_from_slice(&max_multiplier_bps.to_le_bytes());
+            }
+            Self::SetAdminFeeEpochCap(SetAdminFeeEpochCapData { cap_a, cap_b }) => {
+                buf.push(147);
+                buf.extend_from_slice(&cap_a.to_le_bytes());
+                buf.extend_from_slice(&cap_b.to_le_bytes());
+            }
+            Self::SetPriceBound(SetPriceBoundData { price_bound }) => {
+                buf.push(148);
+                buf.extend_from_slice(&price_bound.to_le_bytes());
+            }
+            Self::InitializeFarm(InitializeFarmData {
+                emission_per_slot,
+                nonce,
+            }) => {
+                buf.push(149);
+                buf.extend_from_slice(&emission_per_slot.to_le_bytes());
+                buf.push(nonce);
+            }
+            Self::AddAllowedDepositor(nonce) => {
+                buf.push(150);
+                buf.push(nonce);
+            }
+            Self::RemoveAllowedDepositor => buf.push(151),
+            Self::SetDepositAllowlist(enabled) => {
+                buf.push(152);
+                buf.push(enabled as u8);
+            }
+            Self::SetOracleGuard(SetOracleGuardData {
+                max_staleness_slots,
+                max_confidence_bps,
+                max_deviation_bps,
+            }) => {
+                buf.push(153);
+                buf.extend_from_slice(&max_staleness_slots.to_le_bytes());
+                buf.extend_from_slice(&max_confidence_bps.to_le_bytes());
+                buf.extend_from_slice(&max_deviation_bps.to_le_bytes());
+            }
+            Self::SetOracleStalePolicy(SetOracleStalePolicyData {
+                stale_policy,
+                stale_band_bps,
+            }) => {
+                buf.push(154);
+                buf.push(stale_policy as u8);
+                buf.extend_from_slice(&stale_band_bps.to_le_bytes());
             }
         }
         buf
     }
 }
 
-/// Creates an 'initialize' instruction.
-pub fn initialize(
+/// Creates a 'ramp_a' instruction
+pub fn ramp_a(
     program_id: &Pubkey,
-    pool_token_program_id: &Pubkey, // Token program used for the pool token
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
     admin_pubkey: &Pubkey,
-    admin_fee_a_pubkey: &Pubkey,
-    admin_fee_b_pubkey: &Pubkey,
-    token_a_mint_pubkey: &Pubkey,
-    token_a_pubkey: &Pubkey,
-    token_b_mint_pubkey: &Pubkey,
-    token_b_pubkey: &Pubkey,
-    pool_mint_pubkey: &Pubkey,
-    destination_pubkey: &Pubkey, // Desintation to mint pool tokens for bootstrapper
-    nonce: u8,
-    amp_factor: u64,
-    fees: Fees,
+    target_amp: u64,
+    stop_ramp_ts: i64,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::Initialize(InitializeData {
-        nonce,
-        amp_factor,
-        fees,
+    let data = AdminInstruction::RampA(RampAData {
+        target_amp,
+        stop_ramp_ts,
     })
     .pack();
 
     let accounts = vec![
         AccountMeta::new(*swap_pubkey, true),
         AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*admin_pubkey, false),
-        AccountMeta::new(*admin_fee_a_pubkey, false),
-        AccountMeta::new(*admin_fee_b_pubkey, false),
-        AccountMeta::new(*token_a_mint_pubkey, false),
-        AccountMeta::new(*token_a_pubkey, false),
-        AccountMeta::new(*token_b_mint_pubkey, false),
-        AccountMeta::new(*token_b_pubkey, false),
-        AccountMeta::new(*pool_mint_pubkey, false),
-        AccountMeta::new(*destination_pubkey, false),
-        AccountMeta::new(*pool_token_program_id, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(clock::id(), false),
     ];
 
     Ok(Instruction {
@@ -585,39 +1613,19 @@ pub fn initialize(
     })
 }
 
-/// Creates a 'deposit' instruction.
-pub fn deposit(
+/// Creates a 'stop_ramp_a' instruction
+pub fn stop_ramp_a(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
-    deposit_token_a_pubkey: &Pubkey,
-    deposit_token_b_pubkey: &Pubkey,
-    swap_token_a_pubkey: &Pubkey,
-    swap_token_b_pubkey: &Pubkey,
-    pool_mint_pubkey: &Pubkey,
-    destination_pubkey: &Pubkey,
-    token_a_amount: u64,
-    token_b_amount: u64,
-    min_mint_amount: u64,
+    admin_pubkey: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::Deposit(DepositData {
-        token_a_amount,
-        token_b_amount,
-        min_mint_amount,
-    })
-    .pack();
+    let data = AdminInstruction::StopRampA.pack();
 
     let accounts = vec![
-        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, true),
         AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*deposit_token_a_pubkey, false),
-        AccountMeta::new(*deposit_token_b_pubkey, false),
-        AccountMeta::new(*swap_token_a_pubkey, false),
-        AccountMeta::new(*swap_token_b_pubkey, false),
-        AccountMeta::new(*pool_mint_pubkey, false),
-        AccountMeta::new(*destination_pubkey, false),
-        AccountMeta::new(*token_program_id, false),
+        AccountMeta::new(*admin_pubkey, true),
         AccountMeta::new(clock::id(), false),
     ];
 
@@ -628,43 +1636,41 @@ pub fn deposit(
     })
 }
 
-/// Creates a 'withdraw' instruction.
-pub fn withdraw(
+/// Creates a 'pause' instruction
+pub fn pause(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
-    pool_mint_pubkey: &Pubkey,
-    source_pubkey: &Pubkey,
-    swap_token_a_pubkey: &Pubkey,
-    swap_token_b_pubkey: &Pubkey,
-    destination_token_a_pubkey: &Pubkey,
-    destination_token_b_pubkey: &Pubkey,
-    admin_fee_a_pubkey: &Pubkey,
-    admin_fee_b_pubkey: &Pubkey,
-    pool_token_amount: u64,
-    minimum_token_a_amount: u64,
-    minimum_token_b_amount: u64,
+    admin_pubkey: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::Withdraw(WithdrawData {
-        pool_token_amount,
-        minimum_token_a_amount,
-        minimum_token_b_amount,
+    let data = AdminInstruction::Pause.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
     })
-    .pack();
+}
+
+/// Creates a 'unpause' instruction
+pub fn unpause(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::Unpause.pack();
 
     let accounts = vec![
-        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, true),
         AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*pool_mint_pubkey, false),
-        AccountMeta::new(*source_pubkey, false),
-        AccountMeta::new(*swap_token_a_pubkey, false),
-        AccountMeta::new(*swap_token_b_pubkey, false),
-        AccountMeta::new(*destination_token_a_pubkey, false),
-        AccountMeta::new(*destination_token_b_pubkey, false),
-        AccountMeta::new(*admin_fee_a_pubkey, false),
-        AccountMeta::new(*admin_fee_b_pubkey, false),
-        AccountMeta::new(*token_program_id, false),
+        AccountMeta::new(*admin_pubkey, true),
     ];
 
     Ok(Instruction {
@@ -674,35 +1680,44 @@ pub fn withdraw(
     })
 }
 
-/// Creates a 'swap' instruction.
-pub fn swap(
+/// Creates a 'apply_new_admin' instruction, signed by the new admin
+pub fn apply_new_admin(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
-    source_pubkey: &Pubkey,
-    swap_source_pubkey: &Pubkey,
-    swap_destination_pubkey: &Pubkey,
-    destination_pubkey: &Pubkey,
-    admin_fee_destination_pubkey: &Pubkey,
-    amount_in: u64,
-    minimum_amount_out: u64,
+    new_admin_pubkey: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::Swap(SwapData {
-        amount_in,
-        minimum_amount_out,
+    let data = AdminInstruction::ApplyNewAdmin.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*new_admin_pubkey, true),
+        AccountMeta::new(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
     })
-    .pack();
+}
+
+/// Creates a 'commit_new_admin' instruction
+pub fn commit_new_admin(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    new_admin_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::CommitNewAdmin.pack();
 
     let accounts = vec![
-        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new(*swap_pubkey, true),
         AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*source_pubkey, false),
-        AccountMeta::new(*swap_source_pubkey, false),
-        AccountMeta::new(*swap_destination_pubkey, false),
-        AccountMeta::new(*destination_pubkey, false),
-        AccountMeta::new(*admin_fee_destination_pubkey, false),
-        AccountMeta::new(*token_program_id, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*new_admin_pubkey, false),
         AccountMeta::new(clock::id(), false),
     ];
 
@@ -713,282 +1728,6630 @@ pub fn swap(
     })
 }
 
-/// Creates a 'withdraw_one' instruction.
-pub fn withdraw_one(
+/// Creates a 'set_fee_account' instruction
+pub fn set_fee_account(
     program_id: &Pubkey,
-    token_program_id: &Pubkey,
     swap_pubkey: &Pubkey,
     authority_pubkey: &Pubkey,
-    pool_mint_pubkey: &Pubkey,
-    source_pubkey: &Pubkey,
-    swap_base_token_pubkey: &Pubkey,
-    swap_quote_token_pubkey: &Pubkey,
-    base_destination_pubkey: &Pubkey,
-    admin_fee_destination_pubkey: &Pubkey,
-    pool_token_amount: u64,
-    minimum_token_amount: u64,
+    admin_pubkey: &Pubkey,
+    new_fee_account_pubkey: &Pubkey,
 ) -> Result<Instruction, ProgramError> {
-    let data = SwapInstruction::WithdrawOne(WithdrawOneData {
-        pool_token_amount,
-        minimum_token_amount,
+    let data = AdminInstruction::SetFeeAccount.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*new_fee_account_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
     })
-    .pack();
+}
+
+/// Creates a 'set_new_fees' instruction
+pub fn set_new_fees(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    new_fees: Fees,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetNewFees(new_fees).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'apply_new_fees' instruction
+pub fn apply_new_fees(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::ApplyNewFees.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'cancel_pending_fees' instruction
+pub fn cancel_pending_fees(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::CancelPendingFees.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'create_admin_session' instruction
+pub fn create_admin_session(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    admin_session_pubkey: &Pubkey,
+    session_key_pubkey: &Pubkey,
+    permissions: u8,
+    expiry_slot: u64,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::CreateAdminSession(CreateAdminSessionData {
+        permissions,
+        expiry_slot,
+        nonce,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*admin_session_pubkey, false),
+        AccountMeta::new_readonly(*session_key_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'revoke_admin_session' instruction
+pub fn revoke_admin_session(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    admin_session_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::RevokeAdminSession.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*admin_session_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_harvest_tip' instruction
+pub fn set_harvest_tip(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    harvest_tip_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetHarvestTip(harvest_tip_bps).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_reflection_distributor' instruction
+pub fn set_reflection_distributor(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    distributor_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetReflectionDistributor.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new_readonly(*distributor_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_dev_fee_vesting' instruction
+pub fn initialize_dev_fee_vesting(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    dev_vesting_a_pubkey: &Pubkey,
+    dev_vesting_b_pubkey: &Pubkey,
+    dev_wallet_pubkey: &Pubkey,
+    vesting_duration: i64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::InitializeDevFeeVesting(vesting_duration).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new_readonly(*dev_vesting_a_pubkey, false),
+        AccountMeta::new_readonly(*dev_vesting_b_pubkey, false),
+        AccountMeta::new_readonly(*dev_wallet_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_governance_realm' instruction
+pub fn set_governance_realm(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    realm_pubkey: &Pubkey,
+    governance_vault_pubkey: &Pubkey,
+    governance_lockup: i64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetGovernanceRealm(governance_lockup).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, true),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new_readonly(*realm_pubkey, false),
+        AccountMeta::new_readonly(*governance_vault_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'snapshot_state' instruction
+pub fn snapshot_state(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    snapshot_pubkey: &Pubkey,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SnapshotState(nonce).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*snapshot_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'restore_state' instruction
+pub fn restore_state(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    snapshot_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::RestoreState.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*snapshot_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_admin_recovery' instruction
+pub fn set_admin_recovery(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    recovery_pubkey: &Pubkey,
+    admin_timeout_slots: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetAdminRecovery(admin_timeout_slots).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*recovery_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'admin_heartbeat' instruction
+pub fn admin_heartbeat(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::AdminHeartbeat.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'claim_admin_after_timeout' instruction
+pub fn claim_admin_after_timeout(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    recovery_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::ClaimAdminAfterTimeout.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*recovery_pubkey, true),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_max_swap_amount' instruction
+pub fn set_max_swap_amount(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    max_swap_amount_in: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetMaxSwapAmount(max_swap_amount_in).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_ve_escrow' instruction
+pub fn initialize_ve_escrow(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    ve_lp_vault_pubkey: &Pubkey,
+    ve_fee_vault_a_pubkey: &Pubkey,
+    ve_fee_vault_b_pubkey: &Pubkey,
+    ve_fee_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::InitializeVeEscrow(ve_fee_bps).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*ve_lp_vault_pubkey, false),
+        AccountMeta::new_readonly(*ve_fee_vault_a_pubkey, false),
+        AccountMeta::new_readonly(*ve_fee_vault_b_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_ve_fee_share' instruction
+pub fn set_ve_fee_share(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    ve_fee_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetVeFeeShare(ve_fee_bps).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_gauge_controller' instruction
+pub fn initialize_gauge_controller(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    gauge_controller_pubkey: &Pubkey,
+    emission_per_epoch: u64,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::InitializeGaugeController(InitializeGaugeControllerData {
+        emission_per_epoch,
+        nonce,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*gauge_controller_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'add_gauge' instruction
+pub fn add_gauge(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    gauge_controller_pubkey: &Pubkey,
+    pool_swap_pubkey: &Pubkey,
+    gauge_pubkey: &Pubkey,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::AddGauge(nonce).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new_readonly(*gauge_controller_pubkey, false),
+        AccountMeta::new_readonly(*pool_swap_pubkey, false),
+        AccountMeta::new(*gauge_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_gauge_emission' instruction
+pub fn set_gauge_emission(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    gauge_controller_pubkey: &Pubkey,
+    emission_per_epoch: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetGaugeEmission(emission_per_epoch).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new(*gauge_controller_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_order_flow_auction' instruction
+pub fn set_order_flow_auction(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    bid_token_pubkey: &Pubkey,
+    batch_slots: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetOrderFlowAuction(batch_slots).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*bid_token_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_dynamic_amp' instruction
+pub fn set_dynamic_amp(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    min_amp: u64,
+    max_amp: u64,
+    volatility_ceiling: u64,
+    max_drift_per_slot: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetDynamicAmp(SetDynamicAmpData {
+        min_amp,
+        max_amp,
+        volatility_ceiling,
+        max_drift_per_slot,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_deposit_bonus' instruction
+pub fn set_deposit_bonus(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    fee_buffer_bps: u16,
+    deposit_bonus_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetDepositBonus(SetDepositBonusData {
+        fee_buffer_bps,
+        deposit_bonus_bps,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_insurance_fund' instruction
+pub fn set_insurance_fund(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    realm_pubkey: &Pubkey,
+    insurance_authority_pubkey: &Pubkey,
+    insurance_fee_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetInsuranceFund(insurance_fee_bps).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*realm_pubkey, false),
+        AccountMeta::new_readonly(*insurance_authority_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_params' instruction
+pub fn set_params(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    fees: Fees,
+    target_amp: u64,
+    stop_ramp_ts: i64,
+    max_swap_amount_in: u64,
+    is_paused: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetParams(SetParamsData {
+        fees,
+        target_amp,
+        stop_ramp_ts,
+        max_swap_amount_in,
+        is_paused,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_min_lp_mint' instruction
+pub fn set_min_lp_mint(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    min_lp_mint: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetMinLpMint(min_lp_mint).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_dust_threshold' instruction
+pub fn set_dust_threshold(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    dust_threshold: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetDustThreshold(dust_threshold).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_custodian' instruction
+pub fn set_custodian(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    custodian_pubkey: &Pubkey,
+    custodian_withdraw_threshold: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetCustodian(custodian_withdraw_threshold).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*custodian_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_credential_issuer' instruction
+pub fn set_credential_issuer(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    credential_issuer_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetCredentialIssuer.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*credential_issuer_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_guardian' instruction
+pub fn set_guardian(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    guardian_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetGuardian.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*guardian_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_custom_error_message' instruction
+pub fn set_custom_error_message(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    error_code: u32,
+    message: String,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetCustomErrorMessage(SetCustomErrorMessageData {
+        error_code,
+        message,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Turns a 'deposit' instruction into a 'deploy_protocol_liquidity' instruction
+/// signed by the admin.
+pub fn deploy_protocol_liquidity(
+    deposit_instruction: Instruction,
+    admin_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = match SwapInstruction::unpack(&deposit_instruction.data)? {
+        SwapInstruction::Deposit(deposit_data) => {
+            AdminInstruction::DeployProtocolLiquidity(deposit_data).pack()
+        }
+        _ => return Err(SwapError::InvalidInstruction.into()),
+    };
+    let mut accounts = deposit_instruction.accounts;
+    accounts.push(AccountMeta::new_readonly(*admin_pubkey, true));
+
+    Ok(Instruction {
+        program_id: deposit_instruction.program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Turns a 'withdraw' instruction into a 'withdraw_protocol_liquidity' instruction
+/// signed by the admin.
+pub fn withdraw_protocol_liquidity(
+    withdraw_instruction: Instruction,
+    admin_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = match SwapInstruction::unpack(&withdraw_instruction.data)? {
+        SwapInstruction::Withdraw(withdraw_data) => {
+            AdminInstruction::WithdrawProtocolLiquidity(withdraw_data).pack()
+        }
+        _ => return Err(SwapError::InvalidInstruction.into()),
+    };
+    let mut accounts = withdraw_instruction.accounts;
+    accounts.push(AccountMeta::new_readonly(*admin_pubkey, true));
+
+    Ok(Instruction {
+        program_id: withdraw_instruction.program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'update_lp_metadata' instruction
+pub fn update_lp_metadata(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    metadata_pubkey: &Pubkey,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Result<Instruction, ProgramError> {
+    let data =
+        AdminInstruction::UpdateLpMetadata(UpdateLpMetadataData { name, symbol, uri }).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new(*metadata_pubkey, false),
+        AccountMeta::new_readonly(metadata::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_liquidity_positions' instruction
+pub fn initialize_liquidity_positions(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    liquidity_vault_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::InitializeLiquidityPositions.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*liquidity_vault_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_reflection' instruction
+pub fn initialize_reflection(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    reflection_lp_vault_pubkey: &Pubkey,
+    reflection_vault_a_pubkey: &Pubkey,
+    reflection_vault_b_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::InitializeReflection.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*reflection_lp_vault_pubkey, false),
+        AccountMeta::new_readonly(*reflection_vault_a_pubkey, false),
+        AccountMeta::new_readonly(*reflection_vault_b_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_buyback' instruction
+pub fn initialize_buyback(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    buyback_vault_a_pubkey: &Pubkey,
+    buyback_vault_b_pubkey: &Pubkey,
+    buyback_mint_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::InitializeBuyback.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*buyback_vault_a_pubkey, false),
+        AccountMeta::new_readonly(*buyback_vault_b_pubkey, false),
+        AccountMeta::new_readonly(*buyback_mint_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'add_fee_exemption' instruction
+pub fn add_fee_exemption(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    fee_exemptions_pubkey: &Pubkey,
+    trader_pubkey: &Pubkey,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::AddFeeExemption(nonce).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*fee_exemptions_pubkey, false),
+        AccountMeta::new_readonly(*trader_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'remove_fee_exemption' instruction
+pub fn remove_fee_exemption(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    fee_exemptions_pubkey: &Pubkey,
+    trader_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::RemoveFeeExemption.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new(*fee_exemptions_pubkey, false),
+        AccountMeta::new_readonly(*trader_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_volatility_fee' instruction
+pub fn set_volatility_fee(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    volatility_ceiling: u64,
+    max_multiplier_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetVolatilityFee(SetVolatilityFeeData {
+        volatility_ceiling,
+        max_multiplier_bps,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_admin_fee_epoch_cap' instruction
+pub fn set_admin_fee_epoch_cap(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    cap_a: u64,
+    cap_b: u64,
+) -> Result<Instruction, ProgramError> {
+    let data =
+        AdminInstruction::SetAdminFeeEpochCap(SetAdminFeeEpochCapData { cap_a, cap_b }).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_price_bound' instruction
+pub fn set_price_bound(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    price_bound: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetPriceBound(SetPriceBoundData { price_bound }).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_farm' instruction
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_farm(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    farm_pubkey: &Pubkey,
+    stake_vault_pubkey: &Pubkey,
+    reward_vault_pubkey: &Pubkey,
+    emission_per_slot: u64,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::InitializeFarm(InitializeFarmData {
+        emission_per_slot,
+        nonce,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*farm_pubkey, false),
+        AccountMeta::new_readonly(*stake_vault_pubkey, false),
+        AccountMeta::new_readonly(*reward_vault_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'add_allowed_depositor' instruction
+pub fn add_allowed_depositor(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    deposit_allowlist_pubkey: &Pubkey,
+    depositor_pubkey: &Pubkey,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::AddAllowedDepositor(nonce).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, true),
+        AccountMeta::new(*deposit_allowlist_pubkey, false),
+        AccountMeta::new_readonly(*depositor_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'remove_allowed_depositor' instruction
+pub fn remove_allowed_depositor(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    deposit_allowlist_pubkey: &Pubkey,
+    depositor_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::RemoveAllowedDepositor.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new(*deposit_allowlist_pubkey, false),
+        AccountMeta::new_readonly(*depositor_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_deposit_allowlist' instruction
+pub fn set_deposit_allowlist(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    enabled: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetDepositAllowlist(enabled).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_oracle_guard' instruction
+pub fn set_oracle_guard(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    oracle_a_pubkey: &Pubkey,
+    oracle_b_pubkey: &Pubkey,
+    max_staleness_slots: u64,
+    max_confidence_bps: u16,
+    max_deviation_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetOracleGuard(SetOracleGuardData {
+        max_staleness_slots,
+        max_confidence_bps,
+        max_deviation_bps,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+        AccountMeta::new_readonly(*oracle_a_pubkey, false),
+        AccountMeta::new_readonly(*oracle_b_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'set_oracle_stale_policy' instruction
+pub fn set_oracle_stale_policy(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    stale_policy: OracleStalePolicy,
+    stale_band_bps: u16,
+) -> Result<Instruction, ProgramError> {
+    let data = AdminInstruction::SetOracleStalePolicy(SetOracleStalePolicyData {
+        stale_policy,
+        stale_band_bps,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*admin_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Turns an admin instruction built with a session key as admin into one signed
+/// through the admin session
+pub fn with_admin_session(
+    mut admin_instruction: Instruction,
+    admin_session_pubkey: &Pubkey,
+) -> Instruction {
+    admin_instruction
+        .accounts
+        .push(AccountMeta::new_readonly(*admin_session_pubkey, false));
+    admin_instruction
+        .accounts
+        .push(AccountMeta::new_readonly(clock::id(), false));
+    admin_instruction
+}
+
+/// Instructions supported by the SwapInfo program.
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+// Unpacked once per instruction, `Initialize` carries the whole fee schedule
+#[allow(clippy::large_enum_variant)]
+pub enum SwapInstruction {
+    ///   Initializes a new SwapInfo.
+    ///
+    ///   0. `[writable]` New Token-swap to create, at the `find_pool_address` of the token
+    ///      mints, `fee_tier` and `namespace`. Fails with `AlreadyInUse` if a pool was
+    ///      created there.
+    ///   1. `[]` $authority derived from `create_program_address(&[Token-swap account])`
+    ///   2. `[]` admin Account.
+    ///   3. `[]` admin_fee_a admin fee Account for token_a.
+    ///   4. `[]` admin_fee_b admin fee Account for token_b.
+    ///   5. `[]` token_a mint Account. A freeze authority must be acknowledged by
+    ///      `freeze_authority_acknowledged`.
+    ///   6. `[]` token_a Account. Must be non zero, not frozen, owned by $authority.
+    ///   7. `[]` token_b mint Account. A freeze authority must be acknowledged by
+    ///      `freeze_authority_acknowledged`.
+    ///   8. `[]` token_b Account. Must be non zero, not frozen, owned by $authority.
+    ///   9. `[writable]` Pool Token Mint. Must be empty, owned by $authority. Pool tokens
+    ///      are soulbound if $authority is its freeze authority: pool token accounts are
+    ///      kept frozen, and pool tokens can only be burned by the account owner.
+    ///   10. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
+    ///   11. `[]` Token program id, spl_token or Token-2022. Must own the token_a and token_b
+    ///       Accounts and the Pool Token Mint.
+    ///   12. `[]` Clock sysvar
+    ///   13. `[writable, signer]` Payer of the Token-swap rent, recorded as the pool creator
+    ///   14. `[]` System program
+    ///   15. `[]` Rent sysvar
+    ///   16. `[]` Optional access token Mint. Required if `access_window_slots` is non zero.
+    ///   17. `[]` Program data of the program, after the other accounts. Required in the
+    ///       canonical namespace, its upgrade authority signing as the payer.
+    Initialize(InitializeData),
+
+    ///   Swap the tokens in the pool. In the last slot of an order-flow auction batch,
+    ///   only the SOURCE Accounts of the best bidder of the batch may be swapped from.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` token_(A|B) SOURCE Account, amount is transferable by $authority,
+    ///   3. `[writable]` token_(A|B) Base Account to swap INTO.  Must be the SOURCE token.
+    ///   4. `[writable]` token_(A|B) Base Account to swap FROM.  Must be the DESTINATION token.
+    ///   5. `[writable]` token_(A|B) DESTINATION Account assigned to USER as the owner.
+    ///   6. `[writable]` token_(A|B) reflection fee Account. Must have same mint as the fee token,
+    ///      the SOURCE token if the fees are charged on input and the DESTINATION token otherwise.
+    ///      The reflection vault of the fee token once reflection positions are enabled.
+    ///   7. `[writable]` token_(A|B) buyback fee Account. Must have same mint as the fee token.
+    ///      The buyback vault of the fee token once buybacks are enabled.
+    ///   8. `[writable]` token_(A|B) marketing fee Account. Must have same mint as the fee token.
+    ///      Receives nothing once a marketing wallet is set, the fee accrues in the swap.
+    ///   9. `[writable]` token_(A|B) developer fee Account. Must have same mint as the fee token.
+    ///      The fee token vesting Account once developer fee vesting is initialized.
+    ///      Receives nothing once a developer wallet is set, the fee accrues in the swap.
+    ///   10. `[]` Token program id
+    ///   11. `[]` Clock sysvar
+    ///   12. `[writable]` Referrer token Account of the fee token, paid the host share of the
+    ///       admin fee. Must not be owned by the SOURCE owner. The swap program id when the
+    ///       swap has no referrer.
+    ///   13. `[writable]` Pool MINT account. Only when admin fees are taken in pool tokens.
+    ///   14. `[writable]` Pool token Account owned by the admin to credit with the admin fee.
+    ///       Only when admin fees are taken in pool tokens.
+    ///   15. `[]` Optional access token Account owned by the SOURCE owner. Required during the
+    ///       launch access window. Passed as 13. when admin fees are not taken in pool tokens.
+    ///   16. `[]` Credential of the SOURCE owner, a token Account of the credential issuer
+    ///       mint or the Account of the issuer program at the `credential` address of the
+    ///       owner. Only when the pool requires one.
+    ///   17. `[]` SOURCE and DESTINATION token Mints, after the other accounts. Required for
+    ///       Token-2022 mints charging transfer fees, `minimum_amount_out` then applies to the
+    ///       amount received after the fee.
+    ///   18. `[]` Fee exemptions of the swap, after the other accounts. The trade fee is
+    ///       waived when the SOURCE owner is exempt.
+    ///   19. `[writable]` Optional referral accrual of the swap, after the other accounts.
+    ///       Accrues the host share of the admin fee and the volume swapped instead of the
+    ///       referrer token Account. Its referrer must not be the SOURCE owner.
+    ///   20. `[]` Pyth price accounts of token A and B, after the other accounts. Only when
+    ///       the pool has an oracle guard.
+    Swap(SwapData),
+
+    ///   Deposit some tokens into the pool.  The output is a "pool" token representing ownership
+    ///   into the pool. Inputs are converted to the current ratio.
+    ///   When both amounts cover the pool ratio cost of the minted pool tokens, only
+    ///   that cost is taken and the remainder stays in the source accounts.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` token_a $authority can transfer amount,
+    ///   3. `[writable]` token_b $authority can transfer amount,
+    ///   4. `[writable]` token_a Base Account to deposit into.
+    ///   5. `[writable]` token_b Base Account to deposit into.
+    ///   6. `[writable]` Pool MINT account, $authority is the owner.
+    ///   7. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
+    ///   8. `[]` Token program id
+    ///   9. `[]` Clock sysvar
+    ///   10. `[]` Credential of the token_a owner, a token Account of the credential issuer
+    ///       mint or the Account of the issuer program at the `credential` address of the
+    ///       owner. Only when the pool requires one.
+    ///   11. `[]` token_a and token_b Mints, after the other accounts. Required for Token-2022
+    ///       mints charging transfer fees, pool tokens are then minted for the amounts received
+    ///       after the fee.
+    ///   12. `[]` Deposit allowlist of the pool, after the other accounts. Only when the pool
+    ///       restricts its deposits.
+    Deposit(DepositData),
+
+    ///   Withdraw tokens from the pool at the current ratio.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` Pool mint account, $authority is the owner
+    ///   3. `[writable]` SOURCE Pool account, amount is transferable by $authority.
+    ///   4. `[writable]` token_a Swap Account to withdraw FROM.
+    ///   5. `[writable]` token_b Swap Account to withdraw FROM.
+    ///   6. `[writable]` token_a user Account to credit.
+    ///   7. `[writable]` token_b user Account to credit.
+    ///   8. `[writable]` admin_fee_a admin fee Account for token_a.
+    ///   9. `[writable]` admin_fee_b admin fee Account for token_b.
+    ///   10. `[]` Token program id
+    ///   11. `[]` Clock sysvar
+    ///   12. `[signer]` Owner of the SOURCE Pool account, burning the pool tokens instead of
+    ///       $authority. Only for soulbound pool tokens.
+    ///   13. `[signer]` Custodian. Only for withdrawals above the custodian threshold.
+    ///   14. `[]` token_a and token_b Mints, after the other accounts. Required for Token-2022
+    ///       mints charging transfer fees, the minimum amounts then apply to the amounts
+    ///       received after the fee.
+    Withdraw(WithdrawData),
+
+    ///   Withdraw one token from the pool at the current ratio.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` Pool mint account, $authority is the owner
+    ///   3. `[writable]` SOURCE Pool account, amount is transferable by $authority.
+    ///   4. `[writable]` token_(A|B) BASE token Swap Account to withdraw FROM.
+    ///   5. `[writable]` token_(A|B) QUOTE token Swap Account to exchange to base token.
+    ///   6. `[writable]` token_(A|B) BASE token user Account to credit.
+    ///   7. `[writable]` token_(A|B) admin fee Account. Must have same mint as BASE token.
+    ///   8. `[]` Token program id
+    ///   9. `[]` Clock sysvar
+    ///   10. `[signer]` Owner of the SOURCE Pool account, burning the pool tokens instead of
+    ///       $authority. Only for soulbound pool tokens.
+    ///   11. `[signer]` Custodian. Only for withdrawals above the custodian threshold.
+    ///   12. `[]` BASE token Mint, after the other accounts. Required for Token-2022 mints
+    ///       charging transfer fees, `minimum_token_amount` then applies to the amount
+    ///       received after the fee.
+    WithdrawOne(WithdrawOneData),
+
+    ///   Swap the tokens in the pool and record the trade in a receipt account.
+    ///
+    ///   0. `[writable]` Receipt account, program address derived from
+    ///      `["receipt", Token-swap, receipt owner, receipt id]`. Must not exist yet.
+    ///   1. `[writable, signer]` Receipt owner, pays for the receipt account.
+    ///   2. `[]` System program id
+    ///   3. `[]` Rent sysvar
+    ///   4. .. The accounts of [Swap](enum.SwapInstruction.html#variant.Swap).
+    SwapWithReceipt(SwapWithReceiptData),
+
+    ///   Close a swap receipt, returning its rent to the owner.
+    ///
+    ///   0. `[writable]` Receipt account
+    ///   1. `[signer]` Receipt owner
+    ///   2. `[writable]` Account to credit with the receipt lamports
+    CloseSwapReceipt,
+
+    ///   Transfer the accrued admin trade fees to the admin fee accounts. Anyone may
+    ///   call it and receives the harvest tip out of the harvested fees.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` token_a Swap Account to harvest FROM.
+    ///   3. `[writable]` token_b Swap Account to harvest FROM.
+    ///   4. `[writable]` admin_fee_a admin fee Account for token_a.
+    ///   5. `[writable]` admin_fee_b admin fee Account for token_b.
+    ///   6. `[writable]` token_a Account to credit with the caller tip.
+    ///   7. `[writable]` token_b Account to credit with the caller tip.
+    ///   8. `[]` Token program id
+    ///   9. `[writable]` token_a veLP fee vault, if vote-escrow is enabled.
+    ///   10. `[writable]` token_b veLP fee vault, if vote-escrow is enabled.
+    ///   11. `[]` Clock sysvar, if vote-escrow is enabled.
+    HarvestAdminFees,
+
+    ///   Commit the reflection fees held in a vault as the next reflection distribution.
+    ///   Only the reflection distributor may commit.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Reflection distributor, pays for the distribution account.
+    ///   3. `[writable]` Distribution account, program address derived from
+    ///      `["reflection_distribution", Token-swap, epoch]`. Must not exist yet.
+    ///   4. `[]` Vault token Account owned by $authority holding the reflection fees.
+    ///   5. `[]` System program id
+    ///   6. `[]` Rent sysvar
+    CommitReflectionRoot(CommitReflectionRootData),
+
+    ///   Claim a reflection entitlement of a distribution with its merkle proof.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` Distribution account
+    ///   3. `[writable]` Vault token Account of the distribution.
+    ///   4. `[writable, signer]` Claimant, pays for the claim account.
+    ///   5. `[writable]` Claim account, program address derived from
+    ///      `["reflection_claim", distribution, index]`. Must not exist yet.
+    ///   6. `[writable]` Token Account to credit with the entitlement.
+    ///   7. `[]` System program id
+    ///   8. `[]` Rent sysvar
+    ///   9. `[]` Token program id
+    ClaimReflectionWithProof(ClaimReflectionData),
+
+    ///   Claim the developer fees vested so far to the developer wallet.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Developer wallet
+    ///   3. `[writable]` token_a vesting Account
+    ///   4. `[writable]` token_b vesting Account
+    ///   5. `[writable]` token_a Account to credit with the vested fees.
+    ///   6. `[writable]` token_b Account to credit with the vested fees.
+    ///   7. `[]` Token program id
+    ///   8. `[]` Clock sysvar
+    ClaimVestedDevFees,
+
+    ///   Deposit pool tokens into the governance vault, crediting the voter weight
+    ///   record of the owner in the governance realm. See the `governance` module.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Owner, pays for the deposit and record accounts.
+    ///   3. `[writable]` SOURCE pool token Account, amount is transferable by $authority.
+    ///   4. `[writable]` Governance vault Account
+    ///   5. `[writable]` Governance deposit account, program address derived from
+    ///      `["governance_deposit", Token-swap, owner]`. Created on the first deposit.
+    ///   6. `[writable]` Voter weight record, program address derived from
+    ///      `["voter-weight-record", realm, pool MINT, owner]`. Created on the first deposit.
+    ///   7. `[]` System program id
+    ///   8. `[]` Rent sysvar
+    ///   9. `[]` Token program id
+    ///   10. `[]` Clock sysvar
+    DepositGovernanceTokens(DepositGovernanceTokensData),
+
+    ///   Withdraw pool tokens from the governance vault once the deposit lockup
+    ///   elapsed, debiting the voter weight record of the owner.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Owner
+    ///   3. `[writable]` Governance deposit account
+    ///   4. `[writable]` Voter weight record
+    ///   5. `[writable]` Governance vault Account
+    ///   6. `[writable]` Pool token Account to credit.
+    ///   7. `[]` Token program id
+    ///   8. `[]` Clock sysvar
+    WithdrawGovernanceTokens(u64),
+
+    ///   Withdraw tokens from the pool at the current ratio over several
+    ///   transactions. Burns the pool tokens and records the tokens owed in a
+    ///   withdraw continuation, which are excluded from the pool until paid out
+    ///   by `ContinueWithdraw`.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Owner, pays for the withdraw continuation.
+    ///   3. `[writable]` Pool mint account, $authority is the owner
+    ///   4. `[writable]` SOURCE Pool account, amount is transferable by $authority. The
+    ///      owner burns soulbound pool tokens instead.
+    ///   5. `[]` token_a Swap Account to withdraw FROM.
+    ///   6. `[]` token_b Swap Account to withdraw FROM.
+    ///   7. `[]` token_a user Account to credit.
+    ///   8. `[]` token_b user Account to credit.
+    ///   9. `[writable]` Withdraw continuation, program address derived from
+    ///      `["withdraw_continuation", Token-swap, owner]`. Must not exist yet.
+    ///   10. `[]` Token program id
+    ///   11. `[]` Clock sysvar
+    ///   12. `[]` System program id
+    ///   13. `[]` Rent sysvar
+    ///   14. `[signer]` Custodian. Only for withdrawals above the custodian threshold.
+    BeginWithdraw(BeginWithdrawData),
+
+    ///   Pay out the token of a withdraw continuation held by the given swap
+    ///   account. Can be called by anyone. The continuation is closed once both
+    ///   tokens are paid out.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` Withdraw continuation
+    ///   3. `[writable]` Owner of the withdraw continuation, receives the rent back.
+    ///   4. `[writable]` token_(A|B) Swap Account to withdraw FROM.
+    ///   5. `[writable]` token_(A|B) user Account recorded in the continuation.
+    ///   6. `[writable]` admin fee Account for the token.
+    ///   7. `[]` Token program id
+    ContinueWithdraw,
+
+    ///   Log a single line summarizing the pool: reserves, pool token supply,
+    ///   virtual price, accrued fees and the hash of the pool parameters. Does
+    ///   not modify any account.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` token_a Swap Account
+    ///   2. `[]` token_b Swap Account
+    ///   3. `[]` Pool mint account
+    ///   4. `[]` Clock sysvar
+    EmitPoolSummary,
+
+    ///   Lock pool tokens in the vote-escrow for 1 week to 4 years, receiving a
+    ///   veLP balance proportional to the amount and lock duration. veLP lockers
+    ///   share the fees distributed at the end of each epoch pro-rata.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Owner, pays for the vote-escrow lock.
+    ///   3. `[writable]` SOURCE pool token Account, amount is transferable by $authority.
+    ///   4. `[writable]` Vote-escrow pool token vault Account
+    ///   5. `[writable]` Vote-escrow lock, program address derived from
+    ///      `["ve_lock", Token-swap, owner]`. Must not exist yet.
+    ///   6. `[]` System program id
+    ///   7. `[]` Rent sysvar
+    ///   8. `[]` Token program id
+    ///   9. `[]` Clock sysvar
+    CreateVeLock(CreateVeLockData),
+
+    ///   Claim the fees distributed to a vote-escrow lock so far.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Owner
+    ///   3. `[writable]` Vote-escrow lock
+    ///   4. `[writable]` token_a veLP fee vault
+    ///   5. `[writable]` token_b veLP fee vault
+    ///   6. `[writable]` token_a Account to credit with the fees.
+    ///   7. `[writable]` token_b Account to credit with the fees.
+    ///   8. `[]` Token program id
+    ///   9. `[]` Clock sysvar
+    ClaimVeFees,
+
+    ///   Withdraw the pool tokens of an expired vote-escrow lock without gauge votes
+    ///   along with its unclaimed fees, and close the lock returning its rent to the
+    ///   owner.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Owner
+    ///   3. `[writable]` Vote-escrow lock
+    ///   4. `[writable]` Vote-escrow pool token vault Account
+    ///   5. `[writable]` Pool token Account to credit.
+    ///   6. `[writable]` token_a veLP fee vault
+    ///   7. `[writable]` token_b veLP fee vault
+    ///   8. `[writable]` token_a Account to credit with the fees.
+    ///   9. `[writable]` token_b Account to credit with the fees.
+    ///   10. `[]` Token program id
+    ///   11. `[]` Clock sysvar
+    WithdrawVeLock,
+
+    ///   Vote a share of the veLP balance of a vote-escrow lock for a gauge, replacing
+    ///   the previous vote of the lock for it. The votes of a lock may use up to its
+    ///   whole veLP balance, and take effect from the next epoch. Only unexpired locks
+    ///   may vote, a zero share removes the vote.
+    ///
+    ///   0. `[]` Token-swap, the vote-escrow of the lock.
+    ///   1. `[writable, signer]` Owner, pays for the gauge vote.
+    ///   2. `[writable]` Vote-escrow lock
+    ///   3. `[writable]` Gauge controller of Token-swap
+    ///   4. `[writable]` Gauge
+    ///   5. `[writable]` Gauge vote, program address derived from
+    ///      `["gauge_vote", gauge, owner]`. Created on the first vote.
+    ///   6. `[]` System program id
+    ///   7. `[]` Rent sysvar
+    ///   8. `[]` Clock sysvar
+    VoteGaugeWeight(VoteGaugeWeightData),
+
+    ///   Checkpoint a gauge, allocating it its share of the emission of the current
+    ///   epoch at its first checkpoint in the epoch. Can be called by anyone, and
+    ///   should be cranked every epoch for every gauge.
+    ///
+    ///   0. `[writable]` Gauge controller
+    ///   1. `[writable]` Gauge
+    ///   2. `[]` Clock sysvar
+    CheckpointGauge,
+
+    ///   Bid for the right to backrun the current order-flow auction batch: the best
+    ///   bidder of a batch is the only one allowed to swap in its last slot. The bid
+    ///   must exceed the best bid of the batch, which is refunded. The best bid is
+    ///   credited to the pool once the batch ends. Bids are closed in the last slot
+    ///   of the batch.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` SOURCE Account of the bid token, amount is transferable by
+    ///      $authority. Its owner is the bidder.
+    ///   3. `[writable]` token_(A|B) Base Account the bids are paid into.
+    ///   4. `[writable]` SOURCE Account of the best bid of the batch, refunded. Ignored
+    ///      when the batch has no bid yet.
+    ///   5. `[]` Token program id
+    ///   6. `[]` Clock sysvar
+    BidOrderFlow(u64),
+
+    ///   Computes a swap like `Swap`, with the same accounts, and logs its result as
+    ///   a `SwapQuote` line, then always fails with `SwapSimulated` so that nothing
+    ///   changes. Meant to be run through `simulateTransaction` for an exact quote.
+    SimulateSwap(SwapData),
+
+    ///   Return tokens of the insurance fund to the pool reserves, compensating the
+    ///   LPs for a shortfall, and log an `InsuranceCover` line. Signed by the
+    ///   insurance authority, through an executed proposal of the governance realm.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[signer]` Insurance authority
+    CoverShortfall(CoverShortfallData),
+
+    ///   Initializes a new SwapInfo like `Initialize`, copying the fees, the fee wallets and
+    ///   the current Amp. Coefficient of an existing Token-swap, without an access window. Meant
+    ///   to try new parameters next to a live pool. The freeze authorities of the mints are
+    ///   acknowledged by the instruction like `Initialize`, never inherited from the copy.
+    ///
+    ///   0-15. The accounts of `Initialize`, the Token-swap at the `find_pool_address` of the
+    ///      fee tier of the copy: the tier of the copied pool if `fee_tier` is `Custom`.
+    ///   16. `[]` Token-swap to copy the parameters of.
+    ///   17. `[writable]` Optional global stats.
+    ///   18. `[]` Program data of the program, after the other accounts. Required in the
+    ///       canonical namespace, its upgrade authority signing as the payer.
+    ClonePool(ClonePoolData),
+
+    ///   Swap the whole balance of a token account, when below the dust threshold of
+    ///   the pool, into the other token free of fees. Only `MAX_DUST_SWEEPS_PER_SLOT`
+    ///   sweeps are allowed in a slot.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` token_(A|B) SOURCE Account, amount is transferable by $authority,
+    ///   3. `[writable]` token_(A|B) Base Account to swap INTO.  Must be the SOURCE token.
+    ///   4. `[writable]` token_(A|B) Base Account to swap FROM.  Must be the DESTINATION token.
+    ///   5. `[writable]` token_(A|B) DESTINATION Account assigned to USER as the owner.
+    ///   6. `[]` Token program id
+    ///   7. `[]` Clock sysvar
+    SweepDust,
+
+    ///   Deposit pool tokens in the liquidity position of the owner, creating it on
+    ///   the first deposit. Pool tokens only add to the cumulative liquidity of the
+    ///   position from the time they are deposited.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Owner, pays for the liquidity position.
+    ///   3. `[writable]` SOURCE pool token Account, amount is transferable by $authority.
+    ///   4. `[writable]` Liquidity position pool token vault Account
+    ///   5. `[writable]` Liquidity position, program address derived from
+    ///      `["liquidity_position", Token-swap, owner]`.
+    ///   6. `[]` System program id
+    ///   7. `[]` Rent sysvar
+    ///   8. `[]` Token program id
+    ///   9. `[]` Clock sysvar
+    DepositLiquidityPosition(DepositLiquidityPositionData),
+
+    ///   Withdraw pool tokens from the liquidity position of the owner. The position
+    ///   is kept along with its cumulative liquidity.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Owner
+    ///   3. `[writable]` Liquidity position
+    ///   4. `[writable]` Liquidity position pool token vault Account
+    ///   5. `[writable]` Pool token Account to credit.
+    ///   6. `[]` Token program id
+    ///   7. `[]` Clock sysvar
+    WithdrawLiquidityPosition(u64),
+
+    ///   Checkpoint a liquidity position and the liquidity of all positions of the
+    ///   swap up to the current time. Can be called by anyone, such as incentive
+    ///   programs observing the cumulative liquidity of the position.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[writable]` Liquidity position
+    ///   2. `[]` Clock sysvar
+    CheckpointLiquidityPosition,
+
+    ///   Log the trade fees of the previous Solana epoch per bucket, and reset the
+    ///   fee counters for the current one. Can be called by anyone, and should be
+    ///   cranked at each epoch boundary. Does nothing if the fees of the previous
+    ///   epoch were already reported, the first swap of an epoch reports them too.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` Clock sysvar
+    ReportEpochFees,
+
+    ///   Swap the smallest amount of SOURCE token paying out `amount_out` of the
+    ///   DESTINATION token, the rounding of which may pay out slightly more. Takes
+    ///   the accounts of `Swap`.
+    SwapOut(SwapOutData),
+
+    ///   Log a `PoolStatus` line, a status word of the conditions restricting the
+    ///   pool in the current slot, for bots to poll its health cheaply. Does not
+    ///   modify any account.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` Clock sysvar
+    GetPoolStatus,
+
+    ///   Swap through several pools in one instruction, each leg swapping the whole
+    ///   output of the previous one. Only the output of the last leg is bounded by
+    ///   `minimum_amount_out`.
+    ///
+    ///   0. `[]` Token program id
+    ///   1. `[]` Clock sysvar
+    ///   2. `[writable]` token_(A|B) SOURCE Account of the first leg, amount is
+    ///      transferable by the authority of its pool
+    ///   3. ..3+N `[]` Accounts of each leg: the accounts of `Swap` but for the
+    ///      source, the token program and the clock sysvar. The DESTINATION Account
+    ///      of a leg is the SOURCE Account of the next one, and must let the
+    ///      authority of the next pool transfer its output.
+    Route(RouteData),
+
+    ///   Check that the admin fee accounts exist, are token accounts and hold the
+    ///   tokens of their fees. The admin fees of a failing account are escrowed in
+    ///   the swap token account, until the check passes again or the admin sets
+    ///   another fee account, and a `FeeDestinationAlert` line is logged. Can be
+    ///   called by anyone.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` admin_fee_a admin fee Account for token_a.
+    ///   2. `[]` admin_fee_b admin fee Account for token_b.
+    CheckFeeDestinations,
+
+    ///   Escrow SOURCE tokens in a TWAP order, to be swapped into the DESTINATION
+    ///   Account in `slice_count` equal slices by `ExecuteTwapSlice`.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Owner, pays for the TWAP order.
+    ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by $authority.
+    ///   4. `[writable]` Escrow token Account of the SOURCE token, owned by the TWAP order,
+    ///      without delegate nor close authority.
+    ///   5. `[]` token_(A|B) DESTINATION Account credited with the slices.
+    ///   6. `[writable]` TWAP order, program address derived from
+    ///      `["twap_order", Token-swap, owner, order id]`. Must not exist yet.
+    ///   7. `[]` System program id
+    ///   8. `[]` Rent sysvar
+    ///   9. `[]` Token program id
+    ///   10. `[]` Clock sysvar
+    CreateTwapOrder(CreateTwapOrderData),
+
+    ///   Swap the next slice of a TWAP order, once `slice_interval_slots` passed since
+    ///   the previous one. Fails with `ExceededSlippage` if the slice receives less than
+    ///   its amount at the pool TWAP since the previous slice, minus the maximum
+    ///   deviation. Can be called by anyone. The order owns the SOURCE, which has to
+    ///   hold the credential of pools requiring one.
+    ///
+    ///   0. `[writable]` TWAP order
+    ///   1. .. The accounts of [Swap](enum.SwapInstruction.html#variant.Swap), with the
+    ///      order escrow as SOURCE and the order DESTINATION Account.
+    ExecuteTwapSlice,
+
+    ///   Cancel the remaining slices of a TWAP order, refunding the escrow, and close
+    ///   the order and its escrow returning their rent to the owner. Also closes
+    ///   orders all slices of which executed.
+    ///
+    ///   0. `[writable]` TWAP order
+    ///   1. `[writable, signer]` Owner
+    ///   2. `[writable]` Escrow token Account of the order
+    ///   3. `[writable]` Token Account of the escrow token to refund.
+    ///   4. `[]` Token program id
+    CancelTwapOrder,
+
+    ///   Escrow SOURCE tokens in a trigger, to be swapped into the DESTINATION Account
+    ///   by `ExecuteTrigger` once the oracle price crosses the trigger price. The
+    ///   oracle price is the TWAP of token A in token B of the oracle Token-swap,
+    ///   which may be the swapped one and needs the dynamic amp to be observed.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Owner, pays for the trigger and its bounty.
+    ///   3. `[writable]` token_(A|B) SOURCE Account, amount is transferable by $authority.
+    ///   4. `[writable]` Escrow token Account of the SOURCE token, owned by the trigger,
+    ///      without delegate nor close authority.
+    ///   5. `[]` token_(A|B) DESTINATION Account credited with the swap.
+    ///   6. `[]` Oracle Token-swap
+    ///   7. `[writable]` Trigger, program address derived from
+    ///      `["trigger", Token-swap, owner, trigger id]`. Must not exist yet.
+    ///   8. `[]` System program id
+    ///   9. `[]` Rent sysvar
+    ///   10. `[]` Token program id
+    CreateTrigger(CreateTriggerData),
+
+    ///   Swap the escrow of a trigger the oracle price crossed the trigger price of,
+    ///   and close the trigger and its escrow. Fails with `TriggerNotReached` before,
+    ///   and with `OracleStale` if the oracle TWAP was not observed for a whole TWAP
+    ///   window. Can be called by anyone, for the bounty. The trigger owns the SOURCE,
+    ///   which has to hold the credential of pools requiring one.
+    ///
+    ///   0. `[writable]` Trigger
+    ///   1. `[]` Oracle Token-swap
+    ///   2. `[writable]` Owner of the trigger, receives the rent back.
+    ///   3. `[writable]` Cranker, receives the bounty.
+    ///   4. .. The accounts of [Swap](enum.SwapInstruction.html#variant.Swap), with the
+    ///      trigger escrow as SOURCE and the trigger DESTINATION Account.
+    ExecuteTrigger,
+
+    ///   Cancel a trigger, refunding the escrow, and close the trigger and its
+    ///   escrow returning their rent and the bounty to the owner.
+    ///
+    ///   0. `[writable]` Trigger
+    ///   1. `[writable, signer]` Owner
+    ///   2. `[writable]` Escrow token Account of the trigger
+    ///   3. `[writable]` Token Account of the escrow token to refund.
+    ///   4. `[]` Token program id
+    CancelTrigger,
+
+    ///   Escrow token A and B in a drip deposit, to be deposited into the pool in
+    ///   `tranche_count` equal tranches by `DepositDripTranche`.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Owner, pays for the drip deposit.
+    ///   3. `[writable]` token_a SOURCE Account, amount is transferable by $authority.
+    ///   4. `[writable]` token_b SOURCE Account, amount is transferable by $authority.
+    ///   5. `[writable]` Escrow token_a Account, owned by the drip deposit, without
+    ///      delegate nor close authority.
+    ///   6. `[writable]` Escrow token_b Account, owned by the drip deposit, without
+    ///      delegate nor close authority.
+    ///   7. `[]` Pool Account credited with the tranches.
+    ///   8. `[writable]` Drip deposit, program address derived from
+    ///      `["drip_deposit", Token-swap, owner, drip id]`. Must not exist yet.
+    ///   9. `[]` System program id
+    ///   10. `[]` Rent sysvar
+    ///   11. `[]` Token program id
+    ///   12. `[]` Clock sysvar
+    CreateDripDeposit(CreateDripDepositData),
+
+    ///   Deposit the next tranche of a drip deposit, once `tranche_interval_slots`
+    ///   passed since the previous one. Amounts a tranche doesn't take at the pool
+    ///   ratio stay in the escrows. Can be called by anyone. The drip deposit owns the
+    ///   token_a SOURCE, which has to hold the credential of pools requiring one.
+    ///
+    ///   0. `[writable]` Drip deposit
+    ///   1. .. The accounts of [Deposit](enum.SwapInstruction.html#variant.Deposit),
+    ///      with the drip deposit escrows as SOURCE and its Pool Account.
+    DepositDripTranche,
+
+    ///   Cancel the remaining tranches of a drip deposit, refunding the escrows, and
+    ///   close the drip deposit and its escrows returning their rent to the owner.
+    ///   Also closes drip deposits all tranches of which were deposited.
+    ///
+    ///   0. `[writable]` Drip deposit
+    ///   1. `[writable, signer]` Owner
+    ///   2. `[writable]` Escrow token_a Account of the drip deposit
+    ///   3. `[writable]` Escrow token_b Account of the drip deposit
+    ///   4. `[writable]` token_a Account to refund.
+    ///   5. `[writable]` token_b Account to refund.
+    ///   6. `[]` Token program id
+    CancelDripDeposit,
+
+    ///   Create the program-wide global stats, counting the pools from then on.
+    ///   The stats are best effort: pool instructions update them only when the
+    ///   global stats account is passed after their accounts: Initialize,
+    ///   ClonePool, Swap, SwapOut, Route, Deposit, DepositOne, Withdraw,
+    ///   WithdrawOne and WithdrawImbalanced. Can be called by anyone, takes the
+    ///   nonce of the program address.
+    ///
+    ///   0. `[writable]` Global stats, program address derived from
+    ///      `["global_stats"]`. Must not exist yet.
+    ///   1. `[writable, signer]` Payer of the global stats account.
+    ///   2. `[]` System program id
+    ///   3. `[]` Rent sysvar
+    InitializeGlobalStats(u8),
+
+    ///   Deposit pool tokens in the reflection position of the owner, creating it on
+    ///   the first deposit. Pool tokens only share the reflection fees accrued from
+    ///   the time they are deposited.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable, signer]` Owner, pays for the reflection position.
+    ///   3. `[writable]` SOURCE pool token Account, amount is transferable by $authority.
+    ///   4. `[writable]` Reflection position pool token vault Account
+    ///   5. `[writable]` Reflection position, program address derived from
+    ///      `["reflection_position", Token-swap, owner]`.
+    ///   6. `[]` System program id
+    ///   7. `[]` Rent sysvar
+    ///   8. `[]` Token program id
+    DepositReflectionPosition(DepositReflectionPositionData),
+
+    ///   Withdraw pool tokens from the reflection position of the owner. The position
+    ///   is kept along with the reflection fees it is owed.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Owner
+    ///   3. `[writable]` Reflection position
+    ///   4. `[writable]` Reflection position pool token vault Account
+    ///   5. `[writable]` Pool token Account to credit.
+    ///   6. `[]` Token program id
+    WithdrawReflectionPosition(u64),
+
+    ///   Claim the reflection fees accrued to a reflection position so far.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Owner
+    ///   3. `[writable]` Reflection position
+    ///   4. `[writable]` token_a reflection vault
+    ///   5. `[writable]` token_b reflection vault
+    ///   6. `[writable]` token_a Account to credit with the fees.
+    ///   7. `[writable]` token_b Account to credit with the fees.
+    ///   8. `[]` Token program id
+    ClaimReflection,
+
+    ///   Swap the buyback fees accrued in the other token of the pool into the token
+    ///   bought back, free of trade fees, and burn the buyback vault of that token.
+    ///   Anyone may execute a buyback, bounded by `minimum_amount_out`.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` Buyback vault of the other token, swapped from.
+    ///   3. `[writable]` Buyback vault of the token bought back, burned.
+    ///   4. `[writable]` Base Account of the other token to swap INTO.
+    ///   5. `[writable]` Base Account of the token bought back to swap FROM.
+    ///   6. `[writable]` MINT of the token bought back
+    ///   7. `[]` Token program id
+    ///   8. `[]` Clock sysvar
+    ExecuteBuyback(u64),
+
+    ///   Withdraw the marketing fees accrued in the swap token accounts to token accounts
+    ///   of the marketing wallet.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Marketing wallet
+    ///   3. `[writable]` token_a Base Account to withdraw FROM.
+    ///   4. `[writable]` token_b Base Account to withdraw FROM.
+    ///   5. `[writable]` token_a Account owned by the marketing wallet to credit.
+    ///   6. `[writable]` token_b Account owned by the marketing wallet to credit.
+    ///   7. `[]` Token program id
+    WithdrawMarketingFees,
+
+    ///   Withdraw the developer fees accrued in the swap token accounts to token accounts
+    ///   of the developer wallet.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[signer]` Developer wallet
+    ///   3. `[writable]` token_a Base Account to withdraw FROM.
+    ///   4. `[writable]` token_b Base Account to withdraw FROM.
+    ///   5. `[writable]` token_a Account owned by the developer wallet to credit.
+    ///   6. `[writable]` token_b Account owned by the developer wallet to credit.
+    ///   7. `[]` Token program id
+    WithdrawDeveloperFees,
+
+    ///   Create the referral accrual of a referrer in the pool. Swaps passing it
+    ///   after their accounts accrue their host fee to it, in the swap token
+    ///   accounts, instead of transferring it to a referrer token account. Takes
+    ///   the nonce of the program address.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[writable]` Referral accrual, program address derived from
+    ///      `["referral_accrual", swap, referrer]`. Must not exist yet.
+    ///   2. `[writable, signer]` Referrer, pays for the referral accrual account.
+    ///   3. `[]` System program id
+    ///   4. `[]` Rent sysvar
+    CreateReferralAccrual(u8),
+
+    ///   Claim the host fees accrued to the referral accrual of the referrer, from
+    ///   the swap token accounts to token accounts of the referrer.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` Referral accrual
+    ///   3. `[signer]` Referrer
+    ///   4. `[writable]` token_a Base Account to withdraw FROM.
+    ///   5. `[writable]` token_b Base Account to withdraw FROM.
+    ///   6. `[writable]` token_a Account owned by the referrer to credit.
+    ///   7. `[writable]` token_b Account owned by the referrer to credit.
+    ///   8. `[]` Token program id
+    ClaimReferralFees,
+
+    ///   Deposit a single token into the pool. The pool tokens minted are charged the
+    ///   normalized trade fee on the part of the deposit implicitly swapped to the
+    ///   other token, as for an imbalanced Deposit.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` token_(A|B) SOURCE Account, amount is transferable by $authority.
+    ///   3. `[writable]` token_(A|B) BASE token Swap Account to deposit into.
+    ///   4. `[]` token_(A|B) QUOTE token Swap Account, the other token of the pool.
+    ///   5. `[writable]` Pool MINT account, $authority is the owner.
+    ///   6. `[writable]` Pool Account to deposit the generated tokens, user is the owner.
+    ///   7. `[]` Token program id
+    ///   8. `[]` Clock sysvar
+    ///   9. `[]` Credential of the SOURCE owner, a token Account of the credential issuer
+    ///      mint or the Account of the issuer program at the `credential` address of the
+    ///      owner. Only when the pool requires one.
+    ///   10. `[]` BASE token Mint, after the other accounts. Required for Token-2022 mints
+    ///       charging transfer fees, pool tokens are then minted for the amount received
+    ///       after the fee.
+    ///   11. `[]` Deposit allowlist of the pool, after the other accounts. Only when the pool
+    ///       restricts its deposits.
+    DepositOne(DepositOneData),
+
+    ///   Withdraw exact amounts of the tokens from the pool. The pool tokens burned are
+    ///   charged the normalized trade fee on the part of the withdrawal implicitly swapped
+    ///   from the other token, as for an imbalanced Deposit.
+    ///
+    ///   0. `[writable]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` Pool mint account, $authority is the owner
+    ///   3. `[writable]` SOURCE Pool account, amount is transferable by $authority.
+    ///   4. `[writable]` token_a Swap Account to withdraw FROM.
+    ///   5. `[writable]` token_b Swap Account to withdraw FROM.
+    ///   6. `[writable]` token_a user Account to credit.
+    ///   7. `[writable]` token_b user Account to credit.
+    ///   8. `[writable]` admin_fee_a admin fee Account for token_a.
+    ///   9. `[writable]` admin_fee_b admin fee Account for token_b.
+    ///   10. `[]` Token program id
+    ///   11. `[]` Clock sysvar
+    ///   12. `[signer]` Owner of the SOURCE Pool account, burning the pool tokens instead of
+    ///       $authority. Only for soulbound pool tokens.
+    ///   13. `[signer]` Custodian. Only for withdrawals above the custodian threshold.
+    ///   14. `[]` token_a and token_b Mints, after the other accounts. Required for Token-2022
+    ///       mints charging transfer fees, the amounts then leave the pool before the fee.
+    WithdrawImbalanced(WithdrawImbalancedData),
+
+    ///   Stake pool tokens in the farm, in the stake account of the owner, creating
+    ///   it on the first stake. Staked pool tokens only earn the rewards emitted from
+    ///   the slot they are staked. See the `farm` module.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` Farm
+    ///   3. `[writable, signer]` Owner, pays for the stake account.
+    ///   4. `[writable]` SOURCE pool token Account, amount is transferable by $authority.
+    ///   5. `[writable]` Stake vault of the farm
+    ///   6. `[]` Reward vault of the farm
+    ///   7. `[writable]` Stake account, program address derived from
+    ///      `["stake_account", Farm, owner]`.
+    ///   8. `[]` System program id
+    ///   9. `[]` Rent sysvar
+    ///   10. `[]` Clock sysvar
+    ///   11. `[]` Token program id
+    Stake(StakeData),
+
+    ///   Unstake pool tokens from the stake account of the owner. The stake account
+    ///   is kept along with the rewards it is owed.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` Farm
+    ///   3. `[signer]` Owner
+    ///   4. `[writable]` Stake account
+    ///   5. `[writable]` Stake vault of the farm
+    ///   6. `[]` Reward vault of the farm
+    ///   7. `[writable]` Pool token Account to credit.
+    ///   8. `[]` Clock sysvar
+    ///   9. `[]` Token program id
+    Unstake(u64),
+
+    ///   Harvest the rewards emitted to a stake account so far.
+    ///
+    ///   0. `[]` Token-swap
+    ///   1. `[]` $authority
+    ///   2. `[writable]` Farm
+    ///   3. `[signer]` Owner
+    ///   4. `[writable]` Stake account
+    ///   5. `[writable]` Reward vault of the farm
+    ///   6. `[writable]` Reward token Account to credit.
+    ///   7. `[]` Clock sysvar
+    ///   8. `[]` Token program id
+    HarvestRewards,
+}
+
+impl SwapInstruction {
+    /// Unpacks a byte buffer into a [SwapInstruction](enum.SwapInstruction.html).
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (&tag, rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
+        Ok(match tag {
+            0 => {
+                let (&nonce, rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let (amp_factor, rest) = unpack_u64(rest)?;
+                let (fees, rest) = unpack_fees(rest)?;
+                let (access_window_slots, rest) = unpack_u64(rest)?;
+                let (namespace, rest) = unpack_bytes32(rest)?;
+                let (&fee_tier, rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let fee_tier =
+                    FeeTier::try_from(fee_tier).map_err(|_| SwapError::InvalidInstruction)?;
+                let (marketing_wallet, rest) = unpack_bytes32(rest)?;
+                let (developer_wallet, rest) = unpack_bytes32(rest)?;
+                let (&curve_type, rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let curve_type =
+                    CurveType::from_u8(curve_type).ok_or(SwapError::InvalidInstruction)?;
+                let (price_bound, rest) = unpack_u64(rest)?;
+                let (creator_window_slots, rest) = unpack_u64(rest)?;
+                let freeze_authority_acknowledged = match rest.first() {
+                    Some(0) => false,
+                    Some(1) => true,
+                    _ => return Err(SwapError::InvalidInstruction.into()),
+                };
+                Self::Initialize(InitializeData {
+                    nonce,
+                    amp_factor,
+                    fees,
+                    access_window_slots,
+                    namespace,
+                    fee_tier,
+                    marketing_wallet: Pubkey::new_from_array(marketing_wallet),
+                    developer_wallet: Pubkey::new_from_array(developer_wallet),
+                    curve_type,
+                    price_bound,
+                    creator_window_slots,
+                    freeze_authority_acknowledged,
+                })
+            }
+            1 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, expected_sequence) = unpack_deadline_and_sequence(rest)?;
+                Self::Swap(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    expected_sequence,
+                })
+            }
+            2 => {
+                let (token_a_amount, rest) = unpack_u64(rest)?;
+                let (token_b_amount, rest) = unpack_u64(rest)?;
+                let (min_mint_amount, rest) = unpack_u64(rest)?;
+                let (deadline_slot, expected_sequence) = unpack_deadline_and_sequence(rest)?;
+                Self::Deposit(DepositData {
+                    token_a_amount,
+                    token_b_amount,
+                    min_mint_amount,
+                    deadline_slot,
+                    expected_sequence,
+                })
+            }
+            3 => {
+                let (pool_token_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_a_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_b_amount, rest) = unpack_u64(rest)?;
+                let (deadline_slot, expected_sequence) = unpack_deadline_and_sequence(rest)?;
+                Self::Withdraw(WithdrawData {
+                    pool_token_amount,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                    deadline_slot,
+                    expected_sequence,
+                })
+            }
+            4 => {
+                let (pool_token_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_amount, rest) = unpack_u64(rest)?;
+                let (deadline_slot, expected_sequence) = unpack_deadline_and_sequence(rest)?;
+                Self::WithdrawOne(WithdrawOneData {
+                    pool_token_amount,
+                    minimum_token_amount,
+                    deadline_slot,
+                    expected_sequence,
+                })
+            }
+            5 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (receipt_id, rest) = unpack_u64(rest)?;
+                let (&receipt_nonce, rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let expected_sequence = unpack_optional_u64(rest)?;
+                Self::SwapWithReceipt(SwapWithReceiptData {
+                    amount_in,
+                    minimum_amount_out,
+                    receipt_id,
+                    receipt_nonce,
+                    expected_sequence,
+                })
+            }
+            6 => Self::CloseSwapReceipt,
+            7 => Self::HarvestAdminFees,
+            8 => {
+                let (merkle_root, rest) = unpack_bytes32(rest)?;
+                let (total_amount, rest) = unpack_u64(rest)?;
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Self::CommitReflectionRoot(CommitReflectionRootData {
+                    merkle_root,
+                    total_amount,
+                    nonce,
+                })
+            }
+            9 => {
+                let (index, rest) = unpack_u64(rest)?;
+                let (amount, rest) = unpack_u64(rest)?;
+                let (&nonce, rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let (&proof_len, mut rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                if proof_len as usize > MAX_PROOF_LEN {
+                    return Err(SwapError::InvalidInstruction.into());
+                }
+                let mut proof = Vec::with_capacity(proof_len as usize);
+                for _ in 0..proof_len {
+                    let (node, next) = unpack_bytes32(rest)?;
+                    proof.push(node);
+                    rest = next;
+                }
+                Self::ClaimReflectionWithProof(ClaimReflectionData {
+                    index,
+                    amount,
+                    nonce,
+                    proof,
+                })
+            }
+            10 => Self::ClaimVestedDevFees,
+            11 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (&deposit_nonce, rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let (&record_nonce, _rest) =
+                    rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Self::DepositGovernanceTokens(DepositGovernanceTokensData {
+                    amount,
+                    deposit_nonce,
+                    record_nonce,
+                })
+            }
+            12 => {
+                let (amount, _rest) = unpack_u64(rest)?;
+                Self::WithdrawGovernanceTokens(amount)
+            }
+            13 => {
+                let (pool_token_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_a_amount, rest) = unpack_u64(rest)?;
+                let (minimum_token_b_amount, rest) = unpack_u64(rest)?;
+                let (&nonce, rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let expected_sequence = unpack_optional_u64(rest)?;
+                Self::BeginWithdraw(BeginWithdrawData {
+                    pool_token_amount,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                    nonce,
+                    expected_sequence,
+                })
+            }
+            14 => Self::ContinueWithdraw,
+            15 => Self::EmitPoolSummary,
+            16 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (lock_duration, rest) = unpack_i64(rest)?;
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Self::CreateVeLock(CreateVeLockData {
+                    amount,
+                    lock_duration,
+                    nonce,
+                })
+            }
+            17 => Self::ClaimVeFees,
+            18 => Self::WithdrawVeLock,
+            19 => {
+                let (weight_bps, rest) = unpack_u16(rest)?;
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Self::VoteGaugeWeight(VoteGaugeWeightData { weight_bps, nonce })
+            }
+            20 => Self::CheckpointGauge,
+            21 => {
+                let (amount, _rest) = unpack_u64(rest)?;
+                Self::BidOrderFlow(amount)
+            }
+            22 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (deadline_slot, expected_sequence) = unpack_deadline_and_sequence(rest)?;
+                Self::SimulateSwap(SwapData {
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    expected_sequence,
+                })
+            }
+            23 => {
+                let (amount_a, rest) = unpack_u64(rest)?;
+                let (amount_b, _rest) = unpack_u64(rest)?;
+                Self::CoverShortfall(CoverShortfallData { amount_a, amount_b })
+            }
+            24 => {
+                let (&nonce, rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                let (namespace, rest) = unpack_bytes32(rest)?;
+                let (fee_tier, rest) = rest
+                    .split_first()
+                    .and_then(|(&tier, rest)| Some((FeeTier::try_from(tier).ok()?, rest)))
+                    .ok_or(SwapError::InvalidInstruction)?;
+                let freeze_authority_acknowledged = match rest.first() {
+                    Some(0) => false,
+                    Some(1) => true,
+                    _ => return Err(SwapError::InvalidInstruction.into()),
+                };
+                Self::ClonePool(ClonePoolData {
+                    nonce,
+                    namespace,
+                    fee_tier,
+                    freeze_authority_acknowledged,
+                })
+            }
+            25 => Self::SweepDust,
+            26 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Self::DepositLiquidityPosition(DepositLiquidityPositionData { amount, nonce })
+            }
+            27 => {
+                let (amount, _rest) = unpack_u64(rest)?;
+                Self::WithdrawLiquidityPosition(amount)
+            }
+            28 => Self::CheckpointLiquidityPosition,
+            29 => Self::ReportEpochFees,
+            30 => {
+                let (amount_out, rest) = unpack_u64(rest)?;
+                let (maximum_amount_in, rest) = unpack_u64(rest)?;
+                let (deadline_slot, expected_sequence) = unpack_deadline_and_sequence(rest)?;
+                Self::SwapOut(SwapOutData {
+                    amount_out,
+                    maximum_amount_in,
+                    deadline_slot,
+                    expected_sequence,
+                })
+            }
+            31 => Self::GetPoolStatus,
+            32 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (&leg_count, rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                if leg_count == 0 || leg_count as usize > MAX_ROUTE_LEGS {
+                    return Err(SwapError::InvalidInstruction.into());
+                }
+                let leg_account_counts = rest
+                    .get(..leg_count as usize)
+                    .ok_or(SwapError::InvalidInstruction)?
+                    .to_vec();
+                Self::Route(RouteData {
+                    amount_in,
+                    minimum_amount_out,
+                    leg_account_counts,
+                })
+            }
+            33 => Self::CheckFeeDestinations,
+            34 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (slice_count, rest) = unpack_u16(rest)?;
+                let (slice_interval_slots, rest) = unpack_u64(rest)?;
+                let (max_price_deviation_bps, rest) = unpack_u16(rest)?;
+                let (order_id, rest) = unpack_u64(rest)?;
+                let &order_nonce = rest.first().ok_or(SwapError::InvalidInstruction)?;
+                Self::CreateTwapOrder(CreateTwapOrderData {
+                    amount_in,
+                    slice_count,
+                    slice_interval_slots,
+                    max_price_deviation_bps,
+                    order_id,
+                    order_nonce,
+                })
+            }
+            35 => Self::ExecuteTwapSlice,
+            36 => Self::CancelTwapOrder,
+            37 => {
+                let (amount_in, rest) = unpack_u64(rest)?;
+                let (minimum_amount_out, rest) = unpack_u64(rest)?;
+                let (trigger_price, rest) = unpack_u128(rest)?;
+                let (trigger_above, rest) = match rest.split_first() {
+                    Some((0, rest)) => (false, rest),
+                    Some((1, rest)) => (true, rest),
+                    _ => return Err(SwapError::InvalidInstruction.into()),
+                };
+                let (bounty, rest) = unpack_u64(rest)?;
+                let (trigger_id, rest) = unpack_u64(rest)?;
+                let &trigger_nonce = rest.first().ok_or(SwapError::InvalidInstruction)?;
+                Self::CreateTrigger(CreateTriggerData {
+                    amount_in,
+                    minimum_amount_out,
+                    trigger_price,
+                    trigger_above,
+                    bounty,
+                    trigger_id,
+                    trigger_nonce,
+                })
+            }
+            38 => Self::ExecuteTrigger,
+            39 => Self::CancelTrigger,
+            40 => {
+                let (amount_a, rest) = unpack_u64(rest)?;
+                let (amount_b, rest) = unpack_u64(rest)?;
+                let (tranche_count, rest) = unpack_u16(rest)?;
+                let (tranche_interval_slots, rest) = unpack_u64(rest)?;
+                let (drip_id, rest) = unpack_u64(rest)?;
+                let &drip_nonce = rest.first().ok_or(SwapError::InvalidInstruction)?;
+                Self::CreateDripDeposit(CreateDripDepositData {
+                    amount_a,
+                    amount_b,
+                    tranche_count,
+                    tranche_interval_slots,
+                    drip_id,
+                    drip_nonce,
+                })
+            }
+            41 => Self::DepositDripTranche,
+            42 => Self::CancelDripDeposit,
+            43 => {
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Self::InitializeGlobalStats(nonce)
+            }
+            44 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Self::DepositReflectionPosition(DepositReflectionPositionData { amount, nonce })
+            }
+            45 => {
+                let (amount, _rest) = unpack_u64(rest)?;
+                Self::WithdrawReflectionPosition(amount)
+            }
+            46 => Self::ClaimReflection,
+            47 => {
+                let (minimum_amount_out, _rest) = unpack_u64(rest)?;
+                Self::ExecuteBuyback(minimum_amount_out)
+            }
+            48 => Self::WithdrawMarketingFees,
+            49 => Self::WithdrawDeveloperFees,
+            50 => {
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Self::CreateReferralAccrual(nonce)
+            }
+            51 => Self::ClaimReferralFees,
+            52 => {
+                let (token_amount, rest) = unpack_u64(rest)?;
+                let (min_mint_amount, rest) = unpack_u64(rest)?;
+                let (deadline_slot, expected_sequence) = unpack_deadline_and_sequence(rest)?;
+                Self::DepositOne(DepositOneData {
+                    token_amount,
+                    min_mint_amount,
+                    deadline_slot,
+                    expected_sequence,
+                })
+            }
+            53 => {
+                let (token_a_amount, rest) = unpack_u64(rest)?;
+                let (token_b_amount, rest) = unpack_u64(rest)?;
+                let (maximum_pool_token_amount, rest) = unpack_u64(rest)?;
+                let (deadline_slot, expected_sequence) = unpack_deadline_and_sequence(rest)?;
+                Self::WithdrawImbalanced(WithdrawImbalancedData {
+                    token_a_amount,
+                    token_b_amount,
+                    maximum_pool_token_amount,
+                    deadline_slot,
+                    expected_sequence,
+                })
+            }
+            54 => {
+                let (amount, rest) = unpack_u64(rest)?;
+                let (&nonce, _rest) = rest.split_first().ok_or(SwapError::InvalidInstruction)?;
+                Self::Stake(StakeData { amount, nonce })
+            }
+            55 => {
+                let (amount, _rest) = unpack_u64(rest)?;
+                Self::Unstake(amount)
+            }
+            56 => Self::HarvestRewards,
+            _ => return Err(SwapError::InvalidInstruction.into()),
+        })
+    }
+
+    /// Packs a [SwapInstruction](enum.SwapInstruction.html) into a byte buffer.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(size_of::<Self>());
+        match *self {
+            Self::Initialize(InitializeData {
+                nonce,
+                amp_factor,
+                fees,
+                access_window_slots,
+                namespace,
+                fee_tier,
+                marketing_wallet,
+                developer_wallet,
+                curve_type,
+                price_bound,
+                creator_window_slots,
+                freeze_authority_acknowledged,
+            }) => {
+                buf.push(0);
+                buf.push(nonce);
+                buf.extend_from_slice(&amp_factor.to_le_bytes());
+                let mut fees_slice = [0u8; Fees::LEN];
+                Pack::pack_into_slice(&fees, &mut fees_slice[..]);
+                buf.extend_from_slice(&fees_slice);
+                buf.extend_from_slice(&access_window_slots.to_le_bytes());
+                buf.extend_from_slice(&namespace);
+                buf.push(fee_tier as u8);
+                buf.extend_from_slice(marketing_wallet.as_ref());
+                buf.extend_from_slice(developer_wallet.as_ref());
+                buf.push(curve_type as u8);
+                buf.extend_from_slice(&price_bound.to_le_bytes());
+                buf.extend_from_slice(&creator_window_slots.to_le_bytes());
+                buf.push(freeze_authority_acknowledged as u8);
+            }
+            Self::Swap(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                buf.push(1);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_deadline_and_sequence(&mut buf, deadline_slot, expected_sequence);
+            }
+            Self::Deposit(DepositData {
+                token_a_amount,
+                token_b_amount,
+                min_mint_amount,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                buf.push(2);
+                buf.extend_from_slice(&token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&token_b_amount.to_le_bytes());
+                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
+                pack_deadline_and_sequence(&mut buf, deadline_slot, expected_sequence);
+            }
+            Self::Withdraw(WithdrawData {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                buf.push(3);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+                pack_deadline_and_sequence(&mut buf, deadline_slot, expected_sequence);
+            }
+            Self::WithdrawOne(WithdrawOneData {
+                pool_token_amount,
+                minimum_token_amount,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                buf.push(4);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_amount.to_le_bytes());
+                pack_deadline_and_sequence(&mut buf, deadline_slot, expected_sequence);
+            }
+            Self::SwapWithReceipt(SwapWithReceiptData {
+                amount_in,
+                minimum_amount_out,
+                receipt_id,
+                receipt_nonce,
+                expected_sequence,
+            }) => {
+                buf.push(5);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                buf.extend_from_slice(&receipt_id.to_le_bytes());
+                buf.push(receipt_nonce);
+                pack_optional_u64(&mut buf, expected_sequence);
+            }
+            Self::CloseSwapReceipt => buf.push(6),
+            Self::HarvestAdminFees => buf.push(7),
+            Self::CommitReflectionRoot(CommitReflectionRootData {
+                merkle_root,
+                total_amount,
+                nonce,
+            }) => {
+                buf.push(8);
+                buf.extend_from_slice(&merkle_root);
+                buf.extend_from_slice(&total_amount.to_le_bytes());
+                buf.push(nonce);
+            }
+            Self::ClaimReflectionWithProof(ClaimReflectionData {
+                index,
+                amount,
+                nonce,
+                ref proof,
+            }) => {
+                buf.push(9);
+                buf.extend_from_slice(&index.to_le_bytes());
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(nonce);
+                buf.push(proof.len() as u8);
+                for node in proof {
+                    buf.extend_from_slice(node);
+                }
+            }
+            Self::ClaimVestedDevFees => buf.push(10),
+            Self::DepositGovernanceTokens(DepositGovernanceTokensData {
+                amount,
+                deposit_nonce,
+                record_nonce,
+            }) => {
+                buf.push(11);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(deposit_nonce);
+                buf.push(record_nonce);
+            }
+            Self::WithdrawGovernanceTokens(amount) => {
+                buf.push(12);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::BeginWithdraw(BeginWithdrawData {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+                nonce,
+                expected_sequence,
+            }) => {
+                buf.push(13);
+                buf.extend_from_slice(&pool_token_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+                buf.push(nonce);
+                pack_optional_u64(&mut buf, expected_sequence);
+            }
+            Self::ContinueWithdraw => buf.push(14),
+            Self::EmitPoolSummary => buf.push(15),
+            Self::CreateVeLock(CreateVeLockData {
+                amount,
+                lock_duration,
+                nonce,
+            }) => {
+                buf.push(16);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&lock_duration.to_le_bytes());
+                buf.push(nonce);
+            }
+            Self::ClaimVeFees => buf.push(17),
+            Self::WithdrawVeLock => buf.push(18),
+            Self::VoteGaugeWeight(VoteGaugeWeightData { weight_bps, nonce }) => {
+                buf.push(19);
+                buf.extend_from_slice(&weight_bps.to_le_bytes());
+                buf.push(nonce);
+            }
+            Self::CheckpointGauge => buf.push(20),
+            Self::BidOrderFlow(amount) => {
+                buf.push(21);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::SimulateSwap(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                buf.push(22);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                pack_deadline_and_sequence(&mut buf, deadline_slot, expected_sequence);
+            }
+            Self::CoverShortfall(CoverShortfallData { amount_a, amount_b }) => {
+                buf.push(23);
+                buf.extend_from_slice(&amount_a.to_le_bytes());
+                buf.extend_from_slice(&amount_b.to_le_bytes());
+            }
+            Self::ClonePool(ClonePoolData {
+                nonce,
+                namespace,
+                fee_tier,
+                freeze_authority_acknowledged,
+            }) => {
+                buf.push(24);
+                buf.push(nonce);
+                buf.extend_from_slice(&namespace);
+                buf.push(fee_tier as u8);
+                buf.push(freeze_authority_acknowledged as u8);
+            }
+            Self::SweepDust => buf.push(25),
+            Self::DepositLiquidityPosition(DepositLiquidityPositionData { amount, nonce }) => {
+                buf.push(26);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(nonce);
+            }
+            Self::WithdrawLiquidityPosition(amount) => {
+                buf.push(27);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::CheckpointLiquidityPosition => buf.push(28),
+            Self::ReportEpochFees => buf.push(29),
+            Self::SwapOut(SwapOutData {
+                amount_out,
+                maximum_amount_in,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                buf.push(30);
+                buf.extend_from_slice(&amount_out.to_le_bytes());
+                buf.extend_from_slice(&maximum_amount_in.to_le_bytes());
+                pack_deadline_and_sequence(&mut buf, deadline_slot, expected_sequence);
+            }
+            Self::GetPoolStatus => buf.push(31),
+            Self::Route(RouteData {
+                amount_in,
+                minimum_amount_out,
+                ref leg_account_counts,
+            }) => {
+                buf.push(32);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                buf.push(leg_account_counts.len() as u8);
+                buf.extend_from_slice(leg_account_counts);
+            }
+            Self::CheckFeeDestinations => buf.push(33),
+            Self::CreateTwapOrder(CreateTwapOrderData {
+                amount_in,
+                slice_count,
+                slice_interval_slots,
+                max_price_deviation_bps,
+                order_id,
+                order_nonce,
+            }) => {
+                buf.push(34);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&slice_count.to_le_bytes());
+                buf.extend_from_slice(&slice_interval_slots.to_le_bytes());
+                buf.extend_from_slice(&max_price_deviation_bps.to_le_bytes());
+                buf.extend_from_slice(&order_id.to_le_bytes());
+                buf.push(order_nonce);
+            }
+            Self::ExecuteTwapSlice => buf.push(35),
+            Self::CancelTwapOrder => buf.push(36),
+            Self::CreateTrigger(CreateTriggerData {
+                amount_in,
+                minimum_amount_out,
+                trigger_price,
+                trigger_above,
+                bounty,
+                trigger_id,
+                trigger_nonce,
+            }) => {
+                buf.push(37);
+                buf.extend_from_slice(&amount_in.to_le_bytes());
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+                buf.extend_from_slice(&trigger_price.to_le_bytes());
+                buf.push(trigger_above as u8);
+                buf.extend_from_slice(&bounty.to_le_bytes());
+                buf.extend_from_slice(&trigger_id.to_le_bytes());
+                buf.push(trigger_nonce);
+            }
+            Self::ExecuteTrigger => buf.push(38),
+            Self::CancelTrigger => buf.push(39),
+            Self::CreateDripDeposit(CreateDripDepositData {
+                amount_a,
+                amount_b,
+                tranche_count,
+                tranche_interval_slots,
+                drip_id,
+                drip_nonce,
+            }) => {
+                buf.push(40);
+                buf.extend_from_slice(&amount_a.to_le_bytes());
+                buf.extend_from_slice(&amount_b.to_le_bytes());
+                buf.extend_from_slice(&tranche_count.to_le_bytes());
+                buf.extend_from_slice(&tranche_interval_slots.to_le_bytes());
+                buf.extend_from_slice(&drip_id.to_le_bytes());
+                buf.push(drip_nonce);
+            }
+            Self::DepositDripTranche => buf.push(41),
+            Self::CancelDripDeposit => buf.push(42),
+            Self::InitializeGlobalStats(nonce) => {
+                buf.push(43);
+                buf.push(nonce);
+            }
+            Self::DepositReflectionPosition(DepositReflectionPositionData { amount, nonce }) => {
+                buf.push(44);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(nonce);
+            }
+            Self::WithdrawReflectionPosition(amount) => {
+                buf.push(45);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::ClaimReflection => buf.push(46),
+            Self::ExecuteBuyback(minimum_amount_out) => {
+                buf.push(47);
+                buf.extend_from_slice(&minimum_amount_out.to_le_bytes());
+            }
+            Self::WithdrawMarketingFees => buf.push(48),
+            Self::WithdrawDeveloperFees => buf.push(49),
+            Self::CreateReferralAccrual(nonce) => {
+                buf.push(50);
+                buf.push(nonce);
+            }
+            Self::ClaimReferralFees => buf.push(51),
+            Self::DepositOne(DepositOneData {
+                token_amount,
+                min_mint_amount,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                buf.push(52);
+                buf.extend_from_slice(&token_amount.to_le_bytes());
+                buf.extend_from_slice(&min_mint_amount.to_le_bytes());
+                pack_deadline_and_sequence(&mut buf, deadline_slot, expected_sequence);
+            }
+            Self::WithdrawImbalanced(WithdrawImbalancedData {
+                token_a_amount,
+                token_b_amount,
+                maximum_pool_token_amount,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                buf.push(53);
+                buf.extend_from_slice(&token_a_amount.to_le_bytes());
+                buf.extend_from_slice(&token_b_amount.to_le_bytes());
+                buf.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
+                pack_deadline_and_sequence(&mut buf, deadline_slot, expected_sequence);
+            }
+            Self::Stake(StakeData { amount, nonce }) => {
+                buf.push(54);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.push(nonce);
+            }
+            Self::Unstake(amount) => {
+                buf.push(55);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::HarvestRewards => buf.push(56),
+        }
+        buf
+    }
+}
+
+/// Creates an 'initialize' instruction.
+pub fn initialize(
+    program_id: &Pubkey,
+    pool_token_program_id: &Pubkey, // Token program used for the pool token
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    admin_fee_a_pubkey: &Pubkey,
+    admin_fee_b_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_a_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+    token_b_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey, // Desintation to mint pool tokens for bootstrapper
+    payer_pubkey: &Pubkey,
+    access_mint_pubkey: Option<&Pubkey>,
+    nonce: u8,
+    amp_factor: u64,
+    fees: Fees,
+    access_window_slots: u64,
+    namespace: [u8; 32],
+    fee_tier: FeeTier,
+    marketing_wallet: Pubkey,
+    developer_wallet: Pubkey,
+    curve_type: CurveType,
+    price_bound: u64,
+    creator_window_slots: u64,
+    freeze_authority_acknowledged: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Initialize(InitializeData {
+        nonce,
+        amp_factor,
+        fees,
+        access_window_slots,
+        namespace,
+        fee_tier,
+        marketing_wallet,
+        developer_wallet,
+        curve_type,
+        price_bound,
+        creator_window_slots,
+        freeze_authority_acknowledged,
+    })
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, false),
+        AccountMeta::new(*admin_fee_a_pubkey, false),
+        AccountMeta::new(*admin_fee_b_pubkey, false),
+        AccountMeta::new(*token_a_mint_pubkey, false),
+        AccountMeta::new(*token_a_pubkey, false),
+        AccountMeta::new(*token_b_mint_pubkey, false),
+        AccountMeta::new(*token_b_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*pool_token_program_id, false),
+        AccountMeta::new(clock::id(), false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+    if let Some(access_mint_pubkey) = access_mint_pubkey {
+        accounts.push(AccountMeta::new(*access_mint_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'clone_pool' instruction.
+pub fn clone_pool(
+    program_id: &Pubkey,
+    pool_token_program_id: &Pubkey, // Token program used for the pool token
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    admin_pubkey: &Pubkey,
+    admin_fee_a_pubkey: &Pubkey,
+    admin_fee_b_pubkey: &Pubkey,
+    token_a_mint_pubkey: &Pubkey,
+    token_a_pubkey: &Pubkey,
+    token_b_mint_pubkey: &Pubkey,
+    token_b_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey, // Desintation to mint pool tokens for bootstrapper
+    payer_pubkey: &Pubkey,
+    source_swap_pubkey: &Pubkey,
+    nonce: u8,
+    namespace: [u8; 32],
+    fee_tier: FeeTier,
+    freeze_authority_acknowledged: bool,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ClonePool(ClonePoolData {
+        nonce,
+        namespace,
+        fee_tier,
+        freeze_authority_acknowledged,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*admin_pubkey, false),
+        AccountMeta::new(*admin_fee_a_pubkey, false),
+        AccountMeta::new(*admin_fee_b_pubkey, false),
+        AccountMeta::new(*token_a_mint_pubkey, false),
+        AccountMeta::new(*token_a_pubkey, false),
+        AccountMeta::new(*token_b_mint_pubkey, false),
+        AccountMeta::new(*token_b_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*pool_token_program_id, false),
+        AccountMeta::new(clock::id(), false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(*source_swap_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Turns a 'swap' instruction into a 'simulate swap' instruction.
+pub fn simulate_swap(swap_instruction: Instruction) -> Result<Instruction, ProgramError> {
+    let data = match SwapInstruction::unpack(&swap_instruction.data)? {
+        SwapInstruction::Swap(swap_data) => SwapInstruction::SimulateSwap(swap_data).pack(),
+        _ => return Err(SwapError::InvalidInstruction.into()),
+    };
+    Ok(Instruction {
+        data,
+        ..swap_instruction
+    })
+}
+
+/// Wraps a 'swap' instruction into a 'swap with receipt' instruction.
+pub fn swap_with_receipt(
+    swap_instruction: Instruction,
+    receipt_pubkey: &Pubkey,
+    receipt_owner_pubkey: &Pubkey,
+    receipt_id: u64,
+    receipt_nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let (amount_in, minimum_amount_out, expected_sequence) =
+        match SwapInstruction::unpack(&swap_instruction.data)? {
+            // Receipts don't carry a deadline
+            SwapInstruction::Swap(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot: 0,
+                expected_sequence,
+            }) => (amount_in, minimum_amount_out, expected_sequence),
+            _ => return Err(SwapError::InvalidInstruction.into()),
+        };
+    let data = SwapInstruction::SwapWithReceipt(SwapWithReceiptData {
+        amount_in,
+        minimum_amount_out,
+        receipt_id,
+        receipt_nonce,
+        expected_sequence,
+    })
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*receipt_pubkey, false),
+        AccountMeta::new(*receipt_owner_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+    accounts.extend(swap_instruction.accounts);
+
+    Ok(Instruction {
+        program_id: swap_instruction.program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'close swap receipt' instruction.
+pub fn close_swap_receipt(
+    program_id: &Pubkey,
+    receipt_pubkey: &Pubkey,
+    receipt_owner_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CloseSwapReceipt.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*receipt_pubkey, false),
+        AccountMeta::new_readonly(*receipt_owner_pubkey, true),
+        AccountMeta::new(*destination_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'harvest_admin_fees' instruction.
+pub fn harvest_admin_fees(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    admin_fee_a_pubkey: &Pubkey,
+    admin_fee_b_pubkey: &Pubkey,
+    tip_token_a_pubkey: &Pubkey,
+    tip_token_b_pubkey: &Pubkey,
+    ve_fee_vault_pubkeys: Option<(&Pubkey, &Pubkey)>,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::HarvestAdminFees.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*admin_fee_a_pubkey, false),
+        AccountMeta::new(*admin_fee_b_pubkey, false),
+        AccountMeta::new(*tip_token_a_pubkey, false),
+        AccountMeta::new(*tip_token_b_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+    if let Some((ve_fee_vault_a_pubkey, ve_fee_vault_b_pubkey)) = ve_fee_vault_pubkeys {
+        accounts.push(AccountMeta::new(*ve_fee_vault_a_pubkey, false));
+        accounts.push(AccountMeta::new(*ve_fee_vault_b_pubkey, false));
+        accounts.push(AccountMeta::new_readonly(clock::id(), false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'commit_reflection_root' instruction.
+pub fn commit_reflection_root(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    distributor_pubkey: &Pubkey,
+    distribution_pubkey: &Pubkey,
+    vault_pubkey: &Pubkey,
+    merkle_root: [u8; 32],
+    total_amount: u64,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CommitReflectionRoot(CommitReflectionRootData {
+        merkle_root,
+        total_amount,
+        nonce,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*distributor_pubkey, true),
+        AccountMeta::new(*distribution_pubkey, false),
+        AccountMeta::new_readonly(*vault_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'claim_reflection_with_proof' instruction.
+pub fn claim_reflection_with_proof(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    distribution_pubkey: &Pubkey,
+    vault_pubkey: &Pubkey,
+    claimant_pubkey: &Pubkey,
+    claim_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    index: u64,
+    amount: u64,
+    nonce: u8,
+    proof: Vec<[u8; 32]>,
+) -> Result<Instruction, ProgramError> {
+    if proof.len() > MAX_PROOF_LEN {
+        return Err(SwapError::InvalidInput.into());
+    }
+    let data = SwapInstruction::ClaimReflectionWithProof(ClaimReflectionData {
+        index,
+        amount,
+        nonce,
+        proof,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*distribution_pubkey, false),
+        AccountMeta::new(*vault_pubkey, false),
+        AccountMeta::new(*claimant_pubkey, true),
+        AccountMeta::new(*claim_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'claim_vested_dev_fees' instruction.
+pub fn claim_vested_dev_fees(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    dev_wallet_pubkey: &Pubkey,
+    dev_vesting_a_pubkey: &Pubkey,
+    dev_vesting_b_pubkey: &Pubkey,
+    destination_a_pubkey: &Pubkey,
+    destination_b_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ClaimVestedDevFees.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*dev_wallet_pubkey, true),
+        AccountMeta::new(*dev_vesting_a_pubkey, false),
+        AccountMeta::new(*dev_vesting_b_pubkey, false),
+        AccountMeta::new(*destination_a_pubkey, false),
+        AccountMeta::new(*destination_b_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'deposit_governance_tokens' instruction.
+pub fn deposit_governance_tokens(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    governance_vault_pubkey: &Pubkey,
+    governance_deposit_pubkey: &Pubkey,
+    voter_weight_record_pubkey: &Pubkey,
+    amount: u64,
+    deposit_nonce: u8,
+    record_nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::DepositGovernanceTokens(DepositGovernanceTokensData {
+        amount,
+        deposit_nonce,
+        record_nonce,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*governance_vault_pubkey, false),
+        AccountMeta::new(*governance_deposit_pubkey, false),
+        AccountMeta::new(*voter_weight_record_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw_governance_tokens' instruction.
+pub fn withdraw_governance_tokens(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    governance_deposit_pubkey: &Pubkey,
+    voter_weight_record_pubkey: &Pubkey,
+    governance_vault_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WithdrawGovernanceTokens(amount).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+        AccountMeta::new(*governance_deposit_pubkey, false),
+        AccountMeta::new(*voter_weight_record_pubkey, false),
+        AccountMeta::new(*governance_vault_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'begin_withdraw' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn begin_withdraw(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    destination_token_a_pubkey: &Pubkey,
+    destination_token_b_pubkey: &Pubkey,
+    withdraw_continuation_pubkey: &Pubkey,
+    custodian_pubkey: Option<&Pubkey>, // Only for withdrawals the custodian co-signs
+    pool_token_amount: u64,
+    minimum_token_a_amount: u64,
+    minimum_token_b_amount: u64,
+    nonce: u8,
+    expected_sequence: Option<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::BeginWithdraw(BeginWithdrawData {
+        pool_token_amount,
+        minimum_token_a_amount,
+        minimum_token_b_amount,
+        nonce,
+        expected_sequence,
+    })
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new_readonly(*swap_token_a_pubkey, false),
+        AccountMeta::new_readonly(*swap_token_b_pubkey, false),
+        AccountMeta::new_readonly(*destination_token_a_pubkey, false),
+        AccountMeta::new_readonly(*destination_token_b_pubkey, false),
+        AccountMeta::new(*withdraw_continuation_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+    if let Some(custodian_pubkey) = custodian_pubkey {
+        accounts.push(AccountMeta::new_readonly(*custodian_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'continue_withdraw' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn continue_withdraw(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    withdraw_continuation_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    swap_token_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    admin_fee_destination_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ContinueWithdraw.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*withdraw_continuation_pubkey, false),
+        AccountMeta::new(*owner_pubkey, false),
+        AccountMeta::new(*swap_token_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*admin_fee_destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'emit_pool_summary' instruction.
+pub fn emit_pool_summary(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::EmitPoolSummary.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*swap_token_a_pubkey, false),
+        AccountMeta::new_readonly(*swap_token_b_pubkey, false),
+        AccountMeta::new_readonly(*pool_mint_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'get_pool_status' instruction.
+pub fn get_pool_status(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::GetPoolStatus.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'route' instruction from the `swap` instructions of its legs, whose
+/// amounts are ignored.
+pub fn route(
+    program_id: &Pubkey,
+    legs: &[Instruction],
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Result<Instruction, ProgramError> {
+    if legs.is_empty() || legs.len() > MAX_ROUTE_LEGS {
+        return Err(SwapError::InvalidInput.into());
+    }
+    let mut leg_account_counts = Vec::with_capacity(legs.len());
+    let mut accounts = vec![];
+    for (index, leg) in legs.iter().enumerate() {
+        if leg.accounts.len() < 13 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        if index == 0 {
+            // The token program, the clock and the source of the first leg are shared
+            accounts.extend_from_slice(&[
+                leg.accounts[10].clone(),
+                leg.accounts[11].clone(),
+                leg.accounts[2].clone(),
+            ]);
+        } else if legs[index - 1].accounts[5].pubkey != leg.accounts[2].pubkey {
+            return Err(SwapError::InvalidInput.into());
+        }
+        leg_account_counts.push((leg.accounts.len() - ROUTE_SHARED_ACCOUNTS) as u8);
+        accounts.extend_from_slice(&leg.accounts[..2]);
+        accounts.extend_from_slice(&leg.accounts[3..10]);
+        accounts.extend_from_slice(&leg.accounts[12..]);
+    }
+    let data = SwapInstruction::Route(RouteData {
+        amount_in,
+        minimum_amount_out,
+        leg_account_counts,
+    })
+    .pack();
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'check_fee_destinations' instruction.
+pub fn check_fee_destinations(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    admin_fee_a_pubkey: &Pubkey,
+    admin_fee_b_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CheckFeeDestinations.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*admin_fee_a_pubkey, false),
+        AccountMeta::new_readonly(*admin_fee_b_pubkey, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'create_twap_order' instruction.
+pub fn create_twap_order(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    escrow_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    twap_order_pubkey: &Pubkey,
+    data: CreateTwapOrderData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CreateTwapOrder(data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*escrow_pubkey, false),
+        AccountMeta::new_readonly(*destination_pubkey, false),
+        AccountMeta::new(*twap_order_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'execute_twap_slice' instruction from the 'swap' instruction of the
+/// slice, the amounts of which are ignored.
+pub fn execute_twap_slice(
+    swap_instruction: Instruction,
+    twap_order_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ExecuteTwapSlice.pack();
+
+    let mut accounts = vec![AccountMeta::new(*twap_order_pubkey, false)];
+    accounts.extend(swap_instruction.accounts);
+
+    Ok(Instruction {
+        program_id: swap_instruction.program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'cancel_twap_order' instruction.
+pub fn cancel_twap_order(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    twap_order_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    escrow_pubkey: &Pubkey,
+    refund_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CancelTwapOrder.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*twap_order_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*escrow_pubkey, false),
+        AccountMeta::new(*refund_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'create_trigger' instruction.
+pub fn create_trigger(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    escrow_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    oracle_pubkey: &Pubkey,
+    trigger_pubkey: &Pubkey,
+    data: CreateTriggerData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CreateTrigger(data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*escrow_pubkey, false),
+        AccountMeta::new_readonly(*destination_pubkey, false),
+        AccountMeta::new_readonly(*oracle_pubkey, false),
+        AccountMeta::new(*trigger_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'execute_trigger' instruction from the 'swap' instruction of the
+/// trigger, the amounts of which are ignored.
+pub fn execute_trigger(
+    swap_instruction: Instruction,
+    trigger_pubkey: &Pubkey,
+    oracle_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    cranker_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ExecuteTrigger.pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*trigger_pubkey, false),
+        AccountMeta::new_readonly(*oracle_pubkey, false),
+        AccountMeta::new(*owner_pubkey, false),
+        AccountMeta::new(*cranker_pubkey, false),
+    ];
+    accounts.extend(swap_instruction.accounts);
+
+    Ok(Instruction {
+        program_id: swap_instruction.program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'cancel_trigger' instruction.
+pub fn cancel_trigger(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    trigger_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    escrow_pubkey: &Pubkey,
+    refund_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CancelTrigger.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*trigger_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*escrow_pubkey, false),
+        AccountMeta::new(*refund_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'create_drip_deposit' instruction.
+pub fn create_drip_deposit(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    source_a_pubkey: &Pubkey,
+    source_b_pubkey: &Pubkey,
+    escrow_a_pubkey: &Pubkey,
+    escrow_b_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    drip_deposit_pubkey: &Pubkey,
+    data: CreateDripDepositData,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CreateDripDeposit(data).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*source_a_pubkey, false),
+        AccountMeta::new(*source_b_pubkey, false),
+        AccountMeta::new(*escrow_a_pubkey, false),
+        AccountMeta::new(*escrow_b_pubkey, false),
+        AccountMeta::new_readonly(*destination_pubkey, false),
+        AccountMeta::new(*drip_deposit_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'deposit_drip_tranche' instruction from the 'deposit' instruction of
+/// the tranche, the amounts of which are ignored.
+pub fn deposit_drip_tranche(
+    deposit_instruction: Instruction,
+    drip_deposit_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::DepositDripTranche.pack();
+
+    let mut accounts = vec![AccountMeta::new(*drip_deposit_pubkey, false)];
+    accounts.extend(deposit_instruction.accounts);
+
+    Ok(Instruction {
+        program_id: deposit_instruction.program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'cancel_drip_deposit' instruction.
+pub fn cancel_drip_deposit(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    drip_deposit_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    escrow_a_pubkey: &Pubkey,
+    escrow_b_pubkey: &Pubkey,
+    refund_a_pubkey: &Pubkey,
+    refund_b_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CancelDripDeposit.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*drip_deposit_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*escrow_a_pubkey, false),
+        AccountMeta::new(*escrow_b_pubkey, false),
+        AccountMeta::new(*refund_a_pubkey, false),
+        AccountMeta::new(*refund_b_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'initialize_global_stats' instruction.
+pub fn initialize_global_stats(
+    program_id: &Pubkey,
+    global_stats_pubkey: &Pubkey,
+    payer_pubkey: &Pubkey,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::InitializeGlobalStats(nonce).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*global_stats_pubkey, false),
+        AccountMeta::new(*payer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'create_ve_lock' instruction.
+pub fn create_ve_lock(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    ve_lp_vault_pubkey: &Pubkey,
+    ve_lock_pubkey: &Pubkey,
+    amount: u64,
+    lock_duration: i64,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CreateVeLock(CreateVeLockData {
+        amount,
+        lock_duration,
+        nonce,
+    })
+    .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*ve_lp_vault_pubkey, false),
+        AccountMeta::new(*ve_lock_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'claim_ve_fees' instruction.
+pub fn claim_ve_fees(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    ve_lock_pubkey: &Pubkey,
+    ve_fee_vault_a_pubkey: &Pubkey,
+    ve_fee_vault_b_pubkey: &Pubkey,
+    destination_a_pubkey: &Pubkey,
+    destination_b_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ClaimVeFees.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+        AccountMeta::new(*ve_lock_pubkey, false),
+        AccountMeta::new(*ve_fee_vault_a_pubkey, false),
+        AccountMeta::new(*ve_fee_vault_b_pubkey, false),
+        AccountMeta::new(*destination_a_pubkey, false),
+        AccountMeta::new(*destination_b_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw_ve_lock' instruction.
+pub fn withdraw_ve_lock(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    ve_lock_pubkey: &Pubkey,
+    ve_lp_vault_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    ve_fee_vault_a_pubkey: &Pubkey,
+    ve_fee_vault_b_pubkey: &Pubkey,
+    destination_a_pubkey: &Pubkey,
+    destination_b_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WithdrawVeLock.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*ve_lock_pubkey, false),
+        AccountMeta::new(*ve_lp_vault_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*ve_fee_vault_a_pubkey, false),
+        AccountMeta::new(*ve_fee_vault_b_pubkey, false),
+        AccountMeta::new(*destination_a_pubkey, false),
+        AccountMeta::new(*destination_b_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'vote_gauge_weight' instruction.
+pub fn vote_gauge_weight(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    ve_lock_pubkey: &Pubkey,
+    gauge_controller_pubkey: &Pubkey,
+    gauge_pubkey: &Pubkey,
+    gauge_vote_pubkey: &Pubkey,
+    weight_bps: u16,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::VoteGaugeWeight(VoteGaugeWeightData { weight_bps, nonce }).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*ve_lock_pubkey, false),
+        AccountMeta::new(*gauge_controller_pubkey, false),
+        AccountMeta::new(*gauge_pubkey, false),
+        AccountMeta::new(*gauge_vote_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'checkpoint_gauge' instruction.
+pub fn checkpoint_gauge(
+    program_id: &Pubkey,
+    gauge_controller_pubkey: &Pubkey,
+    gauge_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CheckpointGauge.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*gauge_controller_pubkey, false),
+        AccountMeta::new(*gauge_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'bid_order_flow' instruction.
+pub fn bid_order_flow(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    bid_token_pubkey: &Pubkey,
+    refund_pubkey: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::BidOrderFlow(amount).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*bid_token_pubkey, false),
+        AccountMeta::new(*refund_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'cover_shortfall' instruction.
+pub fn cover_shortfall(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    insurance_authority_pubkey: &Pubkey,
+    amount_a: u64,
+    amount_b: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CoverShortfall(CoverShortfallData { amount_a, amount_b }).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*insurance_authority_pubkey, true),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'deposit' instruction.
+pub fn deposit(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    deposit_token_a_pubkey: &Pubkey,
+    deposit_token_b_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    credential_pubkey: Option<&Pubkey>,
+    token_a_amount: u64,
+    token_b_amount: u64,
+    min_mint_amount: u64,
+    deadline_slot: u64,
+    expected_sequence: Option<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Deposit(DepositData {
+        token_a_amount,
+        token_b_amount,
+        min_mint_amount,
+        deadline_slot,
+        expected_sequence,
+    })
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*deposit_token_a_pubkey, false),
+        AccountMeta::new(*deposit_token_b_pubkey, false),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*token_program_id, false),
+        AccountMeta::new(clock::id(), false),
+    ];
+    if let Some(credential_pubkey) = credential_pubkey {
+        accounts.push(AccountMeta::new_readonly(*credential_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'deposit_one' instruction.
+pub fn deposit_one(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_base_token_pubkey: &Pubkey,
+    swap_quote_token_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    credential_pubkey: Option<&Pubkey>,
+    token_amount: u64,
+    min_mint_amount: u64,
+    deadline_slot: u64,
+    expected_sequence: Option<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::DepositOne(DepositOneData {
+        token_amount,
+        min_mint_amount,
+        deadline_slot,
+        expected_sequence,
+    })
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_base_token_pubkey, false),
+        AccountMeta::new_readonly(*swap_quote_token_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+    if let Some(credential_pubkey) = credential_pubkey {
+        accounts.push(AccountMeta::new_readonly(*credential_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw_imbalanced' instruction.
+pub fn withdraw_imbalanced(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    destination_token_a_pubkey: &Pubkey,
+    destination_token_b_pubkey: &Pubkey,
+    admin_fee_a_pubkey: &Pubkey,
+    admin_fee_b_pubkey: &Pubkey,
+    source_owner_pubkey: Option<&Pubkey>, // Only for soulbound pool tokens
+    custodian_pubkey: Option<&Pubkey>,    // Only for withdrawals the custodian co-signs
+    token_a_amount: u64,
+    token_b_amount: u64,
+    maximum_pool_token_amount: u64,
+    deadline_slot: u64,
+    expected_sequence: Option<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WithdrawImbalanced(WithdrawImbalancedData {
+        token_a_amount,
+        token_b_amount,
+        maximum_pool_token_amount,
+        deadline_slot,
+        expected_sequence,
+    })
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*destination_token_a_pubkey, false),
+        AccountMeta::new(*destination_token_b_pubkey, false),
+        AccountMeta::new(*admin_fee_a_pubkey, false),
+        AccountMeta::new(*admin_fee_b_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+    if let Some(source_owner_pubkey) = source_owner_pubkey {
+        accounts.push(AccountMeta::new_readonly(*source_owner_pubkey, true));
+    }
+    if let Some(custodian_pubkey) = custodian_pubkey {
+        accounts.push(AccountMeta::new_readonly(*custodian_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'stake' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn stake(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    farm_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    stake_vault_pubkey: &Pubkey,
+    reward_vault_pubkey: &Pubkey,
+    stake_account_pubkey: &Pubkey,
+    amount: u64,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Stake(StakeData { amount, nonce }).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*farm_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*stake_vault_pubkey, false),
+        AccountMeta::new_readonly(*reward_vault_pubkey, false),
+        AccountMeta::new(*stake_account_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'unstake' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn unstake(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    farm_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    stake_account_pubkey: &Pubkey,
+    stake_vault_pubkey: &Pubkey,
+    reward_vault_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Unstake(amount).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*farm_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+        AccountMeta::new(*stake_account_pubkey, false),
+        AccountMeta::new(*stake_vault_pubkey, false),
+        AccountMeta::new_readonly(*reward_vault_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'harvest_rewards' instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn harvest_rewards(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    farm_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    stake_account_pubkey: &Pubkey,
+    reward_vault_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::HarvestRewards.pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*farm_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+        AccountMeta::new(*stake_account_pubkey, false),
+        AccountMeta::new(*reward_vault_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'sweep_dust' instruction.
+pub fn sweep_dust(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::SweepDust.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_source_pubkey, false),
+        AccountMeta::new(*swap_destination_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'deposit_liquidity_position' instruction.
+pub fn deposit_liquidity_position(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    liquidity_vault_pubkey: &Pubkey,
+    liquidity_position_pubkey: &Pubkey,
+    amount: u64,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data =
+        SwapInstruction::DepositLiquidityPosition(DepositLiquidityPositionData { amount, nonce })
+            .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*liquidity_vault_pubkey, false),
+        AccountMeta::new(*liquidity_position_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw_liquidity_position' instruction.
+pub fn withdraw_liquidity_position(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    liquidity_position_pubkey: &Pubkey,
+    liquidity_vault_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WithdrawLiquidityPosition(amount).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+        AccountMeta::new(*liquidity_position_pubkey, false),
+        AccountMeta::new(*liquidity_vault_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'deposit_reflection_position' instruction.
+pub fn deposit_reflection_position(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    reflection_lp_vault_pubkey: &Pubkey,
+    reflection_position_pubkey: &Pubkey,
+    amount: u64,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data =
+        SwapInstruction::DepositReflectionPosition(DepositReflectionPositionData { amount, nonce })
+            .pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*owner_pubkey, true),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*reflection_lp_vault_pubkey, false),
+        AccountMeta::new(*reflection_position_pubkey, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw_reflection_position' instruction.
+pub fn withdraw_reflection_position(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    reflection_position_pubkey: &Pubkey,
+    reflection_lp_vault_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    amount: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WithdrawReflectionPosition(amount).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+        AccountMeta::new(*reflection_position_pubkey, false),
+        AccountMeta::new(*reflection_lp_vault_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'claim_reflection' instruction.
+pub fn claim_reflection(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    owner_pubkey: &Pubkey,
+    reflection_position_pubkey: &Pubkey,
+    reflection_vault_a_pubkey: &Pubkey,
+    reflection_vault_b_pubkey: &Pubkey,
+    destination_a_pubkey: &Pubkey,
+    destination_b_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ClaimReflection.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*owner_pubkey, true),
+        AccountMeta::new(*reflection_position_pubkey, false),
+        AccountMeta::new(*reflection_vault_a_pubkey, false),
+        AccountMeta::new(*reflection_vault_b_pubkey, false),
+        AccountMeta::new(*destination_a_pubkey, false),
+        AccountMeta::new(*destination_b_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates an 'execute_buyback' instruction.
+pub fn execute_buyback(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    source_vault_pubkey: &Pubkey,
+    burn_vault_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    buyback_mint_pubkey: &Pubkey,
+    minimum_amount_out: u64,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ExecuteBuyback(minimum_amount_out).pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*source_vault_pubkey, false),
+        AccountMeta::new(*burn_vault_pubkey, false),
+        AccountMeta::new(*swap_source_pubkey, false),
+        AccountMeta::new(*swap_destination_pubkey, false),
+        AccountMeta::new(*buyback_mint_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw_marketing_fees' instruction.
+pub fn withdraw_marketing_fees(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    marketing_wallet_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    destination_a_pubkey: &Pubkey,
+    destination_b_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WithdrawMarketingFees.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*marketing_wallet_pubkey, true),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*destination_a_pubkey, false),
+        AccountMeta::new(*destination_b_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw_developer_fees' instruction.
+pub fn withdraw_developer_fees(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    developer_wallet_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    destination_a_pubkey: &Pubkey,
+    destination_b_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WithdrawDeveloperFees.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new_readonly(*developer_wallet_pubkey, true),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*destination_a_pubkey, false),
+        AccountMeta::new(*destination_b_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'create_referral_accrual' instruction.
+pub fn create_referral_accrual(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    referral_accrual_pubkey: &Pubkey,
+    referrer_pubkey: &Pubkey,
+    nonce: u8,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CreateReferralAccrual(nonce).pack();
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new(*referral_accrual_pubkey, false),
+        AccountMeta::new(*referrer_pubkey, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(rent::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'claim_referral_fees' instruction.
+pub fn claim_referral_fees(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    referral_accrual_pubkey: &Pubkey,
+    referrer_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    destination_a_pubkey: &Pubkey,
+    destination_b_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ClaimReferralFees.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*referral_accrual_pubkey, false),
+        AccountMeta::new_readonly(*referrer_pubkey, true),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*destination_a_pubkey, false),
+        AccountMeta::new(*destination_b_pubkey, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'checkpoint_liquidity_position' instruction.
+pub fn checkpoint_liquidity_position(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    liquidity_position_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::CheckpointLiquidityPosition.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new(*liquidity_position_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'report_epoch_fees' instruction.
+pub fn report_epoch_fees(
+    program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::ReportEpochFees.pack();
+
+    let accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'withdraw' instruction.
+pub fn withdraw(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_token_a_pubkey: &Pubkey,
+    swap_token_b_pubkey: &Pubkey,
+    destination_token_a_pubkey: &Pubkey,
+    destination_token_b_pubkey: &Pubkey,
+    admin_fee_a_pubkey: &Pubkey,
+    admin_fee_b_pubkey: &Pubkey,
+    source_owner_pubkey: Option<&Pubkey>, // Only for soulbound pool tokens
+    custodian_pubkey: Option<&Pubkey>,    // Only for withdrawals the custodian co-signs
+    pool_token_amount: u64,
+    minimum_token_a_amount: u64,
+    minimum_token_b_amount: u64,
+    deadline_slot: u64,
+    expected_sequence: Option<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Withdraw(WithdrawData {
+        pool_token_amount,
+        minimum_token_a_amount,
+        minimum_token_b_amount,
+        deadline_slot,
+        expected_sequence,
+    })
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_token_a_pubkey, false),
+        AccountMeta::new(*swap_token_b_pubkey, false),
+        AccountMeta::new(*destination_token_a_pubkey, false),
+        AccountMeta::new(*destination_token_b_pubkey, false),
+        AccountMeta::new(*admin_fee_a_pubkey, false),
+        AccountMeta::new(*admin_fee_b_pubkey, false),
+        AccountMeta::new(*token_program_id, false),
+        AccountMeta::new_readonly(clock::id(), false),
+    ];
+    if let Some(source_owner_pubkey) = source_owner_pubkey {
+        accounts.push(AccountMeta::new_readonly(*source_owner_pubkey, true));
+    }
+    if let Some(custodian_pubkey) = custodian_pubkey {
+        accounts.push(AccountMeta::new_readonly(*custodian_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'swap' instruction.
+pub fn swap(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    reflection_fee_destination_pubkey: &Pubkey,
+    buyback_fee_destination_pubkey: &Pubkey,
+    marketing_fee_destination_pubkey: &Pubkey,
+    developer_fee_destination_pubkey: &Pubkey,
+    referrer_pubkey: Option<&Pubkey>,
+    admin_fee_pool_pubkeys: Option<(&Pubkey, &Pubkey)>, // Pool mint and admin pool token account
+    access_token_pubkey: Option<&Pubkey>,
+    credential_pubkey: Option<&Pubkey>,
+    amount_in: u64,
+    minimum_amount_out: u64,
+    deadline_slot: u64,
+    expected_sequence: Option<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::Swap(SwapData {
+        amount_in,
+        minimum_amount_out,
+        deadline_slot,
+        expected_sequence,
+    })
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_source_pubkey, false),
+        AccountMeta::new(*swap_destination_pubkey, false),
+        AccountMeta::new(*destination_pubkey, false),
+        AccountMeta::new(*reflection_fee_destination_pubkey, false),
+        AccountMeta::new(*buyback_fee_destination_pubkey, false),
+        AccountMeta::new(*marketing_fee_destination_pubkey, false),
+        AccountMeta::new(*developer_fee_destination_pubkey, false),
+        AccountMeta::new(*token_program_id, false),
+        AccountMeta::new(clock::id(), false),
+        match referrer_pubkey {
+            Some(referrer_pubkey) => AccountMeta::new(*referrer_pubkey, false),
+            None => AccountMeta::new_readonly(*program_id, false),
+        },
+    ];
+    if let Some((pool_mint_pubkey, admin_pool_token_pubkey)) = admin_fee_pool_pubkeys {
+        accounts.push(AccountMeta::new(*pool_mint_pubkey, false));
+        accounts.push(AccountMeta::new(*admin_pool_token_pubkey, false));
+    }
+    if let Some(access_token_pubkey) = access_token_pubkey {
+        accounts.push(AccountMeta::new(*access_token_pubkey, false));
+    }
+    if let Some(credential_pubkey) = credential_pubkey {
+        accounts.push(AccountMeta::new_readonly(*credential_pubkey, false));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+/// Creates a 'swap_out' instruction.
+pub fn swap_out(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    destination_pubkey: &Pubkey,
+    reflection_fee_destination_pubkey: &Pubkey,
+    buyback_fee_destination_pubkey: &Pubkey,
+    marketing_fee_destination_pubkey: &Pubkey,
+    developer_fee_destination_pubkey: &Pubkey,
+    referrer_pubkey: Option<&Pubkey>,
+    admin_fee_pool_pubkeys: Option<(&Pubkey, &Pubkey)>, // Pool mint and admin pool token account
+    access_token_pubkey: Option<&Pubkey>,
+    credential_pubkey: Option<&Pubkey>,
+    amount_out: u64,
+    maximum_amount_in: u64,
+    deadline_slot: u64,
+    expected_sequence: Option<u64>,
+) -> Result<Instruction, ProgramError> {
+    // Same accounts as a swap
+    let swap_instruction = swap(
+        program_id,
+        token_program_id,
+        swap_pubkey,
+        authority_pubkey,
+        source_pubkey,
+        swap_source_pubkey,
+        swap_destination_pubkey,
+        destination_pubkey,
+        reflection_fee_destination_pubkey,
+        buyback_fee_destination_pubkey,
+        marketing_fee_destination_pubkey,
+        developer_fee_destination_pubkey,
+        referrer_pubkey,
+        admin_fee_pool_pubkeys,
+        access_token_pubkey,
+        credential_pubkey,
+        maximum_amount_in,
+        amount_out,
+        deadline_slot,
+        expected_sequence,
+    )?;
+    let data = SwapInstruction::SwapOut(SwapOutData {
+        amount_out,
+        maximum_amount_in,
+        deadline_slot,
+        expected_sequence,
+    })
+    .pack();
+
+    Ok(Instruction {
+        data,
+        ..swap_instruction
+    })
+}
+
+/// Creates a 'withdraw_one' instruction.
+pub fn withdraw_one(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    source_pubkey: &Pubkey,
+    swap_base_token_pubkey: &Pubkey,
+    swap_quote_token_pubkey: &Pubkey,
+    base_destination_pubkey: &Pubkey,
+    admin_fee_destination_pubkey: &Pubkey,
+    source_owner_pubkey: Option<&Pubkey>, // Only for soulbound pool tokens
+    custodian_pubkey: Option<&Pubkey>,    // Only for withdrawals the custodian co-signs
+    pool_token_amount: u64,
+    minimum_token_amount: u64,
+    deadline_slot: u64,
+    expected_sequence: Option<u64>,
+) -> Result<Instruction, ProgramError> {
+    let data = SwapInstruction::WithdrawOne(WithdrawOneData {
+        pool_token_amount,
+        minimum_token_amount,
+        deadline_slot,
+        expected_sequence,
+    })
+    .pack();
+
+    let mut accounts = vec![
+        AccountMeta::new(*swap_pubkey, false),
+        AccountMeta::new(*authority_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*swap_base_token_pubkey, false),
+        AccountMeta::new(*swap_quote_token_pubkey, false),
+        AccountMeta::new(*base_destination_pubkey, false),
+        AccountMeta::new(*admin_fee_destination_pubkey, false),
+        AccountMeta::new(*token_program_id, false),
+        AccountMeta::new(clock::id(), false),
+    ];
+    if let Some(source_owner_pubkey) = source_owner_pubkey {
+        accounts.push(AccountMeta::new_readonly(*source_owner_pubkey, true));
+    }
+    if let Some(custodian_pubkey) = custodian_pubkey {
+        accounts.push(AccountMeta::new_readonly(*custodian_pubkey, true));
+    }
+
+    Ok(Instruction {
+        program_id: *program_id,
+        accounts,
+        data,
+    })
+}
+
+fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
+    if input.len() >= 8 {
+        let (amount, rest) = input.split_at(8);
+        let amount = amount
+            .get(..8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(i64::from_le_bytes)
+            .ok_or(SwapError::InvalidInstruction)?;
+        Ok((amount, rest))
+    } else {
+        Err(SwapError::InvalidInstruction.into())
+    }
+}
+
+fn unpack_fees(input: &[u8]) -> Result<(Fees, &[u8]), ProgramError> {
+    if input.len() >= Fees::LEN {
+        let (fees, rest) = input.split_at(Fees::LEN);
+        Ok((Fees::unpack_unchecked(fees)?, rest))
+    } else {
+        Err(SwapError::InvalidInstruction.into())
+    }
+}
+
+fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+    if input.len() >= 2 {
+        let (amount, rest) = input.split_at(2);
+        let amount = amount
+            .get(..2)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u16::from_le_bytes)
+            .ok_or(SwapError::InvalidInstruction)?;
+        Ok((amount, rest))
+    } else {
+        Err(SwapError::InvalidInstruction.into())
+    }
+}
+
+fn unpack_u32(input: &[u8]) -> Result<(u32, &[u8]), ProgramError> {
+    if input.len() >= 4 {
+        let (amount, rest) = input.split_at(4);
+        let amount = amount
+            .get(..4)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u32::from_le_bytes)
+            .ok_or(SwapError::InvalidInstruction)?;
+        Ok((amount, rest))
+    } else {
+        Err(SwapError::InvalidInstruction.into())
+    }
+}
+
+/// Unpacks a u64 appended to the data of an existing instruction, absent from
+/// the data of clients predating it
+fn unpack_optional_u64(input: &[u8]) -> Result<Option<u64>, ProgramError> {
+    if input.is_empty() {
+        Ok(None)
+    } else {
+        unpack_u64(input).map(|(value, _rest)| Some(value))
+    }
+}
+
+fn pack_optional_u64(buf: &mut Vec<u8>, value: Option<u64>) {
+    if let Some(value) = value {
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// Unpacks the optional deadline slot and expected sequence closing the data of
+/// swaps, deposits and withdrawals. The deadline comes first, zero when both are
+/// left out.
+fn unpack_deadline_and_sequence(input: &[u8]) -> Result<(u64, Option<u64>), ProgramError> {
+    if input.is_empty() {
+        return Ok((0, None));
+    }
+    let (deadline_slot, rest) = unpack_u64(input)?;
+    Ok((deadline_slot, unpack_optional_u64(rest)?))
+}
+
+fn pack_deadline_and_sequence(
+    buf: &mut Vec<u8>,
+    deadline_slot: u64,
+    expected_sequence: Option<u64>,
+) {
+    if deadline_slot != 0 || expected_sequence.is_some() {
+        buf.extend_from_slice(&deadline_slot.to_le_bytes());
+        pack_optional_u64(buf, expected_sequence);
+    }
+}
+
+fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+    if input.len() >= 8 {
+        let (amount, rest) = input.split_at(8);
+        let amount = amount
+            .get(..8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(SwapError::InvalidInstruction)?;
+        Ok((amount, rest))
+    } else {
+        Err(SwapError::InvalidInstruction.into())
+    }
+}
+
+fn unpack_u128(input: &[u8]) -> Result<(u128, &[u8]), ProgramError> {
+    if input.len() >= 16 {
+        let (amount, rest) = input.split_at(16);
+        let amount = amount
+            .get(..16)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u128::from_le_bytes)
+            .ok_or(SwapError::InvalidInstruction)?;
+        Ok((amount, rest))
+    } else {
+        Err(SwapError::InvalidInstruction.into())
+    }
+}
+
+/// Unpacks a string of at most `max_len` bytes, prefixed with its length
+fn unpack_str(input: &[u8], max_len: usize) -> Result<(String, &[u8]), ProgramError> {
+    let (&len, rest) = input.split_first().ok_or(SwapError::InvalidInstruction)?;
+    if len as usize > max_len || rest.len() < len as usize {
+        return Err(SwapError::InvalidInstruction.into());
+    }
+    let (bytes, rest) = rest.split_at(len as usize);
+    let s = String::from_utf8(bytes.to_vec()).map_err(|_| SwapError::InvalidInstruction)?;
+    Ok((s, rest))
+}
+
+fn pack_str(buf: &mut Vec<u8>, s: &str) {
+    buf.push(s.len() as u8);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn unpack_bytes32(input: &[u8]) -> Result<([u8; 32], &[u8]), ProgramError> {
+    if input.len() >= 32 {
+        let (bytes, rest) = input.split_at(32);
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| SwapError::InvalidInstruction)?;
+        Ok((bytes, rest))
+    } else {
+        Err(SwapError::InvalidInstruction.into())
+    }
+}
+
+/// Unpacks a reference from a bytes buffer.
+/// TODO actually pack / unpack instead of relying on normal memory layout.
+pub fn unpack<T>(input: &[u8]) -> Result<&T, ProgramError> {
+    if input.len() < size_of::<u8>() + size_of::<T>() {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    #[allow(clippy::cast_ptr_alignment)]
+    let val: &T = unsafe { &*(&input[1] as *const u8 as *const T) };
+    Ok(val)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_admin_instruction_packing() {
+        let target_amp = 100;
+        let stop_ramp_ts = i64::MAX;
+        let check = AdminInstruction::RampA(RampAData {
+            target_amp,
+            stop_ramp_ts,
+        });
+        let packed = check.pack();
+        let mut expect = vec![];
+        expect.push(100 as u8);
+        expect.extend_from_slice(&target_amp.to_le_bytes());
+        expect.extend_from_slice(&stop_ramp_ts.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::StopRampA;
+        let packed = check.pack();
+        let mut expect = vec![];
+        expect.push(101 as u8);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::Pause;
+        let packed = check.pack();
+        let mut expect = vec![];
+        expect.push(102 as u8);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::Unpause;
+        let packed = check.pack();
+        let mut expect = vec![];
+        expect.push(103 as u8);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::SetFeeAccount;
+        let packed = check.pack();
+        let mut expect = vec![];
+        expect.push(104 as u8);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::ApplyNewAdmin;
+        let packed = check.pack();
+        let mut expect = vec![];
+        expect.push(105 as u8);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::CommitNewAdmin;
+        let packed = check.pack();
+        let mut expect = vec![];
+        expect.push(106 as u8);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let new_fees = Fees {
+            admin_trade_fee_numerator: 1,
+            admin_trade_fee_denominator: 2,
+            admin_withdraw_fee_numerator: 3,
+            admin_withdraw_fee_denominator: 4,
+            trade_fee_numerator: 5,
+            trade_fee_denominator: 6,
+            withdraw_fee_numerator: 7,
+            withdraw_fee_denominator: 8,
+            reflection_fee_numerator: 9,
+            reflection_fee_denominator: 10,
+            buyback_fee_numerator: 11,
+            buyback_fee_denominator: 12,
+            marketing_fee_numerator: 13,
+            marketing_fee_denominator: 14,
+            developer_fee_numerator: 15,
+            developer_fee_denominator: 16,
+            admin_fee_in_pool_tokens: false,
+            fee_on_input: false,
+            imbalance_fee_numerator: 0,
+            imbalance_fee_denominator: 1,
+            imbalance_threshold_bps: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 1,
+        };
+        let check = AdminInstruction::SetNewFees(new_fees);
+        let packed = check.pack();
+        let mut expect = vec![];
+        expect.push(107 as u8);
+        let mut new_fees_slice = [0u8; Fees::LEN];
+        new_fees.pack_into_slice(&mut new_fees_slice[..]);
+        expect.extend_from_slice(&new_fees_slice);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let permissions = 0b101;
+        let expiry_slot = 1_000;
+        let nonce = 254;
+        let check = AdminInstruction::CreateAdminSession(CreateAdminSessionData {
+            permissions,
+            expiry_slot,
+            nonce,
+        });
+        let packed = check.pack();
+        let mut expect = vec![108, permissions];
+        expect.extend_from_slice(&expiry_slot.to_le_bytes());
+        expect.push(nonce);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::RevokeAdminSession;
+        let packed = check.pack();
+        let expect = vec![109];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let harvest_tip_bps: u16 = 25;
+        let check = AdminInstruction::SetHarvestTip(harvest_tip_bps);
+        let packed = check.pack();
+        let mut expect = vec![110];
+        expect.extend_from_slice(&harvest_tip_bps.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::SetReflectionDistributor;
+        let packed = check.pack();
+        let expect = vec![111];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let vesting_duration: i64 = 86_400;
+        let check = AdminInstruction::InitializeDevFeeVesting(vesting_duration);
+        let packed = check.pack();
+        let mut expect = vec![112];
+        expect.extend_from_slice(&vesting_duration.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let governance_lockup: i64 = 259_200;
+        let check = AdminInstruction::SetGovernanceRealm(governance_lockup);
+        let packed = check.pack();
+        let mut expect = vec![113];
+        expect.extend_from_slice(&governance_lockup.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let nonce: u8 = 251;
+        let check = AdminInstruction::SnapshotState(nonce);
+        let packed = check.pack();
+        let expect = vec![114, nonce];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::RestoreState;
+        let packed = check.pack();
+        let expect = vec![115];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let admin_timeout_slots: u64 = 216_000;
+        let check = AdminInstruction::SetAdminRecovery(admin_timeout_slots);
+        let packed = check.pack();
+        let mut expect = vec![116];
+        expect.extend_from_slice(&admin_timeout_slots.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::AdminHeartbeat;
+        let packed = check.pack();
+        let expect = vec![117];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::ClaimAdminAfterTimeout;
+        let packed = check.pack();
+        let expect = vec![118];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let max_swap_amount_in: u64 = 1_000_000;
+        let check = AdminInstruction::SetMaxSwapAmount(max_swap_amount_in);
+        let packed = check.pack();
+        let mut expect = vec![119];
+        expect.extend_from_slice(&max_swap_amount_in.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let ve_fee_bps: u16 = 2_500;
+        let check = AdminInstruction::InitializeVeEscrow(ve_fee_bps);
+        let packed = check.pack();
+        let mut expect = vec![120];
+        expect.extend_from_slice(&ve_fee_bps.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::SetVeFeeShare(ve_fee_bps);
+        let packed = check.pack();
+        let mut expect = vec![121];
+        expect.extend_from_slice(&ve_fee_bps.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let emission_per_epoch: u64 = 1_000_000;
+        let check = AdminInstruction::InitializeGaugeController(InitializeGaugeControllerData {
+            emission_per_epoch,
+            nonce,
+        });
+        let packed = check.pack();
+        let mut expect = vec![122];
+        expect.extend_from_slice(&emission_per_epoch.to_le_bytes());
+        expect.push(nonce);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::AddGauge(nonce);
+        let packed = check.pack();
+        let expect = vec![123, nonce];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::SetGaugeEmission(emission_per_epoch);
+        let packed = check.pack();
+        let mut expect = vec![124];
+        expect.extend_from_slice(&emission_per_epoch.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let batch_slots: u64 = 8;
+        let check = AdminInstruction::SetOrderFlowAuction(batch_slots);
+        let packed = check.pack();
+        let mut expect = vec![125];
+        expect.extend_from_slice(&batch_slots.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let min_amp: u64 = 20;
+        let max_amp: u64 = 400;
+        let volatility_ceiling: u64 = 5_000_000_000;
+        let max_drift_per_slot: u64 = 1;
+        let check = AdminInstruction::SetDynamicAmp(SetDynamicAmpData {
+            min_amp,
+            max_amp,
+            volatility_ceiling,
+            max_drift_per_slot,
+        });
+        let packed = check.pack();
+        let mut expect = vec![126];
+        expect.extend_from_slice(&min_amp.to_le_bytes());
+        expect.extend_from_slice(&max_amp.to_le_bytes());
+        expect.extend_from_slice(&volatility_ceiling.to_le_bytes());
+        expect.extend_from_slice(&max_drift_per_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let fee_buffer_bps: u16 = 2_000;
+        let deposit_bonus_bps: u16 = 50;
+        let check = AdminInstruction::SetDepositBonus(SetDepositBonusData {
+            fee_buffer_bps,
+            deposit_bonus_bps,
+        });
+        let packed = check.pack();
+        let mut expect = vec![127];
+        expect.extend_from_slice(&fee_buffer_bps.to_le_bytes());
+        expect.extend_from_slice(&deposit_bonus_bps.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let insurance_fee_bps: u16 = 1_000;
+        let check = AdminInstruction::SetInsuranceFund(insurance_fee_bps);
+        let packed = check.pack();
+        let mut expect = vec![128];
+        expect.extend_from_slice(&insurance_fee_bps.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let target_amp: u64 = 200;
+        let stop_ramp_ts: i64 = 86_400;
+        let max_swap_amount_in: u64 = 100_000;
+        let check = AdminInstruction::SetParams(SetParamsData {
+            fees: new_fees,
+            target_amp,
+            stop_ramp_ts,
+            max_swap_amount_in,
+            is_paused: true,
+        });
+        let packed = check.pack();
+        let mut expect = vec![129];
+        expect.extend_from_slice(&new_fees_slice);
+        expect.extend_from_slice(&target_amp.to_le_bytes());
+        expect.extend_from_slice(&stop_ramp_ts.to_le_bytes());
+        expect.extend_from_slice(&max_swap_amount_in.to_le_bytes());
+        expect.push(1);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let min_lp_mint: u64 = 1_000;
+        let check = AdminInstruction::SetMinLpMint(min_lp_mint);
+        let packed = check.pack();
+        let mut expect = vec![130];
+        expect.extend_from_slice(&min_lp_mint.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let dust_threshold: u64 = 100;
+        let check = AdminInstruction::SetDustThreshold(dust_threshold);
+        let packed = check.pack();
+        let mut expect = vec![131];
+        expect.extend_from_slice(&dust_threshold.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::InitializeLiquidityPositions;
+        let packed = check.pack();
+        let expect = vec![132];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let custodian_withdraw_threshold: u64 = 1_000;
+        let check = AdminInstruction::SetCustodian(custodian_withdraw_threshold);
+        let packed = check.pack();
+        let mut expect = vec![133];
+        expect.extend_from_slice(&custodian_withdraw_threshold.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::SetCredentialIssuer;
+        let packed = check.pack();
+        let expect = vec![134];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::UpdateLpMetadata(UpdateLpMetadataData {
+            name: "Baby Punk LP".to_string(),
+            symbol: "BPLP".to_string(),
+            uri: "".to_string(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![135, 12];
+        expect.extend_from_slice(b"Baby Punk LP");
+        expect.push(4);
+        expect.extend_from_slice(b"BPLP");
+        expect.push(0);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::SetGuardian;
+        let packed = check.pack();
+        let expect = vec![136];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::DeployProtocolLiquidity(DepositData {
+            token_a_amount: 10,
+            token_b_amount: 20,
+            min_mint_amount: 5,
+            deadline_slot: 0,
+            expected_sequence: Some(7),
+        });
+        let packed = check.pack();
+        let mut expect = vec![137];
+        expect.extend_from_slice(&10u64.to_le_bytes());
+        expect.extend_from_slice(&20u64.to_le_bytes());
+        expect.extend_from_slice(&5u64.to_le_bytes());
+        expect.extend_from_slice(&0u64.to_le_bytes());
+        expect.extend_from_slice(&7u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::ApplyNewFees;
+        let packed = check.pack();
+        let expect = vec![139];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::CancelPendingFees;
+        let packed = check.pack();
+        let expect = vec![140];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::SetCustomErrorMessage(SetCustomErrorMessageData {
+            error_code: 4,
+            message: "use pool XYZ".to_string(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![141];
+        expect.extend_from_slice(&4u32.to_le_bytes());
+        expect.push(12);
+        expect.extend_from_slice(b"use pool XYZ");
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::InitializeReflection;
+        let packed = check.pack();
+        let expect = vec![142];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::InitializeBuyback;
+        let packed = check.pack();
+        let expect = vec![143];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let nonce: u8 = 252;
+        let check = AdminInstruction::AddFeeExemption(nonce);
+        let packed = check.pack();
+        let expect = vec![144, nonce];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::RemoveFeeExemption;
+        let packed = check.pack();
+        let expect = vec![145];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let volatility_ceiling: u64 = 5_000_000_000;
+        let max_multiplier_bps: u16 = 25_000;
+        let check = AdminInstruction::SetVolatilityFee(SetVolatilityFeeData {
+            volatility_ceiling,
+            max_multiplier_bps,
+        });
+        let packed = check.pack();
+        let mut expect = vec![146];
+        expect.extend_from_slice(&volatility_ceiling.to_le_bytes());
+        expect.extend_from_slice(&max_multiplier_bps.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let cap_a: u64 = 50_000;
+        let cap_b: u64 = 0;
+        let check = AdminInstruction::SetAdminFeeEpochCap(SetAdminFeeEpochCapData { cap_a, cap_b });
+        let packed = check.pack();
+        let mut expect = vec![147];
+        expect.extend_from_slice(&cap_a.to_le_bytes());
+        expect.extend_from_slice(&cap_b.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let price_bound: u64 = 1_001_000_000_000;
+        let check = AdminInstruction::SetPriceBound(SetPriceBoundData { price_bound });
+        let packed = check.pack();
+        let mut expect = vec![148];
+        expect.extend_from_slice(&price_bound.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let emission_per_slot: u64 = 1_000;
+        let nonce: u8 = 253;
+        let check = AdminInstruction::InitializeFarm(InitializeFarmData {
+            emission_per_slot,
+            nonce,
+        });
+        let packed = check.pack();
+        let mut expect = vec![149];
+        expect.extend_from_slice(&emission_per_slot.to_le_bytes());
+        expect.push(nonce);
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let nonce: u8 = 251;
+        let check = AdminInstruction::AddAllowedDepositor(nonce);
+        let packed = check.pack();
+        let expect = vec![150, nonce];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::RemoveAllowedDepositor;
+        let packed = check.pack();
+        let expect = vec![151];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::SetDepositAllowlist(true);
+        let packed = check.pack();
+        let expect = vec![152, 1];
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+        assert_eq!(
+            AdminInstruction::unpack(&[152, 0]).unwrap(),
+            Some(AdminInstruction::SetDepositAllowlist(false))
+        );
+        assert!(AdminInstruction::unpack(&[152, 2]).is_err());
+
+        let check = AdminInstruction::SetOracleGuard(SetOracleGuardData {
+            max_staleness_slots: 25,
+            max_confidence_bps: 50,
+            max_deviation_bps: 100,
+        });
+        let packed = check.pack();
+        let mut expect = vec![153];
+        expect.extend_from_slice(&25u64.to_le_bytes());
+        expect.extend_from_slice(&50u16.to_le_bytes());
+        expect.extend_from_slice(&100u16.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        let check = AdminInstruction::SetOracleStalePolicy(SetOracleStalePolicyData {
+            stale_policy: OracleStalePolicy::WidenBand,
+            stale_band_bps: 200,
+        });
+        let packed = check.pack();
+        let mut expect = vec![154, 2];
+        expect.extend_from_slice(&200u16.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+        assert!(AdminInstruction::unpack(&[154, 3, 0, 0]).is_err());
+
+        let check = AdminInstruction::WithdrawProtocolLiquidity(WithdrawData {
+            pool_token_amount: 30,
+            minimum_token_a_amount: 1,
+            minimum_token_b_amount: 2,
+            deadline_slot: 40,
+            expected_sequence: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![138];
+        expect.extend_from_slice(&30u64.to_le_bytes());
+        expect.extend_from_slice(&1u64.to_le_bytes());
+        expect.extend_from_slice(&2u64.to_le_bytes());
+        expect.extend_from_slice(&40u64.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = AdminInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, Some(check));
+
+        // Symbols longer than the metadata program accepts
+        let mut input = vec![135, 0, MAX_SYMBOL_LENGTH as u8 + 1];
+        input.extend_from_slice(&[b'A'; MAX_SYMBOL_LENGTH + 1]);
+        input.push(0);
+        assert_eq!(
+            AdminInstruction::unpack(&input),
+            Err(SwapError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn test_swap_instruction_packing() {
+        let nonce: u8 = 255;
+        let amp_factor: u64 = 0;
+        let access_window_slots: u64 = 150;
+        let namespace = [7u8; 32];
+        let fee_tier = FeeTier::FiveBps;
+        let marketing_wallet = Pubkey::new_unique();
+        let developer_wallet = Pubkey::new_unique();
+        let curve_type = CurveType::PriceBounded;
+        let price_bound: u64 = 1_010_000_000_000;
+        let creator_window_slots: u64 = 20;
+        let freeze_authority_acknowledged = true;
+        let fees = Fees {
+            admin_trade_fee_numerator: 1,
+            admin_trade_fee_denominator: 2,
+            admin_withdraw_fee_numerator: 3,
+            admin_withdraw_fee_denominator: 4,
+            trade_fee_numerator: 5,
+            trade_fee_denominator: 6,
+            withdraw_fee_numerator: 7,
+            withdraw_fee_denominator: 8,
+            reflection_fee_numerator: 9,
+            reflection_fee_denominator: 10,
+            buyback_fee_numerator: 11,
+            buyback_fee_denominator: 12,
+            marketing_fee_numerator: 13,
+            marketing_fee_denominator: 14,
+            developer_fee_numerator: 15,
+            developer_fee_denominator: 16,
+            admin_fee_in_pool_tokens: false,
+            fee_on_input: false,
+            imbalance_fee_numerator: 0,
+            imbalance_fee_denominator: 1,
+            imbalance_threshold_bps: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 1,
+        };
+        let check = SwapInstruction::Initialize(InitializeData {
+            nonce,
+            amp_factor,
+            fees,
+            access_window_slots,
+            namespace,
+            fee_tier,
+            marketing_wallet,
+            developer_wallet,
+            curve_type,
+            price_bound,
+            creator_window_slots,
+            freeze_authority_acknowledged,
+        });
+        let packed = check.pack();
+        let mut expect = vec![];
+        expect.push(0 as u8);
+        expect.push(nonce);
+        expect.extend_from_slice(&amp_factor.to_le_bytes());
+        let mut fees_slice = [0u8; Fees::LEN];
+        fees.pack_into_slice(&mut fees_slice[..]);
+        expect.extend_from_slice(&fees_slice);
+        expect.extend_from_slice(&access_window_slots.to_le_bytes());
+        expect.extend_from_slice(&namespace);
+        expect.push(fee_tier as u8);
+        expect.extend_from_slice(marketing_wallet.as_ref());
+        expect.extend_from_slice(developer_wallet.as_ref());
+        expect.push(curve_type as u8);
+        expect.extend_from_slice(&price_bound.to_le_bytes());
+        expect.extend_from_slice(&creator_window_slots.to_le_bytes());
+        expect.push(freeze_authority_acknowledged as u8);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        expect.truncate(expect.len() - 1);
+        expect.push(2);
+        assert_eq!(
+            SwapInstruction::unpack(&expect),
+            Err(SwapError::InvalidInstruction.into())
+        );
+        expect.truncate(expect.len() - 18);
+        assert_eq!(
+            SwapInstruction::unpack(&expect),
+            Err(SwapError::InvalidInstruction.into())
+        );
+        expect.push(3);
+        expect.extend_from_slice(&price_bound.to_le_bytes());
+        expect.extend_from_slice(&creator_window_slots.to_le_bytes());
+        expect.push(freeze_authority_acknowledged as u8);
+        assert_eq!(
+            SwapInstruction::unpack(&expect),
+            Err(SwapError::InvalidInstruction.into())
+        );
+
+        let amount_in: u64 = 2;
+        let minimum_amount_out: u64 = 10;
+        let check = SwapInstruction::Swap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: 0,
+            expected_sequence: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![1];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let deadline_slot: u64 = 90;
+        let check = SwapInstruction::Swap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            expected_sequence: None,
+        });
+        let packed = check.pack();
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let expected_sequence: u64 = 7;
+        let check = SwapInstruction::Swap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot,
+            expected_sequence: Some(expected_sequence),
+        });
+        let packed = check.pack();
+        expect.extend_from_slice(&expected_sequence.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let token_a_amount: u64 = 10;
+        let token_b_amount: u64 = 20;
+        let min_mint_amount: u64 = 5;
+        let check = SwapInstruction::Deposit(DepositData {
+            token_a_amount,
+            token_b_amount,
+            min_mint_amount,
+            deadline_slot: 0,
+            expected_sequence: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![2];
+        expect.extend_from_slice(&token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&token_b_amount.to_le_bytes());
+        expect.extend_from_slice(&min_mint_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let expected_sequence: u64 = 7;
+        let check = SwapInstruction::Deposit(DepositData {
+            token_a_amount,
+            token_b_amount,
+            min_mint_amount,
+            deadline_slot: 0,
+            expected_sequence: Some(expected_sequence),
+        });
+        let packed = check.pack();
+        expect.extend_from_slice(&0u64.to_le_bytes());
+        expect.extend_from_slice(&expected_sequence.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let pool_token_amount: u64 = 1212438012089;
+        let minimum_token_a_amount: u64 = 102198761982612;
+        let minimum_token_b_amount: u64 = 2011239855213;
+        let check = SwapInstruction::Withdraw(WithdrawData {
+            pool_token_amount,
+            minimum_token_a_amount,
+            minimum_token_b_amount,
+            deadline_slot: 0,
+            expected_sequence: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![3];
+        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let deadline_slot: u64 = 90;
+        let check = SwapInstruction::Withdraw(WithdrawData {
+            pool_token_amount,
+            minimum_token_a_amount,
+            minimum_token_b_amount,
+            deadline_slot,
+            expected_sequence: None,
+        });
+        let packed = check.pack();
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let expected_sequence: u64 = 7;
+        let check = SwapInstruction::Withdraw(WithdrawData {
+            pool_token_amount,
+            minimum_token_a_amount,
+            minimum_token_b_amount,
+            deadline_slot,
+            expected_sequence: Some(expected_sequence),
+        });
+        let packed = check.pack();
+        expect.extend_from_slice(&expected_sequence.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let pool_token_amount: u64 = 1212438012089;
+        let minimum_token_amount: u64 = 102198761982612;
+        let check = SwapInstruction::WithdrawOne(WithdrawOneData {
+            pool_token_amount,
+            minimum_token_amount,
+            deadline_slot: 0,
+            expected_sequence: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![4];
+        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_token_amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let deadline_slot: u64 = 90;
+        let expected_sequence: u64 = 7;
+        let check = SwapInstruction::WithdrawOne(WithdrawOneData {
+            pool_token_amount,
+            minimum_token_amount,
+            deadline_slot,
+            expected_sequence: Some(expected_sequence),
+        });
+        let packed = check.pack();
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        expect.extend_from_slice(&expected_sequence.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount_in: u64 = 2;
+        let minimum_amount_out: u64 = 10;
+        let receipt_id: u64 = 42;
+        let receipt_nonce: u8 = 254;
+        let check = SwapInstruction::SwapWithReceipt(SwapWithReceiptData {
+            amount_in,
+            minimum_amount_out,
+            receipt_id,
+            receipt_nonce,
+            expected_sequence: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![5];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.extend_from_slice(&receipt_id.to_le_bytes());
+        expect.push(receipt_nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::CloseSwapReceipt;
+        let packed = check.pack();
+        let expect = vec![6];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::HarvestAdminFees;
+        let packed = check.pack();
+        let expect = vec![7];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let merkle_root = [9u8; 32];
+        let total_amount: u64 = 5_000;
+        let nonce: u8 = 253;
+        let check = SwapInstruction::CommitReflectionRoot(CommitReflectionRootData {
+            merkle_root,
+            total_amount,
+            nonce,
+        });
+        let packed = check.pack();
+        let mut expect = vec![8];
+        expect.extend_from_slice(&merkle_root);
+        expect.extend_from_slice(&total_amount.to_le_bytes());
+        expect.push(nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let index: u64 = 11;
+        let amount: u64 = 700;
+        let proof = vec![[1u8; 32], [2u8; 32]];
+        let check = SwapInstruction::ClaimReflectionWithProof(ClaimReflectionData {
+            index,
+            amount,
+            nonce,
+            proof: proof.clone(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![9];
+        expect.extend_from_slice(&index.to_le_bytes());
+        expect.extend_from_slice(&amount.to_le_bytes());
+        expect.push(nonce);
+        expect.push(2);
+        expect.extend_from_slice(&proof[0]);
+        expect.extend_from_slice(&proof[1]);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        // truncated proof
+        expect.pop();
+        assert_eq!(
+            SwapInstruction::unpack(&expect),
+            Err(SwapError::InvalidInstruction.into())
+        );
+
+        let check = SwapInstruction::ClaimVestedDevFees;
+        let packed = check.pack();
+        let expect = vec![10];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount: u64 = 4_000;
+        let deposit_nonce: u8 = 254;
+        let record_nonce: u8 = 252;
+        let check = SwapInstruction::DepositGovernanceTokens(DepositGovernanceTokensData {
+            amount,
+            deposit_nonce,
+            record_nonce,
+        });
+        let packed = check.pack();
+        let mut expect = vec![11];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        expect.push(deposit_nonce);
+        expect.push(record_nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::WithdrawGovernanceTokens(amount);
+        let packed = check.pack();
+        let mut expect = vec![12];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let pool_token_amount: u64 = 1_000_000;
+        let minimum_token_a_amount: u64 = 4_000;
+        let minimum_token_b_amount: u64 = 5_000;
+        let nonce: u8 = 253;
+        let check = SwapInstruction::BeginWithdraw(BeginWithdrawData {
+            pool_token_amount,
+            minimum_token_a_amount,
+            minimum_token_b_amount,
+            nonce,
+            expected_sequence: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![13];
+        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
+        expect.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+        expect.push(nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::ContinueWithdraw;
+        let packed = check.pack();
+        let expect = vec![14];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::EmitPoolSummary;
+        let packed = check.pack();
+        let expect = vec![15];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount: u64 = 1_000;
+        let lock_duration: i64 = 604_800;
+        let check = SwapInstruction::CreateVeLock(CreateVeLockData {
+            amount,
+            lock_duration,
+            nonce,
+        });
+        let packed = check.pack();
+        let mut expect = vec![16];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        expect.extend_from_slice(&lock_duration.to_le_bytes());
+        expect.push(nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::ClaimVeFees;
+        let packed = check.pack();
+        let expect = vec![17];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::WithdrawVeLock;
+        let packed = check.pack();
+        let expect = vec![18];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let weight_bps: u16 = 2_500;
+        let check = SwapInstruction::VoteGaugeWeight(VoteGaugeWeightData { weight_bps, nonce });
+        let packed = check.pack();
+        let mut expect = vec![19];
+        expect.extend_from_slice(&weight_bps.to_le_bytes());
+        expect.push(nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::CheckpointGauge;
+        let packed = check.pack();
+        let expect = vec![20];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount: u64 = 5_000;
+        let check = SwapInstruction::BidOrderFlow(amount);
+        let packed = check.pack();
+        let mut expect = vec![21];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount_in: u64 = 2;
+        let minimum_amount_out: u64 = 10;
+        let check = SwapInstruction::SimulateSwap(SwapData {
+            amount_in,
+            minimum_amount_out,
+            deadline_slot: 0,
+            expected_sequence: None,
+        });
+        let packed = check.pack();
+        let mut expect = vec![22];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount_a: u64 = 300;
+        let amount_b: u64 = 400;
+        let check = SwapInstruction::CoverShortfall(CoverShortfallData { amount_a, amount_b });
+        let packed = check.pack();
+        let mut expect = vec![23];
+        expect.extend_from_slice(&amount_a.to_le_bytes());
+        expect.extend_from_slice(&amount_b.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::ClonePool(ClonePoolData {
+            nonce,
+            namespace,
+            fee_tier,
+            freeze_authority_acknowledged: true,
+        });
+        let packed = check.pack();
+        let mut expect = vec![24, nonce];
+        expect.extend_from_slice(&namespace);
+        expect.push(fee_tier as u8);
+        expect.push(1);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+        // the acknowledgement is required
+        assert_eq!(
+            SwapInstruction::unpack(&expect[..expect.len() - 1]),
+            Err(SwapError::InvalidInstruction.into())
+        );
+
+        let check = SwapInstruction::SweepDust;
+        let packed = check.pack();
+        let expect = vec![25];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount: u64 = 6_000;
+        let check = SwapInstruction::DepositLiquidityPosition(DepositLiquidityPositionData {
+            amount,
+            nonce,
+        });
+        let packed = check.pack();
+        let mut expect = vec![26];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        expect.push(nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::WithdrawLiquidityPosition(amount);
+        let packed = check.pack();
+        let mut expect = vec![27];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::CheckpointLiquidityPosition;
+        let packed = check.pack();
+        let expect = vec![28];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::ReportEpochFees;
+        let packed = check.pack();
+        let expect = vec![29];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount_out: u64 = 1_000;
+        let maximum_amount_in: u64 = 1_100;
+        let deadline_slot: u64 = 90;
+        let expected_sequence: u64 = 7;
+        let check = SwapInstruction::SwapOut(SwapOutData {
+            amount_out,
+            maximum_amount_in,
+            deadline_slot,
+            expected_sequence: Some(expected_sequence),
+        });
+        let packed = check.pack();
+        let mut expect = vec![30];
+        expect.extend_from_slice(&amount_out.to_le_bytes());
+        expect.extend_from_slice(&maximum_amount_in.to_le_bytes());
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
+        expect.extend_from_slice(&expected_sequence.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::GetPoolStatus;
+        let packed = check.pack();
+        let expect = vec![31];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount_in: u64 = 1_000;
+        let minimum_amount_out: u64 = 900;
+        let leg_account_counts = vec![9, 11];
+        let check = SwapInstruction::Route(RouteData {
+            amount_in,
+            minimum_amount_out,
+            leg_account_counts: leg_account_counts.clone(),
+        });
+        let packed = check.pack();
+        let mut expect = vec![32];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.push(2);
+        expect.extend_from_slice(&leg_account_counts);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::CheckFeeDestinations;
+        let packed = check.pack();
+        let expect = vec![33];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount_in: u64 = 1_000_000;
+        let slice_count: u16 = 12;
+        let slice_interval_slots: u64 = 150;
+        let max_price_deviation_bps: u16 = 30;
+        let order_id: u64 = 9;
+        let order_nonce: u8 = 253;
+        let check = SwapInstruction::CreateTwapOrder(CreateTwapOrderData {
+            amount_in,
+            slice_count,
+            slice_interval_slots,
+            max_price_deviation_bps,
+            order_id,
+            order_nonce,
+        });
+        let packed = check.pack();
+        let mut expect = vec![34];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&slice_count.to_le_bytes());
+        expect.extend_from_slice(&slice_interval_slots.to_le_bytes());
+        expect.extend_from_slice(&max_price_deviation_bps.to_le_bytes());
+        expect.extend_from_slice(&order_id.to_le_bytes());
+        expect.push(order_nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::ExecuteTwapSlice;
+        let packed = check.pack();
+        let expect = vec![35];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::CancelTwapOrder;
+        let packed = check.pack();
+        let expect = vec![36];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+    }
+
+    #[test]
+    fn test_pack_triggers() {
+        let amount_in: u64 = 1_000_000;
+        let minimum_amount_out: u64 = 950_000;
+        let trigger_price: u128 = 1_020_000_000_000;
+        let bounty: u64 = 10_000;
+        let trigger_id: u64 = 3;
+        let trigger_nonce: u8 = 252;
+        let check = SwapInstruction::CreateTrigger(CreateTriggerData {
+            amount_in,
+            minimum_amount_out,
+            trigger_price,
+            trigger_above: false,
+            bounty,
+            trigger_id,
+            trigger_nonce,
+        });
+        let packed = check.pack();
+        let mut expect = vec![37];
+        expect.extend_from_slice(&amount_in.to_le_bytes());
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.extend_from_slice(&trigger_price.to_le_bytes());
+        expect.push(0);
+        expect.extend_from_slice(&bounty.to_le_bytes());
+        expect.extend_from_slice(&trigger_id.to_le_bytes());
+        expect.push(trigger_nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-    let accounts = vec![
-        AccountMeta::new(*swap_pubkey, false),
-        AccountMeta::new(*authority_pubkey, false),
-        AccountMeta::new(*pool_mint_pubkey, false),
-        AccountMeta::new(*source_pubkey, false),
-        AccountMeta::new(*swap_base_token_pubkey, false),
-        AccountMeta::new(*swap_quote_token_pubkey, false),
-        AccountMeta::new(*base_destination_pubkey, false),
-        AccountMeta::new(*admin_fee_destination_pubkey, false),
-        AccountMeta::new(*token_program_id, false),
-        AccountMeta::new(clock::id(), false),
-    ];
+        let mut invalid = expect.clone();
+        invalid[33] = 2;
+        assert_eq!(
+            SwapInstruction::unpack(&invalid),
+            Err(SwapError::InvalidInstruction.into())
+        );
 
-    Ok(Instruction {
-        program_id: *program_id,
-        accounts,
-        data,
-    })
-}
+        let check = SwapInstruction::ExecuteTrigger;
+        let packed = check.pack();
+        let expect = vec![38];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
-    if input.len() >= 8 {
-        let (amount, rest) = input.split_at(8);
-        let amount = amount
-            .get(..8)
-            .and_then(|slice| slice.try_into().ok())
-            .map(i64::from_le_bytes)
-            .ok_or(SwapError::InvalidInstruction)?;
-        Ok((amount, rest))
-    } else {
-        Err(SwapError::InvalidInstruction.into())
+        let check = SwapInstruction::CancelTrigger;
+        let packed = check.pack();
+        let expect = vec![39];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
     }
-}
 
-fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
-    if input.len() >= 8 {
-        let (amount, rest) = input.split_at(8);
-        let amount = amount
-            .get(..8)
-            .and_then(|slice| slice.try_into().ok())
-            .map(u64::from_le_bytes)
-            .ok_or(SwapError::InvalidInstruction)?;
-        Ok((amount, rest))
-    } else {
-        Err(SwapError::InvalidInstruction.into())
-    }
-}
+    #[test]
+    fn test_pack_drip_deposits() {
+        let amount_a: u64 = 5_000_000;
+        let amount_b: u64 = 4_000_000;
+        let tranche_count: u16 = 24;
+        let tranche_interval_slots: u64 = 9_000;
+        let drip_id: u64 = 1;
+        let drip_nonce: u8 = 250;
+        let check = SwapInstruction::CreateDripDeposit(CreateDripDepositData {
+            amount_a,
+            amount_b,
+            tranche_count,
+            tranche_interval_slots,
+            drip_id,
+            drip_nonce,
+        });
+        let packed = check.pack();
+        let mut expect = vec![40];
+        expect.extend_from_slice(&amount_a.to_le_bytes());
+        expect.extend_from_slice(&amount_b.to_le_bytes());
+        expect.extend_from_slice(&tranche_count.to_le_bytes());
+        expect.extend_from_slice(&tranche_interval_slots.to_le_bytes());
+        expect.extend_from_slice(&drip_id.to_le_bytes());
+        expect.push(drip_nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-/// Unpacks a reference from a bytes buffer.
-/// TODO actually pack / unpack instead of relying on normal memory layout.
-pub fn unpack<T>(input: &[u8]) -> Result<&T, ProgramError> {
-    if input.len() < size_of::<u8>() + size_of::<T>() {
-        return Err(ProgramError::InvalidAccountData);
-    }
-    #[allow(clippy::cast_ptr_alignment)]
-    let val: &T = unsafe { &*(&input[1] as *const u8 as *const T) };
-    Ok(val)
-}
+        let check = SwapInstruction::DepositDripTranche;
+        let packed = check.pack();
+        let expect = vec![41];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let check = SwapInstruction::CancelDripDeposit;
+        let packed = check.pack();
+        let expect = vec![42];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-    #[test]
-    fn test_admin_instruction_packing() {
-        let target_amp = 100;
-        let stop_ramp_ts = i64::MAX;
-        let check = AdminInstruction::RampA(RampAData {
-            target_amp,
-            stop_ramp_ts,
+        let nonce: u8 = 252;
+        let check = SwapInstruction::InitializeGlobalStats(nonce);
+        let packed = check.pack();
+        let expect = vec![43, nonce];
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let amount: u64 = 7_000;
+        let check = SwapInstruction::DepositReflectionPosition(DepositReflectionPositionData {
+            amount,
+            nonce,
         });
         let packed = check.pack();
-        let mut expect = vec![];
-        expect.push(100 as u8);
-        expect.extend_from_slice(&target_amp.to_le_bytes());
-        expect.extend_from_slice(&stop_ramp_ts.to_le_bytes());
+        let mut expect = vec![44];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        expect.push(nonce);
         assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, Some(check));
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-        let check = AdminInstruction::StopRampA;
+        let check = SwapInstruction::WithdrawReflectionPosition(amount);
         let packed = check.pack();
-        let mut expect = vec![];
-        expect.push(101 as u8);
+        let mut expect = vec![45];
+        expect.extend_from_slice(&amount.to_le_bytes());
         assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, Some(check));
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-        let check = AdminInstruction::Pause;
+        let check = SwapInstruction::ClaimReflection;
         let packed = check.pack();
-        let mut expect = vec![];
-        expect.push(102 as u8);
+        let expect = vec![46];
         assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, Some(check));
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-        let check = AdminInstruction::Unpause;
+        let minimum_amount_out: u64 = 900;
+        let check = SwapInstruction::ExecuteBuyback(minimum_amount_out);
         let packed = check.pack();
-        let mut expect = vec![];
-        expect.push(103 as u8);
+        let mut expect = vec![47];
+        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
         assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, Some(check));
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-        let check = AdminInstruction::SetFeeAccount;
+        let check = SwapInstruction::WithdrawMarketingFees;
         let packed = check.pack();
-        let mut expect = vec![];
-        expect.push(104 as u8);
+        let expect = vec![48];
         assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, Some(check));
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-        let check = AdminInstruction::ApplyNewAdmin;
+        let check = SwapInstruction::WithdrawDeveloperFees;
         let packed = check.pack();
-        let mut expect = vec![];
-        expect.push(105 as u8);
+        let expect = vec![49];
         assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, Some(check));
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-        let check = AdminInstruction::CommitNewAdmin;
+        let check = SwapInstruction::CreateReferralAccrual(nonce);
         let packed = check.pack();
-        let mut expect = vec![];
-        expect.push(106 as u8);
+        let expect = vec![50, nonce];
         assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, Some(check));
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-        let new_fees = Fees {
-            admin_trade_fee_numerator: 1,
-            admin_trade_fee_denominator: 2,
-            admin_withdraw_fee_numerator: 3,
-            admin_withdraw_fee_denominator: 4,
-            trade_fee_numerator: 5,
-            trade_fee_denominator: 6,
-            withdraw_fee_numerator: 7,
-            withdraw_fee_denominator: 8,
-            reflection_fee_numerator: 9,
-            reflection_fee_denominator: 10,
-            buyback_fee_numerator: 11,
-            buyback_fee_denominator: 12,
-            marketing_fee_numerator: 13,
-            marketing_fee_denominator: 14,
-            developer_fee_numerator: 15,
-            developer_fee_denominator: 16,
-        };
-        let check = AdminInstruction::SetNewFees(new_fees);
+        let check = SwapInstruction::ClaimReferralFees;
         let packed = check.pack();
-        let mut expect = vec![];
-        expect.push(107 as u8);
-        let mut new_fees_slice = [0u8; Fees::LEN];
-        new_fees.pack_into_slice(&mut new_fees_slice[..]);
-        expect.extend_from_slice(&new_fees_slice);
+        let expect = vec![51];
         assert_eq!(packed, expect);
-        let unpacked = AdminInstruction::unpack(&expect).unwrap();
-        assert_eq!(unpacked, Some(check));
-    }
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
 
-    #[test]
-    fn test_swap_instruction_packing() {
-        let nonce: u8 = 255;
-        let amp_factor: u64 = 0;
-        let fees = Fees {
-            admin_trade_fee_numerator: 1,
-            admin_trade_fee_denominator: 2,
-            admin_withdraw_fee_numerator: 3,
-            admin_withdraw_fee_denominator: 4,
-            trade_fee_numerator: 5,
-            trade_fee_denominator: 6,
-            withdraw_fee_numerator: 7,
-            withdraw_fee_denominator: 8,
-            reflection_fee_numerator: 9,
-            reflection_fee_denominator: 10,
-            buyback_fee_numerator: 11,
-            buyback_fee_denominator: 12,
-            marketing_fee_numerator: 13,
-            marketing_fee_denominator: 14,
-            developer_fee_numerator: 15,
-            developer_fee_denominator: 16,
-        };
-        let check = SwapInstruction::Initialize(InitializeData {
-            nonce,
-            amp_factor,
-            fees,
+        let token_amount: u64 = 4_000;
+        let min_mint_amount: u64 = 3_900;
+        let check = SwapInstruction::DepositOne(DepositOneData {
+            token_amount,
+            min_mint_amount,
+            deadline_slot: 0,
+            expected_sequence: None,
         });
         let packed = check.pack();
-        let mut expect = vec![];
-        expect.push(0 as u8);
-        expect.push(nonce);
-        expect.extend_from_slice(&amp_factor.to_le_bytes());
-        let mut fees_slice = [0u8; Fees::LEN];
-        fees.pack_into_slice(&mut fees_slice[..]);
-        expect.extend_from_slice(&fees_slice);
+        let mut expect = vec![52];
+        expect.extend_from_slice(&token_amount.to_le_bytes());
+        expect.extend_from_slice(&min_mint_amount.to_le_bytes());
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
 
-        let amount_in: u64 = 2;
-        let minimum_amount_out: u64 = 10;
-        let check = SwapInstruction::Swap(SwapData {
-            amount_in,
-            minimum_amount_out,
+        let deadline_slot: u64 = 90;
+        let check = SwapInstruction::DepositOne(DepositOneData {
+            token_amount,
+            min_mint_amount,
+            deadline_slot,
+            expected_sequence: None,
         });
         let packed = check.pack();
-        let mut expect = vec![1];
-        expect.extend_from_slice(&amount_in.to_le_bytes());
-        expect.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
 
-        let token_a_amount: u64 = 10;
-        let token_b_amount: u64 = 20;
-        let min_mint_amount: u64 = 5;
-        let check = SwapInstruction::Deposit(DepositData {
+        let token_a_amount: u64 = 2_000;
+        let token_b_amount: u64 = 500;
+        let maximum_pool_token_amount: u64 = 2_600;
+        let check = SwapInstruction::WithdrawImbalanced(WithdrawImbalancedData {
             token_a_amount,
             token_b_amount,
-            min_mint_amount,
+            maximum_pool_token_amount,
+            deadline_slot: 0,
+            expected_sequence: None,
         });
         let packed = check.pack();
-        let mut expect = vec![2];
+        let mut expect = vec![53];
         expect.extend_from_slice(&token_a_amount.to_le_bytes());
         expect.extend_from_slice(&token_b_amount.to_le_bytes());
-        expect.extend_from_slice(&min_mint_amount.to_le_bytes());
+        expect.extend_from_slice(&maximum_pool_token_amount.to_le_bytes());
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
 
-        let pool_token_amount: u64 = 1212438012089;
-        let minimum_token_a_amount: u64 = 102198761982612;
-        let minimum_token_b_amount: u64 = 2011239855213;
-        let check = SwapInstruction::Withdraw(WithdrawData {
-            pool_token_amount,
-            minimum_token_a_amount,
-            minimum_token_b_amount,
+        let deadline_slot: u64 = 90;
+        let check = SwapInstruction::WithdrawImbalanced(WithdrawImbalancedData {
+            token_a_amount,
+            token_b_amount,
+            maximum_pool_token_amount,
+            deadline_slot,
+            expected_sequence: None,
         });
         let packed = check.pack();
-        let mut expect = vec![3];
-        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
-        expect.extend_from_slice(&minimum_token_a_amount.to_le_bytes());
-        expect.extend_from_slice(&minimum_token_b_amount.to_le_bytes());
+        expect.extend_from_slice(&deadline_slot.to_le_bytes());
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
 
-        let pool_token_amount: u64 = 1212438012089;
-        let minimum_token_amount: u64 = 102198761982612;
-        let check = SwapInstruction::WithdrawOne(WithdrawOneData {
-            pool_token_amount,
-            minimum_token_amount,
-        });
+        let amount: u64 = 3_000;
+        let nonce: u8 = 252;
+        let check = SwapInstruction::Stake(StakeData { amount, nonce });
         let packed = check.pack();
-        let mut expect = vec![4];
-        expect.extend_from_slice(&pool_token_amount.to_le_bytes());
-        expect.extend_from_slice(&minimum_token_amount.to_le_bytes());
+        let mut expect = vec![54];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        expect.push(nonce);
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::Unstake(amount);
+        let packed = check.pack();
+        let mut expect = vec![55];
+        expect.extend_from_slice(&amount.to_le_bytes());
+        assert_eq!(packed, expect);
+        let unpacked = SwapInstruction::unpack(&expect).unwrap();
+        assert_eq!(unpacked, check);
+
+        let check = SwapInstruction::HarvestRewards;
+        let packed = check.pack();
+        let expect = vec![56];
         assert_eq!(packed, expect);
         let unpacked = SwapInstruction::unpack(&expect).unwrap();
         assert_eq!(unpacked, check);
@@ -0,0 +1,73 @@
+//! Insurance fund cover logged by `CoverShortfall` for monitoring
+
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+
+/// Prefix of the insurance cover log line
+pub const INSURANCE_COVER_PREFIX: &str = "insurance_cover";
+
+/// Shortfall covered from the insurance fund. Displays as a single line of space
+/// separated `key=value` pairs, led by `INSURANCE_COVER_PREFIX`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InsuranceCover {
+    /// Token-swap
+    pub swap: Pubkey,
+    /// Token A returned to the pool
+    pub amount_a: u64,
+    /// Token B returned to the pool
+    pub amount_b: u64,
+    /// Token A left in the insurance fund
+    pub insurance_fund_a: u64,
+    /// Token B left in the insurance fund
+    pub insurance_fund_b: u64,
+    /// Token A returned to the pool so far, this cover included
+    pub covered_a: u64,
+    /// Token B returned to the pool so far, this cover included
+    pub covered_b: u64,
+}
+
+impl fmt::Display for InsuranceCover {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} swap={} amount_a={} amount_b={} insurance_fund_a={} insurance_fund_b={} \
+             covered_a={} covered_b={}",
+            INSURANCE_COVER_PREFIX,
+            self.swap,
+            self.amount_a,
+            self.amount_b,
+            self.insurance_fund_a,
+            self.insurance_fund_b,
+            self.covered_a,
+            self.covered_b,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insurance_cover_display() {
+        let swap = Pubkey::new_from_array([1u8; 32]);
+        let cover = InsuranceCover {
+            swap,
+            amount_a: 100,
+            amount_b: 200,
+            insurance_fund_a: 300,
+            insurance_fund_b: 400,
+            covered_a: 500,
+            covered_b: 600,
+        };
+
+        assert_eq!(
+            cover.to_string(),
+            format!(
+                "insurance_cover swap={} amount_a=100 amount_b=200 insurance_fund_a=300 \
+                 insurance_fund_b=400 covered_a=500 covered_b=600",
+                swap
+            )
+        );
+    }
+}
@@ -0,0 +1,588 @@
+//! Sizes, field offsets and seeds of the program accounts, shared with the client
+//! SDK. The account sizes of the `Pack` implementations are taken from here, and
+//! `cargo xtask layout` renders the client constants from `ACCOUNTS` and `SEEDS`.
+//!
+//! Each account module declares a constant per field, holding its offset in the
+//! account data, in packing order.
+
+use crate::{
+    fees::Fees,
+    state::{AdminLog, CustomErrorMessages, MAX_ALLOWED_DEPOSITORS, MAX_FEE_EXEMPTIONS},
+    utils::{
+        ADMIN_SESSION_SEED, DEPOSIT_ALLOWLIST_SEED, DRIP_DEPOSIT_SEED, FARM_SEED,
+        FEE_EXEMPTIONS_SEED, GAUGE_CONTROLLER_SEED, GAUGE_SEED, GAUGE_VOTE_SEED, GLOBAL_STATS_SEED,
+        GOVERNANCE_DEPOSIT_SEED, LIQUIDITY_POSITION_SEED, PARAMETER_SNAPSHOT_SEED, POOL_SEED,
+        RECEIPT_SEED, REFERRAL_ACCRUAL_SEED, REFLECTION_CLAIM_SEED, REFLECTION_DISTRIBUTION_SEED,
+        REFLECTION_POSITION_SEED, STAKE_ACCOUNT_SEED, TRIGGER_SEED, TWAP_ORDER_SEED, VE_LOCK_SEED,
+        VOTER_WEIGHT_RECORD_SEED, WITHDRAW_CONTINUATION_SEED,
+    },
+};
+use solana_program::program_pack::Pack;
+
+/// Layout of an account
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AccountLayout {
+    /// Name of the account module
+    pub name: &'static str,
+    /// Size of the account, in bytes
+    pub len: usize,
+    /// Name and offset of each field, in packing order
+    pub fields: &'static [(&'static str, usize)],
+}
+
+macro_rules! account_layouts {
+    ($($(#[$meta:meta])* $module:ident { $($field:ident: $len:expr,)+ },)+) => {
+        $(
+            $(#[$meta])*
+            pub mod $module {
+                #![allow(missing_docs)]
+                use super::*;
+
+                account_layouts!(@offsets 0; $($field: $len,)+);
+
+                /// Size of the account, in bytes
+                pub const LEN: usize = 0 $(+ $len)+;
+
+                /// Layout of the account
+                pub const LAYOUT: AccountLayout = AccountLayout {
+                    name: stringify!($module),
+                    len: LEN,
+                    fields: &[$((stringify!($field), $field)),+],
+                };
+            }
+        )+
+
+        /// Layouts of all the program accounts
+        pub const ACCOUNTS: &[AccountLayout] = &[$($module::LAYOUT),+];
+    };
+    (@offsets $offset:expr; $field:ident: $len:expr, $($rest:tt)*) => {
+        pub const $field: usize = $offset;
+        account_layouts!(@offsets $field + $len; $($rest)*);
+    };
+    (@offsets $offset:expr;) => {};
+}
+
+account_layouts! {
+    /// `SwapInfo` account
+    swap_info {
+        IS_INITIALIZED: 1,
+        IS_PAUSED: 1,
+        NONCE: 1,
+        INITIAL_AMP_FACTOR: 8,
+        TARGET_AMP_FACTOR: 8,
+        START_RAMP_TS: 8,
+        STOP_RAMP_TS: 8,
+        FUTURE_ADMIN_DEADLINE: 8,
+        FUTURE_ADMIN_KEY: 32,
+        ADMIN_KEY: 32,
+        TOKEN_A: 32,
+        TOKEN_B: 32,
+        POOL_MINT: 32,
+        TOKEN_A_MINT: 32,
+        TOKEN_B_MINT: 32,
+        ADMIN_FEE_KEY_A: 32,
+        ADMIN_FEE_KEY_B: 32,
+        FEES: Fees::LEN,
+        ACCESS_MINT: 32,
+        ACCESS_WINDOW_END_SLOT: 8,
+        NAMESPACE: 32,
+        FEE_TIER: 1,
+        ADMIN_FEES_ACCRUED_A: 8,
+        ADMIN_FEES_ACCRUED_B: 8,
+        HARVEST_TIP_BPS: 2,
+        REFLECTION_DISTRIBUTOR: 32,
+        REFLECTION_EPOCH: 8,
+        DEV_VESTING_A: 32,
+        DEV_VESTING_B: 32,
+        DEV_WALLET: 32,
+        DEV_VESTING_START_TS: 8,
+        DEV_VESTING_END_TS: 8,
+        DEV_FEES_RECEIVED_A: 8,
+        DEV_FEES_RECEIVED_B: 8,
+        DEV_FEES_CLAIMED_A: 8,
+        DEV_FEES_CLAIMED_B: 8,
+        GOVERNANCE_REALM: 32,
+        GOVERNANCE_VAULT: 32,
+        GOVERNANCE_LOCKUP: 8,
+        ADMIN_RECOVERY_KEY: 32,
+        ADMIN_HEARTBEAT_SLOT: 8,
+        ADMIN_TIMEOUT_SLOTS: 8,
+        PENDING_WITHDRAW_A: 8,
+        PENDING_WITHDRAW_B: 8,
+        MAX_SWAP_AMOUNT_IN: 8,
+        LP_SOULBOUND: 1,
+        VE_LP_VAULT: 32,
+        VE_FEE_VAULT_A: 32,
+        VE_FEE_VAULT_B: 32,
+        VE_FEE_BPS: 2,
+        VE_SUPPLY: 8,
+        VE_EPOCH: 8,
+        VE_FEES_PER_VE_A: 16,
+        VE_FEES_PER_VE_B: 16,
+        VE_PENDING_FEES_A: 8,
+        VE_PENDING_FEES_B: 8,
+        ORDER_FLOW_BATCH_SLOTS: 8,
+        ORDER_FLOW_BID_TOKEN: 32,
+        ORDER_FLOW_BATCH: 8,
+        ORDER_FLOW_BIDDER: 32,
+        ORDER_FLOW_REFUND_ACCOUNT: 32,
+        ORDER_FLOW_BID: 8,
+        DYNAMIC_AMP_MIN: 8,
+        DYNAMIC_AMP_MAX: 8,
+        DYNAMIC_AMP_VOLATILITY_CEILING: 8,
+        DYNAMIC_AMP_MAX_DRIFT: 8,
+        TWAP_PRICE: 16,
+        TWAP_VOLATILITY: 8,
+        TWAP_SLOT: 8,
+        FEE_BUFFER_BPS: 2,
+        DEPOSIT_BONUS_BPS: 2,
+        FEE_BUFFER_A: 8,
+        FEE_BUFFER_B: 8,
+        INSURANCE_FEE_BPS: 2,
+        INSURANCE_AUTHORITY: 32,
+        INSURANCE_FUND_A: 8,
+        INSURANCE_FUND_B: 8,
+        INSURANCE_COVERED_A: 8,
+        INSURANCE_COVERED_B: 8,
+        MIN_LP_MINT: 8,
+        DUST_THRESHOLD: 8,
+        DUST_SWEEP_SLOT: 8,
+        DUST_SWEEP_COUNT: 2,
+        LIQUIDITY_VAULT: 32,
+        POSITION_LIQUIDITY: 8,
+        CUMULATIVE_POSITION_LIQUIDITY: 16,
+        POSITION_LIQUIDITY_TS: 8,
+        FEE_REPORT_EPOCH: 8,
+        EPOCH_LP_FEES_A: 8,
+        EPOCH_LP_FEES_B: 8,
+        EPOCH_ADMIN_FEES_A: 8,
+        EPOCH_ADMIN_FEES_B: 8,
+        EPOCH_REFLECTION_FEES_A: 8,
+        EPOCH_REFLECTION_FEES_B: 8,
+        EPOCH_BUYBACK_FEES_A: 8,
+        EPOCH_BUYBACK_FEES_B: 8,
+        EPOCH_MARKETING_FEES_A: 8,
+        EPOCH_MARKETING_FEES_B: 8,
+        EPOCH_DEVELOPER_FEES_A: 8,
+        EPOCH_DEVELOPER_FEES_B: 8,
+        SEQUENCE: 8,
+        CUSTODIAN: 32,
+        CUSTODIAN_WITHDRAW_THRESHOLD: 8,
+        CREDENTIAL_ISSUER: 32,
+        ADMIN_LOG: AdminLog::LEN,
+        ADMIN_FEE_ESCROW_A: 1,
+        ADMIN_FEE_ESCROW_B: 1,
+        PRICE_A_CUMULATIVE: 16,
+        PRICE_B_CUMULATIVE: 16,
+        PRICE_CUMULATIVE_SLOT: 8,
+        GUARDIAN_KEY: 32,
+        PROTOCOL_POOL_AMOUNT: 8,
+        PENDING_FEES: Fees::LEN,
+        PENDING_FEES_SLOT: 8,
+        CUSTOM_ERROR_MESSAGES: CustomErrorMessages::LEN,
+        REFLECTION_LP_VAULT: 32,
+        REFLECTION_VAULT_A: 32,
+        REFLECTION_VAULT_B: 32,
+        REFLECTION_SUPPLY: 8,
+        ACC_REFLECTION_PER_LP_SHARE_A: 16,
+        ACC_REFLECTION_PER_LP_SHARE_B: 16,
+        REFLECTION_PENDING_A: 8,
+        REFLECTION_PENDING_B: 8,
+        BUYBACK_MINT: 32,
+        BUYBACK_VAULT_A: 32,
+        BUYBACK_VAULT_B: 32,
+        BUYBACK_BURNED: 8,
+        MARKETING_WALLET: 32,
+        DEVELOPER_WALLET: 32,
+        MARKETING_FEES_ACCRUED_A: 8,
+        MARKETING_FEES_ACCRUED_B: 8,
+        DEVELOPER_FEES_ACCRUED_A: 8,
+        DEVELOPER_FEES_ACCRUED_B: 8,
+        CONFIG_HASH: 32,
+        VOLATILITY_FEE_CEILING: 8,
+        VOLATILITY_FEE_MAX_MULTIPLIER_BPS: 2,
+        REFERRAL_FEES_ACCRUED_A: 8,
+        REFERRAL_FEES_ACCRUED_B: 8,
+        ADMIN_FEE_EPOCH_CAP_A: 8,
+        ADMIN_FEE_EPOCH_CAP_B: 8,
+        ADMIN_FEE_CAP_EPOCH: 8,
+        ADMIN_FEES_TAKEN_A: 8,
+        ADMIN_FEES_TAKEN_B: 8,
+        CURVE_TYPE: 1,
+        PRICE_BOUND: 8,
+        CREATOR: 32,
+        CREATOR_DEPOSIT_END_SLOT: 8,
+        DEPOSIT_ALLOWLIST_ENABLED: 1,
+        FREEZE_AUTHORITY_ACKNOWLEDGED: 1,
+        ORACLE_A: 32,
+        ORACLE_B: 32,
+        ORACLE_MAX_STALENESS_SLOTS: 8,
+        ORACLE_MAX_CONFIDENCE_BPS: 2,
+        ORACLE_MAX_DEVIATION_BPS: 2,
+        ORACLE_STALE_POLICY: 1,
+        ORACLE_STALE_BAND_BPS: 2,
+    },
+    /// `SwapReceipt` account
+    swap_receipt {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        OWNER: 32,
+        SOURCE_MINT: 32,
+        DESTINATION_MINT: 32,
+        AMOUNT_IN: 8,
+        AMOUNT_OUT: 8,
+        FEE_AMOUNT: 8,
+        SLOT: 8,
+    },
+    /// `AdminSession` account
+    admin_session {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        ADMIN: 32,
+        SESSION_KEY: 32,
+        PERMISSIONS: 1,
+        EXPIRY_SLOT: 8,
+    },
+    /// `ReflectionDistribution` account
+    reflection_distribution {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        EPOCH: 8,
+        VAULT: 32,
+        MERKLE_ROOT: 32,
+        TOTAL_AMOUNT: 8,
+        CLAIMED_AMOUNT: 8,
+    },
+    /// `ReflectionClaim` account
+    reflection_claim {
+        IS_INITIALIZED: 1,
+        DISTRIBUTION: 32,
+        INDEX: 8,
+        CLAIMANT: 32,
+        AMOUNT: 8,
+    },
+    /// `GovernanceDeposit` account
+    governance_deposit {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        OWNER: 32,
+        AMOUNT: 8,
+        UNLOCK_TS: 8,
+    },
+    /// `VeLock` account
+    ve_lock {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        OWNER: 32,
+        AMOUNT: 8,
+        UNLOCK_TS: 8,
+        VE_BALANCE: 8,
+        FEES_DEBT_A: 16,
+        FEES_DEBT_B: 16,
+        GAUGE_VOTE_BPS: 2,
+    },
+    /// `ParameterSnapshot` account
+    parameter_snapshot {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        SNAPSHOT_TS: 8,
+        INITIAL_AMP_FACTOR: 8,
+        TARGET_AMP_FACTOR: 8,
+        START_RAMP_TS: 8,
+        STOP_RAMP_TS: 8,
+        FEES: Fees::LEN,
+        FEE_TIER: 1,
+        ADMIN_FEE_KEY_A: 32,
+        ADMIN_FEE_KEY_B: 32,
+        HARVEST_TIP_BPS: 2,
+    },
+    /// `WithdrawContinuation` account
+    withdraw_continuation {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        OWNER: 32,
+        DESTINATION_A: 32,
+        DESTINATION_B: 32,
+        TOKEN_A_AMOUNT: 8,
+        TOKEN_A_ADMIN_FEE: 8,
+        TOKEN_B_AMOUNT: 8,
+        TOKEN_B_ADMIN_FEE: 8,
+    },
+    /// `LiquidityPosition` account
+    liquidity_position {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        OWNER: 32,
+        AMOUNT: 8,
+        CUMULATIVE_LIQUIDITY: 16,
+        CHECKPOINT_TS: 8,
+    },
+    /// `ReflectionPosition` account
+    reflection_position {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        OWNER: 32,
+        AMOUNT: 8,
+        REFLECTION_DEBT_A: 16,
+        REFLECTION_DEBT_B: 16,
+        REFLECTION_OWED_A: 8,
+        REFLECTION_OWED_B: 8,
+    },
+    /// `TwapOrder` account
+    twap_order {
+        IS_INITIALIZED: 1,
+        NONCE: 1,
+        SWAP: 32,
+        OWNER: 32,
+        ORDER_ID: 8,
+        ESCROW: 32,
+        DESTINATION: 32,
+        AMOUNT_IN: 8,
+        SLICE_COUNT: 2,
+        SLICES_EXECUTED: 2,
+        SLICE_INTERVAL_SLOTS: 8,
+        MAX_PRICE_DEVIATION_BPS: 2,
+        LAST_SLICE_SLOT: 8,
+        PRICE_CUMULATIVE: 16,
+        PRICE_CUMULATIVE_SLOT: 8,
+        AMOUNT_OUT: 8,
+    },
+    /// `DripDeposit` account
+    drip_deposit {
+        IS_INITIALIZED: 1,
+        NONCE: 1,
+        SWAP: 32,
+        OWNER: 32,
+        DRIP_ID: 8,
+        ESCROW_A: 32,
+        ESCROW_B: 32,
+        DESTINATION: 32,
+        AMOUNT_A: 8,
+        AMOUNT_B: 8,
+        TRANCHE_COUNT: 2,
+        TRANCHES_DEPOSITED: 2,
+        TRANCHE_INTERVAL_SLOTS: 8,
+        LAST_TRANCHE_SLOT: 8,
+    },
+    /// `TriggerOrder` account
+    trigger_order {
+        IS_INITIALIZED: 1,
+        NONCE: 1,
+        SWAP: 32,
+        OWNER: 32,
+        TRIGGER_ID: 8,
+        ESCROW: 32,
+        DESTINATION: 32,
+        ORACLE: 32,
+        MINIMUM_AMOUNT_OUT: 8,
+        TRIGGER_PRICE: 16,
+        TRIGGER_ABOVE: 1,
+        BOUNTY: 8,
+    },
+    /// `GlobalStats` account
+    global_stats {
+        IS_INITIALIZED: 1,
+        NONCE: 1,
+        TOTAL_POOLS: 8,
+        TOTAL_VALUE_LOCKED: 16,
+        CUMULATIVE_VOLUME: 16,
+    },
+    /// `FeeExemptions` account
+    fee_exemptions {
+        IS_INITIALIZED: 1,
+        NONCE: 1,
+        SWAP: 32,
+        EXEMPT: 32 * MAX_FEE_EXEMPTIONS,
+    },
+    /// `DepositAllowlist` account
+    deposit_allowlist {
+        IS_INITIALIZED: 1,
+        NONCE: 1,
+        SWAP: 32,
+        DEPOSITORS: 32 * MAX_ALLOWED_DEPOSITORS,
+    },
+    /// `ReferralAccrual` account
+    referral_accrual {
+        IS_INITIALIZED: 1,
+        NONCE: 1,
+        SWAP: 32,
+        REFERRER: 32,
+        REFERRED_VOLUME_A: 16,
+        REFERRED_VOLUME_B: 16,
+        FEES_EARNED_A: 8,
+        FEES_EARNED_B: 8,
+        FEES_CLAIMED_A: 8,
+        FEES_CLAIMED_B: 8,
+    },
+    /// `GaugeController` account
+    gauge_controller {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        EMISSION_PER_EPOCH: 8,
+        EPOCH: 8,
+        TOTAL_NEXT_WEIGHT: 8,
+        EPOCH_TOTAL_WEIGHT: 8,
+        EPOCH_EMISSION: 8,
+    },
+    /// `Gauge` account
+    gauge {
+        IS_INITIALIZED: 1,
+        CONTROLLER: 32,
+        SWAP: 32,
+        NEXT_WEIGHT: 8,
+        EPOCH: 8,
+        EPOCH_WEIGHT: 8,
+        EMISSIONS: 8,
+    },
+    /// `GaugeVote` account
+    gauge_vote {
+        IS_INITIALIZED: 1,
+        GAUGE: 32,
+        OWNER: 32,
+        WEIGHT_BPS: 2,
+        WEIGHT: 8,
+    },
+    /// `Farm` account
+    farm {
+        IS_INITIALIZED: 1,
+        SWAP: 32,
+        POOL_MINT: 32,
+        REWARD_MINT: 32,
+        STAKE_VAULT: 32,
+        REWARD_VAULT: 32,
+        EMISSION_PER_SLOT: 8,
+        LAST_UPDATE_SLOT: 8,
+        ACC_REWARD_PER_SHARE: 16,
+        TOTAL_STAKED: 8,
+        REWARDS_UNHARVESTED: 8,
+    },
+    /// `StakeAccount` account
+    stake_account {
+        IS_INITIALIZED: 1,
+        FARM: 32,
+        OWNER: 32,
+        AMOUNT: 8,
+        REWARD_DEBT: 16,
+        REWARD_OWED: 8,
+    },
+    /// `VoterWeightRecord` account
+    voter_weight_record {
+        DISCRIMINATOR: 8,
+        REALM: 32,
+        GOVERNING_TOKEN_MINT: 32,
+        GOVERNING_TOKEN_OWNER: 32,
+        VOTER_WEIGHT: 8,
+        OPTIONS: 3,
+        _RESERVED: 8,
+    },
+}
+
+/// Seed prefixes of the program addresses, by name
+pub const SEEDS: &[(&str, &[u8])] = &[
+    ("RECEIPT_SEED", RECEIPT_SEED),
+    ("TWAP_ORDER_SEED", TWAP_ORDER_SEED),
+    ("TRIGGER_SEED", TRIGGER_SEED),
+    ("DRIP_DEPOSIT_SEED", DRIP_DEPOSIT_SEED),
+    ("ADMIN_SESSION_SEED", ADMIN_SESSION_SEED),
+    ("REFLECTION_DISTRIBUTION_SEED", REFLECTION_DISTRIBUTION_SEED),
+    ("REFLECTION_CLAIM_SEED", REFLECTION_CLAIM_SEED),
+    ("WITHDRAW_CONTINUATION_SEED", WITHDRAW_CONTINUATION_SEED),
+    ("PARAMETER_SNAPSHOT_SEED", PARAMETER_SNAPSHOT_SEED),
+    ("GOVERNANCE_DEPOSIT_SEED", GOVERNANCE_DEPOSIT_SEED),
+    ("VOTER_WEIGHT_RECORD_SEED", VOTER_WEIGHT_RECORD_SEED),
+    ("VE_LOCK_SEED", VE_LOCK_SEED),
+    ("LIQUIDITY_POSITION_SEED", LIQUIDITY_POSITION_SEED),
+    ("REFLECTION_POSITION_SEED", REFLECTION_POSITION_SEED),
+    ("GAUGE_CONTROLLER_SEED", GAUGE_CONTROLLER_SEED),
+    ("GAUGE_SEED", GAUGE_SEED),
+    ("GAUGE_VOTE_SEED", GAUGE_VOTE_SEED),
+    ("GLOBAL_STATS_SEED", GLOBAL_STATS_SEED),
+    ("FEE_EXEMPTIONS_SEED", FEE_EXEMPTIONS_SEED),
+    ("REFERRAL_ACCRUAL_SEED", REFERRAL_ACCRUAL_SEED),
+    ("FARM_SEED", FARM_SEED),
+    ("STAKE_ACCOUNT_SEED", STAKE_ACCOUNT_SEED),
+    ("DEPOSIT_ALLOWLIST_SEED", DEPOSIT_ALLOWLIST_SEED),
+    ("POOL_SEED", POOL_SEED),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{ReflectionPosition, SwapInfo};
+    use solana_program::pubkey::Pubkey;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_account_layouts() {
+        let mut names = HashSet::new();
+        for account in ACCOUNTS {
+            assert!(names.insert(account.name), "{}", account.name);
+            assert_eq!(account.fields[0].1, 0, "{}", account.name);
+            for pair in account.fields.windows(2) {
+                assert!(pair[0].1 < pair[1].1, "{}::{}", account.name, pair[1].0);
+            }
+            assert!(account.fields.last().unwrap().1 < account.len);
+        }
+        assert_eq!(ACCOUNTS[0], swap_info::LAYOUT);
+
+        let mut seeds = HashSet::new();
+        for (name, seed) in SEEDS {
+            assert!(name.ends_with("_SEED"));
+            assert!(seeds.insert(*seed), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_swap_info_offsets() {
+        let swap_info = SwapInfo {
+            is_paused: true,
+            nonce: 7,
+            token_a: Pubkey::new_unique(),
+            sequence: 0x0102_0304,
+            developer_fees_accrued_b: u64::MAX,
+            ..SwapInfo::default()
+        };
+        let mut packed = vec![0; SwapInfo::LEN];
+        swap_info.pack_into_slice(&mut packed);
+
+        assert_eq!(packed[swap_info::IS_PAUSED], 1);
+        assert_eq!(packed[swap_info::NONCE], 7);
+        assert_eq!(
+            &packed[swap_info::TOKEN_A..swap_info::TOKEN_B],
+            swap_info.token_a.as_ref()
+        );
+        let fees = &packed[swap_info::FEES..swap_info::FEES + Fees::LEN];
+        assert_eq!(Fees::unpack_from_slice(fees).unwrap(), swap_info.fees);
+        assert_eq!(
+            packed[swap_info::SEQUENCE..swap_info::SEQUENCE + 8],
+            0x0102_0304u64.to_le_bytes()
+        );
+        let developer_fees_accrued_b = swap_info::DEVELOPER_FEES_ACCRUED_B;
+        assert_eq!(
+            &packed[developer_fees_accrued_b..developer_fees_accrued_b + 8],
+            &[0xff; 8]
+        );
+    }
+
+    #[test]
+    fn test_reflection_position_offsets() {
+        let position = ReflectionPosition {
+            is_initialized: true,
+            owner: Pubkey::new_unique(),
+            reflection_owed_b: 9,
+            ..ReflectionPosition::default()
+        };
+        let mut packed = vec![0; ReflectionPosition::LEN];
+        position.pack_into_slice(&mut packed);
+
+        assert_eq!(packed[reflection_position::IS_INITIALIZED], 1);
+        assert_eq!(
+            &packed[reflection_position::OWNER..reflection_position::AMOUNT],
+            position.owner.as_ref()
+        );
+        assert_eq!(
+            &packed[reflection_position::REFLECTION_OWED_B..],
+            &9u64.to_le_bytes()
+        );
+    }
+}
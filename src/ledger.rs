@@ -0,0 +1,288 @@
+//! Shadow ledger checking token conservation, enabled by the `shadow-ledger` feature.
+//!
+//! Every token movement issued by the processor is recorded. Once an instruction
+//! succeeded, the balance change of each token account passed to it must match its
+//! recorded movements, and the balance changes of each mint must add up to the tokens
+//! it minted minus the tokens it burned. Meant for host test runs only.
+
+use solana_program::{account_info::AccountInfo, program_pack::Pack, pubkey::Pubkey};
+use spl_token::state::{Account, Mint};
+use std::{cell::RefCell, collections::BTreeMap};
+
+/// Token movement issued by the processor
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Movement {
+    Transfer {
+        source: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    },
+    MintTo {
+        mint: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+    },
+    Burn {
+        mint: Pubkey,
+        source: Pubkey,
+        amount: u64,
+    },
+}
+
+thread_local! {
+    static MOVEMENTS: RefCell<Vec<Movement>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record(movement: Movement) {
+    MOVEMENTS.with(|movements| movements.borrow_mut().push(movement));
+}
+
+/// Records a token transfer
+pub fn record_transfer(source: &Pubkey, destination: &Pubkey, amount: u64) {
+    record(Movement::Transfer {
+        source: *source,
+        destination: *destination,
+        amount,
+    });
+}
+
+/// Records newly minted tokens
+pub fn record_mint_to(mint: &Pubkey, destination: &Pubkey, amount: u64) {
+    record(Movement::MintTo {
+        mint: *mint,
+        destination: *destination,
+        amount,
+    });
+}
+
+/// Records burned tokens
+pub fn record_burn(mint: &Pubkey, source: &Pubkey, amount: u64) {
+    record(Movement::Burn {
+        mint: *mint,
+        source: *source,
+        amount,
+    });
+}
+
+/// Token balances and mint supplies of the accounts of an instruction
+#[derive(Debug, Default, PartialEq)]
+pub struct Snapshot {
+    /// Mint and amount of each token account
+    token_accounts: BTreeMap<Pubkey, (Pubkey, u64)>,
+    /// Supply of each mint
+    mints: BTreeMap<Pubkey, u64>,
+}
+
+impl Snapshot {
+    /// Snapshots the token accounts and mints of an instruction and starts recording
+    /// its token movements.
+    pub fn take(accounts: &[AccountInfo]) -> Self {
+        MOVEMENTS.with(|movements| movements.borrow_mut().clear());
+        Self::read(accounts)
+    }
+
+    fn read(accounts: &[AccountInfo]) -> Self {
+        let mut snapshot = Self::default();
+        for account_info in accounts {
+            if *account_info.owner != spl_token::id() {
+                continue;
+            }
+            let data = account_info.data.borrow();
+            if data.len() == Account::LEN {
+                if let Ok(account) = Account::unpack(&data) {
+                    snapshot
+                        .token_accounts
+                        .insert(*account_info.key, (account.mint, account.amount));
+                }
+            } else if data.len() == Mint::LEN {
+                if let Ok(mint) = Mint::unpack(&data) {
+                    snapshot.mints.insert(*account_info.key, mint.supply);
+                }
+            }
+        }
+        snapshot
+    }
+
+    /// Asserts that the token movements recorded since the snapshot account for all
+    /// balance changes of the accounts of the instruction.
+    pub fn check(&self, accounts: &[AccountInfo]) {
+        let mut after = Self::read(accounts);
+        // Token accounts closed by the instruction were emptied first
+        for (key, (mint, _)) in self.token_accounts.iter() {
+            after.token_accounts.entry(*key).or_insert((*mint, 0));
+        }
+        let mut expected_deltas: BTreeMap<Pubkey, i128> = BTreeMap::new();
+        let mut expected_supply_deltas: BTreeMap<Pubkey, i128> = BTreeMap::new();
+        MOVEMENTS.with(|movements| {
+            for movement in movements.borrow().iter() {
+                match *movement {
+                    Movement::Transfer {
+                        source,
+                        destination,
+                        amount,
+                    } => {
+                        *expected_deltas.entry(source).or_default() -= i128::from(amount);
+                        *expected_deltas.entry(destination).or_default() += i128::from(amount);
+                    }
+                    Movement::MintTo {
+                        mint,
+                        destination,
+                        amount,
+                    } => {
+                        *expected_supply_deltas.entry(mint).or_default() += i128::from(amount);
+                        *expected_deltas.entry(destination).or_default() += i128::from(amount);
+                    }
+                    Movement::Burn {
+                        mint,
+                        source,
+                        amount,
+                    } => {
+                        *expected_supply_deltas.entry(mint).or_default() -= i128::from(amount);
+                        *expected_deltas.entry(source).or_default() -= i128::from(amount);
+                    }
+                }
+            }
+        });
+
+        let mut mint_deltas: BTreeMap<Pubkey, i128> = BTreeMap::new();
+        for (key, (mint, amount)) in after.token_accounts.iter() {
+            let before = self
+                .token_accounts
+                .get(key)
+                .map_or(0, |(_, amount)| *amount);
+            let delta = i128::from(*amount) - i128::from(before);
+            assert_eq!(
+                delta,
+                expected_deltas.get(key).copied().unwrap_or_default(),
+                "unrecorded balance change of token account {}",
+                key
+            );
+            *mint_deltas.entry(*mint).or_default() += delta;
+        }
+        for (mint, delta) in mint_deltas.iter() {
+            let expected_supply_delta = expected_supply_deltas
+                .get(mint)
+                .copied()
+                .unwrap_or_default();
+            assert_eq!(
+                *delta, expected_supply_delta,
+                "tokens of mint {} are not conserved",
+                mint
+            );
+            if let (Some(before), Some(after)) = (self.mints.get(mint), after.mints.get(mint)) {
+                assert_eq!(
+                    i128::from(*after) - i128::from(*before),
+                    expected_supply_delta,
+                    "unrecorded supply change of mint {}",
+                    mint
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::program_option::COption;
+    use spl_token::state::AccountState;
+
+    fn token_account_data(mint: &Pubkey, amount: u64) -> Vec<u8> {
+        let mut data = vec![0; Account::LEN];
+        let account = Account {
+            mint: *mint,
+            owner: Pubkey::new_unique(),
+            amount,
+            delegate: COption::None,
+            state: AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        };
+        Account::pack(account, &mut data).unwrap();
+        data
+    }
+
+    fn set_amount(account_info: &AccountInfo, amount: u64) {
+        let mut account = Account::unpack(&account_info.data.borrow()).unwrap();
+        account.amount = amount;
+        Account::pack(account, &mut account_info.data.borrow_mut()).unwrap();
+    }
+
+    #[test]
+    fn test_recorded_transfer() {
+        let mint = Pubkey::new_unique();
+        let (source_key, destination_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut source_lamports, mut destination_lamports) = (0, 0);
+        let mut source_data = token_account_data(&mint, 100);
+        let mut destination_data = token_account_data(&mint, 0);
+        let token_program_id = spl_token::id();
+        let accounts = [
+            AccountInfo::new(
+                &source_key,
+                false,
+                true,
+                &mut source_lamports,
+                &mut source_data,
+                &token_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &destination_key,
+                false,
+                true,
+                &mut destination_lamports,
+                &mut destination_data,
+                &token_program_id,
+                false,
+                0,
+            ),
+        ];
+
+        let snapshot = Snapshot::take(&accounts);
+        record_transfer(&source_key, &destination_key, 40);
+        set_amount(&accounts[0], 60);
+        set_amount(&accounts[1], 40);
+        snapshot.check(&accounts);
+    }
+
+    #[test]
+    #[should_panic(expected = "unrecorded balance change")]
+    fn test_unrecorded_transfer() {
+        let mint = Pubkey::new_unique();
+        let (source_key, destination_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (mut source_lamports, mut destination_lamports) = (0, 0);
+        let mut source_data = token_account_data(&mint, 100);
+        let mut destination_data = token_account_data(&mint, 0);
+        let token_program_id = spl_token::id();
+        let accounts = [
+            AccountInfo::new(
+                &source_key,
+                false,
+                true,
+                &mut source_lamports,
+                &mut source_data,
+                &token_program_id,
+                false,
+                0,
+            ),
+            AccountInfo::new(
+                &destination_key,
+                false,
+                true,
+                &mut destination_lamports,
+                &mut destination_data,
+                &token_program_id,
+                false,
+                0,
+            ),
+        ];
+
+        let snapshot = Snapshot::take(&accounts);
+        record_transfer(&source_key, &destination_key, 30);
+        set_amount(&accounts[0], 60);
+        set_amount(&accounts[1], 40);
+        snapshot.check(&accounts);
+    }
+}
@@ -1,19 +1,45 @@
 #![deny(missing_docs)]
+// The layout of `SwapInfo` expands one macro call per field
+#![recursion_limit = "256"]
 
 //! An Uniswap-like program for the Solana blockchain.
 
+#[macro_use]
+pub mod logging;
+#[macro_use]
+mod constraints;
+
 pub mod admin;
 pub mod bn;
-pub mod curve;
 pub mod entrypoint;
 pub mod error;
+pub mod farm;
+pub mod fee_alert;
+pub mod fee_payout;
+pub mod fee_report;
 pub mod fees;
+pub mod gauge;
+pub mod governance;
 pub mod instruction;
-pub mod pool_converter;
+pub mod insurance;
+pub mod layout;
+#[cfg(feature = "shadow-ledger")]
+pub mod ledger;
+pub mod merkle;
+pub mod metadata;
+pub mod oracle;
 pub mod processor;
+pub mod quote;
 pub mod state;
+pub mod status;
+pub mod summary;
+pub mod swap_event;
+pub mod token_2022;
+pub mod twap;
 pub mod utils;
 
+pub use baby_punk_swap_math::{curve, pool_converter};
+
 // Export current solana-program types for downstream users who may also be
 // building with a different solana-program version
 pub use solana_program;
@@ -0,0 +1,40 @@
+//! Logging macros compiled in or out by the `log-level-*` features
+//!
+//! Each level logs everything the levels below it log: structured events are
+//! logged unless `log-level-none` is enabled, errors need `log-level-errors`,
+//! instruction names `log-level-info` and intermediate values
+//! `log-level-trace`. Disabled calls are removed at compile time, so a
+//! default build only spends compute on structured events.
+
+/// Logs a structured event, such as a report other programs or indexers parse
+macro_rules! log_event {
+    ($($arg:tt)*) => {
+        if cfg!(not(feature = "log-level-none")) {
+            solana_program::msg!($($arg)*);
+        }
+    };
+}
+
+/// Logs the name of the instruction being processed
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        if cfg!(all(feature = "log-level-info", not(feature = "log-level-none"))) {
+            solana_program::msg!($($arg)*);
+        }
+    };
+}
+
+/// Logs intermediate values, for debugging
+macro_rules! log_trace {
+    ($($arg:tt)*) => {
+        if cfg!(all(feature = "log-level-trace", not(feature = "log-level-none"))) {
+            solana_program::msg!($($arg)*);
+        }
+    };
+}
+
+/// Whether errors returned by the program are logged
+pub const LOG_ERRORS: bool = cfg!(all(
+    feature = "log-level-errors",
+    not(feature = "log-level-none")
+));
@@ -0,0 +1,69 @@
+//! Merkle proofs of reflection entitlements
+
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+/// Maximum number of nodes in a reflection proof
+pub const MAX_PROOF_LEN: usize = 32;
+
+/// Leaf of the reflection entitlement tree
+pub fn reflection_leaf(index: u64, claimant: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        &index.to_le_bytes(),
+        claimant.as_ref(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Hash of two sibling nodes, ordered so that proofs don't need to record sides
+pub fn hash_pair(first: &[u8; 32], second: &[u8; 32]) -> [u8; 32] {
+    if first <= second {
+        hashv(&[first, second]).to_bytes()
+    } else {
+        hashv(&[second, first]).to_bytes()
+    }
+}
+
+/// Checks that `proof` leads from `leaf` to `root`
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(&node, sibling));
+    computed == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify() {
+        let claimants: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> = claimants
+            .iter()
+            .enumerate()
+            .map(|(index, claimant)| reflection_leaf(index as u64, claimant, 100 * index as u64))
+            .collect();
+        // odd leaf count, the last leaf is promoted to the next level
+        let node_01 = hash_pair(&leaves[0], &leaves[1]);
+        let root = hash_pair(&node_01, &leaves[2]);
+
+        assert!(verify(&[leaves[1], leaves[2]], &root, leaves[0]));
+        assert!(verify(&[leaves[0], leaves[2]], &root, leaves[1]));
+        assert!(verify(&[node_01], &root, leaves[2]));
+
+        // wrong amount, wrong claimant, wrong proof
+        assert!(!verify(
+            &[node_01],
+            &root,
+            reflection_leaf(2, &claimants[2], 201)
+        ));
+        assert!(!verify(
+            &[node_01],
+            &root,
+            reflection_leaf(2, &claimants[1], 200)
+        ));
+        assert!(!verify(&[leaves[1]], &root, leaves[0]));
+        assert!(!verify(&[], &root, leaves[0]));
+    }
+}
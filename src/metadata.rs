@@ -0,0 +1,105 @@
+//! Metaplex token metadata of the pool mint. Only the metadata instruction the
+//! pool issues is encoded, in the Borsh layout of the metadata program.
+
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+solana_program::declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Seed of metadata program addresses
+pub const METADATA_SEED: &[u8] = b"metadata";
+/// Maximum length of a token name, in bytes
+pub const MAX_NAME_LENGTH: usize = 32;
+/// Maximum length of a token symbol, in bytes
+pub const MAX_SYMBOL_LENGTH: usize = 10;
+/// Maximum length of a token URI, in bytes
+pub const MAX_URI_LENGTH: usize = 200;
+
+/// Tag of the metadata program `UpdateMetadataAccountV2` instruction
+const UPDATE_METADATA_ACCOUNT_V2: u8 = 15;
+
+/// Finds the metadata account of `mint`
+pub fn find_metadata_address(mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[METADATA_SEED, id().as_ref(), mint.as_ref()], &id())
+}
+
+fn pack_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+/// Creates an `UpdateMetadataAccountV2` instruction replacing the name, symbol and
+/// URI of the metadata. Pool tokens pay no royalties and have no creators,
+/// collection or uses, the update authority and the mutability are kept.
+pub fn update_metadata_accounts_v2(
+    metadata_pubkey: &Pubkey,
+    update_authority_pubkey: &Pubkey,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+) -> Instruction {
+    let mut data = vec![UPDATE_METADATA_ACCOUNT_V2];
+    // Some(DataV2)
+    data.push(1);
+    pack_str(&mut data, name);
+    pack_str(&mut data, symbol);
+    pack_str(&mut data, uri);
+    // seller_fee_basis_points
+    data.extend_from_slice(&0u16.to_le_bytes());
+    // creators, collection, uses
+    data.extend_from_slice(&[0, 0, 0]);
+    // update_authority, primary_sale_happened, is_mutable
+    data.extend_from_slice(&[0, 0, 0]);
+
+    Instruction {
+        program_id: id(),
+        accounts: vec![
+            AccountMeta::new(*metadata_pubkey, false),
+            AccountMeta::new_readonly(*update_authority_pubkey, true),
+        ],
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_metadata_address() {
+        let mint = Pubkey::new_unique();
+        let (metadata, bump) = find_metadata_address(&mint);
+        assert_eq!(
+            Pubkey::create_program_address(
+                &[METADATA_SEED, id().as_ref(), mint.as_ref(), &[bump]],
+                &id()
+            ),
+            Ok(metadata)
+        );
+        assert_ne!(find_metadata_address(&Pubkey::new_unique()).0, metadata);
+    }
+
+    #[test]
+    fn test_update_metadata_accounts_v2() {
+        let metadata = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let ix = update_metadata_accounts_v2(&metadata, &authority, "LP", "BP", "u");
+        assert_eq!(ix.program_id, id());
+        assert_eq!(
+            ix.accounts,
+            vec![
+                AccountMeta::new(metadata, false),
+                AccountMeta::new_readonly(authority, true),
+            ]
+        );
+        assert_eq!(
+            ix.data,
+            vec![
+                15, 1, 2, 0, 0, 0, b'L', b'P', 2, 0, 0, 0, b'B', b'P', 1, 0, 0, 0, b'u', 0, 0, 0,
+                0, 0, 0, 0, 0
+            ]
+        );
+    }
+}
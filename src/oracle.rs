@@ -0,0 +1,366 @@
+//! Oracle guard of swaps. A Pyth price account per token prices both tokens of the
+//! pool in a common quote currency, swaps paying out more than the oracle value of
+//! their input, past a band, are rejected.
+
+use crate::{bn::U256, error::SwapError, twap::PRICE_SCALE};
+use arrayref::{array_ref, array_refs};
+pub use baby_punk_swap_math::oracle::OracleStalePolicy;
+
+/// Magic number of Pyth accounts
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+/// Version of the Pyth account layout
+const PYTH_VERSION: u32 = 2;
+/// Type of the Pyth accounts holding a price
+const PYTH_ACCOUNT_TYPE_PRICE: u32 = 3;
+/// Status of an aggregate price published while the token trades
+const PYTH_STATUS_TRADING: u32 = 1;
+/// Length of a Pyth price account up to the end of its aggregate price
+const PYTH_AGGREGATE_PRICE_END: usize = 240;
+/// Largest difference of the exponents of the two prices of the pool
+const MAX_EXPONENT_DIFFERENCE: i32 = 18;
+
+const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Aggregate price of a Pyth price account, `price * 10^exponent` units of the
+/// quote currency per token
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PythPrice {
+    /// Price, in units of `10^exponent`
+    pub price: i64,
+    /// Confidence interval of the price, in units of `10^exponent`
+    pub confidence: u64,
+    /// Decimal exponent of the price and confidence
+    pub exponent: i32,
+    /// Whether the price was published while the token trades
+    pub is_trading: bool,
+    /// Slot the price was published at
+    pub publish_slot: u64,
+}
+
+impl PythPrice {
+    /// Unpacks the aggregate price of Pyth price account data
+    pub fn unpack(data: &[u8]) -> Result<Self, SwapError> {
+        let data = data
+            .get(..PYTH_AGGREGATE_PRICE_END)
+            .ok_or(SwapError::InvalidOracle)?;
+        let data = array_ref![data, 0, PYTH_AGGREGATE_PRICE_END];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            magic,
+            version,
+            account_type,
+            _size,
+            _price_type,
+            exponent,
+            _,
+            price,
+            confidence,
+            status,
+            _corporate_action,
+            publish_slot,
+        ) = array_refs![data, 4, 4, 4, 4, 4, 4, 184, 8, 8, 4, 4, 8];
+        if u32::from_le_bytes(*magic) != PYTH_MAGIC
+            || u32::from_le_bytes(*version) != PYTH_VERSION
+            || u32::from_le_bytes(*account_type) != PYTH_ACCOUNT_TYPE_PRICE
+        {
+            return Err(SwapError::InvalidOracle);
+        }
+        Ok(Self {
+            price: i64::from_le_bytes(*price),
+            confidence: u64::from_le_bytes(*confidence),
+            exponent: i32::from_le_bytes(*exponent),
+            is_trading: u32::from_le_bytes(*status) == PYTH_STATUS_TRADING,
+            publish_slot: u64::from_le_bytes(*publish_slot),
+        })
+    }
+
+    /// Whether the price is not trading or was published more than
+    /// `max_staleness_slots` before `slot`
+    pub fn is_stale(&self, slot: u64, max_staleness_slots: u64) -> bool {
+        !self.is_trading || slot.saturating_sub(self.publish_slot) > max_staleness_slots
+    }
+
+    /// Whether the confidence interval is at most `max_confidence_bps` of the price
+    pub fn is_confident(&self, max_confidence_bps: u16) -> bool {
+        self.price > 0
+            && (self.confidence as u128) * (BPS_DENOMINATOR as u128)
+                <= (self.price as u128) * (max_confidence_bps as u128)
+    }
+}
+
+/// Price of token A in token B, scaled by `PRICE_SCALE`, of the oracle prices of
+/// both tokens in the same quote currency. Same-decimals pools make it the price
+/// of an amount of token A in an amount of token B.
+pub fn oracle_price(price_a: &PythPrice, price_b: &PythPrice) -> Result<U256, SwapError> {
+    if price_a.price <= 0 || price_b.price <= 0 {
+        return Err(SwapError::InvalidOracle);
+    }
+    let exponent_difference = price_a
+        .exponent
+        .checked_sub(price_b.exponent)
+        .filter(|difference| difference.abs() <= MAX_EXPONENT_DIFFERENCE)
+        .ok_or(SwapError::InvalidOracle)?;
+    let scale = U256::from(10u64.pow(exponent_difference.unsigned_abs()));
+    let (numerator, denominator) = if exponent_difference >= 0 {
+        (U256::from(price_a.price) * scale, U256::from(price_b.price))
+    } else {
+        (U256::from(price_a.price), U256::from(price_b.price) * scale)
+    };
+    Ok(numerator * U256::from(PRICE_SCALE) / denominator)
+}
+
+/// Oracle guard configuration of a pool
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OracleGuard {
+    /// Maximum age of the oracle prices, in slots
+    pub max_staleness_slots: u64,
+    /// Maximum confidence interval of the oracle prices, in basis points of the
+    /// price
+    pub max_confidence_bps: u16,
+    /// Band past the oracle value of their input swaps may pay out, in basis points
+    pub max_deviation_bps: u16,
+    /// What happens to swaps while a price is stale
+    pub stale_policy: OracleStalePolicy,
+    /// Band added to `max_deviation_bps` while a price is stale, for the
+    /// `WidenBand` policy
+    pub stale_band_bps: u16,
+}
+
+impl OracleGuard {
+    /// Checks a swap of `amount_in` into the pool for `amount_out` out of it against
+    /// the oracle prices: the swap must not pay out more than the oracle value of
+    /// `amount_in` and the deviation band besides, and both prices must be
+    /// confident. Stale prices are handled by the stale policy.
+    pub fn check_swap(
+        &self,
+        price_a: &PythPrice,
+        price_b: &PythPrice,
+        a_to_b: bool,
+        amount_in: u64,
+        amount_out: u64,
+        slot: u64,
+    ) -> Result<(), SwapError> {
+        let max_deviation_bps = if price_a.is_stale(slot, self.max_staleness_slots)
+            || price_b.is_stale(slot, self.max_staleness_slots)
+        {
+            match self.stale_policy {
+                OracleStalePolicy::Reject => return Err(SwapError::OracleStale),
+                OracleStalePolicy::Ignore => return Ok(()),
+                OracleStalePolicy::WidenBand => {
+                    self.max_deviation_bps as u64 + self.stale_band_bps as u64
+                }
+            }
+        } else {
+            self.max_deviation_bps as u64
+        };
+        if !price_a.is_confident(self.max_confidence_bps)
+            || !price_b.is_confident(self.max_confidence_bps)
+        {
+            return Err(SwapError::OracleConfidenceTooWide);
+        }
+        let price = oracle_price(price_a, price_b)?;
+        let scale = U256::from(PRICE_SCALE);
+        let (value_out, value_in) = if a_to_b {
+            (
+                U256::from(amount_out) * scale,
+                U256::from(amount_in) * price,
+            )
+        } else {
+            (
+                U256::from(amount_out) * price,
+                U256::from(amount_in) * scale,
+            )
+        };
+        if value_out * U256::from(BPS_DENOMINATOR)
+            > value_in * U256::from(BPS_DENOMINATOR + max_deviation_bps)
+        {
+            return Err(SwapError::OraclePriceDeviation);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::pyth_price_data;
+
+    fn price(price: i64, confidence: u64, exponent: i32) -> PythPrice {
+        PythPrice::unpack(&pyth_price_data(price, confidence, exponent, 100)).unwrap()
+    }
+
+    #[test]
+    fn test_unpack() {
+        // USDC/USD at 0.99995 +- 0.00004, exponent -8
+        let data = pyth_price_data(99_995_000, 4_000, -8, 1_234);
+        assert_eq!(
+            PythPrice::unpack(&data).unwrap(),
+            PythPrice {
+                price: 99_995_000,
+                confidence: 4_000,
+                exponent: -8,
+                is_trading: true,
+                publish_slot: 1_234,
+            }
+        );
+
+        let mut halted = data.clone();
+        halted[224..228].copy_from_slice(&2u32.to_le_bytes());
+        assert!(!PythPrice::unpack(&halted).unwrap().is_trading);
+
+        for offset in [0, 4, 8] {
+            let mut invalid = data.clone();
+            invalid[offset] ^= 1;
+            assert_eq!(PythPrice::unpack(&invalid), Err(SwapError::InvalidOracle));
+        }
+        assert_eq!(
+            PythPrice::unpack(&data[..PYTH_AGGREGATE_PRICE_END - 1]),
+            Err(SwapError::InvalidOracle)
+        );
+    }
+
+    #[test]
+    fn test_oracle_price() {
+        // same exponents
+        assert_eq!(
+            oracle_price(&price(100_000_000, 0, -8), &price(50_000_000, 0, -8)),
+            Ok(U256::from(2 * PRICE_SCALE))
+        );
+        // 1.0 at exponent -6 is 1.0 at exponent -8
+        assert_eq!(
+            oracle_price(&price(1_000_000, 0, -6), &price(100_000_000, 0, -8)),
+            Ok(U256::from(PRICE_SCALE))
+        );
+        assert_eq!(
+            oracle_price(&price(100_000_000, 0, -8), &price(1_000_000, 0, -6)),
+            Ok(U256::from(PRICE_SCALE))
+        );
+        assert_eq!(
+            oracle_price(&price(0, 0, -8), &price(1, 0, -8)),
+            Err(SwapError::InvalidOracle)
+        );
+        assert_eq!(
+            oracle_price(&price(1, 0, -8), &price(-1, 0, -8)),
+            Err(SwapError::InvalidOracle)
+        );
+        assert_eq!(
+            oracle_price(&price(1, 0, 12), &price(1, 0, -8)),
+            Err(SwapError::InvalidOracle)
+        );
+    }
+
+    #[test]
+    fn test_stale_policy_from_u8() {
+        for policy in [
+            OracleStalePolicy::Reject,
+            OracleStalePolicy::Ignore,
+            OracleStalePolicy::WidenBand,
+        ] {
+            assert_eq!(OracleStalePolicy::from_u8(policy as u8), Some(policy));
+        }
+        assert_eq!(OracleStalePolicy::from_u8(3), None);
+    }
+
+    #[test]
+    fn test_check_swap() {
+        let price_a = price(100_000_000, 10_000, -8);
+        let price_b = price(1_000_000, 100, -6);
+        let guard = OracleGuard {
+            max_staleness_slots: 20,
+            max_confidence_bps: 1,
+            max_deviation_bps: 50,
+            stale_policy: OracleStalePolicy::Reject,
+            stale_band_bps: 0,
+        };
+        let check = |a_to_b, amount_in, amount_out| {
+            guard.check_swap(&price_a, &price_b, a_to_b, amount_in, amount_out, 110)
+        };
+        // up to the band past the oracle value, in both directions
+        assert_eq!(check(true, 10_000, 10_050), Ok(()));
+        assert_eq!(check(false, 10_000, 10_050), Ok(()));
+        assert_eq!(check(true, 10_000, 9_000), Ok(()));
+        assert_eq!(
+            check(true, 10_000, 10_051),
+            Err(SwapError::OraclePriceDeviation)
+        );
+        assert_eq!(
+            check(false, 10_000, 10_051),
+            Err(SwapError::OraclePriceDeviation)
+        );
+
+        // a confidence of 1 bps of the price at most
+        let wide = price(100_000_000, 10_001, -8);
+        assert_eq!(
+            guard.check_swap(&wide, &price_b, true, 10_000, 10_000, 110),
+            Err(SwapError::OracleConfidenceTooWide)
+        );
+        assert_eq!(
+            guard.check_swap(&price_a, &wide, true, 10_000, 10_000, 110),
+            Err(SwapError::OracleConfidenceTooWide)
+        );
+
+        // published 20 slots before at most
+        assert_eq!(
+            guard.check_swap(&price_a, &price_b, true, 10_000, 10_000, 120),
+            Ok(())
+        );
+        assert_eq!(
+            guard.check_swap(&price_a, &price_b, true, 10_000, 10_000, 121),
+            Err(SwapError::OracleStale)
+        );
+        let halted = PythPrice {
+            is_trading: false,
+            ..price_a
+        };
+        assert_eq!(
+            guard.check_swap(&halted, &price_b, true, 10_000, 10_000, 110),
+            Err(SwapError::OracleStale)
+        );
+    }
+
+    #[test]
+    fn test_check_swap_stale_policy() {
+        let price_a = price(100_000_000, 10_000, -8);
+        let price_b = price(1_000_000, 100, -6);
+        let guard = OracleGuard {
+            max_staleness_slots: 20,
+            max_confidence_bps: 1,
+            max_deviation_bps: 50,
+            stale_policy: OracleStalePolicy::Ignore,
+            stale_band_bps: 100,
+        };
+        // stale prices leave swaps unchecked
+        assert_eq!(
+            guard.check_swap(&price_a, &price_b, true, 10_000, 20_000, 121),
+            Ok(())
+        );
+        assert_eq!(
+            guard.check_swap(&price_a, &price_b, true, 10_000, 20_000, 120),
+            Err(SwapError::OraclePriceDeviation)
+        );
+
+        // stale prices widen the band by 100 bps, fresh prices don't
+        let guard = OracleGuard {
+            stale_policy: OracleStalePolicy::WidenBand,
+            ..guard
+        };
+        assert_eq!(
+            guard.check_swap(&price_a, &price_b, true, 10_000, 10_150, 121),
+            Ok(())
+        );
+        assert_eq!(
+            guard.check_swap(&price_a, &price_b, true, 10_000, 10_151, 121),
+            Err(SwapError::OraclePriceDeviation)
+        );
+        assert_eq!(
+            guard.check_swap(&price_a, &price_b, true, 10_000, 10_150, 120),
+            Err(SwapError::OraclePriceDeviation)
+        );
+        // still confident
+        let wide = price(100_000_000, 10_001, -8);
+        assert_eq!(
+            guard.check_swap(&wide, &price_b, true, 10_000, 10_000, 121),
+            Err(SwapError::OracleConfidenceTooWide)
+        );
+    }
+}
@@ -1,42 +1,332 @@
 //! Program state processor
 
 use crate::{
-    admin::process_admin_instruction,
-    bn::U256,
-    curve::{StableSwap, MAX_AMP, MIN_AMP, ZERO_TS},
+    admin::{process_admin_instruction, record_admin_action},
+    bn::{U256Conversions, U256},
+    curve::{
+        ConstantProduct, CurveType, PoolCurve, PriceBounded, StableSwap, SwapCurve, SwapResultV2,
+        TransferFee, MAX_AMP, MIN_AMP, PRICE_BOUND_SCALE, ZERO_TS,
+    },
     error::SwapError,
-    fees::Fees,
+    farm::{Farm, StakeAccount},
+    fee_alert::{FeeDestinationAlert, FeeDestinationFault},
+    fee_payout::{FeeBucket, FeePayout},
+    fees::{FeeTier, Fees, FeesValidation},
+    gauge::{Gauge, GaugeController, GaugeVote},
+    governance::VoterWeightRecord,
     instruction::{
-        AdminInstruction, DepositData, InitializeData, SwapData, SwapInstruction, WithdrawData,
+        AdminInstruction, BeginWithdrawData, ClaimReflectionData, ClonePoolData,
+        CommitReflectionRootData, CoverShortfallData, CreateDripDepositData, CreateTriggerData,
+        CreateTwapOrderData, CreateVeLockData, DepositData, DepositGovernanceTokensData,
+        DepositLiquidityPositionData, DepositOneData, DepositReflectionPositionData,
+        InitializeData, RouteData, StakeData, SwapData, SwapInstruction, SwapOutData,
+        SwapWithReceiptData, VoteGaugeWeightData, WithdrawData, WithdrawImbalancedData,
         WithdrawOneData,
     },
+    insurance::InsuranceCover,
+    merkle,
+    oracle::{OracleStalePolicy, PythPrice},
     pool_converter::PoolTokenConverter,
-    state::SwapInfo,
-    utils,
+    quote::SwapQuote,
+    state::{
+        ve_fees, AdminLog, CustomErrorMessages, DepositAllowlist, DripDeposit, FeeExemptions,
+        GlobalStats, GovernanceDeposit, LiquidityPosition, ReferralAccrual, ReflectionClaim,
+        ReflectionDistribution, ReflectionPosition, SwapInfo, SwapReceipt, TriggerOrder, TwapOrder,
+        VeLock, WithdrawContinuation, MAX_TWAP_PRICE_DEVIATION_BPS, VE_EPOCH_DURATION,
+        VE_MAX_LOCK_DURATION, VE_MIN_LOCK_DURATION,
+    },
+    status::PoolStatus,
+    summary::PoolSummary,
+    swap_event::SwapEvent,
+    token_2022,
+    twap::TWAP_WINDOW_SLOTS,
+    utils::{
+        self, DEPOSIT_ALLOWLIST_SEED, DRIP_DEPOSIT_SEED, FEE_EXEMPTIONS_SEED, GAUGE_VOTE_SEED,
+        GLOBAL_STATS_SEED, GOVERNANCE_DEPOSIT_SEED, LIQUIDITY_POSITION_SEED, POOL_SEED,
+        RECEIPT_SEED, REFERRAL_ACCRUAL_SEED, REFLECTION_CLAIM_SEED, REFLECTION_DISTRIBUTION_SEED,
+        REFLECTION_POSITION_SEED, STAKE_ACCOUNT_SEED, TRIGGER_SEED, TWAP_ORDER_SEED, VE_LOCK_SEED,
+        VOTER_WEIGHT_RECORD_SEED, WITHDRAW_CONTINUATION_SEED,
+    },
 };
 use num_traits::FromPrimitive;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::Slot,
     decode_error::DecodeError,
     entrypoint::ProgramResult,
+    hash::Hash,
     msg,
-    program::invoke_signed,
+    program::{invoke, invoke_signed},
     program_error::PrintProgramError,
     program_error::ProgramError,
-    // program_option::COption,
-    program_pack::Pack,
-    pubkey::Pubkey,
-    sysvar::{clock::Clock, Sysvar},
+    program_option::COption,
+    program_pack::{IsInitialized, Pack},
+    program_utils::limited_deserialize,
+    pubkey::{Pubkey, PubkeyError},
+    system_instruction,
+    sysvar::{clock::Clock, rent::Rent, Sysvar},
 };
 use spl_token::state::Mint;
+use std::convert::TryFrom;
+
+/// Side of a swap the amount is exact on, with the bound of the other side
+#[derive(Clone, Copy)]
+enum SwapAmount {
+    /// Swap `amount_in`, paying out at least `minimum_amount_out`
+    ExactIn {
+        amount_in: u64,
+        minimum_amount_out: u64,
+    },
+    /// Pay out `amount_out`, swapping at most `maximum_amount_in`
+    ExactOut {
+        amount_out: u64,
+        maximum_amount_in: u64,
+    },
+}
 
 /// Program state handler. (and general curve params)
 pub struct Processor {}
 
 impl Processor {
-    /// Unpacks a spl_token `Mint`.
+    /// Unpacks a spl_token `Mint`, or the base state of a Token-2022 mint.
     pub fn unpack_mint(data: &[u8]) -> Result<Mint, SwapError> {
-        Mint::unpack(data).map_err(|_| SwapError::ExpectedMint)
+        token_2022::mint_state(data)
+            .and_then(|data| Mint::unpack(data).ok())
+            .ok_or(SwapError::ExpectedMint)
+    }
+
+    /// Checks the custodian signed a withdrawal above its threshold. The custodian
+    /// may be any of the optional trailing accounts.
+    fn check_custodian(
+        token_swap: &SwapInfo,
+        pool_token_amount: u64,
+        trailing_infos: &[AccountInfo],
+    ) -> ProgramResult {
+        if token_swap.requires_custodian(pool_token_amount)
+            && !trailing_infos
+                .iter()
+                .any(|info| info.is_signer && *info.key == token_swap.custodian)
+        {
+            return Err(SwapError::CustodianSignatureRequired.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that `slot` is not past the deadline slot of the instruction, if any
+    fn check_deadline(slot: Slot, deadline_slot: u64) -> ProgramResult {
+        if deadline_slot != 0 && slot > deadline_slot {
+            return Err(SwapError::DeadlineExceeded.into());
+        }
+        Ok(())
+    }
+
+    /// Finds a `T` account of the program among the trailing accounts, recognized
+    /// by its key being its `address`, the program address of its seeds and of the
+    /// nonce it stores. Accounts of other types, of the same length or not, are
+    /// never at that address.
+    fn find_program_account<'b, 'a, T: Pack + IsInitialized>(
+        program_id: &Pubkey,
+        trailing_infos: &'b [AccountInfo<'a>],
+        address: impl Fn(&T) -> Result<Pubkey, PubkeyError>,
+    ) -> Option<(&'b AccountInfo<'a>, T)> {
+        trailing_infos
+            .iter()
+            .filter(|info| info.owner == program_id && info.data_len() == T::LEN)
+            .find_map(|info| {
+                let account = T::unpack(&info.data.borrow()).ok()?;
+                (address(&account).ok()? == *info.key).then_some((info, account))
+            })
+    }
+
+    /// Applies `update` to the global stats if they are among the trailing
+    /// accounts, recognized by their program address. Best effort: the account is
+    /// optional, so the global stats miss the instructions not passing it.
+    fn update_global_stats_best_effort(
+        program_id: &Pubkey,
+        trailing_infos: &[AccountInfo],
+        update: impl FnOnce(&mut GlobalStats),
+    ) -> ProgramResult {
+        let (global_stats_info, mut global_stats) = match Self::find_program_account(
+            program_id,
+            trailing_infos,
+            |global_stats: &GlobalStats| {
+                Pubkey::create_program_address(
+                    &[GLOBAL_STATS_SEED, &[global_stats.nonce]],
+                    program_id,
+                )
+            },
+        ) {
+            Some(global_stats) => global_stats,
+            None => return Ok(()),
+        };
+        update(&mut global_stats);
+        GlobalStats::pack(global_stats, &mut global_stats_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Whether the owner of `source_info` is exempt from the swap fees of the swap,
+    /// by the fee exemptions account of the swap among the trailing accounts,
+    /// recognized by its program address
+    fn is_fee_exempt(
+        program_id: &Pubkey,
+        swap_key: &Pubkey,
+        source_info: &AccountInfo,
+        trailing_infos: &[AccountInfo],
+    ) -> Result<bool, ProgramError> {
+        let fee_exemptions = match Self::find_program_account(
+            program_id,
+            trailing_infos,
+            |fee_exemptions: &FeeExemptions| {
+                Pubkey::create_program_address(
+                    &[
+                        FEE_EXEMPTIONS_SEED,
+                        swap_key.as_ref(),
+                        &[fee_exemptions.nonce],
+                    ],
+                    program_id,
+                )
+            },
+        ) {
+            Some((_, fee_exemptions)) => fee_exemptions,
+            None => return Ok(false),
+        };
+        let source_account = utils::unpack_token_account(&source_info.data.borrow())?;
+        Ok(fee_exemptions.is_exempt(&source_account.owner))
+    }
+
+    /// Finds the referral accrual of a referrer in the swap among the trailing
+    /// accounts, recognized by its program address.
+    fn find_referral_accrual<'b, 'a>(
+        program_id: &Pubkey,
+        swap_key: &Pubkey,
+        trailing_infos: &'b [AccountInfo<'a>],
+    ) -> Option<(&'b AccountInfo<'a>, ReferralAccrual)> {
+        Self::find_program_account(
+            program_id,
+            trailing_infos,
+            |referral_accrual: &ReferralAccrual| {
+                Pubkey::create_program_address(
+                    &[
+                        REFERRAL_ACCRUAL_SEED,
+                        swap_key.as_ref(),
+                        referral_accrual.referrer.as_ref(),
+                        &[referral_accrual.nonce],
+                    ],
+                    program_id,
+                )
+            },
+        )
+    }
+
+    /// Checks that `signer_info` is the upgrade authority of the program, by the
+    /// program data among the trailing accounts
+    fn check_upgrade_authority(
+        program_id: &Pubkey,
+        signer_info: &AccountInfo,
+        trailing_infos: &[AccountInfo],
+    ) -> ProgramResult {
+        let program_data_key = utils::find_program_data_address(program_id);
+        let program_data_info = trailing_infos
+            .iter()
+            .find(|info| {
+                *info.key == program_data_key && info.owner == &bpf_loader_upgradeable::id()
+            })
+            .ok_or(SwapError::CanonicalNamespaceReserved)?;
+        let upgrade_authority = match limited_deserialize(
+            &program_data_info.data.borrow(),
+            UpgradeableLoaderState::size_of_programdata_metadata() as u64,
+        ) {
+            Ok(UpgradeableLoaderState::ProgramData {
+                upgrade_authority_address,
+                ..
+            }) => upgrade_authority_address,
+            _ => None,
+        };
+        if !signer_info.is_signer || upgrade_authority != Some(*signer_info.key) {
+            return Err(SwapError::CanonicalNamespaceReserved.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that `depositor` created the swap while the deposits of the swap are
+    /// reserved to its creator at `slot`
+    fn check_creator_deposit_window(
+        token_swap: &SwapInfo,
+        slot: Slot,
+        depositor: &Pubkey,
+    ) -> ProgramResult {
+        if slot < token_swap.creator_deposit_end_slot && *depositor != token_swap.creator {
+            return Err(SwapError::CreatorDepositWindow.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that `depositor` is on the deposit allowlist of the swap, among the
+    /// trailing accounts at its program address, when the swap restricts its deposits
+    fn check_deposit_allowlist(
+        program_id: &Pubkey,
+        swap_key: &Pubkey,
+        token_swap: &SwapInfo,
+        depositor: &Pubkey,
+        trailing_infos: &[AccountInfo],
+    ) -> ProgramResult {
+        if !token_swap.deposit_allowlist_enabled {
+            return Ok(());
+        }
+        let (_, allowlist) = Self::find_program_account(
+            program_id,
+            trailing_infos,
+            |allowlist: &DepositAllowlist| {
+                Pubkey::create_program_address(
+                    &[
+                        DEPOSIT_ALLOWLIST_SEED,
+                        swap_key.as_ref(),
+                        &[allowlist.nonce],
+                    ],
+                    program_id,
+                )
+            },
+        )
+        .ok_or(SwapError::DepositorNotAllowed)?;
+        if !allowlist.is_allowed(depositor) {
+            return Err(SwapError::DepositorNotAllowed.into());
+        }
+        Ok(())
+    }
+
+    /// Checks that one of the trailing accounts proves `holder` holds the pool
+    /// credential: a token account of the issuer mint owned by `holder`, or the
+    /// account of the issuer program at the credential address of `holder`, storing
+    /// `holder` in its first 32 bytes.
+    fn check_credential(
+        token_swap: &SwapInfo,
+        holder: &Pubkey,
+        trailing_infos: &[AccountInfo],
+    ) -> ProgramResult {
+        if !token_swap.requires_credential() {
+            return Ok(());
+        }
+        let issuer = &token_swap.credential_issuer;
+        let is_credential = |info: &AccountInfo| {
+            let data = info.data.borrow();
+            if info.owner == issuer {
+                data.get(..32) == Some(holder.as_ref())
+                    && *info.key == utils::find_credential_address(issuer, holder).0
+            } else {
+                token_2022::is_token_program(info.owner)
+                    && matches!(
+                        utils::unpack_token_account(&data),
+                        Ok(credential) if credential.mint == *issuer
+                            && credential.owner == *holder
+                            && credential.amount > 0
+                    )
+            }
+        };
+        if !trailing_infos.iter().any(is_credential) {
+            return Err(SwapError::CredentialRequired.into());
+        }
+        Ok(())
     }
 
     /// Issue a spl_token `Burn` instruction.
@@ -52,14 +342,19 @@ impl Processor {
         let swap_bytes = swap.to_bytes();
         let authority_signature_seeds = [&swap_bytes[..32], &[nonce]];
         let signers = &[&authority_signature_seeds[..]];
-        let ix = spl_token::instruction::burn(
+        let ix = token_2022::instruction(
+            spl_token::instruction::burn(
+                &spl_token::id(),
+                burn_account.key,
+                mint.key,
+                authority.key,
+                &[],
+                amount,
+            ),
             token_program.key,
-            burn_account.key,
-            mint.key,
-            authority.key,
-            &[],
-            amount,
         )?;
+        #[cfg(feature = "shadow-ledger")]
+        crate::ledger::record_burn(mint.key, burn_account.key, amount);
 
         invoke_signed(
             &ix,
@@ -68,6 +363,166 @@ impl Processor {
         )
     }
 
+    /// Issue a spl_token `FreezeAccount` instruction.
+    pub fn token_freeze<'a>(
+        swap: &Pubkey,
+        token_program: AccountInfo<'a>,
+        account: AccountInfo<'a>,
+        mint: AccountInfo<'a>,
+        authority: AccountInfo<'a>,
+        nonce: u8,
+    ) -> Result<(), ProgramError> {
+        let swap_bytes = swap.to_bytes();
+        let authority_signature_seeds = [&swap_bytes[..32], &[nonce]];
+        let signers = &[&authority_signature_seeds[..]];
+        let ix = token_2022::instruction(
+            spl_token::instruction::freeze_account(
+                &spl_token::id(),
+                account.key,
+                mint.key,
+                authority.key,
+                &[],
+            ),
+            token_program.key,
+        )?;
+
+        invoke_signed(&ix, &[account, mint, authority, token_program], signers)
+    }
+
+    /// Issue a spl_token `ThawAccount` instruction.
+    pub fn token_thaw<'a>(
+        swap: &Pubkey,
+        token_program: AccountInfo<'a>,
+        account: AccountInfo<'a>,
+        mint: AccountInfo<'a>,
+        authority: AccountInfo<'a>,
+        nonce: u8,
+    ) -> Result<(), ProgramError> {
+        let swap_bytes = swap.to_bytes();
+        let authority_signature_seeds = [&swap_bytes[..32], &[nonce]];
+        let signers = &[&authority_signature_seeds[..]];
+        let ix = token_2022::instruction(
+            spl_token::instruction::thaw_account(
+                &spl_token::id(),
+                account.key,
+                mint.key,
+                authority.key,
+                &[],
+            ),
+            token_program.key,
+        )?;
+
+        invoke_signed(&ix, &[account, mint, authority, token_program], signers)
+    }
+
+    /// Mints pool tokens. Soulbound pool token accounts are thawed for the mint
+    /// and frozen again.
+    #[allow(clippy::too_many_arguments)]
+    fn pool_token_mint_to<'a>(
+        lp_soulbound: bool,
+        swap: &Pubkey,
+        token_program: AccountInfo<'a>,
+        mint: AccountInfo<'a>,
+        destination: AccountInfo<'a>,
+        authority: AccountInfo<'a>,
+        nonce: u8,
+        amount: u64,
+    ) -> Result<(), ProgramError> {
+        if !lp_soulbound {
+            return Self::token_mint_to(
+                swap,
+                token_program,
+                mint,
+                destination,
+                authority,
+                nonce,
+                amount,
+            );
+        }
+        if utils::unpack_token_account(&destination.data.borrow())?.is_frozen() {
+            Self::token_thaw(
+                swap,
+                token_program.clone(),
+                destination.clone(),
+                mint.clone(),
+                authority.clone(),
+                nonce,
+            )?;
+        }
+        Self::token_mint_to(
+            swap,
+            token_program.clone(),
+            mint.clone(),
+            destination.clone(),
+            authority.clone(),
+            nonce,
+            amount,
+        )?;
+        Self::token_freeze(swap, token_program, destination, mint, authority, nonce)
+    }
+
+    /// Burns pool tokens. Soulbound pool token accounts are thawed for the burn,
+    /// signed by `owner` instead of $authority, and frozen again.
+    #[allow(clippy::too_many_arguments)]
+    fn pool_token_burn<'a>(
+        lp_soulbound: bool,
+        swap: &Pubkey,
+        token_program: AccountInfo<'a>,
+        burn_account: AccountInfo<'a>,
+        mint: AccountInfo<'a>,
+        authority: AccountInfo<'a>,
+        owner: Option<&AccountInfo<'a>>,
+        nonce: u8,
+        amount: u64,
+    ) -> Result<(), ProgramError> {
+        if !lp_soulbound {
+            return Self::token_burn(
+                swap,
+                token_program,
+                burn_account,
+                mint,
+                authority,
+                nonce,
+                amount,
+            );
+        }
+        let owner = owner.ok_or(ProgramError::NotEnoughAccountKeys)?;
+        if utils::unpack_token_account(&burn_account.data.borrow())?.is_frozen() {
+            Self::token_thaw(
+                swap,
+                token_program.clone(),
+                burn_account.clone(),
+                mint.clone(),
+                authority.clone(),
+                nonce,
+            )?;
+        }
+        let ix = token_2022::instruction(
+            spl_token::instruction::burn(
+                &spl_token::id(),
+                burn_account.key,
+                mint.key,
+                owner.key,
+                &[],
+                amount,
+            ),
+            token_program.key,
+        )?;
+        #[cfg(feature = "shadow-ledger")]
+        crate::ledger::record_burn(mint.key, burn_account.key, amount);
+
+        invoke(
+            &ix,
+            &[
+                burn_account.clone(),
+                mint.clone(),
+                owner.clone(),
+                token_program.clone(),
+            ],
+        )?;
+        Self::token_freeze(swap, token_program, burn_account, mint, authority, nonce)
+    }
+
     /// Issue a spl_token `MintTo` instruction.
     pub fn token_mint_to<'a>(
         swap: &Pubkey,
@@ -81,23 +536,31 @@ impl Processor {
         let swap_bytes = swap.to_bytes();
         let authority_signature_seeds = [&swap_bytes[..32], &[nonce]];
         let signers = &[&authority_signature_seeds[..]];
-        let ix = spl_token::instruction::mint_to(
+        let ix = token_2022::instruction(
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                mint.key,
+                destination.key,
+                authority.key,
+                &[],
+                amount,
+            ),
             token_program.key,
-            mint.key,
-            destination.key,
-            authority.key,
-            &[],
-            amount,
         )?;
+        #[cfg(feature = "shadow-ledger")]
+        crate::ledger::record_mint_to(mint.key, destination.key, amount);
 
         invoke_signed(&ix, &[mint, destination, authority, token_program], signers)
     }
 
-    /// Issue a spl_token `Transfer` instruction.
-    pub fn token_transfer<'a>(
+    /// Issue a spl_token `TransferChecked` instruction of `mint` when given, which
+    /// Token-2022 requires of mints charging transfer fees, or a `Transfer` otherwise.
+    #[allow(clippy::too_many_arguments)]
+    pub fn token_transfer_with_mint<'a>(
         swap: &Pubkey,
         token_program: AccountInfo<'a>,
         source: AccountInfo<'a>,
+        mint: Option<&AccountInfo<'a>>,
         destination: AccountInfo<'a>,
         authority: AccountInfo<'a>,
         nonce: u8,
@@ -106,28 +569,175 @@ impl Processor {
         let swap_bytes = swap.to_bytes();
         let authority_signature_seeds = [&swap_bytes[..32], &[nonce]];
         let signers = &[&authority_signature_seeds[..]];
-        let ix = spl_token::instruction::transfer(
-            token_program.key,
-            source.key,
-            destination.key,
-            authority.key,
-            &[],
+        let ix = match mint {
+            Some(mint) => spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                source.key,
+                mint.key,
+                destination.key,
+                authority.key,
+                &[],
+                amount,
+                Self::unpack_mint(&mint.data.borrow())?.decimals,
+            ),
+            None => spl_token::instruction::transfer(
+                &spl_token::id(),
+                source.key,
+                destination.key,
+                authority.key,
+                &[],
+                amount,
+            ),
+        };
+        let ix = token_2022::instruction(ix, token_program.key)?;
+        #[cfg(feature = "shadow-ledger")]
+        crate::ledger::record_transfer(source.key, destination.key, amount);
+
+        let mut account_infos = vec![source, destination, authority, token_program];
+        account_infos.extend(mint.cloned());
+        invoke_signed(&ix, &account_infos, signers)
+    }
+
+    /// Finds `mint` among `trailing_infos`, with the fee it withholds from transfers at
+    /// `epoch`. Token-2022 mints charging transfer fees are passed as trailing accounts,
+    /// their tokens can't be transferred without them.
+    fn transfer_mint<'b, 'a>(
+        mint: &Pubkey,
+        trailing_infos: &'b [AccountInfo<'a>],
+        epoch: u64,
+    ) -> Result<(Option<&'b AccountInfo<'a>>, TransferFee), ProgramError> {
+        let mint_info = match trailing_infos.iter().find(|info| info.key == mint) {
+            Some(mint_info) => mint_info,
+            None => return Ok((None, TransferFee::default())),
+        };
+        let transfer_fee = if *mint_info.owner == token_2022::id() {
+            token_2022::transfer_fee(&mint_info.data.borrow(), epoch)?
+        } else {
+            TransferFee::default()
+        };
+        Ok((Some(mint_info), transfer_fee))
+    }
+
+    /// Mint of `token_account` if among `accounts`. Transfers out of the pool pass
+    /// it to Token-2022, which requires the mint of mints charging transfer fees.
+    fn token_mint<'b, 'a>(
+        token_account: &AccountInfo,
+        accounts: &'b [AccountInfo<'a>],
+    ) -> Result<Option<&'b AccountInfo<'a>>, ProgramError> {
+        let mint = utils::unpack_token_account(&token_account.data.borrow())?.mint;
+        Ok(accounts.iter().find(|info| *info.key == mint))
+    }
+
+    /// Rejects a user SOURCE account owned by $authority. $authority signs for the
+    /// user accounts it was approved to spend, and would sign just the same for the
+    /// vaults it owns, which only the instructions managing them may spend.
+    fn check_user_source(source_info: &AccountInfo, authority_info: &AccountInfo) -> ProgramResult {
+        if utils::unpack_token_account(&source_info.data.borrow())?.owner == *authority_info.key {
+            return Err(SwapError::AuthorityOwnedSource.into());
+        }
+        Ok(())
+    }
+
+    /// Transfers out of a user SOURCE account, checked by `check_user_source`
+    #[allow(clippy::too_many_arguments)]
+    fn user_token_transfer<'a>(
+        swap: &Pubkey,
+        token_program: AccountInfo<'a>,
+        source: AccountInfo<'a>,
+        mint: Option<&AccountInfo<'a>>,
+        destination: AccountInfo<'a>,
+        authority: AccountInfo<'a>,
+        nonce: u8,
+        amount: u64,
+    ) -> Result<(), ProgramError> {
+        Self::check_user_source(&source, &authority)?;
+        Self::token_transfer_with_mint(
+            swap,
+            token_program,
+            source,
+            mint,
+            destination,
+            authority,
+            nonce,
             amount,
+        )
+    }
+
+    /// Checks a swap of `amount_in` into the pool for `amount_out` out of it against
+    /// the Pyth prices of the oracle guard, passed among `trailing_infos`. Prices
+    /// are required even while the stale policy ignores them.
+    fn check_oracle_guard(
+        token_swap: &SwapInfo,
+        trailing_infos: &[AccountInfo],
+        a_to_b: bool,
+        amount_in: u64,
+        amount_out: u64,
+        slot: Slot,
+    ) -> ProgramResult {
+        let oracle_price = |oracle: &Pubkey| {
+            let oracle_info = trailing_infos
+                .iter()
+                .find(|info| info.key == oracle)
+                .ok_or(SwapError::InvalidOracle)?;
+            PythPrice::unpack(&oracle_info.data.borrow())
+        };
+        token_swap.oracle_guard().check_swap(
+            &oracle_price(&token_swap.oracle_a)?,
+            &oracle_price(&token_swap.oracle_b)?,
+            a_to_b,
+            amount_in,
+            amount_out,
+            slot,
         )?;
+        Ok(())
+    }
 
-        invoke_signed(
-            &ix,
-            &[source, destination, authority, token_program],
-            signers,
+    /// Transfers the admin fee `amount` of `swap_token` to its admin fee account, or
+    /// escrows it in `swap_token` while the admin fee account can't receive fees.
+    #[allow(clippy::too_many_arguments)]
+    pub fn admin_fee_transfer<'a>(
+        token_swap: &mut SwapInfo,
+        swap: &Pubkey,
+        token_program: AccountInfo<'a>,
+        swap_token: AccountInfo<'a>,
+        mint: Option<&AccountInfo<'a>>,
+        destination: AccountInfo<'a>,
+        authority: AccountInfo<'a>,
+        amount: u64,
+    ) -> Result<(), ProgramError> {
+        if token_swap.is_admin_fee_escrowed(swap_token.key) {
+            return token_swap
+                .escrow_admin_fee(swap_token.key, amount)
+                .ok_or_else(|| SwapError::CalculationFailure.into());
+        }
+        Self::token_transfer_with_mint(
+            swap,
+            token_program,
+            swap_token,
+            mint,
+            destination,
+            authority,
+            token_swap.nonce,
+            amount,
         )
     }
 
     /// Processes an [Initialize](enum.Instruction.html).
+    #[allow(clippy::too_many_arguments)]
     pub fn process_initialize(
         program_id: &Pubkey,
         nonce: u8,
         amp_factor: u64,
         fees: Fees,
+        access_window_slots: u64,
+        namespace: [u8; 32],
+        fee_tier: FeeTier,
+        marketing_wallet: Pubkey,
+        developer_wallet: Pubkey,
+        curve_type: CurveType,
+        price_bound: u64,
+        creator_window_slots: u64,
+        freeze_authority_acknowledged: bool,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
@@ -143,18 +753,44 @@ impl Processor {
         let pool_mint_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?; // Destination account to mint LP tokens to
         let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+        let trailing_infos = account_info_iter.as_slice();
+        let access_mint_info = account_info_iter.next();
 
         if !(MIN_AMP..=MAX_AMP).contains(&amp_factor) {
             return Err(SwapError::InvalidInput.into());
         }
+        match curve_type {
+            CurveType::PriceBounded if price_bound <= PRICE_BOUND_SCALE => {
+                return Err(SwapError::InvalidInput.into());
+            }
+            CurveType::StableSwap | CurveType::ConstantProduct if price_bound != 0 => {
+                return Err(SwapError::InvalidInput.into());
+            }
+            _ => {}
+        }
+        let fees = fee_tier.fees().unwrap_or(fees);
+        fees.validate()?;
 
-        let token_swap = SwapInfo::unpack_unchecked(&swap_info.data.borrow())?;
-        if token_swap.is_initialized {
+        // The Token-swap is created below, unless an existing pool holds its address
+        if !swap_info.data_is_empty()
+            && SwapInfo::unpack_unchecked(&swap_info.data.borrow())?.is_initialized
+        {
             return Err(SwapError::AlreadyInUse.into());
         }
         if *authority_info.key != utils::authority_id(program_id, swap_info.key, nonce)? {
             return Err(SwapError::InvalidProgramAddress.into());
         }
+        // The payer signs as the creator of the pool
+        if !payer_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if namespace == utils::CANONICAL_NAMESPACE {
+            Self::check_upgrade_authority(program_id, payer_info, trailing_infos)?;
+        }
         let destination = utils::unpack_token_account(&destination_info.data.borrow())?;
         let token_a = utils::unpack_token_account(&token_a_info.data.borrow())?;
         let token_b = utils::unpack_token_account(&token_b_info.data.borrow())?;
@@ -167,6 +803,21 @@ impl Processor {
         if *authority_info.key == destination.owner {
             return Err(SwapError::InvalidOutputOwner.into());
         }
+        // The fee wallets own the accounts their fees are withdrawn to, which the swap
+        // itself must not
+        for wallet in [marketing_wallet, developer_wallet] {
+            if wallet != Pubkey::default()
+                && [
+                    authority_info.key,
+                    swap_info.key,
+                    token_a_info.key,
+                    token_b_info.key,
+                ]
+                .contains(&&wallet)
+            {
+                return Err(SwapError::InvalidOutputOwner.into());
+            }
+        }
         if token_a.mint == token_b.mint {
             return Err(SwapError::RepeatedMint.into());
         }
@@ -194,15 +845,29 @@ impl Processor {
         if token_b.close_authority.is_some() {
             return Err(SwapError::InvalidCloseAuthority.into());
         }
+        if token_a.is_frozen() || token_b.is_frozen() {
+            return Err(SwapError::VaultFrozen.into());
+        }
         let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
+        // Either token program, but the same for the whole pool
+        if !token_2022::is_token_program(token_program_info.key)
+            || [token_a_info, token_b_info, pool_mint_info]
+                .iter()
+                .any(|info| info.owner != token_program_info.key)
+        {
+            return Err(SwapError::IncorrectTokenProgramId.into());
+        }
         if pool_mint.mint_authority.is_some()
             && *authority_info.key != pool_mint.mint_authority.unwrap()
         {
             return Err(SwapError::InvalidOwner.into());
         }
-        if pool_mint.freeze_authority.is_some() {
-            return Err(SwapError::InvalidFreezeAuthority.into());
-        }
+        // Pool tokens are soulbound if the swap authority can freeze them
+        let lp_soulbound = match pool_mint.freeze_authority {
+            COption::Some(freeze_authority) if freeze_authority == *authority_info.key => true,
+            COption::Some(_) => return Err(SwapError::InvalidFreezeAuthority.into()),
+            COption::None => false,
+        };
         if pool_mint.supply != 0 {
             return Err(SwapError::InvalidSupply.into());
         }
@@ -214,6 +879,17 @@ impl Processor {
         if pool_mint.decimals != token_a_mint.decimals {
             return Err(SwapError::MismatchedDecimals.into());
         }
+        // The freeze authority of a token mint can halt the pool by freezing its swap
+        // token account, which the creator has to accept up front
+        let freeze_authority_acknowledged =
+            if token_a_mint.freeze_authority.is_some() || token_b_mint.freeze_authority.is_some() {
+                if !freeze_authority_acknowledged {
+                    return Err(SwapError::FreezeAuthorityNotAcknowledged.into());
+                }
+                true
+            } else {
+                false
+            };
         let admin_fee_key_a = utils::unpack_token_account(&admin_fee_a_info.data.borrow())?;
         let admin_fee_key_b = utils::unpack_token_account(&admin_fee_b_info.data.borrow())?;
         if token_a.mint != admin_fee_key_a.mint {
@@ -222,14 +898,49 @@ impl Processor {
         if token_b.mint != admin_fee_key_b.mint {
             return Err(SwapError::InvalidAdmin.into());
         }
+        // A single pool per mint pair, fee tier and namespace
+        let (pool_key, pool_nonce) = utils::find_pool_address(
+            program_id,
+            &token_a.mint,
+            &token_b.mint,
+            fee_tier,
+            &namespace,
+        );
+        if *swap_info.key != pool_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        let (access_mint, access_window_end_slot) = if access_window_slots > 0 {
+            let access_mint_info = access_mint_info.ok_or(ProgramError::NotEnoughAccountKeys)?;
+            Self::unpack_mint(&access_mint_info.data.borrow())?;
+            let access_window_end_slot = clock
+                .slot
+                .checked_add(access_window_slots)
+                .ok_or(SwapError::CalculationFailure)?;
+            (*access_mint_info.key, access_window_end_slot)
+        } else {
+            (Pubkey::default(), 0)
+        };
+        let creator_deposit_end_slot = clock
+            .slot
+            .checked_add(creator_window_slots)
+            .ok_or(SwapError::CalculationFailure)?;
 
         // amp_factor == intial_amp_factor == target_amp_factor on init
-        let invariant = StableSwap::new(amp_factor, amp_factor, ZERO_TS, ZERO_TS, ZERO_TS);
+        let invariant = match curve_type {
+            CurveType::StableSwap => PoolCurve::StableSwap(StableSwap::new(
+                amp_factor, amp_factor, ZERO_TS, ZERO_TS, ZERO_TS,
+            )),
+            CurveType::ConstantProduct => PoolCurve::ConstantProduct(ConstantProduct),
+            CurveType::PriceBounded => PoolCurve::PriceBounded(PriceBounded::new(price_bound)),
+        };
         // Compute amount of LP tokens to mint for bootstrapper
         let mint_amount = invariant
             .compute_d(U256::from(token_a.amount), U256::from(token_b.amount))
             .ok_or(SwapError::CalculationFailure)?;
-        Self::token_mint_to(
+        Self::pool_token_mint_to(
+            lp_soulbound,
             swap_info.key,
             token_program_info.clone(),
             pool_mint_info.clone(),
@@ -239,7 +950,33 @@ impl Processor {
             U256::to_u64(mint_amount)?,
         )?;
 
-        let obj = SwapInfo {
+        let (first_mint, second_mint) = utils::ordered_mints(&token_a.mint, &token_b.mint);
+        let pool_signature_seeds = [
+            POOL_SEED,
+            first_mint.as_ref(),
+            second_mint.as_ref(),
+            &[fee_tier as u8],
+            &namespace,
+            &[pool_nonce],
+        ];
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                swap_info.key,
+                rent.minimum_balance(SwapInfo::LEN),
+                SwapInfo::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                swap_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&pool_signature_seeds],
+        )?;
+
+        let mut obj = SwapInfo {
             is_initialized: true,
             is_paused: false,
             nonce,
@@ -258,9 +995,209 @@ impl Processor {
             admin_fee_key_a: *admin_fee_a_info.key,
             admin_fee_key_b: *admin_fee_b_info.key,
             fees,
+            access_mint,
+            access_window_end_slot,
+            namespace,
+            fee_tier,
+            admin_fees_accrued_a: 0,
+            admin_fees_accrued_b: 0,
+            harvest_tip_bps: 0,
+            reflection_distributor: Pubkey::default(),
+            reflection_epoch: 0,
+            dev_vesting_a: Pubkey::default(),
+            dev_vesting_b: Pubkey::default(),
+            dev_wallet: Pubkey::default(),
+            dev_vesting_start_ts: ZERO_TS,
+            dev_vesting_end_ts: ZERO_TS,
+            dev_fees_received_a: 0,
+            dev_fees_received_b: 0,
+            dev_fees_claimed_a: 0,
+            dev_fees_claimed_b: 0,
+            governance_realm: Pubkey::default(),
+            governance_vault: Pubkey::default(),
+            governance_lockup: 0,
+            admin_recovery_key: Pubkey::default(),
+            admin_heartbeat_slot: 0,
+            admin_timeout_slots: 0,
+            pending_withdraw_a: 0,
+            pending_withdraw_b: 0,
+            max_swap_amount_in: 0,
+            lp_soulbound,
+            ve_lp_vault: Pubkey::default(),
+            ve_fee_vault_a: Pubkey::default(),
+            ve_fee_vault_b: Pubkey::default(),
+            ve_fee_bps: 0,
+            ve_supply: 0,
+            ve_epoch: 0,
+            ve_fees_per_ve_a: 0,
+            ve_fees_per_ve_b: 0,
+            ve_pending_fees_a: 0,
+            ve_pending_fees_b: 0,
+            order_flow_batch_slots: 0,
+            order_flow_bid_token: Pubkey::default(),
+            order_flow_batch: 0,
+            order_flow_bidder: Pubkey::default(),
+            order_flow_refund_account: Pubkey::default(),
+            order_flow_bid: 0,
+            dynamic_amp_min: 0,
+            dynamic_amp_max: 0,
+            dynamic_amp_volatility_ceiling: 0,
+            dynamic_amp_max_drift: 0,
+            twap_price: 0,
+            twap_volatility: 0,
+            twap_slot: 0,
+            fee_buffer_bps: 0,
+            deposit_bonus_bps: 0,
+            fee_buffer_a: 0,
+            fee_buffer_b: 0,
+            insurance_fee_bps: 0,
+            insurance_authority: Pubkey::default(),
+            insurance_fund_a: 0,
+            insurance_fund_b: 0,
+            insurance_covered_a: 0,
+            insurance_covered_b: 0,
+            min_lp_mint: 0,
+            dust_threshold: 0,
+            dust_sweep_slot: 0,
+            dust_sweep_count: 0,
+            liquidity_vault: Pubkey::default(),
+            position_liquidity: 0,
+            cumulative_position_liquidity: 0,
+            position_liquidity_ts: ZERO_TS,
+            fee_report_epoch: clock.epoch,
+            epoch_lp_fees_a: 0,
+            epoch_lp_fees_b: 0,
+            epoch_admin_fees_a: 0,
+            epoch_admin_fees_b: 0,
+            epoch_reflection_fees_a: 0,
+            epoch_reflection_fees_b: 0,
+            epoch_buyback_fees_a: 0,
+            epoch_buyback_fees_b: 0,
+            epoch_marketing_fees_a: 0,
+            epoch_marketing_fees_b: 0,
+            epoch_developer_fees_a: 0,
+            epoch_developer_fees_b: 0,
+            sequence: 0,
+            custodian: Pubkey::default(),
+            custodian_withdraw_threshold: 0,
+            credential_issuer: Pubkey::default(),
+            admin_log: AdminLog::default(),
+            admin_fee_escrow_a: false,
+            admin_fee_escrow_b: false,
+            price_a_cumulative: 0,
+            price_b_cumulative: 0,
+            price_cumulative_slot: clock.slot,
+            guardian_key: Pubkey::default(),
+            protocol_pool_amount: 0,
+            pending_fees: Fees::default(),
+            pending_fees_slot: 0,
+            custom_error_messages: CustomErrorMessages::default(),
+            reflection_lp_vault: Pubkey::default(),
+            reflection_vault_a: Pubkey::default(),
+            reflection_vault_b: Pubkey::default(),
+            reflection_supply: 0,
+            acc_reflection_per_lp_share_a: 0,
+            acc_reflection_per_lp_share_b: 0,
+            reflection_pending_a: 0,
+            reflection_pending_b: 0,
+            buyback_mint: Pubkey::default(),
+            buyback_vault_a: Pubkey::default(),
+            buyback_vault_b: Pubkey::default(),
+            buyback_burned: 0,
+            marketing_wallet,
+            developer_wallet,
+            marketing_fees_accrued_a: 0,
+            marketing_fees_accrued_b: 0,
+            developer_fees_accrued_a: 0,
+            developer_fees_accrued_b: 0,
+            config_hash: Hash::default(),
+            volatility_fee_ceiling: 0,
+            volatility_fee_max_multiplier_bps: 0,
+            referral_fees_accrued_a: 0,
+            referral_fees_accrued_b: 0,
+            admin_fee_epoch_cap_a: 0,
+            admin_fee_epoch_cap_b: 0,
+            admin_fee_cap_epoch: 0,
+            admin_fees_taken_a: 0,
+            admin_fees_taken_b: 0,
+            curve_type,
+            price_bound,
+            creator: *payer_info.key,
+            creator_deposit_end_slot,
+            deposit_allowlist_enabled: false,
+            freeze_authority_acknowledged,
+            oracle_a: Pubkey::default(),
+            oracle_b: Pubkey::default(),
+            oracle_max_staleness_slots: 0,
+            oracle_max_confidence_bps: 0,
+            oracle_max_deviation_bps: 0,
+            oracle_stale_policy: OracleStalePolicy::Reject,
+            oracle_stale_band_bps: 0,
         };
+        obj.config_hash = obj.compute_config_hash();
         SwapInfo::pack(obj, &mut swap_info.data.borrow_mut())?;
-        Ok(())
+        Self::update_global_stats_best_effort(program_id, trailing_infos, |global_stats| {
+            global_stats.record_pool(token_a.amount, token_b.amount)
+        })
+    }
+
+    /// Processes a [ClonePool](enum.Instruction.html).
+    pub fn process_clone_pool(
+        program_id: &Pubkey,
+        nonce: u8,
+        namespace: [u8; 32],
+        fee_tier: FeeTier,
+        freeze_authority_acknowledged: bool,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        // The accounts of Initialize, followed by the Token-swap to copy
+        const INITIALIZE_ACCOUNTS_LEN: usize = 16;
+        const CLOCK_SYSVAR_INDEX: usize = 12;
+        let source_swap_info = accounts
+            .get(INITIALIZE_ACCOUNTS_LEN)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        // The global stats may follow the Token-swap to copy
+        let mut initialize_accounts = accounts[..INITIALIZE_ACCOUNTS_LEN].to_vec();
+        initialize_accounts.extend_from_slice(&accounts[INITIALIZE_ACCOUNTS_LEN + 1..]);
+        let clock_sysvar_info = &initialize_accounts[CLOCK_SYSVAR_INDEX];
+
+        if source_swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let source_swap = SwapInfo::unpack(&source_swap_info.data.borrow())?;
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        let amp_factor = StableSwap::new(
+            source_swap.initial_amp_factor,
+            source_swap.target_amp_factor,
+            clock.unix_timestamp,
+            source_swap.start_ramp_ts,
+            source_swap.stop_ramp_ts,
+        )
+        .compute_amp_factor()
+        .ok_or(SwapError::CalculationFailure)?;
+        // A custom tier keeps the fees and the tier of the copied pool
+        let fee_tier = if fee_tier == FeeTier::Custom {
+            source_swap.fee_tier
+        } else {
+            fee_tier
+        };
+
+        Self::process_initialize(
+            program_id,
+            nonce,
+            U256::to_u64(amp_factor)?,
+            source_swap.fees,
+            0,
+            namespace,
+            fee_tier,
+            source_swap.marketing_wallet,
+            source_swap.developer_wallet,
+            source_swap.curve_type,
+            source_swap.price_bound,
+            0,
+            freeze_authority_acknowledged,
+            &initialize_accounts,
+        )
     }
 
     /// Processes an [Swap](enum.Instruction.html).
@@ -268,26 +1205,144 @@ impl Processor {
         program_id: &Pubkey,
         amount_in: u64,
         minimum_amount_out: u64,
+        deadline_slot: u64,
+        expected_sequence: Option<u64>,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
-        let account_info_iter = &mut accounts.iter();
-        let swap_info = next_account_info(account_info_iter)?;
-        let authority_info = next_account_info(account_info_iter)?;
-        let source_info = next_account_info(account_info_iter)?;
-        let swap_source_info = next_account_info(account_info_iter)?;
-        let swap_destination_info = next_account_info(account_info_iter)?;
-        let destination_info = next_account_info(account_info_iter)?;
-        let reflection_destination_info = next_account_info(account_info_iter)?;
-        let buyback_destination_info = next_account_info(account_info_iter)?;
-        let marketing_destination_info = next_account_info(account_info_iter)?;
-        let developer_destination_info = next_account_info(account_info_iter)?;
-        let token_program_info = next_account_info(account_info_iter)?;
-        let clock_sysvar_info = next_account_info(account_info_iter)?;
+        Self::swap(
+            program_id,
+            SwapAmount::ExactIn {
+                amount_in,
+                minimum_amount_out,
+            },
+            expected_sequence,
+            deadline_slot,
+            accounts,
+        )?;
+        Ok(())
+    }
 
-        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+    /// Processes a [SwapOut](enum.Instruction.html).
+    pub fn process_swap_out(
+        program_id: &Pubkey,
+        amount_out: u64,
+        maximum_amount_in: u64,
+        deadline_slot: u64,
+        expected_sequence: Option<u64>,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        Self::swap(
+            program_id,
+            SwapAmount::ExactOut {
+                amount_out,
+                maximum_amount_in,
+            },
+            expected_sequence,
+            deadline_slot,
+            accounts,
+        )?;
+        Ok(())
+    }
+
+    /// Processes a [Route](enum.Instruction.html).
+    pub fn process_route(
+        program_id: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        leg_account_counts: &[u8],
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+        let mut source_info = next_account_info(account_info_iter)?;
+        let mut leg_infos = account_info_iter.as_slice();
+
+        let mut amount = amount_in;
+        for (index, &account_count) in leg_account_counts.iter().enumerate() {
+            // Swap, authority, swap source and destination, destination, the four fee
+            // destinations and the referrer, followed by the optional accounts of the leg
+            let account_count = account_count as usize;
+            if account_count < 10 || leg_infos.len() < account_count {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            }
+            let (infos, rest) = leg_infos.split_at(account_count);
+            let mut swap_infos = Vec::with_capacity(account_count + 3);
+            swap_infos.extend_from_slice(&infos[..2]);
+            swap_infos.push(source_info.clone());
+            swap_infos.extend_from_slice(&infos[2..9]);
+            swap_infos.push(token_program_info.clone());
+            swap_infos.push(clock_sysvar_info.clone());
+            swap_infos.extend_from_slice(&infos[9..]);
+
+            let minimum_amount_out = if index + 1 == leg_account_counts.len() {
+                minimum_amount_out
+            } else {
+                0
+            };
+            let destination_amount = utils::unpack_token_account(&infos[4].data.borrow())?.amount;
+            Self::swap(
+                program_id,
+                SwapAmount::ExactIn {
+                    amount_in: amount,
+                    minimum_amount_out,
+                },
+                None,
+                0,
+                &swap_infos,
+            )?;
+            // Mints charging transfer fees withhold part of the amount swapped
+            amount = utils::unpack_token_account(&infos[4].data.borrow())?
+                .amount
+                .checked_sub(destination_amount)
+                .ok_or(SwapError::CalculationFailure)?;
+            source_info = &infos[4];
+            leg_infos = rest;
+        }
+        Ok(())
+    }
+
+    /// Executes a swap before `deadline_slot`, if any, returning its result and the
+    /// slot it was executed in.
+    fn swap(
+        program_id: &Pubkey,
+        amount: SwapAmount,
+        expected_sequence: Option<u64>,
+        deadline_slot: u64,
+        accounts: &[AccountInfo],
+    ) -> Result<(SwapResultV2, Slot), ProgramError> {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let reflection_destination_info = next_account_info(account_info_iter)?;
+        let buyback_destination_info = next_account_info(account_info_iter)?;
+        let marketing_destination_info = next_account_info(account_info_iter)?;
+        let developer_destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+        let referrer_info = next_account_info(account_info_iter)?;
+        let referrer_info = (referrer_info.key != program_id).then_some(referrer_info);
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        let admin_fee_pool_infos = if token_swap.fees.admin_fee_in_pool_tokens {
+            let pool_mint_info = next_account_info(account_info_iter)?;
+            let admin_pool_token_info = next_account_info(account_info_iter)?;
+            Some((pool_mint_info, admin_pool_token_info))
+        } else {
+            None
+        };
+        let trailing_infos = account_info_iter.as_slice();
+        let access_token_info = account_info_iter.next();
         if token_swap.is_paused {
             return Err(SwapError::IsPaused.into());
         }
+        if !token_swap.is_at_sequence(expected_sequence) {
+            return Err(SwapError::SequenceMismatch.into());
+        }
         if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
         {
             return Err(SwapError::InvalidProgramAddress.into());
@@ -307,108 +1362,760 @@ impl Processor {
         }
 
         let clock = Clock::from_account_info(clock_sysvar_info)?;
+        Self::check_deadline(clock.slot, deadline_slot)?;
+        token_swap.settle_order_flow_auction(clock.slot);
+        if token_swap.order_flow_bid != 0 && token_swap.is_order_flow_backrun_slot(clock.slot) {
+            let source_account = utils::unpack_token_account(&source_info.data.borrow())?;
+            if source_account.owner != token_swap.order_flow_bidder {
+                return Err(SwapError::OrderFlowSlotReserved.into());
+            }
+        }
+        if token_swap.requires_credential() {
+            let source_account = utils::unpack_token_account(&source_info.data.borrow())?;
+            Self::check_credential(&token_swap, &source_account.owner, trailing_infos)?;
+        }
+        if let Some(referrer_info) = referrer_info {
+            let referrer = utils::unpack_token_account(&referrer_info.data.borrow())?;
+            let source_account = utils::unpack_token_account(&source_info.data.borrow())?;
+            if referrer.owner == source_account.owner {
+                return Err(SwapError::SelfReferral.into());
+            }
+        }
+        if clock.slot < token_swap.access_window_end_slot {
+            let access_token_info = access_token_info.ok_or(SwapError::AccessTokenRequired)?;
+            let access_token = utils::unpack_token_account(&access_token_info.data.borrow())?;
+            let source_account = utils::unpack_token_account(&source_info.data.borrow())?;
+            if access_token.mint != token_swap.access_mint
+                || access_token.owner != source_account.owner
+                || access_token.amount == 0
+            {
+                return Err(SwapError::AccessTokenRequired.into());
+            }
+        }
+        // Without a trade fee, none of the fees taken out of it are charged either
+        let fees = if Self::is_fee_exempt(program_id, swap_info.key, source_info, trailing_infos)? {
+            Fees {
+                trade_fee_numerator: 0,
+                imbalance_fee_numerator: 0,
+                ..token_swap.fees
+            }
+        } else if token_swap.is_volatility_fee_enabled() {
+            token_swap
+                .volatility_fee_multiplier_bps()
+                .and_then(|multiplier_bps| token_swap.fees.with_fee_multiplier(multiplier_bps))
+                .ok_or(SwapError::CalculationFailure)?
+        } else {
+            token_swap.fees
+        };
         let swap_source_account = utils::unpack_token_account(&swap_source_info.data.borrow())?;
         let swap_destination_account =
             utils::unpack_token_account(&swap_destination_info.data.borrow())?;
+        // Rather than the token program failing the transfers
+        if swap_source_account.is_frozen() || swap_destination_account.is_frozen() {
+            return Err(SwapError::VaultFrozen.into());
+        }
+        let (source_mint_info, source_transfer_fee) =
+            Self::transfer_mint(&swap_source_account.mint, trailing_infos, clock.epoch)?;
+        let (destination_mint_info, destination_transfer_fee) =
+            Self::transfer_mint(&swap_destination_account.mint, trailing_infos, clock.epoch)?;
+        let swap_source_amount = token_swap
+            .pool_amount(swap_source_info.key, swap_source_account.amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        let swap_destination_amount = token_swap
+            .pool_amount(swap_destination_info.key, swap_destination_account.amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        // Swap token account the trade fees are charged from
+        let (swap_fee_info, swap_fee_account, swap_fee_mint_info) = if token_swap.fees.fee_on_input
+        {
+            (swap_source_info, &swap_source_account, source_mint_info)
+        } else {
+            (
+                swap_destination_info,
+                &swap_destination_account,
+                destination_mint_info,
+            )
+        };
+        for fee_destination_info in [
+            reflection_destination_info,
+            buyback_destination_info,
+            marketing_destination_info,
+            developer_destination_info,
+        ] {
+            let fee_destination = utils::unpack_token_account(&fee_destination_info.data.borrow())?;
+            if fee_destination.mint != swap_fee_account.mint {
+                return Err(SwapError::InvalidAdmin.into());
+            }
+        }
+        if token_swap.is_reflection_enabled()
+            && Some(*reflection_destination_info.key)
+                != token_swap.reflection_vault(swap_fee_info.key)
+        {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if token_swap.is_buyback_enabled()
+            && Some(*buyback_destination_info.key) != token_swap.buyback_vault(swap_fee_info.key)
+        {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if token_swap.is_dev_fee_vesting() {
+            let dev_vesting = if *swap_fee_info.key == token_swap.token_a {
+                token_swap.dev_vesting_a
+            } else {
+                token_swap.dev_vesting_b
+            };
+            if *developer_destination_info.key != dev_vesting {
+                return Err(SwapError::InvalidAdmin.into());
+            }
+        }
+        if let Some((pool_mint_info, admin_pool_token_info)) = admin_fee_pool_infos {
+            if *pool_mint_info.key != token_swap.pool_mint {
+                return Err(SwapError::IncorrectMint.into());
+            }
+            let admin_pool_token =
+                utils::unpack_token_account(&admin_pool_token_info.data.borrow())?;
+            if admin_pool_token.mint != token_swap.pool_mint
+                || admin_pool_token.owner != token_swap.admin_key
+            {
+                return Err(SwapError::InvalidAdmin.into());
+            }
+        }
 
-        let invariant = StableSwap::new(
-            token_swap.initial_amp_factor,
-            token_swap.target_amp_factor,
-            clock.unix_timestamp,
-            token_swap.start_ramp_ts,
-            token_swap.stop_ramp_ts,
-        );
-        let result = invariant
+        let invariant = token_swap.curve(clock.unix_timestamp);
+        let (reserve_a, reserve_b) = if *swap_source_info.key == token_swap.token_a {
+            (swap_source_amount, swap_destination_amount)
+        } else {
+            (swap_destination_amount, swap_source_amount)
+        };
+        token_swap
+            .accumulate_prices(&invariant, reserve_a, reserve_b, clock.slot)
+            .ok_or(SwapError::CalculationFailure)?;
+        let (amount_in, minimum_amount_out) = match amount {
+            SwapAmount::ExactIn {
+                amount_in,
+                minimum_amount_out,
+            } => (amount_in, minimum_amount_out),
+            SwapAmount::ExactOut {
+                amount_out,
+                maximum_amount_in,
+            } => {
+                // Mint transfer fees are withheld from both sides of the swap
+                let amount_swapped = destination_transfer_fee
+                    .pre_fee_amount(amount_out)
+                    .ok_or(SwapError::CalculationFailure)?;
+                let amount_received = invariant
+                    .compute_swap_in(
+                        U256::from(amount_swapped),
+                        U256::from(swap_source_amount),
+                        U256::from(swap_destination_amount),
+                        &fees,
+                    )
+                    .ok_or(SwapError::CalculationFailure)?;
+                let amount_in = source_transfer_fee
+                    .pre_fee_amount(U256::to_u64(amount_received)?)
+                    .ok_or(SwapError::CalculationFailure)?;
+                if amount_in > maximum_amount_in {
+                    return Err(SwapError::ExceededSlippage.into());
+                }
+                (amount_in, amount_out)
+            }
+        };
+        if token_swap.max_swap_amount_in != 0 && amount_in > token_swap.max_swap_amount_in {
+            return Err(SwapError::SwapAmountTooLarge.into());
+        }
+        // The swap source only receives what is left of `amount_in` after the mint
+        // transfer fee, and the destination of the amount swapped
+        let amount_received = source_transfer_fee
+            .post_fee_amount(amount_in)
+            .ok_or(SwapError::CalculationFailure)?;
+        let mut result = invariant
             .swap_to_v2(
-                U256::from(amount_in),
-                U256::from(swap_source_account.amount),
-                U256::from(swap_destination_account.amount),
-                &token_swap.fees,
+                U256::from(amount_received),
+                U256::from(swap_source_amount),
+                U256::from(swap_destination_amount),
+                &fees,
             )
             .ok_or(SwapError::CalculationFailure)?;
+        // Admin fees past the epoch cap stay in the pool for the LPs
+        if token_swap.is_admin_fee_capped() {
+            let fee_token = if token_swap.fees.fee_on_input {
+                swap_source_info.key
+            } else {
+                swap_destination_info.key
+            };
+            result.admin_fee = U256::from(
+                token_swap
+                    .take_capped_admin_fee(fee_token, clock.epoch, U256::to_u64(result.admin_fee)?)
+                    .ok_or(SwapError::CalculationFailure)?,
+            );
+        }
         let amount_swapped = U256::to_u64(result.amount_swapped)?;
-        if amount_swapped < minimum_amount_out {
+        let amount_out = destination_transfer_fee
+            .post_fee_amount(amount_swapped)
+            .ok_or(SwapError::CalculationFailure)?;
+        if amount_out < minimum_amount_out {
             return Err(SwapError::ExceededSlippage.into());
         }
+        if token_swap.is_oracle_guarded() {
+            Self::check_oracle_guard(
+                &token_swap,
+                trailing_infos,
+                *swap_source_info.key == token_swap.token_a,
+                amount_received,
+                amount_swapped,
+                clock.slot,
+            )?;
+        }
+        log_trace!(
+            "amount_in: {}, amount_swapped: {}, trade_fee: {}, admin_fee: {}",
+            amount_in,
+            amount_swapped,
+            result.trade_fee,
+            result.admin_fee
+        );
 
-        Self::token_transfer(
+        Self::user_token_transfer(
             swap_info.key,
             token_program_info.clone(),
             source_info.clone(),
+            source_mint_info,
             swap_source_info.clone(),
             authority_info.clone(),
             token_swap.nonce,
             amount_in,
         )?;
-        Self::token_transfer(
+        let destination_amount =
+            utils::unpack_token_account(&destination_info.data.borrow())?.amount;
+        Self::token_transfer_with_mint(
             swap_info.key,
             token_program_info.clone(),
             swap_destination_info.clone(),
+            destination_mint_info,
             destination_info.clone(),
             authority_info.clone(),
             token_swap.nonce,
             amount_swapped,
         )?;
-        Self::token_transfer(
+        // Transfer fees or hooks of the destination mint must not short the swapper
+        // of the amount the slippage check passed
+        Self::check_amount_received(destination_info, destination_amount, amount_out)?;
+        Self::token_transfer_with_mint(
             swap_info.key,
             token_program_info.clone(),
-            swap_destination_info.clone(),
+            swap_fee_info.clone(),
+            swap_fee_mint_info,
             reflection_destination_info.clone(),
             authority_info.clone(),
             token_swap.nonce,
             U256::to_u64(result.reflection_fee)?,
         )?;
-        Self::token_transfer(
+        Self::token_transfer_with_mint(
             swap_info.key,
             token_program_info.clone(),
-            swap_destination_info.clone(),
+            swap_fee_info.clone(),
+            swap_fee_mint_info,
             buyback_destination_info.clone(),
             authority_info.clone(),
             token_swap.nonce,
             U256::to_u64(result.buyback_fee)?,
         )?;
-        Self::token_transfer(
-            swap_info.key,
-            token_program_info.clone(),
-            swap_destination_info.clone(),
-            marketing_destination_info.clone(),
-            authority_info.clone(),
-            token_swap.nonce,
-            U256::to_u64(result.marketing_fee)?,
+        // The fees of a wallet set to withdraw them accrue in the swap token account
+        if token_swap.marketing_wallet == Pubkey::default() {
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                swap_fee_info.clone(),
+                swap_fee_mint_info,
+                marketing_destination_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                U256::to_u64(result.marketing_fee)?,
+            )?;
+        }
+        if token_swap.developer_wallet == Pubkey::default() {
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                swap_fee_info.clone(),
+                swap_fee_mint_info,
+                developer_destination_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                U256::to_u64(result.developer_fee)?,
+            )?;
+        }
+        token_swap
+            .accrue_wallet_fees(
+                swap_fee_info.key,
+                U256::to_u64(result.marketing_fee)?,
+                U256::to_u64(result.developer_fee)?,
+            )
+            .ok_or(SwapError::CalculationFailure)?;
+        if token_swap.is_reflection_enabled() {
+            token_swap
+                .accrue_reflection(swap_fee_info.key, U256::to_u64(result.reflection_fee)?)
+                .ok_or(SwapError::CalculationFailure)?;
+        }
+        if token_swap.is_dev_fee_vesting() {
+            let dev_fees_received = if *swap_fee_info.key == token_swap.token_a {
+                &mut token_swap.dev_fees_received_a
+            } else {
+                &mut token_swap.dev_fees_received_b
+            };
+            *dev_fees_received = dev_fees_received
+                .checked_add(U256::to_u64(result.developer_fee)?)
+                .ok_or(SwapError::CalculationFailure)?;
+        }
+
+        // The referrer of the swap, if any, takes the host share of the admin fee,
+        // accrued to its referral accrual or paid to its token account
+        let referral_accrual =
+            Self::find_referral_accrual(program_id, swap_info.key, trailing_infos);
+        let host_fee = if let Some((referral_accrual_info, mut referral_accrual)) = referral_accrual
+        {
+            if referral_accrual.referrer
+                == utils::unpack_token_account(&source_info.data.borrow())?.owner
+            {
+                return Err(SwapError::SelfReferral.into());
+            }
+            let host_fee = fees
+                .host_fee(result.admin_fee)
+                .ok_or(SwapError::CalculationFailure)?;
+            let fee_in_a = *swap_fee_info.key == token_swap.token_a;
+            referral_accrual
+                .record_swap(
+                    *swap_source_info.key == token_swap.token_a,
+                    amount_received,
+                    fee_in_a,
+                    U256::to_u64(host_fee)?,
+                )
+                .ok_or(SwapError::CalculationFailure)?;
+            let referral_fees_accrued = if fee_in_a {
+                &mut token_swap.referral_fees_accrued_a
+            } else {
+                &mut token_swap.referral_fees_accrued_b
+            };
+            *referral_fees_accrued = referral_fees_accrued
+                .checked_add(U256::to_u64(host_fee)?)
+                .ok_or(SwapError::CalculationFailure)?;
+            ReferralAccrual::pack(
+                referral_accrual,
+                &mut referral_accrual_info.data.borrow_mut(),
+            )?;
+            host_fee
+        } else {
+            match referrer_info {
+                Some(referrer_info) => {
+                    if utils::unpack_token_account(&referrer_info.data.borrow())?.mint
+                        != swap_fee_account.mint
+                    {
+                        return Err(SwapError::IncorrectMint.into());
+                    }
+                    let host_fee = fees
+                        .host_fee(result.admin_fee)
+                        .ok_or(SwapError::CalculationFailure)?;
+                    Self::token_transfer_with_mint(
+                        swap_info.key,
+                        token_program_info.clone(),
+                        swap_fee_info.clone(),
+                        swap_fee_mint_info,
+                        referrer_info.clone(),
+                        authority_info.clone(),
+                        token_swap.nonce,
+                        U256::to_u64(host_fee)?,
+                    )?;
+                    host_fee
+                }
+                None => U256::zero(),
+            }
+        };
+
+        if let Some((pool_mint_info, admin_pool_token_info)) = admin_fee_pool_infos {
+            // The admin fee stays in the pool; mint the admin its share of the
+            // invariant instead, but for the host fee paid out of the pool
+            let d_1 = if token_swap.fees.fee_on_input {
+                invariant.compute_d(
+                    result
+                        .new_source_amount
+                        .checked_sub(host_fee)
+                        .ok_or(SwapError::CalculationFailure)?,
+                    result.new_destination_amount,
+                )
+            } else {
+                invariant.compute_d(
+                    result.new_source_amount,
+                    result
+                        .new_destination_amount
+                        .checked_sub(host_fee)
+                        .ok_or(SwapError::CalculationFailure)?,
+                )
+            }
+            .ok_or(SwapError::CalculationFailure)?;
+            let d_0 = if token_swap.fees.fee_on_input {
+                invariant.compute_d(
+                    result
+                        .new_source_amount
+                        .checked_sub(result.admin_fee)
+                        .ok_or(SwapError::CalculationFailure)?,
+                    result.new_destination_amount,
+                )
+            } else {
+                invariant.compute_d(
+                    result.new_source_amount,
+                    result
+                        .new_destination_amount
+                        .checked_sub(result.admin_fee)
+                        .ok_or(SwapError::CalculationFailure)?,
+                )
+            }
+            .ok_or(SwapError::CalculationFailure)?;
+            let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
+            let converter = PoolTokenConverter {
+                supply: U256::from(pool_mint.supply),
+                token_a: U256::from(swap_source_amount),
+                token_b: U256::from(swap_destination_amount),
+                fees: &token_swap.fees,
+            };
+            let admin_pool_token_amount = converter
+                .pool_tokens_for_invariant_growth(d_0, d_1)
+                .ok_or(SwapError::CalculationFailure)?;
+            if !admin_pool_token_amount.is_zero() {
+                Self::pool_token_mint_to(
+                    token_swap.lp_soulbound,
+                    swap_info.key,
+                    token_program_info.clone(),
+                    pool_mint_info.clone(),
+                    admin_pool_token_info.clone(),
+                    authority_info.clone(),
+                    token_swap.nonce,
+                    U256::to_u64(admin_pool_token_amount)?,
+                )?;
+            }
+        } else {
+            // The admin fee stays in the swap token account until harvested, but for
+            // the host fee and the share set aside in the fee buffer
+            let admin_fee = U256::to_u64(result.admin_fee - host_fee)?;
+            let fee_buffer_share = token_swap
+                .fee_buffer_share(admin_fee)
+                .ok_or(SwapError::CalculationFailure)?;
+            let (admin_fees_accrued, fee_buffer) = if *swap_fee_info.key == token_swap.token_a {
+                (
+                    &mut token_swap.admin_fees_accrued_a,
+                    &mut token_swap.fee_buffer_a,
+                )
+            } else {
+                (
+                    &mut token_swap.admin_fees_accrued_b,
+                    &mut token_swap.fee_buffer_b,
+                )
+            };
+            *admin_fees_accrued = admin_fees_accrued
+                .checked_add(admin_fee - fee_buffer_share)
+                .ok_or(SwapError::CalculationFailure)?;
+            *fee_buffer = fee_buffer
+                .checked_add(fee_buffer_share)
+                .ok_or(SwapError::CalculationFailure)?;
+        }
+        if token_swap.insurance_fee_bps != 0 {
+            // The insurance fund takes its share of the trade fee left to the LPs
+            let lp_fee = result
+                .trade_fee
+                .saturating_sub(result.reflection_fee)
+                .saturating_sub(result.buyback_fee)
+                .saturating_sub(result.marketing_fee)
+                .saturating_sub(result.developer_fee)
+                .saturating_sub(result.admin_fee);
+            let insurance_share = token_swap
+                .insurance_share(U256::to_u64(lp_fee)?)
+                .ok_or(SwapError::CalculationFailure)?;
+            let insurance_fund = if *swap_fee_info.key == token_swap.token_a {
+                &mut token_swap.insurance_fund_a
+            } else {
+                &mut token_swap.insurance_fund_b
+            };
+            *insurance_fund = insurance_fund
+                .checked_add(insurance_share)
+                .ok_or(SwapError::CalculationFailure)?;
+        }
+        if token_swap.is_twap_tracked() {
+            let (amount_a, amount_b) = if *swap_source_info.key == token_swap.token_a {
+                (amount_received, amount_swapped)
+            } else {
+                (amount_swapped, amount_received)
+            };
+            if token_swap.is_dynamic_amp_enabled() {
+                token_swap.update_dynamic_amp(amount_a, amount_b, clock.slot)
+            } else {
+                token_swap
+                    .observe_swap_price(amount_a, amount_b, clock.slot)
+                    .map(|_| ())
+            }
+            .ok_or(SwapError::CalculationFailure)?;
+        }
+        // The first swap of an epoch reports the fees of the previous one if the
+        // crank didn't yet, so that fees are counted in the epoch they're charged
+        if let Some(report) = token_swap.take_epoch_fee_report(swap_info.key, clock.epoch) {
+            log_event!("{}", report);
+        }
+        token_swap
+            .record_epoch_fees(swap_fee_info.key, &result)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.bump_sequence();
+        log_event!(
+            "{}",
+            SwapEvent {
+                swap: *swap_info.key,
+                swap_source: *swap_source_info.key,
+                swap_destination: *swap_destination_info.key,
+                amount_in,
+                amount_out,
+                trade_fee: U256::to_u64(result.trade_fee)?,
+                admin_fee: U256::to_u64(result.admin_fee)?,
+                sequence: token_swap.sequence,
+                config_hash: token_swap.config_hash,
+                freeze_authority_acknowledged: token_swap.freeze_authority_acknowledged,
+            }
+        );
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Self::update_global_stats_best_effort(program_id, trailing_infos, |global_stats| {
+            global_stats.record_swap(amount_received, amount_swapped)
+        })?;
+        Ok((result, clock.slot))
+    }
+
+    /// Checks that the balance of the token account `destination_info` grew by
+    /// `expected_amount` from `amount_before`
+    fn check_amount_received(
+        destination_info: &AccountInfo,
+        amount_before: u64,
+        expected_amount: u64,
+    ) -> ProgramResult {
+        let amount = utils::unpack_token_account(&destination_info.data.borrow())?.amount;
+        if amount.checked_sub(amount_before) != Some(expected_amount) {
+            return Err(SwapError::OutputAmountMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Processes a [SimulateSwap](enum.Instruction.html).
+    pub fn process_simulate_swap(
+        program_id: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        deadline_slot: u64,
+        expected_sequence: Option<u64>,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let (result, _slot) = Self::swap(
+            program_id,
+            SwapAmount::ExactIn {
+                amount_in,
+                minimum_amount_out,
+            },
+            expected_sequence,
+            deadline_slot,
+            accounts,
         )?;
-        Self::token_transfer(
-            swap_info.key,
-            token_program_info.clone(),
-            swap_destination_info.clone(),
-            developer_destination_info.clone(),
-            authority_info.clone(),
-            token_swap.nonce,
-            U256::to_u64(result.developer_fee)?,
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let _authority_info = next_account_info(account_info_iter)?;
+        let _source_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+
+        let quote = SwapQuote {
+            swap: *swap_info.key,
+            swap_source: *swap_source_info.key,
+            swap_destination: *swap_destination_info.key,
+            amount_in,
+            amount_out: U256::to_u64(result.amount_swapped)?,
+            trade_fee: U256::to_u64(result.trade_fee)?,
+            admin_fee: U256::to_u64(result.admin_fee)?,
+            new_source_amount: U256::to_u64(result.new_source_amount)?,
+            new_destination_amount: U256::to_u64(result.new_destination_amount)?,
+            config_hash: SwapInfo::unpack(&swap_info.data.borrow())?.config_hash,
+        };
+        log_event!("{}", quote);
+        Err(SwapError::SwapSimulated.into())
+    }
+
+    /// Processes a [CoverShortfall](enum.Instruction.html).
+    pub fn process_cover_shortfall(
+        amount_a: u64,
+        amount_b: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let insurance_authority_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.insurance_authority == Pubkey::default()
+            || *insurance_authority_info.key != token_swap.insurance_authority
+        {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if !insurance_authority_info.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Covered tokens already are in the swap token accounts, returning them to
+        // the pool raises the value of every pool token
+        token_swap.insurance_fund_a = token_swap
+            .insurance_fund_a
+            .checked_sub(amount_a)
+            .ok_or(SwapError::InsufficientInsuranceFund)?;
+        token_swap.insurance_fund_b = token_swap
+            .insurance_fund_b
+            .checked_sub(amount_b)
+            .ok_or(SwapError::InsufficientInsuranceFund)?;
+        token_swap.insurance_covered_a = token_swap
+            .insurance_covered_a
+            .checked_add(amount_a)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.insurance_covered_b = token_swap
+            .insurance_covered_b
+            .checked_add(amount_b)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+
+        let cover = InsuranceCover {
+            swap: *swap_info.key,
+            amount_a,
+            amount_b,
+            insurance_fund_a: token_swap.insurance_fund_a,
+            insurance_fund_b: token_swap.insurance_fund_b,
+            covered_a: token_swap.insurance_covered_a,
+            covered_b: token_swap.insurance_covered_b,
+        };
+        log_event!("{}", cover);
+        Ok(())
+    }
+
+    /// Processes a [SwapWithReceipt](enum.Instruction.html).
+    pub fn process_swap_with_receipt(
+        program_id: &Pubkey,
+        amount_in: u64,
+        minimum_amount_out: u64,
+        receipt_id: u64,
+        receipt_nonce: u8,
+        expected_sequence: Option<u64>,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let receipt_info = next_account_info(account_info_iter)?;
+        let receipt_owner_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+        let swap_accounts = account_info_iter.as_slice();
+        let swap_info = swap_accounts
+            .first()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        if !receipt_owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        let receipt_id_bytes = receipt_id.to_le_bytes();
+        let receipt_signature_seeds = [
+            RECEIPT_SEED,
+            swap_info.key.as_ref(),
+            receipt_owner_info.key.as_ref(),
+            &receipt_id_bytes,
+            &[receipt_nonce],
+        ];
+        let receipt_key = Pubkey::create_program_address(&receipt_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+        if *receipt_info.key != receipt_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+
+        let (result, slot) = Self::swap(
+            program_id,
+            SwapAmount::ExactIn {
+                amount_in,
+                minimum_amount_out,
+            },
+            expected_sequence,
+            0,
+            swap_accounts,
         )?;
+        // Swap source and destination token accounts, validated by the swap
+        let source_mint = utils::unpack_token_account(&swap_accounts[3].data.borrow())?.mint;
+        let destination_mint = utils::unpack_token_account(&swap_accounts[4].data.borrow())?.mint;
+
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                receipt_owner_info.key,
+                receipt_info.key,
+                rent.minimum_balance(SwapReceipt::LEN),
+                SwapReceipt::LEN as u64,
+                program_id,
+            ),
+            &[
+                receipt_owner_info.clone(),
+                receipt_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&receipt_signature_seeds],
+        )?;
+
+        let receipt = SwapReceipt {
+            is_initialized: true,
+            swap: *swap_info.key,
+            owner: *receipt_owner_info.key,
+            source_mint,
+            destination_mint,
+            amount_in,
+            amount_out: U256::to_u64(result.amount_swapped)?,
+            fee_amount: U256::to_u64(result.trade_fee)?,
+            slot,
+        };
+        SwapReceipt::pack(receipt, &mut receipt_info.data.borrow_mut())?;
         Ok(())
     }
 
-    /// Processes an [Deposit](enum.Instruction.html).
-    pub fn process_deposit(
+    /// Processes a [CloseSwapReceipt](enum.Instruction.html).
+    pub fn process_close_swap_receipt(
         program_id: &Pubkey,
-        token_a_amount: u64,
-        token_b_amount: u64,
-        min_mint_amount: u64,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let receipt_info = next_account_info(account_info_iter)?;
+        let receipt_owner_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+
+        if receipt_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let receipt = SwapReceipt::unpack(&receipt_info.data.borrow())?;
+        if !receipt_owner_info.is_signer || receipt.owner != *receipt_owner_info.key {
+            return Err(SwapError::Unauthorized.into());
+        }
+
+        let destination_lamports = destination_info
+            .lamports()
+            .checked_add(receipt_info.lamports())
+            .ok_or(SwapError::CalculationFailure)?;
+        **destination_info.lamports.borrow_mut() = destination_lamports;
+        **receipt_info.lamports.borrow_mut() = 0;
+        receipt_info.data.borrow_mut().fill(0);
+        Ok(())
+    }
+
+    /// Processes a [SweepDust](enum.Instruction.html).
+    pub fn process_sweep_dust(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let swap_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
-        let source_a_info = next_account_info(account_info_iter)?;
-        let source_b_info = next_account_info(account_info_iter)?;
-        let token_a_info = next_account_info(account_info_iter)?;
-        let token_b_info = next_account_info(account_info_iter)?;
-        let pool_mint_info = next_account_info(account_info_iter)?;
-        let dest_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let swap_destination_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
         let clock_sysvar_info = next_account_info(account_info_iter)?;
 
-        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
         if token_swap.is_paused {
             return Err(SwapError::IsPaused.into());
         }
@@ -416,522 +2123,11315 @@ impl Processor {
         {
             return Err(SwapError::InvalidProgramAddress.into());
         }
-        if *token_a_info.key != token_swap.token_a {
+        if !(*swap_source_info.key == token_swap.token_a
+            || *swap_source_info.key == token_swap.token_b)
+        {
             return Err(SwapError::IncorrectSwapAccount.into());
         }
-        if *token_b_info.key != token_swap.token_b {
+        if !(*swap_destination_info.key == token_swap.token_a
+            || *swap_destination_info.key == token_swap.token_b)
+        {
             return Err(SwapError::IncorrectSwapAccount.into());
         }
-        if *pool_mint_info.key != token_swap.pool_mint {
-            return Err(SwapError::IncorrectMint.into());
+        if *swap_source_info.key == *swap_destination_info.key {
+            return Err(SwapError::InvalidInput.into());
         }
 
+        let amount_in = utils::unpack_token_account(&source_info.data.borrow())?.amount;
+        if amount_in == 0 {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if amount_in > token_swap.dust_threshold {
+            return Err(SwapError::NotDust.into());
+        }
         let clock = Clock::from_account_info(clock_sysvar_info)?;
-        let token_a = utils::unpack_token_account(&token_a_info.data.borrow())?;
-        let token_b = utils::unpack_token_account(&token_b_info.data.borrow())?;
-        let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
+        if !token_swap.record_dust_sweep(clock.slot) {
+            return Err(SwapError::DustSweepLimit.into());
+        }
 
-        let invariant = StableSwap::new(
-            token_swap.initial_amp_factor,
-            token_swap.target_amp_factor,
-            clock.unix_timestamp,
-            token_swap.start_ramp_ts,
-            token_swap.stop_ramp_ts,
-        );
-        let mint_amount_u256 = invariant
-            .compute_mint_amount_for_deposit(
-                U256::from(token_a_amount),
-                U256::from(token_b_amount),
-                U256::from(token_a.amount),
-                U256::from(token_b.amount),
-                U256::from(pool_mint.supply),
-                &token_swap.fees,
+        let swap_source_account = utils::unpack_token_account(&swap_source_info.data.borrow())?;
+        let swap_destination_account =
+            utils::unpack_token_account(&swap_destination_info.data.borrow())?;
+        let invariant = token_swap.curve(clock.unix_timestamp);
+        let amount_out = invariant
+            .swap_without_fees(
+                U256::from(amount_in),
+                U256::from(
+                    token_swap
+                        .pool_amount(swap_source_info.key, swap_source_account.amount)
+                        .ok_or(SwapError::CalculationFailure)?,
+                ),
+                U256::from(
+                    token_swap
+                        .pool_amount(swap_destination_info.key, swap_destination_account.amount)
+                        .ok_or(SwapError::CalculationFailure)?,
+                ),
             )
             .ok_or(SwapError::CalculationFailure)?;
-        let mint_amount = U256::to_u64(mint_amount_u256)?;
-        if mint_amount < min_mint_amount {
-            return Err(SwapError::ExceededSlippage.into());
+        let amount_out = U256::to_u64(amount_out)?;
+        if amount_out == 0 {
+            return Err(SwapError::CalculationFailure.into());
         }
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
 
-        Self::token_transfer(
-            swap_info.key,
-            token_program_info.clone(),
-            source_a_info.clone(),
-            token_a_info.clone(),
-            authority_info.clone(),
-            token_swap.nonce,
-            token_a_amount,
-        )?;
-        Self::token_transfer(
+        Self::user_token_transfer(
             swap_info.key,
             token_program_info.clone(),
-            source_b_info.clone(),
-            token_b_info.clone(),
+            source_info.clone(),
+            Self::token_mint(source_info, accounts)?,
+            swap_source_info.clone(),
             authority_info.clone(),
             token_swap.nonce,
-            token_b_amount,
+            amount_in,
         )?;
-        Self::token_mint_to(
+        Self::token_transfer_with_mint(
             swap_info.key,
             token_program_info.clone(),
-            pool_mint_info.clone(),
-            dest_info.clone(),
+            swap_destination_info.clone(),
+            Self::token_mint(swap_destination_info, accounts)?,
+            destination_info.clone(),
             authority_info.clone(),
             token_swap.nonce,
-            mint_amount,
+            amount_out,
         )?;
         Ok(())
     }
 
-    /// Processes an [Withdraw](enum.Instruction.html).
-    pub fn process_withdraw(
+    /// Processes a [DepositLiquidityPosition](enum.Instruction.html).
+    pub fn process_deposit_liquidity_position(
         program_id: &Pubkey,
-        pool_token_amount: u64,
-        minimum_token_a_amount: u64,
-        minimum_token_b_amount: u64,
+        amount: u64,
+        nonce: u8,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let swap_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
-        let pool_mint_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
         let source_info = next_account_info(account_info_iter)?;
-        let token_a_info = next_account_info(account_info_iter)?;
-        let token_b_info = next_account_info(account_info_iter)?;
-        let dest_token_a_info = next_account_info(account_info_iter)?;
-        let dest_token_b_info = next_account_info(account_info_iter)?;
-        let admin_fee_dest_a_info = next_account_info(account_info_iter)?;
-        let admin_fee_dest_b_info = next_account_info(account_info_iter)?;
+        let liquidity_vault_info = next_account_info(account_info_iter)?;
+        let position_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
 
-        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.liquidity_vault == Pubkey::default() || amount == 0 {
+            return Err(SwapError::InvalidInput.into());
+        }
         if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
         {
             return Err(SwapError::InvalidProgramAddress.into());
         }
-        if *token_a_info.key != token_swap.token_a {
-            return Err(SwapError::IncorrectSwapAccount.into());
+        if !owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
         }
-        if *token_b_info.key != token_swap.token_b {
+        if *liquidity_vault_info.key != token_swap.liquidity_vault {
             return Err(SwapError::IncorrectSwapAccount.into());
         }
-        if *pool_mint_info.key != token_swap.pool_mint {
-            return Err(SwapError::IncorrectMint.into());
-        }
-        if *admin_fee_dest_a_info.key != token_swap.admin_fee_key_a {
-            return Err(SwapError::InvalidAdmin.into());
-        }
-        if *admin_fee_dest_b_info.key != token_swap.admin_fee_key_b {
-            return Err(SwapError::InvalidAdmin.into());
-        }
-        let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
-        if pool_mint.supply == 0 {
-            return Err(SwapError::EmptyPool.into());
-        }
-
-        let token_a = utils::unpack_token_account(&token_a_info.data.borrow())?;
-        let token_b = utils::unpack_token_account(&token_b_info.data.borrow())?;
 
-        let converter = PoolTokenConverter {
-            supply: U256::from(pool_mint.supply),
-            token_a: U256::from(token_a.amount),
-            token_b: U256::from(token_b.amount),
-            fees: &token_swap.fees,
-        };
-        let pool_token_amount_u256 = U256::from(pool_token_amount);
-        let (a_amount_u256, a_admin_fee_u256) = converter
-            .token_a_rate(pool_token_amount_u256)
-            .ok_or(SwapError::CalculationFailure)?;
-        let (a_amount, a_admin_fee) = (
-            U256::to_u64(a_amount_u256)?,
-            U256::to_u64(a_admin_fee_u256)?,
-        );
-        if a_amount < minimum_token_a_amount {
-            return Err(SwapError::ExceededSlippage.into());
-        }
-        let (b_amount_u256, b_admin_fee_u256) = converter
-            .token_b_rate(pool_token_amount_u256)
-            .ok_or(SwapError::CalculationFailure)?;
-        let (b_amount, b_admin_fee) = (
-            U256::to_u64(b_amount_u256)?,
-            U256::to_u64(b_admin_fee_u256)?,
-        );
-        if b_amount < minimum_token_b_amount {
-            return Err(SwapError::ExceededSlippage.into());
+        let position_signature_seeds = [
+            LIQUIDITY_POSITION_SEED,
+            swap_info.key.as_ref(),
+            owner_info.key.as_ref(),
+            &[nonce],
+        ];
+        let position_key = Pubkey::create_program_address(&position_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+        if *position_info.key != position_key {
+            return Err(SwapError::InvalidProgramAddress.into());
         }
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        let mut position = if position_info.lamports() == 0 {
+            let rent = Rent::from_account_info(rent_sysvar_info)?;
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner_info.key,
+                    position_info.key,
+                    rent.minimum_balance(LiquidityPosition::LEN),
+                    LiquidityPosition::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    owner_info.clone(),
+                    position_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&position_signature_seeds],
+            )?;
+            LiquidityPosition {
+                is_initialized: true,
+                swap: *swap_info.key,
+                owner: *owner_info.key,
+                amount: 0,
+                cumulative_liquidity: 0,
+                checkpoint_ts: clock.unix_timestamp,
+            }
+        } else {
+            if position_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            LiquidityPosition::unpack(&position_info.data.borrow())?
+        };
 
-        Self::token_transfer(
-            swap_info.key,
-            token_program_info.clone(),
-            token_a_info.clone(),
-            dest_token_a_info.clone(),
-            authority_info.clone(),
-            token_swap.nonce,
-            a_amount,
-        )?;
-        Self::token_transfer(
-            swap_info.key,
-            token_program_info.clone(),
-            token_a_info.clone(),
-            admin_fee_dest_a_info.clone(),
-            authority_info.clone(),
-            token_swap.nonce,
-            a_admin_fee,
-        )?;
-        Self::token_transfer(
-            swap_info.key,
-            token_program_info.clone(),
-            token_b_info.clone(),
-            dest_token_b_info.clone(),
-            authority_info.clone(),
-            token_swap.nonce,
-            b_amount,
-        )?;
-        Self::token_transfer(
-            swap_info.key,
-            token_program_info.clone(),
-            token_b_info.clone(),
-            admin_fee_dest_b_info.clone(),
-            authority_info.clone(),
-            token_swap.nonce,
-            b_admin_fee,
-        )?;
-        Self::token_burn(
+        Self::user_token_transfer(
             swap_info.key,
             token_program_info.clone(),
             source_info.clone(),
-            pool_mint_info.clone(),
+            Self::token_mint(source_info, accounts)?,
+            liquidity_vault_info.clone(),
             authority_info.clone(),
             token_swap.nonce,
-            pool_token_amount,
+            amount,
         )?;
+
+        position
+            .checkpoint(clock.unix_timestamp)
+            .ok_or(SwapError::CalculationFailure)?;
+        position.amount = position
+            .amount
+            .checked_add(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap
+            .checkpoint_position_liquidity(clock.unix_timestamp)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.position_liquidity = token_swap
+            .position_liquidity
+            .checked_add(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        LiquidityPosition::pack(position, &mut position_info.data.borrow_mut())?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
         Ok(())
     }
 
-    /// Processes an [WithdrawOne](enum.Instruction.html).
-    pub fn process_withdraw_one(
+    /// Processes a [WithdrawLiquidityPosition](enum.Instruction.html).
+    pub fn process_withdraw_liquidity_position(
         program_id: &Pubkey,
-        pool_token_amount: u64,
-        minimum_token_amount: u64,
+        amount: u64,
         accounts: &[AccountInfo],
     ) -> ProgramResult {
         let account_info_iter = &mut accounts.iter();
         let swap_info = next_account_info(account_info_iter)?;
         let authority_info = next_account_info(account_info_iter)?;
-        let pool_mint_info = next_account_info(account_info_iter)?;
-        let source_info = next_account_info(account_info_iter)?;
-        let base_token_info = next_account_info(account_info_iter)?;
-        let quote_token_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let position_info = next_account_info(account_info_iter)?;
+        let liquidity_vault_info = next_account_info(account_info_iter)?;
         let destination_info = next_account_info(account_info_iter)?;
-        let admin_destination_info = next_account_info(account_info_iter)?;
         let token_program_info = next_account_info(account_info_iter)?;
         let clock_sysvar_info = next_account_info(account_info_iter)?;
 
-        if *base_token_info.key == *quote_token_info.key {
-            return Err(SwapError::InvalidInput.into());
-        }
-        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
-        if token_swap.is_paused {
-            return Err(SwapError::IsPaused.into());
-        }
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
         if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
         {
             return Err(SwapError::InvalidProgramAddress.into());
         }
-        if *base_token_info.key != token_swap.token_b && *base_token_info.key != token_swap.token_a
-        {
-            return Err(SwapError::IncorrectSwapAccount.into());
+        if !owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
         }
-        if *quote_token_info.key != token_swap.token_b
-            && *quote_token_info.key != token_swap.token_a
-        {
+        if *liquidity_vault_info.key != token_swap.liquidity_vault {
             return Err(SwapError::IncorrectSwapAccount.into());
         }
-        if *base_token_info.key == token_swap.token_a
-            && *admin_destination_info.key != token_swap.admin_fee_key_a
-        {
-            return Err(SwapError::InvalidAdmin.into());
-        }
-        if *base_token_info.key == token_swap.token_b
-            && *admin_destination_info.key != token_swap.admin_fee_key_b
-        {
-            return Err(SwapError::InvalidAdmin.into());
+        if position_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
         }
-        if *pool_mint_info.key != token_swap.pool_mint {
-            return Err(SwapError::IncorrectMint.into());
-        }
-        let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
-        if pool_token_amount > pool_mint.supply {
-            return Err(SwapError::InvalidInput.into());
+        let mut position = LiquidityPosition::unpack(&position_info.data.borrow())?;
+        if position.swap != *swap_info.key || position.owner != *owner_info.key {
+            return Err(SwapError::Unauthorized.into());
         }
 
         let clock = Clock::from_account_info(clock_sysvar_info)?;
-        let base_token = utils::unpack_token_account(&base_token_info.data.borrow())?;
-        let quote_token = utils::unpack_token_account(&quote_token_info.data.borrow())?;
-
-        let invariant = StableSwap::new(
-            token_swap.initial_amp_factor,
-            token_swap.target_amp_factor,
-            clock.unix_timestamp,
-            token_swap.start_ramp_ts,
-            token_swap.stop_ramp_ts,
-        );
-        let (dy, dy_fee) = invariant
-            .compute_withdraw_one(
-                U256::from(pool_token_amount),
-                U256::from(pool_mint.supply),
-                U256::from(base_token.amount),
-                U256::from(quote_token.amount),
-                &token_swap.fees,
-            )
-            .ok_or(SwapError::CalculationFailure)?;
-        let withdraw_fee = token_swap
-            .fees
-            .withdraw_fee(dy)
-            .ok_or(SwapError::CalculationFailure)?;
-        let token_amount = U256::to_u64(
-            dy.checked_sub(withdraw_fee)
-                .ok_or(SwapError::CalculationFailure)?,
-        )?;
-        if token_amount < minimum_token_amount {
-            return Err(SwapError::ExceededSlippage.into());
-        }
-
-        let admin_trade_fee = token_swap
-            .fees
-            .admin_trade_fee(dy_fee)
+        position
+            .checkpoint(clock.unix_timestamp)
             .ok_or(SwapError::CalculationFailure)?;
-        let admin_withdraw_fee = token_swap
-            .fees
-            .admin_withdraw_fee(withdraw_fee)
+        position.amount = position
+            .amount
+            .checked_sub(amount)
+            .ok_or(SwapError::InvalidInput)?;
+        token_swap
+            .checkpoint_position_liquidity(clock.unix_timestamp)
             .ok_or(SwapError::CalculationFailure)?;
-        let admin_fee = admin_trade_fee
-            .checked_add(admin_withdraw_fee)
+        token_swap.position_liquidity = token_swap
+            .position_liquidity
+            .checked_sub(amount)
             .ok_or(SwapError::CalculationFailure)?;
 
-        Self::token_transfer(
+        Self::token_transfer_with_mint(
             swap_info.key,
             token_program_info.clone(),
-            base_token_info.clone(),
+            liquidity_vault_info.clone(),
+            Self::token_mint(liquidity_vault_info, accounts)?,
             destination_info.clone(),
             authority_info.clone(),
             token_swap.nonce,
-            token_amount,
-        )?;
-        Self::token_transfer(
-            swap_info.key,
-            token_program_info.clone(),
-            base_token_info.clone(),
-            admin_destination_info.clone(),
-            authority_info.clone(),
-            token_swap.nonce,
-            U256::to_u64(admin_fee)?,
+            amount,
         )?;
-        Self::token_burn(
+        LiquidityPosition::pack(position, &mut position_info.data.borrow_mut())?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [DepositReflectionPosition](enum.Instruction.html).
+    pub fn process_deposit_reflection_position(
+        program_id: &Pubkey,
+        amount: u64,
+        nonce: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: writable owned(program_id),
+            authority_info,
+            owner_info: signer,
+            source_info,
+            reflection_lp_vault_info,
+            position_info: writable,
+            system_program_info,
+            rent_sysvar_info,
+            token_program_info,
+        }
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if !token_swap.is_reflection_enabled() || amount == 0 {
+            return Err(SwapError::InvalidInput.into());
+        }
+        constrain! {
+            authority_info: authority(program_id, swap_info.key, token_swap.nonce),
+            reflection_lp_vault_info: key(token_swap.reflection_lp_vault),
+        }
+
+        let position_signature_seeds = [
+            REFLECTION_POSITION_SEED,
+            swap_info.key.as_ref(),
+            owner_info.key.as_ref(),
+            &[nonce],
+        ];
+        let position_key = Pubkey::create_program_address(&position_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+        if *position_info.key != position_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        let mut position = if position_info.lamports() == 0 {
+            let rent = Rent::from_account_info(rent_sysvar_info)?;
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner_info.key,
+                    position_info.key,
+                    rent.minimum_balance(ReflectionPosition::LEN),
+                    ReflectionPosition::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    owner_info.clone(),
+                    position_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&position_signature_seeds],
+            )?;
+            ReflectionPosition {
+                is_initialized: true,
+                swap: *swap_info.key,
+                owner: *owner_info.key,
+                ..ReflectionPosition::default()
+            }
+        } else {
+            if position_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            ReflectionPosition::unpack(&position_info.data.borrow())?
+        };
+
+        Self::user_token_transfer(
             swap_info.key,
             token_program_info.clone(),
             source_info.clone(),
-            pool_mint_info.clone(),
+            Self::token_mint(source_info, accounts)?,
+            reflection_lp_vault_info.clone(),
             authority_info.clone(),
             token_swap.nonce,
-            pool_token_amount,
+            amount,
         )?;
+
+        position
+            .set_amount(
+                position
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(SwapError::CalculationFailure)?,
+                token_swap.acc_reflection_per_lp_share_a,
+                token_swap.acc_reflection_per_lp_share_b,
+            )
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.reflection_supply = token_swap
+            .reflection_supply
+            .checked_add(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        ReflectionPosition::pack(position, &mut position_info.data.borrow_mut())?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
         Ok(())
     }
 
-    /// Processes an [Instruction](enum.Instruction.html).
-    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
-        let instruction = AdminInstruction::unpack(input)?;
-        match instruction {
-            None => Self::process_swap_instruction(program_id, accounts, input),
-            Some(admin_instruction) => {
-                process_admin_instruction(&admin_instruction, program_id, accounts)
-            }
+    /// Unpacks the reflection position of `owner_info` in the swap.
+    fn unpack_reflection_position(
+        program_id: &Pubkey,
+        swap_info: &AccountInfo,
+        owner_info: &AccountInfo,
+        position_info: &AccountInfo,
+    ) -> Result<ReflectionPosition, ProgramError> {
+        constrain! {
+            owner_info: signer,
+            position_info: writable owned(program_id),
         }
+        let position = ReflectionPosition::unpack(&position_info.data.borrow())?;
+        if position.swap != *swap_info.key || position.owner != *owner_info.key {
+            return Err(SwapError::Unauthorized.into());
+        }
+        Ok(position)
     }
 
-    fn process_swap_instruction(
+    /// Processes a [WithdrawReflectionPosition](enum.Instruction.html).
+    pub fn process_withdraw_reflection_position(
         program_id: &Pubkey,
+        amount: u64,
         accounts: &[AccountInfo],
-        input: &[u8],
     ) -> ProgramResult {
-        let instruction = SwapInstruction::unpack(input)?;
-        match instruction {
-            SwapInstruction::Initialize(InitializeData {
-                nonce,
-                amp_factor,
-                fees,
-            }) => {
-                msg!("Instruction: Init");
-                Self::process_initialize(program_id, nonce, amp_factor, fees, accounts)
-            }
-            SwapInstruction::Swap(SwapData {
-                amount_in,
-                minimum_amount_out,
-            }) => {
-                msg!("Instruction: Swap");
-                Self::process_swap(program_id, amount_in, minimum_amount_out, accounts)
-            }
-            SwapInstruction::Deposit(DepositData {
-                token_a_amount,
-                token_b_amount,
-                min_mint_amount,
-            }) => {
-                msg!("Instruction: Deposit");
-                Self::process_deposit(
-                    program_id,
-                    token_a_amount,
-                    token_b_amount,
-                    min_mint_amount,
-                    accounts,
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: writable owned(program_id),
+            authority_info,
+            owner_info,
+            position_info,
+            reflection_lp_vault_info,
+            destination_info,
+            token_program_info,
+        }
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        constrain! {
+            authority_info: authority(program_id, swap_info.key, token_swap.nonce),
+            reflection_lp_vault_info: key(token_swap.reflection_lp_vault),
+        }
+        let mut position =
+            Self::unpack_reflection_position(program_id, swap_info, owner_info, position_info)?;
+
+        position
+            .set_amount(
+                position
+                    .amount
+                    .checked_sub(amount)
+                    .ok_or(SwapError::InvalidInput)?,
+                token_swap.acc_reflection_per_lp_share_a,
+                token_swap.acc_reflection_per_lp_share_b,
+            )
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.reflection_supply = token_swap
+            .reflection_supply
+            .checked_sub(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            reflection_lp_vault_info.clone(),
+            Self::token_mint(reflection_lp_vault_info, accounts)?,
+            destination_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            amount,
+        )?;
+        ReflectionPosition::pack(position, &mut position_info.data.borrow_mut())?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [ClaimReflection](enum.Instruction.html).
+    pub fn process_claim_reflection(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: owned(program_id),
+            authority_info,
+            owner_info,
+            position_info,
+            reflection_vault_a_info,
+            reflection_vault_b_info,
+            destination_a_info,
+            destination_b_info,
+            token_program_info,
+        }
+
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        constrain! {
+            authority_info: authority(program_id, swap_info.key, token_swap.nonce),
+            reflection_vault_a_info: key(token_swap.reflection_vault_a),
+            reflection_vault_b_info: key(token_swap.reflection_vault_b),
+        }
+        let mut position =
+            Self::unpack_reflection_position(program_id, swap_info, owner_info, position_info)?;
+
+        position
+            .set_amount(
+                position.amount,
+                token_swap.acc_reflection_per_lp_share_a,
+                token_swap.acc_reflection_per_lp_share_b,
+            )
+            .ok_or(SwapError::CalculationFailure)?;
+        for (reflection_vault_info, destination_info, reflection_owed) in [
+            (
+                reflection_vault_a_info,
+                destination_a_info,
+                &mut position.reflection_owed_a,
+            ),
+            (
+                reflection_vault_b_info,
+                destination_b_info,
+                &mut position.reflection_owed_b,
+            ),
+        ] {
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                reflection_vault_info.clone(),
+                Self::token_mint(reflection_vault_info, accounts)?,
+                destination_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                *reflection_owed,
+            )?;
+            *reflection_owed = 0;
+        }
+        ReflectionPosition::pack(position, &mut position_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes an [ExecuteBuyback](enum.Instruction.html).
+    pub fn process_execute_buyback(
+        program_id: &Pubkey,
+        minimum_amount_out: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: writable owned(program_id),
+            authority_info,
+            source_vault_info: writable,
+            burn_vault_info: writable,
+            swap_source_info: writable,
+            swap_destination_info: writable,
+            buyback_mint_info: writable,
+            token_program_info,
+            clock_sysvar_info,
+        }
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::IsPaused.into());
+        }
+        if !token_swap.is_buyback_enabled() {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let (source_vault, burn_vault, swap_source, swap_destination) =
+            if token_swap.buyback_mint == token_swap.token_b_mint {
+                (
+                    token_swap.buyback_vault_a,
+                    token_swap.buyback_vault_b,
+                    token_swap.token_a,
+                    token_swap.token_b,
                 )
-            }
-            SwapInstruction::Withdraw(WithdrawData {
-                pool_token_amount,
-                minimum_token_a_amount,
-                minimum_token_b_amount,
-            }) => {
-                msg!("Instruction: Withdraw");
-                Self::process_withdraw(
-                    program_id,
-                    pool_token_amount,
-                    minimum_token_a_amount,
-                    minimum_token_b_amount,
-                    accounts,
+            } else {
+                (
+                    token_swap.buyback_vault_b,
+                    token_swap.buyback_vault_a,
+                    token_swap.token_b,
+                    token_swap.token_a,
                 )
-            }
-            SwapInstruction::WithdrawOne(WithdrawOneData {
-                pool_token_amount,
-                minimum_token_amount,
-            }) => {
-                msg!("Instruction: Withdraw One");
-                Self::process_withdraw_one(
-                    program_id,
-                    pool_token_amount,
-                    minimum_token_amount,
-                    accounts,
+            };
+        constrain! {
+            authority_info: authority(program_id, swap_info.key, token_swap.nonce),
+            source_vault_info: key(source_vault),
+            burn_vault_info: key(burn_vault),
+            swap_source_info: key(swap_source),
+            swap_destination_info: key(swap_destination),
+            buyback_mint_info: key(token_swap.buyback_mint),
+        }
+
+        let amount_in = utils::unpack_token_account(&source_vault_info.data.borrow())?.amount;
+        let amount_out = if amount_in == 0 {
+            0
+        } else {
+            let clock = Clock::from_account_info(clock_sysvar_info)?;
+            let swap_source_account = utils::unpack_token_account(&swap_source_info.data.borrow())?;
+            let swap_destination_account =
+                utils::unpack_token_account(&swap_destination_info.data.borrow())?;
+            let invariant = token_swap.curve(clock.unix_timestamp);
+            let amount_out = invariant
+                .swap_without_fees(
+                    U256::from(amount_in),
+                    U256::from(
+                        token_swap
+                            .pool_amount(swap_source_info.key, swap_source_account.amount)
+                            .ok_or(SwapError::CalculationFailure)?,
+                    ),
+                    U256::from(
+                        token_swap
+                            .pool_amount(swap_destination_info.key, swap_destination_account.amount)
+                            .ok_or(SwapError::CalculationFailure)?,
+                    ),
                 )
-            }
+                .ok_or(SwapError::CalculationFailure)?;
+            U256::to_u64(amount_out)?
+        };
+        if amount_out < minimum_amount_out {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+
+        if amount_in != 0 {
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                source_vault_info.clone(),
+                Self::token_mint(source_vault_info, accounts)?,
+                swap_source_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                amount_in,
+            )?;
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                swap_destination_info.clone(),
+                Self::token_mint(swap_destination_info, accounts)?,
+                burn_vault_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                amount_out,
+            )?;
+        }
+        let burn_amount = utils::unpack_token_account(&burn_vault_info.data.borrow())?.amount;
+        if burn_amount == 0 {
+            return Err(SwapError::InvalidInput.into());
         }
+        Self::token_burn(
+            swap_info.key,
+            token_program_info.clone(),
+            burn_vault_info.clone(),
+            buyback_mint_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            burn_amount,
+        )?;
+
+        token_swap.buyback_burned = token_swap
+            .buyback_burned
+            .checked_add(burn_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
     }
-}
 
-impl PrintProgramError for SwapError {
-    fn print<E>(&self)
-    where
-        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
-    {
-        match self {
-            SwapError::AlreadyInUse => msg!("Error: Swap account already in use"),
-            SwapError::InvalidAdmin => {
-                msg!("Error: Address of the admin fee account is incorrect")
-            }
-            SwapError::InvalidOwner => {
-                msg!("Error: The input account owner is not the program address")
-            }
-            SwapError::InvalidOutputOwner => {
-                msg!("Error: Output pool account owner cannot be the program address")
-            }
-            SwapError::InvalidProgramAddress => {
-                msg!("Error: Invalid program address generated from nonce and key")
-            }
-            SwapError::ExpectedMint => {
-                msg!("Error: Deserialized account is not an SPL Token mint")
-            }
-            SwapError::ExpectedAccount => {
-                msg!("Error: Deserialized account is not an SPL Token account")
-            }
-            SwapError::EmptySupply => msg!("Error: Input token account empty"),
-            SwapError::EmptyPool => msg!("Error: Pool token supply is 0"),
-            SwapError::InvalidSupply => msg!("Error: Pool token mint has a non-zero supply"),
-            SwapError::RepeatedMint => msg!("Error: Swap input token accounts have the same mint"),
-            SwapError::InvalidDelegate => msg!("Error: Token account has a delegate"),
-            SwapError::InvalidInput => msg!("Error: InvalidInput"),
-            SwapError::IncorrectSwapAccount => {
-                msg!("Error: Address of the provided swap token account is incorrect")
-            }
-            SwapError::IncorrectMint => {
-                msg!("Error: Address of the provided token mint is incorrect")
+    /// Processes a [WithdrawMarketingFees](enum.Instruction.html) or a
+    /// [WithdrawDeveloperFees](enum.Instruction.html) of the fees of `bucket`.
+    pub fn process_withdraw_wallet_fees(
+        program_id: &Pubkey,
+        bucket: FeeBucket,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: writable owned(program_id),
+            authority_info,
+            wallet_info,
+            token_a_info: writable,
+            token_b_info: writable,
+            destination_a_info: writable,
+            destination_b_info: writable,
+            token_program_info,
+        }
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        let (wallet, amount_a, amount_b) = match bucket {
+            FeeBucket::Marketing => (
+                token_swap.marketing_wallet,
+                token_swap.marketing_fees_accrued_a,
+                token_swap.marketing_fees_accrued_b,
+            ),
+            FeeBucket::Developer => (
+                token_swap.developer_wallet,
+                token_swap.developer_fees_accrued_a,
+                token_swap.developer_fees_accrued_b,
+            ),
+        };
+        if wallet == Pubkey::default() {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if *wallet_info.key != wallet || !wallet_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        constrain! {
+            authority_info: authority(program_id, swap_info.key, token_swap.nonce),
+            token_a_info: key(token_swap.token_a),
+            token_b_info: key(token_swap.token_b),
+            destination_a_info: token(mint = token_swap.token_a_mint, owner = wallet),
+            destination_b_info: token(mint = token_swap.token_b_mint, owner = wallet),
+        }
+
+        for (swap_token_info, destination_info, amount) in [
+            (token_a_info, destination_a_info, amount_a),
+            (token_b_info, destination_b_info, amount_b),
+        ] {
+            if amount == 0 {
+                continue;
             }
-            SwapError::CalculationFailure => msg!("Error: CalculationFailure"),
-            SwapError::InvalidInstruction => msg!("Error: InvalidInstruction"),
-            SwapError::ExceededSlippage => {
-                msg!("Error: Swap instruction exceeds desired slippage limit")
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                swap_token_info.clone(),
+                Self::token_mint(swap_token_info, accounts)?,
+                destination_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                amount,
+            )?;
+        }
+        log_event!(
+            "{}",
+            FeePayout {
+                swap: *swap_info.key,
+                bucket,
+                wallet,
+                destination_a: *destination_a_info.key,
+                destination_b: *destination_b_info.key,
+                amount_a,
+                amount_b,
             }
-            SwapError::InvalidCloseAuthority => msg!("Error: Token account has a close authority"),
-            SwapError::InvalidFreezeAuthority => {
-                msg!("Error: Pool token mint has a freeze authority")
+        );
+
+        match bucket {
+            FeeBucket::Marketing => {
+                token_swap.marketing_fees_accrued_a = 0;
+                token_swap.marketing_fees_accrued_b = 0;
             }
-            SwapError::ConversionFailure => msg!("Error: Conversion to or from u64 failed"),
-            SwapError::Unauthorized => {
-                msg!("Error: Account is not authorized to execute this instruction")
+            FeeBucket::Developer => {
+                token_swap.developer_fees_accrued_a = 0;
+                token_swap.developer_fees_accrued_b = 0;
             }
-            SwapError::IsPaused => msg!("Error: Swap pool is paused"),
-            SwapError::RampLocked => msg!("Error: Ramp is locked in this time period"),
-            SwapError::InsufficientRampTime => msg!("Error: Insufficient ramp time"),
-            SwapError::ActiveTransfer => msg!("Error: Active admin transfer in progress"),
-            SwapError::NoActiveTransfer => msg!("Error: No active admin transfer in progress"),
-            SwapError::AdminDeadlineExceeded => msg!("Error: Admin transfer deadline exceeded"),
-            SwapError::MismatchedDecimals => msg!("Error: Token mints must have same decimals"),
         }
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        instruction::{deposit, swap, withdraw, withdraw_one},
-        utils::test_utils::*,
-    };
-    use solana_sdk::account::Account;
-    use spl_token::{
-        error::TokenError,
-        instruction::{approve, mint_to, revoke, set_authority, AuthorityType},
-    };
 
-    /// Initial amount of pool tokens for swap contract, hard-coded to something
-    /// "sensible" given a maximum of u64.
-    /// Note that on Ethereum, Uniswap uses the geometric mean of all provided
-    /// input amounts, and Balancer uses 100 * 10 ^ 18.
-    const INITIAL_SWAP_POOL_AMOUNT: u64 = 1_000_000_000;
+    /// Processes a [CheckpointLiquidityPosition](enum.Instruction.html).
+    pub fn process_checkpoint_liquidity_position(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let position_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
 
-    #[test]
-    fn test_token_program_id_error() {
-        let swap_key = pubkey_rand();
-        let mut mint = (pubkey_rand(), Account::default());
-        let mut destination = (pubkey_rand(), Account::default());
-        let token_program = (TOKEN_PROGRAM_ID, Account::default());
-        let (authority_key, nonce) =
-            Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
-        let mut authority = (authority_key, Account::default());
-        let swap_bytes = swap_key.to_bytes();
-        let authority_signature_seeds = [&swap_bytes[..32], &[nonce]];
-        let signers = &[&authority_signature_seeds[..]];
-        let ix = mint_to(
-            &token_program.0,
-            &mint.0,
-            &destination.0,
-            &authority.0,
-            &[],
-            10,
-        )
-        .unwrap();
-        let mint = (&mut mint).into();
-        let destination = (&mut destination).into();
-        let authority = (&mut authority).into();
+        if swap_info.owner != program_id || position_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        let mut position = LiquidityPosition::unpack(&position_info.data.borrow())?;
+        if position.swap != *swap_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
 
-        let err = invoke_signed(&ix, &[mint, destination, authority], signers).unwrap_err();
-        assert_eq!(err, ProgramError::InvalidAccountData);
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        position
+            .checkpoint(clock.unix_timestamp)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap
+            .checkpoint_position_liquidity(clock.unix_timestamp)
+            .ok_or(SwapError::CalculationFailure)?;
+        LiquidityPosition::pack(position, &mut position_info.data.borrow_mut())?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
     }
 
-    #[test]
-    fn test_initialize() {
-        let user_key = pubkey_rand();
-        let amp_factor = MIN_AMP;
-        let token_a_amount = 1000;
-        let token_b_amount = 2000;
-        let pool_token_amount = 10;
+    /// Processes an [Deposit](enum.Instruction.html).
+    pub fn process_deposit(
+        program_id: &Pubkey,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        min_mint_amount: u64,
+        deadline_slot: u64,
+        expected_sequence: Option<u64>,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let source_a_info = next_account_info(account_info_iter)?;
+        let source_b_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let dest_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+        let trailing_infos = account_info_iter.as_slice();
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::IsPaused.into());
+        }
+        if token_swap.requires_credential() {
+            let source_a = utils::unpack_token_account(&source_a_info.data.borrow())?;
+            Self::check_credential(&token_swap, &source_a.owner, trailing_infos)?;
+        }
+        if !token_swap.is_at_sequence(expected_sequence) {
+            return Err(SwapError::SequenceMismatch.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if *token_a_info.key != token_swap.token_a {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *token_b_info.key != token_swap.token_b {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *pool_mint_info.key != token_swap.pool_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        Self::check_deadline(clock.slot, deadline_slot)?;
+        {
+            let source_a = utils::unpack_token_account(&source_a_info.data.borrow())?;
+            Self::check_creator_deposit_window(&token_swap, clock.slot, &source_a.owner)?;
+            Self::check_deposit_allowlist(
+                program_id,
+                swap_info.key,
+                &token_swap,
+                &source_a.owner,
+                trailing_infos,
+            )?;
+        }
+        // Bids of ended batches back the pool even before they are settled
+        token_swap.settle_order_flow_auction(clock.slot);
+        let token_a = utils::unpack_token_account(&token_a_info.data.borrow())?;
+        let token_b = utils::unpack_token_account(&token_b_info.data.borrow())?;
+        let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
+        let (token_a_mint_info, token_a_transfer_fee) =
+            Self::transfer_mint(&token_a.mint, trailing_infos, clock.epoch)?;
+        let (token_b_mint_info, token_b_transfer_fee) =
+            Self::transfer_mint(&token_b.mint, trailing_infos, clock.epoch)?;
+
+        let invariant = token_swap.curve(clock.unix_timestamp);
+        let reserve_a = token_swap
+            .pool_amount(token_a_info.key, token_a.amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        let reserve_b = token_swap
+            .pool_amount(token_b_info.key, token_b.amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        let compute_mint = |token_a_amount: u64, token_b_amount: u64| {
+            // The bonus moves from the fee buffer into the pool along the deposit
+            let (bonus_a, bonus_b) = if pool_mint.supply == 0 {
+                (0, 0)
+            } else {
+                token_swap
+                    .deposit_bonus(token_a_amount, token_b_amount, reserve_a, reserve_b)
+                    .ok_or(SwapError::CalculationFailure)?
+            };
+            let mint_amount_u256 = invariant
+                .compute_mint_amount_for_deposit(
+                    U256::from(token_a_amount)
+                        .checked_add(bonus_a.into())
+                        .ok_or(SwapError::CalculationFailure)?,
+                    U256::from(token_b_amount)
+                        .checked_add(bonus_b.into())
+                        .ok_or(SwapError::CalculationFailure)?,
+                    U256::from(reserve_a),
+                    U256::from(reserve_b),
+                    U256::from(pool_mint.supply),
+                    &token_swap.fees,
+                )
+                .ok_or(SwapError::CalculationFailure)?;
+            Ok::<_, SwapError>((U256::to_u64(mint_amount_u256)?, bonus_a, bonus_b))
+        };
+        // Pool tokens are minted for what the pool receives after the mint transfer fees
+        let (mut token_a_amount, mut token_b_amount) = (token_a_amount, token_b_amount);
+        let (mut mint_amount, mut bonus_a, mut bonus_b) = compute_mint(
+            token_a_transfer_fee
+                .post_fee_amount(token_a_amount)
+                .ok_or(SwapError::CalculationFailure)?,
+            token_b_transfer_fee
+                .post_fee_amount(token_b_amount)
+                .ok_or(SwapError::CalculationFailure)?,
+        )?;
+        if mint_amount < token_swap.min_lp_mint.max(1) {
+            return Err(SwapError::MintAmountTooSmall.into());
+        }
+        if mint_amount < min_mint_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        // Tokens beyond the pool ratio cost of the minted pool tokens, lost to rounding
+        // otherwise, stay with the depositor
+        if pool_mint.supply != 0 {
+            // Rounded up against the depositor
+            let cost = |reserve: u64| {
+                U256::from(mint_amount)
+                    .checked_mul(reserve.into())?
+                    .checked_add(U256::from(pool_mint.supply - 1))?
+                    .checked_div(pool_mint.supply.into())
+                    .and_then(|cost| U256::to_u64(cost).ok())
+            };
+            let cost_a = cost(reserve_a).ok_or(SwapError::CalculationFailure)?;
+            let cost_b = cost(reserve_b).ok_or(SwapError::CalculationFailure)?;
+            let transfer_a = token_a_transfer_fee
+                .pre_fee_amount(cost_a)
+                .ok_or(SwapError::CalculationFailure)?;
+            let transfer_b = token_b_transfer_fee
+                .pre_fee_amount(cost_b)
+                .ok_or(SwapError::CalculationFailure)?;
+            if transfer_a <= token_a_amount && transfer_b <= token_b_amount {
+                // Only refund when the cost alone still mints as much
+                if let Ok((cost_mint_amount, cost_bonus_a, cost_bonus_b)) =
+                    compute_mint(cost_a, cost_b)
+                {
+                    if cost_mint_amount >= mint_amount {
+                        token_a_amount = transfer_a;
+                        token_b_amount = transfer_b;
+                        mint_amount = cost_mint_amount;
+                        bonus_a = cost_bonus_a;
+                        bonus_b = cost_bonus_b;
+                    }
+                }
+            }
+        }
+        token_swap.fee_buffer_a -= bonus_a;
+        token_swap.fee_buffer_b -= bonus_b;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        log_trace!(
+            "token_a_amount: {}, token_b_amount: {}, mint_amount: {}, bonus_a: {}, bonus_b: {}",
+            token_a_amount,
+            token_b_amount,
+            mint_amount,
+            bonus_a,
+            bonus_b
+        );
+
+        Self::user_token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            source_a_info.clone(),
+            token_a_mint_info,
+            token_a_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            token_a_amount,
+        )?;
+        Self::user_token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            source_b_info.clone(),
+            token_b_mint_info,
+            token_b_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            token_b_amount,
+        )?;
+        Self::pool_token_mint_to(
+            token_swap.lp_soulbound,
+            swap_info.key,
+            token_program_info.clone(),
+            pool_mint_info.clone(),
+            dest_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            mint_amount,
+        )?;
+        Self::update_global_stats_best_effort(program_id, trailing_infos, |global_stats| {
+            global_stats.add_value(token_a_amount);
+            global_stats.add_value(token_b_amount);
+        })
+    }
+
+    /// Processes a [DepositOne](enum.Instruction.html).
+    pub fn process_deposit_one(
+        program_id: &Pubkey,
+        token_amount: u64,
+        min_mint_amount: u64,
+        deadline_slot: u64,
+        expected_sequence: Option<u64>,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let base_token_info = next_account_info(account_info_iter)?;
+        let quote_token_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let dest_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+        let trailing_infos = account_info_iter.as_slice();
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::IsPaused.into());
+        }
+        if !token_swap.is_at_sequence(expected_sequence) {
+            return Err(SwapError::SequenceMismatch.into());
+        }
+        if token_swap.requires_credential() {
+            let source = utils::unpack_token_account(&source_info.data.borrow())?;
+            Self::check_credential(&token_swap, &source.owner, trailing_infos)?;
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        let base_is_a = if *base_token_info.key == token_swap.token_a
+            && *quote_token_info.key == token_swap.token_b
+        {
+            true
+        } else if *base_token_info.key == token_swap.token_b
+            && *quote_token_info.key == token_swap.token_a
+        {
+            false
+        } else {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        };
+        if *pool_mint_info.key != token_swap.pool_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
+        // The first deposit sets the pool ratio, it takes both tokens
+        if pool_mint.supply == 0 {
+            return Err(SwapError::EmptySupply.into());
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        Self::check_deadline(clock.slot, deadline_slot)?;
+        {
+            let source = utils::unpack_token_account(&source_info.data.borrow())?;
+            Self::check_creator_deposit_window(&token_swap, clock.slot, &source.owner)?;
+            Self::check_deposit_allowlist(
+                program_id,
+                swap_info.key,
+                &token_swap,
+                &source.owner,
+                trailing_infos,
+            )?;
+        }
+        token_swap.settle_order_flow_auction(clock.slot);
+        let base_token = utils::unpack_token_account(&base_token_info.data.borrow())?;
+        let quote_token = utils::unpack_token_account(&quote_token_info.data.borrow())?;
+        let (base_mint_info, base_transfer_fee) =
+            Self::transfer_mint(&base_token.mint, trailing_infos, clock.epoch)?;
+
+        let invariant = token_swap.curve(clock.unix_timestamp);
+        let base_reserve = token_swap
+            .pool_amount(base_token_info.key, base_token.amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        let quote_reserve = token_swap
+            .pool_amount(quote_token_info.key, quote_token.amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        // Pool tokens are minted for what the pool receives after the mint transfer fee
+        let amount_received = base_transfer_fee
+            .post_fee_amount(token_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        let (deposit_a, deposit_b, reserve_a, reserve_b) = if base_is_a {
+            (amount_received, 0, base_reserve, quote_reserve)
+        } else {
+            (0, amount_received, quote_reserve, base_reserve)
+        };
+        // The bonus moves from the fee buffer into the pool along the deposit
+        let (bonus_a, bonus_b) = token_swap
+            .deposit_bonus(deposit_a, deposit_b, reserve_a, reserve_b)
+            .ok_or(SwapError::CalculationFailure)?;
+        let mint_amount = invariant
+            .compute_mint_amount_for_deposit(
+                U256::from(deposit_a)
+                    .checked_add(bonus_a.into())
+                    .ok_or(SwapError::CalculationFailure)?,
+                U256::from(deposit_b)
+                    .checked_add(bonus_b.into())
+                    .ok_or(SwapError::CalculationFailure)?,
+                U256::from(reserve_a),
+                U256::from(reserve_b),
+                U256::from(pool_mint.supply),
+                &token_swap.fees,
+            )
+            .ok_or(SwapError::CalculationFailure)?;
+        let mint_amount = U256::to_u64(mint_amount)?;
+        if mint_amount < token_swap.min_lp_mint.max(1) {
+            return Err(SwapError::MintAmountTooSmall.into());
+        }
+        if mint_amount < min_mint_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        token_swap.fee_buffer_a -= bonus_a;
+        token_swap.fee_buffer_b -= bonus_b;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        log_trace!(
+            "token_amount: {}, mint_amount: {}, bonus_a: {}, bonus_b: {}",
+            token_amount,
+            mint_amount,
+            bonus_a,
+            bonus_b
+        );
+
+        Self::user_token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            base_mint_info,
+            base_token_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            token_amount,
+        )?;
+        Self::pool_token_mint_to(
+            token_swap.lp_soulbound,
+            swap_info.key,
+            token_program_info.clone(),
+            pool_mint_info.clone(),
+            dest_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            mint_amount,
+        )?;
+        Self::update_global_stats_best_effort(program_id, trailing_infos, |global_stats| {
+            global_stats.add_value(token_amount)
+        })
+    }
+
+    /// Processes an [Withdraw](enum.Instruction.html).
+    pub fn process_withdraw(
+        program_id: &Pubkey,
+        pool_token_amount: u64,
+        minimum_token_a_amount: u64,
+        minimum_token_b_amount: u64,
+        deadline_slot: u64,
+        expected_sequence: Option<u64>,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        Self::withdraw(
+            program_id,
+            pool_token_amount,
+            minimum_token_a_amount,
+            minimum_token_b_amount,
+            deadline_slot,
+            expected_sequence,
+            false,
+            accounts,
+        )
+    }
+
+    /// Withdraws, burning pool tokens of SOURCE. Only the instructions spending pool
+    /// tokens $authority holds for the pool set `authority_source`, users never may.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn withdraw(
+        program_id: &Pubkey,
+        pool_token_amount: u64,
+        minimum_token_a_amount: u64,
+        minimum_token_b_amount: u64,
+        deadline_slot: u64,
+        expected_sequence: Option<u64>,
+        authority_source: bool,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let dest_token_a_info = next_account_info(account_info_iter)?;
+        let dest_token_b_info = next_account_info(account_info_iter)?;
+        let admin_fee_dest_a_info = next_account_info(account_info_iter)?;
+        let admin_fee_dest_b_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+        let trailing_infos = account_info_iter.as_slice();
+        let source_owner_info = account_info_iter.next();
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if !token_swap.is_at_sequence(expected_sequence) {
+            return Err(SwapError::SequenceMismatch.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        Self::check_custodian(&token_swap, pool_token_amount, trailing_infos)?;
+        if *token_a_info.key != token_swap.token_a {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *token_b_info.key != token_swap.token_b {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *pool_mint_info.key != token_swap.pool_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        if *admin_fee_dest_a_info.key != token_swap.admin_fee_key_a {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if *admin_fee_dest_b_info.key != token_swap.admin_fee_key_b {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
+        if pool_mint.supply == 0 {
+            return Err(SwapError::EmptyPool.into());
+        }
+
+        let token_a = utils::unpack_token_account(&token_a_info.data.borrow())?;
+        let token_b = utils::unpack_token_account(&token_b_info.data.borrow())?;
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        Self::check_deadline(clock.slot, deadline_slot)?;
+        let epoch = clock.epoch;
+        let (token_a_mint_info, token_a_transfer_fee) =
+            Self::transfer_mint(&token_a.mint, trailing_infos, epoch)?;
+        let (token_b_mint_info, token_b_transfer_fee) =
+            Self::transfer_mint(&token_b.mint, trailing_infos, epoch)?;
+
+        let converter = PoolTokenConverter {
+            supply: U256::from(pool_mint.supply),
+            token_a: U256::from(
+                token_swap
+                    .pool_amount(token_a_info.key, token_a.amount)
+                    .ok_or(SwapError::CalculationFailure)?,
+            ),
+            token_b: U256::from(
+                token_swap
+                    .pool_amount(token_b_info.key, token_b.amount)
+                    .ok_or(SwapError::CalculationFailure)?,
+            ),
+            fees: &token_swap.fees,
+        };
+        let pool_token_amount_u256 = U256::from(pool_token_amount);
+        let (a_amount_u256, a_admin_fee_u256) = converter
+            .token_a_rate(pool_token_amount_u256)
+            .ok_or(SwapError::CalculationFailure)?;
+        let (a_amount, a_admin_fee) = (
+            U256::to_u64(a_amount_u256)?,
+            U256::to_u64(a_admin_fee_u256)?,
+        );
+        if token_a_transfer_fee
+            .post_fee_amount(a_amount)
+            .ok_or(SwapError::CalculationFailure)?
+            < minimum_token_a_amount
+        {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        let (b_amount_u256, b_admin_fee_u256) = converter
+            .token_b_rate(pool_token_amount_u256)
+            .ok_or(SwapError::CalculationFailure)?;
+        let (b_amount, b_admin_fee) = (
+            U256::to_u64(b_amount_u256)?,
+            U256::to_u64(b_admin_fee_u256)?,
+        );
+        if token_b_transfer_fee
+            .post_fee_amount(b_amount)
+            .ok_or(SwapError::CalculationFailure)?
+            < minimum_token_b_amount
+        {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        log_trace!(
+            "a_amount: {}, a_admin_fee: {}, b_amount: {}, b_admin_fee: {}",
+            a_amount,
+            a_admin_fee,
+            b_amount,
+            b_admin_fee
+        );
+
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            token_a_info.clone(),
+            token_a_mint_info,
+            dest_token_a_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            a_amount,
+        )?;
+        Self::admin_fee_transfer(
+            &mut token_swap,
+            swap_info.key,
+            token_program_info.clone(),
+            token_a_info.clone(),
+            token_a_mint_info,
+            admin_fee_dest_a_info.clone(),
+            authority_info.clone(),
+            a_admin_fee,
+        )?;
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            token_b_info.clone(),
+            token_b_mint_info,
+            dest_token_b_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            b_amount,
+        )?;
+        Self::admin_fee_transfer(
+            &mut token_swap,
+            swap_info.key,
+            token_program_info.clone(),
+            token_b_info.clone(),
+            token_b_mint_info,
+            admin_fee_dest_b_info.clone(),
+            authority_info.clone(),
+            b_admin_fee,
+        )?;
+        if !authority_source {
+            Self::check_user_source(source_info, authority_info)?;
+        }
+        Self::pool_token_burn(
+            token_swap.lp_soulbound,
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            pool_mint_info.clone(),
+            authority_info.clone(),
+            source_owner_info,
+            token_swap.nonce,
+            pool_token_amount,
+        )?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Self::update_global_stats_best_effort(program_id, trailing_infos, |global_stats| {
+            global_stats.remove_value(a_amount);
+            global_stats.remove_value(b_amount);
+        })
+    }
+
+    /// Processes a [BeginWithdraw](enum.Instruction.html).
+    pub fn process_begin_withdraw(
+        program_id: &Pubkey,
+        pool_token_amount: u64,
+        minimum_token_a_amount: u64,
+        minimum_token_b_amount: u64,
+        nonce: u8,
+        expected_sequence: Option<u64>,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let dest_token_a_info = next_account_info(account_info_iter)?;
+        let dest_token_b_info = next_account_info(account_info_iter)?;
+        let withdraw_continuation_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+        let trailing_infos = account_info_iter.as_slice();
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if !token_swap.is_at_sequence(expected_sequence) {
+            return Err(SwapError::SequenceMismatch.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        Self::check_custodian(&token_swap, pool_token_amount, trailing_infos)?;
+        if !owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if *token_a_info.key != token_swap.token_a {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *token_b_info.key != token_swap.token_b {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *pool_mint_info.key != token_swap.pool_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        let token_a = utils::unpack_token_account(&token_a_info.data.borrow())?;
+        let token_b = utils::unpack_token_account(&token_b_info.data.borrow())?;
+        // The destinations are paid later on, a wrong mint would lock the tokens owed
+        let dest_token_a = utils::unpack_token_account(&dest_token_a_info.data.borrow())?;
+        let dest_token_b = utils::unpack_token_account(&dest_token_b_info.data.borrow())?;
+        if dest_token_a.mint != token_a.mint || dest_token_b.mint != token_b.mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        let epoch = Clock::from_account_info(clock_sysvar_info)?.epoch;
+        let (_, token_a_transfer_fee) = Self::transfer_mint(&token_a.mint, trailing_infos, epoch)?;
+        let (_, token_b_transfer_fee) = Self::transfer_mint(&token_b.mint, trailing_infos, epoch)?;
+
+        let withdraw_continuation_signature_seeds = [
+            WITHDRAW_CONTINUATION_SEED,
+            swap_info.key.as_ref(),
+            owner_info.key.as_ref(),
+            &[nonce],
+        ];
+        let withdraw_continuation_key =
+            Pubkey::create_program_address(&withdraw_continuation_signature_seeds, program_id)
+                .or(Err(SwapError::InvalidProgramAddress))?;
+        if *withdraw_continuation_info.key != withdraw_continuation_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if withdraw_continuation_info.lamports() != 0 {
+            return Err(SwapError::AlreadyInUse.into());
+        }
+
+        let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
+        if pool_mint.supply == 0 {
+            return Err(SwapError::EmptyPool.into());
+        }
+        let converter = PoolTokenConverter {
+            supply: U256::from(pool_mint.supply),
+            token_a: U256::from(
+                token_swap
+                    .pool_amount(token_a_info.key, token_a.amount)
+                    .ok_or(SwapError::CalculationFailure)?,
+            ),
+            token_b: U256::from(
+                token_swap
+                    .pool_amount(token_b_info.key, token_b.amount)
+                    .ok_or(SwapError::CalculationFailure)?,
+            ),
+            fees: &token_swap.fees,
+        };
+        let pool_token_amount_u256 = U256::from(pool_token_amount);
+        let (a_amount_u256, a_admin_fee_u256) = converter
+            .token_a_rate(pool_token_amount_u256)
+            .ok_or(SwapError::CalculationFailure)?;
+        let (a_amount, a_admin_fee) = (
+            U256::to_u64(a_amount_u256)?,
+            U256::to_u64(a_admin_fee_u256)?,
+        );
+        if token_a_transfer_fee
+            .post_fee_amount(a_amount)
+            .ok_or(SwapError::CalculationFailure)?
+            < minimum_token_a_amount
+        {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        let (b_amount_u256, b_admin_fee_u256) = converter
+            .token_b_rate(pool_token_amount_u256)
+            .ok_or(SwapError::CalculationFailure)?;
+        let (b_amount, b_admin_fee) = (
+            U256::to_u64(b_amount_u256)?,
+            U256::to_u64(b_admin_fee_u256)?,
+        );
+        if token_b_transfer_fee
+            .post_fee_amount(b_amount)
+            .ok_or(SwapError::CalculationFailure)?
+            < minimum_token_b_amount
+        {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        let pending_a = a_amount
+            .checked_add(a_admin_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        let pending_b = b_amount
+            .checked_add(b_admin_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        if pending_a == 0 && pending_b == 0 {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        Self::check_user_source(source_info, authority_info)?;
+        Self::pool_token_burn(
+            token_swap.lp_soulbound,
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            pool_mint_info.clone(),
+            authority_info.clone(),
+            Some(owner_info),
+            token_swap.nonce,
+            pool_token_amount,
+        )?;
+
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                owner_info.key,
+                withdraw_continuation_info.key,
+                rent.minimum_balance(WithdrawContinuation::LEN),
+                WithdrawContinuation::LEN as u64,
+                program_id,
+            ),
+            &[
+                owner_info.clone(),
+                withdraw_continuation_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&withdraw_continuation_signature_seeds],
+        )?;
+        let withdraw_continuation = WithdrawContinuation {
+            is_initialized: true,
+            swap: *swap_info.key,
+            owner: *owner_info.key,
+            destination_a: *dest_token_a_info.key,
+            destination_b: *dest_token_b_info.key,
+            token_a_amount: a_amount,
+            token_a_admin_fee: a_admin_fee,
+            token_b_amount: b_amount,
+            token_b_admin_fee: b_admin_fee,
+        };
+        WithdrawContinuation::pack(
+            withdraw_continuation,
+            &mut withdraw_continuation_info.data.borrow_mut(),
+        )?;
+
+        token_swap.pending_withdraw_a = token_swap
+            .pending_withdraw_a
+            .checked_add(pending_a)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.pending_withdraw_b = token_swap
+            .pending_withdraw_b
+            .checked_add(pending_b)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [ContinueWithdraw](enum.Instruction.html).
+    pub fn process_continue_withdraw(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let withdraw_continuation_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let swap_token_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let admin_fee_destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if withdraw_continuation_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut withdraw_continuation =
+            WithdrawContinuation::unpack(&withdraw_continuation_info.data.borrow())?;
+        if withdraw_continuation.swap != *swap_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if withdraw_continuation.owner != *owner_info.key {
+            return Err(SwapError::Unauthorized.into());
+        }
+
+        let (destination, admin_fee_destination, amount, admin_fee) =
+            if *swap_token_info.key == token_swap.token_a {
+                (
+                    withdraw_continuation.destination_a,
+                    token_swap.admin_fee_key_a,
+                    &mut withdraw_continuation.token_a_amount,
+                    &mut withdraw_continuation.token_a_admin_fee,
+                )
+            } else if *swap_token_info.key == token_swap.token_b {
+                (
+                    withdraw_continuation.destination_b,
+                    token_swap.admin_fee_key_b,
+                    &mut withdraw_continuation.token_b_amount,
+                    &mut withdraw_continuation.token_b_admin_fee,
+                )
+            } else {
+                return Err(SwapError::IncorrectSwapAccount.into());
+            };
+        if *destination_info.key != destination {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if *admin_fee_destination_info.key != admin_fee_destination {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if *amount == 0 && *admin_fee == 0 {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            swap_token_info.clone(),
+            Self::token_mint(swap_token_info, accounts)?,
+            destination_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            *amount,
+        )?;
+        Self::admin_fee_transfer(
+            &mut token_swap,
+            swap_info.key,
+            token_program_info.clone(),
+            swap_token_info.clone(),
+            Self::token_mint(swap_token_info, accounts)?,
+            admin_fee_destination_info.clone(),
+            authority_info.clone(),
+            *admin_fee,
+        )?;
+        let pending = if *swap_token_info.key == token_swap.token_a {
+            &mut token_swap.pending_withdraw_a
+        } else {
+            &mut token_swap.pending_withdraw_b
+        };
+        *pending = pending
+            .checked_sub(*amount)
+            .and_then(|pending| pending.checked_sub(*admin_fee))
+            .ok_or(SwapError::CalculationFailure)?;
+        *amount = 0;
+        *admin_fee = 0;
+
+        if withdraw_continuation.token_a_amount == 0
+            && withdraw_continuation.token_a_admin_fee == 0
+            && withdraw_continuation.token_b_amount == 0
+            && withdraw_continuation.token_b_admin_fee == 0
+        {
+            let owner_lamports = owner_info
+                .lamports()
+                .checked_add(withdraw_continuation_info.lamports())
+                .ok_or(SwapError::CalculationFailure)?;
+            **owner_info.lamports.borrow_mut() = owner_lamports;
+            **withdraw_continuation_info.lamports.borrow_mut() = 0;
+            withdraw_continuation_info.data.borrow_mut().fill(0);
+        } else {
+            WithdrawContinuation::pack(
+                withdraw_continuation,
+                &mut withdraw_continuation_info.data.borrow_mut(),
+            )?;
+        }
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes an [EmitPoolSummary](enum.Instruction.html).
+    pub fn process_emit_pool_summary(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if *token_a_info.key != token_swap.token_a {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *token_b_info.key != token_swap.token_b {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *pool_mint_info.key != token_swap.pool_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        token_swap.settle_order_flow_auction(clock.slot);
+        let token_a = utils::unpack_token_account(&token_a_info.data.borrow())?;
+        let token_b = utils::unpack_token_account(&token_b_info.data.borrow())?;
+        let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
+        let reserve_a = token_swap
+            .pool_amount(token_a_info.key, token_a.amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        let reserve_b = token_swap
+            .pool_amount(token_b_info.key, token_b.amount)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        let invariant = token_swap.curve(clock.unix_timestamp);
+        let virtual_price = invariant
+            .compute_virtual_price(
+                U256::from(reserve_a),
+                U256::from(reserve_b),
+                U256::from(pool_mint.supply),
+            )
+            .ok_or(SwapError::CalculationFailure)?;
+
+        let summary = PoolSummary {
+            swap: *swap_info.key,
+            reserve_a,
+            reserve_b,
+            lp_supply: pool_mint.supply,
+            virtual_price,
+            admin_fees_a: token_swap.admin_fees_accrued_a,
+            admin_fees_b: token_swap.admin_fees_accrued_b,
+            dev_fees_a: token_swap
+                .dev_fees_received_a
+                .checked_sub(token_swap.dev_fees_claimed_a)
+                .ok_or(SwapError::CalculationFailure)?,
+            dev_fees_b: token_swap
+                .dev_fees_received_b
+                .checked_sub(token_swap.dev_fees_claimed_b)
+                .ok_or(SwapError::CalculationFailure)?,
+            config_hash: token_swap.config_hash,
+        };
+        log_event!("{}", summary);
+        Ok(())
+    }
+
+    /// Processes a [GetPoolStatus](enum.Instruction.html).
+    pub fn process_get_pool_status(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        log_event!(
+            "{}",
+            PoolStatus::new(*swap_info.key, &token_swap, clock.slot)
+        );
+        Ok(())
+    }
+
+    /// Processes a [CheckFeeDestinations](enum.Instruction.html).
+    pub fn process_check_fee_destinations(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let admin_fee_a_info = next_account_info(account_info_iter)?;
+        let admin_fee_b_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if *admin_fee_a_info.key != token_swap.admin_fee_key_a {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if *admin_fee_b_info.key != token_swap.admin_fee_key_b {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+
+        let mut changed = false;
+        for (admin_fee_info, mint, escrow) in [
+            (
+                admin_fee_a_info,
+                token_swap.token_a_mint,
+                &mut token_swap.admin_fee_escrow_a,
+            ),
+            (
+                admin_fee_b_info,
+                token_swap.token_b_mint,
+                &mut token_swap.admin_fee_escrow_b,
+            ),
+        ] {
+            let fault = FeeDestinationFault::check(admin_fee_info, &mint);
+            if let Some(fault) = fault {
+                let alert = FeeDestinationAlert {
+                    swap: *swap_info.key,
+                    mint,
+                    destination: *admin_fee_info.key,
+                    fault,
+                };
+                log_event!("{}", alert);
+            }
+            changed |= *escrow != fault.is_some();
+            *escrow = fault.is_some();
+        }
+        if changed {
+            token_swap.bump_sequence();
+            SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        }
+        Ok(())
+    }
+
+    /// Processes a [CreateTwapOrder](enum.Instruction.html).
+    pub fn process_create_twap_order(
+        program_id: &Pubkey,
+        data: &CreateTwapOrderData,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let twap_order_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::IsPaused.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if !owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if data.slice_count == 0
+            || data.slice_interval_slots == 0
+            || data.amount_in < data.slice_count.into()
+            || data.max_price_deviation_bps > MAX_TWAP_PRICE_DEVIATION_BPS
+        {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let order_id_bytes = data.order_id.to_le_bytes();
+        let order_signature_seeds = [
+            TWAP_ORDER_SEED,
+            swap_info.key.as_ref(),
+            owner_info.key.as_ref(),
+            &order_id_bytes,
+            &[data.order_nonce],
+        ];
+        let twap_order_key = Pubkey::create_program_address(&order_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+        if *twap_order_info.key != twap_order_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+
+        let escrow = utils::unpack_token_account(&escrow_info.data.borrow())?;
+        let destination = utils::unpack_token_account(&destination_info.data.borrow())?;
+        let (price_cumulative, destination_mint) = if escrow.mint == token_swap.token_a_mint {
+            (token_swap.price_a_cumulative, token_swap.token_b_mint)
+        } else if escrow.mint == token_swap.token_b_mint {
+            (token_swap.price_b_cumulative, token_swap.token_a_mint)
+        } else {
+            return Err(SwapError::IncorrectMint.into());
+        };
+        if destination.mint != destination_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        if escrow.owner != twap_order_key {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if escrow.delegate.is_some() {
+            return Err(SwapError::InvalidDelegate.into());
+        }
+        if escrow.close_authority.is_some() {
+            return Err(SwapError::InvalidCloseAuthority.into());
+        }
+
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                owner_info.key,
+                twap_order_info.key,
+                rent.minimum_balance(TwapOrder::LEN),
+                TwapOrder::LEN as u64,
+                program_id,
+            ),
+            &[
+                owner_info.clone(),
+                twap_order_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&order_signature_seeds],
+        )?;
+        Self::user_token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            Self::token_mint(source_info, accounts)?,
+            escrow_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            data.amount_in,
+        )?;
+
+        // The TWAP of the first slice is measured from the last price observation
+        // of the pool
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        let order = TwapOrder {
+            is_initialized: true,
+            nonce: data.order_nonce,
+            swap: *swap_info.key,
+            owner: *owner_info.key,
+            order_id: data.order_id,
+            escrow: *escrow_info.key,
+            destination: *destination_info.key,
+            amount_in: data.amount_in,
+            slice_count: data.slice_count,
+            slices_executed: 0,
+            slice_interval_slots: data.slice_interval_slots,
+            max_price_deviation_bps: data.max_price_deviation_bps,
+            last_slice_slot: clock.slot,
+            price_cumulative,
+            price_cumulative_slot: token_swap.price_cumulative_slot,
+            amount_out: 0,
+        };
+        TwapOrder::pack(order, &mut twap_order_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes an [ExecuteTwapSlice](enum.Instruction.html).
+    pub fn process_execute_twap_slice(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let twap_order_info = next_account_info(account_info_iter)?;
+        let swap_accounts = account_info_iter.as_slice();
+        // Swap accounts, validated by the swap
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let swap_source_info = next_account_info(account_info_iter)?;
+        let _swap_destination_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = swap_accounts
+            .get(10)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        if twap_order_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut order = TwapOrder::unpack(&twap_order_info.data.borrow())?;
+        if order.swap != *swap_info.key
+            || order.escrow != *escrow_info.key
+            || order.destination != *destination_info.key
+        {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let amount_in = order
+            .next_slice_amount()
+            .ok_or(SwapError::TwapSliceNotDue)?;
+
+        // The escrow lets $authority transfer the slice for the swap only
+        let order_id_bytes = order.order_id.to_le_bytes();
+        let order_signature_seeds = [
+            TWAP_ORDER_SEED,
+            order.swap.as_ref(),
+            order.owner.as_ref(),
+            &order_id_bytes,
+            &[order.nonce],
+        ];
+        invoke_signed(
+            &token_2022::instruction(
+                spl_token::instruction::approve(
+                    &spl_token::id(),
+                    escrow_info.key,
+                    authority_info.key,
+                    twap_order_info.key,
+                    &[],
+                    amount_in,
+                ),
+                token_program_info.key,
+            )?,
+            &[
+                escrow_info.clone(),
+                authority_info.clone(),
+                twap_order_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&order_signature_seeds],
+        )?;
+        let destination_amount =
+            utils::unpack_token_account(&destination_info.data.borrow())?.amount;
+        let (_result, slot) = Self::swap(
+            program_id,
+            SwapAmount::ExactIn {
+                amount_in,
+                minimum_amount_out: 0,
+            },
+            None,
+            0,
+            swap_accounts,
+        )?;
+        if slot
+            < order
+                .next_slice_slot()
+                .ok_or(SwapError::CalculationFailure)?
+        {
+            return Err(SwapError::TwapSliceNotDue.into());
+        }
+        let amount_out = utils::unpack_token_account(&destination_info.data.borrow())?
+            .amount
+            .checked_sub(destination_amount)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        // The swap accumulated the pool prices up to the current slot
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        let price_cumulative = if *swap_source_info.key == token_swap.token_a {
+            token_swap.price_a_cumulative
+        } else {
+            token_swap.price_b_cumulative
+        };
+        let elapsed_slots = token_swap
+            .price_cumulative_slot
+            .checked_sub(order.price_cumulative_slot)
+            .ok_or(SwapError::CalculationFailure)?;
+        let twap_price = price_cumulative
+            .wrapping_sub(order.price_cumulative)
+            .checked_div(elapsed_slots.into())
+            .ok_or(SwapError::CalculationFailure)?;
+        let minimum_amount_out = order
+            .minimum_slice_amount_out(amount_in, twap_price)
+            .ok_or(SwapError::CalculationFailure)?;
+        if amount_out < minimum_amount_out {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+
+        order.slices_executed += 1;
+        order.last_slice_slot = slot;
+        order.price_cumulative = price_cumulative;
+        order.price_cumulative_slot = token_swap.price_cumulative_slot;
+        order.amount_out = order
+            .amount_out
+            .checked_add(amount_out)
+            .ok_or(SwapError::CalculationFailure)?;
+        TwapOrder::pack(order, &mut twap_order_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [CancelTwapOrder](enum.Instruction.html).
+    pub fn process_cancel_twap_order(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let twap_order_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let refund_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if twap_order_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let order = TwapOrder::unpack(&twap_order_info.data.borrow())?;
+        if !owner_info.is_signer || order.owner != *owner_info.key {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if order.escrow != *escrow_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        let order_id_bytes = order.order_id.to_le_bytes();
+        let order_signature_seeds = [
+            TWAP_ORDER_SEED,
+            order.swap.as_ref(),
+            order.owner.as_ref(),
+            &order_id_bytes,
+            &[order.nonce],
+        ];
+        Self::refund_escrow(
+            twap_order_info,
+            escrow_info,
+            refund_info,
+            token_program_info,
+            &order_signature_seeds,
+            accounts,
+        )?;
+        Self::close_escrow_order(
+            twap_order_info,
+            &[escrow_info],
+            owner_info,
+            token_program_info,
+            &order_signature_seeds,
+        )
+    }
+
+    /// Transfers the whole balance of the escrow of `order_info`, the program address
+    /// of `order_signature_seeds`, to `refund_info`. The transfer is checked against
+    /// the escrow mint when it is among `accounts`.
+    fn refund_escrow<'a>(
+        order_info: &AccountInfo<'a>,
+        escrow_info: &AccountInfo<'a>,
+        refund_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+        order_signature_seeds: &[&[u8]],
+        accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        let escrow_amount = utils::unpack_token_account(&escrow_info.data.borrow())?.amount;
+        if escrow_amount == 0 {
+            return Ok(());
+        }
+        let mint_info = Self::token_mint(escrow_info, accounts)?;
+        let ix = match mint_info {
+            Some(mint_info) => spl_token::instruction::transfer_checked(
+                &spl_token::id(),
+                escrow_info.key,
+                mint_info.key,
+                refund_info.key,
+                order_info.key,
+                &[],
+                escrow_amount,
+                Self::unpack_mint(&mint_info.data.borrow())?.decimals,
+            ),
+            None => spl_token::instruction::transfer(
+                &spl_token::id(),
+                escrow_info.key,
+                refund_info.key,
+                order_info.key,
+                &[],
+                escrow_amount,
+            ),
+        };
+        let ix = token_2022::instruction(ix, token_program_info.key)?;
+        #[cfg(feature = "shadow-ledger")]
+        crate::ledger::record_transfer(escrow_info.key, refund_info.key, escrow_amount);
+
+        let mut account_infos = vec![
+            escrow_info.clone(),
+            refund_info.clone(),
+            order_info.clone(),
+            token_program_info.clone(),
+        ];
+        account_infos.extend(mint_info.cloned());
+        invoke_signed(&ix, &account_infos, &[order_signature_seeds])
+    }
+
+    /// Closes the emptied escrows of `order_info`, the program address of
+    /// `order_signature_seeds`, then `order_info`, returning their lamports to
+    /// `owner_info`
+    fn close_escrow_order<'a>(
+        order_info: &AccountInfo<'a>,
+        escrow_infos: &[&AccountInfo<'a>],
+        owner_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+        order_signature_seeds: &[&[u8]],
+    ) -> ProgramResult {
+        for escrow_info in escrow_infos {
+            invoke_signed(
+                &token_2022::instruction(
+                    spl_token::instruction::close_account(
+                        &spl_token::id(),
+                        escrow_info.key,
+                        owner_info.key,
+                        order_info.key,
+                        &[],
+                    ),
+                    token_program_info.key,
+                )?,
+                &[
+                    (*escrow_info).clone(),
+                    owner_info.clone(),
+                    order_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[order_signature_seeds],
+            )?;
+        }
+
+        let owner_lamports = owner_info
+            .lamports()
+            .checked_add(order_info.lamports())
+            .ok_or(SwapError::CalculationFailure)?;
+        **owner_info.lamports.borrow_mut() = owner_lamports;
+        **order_info.lamports.borrow_mut() = 0;
+        order_info.data.borrow_mut().fill(0);
+        Ok(())
+    }
+
+    /// Processes a [CreateTrigger](enum.Instruction.html).
+    pub fn process_create_trigger(
+        program_id: &Pubkey,
+        data: &CreateTriggerData,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let oracle_info = next_account_info(account_info_iter)?;
+        let trigger_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id || oracle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        SwapInfo::unpack(&oracle_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::IsPaused.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if !owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if data.amount_in == 0 {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let trigger_id_bytes = data.trigger_id.to_le_bytes();
+        let trigger_signature_seeds = [
+            TRIGGER_SEED,
+            swap_info.key.as_ref(),
+            owner_info.key.as_ref(),
+            &trigger_id_bytes,
+            &[data.trigger_nonce],
+        ];
+        let trigger_key = Pubkey::create_program_address(&trigger_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+        if *trigger_info.key != trigger_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+
+        let escrow = utils::unpack_token_account(&escrow_info.data.borrow())?;
+        let destination = utils::unpack_token_account(&destination_info.data.borrow())?;
+        let destination_mint = if escrow.mint == token_swap.token_a_mint {
+            token_swap.token_b_mint
+        } else if escrow.mint == token_swap.token_b_mint {
+            token_swap.token_a_mint
+        } else {
+            return Err(SwapError::IncorrectMint.into());
+        };
+        if destination.mint != destination_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        if escrow.owner != trigger_key {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if escrow.delegate.is_some() {
+            return Err(SwapError::InvalidDelegate.into());
+        }
+        if escrow.close_authority.is_some() {
+            return Err(SwapError::InvalidCloseAuthority.into());
+        }
+
+        // The bounty is held in the trigger account on top of its rent
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        let lamports = rent
+            .minimum_balance(TriggerOrder::LEN)
+            .checked_add(data.bounty)
+            .ok_or(SwapError::CalculationFailure)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                owner_info.key,
+                trigger_info.key,
+                lamports,
+                TriggerOrder::LEN as u64,
+                program_id,
+            ),
+            &[
+                owner_info.clone(),
+                trigger_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&trigger_signature_seeds],
+        )?;
+        Self::user_token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            Self::token_mint(source_info, accounts)?,
+            escrow_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            data.amount_in,
+        )?;
+
+        let trigger = TriggerOrder {
+            is_initialized: true,
+            nonce: data.trigger_nonce,
+            swap: *swap_info.key,
+            owner: *owner_info.key,
+            trigger_id: data.trigger_id,
+            escrow: *escrow_info.key,
+            destination: *destination_info.key,
+            oracle: *oracle_info.key,
+            minimum_amount_out: data.minimum_amount_out,
+            trigger_price: data.trigger_price,
+            trigger_above: data.trigger_above,
+            bounty: data.bounty,
+        };
+        TriggerOrder::pack(trigger, &mut trigger_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes an [ExecuteTrigger](enum.Instruction.html).
+    pub fn process_execute_trigger(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let trigger_info = next_account_info(account_info_iter)?;
+        let oracle_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let cranker_info = next_account_info(account_info_iter)?;
+        let swap_accounts = account_info_iter.as_slice();
+        // Swap accounts, validated by the swap
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let _swap_source_info = next_account_info(account_info_iter)?;
+        let _swap_destination_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = swap_accounts
+            .get(10)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let clock_sysvar_info = swap_accounts
+            .get(11)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+        if trigger_info.owner != program_id || oracle_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let trigger = TriggerOrder::unpack(&trigger_info.data.borrow())?;
+        if trigger.swap != *swap_info.key
+            || trigger.escrow != *escrow_info.key
+            || trigger.destination != *destination_info.key
+            || trigger.oracle != *oracle_info.key
+            || trigger.owner != *owner_info.key
+        {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        // The oracle is read before the swap, which can't move it
+        let oracle = SwapInfo::unpack(&oracle_info.data.borrow())?;
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        if oracle.twap_price == 0 || clock.slot.saturating_sub(oracle.twap_slot) > TWAP_WINDOW_SLOTS
+        {
+            return Err(SwapError::OracleStale.into());
+        }
+        if !trigger.is_triggered(oracle.twap_price) {
+            return Err(SwapError::TriggerNotReached.into());
+        }
+
+        // The escrow lets $authority transfer its balance for the swap only
+        let trigger_id_bytes = trigger.trigger_id.to_le_bytes();
+        let trigger_signature_seeds = [
+            TRIGGER_SEED,
+            trigger.swap.as_ref(),
+            trigger.owner.as_ref(),
+            &trigger_id_bytes,
+            &[trigger.nonce],
+        ];
+        let amount_in = utils::unpack_token_account(&escrow_info.data.borrow())?.amount;
+        invoke_signed(
+            &token_2022::instruction(
+                spl_token::instruction::approve(
+                    &spl_token::id(),
+                    escrow_info.key,
+                    authority_info.key,
+                    trigger_info.key,
+                    &[],
+                    amount_in,
+                ),
+                token_program_info.key,
+            )?,
+            &[
+                escrow_info.clone(),
+                authority_info.clone(),
+                trigger_info.clone(),
+                token_program_info.clone(),
+            ],
+            &[&trigger_signature_seeds],
+        )?;
+        Self::swap(
+            program_id,
+            SwapAmount::ExactIn {
+                amount_in,
+                minimum_amount_out: trigger.minimum_amount_out,
+            },
+            None,
+            0,
+            swap_accounts,
+        )?;
+
+        let cranker_lamports = cranker_info
+            .lamports()
+            .checked_add(trigger.bounty)
+            .ok_or(SwapError::CalculationFailure)?;
+        let trigger_lamports = trigger_info
+            .lamports()
+            .checked_sub(trigger.bounty)
+            .ok_or(SwapError::CalculationFailure)?;
+        **cranker_info.lamports.borrow_mut() = cranker_lamports;
+        **trigger_info.lamports.borrow_mut() = trigger_lamports;
+        Self::close_escrow_order(
+            trigger_info,
+            &[escrow_info],
+            owner_info,
+            token_program_info,
+            &trigger_signature_seeds,
+        )
+    }
+
+    /// Processes a [CancelTrigger](enum.Instruction.html).
+    pub fn process_cancel_trigger(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let trigger_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let escrow_info = next_account_info(account_info_iter)?;
+        let refund_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if trigger_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let trigger = TriggerOrder::unpack(&trigger_info.data.borrow())?;
+        if !owner_info.is_signer || trigger.owner != *owner_info.key {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if trigger.escrow != *escrow_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        let trigger_id_bytes = trigger.trigger_id.to_le_bytes();
+        let trigger_signature_seeds = [
+            TRIGGER_SEED,
+            trigger.swap.as_ref(),
+            trigger.owner.as_ref(),
+            &trigger_id_bytes,
+            &[trigger.nonce],
+        ];
+        Self::refund_escrow(
+            trigger_info,
+            escrow_info,
+            refund_info,
+            token_program_info,
+            &trigger_signature_seeds,
+            accounts,
+        )?;
+        Self::close_escrow_order(
+            trigger_info,
+            &[escrow_info],
+            owner_info,
+            token_program_info,
+            &trigger_signature_seeds,
+        )
+    }
+
+    /// Processes a [CreateDripDeposit](enum.Instruction.html).
+    pub fn process_create_drip_deposit(
+        program_id: &Pubkey,
+        data: &CreateDripDepositData,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let source_a_info = next_account_info(account_info_iter)?;
+        let source_b_info = next_account_info(account_info_iter)?;
+        let escrow_a_info = next_account_info(account_info_iter)?;
+        let escrow_b_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let drip_deposit_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::IsPaused.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if !owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if data.tranche_count == 0
+            || data.tranche_interval_slots == 0
+            || (data.amount_a == 0 && data.amount_b == 0)
+        {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let drip_id_bytes = data.drip_id.to_le_bytes();
+        let drip_signature_seeds = [
+            DRIP_DEPOSIT_SEED,
+            swap_info.key.as_ref(),
+            owner_info.key.as_ref(),
+            &drip_id_bytes,
+            &[data.drip_nonce],
+        ];
+        let drip_deposit_key = Pubkey::create_program_address(&drip_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+        if *drip_deposit_info.key != drip_deposit_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+
+        for (escrow_info, mint) in [
+            (escrow_a_info, &token_swap.token_a_mint),
+            (escrow_b_info, &token_swap.token_b_mint),
+        ] {
+            let escrow = utils::unpack_token_account(&escrow_info.data.borrow())?;
+            if escrow.mint != *mint {
+                return Err(SwapError::IncorrectMint.into());
+            }
+            if escrow.owner != drip_deposit_key {
+                return Err(SwapError::InvalidOwner.into());
+            }
+            if escrow.delegate.is_some() {
+                return Err(SwapError::InvalidDelegate.into());
+            }
+            if escrow.close_authority.is_some() {
+                return Err(SwapError::InvalidCloseAuthority.into());
+            }
+        }
+        let destination = utils::unpack_token_account(&destination_info.data.borrow())?;
+        if destination.mint != token_swap.pool_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                owner_info.key,
+                drip_deposit_info.key,
+                rent.minimum_balance(DripDeposit::LEN),
+                DripDeposit::LEN as u64,
+                program_id,
+            ),
+            &[
+                owner_info.clone(),
+                drip_deposit_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&drip_signature_seeds],
+        )?;
+        for (source_info, escrow_info, amount) in [
+            (source_a_info, escrow_a_info, data.amount_a),
+            (source_b_info, escrow_b_info, data.amount_b),
+        ] {
+            if amount != 0 {
+                Self::user_token_transfer(
+                    swap_info.key,
+                    token_program_info.clone(),
+                    source_info.clone(),
+                    Self::token_mint(source_info, accounts)?,
+                    escrow_info.clone(),
+                    authority_info.clone(),
+                    token_swap.nonce,
+                    amount,
+                )?;
+            }
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        let drip = DripDeposit {
+            is_initialized: true,
+            nonce: data.drip_nonce,
+            swap: *swap_info.key,
+            owner: *owner_info.key,
+            drip_id: data.drip_id,
+            escrow_a: *escrow_a_info.key,
+            escrow_b: *escrow_b_info.key,
+            destination: *destination_info.key,
+            amount_a: data.amount_a,
+            amount_b: data.amount_b,
+            tranche_count: data.tranche_count,
+            tranches_deposited: 0,
+            tranche_interval_slots: data.tranche_interval_slots,
+            last_tranche_slot: clock.slot,
+        };
+        DripDeposit::pack(drip, &mut drip_deposit_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [DepositDripTranche](enum.Instruction.html).
+    pub fn process_deposit_drip_tranche(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let drip_deposit_info = next_account_info(account_info_iter)?;
+        let deposit_accounts = account_info_iter.as_slice();
+        // Deposit accounts, validated by the deposit
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let escrow_a_info = next_account_info(account_info_iter)?;
+        let escrow_b_info = next_account_info(account_info_iter)?;
+        let _token_a_info = next_account_info(account_info_iter)?;
+        let _token_b_info = next_account_info(account_info_iter)?;
+        let _pool_mint_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        if drip_deposit_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut drip = DripDeposit::unpack(&drip_deposit_info.data.borrow())?;
+        if drip.swap != *swap_info.key
+            || drip.escrow_a != *escrow_a_info.key
+            || drip.escrow_b != *escrow_b_info.key
+            || drip.destination != *destination_info.key
+        {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let (amount_a, amount_b) = drip
+            .next_tranche_amounts()
+            .ok_or(SwapError::DripTrancheNotDue)?;
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        if clock.slot
+            < drip
+                .next_tranche_slot()
+                .ok_or(SwapError::CalculationFailure)?
+        {
+            return Err(SwapError::DripTrancheNotDue.into());
+        }
+
+        // The escrows let $authority transfer the tranche for the deposit only, the
+        // allowance the deposit doesn't take is revoked
+        let drip_id_bytes = drip.drip_id.to_le_bytes();
+        let drip_signature_seeds = [
+            DRIP_DEPOSIT_SEED,
+            drip.swap.as_ref(),
+            drip.owner.as_ref(),
+            &drip_id_bytes,
+            &[drip.nonce],
+        ];
+        for (escrow_info, amount) in [(escrow_a_info, amount_a), (escrow_b_info, amount_b)] {
+            invoke_signed(
+                &token_2022::instruction(
+                    spl_token::instruction::approve(
+                        &spl_token::id(),
+                        escrow_info.key,
+                        authority_info.key,
+                        drip_deposit_info.key,
+                        &[],
+                        amount,
+                    ),
+                    token_program_info.key,
+                )?,
+                &[
+                    escrow_info.clone(),
+                    authority_info.clone(),
+                    drip_deposit_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[&drip_signature_seeds],
+            )?;
+        }
+        Self::process_deposit(program_id, amount_a, amount_b, 0, 0, None, deposit_accounts)?;
+        for escrow_info in [escrow_a_info, escrow_b_info] {
+            invoke_signed(
+                &token_2022::instruction(
+                    spl_token::instruction::revoke(
+                        &spl_token::id(),
+                        escrow_info.key,
+                        drip_deposit_info.key,
+                        &[],
+                    ),
+                    token_program_info.key,
+                )?,
+                &[
+                    escrow_info.clone(),
+                    drip_deposit_info.clone(),
+                    token_program_info.clone(),
+                ],
+                &[&drip_signature_seeds],
+            )?;
+        }
+
+        drip.tranches_deposited += 1;
+        drip.last_tranche_slot = clock.slot;
+        DripDeposit::pack(drip, &mut drip_deposit_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [CancelDripDeposit](enum.Instruction.html).
+    pub fn process_cancel_drip_deposit(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let drip_deposit_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let escrow_a_info = next_account_info(account_info_iter)?;
+        let escrow_b_info = next_account_info(account_info_iter)?;
+        let refund_a_info = next_account_info(account_info_iter)?;
+        let refund_b_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        if drip_deposit_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let drip = DripDeposit::unpack(&drip_deposit_info.data.borrow())?;
+        if !owner_info.is_signer || drip.owner != *owner_info.key {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if drip.escrow_a != *escrow_a_info.key || drip.escrow_b != *escrow_b_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        let drip_id_bytes = drip.drip_id.to_le_bytes();
+        let drip_signature_seeds = [
+            DRIP_DEPOSIT_SEED,
+            drip.swap.as_ref(),
+            drip.owner.as_ref(),
+            &drip_id_bytes,
+            &[drip.nonce],
+        ];
+        for (escrow_info, refund_info) in [
+            (escrow_a_info, refund_a_info),
+            (escrow_b_info, refund_b_info),
+        ] {
+            Self::refund_escrow(
+                drip_deposit_info,
+                escrow_info,
+                refund_info,
+                token_program_info,
+                &drip_signature_seeds,
+                accounts,
+            )?;
+        }
+        Self::close_escrow_order(
+            drip_deposit_info,
+            &[escrow_a_info, escrow_b_info],
+            owner_info,
+            token_program_info,
+            &drip_signature_seeds,
+        )
+    }
+
+    /// Processes an [InitializeGlobalStats](enum.Instruction.html).
+    pub fn process_initialize_global_stats(
+        program_id: &Pubkey,
+        nonce: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let global_stats_info = next_account_info(account_info_iter)?;
+        let payer_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+
+        let global_stats_signature_seeds = [GLOBAL_STATS_SEED, &[nonce]];
+        let global_stats_key =
+            Pubkey::create_program_address(&global_stats_signature_seeds, program_id)
+                .or(Err(SwapError::InvalidProgramAddress))?;
+        if *global_stats_info.key != global_stats_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                payer_info.key,
+                global_stats_info.key,
+                rent.minimum_balance(GlobalStats::LEN),
+                GlobalStats::LEN as u64,
+                program_id,
+            ),
+            &[
+                payer_info.clone(),
+                global_stats_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&global_stats_signature_seeds],
+        )?;
+        let global_stats = GlobalStats {
+            is_initialized: true,
+            nonce,
+            ..GlobalStats::default()
+        };
+        GlobalStats::pack(global_stats, &mut global_stats_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [CreateReferralAccrual](enum.Instruction.html).
+    pub fn process_create_referral_accrual(
+        program_id: &Pubkey,
+        nonce: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: owned(program_id),
+            referral_accrual_info: writable,
+            referrer_info: signer writable,
+            system_program_info,
+            rent_sysvar_info,
+        }
+        SwapInfo::unpack(&swap_info.data.borrow())?;
+
+        let referral_accrual_signature_seeds = [
+            REFERRAL_ACCRUAL_SEED,
+            swap_info.key.as_ref(),
+            referrer_info.key.as_ref(),
+            &[nonce],
+        ];
+        let referral_accrual_key =
+            Pubkey::create_program_address(&referral_accrual_signature_seeds, program_id)
+                .or(Err(SwapError::InvalidProgramAddress))?;
+        if *referral_accrual_info.key != referral_accrual_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                referrer_info.key,
+                referral_accrual_info.key,
+                rent.minimum_balance(ReferralAccrual::LEN),
+                ReferralAccrual::LEN as u64,
+                program_id,
+            ),
+            &[
+                referrer_info.clone(),
+                referral_accrual_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&referral_accrual_signature_seeds],
+        )?;
+        let referral_accrual = ReferralAccrual {
+            is_initialized: true,
+            nonce,
+            swap: *swap_info.key,
+            referrer: *referrer_info.key,
+            ..ReferralAccrual::default()
+        };
+        ReferralAccrual::pack(
+            referral_accrual,
+            &mut referral_accrual_info.data.borrow_mut(),
+        )?;
+        Ok(())
+    }
+
+    /// Processes a [ClaimReferralFees](enum.Instruction.html).
+    pub fn process_claim_referral_fees(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: writable owned(program_id),
+            authority_info,
+            referral_accrual_info: writable owned(program_id),
+            referrer_info: signer,
+            token_a_info: writable,
+            token_b_info: writable,
+            destination_a_info: writable,
+            destination_b_info: writable,
+            token_program_info,
+        }
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        let mut referral_accrual = ReferralAccrual::unpack(&referral_accrual_info.data.borrow())?;
+        if referral_accrual.swap != *swap_info.key {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if referral_accrual.referrer != *referrer_info.key {
+            return Err(SwapError::Unauthorized.into());
+        }
+        let referrer = referral_accrual.referrer;
+        constrain! {
+            authority_info: authority(program_id, swap_info.key, token_swap.nonce),
+            token_a_info: key(token_swap.token_a),
+            token_b_info: key(token_swap.token_b),
+            destination_a_info: token(mint = token_swap.token_a_mint, owner = referrer),
+            destination_b_info: token(mint = token_swap.token_b_mint, owner = referrer),
+        }
+
+        let (amount_a, amount_b) = referral_accrual
+            .claimable()
+            .ok_or(SwapError::CalculationFailure)?;
+        for (swap_token_info, destination_info, amount) in [
+            (token_a_info, destination_a_info, amount_a),
+            (token_b_info, destination_b_info, amount_b),
+        ] {
+            if amount == 0 {
+                continue;
+            }
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                swap_token_info.clone(),
+                Self::token_mint(swap_token_info, accounts)?,
+                destination_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                amount,
+            )?;
+        }
+
+        referral_accrual.fees_claimed_a = referral_accrual.fees_earned_a;
+        referral_accrual.fees_claimed_b = referral_accrual.fees_earned_b;
+        token_swap.referral_fees_accrued_a = token_swap
+            .referral_fees_accrued_a
+            .checked_sub(amount_a)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.referral_fees_accrued_b = token_swap
+            .referral_fees_accrued_b
+            .checked_sub(amount_b)
+            .ok_or(SwapError::CalculationFailure)?;
+        ReferralAccrual::pack(
+            referral_accrual,
+            &mut referral_accrual_info.data.borrow_mut(),
+        )?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [ReportEpochFees](enum.Instruction.html).
+    pub fn process_report_epoch_fees(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        if swap_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        if let Some(report) = token_swap.take_epoch_fee_report(swap_info.key, clock.epoch) {
+            log_event!("{}", report);
+            token_swap.bump_sequence();
+            SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        }
+        Ok(())
+    }
+
+    /// Processes an [WithdrawOne](enum.Instruction.html).
+    pub fn process_withdraw_one(
+        program_id: &Pubkey,
+        pool_token_amount: u64,
+        minimum_token_amount: u64,
+        deadline_slot: u64,
+        expected_sequence: Option<u64>,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let base_token_info = next_account_info(account_info_iter)?;
+        let quote_token_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let admin_destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+        let trailing_infos = account_info_iter.as_slice();
+        let source_owner_info = account_info_iter.next();
+
+        if *base_token_info.key == *quote_token_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::IsPaused.into());
+        }
+        if !token_swap.is_at_sequence(expected_sequence) {
+            return Err(SwapError::SequenceMismatch.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        Self::check_custodian(&token_swap, pool_token_amount, trailing_infos)?;
+        if *base_token_info.key != token_swap.token_b && *base_token_info.key != token_swap.token_a
+        {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *quote_token_info.key != token_swap.token_b
+            && *quote_token_info.key != token_swap.token_a
+        {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *base_token_info.key == token_swap.token_a
+            && *admin_destination_info.key != token_swap.admin_fee_key_a
+        {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if *base_token_info.key == token_swap.token_b
+            && *admin_destination_info.key != token_swap.admin_fee_key_b
+        {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if *pool_mint_info.key != token_swap.pool_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
+        if pool_token_amount > pool_mint.supply {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        Self::check_deadline(clock.slot, deadline_slot)?;
+        token_swap.settle_order_flow_auction(clock.slot);
+        let base_token = utils::unpack_token_account(&base_token_info.data.borrow())?;
+        let quote_token = utils::unpack_token_account(&quote_token_info.data.borrow())?;
+        let (base_mint_info, base_transfer_fee) =
+            Self::transfer_mint(&base_token.mint, trailing_infos, clock.epoch)?;
+
+        let invariant = token_swap.curve(clock.unix_timestamp);
+        let (dy, dy_fee) = invariant
+            .compute_withdraw_one(
+                U256::from(pool_token_amount),
+                U256::from(pool_mint.supply),
+                U256::from(
+                    token_swap
+                        .pool_amount(base_token_info.key, base_token.amount)
+                        .ok_or(SwapError::CalculationFailure)?,
+                ),
+                U256::from(
+                    token_swap
+                        .pool_amount(quote_token_info.key, quote_token.amount)
+                        .ok_or(SwapError::CalculationFailure)?,
+                ),
+                &token_swap.fees,
+            )
+            .ok_or(SwapError::CalculationFailure)?;
+        let withdraw_fee = token_swap
+            .fees
+            .withdraw_fee(dy)
+            .ok_or(SwapError::CalculationFailure)?;
+        let token_amount = U256::to_u64(
+            dy.checked_sub(withdraw_fee)
+                .ok_or(SwapError::CalculationFailure)?,
+        )?;
+        if base_transfer_fee
+            .post_fee_amount(token_amount)
+            .ok_or(SwapError::CalculationFailure)?
+            < minimum_token_amount
+        {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+
+        let admin_trade_fee = token_swap
+            .fees
+            .admin_trade_fee(dy_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        let admin_withdraw_fee = token_swap
+            .fees
+            .admin_withdraw_fee(withdraw_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        let admin_fee = admin_trade_fee
+            .checked_add(admin_withdraw_fee)
+            .ok_or(SwapError::CalculationFailure)?;
+        log_trace!(
+            "token_amount: {}, trade_fee: {}, withdraw_fee: {}, admin_fee: {}",
+            token_amount,
+            dy_fee,
+            withdraw_fee,
+            admin_fee
+        );
+
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            base_token_info.clone(),
+            base_mint_info,
+            destination_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            token_amount,
+        )?;
+        Self::admin_fee_transfer(
+            &mut token_swap,
+            swap_info.key,
+            token_program_info.clone(),
+            base_token_info.clone(),
+            base_mint_info,
+            admin_destination_info.clone(),
+            authority_info.clone(),
+            U256::to_u64(admin_fee)?,
+        )?;
+        Self::check_user_source(source_info, authority_info)?;
+        Self::pool_token_burn(
+            token_swap.lp_soulbound,
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            pool_mint_info.clone(),
+            authority_info.clone(),
+            source_owner_info,
+            token_swap.nonce,
+            pool_token_amount,
+        )?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Self::update_global_stats_best_effort(program_id, trailing_infos, |global_stats| {
+            global_stats.remove_value(token_amount)
+        })
+    }
+
+    /// Processes a [WithdrawImbalanced](enum.Instruction.html).
+    pub fn process_withdraw_imbalanced(
+        program_id: &Pubkey,
+        token_a_amount: u64,
+        token_b_amount: u64,
+        maximum_pool_token_amount: u64,
+        deadline_slot: u64,
+        expected_sequence: Option<u64>,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let pool_mint_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let dest_token_a_info = next_account_info(account_info_iter)?;
+        let dest_token_b_info = next_account_info(account_info_iter)?;
+        let admin_fee_dest_a_info = next_account_info(account_info_iter)?;
+        let admin_fee_dest_b_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+        let trailing_infos = account_info_iter.as_slice();
+        let source_owner_info = account_info_iter.next();
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::IsPaused.into());
+        }
+        if !token_swap.is_at_sequence(expected_sequence) {
+            return Err(SwapError::SequenceMismatch.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if *token_a_info.key != token_swap.token_a {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *token_b_info.key != token_swap.token_b {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *pool_mint_info.key != token_swap.pool_mint {
+            return Err(SwapError::IncorrectMint.into());
+        }
+        if *admin_fee_dest_a_info.key != token_swap.admin_fee_key_a {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if *admin_fee_dest_b_info.key != token_swap.admin_fee_key_b {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if token_a_amount == 0 && token_b_amount == 0 {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let pool_mint = Self::unpack_mint(&pool_mint_info.data.borrow())?;
+        if pool_mint.supply == 0 {
+            return Err(SwapError::EmptyPool.into());
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        Self::check_deadline(clock.slot, deadline_slot)?;
+        token_swap.settle_order_flow_auction(clock.slot);
+        let token_a = utils::unpack_token_account(&token_a_info.data.borrow())?;
+        let token_b = utils::unpack_token_account(&token_b_info.data.borrow())?;
+        let (token_a_mint_info, _) =
+            Self::transfer_mint(&token_a.mint, trailing_infos, clock.epoch)?;
+        let (token_b_mint_info, _) =
+            Self::transfer_mint(&token_b.mint, trailing_infos, clock.epoch)?;
+
+        let invariant = token_swap.curve(clock.unix_timestamp);
+        let (burn_amount, trade_fee_a, trade_fee_b) = invariant
+            .compute_burn_amount_for_withdraw(
+                U256::from(token_a_amount),
+                U256::from(token_b_amount),
+                U256::from(
+                    token_swap
+                        .pool_amount(token_a_info.key, token_a.amount)
+                        .ok_or(SwapError::CalculationFailure)?,
+                ),
+                U256::from(
+                    token_swap
+                        .pool_amount(token_b_info.key, token_b.amount)
+                        .ok_or(SwapError::CalculationFailure)?,
+                ),
+                U256::from(pool_mint.supply),
+                &token_swap.fees,
+            )
+            .ok_or(SwapError::CalculationFailure)?;
+        let burn_amount = U256::to_u64(burn_amount)?;
+        if burn_amount > pool_mint.supply {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if burn_amount > maximum_pool_token_amount {
+            return Err(SwapError::ExceededSlippage.into());
+        }
+        Self::check_custodian(&token_swap, burn_amount, trailing_infos)?;
+
+        let a_admin_fee = U256::to_u64(
+            token_swap
+                .fees
+                .admin_trade_fee(trade_fee_a)
+                .ok_or(SwapError::CalculationFailure)?,
+        )?;
+        let b_admin_fee = U256::to_u64(
+            token_swap
+                .fees
+                .admin_trade_fee(trade_fee_b)
+                .ok_or(SwapError::CalculationFailure)?,
+        )?;
+        log_trace!(
+            "burn_amount: {}, trade_fee_a: {}, trade_fee_b: {}, a_admin_fee: {}, b_admin_fee: {}",
+            burn_amount,
+            trade_fee_a,
+            trade_fee_b,
+            a_admin_fee,
+            b_admin_fee
+        );
+
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            token_a_info.clone(),
+            token_a_mint_info,
+            dest_token_a_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            token_a_amount,
+        )?;
+        Self::admin_fee_transfer(
+            &mut token_swap,
+            swap_info.key,
+            token_program_info.clone(),
+            token_a_info.clone(),
+            token_a_mint_info,
+            admin_fee_dest_a_info.clone(),
+            authority_info.clone(),
+            a_admin_fee,
+        )?;
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            token_b_info.clone(),
+            token_b_mint_info,
+            dest_token_b_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            token_b_amount,
+        )?;
+        Self::admin_fee_transfer(
+            &mut token_swap,
+            swap_info.key,
+            token_program_info.clone(),
+            token_b_info.clone(),
+            token_b_mint_info,
+            admin_fee_dest_b_info.clone(),
+            authority_info.clone(),
+            b_admin_fee,
+        )?;
+        Self::check_user_source(source_info, authority_info)?;
+        Self::pool_token_burn(
+            token_swap.lp_soulbound,
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            pool_mint_info.clone(),
+            authority_info.clone(),
+            source_owner_info,
+            token_swap.nonce,
+            burn_amount,
+        )?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Self::update_global_stats_best_effort(program_id, trailing_infos, |global_stats| {
+            global_stats.remove_value(token_a_amount);
+            global_stats.remove_value(token_b_amount);
+        })
+    }
+
+    /// Processes a [HarvestAdminFees](enum.Instruction.html).
+    pub fn process_harvest_admin_fees(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let token_a_info = next_account_info(account_info_iter)?;
+        let token_b_info = next_account_info(account_info_iter)?;
+        let admin_fee_dest_a_info = next_account_info(account_info_iter)?;
+        let admin_fee_dest_b_info = next_account_info(account_info_iter)?;
+        let tip_dest_a_info = next_account_info(account_info_iter)?;
+        let tip_dest_b_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if *token_a_info.key != token_swap.token_a {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *token_b_info.key != token_swap.token_b {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *admin_fee_dest_a_info.key != token_swap.admin_fee_key_a {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        if *admin_fee_dest_b_info.key != token_swap.admin_fee_key_b {
+            return Err(SwapError::InvalidAdmin.into());
+        }
+        let (ve_fee_vault_a_info, ve_fee_vault_b_info) = if token_swap.is_ve_enabled() {
+            let ve_fee_vault_a_info = next_account_info(account_info_iter)?;
+            let ve_fee_vault_b_info = next_account_info(account_info_iter)?;
+            let clock_sysvar_info = next_account_info(account_info_iter)?;
+            if *ve_fee_vault_a_info.key != token_swap.ve_fee_vault_a
+                || *ve_fee_vault_b_info.key != token_swap.ve_fee_vault_b
+            {
+                return Err(SwapError::IncorrectSwapAccount.into());
+            }
+            // Fees harvested now are distributed at the end of the current epoch
+            let clock = Clock::from_account_info(clock_sysvar_info)?;
+            token_swap
+                .checkpoint_ve_fees(clock.unix_timestamp)
+                .ok_or(SwapError::CalculationFailure)?;
+            (Some(ve_fee_vault_a_info), Some(ve_fee_vault_b_info))
+        } else {
+            (None, None)
+        };
+
+        // Escrowed admin fees stay in the swap token accounts
+        let harvested_a = if token_swap.admin_fee_escrow_a {
+            0
+        } else {
+            token_swap.admin_fees_accrued_a
+        };
+        let harvested_b = if token_swap.admin_fee_escrow_b {
+            0
+        } else {
+            token_swap.admin_fees_accrued_b
+        };
+        let ve_fee_a = token_swap
+            .ve_fee(harvested_a)
+            .ok_or(SwapError::CalculationFailure)?;
+        let ve_fee_b = token_swap
+            .ve_fee(harvested_b)
+            .ok_or(SwapError::CalculationFailure)?;
+        for (
+            swap_token_info,
+            admin_fee_dest_info,
+            tip_dest_info,
+            ve_fee_vault_info,
+            accrued,
+            ve_fee,
+        ) in [
+            (
+                token_a_info,
+                admin_fee_dest_a_info,
+                tip_dest_a_info,
+                ve_fee_vault_a_info,
+                harvested_a,
+                ve_fee_a,
+            ),
+            (
+                token_b_info,
+                admin_fee_dest_b_info,
+                tip_dest_b_info,
+                ve_fee_vault_b_info,
+                harvested_b,
+                ve_fee_b,
+            ),
+        ] {
+            if accrued == 0 {
+                continue;
+            }
+            let tip = token_swap
+                .harvest_tip(accrued)
+                .ok_or(SwapError::CalculationFailure)?;
+            let admin_fee = accrued
+                .checked_sub(tip)
+                .and_then(|admin_fee| admin_fee.checked_sub(ve_fee))
+                .ok_or(SwapError::CalculationFailure)?;
+            if let Some(ve_fee_vault_info) = ve_fee_vault_info {
+                Self::token_transfer_with_mint(
+                    swap_info.key,
+                    token_program_info.clone(),
+                    swap_token_info.clone(),
+                    Self::token_mint(swap_token_info, accounts)?,
+                    ve_fee_vault_info.clone(),
+                    authority_info.clone(),
+                    token_swap.nonce,
+                    ve_fee,
+                )?;
+            }
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                swap_token_info.clone(),
+                Self::token_mint(swap_token_info, accounts)?,
+                admin_fee_dest_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                admin_fee,
+            )?;
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                swap_token_info.clone(),
+                Self::token_mint(swap_token_info, accounts)?,
+                tip_dest_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                tip,
+            )?;
+        }
+
+        token_swap.ve_pending_fees_a = token_swap
+            .ve_pending_fees_a
+            .checked_add(ve_fee_a)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.ve_pending_fees_b = token_swap
+            .ve_pending_fees_b
+            .checked_add(ve_fee_b)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.admin_fees_accrued_a -= harvested_a;
+        token_swap.admin_fees_accrued_b -= harvested_b;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [ClaimVestedDevFees](enum.Instruction.html).
+    pub fn process_claim_vested_dev_fees(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let dev_wallet_info = next_account_info(account_info_iter)?;
+        let dev_vesting_a_info = next_account_info(account_info_iter)?;
+        let dev_vesting_b_info = next_account_info(account_info_iter)?;
+        let destination_a_info = next_account_info(account_info_iter)?;
+        let destination_b_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if !token_swap.is_dev_fee_vesting() {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if *dev_wallet_info.key != token_swap.dev_wallet || !dev_wallet_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if *dev_vesting_a_info.key != token_swap.dev_vesting_a {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if *dev_vesting_b_info.key != token_swap.dev_vesting_b {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        let vested_a = token_swap
+            .vested_dev_fees(token_swap.dev_fees_received_a, clock.unix_timestamp)
+            .ok_or(SwapError::CalculationFailure)?;
+        let vested_b = token_swap
+            .vested_dev_fees(token_swap.dev_fees_received_b, clock.unix_timestamp)
+            .ok_or(SwapError::CalculationFailure)?;
+        for (dev_vesting_info, destination_info, vested, claimed) in [
+            (
+                dev_vesting_a_info,
+                destination_a_info,
+                vested_a,
+                token_swap.dev_fees_claimed_a,
+            ),
+            (
+                dev_vesting_b_info,
+                destination_b_info,
+                vested_b,
+                token_swap.dev_fees_claimed_b,
+            ),
+        ] {
+            let claimable = vested
+                .checked_sub(claimed)
+                .ok_or(SwapError::CalculationFailure)?;
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                dev_vesting_info.clone(),
+                Self::token_mint(dev_vesting_info, accounts)?,
+                destination_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                claimable,
+            )?;
+        }
+
+        token_swap.dev_fees_claimed_a = vested_a;
+        token_swap.dev_fees_claimed_b = vested_b;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [DepositGovernanceTokens](enum.Instruction.html).
+    pub fn process_deposit_governance_tokens(
+        program_id: &Pubkey,
+        amount: u64,
+        deposit_nonce: u8,
+        record_nonce: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let governance_vault_info = next_account_info(account_info_iter)?;
+        let governance_deposit_info = next_account_info(account_info_iter)?;
+        let voter_weight_record_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.governance_realm == Pubkey::default() {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if !owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if *governance_vault_info.key != token_swap.governance_vault {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+
+        let deposit_signature_seeds = [
+            GOVERNANCE_DEPOSIT_SEED,
+            swap_info.key.as_ref(),
+            owner_info.key.as_ref(),
+            &[deposit_nonce],
+        ];
+        let deposit_key = Pubkey::create_program_address(&deposit_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+        if *governance_deposit_info.key != deposit_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        let record_signature_seeds = [
+            VOTER_WEIGHT_RECORD_SEED,
+            token_swap.governance_realm.as_ref(),
+            token_swap.pool_mint.as_ref(),
+            owner_info.key.as_ref(),
+            &[record_nonce],
+        ];
+        let record_key = Pubkey::create_program_address(&record_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+        if *voter_weight_record_info.key != record_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        let mut governance_deposit = if governance_deposit_info.lamports() == 0 {
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner_info.key,
+                    governance_deposit_info.key,
+                    rent.minimum_balance(GovernanceDeposit::LEN),
+                    GovernanceDeposit::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    owner_info.clone(),
+                    governance_deposit_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&deposit_signature_seeds],
+            )?;
+            GovernanceDeposit {
+                is_initialized: true,
+                swap: *swap_info.key,
+                owner: *owner_info.key,
+                amount: 0,
+                unlock_ts: ZERO_TS,
+            }
+        } else {
+            if governance_deposit_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            GovernanceDeposit::unpack(&governance_deposit_info.data.borrow())?
+        };
+        let mut voter_weight_record = if voter_weight_record_info.lamports() == 0 {
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner_info.key,
+                    voter_weight_record_info.key,
+                    rent.minimum_balance(VoterWeightRecord::LEN),
+                    VoterWeightRecord::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    owner_info.clone(),
+                    voter_weight_record_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&record_signature_seeds],
+            )?;
+            VoterWeightRecord {
+                realm: token_swap.governance_realm,
+                governing_token_mint: token_swap.pool_mint,
+                governing_token_owner: *owner_info.key,
+                voter_weight: 0,
+            }
+        } else {
+            if voter_weight_record_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            VoterWeightRecord::unpack(&voter_weight_record_info.data.borrow())?
+        };
+
+        Self::user_token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            Self::token_mint(source_info, accounts)?,
+            governance_vault_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            amount,
+        )?;
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        governance_deposit.amount = governance_deposit
+            .amount
+            .checked_add(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        governance_deposit.unlock_ts = clock
+            .unix_timestamp
+            .checked_add(token_swap.governance_lockup)
+            .ok_or(SwapError::CalculationFailure)?;
+        voter_weight_record.voter_weight = voter_weight_record
+            .voter_weight
+            .checked_add(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        GovernanceDeposit::pack(
+            governance_deposit,
+            &mut governance_deposit_info.data.borrow_mut(),
+        )?;
+        VoterWeightRecord::pack(
+            voter_weight_record,
+            &mut voter_weight_record_info.data.borrow_mut(),
+        )?;
+        Ok(())
+    }
+
+    /// Processes a [WithdrawGovernanceTokens](enum.Instruction.html).
+    pub fn process_withdraw_governance_tokens(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let governance_deposit_info = next_account_info(account_info_iter)?;
+        let voter_weight_record_info = next_account_info(account_info_iter)?;
+        let governance_vault_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if !owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if *governance_vault_info.key != token_swap.governance_vault {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if governance_deposit_info.owner != program_id
+            || voter_weight_record_info.owner != program_id
+        {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut governance_deposit =
+            GovernanceDeposit::unpack(&governance_deposit_info.data.borrow())?;
+        if governance_deposit.swap != *swap_info.key || governance_deposit.owner != *owner_info.key
+        {
+            return Err(SwapError::Unauthorized.into());
+        }
+        let mut voter_weight_record =
+            VoterWeightRecord::unpack(&voter_weight_record_info.data.borrow())?;
+        if voter_weight_record.realm != token_swap.governance_realm
+            || voter_weight_record.governing_token_mint != token_swap.pool_mint
+            || voter_weight_record.governing_token_owner != *owner_info.key
+        {
+            return Err(SwapError::Unauthorized.into());
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        if clock.unix_timestamp < governance_deposit.unlock_ts {
+            return Err(SwapError::GovernanceDepositLocked.into());
+        }
+        governance_deposit.amount = governance_deposit
+            .amount
+            .checked_sub(amount)
+            .ok_or(SwapError::InvalidInput)?;
+        voter_weight_record.voter_weight = voter_weight_record
+            .voter_weight
+            .checked_sub(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            governance_vault_info.clone(),
+            Self::token_mint(governance_vault_info, accounts)?,
+            destination_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            amount,
+        )?;
+        GovernanceDeposit::pack(
+            governance_deposit,
+            &mut governance_deposit_info.data.borrow_mut(),
+        )?;
+        VoterWeightRecord::pack(
+            voter_weight_record,
+            &mut voter_weight_record_info.data.borrow_mut(),
+        )?;
+        Ok(())
+    }
+
+    /// Processes a [CreateVeLock](enum.Instruction.html).
+    pub fn process_create_ve_lock(
+        program_id: &Pubkey,
+        amount: u64,
+        lock_duration: i64,
+        nonce: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let ve_lp_vault_info = next_account_info(account_info_iter)?;
+        let ve_lock_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if !token_swap.is_ve_enabled() {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if !owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if *ve_lp_vault_info.key != token_swap.ve_lp_vault {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        if !(VE_MIN_LOCK_DURATION..=VE_MAX_LOCK_DURATION).contains(&lock_duration) {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let ve_balance = (amount as u128)
+            .checked_mul(lock_duration as u128)
+            .and_then(|ve_balance| ve_balance.checked_div(VE_MAX_LOCK_DURATION as u128))
+            .and_then(|ve_balance| u64::try_from(ve_balance).ok())
+            .ok_or(SwapError::CalculationFailure)?;
+        if ve_balance == 0 {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        let ve_lock_signature_seeds = [
+            VE_LOCK_SEED,
+            swap_info.key.as_ref(),
+            owner_info.key.as_ref(),
+            &[nonce],
+        ];
+        let ve_lock_key = Pubkey::create_program_address(&ve_lock_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+        if *ve_lock_info.key != ve_lock_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if ve_lock_info.lamports() != 0 {
+            return Err(SwapError::AlreadyInUse.into());
+        }
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                owner_info.key,
+                ve_lock_info.key,
+                rent.minimum_balance(VeLock::LEN),
+                VeLock::LEN as u64,
+                program_id,
+            ),
+            &[
+                owner_info.clone(),
+                ve_lock_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&ve_lock_signature_seeds],
+        )?;
+
+        Self::user_token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            Self::token_mint(source_info, accounts)?,
+            ve_lp_vault_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            amount,
+        )?;
+
+        // The lock only shares the fees distributed after its creation
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        token_swap
+            .checkpoint_ve_fees(clock.unix_timestamp)
+            .ok_or(SwapError::CalculationFailure)?;
+        let ve_lock = VeLock {
+            is_initialized: true,
+            swap: *swap_info.key,
+            owner: *owner_info.key,
+            amount,
+            unlock_ts: clock
+                .unix_timestamp
+                .checked_add(lock_duration)
+                .ok_or(SwapError::CalculationFailure)?,
+            ve_balance,
+            fees_debt_a: ve_fees(ve_balance, token_swap.ve_fees_per_ve_a)
+                .ok_or(SwapError::CalculationFailure)?,
+            fees_debt_b: ve_fees(ve_balance, token_swap.ve_fees_per_ve_b)
+                .ok_or(SwapError::CalculationFailure)?,
+            gauge_vote_bps: 0,
+        };
+        token_swap.ve_supply = token_swap
+            .ve_supply
+            .checked_add(ve_balance)
+            .ok_or(SwapError::CalculationFailure)?;
+        VeLock::pack(ve_lock, &mut ve_lock_info.data.borrow_mut())?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Unpacks the vote-escrow lock of `owner_info` in the swap.
+    fn unpack_ve_lock(
+        program_id: &Pubkey,
+        swap_info: &AccountInfo,
+        owner_info: &AccountInfo,
+        ve_lock_info: &AccountInfo,
+    ) -> Result<VeLock, ProgramError> {
+        if !owner_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if ve_lock_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let ve_lock = VeLock::unpack(&ve_lock_info.data.borrow())?;
+        if ve_lock.swap != *swap_info.key || ve_lock.owner != *owner_info.key {
+            return Err(SwapError::Unauthorized.into());
+        }
+        Ok(ve_lock)
+    }
+
+    /// Pays the fees distributed to `ve_lock` and not claimed yet out of the veLP fee
+    /// vaults.
+    #[allow(clippy::too_many_arguments)]
+    fn pay_ve_fees<'a>(
+        swap_info: &AccountInfo<'a>,
+        token_swap: &SwapInfo,
+        ve_lock: &mut VeLock,
+        token_program_info: &AccountInfo<'a>,
+        authority_info: &AccountInfo<'a>,
+        ve_fee_vault_a_info: &AccountInfo<'a>,
+        ve_fee_vault_b_info: &AccountInfo<'a>,
+        destination_a_info: &AccountInfo<'a>,
+        destination_b_info: &AccountInfo<'a>,
+        accounts: &[AccountInfo<'a>],
+    ) -> ProgramResult {
+        if *ve_fee_vault_a_info.key != token_swap.ve_fee_vault_a
+            || *ve_fee_vault_b_info.key != token_swap.ve_fee_vault_b
+        {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        for (ve_fee_vault_info, destination_info, fees_per_ve, fees_debt) in [
+            (
+                ve_fee_vault_a_info,
+                destination_a_info,
+                token_swap.ve_fees_per_ve_a,
+                &mut ve_lock.fees_debt_a,
+            ),
+            (
+                ve_fee_vault_b_info,
+                destination_b_info,
+                token_swap.ve_fees_per_ve_b,
+                &mut ve_lock.fees_debt_b,
+            ),
+        ] {
+            let fees =
+                ve_fees(ve_lock.ve_balance, fees_per_ve).ok_or(SwapError::CalculationFailure)?;
+            let claimable = fees
+                .checked_sub(*fees_debt)
+                .and_then(|claimable| u64::try_from(claimable).ok())
+                .ok_or(SwapError::CalculationFailure)?;
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                ve_fee_vault_info.clone(),
+                Self::token_mint(ve_fee_vault_info, accounts)?,
+                destination_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                claimable,
+            )?;
+            *fees_debt = fees;
+        }
+        Ok(())
+    }
+
+    /// Processes a [ClaimVeFees](enum.Instruction.html).
+    pub fn process_claim_ve_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let ve_lock_info = next_account_info(account_info_iter)?;
+        let ve_fee_vault_a_info = next_account_info(account_info_iter)?;
+        let ve_fee_vault_b_info = next_account_info(account_info_iter)?;
+        let destination_a_info = next_account_info(account_info_iter)?;
+        let destination_b_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        let mut ve_lock = Self::unpack_ve_lock(program_id, swap_info, owner_info, ve_lock_info)?;
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        token_swap
+            .checkpoint_ve_fees(clock.unix_timestamp)
+            .ok_or(SwapError::CalculationFailure)?;
+        Self::pay_ve_fees(
+            swap_info,
+            &token_swap,
+            &mut ve_lock,
+            token_program_info,
+            authority_info,
+            ve_fee_vault_a_info,
+            ve_fee_vault_b_info,
+            destination_a_info,
+            destination_b_info,
+            accounts,
+        )?;
+        VeLock::pack(ve_lock, &mut ve_lock_info.data.borrow_mut())?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [WithdrawVeLock](enum.Instruction.html).
+    pub fn process_withdraw_ve_lock(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let ve_lock_info = next_account_info(account_info_iter)?;
+        let ve_lp_vault_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let ve_fee_vault_a_info = next_account_info(account_info_iter)?;
+        let ve_fee_vault_b_info = next_account_info(account_info_iter)?;
+        let destination_a_info = next_account_info(account_info_iter)?;
+        let destination_b_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if *ve_lp_vault_info.key != token_swap.ve_lp_vault {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        let mut ve_lock = Self::unpack_ve_lock(program_id, swap_info, owner_info, ve_lock_info)?;
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        if clock.unix_timestamp < ve_lock.unlock_ts {
+            return Err(SwapError::VeLockActive.into());
+        }
+        // Votes of withdrawn locks would keep weighing on their gauges
+        if ve_lock.gauge_vote_bps != 0 {
+            return Err(SwapError::GaugeVoteActive.into());
+        }
+        token_swap
+            .checkpoint_ve_fees(clock.unix_timestamp)
+            .ok_or(SwapError::CalculationFailure)?;
+        Self::pay_ve_fees(
+            swap_info,
+            &token_swap,
+            &mut ve_lock,
+            token_program_info,
+            authority_info,
+            ve_fee_vault_a_info,
+            ve_fee_vault_b_info,
+            destination_a_info,
+            destination_b_info,
+            accounts,
+        )?;
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            ve_lp_vault_info.clone(),
+            Self::token_mint(ve_lp_vault_info, accounts)?,
+            destination_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            ve_lock.amount,
+        )?;
+        token_swap.ve_supply = token_swap
+            .ve_supply
+            .checked_sub(ve_lock.ve_balance)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        let owner_lamports = owner_info
+            .lamports()
+            .checked_add(ve_lock_info.lamports())
+            .ok_or(SwapError::CalculationFailure)?;
+        **owner_info.lamports.borrow_mut() = owner_lamports;
+        **ve_lock_info.lamports.borrow_mut() = 0;
+        ve_lock_info.data.borrow_mut().fill(0);
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Unpacks a gauge of `controller_info`, checkpointed in the current epoch along
+    /// with the controller.
+    fn unpack_gauge(
+        program_id: &Pubkey,
+        controller_info: &AccountInfo,
+        gauge_info: &AccountInfo,
+        clock: &Clock,
+    ) -> Result<(GaugeController, Gauge), ProgramError> {
+        if controller_info.owner != program_id || gauge_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut controller = GaugeController::unpack(&controller_info.data.borrow())?;
+        let mut gauge = Gauge::unpack(&gauge_info.data.borrow())?;
+        if gauge.controller != *controller_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+        controller.checkpoint(clock.unix_timestamp / VE_EPOCH_DURATION);
+        gauge
+            .checkpoint(&controller)
+            .ok_or(SwapError::CalculationFailure)?;
+        Ok((controller, gauge))
+    }
+
+    /// Processes a [VoteGaugeWeight](enum.Instruction.html).
+    pub fn process_vote_gauge_weight(
+        program_id: &Pubkey,
+        weight_bps: u16,
+        nonce: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let owner_info = next_account_info(account_info_iter)?;
+        let ve_lock_info = next_account_info(account_info_iter)?;
+        let controller_info = next_account_info(account_info_iter)?;
+        let gauge_info = next_account_info(account_info_iter)?;
+        let gauge_vote_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        let mut ve_lock = Self::unpack_ve_lock(program_id, swap_info, owner_info, ve_lock_info)?;
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        if weight_bps != 0 && clock.unix_timestamp >= ve_lock.unlock_ts {
+            return Err(SwapError::InvalidInput.into());
+        }
+        // Votes are applied after the checkpoint, taking effect from the next epoch
+        let (mut controller, mut gauge) =
+            Self::unpack_gauge(program_id, controller_info, gauge_info, &clock)?;
+        if controller.swap != *swap_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        let gauge_vote_signature_seeds = [
+            GAUGE_VOTE_SEED,
+            gauge_info.key.as_ref(),
+            owner_info.key.as_ref(),
+            &[nonce],
+        ];
+        let gauge_vote_key =
+            Pubkey::create_program_address(&gauge_vote_signature_seeds, program_id)
+                .or(Err(SwapError::InvalidProgramAddress))?;
+        if *gauge_vote_info.key != gauge_vote_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        let mut gauge_vote = if gauge_vote_info.lamports() == 0 {
+            let rent = Rent::from_account_info(rent_sysvar_info)?;
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner_info.key,
+                    gauge_vote_info.key,
+                    rent.minimum_balance(GaugeVote::LEN),
+                    GaugeVote::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    owner_info.clone(),
+                    gauge_vote_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&gauge_vote_signature_seeds],
+            )?;
+            GaugeVote {
+                is_initialized: true,
+                gauge: *gauge_info.key,
+                owner: *owner_info.key,
+                weight_bps: 0,
+                weight: 0,
+            }
+        } else {
+            if gauge_vote_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            GaugeVote::unpack(&gauge_vote_info.data.borrow())?
+        };
+
+        let weight = (ve_lock.ve_balance as u128)
+            .checked_mul(weight_bps as u128)
+            .and_then(|weight| weight.checked_div(10_000))
+            .and_then(|weight| u64::try_from(weight).ok())
+            .ok_or(SwapError::CalculationFailure)?;
+        ve_lock.gauge_vote_bps = ve_lock
+            .gauge_vote_bps
+            .checked_sub(gauge_vote.weight_bps)
+            .and_then(|gauge_vote_bps| gauge_vote_bps.checked_add(weight_bps))
+            .ok_or(SwapError::CalculationFailure)?;
+        if ve_lock.gauge_vote_bps > 10_000 {
+            return Err(SwapError::InvalidInput.into());
+        }
+        gauge.next_weight = gauge
+            .next_weight
+            .checked_sub(gauge_vote.weight)
+            .and_then(|next_weight| next_weight.checked_add(weight))
+            .ok_or(SwapError::CalculationFailure)?;
+        controller.total_next_weight = controller
+            .total_next_weight
+            .checked_sub(gauge_vote.weight)
+            .and_then(|total_next_weight| total_next_weight.checked_add(weight))
+            .ok_or(SwapError::CalculationFailure)?;
+        gauge_vote.weight_bps = weight_bps;
+        gauge_vote.weight = weight;
+
+        GaugeVote::pack(gauge_vote, &mut gauge_vote_info.data.borrow_mut())?;
+        Gauge::pack(gauge, &mut gauge_info.data.borrow_mut())?;
+        GaugeController::pack(controller, &mut controller_info.data.borrow_mut())?;
+        VeLock::pack(ve_lock, &mut ve_lock_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [CheckpointGauge](enum.Instruction.html).
+    pub fn process_checkpoint_gauge(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let controller_info = next_account_info(account_info_iter)?;
+        let gauge_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        let (controller, gauge) =
+            Self::unpack_gauge(program_id, controller_info, gauge_info, &clock)?;
+        Gauge::pack(gauge, &mut gauge_info.data.borrow_mut())?;
+        GaugeController::pack(controller, &mut controller_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Unpacks the farm of the swap, its emission accounted up to the current slot
+    fn unpack_farm(
+        program_id: &Pubkey,
+        swap_info: &AccountInfo,
+        farm_info: &AccountInfo,
+        reward_vault_info: &AccountInfo,
+        clock: &Clock,
+    ) -> Result<Farm, ProgramError> {
+        constrain! {
+            farm_info: writable owned(program_id),
+        }
+        let mut farm = Farm::unpack(&farm_info.data.borrow())?;
+        if farm.swap != *swap_info.key {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        constrain! {
+            reward_vault_info: key(farm.reward_vault),
+        }
+        let reward_vault = utils::unpack_token_account(&reward_vault_info.data.borrow())?;
+        farm.update(clock.slot, reward_vault.amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        Ok(farm)
+    }
+
+    /// Unpacks the stake account of `owner_info` in the farm.
+    fn unpack_stake_account(
+        program_id: &Pubkey,
+        farm_info: &AccountInfo,
+        owner_info: &AccountInfo,
+        stake_account_info: &AccountInfo,
+    ) -> Result<StakeAccount, ProgramError> {
+        constrain! {
+            owner_info: signer,
+            stake_account_info: writable owned(program_id),
+        }
+        let stake_account = StakeAccount::unpack(&stake_account_info.data.borrow())?;
+        if stake_account.farm != *farm_info.key || stake_account.owner != *owner_info.key {
+            return Err(SwapError::Unauthorized.into());
+        }
+        Ok(stake_account)
+    }
+
+    /// Processes a [Stake](enum.Instruction.html).
+    pub fn process_stake(
+        program_id: &Pubkey,
+        amount: u64,
+        nonce: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: owned(program_id),
+            authority_info,
+            farm_info,
+            owner_info: signer,
+            source_info,
+            stake_vault_info,
+            reward_vault_info,
+            stake_account_info: writable,
+            system_program_info,
+            rent_sysvar_info,
+            clock_sysvar_info,
+            token_program_info,
+        }
+
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if amount == 0 {
+            return Err(SwapError::InvalidInput.into());
+        }
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        let mut farm =
+            Self::unpack_farm(program_id, swap_info, farm_info, reward_vault_info, &clock)?;
+        constrain! {
+            authority_info: authority(program_id, swap_info.key, token_swap.nonce),
+            stake_vault_info: key(farm.stake_vault),
+        }
+
+        let stake_account_signature_seeds = [
+            STAKE_ACCOUNT_SEED,
+            farm_info.key.as_ref(),
+            owner_info.key.as_ref(),
+            &[nonce],
+        ];
+        let stake_account_key =
+            Pubkey::create_program_address(&stake_account_signature_seeds, program_id)
+                .or(Err(SwapError::InvalidProgramAddress))?;
+        if *stake_account_info.key != stake_account_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        let mut stake_account = if stake_account_info.lamports() == 0 {
+            let rent = Rent::from_account_info(rent_sysvar_info)?;
+            invoke_signed(
+                &system_instruction::create_account(
+                    owner_info.key,
+                    stake_account_info.key,
+                    rent.minimum_balance(StakeAccount::LEN),
+                    StakeAccount::LEN as u64,
+                    program_id,
+                ),
+                &[
+                    owner_info.clone(),
+                    stake_account_info.clone(),
+                    system_program_info.clone(),
+                ],
+                &[&stake_account_signature_seeds],
+            )?;
+            StakeAccount {
+                is_initialized: true,
+                farm: *farm_info.key,
+                owner: *owner_info.key,
+                ..StakeAccount::default()
+            }
+        } else {
+            if stake_account_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            StakeAccount::unpack(&stake_account_info.data.borrow())?
+        };
+
+        Self::user_token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            Self::token_mint(source_info, accounts)?,
+            stake_vault_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            amount,
+        )?;
+
+        stake_account
+            .set_amount(
+                stake_account
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(SwapError::CalculationFailure)?,
+                farm.acc_reward_per_share,
+            )
+            .ok_or(SwapError::CalculationFailure)?;
+        farm.total_staked = farm
+            .total_staked
+            .checked_add(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+        StakeAccount::pack(stake_account, &mut stake_account_info.data.borrow_mut())?;
+        Farm::pack(farm, &mut farm_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes an [Unstake](enum.Instruction.html).
+    pub fn process_unstake(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: owned(program_id),
+            authority_info,
+            farm_info,
+            owner_info,
+            stake_account_info,
+            stake_vault_info,
+            reward_vault_info,
+            destination_info,
+            clock_sysvar_info,
+            token_program_info,
+        }
+
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        let mut farm =
+            Self::unpack_farm(program_id, swap_info, farm_info, reward_vault_info, &clock)?;
+        constrain! {
+            authority_info: authority(program_id, swap_info.key, token_swap.nonce),
+            stake_vault_info: key(farm.stake_vault),
+        }
+        let mut stake_account =
+            Self::unpack_stake_account(program_id, farm_info, owner_info, stake_account_info)?;
+
+        stake_account
+            .set_amount(
+                stake_account
+                    .amount
+                    .checked_sub(amount)
+                    .ok_or(SwapError::InvalidInput)?,
+                farm.acc_reward_per_share,
+            )
+            .ok_or(SwapError::CalculationFailure)?;
+        farm.total_staked = farm
+            .total_staked
+            .checked_sub(amount)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            stake_vault_info.clone(),
+            Self::token_mint(stake_vault_info, accounts)?,
+            destination_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            amount,
+        )?;
+        StakeAccount::pack(stake_account, &mut stake_account_info.data.borrow_mut())?;
+        Farm::pack(farm, &mut farm_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [HarvestRewards](enum.Instruction.html).
+    pub fn process_harvest_rewards(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        accounts! {
+            account_info_iter;
+            swap_info: owned(program_id),
+            authority_info,
+            farm_info,
+            owner_info,
+            stake_account_info,
+            reward_vault_info,
+            destination_info,
+            clock_sysvar_info,
+            token_program_info,
+        }
+
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        constrain! {
+            authority_info: authority(program_id, swap_info.key, token_swap.nonce),
+        }
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        let mut farm =
+            Self::unpack_farm(program_id, swap_info, farm_info, reward_vault_info, &clock)?;
+        let mut stake_account =
+            Self::unpack_stake_account(program_id, farm_info, owner_info, stake_account_info)?;
+
+        stake_account
+            .set_amount(stake_account.amount, farm.acc_reward_per_share)
+            .ok_or(SwapError::CalculationFailure)?;
+        let rewards = stake_account.reward_owed;
+        stake_account.reward_owed = 0;
+        farm.rewards_unharvested = farm
+            .rewards_unharvested
+            .checked_sub(rewards)
+            .ok_or(SwapError::CalculationFailure)?;
+
+        if rewards > 0 {
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                reward_vault_info.clone(),
+                Self::token_mint(reward_vault_info, accounts)?,
+                destination_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                rewards,
+            )?;
+        }
+        StakeAccount::pack(stake_account, &mut stake_account_info.data.borrow_mut())?;
+        Farm::pack(farm, &mut farm_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [BidOrderFlow](enum.Instruction.html).
+    pub fn process_bid_order_flow(
+        program_id: &Pubkey,
+        amount: u64,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let source_info = next_account_info(account_info_iter)?;
+        let bid_token_info = next_account_info(account_info_iter)?;
+        let refund_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+        let clock_sysvar_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.is_paused {
+            return Err(SwapError::IsPaused.into());
+        }
+        if !token_swap.is_order_flow_auction_enabled() {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if *bid_token_info.key != token_swap.order_flow_bid_token {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+
+        let clock = Clock::from_account_info(clock_sysvar_info)?;
+        token_swap.settle_order_flow_auction(clock.slot);
+        if token_swap.is_order_flow_backrun_slot(clock.slot) {
+            return Err(SwapError::OrderFlowAuctionClosed.into());
+        }
+        if amount <= token_swap.order_flow_bid {
+            return Err(SwapError::OrderFlowBidTooLow.into());
+        }
+        if token_swap.order_flow_bid != 0 {
+            if *refund_info.key != token_swap.order_flow_refund_account {
+                return Err(SwapError::InvalidInput.into());
+            }
+            Self::token_transfer_with_mint(
+                swap_info.key,
+                token_program_info.clone(),
+                bid_token_info.clone(),
+                Self::token_mint(bid_token_info, accounts)?,
+                refund_info.clone(),
+                authority_info.clone(),
+                token_swap.nonce,
+                token_swap.order_flow_bid,
+            )?;
+        }
+        let source_account = utils::unpack_token_account(&source_info.data.borrow())?;
+        Self::user_token_transfer(
+            swap_info.key,
+            token_program_info.clone(),
+            source_info.clone(),
+            Self::token_mint(source_info, accounts)?,
+            bid_token_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            amount,
+        )?;
+
+        token_swap.order_flow_batch = token_swap
+            .order_flow_batch(clock.slot)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.order_flow_bidder = source_account.owner;
+        token_swap.order_flow_refund_account = *source_info.key;
+        token_swap.order_flow_bid = amount;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [CommitReflectionRoot](enum.Instruction.html).
+    pub fn process_commit_reflection_root(
+        program_id: &Pubkey,
+        merkle_root: [u8; 32],
+        total_amount: u64,
+        nonce: u8,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let distributor_info = next_account_info(account_info_iter)?;
+        let distribution_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+
+        let mut token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if token_swap.reflection_distributor == Pubkey::default()
+            || *distributor_info.key != token_swap.reflection_distributor
+            || !distributor_info.is_signer
+        {
+            return Err(SwapError::Unauthorized.into());
+        }
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        // The pool reserves, the fees accrued to reflection positions and the fees
+        // set aside for buybacks are never distributed
+        if *vault_info.key == token_swap.token_a
+            || *vault_info.key == token_swap.token_b
+            || (token_swap.is_reflection_enabled()
+                && (*vault_info.key == token_swap.reflection_vault_a
+                    || *vault_info.key == token_swap.reflection_vault_b))
+            || (token_swap.is_buyback_enabled()
+                && (*vault_info.key == token_swap.buyback_vault_a
+                    || *vault_info.key == token_swap.buyback_vault_b))
+        {
+            return Err(SwapError::IncorrectSwapAccount.into());
+        }
+        let vault = utils::unpack_token_account(&vault_info.data.borrow())?;
+        if vault.owner != *authority_info.key {
+            return Err(SwapError::InvalidOwner.into());
+        }
+        if total_amount > vault.amount {
+            return Err(SwapError::InvalidInput.into());
+        }
+
+        let epoch_bytes = token_swap.reflection_epoch.to_le_bytes();
+        let distribution_signature_seeds = [
+            REFLECTION_DISTRIBUTION_SEED,
+            swap_info.key.as_ref(),
+            &epoch_bytes,
+            &[nonce],
+        ];
+        let distribution_key =
+            Pubkey::create_program_address(&distribution_signature_seeds, program_id)
+                .or(Err(SwapError::InvalidProgramAddress))?;
+        if *distribution_info.key != distribution_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                distributor_info.key,
+                distribution_info.key,
+                rent.minimum_balance(ReflectionDistribution::LEN),
+                ReflectionDistribution::LEN as u64,
+                program_id,
+            ),
+            &[
+                distributor_info.clone(),
+                distribution_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&distribution_signature_seeds],
+        )?;
+
+        let distribution = ReflectionDistribution {
+            is_initialized: true,
+            swap: *swap_info.key,
+            epoch: token_swap.reflection_epoch,
+            vault: *vault_info.key,
+            merkle_root,
+            total_amount,
+            claimed_amount: 0,
+        };
+        ReflectionDistribution::pack(distribution, &mut distribution_info.data.borrow_mut())?;
+
+        token_swap.reflection_epoch = token_swap
+            .reflection_epoch
+            .checked_add(1)
+            .ok_or(SwapError::CalculationFailure)?;
+        token_swap.bump_sequence();
+        SwapInfo::pack(token_swap, &mut swap_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes a [ClaimReflectionWithProof](enum.Instruction.html).
+    pub fn process_claim_reflection_with_proof(
+        program_id: &Pubkey,
+        index: u64,
+        amount: u64,
+        nonce: u8,
+        proof: &[[u8; 32]],
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let account_info_iter = &mut accounts.iter();
+        let swap_info = next_account_info(account_info_iter)?;
+        let authority_info = next_account_info(account_info_iter)?;
+        let distribution_info = next_account_info(account_info_iter)?;
+        let vault_info = next_account_info(account_info_iter)?;
+        let claimant_info = next_account_info(account_info_iter)?;
+        let claim_info = next_account_info(account_info_iter)?;
+        let destination_info = next_account_info(account_info_iter)?;
+        let system_program_info = next_account_info(account_info_iter)?;
+        let rent_sysvar_info = next_account_info(account_info_iter)?;
+        let token_program_info = next_account_info(account_info_iter)?;
+
+        let token_swap = SwapInfo::unpack(&swap_info.data.borrow())?;
+        if *authority_info.key != utils::authority_id(program_id, swap_info.key, token_swap.nonce)?
+        {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        if distribution_info.owner != program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let mut distribution = ReflectionDistribution::unpack(&distribution_info.data.borrow())?;
+        if distribution.swap != *swap_info.key || distribution.vault != *vault_info.key {
+            return Err(SwapError::InvalidInput.into());
+        }
+        if !claimant_info.is_signer {
+            return Err(SwapError::Unauthorized.into());
+        }
+        let leaf = merkle::reflection_leaf(index, claimant_info.key, amount);
+        if !merkle::verify(proof, &distribution.merkle_root, leaf) {
+            return Err(SwapError::InvalidMerkleProof.into());
+        }
+        distribution.claimed_amount = distribution
+            .claimed_amount
+            .checked_add(amount)
+            .filter(|claimed_amount| *claimed_amount <= distribution.total_amount)
+            .ok_or(SwapError::InvalidInput)?;
+
+        let index_bytes = index.to_le_bytes();
+        let claim_signature_seeds = [
+            REFLECTION_CLAIM_SEED,
+            distribution_info.key.as_ref(),
+            &index_bytes,
+            &[nonce],
+        ];
+        let claim_key = Pubkey::create_program_address(&claim_signature_seeds, program_id)
+            .or(Err(SwapError::InvalidProgramAddress))?;
+        if *claim_info.key != claim_key {
+            return Err(SwapError::InvalidProgramAddress.into());
+        }
+        // Creating the claim account fails if the entitlement was already claimed
+        let rent = Rent::from_account_info(rent_sysvar_info)?;
+        invoke_signed(
+            &system_instruction::create_account(
+                claimant_info.key,
+                claim_info.key,
+                rent.minimum_balance(ReflectionClaim::LEN),
+                ReflectionClaim::LEN as u64,
+                program_id,
+            ),
+            &[
+                claimant_info.clone(),
+                claim_info.clone(),
+                system_program_info.clone(),
+            ],
+            &[&claim_signature_seeds],
+        )?;
+        let claim = ReflectionClaim {
+            is_initialized: true,
+            distribution: *distribution_info.key,
+            index,
+            claimant: *claimant_info.key,
+            amount,
+        };
+        ReflectionClaim::pack(claim, &mut claim_info.data.borrow_mut())?;
+
+        Self::token_transfer_with_mint(
+            swap_info.key,
+            token_program_info.clone(),
+            vault_info.clone(),
+            Self::token_mint(vault_info, accounts)?,
+            destination_info.clone(),
+            authority_info.clone(),
+            token_swap.nonce,
+            amount,
+        )?;
+        ReflectionDistribution::pack(distribution, &mut distribution_info.data.borrow_mut())?;
+        Ok(())
+    }
+
+    /// Processes an [Instruction](enum.Instruction.html).
+    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+        #[cfg(feature = "compute-units")]
+        solana_program::log::sol_log_compute_units();
+        #[cfg(feature = "shadow-ledger")]
+        let snapshot = crate::ledger::Snapshot::take(accounts);
+        let instruction = AdminInstruction::unpack(input)?;
+        let result = match instruction {
+            None => Self::process_swap_instruction(program_id, accounts, input),
+            Some(admin_instruction) => {
+                process_admin_instruction(&admin_instruction, program_id, accounts)
+                    .and_then(|()| record_admin_action(program_id, input[0], accounts))
+            }
+        };
+        #[cfg(feature = "shadow-ledger")]
+        if result.is_ok() {
+            snapshot.check(accounts);
+        }
+        if let Err(ProgramError::Custom(error_code)) = result {
+            Self::log_custom_error_message(program_id, accounts, error_code);
+        }
+        #[cfg(feature = "compute-units")]
+        solana_program::log::sol_log_compute_units();
+        result
+    }
+
+    /// Logs the message the admin registered for `error_code` in the swap the
+    /// failed instruction was sent to, if any.
+    fn log_custom_error_message(program_id: &Pubkey, accounts: &[AccountInfo], error_code: u32) {
+        let swap_info = match accounts.first() {
+            Some(swap_info)
+                if swap_info.owner == program_id && swap_info.data_len() == SwapInfo::LEN =>
+            {
+                swap_info
+            }
+            _ => return,
+        };
+        let token_swap = match swap_info
+            .try_borrow_data()
+            .ok()
+            .and_then(|data| SwapInfo::unpack(&data).ok())
+        {
+            Some(token_swap) => token_swap,
+            None => return,
+        };
+        if let Some(message) = token_swap.custom_error_messages.message(error_code) {
+            msg!("Pool message: {}", message);
+        }
+    }
+
+    fn process_swap_instruction(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        input: &[u8],
+    ) -> ProgramResult {
+        let instruction = SwapInstruction::unpack(input)?;
+        match instruction {
+            SwapInstruction::Initialize(InitializeData {
+                nonce,
+                amp_factor,
+                fees,
+                access_window_slots,
+                namespace,
+                fee_tier,
+                marketing_wallet,
+                developer_wallet,
+                curve_type,
+                price_bound,
+                creator_window_slots,
+                freeze_authority_acknowledged,
+            }) => {
+                log_info!("Instruction: Init");
+                Self::process_initialize(
+                    program_id,
+                    nonce,
+                    amp_factor,
+                    fees,
+                    access_window_slots,
+                    namespace,
+                    fee_tier,
+                    marketing_wallet,
+                    developer_wallet,
+                    curve_type,
+                    price_bound,
+                    creator_window_slots,
+                    freeze_authority_acknowledged,
+                    accounts,
+                )
+            }
+            SwapInstruction::Swap(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                log_info!("Instruction: Swap");
+                Self::process_swap(
+                    program_id,
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    expected_sequence,
+                    accounts,
+                )
+            }
+            SwapInstruction::Deposit(DepositData {
+                token_a_amount,
+                token_b_amount,
+                min_mint_amount,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                log_info!("Instruction: Deposit");
+                Self::process_deposit(
+                    program_id,
+                    token_a_amount,
+                    token_b_amount,
+                    min_mint_amount,
+                    deadline_slot,
+                    expected_sequence,
+                    accounts,
+                )
+            }
+            SwapInstruction::Withdraw(WithdrawData {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                log_info!("Instruction: Withdraw");
+                Self::process_withdraw(
+                    program_id,
+                    pool_token_amount,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                    deadline_slot,
+                    expected_sequence,
+                    accounts,
+                )
+            }
+            SwapInstruction::WithdrawOne(WithdrawOneData {
+                pool_token_amount,
+                minimum_token_amount,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                log_info!("Instruction: Withdraw One");
+                Self::process_withdraw_one(
+                    program_id,
+                    pool_token_amount,
+                    minimum_token_amount,
+                    deadline_slot,
+                    expected_sequence,
+                    accounts,
+                )
+            }
+            SwapInstruction::SwapWithReceipt(SwapWithReceiptData {
+                amount_in,
+                minimum_amount_out,
+                receipt_id,
+                receipt_nonce,
+                expected_sequence,
+            }) => {
+                log_info!("Instruction: Swap With Receipt");
+                Self::process_swap_with_receipt(
+                    program_id,
+                    amount_in,
+                    minimum_amount_out,
+                    receipt_id,
+                    receipt_nonce,
+                    expected_sequence,
+                    accounts,
+                )
+            }
+            SwapInstruction::CloseSwapReceipt => {
+                log_info!("Instruction: Close Swap Receipt");
+                Self::process_close_swap_receipt(program_id, accounts)
+            }
+            SwapInstruction::HarvestAdminFees => {
+                log_info!("Instruction: Harvest Admin Fees");
+                Self::process_harvest_admin_fees(program_id, accounts)
+            }
+            SwapInstruction::CommitReflectionRoot(CommitReflectionRootData {
+                merkle_root,
+                total_amount,
+                nonce,
+            }) => {
+                log_info!("Instruction: Commit Reflection Root");
+                Self::process_commit_reflection_root(
+                    program_id,
+                    merkle_root,
+                    total_amount,
+                    nonce,
+                    accounts,
+                )
+            }
+            SwapInstruction::ClaimReflectionWithProof(ClaimReflectionData {
+                index,
+                amount,
+                nonce,
+                proof,
+            }) => {
+                log_info!("Instruction: Claim Reflection With Proof");
+                Self::process_claim_reflection_with_proof(
+                    program_id, index, amount, nonce, &proof, accounts,
+                )
+            }
+            SwapInstruction::ClaimVestedDevFees => {
+                log_info!("Instruction: Claim Vested Dev Fees");
+                Self::process_claim_vested_dev_fees(program_id, accounts)
+            }
+            SwapInstruction::DepositGovernanceTokens(DepositGovernanceTokensData {
+                amount,
+                deposit_nonce,
+                record_nonce,
+            }) => {
+                log_info!("Instruction: Deposit Governance Tokens");
+                Self::process_deposit_governance_tokens(
+                    program_id,
+                    amount,
+                    deposit_nonce,
+                    record_nonce,
+                    accounts,
+                )
+            }
+            SwapInstruction::WithdrawGovernanceTokens(amount) => {
+                log_info!("Instruction: Withdraw Governance Tokens");
+                Self::process_withdraw_governance_tokens(program_id, amount, accounts)
+            }
+            SwapInstruction::BeginWithdraw(BeginWithdrawData {
+                pool_token_amount,
+                minimum_token_a_amount,
+                minimum_token_b_amount,
+                nonce,
+                expected_sequence,
+            }) => {
+                log_info!("Instruction: Begin Withdraw");
+                Self::process_begin_withdraw(
+                    program_id,
+                    pool_token_amount,
+                    minimum_token_a_amount,
+                    minimum_token_b_amount,
+                    nonce,
+                    expected_sequence,
+                    accounts,
+                )
+            }
+            SwapInstruction::ContinueWithdraw => {
+                log_info!("Instruction: Continue Withdraw");
+                Self::process_continue_withdraw(program_id, accounts)
+            }
+            SwapInstruction::EmitPoolSummary => {
+                log_info!("Instruction: Emit Pool Summary");
+                Self::process_emit_pool_summary(program_id, accounts)
+            }
+            SwapInstruction::CreateVeLock(CreateVeLockData {
+                amount,
+                lock_duration,
+                nonce,
+            }) => {
+                log_info!("Instruction: Create Ve Lock");
+                Self::process_create_ve_lock(program_id, amount, lock_duration, nonce, accounts)
+            }
+            SwapInstruction::ClaimVeFees => {
+                log_info!("Instruction: Claim Ve Fees");
+                Self::process_claim_ve_fees(program_id, accounts)
+            }
+            SwapInstruction::WithdrawVeLock => {
+                log_info!("Instruction: Withdraw Ve Lock");
+                Self::process_withdraw_ve_lock(program_id, accounts)
+            }
+            SwapInstruction::VoteGaugeWeight(VoteGaugeWeightData { weight_bps, nonce }) => {
+                log_info!("Instruction: Vote Gauge Weight");
+                Self::process_vote_gauge_weight(program_id, weight_bps, nonce, accounts)
+            }
+            SwapInstruction::CheckpointGauge => {
+                log_info!("Instruction: Checkpoint Gauge");
+                Self::process_checkpoint_gauge(program_id, accounts)
+            }
+            SwapInstruction::BidOrderFlow(amount) => {
+                log_info!("Instruction: Bid Order Flow");
+                Self::process_bid_order_flow(program_id, amount, accounts)
+            }
+            SwapInstruction::SimulateSwap(SwapData {
+                amount_in,
+                minimum_amount_out,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                log_info!("Instruction: Simulate Swap");
+                Self::process_simulate_swap(
+                    program_id,
+                    amount_in,
+                    minimum_amount_out,
+                    deadline_slot,
+                    expected_sequence,
+                    accounts,
+                )
+            }
+            SwapInstruction::CoverShortfall(CoverShortfallData { amount_a, amount_b }) => {
+                log_info!("Instruction: Cover Shortfall");
+                Self::process_cover_shortfall(amount_a, amount_b, accounts)
+            }
+            SwapInstruction::ClonePool(ClonePoolData {
+                nonce,
+                namespace,
+                fee_tier,
+                freeze_authority_acknowledged,
+            }) => {
+                log_info!("Instruction: Clone Pool");
+                Self::process_clone_pool(
+                    program_id,
+                    nonce,
+                    namespace,
+                    fee_tier,
+                    freeze_authority_acknowledged,
+                    accounts,
+                )
+            }
+            SwapInstruction::SweepDust => {
+                log_info!("Instruction: Sweep Dust");
+                Self::process_sweep_dust(program_id, accounts)
+            }
+            SwapInstruction::DepositLiquidityPosition(DepositLiquidityPositionData {
+                amount,
+                nonce,
+            }) => {
+                log_info!("Instruction: Deposit Liquidity Position");
+                Self::process_deposit_liquidity_position(program_id, amount, nonce, accounts)
+            }
+            SwapInstruction::WithdrawLiquidityPosition(amount) => {
+                log_info!("Instruction: Withdraw Liquidity Position");
+                Self::process_withdraw_liquidity_position(program_id, amount, accounts)
+            }
+            SwapInstruction::CheckpointLiquidityPosition => {
+                log_info!("Instruction: Checkpoint Liquidity Position");
+                Self::process_checkpoint_liquidity_position(program_id, accounts)
+            }
+            SwapInstruction::ReportEpochFees => {
+                log_info!("Instruction: Report Epoch Fees");
+                Self::process_report_epoch_fees(program_id, accounts)
+            }
+            SwapInstruction::SwapOut(SwapOutData {
+                amount_out,
+                maximum_amount_in,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                log_info!("Instruction: Swap Out");
+                Self::process_swap_out(
+                    program_id,
+                    amount_out,
+                    maximum_amount_in,
+                    deadline_slot,
+                    expected_sequence,
+                    accounts,
+                )
+            }
+            SwapInstruction::GetPoolStatus => {
+                log_info!("Instruction: Get Pool Status");
+                Self::process_get_pool_status(program_id, accounts)
+            }
+            SwapInstruction::Route(RouteData {
+                amount_in,
+                minimum_amount_out,
+                leg_account_counts,
+            }) => {
+                log_info!("Instruction: Route");
+                Self::process_route(
+                    program_id,
+                    amount_in,
+                    minimum_amount_out,
+                    &leg_account_counts,
+                    accounts,
+                )
+            }
+            SwapInstruction::CheckFeeDestinations => {
+                log_info!("Instruction: Check Fee Destinations");
+                Self::process_check_fee_destinations(program_id, accounts)
+            }
+            SwapInstruction::CreateTwapOrder(ref data) => {
+                log_info!("Instruction: Create Twap Order");
+                Self::process_create_twap_order(program_id, data, accounts)
+            }
+            SwapInstruction::ExecuteTwapSlice => {
+                log_info!("Instruction: Execute Twap Slice");
+                Self::process_execute_twap_slice(program_id, accounts)
+            }
+            SwapInstruction::CancelTwapOrder => {
+                log_info!("Instruction: Cancel Twap Order");
+                Self::process_cancel_twap_order(program_id, accounts)
+            }
+            SwapInstruction::CreateTrigger(ref data) => {
+                log_info!("Instruction: Create Trigger");
+                Self::process_create_trigger(program_id, data, accounts)
+            }
+            SwapInstruction::ExecuteTrigger => {
+                log_info!("Instruction: Execute Trigger");
+                Self::process_execute_trigger(program_id, accounts)
+            }
+            SwapInstruction::CancelTrigger => {
+                log_info!("Instruction: Cancel Trigger");
+                Self::process_cancel_trigger(program_id, accounts)
+            }
+            SwapInstruction::CreateDripDeposit(ref data) => {
+                log_info!("Instruction: Create Drip Deposit");
+                Self::process_create_drip_deposit(program_id, data, accounts)
+            }
+            SwapInstruction::DepositDripTranche => {
+                log_info!("Instruction: Deposit Drip Tranche");
+                Self::process_deposit_drip_tranche(program_id, accounts)
+            }
+            SwapInstruction::CancelDripDeposit => {
+                log_info!("Instruction: Cancel Drip Deposit");
+                Self::process_cancel_drip_deposit(program_id, accounts)
+            }
+            SwapInstruction::DepositReflectionPosition(DepositReflectionPositionData {
+                amount,
+                nonce,
+            }) => {
+                log_info!("Instruction: Deposit Reflection Position");
+                Self::process_deposit_reflection_position(program_id, amount, nonce, accounts)
+            }
+            SwapInstruction::WithdrawReflectionPosition(amount) => {
+                log_info!("Instruction: Withdraw Reflection Position");
+                Self::process_withdraw_reflection_position(program_id, amount, accounts)
+            }
+            SwapInstruction::ClaimReflection => {
+                log_info!("Instruction: Claim Reflection");
+                Self::process_claim_reflection(program_id, accounts)
+            }
+            SwapInstruction::ExecuteBuyback(minimum_amount_out) => {
+                log_info!("Instruction: Execute Buyback");
+                Self::process_execute_buyback(program_id, minimum_amount_out, accounts)
+            }
+            SwapInstruction::WithdrawMarketingFees => {
+                log_info!("Instruction: Withdraw Marketing Fees");
+                Self::process_withdraw_wallet_fees(program_id, FeeBucket::Marketing, accounts)
+            }
+            SwapInstruction::WithdrawDeveloperFees => {
+                log_info!("Instruction: Withdraw Developer Fees");
+                Self::process_withdraw_wallet_fees(program_id, FeeBucket::Developer, accounts)
+            }
+            SwapInstruction::InitializeGlobalStats(nonce) => {
+                log_info!("Instruction: Initialize Global Stats");
+                Self::process_initialize_global_stats(program_id, nonce, accounts)
+            }
+            SwapInstruction::CreateReferralAccrual(nonce) => {
+                log_info!("Instruction: Create Referral Accrual");
+                Self::process_create_referral_accrual(program_id, nonce, accounts)
+            }
+            SwapInstruction::ClaimReferralFees => {
+                log_info!("Instruction: Claim Referral Fees");
+                Self::process_claim_referral_fees(program_id, accounts)
+            }
+            SwapInstruction::DepositOne(DepositOneData {
+                token_amount,
+                min_mint_amount,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                log_info!("Instruction: Deposit One");
+                Self::process_deposit_one(
+                    program_id,
+                    token_amount,
+                    min_mint_amount,
+                    deadline_slot,
+                    expected_sequence,
+                    accounts,
+                )
+            }
+            SwapInstruction::WithdrawImbalanced(WithdrawImbalancedData {
+                token_a_amount,
+                token_b_amount,
+                maximum_pool_token_amount,
+                deadline_slot,
+                expected_sequence,
+            }) => {
+                log_info!("Instruction: Withdraw Imbalanced");
+                Self::process_withdraw_imbalanced(
+                    program_id,
+                    token_a_amount,
+                    token_b_amount,
+                    maximum_pool_token_amount,
+                    deadline_slot,
+                    expected_sequence,
+                    accounts,
+                )
+            }
+            SwapInstruction::Stake(StakeData { amount, nonce }) => {
+                log_info!("Instruction: Stake");
+                Self::process_stake(program_id, amount, nonce, accounts)
+            }
+            SwapInstruction::Unstake(amount) => {
+                log_info!("Instruction: Unstake");
+                Self::process_unstake(program_id, amount, accounts)
+            }
+            SwapInstruction::HarvestRewards => {
+                log_info!("Instruction: Harvest Rewards");
+                Self::process_harvest_rewards(program_id, accounts)
+            }
+        }
+    }
+}
+
+impl PrintProgramError for SwapError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    {
+        match self {
+            SwapError::AlreadyInUse => msg!("Error: Swap account already in use"),
+            SwapError::InvalidAdmin => {
+                msg!("Error: Address of the admin fee account is incorrect")
+            }
+            SwapError::InvalidOwner => {
+                msg!("Error: The input account owner is not the program address")
+            }
+            SwapError::InvalidOutputOwner => {
+                msg!("Error: Output pool account owner cannot be the program address")
+            }
+            SwapError::InvalidProgramAddress => {
+                msg!("Error: Invalid program address generated from nonce and key")
+            }
+            SwapError::ExpectedMint => {
+                msg!("Error: Deserialized account is not an SPL Token mint")
+            }
+            SwapError::ExpectedAccount => {
+                msg!("Error: Deserialized account is not an SPL Token account")
+            }
+            SwapError::EmptySupply => msg!("Error: Input token account empty"),
+            SwapError::EmptyPool => msg!("Error: Pool token supply is 0"),
+            SwapError::InvalidSupply => msg!("Error: Pool token mint has a non-zero supply"),
+            SwapError::RepeatedMint => msg!("Error: Swap input token accounts have the same mint"),
+            SwapError::InvalidDelegate => msg!("Error: Token account has a delegate"),
+            SwapError::InvalidInput => msg!("Error: InvalidInput"),
+            SwapError::IncorrectSwapAccount => {
+                msg!("Error: Address of the provided swap token account is incorrect")
+            }
+            SwapError::IncorrectMint => {
+                msg!("Error: Address of the provided token mint is incorrect")
+            }
+            SwapError::CalculationFailure => msg!("Error: CalculationFailure"),
+            SwapError::InvalidInstruction => msg!("Error: InvalidInstruction"),
+            SwapError::ExceededSlippage => {
+                msg!("Error: Swap instruction exceeds desired slippage limit")
+            }
+            SwapError::InvalidCloseAuthority => msg!("Error: Token account has a close authority"),
+            SwapError::InvalidFreezeAuthority => {
+                msg!("Error: Pool token mint has a freeze authority")
+            }
+            SwapError::ConversionFailure => msg!("Error: Conversion to or from u64 failed"),
+            SwapError::Unauthorized => {
+                msg!("Error: Account is not authorized to execute this instruction")
+            }
+            SwapError::IsPaused => msg!("Error: Swap pool is paused"),
+            SwapError::RampLocked => msg!("Error: Ramp is locked in this time period"),
+            SwapError::InsufficientRampTime => msg!("Error: Insufficient ramp time"),
+            SwapError::ActiveTransfer => msg!("Error: Active admin transfer in progress"),
+            SwapError::NoActiveTransfer => msg!("Error: No active admin transfer in progress"),
+            SwapError::AdminDeadlineExceeded => msg!("Error: Admin transfer deadline exceeded"),
+            SwapError::MismatchedDecimals => msg!("Error: Token mints must have same decimals"),
+            SwapError::AccessTokenRequired => {
+                msg!("Error: Access token required during the launch window")
+            }
+            SwapError::AdminSessionExpired => msg!("Error: Admin session expired"),
+            SwapError::InvalidMerkleProof => msg!("Error: Invalid merkle proof"),
+            SwapError::GovernanceDepositLocked => msg!("Error: Governance deposit is locked"),
+            SwapError::SnapshotRestoreLocked => {
+                msg!("Error: Parameter snapshot is not restorable yet")
+            }
+            SwapError::AdminHeartbeatActive => msg!("Error: Admin heartbeat has not timed out"),
+            SwapError::SwapAmountTooLarge => {
+                msg!("Error: Swap amount exceeds the pool maximum, split the order into smaller swaps")
+            }
+            SwapError::LpSoulbound => msg!("Error: Pool tokens are non-transferable"),
+            SwapError::VeLockActive => msg!("Error: Vote-escrow lock is active"),
+            SwapError::GaugeVoteActive => msg!("Error: Vote-escrow lock has gauge votes"),
+            SwapError::OrderFlowBidTooLow => msg!("Error: Order-flow bid too low"),
+            SwapError::OrderFlowAuctionClosed => {
+                msg!("Error: Order-flow auction is closed for the batch")
+            }
+            SwapError::OrderFlowSlotReserved => {
+                msg!("Error: Slot is reserved to the order-flow auction winner")
+            }
+            SwapError::OrderFlowBidPending => msg!("Error: Order-flow bid is pending"),
+            SwapError::DynamicAmpEnabled => msg!("Error: Dynamic amp is enabled"),
+            SwapError::SwapSimulated => msg!("Error: Swap simulated, changes reverted"),
+            SwapError::InsufficientInsuranceFund => msg!("Error: Insufficient insurance fund"),
+            SwapError::MintAmountTooSmall => msg!("Error: Deposit mint amount too small"),
+            SwapError::NotDust => msg!("Error: Balance exceeds the dust threshold"),
+            SwapError::DustSweepLimit => msg!("Error: Dust sweeps of the slot exhausted"),
+            SwapError::SequenceMismatch => {
+                msg!("Error: Pool changed since the instruction was quoted")
+            }
+            SwapError::CustodianSignatureRequired => {
+                msg!("Error: Withdrawal above the threshold requires the custodian signature")
+            }
+            SwapError::CredentialRequired => msg!("Error: Credential of the pool issuer required"),
+            SwapError::IncorrectTokenProgramId => msg!("Error: Incorrect token program id"),
+            SwapError::TwapSliceNotDue => msg!("Error: No TWAP order slice is due"),
+            SwapError::TriggerNotReached => msg!("Error: Trigger price not reached"),
+            SwapError::OracleStale => msg!("Error: Oracle price is stale"),
+            SwapError::DripTrancheNotDue => msg!("Error: No drip deposit tranche is due"),
+            SwapError::InsufficientProtocolLiquidity => {
+                msg!("Error: Withdrawal exceeds the protocol-owned liquidity")
+            }
+            SwapError::NoPendingFees => msg!("Error: No pending fees"),
+            SwapError::PendingFeesNotDue => msg!("Error: Pending fees not applicable yet"),
+            SwapError::InvalidFees => msg!("Error: Invalid fees"),
+            SwapError::OutputAmountMismatch => msg!("Error: Output amount mismatch"),
+            SwapError::DeadlineExceeded => msg!("Error: Deadline exceeded"),
+            SwapError::CreatorDepositWindow => {
+                msg!("Error: Only the pool creator may deposit during the first deposit window")
+            }
+            SwapError::DepositorNotAllowed => {
+                msg!("Error: Depositor is not on the deposit allowlist of the pool")
+            }
+            SwapError::FreezeAuthorityNotAcknowledged => {
+                msg!("Error: Token mint has a freeze authority that was not acknowledged")
+            }
+            SwapError::VaultFrozen => msg!("Error: Swap token account is frozen"),
+            SwapError::InvalidOracle => msg!("Error: Invalid oracle account"),
+            SwapError::OracleConfidenceTooWide => {
+                msg!("Error: Oracle confidence interval too wide")
+            }
+            SwapError::OraclePriceDeviation => {
+                msg!("Error: Swap price deviates from the oracle price")
+            }
+            SwapError::AuthorityOwnedSource => {
+                msg!("Error: Source account is owned by the swap authority")
+            }
+            SwapError::SelfReferral => msg!("Error: Swap referred by its own user"),
+            SwapError::CanonicalNamespaceReserved => {
+                msg!("Error: Only the upgrade authority creates pools in the canonical namespace")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        curve::{SwapResultV2, MIN_RAMP_DURATION},
+        fees::{FEE_TIER_DENOMINATOR, MAX_TRADE_FEE_BPS},
+        instruction::{
+            close_swap_receipt, create_referral_accrual, deposit, execute_buyback,
+            initialize_global_stats, swap, swap_with_receipt, withdraw, withdraw_one,
+        },
+        state::{reflection_owed, time_weighted_liquidity, MAX_DUST_SWEEPS_PER_SLOT},
+        twap::PRICE_SCALE,
+        utils::test_utils::*,
+    };
+    use solana_sdk::account::Account;
+    use spl_token::{
+        error::TokenError,
+        instruction::{approve, mint_to, revoke, set_authority, AuthorityType},
+    };
+
+    /// Initial amount of pool tokens for swap contract, hard-coded to something
+    /// "sensible" given a maximum of u64.
+    /// Note that on Ethereum, Uniswap uses the geometric mean of all provided
+    /// input amounts, and Balancer uses 100 * 10 ^ 18.
+    const INITIAL_SWAP_POOL_AMOUNT: u64 = 1_000_000_000;
+
+    fn assert_fee_accounts(fee_accounts: &FeeAccounts, result: &SwapResultV2) {
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        assert_eq!(
+            amount(&fee_accounts.reflection_account),
+            U256::to_u64(result.reflection_fee).unwrap()
+        );
+        assert_eq!(
+            amount(&fee_accounts.buyback_account),
+            U256::to_u64(result.buyback_fee).unwrap()
+        );
+        assert_eq!(
+            amount(&fee_accounts.marketing_account),
+            U256::to_u64(result.marketing_fee).unwrap()
+        );
+        assert_eq!(
+            amount(&fee_accounts.developer_account),
+            U256::to_u64(result.developer_fee).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_token_program_id_error() {
+        let swap_key = pubkey_rand();
+        let mut mint = (pubkey_rand(), Account::default());
+        let mut destination = (pubkey_rand(), Account::default());
+        let token_program = (TOKEN_PROGRAM_ID, Account::default());
+        let (authority_key, nonce) =
+            Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
+        let mut authority = (authority_key, Account::default());
+        let swap_bytes = swap_key.to_bytes();
+        let authority_signature_seeds = [&swap_bytes[..32], &[nonce]];
+        let signers = &[&authority_signature_seeds[..]];
+        let ix = mint_to(
+            &token_program.0,
+            &mint.0,
+            &destination.0,
+            &authority.0,
+            &[],
+            10,
+        )
+        .unwrap();
+        let mint = (&mut mint).into();
+        let destination = (&mut destination).into();
+        let authority = (&mut authority).into();
+
+        let err = invoke_signed(&ix, &[mint, destination, authority], signers).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidAccountData);
+    }
+
+    #[test]
+    fn test_check_amount_received() {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            amount: 1_010,
+            state: spl_token::state::AccountState::Initialized,
+            ..spl_token::state::Account::default()
+        }
+        .pack_into_slice(&mut data);
+        let mut destination = (
+            pubkey_rand(),
+            Account {
+                data,
+                owner: TOKEN_PROGRAM_ID,
+                ..Account::default()
+            },
+        );
+        let destination_info = (&mut destination).into();
+
+        assert_eq!(
+            Processor::check_amount_received(&destination_info, 1_000, 10),
+            Ok(())
+        );
+        // shorted by a transfer fee or hook
+        assert_eq!(
+            Processor::check_amount_received(&destination_info, 1_000, 11),
+            Err(SwapError::OutputAmountMismatch.into())
+        );
+        assert_eq!(
+            Processor::check_amount_received(&destination_info, 1_020, 0),
+            Err(SwapError::OutputAmountMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_initialize() {
+        let user_key = pubkey_rand();
+        let amp_factor = MIN_AMP;
+        let token_a_amount = 1000;
+        let token_b_amount = 2000;
+        let pool_token_amount = 10;
+        let mut accounts = SwapAccountInfo::new_with_pool_seeds(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+            FeeTier::Custom,
+            [4u8; 32],
+        );
+        // wrong nonce for authority_key
+        {
+            let old_nonce = accounts.nonce;
+            accounts.nonce = old_nonce - 1;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.initialize_swap()
+            );
+            accounts.nonce = old_nonce;
+        }
+
+        // invalid amp factors
+        {
+            let old_initial_amp_factor = accounts.initial_amp_factor;
+            accounts.initial_amp_factor = MIN_AMP - 1;
+            // amp factor too low
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.initialize_swap()
+            );
+            accounts.initial_amp_factor = MAX_AMP + 1;
+            // amp factor too high
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.initialize_swap()
+            );
+            accounts.initial_amp_factor = old_initial_amp_factor;
+        }
+
+        // invalid fees
+        {
+            let old_fees = accounts.fees;
+            accounts.fees = Fees {
+                trade_fee_numerator: MAX_TRADE_FEE_BPS + 1,
+                trade_fee_denominator: FEE_TIER_DENOMINATOR,
+                ..old_fees
+            };
+            assert_eq!(
+                Err(SwapError::InvalidFees.into()),
+                accounts.initialize_swap()
+            );
+            accounts.fees = old_fees;
+        }
+
+        // token a account of another token program
+        {
+            let old_owner = accounts.token_a_account.owner;
+            accounts.token_a_account.owner = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::IncorrectTokenProgramId.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account.owner = old_owner;
+        }
+
+        // uninitialized token a account
+        {
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = Account::default();
+            assert_eq!(
+                Err(SwapError::ExpectedAccount.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // uninitialized token b account
+        {
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = Account::default();
+            assert_eq!(
+                Err(SwapError::ExpectedAccount.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // uninitialized pool mint
+        {
+            let old_account = accounts.pool_mint_account;
+            accounts.pool_mint_account = Account::default();
+            assert_eq!(
+                Err(SwapError::ExpectedMint.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_mint_account = old_account;
+        }
+
+        // token A account owner is not swap authority
+        {
+            let (_token_a_key, token_a_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &user_key,
+                0,
+            );
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // token B account owner is not swap authority
+        {
+            let (_token_b_key, token_b_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &user_key,
+                0,
+            );
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_b_account;
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // pool token account owner is swap authority
+        {
+            let (_pool_token_key, pool_token_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.pool_token_account;
+            accounts.pool_token_account = pool_token_account;
+            assert_eq!(
+                Err(SwapError::InvalidOutputOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_token_account = old_account;
+        }
+
+        // pool mint authority is not swap authority
+        {
+            let (_pool_mint_key, pool_mint_account) =
+                create_mint(&TOKEN_PROGRAM_ID, &user_key, DEFAULT_TOKEN_DECIMALS, None);
+            let old_mint = accounts.pool_mint_account;
+            accounts.pool_mint_account = pool_mint_account;
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_mint_account = old_mint;
+        }
+
+        // pool mint token has freeze authority
+        {
+            let (_pool_mint_key, pool_mint_account) = create_mint(
+                &TOKEN_PROGRAM_ID,
+                &accounts.authority_key,
+                DEFAULT_TOKEN_DECIMALS,
+                Some(&user_key),
+            );
+            let old_mint = accounts.pool_mint_account;
+            accounts.pool_mint_account = pool_mint_account;
+            assert_eq!(
+                Err(SwapError::InvalidFreezeAuthority.into()),
+                accounts.initialize_swap()
+            );
+            accounts.pool_mint_account = old_mint;
+        }
+
+        // empty token A account
+        {
+            let (_token_a_key, token_a_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.token_a_account;
+            accounts.token_a_account = token_a_account;
+            assert_eq!(
+                Err(SwapError::EmptySupply.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        // empty token B account
+        {
+            let (_token_b_key, token_b_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                0,
+            );
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_b_account;
+            assert_eq!(
+                Err(SwapError::EmptySupply.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // invalid pool tokens
+        {
+            let old_mint = accounts.pool_mint_account;
+            let old_pool_account = accounts.pool_token_account;
+
+            let (_pool_mint_key, pool_mint_account) = create_mint(
+                &TOKEN_PROGRAM_ID,
+                &accounts.authority_key,
+                DEFAULT_TOKEN_DECIMALS,
+                None,
+            );
+            accounts.pool_mint_account = pool_mint_account;
+
+            let (_empty_pool_token_key, empty_pool_token_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &user_key,
+                0,
+            );
+
+            let (_pool_token_key, pool_token_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &user_key,
+                pool_token_amount,
+            );
+
+            // non-empty pool token account
+            accounts.pool_token_account = pool_token_account;
+            assert_eq!(
+                Err(SwapError::InvalidSupply.into()),
+                accounts.initialize_swap()
+            );
+
+            // pool tokens already in circulation
+            accounts.pool_token_account = empty_pool_token_account;
+            assert_eq!(
+                Err(SwapError::InvalidSupply.into()),
+                accounts.initialize_swap()
+            );
+
+            accounts.pool_mint_account = old_mint;
+            accounts.pool_token_account = old_pool_account;
+        }
+
+        // token A account is delegated
+        {
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    &accounts.token_a_key,
+                    &user_key,
+                    &accounts.authority_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.token_a_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidDelegate.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                revoke(
+                    &TOKEN_PROGRAM_ID,
+                    &accounts.token_a_key,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // token B account is delegated
+        {
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    &accounts.token_b_key,
+                    &user_key,
+                    &accounts.authority_key,
+                    &[],
+                    1,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.token_b_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidDelegate.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                revoke(
+                    &TOKEN_PROGRAM_ID,
+                    &accounts.token_b_key,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_b_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // token A account has close authority
+        {
+            do_process_instruction(
+                set_authority(
+                    &TOKEN_PROGRAM_ID,
+                    &accounts.token_a_key,
+                    Some(&user_key),
+                    AuthorityType::CloseAccount,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidCloseAuthority.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                set_authority(
+                    &TOKEN_PROGRAM_ID,
+                    &accounts.token_a_key,
+                    None,
+                    AuthorityType::CloseAccount,
+                    &user_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_a_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // token B account has close authority
+        {
+            do_process_instruction(
+                set_authority(
+                    &TOKEN_PROGRAM_ID,
+                    &accounts.token_b_key,
+                    Some(&user_key),
+                    AuthorityType::CloseAccount,
+                    &accounts.authority_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_b_account, &mut Account::default()],
+            )
+            .unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidCloseAuthority.into()),
+                accounts.initialize_swap()
+            );
+
+            do_process_instruction(
+                set_authority(
+                    &TOKEN_PROGRAM_ID,
+                    &accounts.token_b_key,
+                    None,
+                    AuthorityType::CloseAccount,
+                    &user_key,
+                    &[],
+                )
+                .unwrap(),
+                vec![&mut accounts.token_b_account, &mut Account::default()],
+            )
+            .unwrap();
+        }
+
+        // mismatched admin mints
+        {
+            let (wrong_admin_fee_key, wrong_admin_fee_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &user_key,
+                0,
+            );
+
+            // wrong admin_fee_key_a
+            let old_admin_fee_account_a = accounts.admin_fee_a_account;
+            let old_admin_fee_key_a = accounts.admin_fee_a_key;
+            accounts.admin_fee_a_account = wrong_admin_fee_account.clone();
+            accounts.admin_fee_a_key = wrong_admin_fee_key;
+
+            assert_eq!(
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.initialize_swap()
+            );
+
+            accounts.admin_fee_a_account = old_admin_fee_account_a;
+            accounts.admin_fee_a_key = old_admin_fee_key_a;
+
+            // wrong admin_fee_key_b
+            let old_admin_fee_account_b = accounts.admin_fee_b_account;
+            let old_admin_fee_key_b = accounts.admin_fee_b_key;
+            accounts.admin_fee_b_account = wrong_admin_fee_account.clone();
+            accounts.admin_fee_b_key = wrong_admin_fee_key;
+
+            assert_eq!(
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.initialize_swap()
+            );
+
+            accounts.admin_fee_b_account = old_admin_fee_account_b;
+            accounts.admin_fee_b_key = old_admin_fee_key_b;
+        }
+
+        // mimatched mint decimals
+        {
+            let (bad_mint_key, mut bad_mint_account) =
+                create_mint(&TOKEN_PROGRAM_ID, &accounts.authority_key, 2, None);
+
+            // Pool mint decimal does not match
+            let old_pool_mint_key = accounts.pool_mint_key;
+            let old_pool_mint_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = bad_mint_key;
+            accounts.pool_mint_account = bad_mint_account.clone();
+
+            assert_eq!(
+                Err(SwapError::MismatchedDecimals.into()),
+                accounts.initialize_swap()
+            );
+
+            accounts.pool_mint_key = old_pool_mint_key;
+            accounts.pool_mint_account = old_pool_mint_account;
+
+            // Token a mint decimal does not match token b decimals
+            let (bad_token_key, bad_token_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &bad_mint_key,
+                &mut bad_mint_account,
+                &accounts.authority_key,
+                &accounts.authority_key,
+                10,
+            );
+
+            let old_token_a_key = accounts.token_a_key;
+            let old_token_a_account = accounts.token_a_account;
+            let old_token_a_mint_key = accounts.token_a_mint_key;
+            let old_token_a_mint_account = accounts.token_a_mint_account;
+            accounts.token_a_key = bad_token_key;
+            accounts.token_a_account = bad_token_account;
+            accounts.token_a_mint_key = bad_mint_key;
+            accounts.token_a_mint_account = bad_mint_account.clone();
+
+            assert_eq!(
+                Err(SwapError::MismatchedDecimals.into()),
+                accounts.initialize_swap()
+            );
+
+            accounts.token_a_key = old_token_a_key;
+            accounts.token_a_account = old_token_a_account;
+            accounts.token_a_mint_key = old_token_a_mint_key;
+            accounts.token_a_mint_account = old_token_a_mint_account;
+        }
+
+        // create swap with same token A and B
+        {
+            let (_token_a_repeat_key, token_a_repeat_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &accounts.authority_key,
+                10,
+            );
+            let old_account = accounts.token_b_account;
+            accounts.token_b_account = token_a_repeat_account;
+            assert_eq!(
+                Err(SwapError::RepeatedMint.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_b_account = old_account;
+        }
+
+        // not at the pool address of the mints, fee tier and namespace
+        {
+            let old_namespace = accounts.namespace;
+            accounts.namespace = [5u8; 32];
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.initialize_swap()
+            );
+            accounts.namespace = old_namespace;
+        }
+
+        // create valid swap
+        accounts.initialize_swap().unwrap();
+        assert_eq!(accounts.swap_account.owner, SWAP_PROGRAM_ID);
+        assert_eq!(
+            accounts.swap_account.lamports,
+            Rent::default().minimum_balance(SwapInfo::LEN)
+        );
+
+        // create again
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_swap()
+            );
+        }
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.is_initialized, true);
+        assert_eq!(swap_info.is_paused, false);
+        assert_eq!(swap_info.nonce, accounts.nonce);
+        assert_eq!(swap_info.initial_amp_factor, amp_factor);
+        assert_eq!(swap_info.target_amp_factor, amp_factor);
+        assert_eq!(swap_info.start_ramp_ts, ZERO_TS);
+        assert_eq!(swap_info.stop_ramp_ts, ZERO_TS);
+        assert_eq!(swap_info.future_admin_deadline, ZERO_TS);
+        assert_eq!(swap_info.future_admin_key, Pubkey::default());
+        assert_eq!(swap_info.admin_key, accounts.admin_key);
+        assert_eq!(swap_info.token_a, accounts.token_a_key);
+        assert_eq!(swap_info.token_b, accounts.token_b_key);
+        assert_eq!(swap_info.pool_mint, accounts.pool_mint_key);
+        assert_eq!(swap_info.token_a_mint, accounts.token_a_mint_key);
+        assert_eq!(swap_info.token_b_mint, accounts.token_b_mint_key);
+        assert_eq!(swap_info.admin_fee_key_a, accounts.admin_fee_a_key);
+        assert_eq!(swap_info.admin_fee_key_b, accounts.admin_fee_b_key);
+        assert_eq!(swap_info.fees, DEFAULT_TEST_FEES);
+        assert_eq!(swap_info.namespace, accounts.namespace);
+        assert_eq!(swap_info.fee_tier, FeeTier::Custom);
+        assert!(!swap_info.freeze_authority_acknowledged);
+        let token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+        assert_eq!(token_a.amount, token_a_amount);
+        let token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+        assert_eq!(token_b.amount, token_b_amount);
+        let pool_account = utils::unpack_token_account(&accounts.pool_token_account.data).unwrap();
+        let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+        assert_eq!(pool_mint.supply, pool_account.amount);
+    }
+
+    #[test]
+    fn test_initialize_pool_address() {
+        let user_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, MIN_AMP, 1_000, 2_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+        let token_a_mint = (
+            accounts.token_a_mint_key,
+            accounts.token_a_mint_account.clone(),
+        );
+        let token_b_mint = (
+            accounts.token_b_mint_key,
+            accounts.token_b_mint_account.clone(),
+        );
+        let new_pool = |token_a_mint: &(Pubkey, Account),
+                        token_b_mint: &(Pubkey, Account),
+                        fee_tier,
+                        namespace| {
+            SwapAccountInfo::new_with_mints(
+                &user_key,
+                MIN_AMP,
+                1_000,
+                2_000,
+                DEFAULT_TEST_FEES,
+                token_a_mint.clone(),
+                token_b_mint.clone(),
+                fee_tier,
+                namespace,
+            )
+        };
+
+        // a second pool for the same pair, fee tier and namespace, in either direction
+        for (token_a_mint, token_b_mint) in [
+            (&token_a_mint, &token_b_mint),
+            (&token_b_mint, &token_a_mint),
+        ] {
+            let mut pool = new_pool(token_a_mint, token_b_mint, FeeTier::Custom, [0u8; 32]);
+            assert_eq!(pool.swap_key, accounts.swap_key);
+            pool.swap_account = accounts.swap_account.clone();
+            assert_eq!(Err(SwapError::AlreadyInUse.into()), pool.initialize_swap());
+        }
+
+        // pools of the pair in other namespaces and fee tiers
+        for (fee_tier, namespace) in [
+            (FeeTier::Custom, [1u8; 32]),
+            (FeeTier::ThirtyBps, [0u8; 32]),
+        ] {
+            let mut pool = new_pool(&token_a_mint, &token_b_mint, fee_tier, namespace);
+            assert_ne!(pool.swap_key, accounts.swap_key);
+            pool.initialize_swap().unwrap();
+        }
+
+        // the canonical namespace is reserved to the upgrade authority of the program,
+        // other namespaces are open to anyone
+        let squatter_key = pubkey_rand();
+        let mut pool = new_pool(
+            &token_a_mint,
+            &token_b_mint,
+            FeeTier::FiveBps,
+            utils::CANONICAL_NAMESPACE,
+        );
+        pool.program_data_account = program_data_account(&squatter_key);
+        assert_eq!(
+            Err(SwapError::CanonicalNamespaceReserved.into()),
+            pool.initialize_swap()
+        );
+        pool.program_data_account = Account::default();
+        assert_eq!(
+            Err(SwapError::CanonicalNamespaceReserved.into()),
+            pool.initialize_swap()
+        );
+        pool.program_data_account = program_data_account(&pool.admin_key);
+        pool.initialize_swap().unwrap();
+        let mut pool = new_pool(&token_a_mint, &token_b_mint, FeeTier::FiveBps, [2u8; 32]);
+        pool.program_data_account = program_data_account(&squatter_key);
+        pool.initialize_swap().unwrap();
+    }
+
+    #[test]
+    fn test_freeze_authority_mints() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let freeze = |account: &mut Account| {
+            let mut token_account = utils::unpack_token_account(&account.data).unwrap();
+            token_account.state = spl_token::state::AccountState::Frozen;
+            token_account.pack_into_slice(&mut account.data);
+        };
+
+        // acknowledged without any freeze authority
+        {
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                MIN_AMP,
+                token_a_amount,
+                token_b_amount,
+                DEFAULT_TEST_FEES,
+            );
+            accounts.freeze_authority_acknowledged = true;
+            accounts.initialize_swap().unwrap();
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(!swap_info.freeze_authority_acknowledged);
+        }
+
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        let (_token_b_mint_key, token_b_mint_account) = create_mint(
+            &TOKEN_PROGRAM_ID,
+            &user_key,
+            DEFAULT_TOKEN_DECIMALS,
+            Some(&user_key),
+        );
+        accounts.token_b_mint_account = token_b_mint_account;
+
+        // freeze authority not acknowledged
+        assert_eq!(
+            Err(SwapError::FreezeAuthorityNotAcknowledged.into()),
+            accounts.initialize_swap()
+        );
+
+        // frozen swap token account
+        {
+            let old_account = accounts.token_a_account.clone();
+            freeze(&mut accounts.token_a_account);
+            accounts.freeze_authority_acknowledged = true;
+            assert_eq!(
+                Err(SwapError::VaultFrozen.into()),
+                accounts.initialize_swap()
+            );
+            accounts.token_a_account = old_account;
+        }
+
+        accounts.initialize_swap().unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_info.freeze_authority_acknowledged);
+
+        let amount_in = 10_000;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, amount_in, amount_in, 0);
+        let (swap_token_a_key, swap_token_b_key) = (accounts.token_a_key, accounts.token_b_key);
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in / 2,
+                0,
+            )
+            .unwrap();
+
+        // the freeze authority freezes the swap token B account, in either direction
+        freeze(&mut accounts.token_b_account);
+        assert_eq!(
+            Err(SwapError::VaultFrozen.into()),
+            accounts.swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in / 2,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::VaultFrozen.into()),
+            accounts.swap(
+                &swapper_key,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_token_b_key,
+                &swap_token_a_key,
+                &token_a_key,
+                &mut token_a_account,
+                amount_in / 2,
+                0,
+            )
+        );
+    }
+
+    #[test]
+    fn test_initialize_fee_tier() {
+        let user_key = pubkey_rand();
+        let amp_factor = MIN_AMP;
+        let token_a_amount = 1000;
+        let token_b_amount = 2000;
+        let mut accounts = SwapAccountInfo::new_with_pool_seeds(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+            FeeTier::ThirtyBps,
+            [0u8; 32],
+        );
+        accounts.initialize_swap().unwrap();
+
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.fee_tier, FeeTier::ThirtyBps);
+        assert_eq!(swap_info.fees, FeeTier::ThirtyBps.fees().unwrap());
+    }
+
+    #[test]
+    fn test_constant_product_pool() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 4_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.curve_type = CurveType::ConstantProduct;
+        accounts.initialize_swap().unwrap();
+
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.curve_type, CurveType::ConstantProduct);
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        // the geometric mean of the reserves
+        assert_eq!(amount(&accounts.pool_token_account), 2_000_000);
+
+        let deposit_a = 100_000;
+        let deposit_b = 400_000;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+        accounts
+            .deposit(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                deposit_a,
+                deposit_b,
+                0,
+            )
+            .unwrap();
+        assert_eq!(amount(&pool_account), 200_000);
+
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &depositor_key, 10_000, 0, 0);
+        let reserve_a = amount(&accounts.token_a_account);
+        let reserve_b = amount(&accounts.token_b_account);
+        let result = ConstantProduct
+            .swap_to_v2(
+                10_000.into(),
+                reserve_a.into(),
+                reserve_b.into(),
+                &DEFAULT_TEST_FEES,
+            )
+            .unwrap();
+        let stable_swap_result = StableSwap::new(MIN_AMP, MIN_AMP, ZERO_TS, ZERO_TS, ZERO_TS)
+            .swap_to_v2(
+                10_000.into(),
+                reserve_a.into(),
+                reserve_b.into(),
+                &DEFAULT_TEST_FEES,
+            )
+            .unwrap();
+        assert_ne!(result.amount_swapped, stable_swap_result.amount_swapped);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        accounts
+            .swap(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                10_000,
+                0,
+            )
+            .unwrap();
+        assert_eq!(U256::from(amount(&token_b_account)), result.amount_swapped);
+    }
+
+    #[test]
+    fn test_price_bounded_pool() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let token_amount = 1_000_000;
+        let price_bound = PRICE_BOUND_SCALE / 100 * 101;
+        let new_accounts = || {
+            SwapAccountInfo::new(
+                &user_key,
+                MIN_AMP,
+                token_amount,
+                token_amount,
+                DEFAULT_TEST_FEES,
+            )
+        };
+
+        // a bounded curve needs a band, the other curves none
+        let mut accounts = new_accounts();
+        accounts.curve_type = CurveType::PriceBounded;
+        accounts.price_bound = PRICE_BOUND_SCALE;
+        assert_eq!(
+            accounts.initialize_swap(),
+            Err(SwapError::InvalidInput.into())
+        );
+        let mut accounts = new_accounts();
+        accounts.price_bound = price_bound;
+        assert_eq!(
+            accounts.initialize_swap(),
+            Err(SwapError::InvalidInput.into())
+        );
+
+        let mut accounts = new_accounts();
+        accounts.curve_type = CurveType::PriceBounded;
+        accounts.price_bound = price_bound;
+        accounts.initialize_swap().unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.curve_type, CurveType::PriceBounded);
+        assert_eq!(swap_info.price_bound, price_bound);
+        let curve = PriceBounded::new(price_bound);
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        assert_eq!(
+            U256::from(amount(&accounts.pool_token_account)),
+            curve
+                .compute_d(token_amount.into(), token_amount.into())
+                .unwrap()
+        );
+
+        let amount_in = 100_000;
+        let result = curve
+            .swap_to_v2(
+                amount_in.into(),
+                token_amount.into(),
+                token_amount.into(),
+                &DEFAULT_TEST_FEES,
+            )
+            .unwrap();
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, amount_in, 0, 0);
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        assert_eq!(U256::from(amount(&token_b_account)), result.amount_swapped);
+        // the liquidity concentrated within the band slips less
+        let constant_product_result = ConstantProduct
+            .swap_to_v2(
+                amount_in.into(),
+                token_amount.into(),
+                token_amount.into(),
+                &DEFAULT_TEST_FEES,
+            )
+            .unwrap();
+        assert!(result.amount_swapped > constant_product_result.amount_swapped);
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw_one_mid_ramp() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let initial_amp = 100;
+        let target_amp = 1_000;
+        let deposit_a = 100_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            initial_amp,
+            1_000_000_000,
+            3_000_000_000,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        accounts
+            .ramp_a(target_amp, MIN_RAMP_DURATION, MIN_RAMP_DURATION * 3)
+            .unwrap();
+        accounts.current_ts = MIN_RAMP_DURATION * 2;
+        let invariant = StableSwap::new(
+            initial_amp,
+            target_amp,
+            accounts.current_ts,
+            MIN_RAMP_DURATION,
+            MIN_RAMP_DURATION * 3,
+        );
+        assert_eq!(invariant.compute_amp_factor(), Some(550.into()));
+        let target_invariant = StableSwap::new(target_amp, target_amp, ZERO_TS, ZERO_TS, ZERO_TS);
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, 0, 0);
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let supply = Processor::unpack_mint(&accounts.pool_mint_account.data)
+            .unwrap()
+            .supply;
+        let reserve_a = amount(&accounts.token_a_account);
+        let reserve_b = amount(&accounts.token_b_account);
+
+        // Deposits mint at the interpolated A, not the target of the ramp
+        let mint_amount = |invariant: &StableSwap| {
+            invariant
+                .compute_mint_amount_for_deposit(
+                    deposit_a.into(),
+                    U256::zero(),
+                    reserve_a.into(),
+                    reserve_b.into(),
+                    supply.into(),
+                    &DEFAULT_TEST_FEES,
+                )
+                .unwrap()
+        };
+        let expected_mint_amount = mint_amount(&invariant);
+        assert_ne!(expected_mint_amount, mint_amount(&target_invariant));
+        accounts
+            .deposit(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                deposit_a,
+                0,
+                0,
+            )
+            .unwrap();
+        let minted = amount(&pool_account);
+        assert_eq!(U256::from(minted), expected_mint_amount);
+
+        // Withdrawing the pool tokens in the deposited token at the same A pays back
+        // no more than the deposit, the other LPs keep their share
+        let (dy, _) = invariant
+            .compute_withdraw_one(
+                minted.into(),
+                (supply + minted).into(),
+                (reserve_a + deposit_a).into(),
+                reserve_b.into(),
+                &DEFAULT_TEST_FEES,
+            )
+            .unwrap();
+        let withdraw_fee = DEFAULT_TEST_FEES.withdraw_fee(dy).unwrap();
+        accounts
+            .withdraw_one(
+                &depositor_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                minted,
+                0,
+            )
+            .unwrap();
+        let withdrawn = amount(&token_a_account);
+        assert_eq!(U256::from(withdrawn), dy - withdraw_fee);
+        assert!(withdrawn <= deposit_a);
+    }
+
+    #[test]
+    fn test_deposit() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let amp_factor = MIN_AMP;
+        let token_a_amount = 1000;
+        let token_b_amount = 9000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+
+        let deposit_a = token_a_amount / 10;
+        let deposit_b = token_b_amount / 10;
+        let min_mint_amount = 0;
+
+        // swap not initialized
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong nonce for authority_key
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // not enough token A
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &depositor_key,
+                deposit_a / 2,
+                deposit_b,
+                0,
+            );
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+            );
+        }
+
+        // not enough token B
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &depositor_key,
+                deposit_a,
+                deposit_b / 2,
+                0,
+            );
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+            );
+        }
+
+        // wrong swap token accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+            );
+        }
+
+        // wrong pool token account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                mut _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let (
+                wrong_token_key,
+                mut wrong_token_account,
+                _token_b_key,
+                mut _token_b_account,
+                _pool_key,
+                mut _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &wrong_token_key,
+                    &mut wrong_token_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+            );
+        }
+
+        // no approval
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    deposit(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        None,
+                        deposit_a,
+                        deposit_b,
+                        min_mint_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                    ],
+                )
+            );
+        }
+
+        // source owned by the swap authority, which signs for its own accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &accounts.authority_key.clone(),
+                deposit_a,
+                deposit_b,
+                0,
+            );
+            let (_, _, _, _, pool_key, mut pool_account) =
+                accounts.setup_token_accounts(&user_key, &depositor_key, 0, 0, 0);
+            assert_eq!(
+                Err(SwapError::AuthorityOwnedSource.into()),
+                do_process_instruction(
+                    deposit(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        None,
+                        deposit_a,
+                        deposit_b,
+                        min_mint_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                    ],
+                )
+            );
+        }
+
+        // wrong token program id
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let wrong_key = pubkey_rand();
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                do_process_instruction(
+                    deposit(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_key,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        None,
+                        deposit_a,
+                        deposit_b,
+                        min_mint_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                    ],
+                )
+            );
+        }
+
+        // wrong swap token accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+
+            let old_a_key = accounts.token_a_key;
+            let old_a_account = accounts.token_a_account;
+
+            accounts.token_a_key = token_a_key.clone();
+            accounts.token_a_account = token_a_account.clone();
+
+            // wrong swap token a account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+            );
+
+            accounts.token_a_key = old_a_key;
+            accounts.token_a_account = old_a_account;
+
+            let old_b_key = accounts.token_b_key;
+            let old_b_account = accounts.token_b_account;
+
+            accounts.token_b_key = token_b_key.clone();
+            accounts.token_b_account = token_b_account.clone();
+
+            // wrong swap token b account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+            );
+
+            accounts.token_b_key = old_b_key;
+            accounts.token_b_account = old_b_account;
+        }
+
+        // wrong mint
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let (pool_mint_key, pool_mint_account) = create_mint(
+                &TOKEN_PROGRAM_ID,
+                &accounts.authority_key,
+                DEFAULT_TOKEN_DECIMALS,
+                None,
+            );
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+            );
+
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
+        }
+
+        // slippage exceeeded
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            // min mint_amount in too high
+            let high_min_mint_amount = 10000000000000;
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    deposit_a,
+                    deposit_b,
+                    high_min_mint_amount,
+                )
+            );
+        }
+
+        // correctly deposit
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            accounts
+                .deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+                .unwrap();
+
+            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+            assert_eq!(swap_token_a.amount, deposit_a + token_a_amount);
+            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+            assert_eq!(swap_token_b.amount, deposit_b + token_b_amount);
+            let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, 0);
+            let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
+            assert_eq!(token_b.amount, 0);
+            let pool_account = utils::unpack_token_account(&pool_account.data).unwrap();
+            let swap_pool_account =
+                utils::unpack_token_account(&accounts.pool_token_account.data).unwrap();
+            let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+            // XXX: Revisit and make sure amount of LP tokens minted is corrected.
+            assert_eq!(
+                pool_mint.supply,
+                pool_account.amount + swap_pool_account.amount
+            );
+        }
+
+        // Pool is paused
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            // Pause pool
+            accounts.pause().unwrap();
+
+            assert_eq!(
+                Err(SwapError::IsPaused.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    deposit_a,
+                    deposit_b,
+                    min_mint_amount,
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_withdraw() {
+        let user_key = pubkey_rand();
+        let amp_factor = MIN_AMP;
+        let token_a_amount = 1000;
+        let token_b_amount = 2000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        let withdrawer_key = pubkey_rand();
+        let initial_a = token_a_amount / 10;
+        let initial_b = token_b_amount / 10;
+        let initial_pool = INITIAL_SWAP_POOL_AMOUNT;
+        let withdraw_amount = initial_pool / 4;
+        let minimum_a_amount = initial_a / 40;
+        let minimum_b_amount = initial_b / 40;
+
+        // swap not initialized
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                )
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong nonce for authority_key
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                )
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // not enough pool tokens
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount / 2,
+            );
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_a_amount / 2,
+                    minimum_b_amount / 2,
+                )
+            );
+        }
+
+        // wrong token a / b accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
+            );
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                )
+            );
+        }
+
+        // wrong admin a / b accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
+            );
+            let (
+                wrong_admin_a_key,
+                wrong_admin_a_account,
+                wrong_admin_b_key,
+                wrong_admin_b_account,
+                _pool_key,
+                mut _pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
+            );
+
+            let old_admin_a_key = accounts.admin_fee_a_key;
+            let old_admin_a_account = accounts.admin_fee_a_account;
+            accounts.admin_fee_a_key = wrong_admin_a_key;
+            accounts.admin_fee_a_account = wrong_admin_a_account;
+
+            assert_eq!(
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                )
+            );
+
+            accounts.admin_fee_a_key = old_admin_a_key;
+            accounts.admin_fee_a_account = old_admin_a_account;
+
+            let old_admin_b_key = accounts.admin_fee_b_key;
+            let old_admin_b_account = accounts.admin_fee_b_account;
+            accounts.admin_fee_b_key = wrong_admin_b_key;
+            accounts.admin_fee_b_account = wrong_admin_b_account;
+
+            assert_eq!(
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                )
+            );
+
+            accounts.admin_fee_b_key = old_admin_b_key;
+            accounts.admin_fee_b_account = old_admin_b_account;
+        }
+
+        // wrong pool token account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
+            );
+            let (
+                wrong_pool_key,
+                mut wrong_pool_account,
+                _token_b_key,
+                _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                withdraw_amount,
+                initial_b,
+                withdraw_amount,
+            );
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &wrong_pool_key,
+                    &mut wrong_pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                )
+            );
+        }
+
+        // no approval
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, withdraw_amount);
+            assert_eq!(
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    withdraw(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.admin_fee_a_key,
+                        &accounts.admin_fee_b_key,
+                        None,
+                        None,
+                        withdraw_amount,
+                        minimum_a_amount,
+                        minimum_b_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.admin_fee_a_account,
+                        &mut accounts.admin_fee_b_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                    ],
+                )
+            );
+        }
+
+        // source owned by the swap authority, which signs for its own accounts
+        {
+            let (_, _, _, _, pool_key, mut pool_account) = accounts.setup_token_accounts(
+                &user_key,
+                &accounts.authority_key.clone(),
+                0,
+                0,
+                withdraw_amount,
+            );
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 0);
+            assert_eq!(
+                Err(SwapError::AuthorityOwnedSource.into()),
+                do_process_instruction(
+                    withdraw(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.admin_fee_a_key,
+                        &accounts.admin_fee_b_key,
+                        None,
+                        None,
+                        withdraw_amount,
+                        minimum_a_amount,
+                        minimum_b_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.admin_fee_a_account,
+                        &mut accounts.admin_fee_b_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                    ],
+                )
+            );
+        }
+
+        // wrong token program id
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
+            );
+            let wrong_key = pubkey_rand();
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                do_process_instruction(
+                    withdraw(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_key,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.admin_fee_a_key,
+                        &accounts.admin_fee_b_key,
+                        None,
+                        None,
+                        withdraw_amount,
+                        minimum_a_amount,
+                        minimum_b_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.admin_fee_a_account,
+                        &mut accounts.admin_fee_b_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                    ],
+                )
+            );
+        }
+
+        // wrong swap token accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool,
+            );
+
+            let old_a_key = accounts.token_a_key;
+            let old_a_account = accounts.token_a_account;
+
+            accounts.token_a_key = token_a_key.clone();
+            accounts.token_a_account = token_a_account.clone();
+
+            // wrong swap token a account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                )
+            );
+
+            accounts.token_a_key = old_a_key;
+            accounts.token_a_account = old_a_account;
+
+            let old_b_key = accounts.token_b_key;
+            let old_b_account = accounts.token_b_account;
+
+            accounts.token_b_key = token_b_key.clone();
+            accounts.token_b_account = token_b_account.clone();
+
+            // wrong swap token b account
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                )
+            );
+
+            accounts.token_b_key = old_b_key;
+            accounts.token_b_account = old_b_account;
+        }
+
+        // wrong mint
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool,
+            );
+            let (pool_mint_key, pool_mint_account) = create_mint(
+                &TOKEN_PROGRAM_ID,
+                &accounts.authority_key,
+                DEFAULT_TOKEN_DECIMALS,
+                None,
+            );
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
+            assert_eq!(
+                Err(SwapError::IncorrectMint.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                )
+            );
+
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
+        }
+
+        // slippage exceeeded
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool,
+            );
+            // minimum A amount out too high
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_a_amount * 30, // XXX: 10 -> 30: Revisit this slippage multiplier
+                    minimum_b_amount,
+                )
+            );
+            // minimum B amount out too high
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount * 30, // XXX: 10 -> 30; Revisit this splippage multiplier
+                )
+            );
+        }
+
+        // correct withdrawal
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool,
+            );
+
+            accounts
+                .withdraw(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                )
+                .unwrap();
+
+            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+            let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+            let pool_converter = PoolTokenConverter {
+                supply: U256::from(pool_mint.supply),
+                token_a: U256::from(swap_token_a.amount),
+                token_b: U256::from(swap_token_b.amount),
+                fees: &DEFAULT_TEST_FEES,
+            };
+
+            let (withdrawn_a, admin_fee_a) = pool_converter
+                .token_a_rate(U256::from(withdraw_amount))
+                .unwrap();
+            let withrawn_total_a = U256::to_u64(withdrawn_a + admin_fee_a).unwrap();
+            assert_eq!(swap_token_a.amount, token_a_amount - withrawn_total_a);
+            let (withdrawn_b, admin_fee_b) = pool_converter
+                .token_b_rate(U256::from(withdraw_amount))
+                .unwrap();
+            let withrawn_total_b = U256::to_u64(withdrawn_b + admin_fee_b).unwrap();
+            assert_eq!(swap_token_b.amount, token_b_amount - withrawn_total_b);
+            let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
+            assert_eq!(
+                token_a.amount,
+                initial_a + U256::to_u64(withdrawn_a).unwrap()
+            );
+            let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
+            assert_eq!(
+                token_b.amount,
+                initial_b + U256::to_u64(withdrawn_b).unwrap()
+            );
+            let pool_account = utils::unpack_token_account(&pool_account.data).unwrap();
+            assert_eq!(pool_account.amount, initial_pool - withdraw_amount);
+            let admin_fee_key_a =
+                utils::unpack_token_account(&accounts.admin_fee_a_account.data).unwrap();
+            assert_eq!(admin_fee_key_a.amount, U256::to_u64(admin_fee_a).unwrap());
+            let admin_fee_key_b =
+                utils::unpack_token_account(&accounts.admin_fee_b_account.data).unwrap();
+            assert_eq!(admin_fee_key_b.amount, U256::to_u64(admin_fee_b).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_withdraw_custodian() {
+        let user_key = pubkey_rand();
+        let withdrawer_key = pubkey_rand();
+        let custodian_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(&user_key, MIN_AMP, 1000, 2000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+        let initial_pool = INITIAL_SWAP_POOL_AMOUNT;
+        let threshold = initial_pool / 10;
+        accounts.set_custodian(&custodian_key, threshold).unwrap();
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, initial_pool);
+
+        // at the threshold, no custodian needed
+        accounts
+            .withdraw(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                threshold,
+                0,
+                0,
+            )
+            .unwrap();
+
+        // above the threshold without the custodian
+        assert_eq!(
+            Err(SwapError::CustodianSignatureRequired.into()),
+            accounts.withdraw(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                threshold + 1,
+                0,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::CustodianSignatureRequired.into()),
+            accounts.withdraw_one(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                threshold + 1,
+                0,
+            )
+        );
+
+        // wrong custodian
+        accounts.custodian_key = Some(pubkey_rand());
+        assert_eq!(
+            Err(SwapError::CustodianSignatureRequired.into()),
+            accounts.withdraw(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                threshold + 1,
+                0,
+                0,
+            )
+        );
+
+        // co-signed by the custodian
+        accounts.custodian_key = Some(custodian_key);
+        accounts
+            .withdraw(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                threshold + 1,
+                0,
+                0,
+            )
+            .unwrap();
+        accounts
+            .withdraw_one(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                threshold + 1,
+                0,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_swap() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let amp_factor = 85;
+        let token_a_amount = 5000;
+        let token_b_amount = 5000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        let minimum_b_amount = initial_b / 2;
+
+        let swap_token_a_key = accounts.token_a_key.clone();
+        let swap_token_b_key = accounts.token_b_key.clone();
+
+        // swap not initialized
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_b_amount,
+                )
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
+        // wrong nonce
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_b_amount,
+                )
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // wrong token program id
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let wrong_program_id = pubkey_rand();
+            assert_eq!(
+                Err(ProgramError::IncorrectProgramId),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_program_id,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_b_key,
+                        &accounts.fee_accounts_b.reflection_key,
+                        &accounts.fee_accounts_b.buyback_key,
+                        &accounts.fee_accounts_b.marketing_key,
+                        &accounts.fee_accounts_b.developer_key,
+                        None,
+                        None,
+                        None,
+                        None,
+                        initial_a,
+                        minimum_b_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_b_account,
+                        &mut accounts.fee_accounts_b.reflection_account,
+                        &mut accounts.fee_accounts_b.buyback_account,
+                        &mut accounts.fee_accounts_b.marketing_account,
+                        &mut accounts.fee_accounts_b.developer_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                        &mut Account::default(),
+                    ],
+                ),
+            );
+        }
+
+        // not enough token a to swap
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(TokenError::InsufficientFunds.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a * 2,
+                    minimum_b_amount * 2,
+                )
+            );
+        }
+
+        // wrong swap token A / B accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &token_b_key,
+                        &accounts.fee_accounts_b.reflection_key,
+                        &accounts.fee_accounts_b.buyback_key,
+                        &accounts.fee_accounts_b.marketing_key,
+                        &accounts.fee_accounts_b.developer_key,
+                        None,
+                        None,
+                        None,
+                        None,
+                        initial_a,
+                        minimum_b_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut token_a_account.clone(),
+                        &mut token_a_account,
+                        &mut token_b_account.clone(),
+                        &mut token_b_account,
+                        &mut accounts.fee_accounts_b.reflection_account,
+                        &mut accounts.fee_accounts_b.buyback_account,
+                        &mut accounts.fee_accounts_b.marketing_account,
+                        &mut accounts.fee_accounts_b.developer_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                        &mut Account::default(),
+                    ],
+                ),
+            );
+        }
+
+        // wrong admin account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                wrong_admin_key,
+                mut wrong_admin_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(SwapError::InvalidAdmin.into()),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_b_key,
+                        &wrong_admin_key,
+                        &accounts.fee_accounts_b.buyback_key,
+                        &accounts.fee_accounts_b.marketing_key,
+                        &accounts.fee_accounts_b.developer_key,
+                        None,
+                        None,
+                        None,
+                        None,
+                        initial_a,
+                        minimum_b_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_b_account,
+                        &mut wrong_admin_account,
+                        &mut accounts.fee_accounts_b.buyback_account,
+                        &mut accounts.fee_accounts_b.marketing_account,
+                        &mut accounts.fee_accounts_b.developer_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                        &mut Account::default(),
+                    ],
+                ),
+            );
+        }
+
+        // wrong user token A / B accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    initial_a,
+                    minimum_b_amount,
+                )
+            );
+        }
+
+        // swap from a to a
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account.clone(),
+                    &swap_token_a_key,
+                    &swap_token_a_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    initial_a,
+                    minimum_b_amount,
+                )
+            );
+        }
+
+        // no approval
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_b_key,
+                        &accounts.fee_accounts_b.reflection_key,
+                        &accounts.fee_accounts_b.buyback_key,
+                        &accounts.fee_accounts_b.marketing_key,
+                        &accounts.fee_accounts_b.developer_key,
+                        None,
+                        None,
+                        None,
+                        None,
+                        initial_a,
+                        minimum_b_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.fee_accounts_b.reflection_account,
+                        &mut accounts.fee_accounts_b.buyback_account,
+                        &mut accounts.fee_accounts_b.marketing_account,
+                        &mut accounts.fee_accounts_b.developer_account,
+                        &mut token_b_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                        &mut Account::default(),
+                    ],
+                ),
+            );
+        }
+
+        // source owned by the swap authority, which signs for its own accounts
+        {
+            let (token_a_key, mut token_a_account, _, _, _, _) = accounts.setup_token_accounts(
+                &user_key,
+                &accounts.authority_key.clone(),
+                initial_a,
+                0,
+                0,
+            );
+            let (_, _, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &swapper_key, 0, 0, 0);
+            assert_eq!(
+                Err(SwapError::AuthorityOwnedSource.into()),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_b_key,
+                        &accounts.fee_accounts_b.reflection_key,
+                        &accounts.fee_accounts_b.buyback_key,
+                        &accounts.fee_accounts_b.marketing_key,
+                        &accounts.fee_accounts_b.developer_key,
+                        None,
+                        None,
+                        None,
+                        None,
+                        initial_a,
+                        minimum_b_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut accounts.fee_accounts_b.reflection_account,
+                        &mut accounts.fee_accounts_b.buyback_account,
+                        &mut accounts.fee_accounts_b.marketing_account,
+                        &mut accounts.fee_accounts_b.developer_account,
+                        &mut token_b_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                        &mut Account::default(),
+                    ],
+                ),
+            );
+        }
+
+        // slippage exceeeded: minimum out amount too high
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_b_amount * 2,
+                )
+            );
+        }
+
+        // correct swap
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            // swap one way
+            let a_to_b_amount = initial_a / 10;
+            let minimum_b_amount = initial_b / 20;
+            accounts
+                .swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                )
+                .unwrap();
+
+            let invariant = StableSwap::new(
+                accounts.initial_amp_factor,
+                accounts.target_amp_factor,
+                ZERO_TS,
+                ZERO_TS,
+                ZERO_TS,
+            );
+            let result = invariant
+                .swap_to_v2(
+                    U256::from(a_to_b_amount),
+                    U256::from(token_a_amount),
+                    U256::from(token_b_amount),
+                    &DEFAULT_TEST_FEES,
+                )
+                .unwrap();
+
+            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+            let token_a_amount = swap_token_a.amount;
+            assert_eq!(token_a_amount, 5100);
+            assert_eq!(
+                token_a_amount,
+                U256::to_u64(result.new_source_amount).unwrap()
+            );
+            let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, initial_a - a_to_b_amount);
+
+            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+            let token_b_amount = swap_token_b.amount;
+            assert_eq!(token_b_amount, 4906);
+            assert_eq!(
+                token_b_amount,
+                U256::to_u64(result.new_destination_amount).unwrap()
+            );
+            let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
+            assert_eq!(token_b.amount, 1094);
+            assert_eq!(
+                token_b.amount,
+                initial_b + U256::to_u64(result.amount_swapped).unwrap()
+            );
+            assert_fee_accounts(&accounts.fee_accounts_b, &result);
+
+            let first_swap_amount = result.amount_swapped;
+
+            // swap the other way
+            let b_to_a_amount = initial_b / 10;
+            let minimum_a_amount = initial_a / 20;
+            accounts
+                .swap(
+                    &swapper_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &swap_token_b_key,
+                    &swap_token_a_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    b_to_a_amount,
+                    minimum_a_amount,
+                )
+                .unwrap();
+
+            let invariant = StableSwap::new(
+                accounts.initial_amp_factor,
+                accounts.target_amp_factor,
+                ZERO_TS,
+                ZERO_TS,
+                ZERO_TS,
+            );
+            let result = invariant
+                .swap_to_v2(
+                    U256::from(b_to_a_amount),
+                    U256::from(token_b_amount),
+                    U256::from(token_a_amount),
+                    &DEFAULT_TEST_FEES,
+                )
+                .unwrap();
+
+            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+            assert_eq!(swap_token_a.amount, 5005);
+            assert_eq!(
+                swap_token_a.amount,
+                U256::to_u64(result.new_destination_amount).unwrap()
+            );
+            let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, 995);
+            assert_eq!(
+                token_a.amount,
+                initial_a - a_to_b_amount + U256::to_u64(result.amount_swapped).unwrap()
+            );
+
+            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+            assert_eq!(swap_token_b.amount, 5006);
+            assert_eq!(
+                swap_token_b.amount,
+                U256::to_u64(result.new_source_amount).unwrap()
+            );
+            let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
+            assert_eq!(token_b.amount, 994);
+            assert_eq!(
+                token_b.amount,
+                initial_b + U256::to_u64(first_swap_amount).unwrap() - b_to_a_amount
+            );
+            assert_fee_accounts(&accounts.fee_accounts_a, &result);
+        }
+
+        // Pool is paused
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            // Pause pool
+            accounts.pause().unwrap();
+
+            assert_eq!(
+                Err(SwapError::IsPaused.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    initial_a,
+                    minimum_b_amount,
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_creator_deposit_window() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let creator_window_slots = 50;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.creator_window_slots = creator_window_slots;
+        accounts.initialize_swap().unwrap();
+
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        // the payer signing Initialize
+        assert_eq!(swap_info.creator, accounts.admin_key);
+        assert_eq!(swap_info.creator_deposit_end_slot, creator_window_slots);
+
+        let deposit_a = token_a_amount / 10;
+        let deposit_b = token_b_amount / 10;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(
+            &user_key,
+            &depositor_key,
+            deposit_a * 2,
+            deposit_b * 2,
+            0,
+        );
+
+        // anyone else during the window
+        accounts.deposit_slot = creator_window_slots - 1;
+        assert_eq!(
+            Err(SwapError::CreatorDepositWindow.into()),
+            accounts.deposit(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                deposit_a,
+                deposit_b,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::CreatorDepositWindow.into()),
+            accounts.deposit_one(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                false,
+                deposit_a,
+                0,
+            )
+        );
+
+        // the owner of the initial pool tokens, who did not sign Initialize
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &user_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(SwapError::CreatorDepositWindow.into()),
+                accounts.deposit(
+                    &user_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    0,
+                )
+            );
+        }
+
+        // the creator during the window
+        {
+            let creator_key = accounts.admin_key;
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &creator_key, deposit_a, deposit_b, 0);
+            accounts
+                .deposit(
+                    &creator_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    deposit_a,
+                    deposit_b,
+                    0,
+                )
+                .unwrap();
+        }
+
+        // anyone once the window ends
+        accounts.deposit_slot = creator_window_slots;
+        accounts
+            .deposit(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                deposit_a,
+                deposit_b,
+                0,
+            )
+            .unwrap();
+        accounts
+            .deposit_one(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                false,
+                deposit_a,
+                0,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_deposit_allowlist() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let outsider_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        accounts.admin_account.lamports = Rent::default().minimum_balance(DepositAllowlist::LEN);
+        let mut deposit_allowlist_account = Account::new(
+            0,
+            DepositAllowlist::LEN,
+            &solana_program::system_program::id(),
+        );
+        accounts
+            .add_allowed_depositor(&mut deposit_allowlist_account, &depositor_key)
+            .unwrap();
+        accounts.set_deposit_allowlist(true).unwrap();
+        let (deposit_allowlist_key, _nonce) =
+            utils::find_deposit_allowlist_address(&SWAP_PROGRAM_ID, &accounts.swap_key);
+
+        let deposit_a = token_a_amount / 10;
+        let deposit_b = token_b_amount / 10;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a * 2, deposit_b, 0);
+        let (
+            outsider_a_key,
+            mut outsider_a_account,
+            outsider_b_key,
+            mut outsider_b_account,
+            outsider_pool_key,
+            mut outsider_pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &outsider_key, deposit_a, deposit_b, 0);
+
+        // allowlist not passed
+        assert_eq!(
+            Err(SwapError::DepositorNotAllowed.into()),
+            accounts.deposit(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                deposit_a,
+                deposit_b,
+                0,
+            )
+        );
+
+        // an account of the program allowing the depositor at another address than the
+        // allowlist allows no one
+        accounts.deposit_allowlist = Some((pubkey_rand(), deposit_allowlist_account.clone()));
+        assert_eq!(
+            Err(SwapError::DepositorNotAllowed.into()),
+            accounts.deposit(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                deposit_a,
+                deposit_b,
+                0,
+            )
+        );
+        accounts.deposit_allowlist = Some((deposit_allowlist_key, deposit_allowlist_account));
+
+        // depositor not on the allowlist
+        assert_eq!(
+            Err(SwapError::DepositorNotAllowed.into()),
+            accounts.deposit(
+                &outsider_key,
+                &outsider_a_key,
+                &mut outsider_a_account,
+                &outsider_b_key,
+                &mut outsider_b_account,
+                &outsider_pool_key,
+                &mut outsider_pool_account,
+                deposit_a,
+                deposit_b,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::DepositorNotAllowed.into()),
+            accounts.deposit_one(
+                &outsider_key,
+                &outsider_a_key,
+                &mut outsider_a_account,
+                &outsider_pool_key,
+                &mut outsider_pool_account,
+                false,
+                deposit_a,
+                0,
+            )
+        );
+
+        // depositor on the allowlist
+        accounts
+            .deposit(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                deposit_a,
+                deposit_b,
+                0,
+            )
+            .unwrap();
+        accounts
+            .deposit_one(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                false,
+                deposit_a,
+                0,
+            )
+            .unwrap();
+
+        // swaps stay open to everyone
+        let (swap_token_a_key, swap_token_b_key) = (accounts.token_a_key, accounts.token_b_key);
+        accounts
+            .swap(
+                &outsider_key,
+                &outsider_a_key,
+                &mut outsider_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &outsider_b_key,
+                &mut outsider_b_account,
+                deposit_a,
+                0,
+            )
+            .unwrap();
+
+        // deposits open to everyone again
+        accounts.set_deposit_allowlist(false).unwrap();
+        accounts.deposit_allowlist = None;
+        accounts
+            .deposit_one(
+                &outsider_key,
+                &outsider_b_key,
+                &mut outsider_b_account,
+                &outsider_pool_key,
+                &mut outsider_pool_account,
+                true,
+                deposit_b,
+                0,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_swap_access_window() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let amp_factor = 85;
+        let token_a_amount = 5000;
+        let token_b_amount = 5000;
+        let access_window_slots = 100;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.access_window_slots = access_window_slots;
+        accounts.initialize_swap().unwrap();
+
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.access_mint, accounts.access_mint_key);
+        assert_eq!(swap_info.access_window_end_slot, access_window_slots);
+
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        let a_to_b_amount = initial_a / 10;
+        let minimum_b_amount = initial_b / 20;
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+
+        // no access token during the window
+        {
+            assert_eq!(
+                Err(SwapError::AccessTokenRequired.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                )
+            );
+        }
+
+        // access token with zero balance
+        {
+            let (access_token_key, mut access_token_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.access_mint_key,
+                &mut accounts.access_mint_account,
+                &user_key,
+                &swapper_key,
+                0,
+            );
+            assert_eq!(
+                Err(SwapError::AccessTokenRequired.into()),
+                accounts.swap_with_access_token(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    Some((&access_token_key, &mut access_token_account)),
+                    0,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                    None,
+                )
+            );
+        }
+
+        // access token held by someone else
+        {
+            let (access_token_key, mut access_token_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.access_mint_key,
+                &mut accounts.access_mint_account,
+                &user_key,
+                &user_key,
+                1,
+            );
+            assert_eq!(
+                Err(SwapError::AccessTokenRequired.into()),
+                accounts.swap_with_access_token(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    Some((&access_token_key, &mut access_token_account)),
+                    0,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                    None,
+                )
+            );
+        }
+
+        // token of the wrong mint
+        {
+            let (wrong_token_key, mut wrong_token_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &swapper_key,
+                1,
+            );
+            assert_eq!(
+                Err(SwapError::AccessTokenRequired.into()),
+                accounts.swap_with_access_token(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    Some((&wrong_token_key, &mut wrong_token_account)),
+                    0,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                    None,
+                )
+            );
+        }
+
+        // access token holder can swap during the window
+        {
+            let (access_token_key, mut access_token_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.access_mint_key,
+                &mut accounts.access_mint_account,
+                &user_key,
+                &swapper_key,
+                1,
+            );
+            accounts
+                .swap_with_access_token(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    Some((&access_token_key, &mut access_token_account)),
+                    access_window_slots - 1,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                    None,
+                )
+                .unwrap();
+            let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, initial_a - a_to_b_amount);
+        }
+
+        // anyone can swap once the window has ended
+        {
+            accounts
+                .swap_with_access_token(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    None,
+                    access_window_slots,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                    None,
+                )
+                .unwrap();
+            let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
+            assert_eq!(token_a.amount, initial_a - 2 * a_to_b_amount);
+        }
+    }
+
+    #[test]
+    fn test_swap_admin_fee_in_pool_tokens() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let amp_factor = 85;
+        let token_a_amount = 5000;
+        let token_b_amount = 5000;
+        let fees = Fees {
+            admin_fee_in_pool_tokens: true,
+            ..DEFAULT_TEST_FEES
+        };
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, amp_factor, token_a_amount, token_b_amount, fees);
+        accounts.initialize_swap().unwrap();
+
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        let a_to_b_amount = initial_a / 10;
+        let minimum_b_amount = initial_b / 20;
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+
+        // admin pool token account not provided
+        {
+            let mut fee_accounts = accounts.get_fee_accounts(&swap_token_b_key).clone();
+            let mut swap_token_a_account = accounts.token_a_account.clone();
+            let mut swap_token_b_account = accounts.token_b_account.clone();
+            assert_eq!(
+                Err(ProgramError::NotEnoughAccountKeys),
+                do_process_instruction(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &token_a_key,
+                        &swap_token_a_key,
+                        &swap_token_b_key,
+                        &token_b_key,
+                        &fee_accounts.reflection_key,
+                        &fee_accounts.buyback_key,
+                        &fee_accounts.marketing_key,
+                        &fee_accounts.developer_key,
+                        None,
+                        None,
+                        None,
+                        None,
+                        a_to_b_amount,
+                        minimum_b_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut swap_token_a_account,
+                        &mut swap_token_b_account,
+                        &mut token_b_account,
+                        &mut fee_accounts.reflection_account,
+                        &mut fee_accounts.buyback_account,
+                        &mut fee_accounts.marketing_account,
+                        &mut fee_accounts.developer_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                        &mut Account::default(),
+                    ],
+                )
+            );
+        }
+
+        // admin pool token account not owned by the admin
+        {
+            let (wrong_admin_pool_key, wrong_admin_pool_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &swapper_key,
+                0,
+            );
+            let old_admin_pool_key = accounts.admin_pool_token_key;
+            let old_admin_pool_account = std::mem::replace(
+                &mut accounts.admin_pool_token_account,
+                wrong_admin_pool_account,
+            );
+            accounts.admin_pool_token_key = wrong_admin_pool_key;
+            assert_eq!(
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                )
+            );
+            accounts.admin_pool_token_key = old_admin_pool_key;
+            accounts.admin_pool_token_account = old_admin_pool_account;
+        }
+
+        // correct swap
+        {
+            let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+            let initial_supply = pool_mint.supply;
+            accounts
+                .swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                )
+                .unwrap();
+
+            let invariant = StableSwap::new(
+                accounts.initial_amp_factor,
+                accounts.target_amp_factor,
+                ZERO_TS,
+                ZERO_TS,
+                ZERO_TS,
+            );
+            let result = invariant
+                .swap_to_v2(
+                    U256::from(a_to_b_amount),
+                    U256::from(token_a_amount),
+                    U256::from(token_b_amount),
+                    &fees,
+                )
+                .unwrap();
+            assert!(result.admin_fee > U256::zero());
+
+            // the admin fee is left in the pool
+            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+            assert_eq!(
+                swap_token_b.amount,
+                U256::to_u64(result.new_destination_amount).unwrap()
+            );
+            assert_fee_accounts(&accounts.fee_accounts_b, &result);
+
+            let d_1 = invariant
+                .compute_d(result.new_source_amount, result.new_destination_amount)
+                .unwrap();
+            let d_0 = invariant
+                .compute_d(
+                    result.new_source_amount,
+                    result.new_destination_amount - result.admin_fee,
+                )
+                .unwrap();
+            let expected_admin_pool_tokens = U256::from(initial_supply) * (d_1 - d_0) / d_0;
+            assert!(expected_admin_pool_tokens > U256::zero());
+            let admin_pool_token =
+                utils::unpack_token_account(&accounts.admin_pool_token_account.data).unwrap();
+            assert_eq!(
+                admin_pool_token.amount,
+                U256::to_u64(expected_admin_pool_tokens).unwrap()
+            );
+            let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+            assert_eq!(pool_mint.supply, initial_supply + admin_pool_token.amount);
+        }
+    }
+
+    #[test]
+    fn test_swap_fee_on_input() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let amp_factor = 85;
+        let token_a_amount = 5000;
+        let token_b_amount = 5000;
+        let fees = Fees {
+            fee_on_input: true,
+            ..DEFAULT_TEST_FEES
+        };
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, amp_factor, token_a_amount, token_b_amount, fees);
+        accounts.initialize_swap().unwrap();
+
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        let a_to_b_amount = initial_a / 10;
+        let minimum_b_amount = initial_b / 20;
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+
+        // fee accounts of the destination token
+        {
+            let old_fees = accounts.fees;
+            accounts.fees.fee_on_input = false;
+            assert_eq!(
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                )
+            );
+            accounts.fees = old_fees;
+        }
+
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                minimum_b_amount,
+            )
+            .unwrap();
+
+        let invariant = StableSwap::new(
+            accounts.initial_amp_factor,
+            accounts.target_amp_factor,
+            ZERO_TS,
+            ZERO_TS,
+            ZERO_TS,
+        );
+        let result = invariant
+            .swap_to_v2(
+                U256::from(a_to_b_amount),
+                U256::from(token_a_amount),
+                U256::from(token_b_amount),
+                &fees,
+            )
+            .unwrap();
+        assert!(result.trade_fee > U256::zero());
+
+        // the fees are charged in the source token
+        let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+        assert_eq!(
+            swap_token_a.amount,
+            U256::to_u64(result.new_source_amount).unwrap()
+        );
+        let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+        assert_eq!(
+            swap_token_b.amount,
+            U256::to_u64(result.new_destination_amount).unwrap()
+        );
+        let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
+        assert_eq!(
+            token_b.amount,
+            initial_b + U256::to_u64(result.amount_swapped).unwrap()
+        );
+        assert_fee_accounts(&accounts.fee_accounts_a, &result);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(
+            swap_info.admin_fees_accrued_a,
+            U256::to_u64(result.admin_fee).unwrap()
+        );
+        assert_eq!(swap_info.admin_fees_accrued_b, 0);
+    }
+
+    #[test]
+    fn test_swap_amount_cap() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let token_a_amount = 5000;
+        let token_b_amount = 5000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            85,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let initial_a = token_a_amount / 5;
+        let max_swap_amount_in = initial_a / 10;
+        accounts.set_max_swap_amount(max_swap_amount_in).unwrap();
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, 0, 0);
+
+        // swap larger than the cap
+        {
+            assert_eq!(
+                Err(SwapError::SwapAmountTooLarge.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    max_swap_amount_in + 1,
+                    0,
+                )
+            );
+        }
+
+        // swaps up to the cap go through
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                max_swap_amount_in,
+                0,
+            )
+            .unwrap();
+
+        // the cap can be lifted
+        accounts.set_max_swap_amount(0).unwrap();
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                max_swap_amount_in + 1,
+                0,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_swap_oracle_guard() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let token_a_amount = 5000;
+        let token_b_amount = 5000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            85,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let oracle_a_key = pubkey_rand();
+        let oracle_b_key = pubkey_rand();
+        // token B quoted at another exponent than token A
+        accounts.oracles = Some([
+            (oracle_a_key, pyth_price_account(100_000_000, 10_000, -8, 0)),
+            (oracle_b_key, pyth_price_account(1_000_000, 100, -6, 0)),
+        ]);
+        accounts.set_oracle_guard(25, 50, 100).unwrap();
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, 1_000, 1_000, 0);
+        let mut swap = |accounts: &mut SwapAccountInfo, a_to_b: bool, slot: Slot| {
+            let (source, swap_source_key, swap_destination_key, destination) = if a_to_b {
+                (
+                    (&token_a_key, &mut token_a_account),
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    (&token_b_key, &mut token_b_account),
+                )
+            } else {
+                (
+                    (&token_b_key, &mut token_b_account),
+                    &swap_token_b_key,
+                    &swap_token_a_key,
+                    (&token_a_key, &mut token_a_account),
+                )
+            };
+            accounts.swap_with_access_token(
+                &swapper_key,
+                source.0,
+                source.1,
+                swap_source_key,
+                swap_destination_key,
+                destination.0,
+                destination.1,
+                None,
+                slot,
+                100,
+                0,
+                None,
+            )
+        };
+
+        // swaps at about the oracle price go through in both directions
+        swap(&mut accounts, true, 0).unwrap();
+        swap(&mut accounts, false, 25).unwrap();
+
+        // prices published more than 25 slots before
+        assert_eq!(
+            Err(SwapError::OracleStale.into()),
+            swap(&mut accounts, true, 26)
+        );
+        accounts
+            .set_oracle_stale_policy(OracleStalePolicy::Ignore, 0)
+            .unwrap();
+        swap(&mut accounts, true, 26).unwrap();
+        accounts
+            .set_oracle_stale_policy(OracleStalePolicy::Reject, 0)
+            .unwrap();
+
+        // price accounts not passed
+        {
+            let oracles = accounts.oracles.take();
+            assert_eq!(
+                Err(SwapError::InvalidOracle.into()),
+                swap(&mut accounts, true, 0)
+            );
+            accounts.oracles = oracles;
+        }
+
+        // confidence of token B wider than 50 bps of its price
+        {
+            let oracles = accounts.oracles.clone();
+            accounts.oracles.as_mut().unwrap()[1].1 = pyth_price_account(1_000_000, 5_001, -6, 0);
+            assert_eq!(
+                Err(SwapError::OracleConfidenceTooWide.into()),
+                swap(&mut accounts, true, 0)
+            );
+            accounts.oracles = oracles;
+        }
+
+        // token A of half the value of token B: the pool pays out more B than the
+        // oracle value of the A swapped in, but less A than the value of the B
+        accounts.oracles.as_mut().unwrap()[0].1 = pyth_price_account(50_000_000, 5_000, -8, 0);
+        assert_eq!(
+            Err(SwapError::OraclePriceDeviation.into()),
+            swap(&mut accounts, true, 0)
+        );
+        swap(&mut accounts, false, 0).unwrap();
+
+        // a band widened past the deviation while the prices are stale only
+        accounts
+            .set_oracle_stale_policy(OracleStalePolicy::WidenBand, 9_000)
+            .unwrap();
+        swap(&mut accounts, true, 26).unwrap();
+        assert_eq!(
+            Err(SwapError::OraclePriceDeviation.into()),
+            swap(&mut accounts, true, 0)
+        );
+
+        // without the guard
+        accounts.oracles = None;
+        accounts.set_oracle_guard(0, 0, 0).unwrap();
+        swap(&mut accounts, true, 0).unwrap();
+    }
+
+    #[test]
+    fn test_expected_sequence() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let token_a_amount = 5000;
+        let token_b_amount = 5000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            85,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let sequence = SwapInfo::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .sequence;
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, 1000, 1000, 0);
+
+        // pool unchanged since the swap was quoted
+        accounts
+            .swap_with_access_token(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                None,
+                0,
+                100,
+                0,
+                Some(sequence),
+            )
+            .unwrap();
+        assert_eq!(
+            SwapInfo::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .sequence,
+            sequence + 1
+        );
+
+        // pool changed since the swap and the deposit were quoted
+        assert_eq!(
+            Err(SwapError::SequenceMismatch.into()),
+            accounts.swap_with_access_token(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                None,
+                0,
+                100,
+                0,
+                Some(sequence),
+            )
+        );
+        assert_eq!(
+            Err(SwapError::SequenceMismatch.into()),
+            accounts.deposit_at_sequence(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                100,
+                100,
+                0,
+                Some(sequence),
+            )
+        );
+
+        accounts
+            .deposit_at_sequence(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                100,
+                100,
+                0,
+                Some(sequence + 1),
+            )
+            .unwrap();
+        assert_eq!(
+            SwapInfo::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .sequence,
+            sequence + 2
+        );
+
+        // the withdrawals and single-sided deposits check the sequence as well
+        let sequence = sequence + 2;
+        let pool_amount = utils::unpack_token_account(&pool_account.data)
+            .unwrap()
+            .amount;
+        accounts.expected_sequence = Some(sequence - 1);
+        assert_eq!(
+            Err(SwapError::SequenceMismatch.into()),
+            accounts.deposit_one(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                false,
+                100,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::SequenceMismatch.into()),
+            accounts.withdraw_one(
+                &swapper_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                10,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::SequenceMismatch.into()),
+            accounts.withdraw_imbalanced(
+                &swapper_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                10,
+                10,
+                pool_amount,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::SequenceMismatch.into()),
+            accounts.withdraw(
+                &swapper_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                10,
+                0,
+                0,
+            )
+        );
+
+        accounts.expected_sequence = Some(sequence);
+        accounts
+            .withdraw(
+                &swapper_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                10,
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(
+            SwapInfo::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .sequence,
+            sequence + 1
+        );
+    }
+
+    #[test]
+    fn test_deadline_slot() {
+        assert_eq!(Processor::check_deadline(1_000, 0), Ok(()));
+        assert_eq!(Processor::check_deadline(1_000, 1_000), Ok(()));
+        assert_eq!(
+            Processor::check_deadline(1_001, 1_000),
+            Err(SwapError::DeadlineExceeded.into())
+        );
+
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(&user_key, 85, 5000, 5000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, 1000, 1000, 0);
+        accounts.deadline_slot = 10;
+
+        // landed past its deadline
+        assert_eq!(
+            Err(SwapError::DeadlineExceeded.into()),
+            accounts.swap_with_access_token(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                None,
+                11,
+                100,
+                0,
+                None,
+            )
+        );
+
+        // in the deadline slot
+        accounts
+            .swap_with_access_token(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                None,
+                10,
+                100,
+                0,
+                None,
+            )
+            .unwrap();
+
+        // swap outs, single-sided deposits and withdrawals past their deadline
+        accounts.deposit_slot = 11;
+        assert_eq!(
+            Err(SwapError::DeadlineExceeded.into()),
+            accounts.swap_out(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                10,
+                100,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::DeadlineExceeded.into()),
+            accounts.deposit_one(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                false,
+                100,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::DeadlineExceeded.into()),
+            accounts.withdraw_one(
+                &swapper_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                10,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::DeadlineExceeded.into()),
+            accounts.withdraw(
+                &swapper_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                10,
+                0,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::DeadlineExceeded.into()),
+            accounts.withdraw_imbalanced(
+                &swapper_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                10,
+                10,
+                100,
+            )
+        );
+
+        // in the deadline slot
+        accounts.deposit_slot = 10;
+        accounts
+            .swap_out(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                10,
+                100,
+            )
+            .unwrap();
+        accounts
+            .deposit_one(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                false,
+                100,
+                0,
+            )
+            .unwrap();
+        accounts
+            .withdraw_one(
+                &swapper_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                10,
+                0,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_credential() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(&user_key, 85, 5000, 5000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+        let (credential_mint_key, mut credential_mint_account) =
+            create_mint(&TOKEN_PROGRAM_ID, &user_key, 0, None);
+        accounts
+            .set_credential_issuer(&credential_mint_key)
+            .unwrap();
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, 1000, 1000, 0);
+
+        // no credential
+        assert_eq!(
+            Err(SwapError::CredentialRequired.into()),
+            accounts.swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                100,
+                0,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::CredentialRequired.into()),
+            accounts.deposit(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                100,
+                100,
+                0,
+            )
+        );
+
+        // credential token of someone else
+        accounts.credential = Some(mint_token(
+            &TOKEN_PROGRAM_ID,
+            &credential_mint_key,
+            &mut credential_mint_account,
+            &user_key,
+            &user_key,
+            1,
+        ));
+        assert_eq!(
+            Err(SwapError::CredentialRequired.into()),
+            accounts.swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                100,
+                0,
+            )
+        );
+
+        // credential token
+        accounts.credential = Some(mint_token(
+            &TOKEN_PROGRAM_ID,
+            &credential_mint_key,
+            &mut credential_mint_account,
+            &user_key,
+            &swapper_key,
+            1,
+        ));
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                100,
+                0,
+            )
+            .unwrap();
+        accounts
+            .deposit(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                100,
+                100,
+                0,
+            )
+            .unwrap();
+
+        // credential account of the issuer program
+        let issuer_program_id = pubkey_rand();
+        accounts.set_credential_issuer(&issuer_program_id).unwrap();
+        accounts.credential = Some((pubkey_rand(), Account::new(0, 0, &issuer_program_id)));
+        assert_eq!(
+            Err(SwapError::CredentialRequired.into()),
+            accounts.swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                100,
+                0,
+            )
+        );
+        // storing the swapper, at another address than its credential address
+        let mut credential_account = Account::new(0, 40, &issuer_program_id);
+        credential_account.data[..32].copy_from_slice(swapper_key.as_ref());
+        accounts.credential = Some((pubkey_rand(), credential_account.clone()));
+        assert_eq!(
+            Err(SwapError::CredentialRequired.into()),
+            accounts.swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                100,
+                0,
+            )
+        );
+        let (credential_key, _nonce) =
+            utils::find_credential_address(&issuer_program_id, &swapper_key);
+        accounts.credential = Some((credential_key, credential_account));
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                100,
+                0,
+            )
+            .unwrap();
+
+        // requirement lifted
+        accounts.set_credential_issuer(&Pubkey::default()).unwrap();
+        accounts.credential = None;
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                100,
+                0,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_harvest_admin_fees() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let harvester_key = pubkey_rand();
+        let amp_factor = 85;
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let harvest_tip_bps = 500;
+        accounts.set_harvest_tip(harvest_tip_bps).unwrap();
+
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        let a_to_b_amount = initial_a / 10;
+        let minimum_b_amount = initial_b / 20;
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+        let (tip_a_key, mut tip_a_account, tip_b_key, mut tip_b_account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &harvester_key, 0, 0, 0);
+
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                a_to_b_amount,
+                minimum_b_amount,
+            )
+            .unwrap();
+
+        let invariant = StableSwap::new(
+            accounts.initial_amp_factor,
+            accounts.target_amp_factor,
+            ZERO_TS,
+            ZERO_TS,
+            ZERO_TS,
+        );
+        let result = invariant
+            .swap_to_v2(
+                U256::from(a_to_b_amount),
+                U256::from(token_a_amount),
+                U256::from(token_b_amount),
+                &DEFAULT_TEST_FEES,
+            )
+            .unwrap();
+        let admin_fee_b = U256::to_u64(result.admin_fee).unwrap();
+        assert!(admin_fee_b > 0);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_fees_accrued_a, 0);
+        assert_eq!(swap_info.admin_fees_accrued_b, admin_fee_b);
+
+        // the accrued fees are not part of the pool
+        let b_to_a_amount = initial_b / 10;
+        let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+        let b_to_a_result = invariant
+            .swap_to_v2(
+                U256::from(b_to_a_amount),
+                U256::from(swap_token_b.amount - admin_fee_b),
+                result.new_source_amount,
+                &DEFAULT_TEST_FEES,
+            )
+            .unwrap();
+        accounts
+            .swap(
+                &swapper_key,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_token_b_key,
+                &swap_token_a_key,
+                &token_a_key,
+                &mut token_a_account,
+                b_to_a_amount,
+                0,
+            )
+            .unwrap();
+        let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
+        assert_eq!(
+            token_a.amount,
+            initial_a - a_to_b_amount + U256::to_u64(b_to_a_result.amount_swapped).unwrap()
+        );
+        let admin_fee_a = U256::to_u64(b_to_a_result.admin_fee).unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_fees_accrued_a, admin_fee_a);
+        assert_eq!(swap_info.admin_fees_accrued_b, admin_fee_b);
+
+        // wrong admin fee account
+        {
+            let old_admin_fee_b_key = accounts.admin_fee_b_key;
+            accounts.admin_fee_b_key = tip_b_key;
+            assert_eq!(
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.harvest_admin_fees(
+                    &tip_a_key,
+                    &mut tip_a_account,
+                    &tip_b_key,
+                    &mut tip_b_account.clone(),
+                )
+            );
+            accounts.admin_fee_b_key = old_admin_fee_b_key;
+        }
+
+        // harvest
+        let tip_a = admin_fee_a * harvest_tip_bps as u64 / 10_000;
+        let tip_b = admin_fee_b * harvest_tip_bps as u64 / 10_000;
+        assert!(tip_a > 0 && tip_b > 0);
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let swap_token_a_amount = amount(&accounts.token_a_account);
+        let swap_token_b_amount = amount(&accounts.token_b_account);
+        accounts
+            .harvest_admin_fees(
+                &tip_a_key,
+                &mut tip_a_account,
+                &tip_b_key,
+                &mut tip_b_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&accounts.admin_fee_a_account), admin_fee_a - tip_a);
+        assert_eq!(amount(&accounts.admin_fee_b_account), admin_fee_b - tip_b);
+        assert_eq!(amount(&tip_a_account), tip_a);
+        assert_eq!(amount(&tip_b_account), tip_b);
+        assert_eq!(
+            amount(&accounts.token_a_account),
+            swap_token_a_amount - admin_fee_a
+        );
+        assert_eq!(
+            amount(&accounts.token_b_account),
+            swap_token_b_amount - admin_fee_b
+        );
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_fees_accrued_a, 0);
+        assert_eq!(swap_info.admin_fees_accrued_b, 0);
+
+        // nothing left to harvest
+        accounts
+            .harvest_admin_fees(
+                &tip_a_key,
+                &mut tip_a_account,
+                &tip_b_key,
+                &mut tip_b_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&accounts.admin_fee_a_account), admin_fee_a - tip_a);
+        assert_eq!(amount(&accounts.admin_fee_b_account), admin_fee_b - tip_b);
+    }
+
+    #[test]
+    fn test_check_fee_destinations() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let harvester_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            85,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let sequence = SwapInfo::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .sequence;
+
+        // valid fee accounts
+        accounts.check_fee_destinations().unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert!(!swap_info.admin_fee_escrow_a);
+        assert!(!swap_info.admin_fee_escrow_b);
+        assert_eq!(swap_info.sequence, sequence);
+
+        // wrong fee account
+        {
+            let admin_fee_b_key = accounts.admin_fee_b_key;
+            accounts.admin_fee_b_key = accounts.admin_fee_a_key;
+            assert_eq!(
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.check_fee_destinations()
+            );
+            accounts.admin_fee_b_key = admin_fee_b_key;
+        }
+
+        // closed token A fee account
+        let admin_fee_a_account = std::mem::take(&mut accounts.admin_fee_a_account);
+        accounts.check_fee_destinations().unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_info.admin_fee_escrow_a);
+        assert!(!swap_info.admin_fee_escrow_b);
+
+        // the token A admin fees are no longer harvested
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 10_000, 10_000, 0);
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                10_000,
+                0,
+            )
+            .unwrap();
+        accounts
+            .swap(
+                &swapper_key,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_token_b_key,
+                &swap_token_a_key,
+                &token_a_key,
+                &mut token_a_account,
+                10_000,
+                0,
+            )
+            .unwrap();
+        let (tip_a_key, mut tip_a_account, tip_b_key, mut tip_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &harvester_key, 0, 0, 0);
+        accounts
+            .harvest_admin_fees(
+                &tip_a_key,
+                &mut tip_a_account,
+                &tip_b_key,
+                &mut tip_b_account,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        let escrowed_a = swap_info.admin_fees_accrued_a;
+        assert!(escrowed_a > 0);
+        assert_eq!(swap_info.admin_fees_accrued_b, 0);
+
+        // nor paid out by withdrawals
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, 0, 0, 10_000);
+        let admin_fee_b = utils::unpack_token_account(&accounts.admin_fee_b_account.data)
+            .unwrap()
+            .amount;
+        accounts
+            .withdraw(
+                &swapper_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                10_000,
+                0,
+                0,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_info.admin_fees_accrued_a > escrowed_a);
+        let escrowed_a = swap_info.admin_fees_accrued_a;
+        assert!(
+            utils::unpack_token_account(&accounts.admin_fee_b_account.data)
+                .unwrap()
+                .amount
+                > admin_fee_b
+        );
+
+        // the escrow is released once the fee account is valid again
+        accounts.admin_fee_a_account = admin_fee_a_account;
+        accounts.check_fee_destinations().unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert!(!swap_info.admin_fee_escrow_a);
+        accounts
+            .harvest_admin_fees(
+                &tip_a_key,
+                &mut tip_a_account,
+                &tip_b_key,
+                &mut tip_b_account,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_fees_accrued_a, 0);
+        assert_eq!(
+            utils::unpack_token_account(&accounts.admin_fee_a_account.data)
+                .unwrap()
+                .amount,
+            escrowed_a
+        );
+    }
+    #[test]
+    fn test_swap_out() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let token_a_amount = 5000;
+        let token_b_amount = 5000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            85,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let initial_a = 1000;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, 0, 0);
+        let amount_out = 100;
+        let amount_in = U256::to_u64(
+            StableSwap::new(85, 85, ZERO_TS, ZERO_TS, ZERO_TS)
+                .compute_swap_in(
+                    U256::from(amount_out),
+                    U256::from(token_a_amount),
+                    U256::from(token_b_amount),
+                    &DEFAULT_TEST_FEES,
+                )
+                .unwrap(),
+        )
+        .unwrap();
+
+        // more input than the maximum
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.swap_out(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_out,
+                amount_in - 1,
+            )
+        );
+
+        // more output than the reserves
+        assert_eq!(
+            Err(SwapError::CalculationFailure.into()),
+            accounts.swap_out(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                token_b_amount,
+                initial_a,
+            )
+        );
+
+        accounts
+            .swap_out(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_out,
+                amount_in,
+            )
+            .unwrap();
+        let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
+        assert_eq!(token_a.amount, initial_a - amount_in);
+        let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
+        assert!(token_b.amount >= amount_out);
+        let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+        assert_eq!(swap_token_a.amount, token_a_amount + amount_in);
+    }
+
+    #[test]
+    fn test_route() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let pools = || {
+            let mut first = SwapAccountInfo::new(&user_key, 85, 5000, 5000, DEFAULT_TEST_FEES);
+            first.initialize_swap().unwrap();
+            let mut next = SwapAccountInfo::new_with_token_a_mint(
+                &user_key,
+                85,
+                6000,
+                4000,
+                DEFAULT_TEST_FEES,
+                (first.token_b_mint_key, first.token_b_mint_account.clone()),
+            );
+            next.initialize_swap().unwrap();
+            (first, next)
+        };
+        let amount_in = 1000;
+
+        // the same swaps, one at a time
+        let (mut first, mut next) = pools();
+        let (source_key, mut source_account, intermediate_key, mut intermediate_account, _, _) =
+            first.setup_token_accounts(&user_key, &swapper_key, amount_in, 0, 0);
+        let (_, _, destination_key, mut destination_account, _, _) =
+            next.setup_token_accounts(&user_key, &swapper_key, 0, 0, 0);
+        let (swap_token_a_key, swap_token_b_key) = (first.token_a_key, first.token_b_key);
+        first
+            .swap(
+                &swapper_key,
+                &source_key,
+                &mut source_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &intermediate_key,
+                &mut intermediate_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        let intermediate_amount = utils::unpack_token_account(&intermediate_account.data)
+            .unwrap()
+            .amount;
+        let (swap_token_a_key, swap_token_b_key) = (next.token_a_key, next.token_b_key);
+        next.swap(
+            &swapper_key,
+            &intermediate_key,
+            &mut intermediate_account,
+            &swap_token_a_key,
+            &swap_token_b_key,
+            &destination_key,
+            &mut destination_account,
+            intermediate_amount,
+            0,
+        )
+        .unwrap();
+        let expected_amount_out = utils::unpack_token_account(&destination_account.data)
+            .unwrap()
+            .amount;
+        assert!(expected_amount_out > 0);
+
+        let (mut first, mut next) = pools();
+        let (source_key, mut source_account, intermediate_key, mut intermediate_account, _, _) =
+            first.setup_token_accounts(&user_key, &swapper_key, amount_in, 0, 0);
+        let (_, _, destination_key, mut destination_account, _, _) =
+            next.setup_token_accounts(&user_key, &swapper_key, 0, 0, 0);
+
+        // slippage is only bounded on the last leg
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            first.route(
+                &mut next,
+                &swapper_key,
+                &source_key,
+                &mut source_account,
+                &intermediate_key,
+                &mut intermediate_account,
+                &destination_key,
+                &mut destination_account,
+                amount_in,
+                expected_amount_out + 1,
+            )
+        );
+
+        first
+            .route(
+                &mut next,
+                &swapper_key,
+                &source_key,
+                &mut source_account,
+                &intermediate_key,
+                &mut intermediate_account,
+                &destination_key,
+                &mut destination_account,
+                amount_in,
+                expected_amount_out,
+            )
+            .unwrap();
+        let source = utils::unpack_token_account(&source_account.data).unwrap();
+        assert_eq!(source.amount, 0);
+        // no intermediate dust is left behind
+        let intermediate = utils::unpack_token_account(&intermediate_account.data).unwrap();
+        assert_eq!(intermediate.amount, 0);
+        let destination = utils::unpack_token_account(&destination_account.data).unwrap();
+        assert_eq!(destination.amount, expected_amount_out);
+        let swap_token_a = utils::unpack_token_account(&next.token_a_account.data).unwrap();
+        assert_eq!(swap_token_a.amount, 6000 + intermediate_amount);
+    }
+
+    #[test]
+    fn test_swap_with_transfer_mints() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let amount_in = 1000;
+        let swap = |transfer_mints: bool| {
+            let mut accounts = SwapAccountInfo::new(&user_key, 85, 5000, 5000, DEFAULT_TEST_FEES);
+            accounts.initialize_swap().unwrap();
+            accounts.transfer_mints = transfer_mints;
+            let (source_key, mut source_account, destination_key, mut destination_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &swapper_key, amount_in, 0, 0);
+            let (swap_token_a_key, swap_token_b_key) = (accounts.token_a_key, accounts.token_b_key);
+            accounts
+                .swap(
+                    &swapper_key,
+                    &source_key,
+                    &mut source_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &destination_key,
+                    &mut destination_account,
+                    amount_in,
+                    0,
+                )
+                .unwrap();
+            (
+                utils::unpack_token_account(&destination_account.data)
+                    .unwrap()
+                    .amount,
+                utils::unpack_token_account(&accounts.token_b_account.data)
+                    .unwrap()
+                    .amount,
+            )
+        };
+
+        // spl_token mints charge no transfer fee, passing them only checks the transfers
+        let (amount_out, token_b_amount) = swap(false);
+        assert!(amount_out > 0);
+        assert_eq!(swap(true), (amount_out, token_b_amount));
+    }
+
+    #[test]
+    fn test_withdraw_and_harvest_with_transfer_mints() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let harvester_key = pubkey_rand();
+        let withdraw_and_harvest = |transfer_mints: bool| {
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, 85, 100_000, 100_000, DEFAULT_TEST_FEES);
+            accounts.initialize_swap().unwrap();
+            accounts.set_harvest_tip(500).unwrap();
+            accounts.transfer_mints = transfer_mints;
+            let (swap_token_a_key, swap_token_b_key) = (accounts.token_a_key, accounts.token_b_key);
+            let (source_key, mut source_account, destination_key, mut destination_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &swapper_key, 10_000, 0, 0);
+            accounts
+                .swap(
+                    &swapper_key,
+                    &source_key,
+                    &mut source_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &destination_key,
+                    &mut destination_account,
+                    10_000,
+                    0,
+                )
+                .unwrap();
+
+            let pool_amount = 1_000;
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &user_key, 0, 0, pool_amount);
+            accounts
+                .withdraw(
+                    &user_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    pool_amount,
+                    1,
+                    1,
+                )
+                .unwrap();
+
+            let (tip_a_key, mut tip_a_account, tip_b_key, mut tip_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &harvester_key, 0, 0, 0);
+            accounts
+                .harvest_admin_fees(
+                    &tip_a_key,
+                    &mut tip_a_account,
+                    &tip_b_key,
+                    &mut tip_b_account,
+                )
+                .unwrap();
+            [
+                &token_a_account,
+                &token_b_account,
+                &tip_b_account,
+                &accounts.admin_fee_b_account,
+            ]
+            .map(|account| utils::unpack_token_account(&account.data).unwrap().amount)
+        };
+
+        let amounts = withdraw_and_harvest(false);
+        assert!(amounts.iter().all(|amount| *amount > 0));
+        assert_eq!(withdraw_and_harvest(true), amounts);
+    }
+
+    #[test]
+    fn test_swap_with_receipt() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let amp_factor = 85;
+        let token_a_amount = 5000;
+        let token_b_amount = 5000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        let a_to_b_amount = initial_a / 10;
+        let minimum_b_amount = initial_b / 20;
+
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+
+        let receipt_id = 7;
+        let (receipt_key, receipt_nonce) = utils::find_receipt_address(
+            &SWAP_PROGRAM_ID,
+            &accounts.swap_key,
+            &swapper_key,
+            receipt_id,
+        );
+        let new_receipt_account =
+            || Account::new(0, SwapReceipt::LEN, &solana_program::system_program::id());
+        let receipt_lamports = Rent::default().minimum_balance(SwapReceipt::LEN);
+        let payer_lamports = receipt_lamports * 2;
+        let mut swapper_account = Account::new(payer_lamports, 0, &Pubkey::default());
+
+        // wrong receipt nonce
+        {
+            let mut receipt_account = new_receipt_account();
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.swap_with_receipt(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &receipt_key,
+                    &mut receipt_account,
+                    &swapper_key,
+                    &mut swapper_account,
+                    receipt_id,
+                    receipt_nonce.wrapping_add(1),
+                    a_to_b_amount,
+                    minimum_b_amount,
+                )
+            );
+        }
+
+        // receipt of another id
+        {
+            let mut receipt_account = new_receipt_account();
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.swap_with_receipt(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &receipt_key,
+                    &mut receipt_account,
+                    &swapper_key,
+                    &mut swapper_account,
+                    receipt_id + 1,
+                    receipt_nonce,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                )
+            );
+        }
+
+        // receipt owner not signing
+        {
+            let mut receipt_account = new_receipt_account();
+            let mut fee_accounts = accounts.get_fee_accounts(&swap_token_b_key).clone();
+            let mut swap_token_a_account = accounts.token_a_account.clone();
+            let mut swap_token_b_account = accounts.token_b_account.clone();
+            let mut instruction = swap_with_receipt(
+                swap(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &accounts.swap_key,
+                    &accounts.authority_key,
+                    &token_a_key,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &fee_accounts.reflection_key,
+                    &fee_accounts.buyback_key,
+                    &fee_accounts.marketing_key,
+                    &fee_accounts.developer_key,
+                    None,
+                    None,
+                    None,
+                    None,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                    0,
+                    None,
+                )
+                .unwrap(),
+                &receipt_key,
+                &swapper_key,
+                receipt_id,
+                receipt_nonce,
+            )
+            .unwrap();
+            instruction.accounts[1].is_signer = false;
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                do_process_instruction(
+                    instruction,
+                    vec![
+                        &mut receipt_account,
+                        &mut swapper_account,
+                        &mut Account::default(),
+                        &mut rent_account(),
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut token_a_account,
+                        &mut swap_token_a_account,
+                        &mut swap_token_b_account,
+                        &mut token_b_account,
+                        &mut fee_accounts.reflection_account,
+                        &mut fee_accounts.buyback_account,
+                        &mut fee_accounts.marketing_account,
+                        &mut fee_accounts.developer_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                        &mut Account::default(),
+                    ],
+                )
+            );
+        }
+
+        // correct swap
+        let mut receipt_account = new_receipt_account();
+        accounts
+            .swap_with_receipt(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                &receipt_key,
+                &mut receipt_account,
+                &swapper_key,
+                &mut swapper_account,
+                receipt_id,
+                receipt_nonce,
+                a_to_b_amount,
+                minimum_b_amount,
+            )
+            .unwrap();
+
+        let invariant = StableSwap::new(
+            accounts.initial_amp_factor,
+            accounts.target_amp_factor,
+            ZERO_TS,
+            ZERO_TS,
+            ZERO_TS,
+        );
+        let result = invariant
+            .swap_to_v2(
+                U256::from(a_to_b_amount),
+                U256::from(token_a_amount),
+                U256::from(token_b_amount),
+                &DEFAULT_TEST_FEES,
+            )
+            .unwrap();
+        let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
+        assert_eq!(
+            token_b.amount,
+            initial_b + U256::to_u64(result.amount_swapped).unwrap()
+        );
+
+        assert_eq!(receipt_account.owner, SWAP_PROGRAM_ID);
+        assert_eq!(receipt_account.lamports, receipt_lamports);
+        assert_eq!(swapper_account.lamports, payer_lamports - receipt_lamports);
+        let receipt = SwapReceipt::unpack(&receipt_account.data).unwrap();
+        assert_eq!(
+            receipt,
+            SwapReceipt {
+                is_initialized: true,
+                swap: accounts.swap_key,
+                owner: swapper_key,
+                source_mint: accounts.token_a_mint_key,
+                destination_mint: accounts.token_b_mint_key,
+                amount_in: a_to_b_amount,
+                amount_out: U256::to_u64(result.amount_swapped).unwrap(),
+                fee_amount: U256::to_u64(result.trade_fee).unwrap(),
+                slot: 0,
+            }
+        );
+
+        // receipt already created
+        {
+            let mut receipt_account = receipt_account.clone();
+            assert_eq!(
+                Err(ProgramError::AccountAlreadyInitialized),
+                accounts.swap_with_receipt(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &receipt_key,
+                    &mut receipt_account,
+                    &swapper_key,
+                    &mut swapper_account,
+                    receipt_id,
+                    receipt_nonce,
+                    a_to_b_amount,
+                    minimum_b_amount,
+                )
+            );
+        }
+
+        let destination_key = pubkey_rand();
+        let mut destination_account = Account::default();
+
+        // close by someone other than the receipt owner
+        {
+            let other_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                do_process_instruction(
+                    close_swap_receipt(
+                        &SWAP_PROGRAM_ID,
+                        &receipt_key,
+                        &other_key,
+                        &destination_key
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut receipt_account,
+                        &mut Account::default(),
+                        &mut destination_account,
+                    ],
+                )
+            );
+        }
+
+        // correct close
+        do_process_instruction(
+            close_swap_receipt(
+                &SWAP_PROGRAM_ID,
+                &receipt_key,
+                &swapper_key,
+                &destination_key,
+            )
+            .unwrap(),
+            vec![
+                &mut receipt_account,
+                &mut swapper_account,
+                &mut destination_account,
+            ],
+        )
+        .unwrap();
+        assert_eq!(receipt_account.lamports, 0);
+        assert_eq!(destination_account.lamports, receipt_lamports);
+        assert!(receipt_account.data.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_deposit_one() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            100,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &depositor_key, 50_000, 50_000, 0);
+        let invariant = StableSwap::new(
+            accounts.initial_amp_factor,
+            accounts.target_amp_factor,
+            ZERO_TS,
+            ZERO_TS,
+            ZERO_TS,
+        );
+        let mint_amount =
+            |accounts: &SwapAccountInfo, deposit_a: u64, deposit_b: u64, fees: &Fees| {
+                let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+                U256::to_u64(
+                    invariant
+                        .compute_mint_amount_for_deposit(
+                            U256::from(deposit_a),
+                            U256::from(deposit_b),
+                            U256::from(amount(&accounts.token_a_account)),
+                            U256::from(amount(&accounts.token_b_account)),
+                            U256::from(pool_mint.supply),
+                            fees,
+                        )
+                        .unwrap(),
+                )
+                .unwrap()
+            };
+        let expected = mint_amount(&accounts, 20_000, 0, &DEFAULT_TEST_FEES);
+        // the part implicitly swapped to token B is charged the trade fee
+        let no_trade_fee = Fees {
+            trade_fee_numerator: 0,
+            ..DEFAULT_TEST_FEES
+        };
+        assert!(expected < mint_amount(&accounts, 20_000, 0, &no_trade_fee));
+
+        // slippage exceeded
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.deposit_one(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                false,
+                20_000,
+                expected + 1,
+            )
+        );
+
+        let sequence = SwapInfo::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .sequence;
+        accounts
+            .deposit_one(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                false,
+                20_000,
+                expected,
+            )
+            .unwrap();
+        assert_eq!(amount(&pool_account), expected);
+        assert_eq!(amount(&token_a_account), 30_000);
+        assert_eq!(amount(&token_b_account), 50_000);
+        assert_eq!(amount(&accounts.token_a_account), token_a_amount + 20_000);
+        assert_eq!(amount(&accounts.token_b_account), token_b_amount);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.sequence, sequence + 1);
+
+        // token B rebalances the pool
+        let expected_b = mint_amount(&accounts, 0, 20_000, &DEFAULT_TEST_FEES);
+        accounts
+            .deposit_one(
+                &depositor_key,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                true,
+                20_000,
+                0,
+            )
+            .unwrap();
+        assert_eq!(amount(&pool_account), expected + expected_b);
+        assert_eq!(amount(&token_b_account), 30_000);
+
+        // a source of the other token
+        assert_eq!(
+            Err(TokenError::MintMismatch.into()),
+            accounts.deposit_one(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &pool_key,
+                &mut pool_account,
+                true,
+                1_000,
+                0,
+            )
+        );
+    }
+
+    #[test]
+    fn test_withdraw_imbalanced() {
+        let user_key = pubkey_rand();
+        let withdrawer_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            100,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, 100_000);
+        let invariant = StableSwap::new(
+            accounts.initial_amp_factor,
+            accounts.target_amp_factor,
+            ZERO_TS,
+            ZERO_TS,
+            ZERO_TS,
+        );
+        let burn_amount = |accounts: &SwapAccountInfo, fees: &Fees| {
+            let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+            invariant
+                .compute_burn_amount_for_withdraw(
+                    U256::from(20_000),
+                    U256::from(5_000),
+                    U256::from(amount(&accounts.token_a_account)),
+                    U256::from(amount(&accounts.token_b_account)),
+                    U256::from(pool_mint.supply),
+                    fees,
+                )
+                .unwrap()
+        };
+        let (expected, trade_fee_a, trade_fee_b) = burn_amount(&accounts, &DEFAULT_TEST_FEES);
+        let expected = U256::to_u64(expected).unwrap();
+        // the part implicitly swapped from token B is charged the trade fee
+        let no_trade_fee = Fees {
+            trade_fee_numerator: 0,
+            ..DEFAULT_TEST_FEES
+        };
+        assert!(U256::from(expected) > burn_amount(&accounts, &no_trade_fee).0);
+        let admin_fee_a =
+            U256::to_u64(DEFAULT_TEST_FEES.admin_trade_fee(trade_fee_a).unwrap()).unwrap();
+        let admin_fee_b =
+            U256::to_u64(DEFAULT_TEST_FEES.admin_trade_fee(trade_fee_b).unwrap()).unwrap();
+
+        // nothing to withdraw
+        assert_eq!(
+            Err(SwapError::InvalidInput.into()),
+            accounts.withdraw_imbalanced(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                0,
+                0,
+                expected,
+            )
+        );
+
+        // slippage exceeded
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.withdraw_imbalanced(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                20_000,
+                5_000,
+                expected - 1,
+            )
+        );
+
+        let sequence = SwapInfo::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .sequence;
+        accounts
+            .withdraw_imbalanced(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                20_000,
+                5_000,
+                expected,
+            )
+            .unwrap();
+        assert_eq!(amount(&pool_account), 100_000 - expected);
+        assert_eq!(amount(&token_a_account), 20_000);
+        assert_eq!(amount(&token_b_account), 5_000);
+        assert_eq!(amount(&accounts.admin_fee_a_account), admin_fee_a);
+        assert_eq!(amount(&accounts.admin_fee_b_account), admin_fee_b);
+        assert_eq!(
+            amount(&accounts.token_a_account),
+            token_a_amount - 20_000 - admin_fee_a
+        );
+        assert_eq!(
+            amount(&accounts.token_b_account),
+            token_b_amount - 5_000 - admin_fee_b
+        );
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.sequence, sequence + 1);
+
+        // more than the pool tokens held
+        assert_eq!(
+            Err(TokenError::InsufficientFunds.into()),
+            accounts.withdraw_imbalanced(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                500_000,
+                0,
+                u64::MAX,
+            )
+        );
+    }
+
+    #[test]
+    fn test_withdraw_one() {
+        let user_key = pubkey_rand();
+        let amp_factor = MIN_AMP;
+        let token_a_amount = 1000;
+        let token_b_amount = 1000;
         let mut accounts = SwapAccountInfo::new(
             &user_key,
             amp_factor,
@@ -939,826 +13439,1804 @@ mod tests {
             token_b_amount,
             DEFAULT_TEST_FEES,
         );
+        let withdrawer_key = pubkey_rand();
+        let initial_a = token_a_amount / 10;
+        let initial_b = token_b_amount / 10;
+        let initial_pool = initial_a + initial_b;
+        // Withdraw entire pool share
+        let withdraw_amount = initial_pool;
+        let minimum_amount = 0;
+
+        // swap not initialized
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            assert_eq!(
+                Err(ProgramError::UninitializedAccount),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
+            );
+        }
+
+        accounts.initialize_swap().unwrap();
+
         // wrong nonce for authority_key
         {
-            let old_nonce = accounts.nonce;
-            accounts.nonce = old_nonce - 1;
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
+            let old_authority = accounts.authority_key;
+            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
+                &[&accounts.swap_key.to_bytes()[..]],
+                &TOKEN_PROGRAM_ID,
+            );
+            accounts.authority_key = bad_authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidProgramAddress.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
+            );
+            accounts.authority_key = old_authority;
+        }
+
+        // not enough pool tokens
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
+            );
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount * 100,
+                    minimum_amount,
+                )
+            );
+        }
+
+        // same swap / quote accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
+            );
+
+            let old_token_b_key = accounts.token_b_key;
+            let old_token_b_account = accounts.token_b_account;
+            accounts.token_b_key = accounts.token_a_key.clone();
+            accounts.token_b_account = accounts.token_a_account.clone();
+
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
+            );
+
+            accounts.token_b_key = old_token_b_key;
+            accounts.token_b_account = old_token_b_account;
+        }
+
+        // foreign swap / quote accounts
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
+            );
+            let foreign_authority = pubkey_rand();
+            let (foreign_mint_key, mut foreign_mint_account) = create_mint(
+                &TOKEN_PROGRAM_ID,
+                &foreign_authority,
+                DEFAULT_TOKEN_DECIMALS,
+                None,
+            );
+            let (foreign_token_key, foreign_token_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &foreign_mint_key,
+                &mut foreign_mint_account,
+                &foreign_authority,
+                &pubkey_rand(),
+                0,
+            );
+
+            let old_token_a_key = accounts.token_a_key;
+            let old_token_a_account = accounts.token_a_account;
+            accounts.token_a_key = foreign_token_key.clone();
+            accounts.token_a_account = foreign_token_account.clone();
+
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
+            );
+
+            accounts.token_a_key = old_token_a_key;
+            accounts.token_a_account = old_token_a_account;
+
+            let old_token_b_key = accounts.token_b_key;
+            let old_token_b_account = accounts.token_b_account;
+            accounts.token_b_key = foreign_token_key.clone();
+            accounts.token_b_account = foreign_token_account.clone();
+
+            assert_eq!(
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
+            );
+
+            accounts.token_b_key = old_token_b_key;
+            accounts.token_b_account = old_token_b_account;
+        }
+
+        // wrong pool token account
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                wrong_token_b_key,
+                mut wrong_token_b_account,
+                _pool_key,
+                _pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                withdraw_amount,
+                withdraw_amount,
+            );
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.initialize_swap()
+                Err(TokenError::MintMismatch.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &wrong_token_b_key,
+                    &mut wrong_token_b_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
             );
-            accounts.nonce = old_nonce;
         }
 
-        // invalid amp factors
+        // no approval
         {
-            let old_initial_amp_factor = accounts.initial_amp_factor;
-            accounts.initial_amp_factor = MIN_AMP - 1;
-            // amp factor too low
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, withdraw_amount);
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.initialize_swap()
+                Err(TokenError::OwnerMismatch.into()),
+                do_process_instruction(
+                    withdraw_one(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &accounts.admin_fee_a_key,
+                        None,
+                        None,
+                        withdraw_amount,
+                        minimum_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut accounts.admin_fee_a_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                    ],
+                )
             );
-            accounts.initial_amp_factor = MAX_AMP + 1;
-            // amp factor too high
+        }
+
+        // wrong token program id
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
+            );
+            let wrong_key = pubkey_rand();
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.initialize_swap()
+                Err(ProgramError::IncorrectProgramId),
+                do_process_instruction(
+                    withdraw_one(
+                        &SWAP_PROGRAM_ID,
+                        &wrong_key,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &accounts.pool_mint_key,
+                        &pool_key,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &accounts.admin_fee_a_key,
+                        None,
+                        None,
+                        withdraw_amount,
+                        minimum_amount,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut accounts.pool_mint_account,
+                        &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut accounts.admin_fee_a_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                    ],
+                )
             );
-            accounts.initial_amp_factor = old_initial_amp_factor;
         }
 
-        // uninitialized token a account
+        // wrong mint
         {
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = Account::default();
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool,
+            );
+            let (pool_mint_key, pool_mint_account) = create_mint(
+                &TOKEN_PROGRAM_ID,
+                &accounts.authority_key,
+                DEFAULT_TOKEN_DECIMALS,
+                None,
+            );
+            let old_pool_key = accounts.pool_mint_key;
+            let old_pool_account = accounts.pool_mint_account;
+            accounts.pool_mint_key = pool_mint_key;
+            accounts.pool_mint_account = pool_mint_account;
+
             assert_eq!(
-                Err(SwapError::ExpectedAccount.into()),
-                accounts.initialize_swap()
+                Err(SwapError::IncorrectMint.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
+            );
+
+            accounts.pool_mint_key = old_pool_key;
+            accounts.pool_mint_account = old_pool_account;
+        }
+
+        // wrong destination account
+        {
+            let (
+                _token_a_key,
+                _token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
+            );
+
+            assert_eq!(
+                Err(TokenError::MintMismatch.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
             );
-            accounts.token_a_account = old_account;
         }
 
-        // uninitialized token b account
+        // wrong admin account
         {
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = Account::default();
-            assert_eq!(
-                Err(SwapError::ExpectedAccount.into()),
-                accounts.initialize_swap()
+            let (
+                wrong_admin_key,
+                wrong_admin_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                withdraw_amount,
             );
-            accounts.token_b_account = old_account;
-        }
 
-        // uninitialized pool mint
-        {
-            let old_account = accounts.pool_mint_account;
-            accounts.pool_mint_account = Account::default();
+            let old_admin_a_key = accounts.admin_fee_a_key;
+            let old_admin_a_account = accounts.admin_fee_a_account;
+            accounts.admin_fee_a_key = wrong_admin_key;
+            accounts.admin_fee_a_account = wrong_admin_account;
+
             assert_eq!(
-                Err(SwapError::ExpectedMint.into()),
-                accounts.initialize_swap()
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
             );
-            accounts.pool_mint_account = old_account;
+
+            accounts.admin_fee_a_key = old_admin_a_key;
+            accounts.admin_fee_a_account = old_admin_a_account;
         }
 
-        // token A account owner is not swap authority
+        // slippage exceeeded
         {
-            let (_token_a_key, token_a_account) = mint_token(
-                &TOKEN_PROGRAM_ID,
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
-                &user_key,
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
                 &user_key,
-                0,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool,
             );
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = token_a_account;
+
+            let high_minimum_amount = 100000;
             assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                accounts.initialize_swap()
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    high_minimum_amount,
+                )
             );
-            accounts.token_a_account = old_account;
         }
 
-        // token B account owner is not swap authority
+        // correct withdraw
         {
-            let (_token_b_key, token_b_account) = mint_token(
-                &TOKEN_PROGRAM_ID,
-                &accounts.token_b_mint_key,
-                &mut accounts.token_b_mint_account,
-                &user_key,
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
                 &user_key,
-                0,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool,
             );
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_b_account;
+
+            let old_swap_token_a =
+                utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+            let old_swap_token_b =
+                utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+            let old_pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+
+            let invariant = StableSwap::new(
+                accounts.initial_amp_factor,
+                accounts.target_amp_factor,
+                ZERO_TS,
+                ZERO_TS,
+                ZERO_TS,
+            );
+            let (withdraw_one_amount_before_fees, withdraw_one_trade_fee) = invariant
+                .compute_withdraw_one(
+                    withdraw_amount.into(),
+                    old_pool_mint.supply.into(),
+                    old_swap_token_a.amount.into(),
+                    old_swap_token_b.amount.into(),
+                    &DEFAULT_TEST_FEES,
+                )
+                .unwrap();
+            let withdraw_one_withdraw_fee = DEFAULT_TEST_FEES
+                .withdraw_fee(withdraw_one_amount_before_fees)
+                .unwrap();
+            let expected_withdraw_one_amount =
+                withdraw_one_amount_before_fees - withdraw_one_withdraw_fee;
+            let expected_admin_fee = U256::to_u64(
+                DEFAULT_TEST_FEES
+                    .admin_trade_fee(withdraw_one_trade_fee)
+                    .unwrap()
+                    + DEFAULT_TEST_FEES
+                        .admin_withdraw_fee(withdraw_one_withdraw_fee)
+                        .unwrap(),
+            )
+            .unwrap();
+
+            accounts
+                .withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
+                .unwrap();
+
+            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
             assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                accounts.initialize_swap()
+                old_swap_token_a.amount - swap_token_a.amount - expected_admin_fee,
+                U256::to_u64(expected_withdraw_one_amount).unwrap()
             );
-            accounts.token_b_account = old_account;
+            let admin_fee_key_a =
+                utils::unpack_token_account(&accounts.admin_fee_a_account.data).unwrap();
+            assert_eq!(admin_fee_key_a.amount, expected_admin_fee);
+            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+            assert_eq!(swap_token_b.amount, old_swap_token_b.amount);
+            let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+            assert_eq!(pool_mint.supply, old_pool_mint.supply - withdraw_amount);
         }
 
-        // pool token account owner is swap authority
+        // pool is paused
         {
-            let (_pool_token_key, pool_token_account) = mint_token(
-                &TOKEN_PROGRAM_ID,
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &accounts.authority_key,
-                0,
+            let (
+                token_a_key,
+                mut token_a_account,
+                _token_b_key,
+                _token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(
+                &user_key,
+                &withdrawer_key,
+                initial_a,
+                initial_b,
+                initial_pool,
             );
-            let old_account = accounts.pool_token_account;
-            accounts.pool_token_account = pool_token_account;
+            // pause pool
+            accounts.pause().unwrap();
+
             assert_eq!(
-                Err(SwapError::InvalidOutputOwner.into()),
-                accounts.initialize_swap()
+                Err(SwapError::IsPaused.into()),
+                accounts.withdraw_one(
+                    &withdrawer_key,
+                    &pool_key,
+                    &mut pool_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    withdraw_amount,
+                    minimum_amount,
+                )
             );
-            accounts.pool_token_account = old_account;
         }
+    }
 
-        // pool mint authority is not swap authority
+    #[test]
+    fn test_reflection_distribution_claims() {
+        let user_key = pubkey_rand();
+        let distributor_key = pubkey_rand();
+        let holder_keys = [pubkey_rand(), pubkey_rand()];
+        let amounts = [300, 700];
+        let total_amount = amounts[0] + amounts[1];
+        let amp_factor = 85;
+        let token_a_amount = 1000;
+        let token_b_amount = 1000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let (vault_key, mut vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &accounts.authority_key,
+            total_amount,
+        );
+        let leaves = [
+            merkle::reflection_leaf(0, &holder_keys[0], amounts[0]),
+            merkle::reflection_leaf(1, &holder_keys[1], amounts[1]),
+        ];
+        let merkle_root = merkle::hash_pair(&leaves[0], &leaves[1]);
+        let mut distributor_account = Account::new(
+            Rent::default().minimum_balance(ReflectionDistribution::LEN),
+            0,
+            &solana_program::system_program::id(),
+        );
+        let new_distribution_account = || {
+            Account::new(
+                0,
+                ReflectionDistribution::LEN,
+                &solana_program::system_program::id(),
+            )
+        };
+        let (distribution_key, _nonce) =
+            utils::find_reflection_distribution_address(&SWAP_PROGRAM_ID, &accounts.swap_key, 0);
+
+        // no distributor set
         {
-            let (_pool_mint_key, pool_mint_account) =
-                create_mint(&TOKEN_PROGRAM_ID, &user_key, DEFAULT_TOKEN_DECIMALS, None);
-            let old_mint = accounts.pool_mint_account;
-            accounts.pool_mint_account = pool_mint_account;
             assert_eq!(
-                Err(SwapError::InvalidOwner.into()),
-                accounts.initialize_swap()
+                Err(SwapError::Unauthorized.into()),
+                accounts.commit_reflection_root(
+                    &distributor_key,
+                    &mut distributor_account,
+                    &mut new_distribution_account(),
+                    &vault_key,
+                    &mut vault_account,
+                    merkle_root,
+                    total_amount,
+                )
             );
-            accounts.pool_mint_account = old_mint;
         }
 
-        // pool mint token has freeze authority
+        accounts
+            .set_reflection_distributor(&distributor_key)
+            .unwrap();
+
+        // wrong distributor
         {
-            let (_pool_mint_key, pool_mint_account) = create_mint(
-                &TOKEN_PROGRAM_ID,
-                &accounts.authority_key,
-                DEFAULT_TOKEN_DECIMALS,
-                Some(&user_key),
+            let wrong_distributor_key = pubkey_rand();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.commit_reflection_root(
+                    &wrong_distributor_key,
+                    &mut distributor_account.clone(),
+                    &mut new_distribution_account(),
+                    &vault_key,
+                    &mut vault_account,
+                    merkle_root,
+                    total_amount,
+                )
             );
-            let old_mint = accounts.pool_mint_account;
-            accounts.pool_mint_account = pool_mint_account;
+        }
+
+        // pool reserves as vault
+        {
+            let token_a_key = accounts.token_a_key;
+            let mut token_a_account = accounts.token_a_account.clone();
             assert_eq!(
-                Err(SwapError::InvalidFreezeAuthority.into()),
-                accounts.initialize_swap()
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.commit_reflection_root(
+                    &distributor_key,
+                    &mut distributor_account,
+                    &mut new_distribution_account(),
+                    &token_a_key,
+                    &mut token_a_account,
+                    merkle_root,
+                    total_amount,
+                )
             );
-            accounts.pool_mint_account = old_mint;
         }
 
-        // empty token A account
+        // vault not owned by the swap authority
         {
-            let (_token_a_key, token_a_account) = mint_token(
+            let (other_vault_key, mut other_vault_account) = mint_token(
                 &TOKEN_PROGRAM_ID,
                 &accounts.token_a_mint_key,
                 &mut accounts.token_a_mint_account,
                 &user_key,
-                &accounts.authority_key,
-                0,
+                &distributor_key,
+                total_amount,
             );
-            let old_account = accounts.token_a_account;
-            accounts.token_a_account = token_a_account;
             assert_eq!(
-                Err(SwapError::EmptySupply.into()),
-                accounts.initialize_swap()
+                Err(SwapError::InvalidOwner.into()),
+                accounts.commit_reflection_root(
+                    &distributor_key,
+                    &mut distributor_account,
+                    &mut new_distribution_account(),
+                    &other_vault_key,
+                    &mut other_vault_account,
+                    merkle_root,
+                    total_amount,
+                )
             );
-            accounts.token_a_account = old_account;
         }
 
-        // empty token B account
+        // vault balance lower than the distribution
         {
-            let (_token_b_key, token_b_account) = mint_token(
-                &TOKEN_PROGRAM_ID,
-                &accounts.token_b_mint_key,
-                &mut accounts.token_b_mint_account,
-                &user_key,
-                &accounts.authority_key,
-                0,
-            );
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_b_account;
             assert_eq!(
-                Err(SwapError::EmptySupply.into()),
-                accounts.initialize_swap()
+                Err(SwapError::InvalidInput.into()),
+                accounts.commit_reflection_root(
+                    &distributor_key,
+                    &mut distributor_account,
+                    &mut new_distribution_account(),
+                    &vault_key,
+                    &mut vault_account,
+                    merkle_root,
+                    total_amount + 1,
+                )
             );
-            accounts.token_b_account = old_account;
         }
 
-        // invalid pool tokens
-        {
-            let old_mint = accounts.pool_mint_account;
-            let old_pool_account = accounts.pool_token_account;
-
-            let (_pool_mint_key, pool_mint_account) = create_mint(
-                &TOKEN_PROGRAM_ID,
-                &accounts.authority_key,
-                DEFAULT_TOKEN_DECIMALS,
-                None,
-            );
-            accounts.pool_mint_account = pool_mint_account;
+        // valid commit
+        let mut distribution_account = new_distribution_account();
+        accounts
+            .commit_reflection_root(
+                &distributor_key,
+                &mut distributor_account,
+                &mut distribution_account,
+                &vault_key,
+                &mut vault_account,
+                merkle_root,
+                total_amount,
+            )
+            .unwrap();
+        assert_eq!(distribution_account.owner, SWAP_PROGRAM_ID);
+        let distribution = ReflectionDistribution::unpack(&distribution_account.data).unwrap();
+        assert_eq!(distribution.swap, accounts.swap_key);
+        assert_eq!(distribution.epoch, 0);
+        assert_eq!(distribution.vault, vault_key);
+        assert_eq!(distribution.merkle_root, merkle_root);
+        assert_eq!(distribution.total_amount, total_amount);
+        assert_eq!(distribution.claimed_amount, 0);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.reflection_epoch, 1);
 
-            let (_empty_pool_token_key, empty_pool_token_account) = mint_token(
-                &TOKEN_PROGRAM_ID,
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &user_key,
+        let claim_lamports = Rent::default().minimum_balance(ReflectionClaim::LEN);
+        let mut holder_accounts: Vec<Account> = (0..2)
+            .map(|_| Account::new(claim_lamports, 0, &solana_program::system_program::id()))
+            .collect();
+        let new_claim_account = || {
+            Account::new(
                 0,
-            );
-
-            let (_pool_token_key, pool_token_account) = mint_token(
-                &TOKEN_PROGRAM_ID,
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
-                &user_key,
-                pool_token_amount,
-            );
-
-            // non-empty pool token account
-            accounts.pool_token_account = pool_token_account;
-            assert_eq!(
-                Err(SwapError::InvalidSupply.into()),
-                accounts.initialize_swap()
-            );
+                ReflectionClaim::LEN,
+                &solana_program::system_program::id(),
+            )
+        };
+        let (destination_key, mut destination_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &holder_keys[0],
+            0,
+        );
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
 
-            // pool tokens already in circulation
-            accounts.pool_token_account = empty_pool_token_account;
+        // wrong amount
+        {
             assert_eq!(
-                Err(SwapError::InvalidSupply.into()),
-                accounts.initialize_swap()
+                Err(SwapError::InvalidMerkleProof.into()),
+                accounts.claim_reflection_with_proof(
+                    &distribution_key,
+                    &mut distribution_account,
+                    &vault_key,
+                    &mut vault_account,
+                    &holder_keys[0],
+                    &mut holder_accounts[0],
+                    &mut new_claim_account(),
+                    &destination_key,
+                    &mut destination_account,
+                    0,
+                    amounts[1],
+                    vec![leaves[1]],
+                )
             );
-
-            accounts.pool_mint_account = old_mint;
-            accounts.pool_token_account = old_pool_account;
         }
 
-        // token A account is delegated
+        // entitlement of another holder
         {
-            do_process_instruction(
-                approve(
-                    &TOKEN_PROGRAM_ID,
-                    &accounts.token_a_key,
-                    &user_key,
-                    &accounts.authority_key,
-                    &[],
+            assert_eq!(
+                Err(SwapError::InvalidMerkleProof.into()),
+                accounts.claim_reflection_with_proof(
+                    &distribution_key,
+                    &mut distribution_account,
+                    &vault_key,
+                    &mut vault_account,
+                    &holder_keys[0],
+                    &mut holder_accounts[0],
+                    &mut new_claim_account(),
+                    &destination_key,
+                    &mut destination_account,
                     1,
+                    amounts[1],
+                    vec![leaves[0]],
                 )
-                .unwrap(),
-                vec![
-                    &mut accounts.token_a_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
+            );
+        }
+
+        // valid claims
+        let mut claim_account = new_claim_account();
+        accounts
+            .claim_reflection_with_proof(
+                &distribution_key,
+                &mut distribution_account,
+                &vault_key,
+                &mut vault_account,
+                &holder_keys[0],
+                &mut holder_accounts[0],
+                &mut claim_account,
+                &destination_key,
+                &mut destination_account,
+                0,
+                amounts[0],
+                vec![leaves[1]],
             )
             .unwrap();
+        assert_eq!(amount(&destination_account), amounts[0]);
+        assert_eq!(amount(&vault_account), amounts[1]);
+        let claim = ReflectionClaim::unpack(&claim_account.data).unwrap();
+        assert_eq!(claim.distribution, distribution_key);
+        assert_eq!(claim.index, 0);
+        assert_eq!(claim.claimant, holder_keys[0]);
+        assert_eq!(claim.amount, amounts[0]);
+
+        // double claim
+        {
+            holder_accounts[0].lamports = claim_lamports;
             assert_eq!(
-                Err(SwapError::InvalidDelegate.into()),
-                accounts.initialize_swap()
+                Err(ProgramError::AccountAlreadyInitialized),
+                accounts.claim_reflection_with_proof(
+                    &distribution_key,
+                    &mut distribution_account,
+                    &vault_key,
+                    &mut vault_account,
+                    &holder_keys[0],
+                    &mut holder_accounts[0],
+                    &mut claim_account,
+                    &destination_key,
+                    &mut destination_account,
+                    0,
+                    amounts[0],
+                    vec![leaves[1]],
+                )
             );
+        }
 
-            do_process_instruction(
-                revoke(
-                    &TOKEN_PROGRAM_ID,
-                    &accounts.token_a_key,
-                    &accounts.authority_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_a_account, &mut Account::default()],
+        let (holder_destination_key, mut holder_destination_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &holder_keys[1],
+            0,
+        );
+        accounts
+            .claim_reflection_with_proof(
+                &distribution_key,
+                &mut distribution_account,
+                &vault_key,
+                &mut vault_account,
+                &holder_keys[1],
+                &mut holder_accounts[1],
+                &mut new_claim_account(),
+                &holder_destination_key,
+                &mut holder_destination_account,
+                1,
+                amounts[1],
+                vec![leaves[0]],
             )
             .unwrap();
-        }
+        assert_eq!(amount(&holder_destination_account), amounts[1]);
+        assert_eq!(amount(&vault_account), 0);
+        let distribution = ReflectionDistribution::unpack(&distribution_account.data).unwrap();
+        assert_eq!(distribution.claimed_amount, total_amount);
+    }
 
-        // token B account is delegated
-        {
-            do_process_instruction(
-                approve(
-                    &TOKEN_PROGRAM_ID,
-                    &accounts.token_b_key,
-                    &user_key,
-                    &accounts.authority_key,
-                    &[],
-                    1,
-                )
-                .unwrap(),
-                vec![
-                    &mut accounts.token_b_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
+    #[test]
+    fn test_claim_vested_dev_fees() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let dev_wallet_key = pubkey_rand();
+        let amp_factor = 85;
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let vesting_duration = 1_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let authority_key = accounts.authority_key;
+        let (dev_vesting_a_key, mut dev_vesting_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let (dev_vesting_b_key, mut dev_vesting_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        accounts
+            .initialize_dev_fee_vesting(
+                &dev_vesting_a_key,
+                &mut dev_vesting_a_account,
+                &dev_vesting_b_key,
+                &mut dev_vesting_b_account,
+                &dev_wallet_key,
+                vesting_duration,
             )
             .unwrap();
+
+        let initial_a = token_a_amount / 5;
+        let initial_b = token_b_amount / 5;
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+
+        // developer fee not sent to the vesting account
+        {
             assert_eq!(
-                Err(SwapError::InvalidDelegate.into()),
-                accounts.initialize_swap()
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.swap(
+                    &swapper_key,
+                    &token_a_key,
+                    &mut token_a_account.clone(),
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account.clone(),
+                    initial_a / 10,
+                    0,
+                )
             );
+        }
 
-            do_process_instruction(
-                revoke(
-                    &TOKEN_PROGRAM_ID,
-                    &accounts.token_b_key,
-                    &accounts.authority_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_b_account, &mut Account::default()],
+        accounts.fee_accounts_a.developer_key = dev_vesting_a_key;
+        accounts.fee_accounts_a.developer_account = dev_vesting_a_account;
+        accounts.fee_accounts_b.developer_key = dev_vesting_b_key;
+        accounts.fee_accounts_b.developer_account = dev_vesting_b_account;
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                initial_a / 10,
+                0,
+            )
+            .unwrap();
+        accounts
+            .swap(
+                &swapper_key,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_token_b_key,
+                &swap_token_a_key,
+                &token_a_key,
+                &mut token_a_account,
+                initial_b / 10,
+                0,
             )
             .unwrap();
-        }
 
-        // token A account has close authority
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        let dev_fees_a = swap_info.dev_fees_received_a;
+        let dev_fees_b = swap_info.dev_fees_received_b;
+        assert!(dev_fees_a > 0 && dev_fees_b > 0);
+        assert_eq!(
+            amount(&accounts.fee_accounts_a.developer_account),
+            dev_fees_a
+        );
+        assert_eq!(
+            amount(&accounts.fee_accounts_b.developer_account),
+            dev_fees_b
+        );
+
+        let (destination_a_key, mut destination_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &dev_wallet_key,
+            0,
+        );
+        let (destination_b_key, mut destination_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &dev_wallet_key,
+            0,
+        );
+
+        // wrong developer wallet
         {
-            do_process_instruction(
-                set_authority(
-                    &TOKEN_PROGRAM_ID,
-                    &accounts.token_a_key,
-                    Some(&user_key),
-                    AuthorityType::CloseAccount,
-                    &accounts.authority_key,
-                    &[],
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.claim_vested_dev_fees(
+                    &pubkey_rand(),
+                    &destination_a_key,
+                    &mut destination_a_account,
+                    &destination_b_key,
+                    &mut destination_b_account,
+                    ZERO_TS + vesting_duration,
                 )
-                .unwrap(),
-                vec![&mut accounts.token_a_account, &mut Account::default()],
+            );
+        }
+
+        // a quarter of the vesting period
+        let quarter_ts = ZERO_TS + vesting_duration / 4;
+        accounts
+            .claim_vested_dev_fees(
+                &dev_wallet_key,
+                &destination_a_key,
+                &mut destination_a_account,
+                &destination_b_key,
+                &mut destination_b_account,
+                quarter_ts,
             )
             .unwrap();
-            assert_eq!(
-                Err(SwapError::InvalidCloseAuthority.into()),
-                accounts.initialize_swap()
-            );
+        assert_eq!(amount(&destination_a_account), dev_fees_a / 4);
+        assert_eq!(amount(&destination_b_account), dev_fees_b / 4);
 
-            do_process_instruction(
-                set_authority(
-                    &TOKEN_PROGRAM_ID,
-                    &accounts.token_a_key,
-                    None,
-                    AuthorityType::CloseAccount,
+        // nothing more vested at the same time
+        accounts
+            .claim_vested_dev_fees(
+                &dev_wallet_key,
+                &destination_a_key,
+                &mut destination_a_account,
+                &destination_b_key,
+                &mut destination_b_account,
+                quarter_ts,
+            )
+            .unwrap();
+        assert_eq!(amount(&destination_a_account), dev_fees_a / 4);
+        assert_eq!(amount(&destination_b_account), dev_fees_b / 4);
+
+        // fully vested
+        accounts
+            .claim_vested_dev_fees(
+                &dev_wallet_key,
+                &destination_a_key,
+                &mut destination_a_account,
+                &destination_b_key,
+                &mut destination_b_account,
+                ZERO_TS + 2 * vesting_duration,
+            )
+            .unwrap();
+        assert_eq!(amount(&destination_a_account), dev_fees_a);
+        assert_eq!(amount(&destination_b_account), dev_fees_b);
+        assert_eq!(amount(&accounts.fee_accounts_a.developer_account), 0);
+        assert_eq!(amount(&accounts.fee_accounts_b.developer_account), 0);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.dev_fees_claimed_a, dev_fees_a);
+        assert_eq!(swap_info.dev_fees_claimed_b, dev_fees_b);
+    }
+
+    #[test]
+    fn test_governance_deposits() {
+        let user_key = pubkey_rand();
+        let realm_key = pubkey_rand();
+        let amp_factor = 85;
+        let token_a_amount = 1000;
+        let token_b_amount = 1000;
+        let governance_lockup = 100;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let authority_key = accounts.authority_key;
+        let (governance_vault_key, mut governance_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        let pool_token_key = accounts.pool_token_key;
+        let mut pool_token_account = accounts.pool_token_account.clone();
+        let mut owner_account = Account::new(
+            Rent::default().minimum_balance(GovernanceDeposit::LEN)
+                + Rent::default().minimum_balance(VoterWeightRecord::LEN),
+            0,
+            &solana_program::system_program::id(),
+        );
+        let mut governance_deposit_account = Account::new(
+            0,
+            GovernanceDeposit::LEN,
+            &solana_program::system_program::id(),
+        );
+        let mut voter_weight_record_account = Account::new(
+            0,
+            VoterWeightRecord::LEN,
+            &solana_program::system_program::id(),
+        );
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+
+        // no governance realm
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.deposit_governance_tokens(
                     &user_key,
-                    &[],
+                    &mut owner_account,
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &mut governance_vault_account,
+                    &mut governance_deposit_account,
+                    &mut voter_weight_record_account,
+                    1_000,
+                    ZERO_TS,
                 )
-                .unwrap(),
-                vec![&mut accounts.token_a_account, &mut Account::default()],
-            )
-            .unwrap();
+            );
         }
 
-        // token B account has close authority
-        {
-            do_process_instruction(
-                set_authority(
-                    &TOKEN_PROGRAM_ID,
-                    &accounts.token_b_key,
-                    Some(&user_key),
-                    AuthorityType::CloseAccount,
-                    &accounts.authority_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_b_account, &mut Account::default()],
+        accounts
+            .set_governance_realm(
+                &realm_key,
+                &governance_vault_key,
+                &mut governance_vault_account,
+                governance_lockup,
             )
             .unwrap();
-            assert_eq!(
-                Err(SwapError::InvalidCloseAuthority.into()),
-                accounts.initialize_swap()
-            );
 
-            do_process_instruction(
-                set_authority(
-                    &TOKEN_PROGRAM_ID,
-                    &accounts.token_b_key,
-                    None,
-                    AuthorityType::CloseAccount,
-                    &user_key,
-                    &[],
-                )
-                .unwrap(),
-                vec![&mut accounts.token_b_account, &mut Account::default()],
+        // first deposit creates the deposit and the voter weight record
+        accounts
+            .deposit_governance_tokens(
+                &user_key,
+                &mut owner_account,
+                &pool_token_key,
+                &mut pool_token_account,
+                &mut governance_vault_account,
+                &mut governance_deposit_account,
+                &mut voter_weight_record_account,
+                1_000,
+                ZERO_TS,
             )
             .unwrap();
-        }
+        assert_eq!(owner_account.lamports, 0);
+        assert_eq!(voter_weight_record_account.owner, SWAP_PROGRAM_ID);
+        let voter_weight_record =
+            VoterWeightRecord::unpack(&voter_weight_record_account.data).unwrap();
+        assert_eq!(voter_weight_record.realm, realm_key);
+        assert_eq!(
+            voter_weight_record.governing_token_mint,
+            accounts.pool_mint_key
+        );
+        assert_eq!(voter_weight_record.governing_token_owner, user_key);
+        assert_eq!(voter_weight_record.voter_weight, 1_000);
+        assert_eq!(amount(&governance_vault_account), 1_000);
 
-        // mismatched admin mints
-        {
-            let (wrong_admin_fee_key, wrong_admin_fee_account) = mint_token(
-                &TOKEN_PROGRAM_ID,
-                &accounts.pool_mint_key,
-                &mut accounts.pool_mint_account,
-                &accounts.authority_key,
+        // later deposits add up and extend the lockup
+        accounts
+            .deposit_governance_tokens(
                 &user_key,
-                0,
-            );
+                &mut owner_account,
+                &pool_token_key,
+                &mut pool_token_account,
+                &mut governance_vault_account,
+                &mut governance_deposit_account,
+                &mut voter_weight_record_account,
+                500,
+                ZERO_TS + 50,
+            )
+            .unwrap();
+        let governance_deposit =
+            GovernanceDeposit::unpack(&governance_deposit_account.data).unwrap();
+        assert_eq!(governance_deposit.swap, accounts.swap_key);
+        assert_eq!(governance_deposit.owner, user_key);
+        assert_eq!(governance_deposit.amount, 1_500);
+        assert_eq!(
+            governance_deposit.unlock_ts,
+            ZERO_TS + 50 + governance_lockup
+        );
+        let voter_weight_record =
+            VoterWeightRecord::unpack(&voter_weight_record_account.data).unwrap();
+        assert_eq!(voter_weight_record.voter_weight, 1_500);
 
-            // wrong admin_fee_key_a
-            let old_admin_fee_account_a = accounts.admin_fee_a_account;
-            let old_admin_fee_key_a = accounts.admin_fee_a_key;
-            accounts.admin_fee_a_account = wrong_admin_fee_account.clone();
-            accounts.admin_fee_a_key = wrong_admin_fee_key;
+        let (destination_key, mut destination_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &user_key,
+            0,
+        );
+        let unlock_ts = governance_deposit.unlock_ts;
 
+        // strangers can't withdraw the deposited pool tokens from the vault
+        {
+            let stranger_key = pubkey_rand();
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &stranger_key, 0, 0, 0);
             assert_eq!(
-                Err(SwapError::InvalidAdmin.into()),
-                accounts.initialize_swap()
+                Err(SwapError::AuthorityOwnedSource.into()),
+                accounts.withdraw_unapproved(
+                    &stranger_key,
+                    &governance_vault_key,
+                    &mut governance_vault_account.clone(),
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    1_000,
+                    0,
+                    0,
+                )
             );
+        }
 
-            accounts.admin_fee_a_account = old_admin_fee_account_a;
-            accounts.admin_fee_a_key = old_admin_fee_key_a;
-
-            // wrong admin_fee_key_b
-            let old_admin_fee_account_b = accounts.admin_fee_b_account;
-            let old_admin_fee_key_b = accounts.admin_fee_b_key;
-            accounts.admin_fee_b_account = wrong_admin_fee_account.clone();
-            accounts.admin_fee_b_key = wrong_admin_fee_key;
-
+        // still locked
+        {
             assert_eq!(
-                Err(SwapError::InvalidAdmin.into()),
-                accounts.initialize_swap()
+                Err(SwapError::GovernanceDepositLocked.into()),
+                accounts.withdraw_governance_tokens(
+                    &user_key,
+                    &mut governance_deposit_account,
+                    &mut voter_weight_record_account,
+                    &mut governance_vault_account,
+                    &destination_key,
+                    &mut destination_account,
+                    600,
+                    unlock_ts - 1,
+                )
             );
-
-            accounts.admin_fee_b_account = old_admin_fee_account_b;
-            accounts.admin_fee_b_key = old_admin_fee_key_b;
         }
 
-        // mimatched mint decimals
+        // deposit of another owner
         {
-            let (bad_mint_key, mut bad_mint_account) =
-                create_mint(&TOKEN_PROGRAM_ID, &accounts.authority_key, 2, None);
-
-            // Pool mint decimal does not match
-            let old_pool_mint_key = accounts.pool_mint_key;
-            let old_pool_mint_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = bad_mint_key;
-            accounts.pool_mint_account = bad_mint_account.clone();
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.withdraw_governance_tokens(
+                    &pubkey_rand(),
+                    &mut governance_deposit_account,
+                    &mut voter_weight_record_account,
+                    &mut governance_vault_account,
+                    &destination_key,
+                    &mut destination_account,
+                    600,
+                    unlock_ts,
+                )
+            );
+        }
 
+        // more than deposited
+        {
             assert_eq!(
-                Err(SwapError::MismatchedDecimals.into()),
-                accounts.initialize_swap()
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_governance_tokens(
+                    &user_key,
+                    &mut governance_deposit_account,
+                    &mut voter_weight_record_account,
+                    &mut governance_vault_account,
+                    &destination_key,
+                    &mut destination_account,
+                    1_501,
+                    unlock_ts,
+                )
             );
+        }
 
-            accounts.pool_mint_key = old_pool_mint_key;
-            accounts.pool_mint_account = old_pool_mint_account;
+        accounts
+            .withdraw_governance_tokens(
+                &user_key,
+                &mut governance_deposit_account,
+                &mut voter_weight_record_account,
+                &mut governance_vault_account,
+                &destination_key,
+                &mut destination_account,
+                600,
+                unlock_ts,
+            )
+            .unwrap();
+        assert_eq!(amount(&destination_account), 600);
+        assert_eq!(amount(&governance_vault_account), 900);
+        let governance_deposit =
+            GovernanceDeposit::unpack(&governance_deposit_account.data).unwrap();
+        assert_eq!(governance_deposit.amount, 900);
+        let voter_weight_record =
+            VoterWeightRecord::unpack(&voter_weight_record_account.data).unwrap();
+        assert_eq!(voter_weight_record.voter_weight, 900);
+    }
 
-            // Token a mint decimal does not match token b decimals
-            let (bad_token_key, bad_token_account) = mint_token(
-                &TOKEN_PROGRAM_ID,
-                &bad_mint_key,
-                &mut bad_mint_account,
-                &accounts.authority_key,
-                &accounts.authority_key,
-                10,
-            );
+    #[test]
+    fn test_begin_and_continue_withdraw() {
+        let user_key = pubkey_rand();
+        let amp_factor = 85;
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 2_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
 
-            let old_token_a_key = accounts.token_a_key;
-            let old_token_a_account = accounts.token_a_account;
-            let old_token_a_mint_key = accounts.token_a_mint_key;
-            let old_token_a_mint_account = accounts.token_a_mint_account;
-            accounts.token_a_key = bad_token_key;
-            accounts.token_a_account = bad_token_account;
-            accounts.token_a_mint_key = bad_mint_key;
-            accounts.token_a_mint_account = bad_mint_account.clone();
+        let pool_token_key = accounts.pool_token_key;
+        let mut pool_token_account = accounts.pool_token_account.clone();
+        let (
+            dest_a_key,
+            mut dest_a_account,
+            dest_b_key,
+            mut dest_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &user_key, 0, 0, 0);
+        let continuation_lamports = Rent::default().minimum_balance(WithdrawContinuation::LEN);
+        let mut owner_account = Account::new(
+            continuation_lamports,
+            0,
+            &solana_program::system_program::id(),
+        );
+        let new_withdraw_continuation_account = || {
+            Account::new(
+                0,
+                WithdrawContinuation::LEN,
+                &solana_program::system_program::id(),
+            )
+        };
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let initial_pool = amount(&pool_token_account);
+        let withdraw_amount = initial_pool / 2;
 
+        // destinations of the wrong mint
+        {
+            let mut dest_b_for_a_account = dest_b_account.clone();
+            let mut dest_a_for_b_account = dest_a_account.clone();
             assert_eq!(
-                Err(SwapError::MismatchedDecimals.into()),
-                accounts.initialize_swap()
+                Err(SwapError::IncorrectMint.into()),
+                accounts.begin_withdraw(
+                    &user_key,
+                    &mut owner_account,
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &dest_b_key,
+                    &mut dest_b_for_a_account,
+                    &dest_a_key,
+                    &mut dest_a_for_b_account,
+                    &mut new_withdraw_continuation_account(),
+                    withdraw_amount,
+                    0,
+                    0,
+                )
             );
-
-            accounts.token_a_key = old_token_a_key;
-            accounts.token_a_account = old_token_a_account;
-            accounts.token_a_mint_key = old_token_a_mint_key;
-            accounts.token_a_mint_account = old_token_a_mint_account;
         }
 
-        // create swap with same token A and B
-        {
-            let (_token_a_repeat_key, token_a_repeat_account) = mint_token(
-                &TOKEN_PROGRAM_ID,
-                &accounts.token_a_mint_key,
-                &mut accounts.token_a_mint_account,
+        let mut withdraw_continuation_account = new_withdraw_continuation_account();
+        accounts
+            .begin_withdraw(
                 &user_key,
-                &accounts.authority_key,
-                10,
+                &mut owner_account,
+                &pool_token_key,
+                &mut pool_token_account,
+                &dest_a_key,
+                &mut dest_a_account,
+                &dest_b_key,
+                &mut dest_b_account,
+                &mut withdraw_continuation_account,
+                withdraw_amount,
+                0,
+                0,
+            )
+            .unwrap();
+        assert_eq!(amount(&pool_token_account), initial_pool - withdraw_amount);
+        let continuation =
+            WithdrawContinuation::unpack(&withdraw_continuation_account.data).unwrap();
+        let withdrawn_a = (u128::from(token_a_amount) * u128::from(withdraw_amount)
+            / u128::from(initial_pool)) as u64;
+        let withdrawn_b = (u128::from(token_b_amount) * u128::from(withdraw_amount)
+            / u128::from(initial_pool)) as u64;
+        let fee_a = withdrawn_a * DEFAULT_TEST_FEES.withdraw_fee_numerator
+            / DEFAULT_TEST_FEES.withdraw_fee_denominator;
+        let fee_b = withdrawn_b * DEFAULT_TEST_FEES.withdraw_fee_numerator
+            / DEFAULT_TEST_FEES.withdraw_fee_denominator;
+        let admin_fee_a = fee_a * DEFAULT_TEST_FEES.admin_withdraw_fee_numerator
+            / DEFAULT_TEST_FEES.admin_withdraw_fee_denominator;
+        let admin_fee_b = fee_b * DEFAULT_TEST_FEES.admin_withdraw_fee_numerator
+            / DEFAULT_TEST_FEES.admin_withdraw_fee_denominator;
+        assert_eq!(
+            continuation,
+            WithdrawContinuation {
+                is_initialized: true,
+                swap: accounts.swap_key,
+                owner: user_key,
+                destination_a: dest_a_key,
+                destination_b: dest_b_key,
+                token_a_amount: withdrawn_a - fee_a,
+                token_a_admin_fee: admin_fee_a,
+                token_b_amount: withdrawn_b - fee_b,
+                token_b_admin_fee: admin_fee_b,
+            }
+        );
+
+        // the tokens owed are excluded from the pool
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(amount(&accounts.token_a_account), token_a_amount);
+        assert_eq!(
+            swap_info.pool_amount(&accounts.token_a_key, token_a_amount),
+            Some(token_a_amount - (withdrawn_a - fee_a) - admin_fee_a)
+        );
+        assert_eq!(
+            swap_info.pool_amount(&accounts.token_b_key, token_b_amount),
+            Some(token_b_amount - (withdrawn_b - fee_b) - admin_fee_b)
+        );
+
+        // continuation already exists
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.begin_withdraw(
+                    &user_key,
+                    &mut owner_account,
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &dest_a_key,
+                    &mut dest_a_account,
+                    &dest_b_key,
+                    &mut dest_b_account,
+                    &mut withdraw_continuation_account,
+                    withdraw_amount,
+                    0,
+                    0,
+                )
             );
-            let old_account = accounts.token_b_account;
-            accounts.token_b_account = token_a_repeat_account;
+        }
+
+        // destination not recorded in the continuation
+        {
+            let token_a_key = accounts.token_a_key;
+            let (other_dest_a_key, mut other_dest_a_account, _, _, _, _) =
+                accounts.setup_token_accounts(&user_key, &user_key, 0, 0, 0);
             assert_eq!(
-                Err(SwapError::RepeatedMint.into()),
-                accounts.initialize_swap()
+                Err(SwapError::InvalidInput.into()),
+                accounts.continue_withdraw(
+                    &user_key,
+                    &mut owner_account,
+                    &mut withdraw_continuation_account,
+                    &token_a_key,
+                    &other_dest_a_key,
+                    &mut other_dest_a_account,
+                )
             );
-            accounts.token_b_account = old_account;
         }
 
-        // create valid swap
-        accounts.initialize_swap().unwrap();
+        let token_a_key = accounts.token_a_key;
+        let token_b_key = accounts.token_b_key;
+        accounts
+            .continue_withdraw(
+                &user_key,
+                &mut owner_account,
+                &mut withdraw_continuation_account,
+                &token_a_key,
+                &dest_a_key,
+                &mut dest_a_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&dest_a_account), withdrawn_a - fee_a);
+        assert_eq!(amount(&accounts.admin_fee_a_account), admin_fee_a);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.pending_withdraw_a, 0);
+        assert_eq!(
+            withdraw_continuation_account.lamports,
+            continuation_lamports
+        );
 
-        // create again
+        // token A already paid out
         {
             assert_eq!(
-                Err(SwapError::AlreadyInUse.into()),
-                accounts.initialize_swap()
+                Err(SwapError::InvalidInput.into()),
+                accounts.continue_withdraw(
+                    &user_key,
+                    &mut owner_account,
+                    &mut withdraw_continuation_account,
+                    &token_a_key,
+                    &dest_a_key,
+                    &mut dest_a_account,
+                )
             );
         }
+
+        accounts
+            .continue_withdraw(
+                &user_key,
+                &mut owner_account,
+                &mut withdraw_continuation_account,
+                &token_b_key,
+                &dest_b_key,
+                &mut dest_b_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&dest_b_account), withdrawn_b - fee_b);
+        assert_eq!(amount(&accounts.admin_fee_b_account), admin_fee_b);
         let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
-        assert_eq!(swap_info.is_initialized, true);
-        assert_eq!(swap_info.is_paused, false);
-        assert_eq!(swap_info.nonce, accounts.nonce);
-        assert_eq!(swap_info.initial_amp_factor, amp_factor);
-        assert_eq!(swap_info.target_amp_factor, amp_factor);
-        assert_eq!(swap_info.start_ramp_ts, ZERO_TS);
-        assert_eq!(swap_info.stop_ramp_ts, ZERO_TS);
-        assert_eq!(swap_info.future_admin_deadline, ZERO_TS);
-        assert_eq!(swap_info.future_admin_key, Pubkey::default());
-        assert_eq!(swap_info.admin_key, accounts.admin_key);
-        assert_eq!(swap_info.token_a, accounts.token_a_key);
-        assert_eq!(swap_info.token_b, accounts.token_b_key);
-        assert_eq!(swap_info.pool_mint, accounts.pool_mint_key);
-        assert_eq!(swap_info.token_a_mint, accounts.token_a_mint_key);
-        assert_eq!(swap_info.token_b_mint, accounts.token_b_mint_key);
-        assert_eq!(swap_info.admin_fee_key_a, accounts.admin_fee_a_key);
-        assert_eq!(swap_info.admin_fee_key_b, accounts.admin_fee_b_key);
-        assert_eq!(swap_info.fees, DEFAULT_TEST_FEES);
-        let token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
-        assert_eq!(token_a.amount, token_a_amount);
-        let token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
-        assert_eq!(token_b.amount, token_b_amount);
-        let pool_account = utils::unpack_token_account(&accounts.pool_token_account.data).unwrap();
-        let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
-        assert_eq!(pool_mint.supply, pool_account.amount);
-    }
+        assert_eq!(swap_info.pending_withdraw_b, 0);
 
-    #[test]
-    fn test_deposit() {
-        let user_key = pubkey_rand();
-        let depositor_key = pubkey_rand();
-        let amp_factor = MIN_AMP;
-        let token_a_amount = 1000;
-        let token_b_amount = 9000;
-        let mut accounts = SwapAccountInfo::new(
-            &user_key,
-            amp_factor,
-            token_a_amount,
-            token_b_amount,
-            DEFAULT_TEST_FEES,
-        );
+        // the continuation is closed once both tokens are paid out
+        assert_eq!(withdraw_continuation_account.lamports, 0);
+        assert_eq!(owner_account.lamports, continuation_lamports);
+    }
 
-        let deposit_a = token_a_amount / 10;
-        let deposit_b = token_b_amount / 10;
-        let min_mint_amount = 0;
+    #[test]
+    fn test_emit_pool_summary() {
+        let user_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 85, 1_000_000, 2_000_000, DEFAULT_TEST_FEES);
 
         // swap not initialized
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
             assert_eq!(
                 Err(ProgramError::UninitializedAccount),
-                accounts.deposit(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
-                )
+                accounts.emit_pool_summary()
             );
         }
 
         accounts.initialize_swap().unwrap();
 
-        // wrong nonce for authority_key
+        // swap not owned by the program
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
-            );
-            accounts.authority_key = bad_authority_key;
+            let old_swap_account = accounts.swap_account.clone();
+            accounts.swap_account.owner = pubkey_rand();
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.deposit(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
-                )
+                Err(ProgramError::IncorrectProgramId),
+                accounts.emit_pool_summary()
             );
-            accounts.authority_key = old_authority;
+            accounts.swap_account = old_swap_account;
         }
 
-        // not enough token A
+        // wrong swap token account
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &depositor_key,
-                deposit_a / 2,
-                deposit_b,
-                0,
-            );
+            let old_token_b_key = accounts.token_b_key;
+            accounts.token_b_key = accounts.token_a_key;
             assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.deposit(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
-                )
+                Err(SwapError::IncorrectSwapAccount.into()),
+                accounts.emit_pool_summary()
             );
+            accounts.token_b_key = old_token_b_key;
         }
 
-        // not enough token B
+        // wrong pool mint
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &depositor_key,
-                deposit_a,
-                deposit_b / 2,
-                0,
-            );
+            let old_pool_mint_key = accounts.pool_mint_key;
+            accounts.pool_mint_key = pubkey_rand();
             assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.deposit(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
-                )
+                Err(SwapError::IncorrectMint.into()),
+                accounts.emit_pool_summary()
             );
+            accounts.pool_mint_key = old_pool_mint_key;
         }
 
-        // wrong swap token accounts
+        let swap_account = accounts.swap_account.clone();
+        accounts.emit_pool_summary().unwrap();
+        assert_eq!(accounts.swap_account, swap_account);
+    }
+
+    #[test]
+    fn test_get_pool_status() {
+        let user_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 85, 1_000_000, 2_000_000, DEFAULT_TEST_FEES);
+
+        // swap not initialized
+        assert_eq!(
+            Err(ProgramError::UninitializedAccount),
+            accounts.get_pool_status(0)
+        );
+
+        accounts.initialize_swap().unwrap();
+
+        // swap not owned by the program
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let old_swap_account = accounts.swap_account.clone();
+            accounts.swap_account.owner = pubkey_rand();
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.deposit(
-                    &depositor_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
-                )
+                Err(ProgramError::IncorrectProgramId),
+                accounts.get_pool_status(0)
             );
+            accounts.swap_account = old_swap_account;
         }
 
-        // wrong pool token account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                mut _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let (
-                wrong_token_key,
-                mut wrong_token_account,
-                _token_b_key,
-                mut _token_b_account,
-                _pool_key,
-                mut _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.deposit(
+        // reported even while paused, without modifying the swap
+        accounts.pause().unwrap();
+        let swap_account = accounts.swap_account.clone();
+        accounts.get_pool_status(0).unwrap();
+        assert_eq!(accounts.swap_account, swap_account);
+    }
+    #[test]
+    fn test_soulbound_pool_tokens() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            85,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        let (pool_mint_key, mut pool_mint_account) = create_mint(
+            &TOKEN_PROGRAM_ID,
+            &accounts.authority_key,
+            DEFAULT_TOKEN_DECIMALS,
+            Some(&accounts.authority_key),
+        );
+        let (pool_token_key, pool_token_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &pool_mint_key,
+            &mut pool_mint_account,
+            &accounts.authority_key,
+            &user_key,
+            0,
+        );
+        accounts.pool_mint_key = pool_mint_key;
+        accounts.pool_mint_account = pool_mint_account;
+        accounts.pool_token_key = pool_token_key;
+        accounts.pool_token_account = pool_token_account;
+        accounts.initialize_swap().unwrap();
+
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_info.lp_soulbound);
+        let pool_token = |account: &Account| utils::unpack_token_account(&account.data).unwrap();
+        assert!(pool_token(&accounts.pool_token_account).is_frozen());
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &depositor_key, 2_000, 2_000, 0);
+        for _ in 0..2 {
+            accounts
+                .deposit(
                     &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
-                    &wrong_token_key,
-                    &mut wrong_token_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
+                    &pool_key,
+                    &mut pool_account,
+                    1_000,
+                    1_000,
+                    0,
                 )
-            );
+                .unwrap();
+            assert!(pool_token(&pool_account).is_frozen());
         }
+        let pool_amount = pool_token(&pool_account).amount;
+        assert!(pool_amount > 0);
 
-        // no approval
+        // pool tokens can't be transferred
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            let (_, _, _, _, other_pool_key, mut other_pool_account) =
+                accounts.setup_token_accounts(&user_key, &user_key, 0, 0, 0);
             assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
+                Err(TokenError::AccountFrozen.into()),
                 do_process_instruction(
-                    deposit(
-                        &SWAP_PROGRAM_ID,
+                    spl_token::instruction::transfer(
                         &TOKEN_PROGRAM_ID,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &token_b_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &accounts.pool_mint_key,
                         &pool_key,
-                        deposit_a,
-                        deposit_b,
-                        min_mint_amount,
+                        &other_pool_key,
+                        &depositor_key,
+                        &[],
+                        1,
                     )
                     .unwrap(),
                     vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut token_b_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut accounts.pool_mint_account,
                         &mut pool_account,
+                        &mut other_pool_account,
                         &mut Account::default(),
-                        &mut clock_account(ZERO_TS),
                     ],
                 )
             );
         }
 
-        // wrong token program id
+        // governance deposits would transfer pool tokens
+        {
+            let (governance_vault_key, mut governance_vault_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &accounts.authority_key,
+                &accounts.authority_key,
+                0,
+            );
+            assert_eq!(
+                Err(SwapError::LpSoulbound.into()),
+                accounts.set_governance_realm(
+                    &pubkey_rand(),
+                    &governance_vault_key,
+                    &mut governance_vault_account,
+                    0,
+                )
+            );
+        }
+
+        // burn not signed by the owner
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let wrong_key = pubkey_rand();
             assert_eq!(
-                Err(ProgramError::InvalidAccountData),
+                Err(ProgramError::NotEnoughAccountKeys),
                 do_process_instruction(
-                    deposit(
+                    withdraw(
                         &SWAP_PROGRAM_ID,
-                        &wrong_key,
+                        &TOKEN_PROGRAM_ID,
                         &accounts.swap_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &token_b_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
+                        &accounts.authority_key,
                         &accounts.pool_mint_key,
                         &pool_key,
-                        deposit_a,
-                        deposit_b,
-                        min_mint_amount,
+                        &accounts.token_a_key,
+                        &accounts.token_b_key,
+                        &token_a_key,
+                        &token_b_key,
+                        &accounts.admin_fee_a_key,
+                        &accounts.admin_fee_b_key,
+                        None,
+                        None,
+                        pool_amount / 2,
+                        0,
+                        0,
+                        0,
+                        None,
                     )
                     .unwrap(),
                     vec![
                         &mut accounts.swap_account,
                         &mut Account::default(),
-                        &mut token_a_account,
-                        &mut token_b_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
                         &mut accounts.pool_mint_account,
                         &mut pool_account,
+                        &mut accounts.token_a_account,
+                        &mut accounts.token_b_account,
+                        &mut token_a_account,
+                        &mut token_b_account,
+                        &mut accounts.admin_fee_a_account,
+                        &mut accounts.admin_fee_b_account,
                         &mut Account::default(),
                         &mut clock_account(ZERO_TS),
                     ],
@@ -1766,903 +15244,2972 @@ mod tests {
             );
         }
 
-        // wrong swap token accounts
+        accounts
+            .withdraw(
+                &depositor_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                pool_amount / 2,
+                0,
+                0,
+            )
+            .unwrap();
+        let remaining = pool_amount - pool_amount / 2;
+        assert_eq!(pool_token(&pool_account).amount, remaining);
+        assert!(pool_token(&pool_account).is_frozen());
+
+        accounts
+            .withdraw_one(
+                &depositor_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                remaining,
+                0,
+            )
+            .unwrap();
+        assert_eq!(pool_token(&pool_account).amount, 0);
+        assert!(pool_token(&pool_account).is_frozen());
+    }
+
+    #[test]
+    fn test_ve_fee_sharing() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let harvester_key = pubkey_rand();
+        let other_locker_key = pubkey_rand();
+        let amp_factor = 85;
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let ve_fee_bps = 5_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            amp_factor,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let authority_key = accounts.authority_key;
+        let (ve_lp_vault_key, mut ve_lp_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        let (ve_fee_vault_a_key, mut ve_fee_vault_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let (ve_fee_vault_b_key, mut ve_fee_vault_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let pool_token_key = accounts.pool_token_key;
+        let mut pool_token_account = accounts.pool_token_account.clone();
+        let (other_pool_token_key, mut other_pool_token_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &other_locker_key,
+            1_000,
+        );
+        let new_owner_account = || {
+            Account::new(
+                Rent::default().minimum_balance(VeLock::LEN),
+                0,
+                &solana_program::system_program::id(),
+            )
+        };
+        let new_ve_lock_account =
+            || Account::new(0, VeLock::LEN, &solana_program::system_program::id());
+        let mut owner_account = new_owner_account();
+        let mut other_owner_account = new_owner_account();
+        let mut ve_lock_account = new_ve_lock_account();
+        let mut other_ve_lock_account = new_ve_lock_account();
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+
+        // vote-escrow not enabled
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.create_ve_lock(
+                    &user_key,
+                    &mut owner_account,
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &mut ve_lp_vault_account,
+                    &mut ve_lock_account.clone(),
+                    1_000,
+                    VE_MAX_LOCK_DURATION,
+                    ZERO_TS,
+                )
+            );
+        }
 
-            let old_a_key = accounts.token_a_key;
-            let old_a_account = accounts.token_a_account;
+        accounts
+            .initialize_ve_escrow(
+                &ve_lp_vault_key,
+                &mut ve_lp_vault_account,
+                &ve_fee_vault_a_key,
+                &mut ve_fee_vault_a_account,
+                &ve_fee_vault_b_key,
+                &mut ve_fee_vault_b_account,
+                ve_fee_bps,
+                ZERO_TS,
+            )
+            .unwrap();
 
-            accounts.token_a_key = token_a_key.clone();
-            accounts.token_a_account = token_a_account.clone();
+        // lock shorter than the minimum duration
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.create_ve_lock(
+                    &user_key,
+                    &mut owner_account,
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &mut ve_lp_vault_account,
+                    &mut ve_lock_account.clone(),
+                    1_000,
+                    VE_MIN_LOCK_DURATION - 1,
+                    ZERO_TS,
+                )
+            );
+        }
 
-            // wrong swap token a account
+        // the veLP balance grows with the lock duration
+        accounts
+            .create_ve_lock(
+                &user_key,
+                &mut owner_account,
+                &pool_token_key,
+                &mut pool_token_account,
+                &mut ve_lp_vault_account,
+                &mut ve_lock_account,
+                1_000,
+                VE_MAX_LOCK_DURATION,
+                ZERO_TS,
+            )
+            .unwrap();
+        accounts
+            .create_ve_lock(
+                &other_locker_key,
+                &mut other_owner_account,
+                &other_pool_token_key,
+                &mut other_pool_token_account,
+                &mut ve_lp_vault_account,
+                &mut other_ve_lock_account,
+                1_000,
+                VE_MAX_LOCK_DURATION / 2,
+                ZERO_TS,
+            )
+            .unwrap();
+        assert_eq!(owner_account.lamports, 0);
+        let ve_lock = VeLock::unpack(&ve_lock_account.data).unwrap();
+        assert_eq!(ve_lock.swap, accounts.swap_key);
+        assert_eq!(ve_lock.owner, user_key);
+        assert_eq!(ve_lock.amount, 1_000);
+        assert_eq!(ve_lock.unlock_ts, ZERO_TS + VE_MAX_LOCK_DURATION);
+        assert_eq!(ve_lock.ve_balance, 1_000);
+        let other_ve_lock = VeLock::unpack(&other_ve_lock_account.data).unwrap();
+        assert_eq!(other_ve_lock.ve_balance, 500);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.ve_supply, 1_500);
+        assert_eq!(amount(&ve_lp_vault_account), 2_000);
+
+        // lock already exists
+        {
             assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.deposit(
-                    &depositor_key,
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.create_ve_lock(
+                    &user_key,
+                    &mut new_owner_account(),
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &mut ve_lp_vault_account,
+                    &mut ve_lock_account.clone(),
+                    1_000,
+                    VE_MAX_LOCK_DURATION,
+                    ZERO_TS,
+                )
+            );
+        }
+
+        // strangers can't withdraw the locked pool tokens from the vault
+        {
+            let stranger_key = pubkey_rand();
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &stranger_key, 0, 0, 0);
+            assert_eq!(
+                Err(SwapError::AuthorityOwnedSource.into()),
+                accounts.withdraw_unapproved(
+                    &stranger_key,
+                    &ve_lp_vault_key,
+                    &mut ve_lp_vault_account.clone(),
                     &token_a_key,
                     &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
+                    2_000,
+                    0,
+                    0,
                 )
             );
+        }
 
-            accounts.token_a_key = old_a_key;
-            accounts.token_a_account = old_a_account;
+        // a share of the harvested admin fees goes to the fee vaults
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            _pool_key,
+            _pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 100_000, 0);
+        let (tip_a_key, mut tip_a_account, tip_b_key, mut tip_b_account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &harvester_key, 0, 0, 0);
+        let (swap_token_a_key, swap_token_b_key) = (accounts.token_a_key, accounts.token_b_key);
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                10_000,
+                0,
+            )
+            .unwrap();
+        let admin_fee_b = SwapInfo::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .admin_fees_accrued_b;
+        assert!(admin_fee_b > 0);
+        let ve_fee_b = admin_fee_b * u64::from(ve_fee_bps) / 10_000;
+        accounts
+            .harvest_admin_fees_with_ve(
+                &tip_a_key,
+                &mut tip_a_account,
+                &tip_b_key,
+                &mut tip_b_account,
+                &mut ve_fee_vault_a_account,
+                &mut ve_fee_vault_b_account,
+                ZERO_TS,
+            )
+            .unwrap();
+        assert_eq!(amount(&ve_fee_vault_b_account), ve_fee_b);
+        assert_eq!(
+            amount(&accounts.admin_fee_b_account),
+            admin_fee_b - ve_fee_b
+        );
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.ve_pending_fees_b, ve_fee_b);
 
-            let old_b_key = accounts.token_b_key;
-            let old_b_account = accounts.token_b_account;
+        let (_key, _account, fees_b_key, mut fees_b_account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &user_key, 0, 0, 0);
+        let (fees_a_key, mut fees_a_account, _key, _account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &user_key, 0, 0, 0);
 
-            accounts.token_b_key = token_b_key.clone();
-            accounts.token_b_account = token_b_account.clone();
+        // fees are distributed once the epoch ends
+        accounts
+            .claim_ve_fees(
+                &user_key,
+                &mut ve_lock_account,
+                &mut ve_fee_vault_a_account,
+                &mut ve_fee_vault_b_account,
+                &fees_a_key,
+                &mut fees_a_account,
+                &fees_b_key,
+                &mut fees_b_account,
+                ZERO_TS + VE_EPOCH_DURATION - 1,
+            )
+            .unwrap();
+        assert_eq!(amount(&fees_b_account), 0);
 
-            // wrong swap token b account
+        // fees are shared pro-rata to the veLP balances
+        accounts
+            .claim_ve_fees(
+                &user_key,
+                &mut ve_lock_account,
+                &mut ve_fee_vault_a_account,
+                &mut ve_fee_vault_b_account,
+                &fees_a_key,
+                &mut fees_a_account,
+                &fees_b_key,
+                &mut fees_b_account,
+                ZERO_TS + VE_EPOCH_DURATION,
+            )
+            .unwrap();
+        let claimed_b = amount(&fees_b_account);
+        assert_eq!(claimed_b, ve_fee_b * 2 / 3);
+        assert_eq!(amount(&fees_a_account), 0);
+
+        // fees can't be claimed twice
+        accounts
+            .claim_ve_fees(
+                &user_key,
+                &mut ve_lock_account,
+                &mut ve_fee_vault_a_account,
+                &mut ve_fee_vault_b_account,
+                &fees_a_key,
+                &mut fees_a_account,
+                &fees_b_key,
+                &mut fees_b_account,
+                ZERO_TS + 2 * VE_EPOCH_DURATION,
+            )
+            .unwrap();
+        assert_eq!(amount(&fees_b_account), claimed_b);
+
+        // lock of another owner
+        {
             assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.deposit(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
+                Err(SwapError::Unauthorized.into()),
+                accounts.claim_ve_fees(
+                    &user_key,
+                    &mut other_ve_lock_account.clone(),
+                    &mut ve_fee_vault_a_account,
+                    &mut ve_fee_vault_b_account,
+                    &fees_a_key,
+                    &mut fees_a_account,
+                    &fees_b_key,
+                    &mut fees_b_account,
+                    ZERO_TS + 2 * VE_EPOCH_DURATION,
                 )
             );
+        }
 
-            accounts.token_b_key = old_b_key;
-            accounts.token_b_account = old_b_account;
+        let (_key, _account, other_fees_b_key, mut other_fees_b_account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &other_locker_key, 0, 0, 0);
+        let (other_fees_a_key, mut other_fees_a_account, _key, _account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &other_locker_key, 0, 0, 0);
+        let unlock_ts = other_ve_lock.unlock_ts;
+
+        // still locked
+        {
+            assert_eq!(
+                Err(SwapError::VeLockActive.into()),
+                accounts.withdraw_ve_lock(
+                    &other_locker_key,
+                    &mut other_owner_account,
+                    &mut other_ve_lock_account,
+                    &mut ve_lp_vault_account,
+                    &other_pool_token_key,
+                    &mut other_pool_token_account,
+                    &mut ve_fee_vault_a_account,
+                    &mut ve_fee_vault_b_account,
+                    &other_fees_a_key,
+                    &mut other_fees_a_account,
+                    &other_fees_b_key,
+                    &mut other_fees_b_account,
+                    unlock_ts - 1,
+                )
+            );
         }
 
-        // wrong mint
+        // withdrawing pays the unclaimed fees and closes the lock
+        accounts
+            .withdraw_ve_lock(
+                &other_locker_key,
+                &mut other_owner_account,
+                &mut other_ve_lock_account,
+                &mut ve_lp_vault_account,
+                &other_pool_token_key,
+                &mut other_pool_token_account,
+                &mut ve_fee_vault_a_account,
+                &mut ve_fee_vault_b_account,
+                &other_fees_a_key,
+                &mut other_fees_a_account,
+                &other_fees_b_key,
+                &mut other_fees_b_account,
+                unlock_ts,
+            )
+            .unwrap();
+        // rounding dust is distributed in later epochs
+        let other_claimed_b = amount(&other_fees_b_account);
+        assert!(other_claimed_b >= ve_fee_b / 3);
+        assert!(claimed_b + other_claimed_b <= ve_fee_b);
+        assert_eq!(amount(&other_pool_token_account), 1_000);
+        assert_eq!(amount(&ve_lp_vault_account), 1_000);
+        assert_eq!(
+            other_owner_account.lamports,
+            Rent::default().minimum_balance(VeLock::LEN)
+        );
+        assert_eq!(other_ve_lock_account.lamports, 0);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.ve_supply, 1_000);
+    }
+
+    #[test]
+    fn test_gauge_voting() {
+        let user_key = pubkey_rand();
+        let other_locker_key = pubkey_rand();
+        let emission_per_epoch = 1_000_000;
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 85, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+        let mut pool = SwapAccountInfo::new(&user_key, 85, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        pool.initialize_swap().unwrap();
+
+        let authority_key = accounts.authority_key;
+        let (ve_lp_vault_key, mut ve_lp_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        let (ve_fee_vault_a_key, mut ve_fee_vault_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let (ve_fee_vault_b_key, mut ve_fee_vault_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        accounts
+            .initialize_ve_escrow(
+                &ve_lp_vault_key,
+                &mut ve_lp_vault_account,
+                &ve_fee_vault_a_key,
+                &mut ve_fee_vault_a_account,
+                &ve_fee_vault_b_key,
+                &mut ve_fee_vault_b_account,
+                0,
+                ZERO_TS,
+            )
+            .unwrap();
+
+        let mut controller_account = Account::new(
+            0,
+            GaugeController::LEN,
+            &solana_program::system_program::id(),
+        );
+        accounts.admin_account.lamports = Rent::default().minimum_balance(GaugeController::LEN);
+        accounts
+            .initialize_gauge_controller(&mut controller_account, emission_per_epoch, ZERO_TS)
+            .unwrap();
+        let new_gauge_account =
+            || Account::new(0, Gauge::LEN, &solana_program::system_program::id());
+        let swap_key = accounts.swap_key;
+        let mut swap_account = accounts.swap_account.clone();
+        let mut gauge_a_account = new_gauge_account();
+        accounts.admin_account.lamports = Rent::default().minimum_balance(Gauge::LEN);
+        accounts
+            .add_gauge(
+                &mut controller_account,
+                &swap_key,
+                &mut swap_account,
+                &mut gauge_a_account,
+            )
+            .unwrap();
+        let mut gauge_b_account = new_gauge_account();
+        accounts.admin_account.lamports = Rent::default().minimum_balance(Gauge::LEN);
+        accounts
+            .add_gauge(
+                &mut controller_account,
+                &pool.swap_key,
+                &mut pool.swap_account,
+                &mut gauge_b_account,
+            )
+            .unwrap();
+
+        let pool_token_key = accounts.pool_token_key;
+        let mut pool_token_account = accounts.pool_token_account.clone();
+        let (other_pool_token_key, mut other_pool_token_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &other_locker_key,
+            1_000,
+        );
+        let new_owner_account = || {
+            Account::new(
+                Rent::default().minimum_balance(VeLock::LEN)
+                    + 2 * Rent::default().minimum_balance(GaugeVote::LEN),
+                0,
+                &solana_program::system_program::id(),
+            )
+        };
+        let new_gauge_vote_account =
+            || Account::new(0, GaugeVote::LEN, &solana_program::system_program::id());
+        let mut owner_account = new_owner_account();
+        let mut other_owner_account = new_owner_account();
+        let mut ve_lock_account =
+            Account::new(0, VeLock::LEN, &solana_program::system_program::id());
+        let mut other_ve_lock_account = ve_lock_account.clone();
+        let mut vote_a_account = new_gauge_vote_account();
+        let mut vote_b_account = new_gauge_vote_account();
+        let mut other_vote_b_account = new_gauge_vote_account();
+        accounts
+            .create_ve_lock(
+                &user_key,
+                &mut owner_account,
+                &pool_token_key,
+                &mut pool_token_account,
+                &mut ve_lp_vault_account,
+                &mut ve_lock_account,
+                1_000,
+                VE_MAX_LOCK_DURATION,
+                ZERO_TS,
+            )
+            .unwrap();
+        accounts
+            .create_ve_lock(
+                &other_locker_key,
+                &mut other_owner_account,
+                &other_pool_token_key,
+                &mut other_pool_token_account,
+                &mut ve_lp_vault_account,
+                &mut other_ve_lock_account,
+                1_000,
+                VE_MIN_LOCK_DURATION,
+                ZERO_TS,
+            )
+            .unwrap();
+        let other_ve_balance = VeLock::unpack(&other_ve_lock_account.data)
+            .unwrap()
+            .ve_balance;
+
+        // the votes of a lock split its veLP balance
+        accounts
+            .vote_gauge_weight(
+                &user_key,
+                &mut owner_account,
+                &mut ve_lock_account,
+                &mut controller_account,
+                &swap_key,
+                &mut gauge_a_account,
+                &mut vote_a_account,
+                6_000,
+                ZERO_TS,
+            )
+            .unwrap();
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            let (pool_mint_key, pool_mint_account) = create_mint(
-                &TOKEN_PROGRAM_ID,
-                &accounts.authority_key,
-                DEFAULT_TOKEN_DECIMALS,
-                None,
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.vote_gauge_weight(
+                    &user_key,
+                    &mut owner_account,
+                    &mut ve_lock_account,
+                    &mut controller_account,
+                    &pool.swap_key,
+                    &mut gauge_b_account,
+                    &mut vote_b_account.clone(),
+                    5_000,
+                    ZERO_TS,
+                )
             );
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
+        }
+        accounts
+            .vote_gauge_weight(
+                &user_key,
+                &mut owner_account,
+                &mut ve_lock_account,
+                &mut controller_account,
+                &pool.swap_key,
+                &mut gauge_b_account,
+                &mut vote_b_account,
+                4_000,
+                ZERO_TS,
+            )
+            .unwrap();
+        accounts
+            .vote_gauge_weight(
+                &other_locker_key,
+                &mut other_owner_account,
+                &mut other_ve_lock_account,
+                &mut controller_account,
+                &pool.swap_key,
+                &mut gauge_b_account,
+                &mut other_vote_b_account,
+                10_000,
+                ZERO_TS,
+            )
+            .unwrap();
+        let ve_lock = VeLock::unpack(&ve_lock_account.data).unwrap();
+        assert_eq!(ve_lock.gauge_vote_bps, 10_000);
+        let vote_a = GaugeVote::unpack(&vote_a_account.data).unwrap();
+        assert_eq!(vote_a.weight_bps, 6_000);
+        assert_eq!(vote_a.weight, 600);
+        let gauge_b = Gauge::unpack(&gauge_b_account.data).unwrap();
+        assert_eq!(gauge_b.next_weight, 400 + other_ve_balance);
+        let controller = GaugeController::unpack(&controller_account.data).unwrap();
+        assert_eq!(controller.total_next_weight, 1_000 + other_ve_balance);
+
+        // votes take effect from the next epoch
+        let checkpoint = |accounts: &SwapAccountInfo,
+                          controller_account: &mut Account,
+                          pool_swap_key: &Pubkey,
+                          gauge_account: &mut Account,
+                          unix_timestamp: i64| {
+            accounts
+                .checkpoint_gauge(
+                    controller_account,
+                    pool_swap_key,
+                    gauge_account,
+                    unix_timestamp,
+                )
+                .unwrap();
+            Gauge::unpack(&gauge_account.data).unwrap().emissions
+        };
+        assert_eq!(
+            checkpoint(
+                &accounts,
+                &mut controller_account,
+                &swap_key,
+                &mut gauge_a_account,
+                ZERO_TS
+            ),
+            0
+        );
+        let epoch_ts = ZERO_TS + VE_EPOCH_DURATION;
+        let total_weight = 1_000 + other_ve_balance;
+        let emission_a = emission_per_epoch * 600 / total_weight;
+        let emission_b = emission_per_epoch * (400 + other_ve_balance) / total_weight;
+        assert_eq!(
+            checkpoint(
+                &accounts,
+                &mut controller_account,
+                &swap_key,
+                &mut gauge_a_account,
+                epoch_ts
+            ),
+            emission_a
+        );
+        assert_eq!(
+            checkpoint(
+                &accounts,
+                &mut controller_account,
+                &pool.swap_key,
+                &mut gauge_b_account,
+                epoch_ts
+            ),
+            emission_b
+        );
+        // a gauge is allocated once per epoch
+        assert_eq!(
+            checkpoint(
+                &accounts,
+                &mut controller_account,
+                &swap_key,
+                &mut gauge_a_account,
+                epoch_ts + 1
+            ),
+            emission_a
+        );
 
+        // expired locks can only remove their votes, and can't be withdrawn with votes
+        let unlock_ts = ZERO_TS + VE_MIN_LOCK_DURATION;
+        {
             assert_eq!(
-                Err(SwapError::IncorrectMint.into()),
-                accounts.deposit(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
+                Err(SwapError::InvalidInput.into()),
+                accounts.vote_gauge_weight(
+                    &other_locker_key,
+                    &mut other_owner_account,
+                    &mut other_ve_lock_account,
+                    &mut controller_account,
+                    &pool.swap_key,
+                    &mut gauge_b_account,
+                    &mut other_vote_b_account,
+                    5_000,
+                    unlock_ts,
                 )
             );
-
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
         }
-
-        // slippage exceeeded
+        let (_key, _account, other_fees_b_key, mut other_fees_b_account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &other_locker_key, 0, 0, 0);
+        let (other_fees_a_key, mut other_fees_a_account, _key, _account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &other_locker_key, 0, 0, 0);
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            // min mint_amount in too high
-            let high_min_mint_amount = 10000000000000;
             assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.deposit(
-                    &depositor_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    deposit_a,
-                    deposit_b,
-                    high_min_mint_amount,
+                Err(SwapError::GaugeVoteActive.into()),
+                accounts.withdraw_ve_lock(
+                    &other_locker_key,
+                    &mut other_owner_account,
+                    &mut other_ve_lock_account,
+                    &mut ve_lp_vault_account,
+                    &other_pool_token_key,
+                    &mut other_pool_token_account,
+                    &mut ve_fee_vault_a_account,
+                    &mut ve_fee_vault_b_account,
+                    &other_fees_a_key,
+                    &mut other_fees_a_account,
+                    &other_fees_b_key,
+                    &mut other_fees_b_account,
+                    unlock_ts,
                 )
             );
         }
+        accounts
+            .vote_gauge_weight(
+                &other_locker_key,
+                &mut other_owner_account,
+                &mut other_ve_lock_account,
+                &mut controller_account,
+                &pool.swap_key,
+                &mut gauge_b_account,
+                &mut other_vote_b_account,
+                0,
+                unlock_ts,
+            )
+            .unwrap();
+        accounts
+            .withdraw_ve_lock(
+                &other_locker_key,
+                &mut other_owner_account,
+                &mut other_ve_lock_account,
+                &mut ve_lp_vault_account,
+                &other_pool_token_key,
+                &mut other_pool_token_account,
+                &mut ve_fee_vault_a_account,
+                &mut ve_fee_vault_b_account,
+                &other_fees_a_key,
+                &mut other_fees_a_account,
+                &other_fees_b_key,
+                &mut other_fees_b_account,
+                unlock_ts,
+            )
+            .unwrap();
 
-        // correctly deposit
+        // removed votes stop weighing from the next epoch
+        let next_epoch_ts = (unlock_ts / VE_EPOCH_DURATION + 1) * VE_EPOCH_DURATION;
+        assert_eq!(
+            checkpoint(
+                &accounts,
+                &mut controller_account,
+                &swap_key,
+                &mut gauge_a_account,
+                next_epoch_ts
+            ),
+            emission_a + emission_per_epoch * 600 / 1_000
+        );
+        assert_eq!(
+            checkpoint(
+                &accounts,
+                &mut controller_account,
+                &pool.swap_key,
+                &mut gauge_b_account,
+                next_epoch_ts
+            ),
+            emission_b + emission_per_epoch * 400 / 1_000
+        );
+    }
+
+    #[test]
+    fn test_order_flow_auction() {
+        let user_key = pubkey_rand();
+        let bidder_key = pubkey_rand();
+        let other_bidder_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let batch_slots = 4;
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 85, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let pool_amount_a = |accounts: &SwapAccountInfo| {
+            SwapInfo::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .pool_amount(&swap_token_a_key, amount(&accounts.token_a_account))
+                .unwrap()
+        };
+        let (bid_key, mut bid_account, bid_b_key, mut bid_b_account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &bidder_key, 1_000, 1_000, 0);
+        let (other_bid_key, mut other_bid_account, _key, _account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &other_bidder_key, 1_000, 0, 0);
+        let (swapper_a_key, mut swapper_a_account, swapper_b_key, mut swapper_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 1_000, 0, 0);
+
+        // auction not enabled
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            accounts
-                .deposit(
-                    &depositor_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &pool_key,
-                    &mut pool_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.bid_order_flow(
+                    &bidder_key,
+                    &bid_key,
+                    &mut bid_account,
+                    &pubkey_rand(),
+                    &mut Account::default(),
+                    100,
+                    0,
                 )
-                .unwrap();
+            );
+        }
 
-            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
-            assert_eq!(swap_token_a.amount, deposit_a + token_a_amount);
-            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
-            assert_eq!(swap_token_b.amount, deposit_b + token_b_amount);
-            let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, 0);
-            let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
-            assert_eq!(token_b.amount, 0);
-            let pool_account = utils::unpack_token_account(&pool_account.data).unwrap();
-            let swap_pool_account =
-                utils::unpack_token_account(&accounts.pool_token_account.data).unwrap();
-            let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
-            // XXX: Revisit and make sure amount of LP tokens minted is corrected.
+        accounts
+            .set_order_flow_auction(&swap_token_a_key, batch_slots, 0)
+            .unwrap();
+        let pool_amount = pool_amount_a(&accounts);
+        accounts
+            .bid_order_flow(
+                &bidder_key,
+                &bid_key,
+                &mut bid_account,
+                &pubkey_rand(),
+                &mut Account::default(),
+                100,
+                0,
+            )
+            .unwrap();
+        assert_eq!(amount(&bid_account), 900);
+        assert_eq!(pool_amount_a(&accounts), pool_amount);
+
+        // bids must exceed the best bid of the batch
+        {
             assert_eq!(
-                pool_mint.supply,
-                pool_account.amount + swap_pool_account.amount
+                Err(SwapError::OrderFlowBidTooLow.into()),
+                accounts.bid_order_flow(
+                    &other_bidder_key,
+                    &other_bid_key,
+                    &mut other_bid_account,
+                    &bid_key,
+                    &mut bid_account.clone(),
+                    100,
+                    1,
+                )
             );
         }
 
-        // Pool is paused
+        // the best bid is refunded to its source account
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &depositor_key, deposit_a, deposit_b, 0);
-            // Pause pool
-            accounts.pause().unwrap();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.bid_order_flow(
+                    &other_bidder_key,
+                    &other_bid_key,
+                    &mut other_bid_account,
+                    &bid_b_key,
+                    &mut bid_b_account.clone(),
+                    150,
+                    1,
+                )
+            );
+        }
+        accounts
+            .bid_order_flow(
+                &other_bidder_key,
+                &other_bid_key,
+                &mut other_bid_account,
+                &bid_key,
+                &mut bid_account,
+                150,
+                1,
+            )
+            .unwrap();
+        assert_eq!(amount(&bid_account), 1_000);
+        assert_eq!(amount(&other_bid_account), 850);
+        assert_eq!(pool_amount_a(&accounts), pool_amount);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.order_flow_bidder, other_bidder_key);
+        assert_eq!(swap_info.order_flow_bid, 150);
 
+        // bids are closed in the backrun slot, reserved to the best bidder
+        let backrun_slot = batch_slots - 1;
+        {
             assert_eq!(
-                Err(SwapError::IsPaused.into()),
-                accounts.deposit(
-                    &depositor_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    deposit_a,
-                    deposit_b,
-                    min_mint_amount,
+                Err(SwapError::OrderFlowAuctionClosed.into()),
+                accounts.bid_order_flow(
+                    &bidder_key,
+                    &bid_key,
+                    &mut bid_account,
+                    &other_bid_key,
+                    &mut other_bid_account,
+                    200,
+                    backrun_slot,
+                )
+            );
+            assert_eq!(
+                Err(SwapError::OrderFlowSlotReserved.into()),
+                accounts.swap_with_access_token(
+                    &bidder_key,
+                    &bid_key,
+                    &mut bid_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &bid_b_key,
+                    &mut bid_b_account,
+                    None,
+                    backrun_slot,
+                    100,
+                    0,
+                    None,
                 )
             );
         }
+        let (_key, _account, other_bid_b_key, mut other_bid_b_account, _pool_key, _pool_account) =
+            accounts.setup_token_accounts(&user_key, &other_bidder_key, 0, 0, 0);
+        accounts
+            .swap_with_access_token(
+                &other_bidder_key,
+                &other_bid_key,
+                &mut other_bid_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &other_bid_b_key,
+                &mut other_bid_b_account,
+                None,
+                backrun_slot,
+                100,
+                0,
+                None,
+            )
+            .unwrap();
+
+        // the best bid is credited to the pool once its batch ends
+        let mut swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        swap_info.settle_order_flow_auction(batch_slots);
+        assert_eq!(
+            swap_info.pool_amount(&swap_token_a_key, amount(&accounts.token_a_account)),
+            Some(pool_amount_a(&accounts) + 150)
+        );
+        accounts
+            .swap_with_access_token(
+                &swapper_key,
+                &swapper_a_key,
+                &mut swapper_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &swapper_b_key,
+                &mut swapper_b_account,
+                None,
+                batch_slots,
+                100,
+                0,
+                None,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.order_flow_bid, 0);
     }
 
     #[test]
-    fn test_withdraw() {
+    fn test_dynamic_amp() {
         let user_key = pubkey_rand();
-        let amp_factor = MIN_AMP;
-        let token_a_amount = 1000;
-        let token_b_amount = 2000;
+        let swapper_key = pubkey_rand();
         let mut accounts = SwapAccountInfo::new(
             &user_key,
-            amp_factor,
-            token_a_amount,
-            token_b_amount,
+            100,
+            1_000_000_000,
+            1_000_000_000,
             DEFAULT_TEST_FEES,
         );
-        let withdrawer_key = pubkey_rand();
-        let initial_a = token_a_amount / 10;
-        let initial_b = token_b_amount / 10;
-        let initial_pool = INITIAL_SWAP_POOL_AMOUNT;
-        let withdraw_amount = initial_pool / 4;
-        let minimum_a_amount = initial_a / 40;
-        let minimum_b_amount = initial_b / 40;
+        accounts.initialize_swap().unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 1_000_000, 1_000_000, 0);
+        accounts
+            .set_dynamic_amp(50, 150, PRICE_SCALE / 100, 1, ZERO_TS, 0)
+            .unwrap();
 
-        // swap not initialized
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
-                )
+        // The first swap sets the TWAP, A drifts toward the maximum one per slot
+        accounts
+            .swap_with_access_token(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                None,
+                10,
+                10_000,
+                0,
+                None,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_ne!(swap_info.twap_price, 0);
+        assert_eq!(swap_info.twap_slot, 10);
+        assert_eq!(swap_info.target_amp_factor, 110);
+        assert_eq!(swap_info.initial_amp_factor, 110);
+
+        // Swapping back records the spread between the realized prices as
+        // volatility, A moves by at most one per slot
+        accounts
+            .swap_with_access_token(
+                &swapper_key,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_token_b_key,
+                &swap_token_a_key,
+                &token_a_key,
+                &mut token_a_account,
+                None,
+                15,
+                10_000,
+                0,
+                None,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_ne!(swap_info.twap_volatility, 0);
+        assert!((105..=115).contains(&swap_info.target_amp_factor));
+        assert_eq!(swap_info.initial_amp_factor, swap_info.target_amp_factor);
+    }
+
+    #[test]
+    fn test_volatility_fee() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let mut amounts_out = vec![];
+        for volatility_fee in [false, true] {
+            let mut accounts = SwapAccountInfo::new(
+                &user_key,
+                100,
+                1_000_000_000,
+                1_000_000_000,
+                DEFAULT_TEST_FEES,
+            );
+            accounts.initialize_swap().unwrap();
+            let swap_token_a_key = accounts.token_a_key;
+            let swap_token_b_key = accounts.token_b_key;
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &swapper_key, 1_000_000, 1_000_000, 0);
+            if volatility_fee {
+                accounts.set_volatility_fee(1, 20_000, 0).unwrap();
+            }
+
+            // Swapping there and back records the spread between the realized
+            // prices as volatility, above the ceiling of the volatility fee
+            for (slot, a_to_b) in [(10, true), (15, false), (20, true)] {
+                let result = if a_to_b {
+                    accounts.swap_with_access_token(
+                        &swapper_key,
+                        &token_a_key,
+                        &mut token_a_account,
+                        &swap_token_a_key,
+                        &swap_token_b_key,
+                        &token_b_key,
+                        &mut token_b_account,
+                        None,
+                        slot,
+                        10_000,
+                        0,
+                        None,
+                    )
+                } else {
+                    accounts.swap_with_access_token(
+                        &swapper_key,
+                        &token_b_key,
+                        &mut token_b_account,
+                        &swap_token_b_key,
+                        &swap_token_a_key,
+                        &token_a_key,
+                        &mut token_a_account,
+                        None,
+                        slot,
+                        10_000,
+                        0,
+                        None,
+                    )
+                };
+                result.unwrap();
+            }
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            if volatility_fee {
+                assert_ne!(swap_info.twap_volatility, 0);
+                assert_eq!(swap_info.volatility_fee_multiplier_bps(), Some(20_000));
+            } else {
+                assert_eq!(swap_info.twap_slot, 0);
+            }
+            amounts_out.push(
+                utils::unpack_token_account(&token_b_account.data)
+                    .unwrap()
+                    .amount,
             );
         }
 
-        accounts.initialize_swap().unwrap();
+        // Both pools priced the first two swaps alike, the last one paid twice the
+        // trade fee in the pool with the volatility fee
+        assert!(amounts_out[1] < amounts_out[0]);
+    }
 
-        // wrong nonce for authority_key
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
-            );
-            accounts.authority_key = bad_authority_key;
-            assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+    #[test]
+    fn test_swap_price_cumulative() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            100,
+            1_000_000_000,
+            1_000_000_000,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000_000, 0, 0);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.price_a_cumulative, 0);
+        assert_eq!(swap_info.price_b_cumulative, 0);
+        let initial_slot = swap_info.price_cumulative_slot;
+
+        // Prices accumulate over the slots since the last swap, from the reserves
+        // the swap finds
+        let mut swap = |accounts: &mut SwapAccountInfo, slot| {
+            accounts
+                .swap_with_access_token(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
+                    None,
+                    slot,
+                    50_000_000,
+                    0,
+                    None,
                 )
-            );
-            accounts.authority_key = old_authority;
-        }
+                .unwrap();
+            SwapInfo::unpack(&accounts.swap_account.data).unwrap()
+        };
+        let first = swap(&mut accounts, initial_slot + 10);
+        assert_eq!(first.price_a_cumulative, 10 * PRICE_SCALE as u128);
+        assert_eq!(first.price_b_cumulative, 10 * PRICE_SCALE as u128);
+        assert_eq!(first.price_cumulative_slot, initial_slot + 10);
 
-        // not enough pool tokens
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
+        // A is now in excess, and cheaper than B
+        let second = swap(&mut accounts, initial_slot + 15);
+        let price_a = (second.price_a_cumulative - first.price_a_cumulative) / 5;
+        let price_b = (second.price_b_cumulative - first.price_b_cumulative) / 5;
+        assert!(price_a < PRICE_SCALE as u128);
+        assert!(price_b > PRICE_SCALE as u128);
+        assert_eq!(second.price_cumulative_slot, initial_slot + 15);
+    }
+
+    #[test]
+    fn test_twap_order() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            100,
+            1_000_000_000,
+            1_000_000_000,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 10_000_000, 0, 0);
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let order_lamports = Rent::default().minimum_balance(TwapOrder::LEN);
+        let mut swapper_account = Account::new(order_lamports * 2, 0, &Pubkey::default());
+
+        let order_data =
+            |order_id, order_nonce, slice_count, max_price_deviation_bps| CreateTwapOrderData {
+                amount_in: 1_000_000,
+                slice_count,
+                slice_interval_slots: 10,
+                max_price_deviation_bps,
+                order_id,
+                order_nonce,
+            };
+        let new_order = |accounts: &mut SwapAccountInfo, order_id| {
+            let (order_key, order_nonce) = utils::find_twap_order_address(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &swapper_key,
+                order_id,
+            );
+            let (escrow_key, escrow_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount / 2,
+                &order_key,
+                0,
             );
-            assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
+            let order_account =
+                Account::new(0, TwapOrder::LEN, &solana_program::system_program::id());
+            (
+                order_key,
+                order_nonce,
+                order_account,
+                escrow_key,
+                escrow_account,
+            )
+        };
+
+        let (order_key, order_nonce, mut order_account, escrow_key, mut escrow_account) =
+            new_order(&mut accounts, 1);
+
+        // no slices
+        assert_eq!(
+            Err(SwapError::InvalidInput.into()),
+            accounts.create_twap_order(
+                &swapper_key,
+                &mut swapper_account,
+                &token_a_key,
+                &mut token_a_account,
+                &escrow_key,
+                &mut escrow_account,
+                &token_b_key,
+                &mut token_b_account,
+                &order_key,
+                &mut order_account,
+                order_data(1, order_nonce, 0, 1_000),
+                0,
+            )
+        );
+
+        accounts
+            .create_twap_order(
+                &swapper_key,
+                &mut swapper_account,
+                &token_a_key,
+                &mut token_a_account,
+                &escrow_key,
+                &mut escrow_account,
+                &token_b_key,
+                &mut token_b_account,
+                &order_key,
+                &mut order_account,
+                order_data(1, order_nonce, 3, 1_000),
+                0,
+            )
+            .unwrap();
+        assert_eq!(amount(&token_a_account), 9_000_000);
+        assert_eq!(amount(&escrow_account), 1_000_000);
+        assert_eq!(order_account.owner, SWAP_PROGRAM_ID);
+
+        // first slice is not due yet
+        assert_eq!(
+            Err(SwapError::TwapSliceNotDue.into()),
+            accounts.execute_twap_slice(
+                &order_key,
+                &mut order_account,
+                &escrow_key,
+                &mut escrow_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                9,
+            )
+        );
+
+        for (slot, escrowed) in [(10, 666_667), (20, 333_334), (30, 0)] {
+            accounts
+                .execute_twap_slice(
+                    &order_key,
+                    &mut order_account,
+                    &escrow_key,
+                    &mut escrow_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount,
-                    minimum_a_amount / 2,
-                    minimum_b_amount / 2,
+                    slot,
                 )
-            );
+                .unwrap();
+            assert_eq!(amount(&escrow_account), escrowed);
         }
+        let order = TwapOrder::unpack(&order_account.data).unwrap();
+        assert_eq!(order.slices_executed, 3);
+        assert_eq!(order.last_slice_slot, 30);
+        assert_eq!(order.amount_out, amount(&token_b_account));
+        assert!(order.amount_out > 900_000);
 
-        // wrong token a / b accounts
+        // filled
+        assert_eq!(
+            Err(SwapError::TwapSliceNotDue.into()),
+            accounts.execute_twap_slice(
+                &order_key,
+                &mut order_account,
+                &escrow_key,
+                &mut escrow_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                40,
+            )
+        );
+
+        // the trade fee exceeds the deviation allowed from the TWAP
+        let (
+            tight_order_key,
+            tight_order_nonce,
+            mut tight_order_account,
+            tight_escrow_key,
+            mut tight_escrow_account,
+        ) = new_order(&mut accounts, 2);
+        accounts
+            .create_twap_order(
+                &swapper_key,
+                &mut swapper_account,
+                &token_a_key,
+                &mut token_a_account,
+                &tight_escrow_key,
+                &mut tight_escrow_account,
+                &token_b_key,
+                &mut token_b_account,
+                &tight_order_key,
+                &mut tight_order_account,
+                order_data(2, tight_order_nonce, 2, 100),
+                40,
+            )
+            .unwrap();
+        assert_eq!(
+            Err(SwapError::ExceededSlippage.into()),
+            accounts.execute_twap_slice(
+                &tight_order_key,
+                &mut tight_order_account,
+                &tight_escrow_key,
+                &mut tight_escrow_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                50,
+            )
+        );
+
+        // only the owner cancels
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
-            );
+            let wrong_owner_key = pubkey_rand();
+            let mut wrong_owner_account = Account::default();
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
+                Err(SwapError::Unauthorized.into()),
+                accounts.cancel_twap_order(
+                    &tight_order_key,
+                    &mut tight_order_account,
+                    &wrong_owner_key,
+                    &mut wrong_owner_account,
+                    &tight_escrow_key,
+                    &mut tight_escrow_account,
                     &token_a_key,
                     &mut token_a_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
                 )
             );
         }
 
-        // wrong admin a / b accounts
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
-            );
-            let (
-                wrong_admin_a_key,
-                wrong_admin_a_account,
-                wrong_admin_b_key,
-                wrong_admin_b_account,
-                _pool_key,
-                mut _pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
-            );
+        let swapper_lamports = swapper_account.lamports;
+        let order_lamports = tight_order_account.lamports;
+        let escrow_lamports = tight_escrow_account.lamports;
+        accounts
+            .cancel_twap_order(
+                &tight_order_key,
+                &mut tight_order_account,
+                &swapper_key,
+                &mut swapper_account,
+                &tight_escrow_key,
+                &mut tight_escrow_account,
+                &token_a_key,
+                &mut token_a_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&token_a_account), 9_000_000);
+        assert_eq!(
+            swapper_account.lamports,
+            swapper_lamports + order_lamports + escrow_lamports
+        );
+        assert_eq!(tight_order_account.lamports, 0);
+        assert_eq!(tight_escrow_account.lamports, 0);
+        assert!(tight_order_account.data.iter().all(|b| *b == 0));
+    }
 
-            let old_admin_a_key = accounts.admin_fee_a_key;
-            let old_admin_a_account = accounts.admin_fee_a_account;
-            accounts.admin_fee_a_key = wrong_admin_a_key;
-            accounts.admin_fee_a_account = wrong_admin_a_account;
+    #[test]
+    fn test_trigger() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let cranker_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            100,
+            1_000_000_000,
+            1_000_000_000,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let swap_key = accounts.swap_key;
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 10_000_000, 0, 0);
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let trigger_lamports = Rent::default().minimum_balance(TriggerOrder::LEN);
+        let bounty = 5_000;
+        let mut swapper_account =
+            Account::new((trigger_lamports + bounty) * 2, 0, &Pubkey::default());
+        let mut cranker_account = Account::default();
 
-            assert_eq!(
-                Err(SwapError::InvalidAdmin.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
-                )
+        let new_trigger = |accounts: &mut SwapAccountInfo, trigger_id| {
+            let (trigger_key, trigger_nonce) = utils::find_trigger_address(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &swapper_key,
+                trigger_id,
+            );
+            let (escrow_key, escrow_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
+                &user_key,
+                &trigger_key,
+                0,
             );
+            let trigger_account =
+                Account::new(0, TriggerOrder::LEN, &solana_program::system_program::id());
+            (
+                trigger_key,
+                trigger_nonce,
+                trigger_account,
+                escrow_key,
+                escrow_account,
+            )
+        };
+        let trigger_data =
+            |trigger_id, trigger_nonce, trigger_price, trigger_above| CreateTriggerData {
+                amount_in: 1_000_000,
+                minimum_amount_out: 900_000,
+                trigger_price,
+                trigger_above,
+                bounty,
+                trigger_id,
+                trigger_nonce,
+            };
 
-            accounts.admin_fee_a_key = old_admin_a_key;
-            accounts.admin_fee_a_account = old_admin_a_account;
+        // stop-loss below 0.95 B per A, the pool itself is the oracle
+        let (
+            stop_loss_key,
+            stop_loss_nonce,
+            mut stop_loss_account,
+            stop_loss_escrow_key,
+            mut stop_loss_escrow_account,
+        ) = new_trigger(&mut accounts, 1);
+        let mut oracle_account = accounts.swap_account.clone();
+        accounts
+            .create_trigger(
+                &swapper_key,
+                &mut swapper_account,
+                &token_a_key,
+                &mut token_a_account,
+                &stop_loss_escrow_key,
+                &mut stop_loss_escrow_account,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_key,
+                &mut oracle_account,
+                &stop_loss_key,
+                &mut stop_loss_account,
+                trigger_data(1, stop_loss_nonce, PRICE_SCALE as u128 * 95 / 100, false),
+            )
+            .unwrap();
+        assert_eq!(amount(&stop_loss_escrow_account), 1_000_000);
+        assert_eq!(stop_loss_account.lamports, trigger_lamports + bounty);
 
-            let old_admin_b_key = accounts.admin_fee_b_key;
-            let old_admin_b_account = accounts.admin_fee_b_account;
-            accounts.admin_fee_b_key = wrong_admin_b_key;
-            accounts.admin_fee_b_account = wrong_admin_b_account;
+        // the pool TWAP is not observed without dynamic amp
+        let mut oracle_account = accounts.swap_account.clone();
+        assert_eq!(
+            Err(SwapError::OracleStale.into()),
+            accounts.execute_trigger(
+                &stop_loss_key,
+                &mut stop_loss_account,
+                &swap_key,
+                &mut oracle_account,
+                &swapper_key,
+                &mut swapper_account,
+                &cranker_key,
+                &mut cranker_account,
+                &stop_loss_escrow_key,
+                &mut stop_loss_escrow_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                10,
+            )
+        );
 
-            assert_eq!(
-                Err(SwapError::InvalidAdmin.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
-                )
-            );
+        // the realized price of a swap, less its trade fee, is below 0.95
+        accounts
+            .set_dynamic_amp(50, 150, PRICE_SCALE / 100, 1, ZERO_TS, 0)
+            .unwrap();
+        accounts
+            .swap_with_access_token(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                None,
+                10,
+                10_000,
+                0,
+                None,
+            )
+            .unwrap();
+        let twap_price = SwapInfo::unpack(&accounts.swap_account.data)
+            .unwrap()
+            .twap_price;
+        assert!(twap_price < PRICE_SCALE as u128 * 95 / 100);
 
-            accounts.admin_fee_b_key = old_admin_b_key;
-            accounts.admin_fee_b_account = old_admin_b_account;
-        }
+        // take-profit above 1 B per A
+        let (
+            take_profit_key,
+            take_profit_nonce,
+            mut take_profit_account,
+            take_profit_escrow_key,
+            mut take_profit_escrow_account,
+        ) = new_trigger(&mut accounts, 2);
+        let mut oracle_account = accounts.swap_account.clone();
+        accounts
+            .create_trigger(
+                &swapper_key,
+                &mut swapper_account,
+                &token_a_key,
+                &mut token_a_account,
+                &take_profit_escrow_key,
+                &mut take_profit_escrow_account,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_key,
+                &mut oracle_account,
+                &take_profit_key,
+                &mut take_profit_account,
+                trigger_data(2, take_profit_nonce, PRICE_SCALE as u128, true),
+            )
+            .unwrap();
+        let mut oracle_account = accounts.swap_account.clone();
+        assert_eq!(
+            Err(SwapError::TriggerNotReached.into()),
+            accounts.execute_trigger(
+                &take_profit_key,
+                &mut take_profit_account,
+                &swap_key,
+                &mut oracle_account,
+                &swapper_key,
+                &mut swapper_account,
+                &cranker_key,
+                &mut cranker_account,
+                &take_profit_escrow_key,
+                &mut take_profit_escrow_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                20,
+            )
+        );
 
-        // wrong pool token account
+        // rent returned to another account than the owner
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
-            );
-            let (
-                wrong_pool_key,
-                mut wrong_pool_account,
-                _token_b_key,
-                _token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                withdraw_amount,
-                initial_b,
-                withdraw_amount,
-            );
-            assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &wrong_pool_key,
-                    &mut wrong_pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
+            let mut oracle_account = accounts.swap_account.clone();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.execute_trigger(
+                    &stop_loss_key,
+                    &mut stop_loss_account,
+                    &swap_key,
+                    &mut oracle_account,
+                    &cranker_key,
+                    &mut cranker_account.clone(),
+                    &cranker_key,
+                    &mut cranker_account,
+                    &stop_loss_escrow_key,
+                    &mut stop_loss_escrow_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
+                    20,
                 )
             );
         }
 
-        // no approval
+        let swapper_lamports = swapper_account.lamports;
+        let escrow_lamports = stop_loss_escrow_account.lamports;
+        let token_b_amount = amount(&token_b_account);
+        let mut oracle_account = accounts.swap_account.clone();
+        accounts
+            .execute_trigger(
+                &stop_loss_key,
+                &mut stop_loss_account,
+                &swap_key,
+                &mut oracle_account,
+                &swapper_key,
+                &mut swapper_account,
+                &cranker_key,
+                &mut cranker_account,
+                &stop_loss_escrow_key,
+                &mut stop_loss_escrow_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                20,
+            )
+            .unwrap();
+        assert!(amount(&token_b_account) - token_b_amount >= 900_000);
+        assert_eq!(cranker_account.lamports, bounty);
+        assert_eq!(
+            swapper_account.lamports,
+            swapper_lamports + trigger_lamports + escrow_lamports
+        );
+        assert_eq!(stop_loss_account.lamports, 0);
+        assert_eq!(stop_loss_escrow_account.lamports, 0);
+        assert!(stop_loss_account.data.iter().all(|b| *b == 0));
+
+        // the TWAP is stale a window after the last swap
+        let mut oracle_account = accounts.swap_account.clone();
+        assert_eq!(
+            Err(SwapError::OracleStale.into()),
+            accounts.execute_trigger(
+                &take_profit_key,
+                &mut take_profit_account,
+                &swap_key,
+                &mut oracle_account,
+                &swapper_key,
+                &mut swapper_account,
+                &cranker_key,
+                &mut cranker_account,
+                &take_profit_escrow_key,
+                &mut take_profit_escrow_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                21 + TWAP_WINDOW_SLOTS,
+            )
+        );
+
+        // only the owner cancels, and gets the bounty back
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, withdraw_amount);
+            let wrong_owner_key = pubkey_rand();
+            let mut wrong_owner_account = Account::default();
             assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
-                do_process_instruction(
-                    withdraw(
-                        &SWAP_PROGRAM_ID,
-                        &TOKEN_PROGRAM_ID,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.pool_mint_key,
-                        &pool_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_a_key,
-                        &token_b_key,
-                        &accounts.admin_fee_a_key,
-                        &accounts.admin_fee_b_key,
-                        withdraw_amount,
-                        minimum_a_amount,
-                        minimum_b_amount,
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_a_account,
-                        &mut token_b_account,
-                        &mut accounts.admin_fee_a_account,
-                        &mut accounts.admin_fee_b_account,
-                        &mut Account::default(),
-                    ],
+                Err(SwapError::Unauthorized.into()),
+                accounts.cancel_trigger(
+                    &take_profit_key,
+                    &mut take_profit_account,
+                    &wrong_owner_key,
+                    &mut wrong_owner_account,
+                    &take_profit_escrow_key,
+                    &mut take_profit_escrow_account,
+                    &token_a_key,
+                    &mut token_a_account,
                 )
             );
         }
+        let swapper_lamports = swapper_account.lamports;
+        let escrow_lamports = take_profit_escrow_account.lamports;
+        let token_a_amount = amount(&token_a_account);
+        accounts
+            .cancel_trigger(
+                &take_profit_key,
+                &mut take_profit_account,
+                &swapper_key,
+                &mut swapper_account,
+                &take_profit_escrow_key,
+                &mut take_profit_escrow_account,
+                &token_a_key,
+                &mut token_a_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&token_a_account), token_a_amount + 1_000_000);
+        assert_eq!(
+            swapper_account.lamports,
+            swapper_lamports + trigger_lamports + bounty + escrow_lamports
+        );
+        assert_eq!(take_profit_account.lamports, 0);
+    }
 
-        // wrong token program id
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
-            );
-            let wrong_key = pubkey_rand();
-            assert_eq!(
-                Err(ProgramError::InvalidAccountData),
-                do_process_instruction(
-                    withdraw(
-                        &SWAP_PROGRAM_ID,
-                        &wrong_key,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.pool_mint_key,
-                        &pool_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_a_key,
-                        &token_b_key,
-                        &accounts.admin_fee_a_key,
-                        &accounts.admin_fee_b_key,
-                        withdraw_amount,
-                        minimum_a_amount,
-                        minimum_b_amount,
-                    )
+    #[test]
+    fn test_global_stats() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 2_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            100,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        let (global_stats_key, nonce) = utils::find_global_stats_address(&SWAP_PROGRAM_ID);
+        let stats_lamports = Rent::default().minimum_balance(GlobalStats::LEN);
+        let mut payer_account = Account::new(stats_lamports, 0, &Pubkey::default());
+        let mut global_stats_account =
+            Account::new(0, GlobalStats::LEN, &solana_program::system_program::id());
+        let mut initialize_global_stats = |global_stats_account: &mut Account, nonce| {
+            do_process_instruction(
+                initialize_global_stats(&SWAP_PROGRAM_ID, &global_stats_key, &user_key, nonce)
                     .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_a_account,
-                        &mut token_b_account,
-                        &mut accounts.admin_fee_a_account,
-                        &mut accounts.admin_fee_b_account,
-                        &mut Account::default(),
-                    ],
+                vec![
+                    global_stats_account,
+                    &mut payer_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                ],
+            )
+        };
+
+        // wrong nonce
+        assert_eq!(
+            Err(SwapError::InvalidProgramAddress.into()),
+            initialize_global_stats(&mut global_stats_account, nonce.wrapping_sub(1))
+        );
+
+        initialize_global_stats(&mut global_stats_account, nonce).unwrap();
+        let global_stats = GlobalStats::unpack(&global_stats_account.data).unwrap();
+        assert_eq!(global_stats.nonce, nonce);
+        assert_eq!(global_stats.total_pools, 0);
+        assert_eq!(global_stats_account.owner, SWAP_PROGRAM_ID);
+
+        // already created
+        assert_eq!(
+            Err(ProgramError::AccountAlreadyInitialized),
+            initialize_global_stats(&mut global_stats_account, nonce)
+        );
+
+        accounts.global_stats = Some((global_stats_key, global_stats_account));
+        let global_stats = |accounts: &SwapAccountInfo| {
+            GlobalStats::unpack(&accounts.global_stats.as_ref().unwrap().1.data).unwrap()
+        };
+        accounts.initialize_swap().unwrap();
+        let stats = global_stats(&accounts);
+        assert_eq!(stats.total_pools, 1);
+        assert_eq!(
+            stats.total_value_locked,
+            (token_a_amount + token_b_amount) as u128
+        );
+        assert_eq!(stats.cumulative_volume, 0);
+
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &depositor_key, 100_000, 100_000, 0);
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        accounts
+            .deposit(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &pool_key,
+                &mut pool_account,
+                10_000,
+                20_000,
+                0,
+            )
+            .unwrap();
+        let mut total_value_locked = (token_a_amount + token_b_amount + 30_000) as u128;
+        assert_eq!(
+            global_stats(&accounts).total_value_locked,
+            total_value_locked
+        );
+
+        let (swap_token_a_key, swap_token_b_key) = (accounts.token_a_key, accounts.token_b_key);
+        let token_b_before = amount(&token_b_account);
+        accounts
+            .swap(
+                &depositor_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                5_000,
+                0,
+            )
+            .unwrap();
+        let amount_out = amount(&token_b_account) - token_b_before;
+        total_value_locked = total_value_locked + 5_000 - amount_out as u128;
+        let stats = global_stats(&accounts);
+        assert_eq!(stats.cumulative_volume, 5_000);
+        assert_eq!(stats.total_value_locked, total_value_locked);
+
+        let (token_a_before, token_b_before) = (amount(&token_a_account), amount(&token_b_account));
+        let pool_amount = amount(&pool_account);
+        accounts
+            .withdraw(
+                &depositor_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                pool_amount,
+                0,
+                0,
+            )
+            .unwrap();
+        let withdrawn = (amount(&token_a_account) - token_a_before)
+            + (amount(&token_b_account) - token_b_before);
+        let stats = global_stats(&accounts);
+        assert_eq!(
+            stats.total_value_locked,
+            total_value_locked - withdrawn as u128
+        );
+        assert_eq!(stats.total_pools, 1);
+
+        // mixed calls, the totals only count the calls passing the global stats
+        let mut total_value_locked = stats.total_value_locked;
+        let mut cumulative_volume = stats.cumulative_volume;
+        for (amount_in, with_global_stats) in [(1_000, false), (2_000, true), (4_000, false)] {
+            let global_stats_account = accounts.global_stats.take();
+            if with_global_stats {
+                accounts.global_stats = global_stats_account.clone();
+            }
+            let token_b_before = amount(&token_b_account);
+            accounts
+                .swap(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    amount_in,
+                    0,
                 )
-            );
+                .unwrap();
+            if with_global_stats {
+                let amount_out = amount(&token_b_account) - token_b_before;
+                total_value_locked = total_value_locked + amount_in as u128 - amount_out as u128;
+                cumulative_volume += amount_in as u128;
+            } else {
+                accounts.global_stats = global_stats_account;
+            }
         }
+        let stats = global_stats(&accounts);
+        assert_eq!(stats.cumulative_volume, cumulative_volume);
+        assert_eq!(stats.cumulative_volume, 5_000 + 2_000);
+        assert_eq!(stats.total_value_locked, total_value_locked);
 
-        // wrong swap token accounts
+        // an account of the program with the length of the global stats at another
+        // address is not the global stats, the deposit goes through without them
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool,
+            let old_global_stats = accounts.global_stats.take().unwrap();
+            let impostor = old_global_stats.1.clone();
+            accounts.global_stats = Some((pubkey_rand(), impostor.clone()));
+            accounts
+                .deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    1_000,
+                    1_000,
+                    0,
+                )
+                .unwrap();
+            assert_eq!(
+                accounts.global_stats.as_ref().unwrap().1.data,
+                impostor.data
             );
+            accounts.global_stats = Some(old_global_stats);
+        }
+    }
 
-            let old_a_key = accounts.token_a_key;
-            let old_a_account = accounts.token_a_account;
+    #[test]
+    fn test_fee_exemptions() {
+        let user_key = pubkey_rand();
+        let trader_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            100,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        accounts.admin_account.lamports = Rent::default().minimum_balance(FeeExemptions::LEN);
+        let mut fee_exemptions_account =
+            Account::new(0, FeeExemptions::LEN, &solana_program::system_program::id());
+        accounts
+            .add_fee_exemption(&mut fee_exemptions_account, &trader_key)
+            .unwrap();
+        let (fee_exemptions_key, _nonce) =
+            utils::find_fee_exemptions_address(&SWAP_PROGRAM_ID, &accounts.swap_key);
+        let exempting_account = fee_exemptions_account.clone();
+        accounts.fee_exemptions = Some((fee_exemptions_key, fee_exemptions_account));
 
-            accounts.token_a_key = token_a_key.clone();
-            accounts.token_a_account = token_a_account.clone();
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let (swap_token_a_key, swap_token_b_key) = (accounts.token_a_key, accounts.token_b_key);
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &trader_key, 100_000, 0, 0);
+        let amount_in = 10_000;
+
+        accounts
+            .swap(
+                &trader_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        let invariant = StableSwap::new(
+            accounts.initial_amp_factor,
+            accounts.target_amp_factor,
+            ZERO_TS,
+            ZERO_TS,
+            ZERO_TS,
+        );
+        let exempt_fees = Fees {
+            trade_fee_numerator: 0,
+            ..DEFAULT_TEST_FEES
+        };
+        let result = invariant
+            .swap_to_v2(
+                U256::from(amount_in),
+                U256::from(token_a_amount),
+                U256::from(token_b_amount),
+                &exempt_fees,
+            )
+            .unwrap();
+        assert_eq!(
+            amount(&token_b_account),
+            U256::to_u64(result.amount_swapped).unwrap()
+        );
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_fees_accrued_b, 0);
+        let fee_accounts = accounts.get_fee_accounts(&swap_token_b_key);
+        assert_eq!(amount(&fee_accounts.reflection_account), 0);
+        assert_eq!(amount(&fee_accounts.buyback_account), 0);
+
+        // the fees are charged again once the exemption is removed
+        let mut fee_exemptions_account = accounts.fee_exemptions.take().unwrap().1;
+        accounts
+            .remove_fee_exemption(&mut fee_exemptions_account, &trader_key)
+            .unwrap();
+        accounts.fee_exemptions = Some((fee_exemptions_key, fee_exemptions_account));
+        accounts
+            .swap(
+                &trader_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_info.admin_fees_accrued_b > 0);
+        let fee_accounts = accounts.get_fee_accounts(&swap_token_b_key);
+        assert!(amount(&fee_accounts.reflection_account) > 0);
+        assert!(amount(&fee_accounts.buyback_account) > 0);
+
+        // an account of the program exempting the trader at another address than the
+        // fee exemptions exempts no one
+        accounts.fee_exemptions = Some((pubkey_rand(), exempting_account));
+        let admin_fees_accrued_b = swap_info.admin_fees_accrued_b;
+        accounts
+            .swap(
+                &trader_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert!(swap_info.admin_fees_accrued_b > admin_fees_accrued_b);
+    }
+
+    #[test]
+    fn test_referrer_host_fee() {
+        let user_key = pubkey_rand();
+        let trader_key = pubkey_rand();
+        let referrer_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let fees = Fees {
+            host_fee_numerator: 1,
+            host_fee_denominator: 5,
+            ..DEFAULT_TEST_FEES
+        };
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 100, token_a_amount, token_b_amount, fees);
+        accounts.initialize_swap().unwrap();
+
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let (swap_token_a_key, swap_token_b_key) = (accounts.token_a_key, accounts.token_b_key);
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &trader_key, 100_000, 0, 0);
+        let (referrer_a_key, referrer_a_account, referrer_b_key, referrer_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &referrer_key, 0, 0, 0);
+        let amount_in = 10_000;
+        let invariant = StableSwap::new(
+            accounts.initial_amp_factor,
+            accounts.target_amp_factor,
+            ZERO_TS,
+            ZERO_TS,
+            ZERO_TS,
+        );
+        let result = invariant
+            .swap_to_v2(
+                U256::from(amount_in),
+                U256::from(token_a_amount),
+                U256::from(token_b_amount),
+                &fees,
+            )
+            .unwrap();
 
-            // wrong swap token a account
+        // without a referrer, the whole admin fee accrues
+        accounts
+            .swap(
+                &trader_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(
+            swap_info.admin_fees_accrued_b,
+            U256::to_u64(result.admin_fee).unwrap()
+        );
+
+        // a token account of another mint than the fee token
+        {
+            accounts.referrer = Some((referrer_a_key, referrer_a_account));
             assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(SwapError::IncorrectMint.into()),
+                accounts.swap(
+                    &trader_key,
                     &token_a_key,
                     &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
+                    amount_in,
+                    0,
                 )
             );
+            accounts.referrer = None;
+        }
 
-            accounts.token_a_key = old_a_key;
-            accounts.token_a_account = old_a_account;
-
-            let old_b_key = accounts.token_b_key;
-            let old_b_account = accounts.token_b_account;
-
-            accounts.token_b_key = token_b_key.clone();
-            accounts.token_b_account = token_b_account.clone();
-
-            // wrong swap token b account
+        // the trader referring its own swap
+        {
+            accounts.referrer = Some((token_b_key, token_b_account.clone()));
             assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(SwapError::SelfReferral.into()),
+                accounts.swap(
+                    &trader_key,
                     &token_a_key,
                     &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
+                    &mut token_b_account.clone(),
+                    amount_in,
+                    0,
                 )
             );
-
-            accounts.token_b_key = old_b_key;
-            accounts.token_b_account = old_b_account;
+            accounts.referrer = None;
         }
 
-        // wrong mint
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool,
-            );
-            let (pool_mint_key, pool_mint_account) = create_mint(
-                &TOKEN_PROGRAM_ID,
-                &accounts.authority_key,
-                DEFAULT_TOKEN_DECIMALS,
-                None,
-            );
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
+        // the referrer is paid the host share of the admin fee, which accrues the rest
+        let admin_fees_accrued_before = swap_info.admin_fees_accrued_b;
+        accounts.referrer = Some((referrer_b_key, referrer_b_account));
+        accounts
+            .swap(
+                &trader_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        let referrer_b_account = accounts.referrer.take().unwrap().1;
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        let host_fee = amount(&referrer_b_account);
+        let admin_fee = swap_info.admin_fees_accrued_b - admin_fees_accrued_before + host_fee;
+        assert!(host_fee > 0);
+        assert_eq!(
+            U256::from(host_fee),
+            fees.host_fee(U256::from(admin_fee)).unwrap()
+        );
+    }
 
-            assert_eq!(
-                Err(SwapError::IncorrectMint.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+    #[test]
+    fn test_admin_fee_epoch_cap() {
+        let user_key = pubkey_rand();
+        let trader_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            100,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+
+        let (swap_token_a_key, swap_token_b_key) = (accounts.token_a_key, accounts.token_b_key);
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &trader_key, 100_000, 0, 0);
+        let amount_in = 10_000;
+        let invariant = StableSwap::new(
+            accounts.initial_amp_factor,
+            accounts.target_amp_factor,
+            ZERO_TS,
+            ZERO_TS,
+            ZERO_TS,
+        );
+        let admin_fee = U256::to_u64(
+            invariant
+                .swap_to_v2(
+                    U256::from(amount_in),
+                    U256::from(token_a_amount),
+                    U256::from(token_b_amount),
+                    &DEFAULT_TEST_FEES,
+                )
+                .unwrap()
+                .admin_fee,
+        )
+        .unwrap();
+        assert!(admin_fee > 1);
+        let cap = admin_fee + admin_fee / 2;
+        accounts.set_admin_fee_epoch_cap(0, cap).unwrap();
+
+        // once the cap is reached, swaps leave the admin fees to the LPs
+        for _ in 0..3 {
+            accounts
+                .swap(
+                    &trader_key,
                     &token_a_key,
                     &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
+                    amount_in,
+                    0,
                 )
-            );
-
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
+                .unwrap();
         }
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_fees_accrued_b, cap);
+        assert_eq!(swap_info.admin_fees_taken_b, cap);
+        assert_eq!(swap_info.admin_fees_taken_a, 0);
 
-        // slippage exceeeded
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
+        // the admin fees left to the LPs back the pool
+        let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data)
+            .unwrap()
+            .amount;
+        assert_eq!(
+            swap_info.pool_amount(&swap_token_b_key, swap_token_b),
+            Some(swap_token_b - cap)
+        );
+    }
+
+    #[test]
+    fn test_referral_accrual() {
+        let user_key = pubkey_rand();
+        let trader_key = pubkey_rand();
+        let referrer_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 1_000_000;
+        let fees = Fees {
+            host_fee_numerator: 1,
+            host_fee_denominator: 5,
+            ..DEFAULT_TEST_FEES
+        };
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 100, token_a_amount, token_b_amount, fees);
+        accounts.initialize_swap().unwrap();
+
+        let (referral_accrual_key, nonce) = utils::find_referral_accrual_address(
+            &SWAP_PROGRAM_ID,
+            &accounts.swap_key,
+            &referrer_key,
+        );
+        let accrual_lamports = Rent::default().minimum_balance(ReferralAccrual::LEN);
+        let mut referrer_account = Account::new(accrual_lamports, 0, &Pubkey::default());
+        let mut referral_accrual_account = Account::new(
+            0,
+            ReferralAccrual::LEN,
+            &solana_program::system_program::id(),
+        );
+        let mut create_referral_accrual = |referral_accrual_account: &mut Account, nonce| {
+            do_process_instruction(
+                create_referral_accrual(
+                    &SWAP_PROGRAM_ID,
+                    &accounts.swap_key,
+                    &referral_accrual_key,
+                    &referrer_key,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut accounts.swap_account,
+                    referral_accrual_account,
+                    &mut referrer_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                ],
+            )
+        };
+
+        // wrong nonce
+        assert_eq!(
+            Err(SwapError::InvalidProgramAddress.into()),
+            create_referral_accrual(&mut referral_accrual_account, nonce.wrapping_sub(1))
+        );
+
+        create_referral_accrual(&mut referral_accrual_account, nonce).unwrap();
+        let referral_accrual = ReferralAccrual::unpack(&referral_accrual_account.data).unwrap();
+        assert_eq!(referral_accrual.nonce, nonce);
+        assert_eq!(referral_accrual.swap, accounts.swap_key);
+        assert_eq!(referral_accrual.referrer, referrer_key);
+        assert_eq!(referral_accrual_account.owner, SWAP_PROGRAM_ID);
+
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let (swap_token_a_key, swap_token_b_key) = (accounts.token_a_key, accounts.token_b_key);
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &trader_key, 100_000, 0, 0);
+        let (referrer_a_key, mut referrer_a_account, referrer_b_key, mut referrer_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &referrer_key, 0, 0, 0);
+        let amount_in = 10_000;
+        let invariant = StableSwap::new(
+            accounts.initial_amp_factor,
+            accounts.target_amp_factor,
+            ZERO_TS,
+            ZERO_TS,
+            ZERO_TS,
+        );
+        let result = invariant
+            .swap_to_v2(
+                U256::from(amount_in),
+                U256::from(token_a_amount),
+                U256::from(token_b_amount),
+                &fees,
+            )
+            .unwrap();
+        let host_fee = U256::to_u64(fees.host_fee(result.admin_fee).unwrap()).unwrap();
+        assert!(host_fee > 0);
+
+        // the host fee accrues to the referral accrual, in the swap token account
+        accounts.referral_accrual = Some((referral_accrual_key, referral_accrual_account));
+        accounts
+            .swap(
+                &trader_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        let referral_accrual =
+            ReferralAccrual::unpack(&accounts.referral_accrual.as_ref().unwrap().1.data).unwrap();
+        assert_eq!(referral_accrual.referred_volume_a, amount_in as u128);
+        assert_eq!(referral_accrual.referred_volume_b, 0);
+        assert_eq!(referral_accrual.fees_earned_b, host_fee);
+        assert_eq!(referral_accrual.claimable(), Some((0, host_fee)));
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.referral_fees_accrued_b, host_fee);
+        assert_eq!(
+            swap_info.admin_fees_accrued_b,
+            U256::to_u64(result.admin_fee).unwrap() - host_fee
+        );
+
+        // only the referrer claims
+        assert_eq!(
+            Err(SwapError::Unauthorized.into()),
+            accounts.claim_referral_fees(
+                &trader_key,
+                &referrer_a_key,
+                &mut referrer_a_account,
+                &referrer_b_key,
+                &mut referrer_b_account,
+            )
+        );
+        assert_eq!(
+            Err(SwapError::InvalidOwner.into()),
+            accounts.claim_referral_fees(
+                &referrer_key,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+            )
+        );
+
+        let sequence = swap_info.sequence;
+        accounts
+            .claim_referral_fees(
+                &referrer_key,
+                &referrer_a_key,
+                &mut referrer_a_account,
+                &referrer_b_key,
+                &mut referrer_b_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&referrer_a_account), 0);
+        assert_eq!(amount(&referrer_b_account), host_fee);
+        let referral_accrual =
+            ReferralAccrual::unpack(&accounts.referral_accrual.as_ref().unwrap().1.data).unwrap();
+        assert_eq!(referral_accrual.fees_claimed_b, host_fee);
+        assert_eq!(referral_accrual.claimable(), Some((0, 0)));
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.referral_fees_accrued_b, 0);
+        assert_eq!(swap_info.sequence, sequence + 1);
+
+        // nothing left to claim
+        accounts
+            .claim_referral_fees(
+                &referrer_key,
+                &referrer_a_key,
+                &mut referrer_a_account,
+                &referrer_b_key,
+                &mut referrer_b_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&referrer_b_account), host_fee);
+
+        // a referral accrual of the program at another address accrues nothing, the
+        // whole admin fee accrues to the swap
+        let (_, referral_accrual_account) = accounts.referral_accrual.take().unwrap();
+        accounts.referral_accrual = Some((pubkey_rand(), referral_accrual_account.clone()));
+        accounts
+            .swap(
+                &trader_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                amount_in,
+                0,
+            )
+            .unwrap();
+        assert_eq!(
+            accounts.referral_accrual.as_ref().unwrap().1.data,
+            referral_accrual_account.data
+        );
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.referral_fees_accrued_b, 0);
+    }
+
+    #[test]
+    fn test_drip_deposit() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            100,
+            1_000_000_000,
+            1_000_000_000,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &depositor_key, 10_000_000, 10_000_000, 0);
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let drip_lamports = Rent::default().minimum_balance(DripDeposit::LEN);
+        let mut depositor_account = Account::new(drip_lamports * 2, 0, &Pubkey::default());
+
+        let drip_data = |drip_id, drip_nonce, tranche_count| CreateDripDepositData {
+            amount_a: 1_000_000,
+            amount_b: 1_000_000,
+            tranche_count,
+            tranche_interval_slots: 10,
+            drip_id,
+            drip_nonce,
+        };
+        let new_drip = |accounts: &mut SwapAccountInfo, drip_id| {
+            let (drip_key, drip_nonce) = utils::find_drip_deposit_address(
+                &SWAP_PROGRAM_ID,
+                &accounts.swap_key,
+                &depositor_key,
+                drip_id,
+            );
+            let (escrow_a_key, escrow_a_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_a_mint_key,
+                &mut accounts.token_a_mint_account,
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool,
+                &drip_key,
+                0,
             );
-            // minimum A amount out too high
-            assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
+            let (escrow_b_key, escrow_b_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.token_b_mint_key,
+                &mut accounts.token_b_mint_account,
+                &user_key,
+                &drip_key,
+                0,
+            );
+            let drip_account =
+                Account::new(0, DripDeposit::LEN, &solana_program::system_program::id());
+            (
+                drip_key,
+                drip_nonce,
+                drip_account,
+                (escrow_a_key, escrow_a_account),
+                (escrow_b_key, escrow_b_account),
+            )
+        };
+
+        let (
+            drip_key,
+            drip_nonce,
+            mut drip_account,
+            (escrow_a_key, mut escrow_a_account),
+            (escrow_b_key, mut escrow_b_account),
+        ) = new_drip(&mut accounts, 1);
+
+        // no tranches
+        assert_eq!(
+            Err(SwapError::InvalidInput.into()),
+            accounts.create_drip_deposit(
+                &depositor_key,
+                &mut depositor_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &escrow_a_key,
+                &mut escrow_a_account,
+                &escrow_b_key,
+                &mut escrow_b_account,
+                &pool_key,
+                &mut pool_account,
+                &drip_key,
+                &mut drip_account,
+                drip_data(1, drip_nonce, 0),
+                0,
+            )
+        );
+
+        // escrows swapped
+        assert_eq!(
+            Err(SwapError::IncorrectMint.into()),
+            accounts.create_drip_deposit(
+                &depositor_key,
+                &mut depositor_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &escrow_b_key,
+                &mut escrow_b_account,
+                &escrow_a_key,
+                &mut escrow_a_account,
+                &pool_key,
+                &mut pool_account,
+                &drip_key,
+                &mut drip_account,
+                drip_data(1, drip_nonce, 3),
+                0,
+            )
+        );
+
+        accounts
+            .create_drip_deposit(
+                &depositor_key,
+                &mut depositor_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &escrow_a_key,
+                &mut escrow_a_account,
+                &escrow_b_key,
+                &mut escrow_b_account,
+                &pool_key,
+                &mut pool_account,
+                &drip_key,
+                &mut drip_account,
+                drip_data(1, drip_nonce, 3),
+                0,
+            )
+            .unwrap();
+        assert_eq!(amount(&token_a_account), 9_000_000);
+        assert_eq!(amount(&token_b_account), 9_000_000);
+        assert_eq!(amount(&escrow_a_account), 1_000_000);
+        assert_eq!(amount(&escrow_b_account), 1_000_000);
+        assert_eq!(drip_account.owner, SWAP_PROGRAM_ID);
+
+        // first tranche is not due yet
+        assert_eq!(
+            Err(SwapError::DripTrancheNotDue.into()),
+            accounts.deposit_drip_tranche(
+                &drip_key,
+                &mut drip_account,
+                &escrow_a_key,
+                &mut escrow_a_account,
+                &escrow_b_key,
+                &mut escrow_b_account,
+                &pool_key,
+                &mut pool_account,
+                9,
+            )
+        );
+
+        let mut pool_amount = amount(&pool_account);
+        for (slot, escrowed) in [(10, 666_667), (20, 333_334), (30, 0)] {
+            accounts
+                .deposit_drip_tranche(
+                    &drip_key,
+                    &mut drip_account,
+                    &escrow_a_key,
+                    &mut escrow_a_account,
+                    &escrow_b_key,
+                    &mut escrow_b_account,
                     &pool_key,
                     &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount,
-                    minimum_a_amount * 30, // XXX: 10 -> 30: Revisit this slippage multiplier
-                    minimum_b_amount,
+                    slot,
                 )
-            );
-            // minimum B amount out too high
+                .unwrap();
+            assert_eq!(amount(&escrow_a_account), escrowed);
+            assert_eq!(amount(&escrow_b_account), escrowed);
+            for escrow_account in [&escrow_a_account, &escrow_b_account] {
+                let escrow = utils::unpack_token_account(&escrow_account.data).unwrap();
+                assert!(escrow.delegate.is_none());
+            }
+            assert!(amount(&pool_account) > pool_amount);
+            pool_amount = amount(&pool_account);
+        }
+        let drip = DripDeposit::unpack(&drip_account.data).unwrap();
+        assert_eq!(drip.tranches_deposited, 3);
+        assert_eq!(drip.last_tranche_slot, 30);
+
+        // fully deposited
+        assert_eq!(
+            Err(SwapError::DripTrancheNotDue.into()),
+            accounts.deposit_drip_tranche(
+                &drip_key,
+                &mut drip_account,
+                &escrow_a_key,
+                &mut escrow_a_account,
+                &escrow_b_key,
+                &mut escrow_b_account,
+                &pool_key,
+                &mut pool_account,
+                40,
+            )
+        );
+
+        // cancelled after a tranche, the rest is refunded
+        let (
+            drip_key,
+            drip_nonce,
+            mut drip_account,
+            (escrow_a_key, mut escrow_a_account),
+            (escrow_b_key, mut escrow_b_account),
+        ) = new_drip(&mut accounts, 2);
+        accounts
+            .create_drip_deposit(
+                &depositor_key,
+                &mut depositor_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                &escrow_a_key,
+                &mut escrow_a_account,
+                &escrow_b_key,
+                &mut escrow_b_account,
+                &pool_key,
+                &mut pool_account,
+                &drip_key,
+                &mut drip_account,
+                drip_data(2, drip_nonce, 2),
+                40,
+            )
+            .unwrap();
+        accounts
+            .deposit_drip_tranche(
+                &drip_key,
+                &mut drip_account,
+                &escrow_a_key,
+                &mut escrow_a_account,
+                &escrow_b_key,
+                &mut escrow_b_account,
+                &pool_key,
+                &mut pool_account,
+                50,
+            )
+            .unwrap();
+
+        // only the owner cancels
+        {
+            let wrong_owner_key = pubkey_rand();
+            let mut wrong_owner_account = Account::default();
             assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(SwapError::Unauthorized.into()),
+                accounts.cancel_drip_deposit(
+                    &drip_key,
+                    &mut drip_account,
+                    &wrong_owner_key,
+                    &mut wrong_owner_account,
+                    &escrow_a_key,
+                    &mut escrow_a_account,
+                    &escrow_b_key,
+                    &mut escrow_b_account,
                     &token_a_key,
                     &mut token_a_account,
                     &token_b_key,
                     &mut token_b_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount * 30, // XXX: 10 -> 30; Revisit this splippage multiplier
                 )
             );
         }
 
-        // correct withdrawal
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool,
+        let depositor_lamports = depositor_account.lamports;
+        let drip_lamports = drip_account.lamports;
+        let escrow_lamports = escrow_a_account.lamports + escrow_b_account.lamports;
+        accounts
+            .cancel_drip_deposit(
+                &drip_key,
+                &mut drip_account,
+                &depositor_key,
+                &mut depositor_account,
+                &escrow_a_key,
+                &mut escrow_a_account,
+                &escrow_b_key,
+                &mut escrow_b_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&token_a_account), 8_500_000);
+        assert_eq!(amount(&token_b_account), 8_500_000);
+        assert_eq!(
+            depositor_account.lamports,
+            depositor_lamports + drip_lamports + escrow_lamports
+        );
+        assert_eq!(drip_account.lamports, 0);
+        assert_eq!(escrow_a_account.lamports, 0);
+        assert_eq!(escrow_b_account.lamports, 0);
+        assert!(drip_account.data.iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_deposit_bonus() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 100, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+        accounts.set_deposit_bonus(10_000, 100).unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let reserves = |accounts: &SwapAccountInfo| {
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            (
+                swap_info
+                    .pool_amount(&swap_token_a_key, amount(&accounts.token_a_account))
+                    .unwrap(),
+                swap_info
+                    .pool_amount(&swap_token_b_key, amount(&accounts.token_b_account))
+                    .unwrap(),
+            )
+        };
+        let assert_conserved = |accounts: &SwapAccountInfo| {
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            let (reserve_a, reserve_b) = reserves(accounts);
+            assert_eq!(
+                amount(&accounts.token_a_account),
+                reserve_a + swap_info.admin_fees_accrued_a + swap_info.fee_buffer_a
+            );
+            assert_eq!(
+                amount(&accounts.token_b_account),
+                reserve_b + swap_info.admin_fees_accrued_b + swap_info.fee_buffer_b
             );
+        };
 
-            accounts
-                .withdraw(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
-                )
-                .unwrap();
+        // The admin fees of a swap fund the fee buffer and the pool is left short of B
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        accounts
+            .swap_with_access_token(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                None,
+                0,
+                100_000,
+                0,
+                None,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.admin_fees_accrued_b, 0);
+        assert_ne!(swap_info.fee_buffer_b, 0);
+        assert_conserved(&accounts);
 
-            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
-            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+        let (
+            depositor_a_key,
+            mut depositor_a_account,
+            depositor_b_key,
+            mut depositor_b_account,
+            depositor_pool_key,
+            mut depositor_pool_account,
+        ) = accounts.setup_token_accounts(&user_key, &depositor_key, 50_000, 50_000, 0);
+
+        // A deposit of B reduces the imbalance and earns a bonus from the buffer
+        {
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            let (reserve_a, reserve_b) = reserves(&accounts);
+            let (bonus_a, bonus_b) = swap_info
+                .deposit_bonus(0, 20_000, reserve_a, reserve_b)
+                .unwrap();
+            assert_eq!(bonus_a, 0);
+            assert_ne!(bonus_b, 0);
             let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
-            let pool_converter = PoolTokenConverter {
-                supply: U256::from(pool_mint.supply),
-                token_a: U256::from(swap_token_a.amount),
-                token_b: U256::from(swap_token_b.amount),
-                fees: &DEFAULT_TEST_FEES,
+            let invariant = StableSwap::new(
+                swap_info.initial_amp_factor,
+                swap_info.target_amp_factor,
+                ZERO_TS,
+                swap_info.start_ramp_ts,
+                swap_info.stop_ramp_ts,
+            );
+            let mint_amount = |deposit_b: u64| {
+                invariant
+                    .compute_mint_amount_for_deposit(
+                        U256::zero(),
+                        U256::from(deposit_b),
+                        U256::from(reserve_a),
+                        U256::from(reserve_b),
+                        U256::from(pool_mint.supply),
+                        &swap_info.fees,
+                    )
+                    .unwrap()
             };
+            let swap_b_before = amount(&accounts.token_b_account);
 
-            let (withdrawn_a, admin_fee_a) = pool_converter
-                .token_a_rate(U256::from(withdraw_amount))
-                .unwrap();
-            let withrawn_total_a = U256::to_u64(withdrawn_a + admin_fee_a).unwrap();
-            assert_eq!(swap_token_a.amount, token_a_amount - withrawn_total_a);
-            let (withdrawn_b, admin_fee_b) = pool_converter
-                .token_b_rate(U256::from(withdraw_amount))
+            accounts
+                .deposit(
+                    &depositor_key,
+                    &depositor_a_key,
+                    &mut depositor_a_account,
+                    &depositor_b_key,
+                    &mut depositor_b_account,
+                    &depositor_pool_key,
+                    &mut depositor_pool_account,
+                    0,
+                    20_000,
+                    0,
+                )
                 .unwrap();
-            let withrawn_total_b = U256::to_u64(withdrawn_b + admin_fee_b).unwrap();
-            assert_eq!(swap_token_b.amount, token_b_amount - withrawn_total_b);
-            let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
+            let minted = U256::from(amount(&depositor_pool_account));
+            assert_eq!(minted, mint_amount(20_000 + bonus_b));
+            assert!(minted > mint_amount(20_000));
+            assert_eq!(amount(&accounts.token_b_account), swap_b_before + 20_000);
             assert_eq!(
-                token_a.amount,
-                initial_a + U256::to_u64(withdrawn_a).unwrap()
+                reserves(&accounts),
+                (reserve_a, reserve_b + 20_000 + bonus_b)
             );
-            let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
+            let fee_buffer_b = SwapInfo::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .fee_buffer_b;
+            assert_eq!(fee_buffer_b, swap_info.fee_buffer_b - bonus_b);
+            assert_conserved(&accounts);
+        }
+
+        // A deposit of A adds to the imbalance and earns no bonus
+        {
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            accounts
+                .deposit(
+                    &depositor_key,
+                    &depositor_a_key,
+                    &mut depositor_a_account,
+                    &depositor_b_key,
+                    &mut depositor_b_account,
+                    &depositor_pool_key,
+                    &mut depositor_pool_account,
+                    20_000,
+                    0,
+                    0,
+                )
+                .unwrap();
             assert_eq!(
-                token_b.amount,
-                initial_b + U256::to_u64(withdrawn_b).unwrap()
+                SwapInfo::unpack(&accounts.swap_account.data).unwrap(),
+                SwapInfo {
+                    sequence: swap_info.sequence + 1,
+                    ..swap_info
+                }
             );
-            let pool_account = utils::unpack_token_account(&pool_account.data).unwrap();
-            assert_eq!(pool_account.amount, initial_pool - withdraw_amount);
-            let admin_fee_key_a =
-                utils::unpack_token_account(&accounts.admin_fee_a_account.data).unwrap();
-            assert_eq!(admin_fee_key_a.amount, U256::to_u64(admin_fee_a).unwrap());
-            let admin_fee_key_b =
-                utils::unpack_token_account(&accounts.admin_fee_b_account.data).unwrap();
-            assert_eq!(admin_fee_key_b.amount, U256::to_u64(admin_fee_b).unwrap());
+            assert_conserved(&accounts);
         }
     }
 
     #[test]
-    fn test_swap() {
+    fn test_withdraw_fee_stays_with_remaining_lps() {
         let user_key = pubkey_rand();
-        let swapper_key = pubkey_rand();
-        let amp_factor = 85;
-        let token_a_amount = 5000;
-        let token_b_amount = 5000;
-        let mut accounts = SwapAccountInfo::new(
+        let withdrawer_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 100, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let virtual_price = |accounts: &SwapAccountInfo| {
+            let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+            StableSwap::new(100, 100, ZERO_TS, ZERO_TS, ZERO_TS)
+                .compute_virtual_price(
+                    U256::from(amount(&accounts.token_a_account)),
+                    U256::from(amount(&accounts.token_b_account)),
+                    U256::from(pool_mint.supply),
+                )
+                .unwrap()
+        };
+        let (
+            token_a_key,
+            mut token_a_account,
+            token_b_key,
+            mut token_b_account,
+            pool_key,
+            mut pool_account,
+        ) = accounts.setup_token_accounts(
             &user_key,
-            amp_factor,
-            token_a_amount,
-            token_b_amount,
-            DEFAULT_TEST_FEES,
+            &withdrawer_key,
+            0,
+            0,
+            INITIAL_SWAP_POOL_AMOUNT / 4,
         );
-        let initial_a = token_a_amount / 5;
-        let initial_b = token_b_amount / 5;
-        let minimum_b_amount = initial_b / 2;
+        let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+        let share = U256::from(1_000_000u64) * INITIAL_SWAP_POOL_AMOUNT / 4 / pool_mint.supply;
+        let fees = DEFAULT_TEST_FEES;
+        let withdraw_fee = fees.withdraw_fee(share).unwrap();
+        let admin_fee = U256::to_u64(fees.admin_withdraw_fee(withdraw_fee).unwrap()).unwrap();
+        let token_amount = U256::to_u64(share - withdraw_fee).unwrap();
+        let virtual_price_before = virtual_price(&accounts);
 
-        let swap_token_a_key = accounts.token_a_key.clone();
-        let swap_token_b_key = accounts.token_b_key.clone();
+        accounts
+            .withdraw(
+                &withdrawer_key,
+                &pool_key,
+                &mut pool_account,
+                &token_a_key,
+                &mut token_a_account,
+                &token_b_key,
+                &mut token_b_account,
+                INITIAL_SWAP_POOL_AMOUNT / 4,
+                0,
+                0,
+            )
+            .unwrap();
 
-        // swap not initialized
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    initial_a,
-                    minimum_b_amount,
-                )
-            );
-        }
+        // Only the withdrawn amount and the admin share of the fee leave the pool
+        assert_eq!(amount(&token_a_account), token_amount);
+        assert_eq!(amount(&accounts.admin_fee_a_account), admin_fee);
+        assert_eq!(
+            amount(&accounts.token_a_account),
+            1_000_000 - token_amount - admin_fee
+        );
+        assert!(admin_fee < U256::to_u64(withdraw_fee).unwrap());
+        assert!(virtual_price(&accounts) > virtual_price_before);
+    }
 
+    #[test]
+    fn test_simulate_swap() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 85, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
         accounts.initialize_swap().unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 10_000, 0, 0);
+        let swap_account = accounts.swap_account.clone();
+        let swap_token_a_account = accounts.token_a_account.clone();
+        let swap_token_b_account = accounts.token_b_account.clone();
 
-        // wrong nonce
+        // errors of the swap are returned as they are
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
-            );
-            accounts.authority_key = bad_authority_key;
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.swap(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.simulate_swap(
                     &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
@@ -2670,69 +18217,19 @@ mod tests {
                     &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    initial_a,
-                    minimum_b_amount,
-                )
-            );
-            accounts.authority_key = old_authority;
-        }
-
-        // wrong token program id
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            let wrong_program_id = pubkey_rand();
-            assert_eq!(
-                Err(ProgramError::InvalidAccountData),
-                do_process_instruction(
-                    swap(
-                        &SWAP_PROGRAM_ID,
-                        &wrong_program_id,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_b_key,
-                        &accounts.admin_fee_b_key,
-                        initial_a,
-                        minimum_b_amount,
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_b_account,
-                        &mut accounts.admin_fee_b_account,
-                        &mut Account::default(),
-                        &mut clock_account(ZERO_TS),
-                    ],
-                ),
-            );
-        }
-
-        // not enough token a to swap
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+                    1_000,
+                    1_000,
+                )
+            );
+        }
+
+        // a valid swap fails once computed and leaves every account unchanged
+        {
+            let source_account = token_a_account.clone();
+            let destination_account = token_b_account.clone();
             assert_eq!(
-                Err(TokenError::InsufficientFunds.into()),
-                accounts.swap(
+                Err(SwapError::SwapSimulated.into()),
+                accounts.simulate_swap(
                     &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
@@ -2740,991 +18237,1924 @@ mod tests {
                     &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    initial_a * 2,
-                    minimum_b_amount * 2,
+                    1_000,
+                    0,
                 )
             );
+            assert_eq!(accounts.swap_account, swap_account);
+            assert_eq!(accounts.token_a_account, swap_token_a_account);
+            assert_eq!(accounts.token_b_account, swap_token_b_account);
+            assert_eq!(token_a_account, source_account);
+            assert_eq!(token_b_account, destination_account);
         }
+    }
 
-        // wrong swap token A / B accounts
+    #[test]
+    fn test_insurance_fund() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let governance_program_id = pubkey_rand();
+        let realm_key = pubkey_rand();
+        let insurance_authority_key = pubkey_rand();
+        let mut governance_data = vec![18];
+        governance_data.extend_from_slice(realm_key.as_ref());
+        let mut insurance_authority_account = Account {
+            data: governance_data,
+            owner: governance_program_id,
+            ..Account::default()
+        };
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 100, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+        let authority_key = accounts.authority_key;
+        let (governance_vault_key, mut governance_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        accounts
+            .set_governance_realm(
+                &realm_key,
+                &governance_vault_key,
+                &mut governance_vault_account,
+                0,
+            )
+            .unwrap();
+        accounts
+            .set_insurance_fund(
+                &realm_key,
+                &mut Account::new(0, 0, &governance_program_id),
+                &insurance_authority_key,
+                &mut insurance_authority_account,
+                5_000,
+            )
+            .unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let reserve_b = |accounts: &SwapAccountInfo| {
+            SwapInfo::unpack(&accounts.swap_account.data)
+                .unwrap()
+                .pool_amount(&swap_token_b_key, amount(&accounts.token_b_account))
+                .unwrap()
+        };
+
+        // Half of the trade fee left to the LPs funds the insurance fund, in the
+        // token the fees are charged in
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        let result = {
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            StableSwap::new(
+                swap_info.initial_amp_factor,
+                swap_info.target_amp_factor,
+                ZERO_TS,
+                swap_info.start_ramp_ts,
+                swap_info.stop_ramp_ts,
+            )
+            .swap_to_v2(
+                U256::from(100_000),
+                U256::from(amount(&accounts.token_a_account)),
+                U256::from(reserve_b(&accounts)),
+                &swap_info.fees,
+            )
+            .unwrap()
+        };
+        accounts
+            .swap_with_access_token(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                None,
+                0,
+                100_000,
+                0,
+                None,
+            )
+            .unwrap();
+        let lp_fee = result.trade_fee
+            - result.reflection_fee
+            - result.buyback_fee
+            - result.marketing_fee
+            - result.developer_fee
+            - result.admin_fee;
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_ne!(swap_info.insurance_fund_b, 0);
+        assert_eq!(
+            U256::from(swap_info.insurance_fund_b),
+            lp_fee / U256::from(2)
+        );
+        assert_eq!(swap_info.insurance_fund_a, 0);
+        assert_eq!(
+            amount(&accounts.token_b_account),
+            reserve_b(&accounts) + swap_info.admin_fees_accrued_b + swap_info.insurance_fund_b
+        );
+
+        // Only the insurance authority covers shortfalls, up to the insurance fund
+        assert_eq!(
+            Err(SwapError::Unauthorized.into()),
+            accounts.cover_shortfall(&pubkey_rand(), 0, 1)
+        );
+        assert_eq!(
+            Err(SwapError::InsufficientInsuranceFund.into()),
+            accounts.cover_shortfall(&insurance_authority_key, 0, swap_info.insurance_fund_b + 1)
+        );
+
+        // Covering returns the insured tokens to the pool reserves
+        let reserve_b_before = reserve_b(&accounts);
+        let cover_b = swap_info.insurance_fund_b / 2;
+        accounts
+            .cover_shortfall(&insurance_authority_key, 0, cover_b)
+            .unwrap();
+        let covered = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(
+            covered.insurance_fund_b,
+            swap_info.insurance_fund_b - cover_b
+        );
+        assert_eq!(covered.insurance_covered_b, cover_b);
+        assert_eq!(covered.insurance_covered_a, 0);
+        assert_eq!(reserve_b(&accounts), reserve_b_before + cover_b);
+    }
+
+    #[test]
+    fn test_clone_pool() {
+        let user_key = pubkey_rand();
+        let mut source = SwapAccountInfo::new(&user_key, 100, 1_000, 1_000, DEFAULT_TEST_FEES);
+        source.initialize_swap().unwrap();
+        // halfway through a ramp to 200
+        let mut swap_info = SwapInfo::unpack(&source.swap_account.data).unwrap();
+        swap_info.target_amp_factor = 200;
+        swap_info.start_ramp_ts = ZERO_TS;
+        swap_info.stop_ramp_ts = ZERO_TS + 200_000;
+        SwapInfo::pack(swap_info, &mut source.swap_account.data).unwrap();
+        let source_key = source.swap_key;
+        let fees = Fees {
+            trade_fee_numerator: 1,
+            trade_fee_denominator: 1_000,
+            ..Fees::default()
+        };
+
+        // source not owned by the swap program
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let mut accounts = SwapAccountInfo::new(&user_key, 1, 1_000, 1_000, fees);
+            let mut source_account = Account {
+                owner: pubkey_rand(),
+                ..source.swap_account.clone()
+            };
             assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                do_process_instruction(
-                    swap(
-                        &SWAP_PROGRAM_ID,
-                        &TOKEN_PROGRAM_ID,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &token_a_key,
-                        &token_b_key,
-                        &token_b_key,
-                        &accounts.admin_fee_b_key,
-                        initial_a,
-                        minimum_b_amount,
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut token_a_account.clone(),
-                        &mut token_a_account,
-                        &mut token_b_account.clone(),
-                        &mut token_b_account,
-                        &mut accounts.admin_fee_b_account,
-                        &mut Account::default(),
-                        &mut clock_account(ZERO_TS),
-                    ],
-                ),
+                Err(ProgramError::IncorrectProgramId),
+                accounts.clone_pool(&source_key, &mut source_account, 100_000)
             );
         }
 
-        // wrong admin account
+        // source not initialized
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                wrong_admin_key,
-                mut wrong_admin_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let mut accounts = SwapAccountInfo::new(&user_key, 1, 1_000, 1_000, fees);
+            let mut source_account = Account::new(0, SwapInfo::get_packed_len(), &SWAP_PROGRAM_ID);
             assert_eq!(
-                Err(SwapError::InvalidAdmin.into()),
-                do_process_instruction(
-                    swap(
-                        &SWAP_PROGRAM_ID,
-                        &TOKEN_PROGRAM_ID,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_b_key,
-                        &wrong_admin_key,
-                        initial_a,
-                        minimum_b_amount,
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_b_account,
-                        &mut wrong_admin_account,
-                        &mut Account::default(),
-                        &mut clock_account(ZERO_TS),
-                    ],
-                ),
+                Err(ProgramError::UninitializedAccount),
+                accounts.clone_pool(&source_key, &mut source_account, 100_000)
             );
         }
 
-        // wrong user token A / B accounts
+        // the clone copies the fees and the current amp, with its own namespace
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let mut accounts = SwapAccountInfo::new_with_pool_seeds(
+                &user_key,
+                1,
+                1_000,
+                1_000,
+                fees,
+                FeeTier::Custom,
+                [5u8; 32],
+            );
+            accounts
+                .clone_pool(&source_key, &mut source.swap_account, 100_000)
+                .unwrap();
+            let clone = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert!(clone.is_initialized);
+            assert_eq!(clone.fees, DEFAULT_TEST_FEES);
+            assert_eq!(clone.fee_tier, FeeTier::Custom);
+            assert_eq!(clone.initial_amp_factor, 150);
+            assert_eq!(clone.target_amp_factor, 150);
+            assert_eq!(clone.stop_ramp_ts, ZERO_TS);
+            assert_eq!(clone.namespace, [5u8; 32]);
+            assert_eq!(clone.token_a, accounts.token_a_key);
+            assert_eq!(clone.pool_mint, accounts.pool_mint_key);
+            assert_eq!(clone.access_window_end_slot, 0);
+        }
+
+        // a fee tier replaces the copied fees
+        {
+            let mut accounts = SwapAccountInfo::new_with_pool_seeds(
+                &user_key,
+                1,
+                1_000,
+                1_000,
+                fees,
+                FeeTier::FiveBps,
+                [0u8; 32],
+            );
+            accounts
+                .clone_pool(&source_key, &mut source.swap_account, 100_000)
+                .unwrap();
+            let clone = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            assert_eq!(clone.fee_tier, FeeTier::FiveBps);
+            assert_eq!(Some(clone.fees), FeeTier::FiveBps.fees());
+        }
+    }
+
+    #[test]
+    fn test_clone_pool_freeze_authority() {
+        let user_key = pubkey_rand();
+        let with_freeze_authority = |accounts: &mut SwapAccountInfo, freeze_authority: &Pubkey| {
+            let (_mint_key, mint_account) = create_mint(
+                &TOKEN_PROGRAM_ID,
+                &user_key,
+                DEFAULT_TOKEN_DECIMALS,
+                Some(freeze_authority),
+            );
+            accounts.token_b_mint_account = mint_account;
+        };
+        let clone_pool = |source: &mut SwapAccountInfo, freeze_authority_acknowledged| {
+            let mut accounts = SwapAccountInfo::new(&user_key, 1, 1_000, 1_000, DEFAULT_TEST_FEES);
+            with_freeze_authority(&mut accounts, &pubkey_rand());
+            accounts.freeze_authority_acknowledged = freeze_authority_acknowledged;
+            let source_key = source.swap_key;
+            accounts
+                .clone_pool(&source_key, &mut source.swap_account, 100_000)
+                .map(|()| SwapInfo::unpack(&accounts.swap_account.data).unwrap())
+        };
+
+        // a source acknowledging another freeze authority doesn't acknowledge the
+        // mints of the copy
+        {
+            let mut source = SwapAccountInfo::new(&user_key, 100, 1_000, 1_000, DEFAULT_TEST_FEES);
+            with_freeze_authority(&mut source, &pubkey_rand());
+            source.freeze_authority_acknowledged = true;
+            source.initialize_swap().unwrap();
+            assert!(
+                SwapInfo::unpack(&source.swap_account.data)
+                    .unwrap()
+                    .freeze_authority_acknowledged
+            );
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_b_key,
-                    &mut token_b_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
-                    &token_a_key,
-                    &mut token_a_account,
-                    initial_a,
-                    minimum_b_amount,
-                )
+                Err(SwapError::FreezeAuthorityNotAcknowledged.into()),
+                clone_pool(&mut source, false)
+            );
+            assert!(
+                clone_pool(&mut source, true)
+                    .unwrap()
+                    .freeze_authority_acknowledged
             );
         }
 
-        // swap from a to a
+        // a clone of an unacknowledged source acknowledges the mints of the copy
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+            let mut source = SwapAccountInfo::new(&user_key, 100, 1_000, 1_000, DEFAULT_TEST_FEES);
+            source.initialize_swap().unwrap();
+            assert!(
+                !SwapInfo::unpack(&source.swap_account.data)
+                    .unwrap()
+                    .freeze_authority_acknowledged
+            );
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.swap(
-                    &swapper_key,
-                    &token_a_key,
-                    &mut token_a_account.clone(),
-                    &swap_token_a_key,
-                    &swap_token_a_key,
+                Err(SwapError::FreezeAuthorityNotAcknowledged.into()),
+                clone_pool(&mut source, false)
+            );
+            assert!(
+                clone_pool(&mut source, true)
+                    .unwrap()
+                    .freeze_authority_acknowledged
+            );
+        }
+    }
+
+    #[test]
+    fn test_deposit_min_lp_mint() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let fees = Fees {
+            withdraw_fee_numerator: 99,
+            withdraw_fee_denominator: 100,
+            ..DEFAULT_TEST_FEES
+        };
+        let mut accounts = SwapAccountInfo::new(&user_key, MIN_AMP, 1_000_000, 1_000_000, fees);
+        accounts.initialize_swap().unwrap();
+
+        // withdraw fees left in the pool make a pool token worth many tokens
+        {
+            let pool_token_key = accounts.pool_token_key;
+            let mut pool_token_account = accounts.pool_token_account.clone();
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &user_key, 0, 0, 0);
+            accounts
+                .withdraw(
+                    &user_key,
+                    &pool_token_key,
+                    &mut pool_token_account,
                     &token_a_key,
                     &mut token_a_account,
-                    initial_a,
-                    minimum_b_amount,
+                    &token_b_key,
+                    &mut token_b_account,
+                    1_990_000,
+                    0,
+                    0,
                 )
-            );
+                .unwrap();
         }
 
-        // no approval
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
-                do_process_instruction(
-                    swap(
-                        &SWAP_PROGRAM_ID,
-                        &TOKEN_PROGRAM_ID,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &token_a_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_b_key,
-                        &accounts.admin_fee_b_key,
-                        initial_a,
-                        minimum_b_amount,
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut token_a_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut accounts.admin_fee_b_account,
-                        &mut token_b_account,
-                        &mut Account::default(),
-                        &mut clock_account(ZERO_TS),
-                    ],
-                ),
+        // a deposit too small to mint a single pool token is rejected
+        {
+            let (
+                token_a_key,
+                mut token_a_account,
+                token_b_key,
+                mut token_b_account,
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, 1, 1, 0);
+            assert_eq!(
+                Err(SwapError::MintAmountTooSmall.into()),
+                accounts.deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    1,
+                    1,
+                    0,
+                )
             );
         }
 
-        // slippage exceeeded: minimum out amount too high
+        // a deposit minting less than the pool minimum is rejected
         {
+            accounts.set_min_lp_mint(100).unwrap();
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, 1_000, 1_000, 0);
             assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.swap(
-                    &swapper_key,
+                Err(SwapError::MintAmountTooSmall.into()),
+                accounts.deposit(
+                    &depositor_key,
                     &token_a_key,
                     &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    initial_a,
-                    minimum_b_amount * 2,
+                    &pool_key,
+                    &mut pool_account,
+                    1_000,
+                    1_000,
+                    0,
                 )
             );
+            accounts.set_min_lp_mint(0).unwrap();
         }
+    }
 
-        // correct swap
+    #[test]
+    fn test_deposit_refunds_excess() {
+        let user_key = pubkey_rand();
+        let depositor_key = pubkey_rand();
+        let fees = Fees {
+            withdraw_fee_numerator: 99,
+            withdraw_fee_denominator: 100,
+            ..DEFAULT_TEST_FEES
+        };
+        for (token_a_amount, token_b_amount, deposit_a, deposit_b, expected) in [
+            // balanced pool, the remainder lost to rounding is left
+            (1_000_000, 1_000_000, 1_000, 1_000, (4, 4, 20)),
+            // odd ratio pools
+            (1_000_000, 3_000_000, 1_000, 3_000, (9, 28, 37)),
+            (7_000_000, 1_000_000, 7_000, 1_000, (81, 11, 64)),
+            // amounts off the pool ratio are taken in full
+            (1_000_000, 3_000_000, 1_000, 1_000, (0, 0, 21)),
+        ]
+        .iter()
         {
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, MIN_AMP, *token_a_amount, *token_b_amount, fees);
+            accounts.initialize_swap().unwrap();
+
+            // withdraw fees left in the pool make a pool token worth many tokens
+            let pool_token_key = accounts.pool_token_key;
+            let mut pool_token_account = accounts.pool_token_account.clone();
+            let pool_token = utils::unpack_token_account(&pool_token_account.data).unwrap();
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &user_key, 0, 0, 0);
+            accounts
+                .withdraw(
+                    &user_key,
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    pool_token.amount - pool_token.amount / 200,
+                    0,
+                    0,
+                )
+                .unwrap();
+
+            let reserve_a = utils::unpack_token_account(&accounts.token_a_account.data)
+                .unwrap()
+                .amount;
+            let reserve_b = utils::unpack_token_account(&accounts.token_b_account.data)
+                .unwrap()
+                .amount;
             let (
                 token_a_key,
                 mut token_a_account,
                 token_b_key,
                 mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            // swap one way
-            let a_to_b_amount = initial_a / 10;
-            let minimum_b_amount = initial_b / 20;
+                pool_key,
+                mut pool_account,
+            ) = accounts.setup_token_accounts(&user_key, &depositor_key, *deposit_a, *deposit_b, 0);
             accounts
-                .swap(
-                    &swapper_key,
+                .deposit(
+                    &depositor_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &pool_key,
+                    &mut pool_account,
+                    *deposit_a,
+                    *deposit_b,
+                    0,
+                )
+                .unwrap();
+            let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
+            let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
+            let pool = utils::unpack_token_account(&pool_account.data).unwrap();
+            assert_eq!((token_a.amount, token_b.amount, pool.amount), *expected);
+            // the pool only received what the depositor paid
+            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+            assert_eq!(swap_token_a.amount, reserve_a + deposit_a - token_a.amount);
+            assert_eq!(swap_token_b.amount, reserve_b + deposit_b - token_b.amount);
+        }
+    }
+
+    #[test]
+    fn test_sweep_dust() {
+        let user_key = pubkey_rand();
+        let sweeper_key = pubkey_rand();
+        let token_a_amount = 1_000_000;
+        let token_b_amount = 2_000_000;
+        let mut accounts = SwapAccountInfo::new(
+            &user_key,
+            MIN_AMP,
+            token_a_amount,
+            token_b_amount,
+            DEFAULT_TEST_FEES,
+        );
+        accounts.initialize_swap().unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+
+        // no balance is dust until the admin sets a threshold
+        {
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &sweeper_key, 10, 0, 0);
+            assert_eq!(
+                Err(SwapError::NotDust.into()),
+                accounts.sweep_dust(
+                    &sweeper_key,
                     &token_a_key,
                     &mut token_a_account,
                     &swap_token_a_key,
                     &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    a_to_b_amount,
-                    minimum_b_amount,
+                    1,
                 )
-                .unwrap();
+            );
+        }
 
-            let invariant = StableSwap::new(
-                accounts.initial_amp_factor,
-                accounts.target_amp_factor,
-                ZERO_TS,
-                ZERO_TS,
-                ZERO_TS,
+        accounts.set_dust_threshold(100).unwrap();
+
+        // balances above the threshold are not dust
+        {
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &sweeper_key, 101, 0, 0);
+            assert_eq!(
+                Err(SwapError::NotDust.into()),
+                accounts.sweep_dust(
+                    &sweeper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    1,
+                )
             );
-            let result = invariant
-                .swap_to(
-                    U256::from(a_to_b_amount),
+        }
+
+        // the whole balance is swapped free of fees
+        {
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &sweeper_key, 100, 0, 0);
+            let invariant = StableSwap::new(MIN_AMP, MIN_AMP, ZERO_TS, ZERO_TS, ZERO_TS);
+            let expected = invariant
+                .swap_without_fees(
+                    U256::from(100),
                     U256::from(token_a_amount),
                     U256::from(token_b_amount),
-                    &DEFAULT_TEST_FEES,
                 )
                 .unwrap();
-
-            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
-            let token_a_amount = swap_token_a.amount;
-            assert_eq!(token_a_amount, 5100);
-            assert_eq!(
-                token_a_amount,
-                U256::to_u64(result.new_source_amount).unwrap()
-            );
+            accounts
+                .sweep_dust(
+                    &sweeper_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    1,
+                )
+                .unwrap();
             let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, initial_a - a_to_b_amount);
-
-            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
-            let token_b_amount = swap_token_b.amount;
-            assert_eq!(token_b_amount, 4903);
-            assert_eq!(
-                token_b_amount,
-                U256::to_u64(result.new_destination_amount).unwrap()
-            );
             let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
-            assert_eq!(token_b.amount, 1094);
-            assert_eq!(
-                token_b.amount,
-                initial_b + U256::to_u64(result.amount_swapped).unwrap()
-            );
-            let admin_fee_b_account =
-                utils::unpack_token_account(&accounts.admin_fee_b_account.data).unwrap();
+            assert_eq!(token_a.amount, 0);
+            assert_eq!(U256::from(token_b.amount), expected);
+            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
+            assert_eq!(swap_token_a.amount, token_a_amount + 100);
+            assert_eq!(swap_token_b.amount, token_b_amount - token_b.amount);
+        }
+
+        // sweeps of a slot are bounded
+        {
+            for _ in 1..MAX_DUST_SWEEPS_PER_SLOT {
+                let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                    accounts.setup_token_accounts(&user_key, &sweeper_key, 0, 50, 0);
+                accounts
+                    .sweep_dust(
+                        &sweeper_key,
+                        &token_b_key,
+                        &mut token_b_account,
+                        &swap_token_b_key,
+                        &swap_token_a_key,
+                        &token_a_key,
+                        &mut token_a_account,
+                        1,
+                    )
+                    .unwrap();
+            }
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &sweeper_key, 0, 50, 0);
             assert_eq!(
-                admin_fee_b_account.amount,
-                U256::to_u64(result.admin_fee).unwrap()
+                Err(SwapError::DustSweepLimit.into()),
+                accounts.sweep_dust(
+                    &sweeper_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    &swap_token_b_key,
+                    &swap_token_a_key,
+                    &token_a_key,
+                    &mut token_a_account,
+                    1,
+                )
             );
-
-            let first_swap_amount = result.amount_swapped;
-
-            // swap the other way
-            let b_to_a_amount = initial_b / 10;
-            let minimum_a_amount = initial_a / 20;
             accounts
-                .swap(
-                    &swapper_key,
+                .sweep_dust(
+                    &sweeper_key,
                     &token_b_key,
                     &mut token_b_account,
                     &swap_token_b_key,
                     &swap_token_a_key,
                     &token_a_key,
                     &mut token_a_account,
-                    b_to_a_amount,
-                    minimum_a_amount,
+                    2,
                 )
                 .unwrap();
+        }
+    }
 
-            let invariant = StableSwap::new(
-                accounts.initial_amp_factor,
-                accounts.target_amp_factor,
-                ZERO_TS,
-                ZERO_TS,
-                ZERO_TS,
-            );
-            let result = invariant
-                .swap_to(
-                    U256::from(b_to_a_amount),
-                    U256::from(token_b_amount),
-                    U256::from(token_a_amount),
-                    &DEFAULT_TEST_FEES,
-                )
-                .unwrap();
+    #[test]
+    fn test_liquidity_positions() {
+        let user_key = pubkey_rand();
+        let late_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, MIN_AMP, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
 
-            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
-            assert_eq!(swap_token_a.amount, 5002);
-            assert_eq!(
-                swap_token_a.amount,
-                U256::to_u64(result.new_destination_amount).unwrap()
-            );
-            let token_a = utils::unpack_token_account(&token_a_account.data).unwrap();
-            assert_eq!(token_a.amount, 995);
-            assert_eq!(
-                token_a.amount,
-                initial_a - a_to_b_amount + U256::to_u64(result.amount_swapped).unwrap()
-            );
+        let authority_key = accounts.authority_key;
+        let (liquidity_vault_key, mut liquidity_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        let pool_token_key = accounts.pool_token_key;
+        let mut pool_token_account = accounts.pool_token_account.clone();
+        let (late_pool_token_key, mut late_pool_token_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &late_key,
+            1_000,
+        );
+        let new_owner_account = || {
+            Account::new(
+                Rent::default().minimum_balance(LiquidityPosition::LEN),
+                0,
+                &solana_program::system_program::id(),
+            )
+        };
+        let mut position_account = Account::new(
+            0,
+            LiquidityPosition::LEN,
+            &solana_program::system_program::id(),
+        );
+        let mut late_position_account = position_account.clone();
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
 
-            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
-            assert_eq!(swap_token_b.amount, 5003);
-            assert_eq!(
-                swap_token_b.amount,
-                U256::to_u64(result.new_source_amount).unwrap()
-            );
-            let token_b = utils::unpack_token_account(&token_b_account.data).unwrap();
-            assert_eq!(token_b.amount, 994);
-            assert_eq!(
-                token_b.amount,
-                initial_b + U256::to_u64(first_swap_amount).unwrap() - b_to_a_amount
-            );
-            let admin_fee_a_account =
-                utils::unpack_token_account(&accounts.admin_fee_a_account.data).unwrap();
+        // liquidity positions not enabled
+        {
             assert_eq!(
-                admin_fee_a_account.amount,
-                U256::to_u64(result.admin_fee).unwrap()
+                Err(SwapError::InvalidInput.into()),
+                accounts.deposit_liquidity_position(
+                    &user_key,
+                    &mut new_owner_account(),
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &mut liquidity_vault_account,
+                    &mut position_account.clone(),
+                    1_000,
+                    ZERO_TS,
+                )
             );
         }
 
-        // Pool is paused
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                token_b_key,
-                mut token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &swapper_key, initial_a, initial_b, 0);
-            // Pause pool
-            accounts.pause().unwrap();
+        accounts
+            .initialize_liquidity_positions(
+                &liquidity_vault_key,
+                &mut liquidity_vault_account,
+                ZERO_TS,
+            )
+            .unwrap();
+
+        // a position deposited right before the end of the period only counts for
+        // the time it was held
+        accounts
+            .deposit_liquidity_position(
+                &user_key,
+                &mut new_owner_account(),
+                &pool_token_key,
+                &mut pool_token_account,
+                &mut liquidity_vault_account,
+                &mut position_account,
+                1_000,
+                ZERO_TS,
+            )
+            .unwrap();
+        accounts
+            .deposit_liquidity_position(
+                &late_key,
+                &mut new_owner_account(),
+                &late_pool_token_key,
+                &mut late_pool_token_account,
+                &mut liquidity_vault_account,
+                &mut late_position_account,
+                1_000,
+                900,
+            )
+            .unwrap();
+        accounts
+            .checkpoint_liquidity_position(&user_key, &mut position_account, 1_000)
+            .unwrap();
+        accounts
+            .checkpoint_liquidity_position(&late_key, &mut late_position_account, 1_000)
+            .unwrap();
+        let position = LiquidityPosition::unpack(&position_account.data).unwrap();
+        assert_eq!(position.swap, accounts.swap_key);
+        assert_eq!(position.owner, user_key);
+        assert_eq!(position.amount, 1_000);
+        assert_eq!(
+            time_weighted_liquidity(0, ZERO_TS, position.cumulative_liquidity, 1_000),
+            Some(1_000)
+        );
+        let late_position = LiquidityPosition::unpack(&late_position_account.data).unwrap();
+        assert_eq!(
+            time_weighted_liquidity(0, ZERO_TS, late_position.cumulative_liquidity, 1_000),
+            Some(100)
+        );
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.position_liquidity, 2_000);
+        assert_eq!(swap_info.cumulative_position_liquidity, 1_100_000);
+        assert_eq!(amount(&liquidity_vault_account), 2_000);
 
+        // strangers can't withdraw the deposited pool tokens from the vault
+        {
+            let stranger_key = pubkey_rand();
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &stranger_key, 0, 0, 0);
             assert_eq!(
-                Err(SwapError::IsPaused.into()),
-                accounts.swap(
-                    &swapper_key,
+                Err(SwapError::AuthorityOwnedSource.into()),
+                accounts.withdraw_unapproved(
+                    &stranger_key,
+                    &liquidity_vault_key,
+                    &mut liquidity_vault_account.clone(),
                     &token_a_key,
                     &mut token_a_account,
-                    &swap_token_a_key,
-                    &swap_token_b_key,
                     &token_b_key,
                     &mut token_b_account,
-                    initial_a,
-                    minimum_b_amount,
+                    2_000,
+                    0,
+                    0,
+                )
+            );
+        }
+
+        // withdrawing more than the position holds
+        {
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_liquidity_position(
+                    &user_key,
+                    &mut position_account,
+                    &mut liquidity_vault_account,
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    1_001,
+                    2_000,
+                )
+            );
+        }
+
+        // withdrawing from the position of another owner
+        {
+            assert_eq!(
+                Err(SwapError::Unauthorized.into()),
+                accounts.withdraw_liquidity_position(
+                    &late_key,
+                    &mut position_account,
+                    &mut liquidity_vault_account,
+                    &late_pool_token_key,
+                    &mut late_pool_token_account,
+                    1_000,
+                    2_000,
                 )
             );
         }
+
+        let pool_tokens = amount(&pool_token_account);
+        accounts
+            .withdraw_liquidity_position(
+                &user_key,
+                &mut position_account,
+                &mut liquidity_vault_account,
+                &pool_token_key,
+                &mut pool_token_account,
+                500,
+                2_000,
+            )
+            .unwrap();
+        assert_eq!(amount(&pool_token_account), pool_tokens + 500);
+        assert_eq!(amount(&liquidity_vault_account), 1_500);
+        let position = LiquidityPosition::unpack(&position_account.data).unwrap();
+        assert_eq!(position.amount, 500);
+        assert_eq!(position.cumulative_liquidity, 2_000_000);
+        assert_eq!(position.cumulative_liquidity_at(3_000), Some(2_500_000));
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.position_liquidity, 1_500);
+        assert_eq!(swap_info.cumulative_position_liquidity, 3_100_000);
     }
 
     #[test]
-    fn test_withdraw_one() {
+    fn test_reflection_positions() {
         let user_key = pubkey_rand();
-        let amp_factor = MIN_AMP;
-        let token_a_amount = 1000;
-        let token_b_amount = 1000;
-        let mut accounts = SwapAccountInfo::new(
+        let other_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, MIN_AMP, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+
+        let authority_key = accounts.authority_key;
+        let (reflection_lp_vault_key, mut reflection_lp_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        let (reflection_vault_a_key, reflection_vault_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
             &user_key,
-            amp_factor,
-            token_a_amount,
-            token_b_amount,
-            DEFAULT_TEST_FEES,
+            &authority_key,
+            0,
         );
-        let withdrawer_key = pubkey_rand();
-        let initial_a = token_a_amount / 10;
-        let initial_b = token_b_amount / 10;
-        let initial_pool = initial_a + initial_b;
-        // Withdraw entire pool share
-        let withdraw_amount = initial_pool;
-        let minimum_amount = 0;
+        let (reflection_vault_b_key, reflection_vault_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let pool_token_key = accounts.pool_token_key;
+        let mut pool_token_account = accounts.pool_token_account.clone();
+        let (other_pool_token_key, mut other_pool_token_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &other_key,
+            3_000,
+        );
+        let new_owner_account = || {
+            Account::new(
+                Rent::default().minimum_balance(ReflectionPosition::LEN),
+                0,
+                &solana_program::system_program::id(),
+            )
+        };
+        let mut position_account = Account::new(
+            0,
+            ReflectionPosition::LEN,
+            &solana_program::system_program::id(),
+        );
+        let mut other_position_account = position_account.clone();
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        let mut swap = |accounts: &mut SwapAccountInfo| {
+            accounts.swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                10_000,
+                0,
+            )
+        };
 
-        // swap not initialized
+        // reflection positions not enabled
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
             assert_eq!(
-                Err(ProgramError::UninitializedAccount),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount,
-                    minimum_amount,
+                Err(SwapError::InvalidInput.into()),
+                accounts.deposit_reflection_position(
+                    &user_key,
+                    &mut new_owner_account(),
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    &mut reflection_lp_vault_account,
+                    &mut position_account.clone(),
+                    1_000,
                 )
             );
         }
 
-        accounts.initialize_swap().unwrap();
+        accounts
+            .initialize_reflection(
+                &reflection_lp_vault_key,
+                &mut reflection_lp_vault_account,
+                &reflection_vault_a_key,
+                &mut reflection_vault_a_account.clone(),
+                &reflection_vault_b_key,
+                &mut reflection_vault_b_account.clone(),
+            )
+            .unwrap();
 
-        // wrong nonce for authority_key
+        // reflection fees paid into another account than the reflection vault
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, initial_a, initial_b, 0);
-            let old_authority = accounts.authority_key;
-            let (bad_authority_key, _nonce) = Pubkey::find_program_address(
-                &[&accounts.swap_key.to_bytes()[..]],
-                &TOKEN_PROGRAM_ID,
-            );
-            accounts.authority_key = bad_authority_key;
+            assert_eq!(Err(SwapError::InvalidAdmin.into()), swap(&mut accounts));
+        }
+
+        accounts.fee_accounts_a.reflection_key = reflection_vault_a_key;
+        accounts.fee_accounts_a.reflection_account = reflection_vault_a_account;
+        accounts.fee_accounts_b.reflection_key = reflection_vault_b_key;
+        accounts.fee_accounts_b.reflection_account = reflection_vault_b_account;
+
+        accounts
+            .deposit_reflection_position(
+                &user_key,
+                &mut new_owner_account(),
+                &pool_token_key,
+                &mut pool_token_account,
+                &mut reflection_lp_vault_account,
+                &mut position_account,
+                1_000,
+            )
+            .unwrap();
+        accounts
+            .deposit_reflection_position(
+                &other_key,
+                &mut new_owner_account(),
+                &other_pool_token_key,
+                &mut other_pool_token_account,
+                &mut reflection_lp_vault_account,
+                &mut other_position_account,
+                3_000,
+            )
+            .unwrap();
+        let position = ReflectionPosition::unpack(&position_account.data).unwrap();
+        assert_eq!(position.swap, accounts.swap_key);
+        assert_eq!(position.owner, user_key);
+        assert_eq!(position.amount, 1_000);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.reflection_supply, 4_000);
+        assert_eq!(amount(&reflection_lp_vault_account), 4_000);
+
+        // strangers can't withdraw the deposited pool tokens from the vault
+        {
+            let stranger_key = pubkey_rand();
+            let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+                accounts.setup_token_accounts(&user_key, &stranger_key, 0, 0, 0);
             assert_eq!(
-                Err(SwapError::InvalidProgramAddress.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(SwapError::AuthorityOwnedSource.into()),
+                accounts.withdraw_unapproved(
+                    &stranger_key,
+                    &reflection_lp_vault_key,
+                    &mut reflection_lp_vault_account.clone(),
                     &token_a_key,
                     &mut token_a_account,
-                    withdraw_amount,
-                    minimum_amount,
+                    &token_b_key,
+                    &mut token_b_account,
+                    4_000,
+                    0,
+                    0,
                 )
             );
-            accounts.authority_key = old_authority;
         }
 
-        // not enough pool tokens
+        // the reflection fees of a swap accrue pro rata to the positions
+        swap(&mut accounts).unwrap();
+        let reflection_fees = amount(&accounts.fee_accounts_b.reflection_account);
+        assert!(reflection_fees > 0);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(
+            reflection_owed(4_000, swap_info.acc_reflection_per_lp_share_b).unwrap()
+                + swap_info.reflection_pending_b as u128,
+            reflection_fees as u128
+        );
+
+        // claiming the position of another owner
         {
             let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
-            );
+                destination_a_key,
+                mut destination_a_account,
+                destination_b_key,
+                mut destination_b_account,
+                _,
+                _,
+            ) = accounts.setup_token_accounts(&user_key, &other_key, 0, 0, 0);
             assert_eq!(
-                Err(SwapError::InvalidInput.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount * 100,
-                    minimum_amount,
+                Err(SwapError::Unauthorized.into()),
+                accounts.claim_reflection(
+                    &other_key,
+                    &mut position_account,
+                    &mut accounts.fee_accounts_a.reflection_account.clone(),
+                    &mut accounts.fee_accounts_b.reflection_account.clone(),
+                    &destination_a_key,
+                    &mut destination_a_account,
+                    &destination_b_key,
+                    &mut destination_b_account,
                 )
             );
         }
 
-        // same swap / quote accounts
-        {
+        let claim = |accounts: &mut SwapAccountInfo,
+                     owner_key: &Pubkey,
+                     position_account: &mut Account| {
             let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
-            );
+                destination_a_key,
+                mut destination_a_account,
+                destination_b_key,
+                mut destination_b_account,
+                _,
+                _,
+            ) = accounts.setup_token_accounts(&user_key, owner_key, 0, 0, 0);
+            let mut reflection_vault_a_account = accounts.fee_accounts_a.reflection_account.clone();
+            let mut reflection_vault_b_account = accounts.fee_accounts_b.reflection_account.clone();
+            accounts
+                .claim_reflection(
+                    owner_key,
+                    position_account,
+                    &mut reflection_vault_a_account,
+                    &mut reflection_vault_b_account,
+                    &destination_a_key,
+                    &mut destination_a_account,
+                    &destination_b_key,
+                    &mut destination_b_account,
+                )
+                .unwrap();
+            accounts.fee_accounts_a.reflection_account = reflection_vault_a_account;
+            accounts.fee_accounts_b.reflection_account = reflection_vault_b_account;
+            (
+                amount(&destination_a_account),
+                amount(&destination_b_account),
+            )
+        };
+        let (claimed_a, claimed_b) = claim(&mut accounts, &user_key, &mut position_account);
+        let (other_claimed_a, other_claimed_b) =
+            claim(&mut accounts, &other_key, &mut other_position_account);
+        assert_eq!((claimed_a, other_claimed_a), (0, 0));
+        let acc_reflection_per_lp_share_b = swap_info.acc_reflection_per_lp_share_b;
+        assert_eq!(
+            claimed_b as u128,
+            reflection_owed(1_000, acc_reflection_per_lp_share_b).unwrap()
+        );
+        assert_eq!(
+            other_claimed_b as u128,
+            reflection_owed(3_000, acc_reflection_per_lp_share_b).unwrap()
+        );
+        assert!(claimed_b + other_claimed_b <= reflection_fees);
+        assert!(claimed_b + other_claimed_b + 2 >= reflection_fees);
+        assert_eq!(
+            amount(&accounts.fee_accounts_b.reflection_account),
+            reflection_fees - claimed_b - other_claimed_b
+        );
 
-            let old_token_b_key = accounts.token_b_key;
-            let old_token_b_account = accounts.token_b_account;
-            accounts.token_b_key = accounts.token_a_key.clone();
-            accounts.token_b_account = accounts.token_a_account.clone();
+        // nothing left to claim
+        assert_eq!(
+            claim(&mut accounts, &user_key, &mut position_account),
+            (0, 0)
+        );
 
+        // withdrawing more than the position holds
+        {
             assert_eq!(
                 Err(SwapError::InvalidInput.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount,
-                    minimum_amount,
+                accounts.withdraw_reflection_position(
+                    &user_key,
+                    &mut position_account,
+                    &mut reflection_lp_vault_account,
+                    &pool_token_key,
+                    &mut pool_token_account,
+                    1_001,
                 )
             );
-
-            accounts.token_b_key = old_token_b_key;
-            accounts.token_b_account = old_token_b_account;
         }
 
-        // foreign swap / quote accounts
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
+        // withdrawn pool tokens do not share the reflection fees of later swaps, the
+        // fees accrued before are kept
+        accounts
+            .withdraw_reflection_position(
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
-            );
-            let foreign_authority = pubkey_rand();
-            let (foreign_mint_key, mut foreign_mint_account) = create_mint(
-                &TOKEN_PROGRAM_ID,
-                &foreign_authority,
-                DEFAULT_TOKEN_DECIMALS,
-                None,
-            );
-            let (foreign_token_key, foreign_token_account) = mint_token(
-                &TOKEN_PROGRAM_ID,
-                &foreign_mint_key,
-                &mut foreign_mint_account,
-                &foreign_authority,
-                &pubkey_rand(),
-                0,
-            );
+                &mut position_account,
+                &mut reflection_lp_vault_account,
+                &pool_token_key,
+                &mut pool_token_account,
+                1_000,
+            )
+            .unwrap();
+        swap(&mut accounts).unwrap();
+        assert_eq!(
+            claim(&mut accounts, &user_key, &mut position_account),
+            (0, 0)
+        );
+        let (_, other_claimed_b) = claim(&mut accounts, &other_key, &mut other_position_account);
+        assert!(other_claimed_b > 3 * claimed_b);
+        let position = ReflectionPosition::unpack(&position_account.data).unwrap();
+        assert_eq!(position.amount, 0);
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.reflection_supply, 3_000);
+        assert_eq!(amount(&reflection_lp_vault_account), 3_000);
+    }
 
-            let old_token_a_key = accounts.token_a_key;
-            let old_token_a_account = accounts.token_a_account;
-            accounts.token_a_key = foreign_token_key.clone();
-            accounts.token_a_account = foreign_token_account.clone();
+    #[test]
+    fn test_farm_staking() {
+        let user_key = pubkey_rand();
+        let other_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, MIN_AMP, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
 
-            assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount,
-                    minimum_amount,
-                )
+        let authority_key = accounts.authority_key;
+        let (stake_vault_key, mut stake_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &authority_key,
+            0,
+        );
+        let (reward_mint_key, mut reward_mint_account) =
+            create_mint(&TOKEN_PROGRAM_ID, &user_key, 6, None);
+        let (reward_vault_key, mut reward_vault_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &reward_mint_key,
+            &mut reward_mint_account,
+            &user_key,
+            &authority_key,
+            10_000,
+        );
+        let mut farm_account = Account::new(0, Farm::LEN, &solana_program::system_program::id());
+        accounts.admin_account.lamports = Rent::default().minimum_balance(Farm::LEN);
+        accounts
+            .initialize_farm(
+                &mut farm_account,
+                &stake_vault_key,
+                &mut stake_vault_account,
+                &reward_vault_key,
+                &mut reward_vault_account,
+                100,
+                0,
+            )
+            .unwrap();
+
+        let pool_token_key = accounts.pool_token_key;
+        let mut pool_token_account = accounts.pool_token_account.clone();
+        let (other_pool_token_key, mut other_pool_token_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.pool_mint_key,
+            &mut accounts.pool_mint_account,
+            &authority_key,
+            &other_key,
+            3_000,
+        );
+        let new_owner_account = || {
+            Account::new(
+                Rent::default().minimum_balance(StakeAccount::LEN),
+                0,
+                &solana_program::system_program::id(),
+            )
+        };
+        let mut stake_account =
+            Account::new(0, StakeAccount::LEN, &solana_program::system_program::id());
+        let mut other_stake_account = stake_account.clone();
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let mut harvest = |accounts: &mut SwapAccountInfo,
+                           owner_key: &Pubkey,
+                           farm_account: &mut Account,
+                           stake_account: &mut Account,
+                           reward_vault_account: &mut Account,
+                           slot: Slot| {
+            let (destination_key, mut destination_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &reward_mint_key,
+                &mut reward_mint_account,
+                &user_key,
+                owner_key,
+                0,
             );
+            accounts.harvest_rewards(
+                owner_key,
+                farm_account,
+                stake_account,
+                reward_vault_account,
+                &destination_key,
+                &mut destination_account,
+                slot,
+            )?;
+            Ok::<_, ProgramError>(amount(&destination_account))
+        };
 
-            accounts.token_a_key = old_token_a_key;
-            accounts.token_a_account = old_token_a_account;
+        // nothing is emitted before the first stake
+        accounts
+            .stake(
+                &user_key,
+                &mut new_owner_account(),
+                &mut farm_account,
+                &pool_token_key,
+                &mut pool_token_account,
+                &mut stake_vault_account,
+                &mut reward_vault_account,
+                &mut stake_account,
+                1_000,
+                10,
+            )
+            .unwrap();
+        let stake = StakeAccount::unpack(&stake_account.data).unwrap();
+        assert_eq!(stake.owner, user_key);
+        assert_eq!(stake.amount, 1_000);
+        assert_eq!(stake.reward_owed, 0);
 
-            let old_token_b_key = accounts.token_b_key;
-            let old_token_b_account = accounts.token_b_account;
-            accounts.token_b_key = foreign_token_key.clone();
-            accounts.token_b_account = foreign_token_account.clone();
+        // the emission of each slot is shared pro rata to the stakes
+        accounts
+            .stake(
+                &other_key,
+                &mut new_owner_account(),
+                &mut farm_account,
+                &other_pool_token_key,
+                &mut other_pool_token_account,
+                &mut stake_vault_account,
+                &mut reward_vault_account,
+                &mut other_stake_account,
+                3_000,
+                20,
+            )
+            .unwrap();
+        let farm = Farm::unpack(&farm_account.data).unwrap();
+        assert_eq!(farm.total_staked, 4_000);
+        assert_eq!(farm.rewards_unharvested, 1_000);
+        assert_eq!(amount(&stake_vault_account), 4_000);
 
+        // harvesting the stake of another owner
+        {
             assert_eq!(
-                Err(SwapError::IncorrectSwapAccount.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount,
-                    minimum_amount,
+                Err(SwapError::Unauthorized.into()),
+                harvest(
+                    &mut accounts,
+                    &other_key,
+                    &mut farm_account,
+                    &mut stake_account,
+                    &mut reward_vault_account,
+                    40,
                 )
             );
-
-            accounts.token_b_key = old_token_b_key;
-            accounts.token_b_account = old_token_b_account;
         }
 
-        // wrong pool token account
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                wrong_token_b_key,
-                mut wrong_token_b_account,
-                _pool_key,
-                _pool_account,
-            ) = accounts.setup_token_accounts(
+        assert_eq!(
+            harvest(
+                &mut accounts,
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                withdraw_amount,
-                withdraw_amount,
+                &mut farm_account,
+                &mut stake_account,
+                &mut reward_vault_account,
+                40,
+            ),
+            Ok(1_000 + 500)
+        );
+
+        // unstaking more than staked
+        {
+            let (destination_key, mut destination_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &authority_key,
+                &other_key,
+                0,
             );
             assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &wrong_token_b_key,
-                    &mut wrong_token_b_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount,
-                    minimum_amount,
+                Err(SwapError::InvalidInput.into()),
+                accounts.unstake(
+                    &other_key,
+                    &mut farm_account,
+                    &mut other_stake_account,
+                    &mut stake_vault_account,
+                    &mut reward_vault_account,
+                    &destination_key,
+                    &mut destination_account,
+                    3_001,
+                    40,
                 )
             );
         }
 
-        // no approval
+        // unstaked pool tokens stop earning, the rewards owed are kept
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(&user_key, &withdrawer_key, 0, 0, withdraw_amount);
-            assert_eq!(
-                Err(TokenError::OwnerMismatch.into()),
-                do_process_instruction(
-                    withdraw_one(
-                        &SWAP_PROGRAM_ID,
-                        &TOKEN_PROGRAM_ID,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.pool_mint_key,
-                        &pool_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_a_key,
-                        &accounts.admin_fee_a_key,
-                        withdraw_amount,
-                        minimum_amount,
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_a_account,
-                        &mut accounts.admin_fee_a_account,
-                        &mut Account::default(),
-                        &mut clock_account(ZERO_TS),
-                    ],
-                )
+            let (destination_key, mut destination_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &accounts.pool_mint_key,
+                &mut accounts.pool_mint_account,
+                &authority_key,
+                &other_key,
+                0,
             );
+            accounts
+                .unstake(
+                    &other_key,
+                    &mut farm_account,
+                    &mut other_stake_account,
+                    &mut stake_vault_account,
+                    &mut reward_vault_account,
+                    &destination_key,
+                    &mut destination_account,
+                    3_000,
+                    40,
+                )
+                .unwrap();
+            assert_eq!(amount(&destination_account), 3_000);
+            let stake = StakeAccount::unpack(&other_stake_account.data).unwrap();
+            assert_eq!(stake.amount, 0);
+            assert_eq!(stake.reward_owed, 1_500);
         }
 
-        // wrong token program id
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
+        // the emission stops once the reward vault is emitted
+        assert_eq!(
+            harvest(
+                &mut accounts,
                 &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
-            );
-            let wrong_key = pubkey_rand();
+                &mut farm_account,
+                &mut stake_account,
+                &mut reward_vault_account,
+                1_000,
+            ),
+            Ok(10_000 - 1_500 - 1_500)
+        );
+        assert_eq!(
+            harvest(
+                &mut accounts,
+                &other_key,
+                &mut farm_account,
+                &mut other_stake_account,
+                &mut reward_vault_account,
+                2_000,
+            ),
+            Ok(1_500)
+        );
+        assert_eq!(amount(&reward_vault_account), 0);
+        let farm = Farm::unpack(&farm_account.data).unwrap();
+        assert_eq!(farm.rewards_unharvested, 0);
+        assert_eq!(farm.total_staked, 1_000);
+    }
+
+    #[test]
+    fn test_execute_buyback() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, MIN_AMP, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+
+        let authority_key = accounts.authority_key;
+        let (buyback_vault_a_key, mut buyback_vault_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let (buyback_vault_b_key, mut buyback_vault_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &authority_key,
+            0,
+        );
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 100_000, 0);
+
+        // buybacks not enabled
+        {
             assert_eq!(
-                Err(ProgramError::InvalidAccountData),
-                do_process_instruction(
-                    withdraw_one(
-                        &SWAP_PROGRAM_ID,
-                        &wrong_key,
-                        &accounts.swap_key,
-                        &accounts.authority_key,
-                        &accounts.pool_mint_key,
-                        &pool_key,
-                        &accounts.token_a_key,
-                        &accounts.token_b_key,
-                        &token_a_key,
-                        &accounts.admin_fee_a_key,
-                        withdraw_amount,
-                        minimum_amount,
-                    )
-                    .unwrap(),
-                    vec![
-                        &mut accounts.swap_account,
-                        &mut Account::default(),
-                        &mut accounts.pool_mint_account,
-                        &mut pool_account,
-                        &mut accounts.token_a_account,
-                        &mut accounts.token_b_account,
-                        &mut token_a_account,
-                        &mut accounts.admin_fee_a_account,
-                        &mut Account::default(),
-                        &mut clock_account(ZERO_TS),
-                    ],
+                Err(SwapError::InvalidInput.into()),
+                accounts.execute_buyback(
+                    &mut buyback_vault_a_account,
+                    &mut buyback_vault_b_account,
+                    0
                 )
             );
         }
 
-        // wrong mint
-        {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool,
-            );
-            let (pool_mint_key, pool_mint_account) = create_mint(
-                &TOKEN_PROGRAM_ID,
-                &accounts.authority_key,
-                DEFAULT_TOKEN_DECIMALS,
-                None,
-            );
-            let old_pool_key = accounts.pool_mint_key;
-            let old_pool_account = accounts.pool_mint_account;
-            accounts.pool_mint_key = pool_mint_key;
-            accounts.pool_mint_account = pool_mint_account;
+        accounts
+            .initialize_buyback(
+                &buyback_vault_a_key,
+                &mut buyback_vault_a_account.clone(),
+                &buyback_vault_b_key,
+                &mut buyback_vault_b_account.clone(),
+                &accounts.token_b_mint_key.clone(),
+            )
+            .unwrap();
 
+        // buyback fees paid into another account than the buyback vault
+        {
             assert_eq!(
-                Err(SwapError::IncorrectMint.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
+                Err(SwapError::InvalidAdmin.into()),
+                accounts.swap(
+                    &swapper_key,
                     &token_a_key,
                     &mut token_a_account,
-                    withdraw_amount,
-                    minimum_amount,
+                    &swap_token_a_key,
+                    &swap_token_b_key,
+                    &token_b_key,
+                    &mut token_b_account,
+                    50_000,
+                    0,
                 )
             );
-
-            accounts.pool_mint_key = old_pool_key;
-            accounts.pool_mint_account = old_pool_account;
         }
 
-        // wrong destination account
+        // nothing to buy back
         {
-            let (
-                _token_a_key,
-                _token_a_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.execute_buyback(
+                    &mut buyback_vault_a_account,
+                    &mut buyback_vault_b_account,
+                    0
+                )
             );
+        }
 
-            assert_eq!(
-                Err(TokenError::MintMismatch.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount,
-                    minimum_amount,
+        accounts.fee_accounts_a.buyback_key = buyback_vault_a_key;
+        accounts.fee_accounts_a.buyback_account = buyback_vault_a_account;
+        accounts.fee_accounts_b.buyback_key = buyback_vault_b_key;
+        accounts.fee_accounts_b.buyback_account = buyback_vault_b_account;
+
+        // the buyback fees are charged in token B swapping from token A, and in token A
+        // swapping from token B
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                50_000,
+                0,
+            )
+            .unwrap();
+        accounts
+            .swap(
+                &swapper_key,
+                &token_b_key,
+                &mut token_b_account,
+                &swap_token_b_key,
+                &swap_token_a_key,
+                &token_a_key,
+                &mut token_a_account,
+                50_000,
+                0,
+            )
+            .unwrap();
+        let mut buyback_vault_a_account = accounts.fee_accounts_a.buyback_account.clone();
+        let mut buyback_vault_b_account = accounts.fee_accounts_b.buyback_account.clone();
+        let buyback_fees_a = amount(&buyback_vault_a_account);
+        let buyback_fees_b = amount(&buyback_vault_b_account);
+        assert!(buyback_fees_a > 0);
+        assert!(buyback_fees_b > 0);
+
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        let invariant = StableSwap::new(
+            swap_info.initial_amp_factor,
+            swap_info.target_amp_factor,
+            ZERO_TS,
+            swap_info.start_ramp_ts,
+            swap_info.stop_ramp_ts,
+        );
+        let swap_token_a_amount = amount(&accounts.token_a_account);
+        let swap_token_b_amount = amount(&accounts.token_b_account);
+        let amount_bought_back = U256::to_u64(
+            invariant
+                .swap_without_fees(
+                    buyback_fees_a.into(),
+                    swap_info
+                        .pool_amount(&swap_token_a_key, swap_token_a_amount)
+                        .unwrap()
+                        .into(),
+                    swap_info
+                        .pool_amount(&swap_token_b_key, swap_token_b_amount)
+                        .unwrap()
+                        .into(),
+                )
+                .unwrap(),
+        )
+        .unwrap();
+        assert!(amount_bought_back > 0);
+
+        // slippage
+        {
+            assert_eq!(
+                Err(SwapError::ExceededSlippage.into()),
+                accounts.execute_buyback(
+                    &mut buyback_vault_a_account,
+                    &mut buyback_vault_b_account,
+                    amount_bought_back + 1
                 )
             );
         }
 
-        // wrong admin account
+        let supply_b = Mint::unpack(&accounts.token_b_mint_account.data)
+            .unwrap()
+            .supply;
+        accounts
+            .execute_buyback(
+                &mut buyback_vault_a_account,
+                &mut buyback_vault_b_account,
+                amount_bought_back,
+            )
+            .unwrap();
+        let burned = buyback_fees_b + amount_bought_back;
+        assert_eq!(amount(&buyback_vault_a_account), 0);
+        assert_eq!(amount(&buyback_vault_b_account), 0);
+        assert_eq!(
+            amount(&accounts.token_a_account),
+            swap_token_a_amount + buyback_fees_a
+        );
+        assert_eq!(
+            amount(&accounts.token_b_account),
+            swap_token_b_amount - amount_bought_back
+        );
+        assert_eq!(
+            Mint::unpack(&accounts.token_b_mint_account.data)
+                .unwrap()
+                .supply,
+            supply_b - burned
+        );
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.buyback_burned, burned);
+
+        // the buyback vaults were emptied
         {
-            let (
-                wrong_admin_key,
-                wrong_admin_account,
-                token_b_key,
-                mut token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                withdraw_amount,
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.execute_buyback(
+                    &mut buyback_vault_a_account,
+                    &mut buyback_vault_b_account,
+                    0
+                )
             );
+        }
 
-            let old_admin_a_key = accounts.admin_fee_a_key;
-            let old_admin_a_account = accounts.admin_fee_a_account;
-            accounts.admin_fee_a_key = wrong_admin_key;
-            accounts.admin_fee_a_account = wrong_admin_account;
-
+        // wrong buyback vault
+        {
+            let mut swap_token_b_account = accounts.token_b_account.clone();
             assert_eq!(
-                Err(SwapError::InvalidAdmin.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_b_key,
-                    &mut token_b_account,
-                    withdraw_amount,
-                    minimum_amount,
+                Err(SwapError::IncorrectSwapAccount.into()),
+                do_process_instruction(
+                    execute_buyback(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &accounts.swap_key,
+                        &accounts.authority_key,
+                        &buyback_vault_a_key,
+                        &swap_token_b_key,
+                        &swap_token_a_key,
+                        &swap_token_b_key,
+                        &accounts.token_b_mint_key,
+                        0,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut accounts.swap_account,
+                        &mut Account::default(),
+                        &mut buyback_vault_a_account,
+                        &mut swap_token_b_account.clone(),
+                        &mut accounts.token_a_account,
+                        &mut swap_token_b_account,
+                        &mut accounts.token_b_mint_account,
+                        &mut Account::default(),
+                        &mut clock_account(ZERO_TS),
+                    ],
                 )
             );
-
-            accounts.admin_fee_a_key = old_admin_a_key;
-            accounts.admin_fee_a_account = old_admin_a_account;
         }
+    }
+    #[test]
+    fn test_withdraw_wallet_fees() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let marketing_wallet = pubkey_rand();
+        let developer_wallet = pubkey_rand();
 
-        // slippage exceeeded
+        // wallets the swap itself would own the accounts of
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool,
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, MIN_AMP, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+            accounts.marketing_wallet = accounts.authority_key;
+            assert_eq!(
+                Err(SwapError::InvalidOutputOwner.into()),
+                accounts.initialize_swap()
             );
-
-            let high_minimum_amount = 100000;
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, MIN_AMP, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+            accounts.developer_wallet = accounts.token_b_key;
             assert_eq!(
-                Err(SwapError::ExceededSlippage.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount,
-                    high_minimum_amount,
-                )
+                Err(SwapError::InvalidOutputOwner.into()),
+                accounts.initialize_swap()
             );
         }
 
-        // correct withdraw
+        // no wallet to withdraw to
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool,
+            let mut accounts =
+                SwapAccountInfo::new(&user_key, MIN_AMP, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+            accounts.initialize_swap().unwrap();
+            let mut destination_a_account = accounts.fee_accounts_a.marketing_account.clone();
+            let mut destination_b_account = accounts.fee_accounts_b.marketing_account.clone();
+            assert_eq!(
+                Err(SwapError::InvalidInput.into()),
+                accounts.withdraw_wallet_fees(
+                    FeeBucket::Marketing,
+                    &marketing_wallet,
+                    &accounts.fee_accounts_a.marketing_key.clone(),
+                    &mut destination_a_account,
+                    &accounts.fee_accounts_b.marketing_key.clone(),
+                    &mut destination_b_account,
+                )
             );
+        }
 
-            let old_swap_token_a =
-                utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
-            let old_swap_token_b =
-                utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
-            let old_pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, MIN_AMP, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.marketing_wallet = marketing_wallet;
+        accounts.developer_wallet = developer_wallet;
+        accounts.initialize_swap().unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.marketing_wallet, marketing_wallet);
+        assert_eq!(swap_info.developer_wallet, developer_wallet);
 
-            let invariant = StableSwap::new(
-                accounts.initial_amp_factor,
-                accounts.target_amp_factor,
-                ZERO_TS,
-                ZERO_TS,
-                ZERO_TS,
-            );
-            let (withdraw_one_amount_before_fees, withdraw_one_trade_fee) = invariant
-                .compute_withdraw_one(
-                    withdraw_amount.into(),
-                    old_pool_mint.supply.into(),
-                    old_swap_token_a.amount.into(),
-                    old_swap_token_b.amount.into(),
-                    &DEFAULT_TEST_FEES,
+        // the developer fees can't be routed to vesting accounts instead
+        {
+            assert_eq!(
+                Err(SwapError::AlreadyInUse.into()),
+                accounts.initialize_dev_fee_vesting(
+                    &accounts.fee_accounts_a.developer_key.clone(),
+                    &mut accounts.fee_accounts_a.developer_account.clone(),
+                    &accounts.fee_accounts_b.developer_key.clone(),
+                    &mut accounts.fee_accounts_b.developer_account.clone(),
+                    &developer_wallet,
+                    1_000,
                 )
-                .unwrap();
-            let withdraw_one_withdraw_fee = DEFAULT_TEST_FEES
-                .withdraw_fee(withdraw_one_amount_before_fees)
-                .unwrap();
-            let expected_withdraw_one_amount =
-                withdraw_one_amount_before_fees - withdraw_one_withdraw_fee;
-            let expected_admin_fee = U256::to_u64(
-                DEFAULT_TEST_FEES
-                    .admin_trade_fee(withdraw_one_trade_fee)
-                    .unwrap()
-                    + DEFAULT_TEST_FEES
-                        .admin_withdraw_fee(withdraw_one_withdraw_fee)
-                        .unwrap(),
+            );
+        }
+
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        let swap_token_b_amount = amount(&accounts.token_b_account);
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                50_000,
+                0,
             )
             .unwrap();
 
-            accounts
-                .withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount,
-                    minimum_amount,
-                )
-                .unwrap();
+        // the fees accrue in the swap token account instead of the fee accounts
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        let marketing_fees = swap_info.marketing_fees_accrued_b;
+        let developer_fees = swap_info.developer_fees_accrued_b;
+        assert!(marketing_fees > 0);
+        assert_eq!(developer_fees, marketing_fees);
+        assert_eq!(swap_info.marketing_fees_accrued_a, 0);
+        assert_eq!(swap_info.developer_fees_accrued_a, 0);
+        assert_eq!(amount(&accounts.fee_accounts_b.marketing_account), 0);
+        assert_eq!(amount(&accounts.fee_accounts_b.developer_account), 0);
+        assert!(amount(&accounts.fee_accounts_b.reflection_account) > 0);
+        let swap_token_b_amount = swap_token_b_amount
+            - amount(&token_b_account)
+            - amount(&accounts.fee_accounts_b.reflection_account)
+            - amount(&accounts.fee_accounts_b.buyback_account);
+        assert_eq!(amount(&accounts.token_b_account), swap_token_b_amount);
+        assert_eq!(
+            swap_info.pool_amount(&swap_token_b_key, swap_token_b_amount),
+            Some(
+                swap_token_b_amount
+                    - swap_info.admin_fees_accrued_b
+                    - marketing_fees
+                    - developer_fees
+            )
+        );
 
-            let swap_token_a = utils::unpack_token_account(&accounts.token_a_account.data).unwrap();
+        let (marketing_a_key, mut marketing_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &marketing_wallet,
+            0,
+        );
+        let (marketing_b_key, mut marketing_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &marketing_wallet,
+            0,
+        );
+
+        // signed by another wallet
+        {
             assert_eq!(
-                old_swap_token_a.amount - swap_token_a.amount - expected_admin_fee,
-                U256::to_u64(expected_withdraw_one_amount).unwrap()
+                Err(SwapError::Unauthorized.into()),
+                accounts.withdraw_wallet_fees(
+                    FeeBucket::Marketing,
+                    &developer_wallet,
+                    &marketing_a_key,
+                    &mut marketing_a_account,
+                    &marketing_b_key,
+                    &mut marketing_b_account,
+                )
             );
-            let admin_fee_key_a =
-                utils::unpack_token_account(&accounts.admin_fee_a_account.data).unwrap();
-            assert_eq!(admin_fee_key_a.amount, expected_admin_fee);
-            let swap_token_b = utils::unpack_token_account(&accounts.token_b_account.data).unwrap();
-            assert_eq!(swap_token_b.amount, old_swap_token_b.amount);
-            let pool_mint = Processor::unpack_mint(&accounts.pool_mint_account.data).unwrap();
-            assert_eq!(pool_mint.supply, old_pool_mint.supply - withdraw_amount);
         }
 
-        // pool is paused
+        // destination of another owner
         {
-            let (
-                token_a_key,
-                mut token_a_account,
-                _token_b_key,
-                _token_b_account,
-                pool_key,
-                mut pool_account,
-            ) = accounts.setup_token_accounts(
-                &user_key,
-                &withdrawer_key,
-                initial_a,
-                initial_b,
-                initial_pool,
+            assert_eq!(
+                Err(SwapError::InvalidOwner.into()),
+                accounts.withdraw_wallet_fees(
+                    FeeBucket::Marketing,
+                    &marketing_wallet,
+                    &token_a_key,
+                    &mut token_a_account.clone(),
+                    &marketing_b_key,
+                    &mut marketing_b_account,
+                )
             );
-            // pause pool
-            accounts.pause().unwrap();
+        }
 
+        // destinations swapped
+        {
             assert_eq!(
-                Err(SwapError::IsPaused.into()),
-                accounts.withdraw_one(
-                    &withdrawer_key,
-                    &pool_key,
-                    &mut pool_account,
-                    &token_a_key,
-                    &mut token_a_account,
-                    withdraw_amount,
-                    minimum_amount,
+                Err(SwapError::IncorrectMint.into()),
+                accounts.withdraw_wallet_fees(
+                    FeeBucket::Marketing,
+                    &marketing_wallet,
+                    &marketing_b_key,
+                    &mut marketing_b_account.clone(),
+                    &marketing_a_key,
+                    &mut marketing_a_account.clone(),
                 )
             );
         }
+
+        accounts
+            .withdraw_wallet_fees(
+                FeeBucket::Marketing,
+                &marketing_wallet,
+                &marketing_a_key,
+                &mut marketing_a_account,
+                &marketing_b_key,
+                &mut marketing_b_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&marketing_a_account), 0);
+        assert_eq!(amount(&marketing_b_account), marketing_fees);
+        assert_eq!(
+            amount(&accounts.token_b_account),
+            swap_token_b_amount - marketing_fees
+        );
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.marketing_fees_accrued_b, 0);
+        assert_eq!(swap_info.developer_fees_accrued_b, developer_fees);
+
+        let (developer_a_key, mut developer_a_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_a_mint_key,
+            &mut accounts.token_a_mint_account,
+            &user_key,
+            &developer_wallet,
+            0,
+        );
+        let (developer_b_key, mut developer_b_account) = mint_token(
+            &TOKEN_PROGRAM_ID,
+            &accounts.token_b_mint_key,
+            &mut accounts.token_b_mint_account,
+            &user_key,
+            &developer_wallet,
+            0,
+        );
+        accounts
+            .withdraw_wallet_fees(
+                FeeBucket::Developer,
+                &developer_wallet,
+                &developer_a_key,
+                &mut developer_a_account,
+                &developer_b_key,
+                &mut developer_b_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&developer_b_account), developer_fees);
+        assert_eq!(
+            amount(&accounts.token_b_account),
+            swap_token_b_amount - marketing_fees - developer_fees
+        );
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.developer_fees_accrued_b, 0);
+
+        // nothing left to withdraw
+        accounts
+            .withdraw_wallet_fees(
+                FeeBucket::Developer,
+                &developer_wallet,
+                &developer_a_key,
+                &mut developer_a_account,
+                &developer_b_key,
+                &mut developer_b_account,
+            )
+            .unwrap();
+        assert_eq!(amount(&developer_b_account), developer_fees);
+    }
+
+    #[test]
+    fn test_report_epoch_fees() {
+        let user_key = pubkey_rand();
+        let swapper_key = pubkey_rand();
+        let mut accounts =
+            SwapAccountInfo::new(&user_key, 100, 1_000_000, 1_000_000, DEFAULT_TEST_FEES);
+        accounts.initialize_swap().unwrap();
+        let swap_token_a_key = accounts.token_a_key;
+        let swap_token_b_key = accounts.token_b_key;
+        let amount = |account: &Account| utils::unpack_token_account(&account.data).unwrap().amount;
+
+        // swap fees are counted per bucket in the token they are charged in
+        let (token_a_key, mut token_a_account, token_b_key, mut token_b_account, _, _) =
+            accounts.setup_token_accounts(&user_key, &swapper_key, 100_000, 0, 0);
+        let result = {
+            let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+            StableSwap::new(
+                swap_info.initial_amp_factor,
+                swap_info.target_amp_factor,
+                ZERO_TS,
+                swap_info.start_ramp_ts,
+                swap_info.stop_ramp_ts,
+            )
+            .swap_to_v2(
+                U256::from(100_000),
+                U256::from(amount(&accounts.token_a_account)),
+                U256::from(amount(&accounts.token_b_account)),
+                &swap_info.fees,
+            )
+            .unwrap()
+        };
+        accounts
+            .swap(
+                &swapper_key,
+                &token_a_key,
+                &mut token_a_account,
+                &swap_token_a_key,
+                &swap_token_b_key,
+                &token_b_key,
+                &mut token_b_account,
+                100_000,
+                0,
+            )
+            .unwrap();
+        let swap_info = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(swap_info.fee_report_epoch, 0);
+        assert_ne!(swap_info.epoch_lp_fees_b, 0);
+        assert_ne!(swap_info.epoch_admin_fees_b, 0);
+        assert_eq!(U256::from(swap_info.epoch_admin_fees_b), result.admin_fee);
+        assert_eq!(
+            U256::from(
+                swap_info.epoch_lp_fees_b
+                    + swap_info.epoch_admin_fees_b
+                    + swap_info.epoch_reflection_fees_b
+                    + swap_info.epoch_buyback_fees_b
+                    + swap_info.epoch_marketing_fees_b
+                    + swap_info.epoch_developer_fees_b
+            ),
+            result.trade_fee
+        );
+        assert_eq!(swap_info.epoch_lp_fees_a, 0);
+        assert_eq!(swap_info.epoch_admin_fees_a, 0);
+
+        // nothing to report within the epoch
+        accounts.report_epoch_fees(0).unwrap();
+        assert_eq!(
+            SwapInfo::unpack(&accounts.swap_account.data).unwrap(),
+            swap_info
+        );
+
+        // the counters are reset at the epoch boundary
+        accounts.report_epoch_fees(1).unwrap();
+        let reported = SwapInfo::unpack(&accounts.swap_account.data).unwrap();
+        assert_eq!(
+            SwapInfo {
+                fee_report_epoch: 1,
+                epoch_lp_fees_b: 0,
+                epoch_admin_fees_b: 0,
+                epoch_reflection_fees_b: 0,
+                epoch_buyback_fees_b: 0,
+                epoch_marketing_fees_b: 0,
+                epoch_developer_fees_b: 0,
+                sequence: swap_info.sequence + 1,
+                ..swap_info
+            },
+            reported
+        );
     }
 }
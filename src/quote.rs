@@ -0,0 +1,90 @@
+//! Swap quote logged by `SimulateSwap` for preflight checks
+
+use solana_program::{hash::Hash, pubkey::Pubkey};
+use std::fmt;
+
+/// Prefix of the swap quote log line
+pub const SWAP_QUOTE_PREFIX: &str = "swap_quote";
+
+/// Outcome of a simulated swap. Displays as a single line of space separated
+/// `key=value` pairs, led by `SWAP_QUOTE_PREFIX`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SwapQuote {
+    /// Token-swap
+    pub swap: Pubkey,
+    /// Swap token account swapped into
+    pub swap_source: Pubkey,
+    /// Swap token account swapped from
+    pub swap_destination: Pubkey,
+    /// Source token paid in
+    pub amount_in: u64,
+    /// Destination token received
+    pub amount_out: u64,
+    /// Total trade fee, of which the other fees are a share
+    pub trade_fee: u64,
+    /// Admin share of the trade fee
+    pub admin_fee: u64,
+    /// Pool amount of the source token after the swap
+    pub new_source_amount: u64,
+    /// Pool amount of the destination token after the swap
+    pub new_destination_amount: u64,
+    /// Configuration hash of the pool the quote was computed with
+    pub config_hash: Hash,
+}
+
+impl fmt::Display for SwapQuote {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} swap={} swap_source={} swap_destination={} amount_in={} amount_out={} \
+             trade_fee={} admin_fee={} new_source_amount={} new_destination_amount={} \
+             config_hash={}",
+            SWAP_QUOTE_PREFIX,
+            self.swap,
+            self.swap_source,
+            self.swap_destination,
+            self.amount_in,
+            self.amount_out,
+            self.trade_fee,
+            self.admin_fee,
+            self.new_source_amount,
+            self.new_destination_amount,
+            self.config_hash,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_quote_display() {
+        let swap = Pubkey::new_from_array([1u8; 32]);
+        let swap_source = Pubkey::new_from_array([2u8; 32]);
+        let swap_destination = Pubkey::new_from_array([3u8; 32]);
+        let config_hash = Hash::new_from_array([4u8; 32]);
+        let quote = SwapQuote {
+            swap,
+            swap_source,
+            swap_destination,
+            amount_in: 1_000,
+            amount_out: 990,
+            trade_fee: 6,
+            admin_fee: 3,
+            new_source_amount: 11_000,
+            new_destination_amount: 9_007,
+            config_hash,
+        };
+
+        assert_eq!(
+            quote.to_string(),
+            format!(
+                "swap_quote swap={} swap_source={} swap_destination={} amount_in=1000 \
+                 amount_out=990 trade_fee=6 admin_fee=3 new_source_amount=11000 \
+                 new_destination_amount=9007 config_hash={}",
+                swap, swap_source, swap_destination, config_hash
+            )
+        );
+    }
+}
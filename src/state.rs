@@ -1,12 +1,23 @@
 //! State transition types
 
-use crate::fees::Fees;
+use crate::bn::{U256Conversions, U256};
+use crate::curve::{
+    ConstantProduct, CurveType, PoolCurve, PriceBounded, StableSwap, SwapCurve, SwapResultV2,
+};
+use crate::fee_report::EpochFeeReport;
+use crate::fees::{FeeTier, Fees};
+use crate::layout;
+use crate::oracle::{OracleGuard, OracleStalePolicy};
+use crate::twap::{self, PRICE_SCALE};
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
 use solana_program::{
+    clock::{Epoch, Slot},
+    hash::{hashv, Hash},
     program_error::ProgramError,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
+use std::convert::TryFrom;
 
 /// Program states.
 #[repr(C)]
@@ -60,6 +71,1089 @@ pub struct SwapInfo {
     pub admin_fee_key_b: Pubkey,
     /// Fees
     pub fees: Fees,
+
+    /// Mint of the token required to swap during the launch access window
+    pub access_mint: Pubkey,
+    /// Swaps are restricted to access token holders until this slot
+    pub access_window_end_slot: u64,
+
+    /// Creator-chosen namespace distinguishing pools of the same mint pair
+    pub namespace: [u8; 32],
+    /// Fee tier the pool was created with
+    pub fee_tier: FeeTier,
+
+    /// Admin trade fees accrued in token A, awaiting harvest
+    pub admin_fees_accrued_a: u64,
+    /// Admin trade fees accrued in token B, awaiting harvest
+    pub admin_fees_accrued_b: u64,
+    /// Share of harvested admin fees paid to the harvest caller, in basis points
+    pub harvest_tip_bps: u16,
+
+    /// Key allowed to commit reflection distributions, unset disables them
+    pub reflection_distributor: Pubkey,
+    /// Epoch of the next reflection distribution
+    pub reflection_epoch: u64,
+
+    /// Vesting token A account receiving the developer fees, unset disables vesting
+    pub dev_vesting_a: Pubkey,
+    /// Vesting token B account receiving the developer fees
+    pub dev_vesting_b: Pubkey,
+    /// Owner allowed to claim the vested developer fees
+    pub dev_wallet: Pubkey,
+    /// Unix timestamp the developer fee vesting starts at
+    pub dev_vesting_start_ts: i64,
+    /// Unix timestamp the developer fees are fully vested at
+    pub dev_vesting_end_ts: i64,
+    /// Developer fees received by the token A vesting account
+    pub dev_fees_received_a: u64,
+    /// Developer fees received by the token B vesting account
+    pub dev_fees_received_b: u64,
+    /// Vested developer fees claimed from the token A vesting account
+    pub dev_fees_claimed_a: u64,
+    /// Vested developer fees claimed from the token B vesting account
+    pub dev_fees_claimed_b: u64,
+
+    /// SPL Governance realm LP holders vote in, unset disables governance deposits
+    pub governance_realm: Pubkey,
+    /// Pool token Account holding the governance deposits
+    pub governance_vault: Pubkey,
+    /// Seconds governance deposits stay locked after each deposit
+    pub governance_lockup: i64,
+
+    /// Key that may assume admin rights once the admin heartbeat times out, unset disables recovery
+    pub admin_recovery_key: Pubkey,
+    /// Slot of the last admin heartbeat
+    pub admin_heartbeat_slot: u64,
+    /// Slots without a heartbeat after which the recovery key may claim admin rights
+    pub admin_timeout_slots: u64,
+
+    /// Token A owed to withdraw continuations, excluded from the pool
+    pub pending_withdraw_a: u64,
+    /// Token B owed to withdraw continuations, excluded from the pool
+    pub pending_withdraw_b: u64,
+
+    /// Maximum input amount of a single swap, zero disables the cap
+    pub max_swap_amount_in: u64,
+
+    /// Pool token accounts are kept frozen by the swap authority, making pool tokens
+    /// non-transferable
+    pub lp_soulbound: bool,
+
+    /// Pool token Account holding the vote-escrow locks, unset disables vote-escrow
+    pub ve_lp_vault: Pubkey,
+    /// Token A Account holding the fees of veLP lockers
+    pub ve_fee_vault_a: Pubkey,
+    /// Token B Account holding the fees of veLP lockers
+    pub ve_fee_vault_b: Pubkey,
+    /// Share of harvested admin trade fees paid to veLP lockers, in basis points
+    pub ve_fee_bps: u16,
+    /// Total veLP balance of the vote-escrow locks
+    pub ve_supply: u64,
+    /// Vote-escrow epoch the fees were last distributed in
+    pub ve_epoch: i64,
+    /// Token A fees distributed per veLP, scaled by `VE_FEES_PER_VE_SCALE`
+    pub ve_fees_per_ve_a: u128,
+    /// Token B fees distributed per veLP, scaled by `VE_FEES_PER_VE_SCALE`
+    pub ve_fees_per_ve_b: u128,
+    /// Token A fees received in the current epoch, distributed once it ends
+    pub ve_pending_fees_a: u64,
+    /// Token B fees received in the current epoch, distributed once it ends
+    pub ve_pending_fees_b: u64,
+
+    /// Slots of each order-flow auction batch, zero disables the auction
+    pub order_flow_batch_slots: u64,
+    /// Swap token account the order-flow auction bids are paid into
+    pub order_flow_bid_token: Pubkey,
+    /// Order-flow auction batch of the best bid
+    pub order_flow_batch: u64,
+    /// Owner of the best bid, allowed to backrun its batch
+    pub order_flow_bidder: Pubkey,
+    /// Token account the best bid was paid from, refunded when outbid
+    pub order_flow_refund_account: Pubkey,
+    /// Best bid of the batch, excluded from the pool until the batch ends
+    pub order_flow_bid: u64,
+
+    /// Amplification coefficient of the dynamic amp at or above the volatility
+    /// ceiling, zero disables the dynamic amp
+    pub dynamic_amp_min: u64,
+    /// Amplification coefficient of the dynamic amp at zero volatility
+    pub dynamic_amp_max: u64,
+    /// Volatility, scaled by `PRICE_SCALE`, at which the dynamic amp reaches its minimum
+    pub dynamic_amp_volatility_ceiling: u64,
+    /// Maximum change of the amplification coefficient per slot
+    pub dynamic_amp_max_drift: u64,
+    /// Rolling TWAP of the realized swap price of token A in token B, scaled by
+    /// `PRICE_SCALE`, zero until the first swap
+    pub twap_price: u128,
+    /// Rolling average of the relative deviation of the realized swap prices from the
+    /// TWAP, scaled by `PRICE_SCALE`
+    pub twap_volatility: u64,
+    /// Slot of the last swap recorded in the TWAP
+    pub twap_slot: u64,
+
+    /// Share of the admin trade fees set aside in the fee buffer, in basis points
+    pub fee_buffer_bps: u16,
+    /// Maximum deposit bonus, in basis points of the deposited amounts, zero
+    /// disables the bonus
+    pub deposit_bonus_bps: u16,
+    /// Token A held by the swap token A account for deposit bonuses
+    pub fee_buffer_a: u64,
+    /// Token B held by the swap token B account for deposit bonuses
+    pub fee_buffer_b: u64,
+
+    /// Share of the LP trade fees set aside in the insurance fund, in basis points
+    pub insurance_fee_bps: u16,
+    /// Governance account allowed to cover shortfalls from the insurance fund
+    pub insurance_authority: Pubkey,
+    /// Token A held by the swap token A account for the insurance fund
+    pub insurance_fund_a: u64,
+    /// Token B held by the swap token B account for the insurance fund
+    pub insurance_fund_b: u64,
+    /// Token A returned from the insurance fund to the pool so far
+    pub insurance_covered_a: u64,
+    /// Token B returned from the insurance fund to the pool so far
+    pub insurance_covered_b: u64,
+
+    /// Minimum amount of pool tokens a deposit has to mint
+    pub min_lp_mint: u64,
+
+    /// Largest balance `SweepDust` swaps free of fees, zero when disabled
+    pub dust_threshold: u64,
+    /// Slot of the last dust sweep
+    pub dust_sweep_slot: Slot,
+    /// Dust sweeps in `dust_sweep_slot`
+    pub dust_sweep_count: u16,
+
+    /// Pool token vault of liquidity positions, an account owned by the swap
+    /// authority. Default when liquidity positions are disabled.
+    pub liquidity_vault: Pubkey,
+    /// Pool tokens held in liquidity positions
+    pub position_liquidity: u64,
+    /// Pool tokens held in liquidity positions, accumulated over seconds
+    pub cumulative_position_liquidity: u128,
+    /// Unix timestamp `cumulative_position_liquidity` was last accumulated at
+    pub position_liquidity_ts: i64,
+
+    /// Epoch the trade fees below are counted for
+    pub fee_report_epoch: Epoch,
+    /// Token A trade fees left to the LPs of `fee_report_epoch`
+    pub epoch_lp_fees_a: u64,
+    /// Token B trade fees left to the LPs of `fee_report_epoch`
+    pub epoch_lp_fees_b: u64,
+    /// Token A admin trade fees of `fee_report_epoch`
+    pub epoch_admin_fees_a: u64,
+    /// Token B admin trade fees of `fee_report_epoch`
+    pub epoch_admin_fees_b: u64,
+    /// Token A reflection fees of `fee_report_epoch`
+    pub epoch_reflection_fees_a: u64,
+    /// Token B reflection fees of `fee_report_epoch`
+    pub epoch_reflection_fees_b: u64,
+    /// Token A buyback fees of `fee_report_epoch`
+    pub epoch_buyback_fees_a: u64,
+    /// Token B buyback fees of `fee_report_epoch`
+    pub epoch_buyback_fees_b: u64,
+    /// Token A marketing fees of `fee_report_epoch`
+    pub epoch_marketing_fees_a: u64,
+    /// Token B marketing fees of `fee_report_epoch`
+    pub epoch_marketing_fees_b: u64,
+    /// Token A developer fees of `fee_report_epoch`
+    pub epoch_developer_fees_a: u64,
+    /// Token B developer fees of `fee_report_epoch`
+    pub epoch_developer_fees_b: u64,
+    /// Incremented on every change of the pool state, so that swaps and deposits
+    /// can require the pool to be unchanged since they were quoted
+    pub sequence: u64,
+
+    /// Custodian co-signing withdrawals above `custodian_withdraw_threshold`.
+    /// Default when the pool is not custodial.
+    pub custodian: Pubkey,
+    /// Largest amount of pool tokens a withdrawal may burn without the custodian
+    pub custodian_withdraw_threshold: u64,
+
+    /// Mint whose tokens, or program whose accounts, prove the KYC credential
+    /// required to swap and deposit. Default when the pool is not gated.
+    pub credential_issuer: Pubkey,
+
+    /// Last admin actions applied to the pool
+    pub admin_log: AdminLog,
+
+    /// Admin fees of token A are escrowed in the swap token account with the accrued
+    /// admin fees, as `admin_fee_key_a` was found unable to receive them
+    pub admin_fee_escrow_a: bool,
+    /// Admin fees of token B are escrowed in the swap token account with the accrued
+    /// admin fees, as `admin_fee_key_b` was found unable to receive them
+    pub admin_fee_escrow_b: bool,
+
+    /// Sum over the slots since initialization of the spot price of token A in token
+    /// B, scaled by `PRICE_SCALE`. Wraps around on overflow, only the difference of
+    /// two observations is meaningful.
+    pub price_a_cumulative: u128,
+    /// Sum over the slots since initialization of the spot price of token B in token
+    /// A, scaled by `PRICE_SCALE`, wrapping around like `price_a_cumulative`
+    pub price_b_cumulative: u128,
+    /// Slot the cumulative prices were last accumulated at
+    pub price_cumulative_slot: Slot,
+
+    /// Guardian allowed to pause and unpause the pool besides the admin. Default
+    /// when the pool has no guardian.
+    pub guardian_key: Pubkey,
+
+    /// Pool tokens of the protocol-owned liquidity deployed by the admin, part of
+    /// the pool mint supply. User liquidity is the rest of the supply.
+    pub protocol_pool_amount: u64,
+
+    /// Fees staged by SetNewFees, SetParams or RestoreState, applicable from
+    /// `pending_fees_slot`
+    pub pending_fees: Fees,
+    /// First slot the pending fees may be applied in, 0 without pending fees
+    pub pending_fees_slot: Slot,
+    /// Custom messages logged along with errors of the pool
+    pub custom_error_messages: CustomErrorMessages,
+
+    /// Pool token Account holding the reflection positions, unset disables reflection
+    /// positions
+    pub reflection_lp_vault: Pubkey,
+    /// Token A Account the reflection fees are paid into once reflection positions
+    /// are enabled
+    pub reflection_vault_a: Pubkey,
+    /// Token B Account the reflection fees are paid into once reflection positions
+    /// are enabled
+    pub reflection_vault_b: Pubkey,
+    /// Pool tokens held in the reflection positions
+    pub reflection_supply: u64,
+    /// Token A reflection fees accrued per pool token of the reflection positions,
+    /// scaled by `REFLECTION_PER_LP_SHARE_SCALE`
+    pub acc_reflection_per_lp_share_a: u128,
+    /// Token B reflection fees accrued per pool token of the reflection positions,
+    /// scaled by `REFLECTION_PER_LP_SHARE_SCALE`
+    pub acc_reflection_per_lp_share_b: u128,
+    /// Token A reflection fees not accrued yet, received while no reflection position
+    /// held pool tokens or left over by rounding
+    pub reflection_pending_a: u64,
+    /// Token B reflection fees not accrued yet, received while no reflection position
+    /// held pool tokens or left over by rounding
+    pub reflection_pending_b: u64,
+    /// Token A or B mint bought back with the buyback fees and burned, unset disables
+    /// buybacks
+    pub buyback_mint: Pubkey,
+    /// Token A Account the buyback fees are paid into once buybacks are enabled
+    pub buyback_vault_a: Pubkey,
+    /// Token B Account the buyback fees are paid into once buybacks are enabled
+    pub buyback_vault_b: Pubkey,
+    /// Tokens of `buyback_mint` burned by buybacks so far
+    pub buyback_burned: u64,
+    /// Owner of the token accounts the marketing fees are withdrawn to, unset pays
+    /// the marketing fees to the swap's marketing destination instead
+    pub marketing_wallet: Pubkey,
+    /// Owner of the token accounts the developer fees are withdrawn to, unset pays
+    /// the developer fees to the swap's developer destination instead
+    pub developer_wallet: Pubkey,
+    /// Marketing fees accrued in the token A swap account, not withdrawn yet
+    pub marketing_fees_accrued_a: u64,
+    /// Marketing fees accrued in the token B swap account, not withdrawn yet
+    pub marketing_fees_accrued_b: u64,
+    /// Developer fees accrued in the token A swap account, not withdrawn yet
+    pub developer_fees_accrued_a: u64,
+    /// Developer fees accrued in the token B swap account, not withdrawn yet
+    pub developer_fees_accrued_b: u64,
+
+    /// `compute_config_hash` of the pool parameters, refreshed by every admin
+    /// instruction so clients can cache parameters until it changes
+    pub config_hash: Hash,
+
+    /// Volatility, scaled by `PRICE_SCALE`, at which the volatility fee multiplier
+    /// reaches its maximum, zero disables the volatility fee
+    pub volatility_fee_ceiling: u64,
+    /// Multiplier of the swap fees at or above the volatility ceiling, in basis points
+    pub volatility_fee_max_multiplier_bps: u16,
+    /// Host fees accrued in the token A swap account to referral accruals, not
+    /// claimed yet
+    pub referral_fees_accrued_a: u64,
+    /// Host fees accrued in the token B swap account to referral accruals, not
+    /// claimed yet
+    pub referral_fees_accrued_b: u64,
+    /// Cap of the token A admin fees taken per epoch, zero for none. Admin fees past
+    /// the cap are left to the LPs until the epoch ends.
+    pub admin_fee_epoch_cap_a: u64,
+    /// Cap of the token B admin fees taken per epoch, zero for none
+    pub admin_fee_epoch_cap_b: u64,
+    /// Epoch the admin fees are counted towards the caps in
+    pub admin_fee_cap_epoch: Epoch,
+    /// Token A admin fees taken in `admin_fee_cap_epoch`
+    pub admin_fees_taken_a: u64,
+    /// Token B admin fees taken in `admin_fee_cap_epoch`
+    pub admin_fees_taken_b: u64,
+    /// Curve the pool prices its tokens along, chosen at initialization
+    pub curve_type: CurveType,
+    /// Price of either token in the other, scaled by `PRICE_BOUND_SCALE`, bounding
+    /// the band a `PriceBounded` pool provides its liquidity within. Zero for the
+    /// other curves.
+    pub price_bound: u64,
+    /// Payer signing the initialization of the pool, the only depositor until
+    /// `creator_deposit_end_slot`
+    pub creator: Pubkey,
+    /// Slot the deposits open to everyone at, after the creator-only window
+    pub creator_deposit_end_slot: Slot,
+    /// Deposits are restricted to the depositors of the deposit allowlist of the
+    /// swap. Swaps and withdrawals stay open to everyone.
+    pub deposit_allowlist_enabled: bool,
+    /// A token mint has a freeze authority, able to freeze the swap token accounts
+    /// and halt the pool, which the creator acknowledged at initialization
+    pub freeze_authority_acknowledged: bool,
+    /// Pyth price account of token A of the oracle guard, the default pubkey
+    /// disables the guard
+    pub oracle_a: Pubkey,
+    /// Pyth price account of token B of the oracle guard
+    pub oracle_b: Pubkey,
+    /// Maximum age of the oracle prices swaps are checked against, in slots
+    pub oracle_max_staleness_slots: u64,
+    /// Maximum confidence interval of the oracle prices, in basis points of the
+    /// price
+    pub oracle_max_confidence_bps: u16,
+    /// Band past the oracle value of their input swaps may pay out, in basis
+    /// points
+    pub oracle_max_deviation_bps: u16,
+    /// What the oracle guard does with swaps while an oracle price is stale
+    pub oracle_stale_policy: OracleStalePolicy,
+    /// Band added to the oracle deviation band while an oracle price is stale,
+    /// in basis points, for the `WidenBand` stale policy
+    pub oracle_stale_band_bps: u16,
+}
+
+/// Maximum harvest tip, in basis points
+pub const MAX_HARVEST_TIP_BPS: u16 = 500;
+
+/// Maximum number of dust sweeps in a single slot
+pub const MAX_DUST_SWEEPS_PER_SLOT: u16 = 8;
+
+/// Maximum share of harvested admin trade fees paid to veLP lockers, in basis points
+pub const MAX_VE_FEE_BPS: u16 = 10_000 - MAX_HARVEST_TIP_BPS;
+
+/// Duration of a vote-escrow epoch, in seconds
+pub const VE_EPOCH_DURATION: i64 = 604_800;
+
+/// Minimum duration of a vote-escrow lock, in seconds
+pub const VE_MIN_LOCK_DURATION: i64 = VE_EPOCH_DURATION;
+
+/// Maximum duration of a vote-escrow lock, in seconds. Locking for the maximum
+/// duration gives one veLP per pool token.
+pub const VE_MAX_LOCK_DURATION: i64 = 4 * 365 * 86_400;
+
+/// Slots fees staged by SetNewFees, SetParams or RestoreState wait before they
+/// may be applied, about a day
+pub const NEW_FEES_DELAY_SLOTS: Slot = 216_000;
+
+/// Maximum deposit bonus, in basis points of the deposited amounts
+pub const MAX_DEPOSIT_BONUS_BPS: u16 = 100;
+
+/// Maximum multiplier of the swap fees at the volatility ceiling, in basis points
+pub const MAX_VOLATILITY_FEE_MULTIPLIER_BPS: u16 = 50_000;
+
+/// Scale of the fees distributed per veLP
+pub const VE_FEES_PER_VE_SCALE: u128 = 1_000_000_000_000;
+
+/// Scale of the reflection fees accrued per pool token of the reflection positions
+pub const REFLECTION_PER_LP_SHARE_SCALE: u128 = 1_000_000_000_000;
+
+impl SwapInfo {
+    /// Curve of the pool at the unix timestamp `current_ts`, ramping the
+    /// amplification of a StableSwap pool
+    pub fn curve(&self, current_ts: i64) -> PoolCurve {
+        match self.curve_type {
+            CurveType::StableSwap => PoolCurve::StableSwap(StableSwap::new(
+                self.initial_amp_factor,
+                self.target_amp_factor,
+                current_ts,
+                self.start_ramp_ts,
+                self.stop_ramp_ts,
+            )),
+            CurveType::ConstantProduct => PoolCurve::ConstantProduct(ConstantProduct),
+            CurveType::PriceBounded => PoolCurve::PriceBounded(PriceBounded::new(self.price_bound)),
+        }
+    }
+
+    /// Amount held by the swap token account `swap_token` that backs the pool,
+    /// excluding the admin, marketing, developer and referral fees accrued in it,
+    /// the tokens owed to withdraw continuations, the best order-flow bid of the
+    /// open batch, the fee buffer and the insurance fund
+    pub fn pool_amount(&self, swap_token: &Pubkey, amount: u64) -> Option<u64> {
+        let order_flow_bid = if *swap_token == self.order_flow_bid_token {
+            self.order_flow_bid
+        } else {
+            0
+        };
+        if *swap_token == self.token_a {
+            amount
+                .checked_sub(self.admin_fees_accrued_a)?
+                .checked_sub(self.pending_withdraw_a)?
+                .checked_sub(order_flow_bid)?
+                .checked_sub(self.fee_buffer_a)?
+                .checked_sub(self.insurance_fund_a)?
+                .checked_sub(self.marketing_fees_accrued_a)?
+                .checked_sub(self.developer_fees_accrued_a)?
+                .checked_sub(self.referral_fees_accrued_a)
+        } else if *swap_token == self.token_b {
+            amount
+                .checked_sub(self.admin_fees_accrued_b)?
+                .checked_sub(self.pending_withdraw_b)?
+                .checked_sub(order_flow_bid)?
+                .checked_sub(self.fee_buffer_b)?
+                .checked_sub(self.insurance_fund_b)?
+                .checked_sub(self.marketing_fees_accrued_b)?
+                .checked_sub(self.developer_fees_accrued_b)?
+                .checked_sub(self.referral_fees_accrued_b)
+        } else {
+            Some(amount)
+        }
+    }
+
+    /// Part of `amount` harvested admin fees paid to the harvest caller
+    pub fn harvest_tip(&self, amount: u64) -> Option<u64> {
+        let tip = (amount as u128)
+            .checked_mul(self.harvest_tip_bps.into())?
+            .checked_div(10_000)?;
+        u64::try_from(tip).ok()
+    }
+
+    /// Records a change of the pool state
+    pub fn bump_sequence(&mut self) {
+        self.sequence = self.sequence.wrapping_add(1);
+    }
+
+    /// Whether a withdrawal burning `pool_token_amount` needs the custodian to
+    /// co-sign
+    pub fn requires_custodian(&self, pool_token_amount: u64) -> bool {
+        self.custodian != Pubkey::default() && pool_token_amount > self.custodian_withdraw_threshold
+    }
+
+    /// Whether swaps and deposits need a credential of `credential_issuer`
+    pub fn requires_credential(&self) -> bool {
+        self.credential_issuer != Pubkey::default()
+    }
+
+    /// Whether swaps are checked against the oracle prices of the tokens
+    pub fn is_oracle_guarded(&self) -> bool {
+        self.oracle_a != Pubkey::default()
+    }
+
+    /// Oracle guard configuration of the swap
+    pub fn oracle_guard(&self) -> OracleGuard {
+        OracleGuard {
+            max_staleness_slots: self.oracle_max_staleness_slots,
+            max_confidence_bps: self.oracle_max_confidence_bps,
+            max_deviation_bps: self.oracle_max_deviation_bps,
+            stale_policy: self.oracle_stale_policy,
+            stale_band_bps: self.oracle_stale_band_bps,
+        }
+    }
+
+    /// Whether the admin fees of `swap_token` are escrowed in it rather than paid to
+    /// its admin fee account
+    pub fn is_admin_fee_escrowed(&self, swap_token: &Pubkey) -> bool {
+        if *swap_token == self.token_a {
+            self.admin_fee_escrow_a
+        } else {
+            *swap_token == self.token_b && self.admin_fee_escrow_b
+        }
+    }
+
+    /// Escrows `admin_fee` in `swap_token` with the accrued admin fees, until its
+    /// admin fee account can receive them again
+    pub fn escrow_admin_fee(&mut self, swap_token: &Pubkey, admin_fee: u64) -> Option<()> {
+        let admin_fees_accrued = if *swap_token == self.token_a {
+            &mut self.admin_fees_accrued_a
+        } else if *swap_token == self.token_b {
+            &mut self.admin_fees_accrued_b
+        } else {
+            return None;
+        };
+        *admin_fees_accrued = admin_fees_accrued.checked_add(admin_fee)?;
+        Some(())
+    }
+
+    /// Accrues the `marketing_fee` and `developer_fee` charged from `swap_token` in
+    /// it, for the wallets set to withdraw them. The fees of an unset wallet are paid
+    /// out by the swap and not accrued.
+    pub fn accrue_wallet_fees(
+        &mut self,
+        swap_token: &Pubkey,
+        marketing_fee: u64,
+        developer_fee: u64,
+    ) -> Option<()> {
+        let (marketing_fees_accrued, developer_fees_accrued) = if *swap_token == self.token_a {
+            (
+                &mut self.marketing_fees_accrued_a,
+                &mut self.developer_fees_accrued_a,
+            )
+        } else if *swap_token == self.token_b {
+            (
+                &mut self.marketing_fees_accrued_b,
+                &mut self.developer_fees_accrued_b,
+            )
+        } else {
+            return None;
+        };
+        if self.marketing_wallet != Pubkey::default() {
+            *marketing_fees_accrued = marketing_fees_accrued.checked_add(marketing_fee)?;
+        }
+        if self.developer_wallet != Pubkey::default() {
+            *developer_fees_accrued = developer_fees_accrued.checked_add(developer_fee)?;
+        }
+        Some(())
+    }
+
+    /// Whether the pool is at the expected sequence number, if any
+    pub fn is_at_sequence(&self, expected_sequence: Option<u64>) -> bool {
+        expected_sequence.unwrap_or(self.sequence) == self.sequence
+    }
+
+    /// Whether the developer fees are routed to the vesting accounts
+    pub fn is_dev_fee_vesting(&self) -> bool {
+        self.dev_vesting_a != Pubkey::default()
+    }
+
+    /// Part of `received` developer fees vested at `unix_timestamp`. Vesting is
+    /// linear between the start and end timestamps.
+    pub fn vested_dev_fees(&self, received: u64, unix_timestamp: i64) -> Option<u64> {
+        if unix_timestamp >= self.dev_vesting_end_ts {
+            return Some(received);
+        }
+        if unix_timestamp <= self.dev_vesting_start_ts {
+            return Some(0);
+        }
+        let elapsed = unix_timestamp.checked_sub(self.dev_vesting_start_ts)?;
+        let duration = self
+            .dev_vesting_end_ts
+            .checked_sub(self.dev_vesting_start_ts)?;
+        let vested = (received as u128)
+            .checked_mul(elapsed as u128)?
+            .checked_div(duration as u128)?;
+        u64::try_from(vested).ok()
+    }
+
+    /// Whether vote-escrow locks are enabled
+    pub fn is_ve_enabled(&self) -> bool {
+        self.ve_lp_vault != Pubkey::default()
+    }
+
+    /// Share of `amount` harvested admin trade fees paid to veLP lockers
+    pub fn ve_fee(&self, amount: u64) -> Option<u64> {
+        let ve_fee = (amount as u128)
+            .checked_mul(self.ve_fee_bps.into())?
+            .checked_div(10_000)?;
+        u64::try_from(ve_fee).ok()
+    }
+
+    /// Distributes the pending veLP locker fees once the epoch they were received in
+    /// ended. Fees stay pending for the current epoch while no veLP is locked.
+    pub fn checkpoint_ve_fees(&mut self, unix_timestamp: i64) -> Option<()> {
+        let epoch = unix_timestamp.checked_div(VE_EPOCH_DURATION)?;
+        if epoch <= self.ve_epoch {
+            return Some(());
+        }
+        self.ve_epoch = epoch;
+        let ve_supply = self.ve_supply;
+        if ve_supply == 0 {
+            return Some(());
+        }
+        for (fees_per_ve, pending_fees) in [
+            (&mut self.ve_fees_per_ve_a, &mut self.ve_pending_fees_a),
+            (&mut self.ve_fees_per_ve_b, &mut self.ve_pending_fees_b),
+        ] {
+            let delta = (*pending_fees as u128)
+                .checked_mul(VE_FEES_PER_VE_SCALE)?
+                .checked_div(ve_supply as u128)?;
+            // Rounding dust stays pending for the next epoch
+            let distributed = u64::try_from(ve_fees(ve_supply, delta)?).ok()?;
+            *fees_per_ve = fees_per_ve.checked_add(delta)?;
+            *pending_fees = pending_fees.checked_sub(distributed)?;
+        }
+        Some(())
+    }
+
+    /// Whether reflection positions are enabled
+    pub fn is_reflection_enabled(&self) -> bool {
+        self.reflection_lp_vault != Pubkey::default()
+    }
+
+    /// Reflection vault the reflection fees charged from `swap_token` are paid into
+    pub fn reflection_vault(&self, swap_token: &Pubkey) -> Option<Pubkey> {
+        if *swap_token == self.token_a {
+            Some(self.reflection_vault_a)
+        } else if *swap_token == self.token_b {
+            Some(self.reflection_vault_b)
+        } else {
+            None
+        }
+    }
+
+    /// Whether buybacks are enabled
+    pub fn is_buyback_enabled(&self) -> bool {
+        self.buyback_mint != Pubkey::default()
+    }
+
+    /// Buyback vault the buyback fees charged from `swap_token` are paid into
+    pub fn buyback_vault(&self, swap_token: &Pubkey) -> Option<Pubkey> {
+        if *swap_token == self.token_a {
+            Some(self.buyback_vault_a)
+        } else if *swap_token == self.token_b {
+            Some(self.buyback_vault_b)
+        } else {
+            None
+        }
+    }
+
+    /// Accrues `reflection_fee` charged from `swap_token`, along with the reflection
+    /// fees still pending, to the pool tokens of the reflection positions. Fees stay
+    /// pending while no reflection position holds pool tokens.
+    pub fn accrue_reflection(&mut self, swap_token: &Pubkey, reflection_fee: u64) -> Option<()> {
+        let (acc_reflection_per_lp_share, reflection_pending) = if *swap_token == self.token_a {
+            (
+                &mut self.acc_reflection_per_lp_share_a,
+                &mut self.reflection_pending_a,
+            )
+        } else if *swap_token == self.token_b {
+            (
+                &mut self.acc_reflection_per_lp_share_b,
+                &mut self.reflection_pending_b,
+            )
+        } else {
+            return None;
+        };
+        *reflection_pending = reflection_pending.checked_add(reflection_fee)?;
+        let reflection_supply = self.reflection_supply;
+        if reflection_supply == 0 {
+            return Some(());
+        }
+        let delta = (*reflection_pending as u128)
+            .checked_mul(REFLECTION_PER_LP_SHARE_SCALE)?
+            .checked_div(reflection_supply as u128)?;
+        // Rounding dust stays pending for the next accrual
+        let accrued = u64::try_from(reflection_owed(reflection_supply, delta)?).ok()?;
+        *acc_reflection_per_lp_share = acc_reflection_per_lp_share.checked_add(delta)?;
+        *reflection_pending = reflection_pending.checked_sub(accrued)?;
+        Some(())
+    }
+
+    /// Whether the order-flow auction is enabled
+    pub fn is_order_flow_auction_enabled(&self) -> bool {
+        self.order_flow_batch_slots != 0
+    }
+
+    /// Order-flow auction batch of `slot`
+    pub fn order_flow_batch(&self, slot: Slot) -> Option<u64> {
+        slot.checked_div(self.order_flow_batch_slots)
+    }
+
+    /// Whether `slot` is the last slot of its batch, in which only the best bidder
+    /// may swap to backrun the batch
+    pub fn is_order_flow_backrun_slot(&self, slot: Slot) -> bool {
+        self.is_order_flow_auction_enabled()
+            && slot % self.order_flow_batch_slots == self.order_flow_batch_slots - 1
+    }
+
+    /// Credits the best bid to the pool once its batch ended
+    pub fn settle_order_flow_auction(&mut self, slot: Slot) {
+        if self.order_flow_bid != 0 && self.order_flow_batch(slot) > Some(self.order_flow_batch) {
+            self.order_flow_bid = 0;
+            self.order_flow_bidder = Pubkey::default();
+            self.order_flow_refund_account = Pubkey::default();
+        }
+    }
+
+    /// Whether the amplification coefficient follows the realized volatility
+    pub fn is_dynamic_amp_enabled(&self) -> bool {
+        self.dynamic_amp_min != 0
+    }
+
+    /// Amplification coefficient the dynamic amp targets at the current volatility,
+    /// interpolated from the maximum at zero volatility down to the minimum at the
+    /// volatility ceiling
+    pub fn dynamic_amp_target(&self) -> Option<u64> {
+        let volatility = self
+            .twap_volatility
+            .min(self.dynamic_amp_volatility_ceiling);
+        let decrease = (self.dynamic_amp_max.checked_sub(self.dynamic_amp_min)? as u128)
+            .checked_mul(volatility as u128)?
+            .checked_div(self.dynamic_amp_volatility_ceiling as u128)?;
+        self.dynamic_amp_max
+            .checked_sub(u64::try_from(decrease).ok()?)
+    }
+
+    /// Records a swap of `amount_a` token A against `amount_b` token B at `slot` in
+    /// the TWAP, returning the slots elapsed since the previous swap
+    pub fn observe_swap_price(&mut self, amount_a: u64, amount_b: u64, slot: Slot) -> Option<u64> {
+        let elapsed_slots = slot.saturating_sub(self.twap_slot);
+        if amount_a != 0 {
+            let price = (amount_b as u128)
+                .checked_mul(PRICE_SCALE as u128)?
+                .checked_div(amount_a as u128)?;
+            let (twap_price, twap_volatility) =
+                twap::observe(self.twap_price, self.twap_volatility, elapsed_slots, price)?;
+            self.twap_price = twap_price;
+            self.twap_volatility = twap_volatility;
+        }
+        self.twap_slot = slot;
+        Some(elapsed_slots)
+    }
+
+    /// Records a swap of `amount_a` token A against `amount_b` token B at `slot` in
+    /// the TWAP, then moves the amplification coefficient toward the dynamic amp
+    /// target by at most `dynamic_amp_max_drift` per slot since the previous swap
+    pub fn update_dynamic_amp(&mut self, amount_a: u64, amount_b: u64, slot: Slot) -> Option<()> {
+        let elapsed_slots = self.observe_swap_price(amount_a, amount_b, slot)?;
+
+        let target_amp = self.dynamic_amp_target()?;
+        let max_drift = self.dynamic_amp_max_drift.saturating_mul(elapsed_slots);
+        let amp = self.target_amp_factor;
+        let amp = if target_amp > amp {
+            amp.checked_add((target_amp - amp).min(max_drift))?
+        } else {
+            amp.checked_sub((amp - target_amp).min(max_drift))?
+        };
+        self.initial_amp_factor = amp;
+        self.target_amp_factor = amp;
+        Some(())
+    }
+
+    /// Whether the swap fees rise with the realized volatility
+    pub fn is_volatility_fee_enabled(&self) -> bool {
+        self.volatility_fee_ceiling != 0
+    }
+
+    /// Whether swaps record their price in the TWAP, for the dynamic amp or the
+    /// volatility fee
+    pub fn is_twap_tracked(&self) -> bool {
+        self.is_dynamic_amp_enabled() || self.is_volatility_fee_enabled()
+    }
+
+    /// Multiplier of the swap fees at the current volatility, in basis points,
+    /// interpolated from one at zero volatility up to the maximum at the volatility
+    /// ceiling
+    pub fn volatility_fee_multiplier_bps(&self) -> Option<u64> {
+        if !self.is_volatility_fee_enabled() {
+            return Some(10_000);
+        }
+        let volatility = self.twap_volatility.min(self.volatility_fee_ceiling);
+        let increase = (self.volatility_fee_max_multiplier_bps as u128)
+            .checked_sub(10_000)?
+            .checked_mul(volatility as u128)?
+            .checked_div(self.volatility_fee_ceiling as u128)?;
+        10_000u64.checked_add(u64::try_from(increase).ok()?)
+    }
+
+    /// Accumulates the spot prices of the reserves `amount_a` and `amount_b` over the
+    /// slots since the previous accumulation, up to `slot`. Swaps accumulate the prices
+    /// of the reserves they find, before they trade.
+    pub fn accumulate_prices(
+        &mut self,
+        invariant: &impl SwapCurve,
+        amount_a: u64,
+        amount_b: u64,
+        slot: Slot,
+    ) -> Option<()> {
+        let elapsed_slots = slot.saturating_sub(self.price_cumulative_slot);
+        if elapsed_slots == 0 {
+            return Some(());
+        }
+        if amount_a != 0 && amount_b != 0 {
+            let scale = U256::from(PRICE_SCALE);
+            let price_a = invariant.compute_spot_price(amount_a.into(), amount_b.into(), scale)?;
+            let price_b = invariant.compute_spot_price(amount_b.into(), amount_a.into(), scale)?;
+            self.price_a_cumulative = self
+                .price_a_cumulative
+                .wrapping_add(price_a.as_u128().wrapping_mul(elapsed_slots.into()));
+            self.price_b_cumulative = self
+                .price_b_cumulative
+                .wrapping_add(price_b.as_u128().wrapping_mul(elapsed_slots.into()));
+        }
+        self.price_cumulative_slot = slot;
+        Some(())
+    }
+
+    /// Part of `admin_fee` admin trade fees set aside in the fee buffer
+    pub fn fee_buffer_share(&self, admin_fee: u64) -> Option<u64> {
+        let share = (admin_fee as u128)
+            .checked_mul(self.fee_buffer_bps.into())?
+            .checked_div(10_000)?;
+        u64::try_from(share).ok()
+    }
+
+    /// Counts a dust sweep in `slot`, false when the slot has no sweep left
+    pub fn record_dust_sweep(&mut self, slot: Slot) -> bool {
+        if slot != self.dust_sweep_slot {
+            self.dust_sweep_slot = slot;
+            self.dust_sweep_count = 0;
+        }
+        if self.dust_sweep_count >= MAX_DUST_SWEEPS_PER_SLOT {
+            return false;
+        }
+        self.dust_sweep_count += 1;
+        true
+    }
+
+    /// Accumulates the pool tokens held in liquidity positions up to `now`
+    pub fn checkpoint_position_liquidity(&mut self, now: i64) -> Option<()> {
+        self.cumulative_position_liquidity = accumulate_liquidity(
+            self.cumulative_position_liquidity,
+            self.position_liquidity,
+            self.position_liquidity_ts,
+            now,
+        )?;
+        self.position_liquidity_ts = self.position_liquidity_ts.max(now);
+        Some(())
+    }
+
+    /// Part of `lp_fee` LP trade fees set aside in the insurance fund
+    pub fn insurance_share(&self, lp_fee: u64) -> Option<u64> {
+        let share = (lp_fee as u128)
+            .checked_mul(self.insurance_fee_bps.into())?
+            .checked_div(10_000)?;
+        u64::try_from(share).ok()
+    }
+
+    /// Whether the admin fees taken per epoch are capped in either token
+    pub fn is_admin_fee_capped(&self) -> bool {
+        self.admin_fee_epoch_cap_a != 0 || self.admin_fee_epoch_cap_b != 0
+    }
+
+    /// Part of `admin_fee` charged in `fee_token` in `epoch` the admin takes under
+    /// the epoch cap, counted towards the cap. The rest is left to the LPs.
+    pub fn take_capped_admin_fee(
+        &mut self,
+        fee_token: &Pubkey,
+        epoch: Epoch,
+        admin_fee: u64,
+    ) -> Option<u64> {
+        if epoch != self.admin_fee_cap_epoch {
+            self.admin_fee_cap_epoch = epoch;
+            self.admin_fees_taken_a = 0;
+            self.admin_fees_taken_b = 0;
+        }
+        let (cap, taken) = if *fee_token == self.token_a {
+            (self.admin_fee_epoch_cap_a, &mut self.admin_fees_taken_a)
+        } else if *fee_token == self.token_b {
+            (self.admin_fee_epoch_cap_b, &mut self.admin_fees_taken_b)
+        } else {
+            return None;
+        };
+        let admin_fee = if cap == 0 {
+            admin_fee
+        } else {
+            admin_fee.min(cap.saturating_sub(*taken))
+        };
+        *taken = taken.checked_add(admin_fee)?;
+        Some(admin_fee)
+    }
+
+    /// Counts the fees charged in `fee_token` by a swap towards the fees of
+    /// `fee_report_epoch`
+    pub fn record_epoch_fees(&mut self, fee_token: &Pubkey, result: &SwapResultV2) -> Option<()> {
+        let lp_fee = result
+            .trade_fee
+            .saturating_sub(result.reflection_fee)
+            .saturating_sub(result.buyback_fee)
+            .saturating_sub(result.marketing_fee)
+            .saturating_sub(result.developer_fee)
+            .saturating_sub(result.admin_fee);
+        let buckets = if *fee_token == self.token_a {
+            [
+                (&mut self.epoch_lp_fees_a, lp_fee),
+                (&mut self.epoch_admin_fees_a, result.admin_fee),
+                (&mut self.epoch_reflection_fees_a, result.reflection_fee),
+                (&mut self.epoch_buyback_fees_a, result.buyback_fee),
+                (&mut self.epoch_marketing_fees_a, result.marketing_fee),
+                (&mut self.epoch_developer_fees_a, result.developer_fee),
+            ]
+        } else {
+            [
+                (&mut self.epoch_lp_fees_b, lp_fee),
+                (&mut self.epoch_admin_fees_b, result.admin_fee),
+                (&mut self.epoch_reflection_fees_b, result.reflection_fee),
+                (&mut self.epoch_buyback_fees_b, result.buyback_fee),
+                (&mut self.epoch_marketing_fees_b, result.marketing_fee),
+                (&mut self.epoch_developer_fees_b, result.developer_fee),
+            ]
+        };
+        for (bucket, fee) in buckets {
+            *bucket = bucket.checked_add(U256::to_u64(fee).ok()?)?;
+        }
+        Some(())
+    }
+
+    /// Report of the fees of `fee_report_epoch` once `epoch` is past it. The fee
+    /// counters are then reset to count the fees of `epoch`.
+    pub fn take_epoch_fee_report(&mut self, swap: &Pubkey, epoch: Epoch) -> Option<EpochFeeReport> {
+        if epoch <= self.fee_report_epoch {
+            return None;
+        }
+        let report = EpochFeeReport {
+            swap: *swap,
+            epoch: self.fee_report_epoch,
+            lp_fees_a: self.epoch_lp_fees_a,
+            lp_fees_b: self.epoch_lp_fees_b,
+            admin_fees_a: self.epoch_admin_fees_a,
+            admin_fees_b: self.epoch_admin_fees_b,
+            reflection_fees_a: self.epoch_reflection_fees_a,
+            reflection_fees_b: self.epoch_reflection_fees_b,
+            buyback_fees_a: self.epoch_buyback_fees_a,
+            buyback_fees_b: self.epoch_buyback_fees_b,
+            marketing_fees_a: self.epoch_marketing_fees_a,
+            marketing_fees_b: self.epoch_marketing_fees_b,
+            developer_fees_a: self.epoch_developer_fees_a,
+            developer_fees_b: self.epoch_developer_fees_b,
+        };
+        self.fee_report_epoch = epoch;
+        self.epoch_lp_fees_a = 0;
+        self.epoch_lp_fees_b = 0;
+        self.epoch_admin_fees_a = 0;
+        self.epoch_admin_fees_b = 0;
+        self.epoch_reflection_fees_a = 0;
+        self.epoch_reflection_fees_b = 0;
+        self.epoch_buyback_fees_a = 0;
+        self.epoch_buyback_fees_b = 0;
+        self.epoch_marketing_fees_a = 0;
+        self.epoch_marketing_fees_b = 0;
+        self.epoch_developer_fees_a = 0;
+        self.epoch_developer_fees_b = 0;
+        Some(report)
+    }
+
+    /// Bonus token A and token B paid from the fee buffer to a deposit of
+    /// `deposit_a` and `deposit_b` into a pool holding `reserve_a` and `reserve_b`.
+    /// Deposits that reduce the relative imbalance `|a - b| / (a + b)` of the pool
+    /// earn up to `deposit_bonus_bps` of their amounts, scaled by the share of the
+    /// imbalance they remove and capped by the fee buffer.
+    pub fn deposit_bonus(
+        &self,
+        deposit_a: u64,
+        deposit_b: u64,
+        reserve_a: u64,
+        reserve_b: u64,
+    ) -> Option<(u64, u64)> {
+        if self.deposit_bonus_bps == 0 || reserve_a == reserve_b {
+            return Some((0, 0));
+        }
+        let imbalance = |a: u64, b: u64| -> Option<(U256, U256)> {
+            let (a, b) = (U256::from(a), U256::from(b));
+            Some((a.max(b).checked_sub(a.min(b))?, a.checked_add(b)?))
+        };
+        let (old_difference, old_total) = imbalance(reserve_a, reserve_b)?;
+        let (new_difference, new_total) = imbalance(
+            reserve_a.checked_add(deposit_a)?,
+            reserve_b.checked_add(deposit_b)?,
+        )?;
+        // Share of the imbalance removed is 1 - new_imbalance / old_imbalance
+        let old_imbalance = old_difference.checked_mul(new_total)?;
+        let new_imbalance = new_difference.checked_mul(old_total)?;
+        if new_imbalance >= old_imbalance {
+            return Some((0, 0));
+        }
+        let removed = old_imbalance.checked_sub(new_imbalance)?;
+        let bonus = |deposit: u64, fee_buffer: u64| -> Option<u64> {
+            let bonus = U256::from(deposit)
+                .checked_mul(self.deposit_bonus_bps.into())?
+                .checked_mul(removed)?
+                .checked_div(old_imbalance.checked_mul(10_000.into())?)?;
+            Some(U256::to_u64(bonus).ok()?.min(fee_buffer))
+        };
+        Some((
+            bonus(deposit_a, self.fee_buffer_a)?,
+            bonus(deposit_b, self.fee_buffer_b)?,
+        ))
+    }
+
+    /// Hash of the admin-controlled pool parameters: admin and admin fee accounts,
+    /// fees, amplification ramp, harvest tip, maximum swap amount, veLP fee share,
+    /// order-flow auction, dynamic amp bounds, deposit bonus, insurance fund and
+    /// volatility fee
+    pub fn compute_config_hash(&self) -> Hash {
+        let mut fees = [0u8; Fees::LEN];
+        self.fees.pack_into_slice(&mut fees);
+        hashv(&[
+            self.admin_key.as_ref(),
+            self.admin_fee_key_a.as_ref(),
+            self.admin_fee_key_b.as_ref(),
+            &fees,
+            &self.initial_amp_factor.to_le_bytes(),
+            &self.target_amp_factor.to_le_bytes(),
+            &self.start_ramp_ts.to_le_bytes(),
+            &self.stop_ramp_ts.to_le_bytes(),
+            &self.harvest_tip_bps.to_le_bytes(),
+            &self.max_swap_amount_in.to_le_bytes(),
+            &self.ve_fee_bps.to_le_bytes(),
+            &self.order_flow_batch_slots.to_le_bytes(),
+            self.order_flow_bid_token.as_ref(),
+            &self.dynamic_amp_min.to_le_bytes(),
+            &self.dynamic_amp_max.to_le_bytes(),
+            &self.dynamic_amp_volatility_ceiling.to_le_bytes(),
+            &self.dynamic_amp_max_drift.to_le_bytes(),
+            &self.fee_buffer_bps.to_le_bytes(),
+            &self.deposit_bonus_bps.to_le_bytes(),
+            &self.insurance_fee_bps.to_le_bytes(),
+            self.insurance_authority.as_ref(),
+            &self.min_lp_mint.to_le_bytes(),
+            &self.dust_threshold.to_le_bytes(),
+            self.custodian.as_ref(),
+            &self.custodian_withdraw_threshold.to_le_bytes(),
+            self.credential_issuer.as_ref(),
+            self.guardian_key.as_ref(),
+            &self.volatility_fee_ceiling.to_le_bytes(),
+            &self.volatility_fee_max_multiplier_bps.to_le_bytes(),
+            &self.price_bound.to_le_bytes(),
+            self.oracle_a.as_ref(),
+            self.oracle_b.as_ref(),
+            &self.oracle_max_staleness_slots.to_le_bytes(),
+            &self.oracle_max_confidence_bps.to_le_bytes(),
+            &self.oracle_max_deviation_bps.to_le_bytes(),
+            &[self.oracle_stale_policy as u8],
+            &self.oracle_stale_band_bps.to_le_bytes(),
+        ])
+    }
+}
+
+/// Fees owed to `ve_balance` veLP at `fees_per_ve` fees distributed per veLP
+pub fn ve_fees(ve_balance: u64, fees_per_ve: u128) -> Option<u128> {
+    (ve_balance as u128)
+        .checked_mul(fees_per_ve)?
+        .checked_div(VE_FEES_PER_VE_SCALE)
+}
+
+/// Reflection fees owed to `amount` pool tokens at `acc_reflection_per_lp_share`
+/// fees accrued per pool token
+pub fn reflection_owed(amount: u64, acc_reflection_per_lp_share: u128) -> Option<u128> {
+    (amount as u128)
+        .checked_mul(acc_reflection_per_lp_share)?
+        .checked_div(REFLECTION_PER_LP_SHARE_SCALE)
+}
+
+/// `cumulative` liquidity accumulated with `amount` pool tokens held from `from_ts`
+/// to `to_ts`
+pub fn accumulate_liquidity(
+    cumulative: u128,
+    amount: u64,
+    from_ts: i64,
+    to_ts: i64,
+) -> Option<u128> {
+    let elapsed = to_ts.saturating_sub(from_ts).max(0) as u128;
+    cumulative.checked_add((amount as u128).checked_mul(elapsed)?)
+}
+
+/// Time-weighted average liquidity between two observations of a cumulative
+/// liquidity, zero when no time elapsed between them
+pub fn time_weighted_liquidity(
+    start_cumulative: u128,
+    start_ts: i64,
+    end_cumulative: u128,
+    end_ts: i64,
+) -> Option<u64> {
+    if end_ts <= start_ts {
+        return Some(0);
+    }
+    let liquidity = end_cumulative
+        .checked_sub(start_cumulative)?
+        .checked_div(end_ts.checked_sub(start_ts)? as u128)?;
+    u64::try_from(liquidity).ok()
 }
 
 impl Sealed for SwapInfo {}
@@ -70,11 +1164,11 @@ impl IsInitialized for SwapInfo {
 }
 
 impl Pack for SwapInfo {
-    const LEN: usize = 395;
+    const LEN: usize = layout::swap_info::LEN;
 
     /// Unpacks a byte buffer into a [SwapInfo](struct.SwapInfo.html).
     fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
-        let input = array_ref![input, 0, 395];
+        let input = array_ref![input, 0, 3400];
         #[allow(clippy::ptr_offset_with_cast)]
         let (
             is_initialized,
@@ -95,7 +1189,153 @@ impl Pack for SwapInfo {
             admin_fee_key_a,
             admin_fee_key_b,
             fees,
-        ) = array_refs![input, 1, 1, 1, 8, 8, 8, 8, 8, 32, 32, 32, 32, 32, 32, 32, 32, 32, 64];
+            access_mint,
+            access_window_end_slot,
+            namespace,
+            fee_tier,
+            admin_fees_accrued_a,
+            admin_fees_accrued_b,
+            harvest_tip_bps,
+            reflection_distributor,
+            reflection_epoch,
+            dev_vesting_a,
+            dev_vesting_b,
+            dev_wallet,
+            dev_vesting_start_ts,
+            dev_vesting_end_ts,
+            dev_fees_received_a,
+            dev_fees_received_b,
+            dev_fees_claimed_a,
+            dev_fees_claimed_b,
+            governance_realm,
+            governance_vault,
+            governance_lockup,
+            admin_recovery_key,
+            admin_heartbeat_slot,
+            admin_timeout_slots,
+            pending_withdraw_a,
+            pending_withdraw_b,
+            max_swap_amount_in,
+            lp_soulbound,
+            ve_lp_vault,
+            ve_fee_vault_a,
+            ve_fee_vault_b,
+            ve_fee_bps,
+            ve_supply,
+            ve_epoch,
+            ve_fees_per_ve_a,
+            ve_fees_per_ve_b,
+            ve_pending_fees_a,
+            ve_pending_fees_b,
+            order_flow_batch_slots,
+            order_flow_bid_token,
+            order_flow_batch,
+            order_flow_bidder,
+            order_flow_refund_account,
+            order_flow_bid,
+            dynamic_amp_min,
+            dynamic_amp_max,
+            dynamic_amp_volatility_ceiling,
+            dynamic_amp_max_drift,
+            twap_price,
+            twap_volatility,
+            twap_slot,
+            fee_buffer_bps,
+            deposit_bonus_bps,
+            fee_buffer_a,
+            fee_buffer_b,
+            insurance_fee_bps,
+            insurance_authority,
+            insurance_fund_a,
+            insurance_fund_b,
+            insurance_covered_a,
+            insurance_covered_b,
+            min_lp_mint,
+            dust_threshold,
+            dust_sweep_slot,
+            dust_sweep_count,
+            liquidity_vault,
+            position_liquidity,
+            cumulative_position_liquidity,
+            position_liquidity_ts,
+            fee_report_epoch,
+            epoch_lp_fees_a,
+            epoch_lp_fees_b,
+            epoch_admin_fees_a,
+            epoch_admin_fees_b,
+            epoch_reflection_fees_a,
+            epoch_reflection_fees_b,
+            epoch_buyback_fees_a,
+            epoch_buyback_fees_b,
+            epoch_marketing_fees_a,
+            epoch_marketing_fees_b,
+            epoch_developer_fees_a,
+            epoch_developer_fees_b,
+            sequence,
+            custodian,
+            custodian_withdraw_threshold,
+            credential_issuer,
+            admin_log,
+            admin_fee_escrow_a,
+            admin_fee_escrow_b,
+            price_a_cumulative,
+            price_b_cumulative,
+            price_cumulative_slot,
+            guardian_key,
+            protocol_pool_amount,
+            pending_fees,
+            pending_fees_slot,
+            custom_error_messages,
+            reflection_lp_vault,
+            reflection_vault_a,
+            reflection_vault_b,
+            reflection_supply,
+            acc_reflection_per_lp_share_a,
+            acc_reflection_per_lp_share_b,
+            reflection_pending_a,
+            reflection_pending_b,
+            buyback_mint,
+            buyback_vault_a,
+            buyback_vault_b,
+            buyback_burned,
+            marketing_wallet,
+            developer_wallet,
+            marketing_fees_accrued_a,
+            marketing_fees_accrued_b,
+            developer_fees_accrued_a,
+            developer_fees_accrued_b,
+            config_hash,
+            volatility_fee_ceiling,
+            volatility_fee_max_multiplier_bps,
+            referral_fees_accrued_a,
+            referral_fees_accrued_b,
+            admin_fee_epoch_cap_a,
+            admin_fee_epoch_cap_b,
+            admin_fee_cap_epoch,
+            admin_fees_taken_a,
+            admin_fees_taken_b,
+            curve_type,
+            price_bound,
+            creator,
+            creator_deposit_end_slot,
+            deposit_allowlist_enabled,
+            freeze_authority_acknowledged,
+            oracle_a,
+            oracle_b,
+            oracle_max_staleness_slots,
+            oracle_max_confidence_bps,
+            oracle_max_deviation_bps,
+            oracle_stale_policy,
+            oracle_stale_band_bps,
+        ) = array_refs![
+            input, 1, 1, 1, 8, 8, 8, 8, 8, 32, 32, 32, 32, 32, 32, 32, 32, 32, 170, 32, 8, 32, 1,
+            8, 8, 2, 32, 8, 32, 32, 32, 8, 8, 8, 8, 8, 8, 32, 32, 8, 32, 8, 8, 8, 8, 8, 1, 32, 32,
+            32, 2, 8, 8, 16, 16, 8, 8, 8, 32, 8, 32, 32, 8, 8, 8, 8, 8, 16, 8, 8, 2, 2, 8, 8, 2,
+            32, 8, 8, 8, 8, 8, 8, 8, 2, 32, 8, 16, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 32,
+            8, 32, 657, 1, 1, 16, 16, 8, 32, 8, 170, 8, 276, 32, 32, 32, 8, 16, 16, 8, 8, 32, 32,
+            32, 8, 32, 32, 8, 8, 8, 8, 32, 8, 2, 8, 8, 8, 8, 8, 8, 8, 1, 8, 32, 8, 1, 1, 32, 32, 8,
+            2, 2, 1, 2
+        ];
         Ok(Self {
             is_initialized: match is_initialized {
                 [0] => false,
@@ -123,11 +1363,158 @@ impl Pack for SwapInfo {
             admin_fee_key_a: Pubkey::new_from_array(*admin_fee_key_a),
             admin_fee_key_b: Pubkey::new_from_array(*admin_fee_key_b),
             fees: Fees::unpack_from_slice(fees)?,
+            access_mint: Pubkey::new_from_array(*access_mint),
+            access_window_end_slot: u64::from_le_bytes(*access_window_end_slot),
+            namespace: *namespace,
+            fee_tier: FeeTier::try_from(fee_tier[0])
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+            admin_fees_accrued_a: u64::from_le_bytes(*admin_fees_accrued_a),
+            admin_fees_accrued_b: u64::from_le_bytes(*admin_fees_accrued_b),
+            harvest_tip_bps: u16::from_le_bytes(*harvest_tip_bps),
+            reflection_distributor: Pubkey::new_from_array(*reflection_distributor),
+            reflection_epoch: u64::from_le_bytes(*reflection_epoch),
+            dev_vesting_a: Pubkey::new_from_array(*dev_vesting_a),
+            dev_vesting_b: Pubkey::new_from_array(*dev_vesting_b),
+            dev_wallet: Pubkey::new_from_array(*dev_wallet),
+            dev_vesting_start_ts: i64::from_le_bytes(*dev_vesting_start_ts),
+            dev_vesting_end_ts: i64::from_le_bytes(*dev_vesting_end_ts),
+            dev_fees_received_a: u64::from_le_bytes(*dev_fees_received_a),
+            dev_fees_received_b: u64::from_le_bytes(*dev_fees_received_b),
+            dev_fees_claimed_a: u64::from_le_bytes(*dev_fees_claimed_a),
+            dev_fees_claimed_b: u64::from_le_bytes(*dev_fees_claimed_b),
+            governance_realm: Pubkey::new_from_array(*governance_realm),
+            governance_vault: Pubkey::new_from_array(*governance_vault),
+            governance_lockup: i64::from_le_bytes(*governance_lockup),
+            admin_recovery_key: Pubkey::new_from_array(*admin_recovery_key),
+            admin_heartbeat_slot: u64::from_le_bytes(*admin_heartbeat_slot),
+            admin_timeout_slots: u64::from_le_bytes(*admin_timeout_slots),
+            pending_withdraw_a: u64::from_le_bytes(*pending_withdraw_a),
+            pending_withdraw_b: u64::from_le_bytes(*pending_withdraw_b),
+            max_swap_amount_in: u64::from_le_bytes(*max_swap_amount_in),
+            lp_soulbound: match lp_soulbound {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            ve_lp_vault: Pubkey::new_from_array(*ve_lp_vault),
+            ve_fee_vault_a: Pubkey::new_from_array(*ve_fee_vault_a),
+            ve_fee_vault_b: Pubkey::new_from_array(*ve_fee_vault_b),
+            ve_fee_bps: u16::from_le_bytes(*ve_fee_bps),
+            ve_supply: u64::from_le_bytes(*ve_supply),
+            ve_epoch: i64::from_le_bytes(*ve_epoch),
+            ve_fees_per_ve_a: u128::from_le_bytes(*ve_fees_per_ve_a),
+            ve_fees_per_ve_b: u128::from_le_bytes(*ve_fees_per_ve_b),
+            ve_pending_fees_a: u64::from_le_bytes(*ve_pending_fees_a),
+            ve_pending_fees_b: u64::from_le_bytes(*ve_pending_fees_b),
+            order_flow_batch_slots: u64::from_le_bytes(*order_flow_batch_slots),
+            order_flow_bid_token: Pubkey::new_from_array(*order_flow_bid_token),
+            order_flow_batch: u64::from_le_bytes(*order_flow_batch),
+            order_flow_bidder: Pubkey::new_from_array(*order_flow_bidder),
+            order_flow_refund_account: Pubkey::new_from_array(*order_flow_refund_account),
+            order_flow_bid: u64::from_le_bytes(*order_flow_bid),
+            dynamic_amp_min: u64::from_le_bytes(*dynamic_amp_min),
+            dynamic_amp_max: u64::from_le_bytes(*dynamic_amp_max),
+            dynamic_amp_volatility_ceiling: u64::from_le_bytes(*dynamic_amp_volatility_ceiling),
+            dynamic_amp_max_drift: u64::from_le_bytes(*dynamic_amp_max_drift),
+            twap_price: u128::from_le_bytes(*twap_price),
+            twap_volatility: u64::from_le_bytes(*twap_volatility),
+            twap_slot: u64::from_le_bytes(*twap_slot),
+            fee_buffer_bps: u16::from_le_bytes(*fee_buffer_bps),
+            deposit_bonus_bps: u16::from_le_bytes(*deposit_bonus_bps),
+            fee_buffer_a: u64::from_le_bytes(*fee_buffer_a),
+            fee_buffer_b: u64::from_le_bytes(*fee_buffer_b),
+            insurance_fee_bps: u16::from_le_bytes(*insurance_fee_bps),
+            insurance_authority: Pubkey::new_from_array(*insurance_authority),
+            insurance_fund_a: u64::from_le_bytes(*insurance_fund_a),
+            insurance_fund_b: u64::from_le_bytes(*insurance_fund_b),
+            insurance_covered_a: u64::from_le_bytes(*insurance_covered_a),
+            insurance_covered_b: u64::from_le_bytes(*insurance_covered_b),
+            min_lp_mint: u64::from_le_bytes(*min_lp_mint),
+            dust_threshold: u64::from_le_bytes(*dust_threshold),
+            dust_sweep_slot: Slot::from_le_bytes(*dust_sweep_slot),
+            dust_sweep_count: u16::from_le_bytes(*dust_sweep_count),
+            liquidity_vault: Pubkey::new_from_array(*liquidity_vault),
+            position_liquidity: u64::from_le_bytes(*position_liquidity),
+            cumulative_position_liquidity: u128::from_le_bytes(*cumulative_position_liquidity),
+            position_liquidity_ts: i64::from_le_bytes(*position_liquidity_ts),
+            fee_report_epoch: Epoch::from_le_bytes(*fee_report_epoch),
+            epoch_lp_fees_a: u64::from_le_bytes(*epoch_lp_fees_a),
+            epoch_lp_fees_b: u64::from_le_bytes(*epoch_lp_fees_b),
+            epoch_admin_fees_a: u64::from_le_bytes(*epoch_admin_fees_a),
+            epoch_admin_fees_b: u64::from_le_bytes(*epoch_admin_fees_b),
+            epoch_reflection_fees_a: u64::from_le_bytes(*epoch_reflection_fees_a),
+            epoch_reflection_fees_b: u64::from_le_bytes(*epoch_reflection_fees_b),
+            epoch_buyback_fees_a: u64::from_le_bytes(*epoch_buyback_fees_a),
+            epoch_buyback_fees_b: u64::from_le_bytes(*epoch_buyback_fees_b),
+            epoch_marketing_fees_a: u64::from_le_bytes(*epoch_marketing_fees_a),
+            epoch_marketing_fees_b: u64::from_le_bytes(*epoch_marketing_fees_b),
+            epoch_developer_fees_a: u64::from_le_bytes(*epoch_developer_fees_a),
+            epoch_developer_fees_b: u64::from_le_bytes(*epoch_developer_fees_b),
+            sequence: u64::from_le_bytes(*sequence),
+            custodian: Pubkey::new_from_array(*custodian),
+            custodian_withdraw_threshold: u64::from_le_bytes(*custodian_withdraw_threshold),
+            credential_issuer: Pubkey::new_from_array(*credential_issuer),
+            admin_log: AdminLog::unpack_from_slice(admin_log)?,
+            admin_fee_escrow_a: admin_fee_escrow_a[0] != 0,
+            admin_fee_escrow_b: admin_fee_escrow_b[0] != 0,
+            price_a_cumulative: u128::from_le_bytes(*price_a_cumulative),
+            price_b_cumulative: u128::from_le_bytes(*price_b_cumulative),
+            price_cumulative_slot: Slot::from_le_bytes(*price_cumulative_slot),
+            guardian_key: Pubkey::new_from_array(*guardian_key),
+            protocol_pool_amount: u64::from_le_bytes(*protocol_pool_amount),
+            pending_fees: Fees::unpack_from_slice(pending_fees)?,
+            pending_fees_slot: Slot::from_le_bytes(*pending_fees_slot),
+            custom_error_messages: CustomErrorMessages::unpack_from_slice(custom_error_messages)?,
+            reflection_lp_vault: Pubkey::new_from_array(*reflection_lp_vault),
+            reflection_vault_a: Pubkey::new_from_array(*reflection_vault_a),
+            reflection_vault_b: Pubkey::new_from_array(*reflection_vault_b),
+            reflection_supply: u64::from_le_bytes(*reflection_supply),
+            acc_reflection_per_lp_share_a: u128::from_le_bytes(*acc_reflection_per_lp_share_a),
+            acc_reflection_per_lp_share_b: u128::from_le_bytes(*acc_reflection_per_lp_share_b),
+            reflection_pending_a: u64::from_le_bytes(*reflection_pending_a),
+            reflection_pending_b: u64::from_le_bytes(*reflection_pending_b),
+            buyback_mint: Pubkey::new_from_array(*buyback_mint),
+            buyback_vault_a: Pubkey::new_from_array(*buyback_vault_a),
+            buyback_vault_b: Pubkey::new_from_array(*buyback_vault_b),
+            buyback_burned: u64::from_le_bytes(*buyback_burned),
+            marketing_wallet: Pubkey::new_from_array(*marketing_wallet),
+            developer_wallet: Pubkey::new_from_array(*developer_wallet),
+            marketing_fees_accrued_a: u64::from_le_bytes(*marketing_fees_accrued_a),
+            marketing_fees_accrued_b: u64::from_le_bytes(*marketing_fees_accrued_b),
+            developer_fees_accrued_a: u64::from_le_bytes(*developer_fees_accrued_a),
+            developer_fees_accrued_b: u64::from_le_bytes(*developer_fees_accrued_b),
+            config_hash: Hash::new_from_array(*config_hash),
+            volatility_fee_ceiling: u64::from_le_bytes(*volatility_fee_ceiling),
+            volatility_fee_max_multiplier_bps: u16::from_le_bytes(
+                *volatility_fee_max_multiplier_bps,
+            ),
+            referral_fees_accrued_a: u64::from_le_bytes(*referral_fees_accrued_a),
+            referral_fees_accrued_b: u64::from_le_bytes(*referral_fees_accrued_b),
+            admin_fee_epoch_cap_a: u64::from_le_bytes(*admin_fee_epoch_cap_a),
+            admin_fee_epoch_cap_b: u64::from_le_bytes(*admin_fee_epoch_cap_b),
+            admin_fee_cap_epoch: u64::from_le_bytes(*admin_fee_cap_epoch),
+            admin_fees_taken_a: u64::from_le_bytes(*admin_fees_taken_a),
+            admin_fees_taken_b: u64::from_le_bytes(*admin_fees_taken_b),
+            curve_type: CurveType::from_u8(curve_type[0])
+                .ok_or(ProgramError::InvalidAccountData)?,
+            price_bound: u64::from_le_bytes(*price_bound),
+            creator: Pubkey::new_from_array(*creator),
+            creator_deposit_end_slot: u64::from_le_bytes(*creator_deposit_end_slot),
+            deposit_allowlist_enabled: deposit_allowlist_enabled[0] != 0,
+            freeze_authority_acknowledged: freeze_authority_acknowledged[0] != 0,
+            oracle_a: Pubkey::new_from_array(*oracle_a),
+            oracle_b: Pubkey::new_from_array(*oracle_b),
+            oracle_max_staleness_slots: u64::from_le_bytes(*oracle_max_staleness_slots),
+            oracle_max_confidence_bps: u16::from_le_bytes(*oracle_max_confidence_bps),
+            oracle_max_deviation_bps: u16::from_le_bytes(*oracle_max_deviation_bps),
+            oracle_stale_policy: OracleStalePolicy::from_u8(oracle_stale_policy[0])
+                .ok_or(ProgramError::InvalidAccountData)?,
+            oracle_stale_band_bps: u16::from_le_bytes(*oracle_stale_band_bps),
         })
     }
 
     fn pack_into_slice(&self, output: &mut [u8]) {
-        let output = array_mut_ref![output, 0, 395];
+        let output = array_mut_ref![output, 0, 3400];
         let (
             is_initialized,
             is_paused,
@@ -147,7 +1534,153 @@ impl Pack for SwapInfo {
             admin_fee_key_a,
             admin_fee_key_b,
             fees,
-        ) = mut_array_refs![output, 1, 1, 1, 8, 8, 8, 8, 8, 32, 32, 32, 32, 32, 32, 32, 32, 32, 64];
+            access_mint,
+            access_window_end_slot,
+            namespace,
+            fee_tier,
+            admin_fees_accrued_a,
+            admin_fees_accrued_b,
+            harvest_tip_bps,
+            reflection_distributor,
+            reflection_epoch,
+            dev_vesting_a,
+            dev_vesting_b,
+            dev_wallet,
+            dev_vesting_start_ts,
+            dev_vesting_end_ts,
+            dev_fees_received_a,
+            dev_fees_received_b,
+            dev_fees_claimed_a,
+            dev_fees_claimed_b,
+            governance_realm,
+            governance_vault,
+            governance_lockup,
+            admin_recovery_key,
+            admin_heartbeat_slot,
+            admin_timeout_slots,
+            pending_withdraw_a,
+            pending_withdraw_b,
+            max_swap_amount_in,
+            lp_soulbound,
+            ve_lp_vault,
+            ve_fee_vault_a,
+            ve_fee_vault_b,
+            ve_fee_bps,
+            ve_supply,
+            ve_epoch,
+            ve_fees_per_ve_a,
+            ve_fees_per_ve_b,
+            ve_pending_fees_a,
+            ve_pending_fees_b,
+            order_flow_batch_slots,
+            order_flow_bid_token,
+            order_flow_batch,
+            order_flow_bidder,
+            order_flow_refund_account,
+            order_flow_bid,
+            dynamic_amp_min,
+            dynamic_amp_max,
+            dynamic_amp_volatility_ceiling,
+            dynamic_amp_max_drift,
+            twap_price,
+            twap_volatility,
+            twap_slot,
+            fee_buffer_bps,
+            deposit_bonus_bps,
+            fee_buffer_a,
+            fee_buffer_b,
+            insurance_fee_bps,
+            insurance_authority,
+            insurance_fund_a,
+            insurance_fund_b,
+            insurance_covered_a,
+            insurance_covered_b,
+            min_lp_mint,
+            dust_threshold,
+            dust_sweep_slot,
+            dust_sweep_count,
+            liquidity_vault,
+            position_liquidity,
+            cumulative_position_liquidity,
+            position_liquidity_ts,
+            fee_report_epoch,
+            epoch_lp_fees_a,
+            epoch_lp_fees_b,
+            epoch_admin_fees_a,
+            epoch_admin_fees_b,
+            epoch_reflection_fees_a,
+            epoch_reflection_fees_b,
+            epoch_buyback_fees_a,
+            epoch_buyback_fees_b,
+            epoch_marketing_fees_a,
+            epoch_marketing_fees_b,
+            epoch_developer_fees_a,
+            epoch_developer_fees_b,
+            sequence,
+            custodian,
+            custodian_withdraw_threshold,
+            credential_issuer,
+            admin_log,
+            admin_fee_escrow_a,
+            admin_fee_escrow_b,
+            price_a_cumulative,
+            price_b_cumulative,
+            price_cumulative_slot,
+            guardian_key,
+            protocol_pool_amount,
+            pending_fees,
+            pending_fees_slot,
+            custom_error_messages,
+            reflection_lp_vault,
+            reflection_vault_a,
+            reflection_vault_b,
+            reflection_supply,
+            acc_reflection_per_lp_share_a,
+            acc_reflection_per_lp_share_b,
+            reflection_pending_a,
+            reflection_pending_b,
+            buyback_mint,
+            buyback_vault_a,
+            buyback_vault_b,
+            buyback_burned,
+            marketing_wallet,
+            developer_wallet,
+            marketing_fees_accrued_a,
+            marketing_fees_accrued_b,
+            developer_fees_accrued_a,
+            developer_fees_accrued_b,
+            config_hash,
+            volatility_fee_ceiling,
+            volatility_fee_max_multiplier_bps,
+            referral_fees_accrued_a,
+            referral_fees_accrued_b,
+            admin_fee_epoch_cap_a,
+            admin_fee_epoch_cap_b,
+            admin_fee_cap_epoch,
+            admin_fees_taken_a,
+            admin_fees_taken_b,
+            curve_type,
+            price_bound,
+            creator,
+            creator_deposit_end_slot,
+            deposit_allowlist_enabled,
+            freeze_authority_acknowledged,
+            oracle_a,
+            oracle_b,
+            oracle_max_staleness_slots,
+            oracle_max_confidence_bps,
+            oracle_max_deviation_bps,
+            oracle_stale_policy,
+            oracle_stale_band_bps,
+        ) = mut_array_refs![
+            output, 1, 1, 1, 8, 8, 8, 8, 8, 32, 32, 32, 32, 32, 32, 32, 32, 32, 170, 32, 8, 32, 1,
+            8, 8, 2, 32, 8, 32, 32, 32, 8, 8, 8, 8, 8, 8, 32, 32, 8, 32, 8, 8, 8, 8, 8, 1, 32, 32,
+            32, 2, 8, 8, 16, 16, 8, 8, 8, 32, 8, 32, 32, 8, 8, 8, 8, 8, 16, 8, 8, 2, 2, 8, 8, 2,
+            32, 8, 8, 8, 8, 8, 8, 8, 2, 32, 8, 16, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 8, 32,
+            8, 32, 657, 1, 1, 16, 16, 8, 32, 8, 170, 8, 276, 32, 32, 32, 8, 16, 16, 8, 8, 32, 32,
+            32, 8, 32, 32, 8, 8, 8, 8, 32, 8, 2, 8, 8, 8, 8, 8, 8, 8, 1, 8, 32, 8, 1, 1, 32, 32, 8,
+            2, 2, 1, 2
+        ];
         is_initialized[0] = self.is_initialized as u8;
         is_paused[0] = self.is_paused as u8;
         nonce[0] = self.nonce;
@@ -166,136 +1699,4150 @@ impl Pack for SwapInfo {
         admin_fee_key_a.copy_from_slice(self.admin_fee_key_a.as_ref());
         admin_fee_key_b.copy_from_slice(self.admin_fee_key_b.as_ref());
         self.fees.pack_into_slice(&mut fees[..]);
+        access_mint.copy_from_slice(self.access_mint.as_ref());
+        *access_window_end_slot = self.access_window_end_slot.to_le_bytes();
+        *namespace = self.namespace;
+        fee_tier[0] = self.fee_tier as u8;
+        *admin_fees_accrued_a = self.admin_fees_accrued_a.to_le_bytes();
+        *admin_fees_accrued_b = self.admin_fees_accrued_b.to_le_bytes();
+        *harvest_tip_bps = self.harvest_tip_bps.to_le_bytes();
+        reflection_distributor.copy_from_slice(self.reflection_distributor.as_ref());
+        *reflection_epoch = self.reflection_epoch.to_le_bytes();
+        dev_vesting_a.copy_from_slice(self.dev_vesting_a.as_ref());
+        dev_vesting_b.copy_from_slice(self.dev_vesting_b.as_ref());
+        dev_wallet.copy_from_slice(self.dev_wallet.as_ref());
+        *dev_vesting_start_ts = self.dev_vesting_start_ts.to_le_bytes();
+        *dev_vesting_end_ts = self.dev_vesting_end_ts.to_le_bytes();
+        *dev_fees_received_a = self.dev_fees_received_a.to_le_bytes();
+        *dev_fees_received_b = self.dev_fees_received_b.to_le_bytes();
+        *dev_fees_claimed_a = self.dev_fees_claimed_a.to_le_bytes();
+        *dev_fees_claimed_b = self.dev_fees_claimed_b.to_le_bytes();
+        governance_realm.copy_from_slice(self.governance_realm.as_ref());
+        governance_vault.copy_from_slice(self.governance_vault.as_ref());
+        *governance_lockup = self.governance_lockup.to_le_bytes();
+        admin_recovery_key.copy_from_slice(self.admin_recovery_key.as_ref());
+        *admin_heartbeat_slot = self.admin_heartbeat_slot.to_le_bytes();
+        *admin_timeout_slots = self.admin_timeout_slots.to_le_bytes();
+        *pending_withdraw_a = self.pending_withdraw_a.to_le_bytes();
+        *pending_withdraw_b = self.pending_withdraw_b.to_le_bytes();
+        *max_swap_amount_in = self.max_swap_amount_in.to_le_bytes();
+        lp_soulbound[0] = self.lp_soulbound as u8;
+        ve_lp_vault.copy_from_slice(self.ve_lp_vault.as_ref());
+        ve_fee_vault_a.copy_from_slice(self.ve_fee_vault_a.as_ref());
+        ve_fee_vault_b.copy_from_slice(self.ve_fee_vault_b.as_ref());
+        *ve_fee_bps = self.ve_fee_bps.to_le_bytes();
+        *ve_supply = self.ve_supply.to_le_bytes();
+        *ve_epoch = self.ve_epoch.to_le_bytes();
+        *ve_fees_per_ve_a = self.ve_fees_per_ve_a.to_le_bytes();
+        *ve_fees_per_ve_b = self.ve_fees_per_ve_b.to_le_bytes();
+        *ve_pending_fees_a = self.ve_pending_fees_a.to_le_bytes();
+        *ve_pending_fees_b = self.ve_pending_fees_b.to_le_bytes();
+        *order_flow_batch_slots = self.order_flow_batch_slots.to_le_bytes();
+        order_flow_bid_token.copy_from_slice(self.order_flow_bid_token.as_ref());
+        *order_flow_batch = self.order_flow_batch.to_le_bytes();
+        order_flow_bidder.copy_from_slice(self.order_flow_bidder.as_ref());
+        order_flow_refund_account.copy_from_slice(self.order_flow_refund_account.as_ref());
+        *order_flow_bid = self.order_flow_bid.to_le_bytes();
+        *dynamic_amp_min = self.dynamic_amp_min.to_le_bytes();
+        *dynamic_amp_max = self.dynamic_amp_max.to_le_bytes();
+        *dynamic_amp_volatility_ceiling = self.dynamic_amp_volatility_ceiling.to_le_bytes();
+        *dynamic_amp_max_drift = self.dynamic_amp_max_drift.to_le_bytes();
+        *twap_price = self.twap_price.to_le_bytes();
+        *twap_volatility = self.twap_volatility.to_le_bytes();
+        *twap_slot = self.twap_slot.to_le_bytes();
+        *fee_buffer_bps = self.fee_buffer_bps.to_le_bytes();
+        *deposit_bonus_bps = self.deposit_bonus_bps.to_le_bytes();
+        *fee_buffer_a = self.fee_buffer_a.to_le_bytes();
+        *fee_buffer_b = self.fee_buffer_b.to_le_bytes();
+        *insurance_fee_bps = self.insurance_fee_bps.to_le_bytes();
+        insurance_authority.copy_from_slice(self.insurance_authority.as_ref());
+        *insurance_fund_a = self.insurance_fund_a.to_le_bytes();
+        *insurance_fund_b = self.insurance_fund_b.to_le_bytes();
+        *insurance_covered_a = self.insurance_covered_a.to_le_bytes();
+        *insurance_covered_b = self.insurance_covered_b.to_le_bytes();
+        *min_lp_mint = self.min_lp_mint.to_le_bytes();
+        *dust_threshold = self.dust_threshold.to_le_bytes();
+        *dust_sweep_slot = self.dust_sweep_slot.to_le_bytes();
+        *dust_sweep_count = self.dust_sweep_count.to_le_bytes();
+        liquidity_vault.copy_from_slice(self.liquidity_vault.as_ref());
+        *position_liquidity = self.position_liquidity.to_le_bytes();
+        *cumulative_position_liquidity = self.cumulative_position_liquidity.to_le_bytes();
+        *position_liquidity_ts = self.position_liquidity_ts.to_le_bytes();
+        *fee_report_epoch = self.fee_report_epoch.to_le_bytes();
+        *epoch_lp_fees_a = self.epoch_lp_fees_a.to_le_bytes();
+        *epoch_lp_fees_b = self.epoch_lp_fees_b.to_le_bytes();
+        *epoch_admin_fees_a = self.epoch_admin_fees_a.to_le_bytes();
+        *epoch_admin_fees_b = self.epoch_admin_fees_b.to_le_bytes();
+        *epoch_reflection_fees_a = self.epoch_reflection_fees_a.to_le_bytes();
+        *epoch_reflection_fees_b = self.epoch_reflection_fees_b.to_le_bytes();
+        *epoch_buyback_fees_a = self.epoch_buyback_fees_a.to_le_bytes();
+        *epoch_buyback_fees_b = self.epoch_buyback_fees_b.to_le_bytes();
+        *epoch_marketing_fees_a = self.epoch_marketing_fees_a.to_le_bytes();
+        *epoch_marketing_fees_b = self.epoch_marketing_fees_b.to_le_bytes();
+        *epoch_developer_fees_a = self.epoch_developer_fees_a.to_le_bytes();
+        *epoch_developer_fees_b = self.epoch_developer_fees_b.to_le_bytes();
+        *sequence = self.sequence.to_le_bytes();
+        custodian.copy_from_slice(self.custodian.as_ref());
+        *custodian_withdraw_threshold = self.custodian_withdraw_threshold.to_le_bytes();
+        credential_issuer.copy_from_slice(self.credential_issuer.as_ref());
+        self.admin_log.pack_into_slice(&mut admin_log[..]);
+        admin_fee_escrow_a[0] = self.admin_fee_escrow_a as u8;
+        admin_fee_escrow_b[0] = self.admin_fee_escrow_b as u8;
+        *price_a_cumulative = self.price_a_cumulative.to_le_bytes();
+        *price_b_cumulative = self.price_b_cumulative.to_le_bytes();
+        *price_cumulative_slot = self.price_cumulative_slot.to_le_bytes();
+        guardian_key.copy_from_slice(self.guardian_key.as_ref());
+        *protocol_pool_amount = self.protocol_pool_amount.to_le_bytes();
+        self.pending_fees.pack_into_slice(&mut pending_fees[..]);
+        *pending_fees_slot = self.pending_fees_slot.to_le_bytes();
+        self.custom_error_messages
+            .pack_into_slice(&mut custom_error_messages[..]);
+        reflection_lp_vault.copy_from_slice(self.reflection_lp_vault.as_ref());
+        reflection_vault_a.copy_from_slice(self.reflection_vault_a.as_ref());
+        reflection_vault_b.copy_from_slice(self.reflection_vault_b.as_ref());
+        *reflection_supply = self.reflection_supply.to_le_bytes();
+        *acc_reflection_per_lp_share_a = self.acc_reflection_per_lp_share_a.to_le_bytes();
+        *acc_reflection_per_lp_share_b = self.acc_reflection_per_lp_share_b.to_le_bytes();
+        *reflection_pending_a = self.reflection_pending_a.to_le_bytes();
+        *reflection_pending_b = self.reflection_pending_b.to_le_bytes();
+        buyback_mint.copy_from_slice(self.buyback_mint.as_ref());
+        buyback_vault_a.copy_from_slice(self.buyback_vault_a.as_ref());
+        buyback_vault_b.copy_from_slice(self.buyback_vault_b.as_ref());
+        *buyback_burned = self.buyback_burned.to_le_bytes();
+        marketing_wallet.copy_from_slice(self.marketing_wallet.as_ref());
+        developer_wallet.copy_from_slice(self.developer_wallet.as_ref());
+        *marketing_fees_accrued_a = self.marketing_fees_accrued_a.to_le_bytes();
+        *marketing_fees_accrued_b = self.marketing_fees_accrued_b.to_le_bytes();
+        *developer_fees_accrued_a = self.developer_fees_accrued_a.to_le_bytes();
+        *developer_fees_accrued_b = self.developer_fees_accrued_b.to_le_bytes();
+        config_hash.copy_from_slice(self.config_hash.as_ref());
+        *volatility_fee_ceiling = self.volatility_fee_ceiling.to_le_bytes();
+        *volatility_fee_max_multiplier_bps = self.volatility_fee_max_multiplier_bps.to_le_bytes();
+        *referral_fees_accrued_a = self.referral_fees_accrued_a.to_le_bytes();
+        *referral_fees_accrued_b = self.referral_fees_accrued_b.to_le_bytes();
+        *admin_fee_epoch_cap_a = self.admin_fee_epoch_cap_a.to_le_bytes();
+        *admin_fee_epoch_cap_b = self.admin_fee_epoch_cap_b.to_le_bytes();
+        *admin_fee_cap_epoch = self.admin_fee_cap_epoch.to_le_bytes();
+        *admin_fees_taken_a = self.admin_fees_taken_a.to_le_bytes();
+        *admin_fees_taken_b = self.admin_fees_taken_b.to_le_bytes();
+        curve_type[0] = self.curve_type as u8;
+        *price_bound = self.price_bound.to_le_bytes();
+        creator.copy_from_slice(self.creator.as_ref());
+        *creator_deposit_end_slot = self.creator_deposit_end_slot.to_le_bytes();
+        deposit_allowlist_enabled[0] = self.deposit_allowlist_enabled as u8;
+        freeze_authority_acknowledged[0] = self.freeze_authority_acknowledged as u8;
+        oracle_a.copy_from_slice(self.oracle_a.as_ref());
+        oracle_b.copy_from_slice(self.oracle_b.as_ref());
+        *oracle_max_staleness_slots = self.oracle_max_staleness_slots.to_le_bytes();
+        *oracle_max_confidence_bps = self.oracle_max_confidence_bps.to_le_bytes();
+        *oracle_max_deviation_bps = self.oracle_max_deviation_bps.to_le_bytes();
+        oracle_stale_policy[0] = self.oracle_stale_policy as u8;
+        *oracle_stale_band_bps = self.oracle_stale_band_bps.to_le_bytes();
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Number of admin actions kept in an [AdminLog](struct.AdminLog.html)
+pub const ADMIN_LOG_LEN: usize = 16;
 
-    #[test]
-    fn test_swap_info_packing() {
-        let nonce = 255;
-        let initial_amp_factor: u64 = 1;
-        let target_amp_factor: u64 = 1;
-        let start_ramp_ts: i64 = i64::MAX;
-        let stop_ramp_ts: i64 = i64::MAX;
-        let future_admin_deadline: i64 = i64::MAX;
-        let future_admin_key_raw = [1u8; 32];
-        let admin_key_raw = [2u8; 32];
-        let token_a_raw = [3u8; 32];
-        let token_b_raw = [4u8; 32];
-        let pool_mint_raw = [5u8; 32];
-        let token_a_mint_raw = [6u8; 32];
-        let token_b_mint_raw = [7u8; 32];
-        let admin_fee_key_a_raw = [8u8; 32];
-        let admin_fee_key_b_raw = [9u8; 32];
-        let admin_key = Pubkey::new_from_array(admin_key_raw);
-        let future_admin_key = Pubkey::new_from_array(future_admin_key_raw);
-        let token_a = Pubkey::new_from_array(token_a_raw);
-        let token_b = Pubkey::new_from_array(token_b_raw);
-        let pool_mint = Pubkey::new_from_array(pool_mint_raw);
-        let token_a_mint = Pubkey::new_from_array(token_a_mint_raw);
-        let token_b_mint = Pubkey::new_from_array(token_b_mint_raw);
-        let admin_fee_key_a = Pubkey::new_from_array(admin_fee_key_a_raw);
-        let admin_fee_key_b = Pubkey::new_from_array(admin_fee_key_b_raw);
-        let admin_trade_fee_numerator = 1;
-        let admin_trade_fee_denominator = 2;
-        let admin_withdraw_fee_numerator = 3;
-        let admin_withdraw_fee_denominator = 4;
-        let trade_fee_numerator = 5;
-        let trade_fee_denominator = 6;
-        let withdraw_fee_numerator = 7;
-        let withdraw_fee_denominator = 8;
-        let reflection_fee_numerator = 9;
-        let reflection_fee_denominator = 10;
-        let buyback_fee_numerator = 11;
-        let buyback_fee_denominator = 12;
-        let marketing_fee_numerator = 13;
-        let marketing_fee_denominator = 14;
-        let developer_fee_numerator = 15;
-        let developer_fee_denominator = 16;
-        let fees = Fees {
-            admin_trade_fee_numerator,
-            admin_trade_fee_denominator,
-            admin_withdraw_fee_numerator,
-            admin_withdraw_fee_denominator,
-            trade_fee_numerator,
-            trade_fee_denominator,
-            withdraw_fee_numerator,
-            withdraw_fee_denominator,
-            reflection_fee_numerator,
-            reflection_fee_denominator,
-            buyback_fee_numerator,
-            buyback_fee_denominator,
-            marketing_fee_numerator,
-            marketing_fee_denominator,
-            developer_fee_numerator,
-            developer_fee_denominator,
-        };
+/// Admin instruction applied to a pool
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AdminAction {
+    /// Instruction tag, zero for an empty log entry
+    pub tag: u8,
+    /// Slot the instruction was applied in
+    pub slot: Slot,
+    /// First signer of the instruction
+    pub signer: Pubkey,
+}
 
-        let is_initialized = true;
-        let is_paused = false;
-        let swap_info = SwapInfo {
-            is_initialized,
-            is_paused,
-            nonce,
-            initial_amp_factor,
-            target_amp_factor,
-            start_ramp_ts,
-            stop_ramp_ts,
-            future_admin_deadline,
-            future_admin_key,
-            admin_key,
-            token_a,
-            token_b,
-            pool_mint,
-            token_a_mint,
-            token_b_mint,
-            admin_fee_key_a,
-            admin_fee_key_b,
-            fees,
-        };
+impl Sealed for AdminAction {}
+impl Pack for AdminAction {
+    const LEN: usize = 41;
 
-        let mut packed = [0u8; SwapInfo::LEN];
-        SwapInfo::pack(swap_info, &mut packed).unwrap();
-        let unpacked = SwapInfo::unpack(&packed).unwrap();
-        assert_eq!(swap_info, unpacked);
+    /// Unpacks a byte buffer into a [AdminAction](struct.AdminAction.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 41];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (tag, slot, signer) = array_refs![input, 1, 8, 32];
+        Ok(Self {
+            tag: tag[0],
+            slot: Slot::from_le_bytes(*slot),
+            signer: Pubkey::new_from_array(*signer),
+        })
+    }
 
-        let mut packed = vec![];
-        packed.push(1 as u8); // is_initialized
-        packed.push(0 as u8); // is_paused
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 41];
+        let (tag, slot, signer) = mut_array_refs![output, 1, 8, 32];
+        tag[0] = self.tag;
+        *slot = self.slot.to_le_bytes();
+        signer.copy_from_slice(self.signer.as_ref());
+    }
+}
+
+/// Ring buffer of the last [ADMIN_LOG_LEN](constant.ADMIN_LOG_LEN.html) admin actions
+/// applied to a pool, so that parameter changes can be audited from the pool state
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AdminLog {
+    /// Index of the entry the next action is written to
+    pub head: u8,
+    /// Recorded actions, the oldest one is overwritten once the log is full
+    pub actions: [AdminAction; ADMIN_LOG_LEN],
+}
+
+impl AdminLog {
+    /// Records `action`, overwriting the oldest one once the log is full
+    pub fn record(&mut self, action: AdminAction) {
+        let head = self.head as usize % ADMIN_LOG_LEN;
+        self.actions[head] = action;
+        self.head = ((head + 1) % ADMIN_LOG_LEN) as u8;
+    }
+
+    /// Recorded actions, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &AdminAction> {
+        let (newest, oldest) = self.actions.split_at(self.head as usize % ADMIN_LOG_LEN);
+        oldest
+            .iter()
+            .chain(newest.iter())
+            .filter(|action| action.tag != 0)
+    }
+}
+
+impl Sealed for AdminLog {}
+impl Pack for AdminLog {
+    const LEN: usize = 1 + ADMIN_LOG_LEN * AdminAction::LEN;
+
+    /// Unpacks a byte buffer into a [AdminLog](struct.AdminLog.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, AdminLog::LEN];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (head, actions) = array_refs![input, 1, ADMIN_LOG_LEN * AdminAction::LEN];
+        if head[0] as usize >= ADMIN_LOG_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut log = Self {
+            head: head[0],
+            ..Self::default()
+        };
+        for (action, input) in log.actions.iter_mut().zip(actions.chunks(AdminAction::LEN)) {
+            *action = AdminAction::unpack_from_slice(input)?;
+        }
+        Ok(log)
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, AdminLog::LEN];
+        let (head, actions) = mut_array_refs![output, 1, ADMIN_LOG_LEN * AdminAction::LEN];
+        head[0] = self.head;
+        for (action, output) in self
+            .actions
+            .iter()
+            .zip(actions.chunks_mut(AdminAction::LEN))
+        {
+            action.pack_into_slice(output);
+        }
+    }
+}
+
+/// Number of custom error messages a pool registers at most
+pub const MAX_CUSTOM_ERROR_MESSAGES: usize = 4;
+
+/// Maximum length of a custom error message, in bytes
+pub const MAX_CUSTOM_ERROR_MESSAGE_LEN: usize = 64;
+
+/// Reason logged along with an error of a pool
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CustomErrorMessage {
+    /// Code of the `SwapError` the message is logged with
+    pub error_code: u32,
+    /// Length of the message, zero for an empty registry entry
+    pub len: u8,
+    /// UTF-8 message, padded with zeroes
+    pub message: [u8; MAX_CUSTOM_ERROR_MESSAGE_LEN],
+}
+
+impl Default for CustomErrorMessage {
+    fn default() -> Self {
+        Self {
+            error_code: 0,
+            len: 0,
+            message: [0; MAX_CUSTOM_ERROR_MESSAGE_LEN],
+        }
+    }
+}
+
+impl Sealed for CustomErrorMessage {}
+impl Pack for CustomErrorMessage {
+    const LEN: usize = 69;
+
+    /// Unpacks a byte buffer into a [CustomErrorMessage](struct.CustomErrorMessage.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 69];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (error_code, len, message) = array_refs![input, 4, 1, MAX_CUSTOM_ERROR_MESSAGE_LEN];
+        if len[0] as usize > MAX_CUSTOM_ERROR_MESSAGE_LEN {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        Ok(Self {
+            error_code: u32::from_le_bytes(*error_code),
+            len: len[0],
+            message: *message,
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 69];
+        let (error_code, len, message) =
+            mut_array_refs![output, 4, 1, MAX_CUSTOM_ERROR_MESSAGE_LEN];
+        *error_code = self.error_code.to_le_bytes();
+        len[0] = self.len;
+        *message = self.message;
+    }
+}
+
+/// Short reasons the admin registers for errors of a pool, such as a pool
+/// migrating away while paused, logged along with the errors for wallets to show
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CustomErrorMessages {
+    /// Registered messages, at most one per error code
+    pub messages: [CustomErrorMessage; MAX_CUSTOM_ERROR_MESSAGES],
+}
+
+impl CustomErrorMessages {
+    /// Message registered for `error_code`, if any
+    pub fn message(&self, error_code: u32) -> Option<&str> {
+        let entry = self
+            .messages
+            .iter()
+            .find(|entry| entry.len != 0 && entry.error_code == error_code)?;
+        std::str::from_utf8(&entry.message[..entry.len as usize]).ok()
+    }
+
+    /// Registers `message` for `error_code`, replacing the message already
+    /// registered for it. An empty message unregisters it. `None` if the message
+    /// is too long or the registry is full.
+    pub fn set(&mut self, error_code: u32, message: &str) -> Option<()> {
+        if message.len() > MAX_CUSTOM_ERROR_MESSAGE_LEN {
+            return None;
+        }
+        let index = self
+            .messages
+            .iter()
+            .position(|entry| entry.len != 0 && entry.error_code == error_code);
+        if message.is_empty() {
+            if let Some(index) = index {
+                self.messages[index] = CustomErrorMessage::default();
+            }
+            return Some(());
+        }
+        let index = index.or_else(|| self.messages.iter().position(|entry| entry.len == 0))?;
+        let mut entry = CustomErrorMessage {
+            error_code,
+            len: message.len() as u8,
+            ..CustomErrorMessage::default()
+        };
+        entry.message[..message.len()].copy_from_slice(message.as_bytes());
+        self.messages[index] = entry;
+        Some(())
+    }
+}
+
+impl Sealed for CustomErrorMessages {}
+impl Pack for CustomErrorMessages {
+    const LEN: usize = MAX_CUSTOM_ERROR_MESSAGES * CustomErrorMessage::LEN;
+
+    /// Unpacks a byte buffer into a [CustomErrorMessages](struct.CustomErrorMessages.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, CustomErrorMessages::LEN];
+        let mut messages = Self::default();
+        for (entry, input) in messages
+            .messages
+            .iter_mut()
+            .zip(input.chunks(CustomErrorMessage::LEN))
+        {
+            *entry = CustomErrorMessage::unpack_from_slice(input)?;
+        }
+        Ok(messages)
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, CustomErrorMessages::LEN];
+        for (entry, output) in self
+            .messages
+            .iter()
+            .zip(output.chunks_mut(CustomErrorMessage::LEN))
+        {
+            entry.pack_into_slice(output);
+        }
+    }
+}
+
+/// Record of a single swap, stored in a program address owned by the signer
+/// that requested it.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SwapReceipt {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap the trade was executed against
+    pub swap: Pubkey,
+    /// Signer the receipt belongs to, receives the rent back on close
+    pub owner: Pubkey,
+    /// Mint of the token sold
+    pub source_mint: Pubkey,
+    /// Mint of the token bought
+    pub destination_mint: Pubkey,
+    /// Amount of source token paid in
+    pub amount_in: u64,
+    /// Amount of destination token paid out
+    pub amount_out: u64,
+    /// Total trade fee, in destination token
+    pub fee_amount: u64,
+    /// Slot the swap was executed in
+    pub slot: u64,
+}
+
+impl Sealed for SwapReceipt {}
+impl IsInitialized for SwapReceipt {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SwapReceipt {
+    const LEN: usize = layout::swap_receipt::LEN;
+
+    /// Unpacks a byte buffer into a [SwapReceipt](struct.SwapReceipt.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 161];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            swap,
+            owner,
+            source_mint,
+            destination_mint,
+            amount_in,
+            amount_out,
+            fee_amount,
+            slot,
+        ) = array_refs![input, 1, 32, 32, 32, 32, 8, 8, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            owner: Pubkey::new_from_array(*owner),
+            source_mint: Pubkey::new_from_array(*source_mint),
+            destination_mint: Pubkey::new_from_array(*destination_mint),
+            amount_in: u64::from_le_bytes(*amount_in),
+            amount_out: u64::from_le_bytes(*amount_out),
+            fee_amount: u64::from_le_bytes(*fee_amount),
+            slot: u64::from_le_bytes(*slot),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 161];
+        let (
+            is_initialized,
+            swap,
+            owner,
+            source_mint,
+            destination_mint,
+            amount_in,
+            amount_out,
+            fee_amount,
+            slot,
+        ) = mut_array_refs![output, 1, 32, 32, 32, 32, 8, 8, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        source_mint.copy_from_slice(self.source_mint.as_ref());
+        destination_mint.copy_from_slice(self.destination_mint.as_ref());
+        *amount_in = self.amount_in.to_le_bytes();
+        *amount_out = self.amount_out.to_le_bytes();
+        *fee_amount = self.fee_amount.to_le_bytes();
+        *slot = self.slot.to_le_bytes();
+    }
+}
+
+/// Temporary key allowed to execute a subset of the admin instructions of a swap
+/// until an expiry slot, stored in a program address created by the admin.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AdminSession {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap the session applies to
+    pub swap: Pubkey,
+    /// Admin that created the session, the session is void once the admin changes
+    pub admin: Pubkey,
+    /// Key signing admin instructions in place of the admin
+    pub session_key: Pubkey,
+    /// Bitmask of the `AdminSession::PERMISSION_*` instructions allowed
+    pub permissions: u8,
+    /// Last slot the session is valid in
+    pub expiry_slot: u64,
+}
+
+impl AdminSession {
+    /// Allows RampA and StopRampA
+    pub const PERMISSION_RAMP_A: u8 = 1 << 0;
+    /// Allows Pause and Unpause
+    pub const PERMISSION_PAUSE: u8 = 1 << 1;
+    /// Allows SetFeeAccount
+    pub const PERMISSION_SET_FEE_ACCOUNT: u8 = 1 << 2;
+    /// Allows SetNewFees, ApplyNewFees and CancelPendingFees
+    pub const PERMISSION_SET_NEW_FEES: u8 = 1 << 3;
+    /// All the permissions a session may be granted
+    pub const ALL_PERMISSIONS: u8 = Self::PERMISSION_RAMP_A
+        | Self::PERMISSION_PAUSE
+        | Self::PERMISSION_SET_FEE_ACCOUNT
+        | Self::PERMISSION_SET_NEW_FEES;
+
+    /// Whether the session grants `permission`
+    pub fn has_permission(&self, permission: u8) -> bool {
+        self.permissions & permission == permission
+    }
+}
+
+impl Sealed for AdminSession {}
+impl IsInitialized for AdminSession {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for AdminSession {
+    const LEN: usize = layout::admin_session::LEN;
+
+    /// Unpacks a byte buffer into a [AdminSession](struct.AdminSession.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 106];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, swap, admin, session_key, permissions, expiry_slot) =
+            array_refs![input, 1, 32, 32, 32, 1, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            admin: Pubkey::new_from_array(*admin),
+            session_key: Pubkey::new_from_array(*session_key),
+            permissions: permissions[0],
+            expiry_slot: u64::from_le_bytes(*expiry_slot),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 106];
+        let (is_initialized, swap, admin, session_key, permissions, expiry_slot) =
+            mut_array_refs![output, 1, 32, 32, 32, 1, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        admin.copy_from_slice(self.admin.as_ref());
+        session_key.copy_from_slice(self.session_key.as_ref());
+        permissions[0] = self.permissions;
+        *expiry_slot = self.expiry_slot.to_le_bytes();
+    }
+}
+
+/// Reflection fees committed to holders as a merkle root of their entitlements
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReflectionDistribution {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap the reflection fees were collected by
+    pub swap: Pubkey,
+    /// Epoch of the distribution
+    pub epoch: u64,
+    /// Token account owned by the swap authority holding the distributed fees
+    pub vault: Pubkey,
+    /// Root of the tree of `merkle::reflection_leaf` entitlements
+    pub merkle_root: [u8; 32],
+    /// Sum of all entitlements
+    pub total_amount: u64,
+    /// Amount claimed so far
+    pub claimed_amount: u64,
+}
+
+impl Sealed for ReflectionDistribution {}
+impl IsInitialized for ReflectionDistribution {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ReflectionDistribution {
+    const LEN: usize = layout::reflection_distribution::LEN;
+
+    /// Unpacks a byte buffer into a [ReflectionDistribution](struct.ReflectionDistribution.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 121];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, swap, epoch, vault, merkle_root, total_amount, claimed_amount) =
+            array_refs![input, 1, 32, 8, 32, 32, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            epoch: u64::from_le_bytes(*epoch),
+            vault: Pubkey::new_from_array(*vault),
+            merkle_root: *merkle_root,
+            total_amount: u64::from_le_bytes(*total_amount),
+            claimed_amount: u64::from_le_bytes(*claimed_amount),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 121];
+        let (is_initialized, swap, epoch, vault, merkle_root, total_amount, claimed_amount) =
+            mut_array_refs![output, 1, 32, 8, 32, 32, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        *epoch = self.epoch.to_le_bytes();
+        vault.copy_from_slice(self.vault.as_ref());
+        *merkle_root = self.merkle_root;
+        *total_amount = self.total_amount.to_le_bytes();
+        *claimed_amount = self.claimed_amount.to_le_bytes();
+    }
+}
+
+/// Marks an entitlement of a reflection distribution as claimed
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReflectionClaim {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Distribution the entitlement belongs to
+    pub distribution: Pubkey,
+    /// Index of the entitlement in the distribution
+    pub index: u64,
+    /// Holder that claimed the entitlement
+    pub claimant: Pubkey,
+    /// Amount claimed
+    pub amount: u64,
+}
+
+impl Sealed for ReflectionClaim {}
+impl IsInitialized for ReflectionClaim {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ReflectionClaim {
+    const LEN: usize = layout::reflection_claim::LEN;
+
+    /// Unpacks a byte buffer into a [ReflectionClaim](struct.ReflectionClaim.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 81];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, distribution, index, claimant, amount) =
+            array_refs![input, 1, 32, 8, 32, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            distribution: Pubkey::new_from_array(*distribution),
+            index: u64::from_le_bytes(*index),
+            claimant: Pubkey::new_from_array(*claimant),
+            amount: u64::from_le_bytes(*amount),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 81];
+        let (is_initialized, distribution, index, claimant, amount) =
+            mut_array_refs![output, 1, 32, 8, 32, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        distribution.copy_from_slice(self.distribution.as_ref());
+        *index = self.index.to_le_bytes();
+        claimant.copy_from_slice(self.claimant.as_ref());
+        *amount = self.amount.to_le_bytes();
+    }
+}
+
+/// Pool tokens a holder deposited to vote in the governance realm of a swap
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GovernanceDeposit {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap the pool tokens belong to
+    pub swap: Pubkey,
+    /// Holder the deposit belongs to
+    pub owner: Pubkey,
+    /// Pool tokens deposited
+    pub amount: u64,
+    /// Unix timestamp the deposit may be withdrawn from
+    pub unlock_ts: i64,
+}
+
+impl Sealed for GovernanceDeposit {}
+impl IsInitialized for GovernanceDeposit {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for GovernanceDeposit {
+    const LEN: usize = layout::governance_deposit::LEN;
+
+    /// Unpacks a byte buffer into a [GovernanceDeposit](struct.GovernanceDeposit.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 81];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, swap, owner, amount, unlock_ts) = array_refs![input, 1, 32, 32, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            owner: Pubkey::new_from_array(*owner),
+            amount: u64::from_le_bytes(*amount),
+            unlock_ts: i64::from_le_bytes(*unlock_ts),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 81];
+        let (is_initialized, swap, owner, amount, unlock_ts) =
+            mut_array_refs![output, 1, 32, 32, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *amount = self.amount.to_le_bytes();
+        *unlock_ts = self.unlock_ts.to_le_bytes();
+    }
+}
+
+/// Pool tokens a holder locked in the vote-escrow of a swap for a veLP balance
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VeLock {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap the pool tokens belong to
+    pub swap: Pubkey,
+    /// Holder the lock belongs to
+    pub owner: Pubkey,
+    /// Pool tokens locked
+    pub amount: u64,
+    /// Unix timestamp the lock may be withdrawn from
+    pub unlock_ts: i64,
+    /// veLP balance of the lock
+    pub ve_balance: u64,
+    /// Token A fees owed to the lock that were already claimed or were distributed
+    /// before it was created
+    pub fees_debt_a: u128,
+    /// Token B fees owed to the lock that were already claimed or were distributed
+    /// before it was created
+    pub fees_debt_b: u128,
+    /// Share of the veLP balance voted for gauges, in basis points
+    pub gauge_vote_bps: u16,
+}
+
+impl Sealed for VeLock {}
+impl IsInitialized for VeLock {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for VeLock {
+    const LEN: usize = layout::ve_lock::LEN;
+
+    /// Unpacks a byte buffer into a [VeLock](struct.VeLock.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 123];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            swap,
+            owner,
+            amount,
+            unlock_ts,
+            ve_balance,
+            fees_debt_a,
+            fees_debt_b,
+            gauge_vote_bps,
+        ) = array_refs![input, 1, 32, 32, 8, 8, 8, 16, 16, 2];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            owner: Pubkey::new_from_array(*owner),
+            amount: u64::from_le_bytes(*amount),
+            unlock_ts: i64::from_le_bytes(*unlock_ts),
+            ve_balance: u64::from_le_bytes(*ve_balance),
+            fees_debt_a: u128::from_le_bytes(*fees_debt_a),
+            fees_debt_b: u128::from_le_bytes(*fees_debt_b),
+            gauge_vote_bps: u16::from_le_bytes(*gauge_vote_bps),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 123];
+        let (
+            is_initialized,
+            swap,
+            owner,
+            amount,
+            unlock_ts,
+            ve_balance,
+            fees_debt_a,
+            fees_debt_b,
+            gauge_vote_bps,
+        ) = mut_array_refs![output, 1, 32, 32, 8, 8, 8, 16, 16, 2];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *amount = self.amount.to_le_bytes();
+        *unlock_ts = self.unlock_ts.to_le_bytes();
+        *ve_balance = self.ve_balance.to_le_bytes();
+        *fees_debt_a = self.fees_debt_a.to_le_bytes();
+        *fees_debt_b = self.fees_debt_b.to_le_bytes();
+        *gauge_vote_bps = self.gauge_vote_bps.to_le_bytes();
+    }
+}
+
+/// Seconds a parameter snapshot must be public before it can be restored
+pub const SNAPSHOT_RESTORE_DELAY: i64 = 86_400;
+
+/// Backup of the parameter block of a swap, restorable by the admin after
+/// `SNAPSHOT_RESTORE_DELAY`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ParameterSnapshot {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap the parameters were copied from
+    pub swap: Pubkey,
+    /// Unix timestamp the snapshot was taken at
+    pub snapshot_ts: i64,
+
+    /// Initial amplification coefficient (A)
+    pub initial_amp_factor: u64,
+    /// Target amplification coefficient (A)
+    pub target_amp_factor: u64,
+    /// Ramp A start timestamp
+    pub start_ramp_ts: i64,
+    /// Ramp A stop timestamp
+    pub stop_ramp_ts: i64,
+    /// Fees
+    pub fees: Fees,
+    /// Fee tier preset the fees were derived from
+    pub fee_tier: FeeTier,
+    /// Admin fee account for token a
+    pub admin_fee_key_a: Pubkey,
+    /// Admin fee account for token b
+    pub admin_fee_key_b: Pubkey,
+    /// Share of harvested admin fees paid to the harvest caller, in basis points
+    pub harvest_tip_bps: u16,
+}
+
+impl ParameterSnapshot {
+    /// Copies the parameter block of `swap_info`
+    pub fn new(swap: Pubkey, swap_info: &SwapInfo, snapshot_ts: i64) -> Self {
+        Self {
+            is_initialized: true,
+            swap,
+            snapshot_ts,
+            initial_amp_factor: swap_info.initial_amp_factor,
+            target_amp_factor: swap_info.target_amp_factor,
+            start_ramp_ts: swap_info.start_ramp_ts,
+            stop_ramp_ts: swap_info.stop_ramp_ts,
+            fees: swap_info.fees,
+            fee_tier: swap_info.fee_tier,
+            admin_fee_key_a: swap_info.admin_fee_key_a,
+            admin_fee_key_b: swap_info.admin_fee_key_b,
+            harvest_tip_bps: swap_info.harvest_tip_bps,
+        }
+    }
+
+    /// Writes the parameter block back into `swap_info`, staging the fees to apply
+    /// from `pending_fees_slot`. The Amp. Coefficient and its ramp are left to the
+    /// caller, the ramp timestamps of the snapshot being past.
+    pub fn restore(&self, swap_info: &mut SwapInfo, pending_fees_slot: Slot) {
+        swap_info.pending_fees = self.fees;
+        swap_info.pending_fees_slot = pending_fees_slot;
+        swap_info.fee_tier = self.fee_tier;
+        swap_info.admin_fee_key_a = self.admin_fee_key_a;
+        swap_info.admin_fee_key_b = self.admin_fee_key_b;
+        swap_info.harvest_tip_bps = self.harvest_tip_bps;
+    }
+}
+
+impl Sealed for ParameterSnapshot {}
+impl IsInitialized for ParameterSnapshot {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ParameterSnapshot {
+    const LEN: usize = layout::parameter_snapshot::LEN;
+
+    /// Unpacks a byte buffer into a [ParameterSnapshot](struct.ParameterSnapshot.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 310];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            swap,
+            snapshot_ts,
+            initial_amp_factor,
+            target_amp_factor,
+            start_ramp_ts,
+            stop_ramp_ts,
+            fees,
+            fee_tier,
+            admin_fee_key_a,
+            admin_fee_key_b,
+            harvest_tip_bps,
+        ) = array_refs![input, 1, 32, 8, 8, 8, 8, 8, 170, 1, 32, 32, 2];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            snapshot_ts: i64::from_le_bytes(*snapshot_ts),
+            initial_amp_factor: u64::from_le_bytes(*initial_amp_factor),
+            target_amp_factor: u64::from_le_bytes(*target_amp_factor),
+            start_ramp_ts: i64::from_le_bytes(*start_ramp_ts),
+            stop_ramp_ts: i64::from_le_bytes(*stop_ramp_ts),
+            fees: Fees::unpack_from_slice(fees)?,
+            fee_tier: FeeTier::try_from(fee_tier[0])
+                .map_err(|_| ProgramError::InvalidAccountData)?,
+            admin_fee_key_a: Pubkey::new_from_array(*admin_fee_key_a),
+            admin_fee_key_b: Pubkey::new_from_array(*admin_fee_key_b),
+            harvest_tip_bps: u16::from_le_bytes(*harvest_tip_bps),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 310];
+        let (
+            is_initialized,
+            swap,
+            snapshot_ts,
+            initial_amp_factor,
+            target_amp_factor,
+            start_ramp_ts,
+            stop_ramp_ts,
+            fees,
+            fee_tier,
+            admin_fee_key_a,
+            admin_fee_key_b,
+            harvest_tip_bps,
+        ) = mut_array_refs![output, 1, 32, 8, 8, 8, 8, 8, 170, 1, 32, 32, 2];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        *snapshot_ts = self.snapshot_ts.to_le_bytes();
+        *initial_amp_factor = self.initial_amp_factor.to_le_bytes();
+        *target_amp_factor = self.target_amp_factor.to_le_bytes();
+        *start_ramp_ts = self.start_ramp_ts.to_le_bytes();
+        *stop_ramp_ts = self.stop_ramp_ts.to_le_bytes();
+        self.fees.pack_into_slice(&mut fees[..]);
+        fee_tier[0] = self.fee_tier as u8;
+        admin_fee_key_a.copy_from_slice(self.admin_fee_key_a.as_ref());
+        admin_fee_key_b.copy_from_slice(self.admin_fee_key_b.as_ref());
+        *harvest_tip_bps = self.harvest_tip_bps.to_le_bytes();
+    }
+}
+
+/// Tokens owed by a withdrawal spanning several transactions. The pool tokens are
+/// burned when the withdrawal begins; each token is then paid out separately.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct WithdrawContinuation {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap the tokens are withdrawn from
+    pub swap: Pubkey,
+    /// Holder that burned the pool tokens, receives the rent back on completion
+    pub owner: Pubkey,
+    /// Token A Account the withdrawal is paid to
+    pub destination_a: Pubkey,
+    /// Token B Account the withdrawal is paid to
+    pub destination_b: Pubkey,
+    /// Token A still owed, excluding the admin fee
+    pub token_a_amount: u64,
+    /// Token A admin fee still owed
+    pub token_a_admin_fee: u64,
+    /// Token B still owed, excluding the admin fee
+    pub token_b_amount: u64,
+    /// Token B admin fee still owed
+    pub token_b_admin_fee: u64,
+}
+
+impl Sealed for WithdrawContinuation {}
+impl IsInitialized for WithdrawContinuation {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for WithdrawContinuation {
+    const LEN: usize = layout::withdraw_continuation::LEN;
+
+    /// Unpacks a byte buffer into a [WithdrawContinuation](struct.WithdrawContinuation.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 161];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            swap,
+            owner,
+            destination_a,
+            destination_b,
+            token_a_amount,
+            token_a_admin_fee,
+            token_b_amount,
+            token_b_admin_fee,
+        ) = array_refs![input, 1, 32, 32, 32, 32, 8, 8, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            owner: Pubkey::new_from_array(*owner),
+            destination_a: Pubkey::new_from_array(*destination_a),
+            destination_b: Pubkey::new_from_array(*destination_b),
+            token_a_amount: u64::from_le_bytes(*token_a_amount),
+            token_a_admin_fee: u64::from_le_bytes(*token_a_admin_fee),
+            token_b_amount: u64::from_le_bytes(*token_b_amount),
+            token_b_admin_fee: u64::from_le_bytes(*token_b_admin_fee),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 161];
+        let (
+            is_initialized,
+            swap,
+            owner,
+            destination_a,
+            destination_b,
+            token_a_amount,
+            token_a_admin_fee,
+            token_b_amount,
+            token_b_admin_fee,
+        ) = mut_array_refs![output, 1, 32, 32, 32, 32, 8, 8, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        destination_a.copy_from_slice(self.destination_a.as_ref());
+        destination_b.copy_from_slice(self.destination_b.as_ref());
+        *token_a_amount = self.token_a_amount.to_le_bytes();
+        *token_a_admin_fee = self.token_a_admin_fee.to_le_bytes();
+        *token_b_amount = self.token_b_amount.to_le_bytes();
+        *token_b_admin_fee = self.token_b_admin_fee.to_le_bytes();
+    }
+}
+
+/// Pool tokens a holder keeps in a liquidity position of a swap, with the time
+/// they were held for. Incentive programs read the time-weighted average liquidity
+/// of a position between two observations of its cumulative liquidity, which
+/// pool tokens only add to for as long as they stay in the position.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LiquidityPosition {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap the pool tokens belong to
+    pub swap: Pubkey,
+    /// Holder the position belongs to
+    pub owner: Pubkey,
+    /// Pool tokens held in the position
+    pub amount: u64,
+    /// Pool tokens held in the position, accumulated over seconds
+    pub cumulative_liquidity: u128,
+    /// Unix timestamp of the last checkpoint
+    pub checkpoint_ts: i64,
+}
+
+impl LiquidityPosition {
+    /// Accumulates the pool tokens held in the position up to `now`
+    pub fn checkpoint(&mut self, now: i64) -> Option<()> {
+        self.cumulative_liquidity = self.cumulative_liquidity_at(now)?;
+        self.checkpoint_ts = self.checkpoint_ts.max(now);
+        Some(())
+    }
+
+    /// Cumulative liquidity of the position at `now`, as if checkpointed then
+    pub fn cumulative_liquidity_at(&self, now: i64) -> Option<u128> {
+        accumulate_liquidity(
+            self.cumulative_liquidity,
+            self.amount,
+            self.checkpoint_ts,
+            now,
+        )
+    }
+}
+
+impl Sealed for LiquidityPosition {}
+impl IsInitialized for LiquidityPosition {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for LiquidityPosition {
+    const LEN: usize = layout::liquidity_position::LEN;
+
+    /// Unpacks a byte buffer into a [LiquidityPosition](struct.LiquidityPosition.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 97];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, swap, owner, amount, cumulative_liquidity, checkpoint_ts) =
+            array_refs![input, 1, 32, 32, 8, 16, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            owner: Pubkey::new_from_array(*owner),
+            amount: u64::from_le_bytes(*amount),
+            cumulative_liquidity: u128::from_le_bytes(*cumulative_liquidity),
+            checkpoint_ts: i64::from_le_bytes(*checkpoint_ts),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 97];
+        let (is_initialized, swap, owner, amount, cumulative_liquidity, checkpoint_ts) =
+            mut_array_refs![output, 1, 32, 32, 8, 16, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *amount = self.amount.to_le_bytes();
+        *cumulative_liquidity = self.cumulative_liquidity.to_le_bytes();
+        *checkpoint_ts = self.checkpoint_ts.to_le_bytes();
+    }
+}
+
+/// Pool tokens a holder deposited to share the reflection fees of a swap, pro rata
+/// to the pool tokens of all reflection positions.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReflectionPosition {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Swap the pool tokens belong to
+    pub swap: Pubkey,
+    /// Holder the position belongs to
+    pub owner: Pubkey,
+    /// Pool tokens held in the position
+    pub amount: u64,
+    /// Token A reflection fees owed to the pool tokens of the position that were
+    /// already counted or accrued before they were deposited
+    pub reflection_debt_a: u128,
+    /// Token B reflection fees owed to the pool tokens of the position that were
+    /// already counted or accrued before they were deposited
+    pub reflection_debt_b: u128,
+    /// Token A reflection fees counted and not claimed yet
+    pub reflection_owed_a: u64,
+    /// Token B reflection fees counted and not claimed yet
+    pub reflection_owed_b: u64,
+}
+
+impl ReflectionPosition {
+    /// Counts the reflection fees accrued to the position up to
+    /// `acc_reflection_per_lp_share_(a|b)` and sets its pool tokens to `amount`,
+    /// which only share the fees accrued from then on.
+    pub fn set_amount(
+        &mut self,
+        amount: u64,
+        acc_reflection_per_lp_share_a: u128,
+        acc_reflection_per_lp_share_b: u128,
+    ) -> Option<()> {
+        for (acc_reflection_per_lp_share, debt, owed) in [
+            (
+                acc_reflection_per_lp_share_a,
+                &mut self.reflection_debt_a,
+                &mut self.reflection_owed_a,
+            ),
+            (
+                acc_reflection_per_lp_share_b,
+                &mut self.reflection_debt_b,
+                &mut self.reflection_owed_b,
+            ),
+        ] {
+            let accrued =
+                reflection_owed(self.amount, acc_reflection_per_lp_share)?.checked_sub(*debt)?;
+            *owed = owed.checked_add(u64::try_from(accrued).ok()?)?;
+            *debt = reflection_owed(amount, acc_reflection_per_lp_share)?;
+        }
+        self.amount = amount;
+        Some(())
+    }
+}
+
+impl Sealed for ReflectionPosition {}
+impl IsInitialized for ReflectionPosition {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ReflectionPosition {
+    const LEN: usize = layout::reflection_position::LEN;
+
+    /// Unpacks a byte buffer into a [ReflectionPosition](struct.ReflectionPosition.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 121];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            swap,
+            owner,
+            amount,
+            reflection_debt_a,
+            reflection_debt_b,
+            reflection_owed_a,
+            reflection_owed_b,
+        ) = array_refs![input, 1, 32, 32, 8, 16, 16, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            swap: Pubkey::new_from_array(*swap),
+            owner: Pubkey::new_from_array(*owner),
+            amount: u64::from_le_bytes(*amount),
+            reflection_debt_a: u128::from_le_bytes(*reflection_debt_a),
+            reflection_debt_b: u128::from_le_bytes(*reflection_debt_b),
+            reflection_owed_a: u64::from_le_bytes(*reflection_owed_a),
+            reflection_owed_b: u64::from_le_bytes(*reflection_owed_b),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 121];
+        let (
+            is_initialized,
+            swap,
+            owner,
+            amount,
+            reflection_debt_a,
+            reflection_debt_b,
+            reflection_owed_a,
+            reflection_owed_b,
+        ) = mut_array_refs![output, 1, 32, 32, 8, 16, 16, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        swap.copy_from_slice(self.swap.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *amount = self.amount.to_le_bytes();
+        *reflection_debt_a = self.reflection_debt_a.to_le_bytes();
+        *reflection_debt_b = self.reflection_debt_b.to_le_bytes();
+        *reflection_owed_a = self.reflection_owed_a.to_le_bytes();
+        *reflection_owed_b = self.reflection_owed_b.to_le_bytes();
+    }
+}
+
+/// Maximum TWAP order price deviation, in basis points
+pub const MAX_TWAP_PRICE_DEVIATION_BPS: u16 = 10_000;
+
+/// Order swapping an escrowed amount in equal slices, one slice at most every
+/// `slice_interval_slots`, each at a price no worse than the pool TWAP since the
+/// previous slice minus the maximum deviation. Stored in a program address that
+/// owns the escrow token account.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TwapOrder {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Nonce used to create the order program address
+    pub nonce: u8,
+    /// Swap the slices are executed against
+    pub swap: Pubkey,
+    /// Signer the order belongs to, receives the rent back on cancel
+    pub owner: Pubkey,
+    /// Order identifier chosen by the owner, unique per swap and owner
+    pub order_id: u64,
+    /// Token account owned by the order holding the amount left to swap
+    pub escrow: Pubkey,
+    /// Token account credited with the slices
+    pub destination: Pubkey,
+    /// Amount of escrow token to swap over all slices
+    pub amount_in: u64,
+    /// Number of slices
+    pub slice_count: u16,
+    /// Number of slices executed
+    pub slices_executed: u16,
+    /// Minimum number of slots between two slices
+    pub slice_interval_slots: u64,
+    /// Maximum deviation of the slice price below the TWAP, in basis points
+    pub max_price_deviation_bps: u16,
+    /// Slot the order was created in or its last slice executed in
+    pub last_slice_slot: Slot,
+    /// Cumulative price of the escrow token of the swap at `price_cumulative_slot`
+    pub price_cumulative: u128,
+    /// Slot of the cumulative price observation
+    pub price_cumulative_slot: Slot,
+    /// Amount of destination token received over the executed slices
+    pub amount_out: u64,
+}
+
+impl TwapOrder {
+    /// Amount of escrow token swapped by the slices before `slice`
+    fn amount_before(&self, slice: u16) -> Option<u64> {
+        let amount = (self.amount_in as u128)
+            .checked_mul(slice.into())?
+            .checked_div(self.slice_count.into())?;
+        u64::try_from(amount).ok()
+    }
+
+    /// Amount of escrow token the next slice swaps, none once all slices executed.
+    /// Slices differ by rounding only, and add up to `amount_in`.
+    pub fn next_slice_amount(&self) -> Option<u64> {
+        if self.slices_executed >= self.slice_count {
+            return None;
+        }
+        self.amount_before(self.slices_executed + 1)?
+            .checked_sub(self.amount_before(self.slices_executed)?)
+    }
+
+    /// Amount of escrow token left to swap
+    pub fn remaining_amount(&self) -> Option<u64> {
+        self.amount_in
+            .checked_sub(self.amount_before(self.slices_executed)?)
+    }
+
+    /// First slot the next slice may execute in
+    pub fn next_slice_slot(&self) -> Option<Slot> {
+        self.last_slice_slot.checked_add(self.slice_interval_slots)
+    }
+
+    /// Minimum amount of destination token a slice of `amount_in` has to receive at
+    /// `twap_price`, the price of the escrow token scaled by `PRICE_SCALE`
+    pub fn minimum_slice_amount_out(&self, amount_in: u64, twap_price: u128) -> Option<u64> {
+        let amount_out = U256::from(amount_in)
+            .checked_mul(twap_price.into())?
+            .checked_mul(
+                MAX_TWAP_PRICE_DEVIATION_BPS
+                    .checked_sub(self.max_price_deviation_bps)?
+                    .into(),
+            )?
+            .checked_div(
+                U256::from(PRICE_SCALE).checked_mul(MAX_TWAP_PRICE_DEVIATION_BPS.into())?,
+            )?;
+        U256::to_u64(amount_out).ok()
+    }
+}
+
+impl Sealed for TwapOrder {}
+impl IsInitialized for TwapOrder {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for TwapOrder {
+    const LEN: usize = layout::twap_order::LEN;
+
+    /// Unpacks a byte buffer into a [TwapOrder](struct.TwapOrder.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 200];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            nonce,
+            swap,
+            owner,
+            order_id,
+            escrow,
+            destination,
+            amount_in,
+            slice_count,
+            slices_executed,
+            slice_interval_slots,
+            max_price_deviation_bps,
+            last_slice_slot,
+            price_cumulative,
+            price_cumulative_slot,
+            amount_out,
+        ) = array_refs![input, 1, 1, 32, 32, 8, 32, 32, 8, 2, 2, 8, 2, 8, 16, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            swap: Pubkey::new_from_array(*swap),
+            owner: Pubkey::new_from_array(*owner),
+            order_id: u64::from_le_bytes(*order_id),
+            escrow: Pubkey::new_from_array(*escrow),
+            destination: Pubkey::new_from_array(*destination),
+            amount_in: u64::from_le_bytes(*amount_in),
+            slice_count: u16::from_le_bytes(*slice_count),
+            slices_executed: u16::from_le_bytes(*slices_executed),
+            slice_interval_slots: u64::from_le_bytes(*slice_interval_slots),
+            max_price_deviation_bps: u16::from_le_bytes(*max_price_deviation_bps),
+            last_slice_slot: u64::from_le_bytes(*last_slice_slot),
+            price_cumulative: u128::from_le_bytes(*price_cumulative),
+            price_cumulative_slot: u64::from_le_bytes(*price_cumulative_slot),
+            amount_out: u64::from_le_bytes(*amount_out),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 200];
+        let (
+            is_initialized,
+            nonce,
+            swap,
+            owner,
+            order_id,
+            escrow,
+            destination,
+            amount_in,
+            slice_count,
+            slices_executed,
+            slice_interval_slots,
+            max_price_deviation_bps,
+            last_slice_slot,
+            price_cumulative,
+            price_cumulative_slot,
+            amount_out,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 8, 32, 32, 8, 2, 2, 8, 2, 8, 16, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        swap.copy_from_slice(self.swap.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *order_id = self.order_id.to_le_bytes();
+        escrow.copy_from_slice(self.escrow.as_ref());
+        destination.copy_from_slice(self.destination.as_ref());
+        *amount_in = self.amount_in.to_le_bytes();
+        *slice_count = self.slice_count.to_le_bytes();
+        *slices_executed = self.slices_executed.to_le_bytes();
+        *slice_interval_slots = self.slice_interval_slots.to_le_bytes();
+        *max_price_deviation_bps = self.max_price_deviation_bps.to_le_bytes();
+        *last_slice_slot = self.last_slice_slot.to_le_bytes();
+        *price_cumulative = self.price_cumulative.to_le_bytes();
+        *price_cumulative_slot = self.price_cumulative_slot.to_le_bytes();
+        *amount_out = self.amount_out.to_le_bytes();
+    }
+}
+
+/// Deposit of escrowed token A and B amounts into the pool in equal tranches, one
+/// tranche at most every `tranche_interval_slots`, spreading a large deposit over
+/// time. Stored in a program address that owns both escrow token accounts.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DripDeposit {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Nonce used to create the drip deposit program address
+    pub nonce: u8,
+    /// Swap the tranches are deposited into
+    pub swap: Pubkey,
+    /// Signer the drip deposit belongs to, receives the rent back on cancel
+    pub owner: Pubkey,
+    /// Drip deposit identifier chosen by the owner, unique per swap and owner
+    pub drip_id: u64,
+    /// Token A account owned by the drip deposit holding the amount left to deposit
+    pub escrow_a: Pubkey,
+    /// Token B account owned by the drip deposit holding the amount left to deposit
+    pub escrow_b: Pubkey,
+    /// Pool token account credited with the tranches
+    pub destination: Pubkey,
+    /// Amount of token A to deposit over all tranches
+    pub amount_a: u64,
+    /// Amount of token B to deposit over all tranches
+    pub amount_b: u64,
+    /// Number of tranches
+    pub tranche_count: u16,
+    /// Number of tranches deposited
+    pub tranches_deposited: u16,
+    /// Minimum number of slots between two tranches
+    pub tranche_interval_slots: u64,
+    /// Slot the drip deposit was created in or its last tranche deposited in
+    pub last_tranche_slot: Slot,
+}
+
+impl DripDeposit {
+    /// Part of `amount` deposited by the tranches before `tranche`
+    fn amount_before(&self, amount: u64, tranche: u16) -> Option<u64> {
+        let amount = (amount as u128)
+            .checked_mul(tranche.into())?
+            .checked_div(self.tranche_count.into())?;
+        u64::try_from(amount).ok()
+    }
+
+    /// Amounts of token A and B the next tranche deposits, none once all tranches
+    /// are deposited. Tranches differ by rounding only, and add up to the amounts.
+    pub fn next_tranche_amounts(&self) -> Option<(u64, u64)> {
+        if self.tranches_deposited >= self.tranche_count {
+            return None;
+        }
+        let tranche_amount = |amount| {
+            self.amount_before(amount, self.tranches_deposited + 1)?
+                .checked_sub(self.amount_before(amount, self.tranches_deposited)?)
+        };
+        Some((
+            tranche_amount(self.amount_a)?,
+            tranche_amount(self.amount_b)?,
+        ))
+    }
+
+    /// First slot the next tranche may be deposited in
+    pub fn next_tranche_slot(&self) -> Option<Slot> {
+        self.last_tranche_slot
+            .checked_add(self.tranche_interval_slots)
+    }
+}
+
+impl Sealed for DripDeposit {}
+impl IsInitialized for DripDeposit {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for DripDeposit {
+    const LEN: usize = layout::drip_deposit::LEN;
+
+    /// Unpacks a byte buffer into a [DripDeposit](struct.DripDeposit.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 206];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            nonce,
+            swap,
+            owner,
+            drip_id,
+            escrow_a,
+            escrow_b,
+            destination,
+            amount_a,
+            amount_b,
+            tranche_count,
+            tranches_deposited,
+            tranche_interval_slots,
+            last_tranche_slot,
+        ) = array_refs![input, 1, 1, 32, 32, 8, 32, 32, 32, 8, 8, 2, 2, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            swap: Pubkey::new_from_array(*swap),
+            owner: Pubkey::new_from_array(*owner),
+            drip_id: u64::from_le_bytes(*drip_id),
+            escrow_a: Pubkey::new_from_array(*escrow_a),
+            escrow_b: Pubkey::new_from_array(*escrow_b),
+            destination: Pubkey::new_from_array(*destination),
+            amount_a: u64::from_le_bytes(*amount_a),
+            amount_b: u64::from_le_bytes(*amount_b),
+            tranche_count: u16::from_le_bytes(*tranche_count),
+            tranches_deposited: u16::from_le_bytes(*tranches_deposited),
+            tranche_interval_slots: u64::from_le_bytes(*tranche_interval_slots),
+            last_tranche_slot: u64::from_le_bytes(*last_tranche_slot),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 206];
+        let (
+            is_initialized,
+            nonce,
+            swap,
+            owner,
+            drip_id,
+            escrow_a,
+            escrow_b,
+            destination,
+            amount_a,
+            amount_b,
+            tranche_count,
+            tranches_deposited,
+            tranche_interval_slots,
+            last_tranche_slot,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 8, 32, 32, 32, 8, 8, 2, 2, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        swap.copy_from_slice(self.swap.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *drip_id = self.drip_id.to_le_bytes();
+        escrow_a.copy_from_slice(self.escrow_a.as_ref());
+        escrow_b.copy_from_slice(self.escrow_b.as_ref());
+        destination.copy_from_slice(self.destination.as_ref());
+        *amount_a = self.amount_a.to_le_bytes();
+        *amount_b = self.amount_b.to_le_bytes();
+        *tranche_count = self.tranche_count.to_le_bytes();
+        *tranches_deposited = self.tranches_deposited.to_le_bytes();
+        *tranche_interval_slots = self.tranche_interval_slots.to_le_bytes();
+        *last_tranche_slot = self.last_tranche_slot.to_le_bytes();
+    }
+}
+
+/// Order swapping its escrow once the oracle price crosses the trigger price,
+/// executed by any cranker for a lamport bounty. The oracle price is the rolling
+/// TWAP of token A in token B of the oracle pool. Stored in a program address that
+/// owns the escrow token account.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TriggerOrder {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Nonce used to create the trigger program address
+    pub nonce: u8,
+    /// Swap the escrow is swapped against
+    pub swap: Pubkey,
+    /// Signer the trigger belongs to, receives the rent back
+    pub owner: Pubkey,
+    /// Trigger identifier chosen by the owner, unique per swap and owner
+    pub trigger_id: u64,
+    /// Token account owned by the trigger holding the amount to swap
+    pub escrow: Pubkey,
+    /// Token account credited with the swap
+    pub destination: Pubkey,
+    /// Swap whose TWAP is the oracle price
+    pub oracle: Pubkey,
+    /// Minimum amount of destination token the swap has to receive
+    pub minimum_amount_out: u64,
+    /// Oracle price, scaled by `PRICE_SCALE`, the trigger executes at
+    pub trigger_price: u128,
+    /// Whether the trigger executes at or above the trigger price (take-profit),
+    /// or at or below it (stop-loss)
+    pub trigger_above: bool,
+    /// Lamports paid to the cranker executing the trigger
+    pub bounty: u64,
+}
+
+impl TriggerOrder {
+    /// Whether `oracle_price` crossed the trigger price
+    pub fn is_triggered(&self, oracle_price: u128) -> bool {
+        if self.trigger_above {
+            oracle_price >= self.trigger_price
+        } else {
+            oracle_price <= self.trigger_price
+        }
+    }
+}
+
+impl Sealed for TriggerOrder {}
+impl IsInitialized for TriggerOrder {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for TriggerOrder {
+    const LEN: usize = layout::trigger_order::LEN;
+
+    /// Unpacks a byte buffer into a [TriggerOrder](struct.TriggerOrder.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 203];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            nonce,
+            swap,
+            owner,
+            trigger_id,
+            escrow,
+            destination,
+            oracle,
+            minimum_amount_out,
+            trigger_price,
+            trigger_above,
+            bounty,
+        ) = array_refs![input, 1, 1, 32, 32, 8, 32, 32, 32, 8, 16, 1, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            swap: Pubkey::new_from_array(*swap),
+            owner: Pubkey::new_from_array(*owner),
+            trigger_id: u64::from_le_bytes(*trigger_id),
+            escrow: Pubkey::new_from_array(*escrow),
+            destination: Pubkey::new_from_array(*destination),
+            oracle: Pubkey::new_from_array(*oracle),
+            minimum_amount_out: u64::from_le_bytes(*minimum_amount_out),
+            trigger_price: u128::from_le_bytes(*trigger_price),
+            trigger_above: match trigger_above {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            bounty: u64::from_le_bytes(*bounty),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 203];
+        let (
+            is_initialized,
+            nonce,
+            swap,
+            owner,
+            trigger_id,
+            escrow,
+            destination,
+            oracle,
+            minimum_amount_out,
+            trigger_price,
+            trigger_above,
+            bounty,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 8, 32, 32, 32, 8, 16, 1, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        swap.copy_from_slice(self.swap.as_ref());
+        owner.copy_from_slice(self.owner.as_ref());
+        *trigger_id = self.trigger_id.to_le_bytes();
+        escrow.copy_from_slice(self.escrow.as_ref());
+        destination.copy_from_slice(self.destination.as_ref());
+        oracle.copy_from_slice(self.oracle.as_ref());
+        *minimum_amount_out = self.minimum_amount_out.to_le_bytes();
+        *trigger_price = self.trigger_price.to_le_bytes();
+        trigger_above[0] = self.trigger_above as u8;
+        *bounty = self.bounty.to_le_bytes();
+    }
+}
+
+/// Best-effort program-wide statistics for a one-account protocol overview,
+/// updated only by the pool instructions passing the global stats account,
+/// which is optional. Values are in raw token units, the tokens of a pool being
+/// valued at par.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct GlobalStats {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Nonce used to create the global stats program address
+    pub nonce: u8,
+    /// Pools initialized since the global stats were created
+    pub total_pools: u64,
+    /// Estimate of the tokens held by the pools, tracked since the global stats
+    /// were created
+    pub total_value_locked: u128,
+    /// Amount swapped into the pools, in the source token
+    pub cumulative_volume: u128,
+}
+
+impl GlobalStats {
+    /// Records a pool initialized with `amount_a` and `amount_b`
+    pub fn record_pool(&mut self, amount_a: u64, amount_b: u64) {
+        self.total_pools = self.total_pools.saturating_add(1);
+        self.add_value(amount_a);
+        self.add_value(amount_b);
+    }
+
+    /// Records `amount` deposited into a pool
+    pub fn add_value(&mut self, amount: u64) {
+        self.total_value_locked = self.total_value_locked.saturating_add(amount.into());
+    }
+
+    /// Records `amount` withdrawn from a pool. Pools deposited into before the
+    /// global stats were created can withdraw more than was tracked.
+    pub fn remove_value(&mut self, amount: u64) {
+        self.total_value_locked = self.total_value_locked.saturating_sub(amount.into());
+    }
+
+    /// Records a swap of `amount_in` for `amount_out`
+    pub fn record_swap(&mut self, amount_in: u64, amount_out: u64) {
+        self.cumulative_volume = self.cumulative_volume.saturating_add(amount_in.into());
+        self.add_value(amount_in);
+        self.remove_value(amount_out);
+    }
+}
+
+impl Sealed for GlobalStats {}
+impl IsInitialized for GlobalStats {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for GlobalStats {
+    const LEN: usize = layout::global_stats::LEN;
+
+    /// Unpacks a byte buffer into a [GlobalStats](struct.GlobalStats.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 42];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, nonce, total_pools, total_value_locked, cumulative_volume) =
+            array_refs![input, 1, 1, 8, 16, 16];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            total_pools: u64::from_le_bytes(*total_pools),
+            total_value_locked: u128::from_le_bytes(*total_value_locked),
+            cumulative_volume: u128::from_le_bytes(*cumulative_volume),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 42];
+        let (is_initialized, nonce, total_pools, total_value_locked, cumulative_volume) =
+            mut_array_refs![output, 1, 1, 8, 16, 16];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        *total_pools = self.total_pools.to_le_bytes();
+        *total_value_locked = self.total_value_locked.to_le_bytes();
+        *cumulative_volume = self.cumulative_volume.to_le_bytes();
+    }
+}
+
+/// Number of addresses a pool exempts from swap fees at most
+pub const MAX_FEE_EXEMPTIONS: usize = 16;
+
+/// Traders the admin exempts from the swap fees of a pool, such as the pool's
+/// own rebalancing bots
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FeeExemptions {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Nonce used to create the fee exemptions program address
+    pub nonce: u8,
+    /// Swap the exemptions apply to
+    pub swap: Pubkey,
+    /// Exempt traders, unused entries are the default pubkey
+    pub exempt: [Pubkey; MAX_FEE_EXEMPTIONS],
+}
+
+impl FeeExemptions {
+    /// Whether `trader` swaps without fees
+    pub fn is_exempt(&self, trader: &Pubkey) -> bool {
+        *trader != Pubkey::default() && self.exempt.contains(trader)
+    }
+
+    /// Exempts `trader`, doing nothing if it already is. `None` if the list is
+    /// full.
+    pub fn add(&mut self, trader: Pubkey) -> Option<()> {
+        if self.is_exempt(&trader) {
+            return Some(());
+        }
+        let entry = self
+            .exempt
+            .iter_mut()
+            .find(|entry| **entry == Pubkey::default())?;
+        *entry = trader;
+        Some(())
+    }
+
+    /// Removes the exemption of `trader`. `None` if it isn't exempt.
+    pub fn remove(&mut self, trader: &Pubkey) -> Option<()> {
+        if *trader == Pubkey::default() {
+            return None;
+        }
+        let entry = self.exempt.iter_mut().find(|entry| *entry == trader)?;
+        *entry = Pubkey::default();
+        Some(())
+    }
+}
+
+impl Sealed for FeeExemptions {}
+impl IsInitialized for FeeExemptions {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for FeeExemptions {
+    const LEN: usize = layout::fee_exemptions::LEN;
+
+    /// Unpacks a byte buffer into a [FeeExemptions](struct.FeeExemptions.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 546];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, nonce, swap, exempt) = array_refs![input, 1, 1, 32, 512];
+        let mut exemptions = Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            swap: Pubkey::new_from_array(*swap),
+            ..Self::default()
+        };
+        for (entry, input) in exemptions.exempt.iter_mut().zip(exempt.chunks(32)) {
+            *entry = Pubkey::new_from_array(*array_ref![input, 0, 32]);
+        }
+        Ok(exemptions)
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 546];
+        let (is_initialized, nonce, swap, exempt) = mut_array_refs![output, 1, 1, 32, 512];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        swap.copy_from_slice(self.swap.as_ref());
+        for (entry, output) in self.exempt.iter().zip(exempt.chunks_mut(32)) {
+            output.copy_from_slice(entry.as_ref());
+        }
+    }
+}
+
+/// Number of depositors a deposit allowlist holds at most
+pub const MAX_ALLOWED_DEPOSITORS: usize = 32;
+
+/// Depositors allowed to deposit into a pool restricting its deposits, such as
+/// the treasury or the partners of a private pool
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DepositAllowlist {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Nonce used to create the deposit allowlist program address
+    pub nonce: u8,
+    /// Swap the allowlist applies to
+    pub swap: Pubkey,
+    /// Allowed depositors, unused entries are the default pubkey
+    pub depositors: [Pubkey; MAX_ALLOWED_DEPOSITORS],
+}
+
+impl DepositAllowlist {
+    /// Whether `depositor` may deposit
+    pub fn is_allowed(&self, depositor: &Pubkey) -> bool {
+        *depositor != Pubkey::default() && self.depositors.contains(depositor)
+    }
+
+    /// Allows `depositor`, doing nothing if it already is. `None` if the list is
+    /// full.
+    pub fn add(&mut self, depositor: Pubkey) -> Option<()> {
+        if self.is_allowed(&depositor) {
+            return Some(());
+        }
+        let entry = self
+            .depositors
+            .iter_mut()
+            .find(|entry| **entry == Pubkey::default())?;
+        *entry = depositor;
+        Some(())
+    }
+
+    /// Removes `depositor` from the list. `None` if it isn't allowed.
+    pub fn remove(&mut self, depositor: &Pubkey) -> Option<()> {
+        if *depositor == Pubkey::default() {
+            return None;
+        }
+        let entry = self
+            .depositors
+            .iter_mut()
+            .find(|entry| *entry == depositor)?;
+        *entry = Pubkey::default();
+        Some(())
+    }
+}
+
+impl Sealed for DepositAllowlist {}
+impl IsInitialized for DepositAllowlist {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for DepositAllowlist {
+    const LEN: usize = layout::deposit_allowlist::LEN;
+
+    /// Unpacks a byte buffer into a [DepositAllowlist](struct.DepositAllowlist.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 1058];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (is_initialized, nonce, swap, depositors) = array_refs![input, 1, 1, 32, 1024];
+        let mut allowlist = Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            swap: Pubkey::new_from_array(*swap),
+            ..Self::default()
+        };
+        for (entry, input) in allowlist.depositors.iter_mut().zip(depositors.chunks(32)) {
+            *entry = Pubkey::new_from_array(*array_ref![input, 0, 32]);
+        }
+        Ok(allowlist)
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 1058];
+        let (is_initialized, nonce, swap, depositors) = mut_array_refs![output, 1, 1, 32, 1024];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        swap.copy_from_slice(self.swap.as_ref());
+        for (entry, output) in self.depositors.iter().zip(depositors.chunks_mut(32)) {
+            output.copy_from_slice(entry.as_ref());
+        }
+    }
+}
+
+/// Volume referred to a pool by a referrer and the host fees it earned, accrued
+/// in the swap token accounts until claimed
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ReferralAccrual {
+    /// Initialized state
+    pub is_initialized: bool,
+    /// Nonce used to create the referral accrual program address
+    pub nonce: u8,
+    /// Swap the swaps are referred to
+    pub swap: Pubkey,
+    /// Referrer claiming the host fees
+    pub referrer: Pubkey,
+    /// Amount of token A swapped into the pool by referred swaps
+    pub referred_volume_a: u128,
+    /// Amount of token B swapped into the pool by referred swaps
+    pub referred_volume_b: u128,
+    /// Host fees earned in token A
+    pub fees_earned_a: u64,
+    /// Host fees earned in token B
+    pub fees_earned_b: u64,
+    /// Host fees claimed in token A
+    pub fees_claimed_a: u64,
+    /// Host fees claimed in token B
+    pub fees_claimed_b: u64,
+}
+
+impl ReferralAccrual {
+    /// Records a referred swap of `amount_in` into the pool, from token A if
+    /// `from_a`, earning `host_fee` in token A if `fee_in_a`
+    pub fn record_swap(
+        &mut self,
+        from_a: bool,
+        amount_in: u64,
+        fee_in_a: bool,
+        host_fee: u64,
+    ) -> Option<()> {
+        let referred_volume = if from_a {
+            &mut self.referred_volume_a
+        } else {
+            &mut self.referred_volume_b
+        };
+        *referred_volume = referred_volume.saturating_add(amount_in.into());
+        let fees_earned = if fee_in_a {
+            &mut self.fees_earned_a
+        } else {
+            &mut self.fees_earned_b
+        };
+        *fees_earned = fees_earned.checked_add(host_fee)?;
+        Some(())
+    }
+
+    /// Host fees earned and not claimed yet, in token A and B
+    pub fn claimable(&self) -> Option<(u64, u64)> {
+        Some((
+            self.fees_earned_a.checked_sub(self.fees_claimed_a)?,
+            self.fees_earned_b.checked_sub(self.fees_claimed_b)?,
+        ))
+    }
+}
+
+impl Sealed for ReferralAccrual {}
+impl IsInitialized for ReferralAccrual {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ReferralAccrual {
+    const LEN: usize = layout::referral_accrual::LEN;
+
+    /// Unpacks a byte buffer into a [ReferralAccrual](struct.ReferralAccrual.html).
+    fn unpack_from_slice(input: &[u8]) -> Result<Self, ProgramError> {
+        let input = array_ref![input, 0, 130];
+        #[allow(clippy::ptr_offset_with_cast)]
+        let (
+            is_initialized,
+            nonce,
+            swap,
+            referrer,
+            referred_volume_a,
+            referred_volume_b,
+            fees_earned_a,
+            fees_earned_b,
+            fees_claimed_a,
+            fees_claimed_b,
+        ) = array_refs![input, 1, 1, 32, 32, 16, 16, 8, 8, 8, 8];
+        Ok(Self {
+            is_initialized: match is_initialized {
+                [0] => false,
+                [1] => true,
+                _ => return Err(ProgramError::InvalidAccountData),
+            },
+            nonce: nonce[0],
+            swap: Pubkey::new_from_array(*swap),
+            referrer: Pubkey::new_from_array(*referrer),
+            referred_volume_a: u128::from_le_bytes(*referred_volume_a),
+            referred_volume_b: u128::from_le_bytes(*referred_volume_b),
+            fees_earned_a: u64::from_le_bytes(*fees_earned_a),
+            fees_earned_b: u64::from_le_bytes(*fees_earned_b),
+            fees_claimed_a: u64::from_le_bytes(*fees_claimed_a),
+            fees_claimed_b: u64::from_le_bytes(*fees_claimed_b),
+        })
+    }
+
+    fn pack_into_slice(&self, output: &mut [u8]) {
+        let output = array_mut_ref![output, 0, 130];
+        let (
+            is_initialized,
+            nonce,
+            swap,
+            referrer,
+            referred_volume_a,
+            referred_volume_b,
+            fees_earned_a,
+            fees_earned_b,
+            fees_claimed_a,
+            fees_claimed_b,
+        ) = mut_array_refs![output, 1, 1, 32, 32, 16, 16, 8, 8, 8, 8];
+        is_initialized[0] = self.is_initialized as u8;
+        nonce[0] = self.nonce;
+        swap.copy_from_slice(self.swap.as_ref());
+        referrer.copy_from_slice(self.referrer.as_ref());
+        *referred_volume_a = self.referred_volume_a.to_le_bytes();
+        *referred_volume_b = self.referred_volume_b.to_le_bytes();
+        *fees_earned_a = self.fees_earned_a.to_le_bytes();
+        *fees_earned_b = self.fees_earned_b.to_le_bytes();
+        *fees_claimed_a = self.fees_claimed_a.to_le_bytes();
+        *fees_claimed_b = self.fees_claimed_b.to_le_bytes();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_info_packing() {
+        let nonce = 255;
+        let initial_amp_factor: u64 = 1;
+        let target_amp_factor: u64 = 1;
+        let start_ramp_ts: i64 = i64::MAX;
+        let stop_ramp_ts: i64 = i64::MAX;
+        let future_admin_deadline: i64 = i64::MAX;
+        let future_admin_key_raw = [1u8; 32];
+        let admin_key_raw = [2u8; 32];
+        let token_a_raw = [3u8; 32];
+        let token_b_raw = [4u8; 32];
+        let pool_mint_raw = [5u8; 32];
+        let token_a_mint_raw = [6u8; 32];
+        let token_b_mint_raw = [7u8; 32];
+        let admin_fee_key_a_raw = [8u8; 32];
+        let admin_fee_key_b_raw = [9u8; 32];
+        let access_mint_raw = [10u8; 32];
+        let admin_key = Pubkey::new_from_array(admin_key_raw);
+        let future_admin_key = Pubkey::new_from_array(future_admin_key_raw);
+        let token_a = Pubkey::new_from_array(token_a_raw);
+        let token_b = Pubkey::new_from_array(token_b_raw);
+        let pool_mint = Pubkey::new_from_array(pool_mint_raw);
+        let token_a_mint = Pubkey::new_from_array(token_a_mint_raw);
+        let token_b_mint = Pubkey::new_from_array(token_b_mint_raw);
+        let admin_fee_key_a = Pubkey::new_from_array(admin_fee_key_a_raw);
+        let admin_fee_key_b = Pubkey::new_from_array(admin_fee_key_b_raw);
+        let access_mint = Pubkey::new_from_array(access_mint_raw);
+        let access_window_end_slot: u64 = 1_000;
+        let namespace = [11u8; 32];
+        let fee_tier = FeeTier::ThirtyBps;
+        let admin_fees_accrued_a: u64 = 11;
+        let admin_fees_accrued_b: u64 = 12;
+        let harvest_tip_bps: u16 = 25;
+        let reflection_distributor_raw = [13u8; 32];
+        let reflection_distributor = Pubkey::new_from_array(reflection_distributor_raw);
+        let reflection_epoch: u64 = 14;
+        let dev_vesting_a_raw = [14u8; 32];
+        let dev_vesting_a = Pubkey::new_from_array(dev_vesting_a_raw);
+        let dev_vesting_b_raw = [15u8; 32];
+        let dev_vesting_b = Pubkey::new_from_array(dev_vesting_b_raw);
+        let dev_wallet_raw = [16u8; 32];
+        let dev_wallet = Pubkey::new_from_array(dev_wallet_raw);
+        let dev_vesting_start_ts: i64 = 17;
+        let dev_vesting_end_ts: i64 = 18;
+        let dev_fees_received_a: u64 = 19;
+        let dev_fees_received_b: u64 = 20;
+        let dev_fees_claimed_a: u64 = 21;
+        let dev_fees_claimed_b: u64 = 22;
+        let governance_realm_raw = [23u8; 32];
+        let governance_realm = Pubkey::new_from_array(governance_realm_raw);
+        let governance_vault_raw = [24u8; 32];
+        let governance_vault = Pubkey::new_from_array(governance_vault_raw);
+        let governance_lockup: i64 = 25;
+        let admin_recovery_key_raw = [31u8; 32];
+        let admin_recovery_key = Pubkey::new_from_array(admin_recovery_key_raw);
+        let admin_heartbeat_slot: u64 = 3_000;
+        let admin_timeout_slots: u64 = 216_000;
+        let pending_withdraw_a: u64 = 700;
+        let pending_withdraw_b: u64 = 800;
+        let max_swap_amount_in: u64 = 100_000;
+        let lp_soulbound = true;
+        let ve_lp_vault_raw = [21u8; 32];
+        let ve_lp_vault = Pubkey::new_from_array(ve_lp_vault_raw);
+        let ve_fee_vault_a_raw = [22u8; 32];
+        let ve_fee_vault_a = Pubkey::new_from_array(ve_fee_vault_a_raw);
+        let ve_fee_vault_b_raw = [23u8; 32];
+        let ve_fee_vault_b = Pubkey::new_from_array(ve_fee_vault_b_raw);
+        let ve_fee_bps: u16 = 2_500;
+        let ve_supply: u64 = 5_000;
+        let ve_epoch: i64 = 2_900;
+        let ve_fees_per_ve_a: u128 = 1_000_000_000_000_000;
+        let ve_fees_per_ve_b: u128 = 2_000_000_000_000_000;
+        let ve_pending_fees_a: u64 = 300;
+        let ve_pending_fees_b: u64 = 400;
+        let order_flow_batch_slots: u64 = 4;
+        let order_flow_bid_token_raw = [24u8; 32];
+        let order_flow_bid_token = Pubkey::new_from_array(order_flow_bid_token_raw);
+        let order_flow_batch: u64 = 810;
+        let order_flow_bidder_raw = [25u8; 32];
+        let order_flow_bidder = Pubkey::new_from_array(order_flow_bidder_raw);
+        let order_flow_refund_account_raw = [26u8; 32];
+        let order_flow_refund_account = Pubkey::new_from_array(order_flow_refund_account_raw);
+        let order_flow_bid: u64 = 900;
+        let dynamic_amp_min: u64 = 20;
+        let dynamic_amp_max: u64 = 400;
+        let dynamic_amp_volatility_ceiling: u64 = 5_000_000_000;
+        let dynamic_amp_max_drift: u64 = 2;
+        let twap_price: u128 = 1_001_000_000_000;
+        let twap_volatility: u64 = 1_000_000_000;
+        let twap_slot: u64 = 3_250;
+        let fee_buffer_bps: u16 = 2_000;
+        let deposit_bonus_bps: u16 = 50;
+        let fee_buffer_a: u64 = 1_100;
+        let fee_buffer_b: u64 = 1_200;
+        let insurance_fee_bps: u16 = 1_000;
+        let insurance_authority_raw = [27u8; 32];
+        let insurance_authority = Pubkey::new_from_array(insurance_authority_raw);
+        let insurance_fund_a: u64 = 1_300;
+        let insurance_fund_b: u64 = 1_400;
+        let insurance_covered_a: u64 = 1_500;
+        let insurance_covered_b: u64 = 1_600;
+        let min_lp_mint: u64 = 1_700;
+        let dust_threshold: u64 = 1_800;
+        let dust_sweep_slot: Slot = 1_900;
+        let dust_sweep_count: u16 = 3;
+        let liquidity_vault_raw = [28u8; 32];
+        let liquidity_vault = Pubkey::new_from_array(liquidity_vault_raw);
+        let position_liquidity: u64 = 2_000;
+        let cumulative_position_liquidity: u128 = 2_100;
+        let position_liquidity_ts: i64 = 2_200;
+        let fee_report_epoch: Epoch = 2_300;
+        let epoch_lp_fees_a: u64 = 2_400;
+        let epoch_lp_fees_b: u64 = 2_500;
+        let epoch_admin_fees_a: u64 = 2_600;
+        let epoch_admin_fees_b: u64 = 2_700;
+        let epoch_reflection_fees_a: u64 = 2_800;
+        let epoch_reflection_fees_b: u64 = 2_900;
+        let epoch_buyback_fees_a: u64 = 3_000;
+        let epoch_buyback_fees_b: u64 = 3_100;
+        let epoch_marketing_fees_a: u64 = 3_200;
+        let epoch_marketing_fees_b: u64 = 3_300;
+        let epoch_developer_fees_a: u64 = 3_400;
+        let epoch_developer_fees_b: u64 = 3_500;
+        let sequence: u64 = 3_600;
+        let custodian_raw = [29u8; 32];
+        let custodian = Pubkey::new_from_array(custodian_raw);
+        let custodian_withdraw_threshold: u64 = 3_700;
+        let credential_issuer_raw = [30u8; 32];
+        let credential_issuer = Pubkey::new_from_array(credential_issuer_raw);
+        let admin_action_signer_raw = [32u8; 32];
+        let mut admin_log = AdminLog::default();
+        admin_log.record(AdminAction {
+            tag: 132,
+            slot: 3_300,
+            signer: Pubkey::new_from_array(admin_action_signer_raw),
+        });
+        let admin_fee_escrow_a = true;
+        let admin_fee_escrow_b = false;
+        let price_a_cumulative: u128 = u128::MAX - 7;
+        let price_b_cumulative: u128 = 123_456_789_012_345;
+        let price_cumulative_slot: Slot = 4_242;
+        let guardian_key_raw = [38u8; 32];
+        let guardian_key = Pubkey::new_from_array(guardian_key_raw);
+        let protocol_pool_amount: u64 = 39;
+        let pending_fees = Fees {
+            trade_fee_numerator: 40,
+            trade_fee_denominator: 41,
+            ..Fees::default()
+        };
+        let pending_fees_slot: Slot = 42;
+        let mut custom_error_messages = CustomErrorMessages::default();
+        custom_error_messages.set(4, "migrating").unwrap();
+        let reflection_lp_vault_raw = [81u8; 32];
+        let reflection_lp_vault = Pubkey::new_from_array(reflection_lp_vault_raw);
+        let reflection_vault_a_raw = [82u8; 32];
+        let reflection_vault_a = Pubkey::new_from_array(reflection_vault_a_raw);
+        let reflection_vault_b_raw = [83u8; 32];
+        let reflection_vault_b = Pubkey::new_from_array(reflection_vault_b_raw);
+        let reflection_supply: u64 = 4_000;
+        let acc_reflection_per_lp_share_a: u128 = 5_000_000;
+        let acc_reflection_per_lp_share_b: u128 = 6_000_000;
+        let reflection_pending_a: u64 = 7;
+        let reflection_pending_b: u64 = 8;
+        let buyback_mint_raw = [84u8; 32];
+        let buyback_mint = Pubkey::new_from_array(buyback_mint_raw);
+        let buyback_vault_a_raw = [85u8; 32];
+        let buyback_vault_a = Pubkey::new_from_array(buyback_vault_a_raw);
+        let buyback_vault_b_raw = [86u8; 32];
+        let buyback_vault_b = Pubkey::new_from_array(buyback_vault_b_raw);
+        let buyback_burned: u64 = 9_000;
+        let marketing_wallet_raw = [87u8; 32];
+        let marketing_wallet = Pubkey::new_from_array(marketing_wallet_raw);
+        let developer_wallet_raw = [88u8; 32];
+        let developer_wallet = Pubkey::new_from_array(developer_wallet_raw);
+        let marketing_fees_accrued_a: u64 = 9_100;
+        let marketing_fees_accrued_b: u64 = 9_200;
+        let developer_fees_accrued_a: u64 = 9_300;
+        let developer_fees_accrued_b: u64 = 9_400;
+        let config_hash_raw = [29; 32];
+        let config_hash = Hash::new_from_array(config_hash_raw);
+        let volatility_fee_ceiling: u64 = PRICE_SCALE / 20;
+        let volatility_fee_max_multiplier_bps: u16 = 25_000;
+        let referral_fees_accrued_a: u64 = 9_500;
+        let referral_fees_accrued_b: u64 = 9_600;
+        let admin_fee_epoch_cap_a: u64 = 9_700;
+        let admin_fee_epoch_cap_b: u64 = 9_800;
+        let admin_fee_cap_epoch: u64 = 31;
+        let admin_fees_taken_a: u64 = 9_900;
+        let admin_fees_taken_b: u64 = 9_950;
+        let curve_type = CurveType::ConstantProduct;
+        let price_bound: u64 = 1_010_000_000_000;
+        let creator_raw = [41; 32];
+        let creator = Pubkey::new_from_array(creator_raw);
+        let creator_deposit_end_slot: u64 = 2_000;
+        let deposit_allowlist_enabled = true;
+        let freeze_authority_acknowledged = true;
+        let oracle_a_raw = [42; 32];
+        let oracle_a = Pubkey::new_from_array(oracle_a_raw);
+        let oracle_b_raw = [43; 32];
+        let oracle_b = Pubkey::new_from_array(oracle_b_raw);
+        let oracle_max_staleness_slots: u64 = 25;
+        let oracle_max_confidence_bps: u16 = 50;
+        let oracle_max_deviation_bps: u16 = 100;
+        let oracle_stale_policy = OracleStalePolicy::WidenBand;
+        let oracle_stale_band_bps: u16 = 200;
+        let admin_trade_fee_numerator = 1;
+        let admin_trade_fee_denominator = 2;
+        let admin_withdraw_fee_numerator = 3;
+        let admin_withdraw_fee_denominator = 4;
+        let trade_fee_numerator = 5;
+        let trade_fee_denominator = 6;
+        let withdraw_fee_numerator = 7;
+        let withdraw_fee_denominator = 8;
+        let reflection_fee_numerator = 9;
+        let reflection_fee_denominator = 10;
+        let buyback_fee_numerator = 11;
+        let buyback_fee_denominator = 12;
+        let marketing_fee_numerator = 13;
+        let marketing_fee_denominator = 14;
+        let developer_fee_numerator = 15;
+        let developer_fee_denominator = 16;
+        let admin_fee_in_pool_tokens = true;
+        let fee_on_input = true;
+        let imbalance_fee_numerator = 17;
+        let imbalance_fee_denominator = 18;
+        let imbalance_threshold_bps = 19;
+        let host_fee_numerator = 20;
+        let host_fee_denominator = 21;
+        let fees = Fees {
+            admin_trade_fee_numerator,
+            admin_trade_fee_denominator,
+            admin_withdraw_fee_numerator,
+            admin_withdraw_fee_denominator,
+            trade_fee_numerator,
+            trade_fee_denominator,
+            withdraw_fee_numerator,
+            withdraw_fee_denominator,
+            reflection_fee_numerator,
+            reflection_fee_denominator,
+            buyback_fee_numerator,
+            buyback_fee_denominator,
+            marketing_fee_numerator,
+            marketing_fee_denominator,
+            developer_fee_numerator,
+            developer_fee_denominator,
+            admin_fee_in_pool_tokens,
+            fee_on_input,
+            imbalance_fee_numerator,
+            imbalance_fee_denominator,
+            imbalance_threshold_bps,
+            host_fee_numerator,
+            host_fee_denominator,
+        };
+
+        let is_initialized = true;
+        let is_paused = false;
+        let swap_info = SwapInfo {
+            is_initialized,
+            is_paused,
+            nonce,
+            initial_amp_factor,
+            target_amp_factor,
+            start_ramp_ts,
+            stop_ramp_ts,
+            future_admin_deadline,
+            future_admin_key,
+            admin_key,
+            token_a,
+            token_b,
+            pool_mint,
+            token_a_mint,
+            token_b_mint,
+            admin_fee_key_a,
+            admin_fee_key_b,
+            fees,
+            access_mint,
+            access_window_end_slot,
+            namespace,
+            fee_tier,
+            admin_fees_accrued_a,
+            admin_fees_accrued_b,
+            harvest_tip_bps,
+            reflection_distributor,
+            reflection_epoch,
+            dev_vesting_a,
+            dev_vesting_b,
+            dev_wallet,
+            dev_vesting_start_ts,
+            dev_vesting_end_ts,
+            dev_fees_received_a,
+            dev_fees_received_b,
+            dev_fees_claimed_a,
+            dev_fees_claimed_b,
+            governance_realm,
+            governance_vault,
+            governance_lockup,
+            admin_recovery_key,
+            admin_heartbeat_slot,
+            admin_timeout_slots,
+            pending_withdraw_a,
+            pending_withdraw_b,
+            max_swap_amount_in,
+            lp_soulbound,
+            ve_lp_vault,
+            ve_fee_vault_a,
+            ve_fee_vault_b,
+            ve_fee_bps,
+            ve_supply,
+            ve_epoch,
+            ve_fees_per_ve_a,
+            ve_fees_per_ve_b,
+            ve_pending_fees_a,
+            ve_pending_fees_b,
+            order_flow_batch_slots,
+            order_flow_bid_token,
+            order_flow_batch,
+            order_flow_bidder,
+            order_flow_refund_account,
+            order_flow_bid,
+            dynamic_amp_min,
+            dynamic_amp_max,
+            dynamic_amp_volatility_ceiling,
+            dynamic_amp_max_drift,
+            twap_price,
+            twap_volatility,
+            twap_slot,
+            fee_buffer_bps,
+            deposit_bonus_bps,
+            fee_buffer_a,
+            fee_buffer_b,
+            insurance_fee_bps,
+            insurance_authority,
+            insurance_fund_a,
+            insurance_fund_b,
+            insurance_covered_a,
+            insurance_covered_b,
+            min_lp_mint,
+            dust_threshold,
+            dust_sweep_slot,
+            dust_sweep_count,
+            liquidity_vault,
+            position_liquidity,
+            cumulative_position_liquidity,
+            position_liquidity_ts,
+            fee_report_epoch,
+            epoch_lp_fees_a,
+            epoch_lp_fees_b,
+            epoch_admin_fees_a,
+            epoch_admin_fees_b,
+            epoch_reflection_fees_a,
+            epoch_reflection_fees_b,
+            epoch_buyback_fees_a,
+            epoch_buyback_fees_b,
+            epoch_marketing_fees_a,
+            epoch_marketing_fees_b,
+            epoch_developer_fees_a,
+            epoch_developer_fees_b,
+            sequence,
+            custodian,
+            custodian_withdraw_threshold,
+            credential_issuer,
+            admin_log,
+            admin_fee_escrow_a,
+            admin_fee_escrow_b,
+            price_a_cumulative,
+            price_b_cumulative,
+            price_cumulative_slot,
+            guardian_key,
+            protocol_pool_amount,
+            pending_fees,
+            pending_fees_slot,
+            custom_error_messages,
+            reflection_lp_vault,
+            reflection_vault_a,
+            reflection_vault_b,
+            reflection_supply,
+            acc_reflection_per_lp_share_a,
+            acc_reflection_per_lp_share_b,
+            reflection_pending_a,
+            reflection_pending_b,
+            buyback_mint,
+            buyback_vault_a,
+            buyback_vault_b,
+            buyback_burned,
+            marketing_wallet,
+            developer_wallet,
+            marketing_fees_accrued_a,
+            marketing_fees_accrued_b,
+            developer_fees_accrued_a,
+            developer_fees_accrued_b,
+            config_hash,
+            volatility_fee_ceiling,
+            volatility_fee_max_multiplier_bps,
+            referral_fees_accrued_a,
+            referral_fees_accrued_b,
+            admin_fee_epoch_cap_a,
+            admin_fee_epoch_cap_b,
+            admin_fee_cap_epoch,
+            admin_fees_taken_a,
+            admin_fees_taken_b,
+            curve_type,
+            price_bound,
+            creator,
+            creator_deposit_end_slot,
+            deposit_allowlist_enabled,
+            freeze_authority_acknowledged,
+            oracle_a,
+            oracle_b,
+            oracle_max_staleness_slots,
+            oracle_max_confidence_bps,
+            oracle_max_deviation_bps,
+            oracle_stale_policy,
+            oracle_stale_band_bps,
+        };
+
+        let mut packed = [0u8; SwapInfo::LEN];
+        SwapInfo::pack(swap_info, &mut packed).unwrap();
+        let unpacked = SwapInfo::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1 as u8); // is_initialized
+        packed.push(0 as u8); // is_paused
         packed.push(nonce);
         packed.extend_from_slice(&initial_amp_factor.to_le_bytes());
         packed.extend_from_slice(&target_amp_factor.to_le_bytes());
         packed.extend_from_slice(&start_ramp_ts.to_le_bytes());
         packed.extend_from_slice(&stop_ramp_ts.to_le_bytes());
-        packed.extend_from_slice(&future_admin_deadline.to_le_bytes());
-        packed.extend_from_slice(&future_admin_key_raw);
-        packed.extend_from_slice(&admin_key_raw);
-        packed.extend_from_slice(&token_a_raw);
-        packed.extend_from_slice(&token_b_raw);
-        packed.extend_from_slice(&pool_mint_raw);
-        packed.extend_from_slice(&token_a_mint_raw);
-        packed.extend_from_slice(&token_b_mint_raw);
+        packed.extend_from_slice(&future_admin_deadline.to_le_bytes());
+        packed.extend_from_slice(&future_admin_key_raw);
+        packed.extend_from_slice(&admin_key_raw);
+        packed.extend_from_slice(&token_a_raw);
+        packed.extend_from_slice(&token_b_raw);
+        packed.extend_from_slice(&pool_mint_raw);
+        packed.extend_from_slice(&token_a_mint_raw);
+        packed.extend_from_slice(&token_b_mint_raw);
+        packed.extend_from_slice(&admin_fee_key_a_raw);
+        packed.extend_from_slice(&admin_fee_key_b_raw);
+        packed.extend_from_slice(&admin_trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&admin_trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&admin_withdraw_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&admin_withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&trade_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&trade_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&withdraw_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&withdraw_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&reflection_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&reflection_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&buyback_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&buyback_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&marketing_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&marketing_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&developer_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&developer_fee_denominator.to_le_bytes());
+        packed.push(admin_fee_in_pool_tokens as u8);
+        packed.push(fee_on_input as u8);
+        packed.extend_from_slice(&imbalance_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&imbalance_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&imbalance_threshold_bps.to_le_bytes());
+        packed.extend_from_slice(&host_fee_numerator.to_le_bytes());
+        packed.extend_from_slice(&host_fee_denominator.to_le_bytes());
+        packed.extend_from_slice(&access_mint_raw);
+        packed.extend_from_slice(&access_window_end_slot.to_le_bytes());
+        packed.extend_from_slice(&namespace);
+        packed.push(fee_tier as u8);
+        packed.extend_from_slice(&admin_fees_accrued_a.to_le_bytes());
+        packed.extend_from_slice(&admin_fees_accrued_b.to_le_bytes());
+        packed.extend_from_slice(&harvest_tip_bps.to_le_bytes());
+        packed.extend_from_slice(&reflection_distributor_raw);
+        packed.extend_from_slice(&reflection_epoch.to_le_bytes());
+        packed.extend_from_slice(&dev_vesting_a_raw);
+        packed.extend_from_slice(&dev_vesting_b_raw);
+        packed.extend_from_slice(&dev_wallet_raw);
+        packed.extend_from_slice(&dev_vesting_start_ts.to_le_bytes());
+        packed.extend_from_slice(&dev_vesting_end_ts.to_le_bytes());
+        packed.extend_from_slice(&dev_fees_received_a.to_le_bytes());
+        packed.extend_from_slice(&dev_fees_received_b.to_le_bytes());
+        packed.extend_from_slice(&dev_fees_claimed_a.to_le_bytes());
+        packed.extend_from_slice(&dev_fees_claimed_b.to_le_bytes());
+        packed.extend_from_slice(&governance_realm_raw);
+        packed.extend_from_slice(&governance_vault_raw);
+        packed.extend_from_slice(&governance_lockup.to_le_bytes());
+        packed.extend_from_slice(&admin_recovery_key_raw);
+        packed.extend_from_slice(&admin_heartbeat_slot.to_le_bytes());
+        packed.extend_from_slice(&admin_timeout_slots.to_le_bytes());
+        packed.extend_from_slice(&pending_withdraw_a.to_le_bytes());
+        packed.extend_from_slice(&pending_withdraw_b.to_le_bytes());
+        packed.extend_from_slice(&max_swap_amount_in.to_le_bytes());
+        packed.push(lp_soulbound as u8);
+        packed.extend_from_slice(&ve_lp_vault_raw);
+        packed.extend_from_slice(&ve_fee_vault_a_raw);
+        packed.extend_from_slice(&ve_fee_vault_b_raw);
+        packed.extend_from_slice(&ve_fee_bps.to_le_bytes());
+        packed.extend_from_slice(&ve_supply.to_le_bytes());
+        packed.extend_from_slice(&ve_epoch.to_le_bytes());
+        packed.extend_from_slice(&ve_fees_per_ve_a.to_le_bytes());
+        packed.extend_from_slice(&ve_fees_per_ve_b.to_le_bytes());
+        packed.extend_from_slice(&ve_pending_fees_a.to_le_bytes());
+        packed.extend_from_slice(&ve_pending_fees_b.to_le_bytes());
+        packed.extend_from_slice(&order_flow_batch_slots.to_le_bytes());
+        packed.extend_from_slice(&order_flow_bid_token_raw);
+        packed.extend_from_slice(&order_flow_batch.to_le_bytes());
+        packed.extend_from_slice(&order_flow_bidder_raw);
+        packed.extend_from_slice(&order_flow_refund_account_raw);
+        packed.extend_from_slice(&order_flow_bid.to_le_bytes());
+        packed.extend_from_slice(&dynamic_amp_min.to_le_bytes());
+        packed.extend_from_slice(&dynamic_amp_max.to_le_bytes());
+        packed.extend_from_slice(&dynamic_amp_volatility_ceiling.to_le_bytes());
+        packed.extend_from_slice(&dynamic_amp_max_drift.to_le_bytes());
+        packed.extend_from_slice(&twap_price.to_le_bytes());
+        packed.extend_from_slice(&twap_volatility.to_le_bytes());
+        packed.extend_from_slice(&twap_slot.to_le_bytes());
+        packed.extend_from_slice(&fee_buffer_bps.to_le_bytes());
+        packed.extend_from_slice(&deposit_bonus_bps.to_le_bytes());
+        packed.extend_from_slice(&fee_buffer_a.to_le_bytes());
+        packed.extend_from_slice(&fee_buffer_b.to_le_bytes());
+        packed.extend_from_slice(&insurance_fee_bps.to_le_bytes());
+        packed.extend_from_slice(&insurance_authority_raw);
+        packed.extend_from_slice(&insurance_fund_a.to_le_bytes());
+        packed.extend_from_slice(&insurance_fund_b.to_le_bytes());
+        packed.extend_from_slice(&insurance_covered_a.to_le_bytes());
+        packed.extend_from_slice(&insurance_covered_b.to_le_bytes());
+        packed.extend_from_slice(&min_lp_mint.to_le_bytes());
+        packed.extend_from_slice(&dust_threshold.to_le_bytes());
+        packed.extend_from_slice(&dust_sweep_slot.to_le_bytes());
+        packed.extend_from_slice(&dust_sweep_count.to_le_bytes());
+        packed.extend_from_slice(&liquidity_vault_raw);
+        packed.extend_from_slice(&position_liquidity.to_le_bytes());
+        packed.extend_from_slice(&cumulative_position_liquidity.to_le_bytes());
+        packed.extend_from_slice(&position_liquidity_ts.to_le_bytes());
+        packed.extend_from_slice(&fee_report_epoch.to_le_bytes());
+        packed.extend_from_slice(&epoch_lp_fees_a.to_le_bytes());
+        packed.extend_from_slice(&epoch_lp_fees_b.to_le_bytes());
+        packed.extend_from_slice(&epoch_admin_fees_a.to_le_bytes());
+        packed.extend_from_slice(&epoch_admin_fees_b.to_le_bytes());
+        packed.extend_from_slice(&epoch_reflection_fees_a.to_le_bytes());
+        packed.extend_from_slice(&epoch_reflection_fees_b.to_le_bytes());
+        packed.extend_from_slice(&epoch_buyback_fees_a.to_le_bytes());
+        packed.extend_from_slice(&epoch_buyback_fees_b.to_le_bytes());
+        packed.extend_from_slice(&epoch_marketing_fees_a.to_le_bytes());
+        packed.extend_from_slice(&epoch_marketing_fees_b.to_le_bytes());
+        packed.extend_from_slice(&epoch_developer_fees_a.to_le_bytes());
+        packed.extend_from_slice(&epoch_developer_fees_b.to_le_bytes());
+        packed.extend_from_slice(&sequence.to_le_bytes());
+        packed.extend_from_slice(&custodian_raw);
+        packed.extend_from_slice(&custodian_withdraw_threshold.to_le_bytes());
+        packed.extend_from_slice(&credential_issuer_raw);
+        packed.push(1); // admin_log head
+        packed.push(132);
+        packed.extend_from_slice(&3_300u64.to_le_bytes());
+        packed.extend_from_slice(&admin_action_signer_raw);
+        packed.extend_from_slice(&[0u8; (ADMIN_LOG_LEN - 1) * AdminAction::LEN]);
+        packed.push(admin_fee_escrow_a as u8);
+        packed.push(admin_fee_escrow_b as u8);
+        packed.extend_from_slice(&price_a_cumulative.to_le_bytes());
+        packed.extend_from_slice(&price_b_cumulative.to_le_bytes());
+        packed.extend_from_slice(&price_cumulative_slot.to_le_bytes());
+        packed.extend_from_slice(&guardian_key_raw);
+        packed.extend_from_slice(&protocol_pool_amount.to_le_bytes());
+        let mut pending_fees_slice = [0u8; Fees::LEN];
+        pending_fees.pack_into_slice(&mut pending_fees_slice[..]);
+        packed.extend_from_slice(&pending_fees_slice);
+        packed.extend_from_slice(&pending_fees_slot.to_le_bytes());
+        let mut custom_error_messages_slice = [0u8; CustomErrorMessages::LEN];
+        custom_error_messages.pack_into_slice(&mut custom_error_messages_slice[..]);
+        packed.extend_from_slice(&custom_error_messages_slice);
+        packed.extend_from_slice(&reflection_lp_vault_raw);
+        packed.extend_from_slice(&reflection_vault_a_raw);
+        packed.extend_from_slice(&reflection_vault_b_raw);
+        packed.extend_from_slice(&reflection_supply.to_le_bytes());
+        packed.extend_from_slice(&acc_reflection_per_lp_share_a.to_le_bytes());
+        packed.extend_from_slice(&acc_reflection_per_lp_share_b.to_le_bytes());
+        packed.extend_from_slice(&reflection_pending_a.to_le_bytes());
+        packed.extend_from_slice(&reflection_pending_b.to_le_bytes());
+        packed.extend_from_slice(&buyback_mint_raw);
+        packed.extend_from_slice(&buyback_vault_a_raw);
+        packed.extend_from_slice(&buyback_vault_b_raw);
+        packed.extend_from_slice(&buyback_burned.to_le_bytes());
+        packed.extend_from_slice(&marketing_wallet_raw);
+        packed.extend_from_slice(&developer_wallet_raw);
+        packed.extend_from_slice(&marketing_fees_accrued_a.to_le_bytes());
+        packed.extend_from_slice(&marketing_fees_accrued_b.to_le_bytes());
+        packed.extend_from_slice(&developer_fees_accrued_a.to_le_bytes());
+        packed.extend_from_slice(&developer_fees_accrued_b.to_le_bytes());
+        packed.extend_from_slice(&config_hash_raw);
+        packed.extend_from_slice(&volatility_fee_ceiling.to_le_bytes());
+        packed.extend_from_slice(&volatility_fee_max_multiplier_bps.to_le_bytes());
+        packed.extend_from_slice(&referral_fees_accrued_a.to_le_bytes());
+        packed.extend_from_slice(&referral_fees_accrued_b.to_le_bytes());
+        packed.extend_from_slice(&admin_fee_epoch_cap_a.to_le_bytes());
+        packed.extend_from_slice(&admin_fee_epoch_cap_b.to_le_bytes());
+        packed.extend_from_slice(&admin_fee_cap_epoch.to_le_bytes());
+        packed.extend_from_slice(&admin_fees_taken_a.to_le_bytes());
+        packed.extend_from_slice(&admin_fees_taken_b.to_le_bytes());
+        packed.push(curve_type as u8);
+        packed.extend_from_slice(&price_bound.to_le_bytes());
+        packed.extend_from_slice(&creator_raw);
+        packed.extend_from_slice(&creator_deposit_end_slot.to_le_bytes());
+        packed.push(deposit_allowlist_enabled as u8);
+        packed.push(freeze_authority_acknowledged as u8);
+        packed.extend_from_slice(&oracle_a_raw);
+        packed.extend_from_slice(&oracle_b_raw);
+        packed.extend_from_slice(&oracle_max_staleness_slots.to_le_bytes());
+        packed.extend_from_slice(&oracle_max_confidence_bps.to_le_bytes());
+        packed.extend_from_slice(&oracle_max_deviation_bps.to_le_bytes());
+        packed.push(oracle_stale_policy as u8);
+        packed.extend_from_slice(&oracle_stale_band_bps.to_le_bytes());
+        let unpacked = SwapInfo::unpack(&packed).unwrap();
+        assert_eq!(swap_info, unpacked);
+
+        let packed = [0u8; SwapInfo::LEN];
+        let swap_info: SwapInfo = Default::default();
+        let unpack_unchecked = SwapInfo::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, swap_info);
+        let err = SwapInfo::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_swap_receipt_packing() {
+        let swap_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let source_mint_raw = [3u8; 32];
+        let destination_mint_raw = [4u8; 32];
+        let amount_in: u64 = 1_000;
+        let amount_out: u64 = 990;
+        let fee_amount: u64 = 6;
+        let slot: u64 = 123_456;
+        let receipt = SwapReceipt {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            source_mint: Pubkey::new_from_array(source_mint_raw),
+            destination_mint: Pubkey::new_from_array(destination_mint_raw),
+            amount_in,
+            amount_out,
+            fee_amount,
+            slot,
+        };
+
+        let mut packed = [0u8; SwapReceipt::LEN];
+        SwapReceipt::pack(receipt, &mut packed).unwrap();
+        let unpacked = SwapReceipt::unpack(&packed).unwrap();
+        assert_eq!(receipt, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&source_mint_raw);
+        packed.extend_from_slice(&destination_mint_raw);
+        packed.extend_from_slice(&amount_in.to_le_bytes());
+        packed.extend_from_slice(&amount_out.to_le_bytes());
+        packed.extend_from_slice(&fee_amount.to_le_bytes());
+        packed.extend_from_slice(&slot.to_le_bytes());
+        let unpacked = SwapReceipt::unpack(&packed).unwrap();
+        assert_eq!(receipt, unpacked);
+
+        let packed = [0u8; SwapReceipt::LEN];
+        let unpack_unchecked = SwapReceipt::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, SwapReceipt::default());
+        let err = SwapReceipt::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_admin_session_packing() {
+        let swap_raw = [1u8; 32];
+        let admin_raw = [2u8; 32];
+        let session_key_raw = [3u8; 32];
+        let permissions = AdminSession::PERMISSION_PAUSE;
+        let expiry_slot: u64 = 123_456;
+        let session = AdminSession {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            admin: Pubkey::new_from_array(admin_raw),
+            session_key: Pubkey::new_from_array(session_key_raw),
+            permissions,
+            expiry_slot,
+        };
+
+        let mut packed = [0u8; AdminSession::LEN];
+        AdminSession::pack(session, &mut packed).unwrap();
+        let unpacked = AdminSession::unpack(&packed).unwrap();
+        assert_eq!(session, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&admin_raw);
+        packed.extend_from_slice(&session_key_raw);
+        packed.push(permissions);
+        packed.extend_from_slice(&expiry_slot.to_le_bytes());
+        let unpacked = AdminSession::unpack(&packed).unwrap();
+        assert_eq!(session, unpacked);
+        assert!(unpacked.has_permission(AdminSession::PERMISSION_PAUSE));
+        assert!(!unpacked.has_permission(AdminSession::PERMISSION_SET_NEW_FEES));
+
+        let packed = [0u8; AdminSession::LEN];
+        let unpack_unchecked = AdminSession::unpack_unchecked(&packed).unwrap();
+        assert_eq!(unpack_unchecked, AdminSession::default());
+        let err = AdminSession::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_admin_log() {
+        let action = |tag: u8| AdminAction {
+            tag,
+            slot: tag as Slot * 10,
+            signer: Pubkey::new_from_array([tag; 32]),
+        };
+        let mut log = AdminLog::default();
+        assert_eq!(log.iter().count(), 0);
+
+        log.record(action(100));
+        log.record(action(101));
+        assert_eq!(
+            log.iter().collect::<Vec<_>>(),
+            vec![&action(100), &action(101)]
+        );
+
+        // oldest actions are overwritten once the log is full
+        for tag in 102..(100 + ADMIN_LOG_LEN as u8 + 2) {
+            log.record(action(tag));
+        }
+        assert_eq!(log.head, 2);
+        let tags: Vec<u8> = log.iter().map(|action| action.tag).collect();
+        assert_eq!(
+            tags,
+            (102..(100 + ADMIN_LOG_LEN as u8 + 2)).collect::<Vec<_>>()
+        );
+
+        let mut packed = [0u8; AdminLog::LEN];
+        log.pack_into_slice(&mut packed);
+        assert_eq!(packed[0], 2);
+        assert_eq!(&packed[1..1 + AdminAction::LEN], &{
+            let mut packed = [0u8; AdminAction::LEN];
+            action(116).pack_into_slice(&mut packed);
+            packed
+        });
+        assert_eq!(AdminLog::unpack_from_slice(&packed).unwrap(), log);
+
+        packed[0] = ADMIN_LOG_LEN as u8;
+        let err = AdminLog::unpack_from_slice(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::InvalidAccountData);
+    }
+
+    #[test]
+    fn test_reflection_distribution_packing() {
+        let swap_raw = [1u8; 32];
+        let epoch: u64 = 3;
+        let vault_raw = [2u8; 32];
+        let merkle_root = [3u8; 32];
+        let total_amount: u64 = 1_000;
+        let claimed_amount: u64 = 250;
+        let distribution = ReflectionDistribution {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            epoch,
+            vault: Pubkey::new_from_array(vault_raw),
+            merkle_root,
+            total_amount,
+            claimed_amount,
+        };
+
+        let mut packed = [0u8; ReflectionDistribution::LEN];
+        ReflectionDistribution::pack(distribution, &mut packed).unwrap();
+        let unpacked = ReflectionDistribution::unpack(&packed).unwrap();
+        assert_eq!(distribution, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&epoch.to_le_bytes());
+        packed.extend_from_slice(&vault_raw);
+        packed.extend_from_slice(&merkle_root);
+        packed.extend_from_slice(&total_amount.to_le_bytes());
+        packed.extend_from_slice(&claimed_amount.to_le_bytes());
+        let unpacked = ReflectionDistribution::unpack(&packed).unwrap();
+        assert_eq!(distribution, unpacked);
+
+        let packed = [0u8; ReflectionDistribution::LEN];
+        let err = ReflectionDistribution::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_reflection_claim_packing() {
+        let distribution_raw = [1u8; 32];
+        let index: u64 = 7;
+        let claimant_raw = [2u8; 32];
+        let amount: u64 = 1_000;
+        let claim = ReflectionClaim {
+            is_initialized: true,
+            distribution: Pubkey::new_from_array(distribution_raw),
+            index,
+            claimant: Pubkey::new_from_array(claimant_raw),
+            amount,
+        };
+
+        let mut packed = [0u8; ReflectionClaim::LEN];
+        ReflectionClaim::pack(claim, &mut packed).unwrap();
+        let unpacked = ReflectionClaim::unpack(&packed).unwrap();
+        assert_eq!(claim, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&distribution_raw);
+        packed.extend_from_slice(&index.to_le_bytes());
+        packed.extend_from_slice(&claimant_raw);
+        packed.extend_from_slice(&amount.to_le_bytes());
+        let unpacked = ReflectionClaim::unpack(&packed).unwrap();
+        assert_eq!(claim, unpacked);
+
+        let packed = [0u8; ReflectionClaim::LEN];
+        let err = ReflectionClaim::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_ve_lock_packing() {
+        let swap_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let amount: u64 = 5_000;
+        let unlock_ts: i64 = VE_MAX_LOCK_DURATION;
+        let ve_balance: u64 = 5_000;
+        let fees_debt_a: u128 = 1_000_000;
+        let fees_debt_b: u128 = 2_000_000;
+        let gauge_vote_bps: u16 = 10_000;
+        let ve_lock = VeLock {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            amount,
+            unlock_ts,
+            ve_balance,
+            fees_debt_a,
+            fees_debt_b,
+            gauge_vote_bps,
+        };
+
+        let mut packed = [0u8; VeLock::LEN];
+        VeLock::pack(ve_lock, &mut packed).unwrap();
+        let unpacked = VeLock::unpack(&packed).unwrap();
+        assert_eq!(ve_lock, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&amount.to_le_bytes());
+        packed.extend_from_slice(&unlock_ts.to_le_bytes());
+        packed.extend_from_slice(&ve_balance.to_le_bytes());
+        packed.extend_from_slice(&fees_debt_a.to_le_bytes());
+        packed.extend_from_slice(&fees_debt_b.to_le_bytes());
+        packed.extend_from_slice(&gauge_vote_bps.to_le_bytes());
+        let unpacked = VeLock::unpack(&packed).unwrap();
+        assert_eq!(ve_lock, unpacked);
+
+        let packed = [0u8; VeLock::LEN];
+        let err = VeLock::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_checkpoint_ve_fees() {
+        let mut swap_info = SwapInfo {
+            ve_supply: 3,
+            ve_epoch: 1,
+            ve_pending_fees_a: 10,
+            ve_pending_fees_b: 0,
+            ..SwapInfo::default()
+        };
+
+        // Fees stay pending within the epoch they were received in
+        swap_info
+            .checkpoint_ve_fees(2 * VE_EPOCH_DURATION - 1)
+            .unwrap();
+        assert_eq!(swap_info.ve_fees_per_ve_a, 0);
+        assert_eq!(swap_info.ve_pending_fees_a, 10);
+
+        swap_info.checkpoint_ve_fees(2 * VE_EPOCH_DURATION).unwrap();
+        assert_eq!(swap_info.ve_epoch, 2);
+        assert_eq!(swap_info.ve_fees_per_ve_a, 10 * VE_FEES_PER_VE_SCALE / 3);
+        assert_eq!(ve_fees(3, swap_info.ve_fees_per_ve_a), Some(9));
+        assert_eq!(swap_info.ve_pending_fees_a, 1);
+        assert_eq!(swap_info.ve_fees_per_ve_b, 0);
+
+        // Fees stay pending while no veLP is locked
+        swap_info.ve_supply = 0;
+        swap_info.checkpoint_ve_fees(3 * VE_EPOCH_DURATION).unwrap();
+        assert_eq!(swap_info.ve_epoch, 3);
+        assert_eq!(swap_info.ve_pending_fees_a, 1);
+    }
+
+    #[test]
+    fn test_settle_order_flow_auction() {
+        let token_a = Pubkey::new_unique();
+        let bidder = Pubkey::new_unique();
+        let mut swap_info = SwapInfo {
+            token_a,
+            order_flow_batch_slots: 4,
+            order_flow_bid_token: token_a,
+            order_flow_batch: 2,
+            order_flow_bidder: bidder,
+            order_flow_refund_account: Pubkey::new_unique(),
+            order_flow_bid: 100,
+            ..SwapInfo::default()
+        };
+        assert!(!swap_info.is_order_flow_backrun_slot(10));
+        assert!(swap_info.is_order_flow_backrun_slot(11));
+
+        // The bid stays out of the pool until its batch ends
+        swap_info.settle_order_flow_auction(11);
+        assert_eq!(swap_info.order_flow_bid, 100);
+        assert_eq!(swap_info.pool_amount(&token_a, 1_000), Some(900));
+
+        swap_info.settle_order_flow_auction(12);
+        assert_eq!(swap_info.order_flow_bid, 0);
+        assert_eq!(swap_info.order_flow_bidder, Pubkey::default());
+        assert_eq!(swap_info.pool_amount(&token_a, 1_000), Some(1_000));
+    }
+
+    #[test]
+    fn test_update_dynamic_amp() {
+        let mut swap_info = SwapInfo {
+            initial_amp_factor: 100,
+            target_amp_factor: 100,
+            dynamic_amp_min: 50,
+            dynamic_amp_max: 150,
+            dynamic_amp_volatility_ceiling: PRICE_SCALE / 100,
+            dynamic_amp_max_drift: 1,
+            twap_slot: 10,
+            ..SwapInfo::default()
+        };
+        assert!(swap_info.is_dynamic_amp_enabled());
+
+        // The first swap sets the TWAP, A drifts toward the maximum at zero volatility
+        swap_info.update_dynamic_amp(1_000, 1_000, 20).unwrap();
+        assert_eq!(swap_info.twap_price, PRICE_SCALE as u128);
+        assert_eq!(swap_info.twap_volatility, 0);
+        assert_eq!(swap_info.target_amp_factor, 110);
+        assert_eq!(swap_info.initial_amp_factor, 110);
+
+        // Swaps in the same slot neither move the TWAP nor A
+        swap_info.update_dynamic_amp(1_000, 900, 20).unwrap();
+        assert_eq!(swap_info.twap_price, PRICE_SCALE as u128);
+        assert_eq!(swap_info.target_amp_factor, 110);
+
+        // A full window later the TWAP takes the new price, and a 2% deviation is
+        // above the volatility ceiling
+        swap_info
+            .update_dynamic_amp(1_000, 1_020, 20 + twap::TWAP_WINDOW_SLOTS)
+            .unwrap();
+        assert_eq!(swap_info.twap_price, 1_020 * PRICE_SCALE as u128 / 1_000);
+        assert_eq!(swap_info.twap_volatility, PRICE_SCALE / 50);
+        assert_eq!(swap_info.dynamic_amp_target(), Some(50));
+        assert_eq!(swap_info.target_amp_factor, 50);
+    }
+
+    #[test]
+    fn test_volatility_fee_multiplier() {
+        let mut swap_info = SwapInfo {
+            volatility_fee_ceiling: PRICE_SCALE / 100,
+            volatility_fee_max_multiplier_bps: 30_000,
+            ..SwapInfo::default()
+        };
+        assert!(swap_info.is_volatility_fee_enabled());
+        assert!(swap_info.is_twap_tracked());
+        assert_eq!(swap_info.volatility_fee_multiplier_bps(), Some(10_000));
+
+        swap_info.twap_volatility = PRICE_SCALE / 400;
+        assert_eq!(swap_info.volatility_fee_multiplier_bps(), Some(15_000));
+
+        // capped at the volatility ceiling
+        swap_info.twap_volatility = PRICE_SCALE / 50;
+        assert_eq!(swap_info.volatility_fee_multiplier_bps(), Some(30_000));
+
+        swap_info.volatility_fee_ceiling = 0;
+        assert!(!swap_info.is_twap_tracked());
+        assert_eq!(swap_info.volatility_fee_multiplier_bps(), Some(10_000));
+    }
+
+    #[test]
+    fn test_accumulate_prices() {
+        let invariant = StableSwap::new(100, 100, 0, 0, 0);
+        let mut swap_info = SwapInfo {
+            price_cumulative_slot: 10,
+            ..SwapInfo::default()
+        };
+
+        // Swaps in the same slot do not accumulate
+        swap_info
+            .accumulate_prices(&invariant, 1_000_000, 1_000_000, 10)
+            .unwrap();
+        assert_eq!(swap_info.price_a_cumulative, 0);
+
+        // Balanced reserves price both tokens at par
+        swap_info
+            .accumulate_prices(&invariant, 1_000_000, 1_000_000, 15)
+            .unwrap();
+        assert_eq!(swap_info.price_a_cumulative, 5 * PRICE_SCALE as u128);
+        assert_eq!(swap_info.price_b_cumulative, 5 * PRICE_SCALE as u128);
+        assert_eq!(swap_info.price_cumulative_slot, 15);
+
+        // The token in excess is the cheaper one
+        swap_info
+            .accumulate_prices(&invariant, 2_000_000, 1_000_000, 16)
+            .unwrap();
+        let price_a = swap_info.price_a_cumulative - 5 * PRICE_SCALE as u128;
+        let price_b = swap_info.price_b_cumulative - 5 * PRICE_SCALE as u128;
+        assert!(price_a < PRICE_SCALE as u128);
+        assert!(price_b > PRICE_SCALE as u128);
+
+        // Cumulative prices wrap around
+        swap_info.price_a_cumulative = u128::MAX;
+        swap_info
+            .accumulate_prices(&invariant, 1_000_000, 1_000_000, 17)
+            .unwrap();
+        assert_eq!(swap_info.price_a_cumulative, PRICE_SCALE as u128 - 1);
+
+        // Empty reserves have no price, only the slot moves
+        let price_b_cumulative = swap_info.price_b_cumulative;
+        swap_info
+            .accumulate_prices(&invariant, 0, 1_000_000, 20)
+            .unwrap();
+        assert_eq!(swap_info.price_b_cumulative, price_b_cumulative);
+        assert_eq!(swap_info.price_cumulative_slot, 20);
+    }
+
+    #[test]
+    fn test_deposit_bonus() {
+        let swap_info = SwapInfo {
+            fee_buffer_bps: 2_500,
+            deposit_bonus_bps: 100,
+            fee_buffer_a: 1_000,
+            fee_buffer_b: 5,
+            ..SwapInfo::default()
+        };
+        assert_eq!(swap_info.fee_buffer_share(1_000), Some(250));
+
+        // Restoring the balance earns the full bonus, capped by the fee buffer
+        assert_eq!(
+            swap_info.deposit_bonus(20_000, 0, 90_000, 110_000),
+            Some((200, 0))
+        );
+        assert_eq!(
+            swap_info.deposit_bonus(0, 20_000, 110_000, 90_000),
+            Some((0, 5))
+        );
+        // Removing about half of the relative imbalance earns about half of the bonus
+        assert_eq!(
+            swap_info.deposit_bonus(10_000, 0, 90_000, 110_000),
+            Some((52, 0))
+        );
+        // Deposits that don't reduce the imbalance earn nothing
+        assert_eq!(
+            swap_info.deposit_bonus(0, 10_000, 90_000, 110_000),
+            Some((0, 0))
+        );
+        assert_eq!(
+            swap_info.deposit_bonus(10_000, 10_000, 100_000, 100_000),
+            Some((0, 0))
+        );
+        // Overshooting the balance only earns the imbalance removed
+        assert_eq!(
+            swap_info.deposit_bonus(40_000, 0, 90_000, 110_000),
+            Some((66, 0))
+        );
+
+        let disabled = SwapInfo {
+            deposit_bonus_bps: 0,
+            ..swap_info
+        };
+        assert_eq!(
+            disabled.deposit_bonus(20_000, 0, 90_000, 110_000),
+            Some((0, 0))
+        );
+    }
+
+    #[test]
+    fn test_insurance_fund() {
+        let swap_info = SwapInfo {
+            token_a: Pubkey::new_unique(),
+            token_b: Pubkey::new_unique(),
+            insurance_fee_bps: 1_000,
+            insurance_fund_a: 100,
+            insurance_fund_b: 200,
+            ..SwapInfo::default()
+        };
+        assert_eq!(swap_info.insurance_share(1_005), Some(100));
+        assert_eq!(swap_info.insurance_share(9), Some(0));
+
+        // Insured tokens don't back the pool
+        assert_eq!(swap_info.pool_amount(&swap_info.token_a, 1_000), Some(900));
+        assert_eq!(swap_info.pool_amount(&swap_info.token_b, 1_000), Some(800));
+        assert_eq!(swap_info.pool_amount(&swap_info.token_b, 100), None);
+    }
+
+    #[test]
+    fn test_take_capped_admin_fee() {
+        let token_a = Pubkey::new_unique();
+        let token_b = Pubkey::new_unique();
+        let mut swap_info = SwapInfo {
+            token_a,
+            token_b,
+            admin_fee_epoch_cap_a: 100,
+            admin_fee_cap_epoch: 3,
+            ..SwapInfo::default()
+        };
+        assert!(swap_info.is_admin_fee_capped());
+
+        assert_eq!(swap_info.take_capped_admin_fee(&token_a, 3, 60), Some(60));
+        assert_eq!(swap_info.take_capped_admin_fee(&token_a, 3, 60), Some(40));
+        assert_eq!(swap_info.take_capped_admin_fee(&token_a, 3, 60), Some(0));
+        assert_eq!(swap_info.admin_fees_taken_a, 100);
+
+        // token B is not capped
+        assert_eq!(swap_info.take_capped_admin_fee(&token_b, 3, 500), Some(500));
+        assert_eq!(swap_info.admin_fees_taken_b, 500);
+
+        // the counters restart with the next epoch
+        assert_eq!(swap_info.take_capped_admin_fee(&token_a, 4, 70), Some(70));
+        assert_eq!(swap_info.admin_fee_cap_epoch, 4);
+        assert_eq!(swap_info.admin_fees_taken_a, 70);
+        assert_eq!(swap_info.admin_fees_taken_b, 0);
+
+        assert_eq!(
+            swap_info.take_capped_admin_fee(&Pubkey::new_unique(), 4, 1),
+            None
+        );
+        swap_info.admin_fee_epoch_cap_a = 0;
+        assert!(!swap_info.is_admin_fee_capped());
+    }
+
+    #[test]
+    fn test_accrue_wallet_fees() {
+        let token_a = Pubkey::new_unique();
+        let token_b = Pubkey::new_unique();
+        let mut swap_info = SwapInfo {
+            token_a,
+            token_b,
+            marketing_wallet: Pubkey::new_unique(),
+            ..SwapInfo::default()
+        };
+
+        // the developer fees are paid out while no developer wallet is set
+        swap_info.accrue_wallet_fees(&token_b, 30, 40).unwrap();
+        assert_eq!(swap_info.marketing_fees_accrued_b, 30);
+        assert_eq!(swap_info.developer_fees_accrued_b, 0);
+
+        swap_info.developer_wallet = Pubkey::new_unique();
+        swap_info.accrue_wallet_fees(&token_a, 10, 20).unwrap();
+        swap_info.accrue_wallet_fees(&token_b, 1, 2).unwrap();
+        assert_eq!(swap_info.marketing_fees_accrued_a, 10);
+        assert_eq!(swap_info.developer_fees_accrued_a, 20);
+        assert_eq!(swap_info.marketing_fees_accrued_b, 31);
+        assert_eq!(swap_info.developer_fees_accrued_b, 2);
+        assert_eq!(
+            swap_info.accrue_wallet_fees(&Pubkey::new_unique(), 1, 1),
+            None
+        );
+
+        // Accrued fees don't back the pool
+        assert_eq!(swap_info.pool_amount(&token_a, 1_000), Some(970));
+        assert_eq!(swap_info.pool_amount(&token_b, 1_000), Some(967));
+        assert_eq!(swap_info.pool_amount(&token_a, 29), None);
+    }
+
+    #[test]
+    fn test_record_dust_sweep() {
+        let mut swap_info = SwapInfo::default();
+        for _ in 0..MAX_DUST_SWEEPS_PER_SLOT {
+            assert!(swap_info.record_dust_sweep(10));
+        }
+        assert!(!swap_info.record_dust_sweep(10));
+        assert_eq!(swap_info.dust_sweep_count, MAX_DUST_SWEEPS_PER_SLOT);
+
+        // the sweeps of a new slot start over
+        assert!(swap_info.record_dust_sweep(11));
+        assert_eq!(swap_info.dust_sweep_slot, 11);
+        assert_eq!(swap_info.dust_sweep_count, 1);
+    }
+
+    #[test]
+    fn test_governance_deposit_packing() {
+        let swap_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let amount: u64 = 5_000;
+        let unlock_ts: i64 = 86_400;
+        let deposit = GovernanceDeposit {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            amount,
+            unlock_ts,
+        };
+
+        let mut packed = [0u8; GovernanceDeposit::LEN];
+        GovernanceDeposit::pack(deposit, &mut packed).unwrap();
+        let unpacked = GovernanceDeposit::unpack(&packed).unwrap();
+        assert_eq!(deposit, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&amount.to_le_bytes());
+        packed.extend_from_slice(&unlock_ts.to_le_bytes());
+        let unpacked = GovernanceDeposit::unpack(&packed).unwrap();
+        assert_eq!(deposit, unpacked);
+
+        let packed = [0u8; GovernanceDeposit::LEN];
+        let err = GovernanceDeposit::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_withdraw_continuation_packing() {
+        let swap_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let destination_a_raw = [3u8; 32];
+        let destination_b_raw = [4u8; 32];
+        let token_a_amount: u64 = 1_000;
+        let token_a_admin_fee: u64 = 10;
+        let token_b_amount: u64 = 2_000;
+        let token_b_admin_fee: u64 = 20;
+        let continuation = WithdrawContinuation {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            destination_a: Pubkey::new_from_array(destination_a_raw),
+            destination_b: Pubkey::new_from_array(destination_b_raw),
+            token_a_amount,
+            token_a_admin_fee,
+            token_b_amount,
+            token_b_admin_fee,
+        };
+
+        let mut packed = [0u8; WithdrawContinuation::LEN];
+        WithdrawContinuation::pack(continuation, &mut packed).unwrap();
+        let unpacked = WithdrawContinuation::unpack(&packed).unwrap();
+        assert_eq!(continuation, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&destination_a_raw);
+        packed.extend_from_slice(&destination_b_raw);
+        packed.extend_from_slice(&token_a_amount.to_le_bytes());
+        packed.extend_from_slice(&token_a_admin_fee.to_le_bytes());
+        packed.extend_from_slice(&token_b_amount.to_le_bytes());
+        packed.extend_from_slice(&token_b_admin_fee.to_le_bytes());
+        let unpacked = WithdrawContinuation::unpack(&packed).unwrap();
+        assert_eq!(continuation, unpacked);
+
+        let packed = [0u8; WithdrawContinuation::LEN];
+        let err = WithdrawContinuation::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_parameter_snapshot_packing() {
+        let swap_raw = [1u8; 32];
+        let snapshot_ts: i64 = 86_400;
+        let initial_amp_factor: u64 = 100;
+        let target_amp_factor: u64 = 200;
+        let start_ramp_ts: i64 = i64::MAX;
+        let stop_ramp_ts: i64 = i64::MAX;
+        let fees = Fees {
+            admin_trade_fee_numerator: 1,
+            admin_trade_fee_denominator: 2,
+            admin_withdraw_fee_numerator: 3,
+            admin_withdraw_fee_denominator: 4,
+            trade_fee_numerator: 5,
+            trade_fee_denominator: 6,
+            withdraw_fee_numerator: 7,
+            withdraw_fee_denominator: 8,
+            reflection_fee_numerator: 9,
+            reflection_fee_denominator: 10,
+            buyback_fee_numerator: 11,
+            buyback_fee_denominator: 12,
+            marketing_fee_numerator: 13,
+            marketing_fee_denominator: 14,
+            developer_fee_numerator: 15,
+            developer_fee_denominator: 16,
+            admin_fee_in_pool_tokens: true,
+            fee_on_input: false,
+            imbalance_fee_numerator: 0,
+            imbalance_fee_denominator: 1,
+            imbalance_threshold_bps: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 1,
+        };
+        let fee_tier = FeeTier::FiveBps;
+        let admin_fee_key_a_raw = [2u8; 32];
+        let admin_fee_key_b_raw = [3u8; 32];
+        let harvest_tip_bps: u16 = 50;
+        let snapshot = ParameterSnapshot {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            snapshot_ts,
+            initial_amp_factor,
+            target_amp_factor,
+            start_ramp_ts,
+            stop_ramp_ts,
+            fees,
+            fee_tier,
+            admin_fee_key_a: Pubkey::new_from_array(admin_fee_key_a_raw),
+            admin_fee_key_b: Pubkey::new_from_array(admin_fee_key_b_raw),
+            harvest_tip_bps,
+        };
+
+        let mut packed = [0u8; ParameterSnapshot::LEN];
+        ParameterSnapshot::pack(snapshot, &mut packed).unwrap();
+        let unpacked = ParameterSnapshot::unpack(&packed).unwrap();
+        assert_eq!(snapshot, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&snapshot_ts.to_le_bytes());
+        packed.extend_from_slice(&initial_amp_factor.to_le_bytes());
+        packed.extend_from_slice(&target_amp_factor.to_le_bytes());
+        packed.extend_from_slice(&start_ramp_ts.to_le_bytes());
+        packed.extend_from_slice(&stop_ramp_ts.to_le_bytes());
+        let mut fees_slice = [0u8; Fees::LEN];
+        fees.pack_into_slice(&mut fees_slice[..]);
+        packed.extend_from_slice(&fees_slice);
+        packed.push(fee_tier as u8);
         packed.extend_from_slice(&admin_fee_key_a_raw);
         packed.extend_from_slice(&admin_fee_key_b_raw);
-        packed.extend_from_slice(&admin_trade_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&admin_trade_fee_denominator.to_le_bytes());
-        packed.extend_from_slice(&admin_withdraw_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&admin_withdraw_fee_denominator.to_le_bytes());
-        packed.extend_from_slice(&trade_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&trade_fee_denominator.to_le_bytes());
-        packed.extend_from_slice(&withdraw_fee_numerator.to_le_bytes());
-        packed.extend_from_slice(&withdraw_fee_denominator.to_le_bytes());
-        let unpacked = SwapInfo::unpack(&packed).unwrap();
-        assert_eq!(swap_info, unpacked);
+        packed.extend_from_slice(&harvest_tip_bps.to_le_bytes());
+        let unpacked = ParameterSnapshot::unpack(&packed).unwrap();
+        assert_eq!(snapshot, unpacked);
 
-        let packed = [0u8; SwapInfo::LEN];
-        let swap_info: SwapInfo = Default::default();
-        let unpack_unchecked = SwapInfo::unpack_unchecked(&packed).unwrap();
-        assert_eq!(unpack_unchecked, swap_info);
-        let err = SwapInfo::unpack(&packed).unwrap_err();
+        let packed = [0u8; ParameterSnapshot::LEN];
+        let err = ParameterSnapshot::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_liquidity_position_packing() {
+        let swap_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let amount: u64 = 5_000;
+        let cumulative_liquidity: u128 = 3_000_000;
+        let checkpoint_ts: i64 = 600;
+        let position = LiquidityPosition {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            amount,
+            cumulative_liquidity,
+            checkpoint_ts,
+        };
+
+        let mut packed = [0u8; LiquidityPosition::LEN];
+        LiquidityPosition::pack(position, &mut packed).unwrap();
+        let unpacked = LiquidityPosition::unpack(&packed).unwrap();
+        assert_eq!(position, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&amount.to_le_bytes());
+        packed.extend_from_slice(&cumulative_liquidity.to_le_bytes());
+        packed.extend_from_slice(&checkpoint_ts.to_le_bytes());
+        let unpacked = LiquidityPosition::unpack(&packed).unwrap();
+        assert_eq!(position, unpacked);
+
+        let packed = [0u8; LiquidityPosition::LEN];
+        let err = LiquidityPosition::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_reflection_position_packing() {
+        let swap_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let amount: u64 = 5_000;
+        let reflection_debt_a: u128 = 3_000_000;
+        let reflection_debt_b: u128 = 4_000_000;
+        let reflection_owed_a: u64 = 60;
+        let reflection_owed_b: u64 = 70;
+        let position = ReflectionPosition {
+            is_initialized: true,
+            swap: Pubkey::new_from_array(swap_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            amount,
+            reflection_debt_a,
+            reflection_debt_b,
+            reflection_owed_a,
+            reflection_owed_b,
+        };
+
+        let mut packed = [0u8; ReflectionPosition::LEN];
+        ReflectionPosition::pack(position, &mut packed).unwrap();
+        let unpacked = ReflectionPosition::unpack(&packed).unwrap();
+        assert_eq!(position, unpacked);
+
+        let mut packed = vec![];
+        packed.push(1);
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&amount.to_le_bytes());
+        packed.extend_from_slice(&reflection_debt_a.to_le_bytes());
+        packed.extend_from_slice(&reflection_debt_b.to_le_bytes());
+        packed.extend_from_slice(&reflection_owed_a.to_le_bytes());
+        packed.extend_from_slice(&reflection_owed_b.to_le_bytes());
+        let unpacked = ReflectionPosition::unpack(&packed).unwrap();
+        assert_eq!(position, unpacked);
+
+        let packed = [0u8; ReflectionPosition::LEN];
+        let err = ReflectionPosition::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_accrue_reflection() {
+        let token_a = Pubkey::new_unique();
+        let token_b = Pubkey::new_unique();
+        let mut swap_info = SwapInfo {
+            token_a,
+            token_b,
+            ..SwapInfo::default()
+        };
+        let mut position = ReflectionPosition::default();
+
+        // fees stay pending while no position holds pool tokens
+        swap_info.accrue_reflection(&token_a, 100).unwrap();
+        assert_eq!(swap_info.acc_reflection_per_lp_share_a, 0);
+        assert_eq!(swap_info.reflection_pending_a, 100);
+
+        position
+            .set_amount(3, swap_info.acc_reflection_per_lp_share_a, 0)
+            .unwrap();
+        swap_info.reflection_supply = 3;
+        swap_info.accrue_reflection(&token_a, 1).unwrap();
+        // 101 / 3 per pool token, the rounding dust stays pending
+        assert_eq!(swap_info.reflection_pending_a, 1);
+        assert_eq!(swap_info.reflection_pending_b, 0);
+        assert_eq!(swap_info.accrue_reflection(&Pubkey::new_unique(), 1), None);
+
+        // pool tokens deposited after an accrual do not share it
+        position
+            .set_amount(6, swap_info.acc_reflection_per_lp_share_a, 0)
+            .unwrap();
+        assert_eq!(position.reflection_owed_a, 100);
+        swap_info.reflection_supply = 6;
+        swap_info.accrue_reflection(&token_b, 12).unwrap();
+        position
+            .set_amount(
+                0,
+                swap_info.acc_reflection_per_lp_share_a,
+                swap_info.acc_reflection_per_lp_share_b,
+            )
+            .unwrap();
+        assert_eq!(position.reflection_owed_a, 100);
+        assert_eq!(position.reflection_owed_b, 12);
+        assert_eq!(position.reflection_debt_a, 0);
+        assert_eq!(position.reflection_debt_b, 0);
+    }
+
+    #[test]
+    fn test_twap_order_packing() {
+        let swap_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let escrow_raw = [3u8; 32];
+        let destination_raw = [4u8; 32];
+        let nonce = 254;
+        let order_id: u64 = 7;
+        let amount_in: u64 = 1_000_000;
+        let slice_count: u16 = 10;
+        let slices_executed: u16 = 3;
+        let slice_interval_slots: u64 = 25;
+        let max_price_deviation_bps: u16 = 50;
+        let last_slice_slot: Slot = 1_075;
+        let price_cumulative: u128 = u128::MAX - 1;
+        let price_cumulative_slot: Slot = 1_070;
+        let amount_out: u64 = 299_000;
+        let order = TwapOrder {
+            is_initialized: true,
+            nonce,
+            swap: Pubkey::new_from_array(swap_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            order_id,
+            escrow: Pubkey::new_from_array(escrow_raw),
+            destination: Pubkey::new_from_array(destination_raw),
+            amount_in,
+            slice_count,
+            slices_executed,
+            slice_interval_slots,
+            max_price_deviation_bps,
+            last_slice_slot,
+            price_cumulative,
+            price_cumulative_slot,
+            amount_out,
+        };
+
+        let mut packed = [0u8; TwapOrder::LEN];
+        TwapOrder::pack(order, &mut packed).unwrap();
+        let unpacked = TwapOrder::unpack(&packed).unwrap();
+        assert_eq!(order, unpacked);
+
+        let mut packed = vec![1, nonce];
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&order_id.to_le_bytes());
+        packed.extend_from_slice(&escrow_raw);
+        packed.extend_from_slice(&destination_raw);
+        packed.extend_from_slice(&amount_in.to_le_bytes());
+        packed.extend_from_slice(&slice_count.to_le_bytes());
+        packed.extend_from_slice(&slices_executed.to_le_bytes());
+        packed.extend_from_slice(&slice_interval_slots.to_le_bytes());
+        packed.extend_from_slice(&max_price_deviation_bps.to_le_bytes());
+        packed.extend_from_slice(&last_slice_slot.to_le_bytes());
+        packed.extend_from_slice(&price_cumulative.to_le_bytes());
+        packed.extend_from_slice(&price_cumulative_slot.to_le_bytes());
+        packed.extend_from_slice(&amount_out.to_le_bytes());
+        let unpacked = TwapOrder::unpack(&packed).unwrap();
+        assert_eq!(order, unpacked);
+
+        let packed = [0u8; TwapOrder::LEN];
+        let err = TwapOrder::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_twap_order_slices() {
+        let mut order = TwapOrder {
+            is_initialized: true,
+            amount_in: 1_000,
+            slice_count: 3,
+            slice_interval_slots: 10,
+            max_price_deviation_bps: 100,
+            last_slice_slot: 50,
+            ..TwapOrder::default()
+        };
+        assert_eq!(order.next_slice_slot(), Some(60));
+
+        // Slices only differ by rounding and add up to the order amount
+        let mut slices = vec![];
+        while let Some(amount) = order.next_slice_amount() {
+            assert_eq!(
+                order.remaining_amount(),
+                Some(1_000 - slices.iter().sum::<u64>())
+            );
+            slices.push(amount);
+            order.slices_executed += 1;
+        }
+        assert_eq!(slices, vec![333, 333, 334]);
+        assert_eq!(order.remaining_amount(), Some(0));
+
+        // At par, a slice may receive 1% less than it pays in
+        assert_eq!(
+            order.minimum_slice_amount_out(1_000, PRICE_SCALE as u128),
+            Some(990)
+        );
+        assert_eq!(
+            order.minimum_slice_amount_out(1_000, 2 * PRICE_SCALE as u128),
+            Some(1_980)
+        );
+        order.max_price_deviation_bps = MAX_TWAP_PRICE_DEVIATION_BPS;
+        assert_eq!(
+            order.minimum_slice_amount_out(1_000, PRICE_SCALE as u128),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn test_trigger_order_packing() {
+        let swap_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let escrow_raw = [3u8; 32];
+        let destination_raw = [4u8; 32];
+        let oracle_raw = [5u8; 32];
+        let nonce = 253;
+        let trigger_id: u64 = 9;
+        let minimum_amount_out: u64 = 990_000;
+        let trigger_price: u128 = 1_050_000_000_000;
+        let bounty: u64 = 5_000;
+        let trigger = TriggerOrder {
+            is_initialized: true,
+            nonce,
+            swap: Pubkey::new_from_array(swap_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            trigger_id,
+            escrow: Pubkey::new_from_array(escrow_raw),
+            destination: Pubkey::new_from_array(destination_raw),
+            oracle: Pubkey::new_from_array(oracle_raw),
+            minimum_amount_out,
+            trigger_price,
+            trigger_above: true,
+            bounty,
+        };
+
+        let mut packed = [0u8; TriggerOrder::LEN];
+        TriggerOrder::pack(trigger, &mut packed).unwrap();
+        let unpacked = TriggerOrder::unpack(&packed).unwrap();
+        assert_eq!(trigger, unpacked);
+
+        let mut packed = vec![1, nonce];
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&trigger_id.to_le_bytes());
+        packed.extend_from_slice(&escrow_raw);
+        packed.extend_from_slice(&destination_raw);
+        packed.extend_from_slice(&oracle_raw);
+        packed.extend_from_slice(&minimum_amount_out.to_le_bytes());
+        packed.extend_from_slice(&trigger_price.to_le_bytes());
+        packed.push(1);
+        packed.extend_from_slice(&bounty.to_le_bytes());
+        let unpacked = TriggerOrder::unpack(&packed).unwrap();
+        assert_eq!(trigger, unpacked);
+
+        let packed = [0u8; TriggerOrder::LEN];
+        let err = TriggerOrder::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_trigger_order_is_triggered() {
+        let mut trigger = TriggerOrder {
+            trigger_price: 100,
+            trigger_above: true,
+            ..TriggerOrder::default()
+        };
+        assert!(!trigger.is_triggered(99));
+        assert!(trigger.is_triggered(100));
+        assert!(trigger.is_triggered(101));
+        trigger.trigger_above = false;
+        assert!(trigger.is_triggered(99));
+        assert!(trigger.is_triggered(100));
+        assert!(!trigger.is_triggered(101));
+    }
+
+    #[test]
+    fn test_custom_error_messages() {
+        let mut messages = CustomErrorMessages::default();
+        assert_eq!(messages.message(4), None);
+        assert_eq!(messages.set(4, "migrating to V2"), Some(()));
+        assert_eq!(messages.message(4), Some("migrating to V2"));
+        assert_eq!(messages.set(4, "use pool XYZ"), Some(()));
+        assert_eq!(messages.message(4), Some("use pool XYZ"));
+        for code in 5..(4 + MAX_CUSTOM_ERROR_MESSAGES as u32) {
+            assert_eq!(messages.set(code, "paused"), Some(()));
+        }
+        assert_eq!(messages.set(100, "full"), None);
+        assert_eq!(messages.set(4, ""), Some(()));
+        assert_eq!(messages.message(4), None);
+        assert_eq!(messages.set(100, "full"), Some(()));
+        let long = "x".repeat(MAX_CUSTOM_ERROR_MESSAGE_LEN + 1);
+        assert_eq!(messages.set(5, &long), None);
+        assert_eq!(messages.message(5), Some("paused"));
+
+        let mut packed = [0u8; CustomErrorMessages::LEN];
+        messages.pack_into_slice(&mut packed);
+        assert_eq!(
+            CustomErrorMessages::unpack_from_slice(&packed),
+            Ok(messages)
+        );
+        packed[4] = MAX_CUSTOM_ERROR_MESSAGE_LEN as u8 + 1;
+        assert_eq!(
+            CustomErrorMessages::unpack_from_slice(&packed),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_drip_deposit_packing() {
+        let swap_raw = [1u8; 32];
+        let owner_raw = [2u8; 32];
+        let escrow_a_raw = [3u8; 32];
+        let escrow_b_raw = [4u8; 32];
+        let destination_raw = [5u8; 32];
+        let nonce = 251;
+        let drip_id: u64 = 4;
+        let amount_a: u64 = 2_000_000;
+        let amount_b: u64 = 1_000_000;
+        let tranche_count: u16 = 20;
+        let tranches_deposited: u16 = 6;
+        let tranche_interval_slots: u64 = 300;
+        let last_tranche_slot: Slot = 9_000;
+        let drip = DripDeposit {
+            is_initialized: true,
+            nonce,
+            swap: Pubkey::new_from_array(swap_raw),
+            owner: Pubkey::new_from_array(owner_raw),
+            drip_id,
+            escrow_a: Pubkey::new_from_array(escrow_a_raw),
+            escrow_b: Pubkey::new_from_array(escrow_b_raw),
+            destination: Pubkey::new_from_array(destination_raw),
+            amount_a,
+            amount_b,
+            tranche_count,
+            tranches_deposited,
+            tranche_interval_slots,
+            last_tranche_slot,
+        };
+
+        let mut packed = [0u8; DripDeposit::LEN];
+        DripDeposit::pack(drip, &mut packed).unwrap();
+        let unpacked = DripDeposit::unpack(&packed).unwrap();
+        assert_eq!(drip, unpacked);
+
+        let mut packed = vec![1, nonce];
+        packed.extend_from_slice(&swap_raw);
+        packed.extend_from_slice(&owner_raw);
+        packed.extend_from_slice(&drip_id.to_le_bytes());
+        packed.extend_from_slice(&escrow_a_raw);
+        packed.extend_from_slice(&escrow_b_raw);
+        packed.extend_from_slice(&destination_raw);
+        packed.extend_from_slice(&amount_a.to_le_bytes());
+        packed.extend_from_slice(&amount_b.to_le_bytes());
+        packed.extend_from_slice(&tranche_count.to_le_bytes());
+        packed.extend_from_slice(&tranches_deposited.to_le_bytes());
+        packed.extend_from_slice(&tranche_interval_slots.to_le_bytes());
+        packed.extend_from_slice(&last_tranche_slot.to_le_bytes());
+        let unpacked = DripDeposit::unpack(&packed).unwrap();
+        assert_eq!(drip, unpacked);
+
+        let packed = [0u8; DripDeposit::LEN];
+        let err = DripDeposit::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_global_stats_packing() {
+        let nonce = 254;
+        let total_pools: u64 = 12;
+        let total_value_locked: u128 = 50_000_000_000;
+        let cumulative_volume: u128 = u64::MAX as u128 + 1;
+        let stats = GlobalStats {
+            is_initialized: true,
+            nonce,
+            total_pools,
+            total_value_locked,
+            cumulative_volume,
+        };
+
+        let mut packed = [0u8; GlobalStats::LEN];
+        GlobalStats::pack(stats, &mut packed).unwrap();
+        let unpacked = GlobalStats::unpack(&packed).unwrap();
+        assert_eq!(stats, unpacked);
+
+        let mut packed = vec![1, nonce];
+        packed.extend_from_slice(&total_pools.to_le_bytes());
+        packed.extend_from_slice(&total_value_locked.to_le_bytes());
+        packed.extend_from_slice(&cumulative_volume.to_le_bytes());
+        let unpacked = GlobalStats::unpack(&packed).unwrap();
+        assert_eq!(stats, unpacked);
+
+        let packed = [0u8; GlobalStats::LEN];
+        let err = GlobalStats::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_fee_exemptions() {
+        let trader = Pubkey::new_unique();
+        let mut exemptions = FeeExemptions {
+            is_initialized: true,
+            nonce: 253,
+            swap: Pubkey::new_unique(),
+            ..FeeExemptions::default()
+        };
+        assert!(!exemptions.is_exempt(&trader));
+        assert!(!exemptions.is_exempt(&Pubkey::default()));
+        assert_eq!(exemptions.remove(&trader), None);
+
+        assert_eq!(exemptions.add(trader), Some(()));
+        assert_eq!(exemptions.add(trader), Some(()));
+        assert!(exemptions.is_exempt(&trader));
+        assert_eq!(exemptions.exempt[1], Pubkey::default());
+
+        let mut packed = [0u8; FeeExemptions::LEN];
+        FeeExemptions::pack(exemptions, &mut packed).unwrap();
+        assert_eq!(&packed[..2], &[1, 253]);
+        assert_eq!(&packed[34..66], trader.as_ref());
+        assert_eq!(FeeExemptions::unpack(&packed).unwrap(), exemptions);
+
+        for _ in 1..MAX_FEE_EXEMPTIONS {
+            assert_eq!(exemptions.add(Pubkey::new_unique()), Some(()));
+        }
+        assert_eq!(exemptions.add(Pubkey::new_unique()), None);
+        assert_eq!(exemptions.remove(&trader), Some(()));
+        assert!(!exemptions.is_exempt(&trader));
+        assert_eq!(exemptions.add(Pubkey::new_unique()), Some(()));
+
+        let packed = [0u8; FeeExemptions::LEN];
+        let err = FeeExemptions::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_deposit_allowlist() {
+        let depositor = Pubkey::new_unique();
+        let mut allowlist = DepositAllowlist {
+            is_initialized: true,
+            nonce: 253,
+            swap: Pubkey::new_unique(),
+            ..DepositAllowlist::default()
+        };
+        assert!(!allowlist.is_allowed(&depositor));
+        assert!(!allowlist.is_allowed(&Pubkey::default()));
+        assert_eq!(allowlist.remove(&depositor), None);
+
+        assert_eq!(allowlist.add(depositor), Some(()));
+        assert_eq!(allowlist.add(depositor), Some(()));
+        assert!(allowlist.is_allowed(&depositor));
+        assert_eq!(allowlist.depositors[1], Pubkey::default());
+
+        let mut packed = [0u8; DepositAllowlist::LEN];
+        DepositAllowlist::pack(allowlist, &mut packed).unwrap();
+        assert_eq!(&packed[..2], &[1, 253]);
+        assert_eq!(&packed[34..66], depositor.as_ref());
+        assert_eq!(DepositAllowlist::unpack(&packed).unwrap(), allowlist);
+
+        for _ in 1..MAX_ALLOWED_DEPOSITORS {
+            assert_eq!(allowlist.add(Pubkey::new_unique()), Some(()));
+        }
+        assert_eq!(allowlist.add(Pubkey::new_unique()), None);
+        assert_eq!(allowlist.remove(&depositor), Some(()));
+        assert!(!allowlist.is_allowed(&depositor));
+        assert_eq!(allowlist.add(Pubkey::new_unique()), Some(()));
+
+        let packed = [0u8; DepositAllowlist::LEN];
+        let err = DepositAllowlist::unpack(&packed).unwrap_err();
+        assert_eq!(err, ProgramError::UninitializedAccount);
+    }
+
+    #[test]
+    fn test_referral_accrual() {
+        let mut accrual = ReferralAccrual {
+            is_initialized: true,
+            nonce: 252,
+            swap: Pubkey::new_unique(),
+            referrer: Pubkey::new_unique(),
+            ..ReferralAccrual::default()
+        };
+        assert_eq!(accrual.record_swap(true, 1_000, false, 3), Some(()));
+        assert_eq!(accrual.record_swap(false, 2_000, true, 5), Some(()));
+        assert_eq!(accrual.referred_volume_a, 1_000);
+        assert_eq!(accrual.referred_volume_b, 2_000);
+        assert_eq!(accrual.claimable(), Some((5, 3)));
+        accrual.fees_claimed_a = 5;
+        assert_eq!(accrual.claimable(), Some((0, 3)));
+        accrual.fees_earned_b = u64::MAX;
+        assert_eq!(accrual.record_swap(true, 1, false, 1), None);
+
+        let mut packed = [0u8; ReferralAccrual::LEN];
+        ReferralAccrual::pack(accrual, &mut packed).unwrap();
+        assert_eq!(&packed[..2], &[1, 252]);
+        assert_eq!(&packed[2..34], accrual.swap.as_ref());
+        assert_eq!(&packed[34..66], accrual.referrer.as_ref());
+        assert_eq!(ReferralAccrual::unpack(&packed).unwrap(), accrual);
+
+        let packed = [0u8; ReferralAccrual::LEN];
+        let err = ReferralAccrual::unpack(&packed).unwrap_err();
         assert_eq!(err, ProgramError::UninitializedAccount);
     }
+
+    #[test]
+    fn test_global_stats_records() {
+        let mut stats = GlobalStats::default();
+        stats.record_pool(1_000, 2_000);
+        stats.add_value(500);
+        stats.record_swap(100, 99);
+        assert_eq!(stats.total_pools, 1);
+        assert_eq!(stats.total_value_locked, 3_501);
+        assert_eq!(stats.cumulative_volume, 100);
+
+        // untracked liquidity never underflows the estimate
+        stats.remove_value(10_000);
+        assert_eq!(stats.total_value_locked, 0);
+    }
+
+    #[test]
+    fn test_drip_deposit_tranches() {
+        let mut drip = DripDeposit {
+            amount_a: 1_000,
+            amount_b: 10,
+            tranche_count: 3,
+            tranche_interval_slots: 5,
+            last_tranche_slot: 20,
+            ..DripDeposit::default()
+        };
+        assert_eq!(drip.next_tranche_slot(), Some(25));
+        let mut tranches = vec![];
+        while let Some(amounts) = drip.next_tranche_amounts() {
+            tranches.push(amounts);
+            drip.tranches_deposited += 1;
+        }
+        assert_eq!(tranches, vec![(333, 3), (333, 3), (334, 4)]);
+    }
+
+    #[test]
+    fn test_time_weighted_liquidity() {
+        let mut position = LiquidityPosition {
+            is_initialized: true,
+            amount: 100,
+            checkpoint_ts: 1_000,
+            ..Default::default()
+        };
+        let start = position.cumulative_liquidity_at(1_000).unwrap();
+        assert_eq!(start, 0);
+
+        // held for 900 seconds, then topped up right before the end of the period
+        position.checkpoint(1_900).unwrap();
+        position.amount += 10_000;
+        assert_eq!(position.cumulative_liquidity, 90_000);
+        let end = position.cumulative_liquidity_at(2_000).unwrap();
+        assert_eq!(end, 90_000 + 10_100 * 100);
+        assert_eq!(
+            time_weighted_liquidity(start, 1_000, end, 2_000),
+            Some(1_100)
+        );
+
+        // no liquidity accumulates backwards or over an empty period
+        position.checkpoint(1_500).unwrap();
+        assert_eq!(position.checkpoint_ts, 1_900);
+        assert_eq!(position.cumulative_liquidity, 90_000);
+        assert_eq!(time_weighted_liquidity(end, 2_000, end, 2_000), Some(0));
+
+        let mut swap_info = SwapInfo {
+            position_liquidity: 50,
+            position_liquidity_ts: 10,
+            ..Default::default()
+        };
+        swap_info.checkpoint_position_liquidity(30).unwrap();
+        assert_eq!(swap_info.cumulative_position_liquidity, 1_000);
+        assert_eq!(swap_info.position_liquidity_ts, 30);
+    }
+
+    #[test]
+    fn test_take_epoch_fee_report() {
+        let swap = Pubkey::new_from_array([1u8; 32]);
+        let token_a = Pubkey::new_from_array([2u8; 32]);
+        let token_b = Pubkey::new_from_array([3u8; 32]);
+        let mut swap_info = SwapInfo {
+            token_a,
+            token_b,
+            fee_report_epoch: 4,
+            ..Default::default()
+        };
+        let result = SwapResultV2 {
+            new_source_amount: U256::zero(),
+            new_destination_amount: U256::zero(),
+            amount_swapped: U256::zero(),
+            trade_fee: 100.into(),
+            reflection_fee: 10.into(),
+            buyback_fee: 9.into(),
+            marketing_fee: 8.into(),
+            developer_fee: 7.into(),
+            admin_fee: 6.into(),
+        };
+        swap_info.record_epoch_fees(&token_a, &result).unwrap();
+        swap_info.record_epoch_fees(&token_b, &result).unwrap();
+        swap_info.record_epoch_fees(&token_b, &result).unwrap();
+
+        assert_eq!(swap_info.take_epoch_fee_report(&swap, 4), None);
+        let report = swap_info.take_epoch_fee_report(&swap, 6).unwrap();
+        assert_eq!(
+            report,
+            EpochFeeReport {
+                swap,
+                epoch: 4,
+                lp_fees_a: 60,
+                lp_fees_b: 120,
+                admin_fees_a: 6,
+                admin_fees_b: 12,
+                reflection_fees_a: 10,
+                reflection_fees_b: 20,
+                buyback_fees_a: 9,
+                buyback_fees_b: 18,
+                marketing_fees_a: 8,
+                marketing_fees_b: 16,
+                developer_fees_a: 7,
+                developer_fees_b: 14,
+            }
+        );
+        assert_eq!(swap_info.fee_report_epoch, 6);
+        assert_eq!(
+            swap_info.take_epoch_fee_report(&swap, 7),
+            Some(EpochFeeReport {
+                swap,
+                epoch: 6,
+                ..Default::default()
+            })
+        );
+    }
 }
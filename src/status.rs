@@ -0,0 +1,122 @@
+//! Pool status logged by `GetPoolStatus` for health checks
+
+use crate::{
+    state::{SwapInfo, MAX_DUST_SWEEPS_PER_SLOT},
+    twap::TWAP_WINDOW_SLOTS,
+};
+use solana_program::{clock::Slot, pubkey::Pubkey};
+use std::fmt;
+
+/// Prefix of the pool status log line
+pub const POOL_STATUS_PREFIX: &str = "pool_status";
+
+/// The pool is paused
+pub const STATUS_PAUSED: u8 = 1 << 0;
+/// Swaps are restricted to access token holders, during the launch access window
+pub const STATUS_ACCESS_RESTRICTED: u8 = 1 << 1;
+/// Only the best order-flow bidder may swap, in the last slot of the batch
+pub const STATUS_BACKRUN_SLOT: u8 = 1 << 2;
+/// The dynamic amp TWAP was not observed for a whole TWAP window
+pub const STATUS_ORACLE_STALE: u8 = 1 << 3;
+/// The dust sweeps of the slot are exhausted
+pub const STATUS_DUST_SWEEPS_EXHAUSTED: u8 = 1 << 4;
+
+/// Health of a pool at a slot. Displays as a single line of space separated
+/// `key=value` pairs, led by `POOL_STATUS_PREFIX`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PoolStatus {
+    /// Token-swap
+    pub swap: Pubkey,
+    /// Slot the status holds in
+    pub slot: Slot,
+    /// Set `STATUS_*` bits, zero when the pool is fully available
+    pub status: u8,
+}
+
+impl PoolStatus {
+    /// Status of `token_swap` in `slot`
+    pub fn new(swap: Pubkey, token_swap: &SwapInfo, slot: Slot) -> Self {
+        let mut status = 0;
+        if token_swap.is_paused {
+            status |= STATUS_PAUSED;
+        }
+        if slot < token_swap.access_window_end_slot {
+            status |= STATUS_ACCESS_RESTRICTED;
+        }
+        if token_swap.order_flow_bid != 0 && token_swap.is_order_flow_backrun_slot(slot) {
+            status |= STATUS_BACKRUN_SLOT;
+        }
+        if token_swap.is_dynamic_amp_enabled()
+            && slot.saturating_sub(token_swap.twap_slot) > TWAP_WINDOW_SLOTS
+        {
+            status |= STATUS_ORACLE_STALE;
+        }
+        if token_swap.dust_sweep_slot == slot
+            && token_swap.dust_sweep_count >= MAX_DUST_SWEEPS_PER_SLOT
+        {
+            status |= STATUS_DUST_SWEEPS_EXHAUSTED;
+        }
+        Self { swap, slot, status }
+    }
+}
+
+impl fmt::Display for PoolStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} swap={} slot={} status={}",
+            POOL_STATUS_PREFIX, self.swap, self.slot, self.status,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::program_pack::Pack;
+
+    #[test]
+    fn test_pool_status_display() {
+        let swap = Pubkey::new_from_array([1u8; 32]);
+        let status = PoolStatus {
+            swap,
+            slot: 42,
+            status: STATUS_PAUSED | STATUS_ORACLE_STALE,
+        };
+
+        assert_eq!(
+            status.to_string(),
+            format!("pool_status swap={} slot=42 status=9", swap)
+        );
+    }
+
+    #[test]
+    fn test_pool_status_new() {
+        let swap = Pubkey::new_from_array([1u8; 32]);
+        let mut token_swap = SwapInfo::unpack_from_slice(&[0u8; SwapInfo::LEN]).unwrap();
+        assert_eq!(PoolStatus::new(swap, &token_swap, 1_000).status, 0);
+
+        token_swap.is_paused = true;
+        token_swap.access_window_end_slot = 1_001;
+        token_swap.order_flow_batch_slots = 10;
+        token_swap.order_flow_bid = 1;
+        token_swap.dynamic_amp_min = 1;
+        token_swap.dust_sweep_slot = 999;
+        token_swap.dust_sweep_count = MAX_DUST_SWEEPS_PER_SLOT;
+        assert_eq!(
+            PoolStatus::new(swap, &token_swap, 999).status,
+            STATUS_PAUSED
+                | STATUS_ACCESS_RESTRICTED
+                | STATUS_BACKRUN_SLOT
+                | STATUS_ORACLE_STALE
+                | STATUS_DUST_SWEEPS_EXHAUSTED
+        );
+
+        // the next slot starts a batch and resets the dust sweeps
+        token_swap.twap_slot = 1_000 - TWAP_WINDOW_SLOTS;
+        assert_eq!(
+            PoolStatus::new(swap, &token_swap, 1_000).status,
+            STATUS_PAUSED | STATUS_ACCESS_RESTRICTED
+        );
+    }
+}
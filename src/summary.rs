@@ -0,0 +1,88 @@
+//! Pool summary logged by `EmitPoolSummary` for monitoring
+
+use crate::bn::U256;
+use solana_program::{hash::Hash, pubkey::Pubkey};
+use std::fmt;
+
+/// Prefix of the pool summary log line
+pub const POOL_SUMMARY_PREFIX: &str = "pool_summary";
+
+/// State of a pool at the time of the summary. Displays as a single line of
+/// space separated `key=value` pairs, led by `POOL_SUMMARY_PREFIX`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PoolSummary {
+    /// Token-swap
+    pub swap: Pubkey,
+    /// Token A backing the pool, excluding accrued fees and pending withdrawals
+    pub reserve_a: u64,
+    /// Token B backing the pool, excluding accrued fees and pending withdrawals
+    pub reserve_b: u64,
+    /// Pool token supply
+    pub lp_supply: u64,
+    /// Virtual price of a pool token, scaled by `VIRTUAL_PRICE_SCALE`
+    pub virtual_price: U256,
+    /// Admin trade fees accrued in token A, awaiting harvest
+    pub admin_fees_a: u64,
+    /// Admin trade fees accrued in token B, awaiting harvest
+    pub admin_fees_b: u64,
+    /// Developer fees in token A received by the vesting account and not claimed yet
+    pub dev_fees_a: u64,
+    /// Developer fees in token B received by the vesting account and not claimed yet
+    pub dev_fees_b: u64,
+    /// Hash of the admin-controlled pool parameters
+    pub config_hash: Hash,
+}
+
+impl fmt::Display for PoolSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} swap={} reserve_a={} reserve_b={} lp_supply={} virtual_price={} \
+             admin_fees_a={} admin_fees_b={} dev_fees_a={} dev_fees_b={} config_hash={}",
+            POOL_SUMMARY_PREFIX,
+            self.swap,
+            self.reserve_a,
+            self.reserve_b,
+            self.lp_supply,
+            self.virtual_price,
+            self.admin_fees_a,
+            self.admin_fees_b,
+            self.dev_fees_a,
+            self.dev_fees_b,
+            self.config_hash,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_summary_display() {
+        let swap = Pubkey::new_from_array([1u8; 32]);
+        let config_hash = Hash::new_from_array([2u8; 32]);
+        let summary = PoolSummary {
+            swap,
+            reserve_a: 1_000,
+            reserve_b: 2_000,
+            lp_supply: 3_000,
+            virtual_price: U256::from(1_000_000_000_000u64),
+            admin_fees_a: 4,
+            admin_fees_b: 5,
+            dev_fees_a: 6,
+            dev_fees_b: 7,
+            config_hash,
+        };
+
+        assert_eq!(
+            summary.to_string(),
+            format!(
+                "pool_summary swap={} reserve_a=1000 reserve_b=2000 lp_supply=3000 \
+                 virtual_price=1000000000000 admin_fees_a=4 admin_fees_b=5 dev_fees_a=6 \
+                 dev_fees_b=7 config_hash={}",
+                swap, config_hash
+            )
+        );
+    }
+}
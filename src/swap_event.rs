@@ -0,0 +1,92 @@
+//! Swap event logged by every executed swap for indexers and quote caches
+
+use solana_program::{hash::Hash, pubkey::Pubkey};
+use std::fmt;
+
+/// Prefix of the swap event log line
+pub const SWAP_EVENT_PREFIX: &str = "swap_event";
+
+/// Outcome of an executed swap. Displays as a single line of space separated
+/// `key=value` pairs, led by `SWAP_EVENT_PREFIX`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SwapEvent {
+    /// Token-swap
+    pub swap: Pubkey,
+    /// Swap token account swapped into
+    pub swap_source: Pubkey,
+    /// Swap token account swapped from
+    pub swap_destination: Pubkey,
+    /// Source token paid in
+    pub amount_in: u64,
+    /// Destination token received
+    pub amount_out: u64,
+    /// Total trade fee, of which the other fees are a share
+    pub trade_fee: u64,
+    /// Admin share of the trade fee
+    pub admin_fee: u64,
+    /// Sequence number of the swap state after the swap
+    pub sequence: u64,
+    /// Configuration hash of the pool the swap was priced with. Cached quotes and
+    /// parameters stay valid as long as it is unchanged.
+    pub config_hash: Hash,
+    /// Whether the freeze authority of a token mint can halt the pool, as
+    /// acknowledged by its creator
+    pub freeze_authority_acknowledged: bool,
+}
+
+impl fmt::Display for SwapEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} swap={} swap_source={} swap_destination={} amount_in={} amount_out={} \
+             trade_fee={} admin_fee={} sequence={} config_hash={} \
+             freeze_authority_acknowledged={}",
+            SWAP_EVENT_PREFIX,
+            self.swap,
+            self.swap_source,
+            self.swap_destination,
+            self.amount_in,
+            self.amount_out,
+            self.trade_fee,
+            self.admin_fee,
+            self.sequence,
+            self.config_hash,
+            self.freeze_authority_acknowledged,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_event_display() {
+        let swap = Pubkey::new_from_array([1u8; 32]);
+        let swap_source = Pubkey::new_from_array([2u8; 32]);
+        let swap_destination = Pubkey::new_from_array([3u8; 32]);
+        let config_hash = Hash::new_from_array([4u8; 32]);
+        let event = SwapEvent {
+            swap,
+            swap_source,
+            swap_destination,
+            amount_in: 1_000,
+            amount_out: 990,
+            trade_fee: 6,
+            admin_fee: 3,
+            sequence: 42,
+            config_hash,
+            freeze_authority_acknowledged: true,
+        };
+
+        assert_eq!(
+            event.to_string(),
+            format!(
+                "swap_event swap={} swap_source={} swap_destination={} amount_in=1000 \
+                 amount_out=990 trade_fee=6 admin_fee=3 sequence=42 config_hash={} \
+                 freeze_authority_acknowledged=true",
+                swap, swap_source, swap_destination, config_hash
+            )
+        );
+    }
+}
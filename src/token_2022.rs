@@ -0,0 +1,195 @@
+//! Token-2022 support. Token-2022 shares the spl_token instruction and base state
+//! layouts, the extensions of a mint or account follow its base state.
+
+use crate::{curve::TransferFee, error::SwapError};
+use arrayref::{array_ref, array_refs};
+use solana_program::{
+    instruction::Instruction, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+};
+use spl_token::state::{Account, Mint};
+
+solana_program::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+/// Offset of the account type of mints and accounts with extensions, both padded
+/// to the spl_token account length
+const ACCOUNT_TYPE_OFFSET: usize = Account::LEN;
+const ACCOUNT_TYPE_MINT: u8 = 1;
+const ACCOUNT_TYPE_ACCOUNT: u8 = 2;
+/// Type of the mint extension configuring transfer fees
+const TRANSFER_FEE_CONFIG_EXTENSION: u16 = 1;
+const TRANSFER_FEE_CONFIG_LEN: usize = 108;
+
+/// Whether `program_id` is the spl_token or the Token-2022 program
+pub fn is_token_program(program_id: &Pubkey) -> bool {
+    *program_id == spl_token::id() || *program_id == id()
+}
+
+fn base_state(data: &[u8], len: usize, account_type: u8) -> Option<&[u8]> {
+    if data.len() == len {
+        Some(data)
+    } else if data.len() > ACCOUNT_TYPE_OFFSET && data[ACCOUNT_TYPE_OFFSET] == account_type {
+        Some(&data[..len])
+    } else {
+        None
+    }
+}
+
+/// The spl_token `Account` part of token account data, with or without extensions
+pub fn account_state(data: &[u8]) -> Option<&[u8]> {
+    base_state(data, Account::LEN, ACCOUNT_TYPE_ACCOUNT)
+}
+
+/// The spl_token `Mint` part of mint data, with or without extensions
+pub fn mint_state(data: &[u8]) -> Option<&[u8]> {
+    base_state(data, Mint::LEN, ACCOUNT_TYPE_MINT)
+}
+
+/// Unpacks the epoch a transfer fee applies from, and the fee
+fn unpack_transfer_fee(src: &[u8; 18]) -> (u64, TransferFee) {
+    let (epoch, maximum_fee, basis_points) = array_refs![src, 8, 8, 2];
+    (
+        u64::from_le_bytes(*epoch),
+        TransferFee {
+            basis_points: u16::from_le_bytes(*basis_points),
+            maximum_fee: u64::from_le_bytes(*maximum_fee),
+        },
+    )
+}
+
+/// Transfer fee the mint withholds at `epoch`, none unless it has a transfer fee
+/// configuration.
+pub fn transfer_fee(mint_data: &[u8], epoch: u64) -> Result<TransferFee, SwapError> {
+    mint_state(mint_data).ok_or(SwapError::ExpectedMint)?;
+    let mut extensions = mint_data.get(ACCOUNT_TYPE_OFFSET + 1..).unwrap_or(&[]);
+    while extensions.len() >= 4 {
+        let extension_type = u16::from_le_bytes([extensions[0], extensions[1]]);
+        let len = u16::from_le_bytes([extensions[2], extensions[3]]) as usize;
+        let value = extensions.get(4..4 + len).ok_or(SwapError::ExpectedMint)?;
+        if extension_type == TRANSFER_FEE_CONFIG_EXTENSION {
+            let value = value
+                .get(..TRANSFER_FEE_CONFIG_LEN)
+                .ok_or(SwapError::ExpectedMint)?;
+            let value = array_ref![value, 0, TRANSFER_FEE_CONFIG_LEN];
+            let (_config_authority, _withdraw_authority, _withheld_amount, older, newer) =
+                array_refs![value, 32, 32, 8, 18, 18];
+            let (newer_epoch, newer) = unpack_transfer_fee(newer);
+            let (_, older) = unpack_transfer_fee(older);
+            return Ok(if epoch >= newer_epoch { newer } else { older });
+        }
+        extensions = &extensions[4 + len..];
+    }
+    Ok(TransferFee::default())
+}
+
+/// spl_token instruction builders only accept the spl_token program id, `ix` is
+/// built for spl_token and sent to `token_program_id` instead.
+pub fn instruction(
+    ix: Result<Instruction, ProgramError>,
+    token_program_id: &Pubkey,
+) -> Result<Instruction, ProgramError> {
+    if !is_token_program(token_program_id) {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut ix = ix?;
+    ix.program_id = *token_program_id;
+    Ok(ix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer_fee_config(older: (u64, u64, u16), newer: (u64, u64, u16)) -> Vec<u8> {
+        let mut data = vec![0; ACCOUNT_TYPE_OFFSET];
+        Mint {
+            is_initialized: true,
+            ..Mint::default()
+        }
+        .pack_into_slice(&mut data[..Mint::LEN]);
+        data.push(ACCOUNT_TYPE_MINT);
+        data.extend_from_slice(&TRANSFER_FEE_CONFIG_EXTENSION.to_le_bytes());
+        data.extend_from_slice(&(TRANSFER_FEE_CONFIG_LEN as u16).to_le_bytes());
+        data.extend_from_slice(&[0; 72]);
+        for (epoch, maximum_fee, basis_points) in [older, newer] {
+            data.extend_from_slice(&epoch.to_le_bytes());
+            data.extend_from_slice(&maximum_fee.to_le_bytes());
+            data.extend_from_slice(&basis_points.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_base_state() {
+        let mut data = vec![0; Account::LEN];
+        Account {
+            amount: 42,
+            state: spl_token::state::AccountState::Initialized,
+            ..Account::default()
+        }
+        .pack_into_slice(&mut data);
+        assert_eq!(account_state(&data), Some(&data[..]));
+        let mut extended = data.clone();
+        extended.extend_from_slice(&[ACCOUNT_TYPE_ACCOUNT, 0, 0]);
+        assert_eq!(account_state(&extended), Some(&data[..]));
+        extended[ACCOUNT_TYPE_OFFSET] = ACCOUNT_TYPE_MINT;
+        assert_eq!(account_state(&extended), None);
+        assert_eq!(account_state(&data[1..]), None);
+
+        let mint = transfer_fee_config((0, 0, 0), (0, 0, 0));
+        assert_eq!(mint_state(&mint), Some(&mint[..Mint::LEN]));
+        assert_eq!(mint_state(&mint[..Mint::LEN]), Some(&mint[..Mint::LEN]));
+        assert_eq!(mint_state(&data), None);
+    }
+
+    #[test]
+    fn test_transfer_fee() {
+        let mint = transfer_fee_config((0, 10, 50), (5, 20, 100));
+        assert_eq!(
+            transfer_fee(&mint, 4),
+            Ok(TransferFee {
+                basis_points: 50,
+                maximum_fee: 10,
+            })
+        );
+        assert_eq!(
+            transfer_fee(&mint, 5),
+            Ok(TransferFee {
+                basis_points: 100,
+                maximum_fee: 20,
+            })
+        );
+        assert_eq!(
+            transfer_fee(&mint[..Mint::LEN], 5),
+            Ok(TransferFee::default())
+        );
+        assert_eq!(
+            transfer_fee(&mint[..mint.len() - 1], 5),
+            Err(SwapError::ExpectedMint)
+        );
+    }
+
+    #[test]
+    fn test_instruction() {
+        let (source, destination, authority) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let ix = || {
+            spl_token::instruction::transfer(
+                &spl_token::id(),
+                &source,
+                &destination,
+                &authority,
+                &[],
+                1,
+            )
+        };
+        assert_eq!(instruction(ix(), &id()).unwrap().program_id, id());
+        assert_eq!(instruction(ix(), &spl_token::id()).unwrap(), ix().unwrap());
+        assert_eq!(
+            instruction(ix(), &Pubkey::new_unique()),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
+}
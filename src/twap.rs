@@ -0,0 +1,183 @@
+//! Time-weighted average price helpers
+
+use crate::bn::U256;
+use solana_program::pubkey::Pubkey;
+
+/// Fixed-point scale of TWAP prices
+pub const PRICE_SCALE: u64 = 1_000_000_000_000;
+
+/// Slots over which a price observation fully replaces the rolling TWAP
+pub const TWAP_WINDOW_SLOTS: u64 = 150;
+
+/// Time-weighted average price of a pool, quoted as QUOTE per BASE token
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TwapPrice {
+    /// Mint of the token being priced
+    pub base_mint: Pubkey,
+    /// Mint of the token the price is expressed in
+    pub quote_mint: Pubkey,
+    /// Price scaled by `PRICE_SCALE`
+    pub price: U256,
+    /// Absolute confidence interval of the price, scaled by `PRICE_SCALE`
+    pub confidence: U256,
+}
+
+impl TwapPrice {
+    /// Price of QUOTE in BASE. The relative confidence is unchanged.
+    pub fn invert(&self) -> Option<Self> {
+        let scale_squared = U256::from(PRICE_SCALE).checked_mul(PRICE_SCALE.into())?;
+        let price = scale_squared.checked_div(self.price)?;
+        let confidence = self
+            .confidence
+            .checked_mul(price)?
+            .checked_div(self.price)?;
+        Some(Self {
+            base_mint: self.quote_mint,
+            quote_mint: self.base_mint,
+            price,
+            confidence,
+        })
+    }
+}
+
+/// Combines the TWAPs of two pools sharing a token (A/B and B/C) into an A/C price,
+/// pricing the token of `first` that is not shared in the token of `second` that is
+/// not shared. Either input may be quoted in the opposite direction. Relative
+/// confidences add, which is the first order error of a product.
+pub fn combine(first: &TwapPrice, second: &TwapPrice) -> Option<TwapPrice> {
+    let (first, second) = if first.quote_mint == second.base_mint {
+        (*first, *second)
+    } else if first.quote_mint == second.quote_mint {
+        (*first, second.invert()?)
+    } else if first.base_mint == second.base_mint {
+        (first.invert()?, *second)
+    } else if first.base_mint == second.quote_mint {
+        (first.invert()?, second.invert()?)
+    } else {
+        return None;
+    };
+    if first.base_mint == second.quote_mint {
+        return None;
+    }
+
+    let scale = U256::from(PRICE_SCALE);
+    let price = first.price.checked_mul(second.price)?.checked_div(scale)?;
+    let confidence = first
+        .confidence
+        .checked_mul(second.price)?
+        .checked_add(second.confidence.checked_mul(first.price)?)?
+        .checked_div(scale)?;
+    Some(TwapPrice {
+        base_mint: first.base_mint,
+        quote_mint: second.quote_mint,
+        price,
+        confidence,
+    })
+}
+
+/// Updates a rolling TWAP and its volatility, the rolling average of the relative
+/// deviation of the observed prices from the TWAP, with `price` observed
+/// `elapsed_slots` after the previous observation. Prices and volatility are scaled by
+/// `PRICE_SCALE`. An observation weighs `elapsed_slots / TWAP_WINDOW_SLOTS`, capped at
+/// one, so prices observed in the slot of the previous one are ignored. An unset TWAP
+/// takes the first observed price.
+pub fn observe(
+    twap_price: u128,
+    volatility: u64,
+    elapsed_slots: u64,
+    price: u128,
+) -> Option<(u128, u64)> {
+    if twap_price == 0 {
+        return Some((price, 0));
+    }
+    let window = U256::from(TWAP_WINDOW_SLOTS);
+    let weight = U256::from(elapsed_slots.min(TWAP_WINDOW_SLOTS));
+    let remaining = window.checked_sub(weight)?;
+
+    let deviation = U256::from(price.max(twap_price) - price.min(twap_price))
+        .checked_mul(PRICE_SCALE.into())?
+        .checked_div(twap_price.into())?
+        .min(u64::MAX.into());
+    let twap_price = U256::from(twap_price)
+        .checked_mul(remaining)?
+        .checked_add(U256::from(price).checked_mul(weight)?)?
+        .checked_div(window)?;
+    let volatility = U256::from(volatility)
+        .checked_mul(remaining)?
+        .checked_add(deviation.checked_mul(weight)?)?
+        .checked_div(window)?;
+    Some((twap_price.as_u128(), volatility.as_u64()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn twap(base_mint: Pubkey, quote_mint: Pubkey, price: u64, confidence: u64) -> TwapPrice {
+        TwapPrice {
+            base_mint,
+            quote_mint,
+            price: U256::from(price) * U256::from(PRICE_SCALE) / 1_000,
+            confidence: U256::from(confidence) * U256::from(PRICE_SCALE) / 1_000,
+        }
+    }
+
+    #[test]
+    fn test_combine() {
+        let mint_a = Pubkey::new_unique();
+        let mint_b = Pubkey::new_unique();
+        let mint_c = Pubkey::new_unique();
+        // 1 A = 2 B (+/- 0.1%), 1 B = 0.5 C (+/- 0.2%)
+        let a_b = twap(mint_a, mint_b, 2_000, 2);
+        let b_c = twap(mint_b, mint_c, 500, 1);
+        let expected = twap(mint_a, mint_c, 1_000, 3);
+
+        assert_eq!(combine(&a_b, &b_c), Some(expected));
+        assert_eq!(combine(&b_c, &a_b), Some(twap(mint_c, mint_a, 1_000, 3)));
+        assert_eq!(combine(&a_b, &b_c.invert().unwrap()), Some(expected));
+        assert_eq!(combine(&a_b.invert().unwrap(), &b_c), Some(expected));
+        assert_eq!(
+            combine(&a_b.invert().unwrap(), &b_c.invert().unwrap()),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_combine_unrelated_pools() {
+        let a_b = twap(Pubkey::new_unique(), Pubkey::new_unique(), 1_000, 1);
+        let c_d = twap(Pubkey::new_unique(), Pubkey::new_unique(), 1_000, 1);
+        assert_eq!(combine(&a_b, &c_d), None);
+        // the same pool twice does not produce a price
+        assert_eq!(combine(&a_b, &a_b), None);
+        assert_eq!(combine(&a_b, &a_b.invert().unwrap()), None);
+    }
+
+    #[test]
+    fn test_invert() {
+        let price = twap(Pubkey::new_unique(), Pubkey::new_unique(), 4_000, 40);
+        let inverted = price.invert().unwrap();
+        assert_eq!(inverted.base_mint, price.quote_mint);
+        assert_eq!(inverted.quote_mint, price.base_mint);
+        assert_eq!(inverted.price, U256::from(PRICE_SCALE) / 4);
+        assert_eq!(inverted.confidence, U256::from(PRICE_SCALE) / 400);
+        assert_eq!(inverted.invert().unwrap(), price);
+        assert_eq!(twap(price.base_mint, price.quote_mint, 0, 0).invert(), None);
+    }
+
+    #[test]
+    fn test_observe() {
+        let scale = PRICE_SCALE as u128;
+        assert_eq!(observe(0, 0, 0, 2 * scale), Some((2 * scale, 0)));
+        // observations in the slot of the previous one are ignored
+        assert_eq!(observe(scale, 7, 0, 2 * scale), Some((scale, 7)));
+        // a fifth of the window moves the averages a fifth of the way
+        let (price, volatility) = observe(scale, 0, TWAP_WINDOW_SLOTS / 5, 2 * scale).unwrap();
+        assert_eq!(price, scale + scale / 5);
+        assert_eq!(volatility, PRICE_SCALE / 5);
+        // the weight is capped at one
+        assert_eq!(
+            observe(2 * scale, 0, 10 * TWAP_WINDOW_SLOTS, scale),
+            Some((scale, PRICE_SCALE / 2))
+        );
+    }
+}
@@ -1,6 +1,7 @@
 //! Utility methods
 
-use crate::error::SwapError;
+use crate::{error::SwapError, fees::FeeTier, token_2022};
+use solana_program::bpf_loader_upgradeable;
 use solana_program::program_pack::Pack;
 use solana_program::pubkey::Pubkey;
 use spl_token::state::Account;
@@ -11,25 +12,448 @@ pub fn authority_id(program_id: &Pubkey, my_info: &Pubkey, nonce: u8) -> Result<
         .or(Err(SwapError::InvalidProgramAddress))
 }
 
-/// Unpacks a spl_token `Account`.
+/// Seed prefix of pool program addresses
+pub const POOL_SEED: &[u8] = b"pool";
+
+/// Orders the mints of a pair so either direction of the pair yields the same
+/// pool address.
+pub fn ordered_mints<'a>(
+    token_a_mint: &'a Pubkey,
+    token_b_mint: &'a Pubkey,
+) -> (&'a Pubkey, &'a Pubkey) {
+    if token_a_mint <= token_b_mint {
+        (token_a_mint, token_b_mint)
+    } else {
+        (token_b_mint, token_a_mint)
+    }
+}
+
+/// Namespace of the pool clients find by its mint pair and fee tier alone. Only the
+/// upgrade authority of the program creates pools in it.
+pub const CANONICAL_NAMESPACE: [u8; 32] = [0; 32];
+
+/// Finds the address of the program data of an upgradeable program, holding its
+/// upgrade authority.
+pub fn find_program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+/// Finds the program address of the pool for a mint pair, fee tier and `namespace`,
+/// the address `Initialize` creates the pool at.
+pub fn find_pool_address(
+    program_id: &Pubkey,
+    token_a_mint: &Pubkey,
+    token_b_mint: &Pubkey,
+    fee_tier: FeeTier,
+    namespace: &[u8; 32],
+) -> (Pubkey, u8) {
+    let (first, second) = ordered_mints(token_a_mint, token_b_mint);
+    Pubkey::find_program_address(
+        &[
+            POOL_SEED,
+            first.as_ref(),
+            second.as_ref(),
+            &[fee_tier as u8],
+            namespace,
+        ],
+        program_id,
+    )
+}
+
+/// Seed prefix of swap receipt program addresses
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+/// Finds the program address of the swap receipt `receipt_id` of `owner`.
+pub fn find_receipt_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    owner: &Pubkey,
+    receipt_id: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            RECEIPT_SEED,
+            swap.as_ref(),
+            owner.as_ref(),
+            &receipt_id.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Seed prefix of TWAP order program addresses
+pub const TWAP_ORDER_SEED: &[u8] = b"twap_order";
+
+/// Finds the program address of the TWAP order `order_id` of `owner`.
+pub fn find_twap_order_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    owner: &Pubkey,
+    order_id: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            TWAP_ORDER_SEED,
+            swap.as_ref(),
+            owner.as_ref(),
+            &order_id.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Seed prefix of trigger program addresses
+pub const TRIGGER_SEED: &[u8] = b"trigger";
+
+/// Finds the program address of the trigger `trigger_id` of `owner`.
+pub fn find_trigger_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    owner: &Pubkey,
+    trigger_id: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            TRIGGER_SEED,
+            swap.as_ref(),
+            owner.as_ref(),
+            &trigger_id.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Seed prefix of drip deposit program addresses
+pub const DRIP_DEPOSIT_SEED: &[u8] = b"drip_deposit";
+
+/// Finds the program address of the drip deposit `drip_id` of `owner`.
+pub fn find_drip_deposit_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    owner: &Pubkey,
+    drip_id: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            DRIP_DEPOSIT_SEED,
+            swap.as_ref(),
+            owner.as_ref(),
+            &drip_id.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Seed prefix of admin session program addresses
+pub const ADMIN_SESSION_SEED: &[u8] = b"admin_session";
+
+/// Finds the program address of the admin session of `session_key`.
+pub fn find_admin_session_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    session_key: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ADMIN_SESSION_SEED, swap.as_ref(), session_key.as_ref()],
+        program_id,
+    )
+}
+
+/// Seed prefix of reflection distribution program addresses
+pub const REFLECTION_DISTRIBUTION_SEED: &[u8] = b"reflection_distribution";
+
+/// Seed prefix of reflection claim program addresses
+pub const REFLECTION_CLAIM_SEED: &[u8] = b"reflection_claim";
+
+/// Finds the program address of the reflection distribution of `epoch`.
+pub fn find_reflection_distribution_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    epoch: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            REFLECTION_DISTRIBUTION_SEED,
+            swap.as_ref(),
+            &epoch.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Finds the program address marking the entitlement `index` of a reflection
+/// distribution as claimed.
+pub fn find_reflection_claim_address(
+    program_id: &Pubkey,
+    distribution: &Pubkey,
+    index: u64,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            REFLECTION_CLAIM_SEED,
+            distribution.as_ref(),
+            &index.to_le_bytes(),
+        ],
+        program_id,
+    )
+}
+
+/// Seed prefix of withdraw continuation program addresses
+pub const WITHDRAW_CONTINUATION_SEED: &[u8] = b"withdraw_continuation";
+
+/// Finds the program address of the withdraw continuation of `owner` in `swap`.
+pub fn find_withdraw_continuation_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[WITHDRAW_CONTINUATION_SEED, swap.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// Seed prefix of parameter snapshot program addresses
+pub const PARAMETER_SNAPSHOT_SEED: &[u8] = b"parameter_snapshot";
+
+/// Finds the program address of the parameter snapshot of `swap`.
+pub fn find_parameter_snapshot_address(program_id: &Pubkey, swap: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PARAMETER_SNAPSHOT_SEED, swap.as_ref()], program_id)
+}
+
+/// Seed prefix of governance deposit program addresses
+pub const GOVERNANCE_DEPOSIT_SEED: &[u8] = b"governance_deposit";
+
+/// Seed prefix of voter weight record program addresses
+pub const VOTER_WEIGHT_RECORD_SEED: &[u8] = b"voter-weight-record";
+
+/// Finds the program address of the governance deposit of `owner`.
+pub fn find_governance_deposit_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[GOVERNANCE_DEPOSIT_SEED, swap.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// Finds the program address of the voter weight record of `owner` in `realm`.
+pub fn find_voter_weight_record_address(
+    program_id: &Pubkey,
+    realm: &Pubkey,
+    pool_mint: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            VOTER_WEIGHT_RECORD_SEED,
+            realm.as_ref(),
+            pool_mint.as_ref(),
+            owner.as_ref(),
+        ],
+        program_id,
+    )
+}
+
+/// Seed prefix of vote-escrow lock program addresses
+pub const VE_LOCK_SEED: &[u8] = b"ve_lock";
+
+/// Finds the program address of the vote-escrow lock of `owner` in `swap`.
+pub fn find_ve_lock_address(program_id: &Pubkey, swap: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VE_LOCK_SEED, swap.as_ref(), owner.as_ref()], program_id)
+}
+
+/// Seed prefix of liquidity position program addresses
+pub const LIQUIDITY_POSITION_SEED: &[u8] = b"liquidity_position";
+
+/// Finds the program address of the liquidity position of `owner` in `swap`.
+pub fn find_liquidity_position_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LIQUIDITY_POSITION_SEED, swap.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// Seed prefix of reflection position program addresses
+pub const REFLECTION_POSITION_SEED: &[u8] = b"reflection_position";
+
+/// Finds the program address of the reflection position of `owner` in `swap`.
+pub fn find_reflection_position_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REFLECTION_POSITION_SEED, swap.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// Seed prefix of gauge controller program addresses
+pub const GAUGE_CONTROLLER_SEED: &[u8] = b"gauge_controller";
+
+/// Seed prefix of gauge program addresses
+pub const GAUGE_SEED: &[u8] = b"gauge";
+
+/// Seed prefix of gauge vote program addresses
+pub const GAUGE_VOTE_SEED: &[u8] = b"gauge_vote";
+
+/// Finds the program address of the gauge controller of `swap`.
+pub fn find_gauge_controller_address(program_id: &Pubkey, swap: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GAUGE_CONTROLLER_SEED, swap.as_ref()], program_id)
+}
+
+/// Finds the program address of the gauge of the pool `swap` in `controller`.
+pub fn find_gauge_address(program_id: &Pubkey, controller: &Pubkey, swap: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[GAUGE_SEED, controller.as_ref(), swap.as_ref()],
+        program_id,
+    )
+}
+
+/// Finds the program address of the vote of `owner` for `gauge`.
+pub fn find_gauge_vote_address(
+    program_id: &Pubkey,
+    gauge: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[GAUGE_VOTE_SEED, gauge.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// Seed prefix of deposit allowlist program addresses
+pub const DEPOSIT_ALLOWLIST_SEED: &[u8] = b"deposit_allowlist";
+
+/// Finds the program address of the deposit allowlist of `swap`.
+pub fn find_deposit_allowlist_address(program_id: &Pubkey, swap: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DEPOSIT_ALLOWLIST_SEED, swap.as_ref()], program_id)
+}
+
+/// Seed prefix of farm program addresses
+pub const FARM_SEED: &[u8] = b"farm";
+
+/// Seed prefix of stake account program addresses
+pub const STAKE_ACCOUNT_SEED: &[u8] = b"stake_account";
+
+/// Finds the program address of the farm of `pool_mint`.
+pub fn find_farm_address(program_id: &Pubkey, pool_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FARM_SEED, pool_mint.as_ref()], program_id)
+}
+
+/// Finds the program address of the stake account of `owner` in `farm`.
+pub fn find_stake_account_address(
+    program_id: &Pubkey,
+    farm: &Pubkey,
+    owner: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[STAKE_ACCOUNT_SEED, farm.as_ref(), owner.as_ref()],
+        program_id,
+    )
+}
+
+/// Seed of the global stats program address
+pub const GLOBAL_STATS_SEED: &[u8] = b"global_stats";
+
+/// Finds the program address of the global stats.
+pub fn find_global_stats_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GLOBAL_STATS_SEED], program_id)
+}
+
+/// Seed prefix of fee exemptions program addresses
+pub const FEE_EXEMPTIONS_SEED: &[u8] = b"fee_exemptions";
+
+/// Finds the program address of the fee exemptions of `swap`.
+pub fn find_fee_exemptions_address(program_id: &Pubkey, swap: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[FEE_EXEMPTIONS_SEED, swap.as_ref()], program_id)
+}
+
+/// Seed prefix of referral accrual program addresses
+pub const REFERRAL_ACCRUAL_SEED: &[u8] = b"referral_accrual";
+
+/// Finds the program address of the referral accrual of `referrer` in `swap`.
+pub fn find_referral_accrual_address(
+    program_id: &Pubkey,
+    swap: &Pubkey,
+    referrer: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[REFERRAL_ACCRUAL_SEED, swap.as_ref(), referrer.as_ref()],
+        program_id,
+    )
+}
+
+/// Seed prefix of the program addresses of credentials in their issuer program
+pub const CREDENTIAL_SEED: &[u8] = b"credential";
+
+/// Finds the program address of the credential of `holder` in the issuer program.
+pub fn find_credential_address(issuer_program_id: &Pubkey, holder: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CREDENTIAL_SEED, holder.as_ref()], issuer_program_id)
+}
+
+/// Unpacks a spl_token `Account`, or the base state of a Token-2022 account.
 pub fn unpack_token_account(data: &[u8]) -> Result<Account, SwapError> {
-    Account::unpack(data).map_err(|_| SwapError::ExpectedAccount)
+    token_2022::account_state(data)
+        .and_then(|data| Account::unpack(data).ok())
+        .ok_or(SwapError::ExpectedAccount)
 }
 
 #[cfg(test)]
+#[allow(missing_docs)]
 pub mod test_utils {
+    pub mod scenario;
+
     use crate::{
-        curve::ZERO_TS, fees::Fees, instruction::*, processor::Processor, state::SwapInfo,
+        curve::{CurveType, ZERO_TS},
+        farm::Farm,
+        fee_payout::FeeBucket,
+        fees::{FeeTier, Fees},
+        instruction::*,
+        metadata,
+        oracle::OracleStalePolicy,
+        processor::Processor,
+        state::SwapInfo,
+        utils::{
+            find_admin_session_address, find_deposit_allowlist_address, find_farm_address,
+            find_fee_exemptions_address, find_gauge_address, find_gauge_controller_address,
+            find_gauge_vote_address, find_governance_deposit_address,
+            find_liquidity_position_address, find_parameter_snapshot_address, find_pool_address,
+            find_program_data_address, find_reflection_claim_address,
+            find_reflection_distribution_address, find_reflection_position_address,
+            find_stake_account_address, find_ve_lock_address, find_voter_weight_record_address,
+            find_withdraw_continuation_address,
+        },
     };
     use solana_program::{
-        account_info::AccountInfo, entrypoint::ProgramResult, instruction::Instruction,
+        account_info::AccountInfo,
+        bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction},
         program_error::ProgramError,
+        program_utils::limited_deserialize,
+        system_instruction::SystemInstruction,
+        system_program,
     };
     use solana_program::{
-        clock::Clock, msg, program_pack::Pack, program_stubs, pubkey::Pubkey, rent::Rent,
+        clock::{Clock, Epoch, Slot},
+        msg,
+        program_pack::Pack,
+        program_stubs,
+        pubkey::Pubkey,
+        rent::Rent,
         sysvar::id,
     };
-    use solana_sdk::account::{create_account, create_is_signer_account_infos, Account};
+    use solana_sdk::account::{
+        create_account, create_account_for_test, create_is_signer_account_infos, Account,
+    };
     use spl_token::{
         instruction::{approve, initialize_account, initialize_mint, mint_to},
         state::{Account as SplAccount, Mint as SplMint},
@@ -38,7 +462,7 @@ pub mod test_utils {
     /// Test program id for the swap program.
     pub const SWAP_PROGRAM_ID: Pubkey = Pubkey::new_from_array([2u8; 32]);
     /// Test program id for the token program.
-    pub const TOKEN_PROGRAM_ID: Pubkey = Pubkey::new_from_array([1u8; 32]);
+    pub const TOKEN_PROGRAM_ID: Pubkey = spl_token::ID;
 
     /// Fees for testing
     pub const DEFAULT_TEST_FEES: Fees = Fees {
@@ -58,21 +482,154 @@ pub mod test_utils {
         marketing_fee_denominator: 100,
         developer_fee_numerator: 2,
         developer_fee_denominator: 100,
+        admin_fee_in_pool_tokens: false,
+        fee_on_input: false,
+        imbalance_fee_numerator: 0,
+        imbalance_fee_denominator: 1,
+        imbalance_threshold_bps: 0,
+        host_fee_numerator: 0,
+        host_fee_denominator: 1,
     };
 
     /// Default token decimals
     pub const DEFAULT_TOKEN_DECIMALS: u8 = 6;
 
     pub fn clock_account(ts: i64) -> Account {
-        let mut clock = Clock::default();
-        clock.unix_timestamp = ts;
+        clock_account_at_slot(ts, 0)
+    }
+
+    pub fn clock_account_at_slot(ts: i64, slot: Slot) -> Account {
+        let clock = Clock {
+            slot,
+            unix_timestamp: ts,
+            ..Clock::default()
+        };
+        Account::new_data(1, &clock, &id()).unwrap()
+    }
+
+    pub fn clock_account_at_epoch(ts: i64, epoch: Epoch) -> Account {
+        let clock = Clock {
+            unix_timestamp: ts,
+            epoch,
+            ..Clock::default()
+        };
         Account::new_data(1, &clock, &id()).unwrap()
     }
 
+    pub fn rent_account() -> Account {
+        create_account_for_test(&Rent::default())
+    }
+
+    /// Program data account of the swap program, upgradeable by `upgrade_authority`
+    pub fn program_data_account(upgrade_authority: &Pubkey) -> Account {
+        let mut program_data = Account::new(
+            0,
+            UpgradeableLoaderState::size_of_programdata_metadata(),
+            &bpf_loader_upgradeable::id(),
+        );
+        // bincode layout of `UpgradeableLoaderState::ProgramData`, in slot 0
+        program_data.data[0] = 3;
+        program_data.data[12] = 1;
+        program_data.data[13..45].copy_from_slice(upgrade_authority.as_ref());
+        program_data
+    }
+
+    /// Account paying the rent of a new Token-swap
+    pub fn pool_payer_account() -> Account {
+        let lamports = Rent::default().minimum_balance(SwapInfo::get_packed_len());
+        Account::new(lamports, 0, &Pubkey::default())
+    }
+
     pub fn pubkey_rand() -> Pubkey {
         Pubkey::new_unique()
     }
 
+    /// Synthesized Pyth price account data of `price * 10^exponent`, only the
+    /// header and aggregate price fields `PythPrice::unpack` reads are filled in
+    pub fn pyth_price_data(
+        price: i64,
+        confidence: u64,
+        exponent: i32,
+        publish_slot: u64,
+    ) -> Vec<u8> {
+        let mut data = vec![0; 3312];
+        // magic, version 2, price account, size and price type
+        data[0..4].copy_from_slice(&0xa1b2_c3d4u32.to_le_bytes());
+        data[4..8].copy_from_slice(&2u32.to_le_bytes());
+        data[8..12].copy_from_slice(&3u32.to_le_bytes());
+        data[12..16].copy_from_slice(&3312u32.to_le_bytes());
+        data[16..20].copy_from_slice(&1u32.to_le_bytes());
+        data[20..24].copy_from_slice(&exponent.to_le_bytes());
+        // aggregate price, confidence, trading status and publish slot
+        data[208..216].copy_from_slice(&price.to_le_bytes());
+        data[216..224].copy_from_slice(&confidence.to_le_bytes());
+        data[224..228].copy_from_slice(&1u32.to_le_bytes());
+        data[232..240].copy_from_slice(&publish_slot.to_le_bytes());
+        data
+    }
+
+    /// Pyth price account of `price * 10^exponent`, see `pyth_price_data`
+    pub fn pyth_price_account(
+        price: i64,
+        confidence: u64,
+        exponent: i32,
+        publish_slot: u64,
+    ) -> Account {
+        let data = pyth_price_data(price, confidence, exponent, publish_slot);
+        Account {
+            lamports: 1,
+            data,
+            ..Account::default()
+        }
+    }
+
+    /// Token accounts receiving the swap fees charged in a single token
+    #[derive(Clone)]
+    pub struct FeeAccounts {
+        pub reflection_key: Pubkey,
+        pub reflection_account: Account,
+        pub buyback_key: Pubkey,
+        pub buyback_account: Account,
+        pub marketing_key: Pubkey,
+        pub marketing_account: Account,
+        pub developer_key: Pubkey,
+        pub developer_account: Account,
+    }
+
+    impl FeeAccounts {
+        fn new(
+            mint_key: &Pubkey,
+            mint_account: &mut Account,
+            mint_owner: &Pubkey,
+            account_owner: &Pubkey,
+        ) -> Self {
+            let mut fee_account = || {
+                mint_token(
+                    &TOKEN_PROGRAM_ID,
+                    mint_key,
+                    mint_account,
+                    mint_owner,
+                    account_owner,
+                    0,
+                )
+            };
+            let (reflection_key, reflection_account) = fee_account();
+            let (buyback_key, buyback_account) = fee_account();
+            let (marketing_key, marketing_account) = fee_account();
+            let (developer_key, developer_account) = fee_account();
+            Self {
+                reflection_key,
+                reflection_account,
+                buyback_key,
+                buyback_account,
+                marketing_key,
+                marketing_account,
+                developer_key,
+                developer_account,
+            }
+        }
+    }
+
     pub struct SwapAccountInfo {
         pub nonce: u8,
         pub authority_key: Pubkey,
@@ -98,7 +655,53 @@ pub mod test_utils {
         pub admin_fee_a_account: Account,
         pub admin_fee_b_key: Pubkey,
         pub admin_fee_b_account: Account,
+        pub fee_accounts_a: FeeAccounts,
+        pub fee_accounts_b: FeeAccounts,
+        pub admin_pool_token_key: Pubkey,
+        pub admin_pool_token_account: Account,
         pub fees: Fees,
+        pub access_mint_key: Pubkey,
+        pub access_mint_account: Account,
+        pub access_window_slots: u64,
+        pub namespace: [u8; 32],
+        pub fee_tier: FeeTier,
+        /// Wallets the marketing and developer fees are withdrawn to, unset pays them
+        /// to the fee accounts
+        pub marketing_wallet: Pubkey,
+        pub developer_wallet: Pubkey,
+        pub curve_type: CurveType,
+        pub price_bound: u64,
+        pub creator_window_slots: u64,
+        pub freeze_authority_acknowledged: bool,
+        /// Program data passed to Initialize and ClonePool, of the payer by default
+        pub program_data_account: Account,
+        /// Custodian co-signing the withdrawals, if any
+        pub custodian_key: Option<Pubkey>,
+        /// Credential presented by the swappers and depositors, if any
+        pub credential: Option<(Pubkey, Account)>,
+        /// Global stats passed to initialize, swap, deposit and withdraw, if any
+        pub global_stats: Option<(Pubkey, Account)>,
+        /// Fee exemptions passed to swap, if any
+        pub fee_exemptions: Option<(Pubkey, Account)>,
+        /// Deposit allowlist passed to deposit and deposit one, if any
+        pub deposit_allowlist: Option<(Pubkey, Account)>,
+        /// Referrer token account passed to swap, if any
+        pub referrer: Option<(Pubkey, Account)>,
+        /// Referral accrual passed to swap, if any
+        pub referral_accrual: Option<(Pubkey, Account)>,
+        /// Pyth price accounts of token A and B passed to swap, if any
+        pub oracles: Option<[(Pubkey, Account); 2]>,
+        /// Whether swaps pass the token mints, as for mints charging transfer fees
+        pub transfer_mints: bool,
+        /// Unix timestamp of the clock passed to deposit and withdraw one
+        pub current_ts: i64,
+        /// Slot of the clock passed to deposit, deposit one, swap out and withdraw one
+        pub deposit_slot: Slot,
+        /// Deadline slot of the swaps, deposits and withdrawals, zero for none
+        pub deadline_slot: u64,
+        /// Sequence number the withdrawals, single-sided deposits, swap outs and
+        /// swaps with receipts expect the pool at, if any
+        pub expected_sequence: Option<u64>,
     }
 
     impl SwapAccountInfo {
@@ -109,7 +712,93 @@ pub mod test_utils {
             token_b_amount: u64,
             fees: Fees,
         ) -> Self {
-            let swap_key = pubkey_rand();
+            let token_a_mint =
+                create_mint(&TOKEN_PROGRAM_ID, &user_key, DEFAULT_TOKEN_DECIMALS, None);
+            Self::new_with_token_a_mint(
+                user_key,
+                amp_factor,
+                token_a_amount,
+                token_b_amount,
+                fees,
+                token_a_mint,
+            )
+        }
+
+        /// Creates the accounts of a swap whose token A is of an existing mint, such as
+        /// the token B mint of another swap to route through both. The mint authority
+        /// must be `user_key`.
+        pub fn new_with_token_a_mint(
+            user_key: &Pubkey,
+            amp_factor: u64,
+            token_a_amount: u64,
+            token_b_amount: u64,
+            fees: Fees,
+            token_a_mint: (Pubkey, Account),
+        ) -> Self {
+            let token_b_mint =
+                create_mint(&TOKEN_PROGRAM_ID, user_key, DEFAULT_TOKEN_DECIMALS, None);
+            Self::new_with_mints(
+                user_key,
+                amp_factor,
+                token_a_amount,
+                token_b_amount,
+                fees,
+                token_a_mint,
+                token_b_mint,
+                FeeTier::Custom,
+                [0u8; 32],
+            )
+        }
+
+        /// Creates the accounts of a swap at the pool address of its mints, fee tier
+        /// and namespace
+        pub fn new_with_pool_seeds(
+            user_key: &Pubkey,
+            amp_factor: u64,
+            token_a_amount: u64,
+            token_b_amount: u64,
+            fees: Fees,
+            fee_tier: FeeTier,
+            namespace: [u8; 32],
+        ) -> Self {
+            let token_a_mint =
+                create_mint(&TOKEN_PROGRAM_ID, user_key, DEFAULT_TOKEN_DECIMALS, None);
+            let token_b_mint =
+                create_mint(&TOKEN_PROGRAM_ID, user_key, DEFAULT_TOKEN_DECIMALS, None);
+            Self::new_with_mints(
+                user_key,
+                amp_factor,
+                token_a_amount,
+                token_b_amount,
+                fees,
+                token_a_mint,
+                token_b_mint,
+                fee_tier,
+                namespace,
+            )
+        }
+
+        /// Creates the accounts of a swap of existing mints, such as the mints of
+        /// another swap in another namespace. The mint authority must be `user_key`.
+        #[allow(clippy::too_many_arguments)]
+        pub fn new_with_mints(
+            user_key: &Pubkey,
+            amp_factor: u64,
+            token_a_amount: u64,
+            token_b_amount: u64,
+            fees: Fees,
+            (token_a_mint_key, mut token_a_mint_account): (Pubkey, Account),
+            (token_b_mint_key, mut token_b_mint_account): (Pubkey, Account),
+            fee_tier: FeeTier,
+            namespace: [u8; 32],
+        ) -> Self {
+            let (swap_key, _pool_nonce) = find_pool_address(
+                &SWAP_PROGRAM_ID,
+                &token_a_mint_key,
+                &token_b_mint_key,
+                fee_tier,
+                &namespace,
+            );
             let swap_account = Account::new(0, SwapInfo::get_packed_len(), &SWAP_PROGRAM_ID);
             let (authority_key, nonce) =
                 Pubkey::find_program_address(&[&swap_key.to_bytes()[..]], &SWAP_PROGRAM_ID);
@@ -128,8 +817,6 @@ pub mod test_utils {
                 &user_key,
                 0,
             );
-            let (token_a_mint_key, mut token_a_mint_account) =
-                create_mint(&TOKEN_PROGRAM_ID, &user_key, DEFAULT_TOKEN_DECIMALS, None);
             let (token_a_key, token_a_account) = mint_token(
                 &TOKEN_PROGRAM_ID,
                 &token_a_mint_key,
@@ -146,8 +833,12 @@ pub mod test_utils {
                 &authority_key,
                 0,
             );
-            let (token_b_mint_key, mut token_b_mint_account) =
-                create_mint(&TOKEN_PROGRAM_ID, &user_key, DEFAULT_TOKEN_DECIMALS, None);
+            let fee_accounts_a = FeeAccounts::new(
+                &token_a_mint_key,
+                &mut token_a_mint_account,
+                &user_key,
+                &user_key,
+            );
             let (token_b_key, token_b_account) = mint_token(
                 &TOKEN_PROGRAM_ID,
                 &token_b_mint_key,
@@ -164,8 +855,25 @@ pub mod test_utils {
                 &authority_key,
                 0,
             );
+            let fee_accounts_b = FeeAccounts::new(
+                &token_b_mint_key,
+                &mut token_b_mint_account,
+                &user_key,
+                &user_key,
+            );
+            let (access_mint_key, access_mint_account) =
+                create_mint(&TOKEN_PROGRAM_ID, &user_key, 0, None);
 
             let admin_account = Account::default();
+            let program_data_account = program_data_account(&admin_account.owner);
+            let (admin_pool_token_key, admin_pool_token_account) = mint_token(
+                &TOKEN_PROGRAM_ID,
+                &pool_mint_key,
+                &mut pool_mint_account,
+                &authority_key,
+                &admin_account.owner,
+                0,
+            );
 
             SwapAccountInfo {
                 nonce,
@@ -192,31 +900,141 @@ pub mod test_utils {
                 admin_fee_a_account,
                 admin_fee_b_key,
                 admin_fee_b_account,
+                fee_accounts_a,
+                fee_accounts_b,
+                admin_pool_token_key,
+                admin_pool_token_account,
                 fees,
+                access_mint_key,
+                access_mint_account,
+                access_window_slots: 0,
+                namespace,
+                fee_tier,
+                marketing_wallet: Pubkey::default(),
+                developer_wallet: Pubkey::default(),
+                curve_type: CurveType::StableSwap,
+                price_bound: 0,
+                creator_window_slots: 0,
+                freeze_authority_acknowledged: false,
+                program_data_account,
+                custodian_key: None,
+                credential: None,
+                global_stats: None,
+                fee_exemptions: None,
+                deposit_allowlist: None,
+                referrer: None,
+                referral_accrual: None,
+                oracles: None,
+                transfer_mints: false,
+                current_ts: ZERO_TS,
+                deposit_slot: 0,
+                deadline_slot: 0,
+                expected_sequence: None,
             }
         }
 
         pub fn initialize_swap(&mut self) -> ProgramResult {
+            let mut instruction = initialize(
+                &SWAP_PROGRAM_ID,
+                &TOKEN_PROGRAM_ID,
+                &self.swap_key,
+                &self.authority_key,
+                &self.admin_key,
+                &self.admin_fee_a_key,
+                &self.admin_fee_b_key,
+                &self.token_a_mint_key,
+                &self.token_a_key,
+                &self.token_b_mint_key,
+                &self.token_b_key,
+                &self.pool_mint_key,
+                &self.pool_token_key,
+                &self.admin_key,
+                Some(&self.access_mint_key),
+                self.nonce,
+                self.initial_amp_factor,
+                self.fees,
+                self.access_window_slots,
+                self.namespace,
+                self.fee_tier,
+                self.marketing_wallet,
+                self.developer_wallet,
+                self.curve_type,
+                self.price_bound,
+                self.creator_window_slots,
+                self.freeze_authority_acknowledged,
+            )
+            .unwrap();
+            let mut authority_account = Account::default();
+            let mut token_program_account = Account::default();
+            let mut clock = clock_account(ZERO_TS);
+            let mut payer_account = pool_payer_account();
+            let mut system_program_account = Account::default();
+            let mut rent = rent_account();
+            let mut accounts = vec![
+                &mut self.swap_account,
+                &mut authority_account,
+                &mut self.admin_account,
+                &mut self.admin_fee_a_account,
+                &mut self.admin_fee_b_account,
+                &mut self.token_a_mint_account,
+                &mut self.token_a_account,
+                &mut self.token_b_mint_account,
+                &mut self.token_b_account,
+                &mut self.pool_mint_account,
+                &mut self.pool_token_account,
+                &mut token_program_account,
+                &mut clock,
+                &mut payer_account,
+                &mut system_program_account,
+                &mut rent,
+                &mut self.access_mint_account,
+            ];
+            if let Some((key, account)) = &mut self.global_stats {
+                instruction.accounts.push(AccountMeta::new(*key, false));
+                accounts.push(account);
+            }
+            instruction.accounts.push(AccountMeta::new_readonly(
+                find_program_data_address(&SWAP_PROGRAM_ID),
+                false,
+            ));
+            accounts.push(&mut self.program_data_account);
+            do_process_instruction(instruction, accounts)
+        }
+
+        pub fn clone_pool(
+            &mut self,
+            source_swap_key: &Pubkey,
+            source_swap_account: &mut Account,
+            current_ts: i64,
+        ) -> ProgramResult {
+            let mut instruction = clone_pool(
+                &SWAP_PROGRAM_ID,
+                &TOKEN_PROGRAM_ID,
+                &self.swap_key,
+                &self.authority_key,
+                &self.admin_key,
+                &self.admin_fee_a_key,
+                &self.admin_fee_b_key,
+                &self.token_a_mint_key,
+                &self.token_a_key,
+                &self.token_b_mint_key,
+                &self.token_b_key,
+                &self.pool_mint_key,
+                &self.pool_token_key,
+                &self.admin_key,
+                source_swap_key,
+                self.nonce,
+                self.namespace,
+                self.fee_tier,
+                self.freeze_authority_acknowledged,
+            )
+            .unwrap();
+            instruction.accounts.push(AccountMeta::new_readonly(
+                find_program_data_address(&SWAP_PROGRAM_ID),
+                false,
+            ));
             do_process_instruction(
-                initialize(
-                    &SWAP_PROGRAM_ID,
-                    &TOKEN_PROGRAM_ID,
-                    &self.swap_key,
-                    &self.authority_key,
-                    &self.admin_key,
-                    &self.admin_fee_a_key,
-                    &self.admin_fee_b_key,
-                    &self.token_a_mint_key,
-                    &self.token_a_key,
-                    &self.token_b_mint_key,
-                    &self.token_b_key,
-                    &self.pool_mint_key,
-                    &self.pool_token_key,
-                    self.nonce,
-                    self.initial_amp_factor,
-                    self.fees,
-                )
-                .unwrap(),
+                instruction,
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
@@ -230,6 +1048,12 @@ pub mod test_utils {
                     &mut self.pool_mint_account,
                     &mut self.pool_token_account,
                     &mut Account::default(),
+                    &mut clock_account(current_ts),
+                    &mut pool_payer_account(),
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    source_swap_account,
+                    &mut self.program_data_account,
                 ],
             )
         }
@@ -276,33 +1100,24 @@ pub mod test_utils {
             )
         }
 
-        fn get_admin_fee_key(&self, account_key: &Pubkey) -> Pubkey {
-            if *account_key == self.token_a_key {
-                return self.admin_fee_a_key;
-            } else if *account_key == self.token_b_key {
-                return self.admin_fee_b_key;
-            }
-            panic!("Could not find matching admin fee account");
-        }
-
-        fn get_admin_fee_account(&self, account_key: &Pubkey) -> &Account {
-            if *account_key == self.admin_fee_a_key {
-                return &self.admin_fee_a_account;
-            } else if *account_key == self.admin_fee_b_key {
-                return &self.admin_fee_b_account;
+        pub fn get_fee_accounts(&self, swap_destination_key: &Pubkey) -> &FeeAccounts {
+            if *swap_destination_key == self.token_a_key {
+                return &self.fee_accounts_a;
+            } else if *swap_destination_key == self.token_b_key {
+                return &self.fee_accounts_b;
             }
-            panic!("Could not find matching admin fee account");
+            panic!("Could not find matching fee accounts");
         }
 
-        fn set_admin_fee_account_(&mut self, account_key: &Pubkey, account: Account) {
-            if *account_key == self.admin_fee_a_key {
-                self.admin_fee_a_account = account;
+        fn set_fee_accounts(&mut self, swap_destination_key: &Pubkey, fee_accounts: FeeAccounts) {
+            if *swap_destination_key == self.token_a_key {
+                self.fee_accounts_a = fee_accounts;
                 return;
-            } else if *account_key == self.admin_fee_b_key {
-                self.admin_fee_b_account = account;
+            } else if *swap_destination_key == self.token_b_key {
+                self.fee_accounts_b = fee_accounts;
                 return;
             }
-            panic!("Could not find matching admin fee account");
+            panic!("Could not find matching fee accounts");
         }
 
         fn get_token_account(&self, account_key: &Pubkey) -> &Account {
@@ -326,6 +1141,35 @@ pub mod test_utils {
         }
 
         pub fn swap(
+            &mut self,
+            user_key: &Pubkey,
+            user_source_key: &Pubkey,
+            user_source_account: &mut Account,
+            swap_source_key: &Pubkey,
+            swap_destination_key: &Pubkey,
+            user_destination_key: &Pubkey,
+            user_destination_account: &mut Account,
+            amount_in: u64,
+            minimum_amount_out: u64,
+        ) -> ProgramResult {
+            self.swap_with_access_token(
+                user_key,
+                user_source_key,
+                user_source_account,
+                swap_source_key,
+                swap_destination_key,
+                user_destination_key,
+                user_destination_account,
+                None,
+                0,
+                amount_in,
+                minimum_amount_out,
+                None,
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn swap_with_access_token(
             &mut self,
             user_key: &Pubkey,
             user_source_key: &Pubkey,
@@ -333,17 +1177,25 @@ pub mod test_utils {
             swap_source_key: &Pubkey,
             swap_destination_key: &Pubkey,
             user_destination_key: &Pubkey,
-            mut user_destination_account: &mut Account,
+            user_destination_account: &mut Account,
+            access_token: Option<(&Pubkey, &mut Account)>,
+            slot: Slot,
             amount_in: u64,
             minimum_amount_out: u64,
+            expected_sequence: Option<u64>,
         ) -> ProgramResult {
+            let (access_token_key, access_token_account) = match access_token {
+                Some((key, account)) => (Some(key), Some(account)),
+                None => (None, None),
+            };
+
             // approve moving from user source account
             do_process_instruction(
                 approve(
                     &TOKEN_PROGRAM_ID,
-                    &user_source_key,
+                    user_source_key,
                     &self.authority_key,
-                    &user_key,
+                    user_key,
                     &[],
                     amount_in,
                 )
@@ -356,419 +1208,4628 @@ pub mod test_utils {
             )
             .unwrap();
 
-            let admin_destination_key = self.get_admin_fee_key(swap_destination_key);
-            let mut admin_destination_account =
-                self.get_admin_fee_account(&admin_destination_key).clone();
+            let swap_fee_key = if self.fees.fee_on_input {
+                swap_source_key
+            } else {
+                swap_destination_key
+            };
+            let mut fee_accounts = self.get_fee_accounts(swap_fee_key).clone();
             let mut swap_source_account = self.get_token_account(swap_source_key).clone();
             let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
 
-            // perform the swap
-            do_process_instruction(
-                swap(
-                    &SWAP_PROGRAM_ID,
-                    &TOKEN_PROGRAM_ID,
-                    &self.swap_key,
-                    &self.authority_key,
-                    &user_source_key,
-                    &swap_source_key,
-                    &swap_destination_key,
-                    &user_destination_key,
-                    &admin_destination_key,
-                    amount_in,
-                    minimum_amount_out,
-                )
-                .unwrap(),
-                vec![
-                    &mut self.swap_account,
-                    &mut Account::default(),
-                    &mut user_source_account,
-                    &mut swap_source_account,
-                    &mut swap_destination_account,
-                    &mut user_destination_account,
-                    &mut admin_destination_account,
-                    &mut Account::default(),
-                    &mut clock_account(ZERO_TS),
-                ],
-            )?;
-
-            self.set_admin_fee_account_(&admin_destination_key, admin_destination_account);
-            self.set_token_account(swap_source_key, swap_source_account);
-            self.set_token_account(swap_destination_key, swap_destination_account);
+            let mut authority_account = Account::default();
+            let mut token_program_account = Account::default();
+            let mut clock = clock_account_at_slot(ZERO_TS, slot);
+            let mut no_referrer_account = Account::default();
+            let (referrer_key, referrer_account) = match &mut self.referrer {
+                Some((key, account)) => (Some(*key), account),
+                None => (None, &mut no_referrer_account),
+            };
+            let mut accounts = vec![
+                &mut self.swap_account,
+                &mut authority_account,
+                user_source_account,
+                &mut swap_source_account,
+                &mut swap_destination_account,
+                user_destination_account,
+                &mut fee_accounts.reflection_account,
+                &mut fee_accounts.buyback_account,
+                &mut fee_accounts.marketing_account,
+                &mut fee_accounts.developer_account,
+                &mut token_program_account,
+                &mut clock,
+                referrer_account,
+            ];
+            let admin_fee_pool_keys = if self.fees.admin_fee_in_pool_tokens {
+                accounts.push(&mut self.pool_mint_account);
+                accounts.push(&mut self.admin_pool_token_account);
+                Some((self.pool_mint_key, self.admin_pool_token_key))
+            } else {
+                None
+            };
+            accounts.extend(access_token_account);
+            let credential_key = match &mut self.credential {
+                Some((key, account)) => {
+                    accounts.push(account);
+                    Some(*key)
+                }
+                None => None,
+            };
+
+            let mut instruction = swap(
+                &SWAP_PROGRAM_ID,
+                &TOKEN_PROGRAM_ID,
+                &self.swap_key,
+                &self.authority_key,
+                user_source_key,
+                swap_source_key,
+                swap_destination_key,
+                user_destination_key,
+                &fee_accounts.reflection_key,
+                &fee_accounts.buyback_key,
+                &fee_accounts.marketing_key,
+                &fee_accounts.developer_key,
+                referrer_key.as_ref(),
+                admin_fee_pool_keys
+                    .as_ref()
+                    .map(|(mint, account)| (mint, account)),
+                access_token_key,
+                credential_key.as_ref(),
+                amount_in,
+                minimum_amount_out,
+                self.deadline_slot,
+                expected_sequence,
+            )
+            .unwrap();
+            if self.transfer_mints {
+                for mint_key in [self.token_a_mint_key, self.token_b_mint_key] {
+                    instruction
+                        .accounts
+                        .push(AccountMeta::new_readonly(mint_key, false));
+                }
+                accounts.push(&mut self.token_a_mint_account);
+                accounts.push(&mut self.token_b_mint_account);
+            }
+            if let Some((key, account)) = &mut self.global_stats {
+                instruction.accounts.push(AccountMeta::new(*key, false));
+                accounts.push(account);
+            }
+            if let Some((key, account)) = &mut self.fee_exemptions {
+                instruction
+                    .accounts
+                    .push(AccountMeta::new_readonly(*key, false));
+                accounts.push(account);
+            }
+            if let Some((key, account)) = &mut self.referral_accrual {
+                instruction.accounts.push(AccountMeta::new(*key, false));
+                accounts.push(account);
+            }
+            for (key, account) in self.oracles.iter_mut().flatten() {
+                instruction
+                    .accounts
+                    .push(AccountMeta::new_readonly(*key, false));
+                accounts.push(account);
+            }
+
+            // perform the swap
+            do_process_instruction(instruction, accounts)?;
+
+            self.set_fee_accounts(swap_fee_key, fee_accounts);
+            self.set_token_account(swap_source_key, swap_source_account);
+            self.set_token_account(swap_destination_key, swap_destination_account);
 
             Ok(())
         }
 
-        pub fn deposit(
+        #[allow(clippy::too_many_arguments)]
+        pub fn swap_out(
             &mut self,
-            depositor_key: &Pubkey,
-            depositor_token_a_key: &Pubkey,
-            mut depositor_token_a_account: &mut Account,
-            depositor_token_b_key: &Pubkey,
-            mut depositor_token_b_account: &mut Account,
-            depositor_pool_key: &Pubkey,
-            mut depositor_pool_account: &mut Account,
-            amount_a: u64,
-            amount_b: u64,
-            min_mint_amount: u64,
+            user_key: &Pubkey,
+            user_source_key: &Pubkey,
+            mut user_source_account: &mut Account,
+            swap_source_key: &Pubkey,
+            swap_destination_key: &Pubkey,
+            user_destination_key: &Pubkey,
+            user_destination_account: &mut Account,
+            amount_out: u64,
+            maximum_amount_in: u64,
         ) -> ProgramResult {
+            // approve moving up to the maximum from user source account
             do_process_instruction(
                 approve(
                     &TOKEN_PROGRAM_ID,
-                    &depositor_token_a_key,
+                    user_source_key,
                     &self.authority_key,
-                    &depositor_key,
+                    user_key,
                     &[],
-                    amount_a,
+                    maximum_amount_in,
                 )
                 .unwrap(),
                 vec![
-                    &mut depositor_token_a_account,
+                    &mut user_source_account,
                     &mut Account::default(),
                     &mut Account::default(),
                 ],
             )
             .unwrap();
 
+            let swap_fee_key = if self.fees.fee_on_input {
+                swap_source_key
+            } else {
+                swap_destination_key
+            };
+            let mut fee_accounts = self.get_fee_accounts(swap_fee_key).clone();
+            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
+            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
+
+            let mut authority_account = Account::default();
+            let mut token_program_account = Account::default();
+            let mut clock = clock_account_at_slot(ZERO_TS, self.deposit_slot);
+            let mut no_referrer_account = Account::default();
+            let mut accounts = vec![
+                &mut self.swap_account,
+                &mut authority_account,
+                user_source_account,
+                &mut swap_source_account,
+                &mut swap_destination_account,
+                user_destination_account,
+                &mut fee_accounts.reflection_account,
+                &mut fee_accounts.buyback_account,
+                &mut fee_accounts.marketing_account,
+                &mut fee_accounts.developer_account,
+                &mut token_program_account,
+                &mut clock,
+                &mut no_referrer_account,
+            ];
+            let admin_fee_pool_keys = if self.fees.admin_fee_in_pool_tokens {
+                accounts.push(&mut self.pool_mint_account);
+                accounts.push(&mut self.admin_pool_token_account);
+                Some((self.pool_mint_key, self.admin_pool_token_key))
+            } else {
+                None
+            };
+            let credential_key = match &mut self.credential {
+                Some((key, account)) => {
+                    accounts.push(account);
+                    Some(*key)
+                }
+                None => None,
+            };
+
+            do_process_instruction(
+                swap_out(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    user_source_key,
+                    swap_source_key,
+                    swap_destination_key,
+                    user_destination_key,
+                    &fee_accounts.reflection_key,
+                    &fee_accounts.buyback_key,
+                    &fee_accounts.marketing_key,
+                    &fee_accounts.developer_key,
+                    None,
+                    admin_fee_pool_keys
+                        .as_ref()
+                        .map(|(mint, account)| (mint, account)),
+                    None,
+                    credential_key.as_ref(),
+                    amount_out,
+                    maximum_amount_in,
+                    self.deadline_slot,
+                    self.expected_sequence,
+                )
+                .unwrap(),
+                accounts,
+            )?;
+
+            self.set_fee_accounts(swap_fee_key, fee_accounts);
+            self.set_token_account(swap_source_key, swap_source_account);
+            self.set_token_account(swap_destination_key, swap_destination_account);
+
+            Ok(())
+        }
+
+        /// Routes token A through this swap to its token B, then through `next`, whose
+        /// token A is of the same mint, to the token B of `next`
+        #[allow(clippy::too_many_arguments)]
+        pub fn route(
+            &mut self,
+            next: &mut SwapAccountInfo,
+            user_key: &Pubkey,
+            user_source_key: &Pubkey,
+            mut user_source_account: &mut Account,
+            user_intermediate_key: &Pubkey,
+            mut user_intermediate_account: &mut Account,
+            user_destination_key: &Pubkey,
+            user_destination_account: &mut Account,
+            amount_in: u64,
+            minimum_amount_out: u64,
+        ) -> ProgramResult {
+            // approve moving from user source account, then the whole output of the
+            // first leg from the intermediate account
             do_process_instruction(
                 approve(
                     &TOKEN_PROGRAM_ID,
-                    &depositor_token_b_key,
+                    user_source_key,
                     &self.authority_key,
-                    &depositor_key,
+                    user_key,
                     &[],
-                    amount_b,
+                    amount_in,
+                )
+                .unwrap(),
+                vec![
+                    &mut user_source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    user_intermediate_key,
+                    &next.authority_key,
+                    user_key,
+                    &[],
+                    u64::MAX,
+                )
+                .unwrap(),
+                vec![
+                    &mut user_intermediate_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            let swap_fee_key = if self.fees.fee_on_input {
+                self.token_a_key
+            } else {
+                self.token_b_key
+            };
+            let next_swap_fee_key = if next.fees.fee_on_input {
+                next.token_a_key
+            } else {
+                next.token_b_key
+            };
+            let mut fee_accounts = self.get_fee_accounts(&swap_fee_key).clone();
+            let mut next_fee_accounts = next.get_fee_accounts(&next_swap_fee_key).clone();
+            let legs = [
+                swap(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    user_source_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    user_intermediate_key,
+                    &fee_accounts.reflection_key,
+                    &fee_accounts.buyback_key,
+                    &fee_accounts.marketing_key,
+                    &fee_accounts.developer_key,
+                    None,
+                    None,
+                    None,
+                    None,
+                    0,
+                    0,
+                    0,
+                    None,
+                )
+                .unwrap(),
+                swap(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &next.swap_key,
+                    &next.authority_key,
+                    user_intermediate_key,
+                    &next.token_a_key,
+                    &next.token_b_key,
+                    user_destination_key,
+                    &next_fee_accounts.reflection_key,
+                    &next_fee_accounts.buyback_key,
+                    &next_fee_accounts.marketing_key,
+                    &next_fee_accounts.developer_key,
+                    None,
+                    None,
+                    None,
+                    None,
+                    0,
+                    0,
+                    0,
+                    None,
+                )
+                .unwrap(),
+            ];
+
+            let mut clock = clock_account_at_slot(ZERO_TS, 0);
+            do_process_instruction(
+                route(&SWAP_PROGRAM_ID, &legs, amount_in, minimum_amount_out).unwrap(),
+                vec![
+                    &mut Account::default(),
+                    &mut clock,
+                    user_source_account,
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    user_intermediate_account,
+                    &mut fee_accounts.reflection_account,
+                    &mut fee_accounts.buyback_account,
+                    &mut fee_accounts.marketing_account,
+                    &mut fee_accounts.developer_account,
+                    &mut Account::default(),
+                    &mut next.swap_account,
+                    &mut Account::default(),
+                    &mut next.token_a_account,
+                    &mut next.token_b_account,
+                    user_destination_account,
+                    &mut next_fee_accounts.reflection_account,
+                    &mut next_fee_accounts.buyback_account,
+                    &mut next_fee_accounts.marketing_account,
+                    &mut next_fee_accounts.developer_account,
+                    &mut Account::default(),
+                ],
+            )?;
+
+            self.set_fee_accounts(&swap_fee_key, fee_accounts);
+            next.set_fee_accounts(&next_swap_fee_key, next_fee_accounts);
+            Ok(())
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn swap_with_receipt(
+            &mut self,
+            user_key: &Pubkey,
+            user_source_key: &Pubkey,
+            mut user_source_account: &mut Account,
+            swap_source_key: &Pubkey,
+            swap_destination_key: &Pubkey,
+            user_destination_key: &Pubkey,
+            user_destination_account: &mut Account,
+            receipt_key: &Pubkey,
+            receipt_account: &mut Account,
+            receipt_owner_key: &Pubkey,
+            receipt_owner_account: &mut Account,
+            receipt_id: u64,
+            receipt_nonce: u8,
+            amount_in: u64,
+            minimum_amount_out: u64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    user_source_key,
+                    &self.authority_key,
+                    user_key,
+                    &[],
+                    amount_in,
+                )
+                .unwrap(),
+                vec![
+                    &mut user_source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            let swap_fee_key = if self.fees.fee_on_input {
+                swap_source_key
+            } else {
+                swap_destination_key
+            };
+            let mut fee_accounts = self.get_fee_accounts(swap_fee_key).clone();
+            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
+            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
+
+            let mut system_program_account = Account::default();
+            let mut rent = rent_account();
+            let mut authority_account = Account::default();
+            let mut token_program_account = Account::default();
+            let mut clock = clock_account(ZERO_TS);
+            do_process_instruction(
+                swap_with_receipt(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &self.swap_key,
+                        &self.authority_key,
+                        user_source_key,
+                        swap_source_key,
+                        swap_destination_key,
+                        user_destination_key,
+                        &fee_accounts.reflection_key,
+                        &fee_accounts.buyback_key,
+                        &fee_accounts.marketing_key,
+                        &fee_accounts.developer_key,
+                        None,
+                        None,
+                        None,
+                        None,
+                        amount_in,
+                        minimum_amount_out,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    receipt_key,
+                    receipt_owner_key,
+                    receipt_id,
+                    receipt_nonce,
+                )
+                .unwrap(),
+                vec![
+                    receipt_account,
+                    receipt_owner_account,
+                    &mut system_program_account,
+                    &mut rent,
+                    &mut self.swap_account,
+                    &mut authority_account,
+                    user_source_account,
+                    &mut swap_source_account,
+                    &mut swap_destination_account,
+                    user_destination_account,
+                    &mut fee_accounts.reflection_account,
+                    &mut fee_accounts.buyback_account,
+                    &mut fee_accounts.marketing_account,
+                    &mut fee_accounts.developer_account,
+                    &mut token_program_account,
+                    &mut clock,
+                    &mut Account::default(),
+                ],
+            )?;
+
+            self.set_fee_accounts(swap_fee_key, fee_accounts);
+            self.set_token_account(swap_source_key, swap_source_account);
+            self.set_token_account(swap_destination_key, swap_destination_account);
+
+            Ok(())
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn create_twap_order(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            source_key: &Pubkey,
+            mut source_account: &mut Account,
+            escrow_key: &Pubkey,
+            escrow_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            twap_order_key: &Pubkey,
+            twap_order_account: &mut Account,
+            data: CreateTwapOrderData,
+            slot: Slot,
+        ) -> ProgramResult {
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    source_key,
+                    &self.authority_key,
+                    owner_key,
+                    &[],
+                    data.amount_in,
+                )
+                .unwrap(),
+                vec![
+                    &mut source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                create_twap_order(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    source_key,
+                    escrow_key,
+                    destination_key,
+                    twap_order_key,
+                    data,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    owner_account,
+                    source_account,
+                    escrow_account,
+                    destination_account,
+                    twap_order_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn execute_twap_slice(
+            &mut self,
+            twap_order_key: &Pubkey,
+            twap_order_account: &mut Account,
+            escrow_key: &Pubkey,
+            escrow_account: &mut Account,
+            swap_source_key: &Pubkey,
+            swap_destination_key: &Pubkey,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            slot: Slot,
+        ) -> ProgramResult {
+            let swap_fee_key = if self.fees.fee_on_input {
+                swap_source_key
+            } else {
+                swap_destination_key
+            };
+            let mut fee_accounts = self.get_fee_accounts(swap_fee_key).clone();
+            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
+            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
+
+            do_process_instruction(
+                execute_twap_slice(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &self.swap_key,
+                        &self.authority_key,
+                        escrow_key,
+                        swap_source_key,
+                        swap_destination_key,
+                        destination_key,
+                        &fee_accounts.reflection_key,
+                        &fee_accounts.buyback_key,
+                        &fee_accounts.marketing_key,
+                        &fee_accounts.developer_key,
+                        None,
+                        None,
+                        None,
+                        None,
+                        0,
+                        0,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    twap_order_key,
+                )
+                .unwrap(),
+                vec![
+                    twap_order_account,
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    escrow_account,
+                    &mut swap_source_account,
+                    &mut swap_destination_account,
+                    destination_account,
+                    &mut fee_accounts.reflection_account,
+                    &mut fee_accounts.buyback_account,
+                    &mut fee_accounts.marketing_account,
+                    &mut fee_accounts.developer_account,
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                    &mut Account::default(),
+                ],
+            )?;
+
+            self.set_fee_accounts(swap_fee_key, fee_accounts);
+            self.set_token_account(swap_source_key, swap_source_account);
+            self.set_token_account(swap_destination_key, swap_destination_account);
+
+            Ok(())
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn cancel_twap_order(
+            &mut self,
+            twap_order_key: &Pubkey,
+            twap_order_account: &mut Account,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            escrow_key: &Pubkey,
+            escrow_account: &mut Account,
+            refund_key: &Pubkey,
+            refund_account: &mut Account,
+        ) -> ProgramResult {
+            do_process_instruction(
+                cancel_twap_order(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    twap_order_key,
+                    owner_key,
+                    escrow_key,
+                    refund_key,
+                )
+                .unwrap(),
+                vec![
+                    twap_order_account,
+                    owner_account,
+                    escrow_account,
+                    refund_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn create_trigger(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            source_key: &Pubkey,
+            mut source_account: &mut Account,
+            escrow_key: &Pubkey,
+            escrow_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            oracle_key: &Pubkey,
+            oracle_account: &mut Account,
+            trigger_key: &Pubkey,
+            trigger_account: &mut Account,
+            data: CreateTriggerData,
+        ) -> ProgramResult {
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    source_key,
+                    &self.authority_key,
+                    owner_key,
+                    &[],
+                    data.amount_in,
+                )
+                .unwrap(),
+                vec![
+                    &mut source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                create_trigger(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    source_key,
+                    escrow_key,
+                    destination_key,
+                    oracle_key,
+                    trigger_key,
+                    data,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    owner_account,
+                    source_account,
+                    escrow_account,
+                    destination_account,
+                    oracle_account,
+                    trigger_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn execute_trigger(
+            &mut self,
+            trigger_key: &Pubkey,
+            trigger_account: &mut Account,
+            oracle_key: &Pubkey,
+            oracle_account: &mut Account,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            cranker_key: &Pubkey,
+            cranker_account: &mut Account,
+            escrow_key: &Pubkey,
+            escrow_account: &mut Account,
+            swap_source_key: &Pubkey,
+            swap_destination_key: &Pubkey,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            slot: Slot,
+        ) -> ProgramResult {
+            let swap_fee_key = if self.fees.fee_on_input {
+                swap_source_key
+            } else {
+                swap_destination_key
+            };
+            let mut fee_accounts = self.get_fee_accounts(swap_fee_key).clone();
+            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
+            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
+
+            do_process_instruction(
+                execute_trigger(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &self.swap_key,
+                        &self.authority_key,
+                        escrow_key,
+                        swap_source_key,
+                        swap_destination_key,
+                        destination_key,
+                        &fee_accounts.reflection_key,
+                        &fee_accounts.buyback_key,
+                        &fee_accounts.marketing_key,
+                        &fee_accounts.developer_key,
+                        None,
+                        None,
+                        None,
+                        None,
+                        0,
+                        0,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    trigger_key,
+                    oracle_key,
+                    owner_key,
+                    cranker_key,
+                )
+                .unwrap(),
+                vec![
+                    trigger_account,
+                    oracle_account,
+                    owner_account,
+                    cranker_account,
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    escrow_account,
+                    &mut swap_source_account,
+                    &mut swap_destination_account,
+                    destination_account,
+                    &mut fee_accounts.reflection_account,
+                    &mut fee_accounts.buyback_account,
+                    &mut fee_accounts.marketing_account,
+                    &mut fee_accounts.developer_account,
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                    &mut Account::default(),
+                ],
+            )?;
+
+            self.set_fee_accounts(swap_fee_key, fee_accounts);
+            self.set_token_account(swap_source_key, swap_source_account);
+            self.set_token_account(swap_destination_key, swap_destination_account);
+
+            Ok(())
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn cancel_trigger(
+            &mut self,
+            trigger_key: &Pubkey,
+            trigger_account: &mut Account,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            escrow_key: &Pubkey,
+            escrow_account: &mut Account,
+            refund_key: &Pubkey,
+            refund_account: &mut Account,
+        ) -> ProgramResult {
+            do_process_instruction(
+                cancel_trigger(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    trigger_key,
+                    owner_key,
+                    escrow_key,
+                    refund_key,
+                )
+                .unwrap(),
+                vec![
+                    trigger_account,
+                    owner_account,
+                    escrow_account,
+                    refund_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn create_drip_deposit(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            source_a_key: &Pubkey,
+            source_a_account: &mut Account,
+            source_b_key: &Pubkey,
+            source_b_account: &mut Account,
+            escrow_a_key: &Pubkey,
+            escrow_a_account: &mut Account,
+            escrow_b_key: &Pubkey,
+            escrow_b_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            drip_deposit_key: &Pubkey,
+            drip_deposit_account: &mut Account,
+            data: CreateDripDepositData,
+            slot: Slot,
+        ) -> ProgramResult {
+            for (source_key, source_account, amount) in [
+                (source_a_key, &mut *source_a_account, data.amount_a),
+                (source_b_key, &mut *source_b_account, data.amount_b),
+            ] {
+                do_process_instruction(
+                    approve(
+                        &TOKEN_PROGRAM_ID,
+                        source_key,
+                        &self.authority_key,
+                        owner_key,
+                        &[],
+                        amount,
+                    )
+                    .unwrap(),
+                    vec![
+                        source_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+                .unwrap();
+            }
+
+            do_process_instruction(
+                create_drip_deposit(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    source_a_key,
+                    source_b_key,
+                    escrow_a_key,
+                    escrow_b_key,
+                    destination_key,
+                    drip_deposit_key,
+                    data,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    owner_account,
+                    source_a_account,
+                    source_b_account,
+                    escrow_a_account,
+                    escrow_b_account,
+                    destination_account,
+                    drip_deposit_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_drip_tranche(
+            &mut self,
+            drip_deposit_key: &Pubkey,
+            drip_deposit_account: &mut Account,
+            escrow_a_key: &Pubkey,
+            escrow_a_account: &mut Account,
+            escrow_b_key: &Pubkey,
+            escrow_b_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            slot: Slot,
+        ) -> ProgramResult {
+            do_process_instruction(
+                deposit_drip_tranche(
+                    deposit(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &self.swap_key,
+                        &self.authority_key,
+                        escrow_a_key,
+                        escrow_b_key,
+                        &self.token_a_key,
+                        &self.token_b_key,
+                        &self.pool_mint_key,
+                        destination_key,
+                        None,
+                        0,
+                        0,
+                        0,
+                        0,
+                        None,
+                    )
+                    .unwrap(),
+                    drip_deposit_key,
+                )
+                .unwrap(),
+                vec![
+                    drip_deposit_account,
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    escrow_a_account,
+                    escrow_b_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    destination_account,
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn cancel_drip_deposit(
+            &mut self,
+            drip_deposit_key: &Pubkey,
+            drip_deposit_account: &mut Account,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            escrow_a_key: &Pubkey,
+            escrow_a_account: &mut Account,
+            escrow_b_key: &Pubkey,
+            escrow_b_account: &mut Account,
+            refund_a_key: &Pubkey,
+            refund_a_account: &mut Account,
+            refund_b_key: &Pubkey,
+            refund_b_account: &mut Account,
+        ) -> ProgramResult {
+            do_process_instruction(
+                cancel_drip_deposit(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    drip_deposit_key,
+                    owner_key,
+                    escrow_a_key,
+                    escrow_b_key,
+                    refund_a_key,
+                    refund_b_key,
+                )
+                .unwrap(),
+                vec![
+                    drip_deposit_account,
+                    owner_account,
+                    escrow_a_account,
+                    escrow_b_account,
+                    refund_a_account,
+                    refund_b_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn sweep_dust(
+            &mut self,
+            user_key: &Pubkey,
+            user_source_key: &Pubkey,
+            mut user_source_account: &mut Account,
+            swap_source_key: &Pubkey,
+            swap_destination_key: &Pubkey,
+            user_destination_key: &Pubkey,
+            user_destination_account: &mut Account,
+            slot: Slot,
+        ) -> ProgramResult {
+            let amount = SplAccount::unpack(&user_source_account.data)
+                .unwrap()
+                .amount;
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    user_source_key,
+                    &self.authority_key,
+                    user_key,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut user_source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
+            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
+            do_process_instruction(
+                sweep_dust(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    user_source_key,
+                    swap_source_key,
+                    swap_destination_key,
+                    user_destination_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    user_source_account,
+                    &mut swap_source_account,
+                    &mut swap_destination_account,
+                    user_destination_account,
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )?;
+
+            self.set_token_account(swap_source_key, swap_source_account);
+            self.set_token_account(swap_destination_key, swap_destination_account);
+            Ok(())
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn simulate_swap(
+            &mut self,
+            user_key: &Pubkey,
+            user_source_key: &Pubkey,
+            mut user_source_account: &mut Account,
+            swap_source_key: &Pubkey,
+            swap_destination_key: &Pubkey,
+            user_destination_key: &Pubkey,
+            user_destination_account: &mut Account,
+            amount_in: u64,
+            minimum_amount_out: u64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    user_source_key,
+                    &self.authority_key,
+                    user_key,
+                    &[],
+                    amount_in,
+                )
+                .unwrap(),
+                vec![
+                    &mut user_source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            let swap_fee_key = if self.fees.fee_on_input {
+                swap_source_key
+            } else {
+                swap_destination_key
+            };
+            let mut fee_accounts = self.get_fee_accounts(swap_fee_key).clone();
+            let mut swap_source_account = self.get_token_account(swap_source_key).clone();
+            let mut swap_destination_account = self.get_token_account(swap_destination_key).clone();
+
+            // nothing to copy back, the simulation always fails
+            do_process_instruction(
+                simulate_swap(
+                    swap(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &self.swap_key,
+                        &self.authority_key,
+                        user_source_key,
+                        swap_source_key,
+                        swap_destination_key,
+                        user_destination_key,
+                        &fee_accounts.reflection_key,
+                        &fee_accounts.buyback_key,
+                        &fee_accounts.marketing_key,
+                        &fee_accounts.developer_key,
+                        None,
+                        None,
+                        None,
+                        None,
+                        amount_in,
+                        minimum_amount_out,
+                        self.deadline_slot,
+                        None,
+                    )
+                    .unwrap(),
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    user_source_account,
+                    &mut swap_source_account,
+                    &mut swap_destination_account,
+                    user_destination_account,
+                    &mut fee_accounts.reflection_account,
+                    &mut fee_accounts.buyback_account,
+                    &mut fee_accounts.marketing_account,
+                    &mut fee_accounts.developer_account,
+                    &mut Account::default(),
+                    &mut clock_account(ZERO_TS),
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn deposit(
+            &mut self,
+            depositor_key: &Pubkey,
+            depositor_token_a_key: &Pubkey,
+            depositor_token_a_account: &mut Account,
+            depositor_token_b_key: &Pubkey,
+            depositor_token_b_account: &mut Account,
+            depositor_pool_key: &Pubkey,
+            depositor_pool_account: &mut Account,
+            amount_a: u64,
+            amount_b: u64,
+            min_mint_amount: u64,
+        ) -> ProgramResult {
+            self.deposit_at_sequence(
+                depositor_key,
+                depositor_token_a_key,
+                depositor_token_a_account,
+                depositor_token_b_key,
+                depositor_token_b_account,
+                depositor_pool_key,
+                depositor_pool_account,
+                amount_a,
+                amount_b,
+                min_mint_amount,
+                None,
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_at_sequence(
+            &mut self,
+            depositor_key: &Pubkey,
+            depositor_token_a_key: &Pubkey,
+            mut depositor_token_a_account: &mut Account,
+            depositor_token_b_key: &Pubkey,
+            mut depositor_token_b_account: &mut Account,
+            depositor_pool_key: &Pubkey,
+            mut depositor_pool_account: &mut Account,
+            amount_a: u64,
+            amount_b: u64,
+            min_mint_amount: u64,
+            expected_sequence: Option<u64>,
+        ) -> ProgramResult {
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    &depositor_token_a_key,
+                    &self.authority_key,
+                    &depositor_key,
+                    &[],
+                    amount_a,
+                )
+                .unwrap(),
+                vec![
+                    &mut depositor_token_a_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    &depositor_token_b_key,
+                    &self.authority_key,
+                    &depositor_key,
+                    &[],
+                    amount_b,
+                )
+                .unwrap(),
+                vec![
+                    &mut depositor_token_b_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            // perform deposit
+            let mut authority_account = Account::default();
+            let mut token_program_account = Account::default();
+            let mut clock = clock_account_at_slot(self.current_ts, self.deposit_slot);
+            let mut accounts = vec![
+                &mut self.swap_account,
+                &mut authority_account,
+                &mut depositor_token_a_account,
+                &mut depositor_token_b_account,
+                &mut self.token_a_account,
+                &mut self.token_b_account,
+                &mut self.pool_mint_account,
+                &mut depositor_pool_account,
+                &mut token_program_account,
+                &mut clock,
+            ];
+            let credential_key = match &mut self.credential {
+                Some((key, account)) => {
+                    accounts.push(account);
+                    Some(*key)
+                }
+                None => None,
+            };
+            let mut instruction = deposit(
+                &SWAP_PROGRAM_ID,
+                &TOKEN_PROGRAM_ID,
+                &self.swap_key,
+                &self.authority_key,
+                &depositor_token_a_key,
+                &depositor_token_b_key,
+                &self.token_a_key,
+                &self.token_b_key,
+                &self.pool_mint_key,
+                &depositor_pool_key,
+                credential_key.as_ref(),
+                amount_a,
+                amount_b,
+                min_mint_amount,
+                self.deadline_slot,
+                expected_sequence,
+            )
+            .unwrap();
+            if let Some((key, account)) = &mut self.global_stats {
+                instruction.accounts.push(AccountMeta::new(*key, false));
+                accounts.push(account);
+            }
+            if let Some((key, account)) = &mut self.deposit_allowlist {
+                instruction
+                    .accounts
+                    .push(AccountMeta::new_readonly(*key, false));
+                accounts.push(account);
+            }
+            do_process_instruction(instruction, accounts)
+        }
+
+        /// Deposits token B if `token_b`, token A otherwise
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_one(
+            &mut self,
+            depositor_key: &Pubkey,
+            source_key: &Pubkey,
+            mut source_account: &mut Account,
+            depositor_pool_key: &Pubkey,
+            mut depositor_pool_account: &mut Account,
+            token_b: bool,
+            amount: u64,
+            min_mint_amount: u64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    source_key,
+                    &self.authority_key,
+                    depositor_key,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            let (base_key, base_account, quote_key, quote_account) = if token_b {
+                (
+                    self.token_b_key,
+                    &mut self.token_b_account,
+                    self.token_a_key,
+                    &mut self.token_a_account,
+                )
+            } else {
+                (
+                    self.token_a_key,
+                    &mut self.token_a_account,
+                    self.token_b_key,
+                    &mut self.token_b_account,
+                )
+            };
+            let mut authority_account = Account::default();
+            let mut token_program_account = Account::default();
+            let mut clock = clock_account_at_slot(self.current_ts, self.deposit_slot);
+            let mut accounts = vec![
+                &mut self.swap_account,
+                &mut authority_account,
+                &mut source_account,
+                base_account,
+                quote_account,
+                &mut self.pool_mint_account,
+                &mut depositor_pool_account,
+                &mut token_program_account,
+                &mut clock,
+            ];
+            let credential_key = match &mut self.credential {
+                Some((key, account)) => {
+                    accounts.push(account);
+                    Some(*key)
+                }
+                None => None,
+            };
+            let mut instruction = deposit_one(
+                &SWAP_PROGRAM_ID,
+                &TOKEN_PROGRAM_ID,
+                &self.swap_key,
+                &self.authority_key,
+                source_key,
+                &base_key,
+                &quote_key,
+                &self.pool_mint_key,
+                depositor_pool_key,
+                credential_key.as_ref(),
+                amount,
+                min_mint_amount,
+                self.deadline_slot,
+                self.expected_sequence,
+            )
+            .unwrap();
+            if let Some((key, account)) = &mut self.global_stats {
+                instruction.accounts.push(AccountMeta::new(*key, false));
+                accounts.push(account);
+            }
+            if let Some((key, account)) = &mut self.deposit_allowlist {
+                instruction
+                    .accounts
+                    .push(AccountMeta::new_readonly(*key, false));
+                accounts.push(account);
+            }
+            do_process_instruction(instruction, accounts)
+        }
+
+        fn lp_soulbound(&self) -> bool {
+            SwapInfo::unpack_unchecked(&self.swap_account.data)
+                .is_ok_and(|swap_info| swap_info.lp_soulbound)
+        }
+
+        pub fn withdraw(
+            &mut self,
+            user_key: &Pubkey,
+            pool_key: &Pubkey,
+            mut pool_account: &mut Account,
+            token_a_key: &Pubkey,
+            token_a_account: &mut Account,
+            token_b_key: &Pubkey,
+            token_b_account: &mut Account,
+            pool_amount: u64,
+            minimum_a_amount: u64,
+            minimum_b_amount: u64,
+        ) -> ProgramResult {
+            // approve swap program to take out pool tokens, soulbound ones are burned by
+            // their owner
+            let lp_soulbound = self.lp_soulbound();
+            if !lp_soulbound {
+                do_process_instruction(
+                    approve(
+                        &TOKEN_PROGRAM_ID,
+                        &pool_key,
+                        &self.authority_key,
+                        &user_key,
+                        &[],
+                        pool_amount,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut pool_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+                .unwrap();
+            }
+
+            self.withdraw_unapproved(
+                user_key,
+                pool_key,
+                pool_account,
+                token_a_key,
+                token_a_account,
+                token_b_key,
+                token_b_account,
+                pool_amount,
+                minimum_a_amount,
+                minimum_b_amount,
+            )
+        }
+
+        /// Withdraws without the owner of POOL approving the swap authority first, as
+        /// anyone may try with accounts they don't own
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_unapproved(
+            &mut self,
+            user_key: &Pubkey,
+            pool_key: &Pubkey,
+            mut pool_account: &mut Account,
+            token_a_key: &Pubkey,
+            mut token_a_account: &mut Account,
+            token_b_key: &Pubkey,
+            mut token_b_account: &mut Account,
+            pool_amount: u64,
+            minimum_a_amount: u64,
+            minimum_b_amount: u64,
+        ) -> ProgramResult {
+            let lp_soulbound = self.lp_soulbound();
+            let mut instruction = withdraw(
+                &SWAP_PROGRAM_ID,
+                &TOKEN_PROGRAM_ID,
+                &self.swap_key,
+                &self.authority_key,
+                &self.pool_mint_key,
+                &pool_key,
+                &self.token_a_key,
+                &self.token_b_key,
+                &token_a_key,
+                &token_b_key,
+                &self.admin_fee_a_key,
+                &self.admin_fee_b_key,
+                lp_soulbound.then_some(user_key),
+                self.custodian_key.as_ref(),
+                pool_amount,
+                minimum_a_amount,
+                minimum_b_amount,
+                self.deadline_slot,
+                self.expected_sequence,
+            )
+            .unwrap();
+            let mut authority_account = Account::default();
+            let mut token_program_account = Account::default();
+            let mut clock = clock_account_at_slot(self.current_ts, self.deposit_slot);
+            let mut accounts = vec![
+                &mut self.swap_account,
+                &mut authority_account,
+                &mut self.pool_mint_account,
+                &mut pool_account,
+                &mut self.token_a_account,
+                &mut self.token_b_account,
+                &mut token_a_account,
+                &mut token_b_account,
+                &mut self.admin_fee_a_account,
+                &mut self.admin_fee_b_account,
+                &mut token_program_account,
+                &mut clock,
+            ];
+            // source owner and custodian
+            let mut signer_accounts = vec![Account::default(); instruction.accounts.len() - 12];
+            accounts.extend(signer_accounts.iter_mut());
+            if self.transfer_mints {
+                for mint_key in [self.token_a_mint_key, self.token_b_mint_key] {
+                    instruction
+                        .accounts
+                        .push(AccountMeta::new_readonly(mint_key, false));
+                }
+                accounts.push(&mut self.token_a_mint_account);
+                accounts.push(&mut self.token_b_mint_account);
+            }
+            if let Some((key, account)) = &mut self.global_stats {
+                instruction.accounts.push(AccountMeta::new(*key, false));
+                accounts.push(account);
+            }
+            do_process_instruction(instruction, accounts)
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_imbalanced(
+            &mut self,
+            user_key: &Pubkey,
+            pool_key: &Pubkey,
+            mut pool_account: &mut Account,
+            token_a_key: &Pubkey,
+            mut token_a_account: &mut Account,
+            token_b_key: &Pubkey,
+            mut token_b_account: &mut Account,
+            token_a_amount: u64,
+            token_b_amount: u64,
+            maximum_pool_token_amount: u64,
+        ) -> ProgramResult {
+            // approve swap program to take out pool tokens, soulbound ones are burned by
+            // their owner
+            let lp_soulbound = self.lp_soulbound();
+            if !lp_soulbound {
+                do_process_instruction(
+                    approve(
+                        &TOKEN_PROGRAM_ID,
+                        pool_key,
+                        &self.authority_key,
+                        user_key,
+                        &[],
+                        maximum_pool_token_amount,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut pool_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+                .unwrap();
+            }
+
+            let mut instruction = withdraw_imbalanced(
+                &SWAP_PROGRAM_ID,
+                &TOKEN_PROGRAM_ID,
+                &self.swap_key,
+                &self.authority_key,
+                &self.pool_mint_key,
+                pool_key,
+                &self.token_a_key,
+                &self.token_b_key,
+                token_a_key,
+                token_b_key,
+                &self.admin_fee_a_key,
+                &self.admin_fee_b_key,
+                lp_soulbound.then_some(user_key),
+                self.custodian_key.as_ref(),
+                token_a_amount,
+                token_b_amount,
+                maximum_pool_token_amount,
+                self.deadline_slot,
+                self.expected_sequence,
+            )
+            .unwrap();
+            let mut authority_account = Account::default();
+            let mut token_program_account = Account::default();
+            let mut clock = clock_account_at_slot(self.current_ts, self.deposit_slot);
+            let mut accounts = vec![
+                &mut self.swap_account,
+                &mut authority_account,
+                &mut self.pool_mint_account,
+                &mut pool_account,
+                &mut self.token_a_account,
+                &mut self.token_b_account,
+                &mut token_a_account,
+                &mut token_b_account,
+                &mut self.admin_fee_a_account,
+                &mut self.admin_fee_b_account,
+                &mut token_program_account,
+                &mut clock,
+            ];
+            // source owner and custodian
+            let mut signer_accounts = vec![Account::default(); instruction.accounts.len() - 12];
+            accounts.extend(signer_accounts.iter_mut());
+            if let Some((key, account)) = &mut self.global_stats {
+                instruction.accounts.push(AccountMeta::new(*key, false));
+                accounts.push(account);
+            }
+            do_process_instruction(instruction, accounts)
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn deploy_protocol_liquidity(
+            &mut self,
+            treasury_token_a_key: &Pubkey,
+            treasury_token_a_account: &mut Account,
+            treasury_token_b_key: &Pubkey,
+            treasury_token_b_account: &mut Account,
+            treasury_pool_key: &Pubkey,
+            treasury_pool_account: &mut Account,
+            amount_a: u64,
+            amount_b: u64,
+            min_mint_amount: u64,
+        ) -> ProgramResult {
+            for (token_key, token_account, amount) in [
+                (
+                    treasury_token_a_key,
+                    &mut *treasury_token_a_account,
+                    amount_a,
+                ),
+                (
+                    treasury_token_b_key,
+                    &mut *treasury_token_b_account,
+                    amount_b,
+                ),
+            ] {
+                do_process_instruction(
+                    approve(
+                        &TOKEN_PROGRAM_ID,
+                        token_key,
+                        &self.authority_key,
+                        &self.admin_key,
+                        &[],
+                        amount,
+                    )
+                    .unwrap(),
+                    vec![
+                        token_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+                .unwrap();
+            }
+
+            let mut authority_account = Account::default();
+            let mut token_program_account = Account::default();
+            let mut clock = clock_account(ZERO_TS);
+            let mut accounts = vec![
+                &mut self.swap_account,
+                &mut authority_account,
+                treasury_token_a_account,
+                treasury_token_b_account,
+                &mut self.token_a_account,
+                &mut self.token_b_account,
+                &mut self.pool_mint_account,
+                treasury_pool_account,
+                &mut token_program_account,
+                &mut clock,
+            ];
+            let credential_key = match &mut self.credential {
+                Some((key, account)) => {
+                    accounts.push(account);
+                    Some(*key)
+                }
+                None => None,
+            };
+            accounts.push(&mut self.admin_account);
+            do_process_instruction(
+                deploy_protocol_liquidity(
+                    deposit(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &self.swap_key,
+                        &self.authority_key,
+                        treasury_token_a_key,
+                        treasury_token_b_key,
+                        &self.token_a_key,
+                        &self.token_b_key,
+                        &self.pool_mint_key,
+                        treasury_pool_key,
+                        credential_key.as_ref(),
+                        amount_a,
+                        amount_b,
+                        min_mint_amount,
+                        self.deadline_slot,
+                        None,
+                    )
+                    .unwrap(),
+                    &self.admin_key,
+                )
+                .unwrap(),
+                accounts,
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_protocol_liquidity(
+            &mut self,
+            treasury_pool_key: &Pubkey,
+            mut treasury_pool_account: &mut Account,
+            treasury_token_a_key: &Pubkey,
+            treasury_token_a_account: &mut Account,
+            treasury_token_b_key: &Pubkey,
+            treasury_token_b_account: &mut Account,
+            pool_amount: u64,
+            minimum_a_amount: u64,
+            minimum_b_amount: u64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    treasury_pool_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &[],
+                    pool_amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut treasury_pool_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                withdraw_protocol_liquidity(
+                    withdraw(
+                        &SWAP_PROGRAM_ID,
+                        &TOKEN_PROGRAM_ID,
+                        &self.swap_key,
+                        &self.authority_key,
+                        &self.pool_mint_key,
+                        treasury_pool_key,
+                        &self.token_a_key,
+                        &self.token_b_key,
+                        treasury_token_a_key,
+                        treasury_token_b_key,
+                        &self.admin_fee_a_key,
+                        &self.admin_fee_b_key,
+                        None,
+                        None,
+                        pool_amount,
+                        minimum_a_amount,
+                        minimum_b_amount,
+                        self.deadline_slot,
+                        self.expected_sequence,
+                    )
+                    .unwrap(),
+                    &self.admin_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.pool_mint_account,
+                    treasury_pool_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    treasury_token_a_account,
+                    treasury_token_b_account,
+                    &mut self.admin_fee_a_account,
+                    &mut self.admin_fee_b_account,
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(self.current_ts, self.deposit_slot),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn begin_withdraw(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            pool_key: &Pubkey,
+            mut pool_account: &mut Account,
+            token_a_key: &Pubkey,
+            token_a_account: &mut Account,
+            token_b_key: &Pubkey,
+            token_b_account: &mut Account,
+            withdraw_continuation_account: &mut Account,
+            pool_amount: u64,
+            minimum_a_amount: u64,
+            minimum_b_amount: u64,
+        ) -> ProgramResult {
+            let (withdraw_continuation_key, nonce) =
+                find_withdraw_continuation_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+
+            // approve swap program to burn pool tokens, soulbound ones are burned by their
+            // owner
+            if !self.lp_soulbound() {
+                do_process_instruction(
+                    approve(
+                        &TOKEN_PROGRAM_ID,
+                        pool_key,
+                        &self.authority_key,
+                        owner_key,
+                        &[],
+                        pool_amount,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut pool_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+                .unwrap();
+            }
+
+            do_process_instruction(
+                begin_withdraw(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    &self.pool_mint_key,
+                    pool_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    token_a_key,
+                    token_b_key,
+                    &withdraw_continuation_key,
+                    self.custodian_key.as_ref(),
+                    pool_amount,
+                    minimum_a_amount,
+                    minimum_b_amount,
+                    nonce,
+                    self.expected_sequence,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    owner_account,
+                    &mut self.pool_mint_account,
+                    pool_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    token_a_account,
+                    token_b_account,
+                    withdraw_continuation_account,
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(self.current_ts, self.deposit_slot),
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn continue_withdraw(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            withdraw_continuation_account: &mut Account,
+            swap_token_key: &Pubkey,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+        ) -> ProgramResult {
+            let (withdraw_continuation_key, _nonce) =
+                find_withdraw_continuation_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+            let (swap_token_account, admin_fee_key, admin_fee_account) =
+                if *swap_token_key == self.token_a_key {
+                    (
+                        &mut self.token_a_account,
+                        self.admin_fee_a_key,
+                        &mut self.admin_fee_a_account,
+                    )
+                } else {
+                    (
+                        &mut self.token_b_account,
+                        self.admin_fee_b_key,
+                        &mut self.admin_fee_b_account,
+                    )
+                };
+            do_process_instruction(
+                continue_withdraw(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &withdraw_continuation_key,
+                    owner_key,
+                    swap_token_key,
+                    destination_key,
+                    &admin_fee_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    withdraw_continuation_account,
+                    owner_account,
+                    swap_token_account,
+                    destination_account,
+                    admin_fee_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn withdraw_one(
+            &mut self,
+            user_key: &Pubkey,
+            pool_key: &Pubkey,
+            pool_account: &mut Account,
+            dest_token_key: &Pubkey,
+            dest_token_account: &mut Account,
+            pool_amount: u64,
+            minimum_amount: u64,
+        ) -> ProgramResult {
+            self.withdraw_one_in(
+                user_key,
+                pool_key,
+                pool_account,
+                dest_token_key,
+                dest_token_account,
+                false,
+                pool_amount,
+                minimum_amount,
+            )
+        }
+
+        /// Withdraws token B if `token_b`, token A otherwise
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_one_in(
+            &mut self,
+            user_key: &Pubkey,
+            pool_key: &Pubkey,
+            mut pool_account: &mut Account,
+            dest_token_key: &Pubkey,
+            mut dest_token_account: &mut Account,
+            token_b: bool,
+            pool_amount: u64,
+            minimum_amount: u64,
+        ) -> ProgramResult {
+            // approve swap program to take out pool tokens, soulbound ones are burned by
+            // their owner
+            let lp_soulbound = self.lp_soulbound();
+            if !lp_soulbound {
+                do_process_instruction(
+                    approve(
+                        &TOKEN_PROGRAM_ID,
+                        &pool_key,
+                        &self.authority_key,
+                        &user_key,
+                        &[],
+                        pool_amount,
+                    )
+                    .unwrap(),
+                    vec![
+                        &mut pool_account,
+                        &mut Account::default(),
+                        &mut Account::default(),
+                    ],
+                )
+                .unwrap();
+            }
+
+            let (
+                base_key,
+                base_account,
+                quote_key,
+                quote_account,
+                admin_fee_key,
+                admin_fee_account,
+            ) = if token_b {
+                (
+                    self.token_b_key,
+                    &mut self.token_b_account,
+                    self.token_a_key,
+                    &mut self.token_a_account,
+                    self.admin_fee_b_key,
+                    &mut self.admin_fee_b_account,
+                )
+            } else {
+                (
+                    self.token_a_key,
+                    &mut self.token_a_account,
+                    self.token_b_key,
+                    &mut self.token_b_account,
+                    self.admin_fee_a_key,
+                    &mut self.admin_fee_a_account,
+                )
+            };
+
+            // perform withraw_one
+            do_process_instruction(
+                withdraw_one(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.pool_mint_key,
+                    &pool_key,
+                    &base_key,
+                    &quote_key,
+                    &dest_token_key,
+                    &admin_fee_key,
+                    lp_soulbound.then_some(user_key),
+                    self.custodian_key.as_ref(),
+                    pool_amount,
+                    minimum_amount,
+                    self.deadline_slot,
+                    self.expected_sequence,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.pool_mint_account,
+                    &mut pool_account,
+                    base_account,
+                    quote_account,
+                    &mut dest_token_account,
+                    admin_fee_account,
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(self.current_ts, self.deposit_slot),
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        /** Admin functions **/
+
+        pub fn ramp_a(
+            &mut self,
+            target_amp: u64,
+            current_ts: i64,
+            stop_ramp_ts: i64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                ramp_a(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    target_amp,
+                    stop_ramp_ts,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut clock_account(current_ts),
+                ],
+            )
+        }
+
+        pub fn stop_ramp_a(&mut self, current_ts: i64) -> ProgramResult {
+            do_process_instruction(
+                stop_ramp_a(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut clock_account(current_ts),
+                ],
+            )
+        }
+
+        pub fn pause(&mut self) -> ProgramResult {
+            do_process_instruction(
+                pause(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        pub fn unpause(&mut self) -> ProgramResult {
+            do_process_instruction(
+                unpause(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        pub fn set_admin_fee_account(
+            &mut self,
+            new_admin_fee_key: &Pubkey,
+            new_admin_fee_account: &Account,
+        ) -> ProgramResult {
+            do_process_instruction(
+                set_fee_account(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    new_admin_fee_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut new_admin_fee_account.clone(),
+                ],
+            )
+        }
+
+        pub fn apply_new_admin(
+            &mut self,
+            new_admin_key: &Pubkey,
+            current_ts: i64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                apply_new_admin(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    new_admin_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut clock_account(current_ts),
+                ],
+            )
+        }
+
+        pub fn commit_new_admin(
+            &mut self,
+            new_admin_key: &Pubkey,
+            current_ts: i64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                commit_new_admin(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    new_admin_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut Account::default(),
+                    &mut clock_account(current_ts),
+                ],
+            )
+        }
+
+        pub fn set_admin_recovery(
+            &mut self,
+            recovery_key: &Pubkey,
+            admin_timeout_slots: u64,
+            slot: Slot,
+        ) -> ProgramResult {
+            do_process_instruction(
+                set_admin_recovery(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    recovery_key,
+                    admin_timeout_slots,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        pub fn admin_heartbeat(&mut self, slot: Slot) -> ProgramResult {
+            do_process_instruction(
+                admin_heartbeat(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        pub fn claim_admin_after_timeout(
+            &mut self,
+            recovery_key: &Pubkey,
+            slot: Slot,
+        ) -> ProgramResult {
+            do_process_instruction(
+                claim_admin_after_timeout(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    recovery_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        pub fn set_new_fees(&mut self, new_fees: Fees, slot: Slot) -> ProgramResult {
+            do_process_instruction(
+                set_new_fees(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    new_fees,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        pub fn apply_new_fees(&mut self, slot: Slot) -> ProgramResult {
+            do_process_instruction(
+                apply_new_fees(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        pub fn cancel_pending_fees(&mut self) -> ProgramResult {
+            do_process_instruction(
+                cancel_pending_fees(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        pub fn set_harvest_tip(&mut self, harvest_tip_bps: u16) -> ProgramResult {
+            do_process_instruction(
+                set_harvest_tip(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    harvest_tip_bps,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        pub fn set_max_swap_amount(&mut self, max_swap_amount_in: u64) -> ProgramResult {
+            do_process_instruction(
+                set_max_swap_amount(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    max_swap_amount_in,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        pub fn set_dust_threshold(&mut self, dust_threshold: u64) -> ProgramResult {
+            do_process_instruction(
+                set_dust_threshold(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    dust_threshold,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        pub fn set_custodian(
+            &mut self,
+            custodian_key: &Pubkey,
+            custodian_withdraw_threshold: u64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                set_custodian(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    custodian_key,
+                    custodian_withdraw_threshold,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        /// Sets the oracle guard to the price accounts of `oracles`, disables it
+        /// without
+        pub fn set_oracle_guard(
+            &mut self,
+            max_staleness_slots: u64,
+            max_confidence_bps: u16,
+            max_deviation_bps: u16,
+        ) -> ProgramResult {
+            let [(oracle_a_key, mut oracle_a_account), (oracle_b_key, mut oracle_b_account)] =
+                self.oracles.clone().unwrap_or_default();
+            do_process_instruction(
+                set_oracle_guard(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &oracle_a_key,
+                    &oracle_b_key,
+                    max_staleness_slots,
+                    max_confidence_bps,
+                    max_deviation_bps,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut oracle_a_account,
+                    &mut oracle_b_account,
+                ],
+            )
+        }
+
+        pub fn set_oracle_stale_policy(
+            &mut self,
+            stale_policy: OracleStalePolicy,
+            stale_band_bps: u16,
+        ) -> ProgramResult {
+            do_process_instruction(
+                set_oracle_stale_policy(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    stale_policy,
+                    stale_band_bps,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        pub fn set_guardian(&mut self, guardian_key: &Pubkey) -> ProgramResult {
+            do_process_instruction(
+                set_guardian(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    guardian_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn set_custom_error_message(
+            &mut self,
+            error_code: u32,
+            message: &str,
+        ) -> ProgramResult {
+            do_process_instruction(
+                set_custom_error_message(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    error_code,
+                    message.to_string(),
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        pub fn set_credential_issuer(&mut self, credential_issuer_key: &Pubkey) -> ProgramResult {
+            do_process_instruction(
+                set_credential_issuer(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    credential_issuer_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn update_lp_metadata(
+            &mut self,
+            metadata_key: &Pubkey,
+            metadata_account: &mut Account,
+            name: &str,
+            symbol: &str,
+            uri: &str,
+        ) -> ProgramResult {
+            do_process_instruction(
+                update_lp_metadata(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    metadata_key,
+                    name.to_string(),
+                    symbol.to_string(),
+                    uri.to_string(),
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    metadata_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn set_min_lp_mint(&mut self, min_lp_mint: u64) -> ProgramResult {
+            do_process_instruction(
+                set_min_lp_mint(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    min_lp_mint,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        pub fn harvest_admin_fees(
+            &mut self,
+            tip_token_a_key: &Pubkey,
+            tip_token_a_account: &mut Account,
+            tip_token_b_key: &Pubkey,
+            tip_token_b_account: &mut Account,
+        ) -> ProgramResult {
+            let mut instruction = harvest_admin_fees(
+                &SWAP_PROGRAM_ID,
+                &TOKEN_PROGRAM_ID,
+                &self.swap_key,
+                &self.authority_key,
+                &self.token_a_key,
+                &self.token_b_key,
+                &self.admin_fee_a_key,
+                &self.admin_fee_b_key,
+                tip_token_a_key,
+                tip_token_b_key,
+                None,
+            )
+            .unwrap();
+            let mut authority_account = Account::default();
+            let mut token_program_account = Account::default();
+            let mut accounts = vec![
+                &mut self.swap_account,
+                &mut authority_account,
+                &mut self.token_a_account,
+                &mut self.token_b_account,
+                &mut self.admin_fee_a_account,
+                &mut self.admin_fee_b_account,
+                tip_token_a_account,
+                tip_token_b_account,
+                &mut token_program_account,
+            ];
+            if self.transfer_mints {
+                for mint_key in [self.token_a_mint_key, self.token_b_mint_key] {
+                    instruction
+                        .accounts
+                        .push(AccountMeta::new_readonly(mint_key, false));
+                }
+                accounts.push(&mut self.token_a_mint_account);
+                accounts.push(&mut self.token_b_mint_account);
+            }
+            do_process_instruction(instruction, accounts)
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn harvest_admin_fees_with_ve(
+            &mut self,
+            tip_token_a_key: &Pubkey,
+            tip_token_a_account: &mut Account,
+            tip_token_b_key: &Pubkey,
+            tip_token_b_account: &mut Account,
+            ve_fee_vault_a_account: &mut Account,
+            ve_fee_vault_b_account: &mut Account,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            do_process_instruction(
+                harvest_admin_fees(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.admin_fee_a_key,
+                    &self.admin_fee_b_key,
+                    tip_token_a_key,
+                    tip_token_b_key,
+                    Some((&swap_info.ve_fee_vault_a, &swap_info.ve_fee_vault_b)),
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.admin_fee_a_account,
+                    &mut self.admin_fee_b_account,
+                    tip_token_a_account,
+                    tip_token_b_account,
+                    &mut Account::default(),
+                    ve_fee_vault_a_account,
+                    ve_fee_vault_b_account,
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn emit_pool_summary(&mut self) -> ProgramResult {
+            do_process_instruction(
+                emit_pool_summary(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    &self.pool_mint_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    &mut self.pool_mint_account,
+                    &mut clock_account(ZERO_TS),
+                ],
+            )
+        }
+
+        pub fn get_pool_status(&mut self, slot: Slot) -> ProgramResult {
+            do_process_instruction(
+                get_pool_status(&SWAP_PROGRAM_ID, &self.swap_key).unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        pub fn check_fee_destinations(&mut self) -> ProgramResult {
+            do_process_instruction(
+                check_fee_destinations(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.admin_fee_a_key,
+                    &self.admin_fee_b_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut self.admin_fee_a_account,
+                    &mut self.admin_fee_b_account,
+                ],
+            )
+        }
+
+        pub fn create_admin_session(
+            &mut self,
+            session_key: &Pubkey,
+            admin_session_account: &mut Account,
+            permissions: u8,
+            expiry_slot: Slot,
+            slot: Slot,
+        ) -> ProgramResult {
+            let (admin_session_key, nonce) =
+                find_admin_session_address(&SWAP_PROGRAM_ID, &self.swap_key, session_key);
+            do_process_instruction(
+                create_admin_session(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &admin_session_key,
+                    session_key,
+                    permissions,
+                    expiry_slot,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    admin_session_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        pub fn snapshot_state(
+            &mut self,
+            parameter_snapshot_account: &mut Account,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let (parameter_snapshot_key, nonce) =
+                find_parameter_snapshot_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            do_process_instruction(
+                snapshot_state(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &parameter_snapshot_key,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    parameter_snapshot_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn add_fee_exemption(
+            &mut self,
+            fee_exemptions_account: &mut Account,
+            trader_key: &Pubkey,
+        ) -> ProgramResult {
+            let (fee_exemptions_key, nonce) =
+                find_fee_exemptions_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            do_process_instruction(
+                add_fee_exemption(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &fee_exemptions_key,
+                    trader_key,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    fee_exemptions_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut rent_account(),
+                ],
+            )
+        }
+
+        pub fn remove_fee_exemption(
+            &mut self,
+            fee_exemptions_account: &mut Account,
+            trader_key: &Pubkey,
+        ) -> ProgramResult {
+            let (fee_exemptions_key, _nonce) =
+                find_fee_exemptions_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            do_process_instruction(
+                remove_fee_exemption(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &fee_exemptions_key,
+                    trader_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    fee_exemptions_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn add_allowed_depositor(
+            &mut self,
+            deposit_allowlist_account: &mut Account,
+            depositor_key: &Pubkey,
+        ) -> ProgramResult {
+            let (deposit_allowlist_key, nonce) =
+                find_deposit_allowlist_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            do_process_instruction(
+                add_allowed_depositor(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &deposit_allowlist_key,
+                    depositor_key,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    deposit_allowlist_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut rent_account(),
+                ],
+            )
+        }
+
+        pub fn remove_allowed_depositor(
+            &mut self,
+            deposit_allowlist_account: &mut Account,
+            depositor_key: &Pubkey,
+        ) -> ProgramResult {
+            let (deposit_allowlist_key, _nonce) =
+                find_deposit_allowlist_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            do_process_instruction(
+                remove_allowed_depositor(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &deposit_allowlist_key,
+                    depositor_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    deposit_allowlist_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn set_deposit_allowlist(&mut self, enabled: bool) -> ProgramResult {
+            do_process_instruction(
+                set_deposit_allowlist(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    enabled,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        pub fn restore_state(
+            &mut self,
+            parameter_snapshot_account: &mut Account,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let (parameter_snapshot_key, _nonce) =
+                find_parameter_snapshot_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            do_process_instruction(
+                restore_state(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &parameter_snapshot_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    parameter_snapshot_account,
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn revoke_admin_session(
+            &mut self,
+            admin_session_key: &Pubkey,
+            admin_session_account: &mut Account,
+        ) -> ProgramResult {
+            do_process_instruction(
+                revoke_admin_session(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    admin_session_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    admin_session_account,
+                ],
+            )
+        }
+
+        pub fn set_reflection_distributor(&mut self, distributor_key: &Pubkey) -> ProgramResult {
+            do_process_instruction(
+                set_reflection_distributor(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    distributor_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn initialize_dev_fee_vesting(
+            &mut self,
+            dev_vesting_a_key: &Pubkey,
+            dev_vesting_a_account: &mut Account,
+            dev_vesting_b_key: &Pubkey,
+            dev_vesting_b_account: &mut Account,
+            dev_wallet_key: &Pubkey,
+            vesting_duration: i64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                initialize_dev_fee_vesting(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    dev_vesting_a_key,
+                    dev_vesting_b_key,
+                    dev_wallet_key,
+                    vesting_duration,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    dev_vesting_a_account,
+                    dev_vesting_b_account,
+                    &mut Account::default(),
+                    &mut clock_account(ZERO_TS),
+                ],
+            )
+        }
+
+        /// Claims the vested developer fees held by the developer fee accounts,
+        /// which are the vesting accounts once vesting is initialized.
+        pub fn claim_vested_dev_fees(
+            &mut self,
+            dev_wallet_key: &Pubkey,
+            destination_a_key: &Pubkey,
+            destination_a_account: &mut Account,
+            destination_b_key: &Pubkey,
+            destination_b_account: &mut Account,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                claim_vested_dev_fees(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    dev_wallet_key,
+                    &self.fee_accounts_a.developer_key,
+                    &self.fee_accounts_b.developer_key,
+                    destination_a_key,
+                    destination_b_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut self.fee_accounts_a.developer_account,
+                    &mut self.fee_accounts_b.developer_account,
+                    destination_a_account,
+                    destination_b_account,
+                    &mut Account::default(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn set_governance_realm(
+            &mut self,
+            realm_key: &Pubkey,
+            governance_vault_key: &Pubkey,
+            governance_vault_account: &mut Account,
+            governance_lockup: i64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                set_governance_realm(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    realm_key,
+                    governance_vault_key,
+                    governance_lockup,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut Account::default(),
+                    governance_vault_account,
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_governance_tokens(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            source_key: &Pubkey,
+            mut source_account: &mut Account,
+            governance_vault_account: &mut Account,
+            governance_deposit_account: &mut Account,
+            voter_weight_record_account: &mut Account,
+            amount: u64,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (governance_deposit_key, deposit_nonce) =
+                find_governance_deposit_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+            let (voter_weight_record_key, record_nonce) = find_voter_weight_record_address(
+                &SWAP_PROGRAM_ID,
+                &swap_info.governance_realm,
+                &self.pool_mint_key,
+                owner_key,
+            );
+
+            // approve moving from the owner source account
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    source_key,
+                    &self.authority_key,
+                    owner_key,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                deposit_governance_tokens(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    source_key,
+                    &swap_info.governance_vault,
+                    &governance_deposit_key,
+                    &voter_weight_record_key,
+                    amount,
+                    deposit_nonce,
+                    record_nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    owner_account,
+                    source_account,
+                    governance_vault_account,
+                    governance_deposit_account,
+                    voter_weight_record_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut Account::default(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_governance_tokens(
+            &mut self,
+            owner_key: &Pubkey,
+            governance_deposit_account: &mut Account,
+            voter_weight_record_account: &mut Account,
+            governance_vault_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            amount: u64,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (governance_deposit_key, _deposit_nonce) =
+                find_governance_deposit_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+            let (voter_weight_record_key, _record_nonce) = find_voter_weight_record_address(
+                &SWAP_PROGRAM_ID,
+                &swap_info.governance_realm,
+                &self.pool_mint_key,
+                owner_key,
+            );
+            do_process_instruction(
+                withdraw_governance_tokens(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    &governance_deposit_key,
+                    &voter_weight_record_key,
+                    &swap_info.governance_vault,
+                    destination_key,
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    governance_deposit_account,
+                    voter_weight_record_account,
+                    governance_vault_account,
+                    destination_account,
+                    &mut Account::default(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn initialize_ve_escrow(
+            &mut self,
+            ve_lp_vault_key: &Pubkey,
+            ve_lp_vault_account: &mut Account,
+            ve_fee_vault_a_key: &Pubkey,
+            ve_fee_vault_a_account: &mut Account,
+            ve_fee_vault_b_key: &Pubkey,
+            ve_fee_vault_b_account: &mut Account,
+            ve_fee_bps: u16,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                initialize_ve_escrow(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    ve_lp_vault_key,
+                    ve_fee_vault_a_key,
+                    ve_fee_vault_b_key,
+                    ve_fee_bps,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    ve_lp_vault_account,
+                    ve_fee_vault_a_account,
+                    ve_fee_vault_b_account,
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn set_ve_fee_share(&mut self, ve_fee_bps: u16) -> ProgramResult {
+            do_process_instruction(
+                set_ve_fee_share(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    ve_fee_bps,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn create_ve_lock(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            source_key: &Pubkey,
+            mut source_account: &mut Account,
+            ve_lp_vault_account: &mut Account,
+            ve_lock_account: &mut Account,
+            amount: u64,
+            lock_duration: i64,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (ve_lock_key, nonce) =
+                find_ve_lock_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+
+            // approve moving from the owner source account
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    source_key,
+                    &self.authority_key,
+                    owner_key,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                create_ve_lock(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    source_key,
+                    &swap_info.ve_lp_vault,
+                    &ve_lock_key,
+                    amount,
+                    lock_duration,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    owner_account,
+                    source_account,
+                    ve_lp_vault_account,
+                    ve_lock_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut Account::default(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn claim_ve_fees(
+            &mut self,
+            owner_key: &Pubkey,
+            ve_lock_account: &mut Account,
+            ve_fee_vault_a_account: &mut Account,
+            ve_fee_vault_b_account: &mut Account,
+            destination_a_key: &Pubkey,
+            destination_a_account: &mut Account,
+            destination_b_key: &Pubkey,
+            destination_b_account: &mut Account,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (ve_lock_key, _nonce) =
+                find_ve_lock_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+            do_process_instruction(
+                claim_ve_fees(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    &ve_lock_key,
+                    &swap_info.ve_fee_vault_a,
+                    &swap_info.ve_fee_vault_b,
+                    destination_a_key,
+                    destination_b_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    ve_lock_account,
+                    ve_fee_vault_a_account,
+                    ve_fee_vault_b_account,
+                    destination_a_account,
+                    destination_b_account,
+                    &mut Account::default(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_ve_lock(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            ve_lock_account: &mut Account,
+            ve_lp_vault_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            ve_fee_vault_a_account: &mut Account,
+            ve_fee_vault_b_account: &mut Account,
+            destination_a_key: &Pubkey,
+            destination_a_account: &mut Account,
+            destination_b_key: &Pubkey,
+            destination_b_account: &mut Account,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (ve_lock_key, _nonce) =
+                find_ve_lock_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+            do_process_instruction(
+                withdraw_ve_lock(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    &ve_lock_key,
+                    &swap_info.ve_lp_vault,
+                    destination_key,
+                    &swap_info.ve_fee_vault_a,
+                    &swap_info.ve_fee_vault_b,
+                    destination_a_key,
+                    destination_b_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    owner_account,
+                    ve_lock_account,
+                    ve_lp_vault_account,
+                    destination_account,
+                    ve_fee_vault_a_account,
+                    ve_fee_vault_b_account,
+                    destination_a_account,
+                    destination_b_account,
+                    &mut Account::default(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn report_epoch_fees(&mut self, epoch: Epoch) -> ProgramResult {
+            do_process_instruction(
+                report_epoch_fees(&SWAP_PROGRAM_ID, &self.swap_key).unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut clock_account_at_epoch(ZERO_TS, epoch),
+                ],
+            )
+        }
+
+        pub fn initialize_liquidity_positions(
+            &mut self,
+            liquidity_vault_key: &Pubkey,
+            liquidity_vault_account: &mut Account,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                initialize_liquidity_positions(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    liquidity_vault_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    liquidity_vault_account,
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_liquidity_position(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            source_key: &Pubkey,
+            mut source_account: &mut Account,
+            liquidity_vault_account: &mut Account,
+            position_account: &mut Account,
+            amount: u64,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (position_key, nonce) =
+                find_liquidity_position_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+
+            // approve moving from the owner source account
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    source_key,
+                    &self.authority_key,
+                    owner_key,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                deposit_liquidity_position(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    source_key,
+                    &swap_info.liquidity_vault,
+                    &position_key,
+                    amount,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    owner_account,
+                    source_account,
+                    liquidity_vault_account,
+                    position_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut Account::default(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_liquidity_position(
+            &mut self,
+            owner_key: &Pubkey,
+            position_account: &mut Account,
+            liquidity_vault_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            amount: u64,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (position_key, _nonce) =
+                find_liquidity_position_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+            do_process_instruction(
+                withdraw_liquidity_position(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    &position_key,
+                    &swap_info.liquidity_vault,
+                    destination_key,
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    position_account,
+                    liquidity_vault_account,
+                    destination_account,
+                    &mut Account::default(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn initialize_reflection(
+            &mut self,
+            reflection_lp_vault_key: &Pubkey,
+            reflection_lp_vault_account: &mut Account,
+            reflection_vault_a_key: &Pubkey,
+            reflection_vault_a_account: &mut Account,
+            reflection_vault_b_key: &Pubkey,
+            reflection_vault_b_account: &mut Account,
+        ) -> ProgramResult {
+            do_process_instruction(
+                initialize_reflection(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    reflection_lp_vault_key,
+                    reflection_vault_a_key,
+                    reflection_vault_b_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    reflection_lp_vault_account,
+                    reflection_vault_a_account,
+                    reflection_vault_b_account,
+                ],
+            )
+        }
+
+        pub fn initialize_buyback(
+            &mut self,
+            buyback_vault_a_key: &Pubkey,
+            buyback_vault_a_account: &mut Account,
+            buyback_vault_b_key: &Pubkey,
+            buyback_vault_b_account: &mut Account,
+            buyback_mint_key: &Pubkey,
+        ) -> ProgramResult {
+            do_process_instruction(
+                initialize_buyback(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    buyback_vault_a_key,
+                    buyback_vault_b_key,
+                    buyback_mint_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    buyback_vault_a_account,
+                    buyback_vault_b_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        /// Executes a buyback of the token of `buyback_mint`, the buyback vault
+        /// accounts being those of the other token and of the token bought back
+        pub fn execute_buyback(
+            &mut self,
+            source_vault_account: &mut Account,
+            burn_vault_account: &mut Account,
+            minimum_amount_out: u64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (source_vault_key, burn_vault_key, swap_source_key, swap_destination_key) =
+                if swap_info.buyback_mint == self.token_b_mint_key {
+                    (
+                        swap_info.buyback_vault_a,
+                        swap_info.buyback_vault_b,
+                        self.token_a_key,
+                        self.token_b_key,
+                    )
+                } else {
+                    (
+                        swap_info.buyback_vault_b,
+                        swap_info.buyback_vault_a,
+                        self.token_b_key,
+                        self.token_a_key,
+                    )
+                };
+            let mut swap_source_account = self.get_token_account(&swap_source_key).clone();
+            let mut swap_destination_account =
+                self.get_token_account(&swap_destination_key).clone();
+            let buyback_mint_account = if swap_info.buyback_mint == self.token_b_mint_key {
+                &mut self.token_b_mint_account
+            } else {
+                &mut self.token_a_mint_account
+            };
+            do_process_instruction(
+                execute_buyback(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &source_vault_key,
+                    &burn_vault_key,
+                    &swap_source_key,
+                    &swap_destination_key,
+                    &swap_info.buyback_mint,
+                    minimum_amount_out,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    source_vault_account,
+                    burn_vault_account,
+                    &mut swap_source_account,
+                    &mut swap_destination_account,
+                    buyback_mint_account,
+                    &mut Account::default(),
+                    &mut clock_account(ZERO_TS),
+                ],
+            )?;
+
+            self.set_token_account(&swap_source_key, swap_source_account);
+            self.set_token_account(&swap_destination_key, swap_destination_account);
+            Ok(())
+        }
+
+        pub fn withdraw_wallet_fees(
+            &mut self,
+            bucket: FeeBucket,
+            wallet_key: &Pubkey,
+            destination_a_key: &Pubkey,
+            destination_a_account: &mut Account,
+            destination_b_key: &Pubkey,
+            destination_b_account: &mut Account,
+        ) -> ProgramResult {
+            let withdraw = match bucket {
+                FeeBucket::Marketing => withdraw_marketing_fees,
+                FeeBucket::Developer => withdraw_developer_fees,
+            };
+            do_process_instruction(
+                withdraw(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    wallet_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    destination_a_key,
+                    destination_b_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    destination_a_account,
+                    destination_b_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn claim_referral_fees(
+            &mut self,
+            referrer_key: &Pubkey,
+            destination_a_key: &Pubkey,
+            destination_a_account: &mut Account,
+            destination_b_key: &Pubkey,
+            destination_b_account: &mut Account,
+        ) -> ProgramResult {
+            let (referral_accrual_key, referral_accrual_account) =
+                self.referral_accrual.as_mut().unwrap();
+            do_process_instruction(
+                claim_referral_fees(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    referral_accrual_key,
+                    referrer_key,
+                    &self.token_a_key,
+                    &self.token_b_key,
+                    destination_a_key,
+                    destination_b_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    referral_accrual_account,
+                    &mut Account::default(),
+                    &mut self.token_a_account,
+                    &mut self.token_b_account,
+                    destination_a_account,
+                    destination_b_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn deposit_reflection_position(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            source_key: &Pubkey,
+            mut source_account: &mut Account,
+            reflection_lp_vault_account: &mut Account,
+            position_account: &mut Account,
+            amount: u64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (position_key, nonce) =
+                find_reflection_position_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+
+            // approve moving from the owner source account
+            do_process_instruction(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    source_key,
+                    &self.authority_key,
+                    owner_key,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                deposit_reflection_position(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    source_key,
+                    &swap_info.reflection_lp_vault,
+                    &position_key,
+                    amount,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    owner_account,
+                    source_account,
+                    reflection_lp_vault_account,
+                    position_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn withdraw_reflection_position(
+            &mut self,
+            owner_key: &Pubkey,
+            position_account: &mut Account,
+            reflection_lp_vault_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            amount: u64,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (position_key, _nonce) =
+                find_reflection_position_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+            do_process_instruction(
+                withdraw_reflection_position(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    &position_key,
+                    &swap_info.reflection_lp_vault,
+                    destination_key,
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    position_account,
+                    reflection_lp_vault_account,
+                    destination_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn claim_reflection(
+            &mut self,
+            owner_key: &Pubkey,
+            position_account: &mut Account,
+            reflection_vault_a_account: &mut Account,
+            reflection_vault_b_account: &mut Account,
+            destination_a_key: &Pubkey,
+            destination_a_account: &mut Account,
+            destination_b_key: &Pubkey,
+            destination_b_account: &mut Account,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+            let (position_key, _nonce) =
+                find_reflection_position_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+            do_process_instruction(
+                claim_reflection(
+                    &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    owner_key,
+                    &position_key,
+                    &swap_info.reflection_vault_a,
+                    &swap_info.reflection_vault_b,
+                    destination_a_key,
+                    destination_b_key,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                    position_account,
+                    reflection_vault_a_account,
+                    reflection_vault_b_account,
+                    destination_a_account,
+                    destination_b_account,
+                    &mut Account::default(),
+                ],
+            )
+        }
+
+        pub fn checkpoint_liquidity_position(
+            &mut self,
+            owner_key: &Pubkey,
+            position_account: &mut Account,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let (position_key, _nonce) =
+                find_liquidity_position_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+            do_process_instruction(
+                checkpoint_liquidity_position(&SWAP_PROGRAM_ID, &self.swap_key, &position_key)
+                    .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    position_account,
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn initialize_gauge_controller(
+            &mut self,
+            gauge_controller_account: &mut Account,
+            emission_per_epoch: u64,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let (gauge_controller_key, nonce) =
+                find_gauge_controller_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            do_process_instruction(
+                initialize_gauge_controller(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &gauge_controller_key,
+                    emission_per_epoch,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    gauge_controller_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn add_gauge(
+            &mut self,
+            gauge_controller_account: &mut Account,
+            pool_swap_key: &Pubkey,
+            pool_swap_account: &mut Account,
+            gauge_account: &mut Account,
+        ) -> ProgramResult {
+            let (gauge_controller_key, _nonce) =
+                find_gauge_controller_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            let (gauge_key, nonce) =
+                find_gauge_address(&SWAP_PROGRAM_ID, &gauge_controller_key, pool_swap_key);
+            do_process_instruction(
+                add_gauge(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &gauge_controller_key,
+                    pool_swap_key,
+                    &gauge_key,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    gauge_controller_account,
+                    pool_swap_account,
+                    gauge_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                ],
+            )
+        }
+
+        pub fn set_gauge_emission(
+            &mut self,
+            gauge_controller_account: &mut Account,
+            emission_per_epoch: u64,
+        ) -> ProgramResult {
+            let (gauge_controller_key, _nonce) =
+                find_gauge_controller_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            do_process_instruction(
+                set_gauge_emission(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    &gauge_controller_key,
+                    emission_per_epoch,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    gauge_controller_account,
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn vote_gauge_weight(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            ve_lock_account: &mut Account,
+            gauge_controller_account: &mut Account,
+            pool_swap_key: &Pubkey,
+            gauge_account: &mut Account,
+            gauge_vote_account: &mut Account,
+            weight_bps: u16,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let (ve_lock_key, _nonce) =
+                find_ve_lock_address(&SWAP_PROGRAM_ID, &self.swap_key, owner_key);
+            let (gauge_controller_key, _nonce) =
+                find_gauge_controller_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            let (gauge_key, _nonce) =
+                find_gauge_address(&SWAP_PROGRAM_ID, &gauge_controller_key, pool_swap_key);
+            let (gauge_vote_key, nonce) =
+                find_gauge_vote_address(&SWAP_PROGRAM_ID, &gauge_key, owner_key);
+            do_process_instruction(
+                vote_gauge_weight(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    owner_key,
+                    &ve_lock_key,
+                    &gauge_controller_key,
+                    &gauge_key,
+                    &gauge_vote_key,
+                    weight_bps,
+                    nonce,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    owner_account,
+                    ve_lock_account,
+                    gauge_controller_account,
+                    gauge_account,
+                    gauge_vote_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn checkpoint_gauge(
+            &self,
+            gauge_controller_account: &mut Account,
+            pool_swap_key: &Pubkey,
+            gauge_account: &mut Account,
+            unix_timestamp: i64,
+        ) -> ProgramResult {
+            let (gauge_controller_key, _nonce) =
+                find_gauge_controller_address(&SWAP_PROGRAM_ID, &self.swap_key);
+            let (gauge_key, _nonce) =
+                find_gauge_address(&SWAP_PROGRAM_ID, &gauge_controller_key, pool_swap_key);
+            do_process_instruction(
+                checkpoint_gauge(&SWAP_PROGRAM_ID, &gauge_controller_key, &gauge_key).unwrap(),
+                vec![
+                    gauge_controller_account,
+                    gauge_account,
+                    &mut clock_account(unix_timestamp),
+                ],
+            )
+        }
+
+        pub fn set_order_flow_auction(
+            &mut self,
+            bid_token_key: &Pubkey,
+            batch_slots: u64,
+            slot: Slot,
+        ) -> ProgramResult {
+            do_process_instruction(
+                set_order_flow_auction(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    bid_token_key,
+                    batch_slots,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
+                ],
+            )
+        }
+
+        pub fn set_deposit_bonus(
+            &mut self,
+            fee_buffer_bps: u16,
+            deposit_bonus_bps: u16,
+        ) -> ProgramResult {
+            do_process_instruction(
+                set_deposit_bonus(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    fee_buffer_bps,
+                    deposit_bonus_bps,
+                )
+                .unwrap(),
+                vec![
+                    &mut self.swap_account,
+                    &mut Account::default(),
+                    &mut self.admin_account,
+                ],
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn set_params(
+            &mut self,
+            fees: Fees,
+            target_amp: u64,
+            stop_ramp_ts: i64,
+            max_swap_amount_in: u64,
+            is_paused: bool,
+            current_ts: i64,
+        ) -> ProgramResult {
+            do_process_instruction(
+                set_params(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    &self.authority_key,
+                    &self.admin_key,
+                    fees,
+                    target_amp,
+                    stop_ramp_ts,
+                    max_swap_amount_in,
+                    is_paused,
                 )
                 .unwrap(),
                 vec![
-                    &mut depositor_token_b_account,
-                    &mut Account::default(),
+                    &mut self.swap_account,
                     &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut clock_account(current_ts),
                 ],
             )
-            .unwrap();
+        }
 
-            // perform deposit
+        pub fn set_insurance_fund(
+            &mut self,
+            realm_key: &Pubkey,
+            realm_account: &mut Account,
+            insurance_authority_key: &Pubkey,
+            insurance_authority_account: &mut Account,
+            insurance_fee_bps: u16,
+        ) -> ProgramResult {
             do_process_instruction(
-                deposit(
+                set_insurance_fund(
                     &SWAP_PROGRAM_ID,
-                    &TOKEN_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
-                    &depositor_token_a_key,
-                    &depositor_token_b_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    &self.pool_mint_key,
-                    &depositor_pool_key,
-                    amount_a,
-                    amount_b,
-                    min_mint_amount,
+                    &self.admin_key,
+                    realm_key,
+                    insurance_authority_key,
+                    insurance_fee_bps,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
-                    &mut depositor_token_a_account,
-                    &mut depositor_token_b_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut self.pool_mint_account,
-                    &mut depositor_pool_account,
-                    &mut Account::default(),
-                    &mut clock_account(ZERO_TS),
+                    &mut self.admin_account,
+                    realm_account,
+                    insurance_authority_account,
                 ],
             )
         }
 
-        pub fn withdraw(
+        pub fn cover_shortfall(
             &mut self,
-            user_key: &Pubkey,
-            pool_key: &Pubkey,
-            mut pool_account: &mut Account,
-            token_a_key: &Pubkey,
-            mut token_a_account: &mut Account,
-            token_b_key: &Pubkey,
-            mut token_b_account: &mut Account,
-            pool_amount: u64,
-            minimum_a_amount: u64,
-            minimum_b_amount: u64,
+            insurance_authority_key: &Pubkey,
+            amount_a: u64,
+            amount_b: u64,
         ) -> ProgramResult {
-            // approve swap program to take out pool tokens
             do_process_instruction(
-                approve(
-                    &TOKEN_PROGRAM_ID,
-                    &pool_key,
-                    &self.authority_key,
-                    &user_key,
-                    &[],
-                    pool_amount,
+                cover_shortfall(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
+                    insurance_authority_key,
+                    amount_a,
+                    amount_b,
                 )
                 .unwrap(),
-                vec![
-                    &mut pool_account,
-                    &mut Account::default(),
-                    &mut Account::default(),
-                ],
+                vec![&mut self.swap_account, &mut Account::default()],
             )
-            .unwrap();
+        }
 
-            // perform withraw
+        pub fn set_dynamic_amp(
+            &mut self,
+            min_amp: u64,
+            max_amp: u64,
+            volatility_ceiling: u64,
+            max_drift_per_slot: u64,
+            current_ts: i64,
+            slot: Slot,
+        ) -> ProgramResult {
             do_process_instruction(
-                withdraw(
+                set_dynamic_amp(
                     &SWAP_PROGRAM_ID,
-                    &TOKEN_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
-                    &self.pool_mint_key,
-                    &pool_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    &token_a_key,
-                    &token_b_key,
-                    &self.admin_fee_a_key,
-                    &self.admin_fee_b_key,
-                    pool_amount,
-                    minimum_a_amount,
-                    minimum_b_amount,
+                    &self.admin_key,
+                    min_amp,
+                    max_amp,
+                    volatility_ceiling,
+                    max_drift_per_slot,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
-                    &mut self.pool_mint_account,
-                    &mut pool_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut token_a_account,
-                    &mut token_b_account,
-                    &mut self.admin_fee_a_account,
-                    &mut self.admin_fee_b_account,
-                    &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut clock_account_at_slot(current_ts, slot),
                 ],
-            )?;
-
-            Ok(())
+            )
         }
 
-        pub fn withdraw_one(
+        pub fn set_volatility_fee(
             &mut self,
-            user_key: &Pubkey,
-            pool_key: &Pubkey,
-            mut pool_account: &mut Account,
-            dest_token_key: &Pubkey,
-            mut dest_token_account: &mut Account,
-            pool_amount: u64,
-            minimum_amount: u64,
+            volatility_ceiling: u64,
+            max_multiplier_bps: u16,
+            slot: Slot,
         ) -> ProgramResult {
-            // approve swap program to take out pool tokens
             do_process_instruction(
-                approve(
-                    &TOKEN_PROGRAM_ID,
-                    &pool_key,
+                set_volatility_fee(
+                    &SWAP_PROGRAM_ID,
+                    &self.swap_key,
                     &self.authority_key,
-                    &user_key,
-                    &[],
-                    pool_amount,
+                    &self.admin_key,
+                    volatility_ceiling,
+                    max_multiplier_bps,
                 )
                 .unwrap(),
                 vec![
-                    &mut pool_account,
-                    &mut Account::default(),
+                    &mut self.swap_account,
                     &mut Account::default(),
+                    &mut self.admin_account,
+                    &mut clock_account_at_slot(ZERO_TS, slot),
                 ],
             )
-            .unwrap();
+        }
 
-            // perform withraw_one
+        pub fn set_admin_fee_epoch_cap(&mut self, cap_a: u64, cap_b: u64) -> ProgramResult {
             do_process_instruction(
-                withdraw_one(
+                set_admin_fee_epoch_cap(
                     &SWAP_PROGRAM_ID,
-                    &TOKEN_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
-                    &self.pool_mint_key,
-                    &pool_key,
-                    &self.token_a_key,
-                    &self.token_b_key,
-                    &dest_token_key,
-                    &self.admin_fee_a_key,
-                    pool_amount,
-                    minimum_amount,
+                    &self.admin_key,
+                    cap_a,
+                    cap_b,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
-                    &mut self.pool_mint_account,
-                    &mut pool_account,
-                    &mut self.token_a_account,
-                    &mut self.token_b_account,
-                    &mut dest_token_account,
-                    &mut self.admin_fee_a_account,
-                    &mut Account::default(),
-                    &mut clock_account(ZERO_TS),
+                    &mut self.admin_account,
                 ],
             )
         }
 
-        /** Admin functions **/
-
-        pub fn ramp_a(
-            &mut self,
-            target_amp: u64,
-            current_ts: i64,
-            stop_ramp_ts: i64,
-        ) -> ProgramResult {
+        pub fn set_price_bound(&mut self, price_bound: u64) -> ProgramResult {
             do_process_instruction(
-                ramp_a(
+                set_price_bound(
                     &SWAP_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
                     &self.admin_key,
-                    target_amp,
-                    stop_ramp_ts,
+                    price_bound,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
                     &mut self.admin_account,
-                    &mut clock_account(current_ts),
                 ],
             )
         }
 
-        pub fn stop_ramp_a(&mut self, current_ts: i64) -> ProgramResult {
+        #[allow(clippy::too_many_arguments)]
+        pub fn initialize_farm(
+            &mut self,
+            farm_account: &mut Account,
+            stake_vault_key: &Pubkey,
+            stake_vault_account: &mut Account,
+            reward_vault_key: &Pubkey,
+            reward_vault_account: &mut Account,
+            emission_per_slot: u64,
+            slot: Slot,
+        ) -> ProgramResult {
+            let (farm_key, nonce) = find_farm_address(&SWAP_PROGRAM_ID, &self.pool_mint_key);
             do_process_instruction(
-                stop_ramp_a(
+                initialize_farm(
                     &SWAP_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
                     &self.admin_key,
+                    &farm_key,
+                    stake_vault_key,
+                    reward_vault_key,
+                    emission_per_slot,
+                    nonce,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
                     &mut self.admin_account,
-                    &mut clock_account(current_ts),
+                    farm_account,
+                    stake_vault_account,
+                    reward_vault_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut clock_account_at_slot(self.current_ts, slot),
                 ],
             )
         }
 
-        pub fn pause(&mut self) -> ProgramResult {
+        #[allow(clippy::too_many_arguments)]
+        pub fn stake(
+            &mut self,
+            owner_key: &Pubkey,
+            owner_account: &mut Account,
+            farm_account: &mut Account,
+            source_key: &Pubkey,
+            mut source_account: &mut Account,
+            stake_vault_account: &mut Account,
+            reward_vault_account: &mut Account,
+            stake_account: &mut Account,
+            amount: u64,
+            slot: Slot,
+        ) -> ProgramResult {
+            let farm = Farm::unpack(&farm_account.data).unwrap();
+            let (farm_key, _nonce) = find_farm_address(&SWAP_PROGRAM_ID, &self.pool_mint_key);
+            let (stake_account_key, nonce) =
+                find_stake_account_address(&SWAP_PROGRAM_ID, &farm_key, owner_key);
+
+            // approve moving from the owner source account
             do_process_instruction(
-                pause(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    source_key,
+                    &self.authority_key,
+                    owner_key,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            do_process_instruction(
+                stake(
                     &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
-                    &self.admin_key,
+                    &farm_key,
+                    owner_key,
+                    source_key,
+                    &farm.stake_vault,
+                    &farm.reward_vault,
+                    &stake_account_key,
+                    amount,
+                    nonce,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
-                    &mut self.admin_account,
+                    farm_account,
+                    owner_account,
+                    source_account,
+                    stake_vault_account,
+                    reward_vault_account,
+                    stake_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut clock_account_at_slot(self.current_ts, slot),
+                    &mut Account::default(),
                 ],
             )
         }
 
-        pub fn unpause(&mut self) -> ProgramResult {
+        #[allow(clippy::too_many_arguments)]
+        pub fn unstake(
+            &mut self,
+            owner_key: &Pubkey,
+            farm_account: &mut Account,
+            stake_account: &mut Account,
+            stake_vault_account: &mut Account,
+            reward_vault_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            amount: u64,
+            slot: Slot,
+        ) -> ProgramResult {
+            let farm = Farm::unpack(&farm_account.data).unwrap();
+            let (farm_key, _nonce) = find_farm_address(&SWAP_PROGRAM_ID, &self.pool_mint_key);
+            let (stake_account_key, _nonce) =
+                find_stake_account_address(&SWAP_PROGRAM_ID, &farm_key, owner_key);
             do_process_instruction(
-                unpause(
+                unstake(
                     &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
-                    &self.admin_key,
+                    &farm_key,
+                    owner_key,
+                    &stake_account_key,
+                    &farm.stake_vault,
+                    &farm.reward_vault,
+                    destination_key,
+                    amount,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
-                    &mut self.admin_account,
+                    farm_account,
+                    &mut Account::default(),
+                    stake_account,
+                    stake_vault_account,
+                    reward_vault_account,
+                    destination_account,
+                    &mut clock_account_at_slot(self.current_ts, slot),
+                    &mut Account::default(),
                 ],
             )
         }
 
-        pub fn set_admin_fee_account(
+        #[allow(clippy::too_many_arguments)]
+        pub fn harvest_rewards(
             &mut self,
-            new_admin_fee_key: &Pubkey,
-            new_admin_fee_account: &Account,
+            owner_key: &Pubkey,
+            farm_account: &mut Account,
+            stake_account: &mut Account,
+            reward_vault_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            slot: Slot,
         ) -> ProgramResult {
+            let farm = Farm::unpack(&farm_account.data).unwrap();
+            let (farm_key, _nonce) = find_farm_address(&SWAP_PROGRAM_ID, &self.pool_mint_key);
+            let (stake_account_key, _nonce) =
+                find_stake_account_address(&SWAP_PROGRAM_ID, &farm_key, owner_key);
             do_process_instruction(
-                set_fee_account(
+                harvest_rewards(
                     &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
-                    &self.admin_key,
-                    new_admin_fee_key,
+                    &farm_key,
+                    owner_key,
+                    &stake_account_key,
+                    &farm.reward_vault,
+                    destination_key,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
-                    &mut self.admin_account,
-                    &mut new_admin_fee_account.clone(),
+                    farm_account,
+                    &mut Account::default(),
+                    stake_account,
+                    reward_vault_account,
+                    destination_account,
+                    &mut clock_account_at_slot(self.current_ts, slot),
+                    &mut Account::default(),
                 ],
             )
         }
 
-        pub fn apply_new_admin(&mut self, current_ts: i64) -> ProgramResult {
+        #[allow(clippy::too_many_arguments)]
+        pub fn bid_order_flow(
+            &mut self,
+            bidder_key: &Pubkey,
+            source_key: &Pubkey,
+            mut source_account: &mut Account,
+            refund_key: &Pubkey,
+            refund_account: &mut Account,
+            amount: u64,
+            slot: Slot,
+        ) -> ProgramResult {
+            let swap_info = SwapInfo::unpack(&self.swap_account.data).unwrap();
+
+            // approve moving from the bidder source account
             do_process_instruction(
-                apply_new_admin(
+                approve(
+                    &TOKEN_PROGRAM_ID,
+                    source_key,
+                    &self.authority_key,
+                    bidder_key,
+                    &[],
+                    amount,
+                )
+                .unwrap(),
+                vec![
+                    &mut source_account,
+                    &mut Account::default(),
+                    &mut Account::default(),
+                ],
+            )
+            .unwrap();
+
+            let bid_token_account = if swap_info.order_flow_bid_token == self.token_a_key {
+                &mut self.token_a_account
+            } else {
+                &mut self.token_b_account
+            };
+            do_process_instruction(
+                bid_order_flow(
                     &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
-                    &self.admin_key,
+                    source_key,
+                    &swap_info.order_flow_bid_token,
+                    refund_key,
+                    amount,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
-                    &mut self.admin_account,
-                    &mut clock_account(current_ts),
+                    source_account,
+                    bid_token_account,
+                    refund_account,
+                    &mut Account::default(),
+                    &mut clock_account_at_slot(ZERO_TS, slot),
                 ],
             )
         }
 
-        pub fn commit_new_admin(
+        #[allow(clippy::too_many_arguments)]
+        pub fn commit_reflection_root(
             &mut self,
-            new_admin_key: &Pubkey,
-            current_ts: i64,
+            distributor_key: &Pubkey,
+            distributor_account: &mut Account,
+            distribution_account: &mut Account,
+            vault_key: &Pubkey,
+            vault_account: &mut Account,
+            merkle_root: [u8; 32],
+            total_amount: u64,
         ) -> ProgramResult {
+            let epoch = SwapInfo::unpack(&self.swap_account.data)
+                .unwrap()
+                .reflection_epoch;
+            let (distribution_key, nonce) =
+                find_reflection_distribution_address(&SWAP_PROGRAM_ID, &self.swap_key, epoch);
             do_process_instruction(
-                commit_new_admin(
+                commit_reflection_root(
                     &SWAP_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
-                    &self.admin_key,
-                    new_admin_key,
+                    distributor_key,
+                    &distribution_key,
+                    vault_key,
+                    merkle_root,
+                    total_amount,
+                    nonce,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
-                    &mut self.admin_account,
+                    distributor_account,
+                    distribution_account,
+                    vault_account,
                     &mut Account::default(),
-                    &mut clock_account(current_ts),
+                    &mut rent_account(),
                 ],
             )
         }
 
-        pub fn set_new_fees(&mut self, new_fees: Fees) -> ProgramResult {
+        #[allow(clippy::too_many_arguments)]
+        pub fn claim_reflection_with_proof(
+            &mut self,
+            distribution_key: &Pubkey,
+            distribution_account: &mut Account,
+            vault_key: &Pubkey,
+            vault_account: &mut Account,
+            claimant_key: &Pubkey,
+            claimant_account: &mut Account,
+            claim_account: &mut Account,
+            destination_key: &Pubkey,
+            destination_account: &mut Account,
+            index: u64,
+            amount: u64,
+            proof: Vec<[u8; 32]>,
+        ) -> ProgramResult {
+            let (claim_key, nonce) =
+                find_reflection_claim_address(&SWAP_PROGRAM_ID, distribution_key, index);
             do_process_instruction(
-                set_new_fees(
+                claim_reflection_with_proof(
                     &SWAP_PROGRAM_ID,
+                    &TOKEN_PROGRAM_ID,
                     &self.swap_key,
                     &self.authority_key,
-                    &self.admin_key,
-                    new_fees,
+                    distribution_key,
+                    vault_key,
+                    claimant_key,
+                    &claim_key,
+                    destination_key,
+                    index,
+                    amount,
+                    nonce,
+                    proof,
                 )
                 .unwrap(),
                 vec![
                     &mut self.swap_account,
                     &mut Account::default(),
-                    &mut self.admin_account,
+                    distribution_account,
+                    vault_account,
+                    claimant_account,
+                    claim_account,
+                    destination_account,
+                    &mut Account::default(),
+                    &mut rent_account(),
+                    &mut Account::default(),
                 ],
             )
         }
@@ -784,6 +5845,13 @@ pub mod test_utils {
         ) -> ProgramResult {
             msg!("TestSyscallStubs::sol_invoke_signed()");
 
+            if instruction.program_id == system_program::id() {
+                return process_system_instruction(instruction, account_infos);
+            }
+            if instruction.program_id == metadata::id() {
+                return process_metadata_instruction(instruction, account_infos, signers_seeds);
+            }
+
             let mut new_account_infos = vec![];
 
             // mimic check for token program in accounts
@@ -813,6 +5881,77 @@ pub mod test_utils {
                 &instruction.data,
             )
         }
+
+        /// Clocks are passed to instructions as sysvar accounts, `Clock::get` only
+        /// sees the default clock.
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe { *(var_addr as *mut Clock) = Clock::default() };
+            solana_program::entrypoint::SUCCESS
+        }
+    }
+
+    /// Emulates the system program `CreateAccount` instruction. The new account data is
+    /// expected to be allocated with the requested space beforehand.
+    /// The metadata program is not available to tests, its update authority check
+    /// is mimicked and the instruction data written to the metadata account.
+    fn process_metadata_instruction(
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let update_authority_key = instruction.accounts[1].pubkey;
+        let is_signed = signers_seeds.iter().any(|seeds| {
+            Pubkey::create_program_address(seeds, &SWAP_PROGRAM_ID) == Ok(update_authority_key)
+        });
+        if !is_signed {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let metadata_info = account_infos
+            .iter()
+            .find(|account_info| *account_info.key == instruction.accounts[0].pubkey)
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let mut data = metadata_info.data.borrow_mut();
+        data.get_mut(..instruction.data.len())
+            .ok_or(ProgramError::AccountDataTooSmall)?
+            .copy_from_slice(&instruction.data);
+        Ok(())
+    }
+
+    fn process_system_instruction(
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+    ) -> ProgramResult {
+        let find_account_info = |key: &Pubkey| {
+            account_infos
+                .iter()
+                .find(|account_info| account_info.key == key)
+                .ok_or(ProgramError::NotEnoughAccountKeys)
+        };
+        let system_instruction =
+            limited_deserialize(&instruction.data, instruction.data.len() as u64)
+                .or(Err(ProgramError::InvalidInstructionData))?;
+        match system_instruction {
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => {
+                let from_info = find_account_info(&instruction.accounts[0].pubkey)?;
+                let to_info = find_account_info(&instruction.accounts[1].pubkey)?;
+                if to_info.lamports() != 0 || to_info.data_len() as u64 != space {
+                    return Err(ProgramError::AccountAlreadyInitialized);
+                }
+                let from_lamports = from_info
+                    .lamports()
+                    .checked_sub(lamports)
+                    .ok_or(ProgramError::InsufficientFunds)?;
+                **from_info.lamports.borrow_mut() = from_lamports;
+                **to_info.lamports.borrow_mut() = lamports;
+                to_info.assign(&owner);
+                Ok(())
+            }
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
     }
 
     fn test_syscall_stubs() {
@@ -840,6 +5979,9 @@ pub mod test_utils {
             .map(|(account_meta, account)| (&account_meta.pubkey, account_meta.is_signer, account))
             .collect::<Vec<_>>();
         let mut account_infos = create_is_signer_account_infos(&mut meta);
+        for (account_info, account_meta) in account_infos.iter_mut().zip(&instruction.accounts) {
+            account_info.is_writable = account_meta.is_writable;
+        }
         let res = if instruction.program_id == SWAP_PROGRAM_ID {
             Processor::process(&instruction.program_id, &account_infos, &instruction.data)
         } else {
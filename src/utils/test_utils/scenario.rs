@@ -0,0 +1,761 @@
+//! Economic scenarios, scripted in JSON and run against a fresh swap.
+//!
+//! A scenario names its users and their starting balances, then lists steps run
+//! in order. Actions may expect a `SwapError` by name and assertions compare
+//! exact token amounts:
+//!
+//! ```json
+//! {
+//!     "description": "Bob swaps against Alice's liquidity",
+//!     "amp_factor": 100,
+//!     "token_a_amount": 1000000,
+//!     "token_b_amount": 1000000,
+//!     "users": {
+//!         "alice": { "token_a": 500000, "token_b": 500000 },
+//!         "bob": { "token_a": 10000 }
+//!     },
+//!     "steps": [
+//!         { "deposit": { "user": "alice", "token_a": 500000, "token_b": 500000 } },
+//!         { "swap": { "user": "bob", "from": "a", "amount_in": 10000 } },
+//!         { "expect_reserves": { "token_a": 1510000, "token_b": 1490552 } },
+//!         { "swap": { "user": "bob", "from": "a", "amount_in": 1, "error": "Custom(1)" } }
+//!     ]
+//! }
+//! ```
+//!
+//! Token program errors are matched by code, e.g. `Custom(1)` for insufficient funds.
+//! `fees` defaults to `DEFAULT_TEST_FEES`; any fee left out of a `fees` object is zero.
+
+use super::{pubkey_rand, FeeAccounts, SwapAccountInfo, DEFAULT_TEST_FEES};
+use crate::{error::SwapError, fees::Fees};
+use num_traits::FromPrimitive;
+use serde::Deserialize;
+use solana_program::{
+    entrypoint::ProgramResult, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
+};
+use solana_sdk::account::Account;
+use spl_token::state::{Account as SplAccount, Mint as SplMint};
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// A scripted scenario
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// What the scenario covers
+    pub description: String,
+    /// Amplification coefficient of the swap
+    pub amp_factor: u64,
+    /// Token A the swap is initialized with
+    pub token_a_amount: u64,
+    /// Token B the swap is initialized with
+    pub token_b_amount: u64,
+    /// Swap fees, `DEFAULT_TEST_FEES` if left out
+    #[serde(default)]
+    pub fees: Option<ScenarioFees>,
+    /// Users by name
+    #[serde(default)]
+    pub users: BTreeMap<String, ScenarioUser>,
+    /// Steps run in order
+    pub steps: Vec<Step>,
+}
+
+/// Swap fees of a scenario, zero unless set
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScenarioFees {
+    pub admin_trade_fee_numerator: u64,
+    pub admin_trade_fee_denominator: u64,
+    pub admin_withdraw_fee_numerator: u64,
+    pub admin_withdraw_fee_denominator: u64,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub withdraw_fee_numerator: u64,
+    pub withdraw_fee_denominator: u64,
+    pub reflection_fee_numerator: u64,
+    pub reflection_fee_denominator: u64,
+    pub buyback_fee_numerator: u64,
+    pub buyback_fee_denominator: u64,
+    pub marketing_fee_numerator: u64,
+    pub marketing_fee_denominator: u64,
+    pub developer_fee_numerator: u64,
+    pub developer_fee_denominator: u64,
+    pub admin_fee_in_pool_tokens: bool,
+    pub fee_on_input: bool,
+    pub imbalance_fee_numerator: u64,
+    pub imbalance_fee_denominator: u64,
+    pub imbalance_threshold_bps: u64,
+    pub host_fee_numerator: u64,
+    pub host_fee_denominator: u64,
+}
+
+impl Default for ScenarioFees {
+    fn default() -> Self {
+        ScenarioFees {
+            admin_trade_fee_numerator: 0,
+            admin_trade_fee_denominator: 1,
+            admin_withdraw_fee_numerator: 0,
+            admin_withdraw_fee_denominator: 1,
+            trade_fee_numerator: 0,
+            trade_fee_denominator: 1,
+            withdraw_fee_numerator: 0,
+            withdraw_fee_denominator: 1,
+            reflection_fee_numerator: 0,
+            reflection_fee_denominator: 1,
+            buyback_fee_numerator: 0,
+            buyback_fee_denominator: 1,
+            marketing_fee_numerator: 0,
+            marketing_fee_denominator: 1,
+            developer_fee_numerator: 0,
+            developer_fee_denominator: 1,
+            admin_fee_in_pool_tokens: false,
+            fee_on_input: false,
+            imbalance_fee_numerator: 0,
+            imbalance_fee_denominator: 1,
+            imbalance_threshold_bps: 0,
+            host_fee_numerator: 0,
+            host_fee_denominator: 1,
+        }
+    }
+}
+
+impl From<&ScenarioFees> for Fees {
+    fn from(fees: &ScenarioFees) -> Self {
+        Fees {
+            admin_trade_fee_numerator: fees.admin_trade_fee_numerator,
+            admin_trade_fee_denominator: fees.admin_trade_fee_denominator,
+            admin_withdraw_fee_numerator: fees.admin_withdraw_fee_numerator,
+            admin_withdraw_fee_denominator: fees.admin_withdraw_fee_denominator,
+            trade_fee_numerator: fees.trade_fee_numerator,
+            trade_fee_denominator: fees.trade_fee_denominator,
+            withdraw_fee_numerator: fees.withdraw_fee_numerator,
+            withdraw_fee_denominator: fees.withdraw_fee_denominator,
+            reflection_fee_numerator: fees.reflection_fee_numerator,
+            reflection_fee_denominator: fees.reflection_fee_denominator,
+            buyback_fee_numerator: fees.buyback_fee_numerator,
+            buyback_fee_denominator: fees.buyback_fee_denominator,
+            marketing_fee_numerator: fees.marketing_fee_numerator,
+            marketing_fee_denominator: fees.marketing_fee_denominator,
+            developer_fee_numerator: fees.developer_fee_numerator,
+            developer_fee_denominator: fees.developer_fee_denominator,
+            admin_fee_in_pool_tokens: fees.admin_fee_in_pool_tokens,
+            fee_on_input: fees.fee_on_input,
+            imbalance_fee_numerator: fees.imbalance_fee_numerator,
+            imbalance_fee_denominator: fees.imbalance_fee_denominator,
+            imbalance_threshold_bps: fees.imbalance_threshold_bps,
+            host_fee_numerator: fees.host_fee_numerator,
+            host_fee_denominator: fees.host_fee_denominator,
+        }
+    }
+}
+
+/// Starting balances of a user
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScenarioUser {
+    pub token_a: u64,
+    pub token_b: u64,
+}
+
+/// Token held by a user
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Token {
+    A,
+    B,
+    Pool,
+}
+
+/// A step of a scenario. Actions fail the scenario unless they succeed, or fail
+/// with the `SwapError` named by `error`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Step {
+    Deposit {
+        user: String,
+        token_a: u64,
+        token_b: u64,
+        #[serde(default)]
+        min_pool_tokens: u64,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    Swap {
+        user: String,
+        from: Token,
+        amount_in: u64,
+        #[serde(default)]
+        min_amount_out: u64,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    Withdraw {
+        user: String,
+        pool_tokens: u64,
+        #[serde(default)]
+        min_token_a: u64,
+        #[serde(default)]
+        min_token_b: u64,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    WithdrawOne {
+        user: String,
+        to: Token,
+        pool_tokens: u64,
+        #[serde(default)]
+        min_amount_out: u64,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    Pause {
+        #[serde(default)]
+        error: Option<String>,
+    },
+    Unpause {
+        #[serde(default)]
+        error: Option<String>,
+    },
+    RampA {
+        target_amp: u64,
+        current_ts: i64,
+        stop_ramp_ts: i64,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    StopRampA {
+        current_ts: i64,
+        #[serde(default)]
+        error: Option<String>,
+    },
+    ExpectReserves {
+        token_a: u64,
+        token_b: u64,
+    },
+    ExpectBalance {
+        user: String,
+        token: Token,
+        amount: u64,
+    },
+    ExpectPoolSupply {
+        amount: u64,
+    },
+}
+
+impl Step {
+    /// Error an action is expected to fail with, `None` for assertions
+    fn expected_error(&self) -> Option<&Option<String>> {
+        match self {
+            Step::Deposit { error, .. }
+            | Step::Swap { error, .. }
+            | Step::Withdraw { error, .. }
+            | Step::WithdrawOne { error, .. }
+            | Step::Pause { error }
+            | Step::Unpause { error }
+            | Step::RampA { error, .. }
+            | Step::StopRampA { error, .. } => Some(error),
+            Step::ExpectReserves { .. }
+            | Step::ExpectBalance { .. }
+            | Step::ExpectPoolSupply { .. } => None,
+        }
+    }
+}
+
+struct UserAccounts {
+    key: Pubkey,
+    token_a_key: Pubkey,
+    token_a_account: Account,
+    token_b_key: Pubkey,
+    token_b_account: Account,
+    pool_key: Pubkey,
+    pool_account: Account,
+}
+
+impl UserAccounts {
+    fn balance(&self, token: Token) -> u64 {
+        let account = match token {
+            Token::A => &self.token_a_account,
+            Token::B => &self.token_b_account,
+            Token::Pool => &self.pool_account,
+        };
+        token_amount(account)
+    }
+}
+
+fn token_amount(account: &Account) -> u64 {
+    SplAccount::unpack(&account.data).unwrap().amount
+}
+
+/// Custom errors are named after their `SwapError` variant, but errors of the token
+/// program share their codes, so `Custom(<code>)` matches as well.
+fn mint_supply(account: &Account) -> u64 {
+    SplMint::unpack(&account.data).unwrap().supply
+}
+
+fn check_result(step: usize, result: ProgramResult, error: &Option<String>) -> Result<(), String> {
+    let names = match result {
+        Ok(()) => vec![],
+        Err(ProgramError::Custom(code)) => SwapError::from_u32(code)
+            .map(|e| format!("{:?}", e))
+            .into_iter()
+            .chain(Some(format!("Custom({})", code)))
+            .collect(),
+        Err(e) => vec![format!("{:?}", e)],
+    };
+    let matches = match error {
+        Some(error) => names.contains(error),
+        None => names.is_empty(),
+    };
+    if !matches {
+        return Err(format!(
+            "step {}: expected {}, got {}",
+            step,
+            error.as_deref().unwrap_or("success"),
+            if names.is_empty() {
+                "success".to_string()
+            } else {
+                names.join(" or ")
+            },
+        ));
+    }
+    Ok(())
+}
+
+fn check_amount(step: usize, what: &str, expected: u64, actual: u64) -> Result<(), String> {
+    if expected != actual {
+        return Err(format!(
+            "step {}: expected {} {}, got {}",
+            step, what, expected, actual
+        ));
+    }
+    Ok(())
+}
+
+impl Scenario {
+    /// Parses a scenario script
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+
+    /// Runs every step against a fresh swap, stopping at the first one that does
+    /// not go as scripted or breaks an invariant of `ScenarioState::check_invariants`
+    pub fn run(&self) -> Result<(), String> {
+        let mut state = ScenarioState::new(self)?;
+        for (step, s) in self.steps.iter().enumerate() {
+            match s.expected_error() {
+                Some(error) => check_result(step, state.act(step, s)?, error)?,
+                None => state.check(step, s)?,
+            }
+            state.check_invariants(step)?;
+        }
+        Ok(())
+    }
+}
+
+/// Swap and user accounts of a running scenario
+pub struct ScenarioState {
+    swap: SwapAccountInfo,
+    users: BTreeMap<String, UserAccounts>,
+}
+
+impl ScenarioState {
+    /// Initializes the swap of a scenario and funds its users
+    pub fn new(scenario: &Scenario) -> Result<Self, String> {
+        let mint_owner = pubkey_rand();
+        let fees = scenario.fees.as_ref().map_or(DEFAULT_TEST_FEES, Fees::from);
+        let mut swap = SwapAccountInfo::new(
+            &mint_owner,
+            scenario.amp_factor,
+            scenario.token_a_amount,
+            scenario.token_b_amount,
+            fees,
+        );
+        swap.initialize_swap()
+            .map_err(|e| format!("initialize: {:?}", e))?;
+
+        let mut users = BTreeMap::new();
+        for (name, user) in &scenario.users {
+            let key = pubkey_rand();
+            let (
+                token_a_key,
+                token_a_account,
+                token_b_key,
+                token_b_account,
+                pool_key,
+                pool_account,
+            ) = swap.setup_token_accounts(&mint_owner, &key, user.token_a, user.token_b, 0);
+            users.insert(
+                name.clone(),
+                UserAccounts {
+                    key,
+                    token_a_key,
+                    token_a_account,
+                    token_b_key,
+                    token_b_account,
+                    pool_key,
+                    pool_account,
+                },
+            );
+        }
+        Ok(ScenarioState { swap, users })
+    }
+
+    fn take_user(&mut self, step: usize, name: &str) -> Result<UserAccounts, String> {
+        self.users
+            .remove(name)
+            .ok_or_else(|| format!("step {}: unknown user {}", step, name))
+    }
+
+    /// Runs an action step, returning the program result. Errors only if the
+    /// step itself is malformed.
+    pub fn act(&mut self, step: usize, s: &Step) -> Result<ProgramResult, String> {
+        let (token_a_key, token_b_key) = (self.swap.token_a_key, self.swap.token_b_key);
+        let (name, mut u) = match s {
+            Step::Deposit { user, .. }
+            | Step::Swap { user, .. }
+            | Step::Withdraw { user, .. }
+            | Step::WithdrawOne { user, .. } => (user, self.take_user(step, user)?),
+            Step::Pause { .. } => return Ok(self.swap.pause()),
+            Step::Unpause { .. } => return Ok(self.swap.unpause()),
+            Step::RampA {
+                target_amp,
+                current_ts,
+                stop_ramp_ts,
+                ..
+            } => return Ok(self.swap.ramp_a(*target_amp, *current_ts, *stop_ramp_ts)),
+            Step::StopRampA { current_ts, .. } => return Ok(self.swap.stop_ramp_a(*current_ts)),
+            _ => return Err(format!("step {}: not an action", step)),
+        };
+        let result = match s {
+            Step::Deposit {
+                token_a,
+                token_b,
+                min_pool_tokens,
+                ..
+            } => self.swap.deposit(
+                &u.key,
+                &u.token_a_key,
+                &mut u.token_a_account,
+                &u.token_b_key,
+                &mut u.token_b_account,
+                &u.pool_key,
+                &mut u.pool_account,
+                *token_a,
+                *token_b,
+                *min_pool_tokens,
+            ),
+            Step::Swap {
+                from: Token::A,
+                amount_in,
+                min_amount_out,
+                ..
+            } => self.swap.swap(
+                &u.key,
+                &u.token_a_key,
+                &mut u.token_a_account,
+                &token_a_key,
+                &token_b_key,
+                &u.token_b_key,
+                &mut u.token_b_account,
+                *amount_in,
+                *min_amount_out,
+            ),
+            Step::Swap {
+                from: Token::B,
+                amount_in,
+                min_amount_out,
+                ..
+            } => self.swap.swap(
+                &u.key,
+                &u.token_b_key,
+                &mut u.token_b_account,
+                &token_b_key,
+                &token_a_key,
+                &u.token_a_key,
+                &mut u.token_a_account,
+                *amount_in,
+                *min_amount_out,
+            ),
+            Step::Withdraw {
+                pool_tokens,
+                min_token_a,
+                min_token_b,
+                ..
+            } => self.swap.withdraw(
+                &u.key,
+                &u.pool_key,
+                &mut u.pool_account,
+                &u.token_a_key,
+                &mut u.token_a_account,
+                &u.token_b_key,
+                &mut u.token_b_account,
+                *pool_tokens,
+                *min_token_a,
+                *min_token_b,
+            ),
+            Step::WithdrawOne {
+                to: Token::A,
+                pool_tokens,
+                min_amount_out,
+                ..
+            } => self.swap.withdraw_one_in(
+                &u.key,
+                &u.pool_key,
+                &mut u.pool_account,
+                &u.token_a_key,
+                &mut u.token_a_account,
+                false,
+                *pool_tokens,
+                *min_amount_out,
+            ),
+            Step::WithdrawOne {
+                to: Token::B,
+                pool_tokens,
+                min_amount_out,
+                ..
+            } => self.swap.withdraw_one_in(
+                &u.key,
+                &u.pool_key,
+                &mut u.pool_account,
+                &u.token_b_key,
+                &mut u.token_b_account,
+                true,
+                *pool_tokens,
+                *min_amount_out,
+            ),
+            _ => {
+                self.users.insert(name.clone(), u);
+                return Err(format!(
+                    "step {}: pool tokens are neither swapped nor withdrawn into",
+                    step
+                ));
+            }
+        };
+        self.users.insert(name.clone(), u);
+        Ok(result)
+    }
+
+    /// Checks an assertion step
+    pub fn check(&self, step: usize, s: &Step) -> Result<(), String> {
+        match s {
+            Step::ExpectReserves { token_a, token_b } => {
+                check_amount(
+                    step,
+                    "token A reserve",
+                    *token_a,
+                    token_amount(&self.swap.token_a_account),
+                )?;
+                check_amount(
+                    step,
+                    "token B reserve",
+                    *token_b,
+                    token_amount(&self.swap.token_b_account),
+                )
+            }
+            Step::ExpectBalance {
+                user: name,
+                token,
+                amount,
+            } => {
+                let u = self
+                    .users
+                    .get(name)
+                    .ok_or_else(|| format!("step {}: unknown user {}", step, name))?;
+                check_amount(
+                    step,
+                    &format!("{} {:?} balance", name, token),
+                    *amount,
+                    u.balance(*token),
+                )
+            }
+            Step::ExpectPoolSupply { amount } => check_amount(
+                step,
+                "pool token supply",
+                *amount,
+                mint_supply(&self.swap.pool_mint_account),
+            ),
+            _ => Err(format!("step {}: not an assertion", step)),
+        }
+    }
+
+    /// Checks invariants holding after any step: every token minted is held by a
+    /// known account, so no fee goes missing, and reserves are never drained while
+    /// pool tokens are outstanding.
+    pub fn check_invariants(&self, step: usize) -> Result<(), String> {
+        let swap = &self.swap;
+        let fee_accounts = |fee_accounts: &FeeAccounts| {
+            [
+                &fee_accounts.reflection_account,
+                &fee_accounts.buyback_account,
+                &fee_accounts.marketing_account,
+                &fee_accounts.developer_account,
+            ]
+            .iter()
+            .map(|account| token_amount(account))
+            .sum::<u64>()
+        };
+        let held = |token: Token| self.users.values().map(|u| u.balance(token)).sum::<u64>();
+
+        let token_a = token_amount(&swap.token_a_account)
+            + token_amount(&swap.admin_fee_a_account)
+            + fee_accounts(&swap.fee_accounts_a)
+            + held(Token::A);
+        check_amount(
+            step,
+            "token A held",
+            mint_supply(&swap.token_a_mint_account),
+            token_a,
+        )?;
+        let token_b = token_amount(&swap.token_b_account)
+            + token_amount(&swap.admin_fee_b_account)
+            + fee_accounts(&swap.fee_accounts_b)
+            + held(Token::B);
+        check_amount(
+            step,
+            "token B held",
+            mint_supply(&swap.token_b_mint_account),
+            token_b,
+        )?;
+        let pool_supply = mint_supply(&swap.pool_mint_account);
+        let pool_tokens = token_amount(&swap.pool_token_account)
+            + token_amount(&swap.admin_pool_token_account)
+            + held(Token::Pool);
+        check_amount(step, "pool tokens held", pool_supply, pool_tokens)?;
+
+        if pool_supply > 0
+            && (token_amount(&swap.token_a_account) == 0
+                || token_amount(&swap.token_b_account) == 0)
+        {
+            return Err(format!(
+                "step {}: reserves drained with {} pool tokens outstanding",
+                step, pool_supply
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::MIN_RAMP_DURATION;
+    use proptest::{collection::vec, prelude::*};
+
+    const FUZZ_USERS: [&str; 2] = ["alice", "bob"];
+    const FUZZ_BALANCE: u64 = 1_000_000;
+
+    fn user() -> impl Strategy<Value = String> {
+        prop::sample::select(&FUZZ_USERS[..]).prop_map(String::from)
+    }
+
+    fn token() -> impl Strategy<Value = Token> {
+        prop_oneof![Just(Token::A), Just(Token::B)]
+    }
+
+    /// Any action, valid or not, with amounts up to a bit above what users and the
+    /// pool hold
+    fn action() -> impl Strategy<Value = Step> {
+        let amount = 0..=FUZZ_BALANCE * 6 / 5;
+        let pool_tokens = 0..=FUZZ_BALANCE * 3;
+        let ts = 0..=MIN_RAMP_DURATION * 2;
+        prop_oneof![
+            4 => (user(), amount.clone(), amount.clone()).prop_map(|(user, token_a, token_b)| {
+                Step::Deposit {
+                    user,
+                    token_a,
+                    token_b,
+                    min_pool_tokens: 0,
+                    error: None,
+                }
+            }),
+            6 => (user(), token(), amount).prop_map(|(user, from, amount_in)| Step::Swap {
+                user,
+                from,
+                amount_in,
+                min_amount_out: 0,
+                error: None,
+            }),
+            3 => (user(), pool_tokens.clone()).prop_map(|(user, pool_tokens)| Step::Withdraw {
+                user,
+                pool_tokens,
+                min_token_a: 0,
+                min_token_b: 0,
+                error: None,
+            }),
+            3 => (user(), token(), pool_tokens).prop_map(|(user, to, pool_tokens)| {
+                Step::WithdrawOne {
+                    user,
+                    to,
+                    pool_tokens,
+                    min_amount_out: 0,
+                    error: None,
+                }
+            }),
+            1 => Just(Step::Pause { error: None }),
+            2 => Just(Step::Unpause { error: None }),
+            1 => (1..=10_000u64, ts.clone(), ts.clone()).prop_map(
+                |(target_amp, current_ts, stop_ramp_ts)| Step::RampA {
+                    target_amp,
+                    current_ts,
+                    stop_ramp_ts,
+                    error: None,
+                }
+            ),
+            1 => ts.prop_map(|current_ts| Step::StopRampA {
+                current_ts,
+                error: None,
+            }),
+        ]
+    }
+
+    proptest! {
+        /// Random instruction sequences, failing or not, never break the invariants
+        #[test]
+        fn test_fuzz_instruction_sequences(steps in vec(action(), 1..40)) {
+            let scenario = Scenario {
+                description: "fuzz".to_string(),
+                amp_factor: 100,
+                token_a_amount: FUZZ_BALANCE,
+                token_b_amount: FUZZ_BALANCE,
+                fees: None,
+                users: FUZZ_USERS
+                    .iter()
+                    .map(|name| {
+                        let user = ScenarioUser {
+                            token_a: FUZZ_BALANCE,
+                            token_b: FUZZ_BALANCE,
+                        };
+                        (name.to_string(), user)
+                    })
+                    .collect(),
+                steps: vec![],
+            };
+            let mut state = ScenarioState::new(&scenario).unwrap();
+            for (step, s) in steps.iter().enumerate() {
+                state.act(step, s).unwrap().ok();
+                if let Err(e) = state.check_invariants(step) {
+                    prop_assert!(false, "{} after {:?}", e, &steps[..=step]);
+                }
+            }
+        }
+    }
+
+    /// Runs every scenario script of the `scenarios` directory
+    #[test]
+    fn test_scenarios() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenarios");
+        let mut paths = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect::<Vec<_>>();
+        paths.sort();
+        assert!(!paths.is_empty());
+        for path in paths {
+            let scenario = Scenario::from_json(&fs::read_to_string(&path).unwrap())
+                .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            if let Err(e) = scenario.run() {
+                panic!("{} ({}): {}", path.display(), scenario.description, e);
+            }
+        }
+    }
+}
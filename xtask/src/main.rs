@@ -0,0 +1,572 @@
+//! Development tasks, run with `cargo xtask <task>`
+
+use baby_punk_swap_math::params::{self, PoolParams, VolatilityClass};
+use sha2::{Digest, Sha256};
+use stable_swap::{
+    bn::U256,
+    curve::{ConstantProduct, PoolCurve, StableSwap, SwapCurve, TransferFee},
+    fees::{FeeTier, Fees},
+    layout::{ACCOUNTS, SEEDS},
+    solana_program::program_pack::Pack,
+    state::SwapInfo,
+};
+use std::{
+    env, fmt, fs,
+    hint::black_box,
+    path::{Path, PathBuf},
+    process::{self, Command},
+    time::Instant,
+};
+
+/// Pinned toolchain image the program is built in, so that anyone rebuilding a
+/// given commit gets the exact same artifact. Overridden by `BUILD_IMAGE`.
+const DEFAULT_BUILD_IMAGE: &str = "projectserum/build:v0.19.0";
+/// Program artifact, relative to the workspace root
+const PROGRAM_SO: &str = "target/deploy/stable_swap.so";
+/// Client constants rendered from the program account layouts, relative to the
+/// workspace root
+const CLIENT_LAYOUT: &str = "lib/client/src/account-layout.ts";
+
+const USAGE: &str = "\
+Usage: cargo xtask <task>
+Tasks:
+    build
+        Build the program deterministically and print its sha256
+    verify [--skip-build] [--url <cluster>] <address>
+        Compare a deployed program, or an upgrade buffer, with the deterministic
+        build and print a verification report
+    layout
+        Render the account sizes, field offsets and seeds of the program into the
+        client SDK
+    init-pool --auto <pegged|correlated|volatile> <decimals_a> <decimals_b>
+        Print the recommended parameters of a new pool of two tokens of the
+        volatility class and mints of the decimals, for `Initialize`,
+        `SetMinLpMint`, `SetDustThreshold`, `SetOracleGuard` and
+        `SetOracleStalePolicy`
+    bench [--iterations <n>]
+        Time the curve, fee and packing hot paths in host wall-clock nanoseconds
+        per call, not in compute units: a change faster on the host may cost as
+        many compute units or more. Run with
+        `cargo run --release --package xtask -- bench` for optimized numbers.
+        Compute units are measured on a BPF build with the `compute-units`
+        feature";
+
+/// Calls timed by `bench`
+const BENCHES: &[(&str, fn())] = &[
+    ("stable_swap_compute_d", bench_stable_swap_compute_d),
+    ("stable_swap_swap", bench_stable_swap_swap),
+    ("constant_product_swap", bench_constant_product_swap),
+    ("trade_fee", bench_trade_fee),
+    ("transfer_post_fee_amount", bench_transfer_post_fee_amount),
+    ("swap_info_unpack_pack", bench_swap_info_unpack_pack),
+];
+
+type Result<T> = std::result::Result<T, String>;
+
+/// Outcome of comparing a deployed program with the deterministic build
+#[derive(Clone, Debug, PartialEq)]
+struct VerificationReport {
+    /// Program or buffer address
+    address: String,
+    /// Cluster the program was dumped from
+    cluster: String,
+    /// Image the artifact was built in
+    build_image: String,
+    /// Artifact length in bytes
+    build_len: usize,
+    /// Artifact sha256
+    build_hash: String,
+    /// Sha256 of the deployed program, trimmed to the artifact length
+    deployed_hash: String,
+    /// Whether the deployed program is zero padded past the artifact length
+    zero_padded: bool,
+}
+
+impl VerificationReport {
+    fn is_verified(&self) -> bool {
+        self.build_hash == self.deployed_hash && self.zero_padded
+    }
+}
+
+impl fmt::Display for VerificationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Address:       {}", self.address)?;
+        writeln!(f, "Cluster:       {}", self.cluster)?;
+        writeln!(f, "Build image:   {}", self.build_image)?;
+        writeln!(f, "Build length:  {}", self.build_len)?;
+        writeln!(f, "Build hash:    {}", self.build_hash)?;
+        writeln!(f, "Deployed hash: {}", self.deployed_hash)?;
+        writeln!(f, "Zero padded:   {}", self.zero_padded)?;
+        write!(
+            f,
+            "Result:        {}",
+            if self.is_verified() {
+                "verified"
+            } else {
+                "MISMATCH"
+            }
+        )
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Program data accounts are padded with zeros up to their max length, so only
+/// the artifact length of the deployed bytes is hashed.
+fn compare(
+    address: &str,
+    cluster: &str,
+    build_image: &str,
+    build: &[u8],
+    deployed: &[u8],
+) -> VerificationReport {
+    let trimmed = &deployed[..build.len().min(deployed.len())];
+    VerificationReport {
+        address: address.to_string(),
+        cluster: cluster.to_string(),
+        build_image: build_image.to_string(),
+        build_len: build.len(),
+        build_hash: sha256_hex(build),
+        deployed_hash: sha256_hex(trimmed),
+        zero_padded: deployed.len() >= build.len()
+            && deployed[build.len()..].iter().all(|byte| *byte == 0),
+    }
+}
+
+fn workspace_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives in the workspace root")
+        .to_path_buf()
+}
+
+fn build_image() -> String {
+    env::var("BUILD_IMAGE").unwrap_or_else(|_| DEFAULT_BUILD_IMAGE.to_string())
+}
+
+fn run(command: &mut Command) -> Result<()> {
+    let status = command
+        .status()
+        .map_err(|e| format!("failed to run {:?}: {}", command, e))?;
+    if !status.success() {
+        return Err(format!("{:?} exited with {}", command, status));
+    }
+    Ok(())
+}
+
+fn read_artifact(root: &Path) -> Result<Vec<u8>> {
+    let path = root.join(PROGRAM_SO);
+    fs::read(&path).map_err(|e| format!("failed to read {}: {}", path.display(), e))
+}
+
+/// Builds the program in the pinned image, with the workspace mounted as is
+fn build(root: &Path) -> Result<Vec<u8>> {
+    run(Command::new("docker")
+        .arg("run")
+        .arg("--rm")
+        .arg("-v")
+        .arg(format!("{}:/workdir", root.display()))
+        .args(["-w", "/workdir"])
+        .arg(build_image())
+        .args(["cargo", "build-bpf"]))?;
+    read_artifact(root)
+}
+
+fn dump(address: &str, url: Option<&str>, root: &Path) -> Result<Vec<u8>> {
+    let path = root.join("target").join(format!("{}.dump.so", address));
+    let mut command = Command::new("solana");
+    if let Some(url) = url {
+        command.args(["--url", url]);
+    }
+    run(command.args(["program", "dump", address]).arg(&path))?;
+    let deployed = fs::read(&path).map_err(|e| format!("failed to read dump: {}", e))?;
+    let _ = fs::remove_file(&path);
+    Ok(deployed)
+}
+
+fn verify(args: &[String], root: &Path) -> Result<bool> {
+    let mut skip_build = false;
+    let mut url = None;
+    let mut address = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--skip-build" => skip_build = true,
+            "--url" | "-u" => {
+                url = Some(args.next().ok_or("--url expects a cluster")?.as_str());
+            }
+            _ if address.is_none() => address = Some(arg.as_str()),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    let address = address.ok_or("verify expects an address")?;
+
+    let build = if skip_build {
+        read_artifact(root)?
+    } else {
+        build(root)?
+    };
+    let deployed = dump(address, url, root)?;
+    let report = compare(
+        address,
+        url.unwrap_or("solana config default"),
+        &build_image(),
+        &build,
+        &deployed,
+    );
+    println!("{}", report);
+    Ok(report.is_verified())
+}
+
+/// `SNAKE_CASE` or `snake_case` names in camel case
+fn camel_case(name: &str) -> String {
+    let mut camel = String::with_capacity(name.len());
+    for (i, word) in name.split('_').filter(|word| !word.is_empty()).enumerate() {
+        let word = word.to_ascii_lowercase();
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            if i == 0 {
+                camel.push(first);
+            } else {
+                camel.push(first.to_ascii_uppercase());
+            }
+            camel.extend(chars);
+        }
+    }
+    camel
+}
+
+/// Client module of the account layouts and seeds
+fn render_layout() -> String {
+    let mut ts = String::from(
+        "// Generated by `cargo xtask layout` from the program account layouts, do not edit.\n",
+    );
+    for account in ACCOUNTS {
+        let name = account.name.to_ascii_uppercase();
+        ts.push_str(&format!(
+            "\nexport const {}_LEN = {};\n\n",
+            name, account.len
+        ));
+        ts.push_str(&format!("export const {}_OFFSETS = {{\n", name));
+        for (field, offset) in account.fields {
+            ts.push_str(&format!("  {}: {},\n", camel_case(field), offset));
+        }
+        ts.push_str("} as const;\n");
+    }
+    ts.push('\n');
+    for (name, seed) in SEEDS {
+        ts.push_str(&format!(
+            "export const {} = \"{}\";\n",
+            name,
+            String::from_utf8_lossy(seed)
+        ));
+    }
+    ts
+}
+
+/// Writes the client module of the account layouts
+fn layout(root: &Path) -> Result<()> {
+    let path = root.join(CLIENT_LAYOUT);
+    fs::write(&path, render_layout())
+        .map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+    println!("Wrote {}", CLIENT_LAYOUT);
+    Ok(())
+}
+
+/// Volatility class of its command line name
+fn volatility_class(name: &str) -> Result<VolatilityClass> {
+    match name {
+        "pegged" => Ok(VolatilityClass::Pegged),
+        "correlated" => Ok(VolatilityClass::Correlated),
+        "volatile" => Ok(VolatilityClass::Volatile),
+        _ => Err(format!("unknown volatility class {}", name)),
+    }
+}
+
+/// Recommended parameters of the `init-pool --auto` arguments
+fn recommend_pool_params(args: &[String]) -> Result<PoolParams> {
+    let (auto, args) = args.split_first().ok_or("init-pool expects --auto")?;
+    if auto != "--auto" {
+        return Err(format!(
+            "init-pool only recommends parameters, with --auto, got {}",
+            auto
+        ));
+    }
+    let (class, decimals_a, decimals_b) = match args {
+        [class, decimals_a, decimals_b] => (class, decimals_a, decimals_b),
+        _ => return Err("init-pool --auto expects a volatility class and two decimals".into()),
+    };
+    let decimals = |decimals: &str| {
+        decimals
+            .parse::<u8>()
+            .map_err(|_| format!("invalid decimals {}", decimals))
+    };
+    params::recommend(
+        volatility_class(class)?,
+        decimals(decimals_a)?,
+        decimals(decimals_b)?,
+    )
+    .ok_or_else(|| "no parameters for mints of different or too many decimals".into())
+}
+
+/// Instruction fields of `params`
+fn render_pool_params(params: &PoolParams) -> String {
+    let oracle_guard = &params.oracle_guard;
+    format!(
+        "Initialize:\n  \
+         curve_type:     {:?}\n  \
+         amp_factor:     {}\n  \
+         fee_tier:       {:?}\n\
+         SetMinLpMint:     {}\n\
+         SetDustThreshold: {}\n\
+         SetOracleGuard:\n  \
+         max_staleness_slots: {}\n  \
+         max_confidence_bps:  {}\n  \
+         max_deviation_bps:   {}\n\
+         SetOracleStalePolicy:\n  \
+         stale_policy:   {:?}\n  \
+         stale_band_bps: {}",
+        params.curve_type,
+        params.amp_factor,
+        params.fee_tier,
+        params.min_lp_mint,
+        params.dust_threshold,
+        oracle_guard.max_staleness_slots,
+        oracle_guard.max_confidence_bps,
+        oracle_guard.max_deviation_bps,
+        oracle_guard.stale_policy,
+        oracle_guard.stale_band_bps
+    )
+}
+
+fn bench_fees() -> Fees {
+    FeeTier::FiveBps.fees().unwrap()
+}
+
+fn bench_stable_swap() -> StableSwap {
+    StableSwap::new(100, 200, 1_500, 1_000, 2_000)
+}
+
+fn bench_stable_swap_compute_d() {
+    black_box(black_box(bench_stable_swap()).compute_d(
+        black_box(U256::from(1_000_000_000_000u64)),
+        black_box(U256::from(1_200_000_000_000u64)),
+    ));
+}
+
+fn bench_stable_swap_swap() {
+    let curve = PoolCurve::StableSwap(bench_stable_swap());
+    black_box(black_box(curve).swap_to_v2(
+        black_box(U256::from(10_000_000u64)),
+        black_box(U256::from(1_000_000_000_000u64)),
+        black_box(U256::from(1_200_000_000_000u64)),
+        &black_box(bench_fees()),
+    ));
+}
+
+fn bench_constant_product_swap() {
+    let curve = PoolCurve::ConstantProduct(ConstantProduct);
+    black_box(black_box(curve).swap_to_v2(
+        black_box(U256::from(10_000_000u64)),
+        black_box(U256::from(1_000_000_000_000u64)),
+        black_box(U256::from(1_200_000_000_000u64)),
+        &black_box(bench_fees()),
+    ));
+}
+
+fn bench_trade_fee() {
+    black_box(black_box(bench_fees()).trade_fee(black_box(U256::from(10_000_000u64))));
+}
+
+fn bench_transfer_post_fee_amount() {
+    let transfer_fee = TransferFee {
+        basis_points: 50,
+        maximum_fee: 5_000,
+    };
+    black_box(black_box(transfer_fee).post_fee_amount(black_box(10_000_000)));
+}
+
+fn bench_swap_info_unpack_pack() {
+    let mut data = [0u8; SwapInfo::LEN];
+    SwapInfo::pack(
+        SwapInfo {
+            is_initialized: true,
+            ..SwapInfo::default()
+        },
+        &mut data,
+    )
+    .unwrap();
+    let swap_info = SwapInfo::unpack(black_box(&data)).unwrap();
+    SwapInfo::pack(black_box(swap_info), &mut data).unwrap();
+    black_box(data);
+}
+
+/// Prints the time each of `BENCHES` takes per call
+fn bench(args: &[String]) -> Result<()> {
+    let mut iterations: u32 = 100_000;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--iterations" | "-n" => {
+                iterations = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .filter(|n| *n > 0)
+                    .ok_or("--iterations expects a positive number")?;
+            }
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    for (name, call) in BENCHES {
+        // warm up
+        for _ in 0..iterations / 10 {
+            call();
+        }
+        let start = Instant::now();
+        for _ in 0..iterations {
+            call();
+        }
+        let nanos = start.elapsed().as_nanos() as f64 / f64::from(iterations);
+        println!("{:<28}{:>10.1} ns", name, nanos);
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let root = workspace_root();
+    let result = match args.first().map(String::as_str) {
+        Some("build") => build(&root).map(|build| {
+            println!("Build sha256: {}", sha256_hex(&build));
+            true
+        }),
+        Some("verify") => verify(&args[1..], &root),
+        Some("layout") => layout(&root).map(|()| true),
+        Some("init-pool") => recommend_pool_params(&args[1..]).map(|params| {
+            println!("{}", render_pool_params(&params));
+            true
+        }),
+        Some("bench") => bench(&args[1..]).map(|()| true),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(1);
+        }
+    };
+    match result {
+        Ok(true) => {}
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b""),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_camel_case() {
+        assert_eq!(camel_case("IS_INITIALIZED"), "isInitialized");
+        assert_eq!(camel_case("TOKEN_A_MINT"), "tokenAMint");
+        assert_eq!(camel_case("swap_info"), "swapInfo");
+        assert_eq!(camel_case("FEES"), "fees");
+        assert_eq!(camel_case("_RESERVED"), "reserved");
+    }
+
+    #[test]
+    fn test_render_layout() {
+        let ts = render_layout();
+        assert!(ts.contains("\nexport const SWAP_INFO_LEN = "));
+        assert!(ts
+            .contains("export const SWAP_INFO_OFFSETS = {\n  isInitialized: 0,\n  isPaused: 1,\n"));
+        assert!(ts.contains("\nexport const RECEIPT_SEED = \"receipt\";\n"));
+        assert!(ts.ends_with(";\n"));
+    }
+
+    #[test]
+    fn test_client_layout_up_to_date() {
+        let current = fs::read_to_string(workspace_root().join(CLIENT_LAYOUT)).unwrap();
+        assert_eq!(
+            current,
+            render_layout(),
+            "run `cargo xtask layout` to update the client"
+        );
+    }
+
+    #[test]
+    fn test_recommend_pool_params() {
+        let args = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let params = recommend_pool_params(&args(&["--auto", "pegged", "6", "6"])).unwrap();
+        assert_eq!(
+            Some(params),
+            params::recommend(VolatilityClass::Pegged, 6, 6)
+        );
+        assert_eq!(
+            render_pool_params(&params),
+            "Initialize:\n  curve_type:     StableSwap\n  amp_factor:     500\n  \
+             fee_tier:       OneBps\nSetMinLpMint:     1000\nSetDustThreshold: 100\n\
+             SetOracleGuard:\n  max_staleness_slots: 25\n  max_confidence_bps:  10\n  \
+             max_deviation_bps:   50\nSetOracleStalePolicy:\n  stale_policy:   WidenBand\n  \
+             stale_band_bps: 50"
+        );
+
+        assert!(recommend_pool_params(&args(&["pegged", "6", "6"])).is_err());
+        assert!(recommend_pool_params(&args(&["--auto", "stable", "6", "6"])).is_err());
+        assert!(recommend_pool_params(&args(&["--auto", "volatile", "6"])).is_err());
+        assert!(recommend_pool_params(&args(&["--auto", "volatile", "six", "6"])).is_err());
+        // mismatched decimals
+        assert!(recommend_pool_params(&args(&["--auto", "volatile", "6", "9"])).is_err());
+    }
+
+    #[test]
+    fn test_benches() {
+        for (_name, call) in BENCHES {
+            call();
+        }
+    }
+
+    #[test]
+    fn test_compare() {
+        let build = vec![1u8, 2, 3, 4];
+        let mut deployed = build.clone();
+        deployed.extend_from_slice(&[0; 4]);
+
+        let report = compare("program", "localnet", "image", &build, &deployed);
+        assert_eq!(report.build_len, 4);
+        assert_eq!(report.build_hash, report.deployed_hash);
+        assert!(report.zero_padded);
+        assert!(report.is_verified());
+
+        // Trailing bytes past the artifact
+        deployed[6] = 1;
+        let report = compare("program", "localnet", "image", &build, &deployed);
+        assert_eq!(report.build_hash, report.deployed_hash);
+        assert!(!report.is_verified());
+
+        // Different program
+        deployed[6] = 0;
+        deployed[0] = 0;
+        let report = compare("program", "localnet", "image", &build, &deployed);
+        assert_ne!(report.build_hash, report.deployed_hash);
+        assert!(!report.is_verified());
+
+        // Deployed program shorter than the artifact
+        let report = compare("program", "localnet", "image", &build, &build[..2]);
+        assert!(!report.is_verified());
+    }
+}